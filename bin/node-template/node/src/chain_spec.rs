@@ -1,8 +1,11 @@
 use sp_core::{Pair, Public, sr25519};
 use node_template_runtime::{
-	AccountId, AuraConfig, BalancesConfig, GenesisConfig, GrandpaConfig,
+	AccountId, BalancesConfig, GenesisConfig, GrandpaConfig,
 	SudoConfig, SystemConfig, WASM_BINARY, Signature
 };
+// the `manual-seal` and `pow` features remove Aura from the runtime.
+#[cfg(not(any(feature = "manual-seal", feature = "pow")))]
+use node_template_runtime::AuraConfig;
 use sp_consensus_aura::sr25519::{AuthorityId as AuraId};
 use grandpa_primitives::{AuthorityId as GrandpaId};
 use sc_service;
@@ -130,6 +133,7 @@ fn testnet_genesis(initial_authorities: Vec<(AuraId, GrandpaId)>,
 		balances: Some(BalancesConfig {
 			balances: endowed_accounts.iter().cloned().map(|k|(k, 1 << 60)).collect(),
 		}),
+		#[cfg(not(any(feature = "manual-seal", feature = "pow")))]
 		aura: Some(AuraConfig {
 			authorities: initial_authorities.iter().map(|x| (x.0.clone())).collect(),
 		}),
@@ -142,9 +146,20 @@ fn testnet_genesis(initial_authorities: Vec<(AuraId, GrandpaId)>,
 	}
 }
 
+/// The chain specs of the node, the named ones being the alternatives.
+struct ChainSpecs;
+
+impl sc_cli::ChainSpecLoader for ChainSpecs {
+	fn load_named(&self, name: &str) -> Option<Result<Box<dyn sc_service::ChainSpec>, String>> {
+		let spec = Alternative::from(name)?.load();
+		Some(spec.map(|spec| Box::new(spec) as Box<dyn sc_service::ChainSpec>))
+	}
+
+	fn from_json(&self, json: Vec<u8>) -> Result<Box<dyn sc_service::ChainSpec>, String> {
+		Ok(Box::new(ChainSpec::from_json_bytes(json)?))
+	}
+}
+
 pub fn load_spec(id: &str) -> Result<Box<dyn sc_service::ChainSpec>, String> {
-	Ok(match Alternative::from(id) {
-		Some(spec) => Box::new(spec.load()?),
-		None => Box::new(ChainSpec::from_json_file(std::path::PathBuf::from(id))?),
-	})
+	sc_cli::load_spec(&ChainSpecs, id)
 }
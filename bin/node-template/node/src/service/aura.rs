@@ -1,4 +1,4 @@
-//! Service and ServiceFactory implementation. Specialized wrapper over substrate service.
+//! Service builders of a node authoring the blocks with Aura and finalizing them with GRANDPA.
 
 use std::sync::Arc;
 use std::time::Duration;
@@ -7,17 +7,9 @@ use sc_client_api::ExecutorProvider;
 use node_template_runtime::{self, opaque::Block, RuntimeApi};
 use sc_service::{error::{Error as ServiceError}, AbstractService, Configuration, ServiceBuilder};
 use sp_inherents::InherentDataProviders;
-use sc_executor::native_executor_instance;
-pub use sc_executor::NativeExecutor;
 use sp_consensus_aura::sr25519::{AuthorityPair as AuraPair};
 use grandpa::{self, FinalityProofProvider as GrandpaFinalityProofProvider, StorageAndProofProvider};
-
-// Our native executor instance.
-native_executor_instance!(
-	pub Executor,
-	node_template_runtime::api::dispatch,
-	node_template_runtime::native_version,
-);
+use super::Executor;
 
 /// Starts a `ServiceBuilder` for a full service.
 ///
@@ -26,6 +18,7 @@ native_executor_instance!(
 macro_rules! new_full_start {
 	($config:expr) => {{
 		use std::sync::Arc;
+		use sp_consensus_aura::sr25519::AuthorityPair as AuraPair;
 		let mut import_setup = None;
 		let inherent_data_providers = sp_inherents::InherentDataProviders::new();
 
@@ -35,11 +28,15 @@ macro_rules! new_full_start {
 			.with_select_chain(|_config, backend| {
 				Ok(sc_client::LongestChain::new(backend.clone()))
 			})?
-			.with_transaction_pool(|config, client, _fetcher| {
+			.with_transaction_pool(|config, client, _fetcher, prometheus_registry| {
 				let pool_api = sc_transaction_pool::FullChainApi::new(client.clone());
-				Ok(sc_transaction_pool::BasicPool::new(config, std::sync::Arc::new(pool_api)))
+				Ok(sc_transaction_pool::BasicPool::new(
+					config,
+					std::sync::Arc::new(pool_api),
+					prometheus_registry,
+				))
 			})?
-			.with_import_queue(|_config, client, mut select_chain, _transaction_pool| {
+			.with_import_queue(|config, client, mut select_chain, _transaction_pool| {
 				let select_chain = select_chain.take()
 					.ok_or_else(|| sc_service::Error::SelectChainRequired)?;
 
@@ -50,13 +47,14 @@ macro_rules! new_full_start {
 					grandpa_block_import.clone(), client.clone(),
 				);
 
-				let import_queue = sc_consensus_aura::import_queue::<_, _, _, AuraPair>(
+				let import_queue = sc_consensus_aura::import_queue::<_, _, _, AuraPair, _>(
 					sc_consensus_aura::slot_duration(&*client)?,
 					aura_block_import,
 					Some(Box::new(grandpa_block_import.clone())),
 					None,
 					client,
 					inherent_data_providers.clone(),
+					config.prometheus_registry(),
 				)?;
 
 				import_setup = Some((grandpa_block_import, grandpa_link));
@@ -74,8 +72,10 @@ pub fn new_full(config: Configuration)
 {
 	let is_authority = config.roles.is_authority();
 	let force_authoring = config.force_authoring;
+	let max_unfinalized_blocks = config.max_unfinalized_blocks;
 	let name = config.name.clone();
 	let disable_grandpa = config.disable_grandpa;
+	let grandpa_stall_threshold = config.grandpa_stall_threshold;
 
 	// sentry nodes announce themselves as authorities to the network
 	// and should run the same protocols authorities do, but it should
@@ -99,7 +99,8 @@ pub fn new_full(config: Configuration)
 	if participates_in_consensus {
 		let proposer = sc_basic_authorship::ProposerFactory::new(
 			service.client(),
-			service.transaction_pool()
+			service.transaction_pool(),
+			service.prometheus_registry().as_ref(),
 		);
 
 		let client = service.client();
@@ -109,7 +110,10 @@ pub fn new_full(config: Configuration)
 		let can_author_with =
 			sp_consensus::CanAuthorWithNativeVersion::new(client.executor().clone());
 
-		let aura = sc_consensus_aura::start_aura::<_, _, _, _, _, AuraPair, _, _, _>(
+		let backoff_authoring_blocks = max_unfinalized_blocks
+			.map(sc_consensus_aura::BackoffAuthoringOnFinalizedHeadLagging::new);
+
+		let aura = sc_consensus_aura::start_aura::<_, _, _, _, _, AuraPair, _, _, _, _>(
 			sc_consensus_aura::slot_duration(&*client)?,
 			client,
 			select_chain,
@@ -118,6 +122,9 @@ pub fn new_full(config: Configuration)
 			service.network(),
 			inherent_data_providers.clone(),
 			force_authoring,
+			backoff_authoring_blocks,
+			// leave the rest of the slot for the block to be imported and propagated.
+			sc_consensus_aura::SlotProportion::new(2f32 / 3f32),
 			service.keystore(),
 			can_author_with,
 		)?;
@@ -130,7 +137,7 @@ pub fn new_full(config: Configuration)
 	// if the node isn't actively participating in consensus then it doesn't
 	// need a keystore, regardless of which protocol we use below.
 	let keystore = if participates_in_consensus {
-		Some(service.keystore())
+		Some(service.signing_keystore())
 	} else {
 		None
 	};
@@ -143,6 +150,7 @@ pub fn new_full(config: Configuration)
 		observer_enabled: false,
 		keystore,
 		is_authority,
+		stall_threshold: grandpa_stall_threshold,
 	};
 
 	let enable_grandpa = !disable_grandpa;
@@ -190,17 +198,20 @@ pub fn new_light(config: Configuration)
 		.with_select_chain(|_config, backend| {
 			Ok(LongestChain::new(backend.clone()))
 		})?
-		.with_transaction_pool(|config, client, fetcher| {
+		.with_transaction_pool(|config, client, fetcher, prometheus_registry| {
 			let fetcher = fetcher
 				.ok_or_else(|| "Trying to start light transaction pool without active fetcher")?;
 
 			let pool_api = sc_transaction_pool::LightChainApi::new(client.clone(), fetcher.clone());
 			let pool = sc_transaction_pool::BasicPool::with_revalidation_type(
-				config, Arc::new(pool_api), sc_transaction_pool::RevalidationType::Light,
+				config,
+				Arc::new(pool_api),
+				prometheus_registry,
+				sc_transaction_pool::RevalidationType::Light,
 			);
 			Ok(pool)
 		})?
-		.with_import_queue_and_fprb(|_config, client, backend, fetcher, _select_chain, _tx_pool| {
+		.with_import_queue_and_fprb(|config, client, backend, fetcher, _select_chain, _tx_pool| {
 			let fetch_checker = fetcher
 				.map(|fetcher| fetcher.checker().clone())
 				.ok_or_else(|| "Trying to start light import queue without active fetch checker")?;
@@ -214,13 +225,14 @@ pub fn new_light(config: Configuration)
 			let finality_proof_request_builder =
 				finality_proof_import.create_finality_proof_request_builder();
 
-			let import_queue = sc_consensus_aura::import_queue::<_, _, _, AuraPair>(
+			let import_queue = sc_consensus_aura::import_queue::<_, _, _, AuraPair, _>(
 				sc_consensus_aura::slot_duration(&*client)?,
 				grandpa_block_import,
 				None,
 				Some(Box::new(finality_proof_import)),
 				client,
 				inherent_data_providers.clone(),
+				config.prometheus_registry(),
 			)?;
 
 			Ok((import_queue, finality_proof_request_builder))
@@ -0,0 +1,114 @@
+//! Service builders of a node sealing a block as soon as it imports a transaction.
+//!
+//! The blocks are final once sealed, which suits the development chains having a single node.
+
+use std::sync::Arc;
+use sc_client::LongestChain;
+use node_template_runtime::{self, opaque::Block, RuntimeApi};
+use sc_service::{error::{Error as ServiceError}, AbstractService, Configuration, ServiceBuilder};
+use super::Executor;
+
+/// Starts a `ServiceBuilder` for a full service.
+///
+/// Use this macro if you don't actually need the full service, but just the builder in order to
+/// be able to perform chain operations.
+macro_rules! new_full_start {
+	($config:expr) => {{
+		let inherent_data_providers = sp_inherents::InherentDataProviders::new();
+
+		let builder = sc_service::ServiceBuilder::new_full::<
+			node_template_runtime::opaque::Block, node_template_runtime::RuntimeApi, crate::service::Executor
+		>($config)?
+			.with_select_chain(|_config, backend| {
+				Ok(sc_client::LongestChain::new(backend.clone()))
+			})?
+			.with_transaction_pool(|config, client, _fetcher, prometheus_registry| {
+				let pool_api = sc_transaction_pool::FullChainApi::new(client.clone());
+				Ok(sc_transaction_pool::BasicPool::new(
+					config,
+					std::sync::Arc::new(pool_api),
+					prometheus_registry,
+				))
+			})?
+			.with_import_queue(|config, client, _select_chain, _transaction_pool| {
+				Ok(sc_consensus_manual_seal::import_queue(
+					Box::new(client),
+					config.prometheus_registry(),
+				))
+			})?;
+
+		(builder, (), inherent_data_providers)
+	}}
+}
+
+/// Builds a new service for a full client.
+pub fn new_full(config: Configuration)
+	-> Result<impl AbstractService, ServiceError>
+{
+	let is_authority = config.roles.is_authority();
+
+	let (builder, _, inherent_data_providers) = new_full_start!(config);
+	let backend = builder.backend().clone();
+	let service = builder.build()?;
+
+	if is_authority {
+		// the runtime checks the timestamp of each block, which the other engines provide.
+		inherent_data_providers
+			.register_provider(sp_timestamp::InherentDataProvider)
+			.map_err(|e| ServiceError::Other(e.into_string()))?;
+
+		let proposer = sc_basic_authorship::ProposerFactory::new(
+			service.client(),
+			service.transaction_pool(),
+			service.prometheus_registry().as_ref(),
+		);
+
+		let select_chain = service.select_chain()
+			.ok_or(ServiceError::SelectChainRequired)?;
+
+		let authorship = sc_consensus_manual_seal::run_instant_seal(
+			Box::new(service.client()),
+			proposer,
+			backend,
+			service.transaction_pool().pool().clone(),
+			select_chain,
+			inherent_data_providers,
+		);
+
+		// the sealing task is considered essential, i.e. if it
+		// fails we take down the service with it.
+		service.spawn_essential_task("instant-seal", authorship);
+	}
+
+	Ok(service)
+}
+
+/// Builds a new service for a light client.
+pub fn new_light(config: Configuration)
+	-> Result<impl AbstractService, ServiceError>
+{
+	ServiceBuilder::new_light::<Block, RuntimeApi, Executor>(config)?
+		.with_select_chain(|_config, backend| {
+			Ok(LongestChain::new(backend.clone()))
+		})?
+		.with_transaction_pool(|config, client, fetcher, prometheus_registry| {
+			let fetcher = fetcher
+				.ok_or_else(|| "Trying to start light transaction pool without active fetcher")?;
+
+			let pool_api = sc_transaction_pool::LightChainApi::new(client.clone(), fetcher.clone());
+			let pool = sc_transaction_pool::BasicPool::with_revalidation_type(
+				config,
+				Arc::new(pool_api),
+				prometheus_registry,
+				sc_transaction_pool::RevalidationType::Light,
+			);
+			Ok(pool)
+		})?
+		.with_import_queue(|config, client, _select_chain, _transaction_pool| {
+			Ok(sc_consensus_manual_seal::import_queue(
+				Box::new(client),
+				config.prometheus_registry(),
+			))
+		})?
+		.build()
+}
@@ -0,0 +1,37 @@
+//! Service and ServiceFactory implementation. Specialized wrapper over substrate service.
+//!
+//! The consensus of the node is selected at build time by one of the `aura`, `manual-seal` and
+//! `pow` features, `aura` being the default one. Each of them has its own `new_full_start!`,
+//! `new_full` and `new_light`. When several of them are enabled, e.g. by `--all-features`,
+//! `manual-seal` takes precedence over `pow`, which takes precedence over `aura`.
+
+use sc_executor::native_executor_instance;
+pub use sc_executor::NativeExecutor;
+
+#[cfg(not(any(feature = "aura", feature = "manual-seal", feature = "pow")))]
+compile_error!("One of the `aura`, `manual-seal` and `pow` features must be enabled.");
+
+// Our native executor instance.
+native_executor_instance!(
+	pub Executor,
+	node_template_runtime::api::dispatch,
+	node_template_runtime::native_version,
+);
+
+#[cfg(all(feature = "aura", not(any(feature = "manual-seal", feature = "pow"))))]
+#[macro_use]
+mod aura;
+#[cfg(all(feature = "aura", not(any(feature = "manual-seal", feature = "pow"))))]
+pub use aura::{new_full, new_light};
+
+#[cfg(feature = "manual-seal")]
+#[macro_use]
+mod manual_seal;
+#[cfg(feature = "manual-seal")]
+pub use manual_seal::{new_full, new_light};
+
+#[cfg(all(feature = "pow", not(feature = "manual-seal")))]
+#[macro_use]
+mod pow;
+#[cfg(all(feature = "pow", not(feature = "manual-seal")))]
+pub use pow::{new_full, new_light, Blake2Algorithm};
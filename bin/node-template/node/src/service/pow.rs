@@ -0,0 +1,273 @@
+//! Service builders of a node mining the blocks with a CPU proof of work.
+//!
+//! The work is a Blake2 hash of the block's pre-hash and a nonce, at a constant difficulty, which
+//! is enough for a development chain. A real chain would adjust the difficulty, e.g. from the
+//! timestamps of the previous blocks.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use sc_client::LongestChain;
+use sc_client_api::ExecutorProvider;
+use node_template_runtime::{self, opaque::Block, RuntimeApi};
+use sc_service::{error::{Error as ServiceError}, AbstractService, Configuration, ServiceBuilder};
+use sp_core::{U256, hashing::blake2_256};
+use sp_consensus_pow::Seal;
+use sp_inherents::InherentDataProviders;
+use sp_runtime::generic::BlockId;
+use sp_runtime::traits::Block as BlockT;
+use sc_consensus_pow::{Error as PowError, PowAlgorithm};
+use super::Executor;
+
+/// The difficulty of every block, i.e. about that many hashes are needed to mine one.
+const DIFFICULTY: u64 = 1_000_000;
+
+/// The number of nonces tried by each call to `mine`.
+const MINING_ROUND: u32 = 10_000;
+
+/// A Blake2 proof of work at a constant difficulty.
+///
+/// The seal is the little endian encoding of the nonce. The clones share the next nonce to try,
+/// so a miner doesn't retry the nonces it already went through for the same block.
+#[derive(Clone, Default)]
+pub struct Blake2Algorithm {
+	next_nonce: Arc<AtomicU64>,
+}
+
+impl Blake2Algorithm {
+	/// Whether `nonce` seals the block of the given pre-hash at the given difficulty.
+	fn is_valid_nonce<H: AsRef<[u8]>>(pre_hash: &H, nonce: u64, difficulty: U256) -> bool {
+		let mut work = pre_hash.as_ref().to_vec();
+		work.extend_from_slice(&nonce.to_le_bytes());
+		let hash = U256::from(&blake2_256(&work)[..]);
+
+		hash.checked_mul(difficulty).is_some()
+	}
+}
+
+impl<B: BlockT> PowAlgorithm<B> for Blake2Algorithm {
+	type Difficulty = U256;
+
+	fn difficulty(&self, _parent: &BlockId<B>) -> Result<U256, PowError<B>> {
+		Ok(U256::from(DIFFICULTY))
+	}
+
+	fn verify(
+		&self,
+		_parent: &BlockId<B>,
+		pre_hash: &B::Hash,
+		seal: &Seal,
+		difficulty: U256,
+	) -> Result<bool, PowError<B>> {
+		let mut nonce = [0u8; 8];
+		if seal.len() != nonce.len() {
+			return Ok(false)
+		}
+		nonce.copy_from_slice(seal);
+
+		Ok(Self::is_valid_nonce(pre_hash, u64::from_le_bytes(nonce), difficulty))
+	}
+
+	fn mine(
+		&self,
+		_parent: &BlockId<B>,
+		pre_hash: &B::Hash,
+		difficulty: U256,
+		round: u32,
+	) -> Result<Option<Seal>, PowError<B>> {
+		for _ in 0..round {
+			let nonce = self.next_nonce.fetch_add(1, Ordering::Relaxed);
+			if Self::is_valid_nonce(pre_hash, nonce, difficulty) {
+				return Ok(Some(nonce.to_le_bytes().to_vec()))
+			}
+		}
+
+		Ok(None)
+	}
+}
+
+/// Starts a `ServiceBuilder` for a full service.
+///
+/// Use this macro if you don't actually need the full service, but just the builder in order to
+/// be able to perform chain operations.
+macro_rules! new_full_start {
+	($config:expr) => {{
+		let mut import_setup = None;
+		let inherent_data_providers = sp_inherents::InherentDataProviders::new();
+
+		let builder = sc_service::ServiceBuilder::new_full::<
+			node_template_runtime::opaque::Block, node_template_runtime::RuntimeApi, crate::service::Executor
+		>($config)?
+			.with_select_chain(|_config, backend| {
+				Ok(sc_client::LongestChain::new(backend.clone()))
+			})?
+			.with_transaction_pool(|config, client, _fetcher, prometheus_registry| {
+				let pool_api = sc_transaction_pool::FullChainApi::new(client.clone());
+				Ok(sc_transaction_pool::BasicPool::new(
+					config,
+					std::sync::Arc::new(pool_api),
+					prometheus_registry,
+				))
+			})?
+			.with_import_queue(|config, client, select_chain, _transaction_pool| {
+				let algorithm = crate::service::Blake2Algorithm::default();
+
+				let pow_block_import = sc_consensus_pow::PowBlockImport::new(
+					client.clone(),
+					client.clone(),
+					algorithm.clone(),
+					0,
+					select_chain,
+					inherent_data_providers.clone(),
+				);
+
+				let import_queue = sc_consensus_pow::import_queue(
+					Box::new(pow_block_import.clone()),
+					algorithm.clone(),
+					config.prometheus_registry(),
+				)?;
+
+				import_setup = Some((pow_block_import, algorithm));
+
+				Ok(import_queue)
+			})?;
+
+		(builder, import_setup, inherent_data_providers)
+	}}
+}
+
+/// Builds a new service for a full client.
+pub fn new_full(config: Configuration)
+	-> Result<impl AbstractService, ServiceError>
+{
+	let is_authority = config.roles.is_authority();
+
+	// sentry nodes announce themselves as authorities to the network
+	// and should run the same protocols authorities do, but it should
+	// never actively participate in any consensus process.
+	let participates_in_consensus = is_authority && !config.sentry_mode;
+
+	let (builder, mut import_setup, inherent_data_providers) = new_full_start!(config);
+
+	let (block_import, algorithm) =
+		import_setup.take()
+			.expect("Block Import and PoW algorithm are present for Full Services or setup failed before. qed");
+
+	let service = builder.build()?;
+
+	if participates_in_consensus {
+		let proposer = sc_basic_authorship::ProposerFactory::new(
+			service.client(),
+			service.transaction_pool(),
+			service.prometheus_registry().as_ref(),
+		);
+
+		let client = service.client();
+		let select_chain = service.select_chain()
+			.ok_or(ServiceError::SelectChainRequired)?;
+
+		let can_author_with =
+			sp_consensus::CanAuthorWithNativeVersion::new(client.executor().clone());
+
+		// the miner runs on its own thread and restarts on failures, so
+		// unlike the other engines there is no task to spawn here.
+		sc_consensus_pow::start_mine(
+			Box::new(block_import),
+			client,
+			algorithm,
+			proposer,
+			None,
+			MINING_ROUND,
+			service.network(),
+			Duration::from_secs(2),
+			Some(select_chain),
+			inherent_data_providers,
+			can_author_with,
+		);
+	}
+
+	Ok(service)
+}
+
+/// Builds a new service for a light client.
+pub fn new_light(config: Configuration)
+	-> Result<impl AbstractService, ServiceError>
+{
+	let inherent_data_providers = InherentDataProviders::new();
+
+	ServiceBuilder::new_light::<Block, RuntimeApi, Executor>(config)?
+		.with_select_chain(|_config, backend| {
+			Ok(LongestChain::new(backend.clone()))
+		})?
+		.with_transaction_pool(|config, client, fetcher, prometheus_registry| {
+			let fetcher = fetcher
+				.ok_or_else(|| "Trying to start light transaction pool without active fetcher")?;
+
+			let pool_api = sc_transaction_pool::LightChainApi::new(client.clone(), fetcher.clone());
+			let pool = sc_transaction_pool::BasicPool::with_revalidation_type(
+				config,
+				Arc::new(pool_api),
+				prometheus_registry,
+				sc_transaction_pool::RevalidationType::Light,
+			);
+			Ok(pool)
+		})?
+		.with_import_queue(|config, client, select_chain, _transaction_pool| {
+			let algorithm = Blake2Algorithm::default();
+
+			let pow_block_import = sc_consensus_pow::PowBlockImport::new(
+				client.clone(),
+				client,
+				algorithm.clone(),
+				0,
+				select_chain,
+				inherent_data_providers.clone(),
+			);
+
+			let import_queue = sc_consensus_pow::import_queue(
+				Box::new(pow_block_import),
+				algorithm,
+				config.prometheus_registry(),
+			)?;
+
+			Ok(import_queue)
+		})?
+		.build()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::H256;
+
+	#[test]
+	fn mined_seals_are_valid() {
+		let algorithm = Blake2Algorithm::default();
+		let other_miner = algorithm.clone();
+		let parent = BlockId::<Block>::Number(0);
+		let pre_hash = H256::repeat_byte(1);
+		let difficulty = U256::from(DIFFICULTY);
+
+		let seal = loop {
+			if let Some(seal) = algorithm.mine(&parent, &pre_hash, difficulty, MINING_ROUND).unwrap() {
+				break seal
+			}
+		};
+
+		assert!(algorithm.verify(&parent, &pre_hash, &seal, difficulty).unwrap());
+		// the other miner doesn't retry the nonces already tried.
+		assert!(other_miner.next_nonce.load(Ordering::Relaxed) > 0);
+	}
+
+	#[test]
+	fn seals_of_the_wrong_length_are_rejected() {
+		let algorithm = Blake2Algorithm::default();
+		let parent = BlockId::<Block>::Number(0);
+		let pre_hash = H256::repeat_byte(1);
+
+		// any nonce is valid at the lowest difficulty.
+		assert!(algorithm.verify(&parent, &pre_hash, &vec![0; 8], U256::one()).unwrap());
+		assert!(!algorithm.verify(&parent, &pre_hash, &vec![0; 7], U256::one()).unwrap());
+		assert!(!algorithm.verify(&parent, &pre_hash, &vec![0; 9], U256::one()).unwrap());
+	}
+}
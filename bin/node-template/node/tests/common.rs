@@ -0,0 +1,119 @@
+#![cfg(unix)]
+#![allow(dead_code)]
+
+use std::{
+	convert::TryInto, io::{BufRead, BufReader, Read, Write}, net::TcpStream, path::Path,
+	process::{Child, Command, ExitStatus, Stdio}, sync::mpsc, thread, time::{Duration, Instant},
+};
+use assert_cmd::cargo::cargo_bin;
+use nix::sys::signal::{kill, Signal::SIGINT};
+use nix::unistd::Pid;
+
+/// The port of the HTTP RPC server of the nodes started by `start_dev_node`.
+pub const RPC_PORT: u16 = 19933;
+
+/// A development node running in the background.
+pub struct DevNode {
+	child: Child,
+	logs: mpsc::Receiver<String>,
+}
+
+/// Starts a development node storing its chain in `base_path`.
+pub fn start_dev_node(base_path: &Path) -> DevNode {
+	let mut child = Command::new(cargo_bin("node-template"))
+		.args(&["--dev", "--rpc-port", &RPC_PORT.to_string(), "--ws-port", "19944", "--port", "30934"])
+		.arg("-d")
+		.arg(base_path)
+		.stderr(Stdio::piped())
+		.spawn()
+		.unwrap();
+
+	let (sender, logs) = mpsc::channel();
+	let stderr = BufReader::new(child.stderr.take().unwrap());
+	thread::spawn(move || {
+		for line in stderr.lines() {
+			let line = match line {
+				Ok(line) => line,
+				Err(_) => break,
+			};
+			eprintln!("{}", line);
+			if sender.send(line).is_err() {
+				break
+			}
+		}
+	});
+
+	DevNode { child, logs }
+}
+
+impl DevNode {
+	/// Waits at most `secs` seconds for the node to import the block `number`.
+	///
+	/// Returns whether it was imported.
+	pub fn wait_for_block(&self, number: u32, secs: u64) -> bool {
+		let imported = format!("Imported #{} ", number);
+		let deadline = Instant::now() + Duration::from_secs(secs);
+		while let Some(timeout) = deadline.checked_duration_since(Instant::now()) {
+			match self.logs.recv_timeout(timeout) {
+				Ok(line) if line.contains(&imported) => return true,
+				Ok(_) => {},
+				Err(_) => return false,
+			}
+		}
+
+		false
+	}
+
+	/// Interrupts the node and returns whether it exited gracefully.
+	pub fn stop(mut self) -> bool {
+		assert!(self.child.try_wait().unwrap().is_none(), "the process should still be running");
+		kill(Pid::from_raw(self.child.id().try_into().unwrap()), SIGINT).unwrap();
+		wait_for(&mut self.child, 30).map(|x| x.success()).unwrap_or_default()
+	}
+}
+
+/// Calls the `method` of the RPC server of the node started by `start_dev_node`.
+///
+/// Returns the result of the call.
+pub fn rpc(method: &str, params: serde_json::Value) -> serde_json::Value {
+	let body = serde_json::json!({
+		"jsonrpc": "2.0",
+		"id": 1,
+		"method": method,
+		"params": params,
+	}).to_string();
+
+	let mut stream = TcpStream::connect(("127.0.0.1", RPC_PORT)).unwrap();
+	write!(
+		stream,
+		"POST / HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\n\
+		Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+		body.len(),
+		body,
+	).unwrap();
+
+	let mut response = String::new();
+	stream.read_to_string(&mut response).unwrap();
+	let body = &response[response.find("\r\n\r\n").expect("HTTP responses have a body") + 4..];
+	let mut response: serde_json::Value = serde_json::from_str(body).unwrap();
+	assert!(response["error"].is_null(), "`{}` failed: {}", method, response["error"]);
+
+	response["result"].take()
+}
+
+/// Wait for the given `child` the given number of `secs`.
+///
+/// Returns the `Some(exit status)` or `None` if the process did not finish in the given time.
+pub fn wait_for(child: &mut Child, secs: usize) -> Option<ExitStatus> {
+	for _ in 0..secs {
+		match child.try_wait().unwrap() {
+			Some(status) => return Some(status),
+			None => thread::sleep(Duration::from_secs(1)),
+		}
+	}
+	eprintln!("Took too long to exit (> {} seconds). Killing...", secs);
+	let _ = child.kill();
+	child.wait().unwrap();
+
+	None
+}
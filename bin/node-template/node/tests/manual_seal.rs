@@ -0,0 +1,61 @@
+#![cfg(all(unix, feature = "manual-seal"))]
+
+use codec::Encode;
+use node_template_runtime::{BalancesCall, Call, SignedExtra, UncheckedExtrinsic, VERSION};
+use sp_core::{Bytes, H256};
+use sp_keyring::AccountKeyring;
+use sp_runtime::generic::{Era, SignedPayload};
+use tempfile::tempdir;
+
+mod common;
+
+/// Returns a transfer from Alice to Bob, signed for the chain of the given genesis.
+fn transfer(nonce: u32, genesis_hash: H256) -> Bytes {
+	let call = Call::Balances(BalancesCall::transfer(AccountKeyring::Bob.to_account_id(), 1_000));
+	let extra: SignedExtra = (
+		frame_system::CheckVersion::new(),
+		frame_system::CheckGenesis::new(),
+		frame_system::CheckMortality::from(Era::Immortal),
+		frame_system::CheckNonce::from(nonce),
+		frame_system::CheckWeight::new(),
+		pallet_transaction_payment::ChargeTransactionPayment::from(0),
+	);
+	let payload = SignedPayload::from_raw(
+		call,
+		extra,
+		(VERSION.spec_version, genesis_hash, genesis_hash, (), (), ()),
+	);
+	let signature = payload.using_encoded(|payload| AccountKeyring::Alice.sign(payload));
+	let (call, extra, _) = payload.deconstruct();
+
+	UncheckedExtrinsic::new_signed(
+		call,
+		AccountKeyring::Alice.to_account_id(),
+		signature.into(),
+		extra,
+	).encode().into()
+}
+
+#[test]
+fn the_dev_node_seals_a_block_per_transaction() {
+	let base_path = tempdir().expect("could not create a temp dir");
+	let node = common::start_dev_node(base_path.path());
+
+	// wait for the RPC server.
+	std::thread::sleep(std::time::Duration::from_secs(5));
+	let genesis_hash: H256 = serde_json::from_value(
+		common::rpc("chain_getBlockHash", serde_json::json!([0])),
+	).unwrap();
+
+	// the blocks are sealed faster than the slots of Aura, so several blocks have to be imported.
+	for nonce in 0..3 {
+		common::rpc("author_submitExtrinsic", serde_json::json!([transfer(nonce, genesis_hash)]));
+		assert!(
+			node.wait_for_block(nonce + 1, 30),
+			"the node should have sealed the block of transaction {}",
+			nonce,
+		);
+	}
+
+	assert!(node.stop(), "the node must exit gracefully");
+}
@@ -0,0 +1,15 @@
+#![cfg(all(unix, feature = "pow", not(feature = "manual-seal")))]
+
+use tempfile::tempdir;
+
+mod common;
+
+#[test]
+fn the_dev_node_mines_consecutive_blocks() {
+	let base_path = tempdir().expect("could not create a temp dir");
+	let node = common::start_dev_node(base_path.path());
+
+	// the blocks are mined faster than the slots of Aura, so several blocks have to be imported.
+	assert!(node.wait_for_block(3, 120), "the node should have mined three blocks");
+	assert!(node.stop(), "the node must exit gracefully");
+}
@@ -40,7 +40,7 @@ impl system::Trait for Test {
 	type Version = ();
 	type ModuleToIndex = ();
 	type AccountData = ();
-	type MigrateAccount = (); type OnNewAccount = ();
+	type MigrateAccount = (); type MigrateAccountData = (); type OnNewAccount = ();
 	type OnKilledAccount = ();
 }
 impl Trait for Test {
@@ -11,15 +11,16 @@ include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
 use sp_std::prelude::*;
 use sp_core::OpaqueMetadata;
 use sp_runtime::{
-	ApplyExtrinsicResult, transaction_validity::TransactionValidity, generic, create_runtime_str,
-	impl_opaque_keys, MultiSignature,
+	ApplyExtrinsicResult, generic, create_runtime_str, impl_opaque_keys, MultiSignature,
+	transaction_validity::{TransactionValidity, TransactionSource},
 };
 use sp_runtime::traits::{
-	BlakeTwo256, Block as BlockT, IdentityLookup, Verify, ConvertInto, IdentifyAccount
+	BlakeTwo256, Block as BlockT, IdentityLookup, Verify, ConvertInto, IdentifyAccount, NumberFor,
 };
 use sp_api::impl_runtime_apis;
+#[cfg(not(feature = "without-aura"))]
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
-use grandpa::AuthorityList as GrandpaAuthorityList;
+use grandpa::{AuthorityId as GrandpaId, AuthorityList as GrandpaAuthorityList};
 use grandpa::fg_primitives;
 use sp_version::RuntimeVersion;
 #[cfg(feature = "std")]
@@ -82,12 +83,20 @@ pub mod opaque {
 	/// Opaque block identifier type.
 	pub type BlockId = generic::BlockId<Block>;
 
+	#[cfg(not(feature = "without-aura"))]
 	impl_opaque_keys! {
 		pub struct SessionKeys {
 			pub aura: Aura,
 			pub grandpa: Grandpa,
 		}
 	}
+
+	#[cfg(feature = "without-aura")]
+	impl_opaque_keys! {
+		pub struct SessionKeys {
+			pub grandpa: Grandpa,
+		}
+	}
 }
 
 /// This runtime version.
@@ -98,6 +107,7 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	spec_version: 1,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
+	transaction_version: 2,
 };
 
 pub const MILLISECS_PER_BLOCK: u64 = 6000;
@@ -162,29 +172,45 @@ impl system::Trait for Runtime {
 	/// This type is being generated by `construct_runtime!`.
 	type ModuleToIndex = ModuleToIndex;
 	/// What to do if a new account is created.
-	type MigrateAccount = (); type OnNewAccount = ();
+	type MigrateAccount = (); type MigrateAccountData = (); type OnNewAccount = ();
 	/// What to do if an account is fully reaped from the system.
 	type OnKilledAccount = ();
 	/// The data to be stored in an account.
 	type AccountData = balances::AccountData<Balance>;
 }
 
+#[cfg(not(feature = "without-aura"))]
 impl aura::Trait for Runtime {
 	type AuthorityId = AuraId;
 }
 
 impl grandpa::Trait for Runtime {
 	type Event = Event;
+
+	type KeyOwnerProof = sp_core::Void;
+	type KeyOwnerIdentification = sp_core::Void;
+	type KeyOwnerProofSystem = ();
+	type HandleEquivocation = ();
 }
 
+#[cfg(not(feature = "without-aura"))]
 parameter_types! {
 	pub const MinimumPeriod: u64 = SLOT_DURATION / 2;
 }
 
+// Without slots, the blocks can be as close as the clock allows.
+#[cfg(feature = "without-aura")]
+parameter_types! {
+	pub const MinimumPeriod: u64 = 1;
+}
+
 impl timestamp::Trait for Runtime {
 	/// A timestamp: milliseconds since the unix epoch.
 	type Moment = u64;
+	#[cfg(not(feature = "without-aura"))]
 	type OnTimestampSet = Aura;
+	#[cfg(feature = "without-aura")]
+	type OnTimestampSet = ();
 	type MinimumPeriod = MinimumPeriod;
 }
 
@@ -216,9 +242,15 @@ impl transaction_payment::Trait for Runtime {
 	type FeeMultiplierUpdate = ();
 }
 
+parameter_types! {
+	pub const MaxAnnouncementsPerBlock: u32 = 16;
+}
+
 impl sudo::Trait for Runtime {
 	type Event = Event;
 	type Call = Call;
+	type AnnounceDelay = ();
+	type MaxAnnouncementsPerBlock = MaxAnnouncementsPerBlock;
 }
 
 /// Used for the module template in `./template.rs`
@@ -226,6 +258,7 @@ impl template::Trait for Runtime {
 	type Event = Event;
 }
 
+#[cfg(not(feature = "without-aura"))]
 construct_runtime!(
 	pub enum Runtime where
 		Block = Block,
@@ -245,6 +278,27 @@ construct_runtime!(
 	}
 );
 
+// The blocks of the `manual-seal` and `pow` nodes aren't authored in slots, so Aura would
+// reject all but the first block of every slot.
+#[cfg(feature = "without-aura")]
+construct_runtime!(
+	pub enum Runtime where
+		Block = Block,
+		NodeBlock = opaque::Block,
+		UncheckedExtrinsic = UncheckedExtrinsic
+	{
+		System: system::{Module, Call, Config, Storage, Event<T>},
+		RandomnessCollectiveFlip: randomness_collective_flip::{Module, Call, Storage},
+		Timestamp: timestamp::{Module, Call, Storage, Inherent},
+		Grandpa: grandpa::{Module, Call, Storage, Config, Event},
+		Balances: balances::{Module, Call, Storage, Config<T>, Event<T>},
+		TransactionPayment: transaction_payment::{Module, Storage},
+		Sudo: sudo::{Module, Call, Config<T>, Storage, Event<T>},
+		// Used for the module template in `./template.rs`
+		TemplateModule: template::{Module, Call, Storage, Event<T>},
+	}
+);
+
 /// The address format for describing accounts.
 pub type Address = AccountId;
 /// Block header type as expected by this runtime.
@@ -259,7 +313,7 @@ pub type BlockId = generic::BlockId<Block>;
 pub type SignedExtra = (
 	system::CheckVersion<Runtime>,
 	system::CheckGenesis<Runtime>,
-	system::CheckEra<Runtime>,
+	system::CheckMortality<Runtime>,
 	system::CheckNonce<Runtime>,
 	system::CheckWeight<Runtime>,
 	transaction_payment::ChargeTransactionPayment<Runtime>
@@ -305,6 +359,12 @@ impl_runtime_apis! {
 			data.create_extrinsics()
 		}
 
+		fn inherent_extrinsics_with_identifiers(
+			data: sp_inherents::InherentData,
+		) -> Vec<(sp_inherents::InherentIdentifier, bool, <Block as BlockT>::Extrinsic)> {
+			data.create_extrinsics_with_identifiers()
+		}
+
 		fn check_inherents(
 			block: Block,
 			data: sp_inherents::InherentData,
@@ -318,8 +378,11 @@ impl_runtime_apis! {
 	}
 
 	impl sp_transaction_pool::runtime_api::TaggedTransactionQueue<Block> for Runtime {
-		fn validate_transaction(tx: <Block as BlockT>::Extrinsic) -> TransactionValidity {
-			Executive::validate_transaction(tx)
+		fn validate_transaction(
+			source: TransactionSource,
+			tx: <Block as BlockT>::Extrinsic,
+		) -> TransactionValidity {
+			Executive::validate_transaction(source, tx)
 		}
 	}
 
@@ -329,6 +392,7 @@ impl_runtime_apis! {
 		}
 	}
 
+	#[cfg(not(feature = "without-aura"))]
 	impl sp_consensus_aura::AuraApi<Block, AuraId> for Runtime {
 		fn slot_duration() -> u64 {
 			Aura::slot_duration()
@@ -355,5 +419,25 @@ impl_runtime_apis! {
 		fn grandpa_authorities() -> GrandpaAuthorityList {
 			Grandpa::grandpa_authorities()
 		}
+
+		fn submit_report_equivocation_unsigned_extrinsic(
+			_equivocation_proof: fg_primitives::EquivocationProof<
+				<Block as BlockT>::Hash,
+				NumberFor<Block>,
+			>,
+			_key_owner_proof: fg_primitives::OpaqueKeyOwnershipProof,
+		) -> Option<()> {
+			None
+		}
+
+		fn generate_key_ownership_proof(
+			_set_id: fg_primitives::SetId,
+			_authority_id: GrandpaId,
+		) -> Option<fg_primitives::OpaqueKeyOwnershipProof> {
+			// NOTE: this is the only implementation possible since we've
+			// defined our key owner proof type as a bottom type (i.e. a type
+			// with no values).
+			None
+		}
 	}
 }
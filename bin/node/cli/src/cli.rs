@@ -42,6 +42,15 @@ pub enum Subcommand {
 	)]
 	Factory(FactoryCmd),
 
+	/// The custom stress subcommmand for flooding a running node with transactions.
+	#[structopt(
+		name = "stress",
+		about = "Submits transfers from Alice to a running node at a target rate, \
+		and reports how long they took to be included. \
+		Only supported for development or local testnet."
+	)]
+	Stress(StressCmd),
+
 	/// The custom inspect subcommmand for decoding blocks and extrinsics.
 	#[structopt(
 		name = "inspect",
@@ -55,6 +64,14 @@ pub enum Subcommand {
 		about = "Benchmark runtime pallets."
 	)]
 	Benchmark(frame_benchmarking_cli::BenchmarkCmd),
+
+	/// The custom benchmark subcommmand benchmarking the execution of the imported blocks.
+	#[structopt(
+		name = "benchmark-block",
+		about = "Benchmark the execution of the imported blocks. \
+		The runtime must be built with the `execute-block-no-check` feature."
+	)]
+	BenchmarkBlock(frame_benchmarking_cli::BlockCmd),
 }
 
 /// The `factory` command used to generate transactions.
@@ -77,3 +94,29 @@ pub struct FactoryCmd {
 	#[structopt(flatten)]
 	pub import_params: ImportParams,
 }
+
+/// The `stress` command used to measure the transaction pool and the block authorship of a
+/// running node.
+#[derive(Debug, StructOpt, Clone)]
+pub struct StressCmd {
+	/// URL of the HTTP RPC server of the node.
+	#[structopt(long="url", default_value = "http://localhost:9933")]
+	pub url: String,
+
+	/// Number of transfers to submit.
+	#[structopt(long="transfers", default_value = "1000")]
+	pub transfers: u32,
+
+	/// Number of transfers to submit per second.
+	#[structopt(long="tps", default_value = "100")]
+	pub tps: u32,
+
+	/// How long to wait for the inclusion of the transfers after the last one is submitted, in
+	/// seconds.
+	#[structopt(long="timeout", default_value = "60")]
+	pub timeout: u64,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
@@ -58,6 +58,16 @@ where
 
 			cmd.run::<node_runtime::Block, node_executor::Executor>(config)
 		},
+		Some(Subcommand::BenchmarkBlock(cmd)) => {
+			cmd.init(&version)?;
+			cmd.update_config(&mut config, load_spec, &version)?;
+
+			let client = sc_service::new_full_client::<
+				node_runtime::Block, node_runtime::RuntimeApi, node_executor::Executor,
+			>(&config)?;
+
+			cmd.run(client)
+		},
 		Some(Subcommand::Factory(cli_args)) => {
 			cli_args.shared_params.init(&version)?;
 			cli_args.shared_params.update_config(&mut config, load_spec, &version)?;
@@ -103,6 +113,18 @@ where
 
 			Ok(())
 		},
+		Some(Subcommand::Stress(cli_args)) => {
+			cli_args.shared_params.init(&version)?;
+
+			let params = node_transaction_factory::StressParams {
+				url: cli_args.url,
+				transfers: cli_args.transfers,
+				tps: cli_args.tps,
+				timeout: std::time::Duration::from_secs(cli_args.timeout),
+			};
+
+			node_transaction_factory::stress(FactoryState::new(0, 0), params)
+		},
 		Some(Subcommand::Base(subcommand)) => {
 			subcommand.init(&version)?;
 			subcommand.update_config(&mut config, load_spec, &version)?;
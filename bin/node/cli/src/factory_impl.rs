@@ -53,7 +53,7 @@ impl<Number> FactoryState<Number> {
 		(
 			frame_system::CheckVersion::new(),
 			frame_system::CheckGenesis::new(),
-			frame_system::CheckEra::from(Era::mortal(256, phase)),
+			frame_system::CheckMortality::from(Era::mortal(128, phase)),
 			frame_system::CheckNonce::from(index),
 			frame_system::CheckWeight::new(),
 			pallet_transaction_payment::ChargeTransactionPayment::from(0),
@@ -100,6 +100,14 @@ impl RuntimeAdapter for FactoryState<Number> {
 		self.block_number = value;
 	}
 
+	fn index(&self) -> Self::Index {
+		self.index
+	}
+
+	fn set_index(&mut self, value: Self::Index) {
+		self.index = value;
+	}
+
 	fn transfer_extrinsic(
 		&mut self,
 		sender: &Self::AccountId,
@@ -83,9 +83,23 @@ impl ChainSpec {
 	}
 }
 
+/// The chain specs of the node, the named ones being those of `ChainSpec`.
+#[cfg(feature = "cli")]
+struct ChainSpecs;
+
+#[cfg(feature = "cli")]
+impl sc_cli::ChainSpecLoader for ChainSpecs {
+	fn load_named(&self, name: &str) -> Option<Result<Box<dyn sc_service::ChainSpec>, String>> {
+		let spec = ChainSpec::from(name)?.load();
+		Some(spec.map(|spec| Box::new(spec) as Box<dyn sc_service::ChainSpec>))
+	}
+
+	fn from_json(&self, json: Vec<u8>) -> Result<Box<dyn sc_service::ChainSpec>, String> {
+		Ok(Box::new(chain_spec::ChainSpec::from_json_bytes(json)?))
+	}
+}
+
+#[cfg(feature = "cli")]
 fn load_spec(id: &str) -> Result<Box<dyn sc_service::ChainSpec>, String> {
-	Ok(match ChainSpec::from(id) {
-		Some(spec) => Box::new(spec.load()?),
-		None => Box::new(chain_spec::ChainSpec::from_json_file(std::path::PathBuf::from(id))?),
-	})
+	sc_cli::load_spec(&ChainSpecs, id)
 }
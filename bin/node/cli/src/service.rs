@@ -48,6 +48,7 @@ macro_rules! new_full_start {
 		use std::sync::Arc;
 		type RpcExtension = jsonrpc_core::IoHandler<sc_rpc::Metadata>;
 		let mut import_setup = None;
+		let chain_spec = $config.expect_chain_spec().cloned_box();
 		let inherent_data_providers = sp_inherents::InherentDataProviders::new();
 
 		let builder = sc_service::ServiceBuilder::new_full::<
@@ -56,11 +57,15 @@ macro_rules! new_full_start {
 			.with_select_chain(|_config, backend| {
 				Ok(sc_client::LongestChain::new(backend.clone()))
 			})?
-			.with_transaction_pool(|config, client, _fetcher| {
+			.with_transaction_pool(|config, client, _fetcher, prometheus_registry| {
 				let pool_api = sc_transaction_pool::FullChainApi::new(client.clone());
-				Ok(sc_transaction_pool::BasicPool::new(config, std::sync::Arc::new(pool_api)))
+				Ok(sc_transaction_pool::BasicPool::new(
+					config,
+					std::sync::Arc::new(pool_api),
+					prometheus_registry,
+				))
 			})?
-			.with_import_queue(|_config, client, mut select_chain, _transaction_pool| {
+			.with_import_queue(|config, client, mut select_chain, _transaction_pool| {
 				let select_chain = select_chain.take()
 					.ok_or_else(|| sc_service::Error::SelectChainRequired)?;
 				let (grandpa_block_import, grandpa_link) = grandpa::block_import(
@@ -83,26 +88,54 @@ macro_rules! new_full_start {
 					None,
 					client,
 					inherent_data_providers.clone(),
+					config.prometheus_registry(),
 				)?;
 
 				import_setup = Some((block_import, grandpa_link, babe_link));
 				Ok(import_queue)
 			})?
-			.with_rpc_extensions(|builder| -> Result<RpcExtension, _> {
-				let babe_link = import_setup.as_ref().map(|s| &s.2)
-					.expect("BabeLink is present for full services or set up failed; qed.");
-				let deps = node_rpc::FullDeps {
-					client: builder.client().clone(),
-					pool: builder.pool(),
-					select_chain: builder.select_chain().cloned()
-						.expect("SelectChain is present for full services or set up failed; qed."),
-					babe: node_rpc::BabeDeps {
-						keystore: builder.keystore(),
-						babe_config: sc_consensus_babe::BabeLink::config(babe_link).clone(),
-						shared_epoch_changes: sc_consensus_babe::BabeLink::epoch_changes(babe_link).clone()
-					}
-				};
-				Ok(node_rpc::create_full(deps))
+			.with_rpc_extensions_builder(|builder| {
+				let (_, grandpa_link, babe_link) = import_setup.as_ref().expect(
+					"BabeLink and LinkHalf are present for full services or set up failed; qed."
+				);
+				let client = builder.client().clone();
+				let pool = builder.pool();
+				let select_chain = builder.select_chain().cloned()
+					.expect("SelectChain is present for full services or set up failed; qed.");
+				let keystore = builder.signing_keystore();
+				let babe_config = sc_consensus_babe::BabeLink::config(babe_link).clone();
+				let shared_epoch_changes =
+					sc_consensus_babe::BabeLink::epoch_changes(babe_link).clone();
+				let shared_authority_set = grandpa_link.shared_authority_set().clone();
+				let shared_voter_state = grandpa_link.shared_voter_state().clone();
+				let justification_stream = grandpa_link.justification_stream();
+				let finality_proof_provider = Arc::new(grandpa::FinalityProofProvider::new(
+					builder.backend().clone(),
+					client.clone() as Arc<dyn grandpa::StorageAndProofProvider<_, _>>,
+				));
+
+				Ok(move |deny_unsafe, subscriptions: sc_rpc::Subscriptions| -> RpcExtension {
+					let deps = node_rpc::FullDeps {
+						client: client.clone(),
+						pool: pool.clone(),
+						select_chain: select_chain.clone(),
+						chain_spec: chain_spec.cloned_box(),
+						deny_unsafe,
+						babe: node_rpc::BabeDeps {
+							keystore: keystore.clone(),
+							babe_config: babe_config.clone(),
+							shared_epoch_changes: shared_epoch_changes.clone(),
+						},
+						grandpa: node_rpc::GrandpaDeps {
+							shared_authority_set: shared_authority_set.clone(),
+							shared_voter_state: shared_voter_state.clone(),
+							justification_stream: justification_stream.clone(),
+							finality_proof_provider: finality_proof_provider.clone(),
+							subscriptions,
+						},
+					};
+					node_rpc::create_full(deps)
+				})
 			})?;
 
 		(builder, import_setup, inherent_data_providers)
@@ -122,14 +155,18 @@ macro_rules! new_full {
 		let (
 			is_authority,
 			force_authoring,
+			max_unfinalized_blocks,
 			name,
 			disable_grandpa,
+			grandpa_stall_threshold,
 			sentry_nodes,
 		) = (
 			$config.roles.is_authority(),
 			$config.force_authoring,
+			$config.max_unfinalized_blocks,
 			$config.name.clone(),
 			$config.disable_grandpa,
+			$config.grandpa_stall_threshold,
 			$config.network.sentry_nodes.clone(),
 		);
 
@@ -153,10 +190,37 @@ macro_rules! new_full {
 
 		($with_startup_data)(&block_import, &babe_link);
 
+		// keep the stored transactions to prove, and provide their storage proofs.
+		let transaction_storage = sc_transaction_storage::BlobStore::new(service.client());
+		service.spawn_task(
+			"transaction-storage-indexer",
+			sc_transaction_storage::index_finalized_blocks(
+				service.client(),
+				transaction_storage.clone(),
+				node_runtime::StoragePeriod::get(),
+				$crate::service::transaction_storage_call,
+			),
+		);
+		let client = service.client();
+		inherent_data_providers
+			.register_provider(sp_transaction_storage_proof::InherentDataProvider::new(move || {
+				sc_transaction_storage::build_proof(
+					&*client,
+					&transaction_storage,
+					node_runtime::StoragePeriod::get(),
+					&$crate::service::transaction_storage_call,
+				).or_else(|e| {
+					log::warn!("Unable to build the transaction storage proof: {:?}", e);
+					Ok::<_, sp_inherents::Error>(None)
+				})
+			}))
+			.map_err(|e| sc_service::Error::Other(format!("{:?}", e)))?;
+
 		if participates_in_consensus {
 			let proposer = sc_basic_authorship::ProposerFactory::new(
 				service.client(),
-				service.transaction_pool()
+				service.transaction_pool(),
+				service.prometheus_registry().as_ref(),
 			);
 
 			let client = service.client();
@@ -167,14 +231,18 @@ macro_rules! new_full {
 				sp_consensus::CanAuthorWithNativeVersion::new(client.executor().clone());
 
 			let babe_config = sc_consensus_babe::BabeParams {
-				keystore: service.keystore(),
+				keystore: service.signing_keystore(),
 				client,
 				select_chain,
 				env: proposer,
 				block_import,
 				sync_oracle: service.network(),
-				inherent_data_providers: inherent_data_providers.clone(),
+				create_inherent_data_providers: inherent_data_providers.clone(),
 				force_authoring,
+				backoff_authoring_blocks: max_unfinalized_blocks
+					.map(sc_consensus_babe::BackoffAuthoringOnFinalizedHeadLagging::new),
+				// leave the rest of the slot for the block to be imported and propagated.
+				block_proposal_slot_portion: sc_consensus_babe::SlotProportion::new(2f32 / 3f32),
 				babe_link,
 				can_author_with,
 			};
@@ -202,7 +270,7 @@ macro_rules! new_full {
 		// if the node isn't actively participating in consensus then it doesn't
 		// need a keystore, regardless of which protocol we use below.
 		let keystore = if participates_in_consensus {
-			Some(service.keystore())
+			Some(service.signing_keystore())
 		} else {
 			None
 		};
@@ -215,6 +283,7 @@ macro_rules! new_full {
 			observer_enabled: false,
 			keystore,
 			is_authority,
+			stall_threshold: grandpa_stall_threshold,
 		};
 
 		let enable_grandpa = !disable_grandpa;
@@ -249,6 +318,35 @@ macro_rules! new_full {
 			)?;
 		}
 
+		// only the nodes participating in consensus vote, the others just collect the
+		// commitments signed by the BEEFY authorities.
+		let beefy_key_store = if participates_in_consensus {
+			service.keystore()
+		} else {
+			sc_keystore::Store::new_in_memory()
+		};
+		let (signed_commitment_sender, signed_commitments) = futures::channel::mpsc::unbounded();
+		let beefy_params = sc_beefy::BeefyParams {
+			client: service.client(),
+			key_store: beefy_key_store,
+			network: service.network(),
+			signed_commitment_sender,
+			min_block_delta: 8,
+			prometheus_registry: service.prometheus_registry(),
+		};
+		service.spawn_task("beefy-gadget", sc_beefy::start_beefy_gadget(beefy_params));
+		service.spawn_task(
+			"beefy-signed-commitments",
+			signed_commitments.for_each(|commitment| {
+				log::debug!(
+					target: "beefy",
+					"Signed commitment for block #{}",
+					commitment.commitment.block_number,
+				);
+				future::ready(())
+			}),
+		);
+
 		Ok((service, inherent_data_providers))
 	}};
 	($config:expr) => {{
@@ -256,6 +354,24 @@ macro_rules! new_full {
 	}}
 }
 
+/// The call of the transaction storage module made by the extrinsic, if any.
+fn transaction_storage_call(
+	extrinsic: &<Block as BlockT>::Extrinsic,
+) -> Option<sc_transaction_storage::StorageCall<node_primitives::BlockNumber>> {
+	use codec::{Encode, Decode};
+	use pallet_transaction_storage::Call as TransactionStorageCall;
+	use sc_transaction_storage::StorageCall;
+
+	let extrinsic = node_runtime::UncheckedExtrinsic::decode(&mut &extrinsic.encode()[..]).ok()?;
+	match extrinsic.function {
+		node_runtime::Call::TransactionStorage(TransactionStorageCall::store(data)) =>
+			Some(StorageCall::Store(data)),
+		node_runtime::Call::TransactionStorage(TransactionStorageCall::renew(block, index)) =>
+			Some(StorageCall::Renew(block, index)),
+		_ => None,
+	}
+}
+
 type ConcreteBlock = node_primitives::Block;
 type ConcreteClient =
 	Client<
@@ -302,16 +418,19 @@ pub fn new_light(config: Configuration)
 		.with_select_chain(|_config, backend| {
 			Ok(LongestChain::new(backend.clone()))
 		})?
-		.with_transaction_pool(|config, client, fetcher| {
+		.with_transaction_pool(|config, client, fetcher, prometheus_registry| {
 			let fetcher = fetcher
 				.ok_or_else(|| "Trying to start light transaction pool without active fetcher")?;
 			let pool_api = sc_transaction_pool::LightChainApi::new(client.clone(), fetcher.clone());
 			let pool = sc_transaction_pool::BasicPool::with_revalidation_type(
-				config, Arc::new(pool_api), sc_transaction_pool::RevalidationType::Light,
+				config,
+				Arc::new(pool_api),
+				prometheus_registry,
+				sc_transaction_pool::RevalidationType::Light,
 			);
 			Ok(pool)
 		})?
-		.with_import_queue_and_fprb(|_config, client, backend, fetcher, _select_chain, _tx_pool| {
+		.with_import_queue_and_fprb(|config, client, backend, fetcher, _select_chain, _tx_pool| {
 			let fetch_checker = fetcher
 				.map(|fetcher| fetcher.checker().clone())
 				.ok_or_else(|| "Trying to start light import queue without active fetch checker")?;
@@ -339,6 +458,7 @@ pub fn new_light(config: Configuration)
 				Some(Box::new(finality_proof_import)),
 				client.clone(),
 				inherent_data_providers.clone(),
+				config.prometheus_registry(),
 			)?;
 
 			Ok((import_queue, finality_proof_request_builder))
@@ -423,6 +543,7 @@ mod tests {
 				service.transaction_pool().maintain(
 					ChainEvent::NewBlock {
 						is_new_best: true,
+						is_major_syncing: false,
 						id: block_id.clone(),
 						retracted: vec![],
 						header: parent_header,
@@ -488,6 +609,7 @@ mod tests {
 			.expect("Creates keystore");
 		let alice = keystore.write().insert_ephemeral_from_seed::<sc_consensus_babe::AuthorityPair>("//Alice")
 			.expect("Creates authority pair");
+		let keystore: sp_core::traits::BareCryptoStorePtr = keystore;
 
 		let chain_spec = crate::chain_spec::tests::integration_test_config_with_single_authority();
 
@@ -526,6 +648,7 @@ mod tests {
 					service.transaction_pool().maintain(
 						ChainEvent::NewBlock {
 							is_new_best: true,
+							is_major_syncing: false,
 							id: parent_id.clone(),
 							retracted: vec![],
 							header: parent_header.clone(),
@@ -535,7 +658,8 @@ mod tests {
 
 				let mut proposer_factory = sc_basic_authorship::ProposerFactory::new(
 					service.client(),
-					service.transaction_pool()
+					service.transaction_pool(),
+					None,
 				);
 
 				let epoch_descriptor = babe_link.epoch_changes().lock().epoch_descriptor_for_child_of(
@@ -551,6 +675,7 @@ mod tests {
 				// so we must keep trying the next slots until we can claim one.
 				let babe_pre_digest = loop {
 					inherent_data.replace_data(sp_timestamp::INHERENT_IDENTIFIER, &(slot_num * SLOT_DURATION));
+					inherent_data.replace_data(sp_consensus_babe::inherents::INHERENT_IDENTIFIER, &slot_num);
 					if let Some(babe_pre_digest) = sc_consensus_babe::test_helpers::claim_slot(
 						slot_num,
 						&parent_header,
@@ -612,7 +737,7 @@ mod tests {
 
 				let check_version = frame_system::CheckVersion::new();
 				let check_genesis = frame_system::CheckGenesis::new();
-				let check_era = frame_system::CheckEra::from(Era::Immortal);
+				let check_era = frame_system::CheckMortality::from(Era::Immortal);
 				let check_nonce = frame_system::CheckNonce::from(index);
 				let check_weight = frame_system::CheckWeight::new();
 				let payment = pallet_transaction_payment::ChargeTransactionPayment::from(0);
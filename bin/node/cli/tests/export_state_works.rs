@@ -0,0 +1,51 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+#![cfg(unix)]
+
+use assert_cmd::cargo::cargo_bin;
+use std::process::Command;
+use tempfile::tempdir;
+
+mod common;
+
+#[test]
+fn export_state_works() {
+	let base_path = tempdir().expect("could not create a temp dir");
+
+	common::run_dev_node_for_a_while(base_path.path());
+
+	let output = Command::new(cargo_bin("substrate"))
+		.args(&["export-state", "--dev", "--pruning", "archive", "-d"])
+		.arg(base_path.path())
+		.arg("1")
+		.output()
+		.unwrap();
+	assert!(output.status.success());
+
+	let spec: serde_json::Value = serde_json::from_slice(output.stdout.as_slice()).unwrap();
+	assert!(spec["genesis"]["raw"]["top"].as_object().map_or(false, |top| !top.is_empty()));
+
+	let output = Command::new(cargo_bin("substrate"))
+		.args(&["export-state", "--dev", "--pruning", "archive", "--storage-only", "-d"])
+		.arg(base_path.path())
+		.output()
+		.unwrap();
+	assert!(output.status.success());
+
+	let storage: serde_json::Value = serde_json::from_slice(output.stdout.as_slice()).unwrap();
+	assert!(storage["top"].as_object().map_or(false, |top| !top.is_empty()));
+}
@@ -40,7 +40,7 @@ fn import_export_and_revert_work() {
 	let metadata = fs::metadata(&exported_blocks).unwrap();
 	assert!(metadata.len() > 0, "file exported_blocks should not be empty");
 
-	let _ = fs::remove_dir_all(base_path.path().join("db"));
+	fs::remove_dir_all(base_path.path().join("chains/dev/db")).unwrap();
 
 	let status = Command::new(cargo_bin("substrate"))
 		.args(&["import-blocks", "--dev", "--pruning", "archive", "-d"])
@@ -50,6 +50,25 @@ fn import_export_and_revert_work() {
 		.unwrap();
 	assert!(status.success());
 
+	let exported_blocks_binary = base_path.path().join("exported_blocks.bin");
+	let status = Command::new(cargo_bin("substrate"))
+		.args(&["export-blocks", "--dev", "--pruning", "archive", "--binary", "true", "-d"])
+		.arg(base_path.path())
+		.arg(&exported_blocks_binary)
+		.status()
+		.unwrap();
+	assert!(status.success());
+
+	fs::remove_dir_all(base_path.path().join("chains/dev/db")).unwrap();
+
+	let status = Command::new(cargo_bin("substrate"))
+		.args(&["import-blocks", "--dev", "--pruning", "archive", "--binary", "true", "-d"])
+		.arg(base_path.path())
+		.arg(&exported_blocks_binary)
+		.status()
+		.unwrap();
+	assert!(status.success());
+
 	let status = Command::new(cargo_bin("substrate"))
 		.args(&["revert", "--dev", "--pruning", "archive", "-d"])
 		.arg(base_path.path())
@@ -39,3 +39,25 @@ fn purge_chain_works() {
 	assert!(base_path.path().join("chains/dev/").exists());
 	assert!(!base_path.path().join("chains/dev/db").exists());
 }
+
+#[test]
+#[cfg(unix)]
+fn purge_chain_exports_the_blocks_first() {
+	let base_path = tempdir().expect("could not create a temp dir");
+	let blocks_path = base_path.path().join("blocks.bin");
+
+	common::run_dev_node_for_a_while(base_path.path());
+
+	let status = Command::new(cargo_bin("substrate"))
+		.args(&["purge-chain", "--dev", "-d"])
+		.arg(base_path.path())
+		.arg("--export-blocks")
+		.arg(&blocks_path)
+		.arg("-y")
+		.status()
+		.unwrap();
+	assert!(status.success());
+
+	assert!(!base_path.path().join("chains/dev/db").exists());
+	assert!(blocks_path.metadata().map(|m| m.len() > 0).unwrap_or(false));
+}
@@ -0,0 +1,51 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+#![cfg(unix)]
+
+use assert_cmd::cargo::cargo_bin;
+use nix::sys::signal::{kill, Signal::SIGINT};
+use nix::unistd::Pid;
+use std::{convert::TryInto, process::{Command, Stdio}, thread, time::Duration};
+use tempfile::tempdir;
+
+mod common;
+
+#[test]
+fn stress_works() {
+	let base_path = tempdir().expect("could not create a temp dir");
+
+	let mut node = Command::new(cargo_bin("substrate"))
+		.args(&["--dev", "--rpc-port", "19933", "--ws-port", "19944", "--port", "30934", "-d"])
+		.arg(base_path.path())
+		.spawn()
+		.unwrap();
+
+	// Let the RPC server start.
+	thread::sleep(Duration::from_secs(10));
+
+	let status = Command::new(cargo_bin("substrate"))
+		.stdout(Stdio::null())
+		.args(&["stress", "--url", "http://127.0.0.1:19933", "--transfers", "20", "--tps", "20"])
+		.status()
+		.unwrap();
+
+	kill(Pid::from_raw(node.id().try_into().unwrap()), SIGINT).unwrap();
+	assert!(common::wait_for(&mut node, 30).map(|x| x.success()).unwrap_or_default());
+
+	// the command fails unless some of the transfers were included.
+	assert!(status.success());
+}
@@ -138,7 +138,7 @@ fn should_submit_signed_twice_from_the_same_account() {
 fn submitted_transaction_should_be_valid() {
 	use codec::Encode;
 	use frame_support::storage::StorageMap;
-	use sp_runtime::transaction_validity::ValidTransaction;
+	use sp_runtime::transaction_validity::{ValidTransaction, TransactionSource};
 	use sp_runtime::traits::StaticLookup;
 
 	let mut t = new_test_ext(COMPACT_CODE, false);
@@ -172,7 +172,7 @@ fn submitted_transaction_should_be_valid() {
 		<frame_system::Account<Runtime>>::insert(&address, account);
 
 		// check validity
-		let res = Executive::validate_transaction(extrinsic);
+		let res = Executive::validate_transaction(TransactionSource::External, extrinsic);
 
 		assert_eq!(res.unwrap(), ValidTransaction {
 			priority: 2_411_002_000_000,
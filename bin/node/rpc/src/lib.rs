@@ -31,17 +31,23 @@
 
 use std::{sync::Arc, fmt};
 
-use node_primitives::{Block, BlockNumber, AccountId, Index, Balance};
+use node_primitives::{Block, BlockNumber, AccountId, Index, Balance, Hash};
 use node_runtime::UncheckedExtrinsic;
 use sp_api::ProvideRuntimeApi;
 use sp_transaction_pool::TransactionPool;
 use sp_blockchain::{Error as BlockChainError, HeaderMetadata, HeaderBackend};
 use sp_consensus::SelectChain;
-use sc_keystore::KeyStorePtr;
+use sp_core::traits::BareCryptoStorePtr;
 use sp_consensus_babe::BabeApi;
 use sc_consensus_epochs::SharedEpochChanges;
 use sc_consensus_babe::{Config, Epoch};
 use sc_consensus_babe_rpc::BabeRPCHandler;
+use sc_finality_grandpa::{
+	FinalityProofProvider, GrandpaJustificationStream, SharedAuthoritySet, SharedVoterState,
+};
+use sc_finality_grandpa_rpc::GrandpaRpcHandler;
+use sc_rpc_api::{DenyUnsafe, Subscriptions};
+use sc_sync_state_rpc::{SyncStateRpcApi, SyncStateRpcHandler};
 
 /// Light client extra dependencies.
 pub struct LightDeps<C, F, P> {
@@ -62,36 +68,57 @@ pub struct BabeDeps {
 	/// BABE pending epoch changes.
 	pub shared_epoch_changes: SharedEpochChanges<Block, Epoch>,
 	/// The keystore that manages the keys of the node.
-	pub keystore: KeyStorePtr,
+	pub keystore: BareCryptoStorePtr,
+}
+
+/// Extra dependencies for GRANDPA.
+pub struct GrandpaDeps<B> {
+	/// The authority set, kept up to date by the block import.
+	pub shared_authority_set: SharedAuthoritySet<Hash, BlockNumber>,
+	/// Voting round info.
+	pub shared_voter_state: SharedVoterState,
+	/// Receives notifications about justification events from GRANDPA.
+	pub justification_stream: GrandpaJustificationStream<Block>,
+	/// Finality proof provider.
+	pub finality_proof_provider: Arc<FinalityProofProvider<B, Block>>,
+	/// Subscription manager of the RPC server the extensions are built for.
+	pub subscriptions: Subscriptions,
 }
 
 /// Full client dependencies.
-pub struct FullDeps<C, P, SC> {
+pub struct FullDeps<C, P, SC, B> {
 	/// The client instance to use.
 	pub client: Arc<C>,
 	/// Transaction pool instance.
 	pub pool: Arc<P>,
 	/// The SelectChain Strategy
 	pub select_chain: SC,
+	/// The chain spec the node is running.
+	pub chain_spec: Box<dyn sc_chain_spec::ChainSpec>,
+	/// Whether to deny unsafe calls
+	pub deny_unsafe: DenyUnsafe,
 	/// BABE specific dependencies.
 	pub babe: BabeDeps,
+	/// GRANDPA specific dependencies.
+	pub grandpa: GrandpaDeps<B>,
 }
 
 /// Instantiate all Full RPC extensions.
-pub fn create_full<C, P, M, SC>(
-	deps: FullDeps<C, P, SC>,
-) -> jsonrpc_core::IoHandler<M> where
+pub fn create_full<C, P, SC, B>(
+	deps: FullDeps<C, P, SC, B>,
+) -> jsonrpc_core::IoHandler<sc_rpc::Metadata> where
 	C: ProvideRuntimeApi<Block>,
 	C: HeaderBackend<Block> + HeaderMetadata<Block, Error=BlockChainError> + 'static,
-	C: Send + Sync + 'static,
+	C: sc_client_api::AuxStore + Send + Sync + 'static,
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Index>,
+	C::Api: sp_block_builder::BlockBuilder<Block>,
 	C::Api: pallet_contracts_rpc::ContractsRuntimeApi<Block, AccountId, Balance, BlockNumber>,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance, UncheckedExtrinsic>,
 	C::Api: BabeApi<Block>,
 	<C::Api as sp_api::ApiErrorExt>::Error: fmt::Debug,
 	P: TransactionPool + 'static,
-	M: jsonrpc_core::Metadata + Default,
 	SC: SelectChain<Block> +'static,
+	B: sc_client_api::Backend<Block> + Send + Sync + 'static,
 {
 	use substrate_frame_rpc_system::{FullSystem, SystemApi};
 	use pallet_contracts_rpc::{Contracts, ContractsApi};
@@ -102,16 +129,26 @@ pub fn create_full<C, P, M, SC>(
 		client,
 		pool,
 		select_chain,
-		babe
+		chain_spec,
+		deny_unsafe,
+		babe,
+		grandpa,
 	} = deps;
 	let BabeDeps {
 		keystore,
 		babe_config,
 		shared_epoch_changes,
 	} = babe;
+	let GrandpaDeps {
+		shared_authority_set,
+		shared_voter_state,
+		justification_stream,
+		finality_proof_provider,
+		subscriptions,
+	} = grandpa;
 
 	io.extend_with(
-		SystemApi::to_delegate(FullSystem::new(client.clone(), pool))
+		SystemApi::to_delegate(FullSystem::new(client.clone(), pool, deny_unsafe))
 	);
 	// Making synchronous calls in light client freezes the browser currently,
 	// more context: https://github.com/paritytech/substrate/pull/3480
@@ -124,7 +161,35 @@ pub fn create_full<C, P, M, SC>(
 	);
 	io.extend_with(
 		sc_consensus_babe_rpc::BabeApi::to_delegate(
-			BabeRPCHandler::new(client, shared_epoch_changes, keystore, babe_config, select_chain)
+			BabeRPCHandler::new(
+				client.clone(),
+				shared_epoch_changes.clone(),
+				keystore,
+				babe_config,
+				select_chain,
+				deny_unsafe,
+			)
+		)
+	);
+	io.extend_with(
+		sc_finality_grandpa_rpc::GrandpaApi::to_delegate(
+			GrandpaRpcHandler::new(
+				shared_voter_state,
+				justification_stream,
+				finality_proof_provider,
+				subscriptions,
+			)
+		)
+	);
+	io.extend_with(
+		SyncStateRpcApi::to_delegate(
+			SyncStateRpcHandler::new(
+				chain_spec,
+				client,
+				shared_authority_set,
+				shared_epoch_changes,
+				deny_unsafe,
+			)
 		)
 	);
 
@@ -151,7 +216,9 @@ pub fn create_light<C, P, M, F>(
 	} = deps;
 	let mut io = jsonrpc_core::IoHandler::default();
 	io.extend_with(
-		SystemApi::<AccountId, Index>::to_delegate(LightSystem::new(client, remote_blockchain, fetcher, pool))
+		SystemApi::<_, AccountId, Index>::to_delegate(
+			LightSystem::new(client, remote_blockchain, fetcher, pool)
+		)
 	);
 
 	io
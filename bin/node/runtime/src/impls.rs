@@ -16,11 +16,12 @@
 
 //! Some configurable implementations as associated type for the substrate runtime.
 
+use codec::{Encode, Decode};
 use node_primitives::Balance;
 use sp_runtime::traits::{Convert, Saturating};
-use sp_runtime::{Fixed64, Perbill};
-use frame_support::{traits::{OnUnbalanced, Currency, Get}, weights::Weight};
-use crate::{Balances, System, Authorship, MaximumBlockWeight, NegativeImbalance};
+use sp_runtime::{Fixed64, Perbill, RuntimeDebug};
+use frame_support::{traits::{OnUnbalanced, Currency, Get, InstanceFilter}, weights::Weight};
+use crate::{Balances, System, Authorship, MaximumBlockWeight, NegativeImbalance, Call};
 
 pub struct Author;
 impl OnUnbalanced<NegativeImbalance> for Author {
@@ -45,6 +46,35 @@ impl Convert<u128, Balance> for CurrencyToVoteHandler {
 	fn convert(x: u128) -> Balance { x * Self::factor() }
 }
 
+/// The calls a rescuer may make through a recovered account.
+#[derive(Copy, Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub enum RecoveryCallFilter {
+	/// Only the calls needed to reclaim the funds of the account and clean it up.
+	Reclaim,
+	/// Any call.
+	Any,
+}
+
+impl Default for RecoveryCallFilter {
+	fn default() -> Self { RecoveryCallFilter::Reclaim }
+}
+
+impl InstanceFilter<Call> for RecoveryCallFilter {
+	fn filter(&self, c: &Call) -> bool {
+		match self {
+			RecoveryCallFilter::Any => true,
+			RecoveryCallFilter::Reclaim => match c {
+				Call::Balances(..) | Call::Recovery(..) => true,
+				_ => false,
+			},
+		}
+	}
+
+	fn is_superset(&self, o: &Self) -> bool {
+		self == o || *self == RecoveryCallFilter::Any
+	}
+}
+
 /// Convert from weight to balance via a simple coefficient multiplication
 /// The associated type C encapsulates a constant in units of balance per weight
 pub struct LinearWeightToFee<C>(sp_std::marker::PhantomData<C>);
@@ -24,7 +24,7 @@ use sp_std::prelude::*;
 use frame_support::{
 	construct_runtime, parameter_types, debug,
 	weights::Weight,
-	traits::{Currency, Randomness, OnUnbalanced, Imbalance},
+	traits::{Currency, Randomness, OnUnbalanced, Imbalance, KeyOwnerProofSystem},
 };
 use sp_core::u32_trait::{_1, _2, _3, _4};
 pub use node_primitives::{AccountId, Signature};
@@ -35,23 +35,25 @@ use sp_runtime::{
 	impl_opaque_keys, generic, create_runtime_str,
 };
 use sp_runtime::curve::PiecewiseLinear;
-use sp_runtime::transaction_validity::TransactionValidity;
+use sp_runtime::transaction_validity::{TransactionValidity, TransactionSource};
 use sp_runtime::traits::{
 	self, BlakeTwo256, Block as BlockT, StaticLookup, SaturatedConversion,
-	ConvertInto, OpaqueKeys,
+	ConvertInto, OpaqueKeys, NumberFor,
 };
 use sp_version::RuntimeVersion;
 #[cfg(any(feature = "std", test))]
 use sp_version::NativeVersion;
-use sp_core::OpaqueMetadata;
-use pallet_grandpa::AuthorityList as GrandpaAuthorityList;
+use sp_core::{OpaqueMetadata, crypto::KeyTypeId};
+use pallet_grandpa::{AuthorityId as GrandpaId, AuthorityList as GrandpaAuthorityList};
 use pallet_grandpa::fg_primitives;
 use pallet_im_online::sr25519::{AuthorityId as ImOnlineId};
 use sp_authority_discovery::AuthorityId as AuthorityDiscoveryId;
+use sp_beefy::{AuthorityId as BeefyId, ValidatorSet};
 use pallet_transaction_payment_rpc_runtime_api::RuntimeDispatchInfo;
 use pallet_contracts_rpc_runtime_api::ContractExecResult;
+use pallet_staking_rpc_runtime_api::NominatorSlashes;
 use frame_system::offchain::TransactionSubmitter;
-use sp_inherents::{InherentData, CheckInherentsResult};
+use sp_inherents::{InherentData, InherentIdentifier, CheckInherentsResult};
 
 #[cfg(any(feature = "std", test))]
 pub use sp_runtime::BuildStorage;
@@ -63,7 +65,9 @@ pub use pallet_staking::StakerStatus;
 
 /// Implementations of some helper traits passed into runtime modules as associated types.
 pub mod impls;
-use impls::{CurrencyToVoteHandler, Author, LinearWeightToFee, TargetedFeeAdjustment};
+use impls::{
+	CurrencyToVoteHandler, Author, LinearWeightToFee, TargetedFeeAdjustment, RecoveryCallFilter,
+};
 
 /// Constant values used within the runtime.
 pub mod constants;
@@ -82,9 +86,10 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	// and set impl_version to 0. If only runtime
 	// implementation changes and behavior does not, then leave spec_version as
 	// is and increment impl_version.
-	spec_version: 237,
+	spec_version: 250,
 	impl_version: 0,
 	apis: RUNTIME_API_VERSIONS,
+	transaction_version: 2,
 };
 
 /// Native version.
@@ -141,6 +146,7 @@ impl frame_system::Trait for Runtime {
 	type ModuleToIndex = ModuleToIndex;
 	type AccountData = pallet_balances::AccountData<Balance>;
 	type MigrateAccount = (Balances, Identity, Democracy, Elections, ImOnline, Recovery, Session, Society, Staking, Vesting);
+	type MigrateAccountData = ();
 	type OnNewAccount = ();
 	type OnKilledAccount = ();
 }
@@ -171,6 +177,20 @@ impl pallet_babe::Trait for Runtime {
 	type EpochDuration = EpochDuration;
 	type ExpectedBlockTime = ExpectedBlockTime;
 	type EpochChangeTrigger = pallet_babe::ExternalTrigger;
+
+	type KeyOwnerProofSystem = pallet_session::historical::Module<Self>;
+
+	type KeyOwnerProof = <Self::KeyOwnerProofSystem as KeyOwnerProofSystem<(
+		KeyTypeId,
+		pallet_babe::AuthorityId,
+	)>>::Proof;
+
+	type KeyOwnerIdentification = <Self::KeyOwnerProofSystem as KeyOwnerProofSystem<(
+		KeyTypeId,
+		pallet_babe::AuthorityId,
+	)>>::IdentificationTuple;
+
+	type HandleEquivocation = pallet_babe::EquivocationHandler<Offences, SubmitTransaction>;
 }
 
 parameter_types! {
@@ -240,6 +260,7 @@ impl_opaque_keys! {
 		pub babe: Babe,
 		pub im_online: ImOnline,
 		pub authority_discovery: AuthorityDiscovery,
+		pub beefy: Beefy,
 	}
 }
 
@@ -261,6 +282,7 @@ impl pallet_session::Trait for Runtime {
 impl pallet_session::historical::Trait for Runtime {
 	type FullIdentification = pallet_staking::Exposure<AccountId, Balance>;
 	type FullIdentificationOf = pallet_staking::ExposureOf<Runtime>;
+	type HistoricalFullIdentificationOf = pallet_staking::HistoricalExposureOf<Runtime>;
 }
 
 pallet_staking_reward_curve::build! {
@@ -280,6 +302,11 @@ parameter_types! {
 	pub const SlashDeferDuration: pallet_staking::EraIndex = 24 * 7; // 1/4 the bonding duration.
 	pub const RewardCurve: &'static PiecewiseLinear<'static> = &REWARD_CURVE;
 	pub const MaxNominatorRewardedPerValidator: u32 = 64;
+	pub const MaxCommissionChangePerEra: Perbill = Perbill::from_percent(1);
+	pub const MinCommission: Perbill = Perbill::zero();
+	// the unresponsiveness of a few validators is not slashed by im-online.
+	pub const DisableOnlyThreshold: Perbill = Perbill::zero();
+	pub const MaxDisabledValidators: Perbill = Perbill::from_percent(33);
 }
 
 impl pallet_staking::Trait for Runtime {
@@ -296,8 +323,14 @@ impl pallet_staking::Trait for Runtime {
 	/// A super-majority of the council can cancel the slash.
 	type SlashCancelOrigin = pallet_collective::EnsureProportionAtLeast<_3, _4, AccountId, CouncilCollective>;
 	type SessionInterface = Self;
+	type DisablingStrategy = pallet_staking::DisableBelowThreshold<
+		DisableOnlyThreshold,
+		MaxDisabledValidators,
+	>;
 	type RewardCurve = RewardCurve;
 	type MaxNominatorRewardedPerValidator = MaxNominatorRewardedPerValidator;
+	type MaxCommissionChangePerEra = MaxCommissionChangePerEra;
+	type MinCommission = MinCommission;
 }
 
 parameter_types! {
@@ -461,11 +494,18 @@ impl pallet_contracts::Trait for Runtime {
 	type MaxDepth = pallet_contracts::DefaultMaxDepth;
 	type MaxValueSize = pallet_contracts::DefaultMaxValueSize;
 	type BlockGasLimit = pallet_contracts::DefaultBlockGasLimit;
+	type RemoveKeysLimit = pallet_contracts::DefaultRemoveKeysLimit;
+}
+
+parameter_types! {
+	pub const MaxAnnouncementsPerBlock: u32 = 16;
 }
 
 impl pallet_sudo::Trait for Runtime {
 	type Event = Event;
 	type Call = Call;
+	type AnnounceDelay = ();
+	type MaxAnnouncementsPerBlock = MaxAnnouncementsPerBlock;
 }
 
 /// A runtime transaction submitter.
@@ -492,8 +532,22 @@ impl pallet_offences::Trait for Runtime {
 
 impl pallet_authority_discovery::Trait for Runtime {}
 
+impl pallet_beefy::Trait for Runtime {}
+
 impl pallet_grandpa::Trait for Runtime {
 	type Event = Event;
+
+	type KeyOwnerProofSystem = pallet_session::historical::Module<Self>;
+
+	type KeyOwnerProof =
+		<Self::KeyOwnerProofSystem as KeyOwnerProofSystem<(KeyTypeId, GrandpaId)>>::Proof;
+
+	type KeyOwnerIdentification = <Self::KeyOwnerProofSystem as KeyOwnerProofSystem<(
+		KeyTypeId,
+		GrandpaId,
+	)>>::IdentificationTuple;
+
+	type HandleEquivocation = pallet_grandpa::EquivocationHandler<Offences, SubmitTransaction>;
 }
 
 parameter_types! {
@@ -539,10 +593,7 @@ impl frame_system::offchain::CreateTransaction<Runtime, UncheckedExtrinsic> for
 		index: Index,
 	) -> Option<(Call, <UncheckedExtrinsic as traits::Extrinsic>::SignaturePayload)> {
 		// take the biggest period possible.
-		let period = BlockHashCount::get()
-			.checked_next_power_of_two()
-			.map(|c| c / 2)
-			.unwrap_or(2) as u64;
+		let period = System::max_mortality_period();
 		let current_block = System::block_number()
 			.saturated_into::<u64>()
 			// The `System::block_number` is initialized with `n+1`,
@@ -552,7 +603,9 @@ impl frame_system::offchain::CreateTransaction<Runtime, UncheckedExtrinsic> for
 		let extra: SignedExtra = (
 			frame_system::CheckVersion::<Runtime>::new(),
 			frame_system::CheckGenesis::<Runtime>::new(),
-			frame_system::CheckEra::<Runtime>::from(generic::Era::mortal(period, current_block)),
+			frame_system::CheckMortality::<Runtime>::from(
+				generic::Era::mortal(period, current_block),
+			),
 			frame_system::CheckNonce::<Runtime>::from(index),
 			frame_system::CheckWeight::<Runtime>::new(),
 			pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(tip),
@@ -583,6 +636,7 @@ impl pallet_recovery::Trait for Runtime {
 	type FriendDepositFactor = FriendDepositFactor;
 	type MaxFriends = MaxFriends;
 	type RecoveryDeposit = RecoveryDeposit;
+	type CallFilter = RecoveryCallFilter;
 }
 
 parameter_types! {
@@ -622,6 +676,25 @@ impl pallet_vesting::Trait for Runtime {
 	type MinVestedTransfer = MinVestedTransfer;
 }
 
+parameter_types! {
+	pub const StorageByteFee: Balance = 10 * MILLICENTS;
+	pub const StorageEntryFee: Balance = 1 * CENTS;
+	pub const MaxStoredTransactionSize: u32 = 1024 * 1024;
+	pub const MaxBlockStoredTransactions: u32 = 64;
+	pub const StoragePeriod: BlockNumber = 7 * DAYS;
+}
+
+impl pallet_transaction_storage::Trait for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type FeeDestination = DealWithFees;
+	type ByteFee = StorageByteFee;
+	type EntryFee = StorageEntryFee;
+	type MaxTransactionSize = MaxStoredTransactionSize;
+	type MaxBlockTransactions = MaxBlockStoredTransactions;
+	type StoragePeriod = StoragePeriod;
+}
+
 construct_runtime!(
 	pub enum Runtime where
 		Block = Block,
@@ -630,7 +703,7 @@ construct_runtime!(
 	{
 		System: frame_system::{Module, Call, Config, Storage, Event<T>},
 		Utility: pallet_utility::{Module, Call, Storage, Event<T>},
-		Babe: pallet_babe::{Module, Call, Storage, Config, Inherent(Timestamp)},
+		Babe: pallet_babe::{Module, Call, Storage, Config, Inherent(Timestamp), ValidateUnsigned},
 		Timestamp: pallet_timestamp::{Module, Call, Storage, Inherent},
 		Authorship: pallet_authorship::{Module, Call, Storage, Inherent},
 		Indices: pallet_indices::{Module, Call, Storage, Config<T>, Event<T>},
@@ -644,7 +717,7 @@ construct_runtime!(
 		Elections: pallet_elections_phragmen::{Module, Call, Storage, Event<T>},
 		TechnicalMembership: pallet_membership::<Instance1>::{Module, Call, Storage, Event<T>, Config<T>},
 		FinalityTracker: pallet_finality_tracker::{Module, Call, Inherent},
-		Grandpa: pallet_grandpa::{Module, Call, Storage, Config, Event},
+		Grandpa: pallet_grandpa::{Module, Call, Storage, Config, Event, ValidateUnsigned},
 		Treasury: pallet_treasury::{Module, Call, Storage, Config, Event<T>},
 		Contracts: pallet_contracts::{Module, Call, Config<T>, Storage, Event<T>},
 		Sudo: pallet_sudo::{Module, Call, Config<T>, Storage, Event<T>},
@@ -656,6 +729,8 @@ construct_runtime!(
 		Society: pallet_society::{Module, Call, Storage, Event<T>, Config<T>},
 		Recovery: pallet_recovery::{Module, Call, Storage, Event<T>},
 		Vesting: pallet_vesting::{Module, Call, Storage, Event<T>, Config<T>},
+		TransactionStorage: pallet_transaction_storage::{Module, Call, Storage, Event<T>, Inherent},
+		Beefy: pallet_beefy::{Module, Call, Storage, Config},
 	}
 );
 
@@ -673,7 +748,7 @@ pub type BlockId = generic::BlockId<Block>;
 pub type SignedExtra = (
 	frame_system::CheckVersion<Runtime>,
 	frame_system::CheckGenesis<Runtime>,
-	frame_system::CheckEra<Runtime>,
+	frame_system::CheckMortality<Runtime>,
 	frame_system::CheckNonce<Runtime>,
 	frame_system::CheckWeight<Runtime>,
 	pallet_transaction_payment::ChargeTransactionPayment<Runtime>,
@@ -722,6 +797,12 @@ impl_runtime_apis! {
 			data.create_extrinsics()
 		}
 
+		fn inherent_extrinsics_with_identifiers(
+			data: InherentData,
+		) -> Vec<(InherentIdentifier, bool, <Block as BlockT>::Extrinsic)> {
+			data.create_extrinsics_with_identifiers()
+		}
+
 		fn check_inherents(block: Block, data: InherentData) -> CheckInherentsResult {
 			data.check_extrinsics(&block)
 		}
@@ -732,14 +813,18 @@ impl_runtime_apis! {
 	}
 
 	impl sp_transaction_pool::runtime_api::TaggedTransactionQueue<Block> for Runtime {
-		fn validate_transaction(tx: <Block as BlockT>::Extrinsic) -> TransactionValidity {
-			Executive::validate_transaction(tx)
+		fn validate_transaction(
+			source: TransactionSource,
+			tx: <Block as BlockT>::Extrinsic,
+		) -> TransactionValidity {
+			Executive::validate_transaction(source, tx)
 		}
 	}
 
 	impl sp_offchain::OffchainWorkerApi<Block> for Runtime {
 		fn offchain_worker(header: &<Block as BlockT>::Header) {
-			Executive::offchain_worker(header)
+			Executive::offchain_worker(header);
+			pallet_session::historical::offchain::store_session_tries::<Runtime>();
 		}
 	}
 
@@ -747,6 +832,34 @@ impl_runtime_apis! {
 		fn grandpa_authorities() -> GrandpaAuthorityList {
 			Grandpa::grandpa_authorities()
 		}
+
+		fn submit_report_equivocation_unsigned_extrinsic(
+			equivocation_proof: fg_primitives::EquivocationProof<
+				<Block as BlockT>::Hash,
+				NumberFor<Block>,
+			>,
+			key_owner_proof: fg_primitives::OpaqueKeyOwnershipProof,
+		) -> Option<()> {
+			let key_owner_proof = key_owner_proof.decode()?;
+
+			Grandpa::submit_unsigned_equivocation_report(
+				equivocation_proof,
+				key_owner_proof,
+			)
+		}
+
+		fn generate_key_ownership_proof(
+			_set_id: fg_primitives::SetId,
+			authority_id: GrandpaId,
+		) -> Option<fg_primitives::OpaqueKeyOwnershipProof> {
+			use codec::Encode;
+
+			<pallet_session::historical::Module<Runtime>>::prove(
+				(fg_primitives::KEY_TYPE, authority_id),
+			)
+				.map(|p| p.encode())
+				.map(fg_primitives::OpaqueKeyOwnershipProof::new)
+		}
 	}
 
 	impl sp_consensus_babe::BabeApi<Block> for Runtime {
@@ -762,24 +875,67 @@ impl_runtime_apis! {
 				c: PRIMARY_PROBABILITY,
 				genesis_authorities: Babe::authorities(),
 				randomness: Babe::randomness(),
-				secondary_slots: true,
+				allowed_slots: Babe::allowed_slots(),
 			}
 		}
 
 		fn current_epoch_start() -> sp_consensus_babe::SlotNumber {
 			Babe::current_epoch_start()
 		}
+
+		fn current_epoch() -> sp_consensus_babe::Epoch {
+			Babe::current_epoch()
+		}
+
+		fn next_epoch() -> sp_consensus_babe::Epoch {
+			Babe::next_epoch()
+		}
+
+		fn generate_key_ownership_proof(
+			_slot_number: sp_consensus_babe::SlotNumber,
+			authority_id: sp_consensus_babe::AuthorityId,
+		) -> Option<sp_consensus_babe::OpaqueKeyOwnershipProof> {
+			use codec::Encode;
+
+			<pallet_session::historical::Module<Runtime>>::prove(
+				(sp_consensus_babe::KEY_TYPE, authority_id),
+			)
+				.map(|p| p.encode())
+				.map(sp_consensus_babe::OpaqueKeyOwnershipProof::new)
+		}
+
+		fn submit_report_equivocation_unsigned_extrinsic(
+			equivocation_proof: sp_consensus_babe::EquivocationProof<<Block as BlockT>::Header>,
+			key_owner_proof: sp_consensus_babe::OpaqueKeyOwnershipProof,
+		) -> Option<()> {
+			let key_owner_proof = key_owner_proof.decode()?;
+
+			Babe::submit_unsigned_equivocation_report(
+				equivocation_proof,
+				key_owner_proof,
+			)
+		}
 	}
 
 	impl sp_authority_discovery::AuthorityDiscoveryApi<Block> for Runtime {
 		fn authorities() -> Vec<AuthorityDiscoveryId> {
 			AuthorityDiscovery::authorities()
 		}
+
+		fn next_authorities() -> Vec<AuthorityDiscoveryId> {
+			AuthorityDiscovery::next_authorities()
+		}
+	}
+
+	impl sp_beefy::BeefyApi<Block> for Runtime {
+		fn validator_set() -> ValidatorSet<BeefyId> {
+			Beefy::validator_set()
+		}
 	}
 
 	impl frame_system_rpc_runtime_api::AccountNonceApi<Block, AccountId, Index> for Runtime {
 		fn account_nonce(account: AccountId) -> Index {
-			System::account_nonce(account)
+			System::account_nonce(&account)
 		}
 	}
 
@@ -828,6 +984,23 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl pallet_staking_rpc_runtime_api::StakingApi<Block, AccountId, Balance> for Runtime {
+		fn nominator_slashes(stash: AccountId) -> NominatorSlashes<AccountId, Balance> {
+			Staking::nominator_slashes(&stash)
+		}
+
+		fn planned_era_start() -> Option<sp_staking::SessionIndex> {
+			Staking::planned_era_start()
+		}
+	}
+
+	#[cfg(feature = "execute-block-no-check")]
+	impl sp_block_builder::ExecuteBlockNoCheck<Block> for Runtime {
+		fn execute_block_no_check(block: Block) {
+			Executive::execute_block_no_check(block)
+		}
+	}
+
 	impl sp_session::SessionKeys<Block> for Runtime {
 		fn generate_session_keys(seed: Option<Vec<u8>>) -> Vec<u8> {
 			SessionKeys::generate(seed)
@@ -840,6 +1013,22 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl sp_session::HistoricalSessionApi<Block> for Runtime {
+		fn generate_key_ownership_proof(
+			session: sp_staking::SessionIndex,
+			key_type: sp_core::crypto::KeyTypeId,
+			key: Vec<u8>,
+		) -> Option<Vec<u8>> {
+			use codec::Encode;
+
+			pallet_session::historical::offchain::prove_session_membership::<Runtime>(
+				session,
+				(key_type, &key[..]),
+			)
+				.map(|p| p.encode())
+		}
+	}
+
 	#[cfg(feature = "runtime-benchmarks")]
 	impl frame_benchmarking::Benchmark<Block> for Runtime {
 		fn dispatch_benchmark(
@@ -25,7 +25,7 @@ use std::{sync::Arc, path::Path, collections::BTreeMap};
 use node_primitives::Block;
 use crate::client::{Client, Backend};
 use crate::keyring::*;
-use sc_client_db::PruningMode;
+use sc_client_db::{KeepBlocks, PruningMode};
 use sc_executor::{NativeExecutor, WasmExecutionMethod};
 use sp_consensus::{
 	BlockOrigin, BlockImport, BlockImportParams,
@@ -183,7 +183,10 @@ impl BenchDb {
 		let db_config = sc_client_db::DatabaseSettings {
 			state_cache_size: 16*1024*1024,
 			state_cache_child_ratio: Some((0, 100)),
+			trie_cache_size: 16*1024*1024,
 			pruning: PruningMode::ArchiveAll,
+			keep_blocks: KeepBlocks::All,
+			cht_size: sc_client::cht::size(),
 			source: sc_client_db::DatabaseSettingsSrc::Path {
 				path: dir.into(),
 				cache_size: None,
@@ -199,6 +202,7 @@ impl BenchDb {
 			ExecutionExtensions::new(profile.into_execution_strategies(), None),
 			sp_core::tasks::executor(),
 			None,
+			Default::default(),
 		).expect("Should not fail");
 
 		(client, backend)
@@ -105,6 +105,7 @@ pub fn config_endowed(
 		}),
 		pallet_im_online: Some(Default::default()),
 		pallet_authority_discovery: Some(Default::default()),
+		pallet_beefy: Some(Default::default()),
 		pallet_democracy: Some(Default::default()),
 		pallet_collective_Instance1: Some(Default::default()),
 		pallet_collective_Instance2: Some(Default::default()),
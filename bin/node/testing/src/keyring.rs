@@ -19,6 +19,7 @@
 use sp_keyring::{AccountKeyring, Sr25519Keyring, Ed25519Keyring};
 use node_primitives::{AccountId, Balance, Index};
 use node_runtime::{CheckedExtrinsic, UncheckedExtrinsic, SessionKeys, SignedExtra};
+use sp_core::{ecdsa, Pair};
 use sp_runtime::generic::Era;
 use codec::Encode;
 
@@ -62,6 +63,10 @@ pub fn to_session_keys(
 		babe: sr25519_keyring.to_owned().public().into(),
 		im_online: sr25519_keyring.to_owned().public().into(),
 		authority_discovery: sr25519_keyring.to_owned().public().into(),
+		beefy: ecdsa::Pair::from_string(&sr25519_keyring.to_owned().to_seed(), None)
+			.expect("the seeds of the keyrings are valid; qed")
+			.public()
+			.into(),
 	}
 }
 
@@ -70,7 +75,7 @@ pub fn signed_extra(nonce: Index, extra_fee: Balance) -> SignedExtra {
 	(
 		frame_system::CheckVersion::new(),
 		frame_system::CheckGenesis::new(),
-		frame_system::CheckEra::from(Era::mortal(256, 0)),
+		frame_system::CheckMortality::from(Era::mortal(128, 0)),
 		frame_system::CheckNonce::from(nonce),
 		frame_system::CheckWeight::new(),
 		pallet_transaction_payment::ChargeTransactionPayment::from(extra_fee),
@@ -41,6 +41,10 @@ use sp_runtime::traits::{
 };
 use sp_blockchain::HeaderBackend;
 
+mod stress;
+
+pub use stress::{stress, StressParams};
+
 pub trait RuntimeAdapter {
 	type AccountId: Display;
 	type Balance: Display + AtLeast32Bit + From<Self::Number>;
@@ -58,6 +62,9 @@ pub trait RuntimeAdapter {
 	fn block_number(&self) -> u32;
 	fn set_block_number(&mut self, value: u32);
 
+	fn index(&self) -> Self::Index;
+	fn set_index(&mut self, value: Self::Index);
+
 	fn transfer_extrinsic(
 		&mut self,
 		sender: &Self::AccountId,
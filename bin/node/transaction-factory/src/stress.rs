@@ -0,0 +1,315 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Stress test of the transaction pool and of the block authorship of a running node.
+//!
+//! Transfers from the master account are submitted to the node over RPC at a target rate, while
+//! the best block of the node is polled to find out when they are included. The time each
+//! transfer waited before being included, the transfers the pool rejected and the number of
+//! transactions pending in the pool are reported at the end.
+
+use std::{cell::{Cell, RefCell}, collections::HashMap, fmt, time::{Duration, Instant}};
+
+use codec::Encode;
+use futures::future;
+use hyper::{Body, Client, Request, Uri, client::HttpConnector, header::CONTENT_TYPE};
+use log::{debug, info};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use sp_core::Bytes;
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT, UniqueSaturatedInto};
+
+use crate::RuntimeAdapter;
+
+/// How often the best block of the node is polled.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Parameters of a stress test.
+#[derive(Debug, Clone)]
+pub struct StressParams {
+	/// URL of the HTTP RPC server of the node.
+	pub url: String,
+	/// Number of transfers to submit.
+	pub transfers: u32,
+	/// Number of transfers submitted per second.
+	pub tps: u32,
+	/// How long to wait for the inclusion of the transfers after the last submission.
+	pub timeout: Duration,
+}
+
+/// Measurements of a stress test.
+#[derive(Debug, Default)]
+struct Measurements {
+	/// Number of transfers submitted to the node.
+	submitted: u64,
+	/// Number of transfers the pool rejected.
+	rejected: u64,
+	/// Time between the submission and the import of each included transfer.
+	latencies: Vec<Duration>,
+	/// Largest number of transactions seen pending in the pool.
+	peak_pending: usize,
+}
+
+impl Measurements {
+	fn report(&mut self, submission_time: Duration, pending: usize) {
+		self.latencies.sort();
+		let included = self.latencies.len();
+		let percentile = |p: usize| self.latencies
+			.get((included * p / 100).min(included.saturating_sub(1)))
+			.cloned()
+			.unwrap_or_default();
+		let average = if included == 0 {
+			Duration::default()
+		} else {
+			self.latencies.iter().sum::<Duration>() / included as u32
+		};
+
+		info!(
+			"Submitted {} transfers in {:?} ({:.1} per second), {} rejected by the pool, \
+			{} included, {} still pending.",
+			self.submitted,
+			submission_time,
+			self.submitted as f64 / submission_time.as_secs_f64(),
+			self.rejected,
+			included,
+			pending,
+		);
+		info!(
+			"Inclusion latency: average {:?}, median {:?}, 95th percentile {:?}, max {:?}.",
+			average,
+			percentile(50),
+			percentile(95),
+			self.latencies.last().cloned().unwrap_or_default(),
+		);
+		info!("Pool: at most {} pending transactions.", self.peak_pending);
+	}
+}
+
+/// Error of a call to the node.
+enum Error {
+	/// The node couldn't be reached, or didn't answer with a JSON-RPC response.
+	Transport(String),
+	/// The node answered the call with an error.
+	Call(Value),
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Error::Transport(e) => write!(f, "{}", e),
+			Error::Call(e) => write!(f, "{}", e),
+		}
+	}
+}
+
+/// JSON-RPC client of the node.
+struct Rpc {
+	client: Client<HttpConnector>,
+	uri: Uri,
+	next_id: Cell<u64>,
+}
+
+impl Rpc {
+	async fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T, Error> {
+		let id = self.next_id.get();
+		self.next_id.set(id + 1);
+
+		let body = json!({
+			"jsonrpc": "2.0",
+			"id": id,
+			"method": method,
+			"params": params,
+		});
+		let request = Request::post(self.uri.clone())
+			.header(CONTENT_TYPE, "application/json")
+			.body(Body::from(body.to_string()))
+			.map_err(|e| Error::Transport(e.to_string()))?;
+		let response = self.client.request(request).await
+			.map_err(|e| Error::Transport(format!("`{}` failed: {}", method, e)))?;
+		let body = hyper::body::to_bytes(response.into_body()).await
+			.map_err(|e| Error::Transport(format!("`{}` failed: {}", method, e)))?;
+
+		let mut response: Value = serde_json::from_slice(&body)
+			.map_err(|e| Error::Transport(format!("Invalid response to `{}`: {}", method, e)))?;
+		if !response["error"].is_null() {
+			return Err(Error::Call(response["error"].take()))
+		}
+
+		serde_json::from_value(response["result"].take())
+			.map_err(|e| Error::Transport(format!("Invalid result of `{}`: {}", method, e)))
+	}
+
+	/// Returns the number and the hash of the best block.
+	async fn best_block<B: BlockT>(&self) -> Result<(u32, B::Hash), Error> {
+		let header: B::Header = self.call("chain_getHeader", json!([])).await?;
+
+		Ok(((*header.number()).unique_saturated_into(), header.hash()))
+	}
+}
+
+/// Floods the node at `params.url` with transfers from the master account, then reports the
+/// measurements.
+///
+/// The nonce of the transfers starts from the next index of the master account, and only goes
+/// up when the pool accepts a transfer.
+pub fn stress<RA>(factory_state: RA, params: StressParams) -> sc_cli::Result<()>
+	where
+		RA: RuntimeAdapter,
+		RA::Index: From<u32>,
+{
+	if params.tps == 0 {
+		return Err("The target rate of transfers must be positive.".into())
+	}
+
+	let uri = params.url.parse::<Uri>()
+		.map_err(|e| format!("Invalid RPC url {}: {}", params.url, e))?;
+	let rpc = Rpc { client: Client::new(), uri, next_id: Cell::new(0) };
+
+	let mut runtime = tokio::runtime::Builder::new()
+		.basic_scheduler()
+		.enable_all()
+		.build()
+		.map_err(|e| format!("Failed to start the tokio runtime: {}", e))?;
+
+	let included = runtime.block_on(run(factory_state, params, rpc))
+		.map_err(|e| format!("Stress test failed: {}", e))?;
+	if included == 0 {
+		return Err("None of the transfers were included.".into())
+	}
+
+	Ok(())
+}
+
+/// Runs the stress test and returns the number of included transfers.
+async fn run<RA>(mut factory_state: RA, params: StressParams, rpc: Rpc) -> Result<usize, Error>
+	where
+		RA: RuntimeAdapter,
+		RA::Index: From<u32>,
+{
+	let genesis_hash: <RA::Block as BlockT>::Hash =
+		rpc.call("chain_getBlockHash", json!([0])).await?;
+	let runtime_version: Value = rpc.call("state_getRuntimeVersion", json!([])).await?;
+	let version = runtime_version["specVersion"].as_u64()
+		.ok_or_else(|| Error::Transport("The runtime version has no spec version".into()))? as u32;
+
+	let (from, key) = (RA::master_account_id(), RA::master_account_secret());
+	let index: u32 = rpc.call("system_accountNextIndex", json!([from.to_string()])).await?;
+	factory_state.set_index(index.into());
+
+	let amount = RA::minimum_balance();
+	let transfer_interval = Duration::from_secs(1) / params.tps;
+
+	let best = RefCell::new(rpc.best_block::<RA::Block>().await?);
+	let measurements = RefCell::new(Measurements::default());
+	// the encoded transfers accepted by the pool and not included yet, with their submission time.
+	let pending = RefCell::new(HashMap::<String, Instant>::new());
+	let started = Instant::now();
+	let finished = Cell::new(None);
+
+	let submit = async {
+		for n in 0..params.transfers {
+			tokio::time::delay_until((started + transfer_interval * n).into()).await;
+
+			// the transfers are mortal from the best block.
+			let best_hash = {
+				let best = best.borrow();
+				factory_state.set_block_number(best.0);
+				best.1
+			};
+			let index = factory_state.index();
+			let transfer = factory_state.transfer_extrinsic(
+				&from,
+				&key,
+				&RA::gen_random_account_id(n),
+				&amount,
+				version,
+				&genesis_hash,
+				&best_hash,
+			);
+			let transfer = serde_json::to_value(Bytes(transfer.encode()))
+				.expect("Bytes are serialized to a string; qed");
+
+			let submitted = Instant::now();
+			match rpc.call::<Value>("author_submitExtrinsic", json!([transfer])).await {
+				Ok(_) => {
+					let transfer = transfer.as_str().expect("Bytes are serialized to a string; qed");
+					pending.borrow_mut().insert(transfer.to_owned(), submitted);
+				},
+				Err(Error::Call(e)) => {
+					debug!("Transfer {} rejected by the pool: {}", n, e);
+					measurements.borrow_mut().rejected += 1;
+					// the nonce of a rejected transfer is still free.
+					factory_state.set_index(index);
+				},
+				Err(e) => return Err(e),
+			}
+			measurements.borrow_mut().submitted += 1;
+		}
+
+		finished.set(Some(Instant::now()));
+		Ok(())
+	};
+
+	let watch = async {
+		loop {
+			tokio::time::delay_for(POLL_INTERVAL).await;
+
+			let (number, hash) = rpc.best_block::<RA::Block>().await?;
+			let last = best.borrow().0;
+			for n in last + 1..=number {
+				let block_hash = if n == number {
+					hash
+				} else {
+					rpc.call("chain_getBlockHash", json!([n])).await?
+				};
+				let block: Value = rpc.call("chain_getBlock", json!([block_hash])).await?;
+				let imported = Instant::now();
+
+				let mut included = 0;
+				for xt in block["block"]["extrinsics"].as_array().into_iter().flatten() {
+					if let Some(submitted) = xt.as_str().and_then(|xt| pending.borrow_mut().remove(xt)) {
+						measurements.borrow_mut().latencies.push(imported - submitted);
+						included += 1;
+					}
+				}
+				info!("Block #{} includes {} transfers.", n, included);
+			}
+			if number > last {
+				*best.borrow_mut() = (number, hash);
+			}
+
+			let pool: Vec<Value> = rpc.call("author_pendingExtrinsics", json!([])).await?;
+			{
+				let mut measurements = measurements.borrow_mut();
+				measurements.peak_pending = measurements.peak_pending.max(pool.len());
+			}
+
+			if let Some(finished) = finished.get() {
+				if pending.borrow().is_empty() || finished.elapsed() > params.timeout {
+					return Ok::<_, Error>(())
+				}
+			}
+		}
+	};
+
+	future::try_join(submit, watch).await?;
+
+	let mut measurements = measurements.into_inner();
+	let submission_time = finished.get().map(|finished| finished - started).unwrap_or_default();
+	measurements.report(submission_time, pending.into_inner().len());
+
+	Ok(measurements.latencies.len())
+}
@@ -37,6 +37,7 @@ use std::{
 };
 
 mod rpc;
+mod slip10;
 mod vanity;
 
 enum OutputType {
@@ -57,7 +58,7 @@ impl<'a> TryFrom<&'a str> for OutputType {
 
 }
 
-trait Crypto: Sized {
+trait Crypto: Sized + 'static {
 	type Pair: Pair<Public = Self::Public>;
 	type Public: Public + Ss58Codec + AsRef<[u8]> + std::hash::Hash;
 	fn pair_from_suri(suri: &str, password: Option<&str>) -> Self::Pair {
@@ -140,6 +141,10 @@ trait Crypto: Sized {
 			<Self::Pair as Pair>::Public::from_string_with_version(uri)
 		{
 			let v = network_override.unwrap_or(v);
+			let ss58_address = match public_key.to_ss58check_with_version(v) {
+				Ok(ss58_address) => ss58_address,
+				Err(_) => return println!("Invalid network given"),
+			};
 
 			match output {
 				OutputType::Json => {
@@ -148,7 +153,7 @@ trait Crypto: Sized {
 						"networkId": String::from(v),
 						"publicKey": format_public_key::<Self>(public_key.clone()),
 						"accountId": format_account_id::<Self>(public_key.clone()),
-						"ss58Address": public_key.to_ss58check_with_version(v),
+						"ss58Address": ss58_address,
 					});
 					println!("{}", serde_json::to_string_pretty(&json).expect("Json pretty print failed"));
 				},
@@ -162,7 +167,7 @@ trait Crypto: Sized {
 						String::from(v),
 						format_public_key::<Self>(public_key.clone()),
 						format_account_id::<Self>(public_key.clone()),
-						public_key.to_ss58check_with_version(v),
+						ss58_address,
 					);
 				},
 			}
@@ -183,6 +188,61 @@ impl Crypto for Ed25519 {
 	}
 }
 
+/// Ed25519 keys derived from a BIP39 phrase as the hardware wallets do, see `slip10`.
+struct Ed25519Bip32;
+
+impl Crypto for Ed25519Bip32 {
+	type Pair = ed25519::Pair;
+	type Public = ed25519::Public;
+
+	fn pair_from_suri(suri: &str, password: Option<&str>) -> Self::Pair {
+		slip10::pair_from_uri(suri, password).expect("Invalid phrase").0
+	}
+
+	fn print_from_uri(
+		uri: &str,
+		password: Option<&str>,
+		network_override: Option<Ss58AddressFormat>,
+		output: OutputType,
+	) {
+		let (pair, seed) = match slip10::pair_from_uri(uri, password) {
+			Ok(derived) => derived,
+			// public keys and addresses are the same as with plain ed25519.
+			Err(_) if ed25519::Public::from_string_with_version(uri).is_ok() =>
+				return Ed25519::print_from_uri(uri, password, network_override, output),
+			Err(e) => return println!("Invalid phrase or path given: {}", e),
+		};
+		let public_key = Self::public_from_pair(&pair);
+
+		match output {
+			OutputType::Json => {
+				let json = json!({
+					"secretPhrase": uri,
+					"secretSeed": format_seed::<Self>(seed),
+					"publicKey": format_public_key::<Self>(public_key.clone()),
+					"accountId": format_account_id::<Self>(public_key),
+					"ss58Address": Self::ss58_from_pair(&pair),
+				});
+				let json = serde_json::to_string_pretty(&json).expect("Json pretty print failed");
+				println!("{}", json);
+			},
+			OutputType::Text => {
+				println!("Secret phrase `{}` is account:\n  \
+					Secret seed:      {}\n  \
+					Public key (hex): {}\n  \
+					Account ID:       {}\n  \
+					SS58 Address:     {}",
+					uri,
+					format_seed::<Self>(seed),
+					format_public_key::<Self>(public_key.clone()),
+					format_account_id::<Self>(public_key),
+					Self::ss58_from_pair(&pair),
+				);
+			},
+		}
+	}
+}
+
 struct Sr25519;
 
 impl Crypto for Sr25519 {
@@ -229,7 +289,12 @@ fn get_usage() -> String {
 		-e, --ed25519 'Use Ed25519/BIP39 cryptography'
 		-k, --secp256k1 'Use SECP256k1/ECDSA/BIP39 cryptography'
 		-s, --sr25519 'Use Schnorr/Ristretto x25519/BIP39 cryptography'
-		[network] -n, --network <network> 'Specify a network. One of {}. Default is {}'
+		[scheme] --scheme <scheme> 'Specify a cryptography scheme. One of sr25519 (default), \
+			ed25519, secp256k1 and ed25519-bip32. ed25519-bip32 derives Ed25519 keys as the \
+			hardware wallets do, from a BIP39 phrase optionally followed by a BIP44 path of \
+			hardened indices, by default <phrase>/m/44h/354h/0h/0h/0h.'
+		[network] -n, --network <network> 'Specify a network. One of {}, or a numeric SS58 \
+			address prefix. Default is {}'
 		[password] -p, --password <password> 'The password for the key'
 		--password-interactive 'You will be prompted for the password for the key.'
 		[output] -o, --output <output> 'Specify an output format. One of text, json. Default is text.'
@@ -269,19 +334,32 @@ fn get_app<'a, 'b>(usage: &'a str) -> App<'a, 'b> {
 				"),
 			SubCommand::with_name("sign-transaction")
 				.about("Sign transaction from encoded Call. Returns a signed and encoded \
-						UncheckedMortalCompactExtrinsic as hex.")
+						UncheckedMortalCompactExtrinsic as hex, ready to be submitted. \
+						Nothing is fetched from a node, so that it can be done offline.")
 				.args_from_usage("
 					-c, --call <call> 'The call, hex-encoded.'
 					-n, --nonce <nonce> 'The nonce.'
 					-p, --password <password> 'The password for the key.'
-					-h, --prior-block-hash <prior-block-hash> 'The prior block hash, hex-encoded.'
 					-s, --suri <suri> 'The secret key URI.'
+					[genesis] -g, --genesis <genesis> 'The genesis hash or a recognized \
+						chain identifier (elm, alex). Default is alex.'
+					[tip] -t, --tip <tip> 'The tip paid to the block author. Default is 0.'
+					[era-period] --era-period <era-period> 'The number of blocks the \
+						transaction is valid for, from the prior block. It is rounded to a \
+						power of two. The transaction is immortal if not given.'
+					[prior-block-number] --prior-block-number <prior-block-number> 'The \
+						number of the block the era of the transaction starts at.'
+					[prior-block-hash] -h, --prior-block-hash <prior-block-hash> 'The hash \
+						of the block the era of the transaction starts at, hex-encoded.'
+					[spec-version] --spec-version <spec-version> 'The spec version of the \
+						runtime of the chain. Default is the one of the runtime subkey is \
+						built with.'
 				"),
 			SubCommand::with_name("transfer")
 				.about("Author and sign a Node pallet_balances::Transfer transaction with a given (secret) key")
 				.args_from_usage("
 					<genesis> -g, --genesis <genesis> 'The genesis hash or a recognized \
-											chain identifier (dev, elm, alex).'
+											chain identifier (elm, alex).'
 					<from> 'The signing secret key URI.'
 					<to> 'The destination account public key URI.'
 					<amount> 'The number of units to transfer.'
@@ -291,6 +369,15 @@ fn get_app<'a, 'b>(usage: &'a str) -> App<'a, 'b> {
 				.about("Generate a seed that provides a vanity address")
 				.args_from_usage("
 					-n, --number <number> 'Number of keys to generate'
+					-i, --case-insensitive 'Match the pattern regardless of the case of the letters'
+					[position] --position <position> 'Where the pattern must be in the address: \
+						anywhere (default), start (right after the first character, which only \
+						depends on the network) or end'
+					[threads] -t, --threads <threads> 'Number of threads to search with, the \
+						number of cores by default'
+					-j, --junctions 'Derive the keys from a standard seed phrase with hard \
+						junctions, so that the address is restored from the phrase and the \
+						junction instead of a raw seed'
 					<pattern> 'Desired pattern'
 				"),
 			SubCommand::with_name("verify")
@@ -319,6 +406,15 @@ fn main() -> Result<(), Error> {
 	let usage = get_usage();
 	let matches = get_app(&usage).get_matches();
 
+	match matches.value_of("scheme") {
+		Some("sr25519") => return execute::<Sr25519>(matches),
+		Some("ed25519") => return execute::<Ed25519>(matches),
+		Some("secp256k1") => return execute::<Ecdsa>(matches),
+		Some("ed25519-bip32") => return execute::<Ed25519Bip32>(matches),
+		Some(_) => return static_err("Invalid scheme name. See --help for available schemes."),
+		None => {},
+	}
+
 	if matches.is_present("ed25519") {
 		return execute::<Ed25519>(matches);
 	}
@@ -398,7 +494,8 @@ where
 	 };
 
 	if let Some(network) = maybe_network {
-		set_default_ss58_version(network);
+		set_default_ss58_version(network)
+			.map_err(|_| Error::Static("Invalid network name. See --help for available networks."))?;
 	}
 
 	let output: OutputType = match matches.value_of("output").map(TryInto::try_into) {
@@ -447,13 +544,22 @@ where
 			}
 		}
 		("vanity", Some(matches)) => {
-			let desired: String = matches
-				.value_of("pattern")
-				.map(str::to_string)
-				.unwrap_or_default();
-			let result = vanity::generate_key::<C>(&desired)?;
-			let formated_seed = format_seed::<C>(result.seed);
-			C::print_from_uri(&formated_seed, None, maybe_network, output);
+			let desired = matches.value_of("pattern").unwrap_or_default();
+			let position = matches.value_of("position")
+				.map(str::parse)
+				.transpose()?
+				.unwrap_or(vanity::Position::Anywhere);
+			let pattern = vanity::Pattern::new(
+				desired,
+				matches.is_present("case-insensitive"),
+				position,
+			)?;
+			let threads = read_optional_parameter::<usize>(matches, "threads")?
+				.unwrap_or_else(num_cpus::get);
+
+			let junctions = matches.is_present("junctions");
+			let result = vanity::generate_key::<C>(&pattern, threads, junctions)?;
+			C::print_from_uri(&result.uri, None, maybe_network, output);
 		}
 		("transfer", Some(matches)) => {
 			let signer = read_pair::<C>(matches.value_of("from"), password)?;
@@ -464,22 +570,22 @@ where
 			let amount = read_required_parameter::<Balance>(matches, "amount")?;
 			let function = Call::Balances(BalancesCall::transfer(to.into(), amount));
 
-			let extrinsic = create_extrinsic::<C>(function, index, signer, genesis_hash);
+			let params = TransactionParams::immortal(index, genesis_hash);
+			let extrinsic = create_extrinsic::<C>(function, signer, params);
 
 			print_extrinsic(extrinsic);
 		}
 		("sign-transaction", Some(matches)) => {
 			let signer = read_pair::<C>(matches.value_of("suri"), password)?;
-			let index = read_required_parameter::<Index>(matches, "nonce")?;
-			let genesis_hash = read_genesis_hash(matches)?;
+			let params = read_transaction_params(matches)?;
 
 			let call = matches.value_of("call").expect("call is required; qed");
-			let function: Call = hex::decode(&call)
+			let function: Call = decode_hex(strip_hex_prefix(call))
 				.ok()
 				.and_then(|x| Decode::decode(&mut &x[..]).ok())
-				.unwrap();
+				.ok_or(Error::Static("Invalid call: expecting a hex-encoded call of the runtime"))?;
 
-			let extrinsic = create_extrinsic::<C>(function, index, signer, genesis_hash);
+			let extrinsic = create_extrinsic::<C>(function, signer, params);
 
 			print_extrinsic(extrinsic);
 		}
@@ -567,14 +673,98 @@ fn read_required_parameter<T: FromStr>(matches: &ArgMatches, name: &str) -> Resu
 	)
 }
 
+fn read_optional_parameter<T: FromStr>(
+	matches: &ArgMatches,
+	name: &str,
+) -> Result<Option<T>, Error> {
+	matches.value_of(name).map(|value| str::parse::<T>(value).map_err(|_|
+		Error::Formatted(format!("Invalid `{}' parameter; expecting an integer.", name))
+	)).transpose()
+}
+
+fn strip_hex_prefix(data: &str) -> &str {
+	if data.starts_with("0x") {
+		&data[2..]
+	} else {
+		data
+	}
+}
+
+fn read_hash(data: &str) -> Result<H256, Error> {
+	let data = decode_hex(strip_hex_prefix(data))?;
+	if data.len() != 32 {
+		return Err(Error::Static("Invalid hash: expecting 32 hex-encoded bytes"));
+	}
+
+	Ok(H256::from_slice(&data))
+}
+
+/// The parameters of a transaction, but its call and signer.
+struct TransactionParams {
+	nonce: Index,
+	tip: Balance,
+	era: Era,
+	/// The hash of the block the era starts at, which is the genesis for immortal transactions.
+	era_block_hash: H256,
+	genesis_hash: H256,
+	spec_version: u32,
+}
+
+impl TransactionParams {
+	fn immortal(nonce: Index, genesis_hash: H256) -> Self {
+		TransactionParams {
+			nonce,
+			tip: 0,
+			era: Era::Immortal,
+			era_block_hash: genesis_hash,
+			genesis_hash,
+			spec_version: VERSION.spec_version,
+		}
+	}
+}
+
+fn read_transaction_params(matches: &ArgMatches) -> Result<TransactionParams, Error> {
+	let nonce = read_required_parameter::<Index>(matches, "nonce")?;
+	let genesis_hash = read_genesis_hash(matches)?;
+	let mut params = TransactionParams::immortal(nonce, genesis_hash);
+
+	if let Some(tip) = read_optional_parameter::<Balance>(matches, "tip")? {
+		params.tip = tip;
+	}
+	if let Some(spec_version) = read_optional_parameter::<u32>(matches, "spec-version")? {
+		params.spec_version = spec_version;
+	}
+	if let Some(period) = read_optional_parameter::<u64>(matches, "era-period")? {
+		let number = read_optional_parameter::<u64>(matches, "prior-block-number")?
+			.ok_or(Error::Static("A mortal transaction needs `--prior-block-number`"))?;
+		let hash = matches.value_of("prior-block-hash")
+			.ok_or(Error::Static("A mortal transaction needs `--prior-block-hash`"))?;
+
+		// the era is checked against the hash of the block it starts at, which must be the
+		// prior block.
+		let era = Era::mortal(period, number);
+		if era.birth(number) != number {
+			return Err(Error::Static(
+				"The era would start before the prior block; use a shorter `--era-period`"
+			));
+		}
+
+		params.era = era;
+		params.era_block_hash = read_hash(hash)?;
+	}
+
+	Ok(params)
+}
+
 fn read_genesis_hash(matches: &ArgMatches) -> Result<H256, Error> {
 	let genesis_hash: Hash = match matches.value_of("genesis").unwrap_or("alex") {
 		"elm" => hex!["10c08714a10c7da78f40a60f6f732cf0dba97acfb5e2035445b032386157d5c3"].into(),
 		"alex" => hex!["dcd1346701ca8396496e52aa2785b1748deb6db09551b72159dcb3e08991025b"].into(),
-		h => Decode::decode(&mut &decode_hex(h)?[..])
-			.expect("Invalid genesis hash or unrecognized chain identifier"),
+		h => read_hash(h)
+			.map_err(|_| Error::Static("Invalid genesis hash or unrecognized chain identifier"))?,
 	};
-	println!(
+	// the extrinsic is the only output, so that it can be submitted as is.
+	eprintln!(
 		"Using a genesis hash of {}",
 		HexDisplay::from(&genesis_hash.as_ref())
 	);
@@ -668,31 +858,28 @@ fn format_account_id<C: Crypto>(public_key: PublicOf<C>) -> String where
 
 fn create_extrinsic<C: Crypto>(
 	function: Call,
-	index: Index,
 	signer: C::Pair,
-	genesis_hash: H256,
+	params: TransactionParams,
 ) -> UncheckedExtrinsic where
 	PublicOf<C>: PublicT,
 	SignatureOf<C>: SignatureT,
 {
-	let extra = |i: Index, f: Balance| {
-		(
-			frame_system::CheckVersion::<Runtime>::new(),
-			frame_system::CheckGenesis::<Runtime>::new(),
-			frame_system::CheckEra::<Runtime>::from(Era::Immortal),
-			frame_system::CheckNonce::<Runtime>::from(i),
-			frame_system::CheckWeight::<Runtime>::new(),
-			pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(f),
-			Default::default(),
-		)
-	};
+	let extra = (
+		frame_system::CheckVersion::<Runtime>::new(),
+		frame_system::CheckGenesis::<Runtime>::new(),
+		frame_system::CheckMortality::<Runtime>::from(params.era),
+		frame_system::CheckNonce::<Runtime>::from(params.nonce),
+		frame_system::CheckWeight::<Runtime>::new(),
+		pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(params.tip),
+		Default::default(),
+	);
 	let raw_payload = SignedPayload::from_raw(
 		function,
-		extra(index, 0),
+		extra,
 		(
-			VERSION.spec_version as u32,
-			genesis_hash,
-			genesis_hash,
+			params.spec_version,
+			params.genesis_hash,
+			params.era_block_hash,
 			(),
 			(),
 			(),
@@ -768,6 +955,41 @@ mod tests {
 		test_generate_sign_verify::<Sr25519>();
 	}
 
+	#[test]
+	fn transaction_params_are_read() {
+		let usage = get_usage();
+		let hash = format!("0x{}", "11".repeat(32));
+		let read = |args: &[&str]| {
+			let mut arg_vec = vec!["subkey", "sign-transaction", "-c", "00", "-s", "//Alice", "-p", ""];
+			arg_vec.extend(args);
+			let matches = get_app(&usage).get_matches_from(arg_vec);
+			read_transaction_params(matches.subcommand().1.unwrap())
+		};
+
+		let params = read(&["-n", "3", "-g", "alex"]).unwrap();
+		assert_eq!(params.nonce, 3);
+		assert_eq!(params.tip, 0);
+		assert_eq!(params.era, Era::Immortal);
+		assert_eq!(params.era_block_hash, params.genesis_hash);
+		assert_eq!(params.spec_version, VERSION.spec_version);
+
+		let params = read(&[
+			"-n", "3", "-t", "10", "--spec-version", "7",
+			"--era-period", "64", "--prior-block-number", "100", "-h", &hash,
+		]).unwrap();
+		assert_eq!(params.tip, 10);
+		assert_eq!(params.spec_version, 7);
+		assert_eq!(params.era, Era::mortal(64, 100));
+		assert_eq!(params.era_block_hash, read_hash(&hash).unwrap());
+		assert_ne!(params.era_block_hash, params.genesis_hash);
+
+		// the era must start at the prior block, whose hash is given.
+		assert!(read(&["-n", "3", "--era-period", "64", "--prior-block-number", "100"]).is_err());
+		assert!(read(&[
+			"-n", "3", "--era-period", "65536", "--prior-block-number", "100", "-h", &hash,
+		]).is_err());
+	}
+
 	#[test]
 	fn should_work() {
 		let s = "0123456789012345678901234567890123456789012345678901234567890123";
@@ -0,0 +1,150 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! SLIP-0010 derivation of ed25519 keys from a BIP39 phrase along a BIP44 path, as done by the
+//! hardware wallets.
+
+use bip39::{Language, Mnemonic, Seed};
+use hmac::{Hmac, Mac};
+use sha2::Sha512;
+use sp_core::{ed25519, Pair};
+use std::str::FromStr;
+
+/// The path the hardware wallets derive the first Polkadot account with.
+pub const DEFAULT_PATH: &str = "m/44'/354'/0'/0'/0'";
+
+const HARDENED: u32 = 1 << 31;
+
+/// A BIP44 derivation path, whose indices are all hardened as SLIP-0010 requires for ed25519.
+///
+/// The hardened indices are noted either `0'` or `0h`.
+#[derive(Debug, PartialEq)]
+pub struct DerivationPath(Vec<u32>);
+
+impl FromStr for DerivationPath {
+	type Err = &'static str;
+
+	fn from_str(path: &str) -> Result<Self, Self::Err> {
+		let mut parts = path.split('/');
+		if parts.next() != Some("m") {
+			return Err("Derivation path should start with `m`");
+		}
+
+		parts.map(|part| {
+			if !part.ends_with('\'') && !part.ends_with('h') {
+				return Err("Only hardened derivation, e.g. `0'`, is supported for ed25519");
+			}
+
+			part[..part.len() - 1].parse::<u32>().ok()
+				.filter(|index| *index < HARDENED)
+				.ok_or("Invalid derivation path index")
+		}).collect::<Result<_, _>>().map(DerivationPath)
+	}
+}
+
+/// Get the secret seed of the ed25519 key derived along the path from the BIP39 seed.
+pub fn derive_seed(bip39_seed: &[u8], path: &DerivationPath) -> [u8; 32] {
+	let mut key = hmac_sha512(b"ed25519 seed", &[bip39_seed]);
+	for index in &path.0 {
+		let (secret, chain_code) = key.split_at(32);
+		let index = (index | HARDENED).to_be_bytes();
+		key = hmac_sha512(chain_code, &[&[0u8][..], secret, &index[..]]);
+	}
+
+	let mut seed = [0; 32];
+	seed.copy_from_slice(&key[..32]);
+	seed
+}
+
+/// Get the key pair of a URI made of a BIP39 phrase, optionally followed by a derivation path,
+/// e.g. `<phrase>/m/44'/354'/0'/0'/0'`. The path defaults to `DEFAULT_PATH`.
+///
+/// The password is the BIP39 passphrase. Returns the pair along with its secret seed.
+pub fn pair_from_uri(
+	uri: &str,
+	password: Option<&str>,
+) -> Result<(ed25519::Pair, [u8; 32]), &'static str> {
+	let (phrase, path) = match uri.find('/') {
+		Some(index) => (&uri[..index], &uri[index + 1..]),
+		None => (uri, DEFAULT_PATH),
+	};
+
+	let mnemonic = Mnemonic::from_phrase(phrase, Language::English)
+		.map_err(|_| "Invalid BIP39 phrase")?;
+	let bip39_seed = Seed::new(&mnemonic, password.unwrap_or(""));
+	let seed = derive_seed(bip39_seed.as_bytes(), &path.parse()?);
+
+	Ok((ed25519::Pair::from_seed(&seed), seed))
+}
+
+fn hmac_sha512(key: &[u8], data: &[&[u8]]) -> [u8; 64] {
+	let mut mac = Hmac::<Sha512>::new_varkey(key).expect("HMAC accepts keys of any size");
+	for data in data {
+		mac.input(data);
+	}
+
+	let mut result = [0; 64];
+	result.copy_from_slice(&mac.result().code());
+	result
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn derivation_paths_are_parsed() {
+		assert_eq!(
+			DEFAULT_PATH.parse(),
+			Ok(DerivationPath(vec![44, 354, 0, 0, 0])),
+		);
+		assert_eq!("m/44h/354h/1h".parse(), Ok(DerivationPath(vec![44, 354, 1])));
+		assert_eq!("m".parse(), Ok(DerivationPath(vec![])));
+		assert!("44'/0'".parse::<DerivationPath>().is_err());
+		assert!("m/44'/0".parse::<DerivationPath>().is_err());
+		assert!("m/2147483648'".parse::<DerivationPath>().is_err());
+	}
+
+	#[test]
+	fn seeds_match_the_slip10_test_vectors() {
+		// test vector 1 of SLIP-0010, for ed25519.
+		let bip39_seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+		let vectors = vec![
+			("m", "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7"),
+			("m/0'", "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3"),
+			(
+				"m/0'/1'/2'/2'/1000000000'",
+				"8f94d394a8e8fd6b1bc2f3f49f5c47e385281d5c17e65324b0f62483e37e8793",
+			),
+		];
+
+		for (path, seed) in vectors {
+			assert_eq!(hex::encode(derive_seed(&bip39_seed, &path.parse().unwrap())), seed);
+		}
+	}
+
+	#[test]
+	fn uri_path_defaults_to_the_first_polkadot_account() {
+		let phrase = "bottom drive obey lake curtain smoke basket hold race lonely fit walk";
+		let (pair, _) = pair_from_uri(phrase, None).unwrap();
+		let uri = format!("{}/{}", phrase, DEFAULT_PATH);
+		assert_eq!(pair_from_uri(&uri, None).unwrap().0.public(), pair.public());
+
+		let other = format!("{}/m/44'/354'/0'/0'/1'", phrase);
+		assert_ne!(pair_from_uri(&other, None).unwrap().0.public(), pair.public());
+		assert!(pair_from_uri(&format!("{}/m/44'/354'/0/0/0", phrase), None).is_err());
+	}
+}
@@ -14,16 +14,110 @@
 // You should have received a copy of the GNU General Public License
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
-use super::{PublicOf, PublicT, Crypto};
-use sp_core::Pair;
+use super::{PublicOf, PublicT, SeedOf, Crypto, format_seed};
+use bip39::{Language, Mnemonic, MnemonicType};
+use sp_core::{Pair, crypto::DeriveJunction};
 use rand::{rngs::OsRng, RngCore};
+use std::{
+	iter, str::FromStr, thread, time::{Duration, Instant},
+	sync::{Arc, mpsc::{self, RecvTimeoutError}},
+	sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+};
 
-fn good_waypoint(done: u64) -> u64 {
-	match done {
-		0..=1_000_000 => 100_000,
-		0..=10_000_000 => 1_000_000,
-		0..=100_000_000 => 10_000_000,
-		_ => 100_000_000,
+/// The characters of the base58 alphabet, which is the one of the SS58 addresses.
+const ALPHABET: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// How often the progress of the search is reported.
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Where the pattern must be found in the address.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) enum Position {
+	/// Anywhere after the first character, which only depends on the network.
+	Anywhere,
+	/// Right after the first character.
+	Start,
+	/// At the end of the address.
+	End,
+}
+
+impl FromStr for Position {
+	type Err = &'static str;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"anywhere" => Ok(Position::Anywhere),
+			"start" => Ok(Position::Start),
+			"end" => Ok(Position::End),
+			_ => Err("Position must be one of anywhere, start or end"),
+		}
+	}
+}
+
+/// The pattern the address of the key is searched for.
+#[derive(Clone, Debug)]
+pub(super) struct Pattern {
+	text: String,
+	case_insensitive: bool,
+	position: Position,
+}
+
+impl Pattern {
+	/// Create a pattern, which must only contain characters of the base58 alphabet.
+	pub(super) fn new(
+		text: &str,
+		case_insensitive: bool,
+		position: Position,
+	) -> Result<Self, &'static str> {
+		if text.is_empty() {
+			return Err("Pattern must not be empty");
+		}
+
+		let pattern = Pattern {
+			text: if case_insensitive { text.to_ascii_lowercase() } else { text.into() },
+			case_insensitive,
+			position,
+		};
+		if pattern.text.chars().any(|c| pattern.matching_chars(c) == 0) {
+			return Err("Pattern must only contain base58 characters, which exclude 0, I, O and l");
+		}
+
+		Ok(pattern)
+	}
+
+	/// Whether the address matches the pattern.
+	fn matches(&self, address: &str) -> bool {
+		match self.position {
+			Position::Anywhere => address[1..].contains(&self.text),
+			Position::Start => address[1..].starts_with(&self.text),
+			Position::End => address.ends_with(&self.text),
+		}
+	}
+
+	/// The address as it is compared to the pattern.
+	fn normalize(&self, address: String) -> String {
+		if self.case_insensitive { address.to_ascii_lowercase() } else { address }
+	}
+
+	/// The number of characters of the base58 alphabet which match the one of the pattern.
+	fn matching_chars(&self, c: char) -> usize {
+		ALPHABET.chars()
+			.filter(|a| if self.case_insensitive { a.to_ascii_lowercase() == c } else { *a == c })
+			.count()
+	}
+
+	/// The number of keys expected to be searched before finding one whose address, of the
+	/// given length, matches the pattern.
+	fn expected_attempts(&self, address_len: usize) -> f64 {
+		let probability = self.text.chars()
+			.map(|c| self.matching_chars(c) as f64 / ALPHABET.len() as f64)
+			.product::<f64>();
+		let positions = match self.position {
+			Position::Anywhere => address_len.saturating_sub(self.text.len()),
+			Position::Start | Position::End => 1,
+		};
+
+		1.0 / (1.0 - (1.0 - probability).powi(positions as i32))
 	}
 }
 
@@ -41,11 +135,11 @@ fn next_seed(seed: &mut [u8]) {
 	}
 }
 
-/// A structure used to carry both Pair and seed.
+/// A structure used to carry both Pair and the secret URI it is restored from.
 /// This should usually NOT been used. If unsure, use Pair.
 pub(super) struct KeyPair<C: Crypto> {
 	pub pair: C::Pair,
-	pub seed: <C::Pair as Pair>::Seed,
+	pub uri: String,
 	pub score: usize,
 }
 
@@ -56,52 +150,172 @@ fn calculate_score(_desired: &str, key: &str) -> usize {
 		let snip_size = _desired.len() - truncate;
 		let truncated = &_desired[0..snip_size];
 		if let Some(pos) = key.find(truncated) {
-			return (47 - pos) + (snip_size * 48);
+			return 47usize.saturating_sub(pos) + (snip_size * 48);
 		}
 	}
 	0
 }
 
-pub(super) fn generate_key<C: Crypto>(desired: &str) -> Result<KeyPair<C>, &'static str> where
-		PublicOf<C>: PublicT,
-{
-	if desired.is_empty() {
-		return Err("Pattern must not be empty");
+/// The keys searched by a thread.
+enum Candidates<C: Crypto> {
+	/// Random seeds, each followed by the seeds it is incremented to.
+	Seeds { seed: SeedOf<C>, done: u64 },
+	/// The keys derived with the hard junctions `//0`, `//1`, ... of a fresh seed phrase.
+	Junctions { phrase: String, root: C::Pair, index: u64 },
+}
+
+impl<C: Crypto> Candidates<C> {
+	fn new(junctions: bool) -> Self {
+		if junctions {
+			let phrase = Mnemonic::new(MnemonicType::Words12, Language::English).into_phrase();
+			let (root, _) = C::Pair::from_phrase(&phrase, None)
+				.expect("A generated phrase is valid; qed");
+			Candidates::Junctions { phrase, root, index: 0 }
+		} else {
+			Candidates::Seeds { seed: Default::default(), done: 0 }
+		}
 	}
 
-	println!("Generating key containing pattern '{}'", desired);
+	/// Move to the next key.
+	fn next(&mut self) -> C::Pair {
+		match self {
+			Candidates::Seeds { seed, done } => {
+				if *done % 100_000 == 0 {
+					OsRng.fill_bytes(seed.as_mut());
+				} else {
+					next_seed(seed.as_mut());
+				}
+				*done += 1;
+				C::Pair::from_seed(seed)
+			},
+			Candidates::Junctions { root, index, .. } => {
+				let junction = DeriveJunction::hard(*index);
+				*index += 1;
+				root.derive(iter::once(junction), None)
+					.map(|(pair, _)| pair)
+					.unwrap_or_else(|_| panic!("Hard junctions are supported by all schemes; qed"))
+			},
+		}
+	}
 
-	let top = 45 + (desired.len() * 48);
-	let mut best = 0;
-	let mut seed = <C::Pair as Pair>::Seed::default();
-	let mut done = 0;
+	/// The secret URI of the current key.
+	fn uri(&self) -> String {
+		match self {
+			Candidates::Seeds { seed, .. } => format_seed::<C>(seed.clone()),
+			Candidates::Junctions { phrase, index, .. } => format!("{}//{}", phrase, index - 1),
+		}
+	}
+}
 
-	loop {
-		if done % 100000 == 0 {
-			OsRng.fill_bytes(seed.as_mut());
-		} else {
-			next_seed(seed.as_mut());
+/// The state of the search shared by its threads.
+#[derive(Default)]
+struct Search {
+	found: AtomicBool,
+	done: AtomicU64,
+	best: AtomicUsize,
+}
+
+impl Search {
+	/// Record the score of a key if it is the best one so far.
+	fn record_score(&self, score: usize) {
+		let mut best = self.best.load(Ordering::Relaxed);
+		while score > best {
+			match self.best.compare_exchange(best, score, Ordering::Relaxed, Ordering::Relaxed) {
+				Ok(_) => break,
+				Err(current) => best = current,
+			}
 		}
+	}
+}
+
+/// Search for a key matching the pattern until it or another thread finds one.
+fn search<C: Crypto>(pattern: &Pattern, junctions: bool, state: &Search) -> Option<KeyPair<C>> where
+	PublicOf<C>: PublicT,
+{
+	let mut candidates = Candidates::<C>::new(junctions);
+	let mut best = 0;
+
+	while !state.found.load(Ordering::Relaxed) {
+		let pair = candidates.next();
+		let ss58 = pattern.normalize(C::ss58_from_pair(&pair));
+		state.done.fetch_add(1, Ordering::Relaxed);
 
-		let p = C::Pair::from_seed(&seed);
-		let ss58 = C::ss58_from_pair(&p);
-		let score = calculate_score(&desired, &ss58);
-		if score > best || desired.len() < 2 {
+		let score = calculate_score(&pattern.text, &ss58);
+		if score > best {
 			best = score;
-			let keypair = KeyPair {
-				pair: p,
-				seed: seed.clone(),
-				score: score,
-			};
-			if best >= top {
-				println!("best: {} == top: {}", best, top);
-				return Ok(keypair);
-			}
+			state.record_score(score);
 		}
-		done += 1;
 
-		if done % good_waypoint(done) == 0 {
-			println!("{} keys searched; best is {}/{} complete", done, best, top);
+		if pattern.matches(&ss58) {
+			state.found.store(true, Ordering::Relaxed);
+			return Some(KeyPair { pair, uri: candidates.uri(), score });
+		}
+	}
+
+	None
+}
+
+/// Format a duration given in seconds for humans.
+fn format_duration(seconds: f64) -> String {
+	match seconds {
+		s if s < 60.0 => format!("{:.0} seconds", s),
+		s if s < 3_600.0 => format!("{:.1} minutes", s / 60.0),
+		s if s < 86_400.0 => format!("{:.1} hours", s / 3_600.0),
+		s => format!("{:.1} days", s / 86_400.0),
+	}
+}
+
+/// Search for a key whose address matches the pattern with the given number of threads.
+///
+/// The keys are made of random seeds, or derived with hard junctions from fresh seed phrases
+/// if `junctions` is set, so that the key is restored from a standard phrase and its junction.
+pub(super) fn generate_key<C: Crypto>(
+	pattern: &Pattern,
+	threads: usize,
+	junctions: bool,
+) -> Result<KeyPair<C>, &'static str> where
+	PublicOf<C>: PublicT,
+{
+	let address_len = C::ss58_from_pair(&Candidates::<C>::new(false).next()).len();
+	if pattern.text.len() >= address_len {
+		return Err("Pattern is longer than the addresses");
+	}
+
+	let expected = pattern.expected_attempts(address_len);
+	println!("Generating key containing pattern '{}'", pattern.text);
+	println!("Expected number of keys to search: {:.0}", expected);
+
+	let top = 45 + (pattern.text.len() * 48);
+	let state = Arc::new(Search::default());
+	let (sender, receiver) = mpsc::channel();
+	for _ in 0..threads.max(1) {
+		let (pattern, state, sender) =
+			(pattern.clone(), state.clone(), sender.clone());
+		thread::spawn(move || {
+			if let Some(keypair) = search::<C>(&pattern, junctions, &state) {
+				let _ = sender.send(keypair);
+			}
+		});
+	}
+
+	let started = Instant::now();
+	loop {
+		match receiver.recv_timeout(PROGRESS_INTERVAL) {
+			Ok(keypair) => return Ok(keypair),
+			Err(RecvTimeoutError::Timeout) => {
+				let done = state.done.load(Ordering::Relaxed);
+				let rate = done as f64 / started.elapsed().as_secs_f64();
+				println!(
+					"{} keys searched ({:.0} keys/s); best is {}/{} complete; \
+					expected search time: {}",
+					done,
+					rate,
+					state.best.load(Ordering::Relaxed),
+					top,
+					format_duration(expected / rate),
+				);
+			},
+			Err(RecvTimeoutError::Disconnected) => return Err("Search threads stopped"),
 		}
 	}
 }
@@ -110,13 +324,14 @@ pub(super) fn generate_key<C: Crypto>(desired: &str) -> Result<KeyPair<C>, &'sta
 mod tests {
 	use super::super::Ed25519;
 	use super::*;
-	use sp_core::{crypto::Ss58Codec, Pair};
+	use sp_core::{crypto::Ss58Codec, ed25519::Pair as Ed25519Pair, Pair};
 	#[cfg(feature = "bench")]
 	use test::Bencher;
 
 	#[test]
 	fn test_generation_with_single_char() {
-		assert!(generate_key::<Ed25519>("j")
+		let pattern = Pattern::new("j", false, Position::Anywhere).unwrap();
+		assert!(generate_key::<Ed25519>(&pattern, 2, false)
 			.unwrap()
 			.pair
 			.public()
@@ -124,6 +339,55 @@ mod tests {
 			.contains("j"));
 	}
 
+	#[test]
+	fn test_generation_with_junctions() {
+		let pattern = Pattern::new("J", true, Position::End).unwrap();
+		let keypair = generate_key::<Ed25519>(&pattern, 2, true).unwrap();
+		assert!(keypair.pair.public().to_ss58check().to_lowercase().ends_with("j"));
+
+		let (phrase, junction) = keypair.uri.split_at(keypair.uri.find("//").unwrap());
+		assert!(Mnemonic::validate(phrase, Language::English).is_ok());
+		assert!(junction[2..].parse::<u64>().is_ok());
+		assert_eq!(
+			Ed25519Pair::from_string(&keypair.uri, None).unwrap().public(),
+			keypair.pair.public(),
+		);
+	}
+
+	#[test]
+	fn test_pattern_positions() {
+		let address = "5PolkadotwHY5k9GpdTgpqs9xjuNvtv8EcwCFpEeyEf3KHim";
+		let matches = |text, case_insensitive, position| {
+			let pattern = Pattern::new(text, case_insensitive, position).unwrap();
+			pattern.matches(&pattern.normalize(address.into()))
+		};
+
+		assert!(matches("Polka", false, Position::Start));
+		assert!(matches("polka", true, Position::Start));
+		assert!(!matches("polka", false, Position::Start));
+		assert!(!matches("dot", false, Position::Start));
+		assert!(matches("dot", false, Position::Anywhere));
+		assert!(!matches("5Polka", false, Position::Anywhere));
+		assert!(matches("KHim", false, Position::End));
+		assert!(!matches("dot", false, Position::End));
+		assert!(Pattern::new("Polkad0t", false, Position::Anywhere).is_err());
+		assert!(Pattern::new("l", false, Position::Anywhere).is_err());
+		assert!(Pattern::new("L", true, Position::Anywhere).is_ok());
+	}
+
+	#[test]
+	fn test_expected_attempts() {
+		let pattern = Pattern::new("ab", false, Position::Start).unwrap();
+		assert_eq!(pattern.expected_attempts(48).round(), 58.0 * 58.0);
+
+		// `a` matches `a` and `A`, but `o` only matches `o`.
+		let pattern = Pattern::new("ao", true, Position::End).unwrap();
+		assert_eq!(pattern.expected_attempts(48).round(), 58.0 * 58.0 / 2.0);
+
+		let anywhere = Pattern::new("ab", false, Position::Anywhere).unwrap();
+		assert!(anywhere.expected_attempts(48) < pattern.expected_attempts(48) / 20.0);
+	}
+
 	#[test]
 	fn test_score_1_char_100() {
 		let score = calculate_score("j", "5jolkadotwHY5k9GpdTgpqs9xjuNvtv8EcwCFpEeyEf3KHim");
@@ -165,12 +429,14 @@ mod tests {
 	#[cfg(feature = "bench")]
 	#[bench]
 	fn bench_paranoiac(b: &mut Bencher) {
-		b.iter(|| generate_key("polk"));
+		let pattern = Pattern::new("polk", false, Position::Anywhere).unwrap();
+		b.iter(|| generate_key::<Ed25519>(&pattern, 1, false));
 	}
 
 	#[cfg(feature = "bench")]
 	#[bench]
 	fn bench_not_paranoiac(b: &mut Bencher) {
-		b.iter(|| generate_key("polk"));
+		let pattern = Pattern::new("polk", false, Position::Anywhere).unwrap();
+		b.iter(|| generate_key::<Ed25519>(&pattern, 1, false));
 	}
 }
@@ -26,12 +26,13 @@ use sp_state_machine::{
 	ChangesTrieState, ChangesTrieStorage as StateChangesTrieStorage, ChangesTrieTransaction,
 	StorageCollection, ChildStorageCollection,
 };
-use sp_storage::{StorageData, StorageKey, ChildInfo};
+use sp_storage::{StorageData, StorageKey, ChildInfo, OwnedChildInfo};
 use crate::{
 	blockchain::{
 		Backend as BlockchainBackend, well_known_cache_keys
 	},
 	light::RemoteBlockchain,
+	client::BlockImportNotification,
 	UsageInfo,
 };
 use sp_blockchain;
@@ -69,14 +70,41 @@ pub struct ImportSummary<Block: BlockT> {
 	pub retracted: Vec<Block::Hash>,
 }
 
+/// Finalization operation summary.
+///
+/// Contains information about the blocks that just got finalized,
+/// including the branches that got displaced by them.
+pub struct FinalizeSummary<Block: BlockT> {
+	/// Blocks that got finalized, ordered by block number.
+	pub finalized: Vec<Block::Hash>,
+	/// Heads of the branches that are no longer descendants of the last finalized block.
+	pub stale_heads: Vec<Block::Hash>,
+}
+
 /// Import operation wrapper
 pub struct ClientImportOperation<Block: BlockT, B: Backend<Block>> {
 	/// DB Operation.
 	pub op: B::BlockImportOperation,
 	/// Summary of imported block.
 	pub notify_imported: Option<ImportSummary<Block>>,
-	/// A list of hashes of blocks that got finalized.
-	pub notify_finalized: Vec<Block::Hash>,
+	/// Notification of the imported block, made whatever the origin of the block.
+	pub notify_every_imported: Option<BlockImportNotification<Block>>,
+	/// Summary of the blocks that got finalized.
+	pub notify_finalized: Option<FinalizeSummary<Block>>,
+}
+
+impl<Block: BlockT, B: Backend<Block>> ClientImportOperation<Block, B> {
+	/// Record blocks finalized by this operation, ordered by block number, along with the heads
+	/// of the branches they displaced.
+	pub fn push_finalized(&mut self, finalized: Vec<Block::Hash>, stale_heads: Vec<Block::Hash>) {
+		match self.notify_finalized {
+			Some(ref mut summary) => {
+				summary.finalized.extend(finalized);
+				summary.stale_heads.extend(stale_heads);
+			},
+			None => self.notify_finalized = Some(FinalizeSummary { finalized, stale_heads }),
+		}
+	}
 }
 
 /// State of a new block.
@@ -239,9 +267,11 @@ pub trait AuxStore {
 	fn get_aux(&self, key: &[u8]) -> sp_blockchain::Result<Option<Vec<u8>>>;
 }
 
-/// An `Iterator` that iterates keys in a given block under a prefix.
+/// An `Iterator` that iterates keys in a given block under a prefix, in the top storage or in a
+/// child storage.
 pub struct KeyIterator<'a, State, Block> {
 	state: State,
+	child_storage: Option<(StorageKey, OwnedChildInfo)>,
 	prefix: Option<&'a StorageKey>,
 	current_key: Vec<u8>,
 	_phantom: PhantomData<Block>,
@@ -252,6 +282,24 @@ impl <'a, State, Block> KeyIterator<'a, State, Block> {
 	pub fn new(state: State, prefix: Option<&'a StorageKey>, current_key: Vec<u8>) -> Self {
 		Self {
 			state,
+			child_storage: None,
+			prefix,
+			current_key,
+			_phantom: PhantomData,
+		}
+	}
+
+	/// Create a `KeyIterator` instance iterating the keys of a child storage.
+	pub fn new_child(
+		state: State,
+		child_storage_key: StorageKey,
+		child_info: OwnedChildInfo,
+		prefix: Option<&'a StorageKey>,
+		current_key: Vec<u8>,
+	) -> Self {
+		Self {
+			state,
+			child_storage: Some((child_storage_key, child_info)),
 			prefix,
 			current_key,
 			_phantom: PhantomData,
@@ -266,8 +314,14 @@ impl<'a, State, Block> Iterator for KeyIterator<'a, State, Block> where
 	type Item = StorageKey;
 
 	fn next(&mut self) -> Option<Self::Item> {
-		let next_key = self.state
-			.next_storage_key(&self.current_key)
+		let next_key = match &self.child_storage {
+			Some((child_storage_key, child_info)) => self.state.next_child_storage_key(
+				&child_storage_key.0,
+				child_info.as_ref(),
+				&self.current_key,
+			),
+			None => self.state.next_storage_key(&self.current_key),
+		}
 			.ok()
 			.flatten()?;
 		// this terminates the iterator the first time it fails.
@@ -324,6 +378,17 @@ pub trait StorageProvider<Block: BlockT, B: Backend<Block>> {
 		key_prefix: &StorageKey
 	) -> sp_blockchain::Result<Vec<StorageKey>>;
 
+	/// Given a `BlockId`, a child storage key and a key prefix, return a `KeyIterator` iterating
+	/// the matching child storage keys in that block, after the start key if any.
+	fn child_storage_keys_iter<'a>(
+		&self,
+		id: &BlockId<Block>,
+		child_storage_key: StorageKey,
+		child_info: OwnedChildInfo,
+		prefix: Option<&'a StorageKey>,
+		start_key: Option<&StorageKey>,
+	) -> sp_blockchain::Result<KeyIterator<'a, B::State, Block>>;
+
 	/// Given a `BlockId`, a key and a child storage key, return the hash under the key in that block.
 	fn child_storage_hash(
 		&self,
@@ -333,6 +398,14 @@ pub trait StorageProvider<Block: BlockT, B: Backend<Block>> {
 		key: &StorageKey
 	) -> sp_blockchain::Result<Option<Block::Hash>>;
 
+	/// Prevent the state of the given block from being pruned until `unpin_block` is called.
+	///
+	/// See `Backend::pin_block`.
+	fn pin_block(&self, hash: &Block::Hash) -> sp_blockchain::Result<()>;
+
+	/// Release a pin taken with `pin_block`.
+	fn unpin_block(&self, hash: &Block::Hash);
+
 	/// Get longest range within [first; last] that is possible to use in `key_changes`
 	/// and `key_changes_proof` calls.
 	/// Range could be shortened from the beginning if some changes tries have been pruned.
@@ -420,6 +493,18 @@ pub trait Backend<Block: BlockT>: AuxStore + Send + Sync {
 	/// Returns state backend with post-state of given block.
 	fn state_at(&self, block: BlockId<Block>) -> sp_blockchain::Result<Self::State>;
 
+	/// Prevent the state of the given block from being pruned until `unpin_block` is called.
+	///
+	/// Each successful call must be matched by a call to `unpin_block`. Fails with
+	/// `StatePruned` if the state isn't available anymore. Backends that never prune their
+	/// states don't need to do anything.
+	fn pin_block(&self, _hash: &Block::Hash) -> sp_blockchain::Result<()> {
+		Ok(())
+	}
+
+	/// Release a pin taken with `pin_block`.
+	fn unpin_block(&self, _hash: &Block::Hash) {}
+
 	/// Attempts to revert the chain by `n` blocks. If `revert_finalized` is set
 	/// it will attempt to revert past any finalized block, this is unsafe and
 	/// can potentially leave the node in an inconsistent state.
@@ -16,7 +16,7 @@
 
 //! A set of APIs supported by the client along with their primitives.
 
-use std::{fmt, collections::HashSet};
+use std::{fmt, collections::HashSet, sync::Arc};
 use futures::channel::mpsc;
 use sp_core::storage::StorageKey;
 use sp_runtime::{
@@ -40,7 +40,8 @@ pub type FinalityNotifications<Block> = mpsc::UnboundedReceiver<FinalityNotifica
 ///
 /// This may be used as chain spec extension to set trusted checkpoints, i.e.
 /// the client will refuse to import a block with a different hash at the given
-/// height.
+/// height. Light clients refuse such headers too, which keeps them from syncing
+/// a fork that contradicts the checkpoints.
 pub type ForkBlocks<Block> = Option<Vec<(NumberFor<Block>, <Block as BlockT>::Hash)>>;
 
 /// Known bad block hashes.
@@ -48,6 +49,16 @@ pub type ForkBlocks<Block> = Option<Vec<(NumberFor<Block>, <Block as BlockT>::Ha
 /// This may be used as chain spec extension to filter out known, unwanted forks.
 pub type BadBlocks<Block> = Option<HashSet<<Block as BlockT>::Hash>>;
 
+/// Trusted header a light client syncs from instead of the genesis block, with the roots of the
+/// header CHTs of the blocks up to it.
+///
+/// This may be used as chain spec extension. The header must be the last block of a CHT, so that
+/// the blocks before it can still be proven against the given CHT roots.
+pub type LightSyncCheckpoint<Block> = Option<(
+	<Block as BlockT>::Header,
+	Vec<<Block as BlockT>::Hash>,
+)>;
+
 /// Figure out the block type for a given type (for now, just a `Client`).
 pub trait BlockOf {
 	/// The type of the block.
@@ -60,6 +71,10 @@ pub trait BlockchainEvents<Block: BlockT> {
 	/// imported block.
 	fn import_notification_stream(&self) -> ImportNotifications<Block>;
 
+	/// Get a stream of notifications fired for every imported block, whatever its origin,
+	/// including the blocks imported during the initial sync and the blocks of forks.
+	fn every_import_notification_stream(&self) -> ImportNotifications<Block>;
+
 	/// Get a stream of finality notifications. Not guaranteed to be fired for every
 	/// finalized block.
 	fn finality_notification_stream(&self) -> FinalityNotifications<Block>;
@@ -235,10 +250,17 @@ pub struct BlockImportNotification<Block: BlockT> {
 }
 
 /// Summary of a finalized block.
+///
+/// A single notification is sent when several blocks are finalized at once, for the last one.
 #[derive(Clone, Debug)]
 pub struct FinalityNotification<Block: BlockT> {
-	/// Imported block header hash.
+	/// Finalized block header hash.
 	pub hash: Block::Hash,
-	/// Imported block header.
+	/// Finalized block header.
 	pub header: Block::Header,
+	/// Blocks finalized along with this one, i.e. its ancestors down to the previously
+	/// finalized block, excluded, ordered by block number.
+	pub tree_route: Arc<[Block::Hash]>,
+	/// Heads of the branches that got displaced by this finalization, and will be pruned.
+	pub stale_heads: Arc<[Block::Hash]>,
 }
@@ -74,6 +74,9 @@ impl ExtensionsFactory for () {
 	}
 }
 
+/// Produces offchain externalities giving read access to the offchain database.
+type OffchainDbMaker = Box<dyn Fn() -> Box<dyn offchain::Externalities> + Send + Sync>;
+
 /// A producer of execution extensions for offchain calls.
 ///
 /// This crate aggregates extensions available for the offchain calls
@@ -85,6 +88,7 @@ pub struct ExecutionExtensions<Block: traits::Block> {
 	// FIXME: these two are only RwLock because of https://github.com/paritytech/substrate/issues/4587
 	//        remove when fixed.
 	transaction_pool: RwLock<Option<Weak<dyn sp_transaction_pool::OffchainSubmitTransaction<Block>>>>,
+	offchain_db: RwLock<Option<OffchainDbMaker>>,
 	extensions_factory: RwLock<Box<dyn ExtensionsFactory>>,
 }
 
@@ -94,6 +98,7 @@ impl<Block: traits::Block> Default for ExecutionExtensions<Block> {
 			strategies: Default::default(),
 			keystore: None,
 			transaction_pool: RwLock::new(None),
+			offchain_db: RwLock::new(None),
 			extensions_factory: RwLock::new(Box::new(())),
 		}
 	}
@@ -106,8 +111,15 @@ impl<Block: traits::Block> ExecutionExtensions<Block> {
 		keystore: Option<BareCryptoStorePtr>,
 	) -> Self {
 		let transaction_pool = RwLock::new(None);
+		let offchain_db = RwLock::new(None);
 		let extensions_factory = Box::new(());
-		Self { strategies, keystore, extensions_factory: RwLock::new(extensions_factory), transaction_pool }
+		Self {
+			strategies,
+			keystore,
+			extensions_factory: RwLock::new(extensions_factory),
+			transaction_pool,
+			offchain_db,
+		}
 	}
 
 	/// Get a reference to the execution strategies.
@@ -130,6 +142,18 @@ impl<Block: traits::Block> ExecutionExtensions<Block> {
 		*self.transaction_pool.write() = Some(pool);
 	}
 
+	/// Register the offchain externalities giving read access to the offchain database.
+	///
+	/// They are given to the offchain calls with the `OffchainWorkerDbRead` capability which
+	/// aren't given their own offchain externalities, e.g. the ordinary runtime api calls.
+	pub fn register_offchain_db<Db>(&self, db: Db)
+		where Db: offchain::Externalities + Clone + Sync + 'static
+	{
+		*self.offchain_db.write() = Some(Box::new(move || {
+			Box::new(db.clone()) as Box<dyn offchain::Externalities>
+		}));
+	}
+
 	/// Create `ExecutionManager` and `Extensions` for given offchain call.
 	///
 	/// Based on the execution context and capabilities it produces
@@ -178,6 +202,13 @@ impl<Block: traits::Block> ExecutionExtensions<Block> {
 			extensions.register(
 				OffchainExt::new(offchain::LimitedExternalities::new(capabilities, ext.0))
 			)
+		} else if capabilities.has(offchain::Capability::OffchainWorkerDbRead) {
+			if let Some(offchain_db) = self.offchain_db.read().as_ref() {
+				extensions.register(OffchainExt::new(offchain::LimitedExternalities::new(
+					[offchain::Capability::OffchainWorkerDbRead][..].into(),
+					offchain_db(),
+				)))
+			}
 		}
 
 		(manager, extensions)
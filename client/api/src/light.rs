@@ -250,6 +250,16 @@ pub trait Storage<Block: BlockT>: AuxStore + HeaderBackend<Block> + HeaderMetada
 		aux_ops: Vec<(Vec<u8>, Option<Vec<u8>>)>,
 	) -> ClientResult<()>;
 
+	/// Store a trusted header as the best and finalized one, along with the roots of the header
+	/// CHTs of the blocks up to it. Only allowed while the genesis is the best block.
+	///
+	/// The header must be the last block of a CHT, see `LightSyncCheckpoint`.
+	fn import_checkpoint(
+		&self,
+		header: Block::Header,
+		header_cht_roots: Vec<Block::Hash>,
+	) -> ClientResult<()>;
+
 	/// Set an existing block as new best block.
 	fn set_head(&self, block: BlockId<Block>) -> ClientResult<()>;
 
@@ -259,6 +269,9 @@ pub trait Storage<Block: BlockT>: AuxStore + HeaderBackend<Block> + HeaderMetada
 	/// Get last finalized header.
 	fn last_finalized(&self) -> ClientResult<Block::Hash>;
 
+	/// Get the size of the CHTs the finalized headers are replaced with.
+	fn cht_size(&self) -> NumberFor<Block>;
+
 	/// Get headers CHT root for given block. Returns None if the block is not pruned (not a part of any CHT).
 	fn header_cht_root(
 		&self,
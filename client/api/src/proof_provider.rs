@@ -40,6 +40,26 @@ pub trait ProofProvider<Block: BlockT> {
 		keys: &mut dyn Iterator<Item=&[u8]>,
 	) -> sp_blockchain::Result<StorageProof>;
 
+	/// Reads storage value at a given block + key, returning read proof unless it gets larger
+	/// than `size_limit` bytes, in which case the keys left are not read and `None` is returned.
+	fn read_proof_with_size_limit(
+		&self,
+		id: &BlockId<Block>,
+		keys: &mut dyn Iterator<Item=&[u8]>,
+		size_limit: usize,
+	) -> sp_blockchain::Result<Option<StorageProof>>;
+
+	/// Reads child storage value at a given block + storage_key + key, returning read proof
+	/// unless it gets larger than `size_limit` bytes, in which case `None` is returned.
+	fn read_child_proof_with_size_limit(
+		&self,
+		id: &BlockId<Block>,
+		storage_key: &[u8],
+		child_info: ChildInfo,
+		keys: &mut dyn Iterator<Item=&[u8]>,
+		size_limit: usize,
+	) -> sp_blockchain::Result<Option<StorageProof>>;
+
 	/// Execute a call to a contract on top of state in a block of given hash
 	/// AND returning execution proof.
 	///
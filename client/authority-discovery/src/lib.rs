@@ -36,7 +36,8 @@
 //!
 //! 2. **Discovers other authorities**
 //!
-//!    1. Retrieves the current set of authorities.
+//!    1. Retrieves the current and the next set of authorities, so the authorities joining at the
+//!       next session are already connected to when it starts.
 //!
 //!    2. Starts DHT queries for the ids of the authorities.
 //!
@@ -66,7 +67,7 @@ use sp_authority_discovery::{AuthorityDiscoveryApi, AuthorityId, AuthoritySignat
 use sp_core::crypto::{key_types, Pair};
 use sp_core::traits::BareCryptoStorePtr;
 use sp_runtime::{traits::Block as BlockT, generic::BlockId};
-use sp_api::ProvideRuntimeApi;
+use sp_api::{ApiExt, ProvideRuntimeApi};
 use addr_cache::AddrCache;
 
 #[cfg(test)]
@@ -310,11 +311,7 @@ where
 
 		let id = BlockId::hash(self.client.info().best_hash);
 
-		let authorities = self
-			.client
-			.runtime_api()
-			.authorities(&id)
-			.map_err(Error::CallingRuntime)?;
+		let authorities = self.current_and_next_authorities(&id)?;
 
 		for authority_id in authorities.iter() {
 			self.network
@@ -399,7 +396,7 @@ where
 			// From the Dht we only get the hashed authority id. In order to retrieve the actual
 			// authority id and to ensure it is actually an authority, we match the hash against the
 			// hash of the authority id of all other authorities.
-			let authorities = self.client.runtime_api().authorities(&block_id)?;
+			let authorities = self.current_and_next_authorities(&block_id)?;
 			self.addr_cache.retain_ids(&authorities);
 			authorities
 				.into_iter()
@@ -407,7 +404,7 @@ where
 				.collect::<Result<HashMap<_, _>>>()?
 		};
 
-		// Check if the event origins from an authority in the current authority set.
+		// Check if the event origins from an authority in the current or the next authority set.
 		let authority_id: &AuthorityId = authorities
 			.get(&remote_key)
 			.ok_or(Error::MatchingHashedAuthorityIdWithAuthorityId)?;
@@ -446,8 +443,8 @@ where
 		Ok(())
 	}
 
-	/// Retrieve all local authority discovery private keys that are within the current authority
-	/// set.
+	/// Retrieve all local authority discovery private keys that are within the current or the next
+	/// authority set.
 	fn get_priv_keys_within_authority_set(&mut self) -> Result<Vec<AuthorityPair>> {
 		let keys = self.get_own_public_keys_within_authority_set()?
 			.into_iter()
@@ -461,12 +458,13 @@ where
 		Ok(keys)
 	}
 
-	/// Retrieve our public keys within the current authority set.
+	/// Retrieve our public keys within the current or the next authority set.
 	//
 	// A node might have multiple authority discovery keys within its keystore, e.g. an old one and
 	// one for the upcoming session. In addition it could be participating in the current authority
 	// set with two keys. The function does not return all of the local authority discovery public
-	// keys, but only the ones intersecting with the current authority set.
+	// keys, but only the ones intersecting with the current or the next authority set, so that an
+	// authority is discoverable before the session it joins starts.
 	fn get_own_public_keys_within_authority_set(&mut self) -> Result<HashSet<AuthorityId>> {
 		let local_pub_keys = self.key_store
 			.read()
@@ -475,16 +473,12 @@ where
 			.collect::<HashSet<_>>();
 
 		let id = BlockId::hash(self.client.info().best_hash);
-		let current_authorities = self
-			.client
-			.runtime_api()
-			.authorities(&id)
-			.map_err(Error::CallingRuntime)?
+		let authorities = self.current_and_next_authorities(&id)?
 			.into_iter()
 			.map(std::convert::Into::into)
 			.collect::<HashSet<_>>();
 
-		let intersection = local_pub_keys.intersection(&current_authorities)
+		let intersection = local_pub_keys.intersection(&authorities)
 			.cloned()
 			.map(std::convert::Into::into)
 			.collect();
@@ -492,6 +486,30 @@ where
 		Ok(intersection)
 	}
 
+	/// Retrieve the authorities of the current authority set, followed by those of the next one
+	/// which aren't in the current one.
+	///
+	/// Runtimes implementing the first version of the `AuthorityDiscoveryApi` only expose the
+	/// current authority set.
+	fn current_and_next_authorities(&self, id: &BlockId<Block>) -> Result<Vec<AuthorityId>> {
+		let runtime_api = self.client.runtime_api();
+		let mut authorities = runtime_api.authorities(id).map_err(Error::CallingRuntime)?;
+
+		let has_next_authorities = runtime_api
+			.has_api_with::<dyn AuthorityDiscoveryApi<Block, Error = ()>, _>(id, |v| v >= 2)
+			.map_err(Error::CallingRuntime)?;
+		if has_next_authorities {
+			let next_authorities = runtime_api.next_authorities(id)
+				.map_err(Error::CallingRuntime)?
+				.into_iter()
+				.filter(|authority| !authorities.contains(authority))
+				.collect::<Vec<_>>();
+			authorities.extend(next_authorities);
+		}
+
+		Ok(authorities)
+	}
+
 	/// Update the peer set 'authority' priority group.
 	//
 	fn update_peer_set_priority_group(&self) -> Result<()> {
@@ -91,6 +91,7 @@ fn interval_at_with_initial_delay() {
 #[derive(Clone)]
 struct TestApi {
 	authorities: Vec<AuthorityId>,
+	next_authorities: Vec<AuthorityId>,
 }
 
 impl ProvideRuntimeApi<Block> for TestApi {
@@ -99,6 +100,7 @@ impl ProvideRuntimeApi<Block> for TestApi {
 	fn runtime_api<'a>(&'a self) -> ApiRef<'a, Self::Api> {
 		RuntimeApi {
 			authorities: self.authorities.clone(),
+			next_authorities: self.next_authorities.clone(),
 		}
 		.into()
 	}
@@ -147,6 +149,7 @@ impl<Block: BlockT> HeaderBackend<Block> for TestApi {
 
 struct RuntimeApi {
 	authorities: Vec<AuthorityId>,
+	next_authorities: Vec<AuthorityId>,
 }
 
 impl Core<Block> for RuntimeApi {
@@ -201,7 +204,13 @@ impl ApiExt<Block> for RuntimeApi {
 		&self,
 		_: &BlockId<Block>,
 	) -> std::result::Result<RuntimeVersion, Self::Error> {
-		unimplemented!("Not required for testing!")
+		Ok(RuntimeVersion {
+			apis: vec![(
+				<dyn AuthorityDiscoveryApi<Block, Error = ()> as sp_api::RuntimeApiInfo>::ID,
+				2,
+			)].into(),
+			..Default::default()
+		})
 	}
 
 	fn record_proof(&mut self) {
@@ -212,6 +221,14 @@ impl ApiExt<Block> for RuntimeApi {
 		unimplemented!("Not required for testing!")
 	}
 
+	fn collect_changed_keys(&mut self) {
+		unimplemented!("Not required for testing!")
+	}
+
+	fn take_changed_keys(&mut self) -> Vec<Vec<u8>> {
+		unimplemented!("Not required for testing!")
+	}
+
 	fn into_storage_changes(
 		&self,
 		_: &Self::StateBackend,
@@ -234,6 +251,16 @@ impl AuthorityDiscoveryApi<Block> for RuntimeApi {
 	) -> std::result::Result<NativeOrEncoded<Vec<AuthorityId>>, sp_blockchain::Error> {
 		return Ok(NativeOrEncoded::Native(self.authorities.clone()));
 	}
+
+	fn AuthorityDiscoveryApi_next_authorities_runtime_api_impl(
+		&self,
+		_: &BlockId<Block>,
+		_: ExecutionContext,
+		_: Option<()>,
+		_: Vec<u8>,
+	) -> std::result::Result<NativeOrEncoded<Vec<AuthorityId>>, sp_blockchain::Error> {
+		return Ok(NativeOrEncoded::Native(self.next_authorities.clone()));
+	}
 }
 
 #[derive(Default)]
@@ -282,6 +309,7 @@ fn new_registers_metrics() {
 	let key_store = KeyStore::new();
 	let test_api = Arc::new(TestApi {
 		authorities: vec![],
+		next_authorities: vec![],
 	});
 
 	let registry = prometheus_endpoint::Registry::new();
@@ -309,6 +337,7 @@ fn publish_ext_addresses_puts_record_on_dht() {
 		.unwrap();
 	let test_api = Arc::new(TestApi {
 		authorities: vec![public.into()],
+		next_authorities: vec![],
 	});
 
 	let mut authority_discovery = AuthorityDiscovery::new(
@@ -337,6 +366,7 @@ fn request_addresses_of_others_triggers_dht_get_query() {
 
 	let test_api = Arc::new(TestApi {
 		authorities: vec![authority_1_key_pair.public(), authority_2_key_pair.public()],
+		next_authorities: vec![],
 	});
 
 	let network: Arc<TestNetwork> = Arc::new(Default::default());
@@ -366,6 +396,7 @@ fn handle_dht_events_with_value_found_should_call_set_priority_group() {
 	let key_pair = AuthorityPair::from_seed_slice(&[1; 32]).unwrap();
 	let test_api = Arc::new(TestApi {
 		authorities: vec![key_pair.public()],
+		next_authorities: vec![],
 	});
 	let network: Arc<TestNetwork> = Arc::new(Default::default());
 	let key_store = KeyStore::new();
@@ -423,3 +454,72 @@ fn handle_dht_events_with_value_found_should_call_set_priority_group() {
 
 	let _ = block_on(poll_fn(f));
 }
+
+#[test]
+fn next_authority_is_discovered_through_the_dht() {
+	let _ = ::env_logger::try_init();
+
+	// The next authority set is made of a new authority.
+	let current_authority = AuthorityPair::from_seed_slice(&[1; 32]).unwrap();
+	let next_key_store = KeyStore::new();
+	let next_authority = next_key_store
+		.write()
+		.sr25519_generate_new(key_types::AUTHORITY_DISCOVERY, None)
+		.unwrap();
+	let test_api = Arc::new(TestApi {
+		authorities: vec![current_authority.public()],
+		next_authorities: vec![current_authority.public(), next_authority.clone().into()],
+	});
+
+	// The next authority publishes the address of its sentry node.
+	let address: Multiaddr = "/ip6/2001:db8::".parse().unwrap();
+	let (_next_dht_event_tx, next_dht_event_rx) = channel(1000);
+	let next_network: Arc<TestNetwork> = Arc::new(Default::default());
+	let mut next_authority_discovery = AuthorityDiscovery::new(
+		test_api.clone(),
+		next_network.clone(),
+		vec![address.to_string()],
+		next_key_store,
+		next_dht_event_rx.boxed(),
+		None,
+	);
+
+	next_authority_discovery.publish_ext_addresses().unwrap();
+
+	let record = next_network.put_value_call.lock().unwrap().pop()
+		.expect("The next authority publishes its addresses.");
+	assert_eq!(record.0, hash_authority_id(next_authority.as_ref()).unwrap());
+
+	// The current authority looks the next one up and connects to it.
+	let (mut dht_event_tx, dht_event_rx) = channel(1000);
+	let network: Arc<TestNetwork> = Arc::new(Default::default());
+	let mut authority_discovery = AuthorityDiscovery::new(
+		test_api,
+		network.clone(),
+		vec![],
+		KeyStore::new(),
+		dht_event_rx.boxed(),
+		None,
+	);
+
+	authority_discovery.request_addresses_of_others().unwrap();
+	assert!(network.get_value_call.lock().unwrap().contains(&record.0));
+
+	dht_event_tx.try_send(sc_network::DhtEvent::ValueFound(vec![record])).unwrap();
+
+	let f = |cx: &mut Context<'_>| -> Poll<()> {
+		authority_discovery.handle_dht_events(cx).unwrap();
+
+		assert_eq!(
+			network.set_priority_group_call.lock().unwrap().last(),
+			Some(&(
+				"authorities".to_string(),
+				HashSet::from_iter(vec![address.clone()].into_iter()),
+			)),
+		);
+
+		Poll::Ready(())
+	};
+
+	let _ = block_on(poll_fn(f));
+}
@@ -23,8 +23,7 @@ use sc_client_api::backend;
 use codec::Decode;
 use sp_consensus::{evaluation, Proposal, RecordProof};
 use sp_inherents::InherentData;
-use log::{error, info, debug, trace};
-use sp_core::ExecutionContext;
+use log::{error, info, debug, trace, warn};
 use sp_runtime::{
 	generic::BlockId,
 	traits::{Block as BlockT, Hash as HashT, Header as HeaderT, DigestFor, BlakeTwo256},
@@ -36,6 +35,54 @@ use sp_api::{ProvideRuntimeApi, ApiExt};
 use futures::{executor, future, future::Either};
 use sp_blockchain::{HeaderBackend, ApplyExtrinsicFailed};
 use std::marker::PhantomData;
+use prometheus_endpoint::{
+	register, Gauge, Histogram, HistogramOpts, PrometheusError, Registry, U64,
+};
+
+/// If the block is full we will attempt to push at most
+/// this number of transactions before quitting for real, by default.
+/// It allows us to increase block utilization.
+const DEFAULT_MAX_SKIPPED_TRANSACTIONS: usize = 8;
+
+/// The proportion of the proposing time spent pushing transactions to the block by default,
+/// leaving some time for evaluation and block finalization.
+const DEFAULT_PUSHING_TIME_PROPORTION: f32 = 2f32 / 3f32;
+
+/// Prometheus metrics of the proposer.
+#[derive(Clone)]
+struct Metrics {
+	block_constructed: Histogram,
+	number_of_transactions: Gauge<U64>,
+	block_size: Gauge<U64>,
+}
+
+impl Metrics {
+	fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			block_constructed: register(
+				Histogram::with_opts(HistogramOpts::new(
+					"proposer_block_constructed",
+					"Time taken to construct the proposed blocks, in seconds",
+				))?,
+				registry,
+			)?,
+			number_of_transactions: register(
+				Gauge::new(
+					"proposer_number_of_transactions",
+					"Number of transactions included in the last proposed block",
+				)?,
+				registry,
+			)?,
+			block_size: register(
+				Gauge::new(
+					"proposer_block_size_bytes",
+					"Size of the last proposed block, in bytes",
+				)?,
+				registry,
+			)?,
+		})
+	}
+}
 
 /// Proposer factory.
 pub struct ProposerFactory<A, B, C> {
@@ -43,18 +90,52 @@ pub struct ProposerFactory<A, B, C> {
 	client: Arc<C>,
 	/// The transaction pool.
 	transaction_pool: Arc<A>,
+	/// The number of transactions to try once the block is full.
+	max_skipped_transactions: usize,
+	/// The proportion of the proposing time spent pushing transactions.
+	pushing_time_proportion: f32,
+	/// The prometheus metrics, if any.
+	metrics: Option<Metrics>,
 	/// phantom member to pin the `Backend` type.
 	_phantom: PhantomData<B>,
 }
 
 impl<A, B, C> ProposerFactory<A, B, C> {
-	pub fn new(client: Arc<C>, transaction_pool: Arc<A>) -> Self {
+	pub fn new(client: Arc<C>, transaction_pool: Arc<A>, prometheus: Option<&Registry>) -> Self {
+		let metrics = prometheus.and_then(|registry| match Metrics::register(registry) {
+			Ok(metrics) => Some(metrics),
+			Err(e) => {
+				error!("Failed to register the proposer metrics: {:?}", e);
+				None
+			},
+		});
+
 		ProposerFactory {
 			client,
 			transaction_pool,
+			max_skipped_transactions: DEFAULT_MAX_SKIPPED_TRANSACTIONS,
+			pushing_time_proportion: DEFAULT_PUSHING_TIME_PROPORTION,
+			metrics,
 			_phantom: PhantomData,
 		}
 	}
+
+	/// Set the proportion of the proposing time spent pushing transactions to the block, the
+	/// rest being left for evaluation and block finalization.
+	///
+	/// Once it's over, the block is proposed with the transactions pushed so far.
+	pub fn set_pushing_time_proportion(&mut self, proportion: f32) {
+		self.pushing_time_proportion = proportion.max(0.0).min(1.0);
+	}
+
+	/// Set the number of transactions that don't fit in the block the proposer skips before
+	/// it considers the block full.
+	///
+	/// The next transactions of the pool are still tried, since smaller ones, or ones of
+	/// another dispatch class, might fit in the rest of the block.
+	pub fn set_max_skipped_transactions(&mut self, max_skipped_transactions: usize) {
+		self.max_skipped_transactions = max_skipped_transactions;
+	}
 }
 
 impl<B, Block, C, A> ProposerFactory<A, B, C>
@@ -85,6 +166,9 @@ impl<B, Block, C, A> ProposerFactory<A, B, C>
 				parent_id: id,
 				parent_number: *parent_header.number(),
 				transaction_pool: self.transaction_pool.clone(),
+				max_skipped_transactions: self.max_skipped_transactions,
+				pushing_time_proportion: self.pushing_time_proportion,
+				metrics: self.metrics.clone(),
 				now,
 				_phantom: PhantomData,
 			}),
@@ -129,6 +213,9 @@ struct ProposerInner<B, Block: BlockT, C, A: TransactionPool> {
 	parent_id: BlockId<Block>,
 	parent_number: <<Block as BlockT>::Header as HeaderT>::Number,
 	transaction_pool: Arc<A>,
+	max_skipped_transactions: usize,
+	pushing_time_proportion: f32,
+	metrics: Option<Metrics>,
 	now: Box<dyn Fn() -> time::Instant + Send + Sync>,
 	_phantom: PhantomData<B>,
 }
@@ -159,8 +246,8 @@ impl<A, B, Block, C> sp_consensus::Proposer<Block> for
 	) -> Self::Proposal {
 		let inner = self.inner.clone();
 		tokio_executor::blocking::run(move || {
-			// leave some time for evaluation and block finalization (33%)
-			let deadline = (inner.now)() + max_duration - max_duration / 3;
+			// leave some time for evaluation and block finalization
+			let deadline = (inner.now)() + max_duration.mul_f32(inner.pushing_time_proportion);
 			inner.propose_with(inherent_data, inherent_digests, deadline, record_proof)
 		})
 	}
@@ -183,11 +270,7 @@ impl<A, B, Block, C> ProposerInner<B, Block, C, A>
 		deadline: time::Instant,
 		record_proof: RecordProof,
 	) -> Result<Proposal<Block, backend::TransactionFor<B, Block>>, sp_blockchain::Error> {
-		/// If the block is full we will attempt to push at most
-		/// this number of transactions before quitting for real.
-		/// It allows us to increase block utilization.
-		const MAX_SKIPPED_TRANSACTIONS: usize = 8;
-
+		let started = time::Instant::now();
 		let mut block_builder = self.client.new_block_at(
 			&self.parent_id,
 			inherent_digests,
@@ -196,14 +279,12 @@ impl<A, B, Block, C> ProposerInner<B, Block, C, A>
 
 		// We don't check the API versions any further here since the dispatch compatibility
 		// check should be enough.
-		for extrinsic in self.client.runtime_api()
-			.inherent_extrinsics_with_context(
-				&self.parent_id,
-				ExecutionContext::BlockConstruction,
-				inherent_data
-			)?
-		{
-			block_builder.push(extrinsic)?;
+		for error in block_builder.push_inherents(inherent_data)? {
+			warn!(
+				"Dropping the inherent of `{}` which can't be pushed: {}",
+				String::from_utf8_lossy(&error.identifier),
+				error.reason,
+			);
 		}
 
 		// proceed with transactions
@@ -226,16 +307,18 @@ impl<A, B, Block, C> ProposerInner<B, Block, C, A>
 		debug!("Attempting to push transactions from the pool.");
 		debug!("Pool status: {:?}", self.transaction_pool.status());
 		for pending_tx in pending_iterator {
+			let pending_tx_data = pending_tx.data().clone();
+			let pending_tx_hash = pending_tx.hash().clone();
+
 			if (self.now)() > deadline {
 				debug!(
-					"Consensus deadline reached when pushing block transactions, \
-					proceeding with proposing."
+					"[{:?}] Consensus deadline reached when pushing block transactions, \
+					proceeding with proposing.",
+					pending_tx_hash,
 				);
 				break;
 			}
 
-			let pending_tx_data = pending_tx.data().clone();
-			let pending_tx_hash = pending_tx.hash().clone();
 			trace!("[{:?}] Pushing to the block.", pending_tx_hash);
 			match sc_block_builder::BlockBuilder::push(&mut block_builder, pending_tx_data) {
 				Ok(()) => {
@@ -246,14 +329,14 @@ impl<A, B, Block, C> ProposerInner<B, Block, C, A>
 					if is_first {
 						debug!("[{:?}] Invalid transaction: FullBlock on empty block", pending_tx_hash);
 						unqueue_invalid.push(pending_tx_hash);
-					} else if skipped < MAX_SKIPPED_TRANSACTIONS {
+					} else if skipped < self.max_skipped_transactions {
 						skipped += 1;
 						debug!(
 							"Block seems full, but will try {} more transactions before quitting.",
-							MAX_SKIPPED_TRANSACTIONS - skipped,
+							self.max_skipped_transactions - skipped,
 						);
 					} else {
-						debug!("Block is full, proceed with proposing.");
+						debug!("[{:?}] Block is full, proceed with proposing.", pending_tx_hash);
 						break;
 					}
 				}
@@ -277,6 +360,12 @@ impl<A, B, Block, C> ProposerInner<B, Block, C, A>
 
 		let (block, storage_changes, proof) = block_builder.build()?.into_inner();
 
+		if let Some(metrics) = self.metrics.as_ref() {
+			metrics.block_constructed.observe(started.elapsed().as_secs_f64());
+			metrics.number_of_transactions.set(block.extrinsics().len() as u64);
+			metrics.block_size.set(block.encoded_size() as u64);
+		}
+
 		info!("Prepared block for proposing at {} [hash: {:?}; parent_hash: {}; extrinsics ({}): [{}]]",
 			block.header().number(),
 			<Block as BlockT>::Hash::from(block.header().hash()),
@@ -309,19 +398,22 @@ impl<A, B, Block, C> ProposerInner<B, Block, C, A>
 mod tests {
 	use super::*;
 
+	use codec::Encode;
 	use parking_lot::Mutex;
 	use sp_consensus::{BlockOrigin, Proposer};
 	use substrate_test_runtime_client::{
 		prelude::*,
 		runtime::{Extrinsic, Transfer},
 	};
-	use sp_transaction_pool::{ChainEvent, MaintainedTransactionPool};
+	use sp_transaction_pool::{ChainEvent, MaintainedTransactionPool, TransactionSource};
 	use sc_transaction_pool::{BasicPool, FullChainApi};
 	use sp_api::Core;
 	use backend::Backend;
 	use sp_blockchain::HeaderBackend;
 	use sp_runtime::traits::NumberFor;
 
+	const SOURCE: TransactionSource = TransactionSource::External;
+
 	fn extrinsic(nonce: u64) -> Extrinsic {
 		Transfer {
 			amount: Default::default(),
@@ -338,6 +430,7 @@ mod tests {
 			id: BlockId::Number(block_number.into()),
 			retracted: vec![],
 			is_new_best: true,
+			is_major_syncing: false,
 			header: header,
 		}
 	}
@@ -347,11 +440,11 @@ mod tests {
 		// given
 		let client = Arc::new(substrate_test_runtime_client::new());
 		let txpool = Arc::new(
-			BasicPool::new(Default::default(), Arc::new(FullChainApi::new(client.clone()))).0
+			BasicPool::new(Default::default(), Arc::new(FullChainApi::new(client.clone())), None).0
 		);
 
 		futures::executor::block_on(
-			txpool.submit_at(&BlockId::number(0), vec![extrinsic(0), extrinsic(1)])
+			txpool.submit_at(&BlockId::number(0), SOURCE, vec![extrinsic(0), extrinsic(1)])
 		).unwrap();
 
 		futures::executor::block_on(
@@ -361,7 +454,7 @@ mod tests {
 			))
 		);
 
-		let mut proposer_factory = ProposerFactory::new(client.clone(), txpool.clone());
+		let mut proposer_factory = ProposerFactory::new(client.clone(), txpool.clone(), None);
 
 		let cell = Mutex::new((false, time::Instant::now()));
 		let mut proposer = proposer_factory.init_with_now(
@@ -391,19 +484,60 @@ mod tests {
 		assert_eq!(txpool.ready().count(), 2);
 	}
 
+	#[test]
+	fn should_record_metrics_of_proposed_blocks() {
+		// given
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let txpool = Arc::new(
+			BasicPool::new(Default::default(), Arc::new(FullChainApi::new(client.clone())), None).0
+		);
+
+		futures::executor::block_on(
+			txpool.submit_at(&BlockId::number(0), SOURCE, vec![extrinsic(0), extrinsic(1)])
+		).unwrap();
+
+		let genesis_header = client.header(&BlockId::Number(0u64))
+			.expect("header get error")
+			.expect("there should be header");
+		futures::executor::block_on(txpool.maintain(chain_event(0, genesis_header.clone())));
+
+		let registry = Registry::new();
+		let mut proposer_factory = ProposerFactory::new(
+			client.clone(),
+			txpool.clone(),
+			Some(&registry),
+		);
+		let mut proposer = proposer_factory.init_with_now(
+			&genesis_header,
+			Box::new(move || time::Instant::now()),
+		);
+
+		// when
+		let deadline = time::Duration::from_secs(9);
+		let block = futures::executor::block_on(
+			proposer.propose(Default::default(), Default::default(), deadline, RecordProof::No)
+		).map(|r| r.block).unwrap();
+
+		// then
+		let metrics = proposer_factory.metrics.as_ref().unwrap();
+		assert_eq!(metrics.block_constructed.get_sample_count(), 1);
+		assert_eq!(metrics.number_of_transactions.get(), 2);
+		assert_eq!(metrics.block_size.get(), block.encoded_size() as u64);
+	}
+
 	#[test]
 	fn proposed_storage_changes_should_match_execute_block_storage_changes() {
 		let (client, backend) = substrate_test_runtime_client::TestClientBuilder::new()
 			.build_with_backend();
 		let client = Arc::new(client);
 		let txpool = Arc::new(
-			BasicPool::new(Default::default(), Arc::new(FullChainApi::new(client.clone()))).0
+			BasicPool::new(Default::default(), Arc::new(FullChainApi::new(client.clone())), None).0
 		);
 		let genesis_hash = client.info().best_hash;
 		let block_id = BlockId::Hash(genesis_hash);
 
 		futures::executor::block_on(
-			txpool.submit_at(&BlockId::number(0), vec![extrinsic(0)]),
+			txpool.submit_at(&BlockId::number(0), SOURCE, vec![extrinsic(0)]),
 		).unwrap();
 
 		futures::executor::block_on(
@@ -413,7 +547,7 @@ mod tests {
 			))
 		);
 
-		let mut proposer_factory = ProposerFactory::new(client.clone(), txpool.clone());
+		let mut proposer_factory = ProposerFactory::new(client.clone(), txpool.clone(), None);
 
 		let mut proposer = proposer_factory.init_with_now(
 			&client.header(&block_id).unwrap().unwrap(),
@@ -450,11 +584,11 @@ mod tests {
 		// given
 		let mut client = Arc::new(substrate_test_runtime_client::new());
 		let txpool = Arc::new(
-			BasicPool::new(Default::default(), Arc::new(FullChainApi::new(client.clone()))).0
+			BasicPool::new(Default::default(), Arc::new(FullChainApi::new(client.clone())), None).0
 		);
 
 		futures::executor::block_on(
-			txpool.submit_at(&BlockId::number(0), vec![
+			txpool.submit_at(&BlockId::number(0), SOURCE, vec![
 				extrinsic(0),
 				extrinsic(1),
 				Transfer {
@@ -475,7 +609,7 @@ mod tests {
 			])
 		).unwrap();
 
-		let mut proposer_factory = ProposerFactory::new(client.clone(), txpool.clone());
+		let mut proposer_factory = ProposerFactory::new(client.clone(), txpool.clone(), None);
 		let mut propose_block = |
 			client: &TestClient,
 			number,
@@ -523,4 +657,56 @@ mod tests {
 		let block = propose_block(&client, 1, 2, 5);
 		client.import(BlockOrigin::Own, block).unwrap();
 	}
+
+	#[test]
+	fn should_push_smaller_transactions_after_skipping_up_to_the_limit() {
+		// given
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let txpool = Arc::new(
+			BasicPool::new(Default::default(), Arc::new(FullChainApi::new(client.clone())), None).0
+		);
+
+		futures::executor::block_on(
+			txpool.submit_at(&BlockId::number(0), SOURCE, vec![
+				extrinsic(0),
+				Transfer {
+					amount: Default::default(),
+					nonce: 1,
+					from: AccountKeyring::Alice.into(),
+					to: Default::default(),
+				}.into_resources_exhausting_tx(),
+				Transfer {
+					amount: Default::default(),
+					nonce: 0,
+					from: AccountKeyring::Bob.into(),
+					to: Default::default(),
+				}.into_signed_tx(),
+			])
+		).unwrap();
+
+		let genesis_header = client.header(&BlockId::Number(0u64))
+			.expect("header get error")
+			.expect("there should be header");
+		futures::executor::block_on(txpool.maintain(chain_event(0, genesis_header)));
+
+		let propose_block = |max_skipped_transactions| {
+			let mut proposer_factory = ProposerFactory::new(client.clone(), txpool.clone(), None);
+			proposer_factory.set_max_skipped_transactions(max_skipped_transactions);
+			let mut proposer = proposer_factory.init_with_now(
+				&client.header(&BlockId::number(0)).unwrap().unwrap(),
+				Box::new(move || time::Instant::now()),
+			);
+
+			let deadline = time::Duration::from_secs(9);
+			futures::executor::block_on(
+				proposer.propose(Default::default(), Default::default(), deadline, RecordProof::No)
+			).map(|r| r.block).unwrap()
+		};
+
+		// then
+		// the transfer of Bob still fits in the block once the big one is skipped.
+		assert_eq!(propose_block(1).extrinsics().len(), 2);
+		assert_eq!(propose_block(0).extrinsics().len(), 1);
+		assert_eq!(txpool.ready().count(), 3);
+	}
 }
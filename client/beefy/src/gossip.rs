@@ -0,0 +1,241 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Validation of the BEEFY votes gossiped between the voters.
+
+use codec::{Decode, Encode};
+use log::trace;
+use parking_lot::RwLock;
+use sc_network::PeerId;
+use sc_network_gossip::{MessageIntent, ValidationResult, Validator, ValidatorContext};
+use sp_application_crypto::Pair;
+use sp_beefy::{AuthorityId, AuthorityPair, ValidatorSet};
+use sp_runtime::traits::{Block as BlockT, Hash, Header as HeaderT, NumberFor, Saturating};
+
+use crate::{VoteMessage, LOG_TARGET};
+
+/// The maximum number of blocks above the last finalized one whose votes are accepted, as the
+/// peers may finalize the blocks a bit before this node.
+const MAX_BLOCKS_AHEAD: u32 = 128;
+
+/// The topic all the votes are gossiped on.
+pub(crate) fn topic<B: BlockT>() -> B::Hash {
+	<<B::Header as HeaderT>::Hashing as Hash>::hash(b"beefy")
+}
+
+/// Validator of the gossiped votes, keeping those of the authorities of the current set for the
+/// blocks above the last concluded round, up to `MAX_BLOCKS_AHEAD` blocks above the last
+/// finalized one.
+pub(crate) struct GossipValidator<B: BlockT> {
+	topic: B::Hash,
+	validator_set: RwLock<Option<ValidatorSet<AuthorityId>>>,
+	best_finalized: RwLock<Option<NumberFor<B>>>,
+	best_concluded: RwLock<Option<NumberFor<B>>>,
+}
+
+impl<B: BlockT> GossipValidator<B> {
+	pub(crate) fn new() -> Self {
+		GossipValidator {
+			topic: topic::<B>(),
+			validator_set: RwLock::new(None),
+			best_finalized: RwLock::new(None),
+			best_concluded: RwLock::new(None),
+		}
+	}
+
+	/// Note the current set of authorities, the votes of the others being discarded.
+	pub(crate) fn note_validator_set(&self, validator_set: ValidatorSet<AuthorityId>) {
+		*self.validator_set.write() = Some(validator_set);
+	}
+
+	/// Note that the given block has been finalized, which allows the votes for the blocks up to
+	/// `MAX_BLOCKS_AHEAD` blocks above it.
+	pub(crate) fn note_finalized(&self, number: NumberFor<B>) {
+		let mut best_finalized = self.best_finalized.write();
+		if best_finalized.map_or(true, |best| number > best) {
+			*best_finalized = Some(number);
+		}
+	}
+
+	/// Note that the round of the given block has been concluded, which expires the votes for
+	/// it and the blocks before it.
+	pub(crate) fn note_concluded(&self, number: NumberFor<B>) {
+		let mut best_concluded = self.best_concluded.write();
+		if best_concluded.map_or(true, |best| number > best) {
+			*best_concluded = Some(number);
+		}
+	}
+
+	/// Whether the vote is for a concluded round, or for another set of authorities than the
+	/// current one.
+	fn is_expired(&self, vote: &VoteMessage<NumberFor<B>>) -> bool {
+		let other_set = self.validator_set.read().as_ref()
+			.map_or(false, |set| set.id != vote.commitment.validator_set_id);
+		let concluded = self.best_concluded.read()
+			.map_or(false, |best| vote.commitment.block_number <= best);
+
+		other_set || concluded
+	}
+
+	fn is_too_far_ahead(&self, number: NumberFor<B>) -> bool {
+		self.best_finalized.read()
+			.map_or(false, |best| number > best.saturating_add(MAX_BLOCKS_AHEAD.into()))
+	}
+
+	fn is_voter(&self, vote: &VoteMessage<NumberFor<B>>) -> bool {
+		self.validator_set.read().as_ref().map_or(false, |set| {
+			set.id == vote.commitment.validator_set_id && set.validators.contains(&vote.id)
+		})
+	}
+
+	fn decode_vote(data: &[u8]) -> Option<VoteMessage<NumberFor<B>>> {
+		VoteMessage::<NumberFor<B>>::decode(&mut &data[..]).ok()
+	}
+}
+
+impl<B: BlockT> Validator<B> for GossipValidator<B> {
+	fn validate(
+		&self,
+		_context: &mut dyn ValidatorContext<B>,
+		sender: &PeerId,
+		data: &[u8],
+	) -> ValidationResult<B::Hash> {
+		let vote = match Self::decode_vote(data) {
+			Some(vote) => vote,
+			None => return ValidationResult::Discard,
+		};
+
+		if self.is_expired(&vote) || self.is_too_far_ahead(vote.commitment.block_number) {
+			return ValidationResult::Discard;
+		}
+
+		if !self.is_voter(&vote) {
+			trace!(
+				target: LOG_TARGET,
+				"Vote from {:?} isn't from an authority of the current set",
+				sender,
+			);
+			return ValidationResult::Discard;
+		}
+
+		if !AuthorityPair::verify(&vote.signature, &vote.commitment.encode(), &vote.id) {
+			trace!(target: LOG_TARGET, "Bad signature on vote from {:?}", sender);
+			return ValidationResult::Discard;
+		}
+
+		ValidationResult::ProcessAndKeep(self.topic)
+	}
+
+	fn message_expired<'a>(&'a self) -> Box<dyn FnMut(B::Hash, &[u8]) -> bool + 'a> {
+		Box::new(move |_topic, data| match Self::decode_vote(data) {
+			Some(vote) => self.is_expired(&vote),
+			None => true,
+		})
+	}
+
+	fn message_allowed<'a>(
+		&'a self,
+	) -> Box<dyn FnMut(&PeerId, MessageIntent, &B::Hash, &[u8]) -> bool + 'a> {
+		Box::new(move |_who, _intent, _topic, data| match Self::decode_vote(data) {
+			Some(vote) => {
+				!self.is_expired(&vote) && !self.is_too_far_ahead(vote.commitment.block_number)
+			},
+			None => false,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_beefy::{Commitment, MmrRootHash};
+	use sp_runtime::testing::{Block as RawBlock, ExtrinsicWrapper};
+
+	type Block = RawBlock<ExtrinsicWrapper<u64>>;
+
+	type Hash = <Block as BlockT>::Hash;
+
+	struct TestContext;
+	impl ValidatorContext<Block> for TestContext {
+		fn broadcast_topic(&mut self, _topic: Hash, _force: bool) {}
+		fn broadcast_message(&mut self, _topic: Hash, _message: Vec<u8>, _force: bool) {}
+		fn send_message(&mut self, _who: &PeerId, _message: Vec<u8>) {}
+		fn send_topic(&mut self, _who: &PeerId, _topic: Hash, _force: bool) {}
+	}
+
+	fn authority(seed: u8) -> AuthorityPair {
+		AuthorityPair::from_string(&format!("//{}", seed), None).unwrap()
+	}
+
+	fn vote(pair: &AuthorityPair, block_number: u64, validator_set_id: u64) -> Vec<u8> {
+		let commitment = Commitment {
+			payload: MmrRootHash::repeat_byte(1),
+			block_number,
+			validator_set_id,
+		};
+		let signature = pair.sign(&commitment.encode());
+		VoteMessage { commitment, id: pair.public(), signature }.encode()
+	}
+
+	fn is_kept(validator: &GossipValidator<Block>, data: &[u8]) -> bool {
+		match validator.validate(&mut TestContext, &PeerId::random(), data) {
+			ValidationResult::ProcessAndKeep(_) => true,
+			_ => false,
+		}
+	}
+
+	#[test]
+	fn only_votes_of_the_current_set_are_kept() {
+		let validator = GossipValidator::<Block>::new();
+		let (alice, bob) = (authority(0), authority(1));
+
+		// the votes can't be checked until the set is known.
+		assert!(!is_kept(&validator, &vote(&alice, 1, 0)));
+
+		validator.note_validator_set(ValidatorSet { validators: vec![alice.public()], id: 0 });
+		assert!(is_kept(&validator, &vote(&alice, 1, 0)));
+		assert!(!is_kept(&validator, &vote(&bob, 1, 0)));
+		assert!(!is_kept(&validator, &vote(&alice, 1, 1)));
+
+		// the votes of the previous set expire.
+		let previous_vote = vote(&alice, 1, 0);
+		validator.note_validator_set(ValidatorSet { validators: vec![alice.public()], id: 1 });
+		assert!(validator.message_expired()(topic::<Block>(), &previous_vote));
+		assert!(!is_kept(&validator, &previous_vote));
+		assert!(is_kept(&validator, &vote(&alice, 1, 1)));
+	}
+
+	#[test]
+	fn votes_are_kept_between_the_concluded_round_and_the_blocks_ahead() {
+		let validator = GossipValidator::<Block>::new();
+		let alice = authority(0);
+		validator.note_validator_set(ValidatorSet { validators: vec![alice.public()], id: 0 });
+
+		validator.note_finalized(10);
+		assert!(is_kept(&validator, &vote(&alice, 10 + MAX_BLOCKS_AHEAD as u64, 0)));
+		assert!(!is_kept(&validator, &vote(&alice, 11 + MAX_BLOCKS_AHEAD as u64, 0)));
+
+		validator.note_concluded(5);
+		assert!(!is_kept(&validator, &vote(&alice, 5, 0)));
+		assert!(is_kept(&validator, &vote(&alice, 6, 0)));
+
+		// a bad signature is discarded.
+		let mut data = vote(&alice, 6, 0);
+		let last = data.len() - 1;
+		data[last] ^= 1;
+		assert!(!is_kept(&validator, &data));
+	}
+}
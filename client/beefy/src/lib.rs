@@ -0,0 +1,309 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! BEEFY, a secondary finality gadget for Substrate.
+//!
+//! The gadget follows the blocks finalized by the primary finality gadget (e.g. GRANDPA). For
+//! each of them whose header carries the root of the Merkle Mountain Range of the chain in a
+//! `ConsensusLog::MmrRoot` digest, and at least `min_block_delta` blocks after the last one
+//! voted on, the BEEFY authorities sign a commitment to that root and gossip their votes. Once
+//! more than two thirds of the authorities of the set have signed a commitment, the signatures
+//! are collected in a `SignedCommitment`, which is all another chain needs to check that the
+//! block is final.
+//!
+//! The set of authorities is the one announced by the runtime (see `pallet-beefy`) in the
+//! `ConsensusLog::AuthoritiesChange` digests.
+
+use std::sync::Arc;
+
+use codec::{Decode, Encode};
+use futures::{future, prelude::*, channel::mpsc};
+use log::{debug, error, info, trace, warn};
+use parking_lot::Mutex;
+
+use sc_client_api::{BlockchainEvents, FinalityNotification};
+use sc_network_gossip::{GossipEngine, Network as GossipNetwork};
+use sp_api::ProvideRuntimeApi;
+use sp_application_crypto::Pair;
+use sp_beefy::{
+	AuthorityId, AuthorityPair, AuthoritySignature, BeefyApi, Commitment, ConsensusLog,
+	MmrRootHash, ValidatorSet, BEEFY_ENGINE_ID,
+};
+use sp_runtime::{
+	generic::{BlockId, OpaqueDigestItemId},
+	traits::{Block as BlockT, Header as HeaderT, NumberFor, Saturating},
+};
+
+mod gossip;
+mod round;
+
+use gossip::GossipValidator;
+use round::Rounds;
+
+const LOG_TARGET: &str = "beefy";
+
+/// The name of the gossip protocol of BEEFY.
+pub const BEEFY_PROTOCOL_NAME: &[u8] = b"/paritytech/beefy/1";
+
+/// A vote of a BEEFY authority, as gossiped.
+pub type VoteMessage<Number> =
+	sp_beefy::VoteMessage<MmrRootHash, Number, AuthorityId, AuthoritySignature>;
+
+/// A commitment signed by more than two thirds of the BEEFY authorities.
+pub type SignedCommitment<Number> =
+	sp_beefy::SignedCommitment<Number, MmrRootHash, AuthoritySignature>;
+
+/// Parameters used to run BEEFY.
+pub struct BeefyParams<B: BlockT, C, N> {
+	/// The client of the chain.
+	pub client: Arc<C>,
+	/// The keystore holding the BEEFY key of this node, if it is an authority.
+	pub key_store: sc_keystore::KeyStorePtr,
+	/// The network the votes are gossiped on.
+	pub network: N,
+	/// Where to send the commitments signed by the authorities.
+	pub signed_commitment_sender: mpsc::UnboundedSender<SignedCommitment<NumberFor<B>>>,
+	/// The minimal number of finalized blocks between two commitments voted on.
+	pub min_block_delta: u32,
+	/// The prometheus metrics registry, if any.
+	pub prometheus_registry: Option<prometheus_endpoint::Registry>,
+}
+
+/// Run the BEEFY gadget until the client or the network stops.
+pub async fn start_beefy_gadget<B, C, N>(params: BeefyParams<B, C, N>) where
+	B: BlockT,
+	C: BlockchainEvents<B> + ProvideRuntimeApi<B> + Send + Sync + 'static,
+	C::Api: BeefyApi<B, Error = sp_blockchain::Error>,
+	N: GossipNetwork<B> + Clone + Send + 'static,
+{
+	let BeefyParams {
+		client,
+		key_store,
+		network,
+		signed_commitment_sender,
+		min_block_delta,
+		prometheus_registry,
+	} = params;
+
+	let gossip_validator = Arc::new(GossipValidator::new());
+	let gossip_engine = GossipEngine::new(
+		network,
+		BEEFY_ENGINE_ID,
+		BEEFY_PROTOCOL_NAME,
+		gossip_validator.clone(),
+		prometheus_registry.as_ref(),
+	);
+
+	let worker = BeefyWorker {
+		client,
+		key_store,
+		signed_commitment_sender,
+		gossip_engine: Arc::new(Mutex::new(gossip_engine)),
+		gossip_validator,
+		min_block_delta: min_block_delta.max(1),
+		rounds: None,
+		best_voted: None,
+	};
+
+	worker.run().await
+}
+
+struct BeefyWorker<B: BlockT, C> {
+	client: Arc<C>,
+	key_store: sc_keystore::KeyStorePtr,
+	signed_commitment_sender: mpsc::UnboundedSender<SignedCommitment<NumberFor<B>>>,
+	gossip_engine: Arc<Mutex<GossipEngine<B>>>,
+	gossip_validator: Arc<GossipValidator<B>>,
+	min_block_delta: u32,
+	/// The rounds of the current set of authorities, once it is known.
+	rounds: Option<Rounds<MmrRootHash, NumberFor<B>>>,
+	/// The last block this node voted on.
+	best_voted: Option<NumberFor<B>>,
+}
+
+impl<B, C> BeefyWorker<B, C> where
+	B: BlockT,
+	C: BlockchainEvents<B> + ProvideRuntimeApi<B>,
+	C::Api: BeefyApi<B, Error = sp_blockchain::Error>,
+{
+	/// Our key in the current set of authorities, if any.
+	fn local_authority(&self) -> Option<AuthorityPair> {
+		let rounds = self.rounds.as_ref()?;
+		let key_store = self.key_store.read();
+
+		rounds.validators().iter().find_map(|id| key_store.key_pair::<AuthorityPair>(id).ok())
+	}
+
+	fn should_vote_on(&self, number: NumberFor<B>) -> bool {
+		self.best_voted
+			.map_or(true, |best| number >= best.saturating_add(self.min_block_delta.into()))
+	}
+
+	/// Switch to the set of authorities announced in the header, or to the one of the runtime
+	/// if the set is still unknown.
+	fn update_validator_set(&mut self, header: &B::Header) {
+		let announced = find_authorities_change::<B>(header);
+		let validator_set = match (announced, self.rounds.as_ref()) {
+			(Some(set), Some(rounds)) if set.id == rounds.validator_set_id() => return,
+			(Some(set), _) => set,
+			(None, Some(_)) => return,
+			(None, None) => {
+				let at = BlockId::hash(header.hash());
+				match self.client.runtime_api().validator_set(&at) {
+					Ok(set) => set,
+					Err(e) => {
+						debug!(target: LOG_TARGET, "Unable to fetch the validator set: {:?}", e);
+						return;
+					},
+				}
+			},
+		};
+
+		info!(target: LOG_TARGET, "🥩 New validator set with id {}", validator_set.id);
+		self.gossip_validator.note_validator_set(validator_set.clone());
+		self.rounds = Some(Rounds::new(validator_set));
+	}
+
+	fn handle_finality_notification(&mut self, notification: FinalityNotification<B>) {
+		trace!(target: LOG_TARGET, "Finality notification: {:?}", notification.hash);
+		self.update_validator_set(&notification.header);
+
+		let number = *notification.header.number();
+		self.gossip_validator.note_finalized(number);
+		if !self.should_vote_on(number) {
+			return;
+		}
+
+		let payload = match find_mmr_root::<B>(&notification.header) {
+			Some(payload) => payload,
+			None => return,
+		};
+
+		let (pair, validator_set_id) = match (self.local_authority(), self.rounds.as_ref()) {
+			(Some(pair), Some(rounds)) => (pair, rounds.validator_set_id()),
+			_ => return,
+		};
+
+		let commitment = Commitment {
+			payload,
+			block_number: number,
+			validator_set_id,
+		};
+		let signature = pair.sign(&commitment.encode());
+		let message = VoteMessage {
+			commitment,
+			id: pair.public(),
+			signature,
+		};
+
+		debug!(target: LOG_TARGET, "Voting on block {:?} with payload {:?}", number, payload);
+		self.best_voted = Some(number);
+		self.gossip_engine.lock().gossip_message(gossip::topic::<B>(), message.encode(), false);
+		self.handle_vote(message);
+	}
+
+	fn handle_vote(&mut self, vote: VoteMessage<NumberFor<B>>) {
+		let rounds = match self.rounds.as_mut() {
+			Some(rounds) => rounds,
+			None => return,
+		};
+
+		// votes for another set of authorities can't be checked against the current one.
+		if vote.commitment.validator_set_id != rounds.validator_set_id() {
+			return;
+		}
+
+		let round = (vote.commitment.payload, vote.commitment.block_number);
+		if !rounds.add_vote(round, (vote.id, vote.signature)) || !rounds.is_done(&round) {
+			return;
+		}
+
+		let signatures = match rounds.drop(&round) {
+			Some(signatures) => signatures,
+			None => return,
+		};
+		let block_number = vote.commitment.block_number;
+		let signed_commitment = SignedCommitment {
+			commitment: vote.commitment,
+			signatures,
+		};
+
+		info!(
+			target: LOG_TARGET,
+			"🥩 Round #{:?} concluded with {} signatures",
+			block_number,
+			signed_commitment.no_of_signatures(),
+		);
+		self.gossip_validator.note_concluded(block_number);
+		if self.signed_commitment_sender.unbounded_send(signed_commitment).is_err() {
+			warn!(target: LOG_TARGET, "Signed commitment receiver has been dropped");
+		}
+	}
+
+	async fn run(mut self) {
+		let mut finality_notifications = self.client.finality_notification_stream().fuse();
+		let mut votes = self.gossip_engine.lock()
+			.messages_for(gossip::topic::<B>())
+			.filter_map(|notification| future::ready(
+				VoteMessage::<NumberFor<B>>::decode(&mut &notification.message[..]).ok()
+			))
+			.fuse();
+
+		loop {
+			let engine = self.gossip_engine.clone();
+			let gossip_engine = future::poll_fn(|cx| engine.lock().poll_unpin(cx));
+
+			futures::select! {
+				notification = finality_notifications.next() => match notification {
+					Some(notification) => self.handle_finality_notification(notification),
+					None => return,
+				},
+				vote = votes.next() => match vote {
+					Some(vote) => self.handle_vote(vote),
+					None => return,
+				},
+				_ = gossip_engine.fuse() => {
+					error!(target: LOG_TARGET, "Gossip engine has terminated.");
+					return;
+				},
+			}
+		}
+	}
+}
+
+/// The set of authorities announced in the header, if any.
+fn find_authorities_change<B: BlockT>(header: &B::Header) -> Option<ValidatorSet<AuthorityId>> {
+	let id = OpaqueDigestItemId::Consensus(&BEEFY_ENGINE_ID);
+
+	let filter_log = |log: ConsensusLog<AuthorityId>| match log {
+		ConsensusLog::AuthoritiesChange(validator_set) => Some(validator_set),
+		_ => None,
+	};
+
+	header.digest().convert_first(|l| l.try_to(id).and_then(filter_log))
+}
+
+/// The root of the Merkle Mountain Range found in the header, if any.
+fn find_mmr_root<B: BlockT>(header: &B::Header) -> Option<MmrRootHash> {
+	let id = OpaqueDigestItemId::Consensus(&BEEFY_ENGINE_ID);
+
+	let filter_log = |log: ConsensusLog<AuthorityId>| match log {
+		ConsensusLog::MmrRoot(root) => Some(root),
+		_ => None,
+	};
+
+	header.digest().convert_first(|l| l.try_to(id).and_then(filter_log))
+}
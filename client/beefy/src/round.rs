@@ -0,0 +1,128 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Collection of the votes of the BEEFY rounds.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use sp_beefy::{AuthorityId, AuthoritySignature, ValidatorSet, ValidatorSetId};
+
+/// The votes of the rounds of a set of authorities, one round per payload and block number.
+pub(crate) struct Rounds<Payload, Number> {
+	rounds: HashMap<(Payload, Number), Vec<(AuthorityId, AuthoritySignature)>>,
+	validator_set: ValidatorSet<AuthorityId>,
+}
+
+impl<Payload, Number> Rounds<Payload, Number> where
+	Payload: Eq + Hash,
+	Number: Eq + Hash,
+{
+	pub(crate) fn new(validator_set: ValidatorSet<AuthorityId>) -> Self {
+		Rounds {
+			rounds: HashMap::new(),
+			validator_set,
+		}
+	}
+
+	pub(crate) fn validator_set_id(&self) -> ValidatorSetId {
+		self.validator_set.id
+	}
+
+	pub(crate) fn validators(&self) -> &[AuthorityId] {
+		&self.validator_set.validators
+	}
+
+	/// Add the vote of an authority of the set to the round. The votes of the authorities which
+	/// aren't part of the set, and those already counted, are ignored.
+	pub(crate) fn add_vote(
+		&mut self,
+		round: (Payload, Number),
+		vote: (AuthorityId, AuthoritySignature),
+	) -> bool {
+		if !self.validator_set.validators.contains(&vote.0) {
+			return false;
+		}
+
+		let votes = self.rounds.entry(round).or_default();
+		if votes.iter().any(|(id, _)| *id == vote.0) {
+			return false;
+		}
+
+		votes.push(vote);
+		true
+	}
+
+	/// Whether more than two thirds of the authorities voted in the round.
+	pub(crate) fn is_done(&self, round: &(Payload, Number)) -> bool {
+		self.rounds.get(round)
+			.map_or(false, |votes| votes.len() >= self.validator_set.threshold())
+	}
+
+	/// Conclude the round, returning the signatures of its votes in the order of the authorities
+	/// of the set.
+	pub(crate) fn drop(
+		&mut self,
+		round: &(Payload, Number),
+	) -> Option<Vec<Option<AuthoritySignature>>> {
+		let votes = self.rounds.remove(round)?;
+
+		Some(self.validator_set.validators.iter().map(|authority| {
+			votes.iter()
+				.find(|(id, _)| id == authority)
+				.map(|(_, signature)| signature.clone())
+		}).collect())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_application_crypto::Pair;
+	use sp_beefy::AuthorityPair;
+
+	fn authority(seed: u8) -> AuthorityPair {
+		AuthorityPair::from_string(&format!("//{}", seed), None).unwrap()
+	}
+
+	#[test]
+	fn round_is_done_with_more_than_two_thirds_of_the_votes() {
+		let pairs = (0..4).map(authority).collect::<Vec<_>>();
+		let mut rounds = Rounds::<[u8; 4], u64>::new(ValidatorSet {
+			validators: pairs.iter().map(|pair| pair.public()).collect(),
+			id: 0,
+		});
+		let round = (*b"root", 1);
+		let vote = |pair: &AuthorityPair| (pair.public(), pair.sign(b"commitment"));
+
+		assert!(rounds.add_vote(round, vote(&pairs[3])));
+		assert!(rounds.add_vote(round, vote(&pairs[1])));
+		// votes are only counted once, and only for the authorities of the set.
+		assert!(!rounds.add_vote(round, vote(&pairs[1])));
+		assert!(!rounds.add_vote(round, vote(&authority(4))));
+		assert!(!rounds.is_done(&round));
+
+		assert!(rounds.add_vote(round, vote(&pairs[0])));
+		assert!(rounds.is_done(&round));
+
+		let signatures = rounds.drop(&round).unwrap();
+		assert_eq!(
+			signatures.iter().map(Option::is_some).collect::<Vec<_>>(),
+			vec![true, true, false, true],
+		);
+		assert!(rounds.drop(&round).is_none());
+	}
+}
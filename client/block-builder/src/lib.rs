@@ -24,6 +24,8 @@
 
 #![warn(missing_docs)]
 
+mod prefetch;
+
 use codec::Encode;
 
 use sp_runtime::{
@@ -34,11 +36,14 @@ use sp_blockchain::{ApplyExtrinsicFailed, Error};
 use sp_core::ExecutionContext;
 use sp_api::{Core, ApiExt, ApiErrorFor, ApiRef, ProvideRuntimeApi, StorageChanges, StorageProof};
 use sp_consensus::RecordProof;
+use sp_inherents::{InherentData, InherentIdentifier};
 
 pub use sp_block_builder::BlockBuilder as BlockBuilderApi;
 
 use sc_client_api::backend;
 
+use crate::prefetch::StatePrefetcher;
+
 /// A block that was build by [`BlockBuilder`] plus some additional data.
 ///
 /// This additional data includes the `storage_changes`, these changes can be applied to the
@@ -61,6 +66,15 @@ impl<Block: BlockT, StateBackend: backend::StateBackend<HashFor<Block>>> BuiltBl
 	}
 }
 
+/// An inherent which couldn't be pushed onto the block.
+#[derive(Debug)]
+pub struct InherentError<E = Error> {
+	/// The identifier of the inherent data the inherent is created from.
+	pub identifier: InherentIdentifier,
+	/// Why the inherent couldn't be pushed.
+	pub reason: E,
+}
+
 /// Block builder provider
 pub trait BlockBuilderProvider<B, Block, RA>
 	where
@@ -95,6 +109,7 @@ pub struct BlockBuilder<'a, Block: BlockT, A: ProvideRuntimeApi<Block>, B> {
 	block_id: BlockId<Block>,
 	parent_hash: Block::Hash,
 	backend: &'a B,
+	prefetcher: Option<StatePrefetcher>,
 }
 
 impl<'a, Block, A, B> BlockBuilder<'a, Block, A, B>
@@ -103,7 +118,7 @@ where
 	A: ProvideRuntimeApi<Block> + 'a,
 	A::Api: BlockBuilderApi<Block, Error = Error> +
 		ApiExt<Block, StateBackend = backend::StateBackendFor<B, Block>>,
-	B: backend::Backend<Block>,
+	B: backend::Backend<Block> + 'static,
 {
 	/// Create a new instance of builder based on the given `parent_hash` and `parent_number`.
 	///
@@ -134,17 +149,28 @@ where
 
 		let block_id = BlockId::Hash(parent_hash);
 
+		// the storage root is computed in the end, read what it needs as the block is built.
+		let prefetcher = backend.state_at(block_id)
+			.ok()
+			.and_then(StatePrefetcher::new::<Block, _>);
+		if prefetcher.is_some() {
+			api.collect_changed_keys();
+		}
+
 		api.initialize_block_with_context(
 			&block_id, ExecutionContext::BlockConstruction, &header,
 		)?;
 
-		Ok(Self {
+		let mut builder = Self {
 			parent_hash,
 			extrinsics: Vec::new(),
 			api,
 			block_id,
 			backend,
-		})
+			prefetcher,
+		};
+		builder.prefetch_changed_keys();
+		Ok(builder)
 	}
 
 	/// Push onto the block's list of extrinsics.
@@ -166,7 +192,60 @@ where
 				}
 				Err(tx_validity) => Err(ApplyExtrinsicFailed::Validity(tx_validity).into()),
 			}
-		})
+		})?;
+
+		self.prefetch_changed_keys();
+		Ok(())
+	}
+
+	/// Push the inherents created from the inherent data onto the block's list of extrinsics.
+	///
+	/// The inherents are pushed one after the other, those that can't be validly executed being
+	/// left out of the block and returned along with the identifier of their inherent data.
+	/// Failing to push a mandatory inherent fails, as the block would be invalid without it, and
+	/// so do the runtimes that can't tell the inherents apart as soon as one of them can't be
+	/// pushed.
+	pub fn push_inherents(
+		&mut self,
+		inherent_data: InherentData,
+	) -> Result<Vec<InherentError<ApiErrorFor<A, Block>>>, ApiErrorFor<A, Block>> {
+		let separately = self.api.has_api_with::<dyn BlockBuilderApi<Block, Error = ()>, _>(
+			&self.block_id,
+			|version| version >= 5,
+		)?;
+		if !separately {
+			let inherents = self.api.inherent_extrinsics_with_context(
+				&self.block_id,
+				ExecutionContext::BlockConstruction,
+				inherent_data,
+			)?;
+			for inherent in inherents {
+				self.push(inherent)?;
+			}
+			return Ok(Vec::new());
+		}
+
+		let inherents = self.api.inherent_extrinsics_with_identifiers_with_context(
+			&self.block_id,
+			ExecutionContext::BlockConstruction,
+			inherent_data,
+		)?;
+		let mut errors = Vec::new();
+		for (identifier, mandatory, inherent) in inherents {
+			match self.push(inherent) {
+				Ok(()) => {},
+				Err(reason) if mandatory => return Err(reason),
+				Err(reason) => errors.push(InherentError { identifier, reason }),
+			}
+		}
+		Ok(errors)
+	}
+
+	/// Hand the keys changed since the last call over to the prefetcher.
+	fn prefetch_changed_keys(&mut self) {
+		if let Some(ref prefetcher) = self.prefetcher {
+			prefetcher.prefetch(self.api.take_changed_keys());
+		}
 	}
 
 	/// Consume the builder to build a valid `Block` containing all pushed extrinsics.
@@ -218,7 +297,10 @@ mod tests {
 	use sp_blockchain::HeaderBackend;
 	use sp_core::Blake2Hasher;
 	use sp_state_machine::Backend;
-	use substrate_test_runtime_client::{DefaultTestClientBuilderExt, TestClientBuilderExt};
+	use substrate_test_runtime_client::{
+		DefaultTestClientBuilderExt, TestClientBuilderExt, AccountKeyring,
+		runtime::{Extrinsic, Transfer, TEST_INHERENTS_IDENTIFIER},
+	};
 
 	#[test]
 	fn block_building_storage_proof_does_not_include_runtime_by_default() {
@@ -248,4 +330,87 @@ mod tests {
 				.contains("Database missing expected key"),
 		);
 	}
+
+	#[test]
+	fn inherents_are_pushed_separately() {
+		let builder = substrate_test_runtime_client::TestClientBuilder::new();
+		let backend = builder.backend();
+		let client = builder.build();
+
+		let mut block_builder = BlockBuilder::new(
+			&client,
+			client.info().best_hash,
+			client.info().best_number,
+			RecordProof::No,
+			Default::default(),
+			&*backend,
+		).unwrap();
+
+		assert!(block_builder.push_inherents(InherentData::new()).unwrap().is_empty());
+		assert!(block_builder.build().unwrap().block.extrinsics.is_empty());
+	}
+
+	fn invalid_inherent() -> Extrinsic {
+		Extrinsic::Transfer {
+			transfer: Transfer {
+				from: AccountKeyring::Alice.into(),
+				to: AccountKeyring::Bob.into(),
+				amount: 1,
+				nonce: 0,
+			},
+			signature: Default::default(),
+			exhaust_resources_when_not_first: false,
+		}
+	}
+
+	#[test]
+	fn inherents_which_cant_be_pushed_are_dropped() {
+		let builder = substrate_test_runtime_client::TestClientBuilder::new();
+		let backend = builder.backend();
+		let client = builder.build();
+
+		let mut block_builder = BlockBuilder::new(
+			&client,
+			client.info().best_hash,
+			client.info().best_number,
+			RecordProof::No,
+			Default::default(),
+			&*backend,
+		).unwrap();
+
+		let valid = Extrinsic::IncludeData(vec![42]);
+		let mut inherent_data = InherentData::new();
+		inherent_data.put_data(TEST_INHERENTS_IDENTIFIER, &vec![
+			(*b"invalid0", false, invalid_inherent()),
+			(*b"valid000", true, valid.clone()),
+		]).unwrap();
+
+		let errors = block_builder.push_inherents(inherent_data).unwrap();
+		assert_eq!(errors.len(), 1);
+		assert_eq!(&errors[0].identifier, b"invalid0");
+		assert_eq!(block_builder.build().unwrap().block.extrinsics, vec![valid]);
+	}
+
+	#[test]
+	fn mandatory_inherents_are_never_dropped() {
+		let builder = substrate_test_runtime_client::TestClientBuilder::new();
+		let backend = builder.backend();
+		let client = builder.build();
+
+		let mut block_builder = BlockBuilder::new(
+			&client,
+			client.info().best_hash,
+			client.info().best_number,
+			RecordProof::No,
+			Default::default(),
+			&*backend,
+		).unwrap();
+
+		let mut inherent_data = InherentData::new();
+		inherent_data.put_data(TEST_INHERENTS_IDENTIFIER, &vec![
+			(*b"invalid0", true, invalid_inherent()),
+		]).unwrap();
+
+		assert!(block_builder.push_inherents(inherent_data).is_err());
+	}
 }
@@ -0,0 +1,77 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Prefetching of the state the storage root of the block being built is computed from.
+//!
+//! The storage root is only computed once all the extrinsics are applied, and most of that time
+//! is spent reading the trie nodes on the paths of the changed keys from the database. Those are
+//! read on a worker thread while the extrinsics are still being applied, so that the backend has
+//! them cached by the time the root is computed.
+
+use std::sync::mpsc;
+use log::warn;
+use sp_runtime::traits::{Block as BlockT, HashFor};
+use sc_client_api::backend::StateBackend;
+
+/// Handle to the worker thread reading the trie nodes of the changed keys.
+///
+/// The worker stops once the handle is dropped.
+pub(crate) struct StatePrefetcher {
+	keys: mpsc::Sender<Vec<Vec<u8>>>,
+}
+
+impl StatePrefetcher {
+	/// Start prefetching from the state of the parent of the block being built.
+	///
+	/// Returns `None` if the worker couldn't be started.
+	pub(crate) fn new<Block, S>(mut state: S) -> Option<Self>
+		where
+			Block: BlockT,
+			S: StateBackend<HashFor<Block>> + Send + 'static,
+	{
+		let (keys, received_keys) = mpsc::channel::<Vec<Vec<u8>>>();
+		let worker = std::thread::Builder::new()
+			.name("block-builder-prefetch".into())
+			.spawn(move || {
+				// reading from the trie directly, as cached values don't bring their nodes in.
+				let trie_backend = match state.as_trie_backend() {
+					Some(trie_backend) => trie_backend,
+					None => return,
+				};
+				for keys in received_keys {
+					for key in keys {
+						let _ = trie_backend.storage(&key);
+					}
+				}
+			});
+
+		match worker {
+			Ok(_) => Some(StatePrefetcher { keys }),
+			Err(e) => {
+				warn!("Failed to start the block builder prefetch worker: {:?}", e);
+				None
+			},
+		}
+	}
+
+	/// Prefetch the trie nodes of the given keys.
+	pub(crate) fn prefetch(&self, keys: Vec<Vec<u8>>) {
+		if !keys.is_empty() {
+			// the worker only stops once this handle is dropped.
+			let _ = self.keys.send(keys);
+		}
+	}
+}
@@ -17,11 +17,12 @@
 //! Substrate chain configurations.
 
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs::File;
 use std::path::PathBuf;
 use std::sync::Arc;
 use serde::{Serialize, Deserialize};
+use sp_core::Bytes;
 use sp_core::storage::{StorageKey, StorageData, ChildInfo, Storage, StorageChild};
 use sp_runtime::BuildStorage;
 use serde_json as json;
@@ -34,6 +35,7 @@ enum GenesisSource<G> {
 	File(PathBuf),
 	Binary(Cow<'static, [u8]>),
 	Factory(Arc<dyn Fn() -> G + Send + Sync>),
+	Storage(Storage),
 }
 
 impl<G> Clone for GenesisSource<G> {
@@ -42,6 +44,7 @@ impl<G> Clone for GenesisSource<G> {
 			GenesisSource::File(ref path) => GenesisSource::File(path.clone()),
 			GenesisSource::Binary(ref d) => GenesisSource::Binary(d.clone()),
 			GenesisSource::Factory(ref f) => GenesisSource::Factory(f.clone()),
+			GenesisSource::Storage(ref s) => GenesisSource::Storage(s.clone()),
 		}
 	}
 }
@@ -67,10 +70,42 @@ impl<G: RuntimeGenesis> GenesisSource<G> {
 				Ok(genesis.genesis)
 			},
 			GenesisSource::Factory(f) => Ok(Genesis::Runtime(f())),
+			GenesisSource::Storage(storage) => Ok(Genesis::Raw(storage.clone().into())),
 		}
 	}
 }
 
+/// Whether both storages have the same content.
+fn same_storage(storage: &Storage, other: &Storage) -> bool {
+	storage.top == other.top &&
+		storage.children.len() == other.children.len() &&
+		storage.children.iter().all(|(key, child)| other.children.get(key).map_or(false, |other|
+			child.data == other.data && child.child_info == other.child_info
+		))
+}
+
+impl<G: RuntimeGenesis, E> ChainSpec<G, E> {
+	/// Replace the genesis of the spec with the structured one of the given JSON spec, which must
+	/// build the same genesis storage.
+	///
+	/// This converts a raw spec back to a human-readable one, given the runtime genesis config it
+	/// was built from.
+	pub fn set_structured_genesis(&mut self, json: Vec<u8>) -> Result<(), String> {
+		let genesis = GenesisSource::<G>::Binary(json.into());
+		let structured_storage = match genesis.resolve()? {
+			Genesis::Runtime(genesis_config) => genesis_config.build_storage()?,
+			Genesis::Raw(_) => return Err("The given genesis is raw as well".into()),
+		};
+
+		if !same_storage(&structured_storage, &self.build_storage()?) {
+			return Err("The given genesis doesn't build the genesis storage of the spec".into());
+		}
+
+		self.genesis = genesis;
+		Ok(())
+	}
+}
+
 impl<G: RuntimeGenesis, E> BuildStorage for ChainSpec<G, E> {
 	fn build_storage(&self) -> Result<Storage, String> {
 		match self.genesis.resolve()? {
@@ -122,6 +157,31 @@ struct RawGenesis {
 	children: HashMap<StorageKey, ChildRawStorage>,
 }
 
+impl From<Storage> for RawGenesis {
+	fn from(storage: Storage) -> Self {
+		let top = storage.top.into_iter()
+			.map(|(k, v)| (StorageKey(k), StorageData(v)))
+			.collect();
+		let children = storage.children.into_iter()
+			.map(|(sk, child)| {
+				let info = child.child_info.as_ref();
+				let (info, ci_type) = info.info();
+				(
+					StorageKey(sk),
+					ChildRawStorage {
+						data: child.data.into_iter()
+							.map(|(k, v)| (StorageKey(k), StorageData(v)))
+							.collect(),
+						child_info: info.to_vec(),
+						child_type: ci_type,
+					},
+			)})
+			.collect();
+
+		RawGenesis { top, children }
+	}
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 #[serde(deny_unknown_fields)]
@@ -147,6 +207,33 @@ struct ClientSpec<E> {
 	consensus_engine: (),
 	#[serde(skip_serializing)]
 	genesis: serde::de::IgnoredAny,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	light_sync_state: Option<SerializableLightSyncState>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	genesis_state_root: Option<Bytes>,
+	#[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+	code_substitutes: BTreeMap<String, Bytes>,
+}
+
+/// A checkpoint light clients can start syncing from, instead of the genesis block.
+///
+/// Its parts are kept SCALE-encoded, so that the chain spec doesn't depend on their types.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[serde(deny_unknown_fields)]
+pub struct SerializableLightSyncState {
+	/// The header of the finalized block to start from.
+	pub finalized_block_header: Bytes,
+	/// The BABE epoch changes at the finalized block.
+	pub babe_epoch_changes: Bytes,
+	/// The cumulative BABE weight of the finalized block.
+	pub babe_finalized_block_weight: u32,
+	/// The BABE epoch of the finalized block, along with its authorities and randomness.
+	pub babe_current_epoch: Bytes,
+	/// The BABE epoch after the one of the finalized block.
+	pub babe_next_epoch: Bytes,
+	/// The GRANDPA authority set at the finalized block.
+	pub grandpa_authority_set: Bytes,
 }
 
 /// Arbitrary properties defined in chain spec as a JSON object
@@ -215,6 +302,47 @@ impl<G, E> ChainSpec<G, E> {
 		&self.client_spec.extensions
 	}
 
+	/// The checkpoint light clients can start syncing from, if any.
+	pub fn light_sync_state(&self) -> Option<&SerializableLightSyncState> {
+		self.client_spec.light_sync_state.as_ref()
+	}
+
+	/// Set the checkpoint light clients can start syncing from.
+	pub fn set_light_sync_state(&mut self, light_sync_state: SerializableLightSyncState) {
+		self.client_spec.light_sync_state = Some(light_sync_state);
+	}
+
+	/// The state root the genesis block built from the spec is expected to have, if any.
+	pub fn genesis_state_root(&self) -> Option<&[u8]> {
+		self.client_spec.genesis_state_root.as_ref().map(|root| &root.0[..])
+	}
+
+	/// Set the state root the genesis block built from the spec is expected to have.
+	pub fn set_genesis_state_root(&mut self, root: Vec<u8>) {
+		self.client_spec.genesis_state_root = Some(root.into());
+	}
+
+	/// The runtime codes to execute instead of the on-chain one, by the number of the block they
+	/// are executed from.
+	pub fn code_substitutes(&self) -> BTreeMap<String, Vec<u8>> {
+		self.client_spec.code_substitutes.iter()
+			.map(|(number, code)| (number.clone(), code.0.clone()))
+			.collect()
+	}
+
+	/// Execute the given runtime code instead of the on-chain one from the given block number.
+	pub fn add_code_substitute(&mut self, number: u64, code: Vec<u8>) {
+		self.client_spec.code_substitutes.insert(number.to_string(), code.into());
+	}
+
+	/// Replace the genesis of the spec with the given raw storage.
+	///
+	/// The expected genesis state root is cleared, as it was the one of the former genesis.
+	pub fn set_storage(&mut self, storage: Storage) {
+		self.genesis = GenesisSource::Storage(storage);
+		self.client_spec.genesis_state_root = None;
+	}
+
 	/// Create hardcoded spec.
 	pub fn from_genesis<F: Fn() -> G + 'static + Send + Sync>(
 		name: &str,
@@ -236,6 +364,9 @@ impl<G, E> ChainSpec<G, E> {
 			extensions,
 			consensus_engine: (),
 			genesis: Default::default(),
+			light_sync_state: None,
+			genesis_state_root: None,
+			code_substitutes: BTreeMap::new(),
 		};
 
 		ChainSpec {
@@ -281,29 +412,7 @@ impl<G: RuntimeGenesis, E: serde::Serialize + Clone> ChainSpec<G, E> {
 
 		};
 		let genesis = match (raw, self.genesis.resolve()?) {
-			(true, Genesis::Runtime(g)) => {
-				let storage = g.build_storage()?;
-				let top = storage.top.into_iter()
-					.map(|(k, v)| (StorageKey(k), StorageData(v)))
-					.collect();
-				let children = storage.children.into_iter()
-					.map(|(sk, child)| {
-						let info = child.child_info.as_ref();
-						let (info, ci_type) = info.info();
-						(
-							StorageKey(sk),
-							ChildRawStorage {
-								data: child.data.into_iter()
-									.map(|(k, v)| (StorageKey(k), StorageData(v)))
-									.collect(),
-								child_info: info.to_vec(),
-								child_type: ci_type,
-							},
-					)})
-					.collect();
-
-				Genesis::Raw(RawGenesis { top, children })
-			},
+			(true, Genesis::Runtime(g)) => Genesis::Raw(g.build_storage()?.into()),
 			(_, genesis) => genesis,
 		};
 		let container = Container {
@@ -317,8 +426,8 @@ impl<G: RuntimeGenesis, E: serde::Serialize + Clone> ChainSpec<G, E> {
 
 impl<G, E> crate::ChainSpec for ChainSpec<G, E>
 where
-	G: RuntimeGenesis,
-	E: GetExtension + serde::Serialize + Clone,
+	G: RuntimeGenesis + 'static,
+	E: GetExtension + serde::Serialize + Clone + Send + Sync + 'static,
 {
 	fn boot_nodes(&self) -> &[String] {
 		ChainSpec::boot_nodes(self)
@@ -359,6 +468,34 @@ where
 	fn as_storage_builder(&self) -> &dyn BuildStorage {
 		self
 	}
+
+	fn cloned_box(&self) -> Box<dyn crate::ChainSpec> {
+		Box::new(self.clone())
+	}
+
+	fn set_light_sync_state(&mut self, light_sync_state: SerializableLightSyncState) {
+		ChainSpec::set_light_sync_state(self, light_sync_state)
+	}
+
+	fn genesis_state_root(&self) -> Option<&[u8]> {
+		ChainSpec::genesis_state_root(self)
+	}
+
+	fn set_genesis_state_root(&mut self, root: Vec<u8>) {
+		ChainSpec::set_genesis_state_root(self, root)
+	}
+
+	fn code_substitutes(&self) -> BTreeMap<String, Vec<u8>> {
+		ChainSpec::code_substitutes(self)
+	}
+
+	fn set_storage(&mut self, storage: Storage) {
+		ChainSpec::set_storage(self, storage)
+	}
+
+	fn set_structured_genesis(&mut self, json: Vec<u8>) -> Result<(), String> {
+		ChainSpec::set_structured_genesis(self, json)
+	}
 }
 
 #[cfg(test)]
@@ -410,4 +547,98 @@ mod tests {
 
 		assert_eq!(spec.extensions().my_property, "Test Extension");
 	}
+
+	#[test]
+	fn should_round_trip_the_light_sync_state() {
+		let mut spec = TestSpec::from_json_bytes(Cow::Owned(
+			include_bytes!("../res/chain_spec.json").to_vec()
+		)).unwrap();
+		assert_eq!(spec.light_sync_state(), None);
+
+		let light_sync_state = SerializableLightSyncState {
+			finalized_block_header: vec![1, 2].into(),
+			babe_epoch_changes: vec![3].into(),
+			babe_finalized_block_weight: 4,
+			babe_current_epoch: vec![6].into(),
+			babe_next_epoch: vec![7].into(),
+			grandpa_authority_set: vec![5].into(),
+		};
+		spec.set_light_sync_state(light_sync_state.clone());
+
+		let json = spec.as_json(false).unwrap();
+		let spec = TestSpec::from_json_bytes(Cow::Owned(json.into_bytes())).unwrap();
+		assert_eq!(spec.light_sync_state(), Some(&light_sync_state));
+	}
+
+	#[test]
+	fn should_round_trip_the_genesis_state_root() {
+		let mut spec = TestSpec::from_json_bytes(Cow::Owned(
+			include_bytes!("../res/chain_spec.json").to_vec()
+		)).unwrap();
+		assert_eq!(spec.genesis_state_root(), None);
+
+		spec.set_genesis_state_root(vec![1; 32]);
+		let json = spec.as_json(true).unwrap();
+		let spec = TestSpec::from_json_bytes(Cow::Owned(json.into_bytes())).unwrap();
+		assert_eq!(spec.genesis_state_root(), Some(&[1; 32][..]));
+	}
+
+	#[test]
+	fn should_round_trip_the_code_substitutes() {
+		let mut spec = TestSpec::from_json_bytes(Cow::Owned(
+			include_bytes!("../res/chain_spec.json").to_vec()
+		)).unwrap();
+		assert!(spec.code_substitutes().is_empty());
+		assert!(!spec.as_json(true).unwrap().contains("codeSubstitutes"));
+
+		spec.add_code_substitute(42, vec![1, 2, 3]);
+		let json = spec.as_json(true).unwrap();
+		let spec = TestSpec::from_json_bytes(Cow::Owned(json.into_bytes())).unwrap();
+		assert_eq!(
+			spec.code_substitutes(),
+			vec![("42".to_string(), vec![1, 2, 3])].into_iter().collect(),
+		);
+	}
+
+	#[test]
+	fn should_replace_the_genesis_with_the_given_storage() {
+		let mut spec = TestSpec::from_json_bytes(Cow::Owned(
+			include_bytes!("../res/chain_spec.json").to_vec()
+		)).unwrap();
+		spec.set_genesis_state_root(vec![1; 32]);
+
+		let mut storage = Storage::default();
+		storage.top.insert(b"key".to_vec(), b"value".to_vec());
+		spec.set_storage(storage.clone());
+		assert_eq!(spec.build_storage().unwrap().top, storage.top);
+		assert_eq!(spec.genesis_state_root(), None);
+
+		let json = spec.as_json(false).unwrap();
+		let spec = TestSpec::from_json_bytes(Cow::Owned(json.into_bytes())).unwrap();
+		assert_eq!(spec.build_storage().unwrap().top, storage.top);
+	}
+
+	#[test]
+	fn should_convert_a_raw_genesis_back_to_the_structured_one_it_was_built_from() {
+		let structured_spec = |value: &'static str| TestSpec::from_genesis(
+			"Test",
+			"test",
+			move || Genesis(vec![("key".to_string(), value.to_string())].into_iter().collect()),
+			Vec::new(),
+			None,
+			None,
+			None,
+			None,
+		);
+		let structured_json = structured_spec("value").as_json(false).unwrap();
+		let raw_json = structured_spec("value").as_json(true).unwrap();
+		let mut spec = TestSpec::from_json_bytes(Cow::Owned(raw_json.clone().into_bytes())).unwrap();
+
+		let other_json = structured_spec("other value").as_json(false).unwrap();
+		assert!(spec.set_structured_genesis(other_json.into_bytes()).is_err());
+		assert!(spec.set_structured_genesis(raw_json.into_bytes()).is_err());
+
+		spec.set_structured_genesis(structured_json.clone().into_bytes()).unwrap();
+		assert_eq!(spec.as_json(false).unwrap(), structured_json);
+	}
 }
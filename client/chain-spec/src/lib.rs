@@ -111,11 +111,14 @@
 mod chain_spec;
 mod extension;
 
-pub use chain_spec::{ChainSpec as GenericChainSpec, Properties, NoExtension};
+pub use chain_spec::{
+	ChainSpec as GenericChainSpec, Properties, NoExtension, SerializableLightSyncState,
+};
 pub use extension::{Group, Fork, Forks, Extension, GetExtension, get_extension};
 pub use sc_chain_spec_derive::{ChainSpecExtension, ChainSpecGroup};
 
 use serde::{Serialize, de::DeserializeOwned};
+use sp_core::storage::Storage;
 use sp_runtime::BuildStorage;
 use sc_network::Multiaddr;
 use sc_telemetry::TelemetryEndpoints;
@@ -125,7 +128,7 @@ pub trait RuntimeGenesis: Serialize + DeserializeOwned + BuildStorage {}
 impl<T: Serialize + DeserializeOwned + BuildStorage> RuntimeGenesis for T {}
 
 /// Common interface to `GenericChainSpec`
-pub trait ChainSpec: BuildStorage {
+pub trait ChainSpec: BuildStorage + Send + Sync {
 	/// Spec name.
 	fn name(&self) -> &str;
 	/// Spec id.
@@ -148,4 +151,31 @@ pub trait ChainSpec: BuildStorage {
 	fn as_json(&self, raw: bool) -> Result<String, String>;
 	/// Return StorageBuilder for this spec.
 	fn as_storage_builder(&self) -> &dyn BuildStorage;
+	/// Returns a cloned `Box<dyn ChainSpec>`.
+	fn cloned_box(&self) -> Box<dyn ChainSpec>;
+	/// Set the checkpoint light clients can start syncing from.
+	fn set_light_sync_state(&mut self, light_sync_state: SerializableLightSyncState);
+	/// The state root the genesis block built from the spec is expected to have, if any.
+	///
+	/// Nodes refuse to start when their genesis block has another one, which catches the genesis
+	/// files that diverged from the one the root was computed from.
+	fn genesis_state_root(&self) -> Option<&[u8]>;
+	/// Set the state root the genesis block built from the spec is expected to have.
+	fn set_genesis_state_root(&mut self, root: Vec<u8>);
+	/// The runtime codes to execute instead of the on-chain one, by the number of the block they
+	/// are executed from.
+	///
+	/// A substitute is executed as long as the on-chain runtime has its spec version, which allows
+	/// to recover from a runtime that can't be executed until it is upgraded.
+	fn code_substitutes(&self) -> std::collections::BTreeMap<String, Vec<u8>>;
+	/// Replace the genesis of the spec with the given raw storage.
+	///
+	/// This is used to start a new chain from the state of an existing one.
+	fn set_storage(&mut self, storage: Storage);
+	/// Replace the genesis of the spec with the structured one of the given JSON spec, which must
+	/// build the same genesis storage.
+	///
+	/// This converts a raw spec back to a human-readable one, given the runtime genesis config it
+	/// was built from.
+	fn set_structured_genesis(&mut self, json: Vec<u8>) -> Result<(), String>;
 }
@@ -0,0 +1,120 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Loading of the chain spec given to `--chain`.
+//!
+//! The chain spec is either one of those the node registers by name, e.g. `dev` or `local`, or is
+//! read from a JSON file or fetched from an URL. The content of a file or URL may be pinned by
+//! appending its Blake2-256 hash, e.g. `https://example.com/spec.json#0x<hash>`, the pinning being
+//! mandatory for the URLs.
+
+use std::fs;
+use sc_service::ChainSpec;
+use sp_core::{Bytes, hashing::blake2_256};
+
+/// The chain specs a node can load.
+pub trait ChainSpecLoader {
+	/// Load the chain spec the node registers with the given name, `None` if there's none.
+	fn load_named(&self, name: &str) -> Option<Result<Box<dyn ChainSpec>, String>>;
+
+	/// Parse a chain spec from its JSON content.
+	fn from_json(&self, json: Vec<u8>) -> Result<Box<dyn ChainSpec>, String>;
+}
+
+/// Load the chain spec of the given `--chain` value.
+///
+/// This is meant to be the `spec_factory` the commands update their configuration with.
+pub fn load_spec(loader: &dyn ChainSpecLoader, id: &str) -> Result<Box<dyn ChainSpec>, String> {
+	match loader.load_named(id) {
+		Some(spec) => spec,
+		None => loader.from_json(read_spec(id)?),
+	}
+}
+
+/// Read the JSON content of the chain spec file or URL, checking its hash if pinned.
+fn read_spec(id: &str) -> Result<Vec<u8>, String> {
+	let (location, hash) = match id.rfind('#') {
+		Some(index) => (&id[..index], Some(&id[index + 1..])),
+		None => (id, None),
+	};
+
+	let json = if location.starts_with("http://") || location.starts_with("https://") {
+		if hash.is_none() {
+			return Err(format!(
+				"The chain spec fetched from {} must be pinned by its hash: `{}#<Blake2-256 hash>`",
+				location,
+				location,
+			));
+		}
+		fetch(location)?
+	} else {
+		fs::read(location).map_err(|e| format!("Error opening spec file: {}", e))?
+	};
+
+	if let Some(hash) = hash {
+		let expected = hash.parse::<Bytes>()
+			.map_err(|e| format!("Invalid hash of the chain spec {}: {:?}", location, e))?;
+		if blake2_256(&json)[..] != expected.0[..] {
+			return Err(format!("The chain spec {} doesn't have the {} hash", location, hash));
+		}
+	}
+
+	Ok(json)
+}
+
+#[cfg(not(target_os = "unknown"))]
+fn fetch(url: &str) -> Result<Vec<u8>, String> {
+	let error = |e: &dyn std::fmt::Display| format!("Error fetching the chain spec {}: {}", url, e);
+	let uri = url.parse::<hyper::Uri>().map_err(|e| error(&e))?;
+	let mut runtime = tokio::runtime::Runtime::new().map_err(|e| error(&e))?;
+
+	runtime.block_on(async {
+		let client = hyper::Client::builder()
+			.build::<_, hyper::Body>(hyper_rustls::HttpsConnector::new());
+		let response = client.get(uri).await.map_err(|e| error(&e))?;
+		if !response.status().is_success() {
+			return Err(error(&response.status()));
+		}
+
+		hyper::body::to_bytes(response.into_body()).await
+			.map(|json| json.to_vec())
+			.map_err(|e| error(&e))
+	})
+}
+
+#[cfg(target_os = "unknown")]
+fn fetch(url: &str) -> Result<Vec<u8>, String> {
+	Err(format!("Fetching the chain spec {} isn't supported on this platform", url))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::io::Write;
+
+	#[test]
+	fn spec_files_are_checked_against_their_pinned_hash() {
+		let mut file = tempfile::NamedTempFile::new().unwrap();
+		file.write_all(b"{}").unwrap();
+		let path = file.path().to_str().unwrap();
+		let hash = format!("0x{}", sp_core::hexdisplay::HexDisplay::from(&blake2_256(b"{}")));
+
+		assert_eq!(read_spec(path), Ok(b"{}".to_vec()));
+		assert_eq!(read_spec(&format!("{}#{}", path, hash)), Ok(b"{}".to_vec()));
+		assert!(read_spec(&format!("{}#0x{}", path, "00".repeat(32))).is_err());
+		assert!(read_spec("https://example.com/spec.json").is_err());
+	}
+}
@@ -14,10 +14,12 @@
 // You should have received a copy of the GNU General Public License
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::path::PathBuf;
 use structopt::StructOpt;
 use log::info;
 use sc_network::config::build_multiaddr;
 use sc_service::{Configuration, ChainSpec};
+use sp_runtime::traits::Block as BlockT;
 
 use crate::error;
 use crate::VersionInfo;
@@ -31,6 +33,17 @@ pub struct BuildSpecCmd {
 	#[structopt(long = "raw")]
 	pub raw: bool,
 
+	/// Convert the raw genesis of the spec back to the structured one of the given spec file.
+	///
+	/// Fails unless the given structured genesis builds the same genesis storage.
+	#[structopt(
+		long = "structured-genesis",
+		value_name = "PATH",
+		parse(from_os_str),
+		conflicts_with = "raw"
+	)]
+	pub structured_genesis: Option<PathBuf>,
+
 	/// Disable adding the default bootnode to the specification.
 	///
 	/// By default the `/ip4/127.0.0.1/tcp/30333/p2p/NODE_PEER_ID` bootnode is added to the
@@ -49,7 +62,10 @@ pub struct BuildSpecCmd {
 
 impl BuildSpecCmd {
 	/// Run the build-spec command
-	pub fn run(
+	///
+	/// The state root of the genesis block built from the spec is written to it, for the nodes
+	/// to check their genesis against it.
+	pub fn run<B: BlockT>(
 		self,
 		config: Configuration,
 	) -> error::Result<()> {
@@ -68,6 +84,15 @@ impl BuildSpecCmd {
 			spec.add_boot_node(addr)
 		}
 
+		if let Some(path) = self.structured_genesis {
+			let json = std::fs::read(&path)
+				.map_err(|e| format!("Error reading {}: {}", path.display(), e))?;
+			spec.set_structured_genesis(json)?;
+		}
+
+		let genesis_state_root = sc_service::chain_ops::genesis_state_root::<B>(&*spec)?;
+		spec.set_genesis_state_root(genesis_state_root.as_ref().to_vec());
+
 		let json = sc_service::chain_ops::build_spec(&*spec, raw_output)?;
 
 		print!("{}", json);
@@ -0,0 +1,131 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use log::info;
+use structopt::StructOpt;
+use sc_service::{Configuration, ServiceBuilderCommand, ChainSpec, Roles};
+use sp_core::storage::{Storage, StorageKey, StorageData};
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
+
+use crate::error;
+use crate::VersionInfo;
+use crate::params::{SharedParams, BlockNumberOrHash, PruningParams};
+
+/// The `export-state` command used to export the state of a given block.
+///
+/// By default the state is exported as the genesis of a copy of the chain spec, so that a new
+/// chain can be started from it.
+#[derive(Debug, StructOpt, Clone)]
+pub struct ExportStateCmd {
+	/// Block hash or number.
+	///
+	/// Default is best block.
+	#[structopt(value_name = "HASH or NUMBER")]
+	pub input: Option<BlockNumberOrHash>,
+
+	/// Output file name or stdout if unspecified.
+	#[structopt(long = "output", value_name = "FILE", parse(from_os_str))]
+	pub output: Option<PathBuf>,
+
+	/// Only export the key-value pairs of the state, instead of a whole chain spec.
+	#[structopt(long = "storage-only")]
+	pub storage_only: bool,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub pruning_params: PruningParams,
+}
+
+impl ExportStateCmd {
+	/// Run the export-state command
+	pub fn run<B, BC, BB>(
+		self,
+		config: Configuration,
+		builder: B,
+	) -> error::Result<()>
+	where
+		B: FnOnce(Configuration) -> Result<BC, sc_service::error::Error>,
+		BC: ServiceBuilderCommand<Block = BB> + Unpin,
+		BB: sp_runtime::traits::Block + Debug,
+		<<<BB as BlockT>::Header as HeaderT>::Number as std::str::FromStr>::Err: std::fmt::Debug,
+		<BB as BlockT>::Hash: std::str::FromStr,
+	{
+		let block = self.input.as_ref().map(|input| input.parse()).transpose()?;
+		let mut spec = config.expect_chain_spec().cloned_box();
+
+		info!("Exporting raw state...");
+		let storage = builder(config)?.export_raw_state(block)?;
+
+		let json = if self.storage_only {
+			storage_json(storage)?
+		} else {
+			spec.set_storage(storage);
+			let genesis_state_root = sc_service::chain_ops::genesis_state_root::<BB>(&*spec)?;
+			spec.set_genesis_state_root(genesis_state_root.as_ref().to_vec());
+			sc_service::chain_ops::build_spec(&*spec, true)?
+		};
+
+		let mut file: Box<dyn Write> = match &self.output {
+			Some(filename) => Box::new(fs::File::create(filename)?),
+			None => Box::new(io::stdout()),
+		};
+		file.write_all(json.as_bytes())?;
+
+		Ok(())
+	}
+
+	/// Update and prepare a `Configuration` with command line parameters
+	pub fn update_config<F>(
+		&self,
+		mut config: &mut Configuration,
+		spec_factory: F,
+		version: &VersionInfo,
+	) -> error::Result<()> where
+		F: FnOnce(&str) -> Result<Box<dyn ChainSpec>, String>,
+	{
+		self.shared_params.update_config(&mut config, spec_factory, version)?;
+		self.pruning_params.update_config(&mut config, Roles::FULL, true)?;
+		config.use_in_memory_keystore()?;
+
+		Ok(())
+	}
+}
+
+/// Serialize the key-value pairs of the top and child tries, hex encoded.
+fn storage_json(storage: Storage) -> error::Result<String> {
+	type Pairs = BTreeMap<StorageKey, StorageData>;
+
+	let pairs = |data: BTreeMap<Vec<u8>, Vec<u8>>| -> Pairs {
+		data.into_iter().map(|(k, v)| (StorageKey(k), StorageData(v))).collect()
+	};
+
+	let top = pairs(storage.top);
+	let children: BTreeMap<StorageKey, Pairs> = storage.children.into_iter()
+		.map(|(key, child)| (StorageKey(key), pairs(child.data)))
+		.collect();
+
+	serde_json::to_string_pretty(&serde_json::json!({ "top": top, "children": children }))
+		.map_err(|e| error::Error::Other(format!("Error serializing the state: {}", e)))
+}
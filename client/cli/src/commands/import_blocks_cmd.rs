@@ -37,6 +37,10 @@ pub struct ImportBlocksCmd {
 	#[structopt(parse(from_os_str))]
 	pub input: Option<PathBuf>,
 
+	/// Try importing blocks from binary format rather than JSON.
+	#[structopt(long = "binary", value_name = "BOOL", parse(try_from_str), default_value("false"))]
+	pub binary: bool,
+
 	/// The default number of 64KB pages to ever allocate for Wasm execution.
 	///
 	/// Don't alter this unless you know what you're doing.
@@ -81,7 +85,7 @@ impl ImportBlocksCmd {
 		};
 
 		run_until_exit(config, |config| {
-			Ok(builder(config)?.import_blocks(file, false))
+			Ok(builder(config)?.import_blocks(file, false, self.binary))
 		})
 	}
 
@@ -0,0 +1,412 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::{convert::TryFrom, fs, path::{Path, PathBuf}};
+use structopt::StructOpt;
+use sc_network::{PeerId, config::{NodeKeyConfig, Secret, identity::{Keypair, ed25519}}};
+use sc_service::{Configuration, ChainSpec, config::KeystoreConfig};
+use sp_core::{
+	H256, Pair, Public, ed25519 as ed25519_core, sr25519, ecdsa,
+	crypto::KeyTypeId, hexdisplay::HexDisplay, traits::BareCryptoStore,
+};
+
+use crate::error;
+use crate::VersionInfo;
+use crate::arg_enums::CryptoScheme;
+use crate::params::{SharedParams, KeystoreParams, read_password};
+
+/// The `key` command used to manage the keystore and the node key.
+#[derive(Debug, StructOpt, Clone)]
+pub enum KeySubcommand {
+	/// Insert a key into the keystore of the chain.
+	Insert(InsertCmd),
+
+	/// Encrypt all the key files of the keystore with a new password.
+	RotatePassword(RotatePasswordCmd),
+
+	/// Generate a secret key for libp2p networking and print out the peer ID of the node.
+	GenerateNodeKey(GenerateNodeKeyCmd),
+
+	/// Print out the peer ID of the node using the secret key of a file.
+	InspectNodeKey(InspectNodeKeyCmd),
+}
+
+impl KeySubcommand {
+	/// Get the shared parameters of the command
+	pub fn get_shared_params(&self) -> &SharedParams {
+		match self {
+			KeySubcommand::Insert(cmd) => &cmd.shared_params,
+			KeySubcommand::RotatePassword(cmd) => &cmd.shared_params,
+			KeySubcommand::GenerateNodeKey(cmd) => &cmd.shared_params,
+			KeySubcommand::InspectNodeKey(cmd) => &cmd.shared_params,
+		}
+	}
+
+	/// Run the command
+	pub fn run(self, config: Configuration) -> error::Result<()> {
+		match self {
+			KeySubcommand::Insert(cmd) => cmd.run(config),
+			KeySubcommand::RotatePassword(cmd) => cmd.run(config),
+			KeySubcommand::GenerateNodeKey(cmd) => cmd.run(),
+			KeySubcommand::InspectNodeKey(cmd) => cmd.run(),
+		}
+	}
+
+	/// Update and prepare a `Configuration` with command line parameters
+	pub fn update_config<F>(
+		&self,
+		config: &mut Configuration,
+		spec_factory: F,
+		version: &VersionInfo,
+	) -> error::Result<()> where
+		F: FnOnce(&str) -> Result<Box<dyn ChainSpec>, String>,
+	{
+		match self {
+			KeySubcommand::Insert(cmd) => cmd.update_config(config, spec_factory, version),
+			KeySubcommand::RotatePassword(cmd) => cmd.update_config(config, spec_factory, version),
+			KeySubcommand::GenerateNodeKey(cmd) =>
+				cmd.shared_params.update_config(config, spec_factory, version),
+			KeySubcommand::InspectNodeKey(cmd) =>
+				cmd.shared_params.update_config(config, spec_factory, version),
+		}
+	}
+}
+
+/// The `key insert` command used to insert a key into the keystore of the chain, as the
+/// `author_insertKey` RPC does on a running node.
+#[derive(Debug, StructOpt, Clone)]
+pub struct InsertCmd {
+	/// The secret key URI of the key.
+	///
+	/// If the value is a file, the file content is used as URI. If not given, you will be
+	/// prompted for the URI.
+	#[structopt(long = "suri", value_name = "SURI")]
+	pub suri: Option<String>,
+
+	/// The type of the key, e.g. `babe`, `gran`, `imon` or `audi`.
+	#[structopt(long = "key-type", value_name = "KEY_TYPE")]
+	pub key_type: String,
+
+	/// The cryptography scheme of the key.
+	#[structopt(
+		long = "scheme",
+		value_name = "SCHEME",
+		possible_values = &CryptoScheme::variants(),
+		case_insensitive = true,
+		default_value = "Sr25519"
+	)]
+	pub scheme: CryptoScheme,
+
+	/// The hex-encoded public key the key must have.
+	///
+	/// The key isn't inserted if its public key is another one, e.g. because of a wrong URI,
+	/// scheme or password.
+	#[structopt(long = "public", value_name = "PUBLIC")]
+	pub public: Option<String>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub keystore_params: KeystoreParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl InsertCmd {
+	/// Run the insert command
+	pub fn run(self, config: Configuration) -> error::Result<()> {
+		let key_type = KeyTypeId::try_from(self.key_type.as_str())
+			.map_err(|_| "The key type must be 4 characters long, e.g. `babe`")?;
+		let suri = read_uri(self.suri.as_ref())?;
+
+		let keystore = match config.keystore {
+			KeystoreConfig::Path { path, password: Some(password), encrypt: true } =>
+				sc_keystore::Store::open_encrypted(path, password),
+			KeystoreConfig::Path { path, password, .. } => sc_keystore::Store::open(path, password),
+			_ => return Err("The keystore isn't stored on disk".into()),
+		}.map_err(|e| format!("Unable to open the keystore: {}", e))?;
+
+		// The keys are derived with the password of the keystore.
+		let public = {
+			let keystore = keystore.read();
+			let password = keystore.password();
+			match self.scheme {
+				CryptoScheme::Ed25519 => public_of::<ed25519_core::Pair>(&suri, password),
+				CryptoScheme::Sr25519 => public_of::<sr25519::Pair>(&suri, password),
+				CryptoScheme::Ecdsa => public_of::<ecdsa::Pair>(&suri, password),
+			}?
+		};
+
+		if let Some(expected) = self.public.as_ref() {
+			let expected = expected.trim_start_matches("0x");
+			if !expected.eq_ignore_ascii_case(&HexDisplay::from(&public).to_string()) {
+				return Err(format!(
+					"The public key of the URI is 0x{} instead of 0x{}",
+					HexDisplay::from(&public),
+					expected,
+				).into());
+			}
+		}
+
+		BareCryptoStore::insert_unknown(&mut *keystore.write(), key_type, &suri, &public)
+			.map_err(|_| "Unable to insert the key into the keystore")?;
+
+		println!("Inserted the {} key 0x{}.", self.key_type, HexDisplay::from(&public));
+		Ok(())
+	}
+
+	/// Update and prepare a `Configuration` with command line parameters
+	pub fn update_config<F>(
+		&self,
+		mut config: &mut Configuration,
+		spec_factory: F,
+		version: &VersionInfo,
+	) -> error::Result<()> where
+		F: FnOnce(&str) -> Result<Box<dyn ChainSpec>, String>,
+	{
+		self.shared_params.update_config(&mut config, spec_factory, version)?;
+		self.keystore_params.update_config(&mut config)?;
+
+		Ok(())
+	}
+}
+
+/// The `key rotate-password` command used to change the password of the keystore.
+///
+/// The files written with the current password are encrypted with the new one, whether they were
+/// encrypted or not. The current password is still the one the keys they hold are derived with.
+#[derive(Debug, StructOpt, Clone)]
+pub struct RotatePasswordCmd {
+	/// Use interactive shell for entering the new password of the keystore.
+	#[structopt(
+		long = "new-password-interactive",
+		conflicts_with_all = &[ "new-password", "new-password-filename" ]
+	)]
+	pub new_password_interactive: bool,
+
+	/// New password of the keystore.
+	#[structopt(
+		long = "new-password",
+		conflicts_with_all = &[ "new-password-interactive", "new-password-filename" ]
+	)]
+	pub new_password: Option<String>,
+
+	/// File that contains the new password of the keystore.
+	#[structopt(
+		long = "new-password-filename",
+		value_name = "PATH",
+		parse(from_os_str),
+		conflicts_with_all = &[ "new-password-interactive", "new-password" ]
+	)]
+	pub new_password_filename: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub keystore_params: KeystoreParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl RotatePasswordCmd {
+	/// Run the rotate-password command
+	pub fn run(self, config: Configuration) -> error::Result<()> {
+		let (path, password) = match config.keystore {
+			KeystoreConfig::Path { path, password, .. } => (path, password),
+			_ => return Err("The keystore isn't stored on disk".into()),
+		};
+
+		let new_password = read_password(
+			self.new_password_interactive,
+			self.new_password_filename.as_ref(),
+			self.new_password.as_ref(),
+			"New keystore password: ",
+		)?.ok_or_else(|| "A new password must be provided")?;
+
+		let keystore = sc_keystore::Store::open(path, password)
+			.map_err(|e| format!("Unable to open the keystore: {}", e))?;
+		let count = keystore.write().rotate_password(new_password)
+			.map_err(|e| format!("Unable to rotate the password of the keystore: {}", e))?;
+
+		println!("{} key files encrypted with the new password.", count);
+		Ok(())
+	}
+
+	/// Update and prepare a `Configuration` with command line parameters
+	pub fn update_config<F>(
+		&self,
+		mut config: &mut Configuration,
+		spec_factory: F,
+		version: &VersionInfo,
+	) -> error::Result<()> where
+		F: FnOnce(&str) -> Result<Box<dyn ChainSpec>, String>,
+	{
+		self.shared_params.update_config(&mut config, spec_factory, version)?;
+		self.keystore_params.update_config(&mut config)?;
+
+		Ok(())
+	}
+}
+
+/// The `key generate-node-key` command used to generate the secret key of a node.
+///
+/// The peer ID of the node is printed out to stderr, so that the secret key can be piped from
+/// stdout.
+#[derive(Debug, StructOpt, Clone)]
+pub struct GenerateNodeKeyCmd {
+	/// The file to store the secret key in, as read by `--node-key-file`.
+	///
+	/// If no file is given, the secret key is printed out as hex, as read by `--node-key`.
+	#[structopt(long = "file", value_name = "FILE", parse(from_os_str))]
+	pub file: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl GenerateNodeKeyCmd {
+	/// Run the generate-node-key command
+	pub fn run(self) -> error::Result<()> {
+		let peer_id = match self.file {
+			Some(file) => generate_node_key_file(&file)?,
+			None => {
+				let keypair = ed25519::Keypair::generate();
+				println!("{:x}", H256::from_slice(keypair.secret().as_ref()));
+				Keypair::Ed25519(keypair).public().into_peer_id()
+			},
+		};
+
+		eprintln!("{}", peer_id);
+		Ok(())
+	}
+}
+
+/// The `key inspect-node-key` command used to get the peer ID of a node from its secret key.
+#[derive(Debug, StructOpt, Clone)]
+pub struct InspectNodeKeyCmd {
+	/// The file the secret key is read from, as by `--node-key-file`.
+	#[structopt(long = "file", value_name = "FILE", parse(from_os_str))]
+	pub file: PathBuf,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl InspectNodeKeyCmd {
+	/// Run the inspect-node-key command
+	pub fn run(self) -> error::Result<()> {
+		println!("{}", read_node_key_file(&self.file)?);
+		Ok(())
+	}
+}
+
+/// Read a secret key URI, from the file it names if any, prompting for it if not given.
+fn read_uri(uri: Option<&String>) -> error::Result<String> {
+	let uri = match uri {
+		Some(uri) if Path::new(uri).is_file() => fs::read_to_string(uri)?.trim_end().to_string(),
+		Some(uri) => uri.clone(),
+		None => read_password(true, None, None, "Secret key URI: ")?
+			.map(|uri| (*uri).clone())
+			.ok_or("The secret key URI must be given")?,
+	};
+
+	Ok(uri)
+}
+
+/// Get the public key of a secret key URI.
+fn public_of<P: Pair>(suri: &str, password: Option<&str>) -> error::Result<Vec<u8>> {
+	P::from_string(suri, password)
+		.map(|pair| pair.public().to_raw_vec())
+		.map_err(|_| "Invalid secret key URI".into())
+}
+
+/// Generate a secret key and store it in a new file, returning the peer ID of the node.
+fn generate_node_key_file(file: &Path) -> error::Result<PeerId> {
+	if file.exists() {
+		return Err(format!("The file {} already exists", file.display()).into());
+	}
+
+	let keypair = NodeKeyConfig::Ed25519(Secret::File(file.to_path_buf())).into_keypair()?;
+	Ok(keypair.public().into_peer_id())
+}
+
+/// Read the secret key of a file, returning the peer ID of the node.
+fn read_node_key_file(file: &Path) -> error::Result<PeerId> {
+	let mut secret = fs::read(file)?;
+	let secret = ed25519::SecretKey::from_bytes(&mut secret)
+		.map_err(|e| format!("Invalid node key: {}", e))?;
+
+	Ok(Keypair::Ed25519(secret.into()).public().into_peer_id())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn public_key_of_the_uri_is_checked() {
+		let dir = tempfile::tempdir().unwrap();
+		let alice = sr25519::Pair::from_string("//Alice", None).unwrap().public();
+		let cmd = |public: String| InsertCmd {
+			suri: Some("//Alice".into()),
+			key_type: "babe".into(),
+			scheme: CryptoScheme::Sr25519,
+			public: Some(public),
+			keystore_params: KeystoreParams::from_iter(&["key"]),
+			shared_params: SharedParams::from_iter(&["key"]),
+		};
+		let config = || {
+			let mut config = Configuration::default();
+			config.keystore = KeystoreConfig::Path {
+				path: dir.path().into(),
+				password: None,
+				encrypt: false,
+			};
+			config
+		};
+
+		let bob = sr25519::Pair::from_string("//Bob", None).unwrap().public();
+		assert!(cmd(format!("0x{}", HexDisplay::from(&bob.0))).run(config()).is_err());
+		cmd(format!("0x{}", HexDisplay::from(&alice.0))).run(config()).unwrap();
+
+		let keystore = sc_keystore::Store::open(dir.path(), None).unwrap();
+		let keys = keystore.read()
+			.public_keys_by_type::<sr25519::Public>(KeyTypeId(*b"babe"))
+			.unwrap();
+		assert_eq!(keys, vec![alice]);
+	}
+
+	#[test]
+	fn generated_node_key_file_is_read_back() {
+		let dir = tempfile::tempdir().unwrap();
+		let file = dir.path().join("secret_ed25519");
+
+		let peer_id = generate_node_key_file(&file).unwrap();
+		assert_eq!(read_node_key_file(&file).unwrap(), peer_id);
+		assert_eq!(
+			NodeKeyConfig::Ed25519(Secret::File(file.clone())).into_keypair().unwrap()
+				.public().into_peer_id(),
+			peer_id,
+		);
+
+		assert!(generate_node_key_file(&file).is_err());
+		assert!(read_node_key_file(&dir.path().join("missing")).is_err());
+	}
+}
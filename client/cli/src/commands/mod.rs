@@ -16,11 +16,13 @@
 
 mod runcmd;
 mod export_blocks_cmd;
+mod export_state_cmd;
 mod build_spec_cmd;
 mod import_blocks_cmd;
 mod check_block_cmd;
 mod revert_cmd;
 mod purge_chain_cmd;
+mod key_cmd;
 
 use std::fmt::Debug;
 use structopt::StructOpt;
@@ -34,11 +36,15 @@ use crate::params::SharedParams;
 
 pub use crate::commands::runcmd::RunCmd;
 pub use crate::commands::export_blocks_cmd::ExportBlocksCmd;
+pub use crate::commands::export_state_cmd::ExportStateCmd;
 pub use crate::commands::build_spec_cmd::BuildSpecCmd;
 pub use crate::commands::import_blocks_cmd::ImportBlocksCmd;
 pub use crate::commands::check_block_cmd::CheckBlockCmd;
 pub use crate::commands::revert_cmd::RevertCmd;
 pub use crate::commands::purge_chain_cmd::PurgeChainCmd;
+pub use crate::commands::key_cmd::{
+	KeySubcommand, RotatePasswordCmd, GenerateNodeKeyCmd, InspectNodeKeyCmd,
+};
 
 /// default sub directory to store network config
 const DEFAULT_NETWORK_CONFIG_PATH : &'static str = "network";
@@ -56,6 +62,9 @@ pub enum Subcommand {
 	/// Export blocks to a file.
 	ExportBlocks(export_blocks_cmd::ExportBlocksCmd),
 
+	/// Export the state of a given block into a chain spec.
+	ExportState(export_state_cmd::ExportStateCmd),
+
 	/// Import blocks from file.
 	ImportBlocks(import_blocks_cmd::ImportBlocksCmd),
 
@@ -67,6 +76,9 @@ pub enum Subcommand {
 
 	/// Remove the whole chain data.
 	PurgeChain(purge_chain_cmd::PurgeChainCmd),
+
+	/// Manage the keystore and the node key.
+	Key(key_cmd::KeySubcommand),
 }
 
 impl Subcommand {
@@ -77,10 +89,12 @@ impl Subcommand {
 		match self {
 			BuildSpec(params) => &params.shared_params,
 			ExportBlocks(params) => &params.shared_params,
+			ExportState(params) => &params.shared_params,
 			ImportBlocks(params) => &params.shared_params,
 			CheckBlock(params) => &params.shared_params,
 			Revert(params) => &params.shared_params,
 			PurgeChain(params) => &params.shared_params,
+			Key(cmd) => cmd.get_shared_params(),
 		}
 	}
 
@@ -98,12 +112,14 @@ impl Subcommand {
 		<BB as BlockT>::Hash: std::str::FromStr,
 	{
 		match self {
-			Subcommand::BuildSpec(cmd) => cmd.run(config),
+			Subcommand::BuildSpec(cmd) => cmd.run::<BB>(config),
 			Subcommand::ExportBlocks(cmd) => cmd.run(config, builder),
+			Subcommand::ExportState(cmd) => cmd.run(config, builder),
 			Subcommand::ImportBlocks(cmd) => cmd.run(config, builder),
 			Subcommand::CheckBlock(cmd) => cmd.run(config, builder),
-			Subcommand::PurgeChain(cmd) => cmd.run(config),
+			Subcommand::PurgeChain(cmd) => cmd.run(config, builder),
 			Subcommand::Revert(cmd) => cmd.run(config, builder),
+			Subcommand::Key(cmd) => cmd.run(config),
 		}
 	}
 
@@ -119,10 +135,12 @@ impl Subcommand {
 		match self {
 			Subcommand::BuildSpec(cmd) => cmd.update_config(&mut config, spec_factory, version),
 			Subcommand::ExportBlocks(cmd) => cmd.update_config(&mut config, spec_factory, version),
+			Subcommand::ExportState(cmd) => cmd.update_config(&mut config, spec_factory, version),
 			Subcommand::ImportBlocks(cmd) => cmd.update_config(&mut config, spec_factory, version),
 			Subcommand::CheckBlock(cmd) => cmd.update_config(&mut config, spec_factory, version),
 			Subcommand::PurgeChain(cmd) => cmd.update_config(&mut config, spec_factory, version),
 			Subcommand::Revert(cmd) => cmd.update_config(&mut config, spec_factory, version),
+			Subcommand::Key(cmd) => cmd.update_config(&mut config, spec_factory, version),
 		}
 	}
 
@@ -17,11 +17,15 @@
 use std::fmt::Debug;
 use std::io::{Write, self};
 use std::fs;
+use std::path::PathBuf;
+use log::info;
 use structopt::StructOpt;
-use sc_service::{ Configuration, ChainSpec, config::{DatabaseConfig} };
+use sc_service::{ Configuration, ServiceBuilderCommand, ChainSpec, config::{DatabaseConfig} };
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT, One};
 
 use crate::error;
 use crate::VersionInfo;
+use crate::runtime::run_until_exit;
 use crate::params::SharedParams;
 
 /// The `purge-chain` command used to remove the whole chain.
@@ -31,6 +35,19 @@ pub struct PurgeChainCmd {
 	#[structopt(short = "y")]
 	pub yes: bool,
 
+	/// Purge the database at the given path instead of the database of the chain.
+	///
+	/// The backend of the database is still selected with `--database`.
+	#[structopt(long = "database-path", value_name = "PATH", parse(from_os_str))]
+	pub database_path: Option<PathBuf>,
+
+	/// Export the blocks of the chain to the given file before purging the database.
+	///
+	/// The blocks are exported in binary form and can be imported back with
+	/// `import-blocks --binary true`.
+	#[structopt(long = "export-blocks", value_name = "FILE", parse(from_os_str))]
+	pub export_blocks: Option<PathBuf>,
+
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub shared_params: SharedParams,
@@ -38,13 +55,22 @@ pub struct PurgeChainCmd {
 
 impl PurgeChainCmd {
 	/// Run the purge command
-	pub fn run(
+	pub fn run<B, BC, BB>(
 		self,
 		config: Configuration,
-	) -> error::Result<()> {
+		builder: B,
+	) -> error::Result<()>
+	where
+		B: FnOnce(Configuration) -> Result<BC, sc_service::error::Error>,
+		BC: ServiceBuilderCommand<Block = BB> + Unpin,
+		BB: sp_runtime::traits::Block + Debug,
+		<<<BB as BlockT>::Header as HeaderT>::Number as std::str::FromStr>::Err: std::fmt::Debug,
+		<BB as BlockT>::Hash: std::str::FromStr,
+	{
 		let db_path = match config.expect_database() {
-			DatabaseConfig::Path { path, .. } => path,
-			_ => {
+			DatabaseConfig::Path { path, .. } => path.clone(),
+			DatabaseConfig::ParityDb { path } => path.clone(),
+			DatabaseConfig::Custom(_) => {
 				eprintln!("Cannot purge custom database implementation");
 				return Ok(());
 			}
@@ -67,6 +93,14 @@ impl PurgeChainCmd {
 			}
 		}
 
+		if let Some(blocks_path) = &self.export_blocks {
+			info!("Exporting blocks to {}", blocks_path.display());
+			let file = fs::File::create(blocks_path)?;
+			run_until_exit(config, |config| {
+				Ok(builder(config)?.export_blocks(file, One::one(), None, true))
+			})?;
+		}
+
 		match fs::remove_dir_all(&db_path) {
 			Ok(_) => {
 				println!("{:?} removed.", &db_path);
@@ -92,6 +126,14 @@ impl PurgeChainCmd {
 		self.shared_params.update_config(&mut config, spec_factory, version)?;
 		config.use_in_memory_keystore()?;
 
+		if let Some(path) = &self.database_path {
+			match &mut config.database {
+				Some(DatabaseConfig::Path { path: db_path, .. }) |
+				Some(DatabaseConfig::ParityDb { path: db_path }) => *db_path = path.clone(),
+				_ => {},
+			}
+		}
+
 		Ok(())
 	}
 }
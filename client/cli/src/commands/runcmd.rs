@@ -14,9 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::path::PathBuf;
 use std::net::SocketAddr;
-use std::fs;
 use log::info;
 use structopt::{StructOpt, clap::arg_enum};
 use names::{Generator, Name};
@@ -24,24 +22,24 @@ use regex::Regex;
 use chrono::prelude::*;
 use sc_service::{
 	AbstractService, Configuration, ChainSpec, Roles,
-	config::{KeystoreConfig, PrometheusConfig},
+	config::{DatabaseConfig, HealthConfig, InformantOutputFormat, PrometheusConfig},
 };
 use sc_telemetry::TelemetryEndpoints;
 
 use crate::VersionInfo;
+use crate::RpcMethods;
+use crate::LogFormat;
 use crate::error;
 use crate::params::ImportParams;
-use crate::params::SharedParams;
+use crate::params::{SharedParams, DEFAULT_DB_CONFIG_PATH};
 use crate::params::NetworkConfigurationParams;
 use crate::params::TransactionPoolParams;
+use crate::params::KeystoreParams;
 use crate::runtime::run_service_until_exit;
 
 /// The maximum number of characters for a node name.
 const NODE_NAME_MAX_LENGTH: usize = 32;
 
-/// default sub directory for the key store
-const DEFAULT_KEYSTORE_CONFIG_PATH : &'static str = "keystore";
-
 arg_enum! {
 	/// Whether off-chain workers are enabled.
 	#[allow(missing_docs)]
@@ -86,6 +84,19 @@ pub struct RunCmd {
 	#[structopt(long = "no-grandpa")]
 	pub no_grandpa: bool,
 
+	/// Warn when the best block is more than the given number of blocks ahead of the last
+	/// finalized block, i.e. when GRANDPA finality has stalled.
+	///
+	/// A stalled authority set can be replaced through a forced change, scheduled with the
+	/// `note_stalled` call of the GRANDPA module.
+	#[structopt(long = "grandpa-stall-threshold", value_name = "BLOCKS")]
+	pub grandpa_stall_threshold: Option<u32>,
+
+	/// Slow down block authoring once the best block is more than the given number of blocks
+	/// ahead of the last finalized block, skipping more slots the further finality lags behind.
+	#[structopt(long = "max-unfinalized-blocks", value_name = "BLOCKS")]
+	pub max_unfinalized_blocks: Option<u32>,
+
 	/// Experimental: Run in light client mode.
 	#[structopt(long = "light", conflicts_with = "sentry")]
 	pub light: bool,
@@ -104,6 +115,22 @@ pub struct RunCmd {
 	#[structopt(long = "unsafe-rpc-external")]
 	pub unsafe_rpc_external: bool,
 
+	/// RPC methods to expose.
+	///
+	/// - `Unsafe`: Exposes every RPC method.
+	/// - `Safe`: Exposes only a safe subset of RPC methods, denying unsafe RPC methods.
+	/// - `Auto`: Acts as `Safe` if RPC is served externally, e.g. when `--{rpc,ws}-external` is
+	///   passed, otherwise acts as `Unsafe`.
+	#[structopt(
+		long = "rpc-methods",
+		value_name = "METHOD SET",
+		possible_values = &RpcMethods::variants(),
+		case_insensitive = true,
+		default_value = "Auto",
+		verbatim_doc_comment
+	)]
+	pub rpc_methods: RpcMethods,
+
 	/// Listen to all Websocket interfaces.
 	///
 	/// Default is local. Note: not all RPC methods are safe to be exposed publicly. Use a RPC proxy
@@ -145,6 +172,25 @@ pub struct RunCmd {
 	#[structopt(long = "rpc-cors", value_name = "ORIGINS", parse(try_from_str = parse_cors))]
 	pub rpc_cors: Option<Cors>,
 
+	/// Maximum size of an RPC request or WebSocket message, in megabytes.
+	#[structopt(long = "rpc-max-payload", value_name = "MEGABYTES")]
+	pub rpc_max_payload: Option<usize>,
+
+	/// Maximum number of RPC calls per second a single connection can make.
+	///
+	/// Calls above the limit are rejected. HTTP requests share a single limit, unless
+	/// `--rpc-rate-limit-trust-proxy-headers` is set. No limit by default.
+	#[structopt(long = "rpc-rate-limit", value_name = "CALLS")]
+	pub rpc_rate_limit: Option<u32>,
+
+	/// Rate limit HTTP requests per client address, as given by the last `X-Forwarded-For` entry
+	/// or the `X-Real-IP` header.
+	///
+	/// Only set this when the node is behind a reverse proxy setting these headers, clients could
+	/// otherwise pick their own address.
+	#[structopt(long = "rpc-rate-limit-trust-proxy-headers")]
+	pub rpc_rate_limit_trust_proxy_headers: bool,
+
 	/// Specify Prometheus data source server TCP Port.
 	#[structopt(long = "prometheus-port", value_name = "PORT")]
 	pub prometheus_port: Option<u16>,
@@ -155,6 +201,29 @@ pub struct RunCmd {
 	#[structopt(long = "no-prometheus")]
 	pub no_prometheus: bool,
 
+	/// Expose the health check endpoint on the given TCP port.
+	///
+	/// `GET /health` reports the sync status, peer count and finality lag of the node. `GET /ready`
+	/// answers 503 while the node is syncing, has too few peers or lags behind finality, so load
+	/// balancers can take it out of rotation. Disabled by default.
+	#[structopt(long = "health-port", value_name = "PORT")]
+	pub health_port: Option<u16>,
+
+	/// Listen to all health check endpoint interfaces.
+	///
+	/// Default is local.
+	#[structopt(long = "health-external")]
+	pub health_external: bool,
+
+	/// Minimum number of peers for the node to be ready.
+	#[structopt(long = "health-min-peers", value_name = "COUNT", default_value = "1")]
+	pub health_min_peers: usize,
+
+	/// Maximum number of blocks the finalized block may lag behind the best one for the node to
+	/// be ready.
+	#[structopt(long = "health-max-finality-lag", value_name = "BLOCKS", default_value = "32")]
+	pub health_max_finality_lag: u64,
+
 	/// The human-readable name for this node.
 	///
 	/// The node name will be reported to the telemetry server, if enabled.
@@ -240,38 +309,28 @@ pub struct RunCmd {
 	#[structopt(long = "force-authoring")]
 	pub force_authoring: bool,
 
-	/// Specify custom keystore path.
-	#[structopt(long = "keystore-path", value_name = "PATH", parse(from_os_str))]
-	pub keystore_path: Option<PathBuf>,
-
-	/// Use interactive shell for entering the password used by the keystore.
-	#[structopt(
-		long = "password-interactive",
-		conflicts_with_all = &[ "password", "password-filename" ]
-	)]
-	pub password_interactive: bool,
-
-	/// Password used by the keystore.
-	#[structopt(
-		long = "password",
-		conflicts_with_all = &[ "password-interactive", "password-filename" ]
-	)]
-	pub password: Option<String>,
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub keystore_params: KeystoreParams,
 
-	/// File that contains the password used by the keystore.
-	#[structopt(
-		long = "password-filename",
-		value_name = "PATH",
-		parse(from_os_str),
-		conflicts_with_all = &[ "password-interactive", "password" ]
-	)]
-	pub password_filename: Option<PathBuf>,
+	/// URI of a remote signer BABE and GRANDPA sign with, e.g. one in front of an HSM.
+	///
+	/// The signer is reached over HTTP and speaks JSON-RPC. The consensus keys then never need to
+	/// be stored on the node.
+	#[structopt(long = "remote-signer", value_name = "URI")]
+	pub remote_signer: Option<String>,
 
 	/// The size of the instances cache for each runtime.
 	///
 	/// The default value is 8 and the values higher than 256 are ignored.
 	#[structopt(long = "max-runtime-instances", default_value = "8")]
 	pub max_runtime_instances: usize,
+
+	/// Disable the colors of the status lines printed by the node.
+	///
+	/// With `--log-format json`, the status lines are made of `key=value` pairs instead.
+	#[structopt(long = "no-color")]
+	pub no_color: bool,
 }
 
 impl RunCmd {
@@ -302,29 +361,17 @@ impl RunCmd {
 	{
 		self.shared_params.update_config(&mut config, spec_factory, version)?;
 
-		let password = if self.password_interactive {
-			#[cfg(not(target_os = "unknown"))]
-			{
-				Some(input_keystore_password()?.into())
+		// switching to ParityDB starts with a copy of the RocksDB database, if there is one.
+		if let Some(DatabaseConfig::ParityDb { path }) = &config.database {
+			let rocksdb_path = config.in_chain_config_dir(DEFAULT_DB_CONFIG_PATH)
+				.expect("We provided a base_path/config_dir.");
+			if !path.exists() && rocksdb_path.exists() {
+				sc_service::chain_ops::migrate_to_parity_db(&rocksdb_path, path)?;
 			}
-			#[cfg(target_os = "unknown")]
-			None
-		} else if let Some(ref file) = self.password_filename {
-			Some(fs::read_to_string(file).map_err(|e| format!("{}", e))?.into())
-		} else if let Some(ref password) = self.password {
-			Some(password.clone().into())
-		} else {
-			None
-		};
-
-		let path = self.keystore_path.clone().or(
-			config.in_chain_config_dir(DEFAULT_KEYSTORE_CONFIG_PATH)
-		);
+		}
 
-		config.keystore = KeystoreConfig::Path {
-			path: path.ok_or_else(|| "No `base_path` provided to create keystore path!".to_string())?,
-			password,
-		};
+		self.keystore_params.update_config(&mut config)?;
+		config.remote_signer = self.remote_signer.clone();
 
 		let keyring = self.get_keyring();
 		let is_dev = self.shared_params.dev;
@@ -368,6 +415,8 @@ impl RunCmd {
 
 		config.roles = role;
 		config.disable_grandpa = self.no_grandpa;
+		config.grandpa_stall_threshold = self.grandpa_stall_threshold;
+		config.max_unfinalized_blocks = self.max_unfinalized_blocks;
 
 		let client_id = config.client_id();
 		let network_path = config
@@ -401,6 +450,10 @@ impl RunCmd {
 		}
 
 		config.rpc_ws_max_connections = self.ws_max_connections;
+		config.rpc_max_payload = self.rpc_max_payload;
+		config.rpc_rate_limit = self.rpc_rate_limit;
+		config.rpc_rate_limit_trust_proxy_headers = self.rpc_rate_limit_trust_proxy_headers;
+		config.rpc_methods = self.rpc_methods.into();
 		config.rpc_cors = self.rpc_cors.clone().unwrap_or_else(|| if is_dev {
 			log::warn!("Running in --dev mode, RPC CORS has been disabled.");
 			Cors::All
@@ -433,6 +486,15 @@ impl RunCmd {
 			));
 		}
 
+		if let Some(port) = self.health_port {
+			let interface = if self.health_external { "0.0.0.0" } else { "127.0.0.1" };
+			config.health_config = Some(HealthConfig {
+				min_peers: self.health_min_peers,
+				max_finality_lag: self.health_max_finality_lag,
+				..HealthConfig::new(parse_address(&format!("{}:{}", interface, port), None)?)
+			});
+		}
+
 		config.tracing_targets = self.import_params.tracing_targets.clone().into();
 		config.tracing_receiver = self.import_params.tracing_receiver.clone().into();
 
@@ -441,6 +503,14 @@ impl RunCmd {
 
 		config.max_runtime_instances = self.max_runtime_instances.min(256);
 
+		config.informant_output_format = if self.shared_params.log_format == LogFormat::Json {
+			InformantOutputFormat::Machine
+		} else if self.no_color {
+			InformantOutputFormat::Plain
+		} else {
+			InformantOutputFormat::Coloured
+		};
+
 		Ok(())
 	}
 
@@ -511,12 +581,6 @@ pub fn is_node_name_valid(_name: &str) -> Result<(), &str> {
 	Ok(())
 }
 
-#[cfg(not(target_os = "unknown"))]
-fn input_keystore_password() -> Result<String, String> {
-	rpassword::read_password_from_tty(Some("Keystore password: "))
-		.map_err(|e| format!("{:?}", e))
-}
-
 fn generate_node_name() -> String {
 	let result = loop {
 		let node_name = Generator::with_naming(Name::Numbered).next().unwrap();
@@ -621,7 +685,8 @@ fn parse_cors(s: &str) -> Result<Cors, Box<dyn std::error::Error>> {
 #[cfg(test)]
 mod tests {
 	use super::*;
-	use sc_service::{GenericChainSpec, config::DatabaseConfig};
+	use std::path::PathBuf;
+	use sc_service::GenericChainSpec;
 
 	const TEST_VERSION_INFO: &'static VersionInfo = &VersionInfo {
 		name: "node-test",
@@ -665,7 +730,7 @@ mod tests {
 		for keystore_path in vec![None, Some("/keystore/path")] {
 			let args: Vec<&str> = vec![];
 			let mut cli = RunCmd::from_iter(args);
-			cli.keystore_path = keystore_path.clone().map(PathBuf::from);
+			cli.keystore_params.keystore_path = keystore_path.clone().map(PathBuf::from);
 
 			let mut config = Configuration::default();
 			config.config_dir = Some(PathBuf::from("/test/path"));
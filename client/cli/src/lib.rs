@@ -21,9 +21,11 @@
 
 mod params;
 mod arg_enums;
+mod chain_spec_loader;
 mod error;
 mod runtime;
 mod commands;
+mod logging;
 
 pub use sc_service::config::VersionInfo;
 
@@ -35,6 +37,7 @@ pub use structopt;
 pub use params::*;
 pub use commands::*;
 pub use arg_enums::*;
+pub use chain_spec_loader::{ChainSpecLoader, load_spec};
 pub use error::*;
 use log::info;
 use lazy_static::lazy_static;
@@ -138,8 +141,12 @@ where
 ///
 /// 1. Set the panic handler
 /// 2. Raise the FD limit
-/// 3. Initialize the logger
-pub fn init(logger_pattern: &str, version: &VersionInfo) -> error::Result<()> {
+/// 3. Initialize the logger, with lines of the given format
+pub fn init(
+	logger_pattern: &str,
+	log_format: LogFormat,
+	version: &VersionInfo,
+) -> error::Result<()> {
 	let full_version = sc_service::config::full_version_from_strs(
 		version.version,
 		version.commit
@@ -147,13 +154,18 @@ pub fn init(logger_pattern: &str, version: &VersionInfo) -> error::Result<()> {
 	sp_panic_handler::set(version.support_url, &full_version);
 
 	fdlimit::raise_fd_limit();
-	init_logger(logger_pattern);
+	init_logger_with_format(logger_pattern, log_format);
 
 	Ok(())
 }
 
 /// Initialize the logger
 pub fn init_logger(pattern: &str) {
+	init_logger_with_format(pattern, LogFormat::Text)
+}
+
+/// Initialize the logger, with lines of the given format
+pub fn init_logger_with_format(pattern: &str, format: LogFormat) {
 	use ansi_term::Colour;
 
 	let mut builder = env_logger::Builder::new();
@@ -174,41 +186,45 @@ pub fn init_logger(pattern: &str) {
 	let isatty = atty::is(atty::Stream::Stderr);
 	let enable_color = isatty;
 
-	builder.format(move |buf, record| {
-		let now = time::now();
-		let timestamp =
-			time::strftime("%Y-%m-%d %H:%M:%S", &now)
-				.expect("Error formatting log timestamp");
-
-		let mut output = if log::max_level() <= log::LevelFilter::Info {
-			format!("{} {}", Colour::Black.bold().paint(timestamp), record.args())
-		} else {
-			let name = ::std::thread::current()
-				.name()
-				.map_or_else(Default::default, |x| format!("{}", Colour::Blue.bold().paint(x)));
-			let millis = (now.tm_nsec as f32 / 1000000.0).round() as usize;
-			let timestamp = format!("{}.{:03}", timestamp, millis);
-			format!(
-				"{} {} {} {}  {}",
-				Colour::Black.bold().paint(timestamp),
-				name,
-				record.level(),
-				record.target(),
-				record.args()
-			)
-		};
-
-		if !isatty && record.level() <= log::Level::Info && atty::is(atty::Stream::Stdout) {
-			// duplicate INFO/WARN output to console
-			println!("{}", output);
-		}
-
-		if !enable_color {
-			output = kill_color(output.as_ref());
-		}
-
-		writeln!(buf, "{}", output)
-	});
+	if format == LogFormat::Json {
+		builder.format(|buf, record| writeln!(buf, "{}", logging::json_line(record)));
+	} else {
+		builder.format(move |buf, record| {
+			let now = time::now();
+			let timestamp =
+				time::strftime("%Y-%m-%d %H:%M:%S", &now)
+					.expect("Error formatting log timestamp");
+
+			let mut output = if log::max_level() <= log::LevelFilter::Info {
+				format!("{} {}", Colour::Black.bold().paint(timestamp), record.args())
+			} else {
+				let name = ::std::thread::current()
+					.name()
+					.map_or_else(Default::default, |x| format!("{}", Colour::Blue.bold().paint(x)));
+				let millis = (now.tm_nsec as f32 / 1000000.0).round() as usize;
+				let timestamp = format!("{}.{:03}", timestamp, millis);
+				format!(
+					"{} {} {} {}  {}",
+					Colour::Black.bold().paint(timestamp),
+					name,
+					record.level(),
+					record.target(),
+					record.args()
+				)
+			};
+
+			if !isatty && record.level() <= log::Level::Info && atty::is(atty::Stream::Stdout) {
+				// duplicate INFO/WARN output to console
+				println!("{}", output);
+			}
+
+			if !enable_color {
+				output = kill_color(output.as_ref());
+			}
+
+			writeln!(buf, "{}", output)
+		});
+	}
 
 	if builder.try_init().is_err() {
 		info!("Not registering Substrate logger, as there is already a global logger registered!");
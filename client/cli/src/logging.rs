@@ -0,0 +1,69 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Formatting of the log lines as JSON objects, to be parsed by log aggregators.
+
+use chrono::{SecondsFormat, Utc};
+use serde_json::{json, Map, Value};
+
+/// Formats the log record as a JSON object on a single line.
+///
+/// The object has the `timestamp`, `level`, `target` and `thread` of the record, the `spans`
+/// the thread is in, from the outermost one, and the `fields` of the record, its `message` being
+/// the only one for now.
+pub(crate) fn json_line(record: &log::Record) -> String {
+	let spans = sc_tracing::current_spans().into_iter().map(|span| json!({
+		"name": span.name,
+		"target": span.target,
+		"fields": span.fields.into_iter()
+			.map(|(key, value)| (key, Value::String(value)))
+			.collect::<Map<_, _>>(),
+	})).collect::<Vec<_>>();
+
+	json!({
+		"timestamp": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+		"level": record.level().to_string(),
+		"target": record.target(),
+		"thread": std::thread::current().name(),
+		"spans": spans,
+		"fields": {
+			"message": record.args().to_string(),
+		},
+	}).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn records_are_formatted_as_json_objects() {
+		let line = json_line(&log::Record::builder()
+			.args(format_args!("Imported #{}", 42))
+			.level(log::Level::Warn)
+			.target("sync")
+			.build()
+		);
+		assert!(!line.contains('\n'));
+
+		let value: Value = serde_json::from_str(&line).unwrap();
+		assert_eq!(value["level"], "WARN");
+		assert_eq!(value["target"], "sync");
+		assert_eq!(value["fields"]["message"], "Imported #42");
+		assert_eq!(value["spans"], json!([]));
+		assert!(value["timestamp"].as_str().unwrap().ends_with('Z'));
+	}
+}
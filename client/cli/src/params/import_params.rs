@@ -62,7 +62,21 @@ pub struct ImportParams {
 	#[structopt(long = "state-cache-size", value_name = "Bytes", default_value = "67108864")]
 	pub state_cache_size: usize,
 
+	/// Specify the trie node cache size.
+	#[structopt(long = "trie-cache-size", value_name = "Bytes", default_value = "67108864")]
+	pub trie_cache_size: usize,
+
+	/// Specify the number of blocks of the canonical hash tries (CHTs).
+	///
+	/// Light clients replace the finalized headers with the CHTs and full nodes serve header
+	/// proofs against them, so all the nodes of the network must use the same size. The light
+	/// client database refuses to open with another size than the one it was created with.
+	#[structopt(long = "cht-size", value_name = "BLOCKS")]
+	pub cht_size: Option<u32>,
+
 	/// Comma separated list of targets for tracing
+	///
+	/// The spans of the runtime executed in Wasm are traced as well, e.g. with `pallet_balances`.
 	#[structopt(long = "tracing-targets", value_name = "TARGETS")]
 	pub tracing_targets: Option<String>,
 
@@ -92,9 +106,16 @@ impl ImportParams {
 		}
 
 		config.state_cache_size = self.state_cache_size;
+		config.trie_cache_size = self.trie_cache_size;
 
 		self.pruning_params.update_config(&mut config, role, self.unsafe_pruning)?;
 
+		match self.cht_size {
+			Some(0) => return Err(error::Error::Input("The CHT size can't be zero".to_string())),
+			Some(cht_size) => config.cht_size = cht_size,
+			None => {},
+		}
+
 		config.wasm_method = self.wasm_method.into();
 
 		let exec = &self.execution_strategies;
@@ -0,0 +1,129 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::fs;
+use std::path::PathBuf;
+use structopt::StructOpt;
+use sc_service::{Configuration, config::KeystoreConfig};
+use sp_core::crypto::Protected;
+
+use crate::error;
+
+/// default sub directory for the key store
+const DEFAULT_KEYSTORE_CONFIG_PATH : &'static str = "keystore";
+
+/// Parameters of the keystore
+#[derive(Debug, StructOpt, Clone)]
+pub struct KeystoreParams {
+	/// Specify custom keystore path.
+	#[structopt(long = "keystore-path", value_name = "PATH", parse(from_os_str))]
+	pub keystore_path: Option<PathBuf>,
+
+	/// Use interactive shell for entering the password used by the keystore.
+	#[structopt(
+		long = "password-interactive",
+		conflicts_with_all = &[ "password", "password-filename" ]
+	)]
+	pub password_interactive: bool,
+
+	/// Password used by the keystore.
+	#[structopt(
+		long = "password",
+		conflicts_with_all = &[ "password-interactive", "password-filename" ]
+	)]
+	pub password: Option<String>,
+
+	/// File that contains the password used by the keystore.
+	#[structopt(
+		long = "password-filename",
+		value_name = "PATH",
+		parse(from_os_str),
+		conflicts_with_all = &[ "password-interactive", "password" ]
+	)]
+	pub password_filename: Option<PathBuf>,
+
+	/// Encrypt the key files written to the keystore with its password.
+	///
+	/// The keys of the files written before stay readable. They are all encrypted by the
+	/// `key rotate-password` command.
+	#[structopt(long = "encrypt-keystore")]
+	pub encrypt_keystore: bool,
+}
+
+impl KeystoreParams {
+	/// Put the keystore CLI params into `config` object.
+	///
+	/// The chain must already be set in `config`, the keystore being stored with it by default.
+	pub fn update_config(&self, config: &mut Configuration) -> error::Result<()> {
+		let password = self.read_password()?;
+		if self.encrypt_keystore && password.is_none() {
+			return Err("The keystore can't be encrypted without a password".into());
+		}
+
+		let path = self.keystore_path.clone()
+			.or(config.in_chain_config_dir(DEFAULT_KEYSTORE_CONFIG_PATH))
+			.ok_or_else(|| "No `base_path` provided to create keystore path!".to_string())?;
+
+		config.keystore = KeystoreConfig::Path {
+			path,
+			password,
+			encrypt: self.encrypt_keystore,
+		};
+
+		Ok(())
+	}
+
+	/// The password of the keystore, if any.
+	pub fn read_password(&self) -> error::Result<Option<Protected<String>>> {
+		read_password(
+			self.password_interactive,
+			self.password_filename.as_ref(),
+			self.password.as_ref(),
+			"Keystore password: ",
+		)
+	}
+}
+
+/// Read a password from the first of its sources that is given.
+pub fn read_password(
+	interactive: bool,
+	filename: Option<&PathBuf>,
+	password: Option<&String>,
+	prompt: &str,
+) -> error::Result<Option<Protected<String>>> {
+	let password = if interactive {
+		#[cfg(not(target_os = "unknown"))]
+		{
+			Some(input_password(prompt)?.into())
+		}
+		#[cfg(target_os = "unknown")]
+		None
+	} else if let Some(file) = filename {
+		Some(fs::read_to_string(file).map_err(|e| format!("{}", e))?.into())
+	} else if let Some(password) = password {
+		Some(password.clone().into())
+	} else {
+		None
+	};
+
+	Ok(password)
+}
+
+#[cfg(not(target_os = "unknown"))]
+fn input_password(prompt: &str) -> Result<String, String> {
+	rpassword::read_password_from_tty(Some(prompt))
+		.map_err(|e| format!("{:?}", e))
+}
@@ -20,9 +20,12 @@ mod shared_params;
 mod node_key_params;
 mod network_configuration_params;
 mod pruning_params;
+mod keystore_params;
 
 use std::str::FromStr;
 use std::fmt::Debug;
+use sp_runtime::generic::BlockId;
+use sp_runtime::traits::{Block as BlockT, NumberFor};
 
 pub use crate::params::import_params::*;
 pub use crate::params::transaction_pool_params::*;
@@ -30,6 +33,7 @@ pub use crate::params::shared_params::*;
 pub use crate::params::node_key_params::*;
 pub use crate::params::network_configuration_params::*;
 pub use crate::params::pruning_params::*;
+pub use crate::params::keystore_params::*;
 
 /// Wrapper type of `String` that holds an unsigned integer of arbitrary size, formatted as a decimal.
 #[derive(Debug, Clone)]
@@ -65,3 +69,42 @@ impl BlockNumber {
 			.map_err(|e| format!("BlockNumber: {} parsing failed because of {:?}", self.0, e))
 	}
 }
+
+/// Wrapper type of `String` that holds either a block hash, formatted as hexadecimal, or a block
+/// number, formatted as a decimal.
+#[derive(Debug, Clone)]
+pub struct BlockNumberOrHash(String);
+
+impl FromStr for BlockNumberOrHash {
+	type Err = String;
+
+	fn from_str(block_number_or_hash: &str) -> Result<Self, Self::Err> {
+		if block_number_or_hash.starts_with("0x") {
+			let hash = &block_number_or_hash[2..];
+			if hash.is_empty() || hash.chars().any(|d| !d.is_digit(16)) {
+				return Err(format!("Invalid block hash: {}", block_number_or_hash));
+			}
+			Ok(Self(block_number_or_hash.to_owned()))
+		} else {
+			BlockNumber::from_str(block_number_or_hash).map(|n| Self(n.0))
+		}
+	}
+}
+
+impl BlockNumberOrHash {
+	/// Parse the inner value as a `BlockId`.
+	pub fn parse<B: BlockT>(&self) -> Result<BlockId<B>, String>
+	where
+		B::Hash: FromStr,
+		<NumberFor<B> as FromStr>::Err: Debug,
+	{
+		if self.0.starts_with("0x") {
+			self.0[2..]
+				.parse()
+				.map(BlockId::Hash)
+				.map_err(|_| format!("BlockHash: {} parsing failed", self.0))
+		} else {
+			BlockNumber(self.0.clone()).parse().map(BlockId::Number)
+		}
+	}
+}
@@ -90,6 +90,16 @@ pub struct NetworkConfigurationParams {
 	#[structopt(long = "max-parallel-downloads", value_name = "COUNT", default_value = "5")]
 	pub max_parallel_downloads: u32,
 
+	/// Maximum number of light client requests served to each peer per second.
+	///
+	/// The peers sending more requests get the extra ones ignored.
+	#[structopt(long = "max-light-requests-per-sec", value_name = "COUNT", default_value = "32")]
+	pub max_light_requests_per_sec: u32,
+
+	/// Maximum size in bytes of the proofs sent in response to light client requests.
+	#[structopt(long = "max-light-proof-size", value_name = "BYTES", default_value = "8388608")]
+	pub max_light_proof_size: usize,
+
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub node_key_params: NodeKeyParams,
@@ -151,6 +161,8 @@ impl NetworkConfigurationParams {
 		};
 
 		config.network.max_parallel_downloads = self.max_parallel_downloads;
+		config.network.max_light_requests_per_sec = self.max_light_requests_per_sec;
+		config.network.max_light_proof_size = self.max_light_proof_size;
 
 		Ok(())
 	}
@@ -85,7 +85,7 @@ pub struct NodeKeyParams {
 	///   The file must contain an unencoded 32 bytes Ed25519 secret key.
 	///
 	/// If the file does not exist, it is created with a newly generated secret key of
-	/// the chosen type.
+	/// the chosen type. The file can also be provisioned with `key generate-node-key --file`.
 	#[structopt(long = "node-key-file", value_name = "FILE")]
 	pub node_key_file: Option<PathBuf>,
 }
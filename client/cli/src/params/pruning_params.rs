@@ -15,20 +15,29 @@
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
 use structopt::StructOpt;
-use sc_service::{Configuration, PruningMode};
+use sc_service::{Configuration, PruningMode, KeepBlocks};
 
 use crate::error;
 
 /// Parameters to define the pruning mode
 #[derive(Debug, StructOpt, Clone)]
 pub struct PruningParams {
-	/// Specify the state pruning mode, a number of blocks to keep or 'archive'.
+	/// Specify the state pruning mode, a number of blocks to keep, 'archive' or
+	/// 'archive-canonical'.
 	///
 	/// Default is to keep all block states if the node is running as a
 	/// validator (i.e. 'archive'), otherwise state is only kept for the last
-	/// 256 blocks.
+	/// 256 blocks. 'archive-canonical' keeps the state of all the finalized
+	/// blocks, but not of the blocks of the abandoned forks.
 	#[structopt(long = "pruning", value_name = "PRUNING_MODE")]
 	pub pruning: Option<String>,
+
+	/// Specify the number of finalized blocks whose bodies are kept.
+	///
+	/// Default is to keep the bodies of all the blocks. Headers and
+	/// justifications are kept regardless.
+	#[structopt(long = "keep-blocks", value_name = "COUNT")]
+	pub keep_blocks: Option<u32>,
 }
 
 impl PruningParams {
@@ -45,6 +54,7 @@ impl PruningParams {
 		// unless `unsafe_pruning` is set.
 		config.pruning = match &self.pruning {
 			Some(ref s) if s == "archive" => PruningMode::ArchiveAll,
+			Some(ref s) if s == "archive-canonical" => PruningMode::ArchiveCanonical,
 			None if role == sc_service::Roles::AUTHORITY => PruningMode::ArchiveAll,
 			None => PruningMode::default(),
 			Some(s) => {
@@ -61,6 +71,11 @@ impl PruningParams {
 			},
 		};
 
+		config.keep_blocks = match self.keep_blocks {
+			Some(n) => KeepBlocks::Some(n),
+			None => KeepBlocks::All,
+		};
+
 		Ok(())
 	}
 }
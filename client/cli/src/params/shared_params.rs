@@ -14,23 +14,31 @@
 // You should have received a copy of the GNU General Public License
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::path::PathBuf;
+use std::{convert::TryFrom, path::PathBuf};
 use structopt::StructOpt;
 use app_dirs::{AppInfo, AppDataType};
 use sc_service::{
 	Configuration, config::DatabaseConfig, ChainSpec,
 };
+use sp_core::crypto::{set_default_ss58_version, Ss58AddressFormat};
 
 use crate::VersionInfo;
 use crate::error;
+use crate::arg_enums::{Database, LogFormat};
 
 /// default sub directory to store database
-const DEFAULT_DB_CONFIG_PATH : &'static str = "db";
+pub(crate) const DEFAULT_DB_CONFIG_PATH : &'static str = "db";
+
+/// default sub directory to store the ParityDB database
+pub(crate) const DEFAULT_PARITY_DB_CONFIG_PATH : &'static str = "paritydb";
 
 /// Shared parameters used by all `CoreParams`.
 #[derive(Debug, StructOpt, Clone)]
 pub struct SharedParams {
 	/// Specify the chain specification (one of dev, local or staging).
+	///
+	/// It may also be the path to a JSON file or an URL, pinned by its Blake2-256 hash, e.g.
+	/// `https://example.com/spec.json#0x<hash>`.
 	#[structopt(long = "chain", value_name = "CHAIN_SPEC")]
 	pub chain: Option<String>,
 
@@ -45,6 +53,32 @@ pub struct SharedParams {
 	/// Sets a custom logging filter.
 	#[structopt(short = "l", long = "log", value_name = "LOG_PATTERN")]
 	pub log: Option<String>,
+
+	/// Format of the log lines.
+	///
+	/// With `json`, each log line is a JSON object with the timestamp, level, target, thread,
+	/// spans and fields of the entry.
+	#[structopt(
+		long = "log-format",
+		value_name = "FORMAT",
+		possible_values = &LogFormat::variants(),
+		case_insensitive = true,
+		default_value = "Text"
+	)]
+	pub log_format: LogFormat,
+
+	/// Select database backend to use.
+	///
+	/// When switching to ParityDB, an existing RocksDB database of the chain is copied into the
+	/// new ParityDB database the first time the node runs.
+	#[structopt(
+		long = "database",
+		value_name = "DB",
+		possible_values = &Database::variants(),
+		case_insensitive = true,
+		default_value = "RocksDb"
+	)]
+	pub database: Database,
 }
 
 impl SharedParams {
@@ -62,6 +96,10 @@ impl SharedParams {
 			None => if self.dev { "dev".into() } else { "".into() }
 		};
 		let spec = spec_factory(&chain_key)?;
+		if let Some(format) = ss58_format(&*spec)? {
+			set_default_ss58_version(format)
+				.map_err(|_| "Invalid `ss58Format` property of the chain spec")?;
+		}
 		config.network.boot_nodes = spec.boot_nodes().to_vec();
 		config.telemetry_endpoints = spec.telemetry_endpoints().clone();
 
@@ -72,11 +110,17 @@ impl SharedParams {
 		}
 
 		if config.database.is_none() {
-			config.database = Some(DatabaseConfig::Path {
-				path: config
-					.in_chain_config_dir(DEFAULT_DB_CONFIG_PATH)
-					.expect("We provided a base_path/config_dir."),
-				cache_size: None,
+			let rocksdb_path = config
+				.in_chain_config_dir(DEFAULT_DB_CONFIG_PATH)
+				.expect("We provided a base_path/config_dir.");
+
+			config.database = Some(match self.database {
+				Database::RocksDb => DatabaseConfig::Path { path: rocksdb_path, cache_size: None },
+				Database::ParityDb => DatabaseConfig::ParityDb {
+					path: config
+						.in_chain_config_dir(DEFAULT_PARITY_DB_CONFIG_PATH)
+						.expect("We provided a base_path/config_dir."),
+				},
 			});
 		}
 
@@ -91,7 +135,7 @@ impl SharedParams {
 	/// 2. Raise the FD limit
 	/// 3. Initialize the logger
 	pub fn init(&self, version: &VersionInfo) -> error::Result<()> {
-		crate::init(self.log.as_ref().map(|v| v.as_ref()).unwrap_or(""), version)
+		crate::init(self.log.as_ref().map(|v| v.as_ref()).unwrap_or(""), self.log_format, version)
 	}
 }
 
@@ -107,3 +151,17 @@ fn base_path(cli: &SharedParams, version: &VersionInfo) -> PathBuf {
 			).expect("app directories exist on all supported platforms; qed")
 		)
 }
+
+/// The SS58 address format given by the `ss58Format` property of the chain spec, if any.
+fn ss58_format(spec: &dyn ChainSpec) -> error::Result<Option<Ss58AddressFormat>> {
+	let format = match spec.properties().get("ss58Format") {
+		Some(format) => format.clone(),
+		None => return Ok(None),
+	};
+
+	let ident = format.as_u64()
+		.and_then(|ident| u16::try_from(ident).ok())
+		.filter(|ident| *ident < 16_384)
+		.ok_or_else(|| format!("Invalid `ss58Format` property of the chain spec: {}", format))?;
+	Ok(Some(Ss58AddressFormat::try_from(ident).unwrap_or(Ss58AddressFormat::Custom(ident))))
+}
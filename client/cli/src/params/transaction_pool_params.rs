@@ -14,6 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::time::Duration;
 use structopt::StructOpt;
 use sc_service::Configuration;
 use crate::error;
@@ -27,6 +28,23 @@ pub struct TransactionPoolParams {
 	/// Maximum number of kilobytes of all transactions stored in the pool.
 	#[structopt(long = "pool-kbytes", value_name = "COUNT", default_value = "20480")]
 	pub pool_kbytes: usize,
+	/// Maximum number of transactions of a single sender in the transaction pool.
+	///
+	/// The transactions of a sender are the ones depending on each other, like the transactions
+	/// of an account ordered by nonce.
+	#[structopt(long = "pool-sender-limit", value_name = "COUNT", default_value = "640")]
+	pub pool_sender_limit: usize,
+	/// How long, in seconds, the transactions found to be invalid are banned from entering the
+	/// pool again.
+	#[structopt(long = "tx-ban-seconds", value_name = "SECONDS")]
+	pub tx_ban_seconds: Option<u64>,
+	/// Number of transactions revalidated at once by the background revalidation of the pool.
+	#[structopt(long = "pool-revalidation-batch", value_name = "COUNT")]
+	pub pool_revalidation_batch: Option<usize>,
+	/// How long, in milliseconds, the background revalidation of the pool waits between two
+	/// batches.
+	#[structopt(long = "pool-revalidation-interval", value_name = "MILLISECONDS")]
+	pub pool_revalidation_interval: Option<u64>,
 }
 
 impl TransactionPoolParams {
@@ -38,11 +56,24 @@ impl TransactionPoolParams {
 		// ready queue
 		config.transaction_pool.ready.count = self.pool_limit;
 		config.transaction_pool.ready.total_bytes = self.pool_kbytes * 1024;
+		config.transaction_pool.sender.ready = self.pool_sender_limit;
 
 		// future queue
 		let factor = 10;
 		config.transaction_pool.future.count = self.pool_limit / factor;
 		config.transaction_pool.future.total_bytes = self.pool_kbytes * 1024 / factor;
+		config.transaction_pool.sender.future = self.pool_sender_limit / factor;
+
+		if let Some(tx_ban_seconds) = self.tx_ban_seconds {
+			config.transaction_pool.ban_time = Duration::from_secs(tx_ban_seconds);
+		}
+
+		if let Some(batch_size) = self.pool_revalidation_batch {
+			config.transaction_pool.revalidation_batch_size = batch_size;
+		}
+		if let Some(interval) = self.pool_revalidation_interval {
+			config.transaction_pool.revalidation_interval = Duration::from_millis(interval);
+		}
 
 		Ok(())
 	}
@@ -121,9 +121,13 @@ where
 		Some(Arc::new(move |fut| { runtime_handle.spawn(fut); }))
 	};
 
+	let informant_format = config.informant_output_format;
+	let database_path = config.database.as_ref()
+		.and_then(|database| database.path())
+		.map(|path| path.to_path_buf());
 	let service = service_builder(config)?;
 
-	let informant_future = sc_informant::build(&service, sc_informant::OutputFormat::Coloured);
+	let informant_future = sc_informant::build(&service, informant_format, database_path);
 	let _informant_handle = runtime.spawn(informant_future);
 
 	// we eagerly drop the service so that the internal exit future is fired,
@@ -38,13 +38,14 @@ use parking_lot::Mutex;
 use log::{debug, info, trace};
 
 use codec::{Encode, Decode, Codec};
+use prometheus_endpoint::Registry;
 
 use sp_consensus::{
 	self, BlockImport, Environment, Proposer, CanAuthorWith, ForkChoiceStrategy, BlockImportParams,
 	BlockOrigin, Error as ConsensusError, SelectChain, SlotData, BlockCheckParams, ImportResult
 };
 use sp_consensus::import_queue::{
-	Verifier, BasicQueue, BoxJustificationImport, BoxFinalityProofImport,
+	Verifier, PreVerifier, BasicQueue, BoxJustificationImport, BoxFinalityProofImport,
 };
 use sc_client_api::backend::AuxStore;
 use sc_client::BlockOf;
@@ -58,16 +59,19 @@ use sp_runtime::traits::{Block as BlockT, Header, DigestItemFor, Zero, Member};
 use sp_api::ProvideRuntimeApi;
 
 use sp_core::crypto::Pair;
-use sp_inherents::{InherentDataProviders, InherentData};
+use sp_inherents::{InherentDataProviders, InherentData, CreateInherentDataProviders};
 use sp_timestamp::{
 	TimestampInherentData, InherentType as TimestampInherent, InherentError as TIError
 };
 use sc_telemetry::{telemetry, CONSENSUS_TRACE, CONSENSUS_DEBUG, CONSENSUS_INFO};
 
 use sc_consensus_slots::{
-	CheckedHeader, SlotWorker, SlotInfo, SlotCompatible, StorageChanges, check_equivocation,
+	CheckedHeader, SlotWorker, SlotInfo, SlotCompatible, SlotLenienceType, StorageChanges,
+	check_equivocation,
 };
 
+pub use sc_consensus_slots::{BackoffAuthoringOnFinalizedHeadLagging, SlotProportion};
+
 use sc_keystore::KeyStorePtr;
 use sp_api::ApiExt;
 
@@ -132,20 +136,27 @@ impl SlotCompatible for AuraSlotCompatible {
 }
 
 /// Start the aura worker. The returned future should be run in a futures executor.
-pub fn start_aura<B, C, SC, E, I, P, SO, CAW, Error>(
+///
+/// The providers of the inherent data of each block are created by
+/// `create_inherent_data_providers` from its parent hash, besides the slot and timestamp that
+/// Aura provides itself.
+pub fn start_aura<B, C, SC, E, I, P, SO, CAW, CIDP, Error>(
 	slot_duration: SlotDuration,
 	client: Arc<C>,
 	select_chain: SC,
 	block_import: I,
 	env: E,
 	sync_oracle: SO,
-	inherent_data_providers: InherentDataProviders,
+	create_inherent_data_providers: CIDP,
 	force_authoring: bool,
+	backoff_authoring_blocks: Option<BackoffAuthoringOnFinalizedHeadLagging>,
+	block_proposal_slot_portion: SlotProportion,
 	keystore: KeyStorePtr,
 	can_author_with: CAW,
 ) -> Result<impl Future<Output = ()>, sp_consensus::Error> where
 	B: BlockT,
-	C: ProvideRuntimeApi<B> + BlockOf + ProvideCache<B> + AuxStore + Send + Sync,
+	C: ProvideRuntimeApi<B> + BlockOf + ProvideCache<B> + AuxStore + HeaderBackend<B>
+		+ Send + Sync,
 	C::Api: AuraApi<B, AuthorityId<P>>,
 	SC: SelectChain<B>,
 	E: Environment<B, Error = Error> + Send + Sync + 'static,
@@ -157,6 +168,7 @@ pub fn start_aura<B, C, SC, E, I, P, SO, CAW, Error>(
 	Error: std::error::Error + Send + From<sp_consensus::Error> + 'static,
 	SO: SyncOracle + Send + Sync + Clone,
 	CAW: CanAuthorWith<B> + Send,
+	CIDP: CreateInherentDataProviders<B::Hash, ()>,
 {
 	let worker = AuraWorker {
 		client: client.clone(),
@@ -165,18 +177,22 @@ pub fn start_aura<B, C, SC, E, I, P, SO, CAW, Error>(
 		keystore,
 		sync_oracle: sync_oracle.clone(),
 		force_authoring,
+		backoff_authoring_blocks,
+		block_proposal_slot_portion,
 		_key_type: PhantomData::<P>,
 	};
+	let inherent_data_providers = InherentDataProviders::new();
 	register_aura_inherent_data_provider(
 		&inherent_data_providers,
 		slot_duration.slot_duration()
 	)?;
-	Ok(sc_consensus_slots::start_slot_worker::<_, _, _, _, _, AuraSlotCompatible, _>(
+	Ok(sc_consensus_slots::start_slot_worker::<_, _, _, _, _, AuraSlotCompatible, _, _>(
 		slot_duration,
 		select_chain,
 		worker,
 		sync_oracle,
 		inherent_data_providers,
+		create_inherent_data_providers,
 		AuraSlotCompatible,
 		can_author_with,
 	))
@@ -189,12 +205,14 @@ struct AuraWorker<C, E, I, P, SO> {
 	keystore: KeyStorePtr,
 	sync_oracle: SO,
 	force_authoring: bool,
+	backoff_authoring_blocks: Option<BackoffAuthoringOnFinalizedHeadLagging>,
+	block_proposal_slot_portion: SlotProportion,
 	_key_type: PhantomData<P>,
 }
 
 impl<B, C, E, I, P, Error, SO> sc_consensus_slots::SimpleSlotWorker<B> for AuraWorker<C, E, I, P, SO> where
 	B: BlockT,
-	C: ProvideRuntimeApi<B> + BlockOf + ProvideCache<B> + Sync,
+	C: ProvideRuntimeApi<B> + BlockOf + ProvideCache<B> + HeaderBackend<B> + Sync,
 	C::Api: AuraApi<B, AuthorityId<P>>,
 	E: Environment<B, Error = Error>,
 	E::Proposer: Proposer<B, Error = Error, Transaction = sp_api::TransactionFor<C, B>>,
@@ -265,7 +283,10 @@ impl<B, C, E, I, P, Error, SO> sc_consensus_slots::SimpleSlotWorker<B> for AuraW
 		StorageChanges<sp_api::TransactionFor<C, B>, B>,
 		Self::Claim,
 		Self::EpochData,
-	) -> sp_consensus::BlockImportParams<B, sp_api::TransactionFor<C, B>> + Send> {
+	) -> Result<
+		sp_consensus::BlockImportParams<B, sp_api::TransactionFor<C, B>>,
+		sp_consensus::Error,
+	> + Send> {
 		Box::new(|header, header_hash, body, storage_changes, pair, _epoch| {
 			// sign the pre-sealed hash of the block and then
 			// add it to a digest item.
@@ -278,7 +299,7 @@ impl<B, C, E, I, P, Error, SO> sc_consensus_slots::SimpleSlotWorker<B> for AuraW
 			import_block.storage_changes = Some(storage_changes);
 			import_block.fork_choice = Some(ForkChoiceStrategy::LongestChain);
 
-			import_block
+			Ok(import_block)
 		})
 	}
 
@@ -296,36 +317,43 @@ impl<B, C, E, I, P, Error, SO> sc_consensus_slots::SimpleSlotWorker<B> for AuraW
 		}))
 	}
 
+	fn should_backoff(&self, slot_number: u64, chain_head: &B::Header) -> bool {
+		let strategy = match self.backoff_authoring_blocks {
+			Some(ref strategy) => strategy,
+			None => return false,
+		};
+
+		match find_pre_digest::<B, P>(chain_head) {
+			Ok(chain_head_slot) => strategy.should_backoff(
+				*chain_head.number(),
+				chain_head_slot,
+				self.client.info().finalized_number,
+				slot_number,
+				self.logging_target(),
+			),
+			// the genesis block has no pre-digest, and it is always finalized.
+			Err(_) => false,
+		}
+	}
+
 	fn proposing_remaining_duration(
 		&self,
 		head: &B::Header,
 		slot_info: &SlotInfo
 	) -> Option<std::time::Duration> {
-		// never give more than 20 times more lenience.
-		const BACKOFF_CAP: u64 = 20;
-
-		let slot_remaining = self.slot_remaining_duration(slot_info);
-		let parent_slot = match find_pre_digest::<B, P>(head) {
-			Err(_) => return Some(slot_remaining),
-			Ok(d) => d,
-		};
-
-		// we allow a lenience of the number of slots since the head of the
-		// chain was produced, minus 1 (since there is always a difference of at least 1)
-		//
-		// linear back-off.
-		// in normal cases we only attempt to issue blocks up to the end of the slot.
-		// when the chain has been stalled for a few slots, we give more lenience.
-		let slot_lenience = slot_info.number.saturating_sub(parent_slot + 1);
-		let slot_lenience = std::cmp::min(slot_lenience, BACKOFF_CAP);
-		let slot_lenience = Duration::from_secs(slot_lenience * slot_info.duration);
-		Some(slot_lenience + slot_remaining)
+		Some(sc_consensus_slots::proposing_remaining_duration(
+			find_pre_digest::<B, P>(head).ok(),
+			slot_info,
+			&self.block_proposal_slot_portion,
+			SlotLenienceType::Linear,
+			self.logging_target(),
+		))
 	}
 }
 
 impl<B: BlockT, C, E, I, P, Error, SO> SlotWorker<B> for AuraWorker<C, E, I, P, SO> where
 	B: BlockT,
-	C: ProvideRuntimeApi<B> + BlockOf + ProvideCache<B> + Sync + Send,
+	C: ProvideRuntimeApi<B> + BlockOf + ProvideCache<B> + HeaderBackend<B> + Sync + Send,
 	C::Api: AuraApi<B, AuthorityId<P>>,
 	E: Environment<B, Error = Error> + Send + Sync,
 	E::Proposer: Proposer<B, Error = Error, Transaction = sp_api::TransactionFor<C, B>>,
@@ -411,6 +439,7 @@ fn check_header<C, B: BlockT, P: Pair>(
 	mut header: B::Header,
 	hash: B::Hash,
 	authorities: &[AuthorityId<P>],
+	seal_verified: bool,
 ) -> Result<CheckedHeader<B::Header, (u64, DigestItemFor<B>)>, Error<B>> where
 	DigestItemFor<B>: CompatibleDigestItem<P>,
 	P::Signature: Decode,
@@ -441,7 +470,7 @@ fn check_header<C, B: BlockT, P: Pair>(
 
 		let pre_hash = header.hash();
 
-		if P::verify(&sig, pre_hash.as_ref(), expected_author) {
+		if seal_verified || P::verify(&sig, pre_hash.as_ref(), expected_author) {
 			if let Some(equivocation_proof) = check_equivocation(
 				client,
 				slot_now,
@@ -464,21 +493,91 @@ fn check_header<C, B: BlockT, P: Pair>(
 	}
 }
 
+/// Maximum number of seals kept by an `AuraPreVerifier` until their block is verified.
+const MAX_PRE_VERIFIED_SEALS: usize = 4096;
+
+/// The encoded hashes of the blocks whose seal was checked by an `AuraPreVerifier`, along with
+/// the authorities it was checked against.
+type PreVerifiedSeals<P> = Arc<Mutex<HashMap<Vec<u8>, Vec<AuthorityId<P>>>>>;
+
 /// A verifier for Aura blocks.
-pub struct AuraVerifier<C, P> {
+pub struct AuraVerifier<C, P: Pair, CIDP> {
 	client: Arc<C>,
 	phantom: PhantomData<P>,
-	inherent_data_providers: sp_inherents::InherentDataProviders,
+	inherent_data_providers: InherentDataProviders,
+	create_inherent_data_providers: CIDP,
+	pre_verified_seals: PreVerifiedSeals<P>,
+}
+
+impl<C, P: Pair, CIDP> AuraVerifier<C, P, CIDP> {
+	/// A pre-verifier checking the seals of the queued headers ahead of this verifier.
+	pub fn pre_verifier(&self) -> AuraPreVerifier<C, P> {
+		AuraPreVerifier {
+			client: self.client.clone(),
+			pre_verified_seals: self.pre_verified_seals.clone(),
+		}
+	}
+}
+
+/// A pre-verifier for Aura blocks, checking the seals of the queued headers concurrently.
+///
+/// The authorities of a queued header are only known once its parent is imported, so its seal is
+/// checked against the authorities of the best block. The `AuraVerifier` only skips checking it
+/// again if the authorities turn out to be the same.
+pub struct AuraPreVerifier<C, P: Pair> {
+	client: Arc<C>,
+	pre_verified_seals: PreVerifiedSeals<P>,
 }
 
-impl<C, P> AuraVerifier<C, P>
-	where P: Send + Sync + 'static
+impl<B: BlockT, C, P> PreVerifier<B> for AuraPreVerifier<C, P> where
+	C: ProvideRuntimeApi<B> + BlockOf + ProvideCache<B> + HeaderBackend<B> + Send + Sync,
+	C::Api: AuraApi<B, AuthorityId<P>>,
+	DigestItemFor<B>: CompatibleDigestItem<P>,
+	P: Pair + Send + Sync,
+	P::Public: Codec + PartialEq + Clone + Send + Sync,
+	P::Signature: Decode,
+{
+	fn pre_verify(&self, header: &B::Header) -> Result<(), String> {
+		let hash = header.hash();
+		let mut header = header.clone();
+		let seal = header.digest_mut().pop()
+			.ok_or_else(|| Error::<B>::HeaderUnsealed(hash).to_string())?;
+		let signature = seal.as_aura_seal()
+			.ok_or_else(|| aura_err(Error::<B>::HeaderBadSeal(hash)).to_string())?;
+		let slot_num = find_pre_digest::<B, P>(&header).map_err(|e| e.to_string())?;
+
+		let best_id = BlockId::Hash(self.client.info().best_hash);
+		let authorities = match authorities(self.client.as_ref(), &best_id) {
+			Ok(authorities) => authorities,
+			// the seal is left to the verifier.
+			Err(_) => return Ok(()),
+		};
+
+		// the header may have other authorities, so it is only rejected by the verifier.
+		let pre_hash = header.hash();
+		let verified = slot_author::<P>(slot_num, &authorities)
+			.map_or(false, |author| P::verify(&signature, pre_hash.as_ref(), author));
+		if verified {
+			let mut pre_verified_seals = self.pre_verified_seals.lock();
+			if pre_verified_seals.len() >= MAX_PRE_VERIFIED_SEALS {
+				pre_verified_seals.clear();
+			}
+			pre_verified_seals.insert(hash.encode(), authorities);
+		}
+
+		Ok(())
+	}
+}
+
+impl<C, P, CIDP> AuraVerifier<C, P, CIDP>
+	where P: Pair + Send + Sync + 'static
 {
 	fn check_inherents<B: BlockT>(
 		&self,
 		block: B,
 		block_id: BlockId<B>,
 		inherent_data: InherentData,
+		inherent_data_providers: &impl sp_inherents::InherentDataProvider,
 		timestamp_now: u64,
 	) -> Result<(), Error<B>>
 		where C: ProvideRuntimeApi<B>, C::Api: BlockBuilderApi<B, Error = sp_blockchain::Error>
@@ -516,7 +615,7 @@ impl<C, P> AuraVerifier<C, P>
 					},
 					Some(TIError::Other(e)) => Err(Error::Runtime(e.into())),
 					None => Err(Error::DataProvider(
-						self.inherent_data_providers.error_to_string(&i, &e)
+						inherent_data_providers.describe_error(&i, &e)
 					)),
 				})
 		} else {
@@ -526,7 +625,7 @@ impl<C, P> AuraVerifier<C, P>
 }
 
 #[forbid(deprecated)]
-impl<B: BlockT, C, P> Verifier<B> for AuraVerifier<C, P> where
+impl<B: BlockT, C, P, CIDP> Verifier<B> for AuraVerifier<C, P, CIDP> where
 	C: ProvideRuntimeApi<B> +
 		Send +
 		Sync +
@@ -538,6 +637,7 @@ impl<B: BlockT, C, P> Verifier<B> for AuraVerifier<C, P> where
 	P: Pair + Send + Sync + 'static,
 	P::Public: Send + Sync + Hash + Eq + Clone + Decode + Encode + Debug + 'static,
 	P::Signature: Encode + Decode,
+	CIDP: CreateInherentDataProviders<B::Hash, ()>,
 {
 	fn verify(
 		&mut self,
@@ -546,15 +646,27 @@ impl<B: BlockT, C, P> Verifier<B> for AuraVerifier<C, P> where
 		justification: Option<Justification>,
 		mut body: Option<Vec<B::Extrinsic>>,
 	) -> Result<(BlockImportParams<B, ()>, Option<Vec<(CacheKeyId, Vec<u8>)>>), String> {
-		let mut inherent_data = self.inherent_data_providers
+		use sp_inherents::InherentDataProvider as _;
+
+		let hash = header.hash();
+		let parent_hash = *header.parent_hash();
+
+		// the verifier isn't asynchronous, so the providers are created in place.
+		let inherent_data_providers = (
+			self.inherent_data_providers.clone(),
+			futures::executor::block_on(
+				self.create_inherent_data_providers.create_inherent_data_providers(parent_hash, ()),
+			).map_err(|e| e.into_string())?,
+		);
+		let mut inherent_data = inherent_data_providers
 			.create_inherent_data()
 			.map_err(|e| e.into_string())?;
 		let (timestamp_now, slot_now, _) = AuraSlotCompatible.extract_timestamp_and_slot(&inherent_data)
 			.map_err(|e| format!("Could not extract timestamp and slot: {:?}", e))?;
-		let hash = header.hash();
-		let parent_hash = *header.parent_hash();
 		let authorities = authorities(self.client.as_ref(), &BlockId::Hash(parent_hash))
 			.map_err(|e| format!("Could not fetch authorities at {:?}: {:?}", parent_hash, e))?;
+		let seal_verified = self.pre_verified_seals.lock().remove(&hash.encode())
+			.map_or(false, |pre_verified_authorities| pre_verified_authorities == authorities);
 
 		// we add one to allow for some small drift.
 		// FIXME #1019 in the future, alter this queue to allow deferring of
@@ -565,6 +677,7 @@ impl<B: BlockT, C, P> Verifier<B> for AuraVerifier<C, P> where
 			header,
 			hash,
 			&authorities[..],
+			seal_verified,
 		).map_err(|e| e.to_string())?;
 		match checked_header {
 			CheckedHeader::Checked(pre_header, (slot_num, seal)) => {
@@ -588,6 +701,7 @@ impl<B: BlockT, C, P> Verifier<B> for AuraVerifier<C, P> where
 							block.clone(),
 							BlockId::Hash(parent_hash),
 							inherent_data,
+							&inherent_data_providers,
 							timestamp_now,
 						).map_err(|e| e.to_string())?;
 					}
@@ -788,13 +902,18 @@ impl<Block: BlockT, C, I, P> BlockImport<Block> for AuraBlockImport<Block, C, I,
 }
 
 /// Start an import queue for the Aura consensus algorithm.
-pub fn import_queue<B, I, C, P>(
+///
+/// The providers of the inherent data of each block are created by
+/// `create_inherent_data_providers` from its parent hash, besides the slot and timestamp that
+/// Aura provides itself.
+pub fn import_queue<B, I, C, P, CIDP>(
 	slot_duration: SlotDuration,
 	block_import: I,
 	justification_import: Option<BoxJustificationImport<B>>,
 	finality_proof_import: Option<BoxFinalityProofImport<B>>,
 	client: Arc<C>,
-	inherent_data_providers: InherentDataProviders,
+	create_inherent_data_providers: CIDP,
+	prometheus_registry: Option<&Registry>,
 ) -> Result<AuraImportQueue<B, sp_api::TransactionFor<C, B>>, sp_consensus::Error> where
 	B: BlockT,
 	C::Api: BlockBuilderApi<B> + AuraApi<B, AuthorityId<P>> + ApiExt<B, Error = sp_blockchain::Error>,
@@ -804,20 +923,28 @@ pub fn import_queue<B, I, C, P>(
 	P: Pair + Send + Sync + 'static,
 	P::Public: Clone + Eq + Send + Sync + Hash + Debug + Encode + Decode,
 	P::Signature: Encode + Decode,
+	CIDP: CreateInherentDataProviders<B::Hash, ()> + 'static,
 {
+	let inherent_data_providers = InherentDataProviders::new();
 	register_aura_inherent_data_provider(&inherent_data_providers, slot_duration.get())?;
 	initialize_authorities_cache(&*client)?;
 
 	let verifier = AuraVerifier {
 		client: client.clone(),
 		inherent_data_providers,
+		create_inherent_data_providers,
 		phantom: PhantomData,
+		pre_verified_seals: Default::default(),
 	};
+	let pre_verifier = verifier.pre_verifier();
+
 	Ok(BasicQueue::new(
 		verifier,
+		Some(Arc::new(pre_verifier)),
 		Box::new(block_import),
 		justification_import,
 		finality_proof_import,
+		prometheus_registry,
 	))
 }
 
@@ -825,6 +952,7 @@ pub fn import_queue<B, I, C, P>(
 mod tests {
 	use super::*;
 	use sp_consensus::{NoNetwork as DummyOracle, Proposal, RecordProof};
+	use sp_consensus::import_queue::SharedPreVerifier;
 	use sc_network_test::{Block as TestBlock, *};
 	use sp_runtime::traits::{Block as BlockT, DigestFor};
 	use sc_network::config::ProtocolConfig;
@@ -891,7 +1019,7 @@ mod tests {
 	}
 
 	impl TestNetFactory for AuraTestNet {
-		type Verifier = AuraVerifier<PeersFullClient, AuthorityPair>;
+		type Verifier = AuraVerifier<PeersFullClient, AuthorityPair, InherentDataProviders>;
 		type PeerData = ();
 
 		/// Create new test network with peers and given config.
@@ -917,13 +1045,19 @@ mod tests {
 					AuraVerifier {
 						client,
 						inherent_data_providers,
+						create_inherent_data_providers: InherentDataProviders::new(),
 						phantom: Default::default(),
+						pre_verified_seals: Default::default(),
 					}
 				},
 				PeersClient::Light(_, _) => unreachable!("No (yet) tests for light client + Aura"),
 			}
 		}
 
+		fn make_pre_verifier(&self, verifier: &Self::Verifier) -> Option<SharedPreVerifier<TestBlock>> {
+			Some(Arc::new(verifier.pre_verifier()))
+		}
+
 		fn peer(&mut self, i: usize) -> &mut Peer<Self::PeerData> {
 			&mut self.peers[i]
 		}
@@ -975,20 +1109,17 @@ mod tests {
 
 			let slot_duration = slot_duration(&*client).expect("slot duration available");
 
-			let inherent_data_providers = InherentDataProviders::new();
-			register_aura_inherent_data_provider(
-				&inherent_data_providers, slot_duration.get()
-			).expect("Registers aura inherent data provider");
-
-			aura_futures.push(start_aura::<_, _, _, _, _, AuthorityPair, _, _, _>(
+			aura_futures.push(start_aura::<_, _, _, _, _, AuthorityPair, _, _, _, _>(
 				slot_duration,
 				client.clone(),
 				select_chain,
 				client,
 				environ,
 				DummyOracle,
-				inherent_data_providers,
+				InherentDataProviders::new(),
 				false,
+				None,
+				SlotProportion::new(0.5),
 				keystore,
 				sp_consensus::AlwaysCanAuthor,
 			).expect("Starts aura"));
@@ -1017,4 +1148,51 @@ mod tests {
 			Keyring::Charlie.public().into()
 		]);
 	}
+
+	#[test]
+	fn pre_verifier_checks_seals_against_the_authorities_of_the_best_block() {
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let verifier = AuraVerifier::<_, AuthorityPair, _> {
+			client: client.clone(),
+			inherent_data_providers: InherentDataProviders::new(),
+			create_inherent_data_providers: InherentDataProviders::new(),
+			phantom: PhantomData,
+			pre_verified_seals: Default::default(),
+		};
+		let pre_verifier = verifier.pre_verifier();
+
+		let slot_num = 4;
+		let mut unsealed_header = client.new_block(Default::default()).unwrap().build().unwrap().block.header;
+		unsealed_header.digest_mut().push(
+			<DigestItemFor<TestBlock> as CompatibleDigestItem<AuthorityPair>>::aura_pre_digest(slot_num)
+		);
+		let seal_with = |key: Keyring| {
+			let mut header = unsealed_header.clone();
+			let pair = AuthorityPair::from_string(&key.to_seed(), None).unwrap();
+			let signature = pair.sign(header.hash().as_ref());
+			header.digest_mut().push(
+				<DigestItemFor<TestBlock> as CompatibleDigestItem<AuthorityPair>>::aura_seal(signature)
+			);
+			header
+		};
+		let pre_verify = |header: &<TestBlock as BlockT>::Header| {
+			PreVerifier::<TestBlock>::pre_verify(&pre_verifier, header)
+		};
+
+		// Bob is the author of slot 4, as the second of the three authorities.
+		let header = seal_with(Keyring::Bob);
+		pre_verify(&header).unwrap();
+		assert_eq!(
+			verifier.pre_verified_seals.lock().get(&header.hash().encode()),
+			Some(&authorities(&*client, &BlockId::Number(0)).unwrap()),
+		);
+
+		// any other seal is left to the verifier.
+		let header = seal_with(Keyring::Alice);
+		pre_verify(&header).unwrap();
+		assert!(verifier.pre_verified_seals.lock().get(&header.hash().encode()).is_none());
+
+		// and headers without a seal are rejected.
+		assert!(pre_verify(&unsealed_header).is_err());
+	}
 }
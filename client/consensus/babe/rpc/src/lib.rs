@@ -30,9 +30,9 @@ use sp_consensus_babe::{
 	digests::PreDigest,
 };
 use serde::{Deserialize, Serialize};
-use sc_keystore::KeyStorePtr;
+use sc_rpc_api::DenyUnsafe;
 use sp_api::{ProvideRuntimeApi, BlockId};
-use sp_core::crypto::Pair;
+use sp_core::traits::BareCryptoStorePtr;
 use sp_runtime::traits::{Block as BlockT, Header as _};
 use sp_consensus::{SelectChain, Error as ConsensusError};
 use sp_blockchain::{HeaderBackend, HeaderMetadata, Error as BlockChainError};
@@ -44,7 +44,9 @@ type FutureResult<T> = Box<dyn rpc_future::Future<Item = T, Error = RpcError> +
 #[rpc]
 pub trait BabeApi {
 	/// Returns data about which slots (primary or secondary) can be claimed in the current epoch
-	/// with the keys in the keystore.
+	/// by each of the authority keys in the keystore.
+	///
+	/// This is an unsafe call, since it reveals which authorities the node runs.
 	#[rpc(name = "babe_epochAuthorship")]
 	fn epoch_authorship(&self) -> FutureResult<HashMap<AuthorityId, EpochAuthorship>>;
 }
@@ -56,11 +58,13 @@ pub struct BabeRPCHandler<B: BlockT, C, SC> {
 	/// shared reference to EpochChanges
 	shared_epoch_changes: SharedEpochChanges<B, Epoch>,
 	/// shared reference to the Keystore
-	keystore: KeyStorePtr,
+	keystore: BareCryptoStorePtr,
 	/// config (actually holds the slot duration)
 	babe_config: Config,
 	/// The SelectChain strategy
 	select_chain: SC,
+	/// Whether to deny unsafe calls
+	deny_unsafe: DenyUnsafe,
 }
 
 impl<B: BlockT, C, SC> BabeRPCHandler<B, C, SC> {
@@ -68,9 +72,10 @@ impl<B: BlockT, C, SC> BabeRPCHandler<B, C, SC> {
 	pub fn new(
 		client: Arc<C>,
 		shared_epoch_changes: SharedEpochChanges<B, Epoch>,
-		keystore: KeyStorePtr,
+		keystore: BareCryptoStorePtr,
 		babe_config: Config,
 		select_chain: SC,
+		deny_unsafe: DenyUnsafe,
 	) -> Self {
 		Self {
 			client,
@@ -78,6 +83,7 @@ impl<B: BlockT, C, SC> BabeRPCHandler<B, C, SC> {
 			keystore,
 			babe_config,
 			select_chain,
+			deny_unsafe,
 		}
 	}
 }
@@ -91,6 +97,10 @@ impl<B, C, SC> BabeApi for BabeRPCHandler<B, C, SC>
 		SC: SelectChain<B> + Clone + 'static,
 {
 	fn epoch_authorship(&self) -> FutureResult<HashMap<AuthorityId, EpochAuthorship>> {
+		if let Err(err) = self.deny_unsafe.check_if_safe() {
+			return Box::new(rpc_future::err(err.into()));
+		}
+
 		let (
 			babe_config,
 			keystore,
@@ -114,19 +124,44 @@ impl<B, C, SC> BabeApi for BabeRPCHandler<B, C, SC>
 			let epoch = epoch_data(&shared_epoch, &client, &babe_config, epoch_start, &select_chain)?;
 			let (epoch_start, epoch_end) = (epoch.start_slot(), epoch.end_slot());
 
+			// the authorities of the epoch we have a key for, along with their index.
+			let keys = {
+				let local_keys = keystore.read()
+					.sr25519_public_keys(sp_consensus_babe::KEY_TYPE)
+					.into_iter()
+					.map(AuthorityId::from)
+					.collect::<Vec<_>>();
+				epoch.authorities.iter()
+					.enumerate()
+					.filter(|(_, a)| local_keys.contains(&a.0))
+					.map(|(index, a)| (a.0.clone(), index))
+					.collect::<Vec<_>>()
+			};
+
 			let mut claims: HashMap<AuthorityId, EpochAuthorship> = HashMap::new();
 
 			for slot_number in epoch_start..epoch_end {
 				let epoch = epoch_data(&shared_epoch, &client, &babe_config, slot_number, &select_chain)?;
-				if let Some((claim, key)) = authorship::claim_slot(slot_number, &epoch, &babe_config, &keystore) {
-					match claim {
-						PreDigest::Primary { .. } => {
-							claims.entry(key.public()).or_default().primary.push(slot_number);
-						}
-						PreDigest::Secondary { .. } => {
-							claims.entry(key.public()).or_default().secondary.push(slot_number);
-						}
-					};
+				// each key is tried separately, as the first successful claim would otherwise hide
+				// the claims of the other keys for the same slot.
+				for key in &keys {
+					let claim = authorship::claim_slot_using_keys(
+						slot_number,
+						&epoch,
+						&babe_config,
+						&keystore,
+						std::slice::from_ref(key),
+					);
+					if let Some((claim, key)) = claim {
+						match claim {
+							PreDigest::Primary { .. } => {
+								claims.entry(key).or_default().primary.push(slot_number);
+							}
+							PreDigest::Secondary { .. } | PreDigest::SecondaryVRF { .. } => {
+								claims.entry(key).or_default().secondary.push(slot_number);
+							}
+						};
+					}
 				}
 			}
 
@@ -200,7 +235,7 @@ mod tests {
 	};
 	use sp_application_crypto::AppPair;
 	use sp_keyring::Ed25519Keyring;
-	use sc_keystore::Store;
+	use sc_keystore::{KeyStorePtr, Store};
 
 	use std::sync::Arc;
 	use sc_consensus_babe::{Config, block_import, AuthorityPair};
@@ -216,8 +251,7 @@ mod tests {
 		(keystore, keystore_path)
 	}
 
-	#[test]
-	fn rpc() {
+	fn test_babe_rpc_io(deny_unsafe: DenyUnsafe) -> IoHandler {
 		let builder = TestClientBuilder::new();
 		let (client, longest_chain) = builder.build_with_longest_chain();
 		let client = Arc::new(client);
@@ -231,13 +265,32 @@ mod tests {
 		let epoch_changes = link.epoch_changes().clone();
 		let select_chain = longest_chain;
 		let keystore = create_temp_keystore::<AuthorityPair>(Ed25519Keyring::Alice).0;
-		let handler = BabeRPCHandler::new(client.clone(), epoch_changes, keystore, config, select_chain);
+
+		let handler =
+			BabeRPCHandler::new(client, epoch_changes, keystore, config, select_chain, deny_unsafe);
 		let mut io = IoHandler::new();
 
 		io.extend_with(BabeApi::to_delegate(handler));
+		io
+	}
+
+	#[test]
+	fn rpc() {
+		let io = test_babe_rpc_io(DenyUnsafe::No);
+
 		let request = r#"{"jsonrpc":"2.0","method":"babe_epochAuthorship","params": [],"id":1}"#;
 		let response = r#"{"jsonrpc":"2.0","result":{"5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY":{"primary":[0],"secondary":[1,2,4]}},"id":1}"#;
 
 		assert_eq!(Some(response.into()), io.handle_request_sync(request));
 	}
+
+	#[test]
+	fn epoch_authorship_is_unsafe() {
+		let io = test_babe_rpc_io(DenyUnsafe::Yes);
+
+		let request = r#"{"jsonrpc":"2.0","method":"babe_epochAuthorship","params": [],"id":1}"#;
+		let response = r#"{"jsonrpc":"2.0","error":{"code":-32601,"message":"RPC call is unsafe to be called externally"},"id":1}"#;
+
+		assert_eq!(Some(response.into()), io.handle_request_sync(request));
+	}
 }
@@ -19,14 +19,13 @@
 use merlin::Transcript;
 use sp_consensus_babe::{
 	AuthorityId, BabeAuthorityWeight, BABE_ENGINE_ID, BABE_VRF_PREFIX,
-	SlotNumber, AuthorityPair, BabeConfiguration
+	SlotNumber, BabeConfiguration, KEY_TYPE,
 };
 use sp_consensus_babe::digests::PreDigest;
-use sp_core::{U256, blake2_256};
+use sp_core::{U256, blake2_256, crypto::{IsWrappedBy, Public}, traits::BareCryptoStorePtr};
+use sp_core::vrf::{VRFTranscriptData, VRFTranscriptValue};
 use codec::Encode;
 use schnorrkel::vrf::VRFInOut;
-use sp_core::Pair;
-use sc_keystore::KeyStorePtr;
 use super::Epoch;
 
 /// Calculates the primary selection threshold for a given authority, taking
@@ -98,16 +97,35 @@ pub(super) fn make_transcript(
 	transcript
 }
 
+/// The data of the transcript built by `make_transcript`, for the keystore to sign.
+pub(super) fn make_transcript_data(
+	randomness: &[u8],
+	slot_number: u64,
+	epoch: u64,
+) -> VRFTranscriptData {
+	VRFTranscriptData {
+		label: &BABE_ENGINE_ID,
+		items: vec![
+			("slot number", VRFTranscriptValue::U64(slot_number)),
+			("current epoch", VRFTranscriptValue::U64(epoch)),
+			("chain randomness", VRFTranscriptValue::Bytes(randomness.to_vec())),
+		],
+	}
+}
 
 /// Claim a secondary slot if it is our turn to propose, returning the
 /// pre-digest to use when authoring the block, or `None` if it is not our turn
-/// to propose.
+/// to propose. With `author_secondary_vrf`, the pre-digest carries a VRF output
+/// which contributes to the epoch randomness.
 fn claim_secondary_slot(
 	slot_number: SlotNumber,
-	authorities: &[(AuthorityId, BabeAuthorityWeight)],
-	keystore: &KeyStorePtr,
-	randomness: [u8; 32],
-) -> Option<(PreDigest, AuthorityPair)> {
+	epoch: &Epoch,
+	keystore: &BareCryptoStorePtr,
+	keys: &[(AuthorityId, usize)],
+	author_secondary_vrf: bool,
+) -> Option<(PreDigest, AuthorityId)> {
+	let Epoch { authorities, randomness, epoch_index, .. } = epoch;
+
 	if authorities.is_empty() {
 		return None;
 	}
@@ -115,25 +133,49 @@ fn claim_secondary_slot(
 	let expected_author = super::authorship::secondary_slot_author(
 		slot_number,
 		authorities,
-		randomness,
+		*randomness,
 	)?;
 
 	let keystore = keystore.read();
 
-	for (pair, authority_index) in authorities.iter()
-		.enumerate()
-		.flat_map(|(i, a)| {
-			keystore.key_pair::<AuthorityPair>(&a.0).ok().map(|kp| (kp, i))
-		})
-	{
-		if pair.public() == *expected_author {
-			let pre_digest = PreDigest::Secondary {
+	for (authority_id, authority_index) in keys {
+		if authority_id != expected_author ||
+			!keystore.has_keys(&[(authority_id.to_raw_vec(), KEY_TYPE)])
+		{
+			continue;
+		}
+
+		let pre_digest = if author_secondary_vrf {
+			let transcript_data = super::authorship::make_transcript_data(
+				randomness,
+				slot_number,
+				*epoch_index,
+			);
+			let result = keystore.sr25519_vrf_sign(
+				KEY_TYPE,
+				sp_core::sr25519::Public::from_ref(authority_id),
+				transcript_data,
+			);
+			match result {
+				Ok(signature) => PreDigest::SecondaryVRF {
+					slot_number,
+					vrf_output: signature.output,
+					vrf_proof: signature.proof,
+					authority_index: *authority_index as u32,
+				},
+				Err(e) => {
+					log::warn!(target: "babe", "Unable to sign the secondary slot VRF: {}", e);
+					continue;
+				},
+			}
+		} else {
+			PreDigest::Secondary {
 				slot_number,
-				authority_index: authority_index as u32,
-			};
+				authority_index: *authority_index as u32,
+			}
+		};
 
-			return Some((pre_digest, pair));
-		}
+		return Some((pre_digest, authority_id.clone()));
 	}
 
 	None
@@ -147,16 +189,35 @@ pub fn claim_slot(
 	slot_number: SlotNumber,
 	epoch: &Epoch,
 	config: &BabeConfiguration,
-	keystore: &KeyStorePtr,
-) -> Option<(PreDigest, AuthorityPair)> {
-	claim_primary_slot(slot_number, epoch, config.c, keystore)
+	keystore: &BareCryptoStorePtr,
+) -> Option<(PreDigest, AuthorityId)> {
+	let authorities = epoch.authorities.iter()
+		.enumerate()
+		.map(|(index, a)| (a.0.clone(), index))
+		.collect::<Vec<_>>();
+	claim_slot_using_keys(slot_number, epoch, config, keystore, &authorities)
+}
+
+/// Like `claim_slot`, but only tries to claim the slot with the given keys, each given along
+/// with the index of its authority in the epoch. Keys that aren't in the keystore are skipped.
+pub fn claim_slot_using_keys(
+	slot_number: SlotNumber,
+	epoch: &Epoch,
+	config: &BabeConfiguration,
+	keystore: &BareCryptoStorePtr,
+	keys: &[(AuthorityId, usize)],
+) -> Option<(PreDigest, AuthorityId)> {
+	claim_primary_slot(slot_number, epoch, config.c, keystore, keys)
 		.or_else(|| {
-			if config.secondary_slots {
+			if config.allowed_slots.is_secondary_plain_slots_allowed() ||
+				config.allowed_slots.is_secondary_vrf_slots_allowed()
+			{
 				claim_secondary_slot(
 					slot_number,
-					&epoch.authorities,
+					epoch,
 					keystore,
-					epoch.randomness,
+					keys,
+					config.allowed_slots.is_secondary_vrf_slots_allowed(),
 				)
 			} else {
 				None
@@ -164,11 +225,6 @@ pub fn claim_slot(
 		})
 }
 
-fn get_keypair(q: &AuthorityPair) -> &schnorrkel::Keypair {
-	use sp_core::crypto::IsWrappedBy;
-	sp_core::sr25519::Pair::from_ref(q).as_ref()
-}
-
 /// Claim a primary slot if it is our turn.  Returns `None` if it is not our turn.
 /// This hashes the slot number, epoch, genesis hash, and chain randomness into
 /// the VRF.  If the VRF produces a value less than `threshold`, it is our turn,
@@ -177,39 +233,62 @@ fn claim_primary_slot(
 	slot_number: SlotNumber,
 	epoch: &Epoch,
 	c: (u64, u64),
-	keystore: &KeyStorePtr,
-) -> Option<(PreDigest, AuthorityPair)> {
+	keystore: &BareCryptoStorePtr,
+	keys: &[(AuthorityId, usize)],
+) -> Option<(PreDigest, AuthorityId)> {
 	let Epoch { authorities, randomness, epoch_index, .. } = epoch;
 	let keystore = keystore.read();
 
-	for (pair, authority_index) in authorities.iter()
-		.enumerate()
-		.flat_map(|(i, a)| {
-			keystore.key_pair::<AuthorityPair>(&a.0).ok().map(|kp| (kp, i))
-		})
-	{
+	for (authority_id, authority_index) in keys {
+		// only ask the keystore, which may be remote, to sign with keys it actually holds.
+		if !keystore.has_keys(&[(authority_id.to_raw_vec(), KEY_TYPE)]) {
+			continue;
+		}
+
 		let transcript = super::authorship::make_transcript(randomness, slot_number, *epoch_index);
+		let transcript_data = super::authorship::make_transcript_data(
+			randomness,
+			slot_number,
+			*epoch_index,
+		);
 
 		// Compute the threshold we will use.
 		//
 		// We already checked that authorities contains `key.public()`, so it can't
 		// be empty.  Therefore, this division in `calculate_threshold` is safe.
-		let threshold = super::authorship::calculate_primary_threshold(c, authorities, authority_index);
-
-		let pre_digest = get_keypair(&pair)
-			.vrf_sign_after_check(transcript, |inout| super::authorship::check_primary_threshold(inout, threshold))
-			.map(|s| {
-				PreDigest::Primary {
+		let threshold = super::authorship::calculate_primary_threshold(
+			c,
+			authorities,
+			*authority_index,
+		);
+
+		let public = sp_core::sr25519::Public::from_ref(authority_id);
+		let signature = match keystore.sr25519_vrf_sign(KEY_TYPE, public, transcript_data) {
+			Ok(signature) => signature,
+			Err(e) => {
+				log::warn!(target: "babe", "Unable to sign the primary slot VRF: {}", e);
+				continue;
+			},
+		};
+
+		// the output is only trusted once checked against the proof, as with any other block.
+		let inout = schnorrkel::PublicKey::from_bytes(public.as_ref())
+			.and_then(|public| public.vrf_verify(transcript, &signature.output, &signature.proof))
+			.map(|(inout, _)| inout);
+		match inout {
+			Ok(inout) if super::authorship::check_primary_threshold(&inout, threshold) => {
+				let pre_digest = PreDigest::Primary {
 					slot_number,
-					vrf_output: s.0.to_output(),
-					vrf_proof: s.1,
-					authority_index: authority_index as u32,
-				}
-			});
-
-		// early exit on first successful claim
-		if let Some(pre_digest) = pre_digest {
-			return Some((pre_digest, pair));
+					vrf_output: signature.output,
+					vrf_proof: signature.proof,
+					authority_index: *authority_index as u32,
+				};
+
+				// early exit on first successful claim
+				return Some((pre_digest, authority_id.clone()));
+			},
+			Ok(_) => {},
+			Err(e) => log::warn!(target: "babe", "Invalid VRF signature from the keystore: {}", e),
 		}
 	}
 
@@ -70,6 +70,7 @@ use std::{
 	any::Any, borrow::Cow
 };
 use sp_consensus_babe;
+use prometheus_endpoint::Registry;
 use sp_consensus::{ImportResult, CanAuthorWith};
 use sp_consensus::import_queue::{
 	BoxJustificationImport, BoxFinalityProofImport,
@@ -79,10 +80,11 @@ use sp_runtime::{
 	traits::{Block as BlockT, Header, DigestItemFor, Zero},
 };
 use sp_api::{ProvideRuntimeApi, NumberFor};
-use sc_keystore::KeyStorePtr;
 use parking_lot::Mutex;
-use sp_core::Pair;
-use sp_inherents::{InherentDataProviders, InherentData};
+use sp_core::{crypto::{CryptoTypePublicPair, IsWrappedBy}, traits::BareCryptoStorePtr};
+use sp_inherents::{
+	InherentDataProviders, InherentData, InherentDataProvider, CreateInherentDataProviders,
+};
 use sc_telemetry::{telemetry, CONSENSUS_TRACE, CONSENSUS_DEBUG};
 use sp_consensus::{
 	self, BlockImport, Environment, Proposer, BlockCheckParams,
@@ -91,7 +93,7 @@ use sp_consensus::{
 };
 use sp_consensus_babe::inherents::BabeInherentData;
 use sp_timestamp::{TimestampInherentData, InherentType as TimestampInherent};
-use sp_consensus::import_queue::{Verifier, BasicQueue, CacheKeyId};
+use sp_consensus::import_queue::{Verifier, PreVerifier, BasicQueue, CacheKeyId};
 use sc_client_api::{
 	backend::AuxStore,
 	BlockchainEvents, ProvideUncles,
@@ -101,8 +103,10 @@ use sp_block_builder::BlockBuilder as BlockBuilderApi;
 use futures::prelude::*;
 use log::{warn, debug, info, trace};
 use sc_consensus_slots::{
-	SlotWorker, SlotInfo, SlotCompatible, StorageChanges, CheckedHeader, check_equivocation,
+	SlotWorker, SlotInfo, SlotCompatible, SlotLenienceType, StorageChanges, CheckedHeader,
+	check_equivocation,
 };
+pub use sc_consensus_slots::{BackoffAuthoringOnFinalizedHeadLagging, SlotProportion};
 use sc_consensus_epochs::{
 	descendent_query, SharedEpochChanges, EpochChangesFor, Epoch as EpochT, ViableEpochDescriptor,
 };
@@ -114,7 +118,7 @@ use schnorrkel::SignatureError;
 use codec::{Encode, Decode};
 use sp_api::ApiExt;
 
-mod aux_schema;
+pub mod aux_schema;
 mod verification;
 pub mod authorship;
 #[cfg(test)]
@@ -158,6 +162,18 @@ impl EpochT for Epoch {
 	}
 }
 
+impl From<sp_consensus_babe::Epoch> for Epoch {
+	fn from(epoch: sp_consensus_babe::Epoch) -> Self {
+		Epoch {
+			epoch_index: epoch.epoch_index,
+			start_slot: epoch.start_slot,
+			duration: epoch.duration,
+			authorities: epoch.authorities,
+			randomness: epoch.randomness,
+		}
+	}
+}
+
 #[derive(derive_more::Display, Debug)]
 enum Error<B: BlockT> {
 	#[display(fmt = "Multiple BABE pre-runtime digests, rejecting!")]
@@ -284,9 +300,9 @@ impl std::ops::Deref for Config {
 }
 
 /// Parameters for BABE.
-pub struct BabeParams<B: BlockT, C, E, I, SO, SC, CAW> {
+pub struct BabeParams<B: BlockT, C, E, I, SO, SC, CAW, CIDP> {
 	/// The keystore that manages the keys of the node.
-	pub keystore: KeyStorePtr,
+	pub keystore: BareCryptoStorePtr,
 
 	/// The client to use
 	pub client: Arc<C>,
@@ -305,12 +321,19 @@ pub struct BabeParams<B: BlockT, C, E, I, SO, SC, CAW> {
 	/// A sync oracle
 	pub sync_oracle: SO,
 
-	/// Providers for inherent data.
-	pub inherent_data_providers: InherentDataProviders,
+	/// Creates the providers of the inherent data of each block from its parent hash, besides
+	/// the slot, timestamp and uncles that BABE provides itself.
+	pub create_inherent_data_providers: CIDP,
 
 	/// Force authoring of blocks even if we are offline
 	pub force_authoring: bool,
 
+	/// Strategy for slowing down authoring when finality lags behind, if any.
+	pub backoff_authoring_blocks: Option<BackoffAuthoringOnFinalizedHeadLagging>,
+
+	/// The proportion of the slot dedicated to proposing blocks.
+	pub block_proposal_slot_portion: SlotProportion,
+
 	/// The source of timestamps for relative slots
 	pub babe_link: BabeLink<B>,
 
@@ -319,18 +342,20 @@ pub struct BabeParams<B: BlockT, C, E, I, SO, SC, CAW> {
 }
 
 /// Start the babe worker.
-pub fn start_babe<B, C, SC, E, I, SO, CAW, Error>(BabeParams {
+pub fn start_babe<B, C, SC, E, I, SO, CAW, CIDP, Error>(BabeParams {
 	keystore,
 	client,
 	select_chain,
 	env,
 	block_import,
 	sync_oracle,
-	inherent_data_providers,
+	create_inherent_data_providers,
 	force_authoring,
+	backoff_authoring_blocks,
+	block_proposal_slot_portion,
 	babe_link,
 	can_author_with,
-}: BabeParams<B, C, E, I, SO, SC, CAW>) -> Result<
+}: BabeParams<B, C, E, I, SO, SC, CAW, CIDP>) -> Result<
 	impl futures::Future<Output=()>,
 	sp_consensus::Error,
 > where
@@ -346,6 +371,7 @@ pub fn start_babe<B, C, SC, E, I, SO, CAW, Error>(BabeParams {
 	Error: std::error::Error + Send + From<ConsensusError> + From<I::Error> + 'static,
 	SO: SyncOracle + Send + Sync + Clone,
 	CAW: CanAuthorWith<B> + Send,
+	CIDP: CreateInherentDataProviders<B::Hash, ()>,
 {
 	let config = babe_link.config;
 	let worker = BabeWorker {
@@ -354,11 +380,14 @@ pub fn start_babe<B, C, SC, E, I, SO, CAW, Error>(BabeParams {
 		env,
 		sync_oracle: sync_oracle.clone(),
 		force_authoring,
+		backoff_authoring_blocks,
+		block_proposal_slot_portion,
 		keystore,
 		epoch_changes: babe_link.epoch_changes.clone(),
 		config: config.clone(),
 	};
 
+	let inherent_data_providers = InherentDataProviders::new();
 	register_babe_inherent_data_provider(&inherent_data_providers, config.slot_duration())?;
 	sc_consensus_uncles::register_uncles_inherent_data_provider(
 		client.clone(),
@@ -373,6 +402,7 @@ pub fn start_babe<B, C, SC, E, I, SO, CAW, Error>(BabeParams {
 		worker,
 		sync_oracle,
 		inherent_data_providers,
+		create_inherent_data_providers,
 		babe_link.time_source,
 		can_author_with,
 	))
@@ -384,7 +414,9 @@ struct BabeWorker<B: BlockT, C, E, I, SO> {
 	env: E,
 	sync_oracle: SO,
 	force_authoring: bool,
-	keystore: KeyStorePtr,
+	backoff_authoring_blocks: Option<BackoffAuthoringOnFinalizedHeadLagging>,
+	block_proposal_slot_portion: SlotProportion,
+	keystore: BareCryptoStorePtr,
 	epoch_changes: SharedEpochChanges<B, Epoch>,
 	config: Config,
 }
@@ -403,7 +435,7 @@ impl<B, C, E, I, Error, SO> sc_consensus_slots::SimpleSlotWorker<B> for BabeWork
 	Error: std::error::Error + Send + From<ConsensusError> + From<I::Error> + 'static,
 {
 	type EpochData = ViableEpochDescriptor<B::Hash, NumberFor<B>, Epoch>;
-	type Claim = (PreDigest, AuthorityPair);
+	type Claim = (PreDigest, AuthorityId);
 	type SyncOracle = SO;
 	type CreateProposer = Pin<Box<
 		dyn Future<Output = Result<E::Proposer, sp_consensus::Error>> + Send + 'static
@@ -481,11 +513,20 @@ impl<B, C, E, I, Error, SO> sc_consensus_slots::SimpleSlotWorker<B> for BabeWork
 		StorageChanges<I::Transaction, B>,
 		Self::Claim,
 		Self::EpochData,
-	) -> sp_consensus::BlockImportParams<B, I::Transaction> + Send> {
-		Box::new(|header, header_hash, body, storage_changes, (_, pair), epoch_descriptor| {
+	) -> Result<
+		sp_consensus::BlockImportParams<B, I::Transaction>,
+		sp_consensus::Error,
+	> + Send> {
+		let keystore = self.keystore.clone();
+		Box::new(move |header, header_hash, body, storage_changes, (_, public), epoch_descriptor| {
 			// sign the pre-sealed hash of the block and then
 			// add it to a digest item.
-			let signature = pair.sign(header_hash.as_ref());
+			let public: CryptoTypePublicPair = sp_core::sr25519::Public::from_ref(&public).into();
+			let signature = keystore.read()
+				.sign_with(sp_consensus_babe::KEY_TYPE, &public, header_hash.as_ref())
+				.map_err(|e| sp_consensus::Error::CannotSign(public.1.clone(), e.to_string()))?;
+			let signature = AuthoritySignature::decode(&mut &signature[..])
+				.map_err(|e| sp_consensus::Error::CannotSign(public.1.clone(), e.to_string()))?;
 			let digest_item = <DigestItemFor<B> as CompatibleDigestItem>::babe_seal(signature);
 
 			let mut import_block = BlockImportParams::new(BlockOrigin::Own, header);
@@ -497,7 +538,7 @@ impl<B, C, E, I, Error, SO> sc_consensus_slots::SimpleSlotWorker<B> for BabeWork
 				Box::new(BabeIntermediate::<B> { epoch_descriptor }) as Box<dyn Any>,
 			);
 
-			import_block
+			Ok(import_block)
 		})
 	}
 
@@ -515,41 +556,43 @@ impl<B, C, E, I, Error, SO> sc_consensus_slots::SimpleSlotWorker<B> for BabeWork
 		}))
 	}
 
+	fn should_backoff(&self, slot_number: u64, chain_head: &B::Header) -> bool {
+		let strategy = match self.backoff_authoring_blocks {
+			Some(ref strategy) => strategy,
+			None => return false,
+		};
+
+		match find_pre_digest::<B>(chain_head) {
+			Ok(pre_digest) => strategy.should_backoff(
+				*chain_head.number(),
+				pre_digest.slot_number(),
+				self.client.info().finalized_number,
+				slot_number,
+				self.logging_target(),
+			),
+			Err(_) => false,
+		}
+	}
+
 	fn proposing_remaining_duration(
 		&self,
 		head: &B::Header,
 		slot_info: &SlotInfo
 	) -> Option<std::time::Duration> {
-		// never give more than 2^this times the lenience.
-		const BACKOFF_CAP: u64 = 8;
-
-		// how many slots it takes before we double the lenience.
-		const BACKOFF_STEP: u64 = 2;
-
-		let slot_remaining = self.slot_remaining_duration(slot_info);
-		let parent_slot = match find_pre_digest::<B>(head) {
-			Err(_) => return Some(slot_remaining),
-			Ok(d) => d.slot_number(),
+		// the genesis block has a dummy pre-digest, the chain doesn't lag behind it.
+		let parent_slot = if head.number().is_zero() {
+			None
+		} else {
+			find_pre_digest::<B>(head).ok().map(|d| d.slot_number())
 		};
 
-		// we allow a lenience of the number of slots since the head of the
-		// chain was produced, minus 1 (since there is always a difference of at least 1)
-		//
-		// exponential back-off.
-		// in normal cases we only attempt to issue blocks up to the end of the slot.
-		// when the chain has been stalled for a few slots, we give more lenience.
-		let slot_lenience = slot_info.number.saturating_sub(parent_slot + 1);
-
-		let slot_lenience = std::cmp::min(slot_lenience, BACKOFF_CAP);
-		let slot_duration = slot_info.duration << (slot_lenience / BACKOFF_STEP);
-
-		if slot_lenience >= 1 {
-			debug!(target: "babe", "No block for {} slots. Applying 2^({}/{}) lenience",
-				slot_lenience, slot_lenience, BACKOFF_STEP);
-		}
-
-		let slot_lenience = Duration::from_secs(slot_duration);
-		Some(slot_lenience + slot_remaining)
+		Some(sc_consensus_slots::proposing_remaining_duration(
+			parent_slot,
+			slot_info,
+			&self.block_proposal_slot_portion,
+			SlotLenienceType::Exponential,
+			self.logging_target(),
+		))
 	}
 }
 
@@ -655,16 +698,105 @@ impl<Block: BlockT> BabeLink<Block> {
 	}
 }
 
+/// Maximum number of seals kept by a `BabePreVerifier` until their block is verified.
+const MAX_PRE_VERIFIED_SEALS: usize = 4096;
+
+/// The blocks whose seal was checked by a `BabePreVerifier`, along with the epoch it was checked
+/// against.
+type PreVerifiedSeals<Block> = Arc<Mutex<HashMap<<Block as BlockT>::Hash, Epoch>>>;
+
 /// A verifier for Babe blocks.
-pub struct BabeVerifier<Block: BlockT, Client> {
+pub struct BabeVerifier<Block: BlockT, Client, CIDP> {
 	client: Arc<Client>,
-	inherent_data_providers: sp_inherents::InherentDataProviders,
+	inherent_data_providers: InherentDataProviders,
+	create_inherent_data_providers: CIDP,
 	config: Config,
 	epoch_changes: SharedEpochChanges<Block, Epoch>,
 	time_source: TimeSource,
+	pre_verified_seals: PreVerifiedSeals<Block>,
+}
+
+impl<Block: BlockT, Client, CIDP> BabeVerifier<Block, Client, CIDP> {
+	/// A pre-verifier checking the seals of the queued headers ahead of this verifier.
+	pub fn pre_verifier(&self) -> BabePreVerifier<Block, Client> {
+		BabePreVerifier {
+			client: self.client.clone(),
+			config: self.config.clone(),
+			epoch_changes: self.epoch_changes.clone(),
+			pre_verified_seals: self.pre_verified_seals.clone(),
+		}
+	}
+}
+
+/// A pre-verifier for Babe blocks, checking the seals and VRF proofs of the queued headers
+/// concurrently.
+///
+/// The epoch of a queued header is only known once its parent is imported, so its seal is checked
+/// against the epoch it would have as a child of the best block. The `BabeVerifier` only skips
+/// checking it again if the header turns out to be in the same epoch.
+pub struct BabePreVerifier<Block: BlockT, Client> {
+	client: Arc<Client>,
+	config: Config,
+	epoch_changes: SharedEpochChanges<Block, Epoch>,
+	pre_verified_seals: PreVerifiedSeals<Block>,
+}
+
+impl<Block, Client> PreVerifier<Block> for BabePreVerifier<Block, Client> where
+	Block: BlockT,
+	Client: HeaderMetadata<Block, Error = sp_blockchain::Error> + HeaderBackend<Block> + Send + Sync,
+{
+	fn pre_verify(&self, header: &Block::Header) -> Result<(), String> {
+		let hash = header.hash();
+		let pre_digest = find_pre_digest::<Block>(header)?;
+		match header.digest().logs().last() {
+			Some(seal) if seal.as_babe_seal().is_some() => {},
+			Some(_) => return Err(babe_err(Error::<Block>::HeaderBadSeal(hash)).into()),
+			None => return Err(babe_err(Error::<Block>::HeaderUnsealed(hash)).into()),
+		}
+
+		let info = self.client.info();
+		let epoch = {
+			let epoch_changes = self.epoch_changes.lock();
+			let epoch_descriptor = match epoch_changes.epoch_descriptor_for_child_of(
+				descendent_query(&*self.client),
+				&info.best_hash,
+				info.best_number,
+				pre_digest.slot_number(),
+			) {
+				Ok(Some(epoch_descriptor)) => epoch_descriptor,
+				// the seal is left to the verifier.
+				_ => return Ok(()),
+			};
+			match epoch_changes.viable_epoch(&epoch_descriptor, |slot| self.config.genesis_epoch(slot)) {
+				Some(epoch) => epoch.as_ref().clone(),
+				None => return Ok(()),
+			}
+		};
+
+		let v_params = verification::VerificationParams {
+			header: header.clone(),
+			pre_digest: Some(pre_digest),
+			// whether the slot is in the future is checked by the verifier.
+			slot_now: SlotNumber::max_value(),
+			epoch: &epoch,
+			config: &self.config,
+			seal_verified: false,
+		};
+
+		// the header may be in another epoch, so it is only rejected by the verifier.
+		if let Ok(CheckedHeader::Checked(..)) = verification::check_header::<Block>(v_params) {
+			let mut pre_verified_seals = self.pre_verified_seals.lock();
+			if pre_verified_seals.len() >= MAX_PRE_VERIFIED_SEALS {
+				pre_verified_seals.clear();
+			}
+			pre_verified_seals.insert(hash, epoch);
+		}
+
+		Ok(())
+	}
 }
 
-impl<Block, Client> BabeVerifier<Block, Client>
+impl<Block, Client, CIDP> BabeVerifier<Block, Client, CIDP>
 	where
 		Block: BlockT,
 		Client: HeaderBackend<Block> + HeaderMetadata<Block> + ProvideRuntimeApi<Block>,
@@ -675,6 +807,7 @@ impl<Block, Client> BabeVerifier<Block, Client>
 		block: Block,
 		block_id: BlockId<Block>,
 		inherent_data: InherentData,
+		inherent_data_providers: &impl InherentDataProvider,
 	) -> Result<(), Error<Block>>
 	{
 		let inherent_res = self.client.runtime_api().check_inherents(
@@ -687,7 +820,7 @@ impl<Block, Client> BabeVerifier<Block, Client>
 			inherent_res
 				.into_errors()
 				.try_for_each(|(i, e)| {
-					Err(Error::CheckInherents(self.inherent_data_providers.error_to_string(&i, &e)))
+					Err(Error::CheckInherents(inherent_data_providers.describe_error(&i, &e)))
 				})
 		} else {
 			Ok(())
@@ -695,11 +828,69 @@ impl<Block, Client> BabeVerifier<Block, Client>
 	}
 }
 
-impl<Block, Client> Verifier<Block> for BabeVerifier<Block, Client> where
+impl<Block, Client, CIDP> BabeVerifier<Block, Client, CIDP>
+	where
+		Block: BlockT,
+		Client: HeaderBackend<Block> + ProvideRuntimeApi<Block>,
+		Client::Api: BabeApi<Block, Error = sp_blockchain::Error>,
+{
+	/// Submit a report of the given equivocation to the runtime, along with a proof that the
+	/// offender owned its key during the session of the equivocation.
+	fn report_equivocation(
+		&self,
+		equivocation_proof: sp_consensus_babe::EquivocationProof<Block::Header>,
+	) -> Result<(), Error<Block>> {
+		let runtime_api = self.client.runtime_api();
+		let best_id = BlockId::Hash(self.client.info().best_hash);
+
+		// the key ownership proof can only be generated while the session of the equivocation
+		// is ongoing, so we first try at the parent of the equivocating header. this fails if
+		// the header is the first of its session, in which case we fall back to the best block.
+		let generate_key_owner_proof = |block_id: &BlockId<Block>| {
+			runtime_api.generate_key_ownership_proof(
+				block_id,
+				equivocation_proof.slot_number,
+				equivocation_proof.offender.clone(),
+			).map_err(Error::Client)
+		};
+
+		let parent_id = BlockId::Hash(*equivocation_proof.first_header.parent_hash());
+		let key_owner_proof = match generate_key_owner_proof(&parent_id)? {
+			Some(proof) => proof,
+			None => match generate_key_owner_proof(&best_id)? {
+				Some(proof) => proof,
+				None => {
+					debug!(target: "babe", "Equivocation offender is not an authority.");
+					return Ok(());
+				},
+			},
+		};
+
+		let offender = equivocation_proof.offender.clone();
+
+		// submit the equivocation report extrinsic to the pool of the best block.
+		let submitted = runtime_api.submit_report_equivocation_unsigned_extrinsic(
+			&best_id,
+			equivocation_proof,
+			key_owner_proof,
+		).map_err(Error::Client)?;
+
+		match submitted {
+			Some(()) =>
+				info!(target: "babe", "Submitted equivocation report for author {:?}", offender),
+			None => debug!(target: "babe", "Equivocation reporting is disabled by the runtime."),
+		}
+
+		Ok(())
+	}
+}
+
+impl<Block, Client, CIDP> Verifier<Block> for BabeVerifier<Block, Client, CIDP> where
 	Block: BlockT,
 	Client: HeaderMetadata<Block, Error = sp_blockchain::Error> + HeaderBackend<Block> + ProvideRuntimeApi<Block>
 	+ Send + Sync + AuxStore + ProvideCache<Block>,
 	Client::Api: BlockBuilderApi<Block, Error = sp_blockchain::Error> + BabeApi<Block, Error = sp_blockchain::Error>,
+	CIDP: CreateInherentDataProviders<Block::Hash, ()>,
 {
 	fn verify(
 		&mut self,
@@ -718,17 +909,23 @@ impl<Block, Client> Verifier<Block> for BabeVerifier<Block, Client> where
 		);
 
 		debug!(target: "babe", "We have {:?} logs in this header", header.digest().logs().len());
-		let mut inherent_data = self
-			.inherent_data_providers
+		let hash = header.hash();
+		let parent_hash = *header.parent_hash();
+
+		// the verifier isn't asynchronous, so the providers are created in place.
+		let inherent_data_providers = (
+			self.inherent_data_providers.clone(),
+			futures::executor::block_on(
+				self.create_inherent_data_providers.create_inherent_data_providers(parent_hash, ()),
+			).map_err(Error::<Block>::Runtime)?,
+		);
+		let mut inherent_data = inherent_data_providers
 			.create_inherent_data()
 			.map_err(Error::<Block>::Runtime)?;
 
 		let (_, slot_now, _) = self.time_source.extract_timestamp_and_slot(&inherent_data)
 			.map_err(Error::<Block>::Extraction)?;
 
-		let hash = header.hash();
-		let parent_hash = *header.parent_hash();
-
 		let parent_header_metadata = self.client.header_metadata(parent_hash)
 			.map_err(Error::<Block>::FetchParentHeader)?;
 
@@ -747,6 +944,9 @@ impl<Block, Client> Verifier<Block> for BabeVerifier<Block, Client> where
 			|slot| self.config.genesis_epoch(slot)
 		).ok_or_else(|| Error::<Block>::FetchEpoch(parent_hash))?;
 
+		let seal_verified = self.pre_verified_seals.lock().remove(&hash)
+			.map_or(false, |epoch| &epoch == viable_epoch.as_ref());
+
 		// We add one to the current slot to allow for some small drift.
 		// FIXME #1019 in the future, alter this queue to allow deferring of headers
 		let v_params = verification::VerificationParams {
@@ -755,6 +955,7 @@ impl<Block, Client> Verifier<Block> for BabeVerifier<Block, Client> where
 			slot_now: slot_now + 1,
 			epoch: viable_epoch.as_ref(),
 			config: &self.config,
+			seal_verified,
 		};
 
 		match verification::check_header::<Block>(v_params)? {
@@ -782,6 +983,17 @@ impl<Block, Client> Verifier<Block> for BabeVerifier<Block, Client> where
 						equivocation_proof.fst_header().hash(),
 						equivocation_proof.snd_header().hash(),
 					);
+
+					let equivocation_proof = sp_consensus_babe::EquivocationProof {
+						offender: author.clone(),
+						slot_number,
+						first_header: equivocation_proof.fst_header().clone(),
+						second_header: equivocation_proof.snd_header().clone(),
+					};
+
+					if let Err(err) = self.report_equivocation(equivocation_proof) {
+						warn!(target: "babe", "Error reporting equivocation: {:?}", err);
+					}
 				}
 
 				// if the body is passed through, we need to use the runtime
@@ -795,6 +1007,7 @@ impl<Block, Client> Verifier<Block> for BabeVerifier<Block, Client> where
 						block.clone(),
 						BlockId::Hash(parent_hash),
 						inherent_data,
+						&inherent_data_providers,
 					)?;
 
 					let (_, inner_body) = block.deconstruct();
@@ -1190,35 +1403,47 @@ pub fn block_import<Client, Block: BlockT, I>(
 ///
 /// The block import object provided must be the `BabeBlockImport` or a wrapper
 /// of it, otherwise crucial import logic will be omitted.
-pub fn import_queue<Block: BlockT, Client, Inner>(
+///
+/// The providers of the inherent data of each block are created by
+/// `create_inherent_data_providers` from its parent hash, besides the slot and timestamp that
+/// BABE provides itself.
+pub fn import_queue<Block: BlockT, Client, Inner, CIDP>(
 	babe_link: BabeLink<Block>,
 	block_import: Inner,
 	justification_import: Option<BoxJustificationImport<Block>>,
 	finality_proof_import: Option<BoxFinalityProofImport<Block>>,
 	client: Arc<Client>,
-	inherent_data_providers: InherentDataProviders,
+	create_inherent_data_providers: CIDP,
+	prometheus_registry: Option<&Registry>,
 ) -> ClientResult<BabeImportQueue<Block, sp_api::TransactionFor<Client, Block>>> where
 	Inner: BlockImport<Block, Error = ConsensusError, Transaction = sp_api::TransactionFor<Client, Block>>
 		+ Send + Sync + 'static,
 	Client: ProvideRuntimeApi<Block> + ProvideCache<Block> + Send + Sync + AuxStore + 'static,
 	Client: HeaderBackend<Block> + HeaderMetadata<Block, Error = sp_blockchain::Error>,
 	Client::Api: BlockBuilderApi<Block> + BabeApi<Block> + ApiExt<Block, Error = sp_blockchain::Error>,
+	CIDP: CreateInherentDataProviders<Block::Hash, ()> + 'static,
 {
+	let inherent_data_providers = InherentDataProviders::new();
 	register_babe_inherent_data_provider(&inherent_data_providers, babe_link.config.slot_duration)?;
 
 	let verifier = BabeVerifier {
 		client: client.clone(),
 		inherent_data_providers,
+		create_inherent_data_providers,
 		config: babe_link.config,
 		epoch_changes: babe_link.epoch_changes,
 		time_source: babe_link.time_source,
+		pre_verified_seals: Default::default(),
 	};
+	let pre_verifier = verifier.pre_verifier();
 
 	Ok(BasicQueue::new(
 		verifier,
+		Some(Arc::new(pre_verifier)),
 		Box::new(block_import),
 		justification_import,
 		finality_proof_import,
+		prometheus_registry,
 	))
 }
 
@@ -1233,7 +1458,7 @@ pub mod test_helpers {
 		slot_number: u64,
 		parent: &B::Header,
 		client: &C,
-		keystore: &KeyStorePtr,
+		keystore: &BareCryptoStorePtr,
 		link: &BabeLink<B>,
 	) -> Option<PreDigest> where
 		B: BlockT,
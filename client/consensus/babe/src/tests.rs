@@ -22,11 +22,14 @@
 use super::*;
 use authorship::claim_slot;
 
-use sp_consensus_babe::{AuthorityPair, SlotNumber};
+use sp_consensus_babe::{AuthorityPair, SlotNumber, AllowedSlots};
+use sp_core::Pair;
 use sc_block_builder::{BlockBuilder, BlockBuilderProvider};
 use sp_consensus::{
 	NoNetwork as DummyOracle, Proposal, RecordProof,
-	import_queue::{BoxBlockImport, BoxJustificationImport, BoxFinalityProofImport},
+	import_queue::{
+		BoxBlockImport, BoxJustificationImport, BoxFinalityProofImport, SharedPreVerifier,
+	},
 };
 use sc_network_test::*;
 use sc_network_test::{Block as TestBlock, PeersClient};
@@ -204,7 +207,7 @@ type TestHeader = <TestBlock as BlockT>::Header;
 type TestExtrinsic = <TestBlock as BlockT>::Extrinsic;
 
 pub struct TestVerifier {
-	inner: BabeVerifier<TestBlock, PeersFullClient>,
+	inner: BabeVerifier<TestBlock, PeersFullClient, InherentDataProviders>,
 	mutator: Mutator,
 }
 
@@ -255,9 +258,11 @@ impl TestNetFactory for BabeTestNet {
 		)
 	{
 		let client = client.as_full().expect("only full clients are tested");
-		let inherent_data_providers = InherentDataProviders::new();
 
 		let config = Config::get_or_compute(&*client).expect("config available");
+		let inherent_data_providers = InherentDataProviders::new();
+		register_babe_inherent_data_provider(&inherent_data_providers, config.slot_duration)
+			.expect("registers the babe inherent data provider");
 		let (block_import, link) = crate::block_import(
 			config,
 			client.clone(),
@@ -296,14 +301,20 @@ impl TestNetFactory for BabeTestNet {
 			inner: BabeVerifier {
 				client: client.clone(),
 				inherent_data_providers: data.inherent_data_providers.clone(),
+				create_inherent_data_providers: InherentDataProviders::new(),
 				config: data.link.config.clone(),
 				epoch_changes: data.link.epoch_changes.clone(),
 				time_source: data.link.time_source.clone(),
+				pre_verified_seals: Default::default(),
 			},
 			mutator: MUTATOR.with(|m| m.borrow().clone()),
 		}
 	}
 
+	fn make_pre_verifier(&self, verifier: &TestVerifier) -> Option<SharedPreVerifier<TestBlock>> {
+		Some(Arc::new(verifier.inner.pre_verifier()))
+	}
+
 	fn peer(&mut self, i: usize) -> &mut Peer<Self::PeerData> {
 		trace!(target: "babe", "Retrieving a peer");
 		&mut self.peers[i]
@@ -403,8 +414,10 @@ fn run_one_test(
 			client,
 			env: environ,
 			sync_oracle: DummyOracle,
-			inherent_data_providers: data.inherent_data_providers.clone(),
+			create_inherent_data_providers: InherentDataProviders::new(),
 			force_authoring: false,
+			backoff_authoring_blocks: None,
+			block_proposal_slot_portion: SlotProportion::new(0.5),
 			babe_link: data.link.clone(),
 			keystore,
 			can_author_with: sp_consensus::AlwaysCanAuthor,
@@ -497,6 +510,7 @@ fn can_author_block() {
 	let keystore = sc_keystore::Store::open(keystore_path.path(), None).expect("Creates keystore");
 	let pair = keystore.write().insert_ephemeral_from_seed::<AuthorityPair>("//Alice")
 		.expect("Generates authority pair");
+	let keystore: BareCryptoStorePtr = keystore;
 
 	let mut i = 0;
 	let epoch = Epoch {
@@ -513,7 +527,7 @@ fn can_author_block() {
 		c: (3, 10),
 		genesis_authorities: Vec::new(),
 		randomness: [0; 32],
-		secondary_slots: true,
+		allowed_slots: AllowedSlots::PrimaryAndSecondaryPlainSlots,
 	};
 
 	// with secondary slots enabled it should never be empty
@@ -524,7 +538,7 @@ fn can_author_block() {
 
 	// otherwise with only vrf-based primary slots we might need to try a couple
 	// of times.
-	config.secondary_slots = false;
+	config.allowed_slots = AllowedSlots::PrimarySlots;
 	loop {
 		match claim_slot(i, &epoch, &config, &keystore) {
 			None => i += 1,
@@ -536,6 +550,60 @@ fn can_author_block() {
 	}
 }
 
+#[test]
+fn can_author_secondary_vrf_block() {
+	let _ = env_logger::try_init();
+	let keystore_path = tempfile::tempdir().expect("Creates keystore path");
+	let keystore = sc_keystore::Store::open(keystore_path.path(), None).expect("Creates keystore");
+	let pair = keystore.write().insert_ephemeral_from_seed::<AuthorityPair>("//Alice")
+		.expect("Generates authority pair");
+	let keystore: BareCryptoStorePtr = keystore;
+
+	let epoch = Epoch {
+		start_slot: 0,
+		authorities: vec![(pair.public(), 1)],
+		randomness: [0; 32],
+		epoch_index: 1,
+		duration: 100,
+	};
+
+	let config = crate::BabeConfiguration {
+		slot_duration: 1000,
+		epoch_length: 100,
+		c: (3, 10),
+		genesis_authorities: Vec::new(),
+		randomness: [0; 32],
+		allowed_slots: AllowedSlots::PrimaryAndSecondaryVRFSlots,
+	};
+
+	// slots that aren't claimed as primary slots are claimed as secondary VRF slots.
+	let (slot_number, vrf_output, vrf_proof) = (0..)
+		.filter_map(|slot_number| match claim_slot(slot_number, &epoch, &config, &keystore) {
+			Some((PreDigest::SecondaryVRF { vrf_output, vrf_proof, .. }, _)) =>
+				Some((slot_number, vrf_output, vrf_proof)),
+			Some((PreDigest::Primary { .. }, _)) => None,
+			other => panic!("Unexpected claim {:?}", other.map(|claim| claim.0)),
+		})
+		.next()
+		.unwrap();
+
+	let transcript = authorship::make_transcript(&epoch.randomness, slot_number, epoch.epoch_index);
+	let public = schnorrkel::PublicKey::from_bytes(pair.public().as_ref()).unwrap();
+	assert!(public.vrf_verify(transcript, &vrf_output, &vrf_proof).is_ok());
+
+	// the VRF output survives the encoding of the pre-digest.
+	let pre_digest = PreDigest::SecondaryVRF {
+		vrf_output: vrf_output.clone(),
+		vrf_proof: vrf_proof.clone(),
+		authority_index: 0,
+		slot_number,
+	};
+	match PreDigest::decode(&mut &pre_digest.encode()[..]).unwrap() {
+		PreDigest::SecondaryVRF { vrf_output: decoded, .. } => assert_eq!(decoded, vrf_output),
+		other => panic!("Unexpected pre-digest {:?}", other),
+	}
+}
+
 // Propose and import a new BABE block on top of the given parent.
 fn propose_and_import_block<Transaction>(
 	parent: &TestHeader,
@@ -792,3 +860,85 @@ fn verify_slots_are_strictly_increasing() {
 		&mut block_import,
 	);
 }
+
+#[test]
+fn pre_verifier_checks_seals_against_the_epoch_of_the_best_block() {
+	let mut net = BabeTestNet::new(1);
+
+	let peer = net.peer(0);
+	let data = peer.data.as_ref().expect("babe link set up during initialization");
+
+	let client = peer.client().as_full().expect("Only full clients are used in tests").clone();
+	let mut block_import = data.block_import.lock().take().expect("import set up during init");
+
+	let mut proposer_factory = DummyFactory {
+		client: client.clone(),
+		config: data.link.config.clone(),
+		epoch_changes: data.link.epoch_changes.clone(),
+		mutator: Arc::new(|_, _| ()),
+	};
+
+	let genesis_header = client.header(&BlockId::Number(0)).unwrap().unwrap();
+	let b1 = propose_and_import_block(
+		&genesis_header,
+		Some(999),
+		&mut proposer_factory,
+		&mut block_import,
+	);
+	let b1 = client.header(&BlockId::Hash(b1)).unwrap().unwrap();
+
+	let verifier = BabeVerifier {
+		client: client.clone(),
+		inherent_data_providers: data.inherent_data_providers.clone(),
+		create_inherent_data_providers: InherentDataProviders::new(),
+		config: data.link.config.clone(),
+		epoch_changes: data.link.epoch_changes.clone(),
+		time_source: data.link.time_source.clone(),
+		pre_verified_seals: Default::default(),
+	};
+	let pre_verifier = verifier.pre_verifier();
+
+	// propose a block for the next slot on behalf of its secondary slot author.
+	let epoch = data.link.config.genesis_epoch(999);
+	let slot_number = 1000;
+	let author = authorship::secondary_slot_author(slot_number, &epoch.authorities, epoch.randomness)
+		.unwrap();
+	let authority_index = epoch.authorities.iter().position(|(id, _)| id == author).unwrap();
+	let author_pair = ["//Alice", "//Bob", "//Charlie"].iter()
+		.map(|seed| AuthorityPair::from_string(seed, None).unwrap())
+		.find(|pair| &pair.public() == author)
+		.unwrap();
+
+	let mut proposer = futures::executor::block_on(proposer_factory.init(&b1)).unwrap();
+	let pre_digest = sp_runtime::generic::Digest {
+		logs: vec![
+			Item::babe_pre_digest(PreDigest::Secondary {
+				authority_index: authority_index as u32,
+				slot_number,
+			}),
+		],
+	};
+	let unsealed_header = futures::executor::block_on(proposer.propose_with(pre_digest))
+		.unwrap()
+		.block
+		.header;
+	let seal_with = |pair: &AuthorityPair| {
+		let mut header = unsealed_header.clone();
+		let signature = pair.sign(header.hash().as_ref());
+		header.digest_mut().push(Item::babe_seal(signature));
+		header
+	};
+
+	// the seal of the slot author is recorded along with the epoch it was checked against.
+	let header = seal_with(&author_pair);
+	pre_verifier.pre_verify(&header).unwrap();
+	assert_eq!(verifier.pre_verified_seals.lock().get(&header.hash()), Some(&epoch));
+
+	// any other seal is left to the verifier.
+	let header = seal_with(&AuthorityPair::from_seed(&[42; 32]));
+	pre_verifier.pre_verify(&header).unwrap();
+	assert!(verifier.pre_verified_seals.lock().get(&header.hash()).is_none());
+
+	// and headers without a seal are rejected.
+	assert!(pre_verifier.pre_verify(&unsealed_header).is_err());
+}
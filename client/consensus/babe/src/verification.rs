@@ -39,6 +39,9 @@ pub(super) struct VerificationParams<'a, B: 'a + BlockT> {
 	pub(super) epoch: &'a Epoch,
 	/// genesis config of this BABE chain.
 	pub(super) config: &'a super::Config,
+	/// whether the seal and the VRF proof of the header were already checked against the same
+	/// epoch by the `BabePreVerifier`, in which case they aren't checked again.
+	pub(super) seal_verified: bool,
 }
 
 /// Check a header has been signed by the right key. If the slot is too far in
@@ -63,6 +66,7 @@ pub(super) fn check_header<B: BlockT + Sized>(
 		slot_now,
 		epoch,
 		config,
+		seal_verified,
 	} = params;
 
 	let authorities = &epoch.authorities;
@@ -92,34 +96,52 @@ pub(super) fn check_header<B: BlockT + Sized>(
 		None => return Err(babe_err(Error::SlotAuthorNotFound)),
 	};
 
-	match &pre_digest {
-		PreDigest::Primary { vrf_output, vrf_proof, authority_index, slot_number } => {
-			debug!(target: "babe", "Verifying Primary block");
-
-			let digest = (vrf_output, vrf_proof, *authority_index, *slot_number);
-
-			check_primary_header::<B>(
-				pre_hash,
-				digest,
-				sig,
-				&epoch,
-				config.c,
-			)?;
-		},
-		PreDigest::Secondary { authority_index, slot_number } if config.secondary_slots => {
-			debug!(target: "babe", "Verifying Secondary block");
-
-			let digest = (*authority_index, *slot_number);
-
-			check_secondary_header::<B>(
-				pre_hash,
-				digest,
-				sig,
-				&epoch,
-			)?;
-		},
-		_ => {
-			return Err(babe_err(Error::SecondarySlotAssignmentsDisabled));
+	if !seal_verified {
+		match &pre_digest {
+			PreDigest::Primary { vrf_output, vrf_proof, authority_index, slot_number } => {
+				debug!(target: "babe", "Verifying Primary block");
+
+				let digest = (vrf_output, vrf_proof, *authority_index, *slot_number);
+
+				check_primary_header::<B>(
+					pre_hash,
+					digest,
+					sig,
+					&epoch,
+					config.c,
+				)?;
+			},
+			PreDigest::Secondary { authority_index, slot_number }
+				if config.allowed_slots.is_secondary_plain_slots_allowed() =>
+			{
+				debug!(target: "babe", "Verifying Secondary plain block");
+
+				let digest = (*authority_index, *slot_number);
+
+				check_secondary_plain_header::<B>(
+					pre_hash,
+					digest,
+					sig,
+					&epoch,
+				)?;
+			},
+			PreDigest::SecondaryVRF { vrf_output, vrf_proof, authority_index, slot_number }
+				if config.allowed_slots.is_secondary_vrf_slots_allowed() =>
+			{
+				debug!(target: "babe", "Verifying Secondary VRF block");
+
+				let digest = (vrf_output, vrf_proof, *authority_index, *slot_number);
+
+				check_secondary_vrf_header::<B>(
+					pre_hash,
+					digest,
+					sig,
+					&epoch,
+				)?;
+			},
+			_ => {
+				return Err(babe_err(Error::SecondarySlotAssignmentsDisabled));
+			}
 		}
 	}
 
@@ -187,14 +209,59 @@ fn check_primary_header<B: BlockT + Sized>(
 /// properly signed by the expected authority, which we have a deterministic way
 /// of computing. Additionally, the weight of this block must stay the same
 /// compared to its parent since it is a secondary block.
-fn check_secondary_header<B: BlockT>(
+fn check_secondary_plain_header<B: BlockT>(
 	pre_hash: B::Hash,
 	pre_digest: (AuthorityIndex, SlotNumber),
 	signature: AuthoritySignature,
 	epoch: &Epoch,
 ) -> Result<(), Error<B>> {
 	let (authority_index, slot_number) = pre_digest;
+	let author = check_secondary_author::<B>(authority_index, slot_number, epoch)?;
+
+	if AuthorityPair::verify(&signature, pre_hash.as_ref(), author) {
+		Ok(())
+	} else {
+		Err(Error::BadSignature(pre_hash))
+	}
+}
+
+/// Check a secondary VRF slot proposal header. On top of the checks of plain secondary
+/// headers, we validate that the contained VRF proof is valid for the slot.
+fn check_secondary_vrf_header<B: BlockT>(
+	pre_hash: B::Hash,
+	pre_digest: (&VRFOutput, &VRFProof, AuthorityIndex, SlotNumber),
+	signature: AuthoritySignature,
+	epoch: &Epoch,
+) -> Result<(), Error<B>> {
+	let (vrf_output, vrf_proof, authority_index, slot_number) = pre_digest;
+	let author = check_secondary_author::<B>(authority_index, slot_number, epoch)?;
+
+	if AuthorityPair::verify(&signature, pre_hash.as_ref(), author) {
+		let transcript = make_transcript(
+			&epoch.randomness,
+			slot_number,
+			epoch.epoch_index,
+		);
+
+		schnorrkel::PublicKey::from_bytes(author.as_slice()).and_then(|p| {
+			p.vrf_verify(transcript, vrf_output, vrf_proof)
+		}).map_err(|s| {
+			babe_err(Error::VRFVerificationFailed(s))
+		})?;
+
+		Ok(())
+	} else {
+		Err(Error::BadSignature(pre_hash))
+	}
+}
 
+/// Check that the authority with the given index is the one we expect to claim the given
+/// secondary slot, returning its id.
+fn check_secondary_author<B: BlockT>(
+	authority_index: AuthorityIndex,
+	slot_number: SlotNumber,
+	epoch: &Epoch,
+) -> Result<&AuthorityId, Error<B>> {
 	// check the signature is valid under the expected authority and
 	// chain state.
 	let expected_author = secondary_slot_author(
@@ -209,9 +276,5 @@ fn check_secondary_header<B: BlockT>(
 		return Err(Error::InvalidAuthor(expected_author.clone(), author.clone()));
 	}
 
-	if AuthorityPair::verify(&signature, pre_hash.as_ref(), author) {
-		Ok(())
-	} else {
-		Err(Error::BadSignature(pre_hash))
-	}
+	Ok(author)
 }
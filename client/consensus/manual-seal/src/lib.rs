@@ -28,8 +28,9 @@ use sp_consensus::{
 		BoxBlockImport,
 	},
 };
-use sp_inherents::InherentDataProviders;
+use sp_inherents::CreateInherentDataProviders;
 use sp_runtime::{traits::Block as BlockT, Justification};
+use prometheus_endpoint::Registry;
 use sc_client_api::backend::Backend as ClientBackend;
 use futures::prelude::*;
 use sc_transaction_pool::txpool;
@@ -100,25 +101,34 @@ impl<B: BlockT> Verifier<B> for ManualSealVerifier {
 }
 
 /// Instantiate the import queue for the manual seal consensus engine.
-pub fn import_queue<B: BlockT>(block_import: BoxBlockImport<B, ()>) -> BasicQueue<B, ()>
+pub fn import_queue<B: BlockT, Transaction: Send + 'static>(
+	block_import: BoxBlockImport<B, Transaction>,
+	prometheus_registry: Option<&Registry>,
+) -> BasicQueue<B, Transaction>
 {
 	BasicQueue::new(
 		ManualSealVerifier,
+		None,
 		block_import,
 		None,
 		None,
+		prometheus_registry,
 	)
 }
 
 /// Creates the background authorship task for the manual seal engine.
-pub async fn run_manual_seal<B, CB, E, A, C, S, T>(
+///
+/// The inherent data providers of each block are created by `create_inherent_data_providers`
+/// from its parent hash. `InherentDataProviders` can be given to provide the same data to all
+/// the blocks.
+pub async fn run_manual_seal<B, CB, E, A, C, S, T, CIDP>(
 	mut block_import: BoxBlockImport<B, T>,
 	mut env: E,
 	backend: Arc<CB>,
 	pool: Arc<txpool::Pool<A>>,
 	mut seal_block_channel: S,
 	select_chain: C,
-	inherent_data_providers: InherentDataProviders,
+	create_inherent_data_providers: CIDP,
 )
 	where
 		B: BlockT + 'static,
@@ -129,6 +139,7 @@ pub async fn run_manual_seal<B, CB, E, A, C, S, T>(
 		A: txpool::ChainApi<Block=B, Hash=<B as BlockT>::Hash> + 'static,
 		S: Stream<Item=EngineCommand<<B as BlockT>::Hash>> + Unpin + 'static,
 		C: SelectChain<B> + 'static,
+		CIDP: CreateInherentDataProviders<<B as BlockT>::Hash, ()>,
 {
 	while let Some(command) = seal_block_channel.next().await {
 		match command {
@@ -147,7 +158,7 @@ pub async fn run_manual_seal<B, CB, E, A, C, S, T>(
 						env: &mut env,
 						select_chain: &select_chain,
 						block_import: &mut block_import,
-						inherent_data_provider: &inherent_data_providers,
+						create_inherent_data_providers: &create_inherent_data_providers,
 						pool: pool.clone(),
 						backend: backend.clone(),
 					}
@@ -170,13 +181,13 @@ pub async fn run_manual_seal<B, CB, E, A, C, S, T>(
 /// runs the background authorship task for the instant seal engine.
 /// instant-seal creates a new block for every transaction imported into
 /// the transaction pool.
-pub async fn run_instant_seal<B, CB, E, A, C, T>(
+pub async fn run_instant_seal<B, CB, E, A, C, T, CIDP>(
 	block_import: BoxBlockImport<B, T>,
 	env: E,
 	backend: Arc<CB>,
 	pool: Arc<txpool::Pool<A>>,
 	select_chain: C,
-	inherent_data_providers: InherentDataProviders,
+	create_inherent_data_providers: CIDP,
 )
 	where
 		A: txpool::ChainApi<Block=B, Hash=<B as BlockT>::Hash> + 'static,
@@ -185,7 +196,8 @@ pub async fn run_instant_seal<B, CB, E, A, C, T>(
 		E: Environment<B> + 'static,
 		E::Error: std::fmt::Display,
 		<E::Proposer as Proposer<B>>::Error: std::fmt::Display,
-		C: SelectChain<B> + 'static
+		C: SelectChain<B> + 'static,
+		CIDP: CreateInherentDataProviders<<B as BlockT>::Hash, ()>,
 {
 	// instant-seal creates blocks as soon as transactions are imported
 	// into the transaction pool.
@@ -206,7 +218,7 @@ pub async fn run_instant_seal<B, CB, E, A, C, T>(
 		pool,
 		seal_block_channel,
 		select_chain,
-		inherent_data_providers,
+		create_inherent_data_providers,
 	).await
 }
 
@@ -224,7 +236,7 @@ mod tests {
 		txpool::Options,
 	};
 	use substrate_test_runtime_transaction_pool::{TestApi, uxt};
-	use sp_transaction_pool::{TransactionPool, MaintainedTransactionPool};
+	use sp_transaction_pool::{TransactionPool, MaintainedTransactionPool, TransactionSource};
 	use sp_runtime::generic::BlockId;
 	use sp_blockchain::HeaderBackend;
 	use sp_consensus::ImportedAux;
@@ -232,6 +244,8 @@ mod tests {
 	use sp_inherents::InherentDataProviders;
 	use sc_basic_authorship::ProposerFactory;
 
+	const SOURCE: TransactionSource = TransactionSource::External;
+
 	fn api() -> Arc<TestApi> {
 		Arc::new(TestApi::empty())
 	}
@@ -243,10 +257,11 @@ mod tests {
 		let client = Arc::new(builder.build());
 		let select_chain = LongestChain::new(backend.clone());
 		let inherent_data_providers = InherentDataProviders::new();
-		let pool = Arc::new(BasicPool::new(Options::default(), api()).0);
+		let pool = Arc::new(BasicPool::new(Options::default(), api(), None).0);
 		let env = ProposerFactory::new(
 			client.clone(),
-			pool.clone()
+			pool.clone(),
+			None,
 		);
 		// this test checks that blocks are created as soon as transactions are imported into the pool.
 		let (sender, receiver) = futures::channel::oneshot::channel();
@@ -278,7 +293,7 @@ mod tests {
 			rt.block_on(future);
 		});
 		// submit a transaction to pool.
-		let result = pool.submit_one(&BlockId::Number(0), uxt(Alice, 0)).await;
+		let result = pool.submit_one(&BlockId::Number(0), SOURCE, uxt(Alice, 0)).await;
 		// assert that it was successfully imported
 		assert!(result.is_ok());
 		// assert that the background task returns ok
@@ -308,10 +323,11 @@ mod tests {
 		let client = Arc::new(builder.build());
 		let select_chain = LongestChain::new(backend.clone());
 		let inherent_data_providers = InherentDataProviders::new();
-		let pool = Arc::new(BasicPool::new(Options::default(), api()).0);
+		let pool = Arc::new(BasicPool::new(Options::default(), api(), None).0);
 		let env = ProposerFactory::new(
 			client.clone(),
-			pool.clone()
+			pool.clone(),
+			None,
 		);
 		// this test checks that blocks are created as soon as an engine command is sent over the stream.
 		let (mut sink, stream) = futures::channel::mpsc::channel(1024);
@@ -330,7 +346,7 @@ mod tests {
 			rt.block_on(future);
 		});
 		// submit a transaction to pool.
-		let result = pool.submit_one(&BlockId::Number(0), uxt(Alice, 0)).await;
+		let result = pool.submit_one(&BlockId::Number(0), SOURCE, uxt(Alice, 0)).await;
 		// assert that it was successfully imported
 		assert!(result.is_ok());
 		let (tx, rx) = futures::channel::oneshot::channel();
@@ -377,10 +393,11 @@ mod tests {
 		let select_chain = LongestChain::new(backend.clone());
 		let inherent_data_providers = InherentDataProviders::new();
 		let pool_api = api();
-		let pool = Arc::new(BasicPool::new(Options::default(), pool_api.clone()).0);
+		let pool = Arc::new(BasicPool::new(Options::default(), pool_api.clone(), None).0);
 		let env = ProposerFactory::new(
 			client.clone(),
 			pool.clone(),
+			None,
 		);
 		// this test checks that blocks are created as soon as an engine command is sent over the stream.
 		let (mut sink, stream) = futures::channel::mpsc::channel(1024);
@@ -399,7 +416,7 @@ mod tests {
 			rt.block_on(future);
 		});
 		// submit a transaction to pool.
-		let result = pool.submit_one(&BlockId::Number(0), uxt(Alice, 0)).await;
+		let result = pool.submit_one(&BlockId::Number(0), SOURCE, uxt(Alice, 0)).await;
 		// assert that it was successfully imported
 		assert!(result.is_ok());
 
@@ -430,12 +447,13 @@ mod tests {
 		);
 		// assert that there's a new block in the db.
 		assert!(backend.blockchain().header(BlockId::Number(0)).unwrap().is_some());
-		assert!(pool.submit_one(&BlockId::Number(1), uxt(Alice, 1)).await.is_ok());
+		assert!(pool.submit_one(&BlockId::Number(1), SOURCE, uxt(Alice, 1)).await.is_ok());
 
 		pool.maintain(sp_transaction_pool::ChainEvent::NewBlock {
 			id: BlockId::Number(1),
 			header: backend.blockchain().header(BlockId::Number(1)).expect("db error").expect("imported above"),
 			is_new_best: true,
+			is_major_syncing: false,
 			retracted: vec![],
 		}).await;
 
@@ -453,7 +471,7 @@ mod tests {
 		assert!(backend.blockchain().header(BlockId::Number(1)).unwrap().is_some());
 		pool_api.increment_nonce(Alice.into());
 
-		assert!(pool.submit_one(&BlockId::Number(2), uxt(Alice, 2)).await.is_ok());
+		assert!(pool.submit_one(&BlockId::Number(2), SOURCE, uxt(Alice, 2)).await.is_ok());
 		let (tx2, rx2) = futures::channel::oneshot::channel();
 		assert!(sink.send(EngineCommand::SealNewBlock {
 			parent_hash: Some(created_block.hash),
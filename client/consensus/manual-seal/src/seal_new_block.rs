@@ -36,13 +36,13 @@ use sp_blockchain::HeaderBackend;
 use sc_client_api::backend::Backend as ClientBackend;
 use std::collections::HashMap;
 use std::time::Duration;
-use sp_inherents::InherentDataProviders;
+use sp_inherents::{CreateInherentDataProviders, InherentDataProvider};
 
 /// max duration for creating a proposal in secs
 const MAX_PROPOSAL_DURATION: u64 = 10;
 
 /// params for sealing a new block
-pub struct SealBlockParams<'a, B: BlockT, C, CB, E, T, P: txpool::ChainApi> {
+pub struct SealBlockParams<'a, B: BlockT, C, CB, E, T, P: txpool::ChainApi, CIDP> {
 	/// if true, empty blocks(without extrinsics) will be created.
 	/// otherwise, will return Error::EmptyTransactionPool.
 	pub create_empty: bool,
@@ -62,12 +62,12 @@ pub struct SealBlockParams<'a, B: BlockT, C, CB, E, T, P: txpool::ChainApi> {
 	pub select_chain: &'a C,
 	/// block import object
 	pub block_import: &'a mut BoxBlockImport<B, T>,
-	/// inherent data provider
-	pub inherent_data_provider: &'a InherentDataProviders,
+	/// creates the inherent data providers of the new block from its parent hash
+	pub create_inherent_data_providers: &'a CIDP,
 }
 
 /// seals a new block with the given params
-pub async fn seal_new_block<B, SC, CB, E, T, P>(
+pub async fn seal_new_block<B, SC, CB, E, T, P, CIDP>(
 	SealBlockParams {
 		create_empty,
 		finalize,
@@ -77,10 +77,10 @@ pub async fn seal_new_block<B, SC, CB, E, T, P>(
 		select_chain,
 		block_import,
 		env,
-		inherent_data_provider,
+		create_inherent_data_providers,
 		mut sender,
 		..
-	}: SealBlockParams<'_, B, SC, CB, E, T, P>
+	}: SealBlockParams<'_, B, SC, CB, E, T, P, CIDP>
 )
 	where
 		B: BlockT,
@@ -90,6 +90,7 @@ pub async fn seal_new_block<B, SC, CB, E, T, P>(
 		<E::Proposer as Proposer<B>>::Error: std::fmt::Display,
 		P: txpool::ChainApi<Block=B, Hash=<B as BlockT>::Hash>,
 		SC: SelectChain<B>,
+		CIDP: CreateInherentDataProviders<<B as BlockT>::Hash, ()>,
 {
 	let future = async {
 		if pool.validated_pool().status().ready == 0 && !create_empty {
@@ -111,7 +112,10 @@ pub async fn seal_new_block<B, SC, CB, E, T, P>(
 
 		let mut proposer = env.init(&header)
 			.map_err(|err| Error::StringError(format!("{}", err))).await?;
-		let id = inherent_data_provider.create_inherent_data()?;
+		let inherent_data_providers = create_inherent_data_providers
+			.create_inherent_data_providers(<B as BlockT>::Header::hash(&header), ())
+			.await?;
+		let id = inherent_data_providers.create_inherent_data()?;
 		let inherents_len = id.len();
 		let proposal = proposer.propose(id, Default::default(), Duration::from_secs(MAX_PROPOSAL_DURATION), false.into())
 			.map_err(|err| Error::StringError(format!("{}", err))).await?;
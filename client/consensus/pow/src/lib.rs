@@ -32,6 +32,7 @@
 use std::sync::Arc;
 use std::any::Any;
 use std::borrow::Cow;
+use prometheus_endpoint::Registry;
 use std::thread;
 use std::collections::HashMap;
 use std::marker::PhantomData;
@@ -43,13 +44,15 @@ use sp_runtime::generic::{BlockId, Digest, DigestItem};
 use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
 use sp_api::ProvideRuntimeApi;
 use sp_consensus_pow::{Seal, TotalDifficulty, POW_ENGINE_ID};
-use sp_inherents::{InherentDataProviders, InherentData};
+use sp_inherents::{
+	InherentDataProviders, InherentData, InherentDataProvider, CreateInherentDataProviders,
+};
 use sp_consensus::{
 	BlockImportParams, BlockOrigin, ForkChoiceStrategy, SyncOracle, Environment, Proposer,
 	SelectChain, Error as ConsensusError, CanAuthorWith, RecordProof, BlockImport,
 	BlockCheckParams, ImportResult,
 };
-use sp_consensus::import_queue::{BoxBlockImport, BasicQueue, Verifier};
+use sp_consensus::import_queue::{BoxBlockImport, BasicQueue, Verifier, PreVerifier};
 use codec::{Encode, Decode};
 use sc_client_api;
 use log::*;
@@ -181,16 +184,19 @@ pub trait PowAlgorithm<B: BlockT> {
 }
 
 /// A block importer for PoW.
-pub struct PowBlockImport<B: BlockT, I, C, S, Algorithm> {
+pub struct PowBlockImport<B: BlockT, I, C, S, Algorithm, CIDP> {
 	algorithm: Algorithm,
 	inner: I,
 	select_chain: Option<S>,
 	client: Arc<C>,
-	inherent_data_providers: sp_inherents::InherentDataProviders,
+	inherent_data_providers: InherentDataProviders,
+	create_inherent_data_providers: CIDP,
 	check_inherents_after: <<B as BlockT>::Header as HeaderT>::Number,
 }
 
-impl<B: BlockT, I: Clone, C, S: Clone, Algorithm: Clone> Clone for PowBlockImport<B, I, C, S, Algorithm> {
+impl<B: BlockT, I: Clone, C, S: Clone, Algorithm: Clone, CIDP: Clone> Clone
+	for PowBlockImport<B, I, C, S, Algorithm, CIDP>
+{
 	fn clone(&self) -> Self {
 		Self {
 			algorithm: self.algorithm.clone(),
@@ -198,12 +204,13 @@ impl<B: BlockT, I: Clone, C, S: Clone, Algorithm: Clone> Clone for PowBlockImpor
 			select_chain: self.select_chain.clone(),
 			client: self.client.clone(),
 			inherent_data_providers: self.inherent_data_providers.clone(),
+			create_inherent_data_providers: self.create_inherent_data_providers.clone(),
 			check_inherents_after: self.check_inherents_after.clone(),
 		}
 	}
 }
 
-impl<B, I, C, S, Algorithm> PowBlockImport<B, I, C, S, Algorithm> where
+impl<B, I, C, S, Algorithm, CIDP> PowBlockImport<B, I, C, S, Algorithm, CIDP> where
 	B: BlockT,
 	I: BlockImport<B, Transaction = sp_api::TransactionFor<C, B>> + Send + Sync,
 	I::Error: Into<ConsensusError>,
@@ -212,16 +219,25 @@ impl<B, I, C, S, Algorithm> PowBlockImport<B, I, C, S, Algorithm> where
 	Algorithm: PowAlgorithm<B>,
 {
 	/// Create a new block import suitable to be used in PoW
+	///
+	/// The providers of the inherent data of each block are created by
+	/// `create_inherent_data_providers` from its parent hash, besides the timestamp that PoW
+	/// provides itself.
 	pub fn new(
 		inner: I,
 		client: Arc<C>,
 		algorithm: Algorithm,
 		check_inherents_after: <<B as BlockT>::Header as HeaderT>::Number,
 		select_chain: Option<S>,
-		inherent_data_providers: sp_inherents::InherentDataProviders,
+		create_inherent_data_providers: CIDP,
 	) -> Self {
+		let inherent_data_providers = InherentDataProviders::new();
+		if let Err(_) = register_pow_inherent_data_provider(&inherent_data_providers) {
+			warn!("Registering inherent data provider for timestamp failed");
+		}
+
 		Self { inner, client, algorithm, check_inherents_after,
-			   select_chain, inherent_data_providers }
+			   select_chain, inherent_data_providers, create_inherent_data_providers }
 	}
 
 	fn check_inherents(
@@ -229,6 +245,7 @@ impl<B, I, C, S, Algorithm> PowBlockImport<B, I, C, S, Algorithm> where
 		block: B,
 		block_id: BlockId<B>,
 		inherent_data: InherentData,
+		inherent_data_providers: &impl InherentDataProvider,
 		timestamp_now: u64,
 	) -> Result<(), Error<B>> {
 		const MAX_TIMESTAMP_DRIFT_SECS: u64 = 60;
@@ -256,7 +273,7 @@ impl<B, I, C, S, Algorithm> PowBlockImport<B, I, C, S, Algorithm> where
 					},
 					Some(TIError::Other(e)) => Err(Error::Runtime(e)),
 					None => Err(Error::CheckInherents(
-						self.inherent_data_providers.error_to_string(&i, &e)
+						inherent_data_providers.describe_error(&i, &e)
 					)),
 				})
 		} else {
@@ -265,7 +282,7 @@ impl<B, I, C, S, Algorithm> PowBlockImport<B, I, C, S, Algorithm> where
 	}
 }
 
-impl<B, I, C, S, Algorithm> BlockImport<B> for PowBlockImport<B, I, C, S, Algorithm> where
+impl<B, I, C, S, Algorithm, CIDP> BlockImport<B> for PowBlockImport<B, I, C, S, Algorithm, CIDP> where
 	B: BlockT,
 	I: BlockImport<B, Transaction = sp_api::TransactionFor<C, B>> + Send + Sync,
 	I::Error: Into<ConsensusError>,
@@ -274,6 +291,7 @@ impl<B, I, C, S, Algorithm> BlockImport<B> for PowBlockImport<B, I, C, S, Algori
 	C::Api: BlockBuilderApi<B, Error = sp_blockchain::Error>,
 	Algorithm: PowAlgorithm<B>,
 	Algorithm::Difficulty: 'static,
+	CIDP: CreateInherentDataProviders<B::Hash, ()>,
 {
 	type Error = ConsensusError;
 	type Transaction = sp_api::TransactionFor<C, B>;
@@ -302,7 +320,14 @@ impl<B, I, C, S, Algorithm> BlockImport<B> for PowBlockImport<B, I, C, S, Algori
 		let mut aux = PowAux::read::<_, B>(self.client.as_ref(), &parent_hash)?;
 
 		if let Some(inner_body) = block.body.take() {
-			let inherent_data = self.inherent_data_providers
+			// the block import isn't asynchronous, so the providers are created in place.
+			let inherent_data_providers = (
+				self.inherent_data_providers.clone(),
+				futures::executor::block_on(
+					self.create_inherent_data_providers.create_inherent_data_providers(parent_hash, ()),
+				).map_err(|e| e.into_string())?,
+			);
+			let inherent_data = inherent_data_providers
 				.create_inherent_data().map_err(|e| e.into_string())?;
 			let timestamp_now = inherent_data.timestamp_inherent_data().map_err(|e| e.into_string())?;
 
@@ -312,6 +337,7 @@ impl<B, I, C, S, Algorithm> BlockImport<B> for PowBlockImport<B, I, C, S, Algori
 				check_block.clone(),
 				BlockId::Hash(parent_hash),
 				inherent_data,
+				&inherent_data_providers,
 				timestamp_now
 			)?;
 
@@ -366,6 +392,7 @@ impl<B, I, C, S, Algorithm> BlockImport<B> for PowBlockImport<B, I, C, S, Algori
 /// A verifier for PoW blocks.
 pub struct PowVerifier<B: BlockT, Algorithm> {
 	algorithm: Algorithm,
+	pre_verified: bool,
 	_marker: PhantomData<B>,
 }
 
@@ -373,7 +400,15 @@ impl<B: BlockT, Algorithm> PowVerifier<B, Algorithm> {
 	pub fn new(
 		algorithm: Algorithm,
 	) -> Self {
-		Self { algorithm, _marker: PhantomData }
+		Self { algorithm, pre_verified: false, _marker: PhantomData }
+	}
+
+	/// Create a verifier for the headers already checked by a `PowPreVerifier`, which skips
+	/// their preliminary verification.
+	pub fn new_pre_verified(
+		algorithm: Algorithm,
+	) -> Self {
+		Self { algorithm, pre_verified: true, _marker: PhantomData }
 	}
 
 	fn check_header(
@@ -395,16 +430,41 @@ impl<B: BlockT, Algorithm> PowVerifier<B, Algorithm> {
 			_ => return Err(Error::HeaderUnsealed(hash)),
 		};
 
-		let pre_hash = header.hash();
+		if !self.pre_verified {
+			let pre_hash = header.hash();
 
-		if !self.algorithm.preliminary_verify(&pre_hash, &inner_seal)?.unwrap_or(true) {
-			return Err(Error::FailedPreliminaryVerify);
+			if !self.algorithm.preliminary_verify(&pre_hash, &inner_seal)?.unwrap_or(true) {
+				return Err(Error::FailedPreliminaryVerify);
+			}
 		}
 
 		Ok((header, seal))
 	}
 }
 
+/// A pre-verifier for PoW blocks, running the preliminary verification of their seals
+/// concurrently for all the queued blocks.
+pub struct PowPreVerifier<B: BlockT, Algorithm> {
+	verifier: PowVerifier<B, Algorithm>,
+}
+
+impl<B: BlockT, Algorithm> PowPreVerifier<B, Algorithm> {
+	pub fn new(
+		algorithm: Algorithm,
+	) -> Self {
+		Self { verifier: PowVerifier::new(algorithm) }
+	}
+}
+
+impl<B: BlockT, Algorithm> PreVerifier<B> for PowPreVerifier<B, Algorithm> where
+	Algorithm: PowAlgorithm<B> + Send + Sync,
+{
+	fn pre_verify(&self, header: &B::Header) -> Result<(), String> {
+		self.verifier.check_header(header.clone())?;
+		Ok(())
+	}
+}
+
 impl<B: BlockT, Algorithm> Verifier<B> for PowVerifier<B, Algorithm> where
 	Algorithm: PowAlgorithm<B> + Send + Sync,
 	Algorithm::Difficulty: 'static,
@@ -455,10 +515,13 @@ pub fn register_pow_inherent_data_provider(
 pub type PowImportQueue<B, Transaction> = BasicQueue<B, Transaction>;
 
 /// Import queue for PoW engine.
+///
+/// The inherents are checked by the `PowBlockImport`, which should be the given block import or
+/// be wrapped by it.
 pub fn import_queue<B, Transaction, Algorithm>(
 	block_import: BoxBlockImport<B, Transaction>,
 	algorithm: Algorithm,
-	inherent_data_providers: InherentDataProviders,
+	prometheus_registry: Option<&Registry>,
 ) -> Result<
 	PowImportQueue<B, Transaction>,
 	sp_consensus::Error
@@ -467,15 +530,16 @@ pub fn import_queue<B, Transaction, Algorithm>(
 	Transaction: Send + Sync + 'static,
 	Algorithm: PowAlgorithm<B> + Clone + Send + Sync + 'static,
 {
-	register_pow_inherent_data_provider(&inherent_data_providers)?;
-
-	let verifier = PowVerifier::new(algorithm);
+	let verifier = PowVerifier::new_pre_verified(algorithm.clone());
+	let pre_verifier = PowPreVerifier::new(algorithm);
 
 	Ok(BasicQueue::new(
 		verifier,
+		Some(Arc::new(pre_verifier)),
 		block_import,
 		None,
-		None
+		None,
+		prometheus_registry,
 	))
 }
 
@@ -489,7 +553,11 @@ pub fn import_queue<B, Transaction, Algorithm>(
 /// information, or just be a graffiti. `round` is for number of rounds the
 /// CPU miner runs each time. This parameter should be tweaked so that each
 /// mining round is within sub-second time.
-pub fn start_mine<B: BlockT, C, Algorithm, E, SO, S, CAW>(
+///
+/// The providers of the inherent data of each block are created by
+/// `create_inherent_data_providers` from its parent hash, besides the timestamp that PoW
+/// provides itself.
+pub fn start_mine<B: BlockT, C, Algorithm, E, SO, S, CAW, CIDP>(
 	mut block_import: BoxBlockImport<B, sp_api::TransactionFor<C, B>>,
 	client: Arc<C>,
 	algorithm: Algorithm,
@@ -499,7 +567,7 @@ pub fn start_mine<B: BlockT, C, Algorithm, E, SO, S, CAW>(
 	mut sync_oracle: SO,
 	build_time: std::time::Duration,
 	select_chain: Option<S>,
-	inherent_data_providers: sp_inherents::InherentDataProviders,
+	create_inherent_data_providers: CIDP,
 	can_author_with: CAW,
 ) where
 	C: HeaderBackend<B> + AuxStore + ProvideRuntimeApi<B> + 'static,
@@ -510,7 +578,9 @@ pub fn start_mine<B: BlockT, C, Algorithm, E, SO, S, CAW>(
 	SO: SyncOracle + Send + Sync + 'static,
 	S: SelectChain<B> + 'static,
 	CAW: CanAuthorWith<B> + Send + 'static,
+	CIDP: CreateInherentDataProviders<B::Hash, ()> + 'static,
 {
+	let inherent_data_providers = InherentDataProviders::new();
 	if let Err(_) = register_pow_inherent_data_provider(&inherent_data_providers) {
 		warn!("Registering inherent data provider for timestamp failed");
 	}
@@ -528,6 +598,7 @@ pub fn start_mine<B: BlockT, C, Algorithm, E, SO, S, CAW>(
 				build_time.clone(),
 				select_chain.as_ref(),
 				&inherent_data_providers,
+				&create_inherent_data_providers,
 				&can_author_with,
 			) {
 				Ok(()) => (),
@@ -541,7 +612,7 @@ pub fn start_mine<B: BlockT, C, Algorithm, E, SO, S, CAW>(
 	});
 }
 
-fn mine_loop<B: BlockT, C, Algorithm, E, SO, S, CAW>(
+fn mine_loop<B: BlockT, C, Algorithm, E, SO, S, CAW, CIDP>(
 	block_import: &mut BoxBlockImport<B, sp_api::TransactionFor<C, B>>,
 	client: &C,
 	algorithm: &Algorithm,
@@ -551,7 +622,8 @@ fn mine_loop<B: BlockT, C, Algorithm, E, SO, S, CAW>(
 	sync_oracle: &mut SO,
 	build_time: std::time::Duration,
 	select_chain: Option<&S>,
-	inherent_data_providers: &sp_inherents::InherentDataProviders,
+	inherent_data_providers: &InherentDataProviders,
+	create_inherent_data_providers: &CIDP,
 	can_author_with: &CAW,
 ) -> Result<(), Error<B>> where
 	C: HeaderBackend<B> + AuxStore + ProvideRuntimeApi<B>,
@@ -564,6 +636,7 @@ fn mine_loop<B: BlockT, C, Algorithm, E, SO, S, CAW>(
 	S: SelectChain<B>,
 	sp_api::TransactionFor<C, B>: 'static,
 	CAW: CanAuthorWith<B>,
+	CIDP: CreateInherentDataProviders<B::Hash, ()>,
 {
 	'outer: loop {
 		if sync_oracle.is_major_syncing() {
@@ -602,6 +675,12 @@ fn mine_loop<B: BlockT, C, Algorithm, E, SO, S, CAW>(
 		let mut proposer = futures::executor::block_on(env.init(&best_header))
 			.map_err(|e| Error::Environment(format!("{:?}", e)))?;
 
+		let inherent_data_providers = (
+			inherent_data_providers.clone(),
+			futures::executor::block_on(
+				create_inherent_data_providers.create_inherent_data_providers(best_hash, ()),
+			).map_err(Error::CreateInherents)?,
+		);
 		let inherent_data = inherent_data_providers
 			.create_inherent_data().map_err(Error::CreateInherents)?;
 		let mut inherent_digest = Digest::default();
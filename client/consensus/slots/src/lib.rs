@@ -32,12 +32,16 @@ pub use aux_schema::{check_equivocation, MAX_SLOT_CAPACITY, PRUNING_BOUND};
 
 use codec::{Decode, Encode};
 use sp_consensus::{BlockImport, Proposer, SyncOracle, SelectChain, CanAuthorWith, SlotData, RecordProof};
-use futures::{prelude::*, future::{self, Either}};
+use futures::{prelude::*, future};
 use futures_timer::Delay;
-use sp_inherents::{InherentData, InherentDataProviders};
+use sp_inherents::{
+	InherentData, InherentDataProviders, InherentDataProvider, CreateInherentDataProviders,
+};
 use log::{debug, error, info, warn};
 use sp_runtime::generic::BlockId;
-use sp_runtime::traits::{Block as BlockT, Header, HashFor, NumberFor};
+use sp_runtime::traits::{
+	AtLeast32Bit, Block as BlockT, Header, HashFor, NumberFor, UniqueSaturatedInto,
+};
 use sp_api::{ProvideRuntimeApi, ApiRef};
 use std::{fmt::Debug, ops::Deref, pin::Pin, sync::Arc, time::{Instant, Duration}};
 use sc_telemetry::{telemetry, CONSENSUS_DEBUG, CONSENSUS_WARN, CONSENSUS_INFO};
@@ -113,6 +117,9 @@ pub trait SimpleSlotWorker<B: BlockT> {
 	) -> Vec<sp_runtime::DigestItem<B::Hash>>;
 
 	/// Returns a function which produces a `BlockImportParams`.
+	///
+	/// The function fails if the block can't be sealed, e.g. because the keystore holding the
+	/// key of the claim is unavailable.
 	fn block_import_params(&self) -> Box<
 		dyn Fn(
 			B::Header,
@@ -121,9 +128,9 @@ pub trait SimpleSlotWorker<B: BlockT> {
 			StorageChanges<<Self::BlockImport as BlockImport<B>>::Transaction, B>,
 			Self::Claim,
 			Self::EpochData,
-		) -> sp_consensus::BlockImportParams<
-			B,
-			<Self::BlockImport as BlockImport<B>>::Transaction
+		) -> Result<
+			sp_consensus::BlockImportParams<B, <Self::BlockImport as BlockImport<B>>::Transaction>,
+			sp_consensus::Error,
 		>
 		+ Send
 	>;
@@ -137,6 +144,13 @@ pub trait SimpleSlotWorker<B: BlockT> {
 	/// Returns a `Proposer` to author on top of the given block.
 	fn proposer(&mut self, block: &B::Header) -> Self::CreateProposer;
 
+	/// Whether authoring on top of `chain_head` at the given slot should be skipped, e.g.
+	/// because the chain head is too far ahead of the last finalized block. Defaults to never
+	/// backing off.
+	fn should_backoff(&self, _slot_number: u64, _chain_head: &B::Header) -> bool {
+		false
+	}
+
 	/// Remaining duration of the slot.
 	fn slot_remaining_duration(&self, slot_info: &SlotInfo) -> Duration {
 		let now = Instant::now();
@@ -209,6 +223,17 @@ pub trait SimpleSlotWorker<B: BlockT> {
 			return Box::pin(future::ready(Ok(())));
 		}
 
+		if self.should_backoff(slot_number, &chain_head) {
+			telemetry!(
+				CONSENSUS_DEBUG;
+				"slots.backing_off_authoring";
+				"slot" => slot_number,
+				"chain_head" => ?chain_head.hash(),
+			);
+
+			return Box::pin(future::ready(Ok(())));
+		}
+
 		let claim = match self.claim_slot(&chain_head, slot_number, &epoch_data) {
 			None => return Box::pin(future::ready(Ok(()))),
 			Some(claim) => claim,
@@ -235,8 +260,10 @@ pub trait SimpleSlotWorker<B: BlockT> {
 			err
 		});
 
-		let slot_remaining_duration = self.slot_remaining_duration(&slot_info);
 		let proposing_remaining_duration = self.proposing_remaining_duration(&chain_head, &slot_info);
+		// the proposer gets the same time budget as the one after which the proposal is discarded.
+		let proposer_max_duration = proposing_remaining_duration
+			.unwrap_or_else(|| self.slot_remaining_duration(&slot_info));
 		let logs = self.pre_digest_data(slot_number, &claim);
 
 		// deadline our production to approx. the end of the slot
@@ -245,7 +272,7 @@ pub trait SimpleSlotWorker<B: BlockT> {
 			sp_runtime::generic::Digest {
 				logs,
 			},
-			slot_remaining_duration,
+			proposer_max_duration,
 			RecordProof::No,
 		).map_err(|e| sp_consensus::Error::ClientImport(format!("{:?}", e))));
 
@@ -279,14 +306,29 @@ pub trait SimpleSlotWorker<B: BlockT> {
 			let header_hash = header.hash();
 			let parent_hash = *header.parent_hash();
 
-			let block_import_params = block_import_params_maker(
+			let block_import_params = match block_import_params_maker(
 				header,
 				&header_hash,
 				body,
 				proposal.storage_changes,
 				claim,
 				epoch_data,
-			);
+			) {
+				Ok(params) => params,
+				Err(err) => {
+					warn!(
+						target: logging_target,
+						"Unable to seal block {:?}: {:?}",
+						header_hash,
+						err,
+					);
+
+					telemetry!(CONSENSUS_WARN; "slots.unable_to_seal_block";
+						"hash" => ?header_hash, "err" => ?err,
+					);
+					return;
+				},
+			};
 
 			info!(
 				"Pre-sealed block for proposal at {}. Hash now {:?}, previously {:?}.",
@@ -316,6 +358,161 @@ pub trait SimpleSlotWorker<B: BlockT> {
 	}
 }
 
+/// A unit type wrapper to express the proportion of a slot.
+#[derive(Debug, Clone, Copy)]
+pub struct SlotProportion(f32);
+
+impl SlotProportion {
+	/// Create a new proportion, clamped between `0` and `1`.
+	pub fn new(inner: f32) -> Self {
+		Self(inner.max(0.0).min(1.0))
+	}
+
+	/// Returns the inner value of the proportion.
+	pub fn get(&self) -> f32 {
+		self.0
+	}
+}
+
+/// The kind of lenience given to the block proposal when slots were missed since the chain head.
+#[derive(Debug, Clone, Copy)]
+pub enum SlotLenienceType {
+	/// Increase the lenience linearly with the number of missed slots.
+	Linear,
+	/// Double the lenience every two missed slots.
+	Exponential,
+}
+
+impl SlotLenienceType {
+	// never give more than this many slots of linear lenience.
+	const LINEAR_CAP: u64 = 20;
+	// never give more than 2^(this / 2) slots of exponential lenience.
+	const EXPONENTIAL_CAP: u64 = 8;
+
+	/// The lenience for the given number of missed slots of the given duration, if any.
+	fn lenience(&self, missed_slots: u64, slot_duration: u64) -> Option<Duration> {
+		if missed_slots == 0 {
+			return None;
+		}
+
+		let lenience = match self {
+			SlotLenienceType::Linear => missed_slots.min(Self::LINEAR_CAP) * slot_duration,
+			SlotLenienceType::Exponential =>
+				slot_duration << (missed_slots.min(Self::EXPONENTIAL_CAP) / 2),
+		};
+
+		Some(Duration::from_millis(lenience))
+	}
+}
+
+/// Calculate the remaining duration for proposing a block at the given slot.
+///
+/// In normal cases, the proposal can take `block_proposal_slot_portion` of the slot, bounded by
+/// the time remaining in the slot (e.g. because importing the chain head ran long). When slots
+/// were missed since `parent_slot`, the slot of the chain head, more lenience is given so that
+/// a stalled chain can make progress with slow proposals.
+pub fn proposing_remaining_duration(
+	parent_slot: Option<u64>,
+	slot_info: &SlotInfo,
+	block_proposal_slot_portion: &SlotProportion,
+	slot_lenience_type: SlotLenienceType,
+	logging_target: &str,
+) -> Duration {
+	let proposing_duration = Duration::from_millis(
+		(slot_info.duration as f32 * block_proposal_slot_portion.get()) as u64
+	);
+	let slot_remaining = slot_info.ends_at
+		.checked_duration_since(Instant::now())
+		.unwrap_or_default();
+	let proposing_remaining = proposing_duration.min(slot_remaining);
+
+	// the genesis block has no slot, there are no missed slots to account for.
+	let parent_slot = match parent_slot {
+		Some(parent_slot) => parent_slot,
+		None => return proposing_remaining,
+	};
+
+	let missed_slots = slot_info.number.saturating_sub(parent_slot + 1);
+	match slot_lenience_type.lenience(missed_slots, slot_info.duration) {
+		Some(lenience) => {
+			debug!(target: logging_target,
+				"No block for {} slots. Applying {:?} lenience of {:?}, proposing for {:?}.",
+				missed_slots,
+				slot_lenience_type,
+				lenience,
+				proposing_remaining + lenience,
+			);
+
+			proposing_remaining + lenience
+		},
+		None => proposing_remaining,
+	}
+}
+
+/// Backoff of block authoring when the best block is too far ahead of the last finalized
+/// block, so that the unfinalized part of the chain doesn't grow unboundedly while finality
+/// is stalled.
+///
+/// Once the chain head is more than `unfinalized_slack` blocks ahead of the last finalized
+/// block, one more slot is skipped after the slot of the chain head for every `authoring_bias`
+/// further blocks, up to `max_interval` slots.
+#[derive(Debug, Clone, Copy)]
+pub struct BackoffAuthoringOnFinalizedHeadLagging {
+	/// The number of unfinalized blocks authored without any backoff.
+	pub unfinalized_slack: u32,
+	/// The number of unfinalized blocks after which one more slot is skipped.
+	pub authoring_bias: u32,
+	/// The maximum number of slots skipped after the slot of the chain head.
+	pub max_interval: u32,
+}
+
+impl BackoffAuthoringOnFinalizedHeadLagging {
+	/// Create a strategy that starts backing off once there are more than
+	/// `max_unfinalized_blocks` unfinalized blocks.
+	pub fn new(max_unfinalized_blocks: u32) -> Self {
+		BackoffAuthoringOnFinalizedHeadLagging {
+			unfinalized_slack: max_unfinalized_blocks,
+			authoring_bias: 2,
+			max_interval: 100,
+		}
+	}
+
+	/// Whether authoring at `slot_now` on top of the chain head, authored at
+	/// `chain_head_slot`, should be skipped.
+	pub fn should_backoff<N: AtLeast32Bit>(
+		&self,
+		chain_head_number: N,
+		chain_head_slot: u64,
+		finalized_number: N,
+		slot_now: u64,
+		logging_target: &str,
+	) -> bool {
+		// this should not happen, but we don't want to stop authoring if it does.
+		if slot_now <= chain_head_slot {
+			return false;
+		}
+
+		let unfinalized_blocks: u32 = chain_head_number.saturating_sub(finalized_number)
+			.unique_saturated_into();
+		let interval = unfinalized_blocks.saturating_sub(self.unfinalized_slack)
+			/ self.authoring_bias.max(1);
+		let interval = interval.min(self.max_interval);
+
+		if slot_now <= chain_head_slot + interval as u64 {
+			debug!(target: logging_target,
+				"Backing off authoring at slot {}: {} unfinalized blocks, waiting until slot {}",
+				slot_now,
+				unfinalized_blocks,
+				chain_head_slot + interval as u64 + 1,
+			);
+
+			true
+		} else {
+			false
+		}
+	}
+}
+
 /// Slot compatible inherent data.
 pub trait SlotCompatible {
 	/// Extract timestamp and slot from inherent data.
@@ -333,12 +530,16 @@ pub trait SlotCompatible {
 ///
 /// Every time a new slot is triggered, `worker.on_slot` is called and the future it returns is
 /// polled until completion, unless we are major syncing.
-pub fn start_slot_worker<B, C, W, T, SO, SC, CAW>(
+///
+/// The slots are computed from the inherent data of `inherent_data_providers`, to which the data
+/// of the providers `create_inherent_data_providers` creates for the best block is added.
+pub fn start_slot_worker<B, C, W, T, SO, SC, CAW, CIDP>(
 	slot_duration: SlotDuration<T>,
 	client: C,
 	mut worker: W,
 	mut sync_oracle: SO,
 	inherent_data_providers: InherentDataProviders,
+	create_inherent_data_providers: CIDP,
 	timestamp_extractor: SC,
 	can_author_with: CAW,
 ) -> impl Future<Output = ()>
@@ -351,20 +552,33 @@ where
 	SC: SlotCompatible + Unpin,
 	T: SlotData + Clone,
 	CAW: CanAuthorWith<B> + Send,
+	CIDP: CreateInherentDataProviders<B::Hash, ()>,
 {
 	let SlotDuration(slot_duration) = slot_duration;
 
 	// rather than use a timer interval, we schedule our waits ourselves
-	Slots::<SC>::new(
+	let mut slots = Slots::<SC>::new(
 		slot_duration.slot_duration(),
 		inherent_data_providers,
 		timestamp_extractor,
-	).inspect_err(|e| debug!(target: "slots", "Faulty timer: {:?}", e))
-		.try_for_each(move |slot_info| {
+	);
+
+	async move {
+		loop {
+			let mut slot_info = match slots.next().await {
+				Some(Ok(slot_info)) => slot_info,
+				Some(Err(err)) => {
+					debug!(target: "slots", "Faulty timer: {:?}", err);
+					warn!(target: "slots", "Slots stream terminated with an error: {:?}", err);
+					return;
+				},
+				None => return,
+			};
+
 			// only propose when we are not syncing.
 			if sync_oracle.is_major_syncing() {
 				debug!(target: "slots", "Skipping proposal slot due to sync.");
-				return Either::Right(future::ready(Ok(())));
+				continue;
 			}
 
 			let slot_num = slot_info.number;
@@ -373,7 +587,7 @@ where
 				Err(e) => {
 					warn!(target: "slots", "Unable to author block in slot {}. \
 					no best block header: {:?}", slot_num, e);
-					return Either::Right(future::ready(Ok(())));
+					continue;
 				}
 			};
 
@@ -385,22 +599,32 @@ where
 					slot_num,
 					err,
 				);
-				Either::Right(future::ready(Ok(())))
-			} else {
-				Either::Left(
-					worker.on_slot(chain_head, slot_info)
-						.map_err(|e| {
-							warn!(target: "slots", "Encountered consensus error: {:?}", e);
-						})
-						.or_else(|_| future::ready(Ok(())))
-				)
+				continue;
+			}
+
+			let inherent_data_providers = match create_inherent_data_providers
+				.create_inherent_data_providers(chain_head.hash(), ())
+				.await
+			{
+				Ok(providers) => providers,
+				Err(err) => {
+					warn!(target: "slots", "Unable to author block in slot {}. \
+					Creating the inherent data providers failed: {:?}", slot_num, err);
+					continue;
+				}
+			};
+			let inherent_data = &mut slot_info.inherent_data;
+			if let Err(err) = inherent_data_providers.provide_inherent_data(inherent_data) {
+				warn!(target: "slots", "Unable to author block in slot {}. \
+				Providing the inherent data failed: {:?}", slot_num, err);
+				continue;
 			}
-		}).then(|res| {
-			if let Err(err) = res {
-				warn!(target: "slots", "Slots stream terminated with an error: {:?}", err);
+
+			if let Err(err) = worker.on_slot(chain_head, slot_info).await {
+				warn!(target: "slots", "Encountered consensus error: {:?}", err);
 			}
-			future::ready(())
-		})
+		}
+	}
 }
 
 /// A header which has been checked
@@ -483,3 +707,91 @@ impl<T: Clone> SlotDuration<T> {
 		self.0.clone()
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::{Duration, Instant};
+
+	#[test]
+	fn backoff_grows_with_the_unfinalized_blocks() {
+		let strategy = BackoffAuthoringOnFinalizedHeadLagging {
+			unfinalized_slack: 10,
+			authoring_bias: 2,
+			max_interval: 5,
+		};
+		let should_backoff = |head: u32, head_slot, finalized: u32, slot_now| {
+			strategy.should_backoff(head, head_slot, finalized, slot_now, "slots")
+		};
+
+		// within the slack, authoring at the next slot.
+		assert!(!should_backoff(10, 100, 0, 101));
+		// 4 blocks beyond the slack, skipping 2 slots.
+		assert!(should_backoff(14, 100, 0, 101));
+		assert!(should_backoff(14, 100, 0, 102));
+		assert!(!should_backoff(14, 100, 0, 103));
+		// the number of skipped slots is bounded.
+		assert!(should_backoff(1000, 100, 0, 105));
+		assert!(!should_backoff(1000, 100, 0, 106));
+		// finality catching up resumes authoring at every slot.
+		assert!(!should_backoff(1000, 100, 995, 101));
+		// never backing off for a slot that isn't after the one of the chain head.
+		assert!(!should_backoff(1000, 100, 0, 100));
+	}
+
+	fn slot_info(number: u64, ends_in: Duration) -> SlotInfo {
+		SlotInfo {
+			number,
+			last_number: number - 1,
+			timestamp: 0,
+			ends_at: Instant::now() + ends_in,
+			inherent_data: Default::default(),
+			duration: 6000,
+		}
+	}
+
+	#[test]
+	fn proposing_duration_is_a_portion_of_the_slot() {
+		let portion = SlotProportion::new(0.5);
+		let linear = SlotLenienceType::Linear;
+
+		// half of the slot when the slot just started.
+		let duration = proposing_remaining_duration(
+			Some(9),
+			&slot_info(10, Duration::from_secs(6)),
+			&portion,
+			linear,
+			"slots",
+		);
+		assert_eq!(duration, Duration::from_millis(3000));
+
+		// bounded by the remaining time of the slot.
+		let duration = proposing_remaining_duration(
+			Some(9),
+			&slot_info(10, Duration::from_secs(1)),
+			&portion,
+			linear,
+			"slots",
+		);
+		assert!(duration <= Duration::from_secs(1));
+
+		// lenience for the missed slots.
+		let duration = proposing_remaining_duration(
+			Some(7),
+			&slot_info(10, Duration::from_secs(6)),
+			&portion,
+			linear,
+			"slots",
+		);
+		assert_eq!(duration, Duration::from_millis(3000 + 2 * 6000));
+
+		let duration = proposing_remaining_duration(
+			Some(1),
+			&slot_info(10, Duration::from_secs(6)),
+			&portion,
+			SlotLenienceType::Exponential,
+			"slots",
+		);
+		assert_eq!(duration, Duration::from_millis(3000 + (6000 << 4)));
+	}
+}
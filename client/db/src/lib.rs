@@ -36,15 +36,18 @@ mod children;
 mod cache;
 mod changes_tries_storage;
 mod storage_cache;
+mod trie_cache;
 #[cfg(any(feature = "kvdb-rocksdb", test))]
 mod upgrade;
 mod utils;
+#[cfg(feature = "parity-db")]
+mod parity_db;
 mod stats;
 
 use std::sync::Arc;
 use std::path::PathBuf;
 use std::io;
-use std::collections::HashMap;
+use std::collections::{HashMap, BTreeMap};
 
 use sc_client_api::{
 	ForkBlocks, UsageInfo, MemoryInfo, BadBlocks, IoInfo, MemorySize, CloneableSpawn,
@@ -81,6 +84,7 @@ use sc_client::leaves::{LeafSet, FinalizationDisplaced};
 use sc_state_db::StateDb;
 use sp_blockchain::{CachedHeaderMetadata, HeaderMetadata, HeaderMetadataCache};
 use crate::storage_cache::{CachingState, SyncingCachingState, SharedCache, new_shared_cache};
+use crate::trie_cache::{CachingTrieStorage, SharedTrieNodeCache, new_shared_trie_node_cache};
 use crate::stats::StateUsageStats;
 use log::{trace, debug, warn};
 pub use sc_state_db::PruningMode;
@@ -89,6 +93,11 @@ use prometheus_endpoint::Registry;
 #[cfg(any(feature = "kvdb-rocksdb", test))]
 pub use bench::BenchmarkingState;
 
+#[cfg(feature = "parity-db")]
+pub use parity_db::ParityDb;
+#[cfg(all(feature = "parity-db", any(feature = "kvdb-rocksdb", test)))]
+pub use parity_db::migrate_from_rocksdb;
+
 #[cfg(feature = "test-helpers")]
 use sc_client::in_mem::Backend as InMemoryBackend;
 
@@ -264,12 +273,38 @@ pub struct DatabaseSettings {
 	pub state_cache_size: usize,
 	/// Ratio of cache size dedicated to child tries.
 	pub state_cache_child_ratio: Option<(usize, usize)>,
+	/// Trie node cache size.
+	pub trie_cache_size: usize,
 	/// Pruning mode.
 	pub pruning: PruningMode,
+	/// Block body pruning mode.
+	pub keep_blocks: KeepBlocks,
+	/// The size of the CHTs the header proofs are served against, or the light client replaces
+	/// the finalized headers with.
+	///
+	/// It must be the same for all the nodes of the network, `sc_client::cht::size()` by default.
+	pub cht_size: u32,
 	/// Where to find the database.
 	pub source: DatabaseSettingsSrc,
 }
 
+/// The number of finalized blocks whose bodies are kept.
+///
+/// Headers and justifications are always kept, so that the chain can still be followed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeepBlocks {
+	/// Keep the bodies of all the blocks.
+	All,
+	/// Keep the bodies of the last `n` finalized blocks only.
+	Some(u32),
+}
+
+impl Default for KeepBlocks {
+	fn default() -> Self {
+		KeepBlocks::All
+	}
+}
+
 /// Where to find the database..
 pub enum DatabaseSettingsSrc {
 	/// Load a database from a given path. Recommended for most uses.
@@ -280,6 +315,12 @@ pub enum DatabaseSettingsSrc {
 		cache_size: Option<usize>,
 	},
 
+	/// Load a ParityDB database from a given path.
+	ParityDb {
+		/// Path to the database.
+		path: PathBuf,
+	},
+
 	/// Use a custom already-open database.
 	Custom(Arc<dyn KeyValueDB>),
 }
@@ -294,6 +335,7 @@ pub fn new_client<E, Block, RA>(
 	execution_extensions: ExecutionExtensions<Block>,
 	spawn_handle: Box<dyn CloneableSpawn>,
 	prometheus_registry: Option<Registry>,
+	code_substitutes: BTreeMap<u64, Vec<u8>>,
 ) -> Result<(
 		sc_client::Client<
 			Backend<Block>,
@@ -309,8 +351,10 @@ pub fn new_client<E, Block, RA>(
 		Block: BlockT,
 		E: CodeExecutor + RuntimeInfo,
 {
+	let cht_size = settings.cht_size.into();
 	let backend = Arc::new(Backend::new(settings, CANONICALIZATION_DELAY)?);
-	let executor = sc_client::LocalCallExecutor::new(backend.clone(), executor, spawn_handle);
+	let executor = sc_client::LocalCallExecutor::new(backend.clone(), executor, spawn_handle)
+		.with_code_substitutes(code_substitutes)?;
 	Ok((
 		sc_client::Client::new(
 			backend.clone(),
@@ -319,6 +363,7 @@ pub fn new_client<E, Block, RA>(
 			fork_blocks,
 			bad_blocks,
 			execution_extensions,
+			cht_size,
 			prometheus_registry,
 		)?,
 		backend,
@@ -524,6 +569,7 @@ impl<Block: BlockT> HeaderMetadata<Block> for BlockchainDb<Block> {
 /// Database transaction
 pub struct BlockImportOperation<Block: BlockT> {
 	old_state: SyncingCachingState<RefTrackingState<Block>, Block>,
+	old_trie_storage: Option<Arc<TrieStorage<Block>>>,
 	db_updates: PrefixedMemoryDB<HashFor<Block>>,
 	storage_updates: StorageCollection,
 	child_storage_updates: ChildStorageCollection,
@@ -666,6 +712,9 @@ impl<Block: BlockT> sc_client_api::backend::BlockImportOperation<Block> for Bloc
 	}
 }
 
+/// Trie node storage of the database, reading through the trie node cache.
+type TrieStorage<Block> = CachingTrieStorage<HashFor<Block>, StorageDb<Block>>;
+
 struct StorageDb<Block: BlockT> {
 	pub db: Arc<dyn KeyValueDB>,
 	pub state_db: StateDb<Block::Hash, Vec<u8>>,
@@ -755,8 +804,10 @@ pub struct Backend<Block: BlockT> {
 	blockchain: BlockchainDb<Block>,
 	canonicalization_delay: u64,
 	shared_cache: SharedCache<Block>,
+	trie_cache: SharedTrieNodeCache,
 	import_lock: Arc<RwLock<()>>,
 	is_archive: bool,
+	keep_blocks: KeepBlocks,
 	io_stats: FrozenForDuration<(kvdb::IoStats, StateUsageInfo)>,
 	state_usage: Arc<StateUsageStats>,
 }
@@ -777,7 +828,10 @@ impl<Block: BlockT> Backend<Block> {
 		let db_setting = DatabaseSettings {
 			state_cache_size: 16777216,
 			state_cache_child_ratio: Some((50, 100)),
+			trie_cache_size: 16777216,
 			pruning: PruningMode::keep_blocks(keep_blocks),
+			keep_blocks: KeepBlocks::All,
+			cht_size: sc_client::cht::size(),
 			source: DatabaseSettingsSrc::Custom(db),
 		};
 
@@ -827,13 +881,95 @@ impl<Block: BlockT> Backend<Block> {
 				config.state_cache_size,
 				config.state_cache_child_ratio.unwrap_or(DEFAULT_CHILD_RATIO),
 			),
+			trie_cache: new_shared_trie_node_cache(config.trie_cache_size),
 			import_lock: Default::default(),
 			is_archive: is_archive_pruning,
+			keep_blocks: config.keep_blocks,
 			io_stats: FrozenForDuration::new(std::time::Duration::from_secs(1)),
 			state_usage: Arc::new(StateUsageStats::new()),
 		})
 	}
 
+	/// Get the state at the given block, along with the trie node storage it reads through.
+	///
+	/// The trie nodes read by a `staged` state are only shared with the other states once
+	/// committed, which is what the states of the blocks being imported need.
+	fn state_with_trie_storage(
+		&self,
+		block: BlockId<Block>,
+		staged: bool,
+	) -> ClientResult<(
+		SyncingCachingState<RefTrackingState<Block>, Block>,
+		Option<Arc<TrieStorage<Block>>>,
+	)> {
+		use sc_client_api::backend::Backend as ClientBackend;
+		use sc_client::blockchain::HeaderBackend as BcHeaderBackend;
+
+		// special case for genesis initialization
+		match block {
+			BlockId::Hash(h) if h == Default::default() => {
+				let genesis_storage = DbGenesisStorage::<Block>::new();
+				let root = genesis_storage.0.clone();
+				let db_state = DbState::<Block>::new(Arc::new(genesis_storage), root);
+				let state = RefTrackingState::new(db_state, self.storage.clone(), None);
+				let caching_state = CachingState::new(
+					state,
+					self.shared_cache.clone(),
+					None,
+				);
+				return Ok((SyncingCachingState::new(
+					caching_state,
+					self.state_usage.clone(),
+					self.blockchain.meta.clone(),
+					self.import_lock.clone(),
+				), None));
+			},
+			_ => {}
+		}
+
+		match self.blockchain.header(block) {
+			Ok(Some(ref hdr)) => {
+				let hash = hdr.hash();
+				if !self.have_state_at(&hash, *hdr.number()) {
+					return Err(sp_blockchain::Error::StatePruned(format!("{:?}", block)))
+				}
+				if let Ok(()) = self.storage.state_db.pin(&hash) {
+					let root = hdr.state_root();
+					let trie_storage = Arc::new(TrieStorage::<Block>::new(
+						self.storage.clone(),
+						self.trie_cache.clone(),
+						staged,
+					));
+					let db_state = DbState::<Block>::new(trie_storage.clone(), *root);
+					let state = RefTrackingState::new(
+						db_state,
+						self.storage.clone(),
+						Some(hash.clone()),
+					);
+					let caching_state = CachingState::new(
+						state,
+						self.shared_cache.clone(),
+						Some(hash),
+					);
+					Ok((SyncingCachingState::new(
+						caching_state,
+						self.state_usage.clone(),
+						self.blockchain.meta.clone(),
+						self.import_lock.clone(),
+					), Some(trie_storage)))
+				} else {
+					Err(sp_blockchain::Error::StatePruned(format!("{:?}", block)))
+				}
+			},
+			Ok(None) => Err(
+				sp_blockchain::Error::UnknownBlock(
+					format!("Unknown state for block {:?}", block)
+				)
+			),
+			Err(e) => Err(e),
+		}
+	}
+
 	/// Returns in-memory blockchain that contains the same set of blocks as self.
 	#[cfg(feature = "test-helpers")]
 	pub fn as_in_memory(&self) -> InMemoryBackend<Block> {
@@ -1121,6 +1257,9 @@ impl<Block: BlockT> Backend<Block> {
 						ops += 1;
 						bytes += key.len() as u64 + val.len() as u64;
 
+						if let Some(ref trie_storage) = operation.old_trie_storage {
+							trie_storage.stage(key.clone(), val.clone());
+						}
 						changeset.inserted.push((key, val.to_vec()));
 					} else if rc < 0 {
 						ops += 1;
@@ -1271,6 +1410,9 @@ impl<Block: BlockT> Backend<Block> {
 				Some(number),
 				is_best,
 			);
+			if let Some(trie_storage) = operation.old_trie_storage {
+				trie_storage.commit(is_best);
+			}
 		}
 
 		if let Some(changes_trie_build_cache_update) = operation.changes_trie_build_cache_update {
@@ -1310,6 +1452,7 @@ impl<Block: BlockT> Backend<Block> {
 			let commit = self.storage.state_db.canonicalize_block(&f_hash)
 				.map_err(|e: sc_state_db::Error<io::Error>| sp_blockchain::Error::from(format!("State database error: {:?}", e)))?;
 			apply_state_commit(transaction, commit);
+			self.prune_block_body(transaction, f_num)?;
 
 			if !f_num.is_zero() {
 				let new_changes_trie_cache_ops = self.changes_tries_storage.finalize(
@@ -1332,6 +1475,31 @@ impl<Block: BlockT> Backend<Block> {
 
 		Ok(())
 	}
+
+	/// Remove the body of the finalized block that just went out of the `keep_blocks` window.
+	fn prune_block_body(
+		&self,
+		transaction: &mut DBTransaction,
+		finalized: NumberFor<Block>,
+	) -> ClientResult<()> {
+		let keep_blocks = match self.keep_blocks {
+			KeepBlocks::All => return Ok(()),
+			// the body of the last finalized block is always kept.
+			KeepBlocks::Some(n) => NumberFor::<Block>::from(n.max(1)),
+		};
+
+		if finalized < keep_blocks {
+			return Ok(());
+		}
+
+		let number = finalized - keep_blocks;
+		if let Some(hash) = self.blockchain.hash(number)? {
+			let lookup_key = utils::number_and_hash_to_lookup_key(number, hash)?;
+			transaction.delete(columns::BODY, &lookup_key);
+		}
+
+		Ok(())
+	}
 }
 
 fn apply_state_commit(transaction: &mut DBTransaction, commit: sc_state_db::CommitSet<Vec<u8>>) {
@@ -1386,6 +1554,7 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 		Ok(BlockImportOperation {
 			pending_block: None,
 			old_state,
+			old_trie_storage: None,
 			db_updates: PrefixedMemoryDB::default(),
 			storage_updates: Default::default(),
 			child_storage_updates: Default::default(),
@@ -1404,8 +1573,10 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 		operation: &mut Self::BlockImportOperation,
 		block: BlockId<Block>,
 	) -> ClientResult<()> {
-		operation.old_state = self.state_at(block)?;
+		let (old_state, old_trie_storage) = self.state_with_trie_storage(block, true)?;
+		operation.old_state = old_state;
 		operation.old_state.disable_syncing();
+		operation.old_trie_storage = old_trie_storage;
 
 		operation.commit_state = true;
 		Ok(())
@@ -1586,74 +1757,16 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 	}
 
 	fn state_at(&self, block: BlockId<Block>) -> ClientResult<Self::State> {
-		use sc_client::blockchain::HeaderBackend as BcHeaderBackend;
+		self.state_with_trie_storage(block, false).map(|(state, _)| state)
+	}
 
-		// special case for genesis initialization
-		match block {
-			BlockId::Hash(h) if h == Default::default() => {
-				let genesis_storage = DbGenesisStorage::<Block>::new();
-				let root = genesis_storage.0.clone();
-				let db_state = DbState::<Block>::new(Arc::new(genesis_storage), root);
-				let state = RefTrackingState::new(db_state, self.storage.clone(), None);
-				let caching_state = CachingState::new(
-					state,
-					self.shared_cache.clone(),
-					None,
-				);
-				return Ok(SyncingCachingState::new(
-					caching_state,
-					self.state_usage.clone(),
-					self.blockchain.meta.clone(),
-					self.import_lock.clone(),
-				));
-			},
-			_ => {}
-		}
+	fn pin_block(&self, hash: &Block::Hash) -> ClientResult<()> {
+		self.storage.state_db.pin(hash)
+			.map_err(|_| sp_blockchain::Error::StatePruned(format!("{:?}", hash)))
+	}
 
-		match self.blockchain.header(block) {
-			Ok(Some(ref hdr)) => {
-				let hash = hdr.hash();
-				if !self.have_state_at(&hash, *hdr.number()) {
-					return Err(
-						sp_blockchain::Error::UnknownBlock(
-							format!("State already discarded for {:?}", block)
-						)
-					)
-				}
-				if let Ok(()) = self.storage.state_db.pin(&hash) {
-					let root = hdr.state_root();
-					let db_state = DbState::<Block>::new(self.storage.clone(), *root);
-					let state = RefTrackingState::new(
-						db_state,
-						self.storage.clone(),
-						Some(hash.clone()),
-					);
-					let caching_state = CachingState::new(
-						state,
-						self.shared_cache.clone(),
-						Some(hash),
-					);
-					Ok(SyncingCachingState::new(
-						caching_state,
-						self.state_usage.clone(),
-						self.blockchain.meta.clone(),
-						self.import_lock.clone(),
-					))
-				} else {
-					Err(
-						sp_blockchain::Error::UnknownBlock(
-							format!("State already discarded for {:?}", block)
-						)
-					)
-				}
-			},
-			Ok(None) => Err(
-				sp_blockchain::Error::UnknownBlock(
-					format!("Unknown state for block {:?}", block)
-				)
-			),
-			Err(e) => Err(e),
-		}
+	fn unpin_block(&self, hash: &Block::Hash) {
+		self.storage.state_db.unpin(hash)
 	}
 
 	fn have_state_at(&self, hash: &Block::Hash, number: NumberFor<Block>) -> bool {
@@ -1796,7 +1909,10 @@ pub(crate) mod tests {
 		let backend = Backend::<Block>::new(DatabaseSettings {
 			state_cache_size: 16777216,
 			state_cache_child_ratio: Some((50, 100)),
+			trie_cache_size: 16777216,
 			pruning: PruningMode::keep_blocks(1),
+			keep_blocks: KeepBlocks::All,
+			cht_size: sc_client::cht::size(),
 			source: DatabaseSettingsSrc::Custom(backing),
 		}, 0).unwrap();
 		assert_eq!(backend.blockchain().info().best_number, 9);
@@ -2321,4 +2437,62 @@ pub(crate) mod tests {
 			backend.commit_operation(op).unwrap_err();
 		}
 	}
+
+	#[test]
+	fn pinned_states_are_not_pruned() {
+		let backend = Backend::<Block>::new_test(1, 0);
+		let mut hash = insert_header(&backend, 0, Default::default(), None, Default::default());
+		let block1 = insert_header(&backend, 1, hash, None, Default::default());
+		backend.pin_block(&block1).unwrap();
+
+		hash = block1;
+		for number in 2..5 {
+			hash = insert_header(&backend, number, hash, None, Default::default());
+		}
+		assert!(backend.state_at(BlockId::Hash(block1)).is_ok());
+
+		backend.unpin_block(&block1);
+		for number in 5..7 {
+			hash = insert_header(&backend, number, hash, None, Default::default());
+		}
+		match backend.state_at(BlockId::Hash(block1)) {
+			Err(sp_blockchain::Error::StatePruned(_)) => {},
+			_ => panic!("the state of block 1 should be pruned"),
+		}
+		assert!(backend.pin_block(&block1).is_err());
+	}
+
+	#[test]
+	fn prunes_block_bodies_out_of_the_keep_blocks_window() {
+		use sc_client::blockchain::{Backend as BlockChainBackend};
+
+		let backend = Backend::<Block>::new(DatabaseSettings {
+			state_cache_size: 16777216,
+			state_cache_child_ratio: Some((50, 100)),
+			trie_cache_size: 16777216,
+			pruning: PruningMode::keep_blocks(10),
+			keep_blocks: KeepBlocks::Some(2),
+			cht_size: sc_client::cht::size(),
+			source: DatabaseSettingsSrc::Custom(Arc::new(kvdb_memorydb::create(
+				crate::utils::NUM_COLUMNS,
+			))),
+		}, 0).unwrap();
+
+		let mut parent = Default::default();
+		for number in 0..5 {
+			parent = insert_header(&backend, number, parent, None, Default::default());
+		}
+		for number in 1..5 {
+			backend.finalize_block(BlockId::Number(number), None).unwrap();
+		}
+
+		for number in 0..3 {
+			assert_eq!(backend.blockchain().body(BlockId::Number(number)).unwrap(), None);
+			assert!(backend.blockchain().header(BlockId::Number(number)).unwrap().is_some());
+		}
+		for number in 3..5 {
+			let body = backend.blockchain().body(BlockId::Number(number)).unwrap();
+			assert_eq!(body, Some(Vec::new()));
+		}
+	}
 }
@@ -63,30 +63,67 @@ pub struct LightStorage<Block: BlockT> {
 	meta: RwLock<Meta<NumberFor<Block>, Block::Hash>>,
 	cache: Arc<DbCacheSync<Block>>,
 	header_metadata_cache: HeaderMetadataCache<Block>,
+	cht_size: NumberFor<Block>,
 
 	#[cfg(not(target_os = "unknown"))]
 	io_stats: FrozenForDuration<kvdb::IoStats>,
 }
 
+/// Check that the CHTs of the database are of the given size, storing it when the database is
+/// new.
+///
+/// The databases created before the size was stored use the default one.
+fn check_cht_size(db: &dyn KeyValueDB, cht_size: u32, is_new: bool) -> ClientResult<()> {
+	let stored_cht_size = match db.get(columns::META, meta_keys::CHT_SIZE).map_err(db_err)? {
+		Some(stored) => u32::decode(&mut &stored[..]).map_err(|err| ClientError::Backend(
+			format!("Error decoding the CHT size of the database: {}", err)
+		))?,
+		None if is_new => {
+			let mut transaction = DBTransaction::new();
+			transaction.put(columns::META, meta_keys::CHT_SIZE, &cht_size.encode());
+			return db.write(transaction).map_err(db_err);
+		},
+		None => cht::size(),
+	};
+
+	if stored_cht_size != cht_size {
+		return Err(ClientError::Backend(format!(
+			"The database was created with CHTs of {} blocks, not {}",
+			stored_cht_size,
+			cht_size,
+		)));
+	}
+
+	Ok(())
+}
+
 impl<Block: BlockT> LightStorage<Block> {
 	/// Create new storage with given settings.
 	pub fn new(config: DatabaseSettings) -> ClientResult<Self> {
 		let db = crate::utils::open_database::<Block>(&config, DatabaseType::Light)?;
-		Self::from_kvdb(db as Arc<_>)
+		Self::from_kvdb(db as Arc<_>, config.cht_size)
 	}
 
 	/// Create new memory-backed `LightStorage` for tests.
 	#[cfg(any(test, feature = "test-helpers"))]
 	pub fn new_test() -> Self {
+		Self::new_test_with_cht_size(cht::size())
+	}
+
+	/// Create new memory-backed `LightStorage` replacing the headers with CHTs of the given size
+	/// for tests.
+	#[cfg(any(test, feature = "test-helpers"))]
+	pub fn new_test_with_cht_size(cht_size: u32) -> Self {
 		use utils::NUM_COLUMNS;
 
 		let db = Arc::new(::kvdb_memorydb::create(NUM_COLUMNS));
 
-		Self::from_kvdb(db as Arc<_>).expect("failed to create test-db")
+		Self::from_kvdb(db as Arc<_>, cht_size).expect("failed to create test-db")
 	}
 
-	fn from_kvdb(db: Arc<dyn KeyValueDB>) -> ClientResult<Self> {
+	fn from_kvdb(db: Arc<dyn KeyValueDB>, cht_size: u32) -> ClientResult<Self> {
 		let meta = read_meta::<Block>(&*db, columns::HEADER)?;
+		check_cht_size(&*db, cht_size, meta.genesis_hash == Default::default())?;
 		let cache = DbCache::new(
 			db.clone(),
 			columns::KEY_LOOKUP,
@@ -101,6 +138,7 @@ impl<Block: BlockT> LightStorage<Block> {
 			meta: RwLock::new(meta),
 			cache: Arc::new(DbCacheSync(RwLock::new(cache))),
 			header_metadata_cache: HeaderMetadataCache::default(),
+			cht_size: cht_size.into(),
 			#[cfg(not(target_os = "unknown"))]
 			io_stats: FrozenForDuration::new(std::time::Duration::from_secs(1)),
 		})
@@ -294,9 +332,11 @@ impl<Block: BlockT> LightStorage<Block> {
 		let lookup_key = utils::number_and_hash_to_lookup_key(header.number().clone(), hash)?;
 		transaction.put(columns::META, meta_keys::FINALIZED_BLOCK, &lookup_key);
 
-		// build new CHT(s) if required
-		if let Some(new_cht_number) = cht::is_build_required(cht::size(), *header.number()) {
-			let new_cht_start: NumberFor<Block> = cht::start_number(cht::size(), new_cht_number);
+		// build new CHT(s) if required, unless they were given with a checkpoint
+		if let Some(new_cht_number) = cht::is_build_required(self.cht_size, *header.number()) {
+			let new_cht_start: NumberFor<Block> = cht::start_number(self.cht_size, new_cht_number);
+			let new_cht_key = cht_key(HEADER_CHT_PREFIX, new_cht_start)?;
+			let from_checkpoint = self.db.get(columns::CHT, &new_cht_key).map_err(db_err)?.is_some();
 
 			let mut current_num = new_cht_start;
 			let cht_range = ::std::iter::from_fn(|| {
@@ -305,17 +345,15 @@ impl<Block: BlockT> LightStorage<Block> {
 				Some(old_current_num)
 			});
 
-			let new_header_cht_root = cht::compute_root::<Block::Header, HashFor<Block>, _>(
-				cht::size(), new_cht_number, cht_range.map(|num| self.hash(num))
-			)?;
-			transaction.put(
-				columns::CHT,
-				&cht_key(HEADER_CHT_PREFIX, new_cht_start)?,
-				new_header_cht_root.as_ref()
-			);
+			if !from_checkpoint {
+				let new_header_cht_root = cht::compute_root::<Block::Header, HashFor<Block>, _>(
+					self.cht_size, new_cht_number, cht_range.map(|num| self.hash(num))
+				)?;
+				transaction.put(columns::CHT, &new_cht_key, new_header_cht_root.as_ref());
+			}
 
 			// if the header includes changes trie root, let's build a changes tries roots CHT
-			if header.digest().log(DigestItem::as_changes_trie_root).is_some() {
+			if !from_checkpoint && header.digest().log(DigestItem::as_changes_trie_root).is_some() {
 				let mut current_num = new_cht_start;
 				let cht_range = std::iter::from_fn(|| {
 					let old_current_num = current_num;
@@ -323,7 +361,7 @@ impl<Block: BlockT> LightStorage<Block> {
 					Some(old_current_num)
 				});
 				let new_changes_trie_cht_root = cht::compute_root::<Block::Header, HashFor<Block>, _>(
-					cht::size(), new_cht_number, cht_range
+					self.cht_size, new_cht_number, cht_range
 						.map(|num| self.changes_trie_root(BlockId::Number(num)))
 				)?;
 				transaction.put(
@@ -335,7 +373,7 @@ impl<Block: BlockT> LightStorage<Block> {
 
 			// prune headers that are replaced with CHT
 			let mut prune_block = new_cht_start;
-			let new_cht_end = cht::end_number(cht::size(), new_cht_number);
+			let new_cht_end = cht::end_number(self.cht_size, new_cht_number);
 			trace!(target: "db", "Replacing blocks [{}..{}] with CHT#{}",
 				new_cht_start, new_cht_end, new_cht_number);
 
@@ -370,13 +408,16 @@ impl<Block: BlockT> LightStorage<Block> {
 		let meta = self.meta.read();
 		let max_cht_number = cht::max_cht_number(cht_size, meta.finalized_number);
 		let cht_number = cht::block_to_cht_number(cht_size, block).ok_or_else(no_cht_for_block)?;
+		let cht_start = cht::start_number(cht_size, cht_number);
+		let cht_root = self.db.get(columns::CHT, &cht_key(cht_type, cht_start)?).map_err(db_err)?;
 		match max_cht_number {
 			Some(max_cht_number) if cht_number <= max_cht_number => (),
+			// the roots of the CHTs up to a checkpoint are known before they could be built.
+			_ if cht_root.is_some() && block <= meta.finalized_number => (),
 			_ => return Ok(None),
 		}
 
-		let cht_start = cht::start_number(cht_size, cht_number);
-		self.db.get(columns::CHT, &cht_key(cht_type, cht_start)?).map_err(db_err)?
+		cht_root
 			.ok_or_else(no_cht_for_block)
 			.and_then(|hash| Block::Hash::decode(&mut &*hash).map_err(|_| no_cht_for_block()))
 			.map(Some)
@@ -503,6 +544,57 @@ impl<Block> LightBlockchainStorage<Block> for LightStorage<Block>
 		Ok(())
 	}
 
+	fn import_checkpoint(
+		&self,
+		header: Block::Header,
+		header_cht_roots: Vec<Block::Hash>,
+	) -> ClientResult<()> {
+		let hash = header.hash();
+		let number = *header.number();
+		cht::check_checkpoint(self.cht_size, number, header_cht_roots.len())?;
+		if !self.meta.read().best_number.is_zero() {
+			return Err(ClientError::Backend(
+				format!("Cannot import checkpoint {:?} once blocks past the genesis are known", hash),
+			));
+		}
+
+		let mut transaction = DBTransaction::new();
+		for (cht_number, cht_root) in header_cht_roots.iter().enumerate() {
+			let cht_start = cht::start_number(self.cht_size, (cht_number as u32).into());
+			transaction.put(columns::CHT, &cht_key(HEADER_CHT_PREFIX, cht_start)?, cht_root.as_ref());
+		}
+
+		let lookup_key = utils::number_and_hash_to_lookup_key(number, &hash)?;
+		utils::insert_hash_to_key_mapping(&mut transaction, columns::KEY_LOOKUP, number, hash)?;
+		utils::insert_number_to_key_mapping(&mut transaction, columns::KEY_LOOKUP, number, hash)?;
+		transaction.put(columns::HEADER, &lookup_key, &header.encode());
+		transaction.put(columns::META, meta_keys::BEST_BLOCK, &lookup_key);
+		transaction.put(columns::META, meta_keys::FINALIZED_BLOCK, &lookup_key);
+
+		{
+			// as for the genesis, no ancestor of the checkpoint is known to the cache.
+			let mut cache = self.cache.0.write();
+			let cache_ops = cache.transaction(&mut transaction)
+				.on_block_insert(
+					ComplexBlockId::new(*header.parent_hash(), number - One::one()),
+					ComplexBlockId::new(hash, number),
+					HashMap::new(),
+					CacheEntryType::Genesis,
+				)?
+				.into_ops();
+
+			self.db.write(transaction).map_err(db_err)?;
+			cache.commit(cache_ops)
+				.expect("only fails if cache with given name isn't loaded yet;\
+						cache is already loaded because there are cache_ops; qed");
+		}
+
+		self.header_metadata_cache.insert_header_metadata(hash, CachedHeaderMetadata::from(&header));
+		self.update_meta(hash, number, true, true);
+
+		Ok(())
+	}
+
 	fn set_head(&self, id: BlockId<Block>) -> ClientResult<()> {
 		if let Some(header) = self.header(id)? {
 			let hash = header.hash();
@@ -518,6 +610,10 @@ impl<Block> LightBlockchainStorage<Block> for LightStorage<Block>
 		}
 	}
 
+	fn cht_size(&self) -> NumberFor<Block> {
+		self.cht_size
+	}
+
 	fn header_cht_root(
 		&self,
 		cht_size: NumberFor<Block>,
@@ -810,6 +906,28 @@ pub(crate) mod tests {
 		assert!(db.changes_trie_cht_root(cht_size, cht_size + cht_size / 2).unwrap().is_none());
 	}
 
+	#[test]
+	fn finalized_ancient_headers_are_replaced_with_cht_of_configured_size() {
+		let db = LightStorage::<Block>::new_test_with_cht_size(4);
+		assert_eq!(db.cht_size(), 4);
+
+		let mut prev_hash = Default::default();
+		for number in 0..10 {
+			prev_hash = insert_final_block(
+				&db,
+				HashMap::new(),
+				|| default_header(&prev_hash, number),
+			);
+		}
+
+		// the first CHT is built once #9 is finalized, and its headers are pruned.
+		assert_eq!(db.db.iter(columns::CHT).count(), 1);
+		assert!((1..5).all(|i| db.header(BlockId::Number(i)).unwrap().is_none()));
+		assert!((5..10).all(|i| db.header(BlockId::Number(i)).unwrap().is_some()));
+		assert!(db.header_cht_root(4, 2).unwrap().is_some());
+		assert!(db.header_cht_root(4, 6).unwrap().is_none());
+	}
+
 	#[test]
 	fn get_cht_fails_for_genesis_block() {
 		assert!(LightStorage::<Block>::new_test().header_cht_root(cht::size(), 0).is_err());
@@ -850,6 +968,40 @@ pub(crate) mod tests {
 		assert_eq!(cht_root_2, cht_root_3);
 	}
 
+	#[test]
+	fn blocks_are_imported_on_top_of_a_checkpoint() {
+		let db = LightStorage::<Block>::new_test_with_cht_size(4);
+		let genesis_hash = insert_final_block(&db, HashMap::new(), || default_header(&Default::default(), 0));
+
+		// the checkpoint is the last block of CHT#1, and is given with the roots of CHT#0 and CHT#1.
+		let checkpoint = default_header(&Hash::random(), 8);
+		let cht_roots = vec![Hash::random(), Hash::random()];
+		assert!(db.import_checkpoint(default_header(&Hash::random(), 7), cht_roots.clone()).is_err());
+		assert!(db.import_checkpoint(checkpoint.clone(), cht_roots[..1].to_vec()).is_err());
+		db.import_checkpoint(checkpoint.clone(), cht_roots.clone()).unwrap();
+
+		let info = db.info();
+		assert_eq!(info.genesis_hash, genesis_hash);
+		assert_eq!((info.best_number, info.best_hash), (8, checkpoint.hash()));
+		assert_eq!((info.finalized_number, info.finalized_hash), (8, checkpoint.hash()));
+		assert_eq!(db.header_cht_root(4, 3).unwrap(), Some(cht_roots[0]));
+		assert_eq!(db.header_cht_root(4, 5).unwrap(), Some(cht_roots[1]));
+		assert_eq!(db.header_cht_root(4, 9).unwrap(), None);
+
+		// the CHTs given with the checkpoint are kept once the blocks after it are finalized.
+		let mut prev_hash = checkpoint.hash();
+		for number in 9..18 {
+			prev_hash = insert_block(&db, HashMap::new(), || default_header(&prev_hash, number));
+			db.finalize_header(BlockId::Hash(prev_hash)).unwrap();
+		}
+		assert_eq!(db.header_cht_root(4, 3).unwrap(), Some(cht_roots[0]));
+		assert_eq!(db.header_cht_root(4, 5).unwrap(), Some(cht_roots[1]));
+		assert!(db.header_cht_root(4, 9).unwrap().is_some());
+		assert!(db.header(BlockId::Hash(checkpoint.hash())).unwrap().is_none());
+
+		assert!(db.import_checkpoint(default_header(&Hash::random(), 16), cht_roots.clone()).is_err());
+	}
+
 	#[test]
 	fn tree_route_works() {
 		let db = LightStorage::new_test();
@@ -1126,11 +1278,21 @@ pub(crate) mod tests {
 		assert_eq!(db.header(BlockId::Hash(hash0)).unwrap().unwrap().hash(), hash0);
 
 		let db = db.db;
-		let db = LightStorage::from_kvdb(db).unwrap();
+		let db = LightStorage::from_kvdb(db, cht::size()).unwrap();
 		assert_eq!(db.info().best_hash, hash0);
 		assert_eq!(db.header(BlockId::Hash::<Block>(hash0)).unwrap().unwrap().hash(), hash0);
 	}
 
+	#[test]
+	fn database_with_other_cht_size_is_not_reopened() {
+		let db = LightStorage::new_test_with_cht_size(4);
+		insert_final_block(&db, HashMap::new(), || default_header(&Default::default(), 0));
+
+		let db = db.db;
+		assert!(LightStorage::<Block>::from_kvdb(db.clone(), 8).is_err());
+		assert!(LightStorage::<Block>::from_kvdb(db, 4).is_ok());
+	}
+
 	#[test]
 	fn aux_store_works() {
 		let db = LightStorage::<Block>::new_test();
@@ -1187,7 +1349,8 @@ pub(crate) mod tests {
 		};
 
 		// restart && check that after restart value is read from the cache
-		let db = LightStorage::<Block>::from_kvdb(storage as Arc<_>).expect("failed to create test-db");
+		let db = LightStorage::<Block>::from_kvdb(storage as Arc<_>, cht::size())
+			.expect("failed to create test-db");
 		assert_eq!(
 			db.cache().get_at(b"test", &BlockId::Number(0)).unwrap(),
 			Some(((0, genesis_hash.unwrap()), None, vec![42])),
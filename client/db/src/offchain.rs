@@ -75,6 +75,15 @@ impl sp_core::offchain::OffchainStorage for LocalStorage {
 			.map(|v| v.to_vec())
 	}
 
+	fn iter_prefix(&self, prefix: &[u8], key_prefix: &[u8]) -> Vec<(Vec<u8>, Vec<u8>)> {
+		let full_prefix: Vec<u8> = prefix.iter().chain(key_prefix).cloned().collect();
+		self.db.iter_from_prefix(columns::OFFCHAIN, &full_prefix)
+			// not every backend limits the iteration to the prefix.
+			.take_while(|(key, _)| key.starts_with(&full_prefix))
+			.map(|(key, value)| (key[prefix.len()..].to_vec(), value.to_vec()))
+			.collect()
+	}
+
 	fn compare_and_set(
 		&mut self,
 		prefix: &[u8],
@@ -145,4 +154,19 @@ mod tests {
 		assert!(storage.locks.lock().is_empty(), "Locks map should be empty!");
 	}
 
+	#[test]
+	fn should_iterate_over_keys_with_prefix() {
+		let mut storage = LocalStorage::new_test();
+		storage.set(b"prefix", b"nonce_1", b"1");
+		storage.set(b"prefix", b"nonce_2", b"2");
+		storage.set(b"prefix", b"price", b"3");
+		storage.set(b"other", b"nonce_3", b"4");
+
+		assert_eq!(storage.iter_prefix(b"prefix", b"nonce"), vec![
+			(b"nonce_1".to_vec(), b"1".to_vec()),
+			(b"nonce_2".to_vec(), b"2".to_vec()),
+		]);
+		assert_eq!(storage.iter_prefix(b"prefix", b"").len(), 3);
+		assert!(storage.iter_prefix(b"prefix", b"unknown").is_empty());
+	}
 }
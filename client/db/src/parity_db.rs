@@ -0,0 +1,475 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! ParityDB backend of the client database.
+//!
+//! ParityDB doesn't compact its storage in the background the way RocksDB does, which keeps its
+//! write latency predictable on slow disks. It is exposed through the `KeyValueDB` interface the
+//! rest of the crate is written against, with the same columns as the RocksDB database.
+//!
+//! ParityDB is a hash table, which can't iterate over the keys of a column. The sorted keys of the
+//! few columns which are iterated are kept in an additional column, and iterating over any other
+//! column is an error.
+//!
+//! The keys of a column are stored as a snapshot, followed by a log of the keys changed by each
+//! write since. The log is folded into a new snapshot once it holds as many changes as there are
+//! keys, so that a write costs the same on average whatever the size of the column.
+
+use std::collections::{BTreeSet, HashMap};
+use std::io;
+use std::path::Path;
+#[cfg(any(feature = "kvdb-rocksdb", test))]
+use std::{fs, path::PathBuf};
+
+use codec::{Encode, Decode};
+use kvdb::{DBOp, DBTransaction, DBValue, KeyValueDB};
+use log::info;
+use parking_lot::RwLock;
+use parity_util_mem::{MallocSizeOf, MallocSizeOfOps};
+
+use crate::columns;
+use crate::utils::NUM_COLUMNS;
+
+/// The columns which can be iterated over.
+const ITERABLE_COLUMNS: &[u32] = &[columns::OFFCHAIN];
+
+/// The column keeping the sorted keys of each iterable column, see `KeysEntry`.
+const KEYS_COLUMN: u32 = NUM_COLUMNS;
+
+/// Number of logged key changes of a column below which its keys are never snapshotted again.
+const MIN_SNAPSHOT_CHANGES: usize = 1024;
+
+/// Suffix of the name of the database while a migration writes it.
+#[cfg(any(feature = "kvdb-rocksdb", test))]
+const MIGRATION_SUFFIX: &str = ".migration";
+
+/// Number of key-value pairs written at once when migrating a database.
+#[cfg(any(feature = "kvdb-rocksdb", test))]
+const MIGRATION_BATCH_SIZE: usize = 10_000;
+
+/// The entries of the keys column, for the iterable column of the given index.
+#[derive(Encode)]
+enum KeysEntry {
+	/// The sorted keys of the column as of the last snapshot.
+	Snapshot(u32),
+	/// The number of writes logged since the last snapshot.
+	LogLength(u32),
+	/// The keys inserted (`true`) or removed (`false`) by the write of the given index since the
+	/// last snapshot.
+	Log(u32, u32),
+}
+
+/// The changes of the keys of a column made by a write.
+type KeyChanges = Vec<(Vec<u8>, bool)>;
+
+/// The sorted keys of an iterable column.
+#[derive(Default)]
+struct ColumnKeys {
+	keys: BTreeSet<Vec<u8>>,
+	/// Number of writes logged since the last snapshot.
+	log_length: u32,
+	/// Number of key changes logged since the last snapshot.
+	logged_changes: usize,
+}
+
+impl ColumnKeys {
+	fn apply(&mut self, changes: &KeyChanges) {
+		apply_changes(&mut self.keys, changes);
+		self.log_length += 1;
+		self.logged_changes += changes.len();
+	}
+}
+
+fn apply_changes(keys: &mut BTreeSet<Vec<u8>>, changes: &KeyChanges) {
+	for (key, inserted) in changes {
+		if *inserted {
+			keys.insert(key.clone());
+		} else {
+			keys.remove(key);
+		}
+	}
+}
+
+/// A ParityDB database.
+pub struct ParityDb {
+	db: parity_db::Db,
+	/// The sorted keys of the iterable columns.
+	keys: RwLock<HashMap<u32, ColumnKeys>>,
+}
+
+fn map_err(error: parity_db::Error) -> io::Error {
+	io::Error::new(io::ErrorKind::Other, format!("ParityDB error: {:?}", error))
+}
+
+fn read_keys_entry<T: Decode>(db: &parity_db::Db, entry: KeysEntry) -> io::Result<Option<T>> {
+	match db.get(KEYS_COLUMN as u8, &entry.encode()).map_err(map_err)? {
+		Some(encoded) => T::decode(&mut &encoded[..]).map(Some).map_err(|e| io::Error::new(
+			io::ErrorKind::InvalidData,
+			format!("Invalid keys entry: {}", e.what()),
+		)),
+		None => Ok(None),
+	}
+}
+
+impl ParityDb {
+	/// Open the database at the given path, creating it if it doesn't exist.
+	pub fn open(path: &Path) -> io::Result<Self> {
+		let options = parity_db::Options::with_columns(path, KEYS_COLUMN as u8 + 1);
+		let db = parity_db::Db::open(&options).map_err(map_err)?;
+
+		let mut keys = HashMap::new();
+		for col in ITERABLE_COLUMNS {
+			let mut col_keys = ColumnKeys {
+				keys: read_keys_entry(&db, KeysEntry::Snapshot(*col))?.unwrap_or_default(),
+				..Default::default()
+			};
+			let log_length: u32 = read_keys_entry(&db, KeysEntry::LogLength(*col))?.unwrap_or(0);
+			for index in 0..log_length {
+				let changes = read_keys_entry(&db, KeysEntry::Log(*col, index))?.ok_or_else(|| io::Error::new(
+					io::ErrorKind::InvalidData,
+					format!("Missing keys log {} of column {}", index, col),
+				))?;
+				col_keys.apply(&changes);
+			}
+			keys.insert(*col, col_keys);
+		}
+
+		Ok(ParityDb { db, keys: RwLock::new(keys) })
+	}
+
+	/// The keys of the iterable column `col` starting with `prefix`, in order.
+	fn keys_with_prefix(&self, col: u32, prefix: &[u8]) -> io::Result<Vec<Vec<u8>>> {
+		let keys = self.keys.read();
+		let col_keys = keys.get(&col).ok_or_else(|| io::Error::new(
+			io::ErrorKind::Other,
+			format!("ParityDB can't iterate over the keys of column {}, which isn't iterable", col),
+		))?;
+		Ok(col_keys.keys.range(prefix.to_vec()..)
+			.take_while(|key| key.starts_with(prefix))
+			.cloned()
+			.collect())
+	}
+
+	/// Iterate over the key-value pairs of the iterable column `col` whose key starts with
+	/// `prefix`, in order.
+	///
+	/// Returns an error if the column isn't iterable.
+	pub fn try_iter_from_prefix<'a>(
+		&'a self,
+		col: u32,
+		prefix: &[u8],
+	) -> io::Result<Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a>> {
+		self.keys_with_prefix(col, prefix).map(|keys| self.iter_keys(col, keys))
+	}
+
+	fn iter_keys<'a>(
+		&'a self,
+		col: u32,
+		keys: Vec<Vec<u8>>,
+	) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a> {
+		Box::new(keys.into_iter().filter_map(move |key| {
+			// the value may have been removed since the keys were read.
+			let value = self.db.get(col as u8, &key).ok()??;
+			Some((key.into_boxed_slice(), value.into_boxed_slice()))
+		}))
+	}
+}
+
+impl MallocSizeOf for ParityDb {
+	fn size_of(&self, _ops: &mut MallocSizeOfOps) -> usize {
+		// the memory used by ParityDB isn't tracked.
+		0
+	}
+}
+
+impl KeyValueDB for ParityDb {
+	fn get(&self, col: u32, key: &[u8]) -> io::Result<Option<DBValue>> {
+		self.db.get(col as u8, key).map_err(map_err)
+	}
+
+	fn get_by_prefix(&self, col: u32, prefix: &[u8]) -> Option<Box<[u8]>> {
+		self.iter_from_prefix(col, prefix).next().map(|(_, value)| value)
+	}
+
+	fn write_buffered(&self, transaction: DBTransaction) {
+		if let Err(e) = self.write(transaction) {
+			log::error!(target: "db", "Failed to write to ParityDB: {}", e);
+		}
+	}
+
+	fn write(&self, transaction: DBTransaction) -> io::Result<()> {
+		// the keys are locked until the changes are committed, so that they are written in order.
+		let mut keys = self.keys.write();
+		let mut key_changes: HashMap<u32, KeyChanges> = HashMap::new();
+		let mut changes: Vec<_> = transaction.ops.into_iter().map(|op| {
+			let (col, key, value) = match op {
+				DBOp::Insert { col, key, value } => (col, key.to_vec(), Some(value)),
+				DBOp::Delete { col, key } => (col, key.to_vec(), None),
+			};
+			if keys.contains_key(&col) {
+				key_changes.entry(col).or_default().push((key.clone(), value.is_some()));
+			}
+			(col as u8, key, value)
+		}).collect();
+
+		let mut snapshots = HashMap::new();
+		for (col, col_changes) in &key_changes {
+			let col_keys = &keys[col];
+			let logged_changes = col_keys.logged_changes + col_changes.len();
+			if logged_changes > col_keys.keys.len().max(MIN_SNAPSHOT_CHANGES) {
+				// writing all the keys costs as much as the changes logged since the last snapshot.
+				let mut snapshot = col_keys.keys.clone();
+				apply_changes(&mut snapshot, col_changes);
+				changes.push((KEYS_COLUMN as u8, KeysEntry::Snapshot(*col).encode(), Some(snapshot.encode())));
+				changes.push((KEYS_COLUMN as u8, KeysEntry::LogLength(*col).encode(), None));
+				changes.extend((0..col_keys.log_length)
+					.map(|index| (KEYS_COLUMN as u8, KeysEntry::Log(*col, index).encode(), None)));
+				snapshots.insert(*col, snapshot);
+			} else {
+				changes.push((
+					KEYS_COLUMN as u8,
+					KeysEntry::Log(*col, col_keys.log_length).encode(),
+					Some(col_changes.encode()),
+				));
+				changes.push((
+					KEYS_COLUMN as u8,
+					KeysEntry::LogLength(*col).encode(),
+					Some((col_keys.log_length + 1).encode()),
+				));
+			}
+		}
+
+		self.db.commit(changes).map_err(map_err)?;
+		for (col, col_changes) in key_changes {
+			let col_keys = keys.get_mut(&col).expect("only the keys of iterable columns are changed; qed");
+			match snapshots.remove(&col) {
+				Some(snapshot) => *col_keys = ColumnKeys { keys: snapshot, ..Default::default() },
+				None => col_keys.apply(&col_changes),
+			}
+		}
+		Ok(())
+	}
+
+	fn flush(&self) -> io::Result<()> {
+		// every write is committed right away.
+		Ok(())
+	}
+
+	fn iter<'a>(&'a self, col: u32) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a> {
+		self.iter_from_prefix(col, &[])
+	}
+
+	fn iter_from_prefix<'a>(
+		&'a self,
+		col: u32,
+		prefix: &'a [u8],
+	) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a> {
+		// `KeyValueDB` can't return an error here, the column is reported as empty instead.
+		self.try_iter_from_prefix(col, prefix).unwrap_or_else(|e| {
+			log::error!(target: "db", "{}", e);
+			Box::new(std::iter::empty())
+		})
+	}
+
+	fn restore(&self, _new_db: &str) -> io::Result<()> {
+		Err(io::Error::new(io::ErrorKind::Other, "Restoring is not supported by ParityDB"))
+	}
+}
+
+/// Returns the path the database at `path` is written to while being migrated.
+#[cfg(any(feature = "kvdb-rocksdb", test))]
+fn migration_path(path: &Path) -> PathBuf {
+	let mut file_name = path.file_name().map(|name| name.to_owned()).unwrap_or_default();
+	file_name.push(MIGRATION_SUFFIX);
+	path.with_file_name(file_name)
+}
+
+/// Copy the content of the RocksDB database at `rocksdb_path` into a new ParityDB database at
+/// `paritydb_path`.
+///
+/// The new database is written next to `paritydb_path`, and only moved there once complete, so
+/// that an interrupted migration is started over.
+///
+/// The RocksDB database is left untouched, it can be removed once the node runs fine on the new
+/// database.
+#[cfg(any(feature = "kvdb-rocksdb", test))]
+pub fn migrate_from_rocksdb(rocksdb_path: &Path, paritydb_path: &Path) -> io::Result<()> {
+	let migration_path = migration_path(paritydb_path);
+	if migration_path.exists() {
+		fs::remove_dir_all(&migration_path)?;
+	}
+	copy_from_rocksdb(rocksdb_path, &migration_path)?;
+	fs::rename(&migration_path, paritydb_path)
+}
+
+#[cfg(any(feature = "kvdb-rocksdb", test))]
+fn copy_from_rocksdb(rocksdb_path: &Path, paritydb_path: &Path) -> io::Result<()> {
+	let rocksdb_path_str = rocksdb_path.to_str()
+		.ok_or_else(|| io::Error::new(io::ErrorKind::Other, "Invalid database path"))?;
+	let rocksdb = kvdb_rocksdb::Database::open(
+		&kvdb_rocksdb::DatabaseConfig::with_columns(NUM_COLUMNS),
+		rocksdb_path_str,
+	)?;
+	let paritydb = ParityDb::open(paritydb_path)?;
+
+	info!(
+		"Migrating the database at {} to ParityDB at {}",
+		rocksdb_path.display(),
+		paritydb_path.display(),
+	);
+
+	for col in 0..NUM_COLUMNS {
+		let mut pairs = rocksdb.iter(col).peekable();
+		let mut migrated = 0;
+		while pairs.peek().is_some() {
+			let mut transaction = DBTransaction::new();
+			for (key, value) in pairs.by_ref().take(MIGRATION_BATCH_SIZE) {
+				transaction.put_vec(col, &key, value.into_vec());
+				migrated += 1;
+			}
+			paritydb.write(transaction)?;
+		}
+		info!("Migrated {} entries of column {}", migrated, col);
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn writes_and_reads_back_the_columns() {
+		let dir = tempfile::tempdir().unwrap();
+		let db = ParityDb::open(dir.path()).unwrap();
+
+		let mut transaction = DBTransaction::new();
+		transaction.put(1, b"key", b"state");
+		transaction.put(2, b"key", b"meta");
+		db.write(transaction).unwrap();
+
+		assert_eq!(db.get(1, b"key").unwrap(), Some(b"state".to_vec()));
+		assert_eq!(db.get(2, b"key").unwrap(), Some(b"meta".to_vec()));
+
+		let mut transaction = DBTransaction::new();
+		transaction.delete(1, b"key");
+		db.write(transaction).unwrap();
+
+		assert_eq!(db.get(1, b"key").unwrap(), None);
+		assert_eq!(db.get(2, b"key").unwrap(), Some(b"meta".to_vec()));
+	}
+
+	fn pairs<'a>(iter: Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a>) -> Vec<(Vec<u8>, Vec<u8>)> {
+		iter.map(|(key, value)| (key.into_vec(), value.into_vec())).collect()
+	}
+
+	#[test]
+	fn iterates_over_the_iterable_columns_in_order() {
+		let dir = tempfile::tempdir().unwrap();
+		{
+			let db = ParityDb::open(dir.path()).unwrap();
+			let mut transaction = DBTransaction::new();
+			transaction.put(columns::OFFCHAIN, b"prefix_b", b"2");
+			transaction.put(columns::OFFCHAIN, b"prefix_a", b"1");
+			transaction.put(columns::OFFCHAIN, b"other", b"3");
+			db.write(transaction).unwrap();
+			let mut transaction = DBTransaction::new();
+			transaction.delete(columns::OFFCHAIN, b"other");
+			transaction.put(columns::OFFCHAIN, b"prefix_c", b"4");
+			db.write(transaction).unwrap();
+		}
+
+		// the keys are kept along with the database.
+		let db = ParityDb::open(dir.path()).unwrap();
+		assert_eq!(pairs(db.iter(columns::OFFCHAIN)), vec![
+			(b"prefix_a".to_vec(), b"1".to_vec()),
+			(b"prefix_b".to_vec(), b"2".to_vec()),
+			(b"prefix_c".to_vec(), b"4".to_vec()),
+		]);
+		assert_eq!(pairs(db.iter_from_prefix(columns::OFFCHAIN, b"prefix_b")), vec![
+			(b"prefix_b".to_vec(), b"2".to_vec()),
+		]);
+		assert_eq!(db.get_by_prefix(columns::OFFCHAIN, b"prefix"), Some(b"1".to_vec().into_boxed_slice()));
+		assert_eq!(db.get_by_prefix(columns::OFFCHAIN, b"other"), None);
+	}
+
+	#[test]
+	fn keeps_the_keys_across_snapshots() {
+		let dir = tempfile::tempdir().unwrap();
+		let key = |i: usize| format!("key_{:05}", i).into_bytes();
+		{
+			let db = ParityDb::open(dir.path()).unwrap();
+			for i in 0..MIN_SNAPSHOT_CHANGES + 10 {
+				let mut transaction = DBTransaction::new();
+				transaction.put(columns::OFFCHAIN, &key(i), b"value");
+				if i % 2 == 1 {
+					transaction.delete(columns::OFFCHAIN, &key(i - 1));
+				}
+				db.write(transaction).unwrap();
+			}
+			// the log has been folded into a snapshot.
+			assert!(db.keys.read()[&columns::OFFCHAIN].logged_changes < MIN_SNAPSHOT_CHANGES);
+		}
+
+		let db = ParityDb::open(dir.path()).unwrap();
+		let expected: Vec<_> = (0..MIN_SNAPSHOT_CHANGES + 10)
+			.filter(|i| i % 2 == 1)
+			.map(|i| (key(i), b"value".to_vec()))
+			.collect();
+		assert_eq!(pairs(db.iter(columns::OFFCHAIN)), expected);
+	}
+
+	#[test]
+	fn iterating_over_other_columns_is_an_error() {
+		let dir = tempfile::tempdir().unwrap();
+		let db = ParityDb::open(dir.path()).unwrap();
+		let mut transaction = DBTransaction::new();
+		transaction.put(columns::HEADER, b"header", b"value");
+		db.write(transaction).unwrap();
+
+		assert!(db.try_iter_from_prefix(columns::HEADER, &[]).is_err());
+		assert_eq!(db.iter(columns::HEADER).count(), 0);
+		assert_eq!(db.get_by_prefix(columns::HEADER, b"header"), None);
+	}
+
+	#[test]
+	fn migrates_a_rocksdb_database() {
+		let dir = tempfile::tempdir().unwrap();
+		let rocksdb_path = dir.path().join("db");
+		let paritydb_path = dir.path().join("paritydb");
+		{
+			let rocksdb = kvdb_rocksdb::Database::open(
+				&kvdb_rocksdb::DatabaseConfig::with_columns(NUM_COLUMNS),
+				rocksdb_path.to_str().unwrap(),
+			).unwrap();
+			let mut transaction = DBTransaction::new();
+			transaction.put(crate::columns::HEADER, b"header", b"value");
+			rocksdb.write(transaction).unwrap();
+		}
+
+		// a previous migration was interrupted.
+		fs::create_dir_all(migration_path(&paritydb_path)).unwrap();
+		fs::write(migration_path(&paritydb_path).join("partial"), b"").unwrap();
+
+		migrate_from_rocksdb(&rocksdb_path, &paritydb_path).unwrap();
+		assert!(!migration_path(&paritydb_path).exists());
+
+		let db = ParityDb::open(&paritydb_path).unwrap();
+		assert_eq!(db.get(crate::columns::HEADER, b"header").unwrap(), Some(b"value".to_vec()));
+		assert!(!paritydb_path.join("partial").exists());
+	}
+}
@@ -0,0 +1,234 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Cache of the trie nodes, shared by the states of all the blocks.
+//!
+//! Trie nodes are stored under their hash, so a cached node is valid for every state that
+//! references it. The nodes read while importing a block are first kept in a layer local to
+//! the import and only make it to the shared cache once the block becomes the best block,
+//! so that importing forks that are never followed doesn't evict the nodes of the best chain.
+
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use hash_db::{Hasher, Prefix};
+use linked_hash_map::LinkedHashMap;
+use parking_lot::{Mutex, RwLock};
+use sp_state_machine::DBValue;
+use sp_trie::prefixed_key;
+
+/// Trie node cache shared between all the states.
+pub type SharedTrieNodeCache = Arc<Mutex<TrieNodeCache>>;
+
+/// Create a new shared trie node cache, using at most `cache_size` bytes.
+pub fn new_shared_trie_node_cache(cache_size: usize) -> SharedTrieNodeCache {
+	Arc::new(Mutex::new(TrieNodeCache {
+		nodes: LinkedHashMap::new(),
+		used_size: 0,
+		limit: cache_size,
+	}))
+}
+
+/// Least recently used cache of the trie nodes, by prefixed key.
+pub struct TrieNodeCache {
+	nodes: LinkedHashMap<Vec<u8>, DBValue>,
+	used_size: usize,
+	limit: usize,
+}
+
+impl TrieNodeCache {
+	fn get(&mut self, key: &[u8]) -> Option<DBValue> {
+		self.nodes.get_refresh(key).map(|node| node.clone())
+	}
+
+	fn insert(&mut self, key: Vec<u8>, node: DBValue) {
+		let size = key.len() + node.len();
+		if size > self.limit {
+			return;
+		}
+
+		self.used_size += size;
+		if let Some(old_node) = self.nodes.insert(key.clone(), node) {
+			self.used_size -= key.len() + old_node.len();
+		}
+
+		while self.used_size > self.limit {
+			match self.nodes.pop_front() {
+				Some((key, node)) => self.used_size -= key.len() + node.len(),
+				None => break,
+			}
+		}
+	}
+
+	/// Size of the cached nodes, in bytes.
+	pub fn used_size(&self) -> usize {
+		self.used_size
+	}
+}
+
+/// Trie node storage reading through the shared trie node cache.
+///
+/// When `staged`, the nodes missing from the shared cache are kept in a local layer until
+/// `commit` is called. Otherwise, they are added to the shared cache as soon as they are read.
+pub struct CachingTrieStorage<H, S> {
+	storage: Arc<S>,
+	shared_cache: SharedTrieNodeCache,
+	staged: Option<RwLock<HashMap<Vec<u8>, DBValue>>>,
+	_hasher: PhantomData<H>,
+}
+
+impl<H: Hasher, S: sp_state_machine::Storage<H>> CachingTrieStorage<H, S> {
+	/// Create a new storage reading the nodes missing from the cache from `storage`.
+	pub fn new(storage: Arc<S>, shared_cache: SharedTrieNodeCache, staged: bool) -> Self {
+		CachingTrieStorage {
+			storage,
+			shared_cache,
+			staged: if staged { Some(Default::default()) } else { None },
+			_hasher: PhantomData,
+		}
+	}
+
+	/// Stage a node written by the block being imported, as it is likely to be read again when
+	/// importing its children.
+	pub fn stage(&self, key: Vec<u8>, node: DBValue) {
+		match self.staged {
+			Some(ref staged) => { staged.write().insert(key, node); },
+			None => self.shared_cache.lock().insert(key, node),
+		}
+	}
+
+	/// Move the staged nodes to the shared cache if the block is now the best block, or
+	/// discard them otherwise.
+	pub fn commit(&self, is_best: bool) {
+		let staged = match self.staged {
+			Some(ref staged) => std::mem::replace(&mut *staged.write(), HashMap::new()),
+			None => return,
+		};
+
+		if is_best {
+			let mut shared_cache = self.shared_cache.lock();
+			for (key, node) in staged {
+				shared_cache.insert(key, node);
+			}
+		}
+	}
+}
+
+impl<H, S> sp_state_machine::Storage<H> for CachingTrieStorage<H, S>
+	where
+		H: Hasher,
+		S: sp_state_machine::Storage<H>,
+{
+	fn get(&self, key: &H::Out, prefix: Prefix) -> Result<Option<DBValue>, String> {
+		let cache_key = prefixed_key::<H>(key, prefix);
+		if let Some(ref staged) = self.staged {
+			if let Some(node) = staged.read().get(&cache_key) {
+				return Ok(Some(node.clone()));
+			}
+		}
+		if let Some(node) = self.shared_cache.lock().get(&cache_key) {
+			return Ok(Some(node));
+		}
+
+		let node = self.storage.get(key, prefix)?;
+		if let Some(ref node) = node {
+			self.stage(cache_key, node.clone());
+		}
+		Ok(node)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use hash_db::EMPTY_PREFIX;
+	use sp_core::{Blake2Hasher, H256};
+
+	#[derive(Default)]
+	struct CountingStorage {
+		nodes: HashMap<H256, DBValue>,
+		reads: AtomicUsize,
+	}
+
+	impl sp_state_machine::Storage<Blake2Hasher> for CountingStorage {
+		fn get(&self, key: &H256, _prefix: Prefix) -> Result<Option<DBValue>, String> {
+			self.reads.fetch_add(1, Ordering::SeqCst);
+			Ok(self.nodes.get(key).cloned())
+		}
+	}
+
+	fn storage_with_nodes(nodes: &[(H256, DBValue)]) -> Arc<CountingStorage> {
+		Arc::new(CountingStorage {
+			nodes: nodes.iter().cloned().collect(),
+			reads: AtomicUsize::new(0),
+		})
+	}
+
+	#[test]
+	fn nodes_are_only_read_once_from_the_storage() {
+		let key = H256::repeat_byte(1);
+		let storage = storage_with_nodes(&[(key, vec![1, 2, 3])]);
+		let shared_cache = new_shared_trie_node_cache(1024);
+
+		let first = CachingTrieStorage::new(storage.clone(), shared_cache.clone(), false);
+		let second = CachingTrieStorage::new(storage.clone(), shared_cache.clone(), false);
+		assert_eq!(first.get(&key, EMPTY_PREFIX).unwrap(), Some(vec![1, 2, 3]));
+		assert_eq!(second.get(&key, EMPTY_PREFIX).unwrap(), Some(vec![1, 2, 3]));
+		assert_eq!(first.get(&H256::repeat_byte(2), EMPTY_PREFIX).unwrap(), None);
+
+		assert_eq!(storage.reads.load(Ordering::SeqCst), 2);
+	}
+
+	#[test]
+	fn staged_nodes_are_only_shared_when_the_block_is_the_best() {
+		let key = H256::repeat_byte(1);
+		let storage = storage_with_nodes(&[(key, vec![1, 2, 3])]);
+		let shared_cache = new_shared_trie_node_cache(1024);
+
+		let fork = CachingTrieStorage::new(storage.clone(), shared_cache.clone(), true);
+		fork.get(&key, EMPTY_PREFIX).unwrap();
+		fork.get(&key, EMPTY_PREFIX).unwrap();
+		assert_eq!(storage.reads.load(Ordering::SeqCst), 1);
+		fork.commit(false);
+		assert_eq!(shared_cache.lock().used_size(), 0);
+
+		let best = CachingTrieStorage::new(storage.clone(), shared_cache.clone(), true);
+		best.get(&key, EMPTY_PREFIX).unwrap();
+		best.stage(vec![4], vec![5, 6]);
+		best.commit(true);
+		assert_eq!(shared_cache.lock().used_size(), 32 + 3 + 1 + 2);
+		assert_eq!(storage.reads.load(Ordering::SeqCst), 2);
+	}
+
+	#[test]
+	fn least_recently_used_nodes_are_evicted() {
+		let shared_cache = new_shared_trie_node_cache(10);
+		let mut cache = shared_cache.lock();
+		cache.insert(vec![1], vec![0; 4]);
+		cache.insert(vec![2], vec![0; 4]);
+		assert!(cache.get(&[1]).is_some());
+		cache.insert(vec![3], vec![0; 4]);
+
+		assert!(cache.get(&[1]).is_some());
+		assert!(cache.get(&[2]).is_none());
+		assert!(cache.get(&[3]).is_some());
+		assert_eq!(cache.used_size(), 10);
+
+		cache.insert(vec![4], vec![0; 10]);
+		assert!(cache.get(&[4]).is_none());
+	}
+}
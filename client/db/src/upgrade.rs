@@ -15,14 +15,23 @@
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
 //! Database upgrade logic.
+//!
+//! The version of the on-disk format is kept in a file next to the database. On startup, the
+//! migrations from that version to the current one are run in order, and the version is bumped
+//! after each of them. A copy of the database is kept while a migration runs, so that it can be
+//! restored should the migration fail or the node be stopped in the middle of it.
+//!
+//! The copy is first written to a temporary directory, which is only given the name of the backup
+//! once complete, so that a copy interrupted halfway is never mistaken for a backup.
 
 use std::fs;
-use std::io::{Read, Write, ErrorKind};
+use std::io::{self, Read, Write, ErrorKind};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use codec::Encode;
 use kvdb_rocksdb::{Database, DatabaseConfig};
+use log::{info, warn};
 use parking_lot::RwLock;
 use sp_blockchain::{well_known_cache_keys, Cache};
 use sp_core::ChangesTrieConfiguration;
@@ -35,22 +44,155 @@ use crate::{
 /// Version file name.
 const VERSION_FILE_NAME: &'static str = "db_version";
 
+/// Suffix of the name of the copy of the database kept while it is being upgraded.
+const BACKUP_SUFFIX: &'static str = ".upgrade-backup";
+
+/// Suffix of the name of the copy of the database while it is being written.
+const BACKUP_TMP_SUFFIX: &'static str = ".upgrade-backup.tmp";
+
+/// Name of the file marking a backup as complete.
+const BACKUP_COMPLETE_FILE_NAME: &'static str = "upgrade-backup-complete";
+
 /// Current db version.
 const CURRENT_VERSION: u32 = 1;
 
 /// Number of columns in v0.
 const V0_NUM_COLUMNS: u32 = 10;
 
+/// A step of the database upgrade.
+struct Migration {
+	/// The version the migration upgrades from, to the next one.
+	from: u32,
+	/// What the migration does.
+	description: &'static str,
+	/// Run the migration on the database at the given path.
+	run: fn(&Path, DatabaseType) -> sp_blockchain::Result<()>,
+}
+
+/// All the migrations, by version.
+fn migrations<Block: BlockT>() -> Vec<Migration> {
+	vec![
+		Migration {
+			from: 0,
+			description: "add the cache column and cache the changes tries configuration",
+			run: migrate_0_to_1::<Block>,
+		},
+	]
+}
+
 /// Upgrade database to current version.
 pub fn upgrade_db<Block: BlockT>(db_path: &Path, db_type: DatabaseType) -> sp_blockchain::Result<()> {
+	restore_interrupted_upgrade(db_path)?;
+
 	let db_version = current_version(db_path)?;
-	match db_version {
-		0 => migrate_0_to_1::<Block>(db_path, db_type)?,
-		1 => (),
-		_ => Err(sp_blockchain::Error::Backend(format!("Future database version: {}", db_version)))?,
+	if db_version > CURRENT_VERSION {
+		return Err(
+			sp_blockchain::Error::Backend(format!("Future database version: {}", db_version))
+		);
 	}
 
-	update_version(db_path)
+	let migrations = migrations::<Block>();
+	let pending: Vec<_> = migrations.iter()
+		.filter(|migration| migration.from >= db_version)
+		.collect();
+	if !pending.is_empty() {
+		info!("Upgrading database from version {} to {}", db_version, CURRENT_VERSION);
+		for (index, migration) in pending.iter().enumerate() {
+			info!(
+				"Database upgrade step {}/{}: {}",
+				index + 1,
+				pending.len(),
+				migration.description,
+			);
+			run_migration(db_path, db_type, migration)?;
+		}
+		info!("Database upgraded to version {}", CURRENT_VERSION);
+	}
+
+	update_version(db_path, CURRENT_VERSION)
+}
+
+/// Run the migration, restoring the database as it was before if it fails.
+fn run_migration(
+	db_path: &Path,
+	db_type: DatabaseType,
+	migration: &Migration,
+) -> sp_blockchain::Result<()> {
+	let backup_path = backup_path(db_path);
+	if db_path.exists() {
+		write_backup(db_path).map_err(db_err)?;
+	}
+
+	let result = (migration.run)(db_path, db_type)
+		.and_then(|_| update_version(db_path, migration.from + 1));
+	match result {
+		Ok(()) => {
+			if backup_path.exists() {
+				fs::remove_dir_all(&backup_path).map_err(db_err)?;
+			}
+			Ok(())
+		},
+		Err(e) => {
+			warn!("Database upgrade from version {} failed: {:?}", migration.from, e);
+			restore_backup(db_path)?;
+			Err(e)
+		},
+	}
+}
+
+/// Copy the database to a temporary directory, then give it the name of the backup once the copy
+/// is complete and synced to the disk.
+fn write_backup(db_path: &Path) -> io::Result<()> {
+	let tmp_path = backup_tmp_path(db_path);
+	if tmp_path.exists() {
+		fs::remove_dir_all(&tmp_path)?;
+	}
+	copy_dir(db_path, &tmp_path)?;
+	fs::File::create(tmp_path.join(BACKUP_COMPLETE_FILE_NAME))?.sync_all()?;
+	sync_dir(&tmp_path)?;
+	fs::rename(&tmp_path, backup_path(db_path))?;
+	if let Some(parent) = db_path.parent() {
+		sync_dir(parent)?;
+	}
+	Ok(())
+}
+
+/// Whether there is a complete backup of the database.
+fn has_complete_backup(db_path: &Path) -> bool {
+	backup_path(db_path).join(BACKUP_COMPLETE_FILE_NAME).exists()
+}
+
+/// Restore the database from the backup left by an upgrade that was interrupted, if any.
+///
+/// A backup whose copy was interrupted is removed, leaving the database as it is, since the
+/// migration didn't start yet.
+fn restore_interrupted_upgrade(db_path: &Path) -> sp_blockchain::Result<()> {
+	let tmp_path = backup_tmp_path(db_path);
+	if tmp_path.exists() {
+		warn!("Removing the incomplete backup of an interrupted database upgrade");
+		fs::remove_dir_all(&tmp_path).map_err(db_err)?;
+	}
+	let backup_path = backup_path(db_path);
+	if has_complete_backup(db_path) {
+		warn!("Restoring the database as it was before an interrupted upgrade");
+		restore_backup(db_path)?;
+	} else if backup_path.exists() {
+		warn!("Removing the incomplete backup of an interrupted database upgrade");
+		fs::remove_dir_all(&backup_path).map_err(db_err)?;
+	}
+	Ok(())
+}
+
+/// Replace the database with its backup, or remove it if there is no complete backup.
+fn restore_backup(db_path: &Path) -> sp_blockchain::Result<()> {
+	if db_path.exists() {
+		fs::remove_dir_all(db_path).map_err(db_err)?;
+	}
+	if has_complete_backup(db_path) {
+		fs::rename(backup_path(db_path), db_path).map_err(db_err)?;
+		fs::remove_file(db_path.join(BACKUP_COMPLETE_FILE_NAME)).map_err(db_err)?;
+	}
+	Ok(())
 }
 
 /// Migration from version0 to version1:
@@ -128,12 +270,20 @@ fn open_database(db_path: &Path, db_type: DatabaseType, db_columns: u32) -> sp_b
 	Ok(db)
 }
 
-/// Writes current database version to the file.
-/// Creates a new file if the version file does not exist yet.
-fn update_version(path: &Path) -> sp_blockchain::Result<()> {
+/// Writes the given database version to the file.
+///
+/// The version is written to a temporary file first, so that the version file is either the
+/// previous one or the new one if the node is stopped while writing it.
+fn update_version(path: &Path, version: u32) -> sp_blockchain::Result<()> {
 	fs::create_dir_all(path).map_err(db_err)?;
-	let mut file = fs::File::create(version_file_path(path)).map_err(db_err)?;
-	file.write_all(format!("{}", CURRENT_VERSION).as_bytes()).map_err(db_err)?;
+	let version_file_path = version_file_path(path);
+	let tmp_file_path = version_file_path.with_extension("tmp");
+	{
+		let mut file = fs::File::create(&tmp_file_path).map_err(db_err)?;
+		file.write_all(format!("{}", version).as_bytes()).map_err(db_err)?;
+		file.sync_all().map_err(db_err)?;
+	}
+	fs::rename(tmp_file_path, version_file_path).map_err(db_err)?;
 	Ok(())
 }
 
@@ -144,10 +294,54 @@ fn version_file_path(path: &Path) -> PathBuf {
 	file_path
 }
 
+/// Returns the path of the copy of the database kept while it is being upgraded.
+fn backup_path(path: &Path) -> PathBuf {
+	sibling_path(path, BACKUP_SUFFIX)
+}
+
+/// Returns the path of the copy of the database while it is being written.
+fn backup_tmp_path(path: &Path) -> PathBuf {
+	sibling_path(path, BACKUP_TMP_SUFFIX)
+}
+
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+	let mut file_name = path.file_name().map(|name| name.to_owned()).unwrap_or_default();
+	file_name.push(suffix);
+	path.with_file_name(file_name)
+}
+
+/// Copy the directory and its content, syncing the copied files to the disk.
+fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+	fs::create_dir_all(to)?;
+	for entry in fs::read_dir(from)? {
+		let entry = entry?;
+		let target = to.join(entry.file_name());
+		if entry.file_type()?.is_dir() {
+			copy_dir(&entry.path(), &target)?;
+		} else {
+			fs::copy(entry.path(), &target)?;
+			fs::File::open(&target)?.sync_all()?;
+		}
+	}
+	sync_dir(to)
+}
+
+/// Sync the entries of the directory to the disk.
+#[cfg(unix)]
+fn sync_dir(path: &Path) -> io::Result<()> {
+	fs::File::open(path)?.sync_all()
+}
+
+/// Sync the entries of the directory to the disk, which isn't supported on this platform.
+#[cfg(not(unix))]
+fn sync_dir(_path: &Path) -> io::Result<()> {
+	Ok(())
+}
+
 #[cfg(test)]
 mod tests {
 	use sc_state_db::PruningMode;
-	use crate::{DatabaseSettings, DatabaseSettingsSrc};
+	use crate::{DatabaseSettings, DatabaseSettingsSrc, KeepBlocks};
 	use crate::tests::Block;
 	use super::*;
 
@@ -165,7 +359,10 @@ mod tests {
 		crate::utils::open_database::<Block>(&DatabaseSettings {
 			state_cache_size: 0,
 			state_cache_child_ratio: None,
+			trie_cache_size: 0,
 			pruning: PruningMode::ArchiveAll,
+			keep_blocks: KeepBlocks::All,
+			cht_size: sc_client::cht::size(),
 			source: DatabaseSettingsSrc::Path { path: db_path.to_owned(), cache_size: None },
 		}, DatabaseType::Full).map(|_| ())
 	}
@@ -185,6 +382,63 @@ mod tests {
 		assert_eq!(current_version(db_dir.path()).unwrap(), CURRENT_VERSION);
 	}
 
+	#[test]
+	fn failed_migration_is_rolled_back() {
+		let db_dir = tempfile::TempDir::new().unwrap();
+		let db_path = db_dir.path().join("db");
+		create_db(&db_path, Some(0));
+
+		let migration = Migration {
+			from: 0,
+			description: "fail",
+			run: |db_path, _| {
+				fs::remove_file(version_file_path(db_path)).unwrap();
+				Err(sp_blockchain::Error::Backend("Migration failed".into()))
+			},
+		};
+		assert!(run_migration(&db_path, DatabaseType::Full, &migration).is_err());
+		assert_eq!(current_version(&db_path).unwrap(), 0);
+		assert!(version_file_path(&db_path).exists());
+		assert!(!backup_path(&db_path).exists());
+	}
+
+	#[test]
+	fn interrupted_upgrade_is_restored() {
+		let db_dir = tempfile::TempDir::new().unwrap();
+		let db_path = db_dir.path().join("db");
+		create_db(&db_path, Some(0));
+		write_backup(&db_path).unwrap();
+		update_version(&db_path, CURRENT_VERSION + 1).unwrap();
+
+		open_database(&db_path).unwrap();
+		assert_eq!(current_version(&db_path).unwrap(), CURRENT_VERSION);
+		assert!(!backup_path(&db_path).exists());
+	}
+
+	#[test]
+	fn interrupted_backup_is_discarded() {
+		let db_dir = tempfile::TempDir::new().unwrap();
+		let db_path = db_dir.path().join("db");
+		create_db(&db_path, Some(0));
+		// The node was stopped while copying the database, before the copy was renamed.
+		fs::create_dir_all(backup_tmp_path(&db_path)).unwrap();
+		fs::File::create(backup_tmp_path(&db_path).join("partial")).unwrap();
+
+		restore_interrupted_upgrade(&db_path).unwrap();
+		assert!(!backup_tmp_path(&db_path).exists());
+		assert!(!backup_path(&db_path).exists());
+		assert_eq!(current_version(&db_path).unwrap(), 0);
+
+		// A backup lacking the completion marker isn't restored either.
+		fs::create_dir_all(backup_path(&db_path)).unwrap();
+		restore_interrupted_upgrade(&db_path).unwrap();
+		assert!(!backup_path(&db_path).exists());
+		assert_eq!(current_version(&db_path).unwrap(), 0);
+
+		open_database(&db_path).unwrap();
+		assert_eq!(current_version(&db_path).unwrap(), CURRENT_VERSION);
+	}
+
 	#[test]
 	fn upgrade_from_0_to_1_works() {
 		for version_from_file in &[None, Some(0)] {
@@ -36,7 +36,7 @@ use crate::{DatabaseSettings, DatabaseSettingsSrc};
 
 /// Number of columns in the db. Must be the same for both full && light dbs.
 /// Otherwise RocksDb will fail to open database && check its type.
-#[cfg(any(feature = "kvdb-rocksdb", feature = "test-helpers", test))]
+#[cfg(any(feature = "kvdb-rocksdb", feature = "parity-db", feature = "test-helpers", test))]
 pub const NUM_COLUMNS: u32 = 11;
 /// Meta column. The set of keys in the column is shared by full && light storages.
 pub const COLUMN_META: u32 = 0;
@@ -59,6 +59,8 @@ pub mod meta_keys {
 	pub const LEAF_PREFIX: &[u8; 4] = b"leaf";
 	/// Children prefix list key.
 	pub const CHILDREN_PREFIX: &[u8; 8] = b"children";
+	/// Size of the CHTs of the light client database.
+	pub const CHT_SIZE: &[u8; 7] = b"chtsize";
 }
 
 /// Database metadata.
@@ -214,7 +216,7 @@ pub fn db_err(err: io::Error) -> sp_blockchain::Error {
 	sp_blockchain::Error::Backend(format!("{}", err))
 }
 
-/// Open RocksDB database.
+/// Open the database of the client.
 pub fn open_database<Block: BlockT>(
 	config: &DatabaseSettings,
 	db_type: DatabaseType,
@@ -252,6 +254,14 @@ pub fn open_database<Block: BlockT>(
 			let msg = "Try to open RocksDB database with RocksDB disabled".into();
 			return Err(sp_blockchain::Error::Backend(msg));
 		},
+		#[cfg(feature = "parity-db")]
+		DatabaseSettingsSrc::ParityDb { path } =>
+			Arc::new(crate::parity_db::ParityDb::open(&path).map_err(db_err)?),
+		#[cfg(not(feature = "parity-db"))]
+		DatabaseSettingsSrc::ParityDb { .. } => {
+			let msg = "Try to open ParityDB database with ParityDB disabled".into();
+			return Err(sp_blockchain::Error::Backend(msg));
+		},
 		DatabaseSettingsSrc::Custom(db) => db.clone(),
 	};
 
@@ -41,7 +41,11 @@ pub fn with_externalities_safe<F, U>(ext: &mut dyn Externalities, f: F) -> Resul
 			// Substrate uses custom panic hook that terminates process on panic. Disable
 			// termination for the native call.
 			let _guard = sp_panic_handler::AbortGuard::force_unwind();
-			std::panic::catch_unwind(f).map_err(|e| {
+			// The spans a runtime that panicked or trapped didn't exit are exited once it returns.
+			let entered_spans = sp_tracing::proxy::entered_spans();
+			let result = std::panic::catch_unwind(f);
+			sp_tracing::proxy::exit_spans_after(entered_spans);
+			result.map_err(|e| {
 				if let Some(err) = e.downcast_ref::<String>() {
 					Error::RuntimePanicked(err.clone())
 				} else if let Some(err) = e.downcast_ref::<&'static str>() {
@@ -22,7 +22,6 @@
 use std::sync::Arc;
 use crate::error::{Error, WasmError};
 use parking_lot::Mutex;
-use codec::Decode;
 use sp_core::traits::{Externalities, RuntimeCode, FetchRuntimeCode};
 use sp_version::RuntimeVersion;
 use std::panic::AssertUnwindSafe;
@@ -315,7 +314,7 @@ fn create_versioned_wasm_runtime(
 		).map_err(|_| WasmError::Instantiation("panic in call to get runtime version".into()))?
 	};
 	let version = match version_result {
-		Ok(version) => Some(RuntimeVersion::decode(&mut version.as_slice())
+		Ok(version) => Some(RuntimeVersion::decode_compat(&version)
 			.map_err(|_|
 				WasmError::Instantiation("failed to decode \"Core_version\" result".into())
 			)?),
@@ -0,0 +1,187 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! RPC api for GRANDPA.
+
+#![warn(missing_docs)]
+
+mod report;
+
+use std::sync::Arc;
+
+use finality_grandpa::BlockNumberOps;
+use futures::{StreamExt as _, compat::Compat};
+use jsonrpc_core::futures::{Future, Sink};
+use jsonrpc_derive::rpc;
+use jsonrpc_pubsub::{typed::Subscriber, SubscriptionId};
+use log::warn;
+use parity_scale_codec::Encode;
+use sc_client_api::backend::Backend;
+use sc_finality_grandpa::{FinalityProofProvider, GrandpaJustificationStream, SharedVoterState};
+use sc_rpc_api::Subscriptions;
+use sp_core::Bytes;
+use sp_runtime::traits::{Block as BlockT, NumberFor};
+
+pub use report::{ReportedRoundStates, RoundState, Votes};
+
+/// Provides RPC methods for interacting with GRANDPA.
+#[rpc]
+pub trait GrandpaApi<Hash> {
+	/// RPC metadata
+	type Metadata;
+
+	/// Returns the votes seen in the best round of the voter, as well as in the rounds still
+	/// running in the background.
+	#[rpc(name = "grandpa_roundState")]
+	fn round_state(&self) -> jsonrpc_core::Result<ReportedRoundStates>;
+
+	/// Returns the SCALE-encoded proof of finality of the given block, for a caller that knows
+	/// its parent.
+	///
+	/// The first justification of the proof is signed by the given authority set, defaulting
+	/// to the set the voter runs for. Returns `None` if the block isn't finalized yet.
+	#[rpc(name = "grandpa_proveFinality")]
+	fn prove_finality(
+		&self,
+		block: Hash,
+		authorities_set_id: Option<u64>,
+	) -> jsonrpc_core::Result<Option<Bytes>>;
+
+	/// Subscribe to the SCALE-encoded justifications of the blocks finalized from now on.
+	#[pubsub(
+		subscription = "grandpa_justifications",
+		subscribe,
+		name = "grandpa_subscribeJustifications"
+	)]
+	fn subscribe_justifications(&self, metadata: Self::Metadata, subscriber: Subscriber<Bytes>);
+
+	/// Unsubscribe from justifications.
+	#[pubsub(
+		subscription = "grandpa_justifications",
+		unsubscribe,
+		name = "grandpa_unsubscribeJustifications"
+	)]
+	fn unsubscribe_justifications(
+		&self,
+		metadata: Option<Self::Metadata>,
+		id: SubscriptionId,
+	) -> jsonrpc_core::Result<bool>;
+}
+
+/// Implements the GrandpaApi trait for interacting with GRANDPA.
+pub struct GrandpaRpcHandler<B, Block: BlockT> {
+	/// The state reported by the voter.
+	voter_state: SharedVoterState,
+	/// Stream of the justifications of the finalized blocks.
+	justification_stream: GrandpaJustificationStream<Block>,
+	/// Provider of the finality proofs.
+	finality_proof_provider: Arc<FinalityProofProvider<B, Block>>,
+	/// Subscriptions manager.
+	subscriptions: Subscriptions,
+}
+
+impl<B, Block: BlockT> GrandpaRpcHandler<B, Block> {
+	/// Creates a new instance of the GRANDPA RPC handler.
+	pub fn new(
+		voter_state: SharedVoterState,
+		justification_stream: GrandpaJustificationStream<Block>,
+		finality_proof_provider: Arc<FinalityProofProvider<B, Block>>,
+		subscriptions: Subscriptions,
+	) -> Self {
+		Self {
+			voter_state,
+			justification_stream,
+			finality_proof_provider,
+			subscriptions,
+		}
+	}
+}
+
+impl<B, Block> GrandpaApi<Block::Hash> for GrandpaRpcHandler<B, Block>
+	where
+		Block: BlockT,
+		NumberFor<Block>: BlockNumberOps,
+		B: Backend<Block> + Send + Sync + 'static,
+{
+	type Metadata = sc_rpc::Metadata;
+
+	fn round_state(&self) -> jsonrpc_core::Result<ReportedRoundStates> {
+		self.voter_state.voter_state()
+			.and_then(|report| ReportedRoundStates::from_report(&report))
+			.ok_or_else(|| Error::VoterNotRunning.into())
+	}
+
+	fn prove_finality(
+		&self,
+		block: Block::Hash,
+		authorities_set_id: Option<u64>,
+	) -> jsonrpc_core::Result<Option<Bytes>> {
+		let authorities_set_id = match authorities_set_id {
+			Some(set_id) => set_id,
+			None => self.voter_state.voter_state().ok_or(Error::VoterNotRunning)?.set_id,
+		};
+
+		self.finality_proof_provider.prove_block_finality(block, authorities_set_id)
+			.map(|proof| proof.map(Into::into))
+			.map_err(|e| Error::ProveFinality(e).into())
+	}
+
+	fn subscribe_justifications(&self, _metadata: Self::Metadata, subscriber: Subscriber<Bytes>) {
+		let justifications = self.justification_stream.subscribe()
+			.map(|justification| Ok::<_, ()>(Ok(Bytes(justification.encode()))));
+
+		self.subscriptions.add(subscriber, |sink| {
+			sink
+				.sink_map_err(|e| warn!("Error sending notifications: {:?}", e))
+				.send_all(Compat::new(justifications))
+				.map(|_| ())
+		});
+	}
+
+	fn unsubscribe_justifications(
+		&self,
+		_metadata: Option<Self::Metadata>,
+		id: SubscriptionId,
+	) -> jsonrpc_core::Result<bool> {
+		Ok(self.subscriptions.cancel(id))
+	}
+}
+
+/// Errors encountered by the RPC
+#[derive(Debug, derive_more::Display)]
+pub enum Error {
+	/// The voter hasn't started or hasn't seen any vote yet.
+	#[display(fmt = "GRANDPA voter is not running")]
+	VoterNotRunning,
+	/// The finality proof couldn't be built.
+	#[display(fmt = "Failed to prove finality: {}", _0)]
+	ProveFinality(sp_blockchain::Error),
+}
+
+impl From<Error> for jsonrpc_core::Error {
+	fn from(error: Error) -> Self {
+		let code = match error {
+			Error::VoterNotRunning => 1,
+			Error::ProveFinality(_) => 2,
+		};
+
+		jsonrpc_core::Error {
+			message: format!("{}", error).into(),
+			code: jsonrpc_core::ErrorCode::ServerError(code),
+			data: None,
+		}
+	}
+}
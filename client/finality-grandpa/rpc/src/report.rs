@@ -0,0 +1,164 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Round states as reported over RPC.
+
+use std::collections::BTreeSet;
+
+use sc_finality_grandpa::{RoundVotes, VoterStateReport};
+use serde::{Deserialize, Serialize};
+use sp_finality_grandpa::AuthorityId;
+
+/// The votes of one kind seen in a round.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Votes {
+	/// The total weight of the voters we've seen a vote from.
+	pub current_weight: u64,
+	/// The voters we haven't seen a vote from.
+	pub missing: BTreeSet<AuthorityId>,
+}
+
+/// The state of a single round.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoundState {
+	/// The round number.
+	pub round: u64,
+	/// The total weight of the voters.
+	pub total_weight: u64,
+	/// The weight the prevotes and precommits need to reach to be supermajority.
+	pub threshold_weight: u64,
+	/// The prevotes seen so far.
+	pub prevotes: Votes,
+	/// The precommits seen so far.
+	pub precommits: Votes,
+}
+
+/// The state of the best round and of the rounds still running in the background.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportedRoundStates {
+	/// The id of the authority set the voter is running for.
+	pub set_id: u64,
+	/// The best round.
+	pub best: RoundState,
+	/// The older rounds, which are still running in the background.
+	pub background: Vec<RoundState>,
+}
+
+impl ReportedRoundStates {
+	/// Build the round states from the report of the voter.
+	///
+	/// Returns `None` if the voter hasn't seen any vote yet.
+	pub fn from_report(report: &VoterStateReport) -> Option<Self> {
+		let total_weight: u64 = report.voters.iter().map(|(_, weight)| weight).sum();
+		let round_state = |votes: &RoundVotes| RoundState {
+			round: votes.round,
+			total_weight,
+			threshold_weight: report.threshold,
+			prevotes: self::votes(report, &votes.prevotes),
+			precommits: self::votes(report, &votes.precommits),
+		};
+
+		let (best, background) = report.rounds.split_last()?;
+		Some(ReportedRoundStates {
+			set_id: report.set_id,
+			best: round_state(best),
+			background: background.iter().rev().map(round_state).collect(),
+		})
+	}
+}
+
+fn votes(report: &VoterStateReport, seen: &BTreeSet<AuthorityId>) -> Votes {
+	let (voted, missing): (Vec<_>, Vec<_>) = report.voters.iter()
+		.partition(|(id, _)| seen.contains(id));
+
+	Votes {
+		current_weight: voted.into_iter().map(|(_, weight)| weight).sum(),
+		missing: missing.into_iter().map(|(id, _)| id.clone()).collect(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_keyring::Ed25519Keyring;
+
+	#[test]
+	fn reports_weights_and_missing_voters_of_each_round() {
+		let alice: AuthorityId = Ed25519Keyring::Alice.public().into();
+		let bob: AuthorityId = Ed25519Keyring::Bob.public().into();
+		let charlie: AuthorityId = Ed25519Keyring::Charlie.public().into();
+		let report = VoterStateReport {
+			set_id: 3,
+			voters: vec![(alice.clone(), 1), (bob.clone(), 2), (charlie.clone(), 3)],
+			threshold: 5,
+			rounds: vec![
+				RoundVotes {
+					round: 7,
+					prevotes: vec![alice.clone(), bob.clone(), charlie.clone()]
+						.into_iter()
+						.collect(),
+					precommits: vec![bob.clone(), charlie.clone()].into_iter().collect(),
+				},
+				RoundVotes {
+					round: 8,
+					prevotes: vec![charlie.clone()].into_iter().collect(),
+					precommits: BTreeSet::new(),
+				},
+			],
+		};
+
+		let states = ReportedRoundStates::from_report(&report).unwrap();
+		assert_eq!(states.set_id, 3);
+		assert_eq!(states.best.round, 8);
+		assert_eq!(states.best.total_weight, 6);
+		assert_eq!(states.best.threshold_weight, 5);
+		assert_eq!(states.best.prevotes, Votes {
+			current_weight: 3,
+			missing: vec![alice.clone(), bob.clone()].into_iter().collect(),
+		});
+		assert_eq!(states.best.precommits.current_weight, 0);
+		assert_eq!(states.best.precommits.missing.len(), 3);
+
+		assert_eq!(states.background.len(), 1);
+		assert_eq!(states.background[0].round, 7);
+		assert_eq!(states.background[0].prevotes.current_weight, 6);
+		assert!(states.background[0].prevotes.missing.is_empty());
+		assert_eq!(states.background[0].precommits, Votes {
+			current_weight: 5,
+			missing: vec![alice].into_iter().collect(),
+		});
+
+		let json = serde_json::to_value(&states).unwrap();
+		assert_eq!(json["setId"], 3);
+		assert_eq!(json["best"]["thresholdWeight"], 5);
+		assert_eq!(json["best"]["prevotes"]["currentWeight"], 3);
+	}
+
+	#[test]
+	fn nothing_is_reported_before_the_first_vote() {
+		let report = VoterStateReport {
+			set_id: 0,
+			voters: Vec::new(),
+			threshold: 0,
+			rounds: Vec::new(),
+		};
+
+		assert_eq!(ReportedRoundStates::from_report(&report), None);
+	}
+}
@@ -1574,6 +1574,7 @@ mod tests {
 			name: None,
 			is_authority: true,
 			observer_enabled: true,
+			stall_threshold: None,
 		}
 	}
 
@@ -38,7 +38,7 @@ use finality_grandpa::{voter, voter_set::VoterSet};
 use sc_network::{NetworkService, ReputationChange};
 use sc_network_gossip::{GossipEngine, Network as GossipNetwork};
 use parity_scale_codec::{Encode, Decode};
-use sp_core::Pair;
+use sp_core::{Pair, crypto::{CryptoTypePublicPair, IsWrappedBy}, traits::BareCryptoStorePtr};
 use sp_runtime::traits::{Block as BlockT, Hash as HashT, Header as HeaderT, NumberFor};
 use sc_telemetry::{telemetry, CONSENSUS_DEBUG, CONSENSUS_INFO};
 
@@ -192,7 +192,8 @@ impl<B: BlockT, N: Network<B>> NetworkBridge<B, N> {
 			service.clone(),
 			GRANDPA_ENGINE_ID,
 			GRANDPA_PROTOCOL_NAME,
-			validator.clone()
+			validator.clone(),
+			prometheus_registry,
 		)));
 
 		{
@@ -274,7 +275,8 @@ impl<B: BlockT, N: Network<B>> NetworkBridge<B, N> {
 		round: Round,
 		set_id: SetId,
 		voters: Arc<VoterSet<AuthorityId>>,
-		local_key: Option<AuthorityPair>,
+		keystore: Option<BareCryptoStorePtr>,
+		local_key: Option<AuthorityId>,
 		has_voted: HasVoted<B>,
 	) -> (
 		impl Stream<Item = SignedMessage<B>> + Unpin,
@@ -286,14 +288,10 @@ impl<B: BlockT, N: Network<B>> NetworkBridge<B, N> {
 			&*voters,
 		);
 
-		let locals = local_key.and_then(|pair| {
-			let id = pair.public();
-			if voters.contains_key(&id) {
-				Some((pair, id))
-			} else {
-				None
-			}
-		});
+		let locals = match (keystore, local_key) {
+			(Some(keystore), Some(id)) if voters.contains_key(&id) => Some((keystore, id)),
+			_ => None,
+		};
 
 		let topic = round_topic::<B>(round.0, set_id.0);
 		let incoming = self.gossip_engine.lock().messages_for(topic)
@@ -692,7 +690,7 @@ pub(crate) fn check_message_sig_with_buffer<Block: BlockT>(
 pub(crate) struct OutgoingMessages<Block: BlockT> {
 	round: RoundNumber,
 	set_id: SetIdNumber,
-	locals: Option<(AuthorityPair, AuthorityId)>,
+	locals: Option<(BareCryptoStorePtr, AuthorityId)>,
 	sender: mpsc::Sender<SignedMessage<Block>>,
 	network: Arc<Mutex<GossipEngine<Block>>>,
 	has_voted: HasVoted<Block>,
@@ -729,9 +727,14 @@ impl<Block: BlockT> Sink<Message<Block>> for OutgoingMessages<Block>
 		}
 
 		// when locals exist, sign messages on import
-		if let Some((ref pair, ref local_id)) = self.locals {
+		if let Some((ref keystore, ref local_id)) = self.locals {
 			let encoded = localized_payload(self.round, self.set_id, &msg);
-			let signature = pair.sign(&encoded[..]);
+			let public: CryptoTypePublicPair = sp_core::ed25519::Public::from_ref(local_id).into();
+			let signature = keystore.read()
+				.sign_with(sp_finality_grandpa::KEY_TYPE, &public, &encoded[..])
+				.map_err(|e| Error::Signing(format!("Failed to sign the vote: {}", e)))?;
+			let signature = AuthoritySignature::decode(&mut &signature[..])
+				.map_err(|e| Error::Signing(format!("Invalid vote signature: {}", e)))?;
 
 			let target_hash = msg.target().0.clone();
 			let signed = SignedMessage::<Block> {
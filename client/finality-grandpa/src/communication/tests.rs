@@ -134,6 +134,7 @@ fn config() -> crate::Config {
 		name: None,
 		is_authority: true,
 		observer_enabled: true,
+		stall_threshold: None,
 	}
 }
 
@@ -30,11 +30,11 @@ use std::marker::PhantomData;
 
 use sc_client_api::{backend::Backend, utils::is_descendent_of};
 use sc_client::apply_aux;
+use sp_api::ProvideRuntimeApi;
 use finality_grandpa::{
 	BlockNumberOps, Equivocation, Error as GrandpaError, round::State as RoundState,
 	voter, voter_set::VoterSet,
 };
-use sp_core::Pair;
 use sp_runtime::generic::BlockId;
 use sp_runtime::traits::{
 	Block as BlockT, Header as HeaderT, NumberFor, One, Zero,
@@ -52,9 +52,11 @@ use crate::authorities::{AuthoritySet, SharedAuthoritySet};
 use crate::communication::Network as NetworkT;
 use crate::consensus_changes::SharedConsensusChanges;
 use crate::justification::GrandpaJustification;
+use crate::notification::GrandpaJustificationSender;
 use crate::until_imported::UntilVoteTargetImported;
+use crate::voter_state::SharedVoterState;
 use crate::voting_rule::VotingRule;
-use sp_finality_grandpa::{AuthorityId, AuthoritySignature, SetId, RoundNumber};
+use sp_finality_grandpa::{AuthorityId, AuthoritySignature, GrandpaApi, SetId, RoundNumber};
 use prometheus_endpoint::{Gauge, U64, register, PrometheusError};
 
 type HistoricalVotes<Block> = finality_grandpa::HistoricalVotes<
@@ -400,6 +402,8 @@ pub(crate) struct Environment<Backend, Block: BlockT, C, N: NetworkT<Block>, SC,
 	pub(crate) set_id: SetId,
 	pub(crate) voter_set_state: SharedVoterSetState<Block>,
 	pub(crate) voting_rule: VR,
+	pub(crate) voter_state: SharedVoterState,
+	pub(crate) justification_sender: GrandpaJustificationSender<Block>,
 	pub(crate) metrics: Option<Metrics>,
 	pub(crate) _phantom: PhantomData<Backend>,
 }
@@ -431,6 +435,75 @@ impl<Backend, Block: BlockT, C, N: NetworkT<Block>, SC, VR> Environment<Backend,
 	}
 }
 
+impl<BE, Block, C, N, SC, VR> Environment<BE, Block, C, N, SC, VR>
+where
+	Block: BlockT,
+	BE: Backend<Block>,
+	C: crate::ClientForGrandpa<Block, BE>,
+	C::Api: GrandpaApi<Block, Error = sp_blockchain::Error>,
+	N: NetworkT<Block>,
+	SC: SelectChain<Block> + 'static,
+{
+	/// Report the given equivocation to the GRANDPA runtime module. A proof
+	/// that the offender owns its key in the current session is generated at
+	/// the best block, and submitted along with the equivocation proof as an
+	/// unsigned extrinsic.
+	fn report_equivocation(
+		&self,
+		equivocation: sp_finality_grandpa::Equivocation<Block::Hash, NumberFor<Block>>,
+	) -> Result<(), Error> {
+		// the equivocations of the older sets can't be proven anymore, as the
+		// ownership proof is generated against the current session.
+		if self.set_id != self.authority_set.inner().read().current().0 {
+			debug!(target: "afg", "Skipping report for equivocation of an outdated set.");
+			return Ok(());
+		}
+
+		// we don't report our own equivocations, which can only happen when
+		// the same key is used by several nodes.
+		let local_id = crate::is_voter(&self.voters, &self.config.keystore);
+		if local_id.as_ref() == Some(equivocation.offender()) {
+			return Err(Error::Safety(
+				"Refraining from sending equivocation report for our own equivocation.".into(),
+			));
+		}
+
+		let best_header = self.select_chain.best_chain()
+			.map_err(|e| Error::Blockchain(e.to_string()))?;
+		let best_id = BlockId::Hash(best_header.hash());
+		let runtime_api = self.client.runtime_api();
+
+		let key_owner_proof = match runtime_api.generate_key_ownership_proof(
+			&best_id,
+			self.set_id,
+			equivocation.offender().clone(),
+		)? {
+			Some(proof) => proof,
+			None => {
+				debug!(target: "afg", "Equivocation offender is not part of the authority set.");
+				return Ok(());
+			},
+		};
+
+		let equivocation_proof = sp_finality_grandpa::EquivocationProof::new(
+			self.set_id,
+			equivocation,
+		);
+
+		// submit the equivocation report extrinsic to the pool of the best block.
+		match runtime_api.submit_report_equivocation_unsigned_extrinsic(
+			&best_id,
+			equivocation_proof,
+			key_owner_proof,
+		)? {
+			Some(()) => info!(target: "afg", "Submitted equivocation report."),
+			None => debug!(target: "afg", "Equivocation reporting is disabled by the runtime."),
+		}
+
+		Ok(())
+	}
+}
+
 impl<BE, Block: BlockT, C, N, SC, VR>
 	finality_grandpa::Chain<Block::Hash, NumberFor<Block>>
 for Environment<BE, Block, C, N, SC, VR>
@@ -580,6 +653,7 @@ where
 	Block: 'static,
 	B: Backend<Block>,
 	C: crate::ClientForGrandpa<Block, B> + 'static,
+	C::Api: GrandpaApi<Block, Error = sp_blockchain::Error>,
  	N: NetworkT<Block> + 'static + Send,
 	SC: SelectChain<Block> + 'static,
 	VR: VotingRule<Block, C>,
@@ -611,7 +685,7 @@ where
 
 		let has_voted = match self.voter_set_state.has_voted(round) {
 			HasVoted::Yes(id, vote) => {
-				if local_key.as_ref().map(|k| k.public() == id).unwrap_or(false) {
+				if local_key.as_ref().map(|k| *k == id).unwrap_or(false) {
 					HasVoted::Yes(id, vote)
 				} else {
 					HasVoted::No
@@ -624,10 +698,22 @@ where
 			crate::communication::Round(round),
 			crate::communication::SetId(self.set_id),
 			self.voters.clone(),
+			self.config.keystore.clone(),
 			local_key.clone(),
 			has_voted,
 		);
 
+		// report the votes of the other voters as they reach the voter.
+		let voter_state = self.voter_state.clone();
+		let set_id = self.set_id;
+		let incoming = incoming.inspect(move |signed: &SignedMessage<Block>| match signed.message {
+			finality_grandpa::Message::Prevote(_) =>
+				voter_state.note_prevote(set_id, round, signed.id.clone()),
+			finality_grandpa::Message::Precommit(_) =>
+				voter_state.note_precommit(set_id, round, signed.id.clone()),
+			finality_grandpa::Message::PrimaryPropose(_) => {},
+		});
+
 		// schedule incoming messages from the network to be held until
 		// corresponding blocks are imported.
 		let incoming = Box::pin(UntilVoteTargetImported::new(
@@ -642,7 +728,7 @@ where
 		let outgoing = Box::pin(outgoing.sink_err_into());
 
 		voter::RoundData {
-			voter_id: local_key.map(|pair| pair.public()),
+			voter_id: local_key,
 			prevote_timer: Box::pin(prevote_timer.map(Ok)),
 			precommit_timer: Box::pin(precommit_timer.map(Ok)),
 			incoming,
@@ -654,7 +740,7 @@ where
 		let local_id = crate::is_voter(&self.voters, &self.config.keystore);
 
 		let local_id = match local_id {
-			Some(id) => id.public(),
+			Some(id) => id,
 			None => return Ok(()),
 		};
 
@@ -693,10 +779,12 @@ where
 		let local_id = crate::is_voter(&self.voters, &self.config.keystore);
 
 		let local_id = match local_id {
-			Some(id) => id.public(),
+			Some(id) => id,
 			None => return Ok(()),
 		};
 
+		self.voter_state.note_prevote(self.set_id, round, local_id.clone());
+
 		self.update_voter_set_state(|voter_set_state| {
 			let (completed_rounds, current_rounds) = voter_set_state.with_current_round(round)?;
 			let current_round = current_rounds.get(&round)
@@ -734,10 +822,12 @@ where
 		let local_id = crate::is_voter(&self.voters, &self.config.keystore);
 
 		let local_id = match local_id {
-			Some(id) => id.public(),
+			Some(id) => id,
 			None => return Ok(()),
 		};
 
+		self.voter_state.note_precommit(self.set_id, round, local_id.clone());
+
 		self.update_voter_set_state(|voter_set_state| {
 			let (completed_rounds, current_rounds) = voter_set_state.with_current_round(round)?;
 			let current_round = current_rounds.get(&round)
@@ -906,6 +996,7 @@ where
 			self.client.clone(),
 			&self.authority_set,
 			&self.consensus_changes,
+			Some(&self.justification_sender),
 			Some(self.config.justification_period.into()),
 			hash,
 			number,
@@ -927,7 +1018,9 @@ where
 		equivocation: ::finality_grandpa::Equivocation<Self::Id, Prevote<Block>, Self::Signature>
 	) {
 		warn!(target: "afg", "Detected prevote equivocation in the finality worker: {:?}", equivocation);
-		// nothing yet; this could craft misbehavior reports of some kind.
+		if let Err(err) = self.report_equivocation(equivocation.into()) {
+			warn!(target: "afg", "Error reporting prevote equivocation: {:?}", err);
+		}
 	}
 
 	fn precommit_equivocation(
@@ -936,7 +1029,9 @@ where
 		equivocation: Equivocation<Self::Id, Precommit<Block>, Self::Signature>
 	) {
 		warn!(target: "afg", "Detected precommit equivocation in the finality worker: {:?}", equivocation);
-		// nothing yet
+		if let Err(err) = self.report_equivocation(equivocation.into()) {
+			warn!(target: "afg", "Error reporting precommit equivocation: {:?}", err);
+		}
 	}
 }
 
@@ -965,6 +1060,7 @@ pub(crate) fn finalize_block<BE, Block, Client>(
 	client: Arc<Client>,
 	authority_set: &SharedAuthoritySet<Block::Hash, NumberFor<Block>>,
 	consensus_changes: &SharedConsensusChanges<Block::Hash, NumberFor<Block>>,
+	justification_sender: Option<&GrandpaJustificationSender<Block>>,
 	justification_period: Option<NumberFor<Block>>,
 	hash: Block::Hash,
 	number: NumberFor<Block>,
@@ -1000,6 +1096,8 @@ pub(crate) fn finalize_block<BE, Block, Client>(
 	let mut old_consensus_changes = None;
 
 	let mut consensus_changes = consensus_changes.lock();
+	// the justification to notify the subscribers of once the block is finalized.
+	let mut notified_justification = None;
 	let canon_at_height = |canon_number| {
 		// "true" because the block is finalized
 		canonical_at_height(&*client, (hash, number), true, canon_number)
@@ -1038,8 +1136,16 @@ pub(crate) fn finalize_block<BE, Block, Client>(
 		// `N+1`. this assumption is required to make sure we store
 		// justifications for transition blocks which will be requested by
 		// syncing clients.
+		let has_subscribers = justification_sender
+			.map_or(false, |sender| sender.has_subscribers());
 		let justification = match justification_or_commit {
-			JustificationOrCommit::Justification(justification) => Some(justification.encode()),
+			JustificationOrCommit::Justification(justification) => {
+				let encoded = justification.encode();
+				if has_subscribers {
+					notified_justification = Some(justification);
+				}
+				Some(encoded)
+			},
 			JustificationOrCommit::Commit((round_number, commit)) => {
 				let mut justification_required =
 					// justification is always required when block that enacts new authorities
@@ -1059,14 +1165,24 @@ pub(crate) fn finalize_block<BE, Block, Client>(
 					}
 				}
 
-				if justification_required {
+				// the subscribers are notified of every block finalized by the voter, even
+				// though only some of the justifications are stored.
+				if justification_required || has_subscribers {
 					let justification = GrandpaJustification::from_commit(
 						&client,
 						round_number,
 						commit,
 					)?;
+					let encoded = justification.encode();
 
-					Some(justification.encode())
+					if has_subscribers {
+						notified_justification = Some(justification);
+					}
+					if justification_required {
+						Some(encoded)
+					} else {
+						None
+					}
 				} else {
 					None
 				}
@@ -1128,6 +1244,12 @@ pub(crate) fn finalize_block<BE, Block, Client>(
 		Ok(new_authorities.map(VoterCommand::ChangeAuthorities))
 	});
 
+	if let (Ok(_), Some(sender), Some(justification)) =
+		(&update_res, justification_sender, notified_justification)
+	{
+		sender.notify(&justification);
+	}
+
 	match update_res {
 		Ok(Some(command)) => Err(CommandOrError::VoterCommand(command)),
 		Ok(None) => Ok(()),
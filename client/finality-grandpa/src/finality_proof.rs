@@ -168,6 +168,35 @@ impl<B, Block: BlockT> FinalityProofProvider<B, Block>
 	}
 }
 
+impl<B, Block> FinalityProofProvider<B, Block>
+	where
+		Block: BlockT,
+		NumberFor<Block>: BlockNumberOps,
+		B: Backend<Block> + Send + Sync + 'static,
+{
+	/// Prove finality of the given block to a caller knowing its parent and the authority set
+	/// with the given id.
+	///
+	/// Returns `None` if the block isn't finalized yet, or if the oldest justification that
+	/// could be used is not signed by the given authority set.
+	pub fn prove_block_finality(
+		&self,
+		block: Block::Hash,
+		authorities_set_id: u64,
+	) -> ClientResult<Option<Vec<u8>>> {
+		let blockchain = self.backend.blockchain();
+		let parent = *blockchain.expect_header(BlockId::Hash(block))?.parent_hash();
+
+		prove_finality::<_, _, GrandpaJustification<Block>>(
+			&*blockchain,
+			&*self.authority_provider,
+			authorities_set_id,
+			parent,
+			block,
+		)
+	}
+}
+
 impl<B, Block> sc_network::config::FinalityProofProvider<Block> for FinalityProofProvider<B, Block>
 	where
 		Block: BlockT,
@@ -54,7 +54,7 @@
 
 use futures::prelude::*;
 use futures::StreamExt;
-use log::{debug, info};
+use log::{debug, info, warn};
 use futures::channel::mpsc;
 use sc_client_api::{
 	backend::{AuxStore, Backend},
@@ -64,11 +64,12 @@ use sc_client_api::{
 use sp_blockchain::{HeaderBackend, Error as ClientError, HeaderMetadata};
 use parity_scale_codec::{Decode, Encode};
 use sp_runtime::generic::BlockId;
-use sp_runtime::traits::{NumberFor, Block as BlockT, DigestFor, Zero};
-use sc_keystore::KeyStorePtr;
+use sp_runtime::traits::{
+	NumberFor, Block as BlockT, DigestFor, Header as HeaderT, Saturating, Zero,
+};
 use sp_inherents::InherentDataProviders;
 use sp_consensus::{SelectChain, BlockImport};
-use sp_core::Pair;
+use sp_core::{crypto::Public, traits::BareCryptoStorePtr};
 use sc_telemetry::{telemetry, CONSENSUS_INFO, CONSENSUS_DEBUG};
 use serde_json;
 
@@ -92,13 +93,18 @@ mod finality_proof;
 mod import;
 mod justification;
 mod light_import;
+mod notification;
 mod observer;
 mod until_imported;
+mod voter_state;
 mod voting_rule;
 
+pub use authorities::{AuthoritySet, SharedAuthoritySet};
 pub use finality_proof::{FinalityProofProvider, StorageAndProofProvider};
 pub use justification::GrandpaJustification;
 pub use light_import::light_block_import;
+pub use notification::GrandpaJustificationStream;
+pub use voter_state::{RoundVotes, SharedVoterState, VoterStateReport};
 pub use voting_rule::{
 	BeforeBestBlockBy, ThreeQuartersOfTheUnfinalizedChain, VotingRule, VotingRulesBuilder
 };
@@ -106,9 +112,10 @@ pub use voting_rule::{
 use aux_schema::PersistentData;
 use environment::{Environment, VoterSetState, Metrics};
 use import::GrandpaBlockImport;
+use notification::GrandpaJustificationSender;
 use until_imported::UntilGlobalMessageBlocksImported;
 use communication::{NetworkBridge, Network as NetworkT};
-use sp_finality_grandpa::{AuthorityList, AuthorityPair, AuthoritySignature, SetId};
+use sp_finality_grandpa::{AuthorityList, AuthoritySignature, GrandpaApi, SetId};
 
 // Re-export these two because it's just so damn convenient.
 pub use sp_finality_grandpa::{AuthorityId, ScheduledChange};
@@ -204,7 +211,10 @@ pub struct Config {
 	/// Some local identifier of the voter.
 	pub name: Option<String>,
 	/// The keystore that manages the keys of this node.
-	pub keystore: Option<sc_keystore::KeyStorePtr>,
+	pub keystore: Option<BareCryptoStorePtr>,
+	/// Number of blocks the best block can be ahead of the last finalized block before
+	/// finality is considered stalled, and a warning is logged. No check is done if `None`.
+	pub stall_threshold: Option<u32>,
 }
 
 impl Config {
@@ -228,6 +238,8 @@ pub enum Error {
 	Safety(String),
 	/// A timer failed to fire.
 	Timer(io::Error),
+	/// The keystore failed to sign a message.
+	Signing(String),
 }
 
 impl From<GrandpaError> for Error {
@@ -379,6 +391,27 @@ pub struct LinkHalf<Block: BlockT, C, SC> {
 	select_chain: SC,
 	persistent_data: PersistentData<Block>,
 	voter_commands_rx: mpsc::UnboundedReceiver<VoterCommand<Block::Hash, NumberFor<Block>>>,
+	shared_voter_state: SharedVoterState,
+	justification_sender: GrandpaJustificationSender<Block>,
+	justification_stream: GrandpaJustificationStream<Block>,
+}
+
+impl<Block: BlockT, C, SC> LinkHalf<Block, C, SC> {
+	/// Get the shared authority set, which is kept up to date by the block import.
+	pub fn shared_authority_set(&self) -> &SharedAuthoritySet<Block::Hash, NumberFor<Block>> {
+		&self.persistent_data.authority_set
+	}
+
+	/// Get the state of the voter, which is updated once it runs.
+	pub fn shared_voter_state(&self) -> &SharedVoterState {
+		&self.shared_voter_state
+	}
+
+	/// Get a handle to subscribe to the justifications of the blocks finalized by the voter or
+	/// the block import.
+	pub fn justification_stream(&self) -> GrandpaJustificationStream<Block> {
+		self.justification_stream.clone()
+	}
 }
 
 /// Provider for the Grandpa authority set configured on the genesis block.
@@ -443,6 +476,7 @@ where
 	)?;
 
 	let (voter_commands_tx, voter_commands_rx) = mpsc::unbounded();
+	let (justification_sender, justification_stream) = GrandpaJustificationSender::channel();
 
 	Ok((
 		GrandpaBlockImport::new(
@@ -451,12 +485,16 @@ where
 			persistent_data.authority_set.clone(),
 			voter_commands_tx,
 			persistent_data.consensus_changes.clone(),
+			justification_sender.clone(),
 		),
 		LinkHalf {
 			client,
 			select_chain,
 			persistent_data,
 			voter_commands_rx,
+			shared_voter_state: SharedVoterState::empty(),
+			justification_sender,
+			justification_stream,
 		},
 	))
 }
@@ -466,7 +504,7 @@ fn global_communication<BE, Block: BlockT, C, N>(
 	voters: &Arc<VoterSet<AuthorityId>>,
 	client: Arc<C>,
 	network: &NetworkBridge<Block, N>,
-	keystore: &Option<KeyStorePtr>,
+	keystore: &Option<BareCryptoStorePtr>,
 ) -> (
 	impl Stream<
 		Item = Result<CommunicationInH<Block, Block::Hash>, CommandOrError<Block::Hash, NumberFor<Block>>>,
@@ -563,6 +601,7 @@ pub fn run_grandpa_voter<Block: BlockT, BE: 'static, C, N, SC, VR>(
 	NumberFor<Block>: BlockNumberOps,
 	DigestFor<Block>: Encode,
 	C: ClientForGrandpa<Block, BE> + 'static,
+	C::Api: GrandpaApi<Block, Error = sp_blockchain::Error>,
 {
 	let GrandpaParams {
 		mut config,
@@ -585,6 +624,9 @@ pub fn run_grandpa_voter<Block: BlockT, BE: 'static, C, N, SC, VR>(
 		select_chain,
 		persistent_data,
 		voter_commands_rx,
+		shared_voter_state,
+		justification_sender,
+		..
 	} = link;
 
 	let network = NetworkBridge::new(
@@ -596,6 +638,16 @@ pub fn run_grandpa_voter<Block: BlockT, BE: 'static, C, N, SC, VR>(
 
 	register_finality_tracker_inherent_data_provider(client.clone(), &inherent_data_providers)?;
 
+	let stall_watcher = match config.stall_threshold {
+		Some(threshold) => future::Either::Left(
+			finality_stall_watcher(client.clone(), threshold).for_each(|stall| {
+				report_finality_stall(stall);
+				future::ready(())
+			})
+		),
+		None => future::Either::Right(future::pending()),
+	};
+
 	let conf = config.clone();
 	let telemetry_task = if let Some(telemetry_on_connect) = telemetry_on_connect {
 		let authorities = persistent_data.authority_set.clone();
@@ -632,16 +684,83 @@ pub fn run_grandpa_voter<Block: BlockT, BE: 'static, C, N, SC, VR>(
 		persistent_data,
 		voter_commands_rx,
 		prometheus_registry,
+		shared_voter_state,
+		justification_sender,
 	);
 
 	let voter_work = voter_work
 		.map(|_| ());
 
-	// Make sure that `telemetry_task` doesn't accidentally finish and kill grandpa.
+	// Make sure that `telemetry_task` and `stall_watcher` don't accidentally finish and kill
+	// grandpa.
 	let telemetry_task = telemetry_task
 		.then(|_| future::pending::<()>());
+	let stall_watcher = stall_watcher
+		.then(|_| future::pending::<()>());
 
-	Ok(future::select(voter_work, telemetry_task).map(drop))
+	Ok(future::select(voter_work, future::select(telemetry_task, stall_watcher)).map(drop))
+}
+
+/// A change of the finality status, as reported by `finality_stall_watcher`.
+#[derive(Debug, PartialEq)]
+enum FinalityStall<N> {
+	/// The best block got more than the threshold ahead of the last finalized block.
+	Stalled { best: N, finalized: N },
+	/// The best block got back within the threshold of the last finalized block.
+	Resumed { finalized: N },
+}
+
+/// Watch the imported best blocks, and report once the best block gets more than `threshold`
+/// blocks ahead of the last finalized block, and once it gets back within `threshold` blocks.
+fn finality_stall_watcher<Block: BlockT, C>(
+	client: Arc<C>,
+	threshold: u32,
+) -> impl Stream<Item = FinalityStall<NumberFor<Block>>> + Unpin + Send + 'static
+where
+	C: HeaderBackend<Block> + BlockchainEvents<Block> + 'static,
+{
+	let mut stalled = false;
+	client.import_notification_stream().filter_map(move |notification| {
+		let mut stall = None;
+		if notification.is_new_best {
+			let best = *notification.header.number();
+			let finalized = client.info().finalized_number;
+			let lag = best.saturating_sub(finalized);
+
+			if lag > threshold.into() && !stalled {
+				stall = Some(FinalityStall::Stalled { best, finalized });
+				stalled = true;
+			} else if lag <= threshold.into() && stalled {
+				stall = Some(FinalityStall::Resumed { finalized });
+				stalled = false;
+			}
+		}
+
+		future::ready(stall)
+	})
+}
+
+fn report_finality_stall<N: std::fmt::Display + std::fmt::Debug + Saturating + Copy>(
+	stall: FinalityStall<N>,
+) {
+	match stall {
+		FinalityStall::Stalled { best, finalized } => {
+			warn!(target: "afg",
+				"Finality stalled: best block #{} is {} blocks ahead of the last finalized \
+				block #{}. If the current authority set can't recover, a forced authority \
+				set change can be scheduled through `note_stalled`.",
+				best,
+				best.saturating_sub(finalized),
+				finalized,
+			);
+			telemetry!(CONSENSUS_INFO; "afg.finality_stalled";
+				"best" => ?best, "finalized" => ?finalized,
+			);
+		},
+		FinalityStall::Resumed { finalized } => {
+			info!(target: "afg", "Finality resumed at block #{}.", finalized);
+		},
+	}
 }
 
 /// Future that powers the voter.
@@ -658,6 +777,7 @@ where
 	Block: BlockT,
 	B: Backend<Block> + 'static,
 	C: ClientForGrandpa<Block, B> + 'static,
+	C::Api: GrandpaApi<Block, Error = sp_blockchain::Error>,
 	N: NetworkT<Block> + Sync,
 	NumberFor<Block>: BlockNumberOps,
 	SC: SelectChain<Block> + 'static,
@@ -672,6 +792,8 @@ where
 		persistent_data: PersistentData<Block>,
 		voter_commands_rx: mpsc::UnboundedReceiver<VoterCommand<Block::Hash, NumberFor<Block>>>,
 		prometheus_registry: Option<prometheus_endpoint::Registry>,
+		voter_state: SharedVoterState,
+		justification_sender: GrandpaJustificationSender<Block>,
 	) -> Self {
 
 		let voters = persistent_data.authority_set.current_authorities();
@@ -686,6 +808,8 @@ where
 			authority_set: persistent_data.authority_set.clone(),
 			consensus_changes: persistent_data.consensus_changes.clone(),
 			voter_set_state: persistent_data.set_state.clone(),
+			voter_state,
+			justification_sender,
 			metrics: prometheus_registry.map(|registry| {
 				Metrics::register(&registry)
 					.expect("Other metrics would have failed to register before these; qed")
@@ -712,7 +836,6 @@ where
 		debug!(target: "afg", "{}: Starting new voter with set ID {}", self.env.config.name(), self.env.set_id);
 
 		let authority_id = is_voter(&self.env.voters, &self.env.config.keystore)
-			.map(|ap| ap.public())
 			.unwrap_or(Default::default());
 
 		telemetry!(CONSENSUS_DEBUG; "afg.starting_new_voter";
@@ -752,6 +875,8 @@ where
 
 				let last_completed_round = completed_rounds.last();
 
+				self.env.voter_state.reset(self.env.set_id, &self.env.voters);
+
 				let voter = voter::Voter::new(
 					self.env.clone(),
 					(*self.env.voters).clone(),
@@ -810,6 +935,8 @@ where
 					consensus_changes: self.env.consensus_changes.clone(),
 					network: self.env.network.clone(),
 					voting_rule: self.env.voting_rule.clone(),
+					voter_state: self.env.voter_state.clone(),
+					justification_sender: self.env.justification_sender.clone(),
 					metrics: self.env.metrics.clone(),
 					_phantom: PhantomData,
 				});
@@ -844,6 +971,7 @@ where
 	NumberFor<Block>: BlockNumberOps,
 	SC: SelectChain<Block> + 'static,
 	C: ClientForGrandpa<Block, B> + 'static,
+	C::Api: GrandpaApi<Block, Error = sp_blockchain::Error>,
 	VR: VotingRule<Block, C> + Clone + 'static,
 {
 	type Output = Result<(), Error>;
@@ -915,33 +1043,28 @@ pub fn setup_disabled_grandpa<Block: BlockT, Client, N>(
 
 /// Checks if this node is a voter in the given voter set.
 ///
-/// Returns the key pair of the node that is being used in the current voter set or `None`.
+/// Returns the authority id of the key of the node that is being used in the current voter set
+/// or `None`.
 fn is_voter(
 	voters: &Arc<VoterSet<AuthorityId>>,
-	keystore: &Option<KeyStorePtr>,
-) -> Option<AuthorityPair> {
-	match keystore {
-		Some(keystore) => voters.voters().iter()
-			.find_map(|(p, _)| keystore.read().key_pair::<AuthorityPair>(&p).ok()),
-		None => None,
-	}
+	keystore: &Option<BareCryptoStorePtr>,
+) -> Option<AuthorityId> {
+	authority_id(&mut voters.voters().iter().map(|(p, _)| p), keystore)
 }
 
 /// Returns the authority id of this node, if available.
 fn authority_id<'a, I>(
 	authorities: &mut I,
-	keystore: &Option<KeyStorePtr>,
+	keystore: &Option<BareCryptoStorePtr>,
 ) -> Option<AuthorityId> where
 	I: Iterator<Item = &'a AuthorityId>,
 {
 	match keystore {
 		Some(keystore) => {
+			let keystore = keystore.read();
 			authorities
-				.find_map(|p| {
-					keystore.read().key_pair::<AuthorityPair>(&p)
-						.ok()
-						.map(|ap| ap.public())
-				})
+				.find(|p| keystore.has_keys(&[(p.to_raw_vec(), sp_finality_grandpa::KEY_TYPE)]))
+				.cloned()
 		}
 		None => None,
 	}
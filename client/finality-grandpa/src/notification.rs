@@ -0,0 +1,88 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Notifications of the justifications of blocks finalized by GRANDPA.
+
+use std::sync::Arc;
+
+use futures::channel::mpsc;
+use parking_lot::Mutex;
+use sp_runtime::traits::Block as BlockT;
+
+use crate::justification::GrandpaJustification;
+
+type JustificationSinks<Block> =
+	Arc<Mutex<Vec<mpsc::UnboundedSender<GrandpaJustification<Block>>>>>;
+
+/// Sending half of the justification notifications, used when finalizing blocks.
+pub(crate) struct GrandpaJustificationSender<Block: BlockT> {
+	sinks: JustificationSinks<Block>,
+}
+
+impl<Block: BlockT> Clone for GrandpaJustificationSender<Block> {
+	fn clone(&self) -> Self {
+		GrandpaJustificationSender { sinks: self.sinks.clone() }
+	}
+}
+
+impl<Block: BlockT> GrandpaJustificationSender<Block> {
+	/// Create a new sender and the stream handle its notifications are delivered to.
+	pub(crate) fn channel() -> (Self, GrandpaJustificationStream<Block>) {
+		let sinks = JustificationSinks::<Block>::default();
+		(
+			GrandpaJustificationSender { sinks: sinks.clone() },
+			GrandpaJustificationStream { sinks },
+		)
+	}
+
+	/// Whether anyone is still listening for justifications.
+	///
+	/// Building a justification from a commit isn't free, so it is only worth doing for a block
+	/// that doesn't otherwise need one when this returns `true`.
+	pub(crate) fn has_subscribers(&self) -> bool {
+		let mut sinks = self.sinks.lock();
+		sinks.retain(|sink| !sink.is_closed());
+		!sinks.is_empty()
+	}
+
+	/// Send the justification to all the current subscribers.
+	pub(crate) fn notify(&self, justification: &GrandpaJustification<Block>) {
+		self.sinks.lock().retain(|sink| sink.unbounded_send(justification.clone()).is_ok());
+	}
+}
+
+/// Handle to subscribe to the justifications of the blocks finalized by GRANDPA.
+///
+/// Justifications are only sent for blocks finalized by the local voter or through an imported
+/// justification, while the node is following the chain.
+pub struct GrandpaJustificationStream<Block: BlockT> {
+	sinks: JustificationSinks<Block>,
+}
+
+impl<Block: BlockT> Clone for GrandpaJustificationStream<Block> {
+	fn clone(&self) -> Self {
+		GrandpaJustificationStream { sinks: self.sinks.clone() }
+	}
+}
+
+impl<Block: BlockT> GrandpaJustificationStream<Block> {
+	/// Subscribe to the justifications of the blocks finalized from now on.
+	pub fn subscribe(&self) -> mpsc::UnboundedReceiver<GrandpaJustification<Block>> {
+		let (sink, stream) = mpsc::unbounded();
+		self.sinks.lock().push(sink);
+		stream
+	}
+}
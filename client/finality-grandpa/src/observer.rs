@@ -119,6 +119,7 @@ fn grandpa_observer<BE, Block: BlockT, Client, S, F>(
 				&authority_set,
 				&consensus_changes,
 				None,
+				None,
 				finalized_hash,
 				finalized_number,
 				(round, commit).into(),
@@ -172,6 +173,7 @@ where
 		select_chain: _,
 		persistent_data,
 		voter_commands_rx,
+		..
 	} = link;
 
 	let network = NetworkBridge::new(
@@ -205,7 +207,7 @@ struct ObserverWork<B: BlockT, BE, Client, N: NetworkT<B>> {
 	client: Arc<Client>,
 	network: NetworkBridge<B, N>,
 	persistent_data: PersistentData<B>,
-	keystore: Option<sc_keystore::KeyStorePtr>,
+	keystore: Option<sp_core::traits::BareCryptoStorePtr>,
 	voter_commands_rx: mpsc::UnboundedReceiver<VoterCommand<B::Hash, NumberFor<B>>>,
 	_phantom: PhantomData<BE>,
 }
@@ -222,7 +224,7 @@ where
 		client: Arc<Client>,
 		network: NetworkBridge<B, Network>,
 		persistent_data: PersistentData<B>,
-		keystore: Option<sc_keystore::KeyStorePtr>,
+		keystore: Option<sp_core::traits::BareCryptoStorePtr>,
 		voter_commands_rx: mpsc::UnboundedReceiver<VoterCommand<B::Hash, NumberFor<B>>>,
 	) -> Self {
 
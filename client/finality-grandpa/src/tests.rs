@@ -45,7 +45,10 @@ use parity_scale_codec::Decode;
 use sp_runtime::traits::{Block as BlockT, Header as HeaderT, HashFor};
 use sp_runtime::generic::{BlockId, DigestItem};
 use sp_core::{H256, NativeOrEncoded, ExecutionContext, crypto::Public};
-use sp_finality_grandpa::{GRANDPA_ENGINE_ID, AuthorityList, GrandpaApi};
+use sp_finality_grandpa::{
+	GRANDPA_ENGINE_ID, AuthorityId, AuthorityList, AuthorityPair, EquivocationProof, GrandpaApi,
+	OpaqueKeyOwnershipProof, SetId,
+};
 use sp_state_machine::{InMemoryBackend, prove_read, read_proof_check};
 
 use authorities::AuthoritySet;
@@ -274,6 +277,14 @@ impl ApiExt<Block> for RuntimeApi {
 		unimplemented!("Not required for testing!")
 	}
 
+	fn collect_changed_keys(&mut self) {
+		unimplemented!("Not required for testing!")
+	}
+
+	fn take_changed_keys(&mut self) -> Vec<Vec<u8>> {
+		unimplemented!("Not required for testing!")
+	}
+
 	fn into_storage_changes(
 		&self,
 		_: &Self::StateBackend,
@@ -296,6 +307,26 @@ impl GrandpaApi<Block> for RuntimeApi {
 	) -> Result<NativeOrEncoded<AuthorityList>> {
 		Ok(self.inner.genesis_authorities.clone()).map(NativeOrEncoded::Native)
 	}
+
+	fn GrandpaApi_submit_report_equivocation_unsigned_extrinsic_runtime_api_impl(
+		&self,
+		_: &BlockId<Block>,
+		_: ExecutionContext,
+		_: Option<(EquivocationProof<H256, BlockNumber>, OpaqueKeyOwnershipProof)>,
+		_: Vec<u8>,
+	) -> Result<NativeOrEncoded<Option<()>>> {
+		Ok(NativeOrEncoded::Native(None))
+	}
+
+	fn GrandpaApi_generate_key_ownership_proof_runtime_api_impl(
+		&self,
+		_: &BlockId<Block>,
+		_: ExecutionContext,
+		_: Option<(SetId, AuthorityId)>,
+		_: Vec<u8>,
+	) -> Result<NativeOrEncoded<Option<OpaqueKeyOwnershipProof>>> {
+		Ok(NativeOrEncoded::Native(None))
+	}
 }
 
 impl GenesisAuthoritySetProvider<Block> for TestApi {
@@ -347,12 +378,13 @@ fn make_ids(keys: &[Ed25519Keyring]) -> AuthorityList {
 	keys.iter().map(|key| key.clone().public().into()).map(|id| (id, 1)).collect()
 }
 
-fn create_keystore(authority: Ed25519Keyring) -> (KeyStorePtr, tempfile::TempDir) {
+fn create_keystore(authority: Ed25519Keyring) -> (BareCryptoStorePtr, tempfile::TempDir) {
 	let keystore_path = tempfile::tempdir().expect("Creates keystore path");
 	let keystore = sc_keystore::Store::open(keystore_path.path(), None).expect("Creates keystore");
 	keystore.write().insert_ephemeral_from_seed::<AuthorityPair>(&authority.to_seed())
 		.expect("Creates authority key");
 
+	let keystore: BareCryptoStorePtr = keystore;
 	(keystore, keystore_path)
 }
 
@@ -428,6 +460,7 @@ fn run_to_completion_with<F>(
 				name: Some(format!("peer#{}", peer_id)),
 				is_authority: true,
 				observer_enabled: true,
+				stall_threshold: None,
 			},
 			link: link,
 			network: net_service,
@@ -559,6 +592,7 @@ fn finalize_3_voters_1_full_observer() {
 				name: Some(format!("peer#{}", peer_id)),
 				is_authority: true,
 				observer_enabled: true,
+				stall_threshold: None,
 			},
 			link: link,
 			network: net_service,
@@ -722,6 +756,7 @@ fn transition_3_voters_twice_1_full_observer() {
 				name: Some(format!("peer#{}", peer_id)),
 				is_authority: true,
 				observer_enabled: true,
+				stall_threshold: None,
 			},
 			link: link,
 			network: net_service,
@@ -1115,7 +1150,7 @@ fn voter_persists_its_votes() {
 			voter_rx: mpsc::UnboundedReceiver<()>,
 			net: Arc<Mutex<GrandpaTestNet>>,
 			client: PeersClient,
-			keystore: KeyStorePtr,
+			keystore: BareCryptoStorePtr,
 		}
 
 		impl Future for ResettableVoter {
@@ -1147,6 +1182,7 @@ fn voter_persists_its_votes() {
 								name: Some(format!("peer#{}", 0)),
 								is_authority: true,
 								observer_enabled: true,
+								stall_threshold: None,
 							},
 							link,
 							network: this.net.lock().peers[0].network_service().clone(),
@@ -1204,6 +1240,7 @@ fn voter_persists_its_votes() {
 			name: Some(format!("peer#{}", 1)),
 			is_authority: true,
 			observer_enabled: true,
+			stall_threshold: None,
 		};
 
 		let set_state = {
@@ -1227,7 +1264,8 @@ fn voter_persists_its_votes() {
 			communication::Round(1),
 			communication::SetId(0),
 			Arc::new(VoterSet::from_iter(voters)),
-			Some(peers[1].pair().into()),
+			config.keystore.clone(),
+			Some(peers[1].public().into()),
 			HasVoted::No,
 		);
 
@@ -1364,6 +1402,7 @@ fn finalize_3_voters_1_light_observer() {
 					name: Some("observer".to_string()),
 					is_authority: false,
 					observer_enabled: true,
+					stall_threshold: None,
 				},
 				link,
 				net.lock().peers[3].network_service().clone(),
@@ -1492,6 +1531,7 @@ fn voter_catches_up_to_latest_round_when_behind() {
 				name: Some(format!("peer#{}", peer_id)),
 				is_authority: true,
 				observer_enabled: true,
+				stall_threshold: None,
 			},
 			link,
 			network: net.lock().peer(peer_id).network_service().clone(),
@@ -1611,6 +1651,7 @@ fn grandpa_environment_respects_voting_rules() {
 			name: None,
 			is_authority: true,
 			observer_enabled: true,
+			stall_threshold: None,
 		};
 
 		let network = NetworkBridge::new(
@@ -1631,6 +1672,8 @@ fn grandpa_environment_respects_voting_rules() {
 			voters: Arc::new(authority_set.current_authorities()),
 			network,
 			voting_rule,
+			voter_state: link.shared_voter_state.clone(),
+			justification_sender: link.justification_sender.clone(),
 			metrics: None,
 			_phantom: PhantomData,
 		}
@@ -1787,3 +1830,182 @@ fn imports_justification_for_regular_blocks_on_import() {
 		client.justification(&BlockId::Hash(block_hash)).unwrap().is_some(),
 	);
 }
+
+#[test]
+fn finality_stall_is_reported_until_finality_resumes() {
+	let peers = &[Ed25519Keyring::Alice];
+	let voters = make_ids(peers);
+	let mut net = GrandpaTestNet::new(TestApi::new(voters), 1);
+	let client = net.peer(0).client().as_full().expect("only full clients are used in test");
+
+	let mut stalls = finality_stall_watcher(client, 2);
+	let import_best_blocks = |net: &mut GrandpaTestNet, count| {
+		net.peer(0).generate_blocks(count, BlockOrigin::Own, |builder| builder.build().unwrap().block);
+	};
+
+	// the best block is within 2 blocks of the genesis block.
+	import_best_blocks(&mut net, 2);
+	assert_eq!(stalls.next().now_or_never(), None);
+
+	// finality stalls as block #3 is imported, and is only reported once.
+	import_best_blocks(&mut net, 1);
+	assert_eq!(
+		stalls.next().now_or_never(),
+		Some(Some(FinalityStall::Stalled { best: 3, finalized: 0 })),
+	);
+	import_best_blocks(&mut net, 1);
+	assert_eq!(stalls.next().now_or_never(), None);
+
+	// and resumes once the best block is back within 2 blocks of the last finalized block.
+	net.peer(0).client().finalize_block(BlockId::Number(3), None, false).unwrap();
+	import_best_blocks(&mut net, 1);
+	assert_eq!(
+		stalls.next().now_or_never(),
+		Some(Some(FinalityStall::Resumed { finalized: 3 })),
+	);
+}
+
+// spawn a voter for each of the given peers, returning the paths of their keystores.
+fn spawn_voters(
+	runtime: &mut Runtime,
+	net: &Arc<Mutex<GrandpaTestNet>>,
+	peers: &[Ed25519Keyring],
+) -> Vec<tempfile::TempDir> {
+	let mut keystore_paths = Vec::new();
+	for (peer_id, key) in peers.iter().enumerate() {
+		let (keystore, keystore_path) = create_keystore(*key);
+		keystore_paths.push(keystore_path);
+
+		let (net_service, link) = {
+			let net = net.lock();
+			let link = net.peers[peer_id].data.lock().take().expect("link initialized at startup; qed");
+			(net.peers[peer_id].network_service().clone(), link)
+		};
+
+		let grandpa_params = GrandpaParams {
+			config: Config {
+				gossip_duration: TEST_GOSSIP_DURATION,
+				justification_period: 32,
+				keystore: Some(keystore),
+				name: Some(format!("peer#{}", peer_id)),
+				is_authority: true,
+				observer_enabled: true,
+				stall_threshold: None,
+			},
+			link,
+			network: net_service,
+			inherent_data_providers: InherentDataProviders::new(),
+			telemetry_on_connect: None,
+			voting_rule: (),
+			prometheus_registry: None,
+		};
+
+		runtime.spawn(run_grandpa_voter(grandpa_params).expect("all in order with client and network"));
+	}
+
+	keystore_paths
+}
+
+// drive the network until `condition` holds or `timeout` elapses, returning whether the
+// condition held.
+fn drive_until(
+	net: &Arc<Mutex<GrandpaTestNet>>,
+	runtime: &mut Runtime,
+	timeout: Duration,
+	mut condition: impl FnMut(&mut GrandpaTestNet) -> bool,
+) -> bool {
+	let mut timeout = Delay::new(timeout);
+	runtime.block_on(futures::future::poll_fn(|cx| {
+		let mut net = net.lock();
+		net.poll(cx);
+
+		if condition(&mut net) {
+			Poll::Ready(true)
+		} else if Pin::new(&mut timeout).poll(cx).is_ready() {
+			Poll::Ready(false)
+		} else {
+			Poll::Pending
+		}
+	}))
+}
+
+fn finalized_number(net: &mut GrandpaTestNet, peer: usize) -> BlockNumber {
+	net.peer(peer).client().info().finalized_number
+}
+
+#[test]
+fn finality_stalls_in_partitions_without_supermajority_and_resumes_once_healed() {
+	let _ = env_logger::try_init();
+	let mut runtime = Runtime::new().unwrap();
+	let peers = &[
+		Ed25519Keyring::Alice,
+		Ed25519Keyring::Bob,
+		Ed25519Keyring::Charlie,
+		Ed25519Keyring::Dave,
+	];
+	let voters = make_ids(peers);
+
+	let mut net = GrandpaTestNet::new(TestApi::new(voters), 4);
+	net.peer(0).push_blocks(20, false);
+	net.block_until_sync();
+
+	// neither half of the voters is a supermajority.
+	net.partition(&[&[0, 1], &[2, 3]]);
+	net.block_until_connected();
+
+	let net = Arc::new(Mutex::new(net));
+	let _keystore_paths = spawn_voters(&mut runtime, &net, peers);
+
+	assert!(
+		!drive_until(&net, &mut runtime, Duration::from_secs(10), |net| {
+			(0..4).any(|peer| finalized_number(net, peer) > 0)
+		}),
+		"No partition can finalize on its own",
+	);
+
+	net.lock().heal_partition();
+	assert!(
+		drive_until(&net, &mut runtime, Duration::from_secs(60), |net| {
+			(0..4).all(|peer| finalized_number(net, peer) == 20)
+		}),
+		"All the voters finalize once the partition is healed",
+	);
+}
+
+#[test]
+fn supermajority_partition_finalizes_and_the_others_catch_up_once_healed() {
+	let _ = env_logger::try_init();
+	let mut runtime = Runtime::new().unwrap();
+	let peers = &[
+		Ed25519Keyring::Alice,
+		Ed25519Keyring::Bob,
+		Ed25519Keyring::Charlie,
+		Ed25519Keyring::Dave,
+	];
+	let voters = make_ids(peers);
+
+	let mut net = GrandpaTestNet::new(TestApi::new(voters), 4);
+	net.peer(0).push_blocks(20, false);
+	net.block_until_sync();
+
+	// 3 out of 4 voters are a supermajority.
+	net.partition(&[&[0, 1, 2], &[3]]);
+	net.block_until_connected();
+
+	let net = Arc::new(Mutex::new(net));
+	let _keystore_paths = spawn_voters(&mut runtime, &net, peers);
+
+	assert!(
+		drive_until(&net, &mut runtime, Duration::from_secs(60), |net| {
+			(0..3).all(|peer| finalized_number(net, peer) == 20)
+		}),
+		"The supermajority partition finalizes",
+	);
+	assert_eq!(finalized_number(&mut net.lock(), 3), 0);
+
+	net.lock().heal_partition();
+	assert!(
+		drive_until(&net, &mut runtime, Duration::from_secs(60), |net| finalized_number(net, 3) == 20),
+		"The isolated voter catches up once the partition is healed",
+	);
+}
@@ -0,0 +1,161 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Report of the votes seen by the running voter, so that it can be inspected from the outside
+//! (e.g. through RPC).
+
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use finality_grandpa::voter_set::VoterSet;
+use parking_lot::RwLock;
+use sp_finality_grandpa::{AuthorityId, SetId};
+
+/// Number of rounds kept in the report: the best round and the one before it, which may still
+/// be running in the background.
+const MAX_REPORTED_ROUNDS: usize = 2;
+
+/// The voters that were seen voting in a round.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoundVotes {
+	/// The round number.
+	pub round: u64,
+	/// The voters we've seen a prevote from.
+	pub prevotes: BTreeSet<AuthorityId>,
+	/// The voters we've seen a precommit from.
+	pub precommits: BTreeSet<AuthorityId>,
+}
+
+/// Snapshot of the state of the voter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VoterStateReport {
+	/// The id of the authority set the voter is running for.
+	pub set_id: SetId,
+	/// The voters of the set along with their weight.
+	pub voters: Vec<(AuthorityId, u64)>,
+	/// The weight a vote needs to reach to be supermajority.
+	pub threshold: u64,
+	/// The last rounds the voter has seen votes for, ordered by round number.
+	pub rounds: Vec<RoundVotes>,
+}
+
+/// Voter state shared between the voter and whoever wants to inspect it.
+///
+/// The report is `None` until the voter has started.
+#[derive(Clone, Default)]
+pub struct SharedVoterState {
+	inner: Arc<RwLock<Option<VoterStateReport>>>,
+}
+
+impl SharedVoterState {
+	/// Create a shared voter state that hasn't been reported to yet.
+	pub fn empty() -> Self {
+		Self::default()
+	}
+
+	/// Get the last reported state of the voter.
+	pub fn voter_state(&self) -> Option<VoterStateReport> {
+		self.inner.read().clone()
+	}
+
+	/// Start reporting for a new voter of the given set, forgetting the votes seen so far.
+	pub(crate) fn reset(&self, set_id: SetId, voters: &VoterSet<AuthorityId>) {
+		*self.inner.write() = Some(VoterStateReport {
+			set_id,
+			voters: voters.voters().to_vec(),
+			threshold: voters.threshold(),
+			rounds: Vec::new(),
+		});
+	}
+
+	/// Note that a prevote of `id` was seen in the given round.
+	pub(crate) fn note_prevote(&self, set_id: SetId, round: u64, id: AuthorityId) {
+		self.note_vote(set_id, round, |votes| { votes.prevotes.insert(id); });
+	}
+
+	/// Note that a precommit of `id` was seen in the given round.
+	pub(crate) fn note_precommit(&self, set_id: SetId, round: u64, id: AuthorityId) {
+		self.note_vote(set_id, round, |votes| { votes.precommits.insert(id); });
+	}
+
+	fn note_vote(&self, set_id: SetId, round: u64, note: impl FnOnce(&mut RoundVotes)) {
+		let mut inner = self.inner.write();
+		let report = match inner.as_mut() {
+			// votes of a former set can still arrive while the voter is being rebuilt.
+			Some(report) if report.set_id == set_id => report,
+			_ => return,
+		};
+
+		let position = match report.rounds.binary_search_by_key(&round, |votes| votes.round) {
+			Ok(position) => position,
+			Err(position) => {
+				report.rounds.insert(position, RoundVotes {
+					round,
+					prevotes: BTreeSet::new(),
+					precommits: BTreeSet::new(),
+				});
+				position
+			},
+		};
+		note(&mut report.rounds[position]);
+
+		let excess = report.rounds.len().saturating_sub(MAX_REPORTED_ROUNDS);
+		report.rounds.drain(..excess);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_keyring::Ed25519Keyring;
+
+	#[test]
+	fn only_keeps_votes_of_the_last_rounds_of_the_current_set() {
+		let alice: AuthorityId = Ed25519Keyring::Alice.public().into();
+		let bob: AuthorityId = Ed25519Keyring::Bob.public().into();
+		let voters: VoterSet<AuthorityId> = vec![(alice.clone(), 1), (bob.clone(), 1)]
+			.into_iter()
+			.collect();
+
+		let state = SharedVoterState::empty();
+		state.note_prevote(1, 1, alice.clone());
+		assert_eq!(state.voter_state(), None);
+
+		state.reset(1, &voters);
+		state.note_prevote(1, 1, alice.clone());
+		state.note_prevote(1, 2, alice.clone());
+		state.note_precommit(1, 2, bob.clone());
+		state.note_prevote(1, 3, bob.clone());
+		// ignored, the voter isn't running for this set.
+		state.note_prevote(0, 3, alice.clone());
+
+		let report = state.voter_state().unwrap();
+		assert_eq!(report.set_id, 1);
+		assert_eq!(report.threshold, 2);
+		assert_eq!(report.rounds, vec![
+			RoundVotes {
+				round: 2,
+				prevotes: vec![alice].into_iter().collect(),
+				precommits: vec![bob.clone()].into_iter().collect(),
+			},
+			RoundVotes {
+				round: 3,
+				prevotes: vec![bob].into_iter().collect(),
+				precommits: BTreeSet::new(),
+			},
+		]);
+	}
+}
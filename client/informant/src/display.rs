@@ -15,12 +15,12 @@
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
 use ansi_term::Colour;
-use sc_client_api::ClientInfo;
+use sc_client_api::{ClientInfo, MemorySize};
 use log::info;
 use sc_network::SyncState;
 use sp_runtime::traits::{Block as BlockT, CheckedDiv, NumberFor, Zero, Saturating};
 use sc_service::NetworkStatus;
-use std::{convert::{TryFrom, TryInto}, fmt};
+use std::{convert::{TryFrom, TryInto}, fmt, time::Duration};
 use wasm_timer::Instant;
 use crate::OutputFormat;
 
@@ -29,8 +29,12 @@ use crate::OutputFormat;
 /// This is the system that handles the line that gets regularly printed and that looks something
 /// like:
 ///
-/// > Syncing  5.4 bps, target=#531028 (4 peers), best: #90683 (0x4ca8…51b8),
-/// >  finalized #360 (0x6f24…a38b), ⬇ 5.5kiB/s ⬆ 0.9kiB/s
+/// > Syncing  5.4 bps, target=#531028, eta 1d 2h (4 peers), best: #90683 (0x4ca8…51b8),
+/// >  finalized #360 (0x6f24…a38b), ⬇ 5.5kiB/s ⬆ 0.9kiB/s, db 1.32 GiB, cache hits 93%
+///
+/// or with the `Machine` format:
+///
+/// > status=syncing bps=5.4 target=531028 eta_secs=93600 peers=4 best=90683 best_hash=0x4ca8…
 ///
 /// # Usage
 ///
@@ -58,25 +62,90 @@ impl<B: BlockT> InformantDisplay<B> {
 	}
 
 	/// Displays the informant by calling `info!`.
-	pub fn display(&mut self, info: &ClientInfo<B>, net_status: NetworkStatus<B>) {
+	///
+	/// The size of the database is displayed if known.
+	pub fn display(
+		&mut self,
+		info: &ClientInfo<B>,
+		net_status: NetworkStatus<B>,
+		database_size: Option<u64>,
+	) {
 		let best_number = info.chain.best_number;
 		let best_hash = info.chain.best_hash;
 		let finalized_number = info.chain.finalized_number;
 		let num_connected_peers = net_status.num_connected_peers;
 		let speed = speed::<B>(best_number, self.last_number, self.last_update);
+		let target = match net_status.sync_state {
+			SyncState::Idle => None,
+			SyncState::Downloading => net_status.best_seen_block,
+		};
+		let eta = target.and_then(|target|
+			eta::<B>(best_number, target, self.last_number, self.last_update.elapsed())
+		);
 		self.last_update = Instant::now();
 		self.last_number = Some(best_number);
 
-		let (status, target) = match (net_status.sync_state, net_status.best_seen_block) {
-			(SyncState::Idle, _) => ("Idle".into(), "".into()),
-			(SyncState::Downloading, None) => (format!("Syncing{}", speed), "".into()),
-			(SyncState::Downloading, Some(n)) => (format!("Syncing{}", speed), format!(", target=#{}", n)),
+		let cache_hit_rate = info.usage.as_ref()
+			.filter(|usage| usage.io.state_reads > 0)
+			.map(|usage| usage.io.state_reads_cache.saturating_mul(100) / usage.io.state_reads);
+
+		if self.format == OutputFormat::Machine {
+			let mut line = match net_status.sync_state {
+				SyncState::Idle => "status=idle".to_string(),
+				SyncState::Downloading => "status=syncing".to_string(),
+			};
+			if let (SyncState::Downloading, Some(speed)) = (&net_status.sync_state, &speed) {
+				line += &format!(" bps={}", speed);
+			}
+			if let Some(target) = target {
+				line += &format!(" target={}", target);
+			}
+			if let Some(eta) = eta {
+				line += &format!(" eta_secs={}", eta.as_secs());
+			}
+			line += &format!(
+				" peers={} best={} best_hash={:?} finalized={} finalized_hash={:?} \
+				download_bps={} upload_bps={}",
+				num_connected_peers,
+				best_number,
+				best_hash,
+				finalized_number,
+				info.chain.finalized_hash,
+				net_status.average_download_per_sec,
+				net_status.average_upload_per_sec,
+			);
+			if let Some(size) = database_size {
+				line += &format!(" db_bytes={}", size);
+			}
+			if let Some(rate) = cache_hit_rate {
+				line += &format!(" cache_hit_percent={}", rate);
+			}
+			info!(target: "substrate", "{}", line);
+			return;
+		}
+
+		let speed = speed.map(|speed| format!(" {:>4} bps", speed)).unwrap_or_default();
+		let status = match net_status.sync_state {
+			SyncState::Idle => "Idle".into(),
+			SyncState::Downloading => format!("Syncing{}", speed),
 		};
+		let mut target = target.map(|n| format!(", target=#{}", n)).unwrap_or_default();
+		if let Some(eta) = eta {
+			target += &format!(", eta {}", EtaFormat(eta));
+		}
+
+		let mut usage = String::new();
+		if let Some(size) = database_size {
+			usage += &format!(", db {}", MemorySize::from_bytes(size as usize));
+		}
+		if let Some(rate) = cache_hit_rate {
+			usage += &format!(", cache hits {}%", rate);
+		}
 
 		if self.format == OutputFormat::Coloured {
 			info!(
 				target: "substrate",
-				"{}{} ({} peers), best: #{} ({}), finalized #{} ({}), ⬇ {} ⬆ {}",
+				"{}{} ({} peers), best: #{} ({}), finalized #{} ({}), ⬇ {} ⬆ {}{}",
 				Colour::White.bold().paint(&status),
 				target,
 				Colour::White.bold().paint(format!("{}", num_connected_peers)),
@@ -86,11 +155,12 @@ impl<B: BlockT> InformantDisplay<B> {
 				info.chain.finalized_hash,
 				TransferRateFormat(net_status.average_download_per_sec),
 				TransferRateFormat(net_status.average_upload_per_sec),
+				usage,
 			);
 		} else {
 			info!(
 				target: "substrate",
-				"{}{} ({} peers), best: #{} ({}), finalized #{} ({}), ⬇ {} ⬆ {}",
+				"{}{} ({} peers), best: #{} ({}), finalized #{} ({}), ⬇ {} ⬆ {}{}",
 				status,
 				target,
 				num_connected_peers,
@@ -100,18 +170,20 @@ impl<B: BlockT> InformantDisplay<B> {
 				info.chain.finalized_hash,
 				TransferRateFormat(net_status.average_download_per_sec),
 				TransferRateFormat(net_status.average_upload_per_sec),
+				usage,
 			);
 		}
 	}
 }
 
 /// Calculates `(best_number - last_number) / (now - last_update)` and returns a `String`
-/// representing the speed of import.
+/// representing the speed of import in blocks per second, or `None` if `display` has never been
+/// called.
 fn speed<B: BlockT>(
 	best_number: NumberFor<B>,
 	last_number: Option<NumberFor<B>>,
 	last_update: Instant
-) -> String {
+) -> Option<String> {
 	// Number of milliseconds elapsed since last time.
 	let elapsed_ms = {
 		let elapsed = last_update.elapsed();
@@ -122,7 +194,7 @@ fn speed<B: BlockT>(
 
 	// Number of blocks that have been imported since last time.
 	let diff = match last_number {
-		None => return String::new(),
+		None => return None,
 		Some(n) => best_number.saturating_sub(n)
 	};
 
@@ -131,7 +203,7 @@ fn speed<B: BlockT>(
 		// do the math and turn it into a `f64`.
 		let speed = diff.saturating_mul(10_000).checked_div(u128::from(elapsed_ms))
 			.map_or(0.0, |s| s as f64) / 10.0;
-		format!(" {:4.1} bps", speed)
+		Some(format!("{:.1}", speed))
 
 	} else {
 		// If the number of blocks can't be converted to a regular integer, then we need a more
@@ -143,7 +215,44 @@ fn speed<B: BlockT>(
 
 		let speed = diff.saturating_mul(one_thousand).checked_div(&elapsed)
 			.unwrap_or_else(Zero::zero);
-		format!(" {} bps", speed)
+		Some(format!("{}", speed))
+	}
+}
+
+/// Estimates the time to import the blocks up to `target_number`, from the speed of import since
+/// the last time. Returns `None` if no block has been imported since.
+fn eta<B: BlockT>(
+	best_number: NumberFor<B>,
+	target_number: NumberFor<B>,
+	last_number: Option<NumberFor<B>>,
+	elapsed: Duration,
+) -> Option<Duration> {
+	let imported = TryInto::<u128>::try_into(best_number.saturating_sub(last_number?)).ok()?;
+	let remaining = TryInto::<u128>::try_into(target_number.saturating_sub(best_number)).ok()?;
+	if imported == 0 {
+		return None;
+	}
+
+	let secs = remaining.saturating_mul(elapsed.as_millis()) / imported / 1000;
+	Some(Duration::from_secs(u64::try_from(secs).unwrap_or(u64::max_value())))
+}
+
+/// Contains an estimated time to completion. Implements `fmt::Display` and shows its two most
+/// significant units.
+struct EtaFormat(Duration);
+impl fmt::Display for EtaFormat {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let secs = self.0.as_secs();
+		let (days, hours, mins) = (secs / 86_400, secs / 3_600 % 24, secs / 60 % 60);
+		if days > 0 {
+			write!(f, "{}d {}h", days, hours)
+		} else if hours > 0 {
+			write!(f, "{}h {}m", hours, mins)
+		} else if mins > 0 {
+			write!(f, "{}m {}s", mins, secs % 60)
+		} else {
+			write!(f, "{}s", secs)
+		}
 	}
 }
 
@@ -170,3 +279,27 @@ impl fmt::Display for TransferRateFormat {
 		write!(f, "{:.1}MiB/s", self.0 as f64 / (1024.0 * 1024.0))
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_runtime::testing::{Block as RawBlock, ExtrinsicWrapper};
+
+	type Block = RawBlock<ExtrinsicWrapper<u64>>;
+
+	#[test]
+	fn eta_is_estimated_from_the_speed_of_import() {
+		let eta = eta::<Block>(150, 1_150, Some(100), Duration::from_secs(10));
+		assert_eq!(eta, Some(Duration::from_secs(200)));
+		assert_eq!(eta::<Block>(150, 1_150, Some(150), Duration::from_secs(10)), None);
+		assert_eq!(eta::<Block>(150, 1_150, None, Duration::from_secs(10)), None);
+	}
+
+	#[test]
+	fn eta_is_displayed_with_two_units() {
+		assert_eq!(EtaFormat(Duration::from_secs(42)).to_string(), "42s");
+		assert_eq!(EtaFormat(Duration::from_secs(200)).to_string(), "3m 20s");
+		assert_eq!(EtaFormat(Duration::from_secs(7_980)).to_string(), "2h 13m");
+		assert_eq!(EtaFormat(Duration::from_secs(93_600)).to_string(), "1d 2h");
+	}
+}
@@ -21,23 +21,35 @@ use futures::prelude::*;
 use log::{info, warn, trace};
 use sp_runtime::traits::Header;
 use sc_service::AbstractService;
-use std::time::Duration;
+use std::{
+	fs, io, path::{Path, PathBuf}, time::Duration,
+	sync::{Arc, Weak, atomic::{AtomicU64, Ordering}},
+};
 
 mod display;
 
-/// The format to print telemetry output in.
-#[derive(PartialEq)]
-pub enum OutputFormat {
-	Coloured,
-	Plain,
-}
+/// How often the size of the database is computed, as walking its directory is slow.
+const DATABASE_SIZE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Size of the database not computed yet.
+const UNKNOWN_DATABASE_SIZE: u64 = u64::max_value();
+
+/// The format to print the informant output in.
+pub use sc_service::config::InformantOutputFormat as OutputFormat;
 
 /// Creates an informant in the form of a `Future` that must be polled regularly.
-pub fn build(service: &impl AbstractService, format: OutputFormat) -> impl futures::Future<Output = ()> {
+///
+/// The size of the database is reported when its path is given.
+pub fn build(
+	service: &impl AbstractService,
+	format: OutputFormat,
+	database_path: Option<PathBuf>,
+) -> impl futures::Future<Output = ()> {
 	let client = service.client();
 	let pool = service.transaction_pool();
 
 	let mut display = display::InformantDisplay::new(format);
+	let database_size = database_path.map(watch_directory_size);
 
 	let display_notifications = service
 		.network_status(Duration::from_millis(5000))
@@ -57,7 +69,10 @@ pub fn build(service: &impl AbstractService, format: OutputFormat) -> impl futur
 				"Subsystems memory [txpool: {} kB]",
 				parity_util_mem::malloc_size(&*pool) / 1024,
 			);
-			display.display(&info, net_status);
+			let database_size = database_size.as_ref()
+				.map(|size| size.load(Ordering::Relaxed))
+				.filter(|size| *size != UNKNOWN_DATABASE_SIZE);
+			display.display(&info, net_status, database_size);
 			future::ready(())
 		});
 
@@ -103,3 +118,44 @@ pub fn build(service: &impl AbstractService, format: OutputFormat) -> impl futur
 		display_block_import
 	).map(|_| ())
 }
+
+/// Computes the size of the directory every `DATABASE_SIZE_INTERVAL` on a thread of its own, until
+/// the returned size is dropped.
+fn watch_directory_size(path: PathBuf) -> Arc<AtomicU64> {
+	let size = Arc::new(AtomicU64::new(UNKNOWN_DATABASE_SIZE));
+	let watched_size: Weak<AtomicU64> = Arc::downgrade(&size);
+
+	let spawned = std::thread::Builder::new()
+		.name("informant-db-size".into())
+		.spawn(move || loop {
+			let computed_size = match directory_size(&path) {
+				Ok(size) => size,
+				Err(err) => {
+					trace!(target: "usage", "Failed to compute the size of the database: {}", err);
+					UNKNOWN_DATABASE_SIZE
+				},
+			};
+			match watched_size.upgrade() {
+				Some(size) => size.store(computed_size, Ordering::Relaxed),
+				None => return,
+			}
+			std::thread::sleep(DATABASE_SIZE_INTERVAL);
+		});
+	if let Err(err) = spawned {
+		warn!("Failed to spawn the thread computing the size of the database: {}", err);
+	}
+
+	size
+}
+
+/// Returns the total size of the files in the directory and its subdirectories.
+fn directory_size(path: &Path) -> io::Result<u64> {
+	let mut size = 0;
+	for entry in fs::read_dir(path)? {
+		let entry = entry?;
+		let metadata = entry.metadata()?;
+		size += if metadata.is_dir() { directory_size(&entry.path())? } else { metadata.len() };
+	}
+
+	Ok(size)
+}
@@ -0,0 +1,186 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate. If not, see <http://www.gnu.org/licenses/>.
+
+//! Encryption of the key files.
+//!
+//! The secret of an encrypted key file is encrypted with AES-128-CTR and authenticated with
+//! HMAC-SHA256, the keys of both being derived from the password of the keystore and the salt of
+//! the file with PBKDF2-HMAC-SHA256.
+
+use aes_ctr::{
+	Aes128Ctr,
+	stream_cipher::{NewStreamCipher, SyncStreamCipher, generic_array::GenericArray},
+};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::{Serialize, Deserialize};
+use sha2::Sha256;
+
+use crate::{Error, Result};
+
+/// The version of the format of the encrypted key files.
+const VERSION: u8 = 1;
+
+/// The number of PBKDF2 rounds the keys of new files are derived with.
+///
+/// The derivation would dominate the run time of the tests otherwise.
+const ROUNDS: u32 = if cfg!(test) { 1_024 } else { 100_000 };
+
+/// The keys a file is encrypted and authenticated with, as derived from the password.
+pub(crate) type DerivedKey = [u8; 32];
+
+/// The content of a key file.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum KeyFile {
+	/// The phrase of the key, stored as is.
+	Plain(String),
+	/// The encrypted secret of the key.
+	Encrypted(EncryptedKey),
+}
+
+/// The secret stored in an encrypted key file.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct Secret {
+	/// The phrase of the key.
+	pub(crate) phrase: String,
+	/// The password the key is derived with from the phrase.
+	///
+	/// It is the password of the keystore when the key was stored, which the key keeps once the
+	/// password of the keystore changes.
+	pub(crate) password: Option<String>,
+}
+
+/// An encrypted secret, along with what is needed to decrypt it.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct EncryptedKey {
+	version: u8,
+	rounds: u32,
+	salt: String,
+	iv: String,
+	ciphertext: String,
+	mac: String,
+}
+
+impl EncryptedKey {
+	/// Encrypt the secret with the password.
+	pub(crate) fn encrypt(secret: &Secret, password: &str) -> Result<Self> {
+		let mut salt = [0u8; 32];
+		let mut iv = [0u8; 16];
+		rand::thread_rng().fill_bytes(&mut salt);
+		rand::thread_rng().fill_bytes(&mut iv);
+
+		let key = derive_key(password, &salt, ROUNDS);
+		let mut ciphertext = serde_json::to_vec(secret)?;
+		cipher(&key, &iv).apply_keystream(&mut ciphertext);
+		let mac = mac(&key, &iv, &ciphertext).result().code();
+
+		Ok(EncryptedKey {
+			version: VERSION,
+			rounds: ROUNDS,
+			salt: hex::encode(salt),
+			iv: hex::encode(iv),
+			ciphertext: hex::encode(ciphertext),
+			mac: hex::encode(mac),
+		})
+	}
+
+	/// The salt and the number of rounds the keys of the file are derived with.
+	pub(crate) fn kdf_params(&self) -> Result<(Vec<u8>, u32)> {
+		if self.version != VERSION {
+			return Err(Error::InvalidKeyFile);
+		}
+
+		Ok((decode_hex(&self.salt)?, self.rounds))
+	}
+
+	/// Decrypt the secret with the keys derived from the password.
+	///
+	/// Fails with `Error::InvalidPassword` if the keys aren't the ones it was encrypted with.
+	pub(crate) fn decrypt(&self, key: &DerivedKey) -> Result<Secret> {
+		let iv = decode_hex(&self.iv)?;
+		let mut plaintext = decode_hex(&self.ciphertext)?;
+		if iv.len() != 16 {
+			return Err(Error::InvalidKeyFile);
+		}
+
+		mac(key, &iv, &plaintext).verify(&decode_hex(&self.mac)?)
+			.map_err(|_| Error::InvalidPassword)?;
+		cipher(key, &iv).apply_keystream(&mut plaintext);
+
+		serde_json::from_slice(&plaintext).map_err(|_| Error::InvalidKeyFile)
+	}
+}
+
+/// Derive the keys of a file from the password.
+pub(crate) fn derive_key(password: &str, salt: &[u8], rounds: u32) -> DerivedKey {
+	let mut key = [0u8; 32];
+	pbkdf2::pbkdf2::<Hmac<Sha256>>(password.as_bytes(), salt, rounds as usize, &mut key);
+	key
+}
+
+fn cipher(key: &DerivedKey, iv: &[u8]) -> Aes128Ctr {
+	Aes128Ctr::new(GenericArray::from_slice(&key[..16]), GenericArray::from_slice(iv))
+}
+
+fn mac(key: &DerivedKey, iv: &[u8], ciphertext: &[u8]) -> Hmac<Sha256> {
+	let mut mac = Hmac::<Sha256>::new_varkey(&key[16..]).expect("HMAC accepts keys of any size");
+	mac.input(iv);
+	mac.input(ciphertext);
+	mac
+}
+
+fn decode_hex(data: &str) -> Result<Vec<u8>> {
+	hex::decode(data).map_err(|_| Error::InvalidKeyFile)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn secret() -> Secret {
+		Secret { phrase: "//Alice".into(), password: Some("derivation".into()) }
+	}
+
+	#[test]
+	fn secret_is_decrypted_with_the_password_it_was_encrypted_with() {
+		let encrypted = EncryptedKey::encrypt(&secret(), "password").unwrap();
+		assert!(!encrypted.ciphertext.contains(&hex::encode("//Alice")));
+
+		let (salt, rounds) = encrypted.kdf_params().unwrap();
+		let decrypted = encrypted.decrypt(&derive_key("password", &salt, rounds)).unwrap();
+		assert_eq!(decrypted.phrase, "//Alice");
+		assert_eq!(decrypted.password, Some("derivation".into()));
+
+		match encrypted.decrypt(&derive_key("wrong", &salt, rounds)) {
+			Err(Error::InvalidPassword) => {},
+			_ => panic!("secret is decrypted with a wrong password"),
+		}
+	}
+
+	#[test]
+	fn plain_key_files_keep_their_format() {
+		let plain = serde_json::to_string(&KeyFile::Plain("//Alice".into())).unwrap();
+		assert_eq!(plain, "\"//Alice\"");
+
+		let encrypted = KeyFile::Encrypted(EncryptedKey::encrypt(&secret(), "password").unwrap());
+		let encrypted = serde_json::to_string(&encrypted).unwrap();
+		match serde_json::from_str(&encrypted).unwrap() {
+			KeyFile::Encrypted(_) => {},
+			KeyFile::Plain(_) => panic!("encrypted key file is read back as a plain one"),
+		}
+	}
+}
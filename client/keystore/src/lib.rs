@@ -18,12 +18,21 @@
 
 #![warn(missing_docs)]
 
-use std::{collections::HashMap, path::PathBuf, fs::{self, File}, io::{self, Write}, sync::Arc};
+use std::{
+	collections::HashMap, path::{Path, PathBuf}, fs::{self, File}, io::{self, Write}, sync::Arc,
+};
 use sp_core::{
 	crypto::{KeyTypeId, Pair as PairT, Public, IsWrappedBy, Protected}, traits::BareCryptoStore,
 };
-use sp_application_crypto::{AppKey, AppPublic, AppPair, ed25519, sr25519};
-use parking_lot::RwLock;
+use sp_application_crypto::{AppKey, AppPublic, AppPair, ecdsa, ed25519, sr25519};
+use parking_lot::{Mutex, RwLock};
+
+use encryption::{DerivedKey, EncryptedKey, KeyFile, Secret};
+
+mod encryption;
+pub mod remote;
+
+pub use remote::RemoteKeystore;
 
 /// Keystore pointer
 pub type KeyStorePtr = Arc<RwLock<Store>>;
@@ -47,6 +56,13 @@ pub enum Error {
 	/// Keystore unavailable
 	#[display(fmt="Keystore unavailable")]
 	Unavailable,
+	/// Invalid key file
+	#[display(fmt="Invalid key file")]
+	InvalidKeyFile,
+	/// Invalid URI of the remote signer.
+	#[display(fmt="Invalid remote signer URI: {}", _0)]
+	#[from(ignore)]
+	InvalidUri(String),
 }
 
 /// Keystore Result
@@ -72,17 +88,46 @@ pub struct Store {
 	/// Map over `(KeyTypeId, Raw public key)` -> `Key phrase/seed`
 	additional: HashMap<(KeyTypeId, Vec<u8>), String>,
 	password: Option<Protected<String>>,
+	/// Whether the new key files are encrypted with the password.
+	encrypt: bool,
+	/// The keys derived from the password, by salt and number of rounds of the key files.
+	derived_keys: Mutex<HashMap<(Vec<u8>, u32), DerivedKey>>,
 }
 
 impl Store {
 	/// Open the store at the given path.
 	///
-	/// Optionally takes a password that will be used to encrypt/decrypt the keys.
+	/// Optionally takes a password that the keys are derived with from their phrase.
 	pub fn open<T: Into<PathBuf>>(path: T, password: Option<Protected<String>>) -> Result<KeyStorePtr> {
-		let path = path.into();
+		Self::open_with(path.into(), password, false)
+	}
+
+	/// Open the store at the given path, encrypting the key files it writes with the password.
+	///
+	/// The password is still the one the new keys are derived with, so that a phrase yields the
+	/// same keys whether the store is encrypted or not. The plain key files written before are
+	/// still read as they are.
+	pub fn open_encrypted<T: Into<PathBuf>>(
+		path: T,
+		password: Protected<String>,
+	) -> Result<KeyStorePtr> {
+		Self::open_with(path.into(), Some(password), true)
+	}
+
+	fn open_with(
+		path: PathBuf,
+		password: Option<Protected<String>>,
+		encrypt: bool,
+	) -> Result<KeyStorePtr> {
 		fs::create_dir_all(&path)?;
 
-		let instance = Self { path: Some(path), additional: HashMap::new(), password };
+		let instance = Self {
+			path: Some(path),
+			additional: HashMap::new(),
+			password,
+			encrypt,
+			derived_keys: Mutex::new(HashMap::new()),
+		};
 		Ok(Arc::new(RwLock::new(instance)))
 	}
 
@@ -91,10 +136,76 @@ impl Store {
 		Arc::new(RwLock::new(Self {
 			path: None,
 			additional: HashMap::new(),
-			password: None
+			password: None,
+			encrypt: false,
+			derived_keys: Mutex::new(HashMap::new()),
 		}))
 	}
 
+	/// Encrypt all the key files of the store with the given password, which the store uses from
+	/// then on.
+	///
+	/// The encrypted files are decrypted with the current password, and the plain ones are
+	/// encrypted along with it, since their keys are derived with it. All of them are read before
+	/// any is written, so that a wrong current password leaves the store untouched. Returns the
+	/// number of encrypted files.
+	pub fn rotate_password(&mut self, password: Protected<String>) -> Result<usize> {
+		let path = self.path.clone().ok_or_else(|| Error::Unavailable)?;
+
+		let mut secrets = Vec::new();
+		for entry in fs::read_dir(&path)? {
+			let path = entry?.path();
+			if !is_key_file(&path) { continue }
+
+			let secret = match read_key_file(&path)? {
+				KeyFile::Plain(phrase) => Secret { phrase, password: self.password_string() },
+				KeyFile::Encrypted(key) => self.decrypt(&key)?,
+			};
+			secrets.push((path, secret));
+		}
+
+		for (path, secret) in &secrets {
+			// the file is replaced at once, so that it is never left half written.
+			let temp_path = path.with_extension("tmp");
+			let key_file = KeyFile::Encrypted(EncryptedKey::encrypt(secret, &password)?);
+			write_key_file(&temp_path, &key_file)?;
+			fs::rename(&temp_path, path)?;
+		}
+
+		self.password = Some(password);
+		self.encrypt = true;
+		self.derived_keys.get_mut().clear();
+		Ok(secrets.len())
+	}
+
+	fn password_string(&self) -> Option<String> {
+		self.password.as_ref().map(|p| p.as_str().to_owned())
+	}
+
+	/// Decrypt the secret of an encrypted key file with the password of the store.
+	fn decrypt(&self, key: &EncryptedKey) -> Result<Secret> {
+		let password = self.password.as_ref().ok_or_else(|| Error::InvalidPassword)?;
+		let (salt, rounds) = key.kdf_params()?;
+		let mut derived_keys = self.derived_keys.lock();
+		let derived_key = derived_keys.entry((salt.clone(), rounds))
+			.or_insert_with(|| encryption::derive_key(password, &salt, rounds));
+
+		key.decrypt(derived_key)
+	}
+
+	/// Write the phrase of a key to the given file, encrypted if the store is.
+	fn store_phrase(&self, path: &Path, phrase: &str) -> Result<()> {
+		let key_file = match &self.password {
+			Some(password) if self.encrypt => {
+				let secret = Secret { phrase: phrase.into(), password: self.password_string() };
+				KeyFile::Encrypted(EncryptedKey::encrypt(&secret, password)?)
+			},
+			_ => KeyFile::Plain(phrase.into()),
+		};
+
+		write_key_file(path, &key_file)
+	}
+
 	/// Get the key phrase for the given public key and key type from the in-memory store.
 	fn get_additional_pair(
 		&self,
@@ -118,9 +229,7 @@ impl Store {
 	/// Places it into the file system store.
 	fn insert_unknown(&self, key_type: KeyTypeId, suri: &str, public: &[u8]) -> Result<()> {
 		if let Some(path) = self.key_file_path(public, key_type) {
-			let mut file = File::create(path).map_err(Error::Io)?;
-			serde_json::to_writer(&file, &suri).map_err(Error::Json)?;
-			file.flush().map_err(Error::Io)?;
+			self.store_phrase(&path, suri)?;
 		}
 		Ok(())
 	}
@@ -147,21 +256,20 @@ impl Store {
 
 	/// Generate a new key.
 	///
-	/// Places it into the file system store.
-	pub fn generate_by_type<Pair: PairT>(&self, key_type: KeyTypeId) -> Result<Pair> {
+	/// Places it into the file system store, or in memory for an in-memory store.
+	pub fn generate_by_type<Pair: PairT>(&mut self, key_type: KeyTypeId) -> Result<Pair> {
 		let (pair, phrase, _) = Pair::generate_with_phrase(self.password.as_ref().map(|p| &***p));
-		if let Some(path) = self.key_file_path(pair.public().as_slice(), key_type) {
-			let mut file = File::create(path)?;
-			serde_json::to_writer(&file, &phrase)?;
-			file.flush()?;
+		match self.key_file_path(pair.public().as_slice(), key_type) {
+			Some(path) => self.store_phrase(&path, &phrase)?,
+			None => self.insert_ephemeral_pair(&pair, &phrase, key_type),
 		}
 		Ok(pair)
 	}
 
 	/// Generate a new key.
 	///
-	/// Places it into the file system store.
-	pub fn generate<Pair: AppPair>(&self) -> Result<Pair> {
+	/// Places it into the file system store, or in memory for an in-memory store.
+	pub fn generate<Pair: AppPair>(&mut self) -> Result<Pair> {
 		self.generate_by_type::<Pair::Generic>(Pair::ID).map(Into::into)
 	}
 
@@ -185,16 +293,18 @@ impl Store {
 		self.insert_ephemeral_from_seed_by_type::<Pair::Generic>(seed, Pair::ID).map(Into::into)
 	}
 
-	/// Get the key phrase for a given public key and key type.
-	fn key_phrase_by_type(&self, public: &[u8], key_type: KeyTypeId) -> Result<String> {
+	/// Get the key phrase for a given public key and key type, along with the password the key is
+	/// derived with.
+	fn key_phrase_by_type(&self, public: &[u8], key_type: KeyTypeId) -> Result<Secret> {
 		if let Some(phrase) = self.get_additional_pair(public, key_type) {
-			return Ok(phrase.clone())
+			return Ok(Secret { phrase: phrase.clone(), password: self.password_string() })
 		}
 
 		let path = self.key_file_path(public, key_type).ok_or_else(|| Error::Unavailable)?;
-		let file = File::open(path)?;
-
-		serde_json::from_reader(&file).map_err(Into::into)
+		match read_key_file(&path)? {
+			KeyFile::Plain(phrase) => Ok(Secret { phrase, password: self.password_string() }),
+			KeyFile::Encrypted(key) => self.decrypt(&key),
+		}
 	}
 
 	/// Get a key pair for the given public key and key type.
@@ -202,10 +312,10 @@ impl Store {
 		public: &Pair::Public,
 		key_type: KeyTypeId,
 	) -> Result<Pair> {
-		let phrase = self.key_phrase_by_type(public.as_slice(), key_type)?;
+		let secret = self.key_phrase_by_type(public.as_slice(), key_type)?;
 		let pair = Pair::from_string(
-			&phrase,
-			self.password.as_ref().map(|p| &***p),
+			&secret.phrase,
+			secret.password.as_ref().map(|p| p.as_str()),
 		).map_err(|_| Error::InvalidPhrase)?;
 
 		if &pair.public() == public {
@@ -221,10 +331,13 @@ impl Store {
 	}
 
 	/// Get public keys of all stored keys that match the given key type.
+	///
+	/// Keys of another length, i.e. of another crypto stored under the same key type, are skipped.
 	pub fn public_keys_by_type<TPublic: Public>(&self, key_type: KeyTypeId) -> Result<Vec<TPublic>> {
+		let public_len = TPublic::default().as_ref().len();
 		let mut public_keys: Vec<TPublic> = self.additional.keys()
 			.filter_map(|(ty, public)| {
-				if *ty == key_type {
+				if *ty == key_type && public.len() == public_len {
 					Some(TPublic::from_slice(public))
 				} else {
 					None
@@ -241,7 +354,7 @@ impl Store {
 				if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
 					match hex::decode(name) {
 						Ok(ref hex) if hex.len() > 4 => {
-							if &hex[0..4] != &key_type.0 { continue }
+							if &hex[0..4] != &key_type.0 || hex.len() - 4 != public_len { continue }
 							let public = TPublic::from_slice(&hex[4..]);
 							public_keys.push(public);
 						}
@@ -275,6 +388,26 @@ impl Store {
 	}
 }
 
+/// Whether the file is named after a key type and a public key, as the key files are.
+fn is_key_file(path: &Path) -> bool {
+	path.file_name()
+		.and_then(|n| n.to_str())
+		.and_then(|n| hex::decode(n).ok())
+		.map_or(false, |hex| hex.len() > 4)
+}
+
+fn read_key_file(path: &Path) -> Result<KeyFile> {
+	let file = File::open(path)?;
+	serde_json::from_reader(&file).map_err(Into::into)
+}
+
+fn write_key_file(path: &Path, key_file: &KeyFile) -> Result<()> {
+	let mut file = File::create(path)?;
+	serde_json::to_writer(&file, key_file)?;
+	file.flush()?;
+	Ok(())
+}
+
 impl BareCryptoStore for Store {
 	fn sr25519_public_keys(&self, key_type: KeyTypeId) -> Vec<sr25519::Public> {
 		self.public_keys_by_type::<sr25519::Public>(key_type).unwrap_or_default()
@@ -318,9 +451,37 @@ impl BareCryptoStore for Store {
 		self.key_pair_by_type::<ed25519::Pair>(pub_key, id).ok()
 	}
 
+	fn ecdsa_public_keys(&self, key_type: KeyTypeId) -> Vec<ecdsa::Public> {
+		self.public_keys_by_type::<ecdsa::Public>(key_type).unwrap_or_default()
+	}
+
+	fn ecdsa_generate_new(
+		&mut self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> std::result::Result<ecdsa::Public, String> {
+		let pair = match seed {
+			Some(seed) => self.insert_ephemeral_from_seed_by_type::<ecdsa::Pair>(seed, id),
+			None => self.generate_by_type::<ecdsa::Pair>(id),
+		}.map_err(|e| e.to_string())?;
+
+		Ok(pair.public())
+	}
+
+	fn ecdsa_key_pair(&self, id: KeyTypeId, pub_key: &ecdsa::Public) -> Option<ecdsa::Pair> {
+		self.key_pair_by_type::<ecdsa::Pair>(pub_key, id).ok()
+	}
+
 	fn insert_unknown(&mut self, key_type: KeyTypeId, suri: &str, public: &[u8])
 		-> std::result::Result<(), ()>
 	{
+		if self.path.is_none() {
+			// Without a file system store the key would be silently dropped, which would make
+			// `has_keys` report a successful insertion as missing.
+			self.additional.insert((key_type, public.to_vec()), suri.into());
+			return Ok(());
+		}
+
 		Store::insert_unknown(self, key_type, suri, public).map_err(|_| ())
 	}
 
@@ -337,7 +498,8 @@ impl BareCryptoStore for Store {
 mod tests {
 	use super::*;
 	use tempfile::TempDir;
-	use sp_core::{testing::{SR25519}, crypto::{Ss58Codec}};
+	use sp_core::{testing::{ED25519, SR25519}, crypto::{Ss58Codec, CryptoTypePublicPair}};
+	use sp_application_crypto::codec::Decode;
 
 	#[test]
 	fn basic_store() {
@@ -354,6 +516,28 @@ mod tests {
 		assert_eq!(store.read().public_keys::<ed25519::AppPublic>().unwrap()[0], key.public());
 	}
 
+	#[test]
+	fn insert_unknown_into_in_memory_store() {
+		let store = Store::new_in_memory();
+		let pair = ed25519::Pair::from_string("//Alice", None).unwrap();
+		let public = pair.public().to_raw_vec();
+
+		assert!(!store.read().has_keys(&[(public.clone(), ED25519)]));
+		BareCryptoStore::insert_unknown(&mut *store.write(), ED25519, "//Alice", &public).unwrap();
+		assert!(store.read().has_keys(&[(public, ED25519)]));
+	}
+
+	#[test]
+	fn generate_into_in_memory_store() {
+		let store = Store::new_in_memory();
+		let public = BareCryptoStore::sr25519_generate_new(&mut *store.write(), SR25519, None)
+			.unwrap();
+
+		assert!(store.read().has_keys(&[(public.to_raw_vec(), SR25519)]));
+		assert!(store.read().sr25519_key_pair(SR25519, &public).is_some());
+		assert_eq!(store.read().sr25519_public_keys(SR25519), vec![public]);
+	}
+
 	#[test]
 	fn test_insert_ephemeral_from_seed() {
 		let temp_dir = TempDir::new().unwrap();
@@ -397,6 +581,66 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn encrypted_store_yields_the_keys_of_a_plain_one() {
+		let password = String::from("password");
+		let temp_dir = TempDir::new().unwrap();
+		let store = Store::open_encrypted(temp_dir.path(), password.clone().into()).unwrap();
+
+		let pair: ed25519::AppPair = store.write().generate().unwrap();
+		let file = fs::read_dir(temp_dir.path()).unwrap().next().unwrap().unwrap().path();
+		match read_key_file(&file).unwrap() {
+			KeyFile::Encrypted(_) => {},
+			KeyFile::Plain(_) => panic!("key file is not encrypted"),
+		}
+
+		let secret_uri = "//Alice";
+		let alice = sr25519::AppPair::from_string(secret_uri, Some(&password)).unwrap();
+		store.write().insert_unknown(SR25519, secret_uri, alice.public().as_ref()).unwrap();
+
+		let store = Store::open_encrypted(temp_dir.path(), password.clone().into()).unwrap();
+		assert_eq!(
+			store.read().key_pair::<ed25519::AppPair>(&pair.public()).unwrap().public(),
+			pair.public(),
+		);
+		assert_eq!(
+			store.read().key_pair_by_type::<sr25519::AppPair>(&alice.public(), SR25519)
+				.unwrap().public(),
+			alice.public(),
+		);
+
+		// the files can't be read without the password they are encrypted with.
+		let store = Store::open(temp_dir.path(), Some(String::from("wrong").into())).unwrap();
+		assert!(store.read().key_pair::<ed25519::AppPair>(&pair.public()).is_err());
+	}
+
+	#[test]
+	fn rotate_password_reencrypts_all_key_files() {
+		let temp_dir = TempDir::new().unwrap();
+		let store = Store::open(temp_dir.path(), Some(String::from("old").into())).unwrap();
+		let plain = store.write().generate::<ed25519::AppPair>().unwrap().public();
+		let store = Store::open_encrypted(temp_dir.path(), String::from("old").into()).unwrap();
+		let encrypted = store.write().generate::<ed25519::AppPair>().unwrap().public();
+
+		let wrong = Store::open_encrypted(temp_dir.path(), String::from("wrong").into()).unwrap();
+		assert!(wrong.write().rotate_password(String::from("new").into()).is_err());
+
+		assert_eq!(store.write().rotate_password(String::from("new").into()).unwrap(), 2);
+		assert!(store.read().key_pair::<ed25519::AppPair>(&encrypted).is_ok());
+
+		// the keys are still derived with the password they were generated with.
+		let store = Store::open_encrypted(temp_dir.path(), String::from("new").into()).unwrap();
+		for public in &[plain.clone(), encrypted] {
+			assert_eq!(
+				store.read().key_pair::<ed25519::AppPair>(public).unwrap().public(),
+				*public,
+			);
+		}
+
+		let store = Store::open_encrypted(temp_dir.path(), String::from("old").into()).unwrap();
+		assert!(store.read().key_pair::<ed25519::AppPair>(&plain).is_err());
+	}
+
 	#[test]
 	fn public_keys_are_returned() {
 		let temp_dir = TempDir::new().unwrap();
@@ -420,6 +664,27 @@ mod tests {
 		assert_eq!(public_keys, store_pubs);
 	}
 
+	#[test]
+	fn ecdsa_keys_are_kept_apart_from_the_other_keys_of_their_key_type() {
+		let temp_dir = TempDir::new().unwrap();
+		let store = Store::open(temp_dir.path(), None).unwrap();
+
+		let sr25519 = BareCryptoStore::sr25519_generate_new(&mut *store.write(), SR25519, None)
+			.unwrap();
+		let ecdsa = BareCryptoStore::ecdsa_generate_new(&mut *store.write(), SR25519, None)
+			.unwrap();
+
+		assert_eq!(store.read().sr25519_public_keys(SR25519), vec![sr25519]);
+		assert_eq!(store.read().ecdsa_public_keys(SR25519), vec![ecdsa.clone()]);
+
+		let key = CryptoTypePublicPair::from(&ecdsa);
+		assert!(store.read().keys(SR25519).unwrap().contains(&key));
+
+		let signature = store.read().sign_with(SR25519, &key, b"message").unwrap();
+		let signature = ecdsa::Signature::decode(&mut &signature[..]).unwrap();
+		assert!(ecdsa::Pair::verify(&signature, b"message", &ecdsa));
+	}
+
 	#[test]
 	fn store_unknown_and_extract_it() {
 		let temp_dir = TempDir::new().unwrap();
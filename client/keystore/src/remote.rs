@@ -0,0 +1,423 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Keystore whose keys are held by a remote signer, e.g. in front of an HSM.
+//!
+//! The signer is reached over HTTP and speaks JSON-RPC 2.0. All the bytes, key types and
+//! crypto ids included, are hex encoded with a `0x` prefix. The methods are:
+//!
+//! - `signer_keys(key_type)`, returning the `[crypto_id, public]` pairs of the key type.
+//! - `signer_generate(key_type, crypto_id)`, returning the public key of the new key.
+//! - `signer_signWith(key_type, crypto_id, public, message)`, returning the SCALE encoded
+//!   signature.
+//! - `signer_sr25519VrfSign(key_type, public, transcript)`, where the transcript is
+//!   `{ "label": bytes, "items": [[label, { "bytes": bytes } | { "u64": number }]] }` and the
+//!   labels of the items are plain strings, returning `{ "output": bytes, "proof": bytes }`.
+//! - `signer_hasKeys([[public, key_type]])`, returning whether all the keys are held.
+//!
+//! The private keys never leave the signer: the key pairs can't be retrieved from this keystore,
+//! so its users must sign through `BareCryptoStore::sign_with` and
+//! `BareCryptoStore::sr25519_vrf_sign`.
+
+use std::{sync::{Arc, atomic::{AtomicU64, Ordering}}, thread, time::Duration};
+
+use futures::{channel::{mpsc, oneshot}, prelude::*};
+use hyper::{Body, Client, Request, Uri, client::HttpConnector, header::CONTENT_TYPE};
+use log::warn;
+use parking_lot::RwLock;
+use serde_json::{json, Value};
+use sp_core::{
+	crypto::{CryptoTypeId, CryptoTypePublicPair, KeyTypeId, Public},
+	ecdsa, ed25519, sr25519,
+	traits::{BareCryptoStore, BareCryptoStoreError},
+	vrf::{VRFSignature, VRFTranscriptData, VRFTranscriptValue},
+};
+
+use crate::{Error, Result};
+
+/// How long to wait for the remote signer to answer by default.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A call to the remote signer, waiting for its response body.
+struct Call {
+	body: String,
+	response: oneshot::Sender<std::result::Result<Vec<u8>, String>>,
+}
+
+/// Keystore forwarding the signing requests to a remote signer.
+///
+/// The requests are sent from a dedicated thread, and the calls block until the signer answers
+/// or the timeout elapses, as the users of the keystore sign synchronously.
+pub struct RemoteKeystore {
+	calls: mpsc::UnboundedSender<Call>,
+	next_id: AtomicU64,
+}
+
+impl RemoteKeystore {
+	/// Connect to the remote signer at the given URI.
+	pub fn open(uri: &str, timeout: Duration) -> Result<Arc<RwLock<Self>>> {
+		let uri = uri.parse::<Uri>().map_err(|e| Error::InvalidUri(e.to_string()))?;
+		let (calls, receiver) = mpsc::unbounded();
+
+		let mut runtime = tokio::runtime::Builder::new()
+			.basic_scheduler()
+			.enable_all()
+			.build()?;
+		thread::Builder::new()
+			.name("remote-keystore".into())
+			.spawn(move || runtime.block_on(run_calls(uri, timeout, receiver)))?;
+
+		Ok(Arc::new(RwLock::new(RemoteKeystore {
+			calls,
+			next_id: AtomicU64::new(0),
+		})))
+	}
+
+	fn call(
+		&self,
+		method: &str,
+		params: Value,
+	) -> std::result::Result<Value, BareCryptoStoreError> {
+		let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+		let (response, receiver) = oneshot::channel();
+		let call = Call { body: request(id, method, params).to_string(), response };
+
+		let stopped = || BareCryptoStoreError::Unavailable("The signer thread has stopped".into());
+		self.calls.unbounded_send(call).map_err(|_| stopped())?;
+		let body = futures::executor::block_on(receiver)
+			.map_err(|_| stopped())?
+			.map_err(BareCryptoStoreError::Unavailable)?;
+
+		response_result(id, &body)
+	}
+
+	fn generate(
+		&self,
+		id: KeyTypeId,
+		crypto_id: CryptoTypeId,
+		seed: Option<&str>,
+	) -> std::result::Result<Vec<u8>, String> {
+		if seed.is_some() {
+			return Err("Keys can't be generated from a seed by the remote signer".into());
+		}
+
+		self.call("signer_generate", json!([to_hex(&id.0), to_hex(&crypto_id.0)]))
+			.and_then(|public| from_hex(&public))
+			.map_err(|e| e.to_string())
+	}
+
+	fn public_keys<T: Public>(&self, id: KeyTypeId, crypto_id: CryptoTypeId) -> Vec<T> {
+		match self.keys(id) {
+			Ok(keys) => keys.into_iter()
+				.filter(|key| key.0 == crypto_id && key.1.len() == T::default().as_ref().len())
+				.map(|key| T::from_slice(&key.1))
+				.collect(),
+			Err(e) => {
+				warn!("Unable to fetch the keys from the remote signer: {}", e);
+				Vec::new()
+			},
+		}
+	}
+}
+
+impl BareCryptoStore for RemoteKeystore {
+	fn sr25519_public_keys(&self, id: KeyTypeId) -> Vec<sr25519::Public> {
+		self.public_keys(id, sr25519::CRYPTO_ID)
+	}
+
+	fn sr25519_generate_new(
+		&mut self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> std::result::Result<sr25519::Public, String> {
+		let public = self.generate(id, sr25519::CRYPTO_ID, seed)?;
+		check_len(&public, 32).map(|_| sr25519::Public::from_slice(&public))
+	}
+
+	fn sr25519_key_pair(&self, _: KeyTypeId, _: &sr25519::Public) -> Option<sr25519::Pair> {
+		None
+	}
+
+	fn ed25519_public_keys(&self, id: KeyTypeId) -> Vec<ed25519::Public> {
+		self.public_keys(id, ed25519::CRYPTO_ID)
+	}
+
+	fn ed25519_generate_new(
+		&mut self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> std::result::Result<ed25519::Public, String> {
+		let public = self.generate(id, ed25519::CRYPTO_ID, seed)?;
+		check_len(&public, 32).map(|_| ed25519::Public::from_slice(&public))
+	}
+
+	fn ed25519_key_pair(&self, _: KeyTypeId, _: &ed25519::Public) -> Option<ed25519::Pair> {
+		None
+	}
+
+	fn ecdsa_public_keys(&self, id: KeyTypeId) -> Vec<ecdsa::Public> {
+		self.public_keys(id, ecdsa::CRYPTO_ID)
+	}
+
+	fn ecdsa_generate_new(
+		&mut self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> std::result::Result<ecdsa::Public, String> {
+		let public = self.generate(id, ecdsa::CRYPTO_ID, seed)?;
+		check_len(&public, 33).map(|_| ecdsa::Public::from_slice(&public))
+	}
+
+	fn ecdsa_key_pair(&self, _: KeyTypeId, _: &ecdsa::Public) -> Option<ecdsa::Pair> {
+		None
+	}
+
+	fn insert_unknown(&mut self, _: KeyTypeId, _: &str, _: &[u8]) -> std::result::Result<(), ()> {
+		// the secrets are never sent to the signer.
+		Err(())
+	}
+
+	fn password(&self) -> Option<&str> {
+		None
+	}
+
+	fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+		let keys = public_keys.iter()
+			.map(|(public, id)| json!([to_hex(public), to_hex(&id.0)]))
+			.collect::<Vec<_>>();
+
+		match self.call("signer_hasKeys", json!([keys])) {
+			Ok(Value::Bool(has_keys)) => has_keys,
+			Ok(_) => false,
+			Err(e) => {
+				warn!("Unable to check the keys of the remote signer: {}", e);
+				false
+			},
+		}
+	}
+
+	fn keys(
+		&self,
+		id: KeyTypeId,
+	) -> std::result::Result<Vec<CryptoTypePublicPair>, BareCryptoStoreError> {
+		let keys = self.call("signer_keys", json!([to_hex(&id.0)]))?;
+
+		keys.as_array()
+			.ok_or_else(|| invalid_response("Expected an array of keys"))?
+			.iter()
+			.map(|key| match key.as_array() {
+				Some(key) if key.len() == 2 => Ok(CryptoTypePublicPair(
+					crypto_type_id(&from_hex(&key[0])?)?,
+					from_hex(&key[1])?,
+				)),
+				_ => Err(invalid_response("Expected a pair of crypto id and public key")),
+			})
+			.collect()
+	}
+
+	fn sign_with(
+		&self,
+		id: KeyTypeId,
+		key: &CryptoTypePublicPair,
+		msg: &[u8],
+	) -> std::result::Result<Vec<u8>, BareCryptoStoreError> {
+		let params = json!([to_hex(&id.0), to_hex(&(key.0).0), to_hex(&key.1), to_hex(msg)]);
+		from_hex(&self.call("signer_signWith", params)?)
+	}
+
+	fn sr25519_vrf_sign(
+		&self,
+		id: KeyTypeId,
+		public: &sr25519::Public,
+		transcript_data: VRFTranscriptData,
+	) -> std::result::Result<VRFSignature, BareCryptoStoreError> {
+		let params = json!([to_hex(&id.0), to_hex(public.as_ref()), transcript(&transcript_data)]);
+		vrf_signature(&self.call("signer_sr25519VrfSign", params)?)
+	}
+}
+
+/// Send the calls to the signer until the keystore is dropped.
+async fn run_calls(uri: Uri, timeout: Duration, calls: mpsc::UnboundedReceiver<Call>) {
+	let client = Client::new();
+
+	calls.for_each_concurrent(None, |call| {
+		let send = tokio::time::timeout(timeout, send(&client, uri.clone(), call.body));
+		send.map(move |result| {
+			let result = result.unwrap_or_else(|_| Err("The remote signer timed out".into()));
+			// the caller only goes away if the node is shutting down.
+			let _ = call.response.send(result);
+		})
+	}).await
+}
+
+async fn send(
+	client: &Client<HttpConnector>,
+	uri: Uri,
+	body: String,
+) -> std::result::Result<Vec<u8>, String> {
+	let request = Request::post(uri)
+		.header(CONTENT_TYPE, "application/json")
+		.body(Body::from(body))
+		.map_err(|e| e.to_string())?;
+	let response = client.request(request).await.map_err(|e| e.to_string())?;
+
+	if !response.status().is_success() {
+		return Err(format!("The remote signer answered with {}", response.status()));
+	}
+
+	hyper::body::to_bytes(response.into_body()).await
+		.map(|body| body.to_vec())
+		.map_err(|e| e.to_string())
+}
+
+fn request(id: u64, method: &str, params: Value) -> Value {
+	json!({
+		"jsonrpc": "2.0",
+		"id": id,
+		"method": method,
+		"params": params,
+	})
+}
+
+fn response_result(id: u64, body: &[u8]) -> std::result::Result<Value, BareCryptoStoreError> {
+	let mut response: Value = serde_json::from_slice(body)
+		.map_err(|e| invalid_response(&e.to_string()))?;
+
+	if response["id"] != json!(id) {
+		return Err(invalid_response("Unexpected id"));
+	}
+
+	if let Some(error) = response.get("error") {
+		let message = error["message"].as_str().unwrap_or("Unknown error").to_string();
+		return Err(BareCryptoStoreError::Other(message));
+	}
+
+	match response.get_mut("result") {
+		Some(result) => Ok(result.take()),
+		None => Err(invalid_response("Missing result")),
+	}
+}
+
+fn transcript(data: &VRFTranscriptData) -> Value {
+	let items = data.items.iter()
+		.map(|(label, value)| match value {
+			VRFTranscriptValue::Bytes(bytes) => json!([label, { "bytes": to_hex(bytes) }]),
+			VRFTranscriptValue::U64(number) => json!([label, { "u64": number }]),
+		})
+		.collect::<Vec<_>>();
+
+	json!({
+		"label": to_hex(data.label),
+		"items": items,
+	})
+}
+
+fn vrf_signature(value: &Value) -> std::result::Result<VRFSignature, BareCryptoStoreError> {
+	let output = from_hex(&value["output"])?;
+	let proof = from_hex(&value["proof"])?;
+
+	Ok(VRFSignature {
+		output: schnorrkel::vrf::VRFOutput::from_bytes(&output)
+			.map_err(|e| invalid_response(&e.to_string()))?,
+		proof: schnorrkel::vrf::VRFProof::from_bytes(&proof)
+			.map_err(|e| invalid_response(&e.to_string()))?,
+	})
+}
+
+fn crypto_type_id(bytes: &[u8]) -> std::result::Result<CryptoTypeId, BareCryptoStoreError> {
+	let mut id = [0; 4];
+	check_len(bytes, id.len()).map_err(|e| invalid_response(&e))?;
+	id.copy_from_slice(bytes);
+	Ok(CryptoTypeId(id))
+}
+
+fn check_len(bytes: &[u8], len: usize) -> std::result::Result<(), String> {
+	if bytes.len() == len {
+		Ok(())
+	} else {
+		Err(format!("Expected {} bytes, got {}", len, bytes.len()))
+	}
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+	format!("0x{}", hex::encode(bytes))
+}
+
+fn from_hex(value: &Value) -> std::result::Result<Vec<u8>, BareCryptoStoreError> {
+	value.as_str()
+		.filter(|value| value.starts_with("0x"))
+		.and_then(|value| hex::decode(&value[2..]).ok())
+		.ok_or_else(|| invalid_response("Expected 0x-prefixed hex"))
+}
+
+fn invalid_response(e: &str) -> BareCryptoStoreError {
+	BareCryptoStoreError::Other(format!("Invalid response of the remote signer: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::crypto::Pair;
+
+	#[test]
+	fn results_are_matched_with_their_request() {
+		let body = br#"{"jsonrpc":"2.0","id":3,"result":"0x0102"}"#;
+		assert_eq!(response_result(3, body).and_then(|r| from_hex(&r)), Ok(vec![1, 2]));
+		assert!(response_result(4, body).is_err());
+
+		let error = br#"{"jsonrpc":"2.0","id":3,"error":{"code":1,"message":"No such key"}}"#;
+		assert_eq!(
+			response_result(3, error),
+			Err(BareCryptoStoreError::Other("No such key".into())),
+		);
+	}
+
+	#[test]
+	fn transcript_is_sent_as_labeled_values() {
+		let data = VRFTranscriptData {
+			label: b"BABE",
+			items: vec![
+				("slot number", VRFTranscriptValue::U64(7)),
+				("chain randomness", VRFTranscriptValue::Bytes(vec![0xff])),
+			],
+		};
+
+		assert_eq!(transcript(&data), json!({
+			"label": "0x42414245",
+			"items": [
+				["slot number", { "u64": 7 }],
+				["chain randomness", { "bytes": "0xff" }],
+			],
+		}));
+	}
+
+	#[test]
+	fn vrf_signature_is_decoded() {
+		let pair = sr25519::Pair::from_seed(&[1; 32]);
+		let keypair: &schnorrkel::Keypair = pair.as_ref();
+		let transcript = VRFTranscriptData { label: b"test", items: Vec::new() };
+		let (inout, proof, _) = keypair.vrf_sign(sp_core::vrf::make_transcript(transcript));
+		let response = json!({
+			"output": to_hex(&inout.to_output().to_bytes()),
+			"proof": to_hex(&proof.to_bytes()),
+		});
+
+		let signature = vrf_signature(&response).unwrap();
+		assert_eq!(signature.output, inout.to_output());
+		assert_eq!(signature.proof.to_bytes()[..], proof.to_bytes()[..]);
+		assert!(vrf_signature(&json!({ "output": "0x00", "proof": "0x00" })).is_err());
+	}
+}
@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
-use crate::{Network, Validator};
+use crate::{Network, TopicPolicy, Validator};
 use crate::state_machine::{ConsensusGossip, TopicNotification, PERIODIC_MAINTENANCE_INTERVAL};
 
 use sc_network::message::generic::ConsensusMessage;
@@ -22,6 +22,7 @@ use sc_network::{Event, ReputationChange};
 
 use futures::{prelude::*, channel::mpsc};
 use libp2p::PeerId;
+use prometheus_endpoint::Registry;
 use sp_runtime::{traits::Block as BlockT, ConsensusEngineId};
 use std::{borrow::Cow, pin::Pin, sync::Arc, task::{Context, Poll}};
 
@@ -44,14 +45,19 @@ impl<B: BlockT> GossipEngine<B> {
 		engine_id: ConsensusEngineId,
 		protocol_name: impl Into<Cow<'static, [u8]>>,
 		validator: Arc<dyn Validator<B>>,
+		metrics_registry: Option<&Registry>,
 	) -> Self where B: 'static {
-		let mut state_machine = ConsensusGossip::new();
+		let protocol_name = protocol_name.into();
+		let mut state_machine = ConsensusGossip::new(
+			&String::from_utf8_lossy(&protocol_name),
+			metrics_registry,
+		);
 
 		// We grab the event stream before registering the notifications protocol, otherwise we
 		// might miss events.
 		let network_event_stream = network.event_stream();
 
-		network.register_notifications_protocol(engine_id, protocol_name.into());
+		network.register_notifications_protocol(engine_id, protocol_name);
 		state_machine.register_validator(&mut network, engine_id, validator);
 
 		GossipEngine {
@@ -85,6 +91,17 @@ impl<B: BlockT> GossipEngine<B> {
 		self.state_machine.register_message(topic, message);
 	}
 
+	/// Register a policy controlling how messages on the given topic are validated, expired and
+	/// rebroadcast. Replaces any policy previously registered for the topic.
+	pub fn register_topic_policy(&mut self, topic: B::Hash, policy: TopicPolicy<B>) {
+		self.state_machine.register_topic_policy(self.engine_id, topic, policy);
+	}
+
+	/// Remove the policy registered for the given topic, if any.
+	pub fn unregister_topic_policy(&mut self, topic: B::Hash) {
+		self.state_machine.unregister_topic_policy(self.engine_id, topic);
+	}
+
 	/// Broadcast all messages with given topic.
 	pub fn broadcast_topic(&mut self, topic: B::Hash, force: bool) {
 		self.state_machine.broadcast_topic(&mut *self.network, topic, force);
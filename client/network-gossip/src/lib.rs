@@ -53,10 +53,20 @@
 //! opens the door for neighbor status packets to be baked into the gossip protocol.
 //! These status packets will typically contain light pieces of information
 //! used to inform peers of a current view of protocol state.
+//!
+//! # Topic policies
+//!
+//! On top of the engine-wide `Validator`, a `TopicPolicy` can be registered for an individual
+//! topic. It allows messages on that topic to expire after a fixed duration, to be checked by a
+//! dedicated `TopicValidator`, and to be rebroadcast at their own interval rather than the
+//! default one.
 
 pub use self::bridge::GossipEngine;
 pub use self::state_machine::TopicNotification;
-pub use self::validator::{DiscardAll, MessageIntent, Validator, ValidatorContext, ValidationResult};
+pub use self::validator::{
+	DiscardAll, MessageIntent, TopicPolicy, TopicValidator, Validator, ValidatorContext,
+	ValidationResult,
+};
 
 use futures::prelude::*;
 use sc_network::{Event, ExHashT, NetworkService, PeerId, ReputationChange};
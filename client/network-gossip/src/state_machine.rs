@@ -14,13 +14,13 @@
 // You should have received a copy of the GNU General Public License
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
-use crate::{Network, MessageIntent, Validator, ValidatorContext, ValidationResult};
+use crate::{Network, MessageIntent, TopicPolicy, Validator, ValidatorContext, ValidationResult};
 
 use std::collections::{HashMap, HashSet, hash_map::Entry};
 use std::sync::Arc;
 use std::iter;
 use std::time;
-use log::{trace, debug};
+use log::{trace, debug, error};
 use futures::channel::mpsc;
 use lru::LruCache;
 use libp2p::PeerId;
@@ -28,6 +28,7 @@ use sp_runtime::traits::{Block as BlockT, Hash, HashFor};
 use sp_runtime::ConsensusEngineId;
 pub use sc_network::message::generic::{Message, ConsensusMessage};
 use sc_network::config::Roles;
+use prometheus_endpoint::{Counter, Opts, PrometheusError, Registry, U64, register};
 use wasm_timer::Instant;
 
 // FIXME: Add additional spam/DoS attack protection: https://github.com/paritytech/substrate/issues/1115
@@ -48,6 +49,47 @@ mod rep {
 	pub const UNKNOWN_GOSSIP: Rep = Rep::new(-(1 << 6), "Unknown gossip message engine id");
 	/// Reputation change when a peer sends a message from a topic it isn't registered on.
 	pub const UNREGISTERED_TOPIC: Rep = Rep::new(-(1 << 10), "Unregistered gossip message topic");
+	/// Reputation change when a peer sends a message that is rejected by its topic validator.
+	pub const TOPIC_VALIDATION_FAILED: Rep = Rep::new(-(1 << 4), "Gossip message rejected by topic validator");
+}
+
+/// Prometheus metrics for a `ConsensusGossip`, labelled with the protocol it gossips on.
+#[derive(Clone)]
+struct Metrics {
+	registered_messages: Counter<U64>,
+	expired_messages: Counter<U64>,
+	rebroadcast_messages: Counter<U64>,
+	topic_validation_failures: Counter<U64>,
+}
+
+impl Metrics {
+	fn register(registry: &Registry, protocol: &str) -> Result<Self, PrometheusError> {
+		let counter = |name: &str, help: &str| -> Result<Counter<U64>, PrometheusError> {
+			register(
+				Counter::with_opts(Opts::new(name, help).const_label("protocol", protocol))?,
+				registry,
+			)
+		};
+
+		Ok(Self {
+			registered_messages: counter(
+				"network_gossip_registered_messages_total",
+				"Number of registered messages by the gossip service.",
+			)?,
+			expired_messages: counter(
+				"network_gossip_expired_messages_total",
+				"Number of expired messages by the gossip service.",
+			)?,
+			rebroadcast_messages: counter(
+				"network_gossip_rebroadcast_messages_total",
+				"Number of messages periodically rebroadcast by the gossip service.",
+			)?,
+			topic_validation_failures: counter(
+				"network_gossip_topic_validation_failures_total",
+				"Number of incoming messages rejected by a topic validator.",
+			)?,
+		})
+	}
 }
 
 struct PeerConsensus<H> {
@@ -69,6 +111,13 @@ struct MessageEntry<B: BlockT> {
 	topic: B::Hash,
 	message: ConsensusMessage,
 	sender: Option<PeerId>,
+	registered_at: Instant,
+}
+
+/// A registered topic policy along with its rebroadcast schedule.
+struct TopicState<B: BlockT> {
+	policy: TopicPolicy<B>,
+	next_rebroadcast: Option<Instant>,
 }
 
 /// Local implementation of `ValidatorContext`.
@@ -111,10 +160,11 @@ fn propagate<'a, B: BlockT, I>(
 	intent: MessageIntent,
 	peers: &mut HashMap<PeerId, PeerConsensus<B::Hash>>,
 	validators: &HashMap<ConsensusEngineId, Arc<dyn Validator<B>>>,
-)
+) -> usize
 	// (msg_hash, topic, message)
 	where I: Clone + IntoIterator<Item=(&'a B::Hash, &'a B::Hash, &'a ConsensusMessage)>,
 {
+	let mut sent = 0;
 	let mut check_fns = HashMap::new();
 	let mut message_allowed = move |who: &PeerId, intent: MessageIntent, topic: &B::Hash, message: &ConsensusMessage| {
 		let engine_id = message.engine_id;
@@ -157,8 +207,11 @@ fn propagate<'a, B: BlockT, I>(
 
 			trace!(target: "gossip", "Propagating to {}: {:?}", id, message);
 			network.write_notification(id.clone(), message.engine_id, message.data.clone());
+			sent += 1;
 		}
 	}
+
+	sent
 }
 
 /// Consensus network protocol handler. Manages statements and candidate requests.
@@ -168,22 +221,60 @@ pub struct ConsensusGossip<B: BlockT> {
 	messages: Vec<MessageEntry<B>>,
 	known_messages: LruCache<B::Hash, ()>,
 	validators: HashMap<ConsensusEngineId, Arc<dyn Validator<B>>>,
+	topic_policies: HashMap<(ConsensusEngineId, B::Hash), TopicState<B>>,
 	next_broadcast: Instant,
+	metrics: Option<Metrics>,
 }
 
 impl<B: BlockT> ConsensusGossip<B> {
-	/// Create a new instance.
-	pub fn new() -> Self {
+	/// Create a new instance gossiping on the given protocol, registering its metrics with the
+	/// given registry if any.
+	pub fn new(protocol: &str, metrics_registry: Option<&Registry>) -> Self {
+		let metrics = match metrics_registry.map(|registry| Metrics::register(registry, protocol)) {
+			Some(Ok(metrics)) => Some(metrics),
+			// the metrics of a gossip engine started again on the same protocol are already
+			// registered by the previous one.
+			Some(Err(PrometheusError::AlreadyReg)) => {
+				debug!(target: "gossip", "Metrics of {} already registered", protocol);
+				None
+			},
+			Some(Err(e)) => {
+				error!(target: "gossip", "Failed to register metrics: {:?}", e);
+				None
+			},
+			None => None,
+		};
+
 		ConsensusGossip {
 			peers: HashMap::new(),
 			live_message_sinks: HashMap::new(),
 			messages: Default::default(),
 			known_messages: LruCache::new(KNOWN_MESSAGES_CACHE_SIZE),
 			validators: Default::default(),
+			topic_policies: HashMap::new(),
 			next_broadcast: Instant::now() + REBROADCAST_INTERVAL,
+			metrics,
 		}
 	}
 
+	/// Register a policy for the messages of an engine on a topic, replacing any previously
+	/// registered one.
+	pub fn register_topic_policy(
+		&mut self,
+		engine_id: ConsensusEngineId,
+		topic: B::Hash,
+		policy: TopicPolicy<B>,
+	) {
+		let next_rebroadcast = policy.rebroadcast_interval.map(|interval| Instant::now() + interval);
+		self.topic_policies.insert((engine_id, topic), TopicState { policy, next_rebroadcast });
+	}
+
+	/// Remove the policy registered for the messages of an engine on a topic. Messages on the
+	/// topic fall back to the default expiration and rebroadcast behavior.
+	pub fn unregister_topic_policy(&mut self, engine_id: ConsensusEngineId, topic: B::Hash) {
+		self.topic_policies.remove(&(engine_id, topic));
+	}
+
 	/// Register message validator for a message type.
 	pub fn register_validator(
 		&mut self,
@@ -234,7 +325,12 @@ impl<B: BlockT> ConsensusGossip<B> {
 				topic,
 				message,
 				sender,
+				registered_at: Instant::now(),
 			});
+
+			if let Some(ref metrics) = self.metrics {
+				metrics.registered_messages.inc();
+			}
 		}
 	}
 
@@ -264,17 +360,56 @@ impl<B: BlockT> ConsensusGossip<B> {
 	/// Perform periodic maintenance
 	pub fn tick(&mut self, network: &mut dyn Network<B>) {
 		self.collect_garbage();
-		if Instant::now() >= self.next_broadcast {
+		let now = Instant::now();
+		if now >= self.next_broadcast {
 			self.rebroadcast(network);
-			self.next_broadcast = Instant::now() + REBROADCAST_INTERVAL;
+			self.next_broadcast = now + REBROADCAST_INTERVAL;
 		}
+		self.rebroadcast_scheduled_topics(network, now);
 	}
 
-	/// Rebroadcast all messages to all peers.
+	/// Rebroadcast all messages to all peers, except those on topics which have their own
+	/// rebroadcast schedule.
 	fn rebroadcast(&mut self, network: &mut dyn Network<B>) {
+		let topic_policies = &self.topic_policies;
 		let messages = self.messages.iter()
+			.filter(|entry| topic_policies
+				.get(&(entry.message.engine_id, entry.topic))
+				.map_or(true, |state| state.next_rebroadcast.is_none())
+			)
 			.map(|entry| (&entry.message_hash, &entry.topic, &entry.message));
-		propagate(network, messages, MessageIntent::PeriodicRebroadcast, &mut self.peers, &self.validators);
+		let sent = propagate(network, messages, MessageIntent::PeriodicRebroadcast, &mut self.peers, &self.validators);
+
+		if let Some(ref metrics) = self.metrics {
+			metrics.rebroadcast_messages.inc_by(sent as u64);
+		}
+	}
+
+	/// Rebroadcast the messages of all topics whose rebroadcast interval has elapsed.
+	fn rebroadcast_scheduled_topics(&mut self, network: &mut dyn Network<B>, now: Instant) {
+		let mut due = Vec::new();
+		for (key, state) in self.topic_policies.iter_mut() {
+			match (state.next_rebroadcast, state.policy.rebroadcast_interval) {
+				(Some(next), Some(interval)) if now >= next => {
+					state.next_rebroadcast = Some(now + interval);
+					due.push(*key);
+				},
+				_ => {},
+			}
+		}
+
+		if due.is_empty() {
+			return;
+		}
+
+		let messages = self.messages.iter()
+			.filter(|entry| due.contains(&(entry.message.engine_id, entry.topic)))
+			.map(|entry| (&entry.message_hash, &entry.topic, &entry.message));
+		let sent = propagate(network, messages, MessageIntent::PeriodicRebroadcast, &mut self.peers, &self.validators);
+
+		if let Some(ref metrics) = self.metrics {
+			metrics.rebroadcast_messages.inc_by(sent as u64);
+		}
 	}
 
 	/// Broadcast all messages with given topic.
@@ -298,10 +433,24 @@ impl<B: BlockT> ConsensusGossip<B> {
 		let known_messages = &mut self.known_messages;
 		let before = self.messages.len();
 		let validators = &self.validators;
+		let topic_policies = &self.topic_policies;
+		let now = Instant::now();
 
 		let mut check_fns = HashMap::new();
 		let mut message_expired = move |entry: &MessageEntry<B>| {
 			let engine_id = entry.message.engine_id;
+			if let Some(state) = topic_policies.get(&(engine_id, entry.topic)) {
+				let timed_out = state.policy.expiration
+					.map_or(false, |expiration| now.duration_since(entry.registered_at) >= expiration);
+				let expired = state.policy.validator
+					.as_ref()
+					.map_or(false, |validator| validator.message_expired(&entry.message.data));
+
+				if timed_out || expired {
+					return true;
+				}
+			}
+
 			let check_fn = match check_fns.entry(engine_id) {
 				Entry::Occupied(entry) => entry.into_mut(),
 				Entry::Vacant(vacant) => match validators.get(&engine_id) {
@@ -315,6 +464,10 @@ impl<B: BlockT> ConsensusGossip<B> {
 
 		self.messages.retain(|entry| !message_expired(entry));
 
+		if let Some(ref metrics) = self.metrics {
+			metrics.expired_messages.inc_by((before - self.messages.len()) as u64);
+		}
+
 		trace!(target: "gossip", "Cleaned up {} stale messages, {} left ({} known)",
 			before - self.messages.len(),
 			self.messages.len(),
@@ -390,6 +543,23 @@ impl<B: BlockT> ConsensusGossip<B> {
 				}
 			};
 
+			// check the message against its topic validator, if any
+			let topic_validator = validation_result.as_ref()
+				.and_then(|(topic, _)| self.topic_policies.get(&(engine_id, *topic)))
+				.and_then(|state| state.policy.validator.clone());
+
+			if let Some(topic_validator) = topic_validator {
+				let mut context = NetworkContext { gossip: self, network, engine_id };
+				if !topic_validator.validate(&mut context, &who, &message.data) {
+					trace!(target:"gossip", "Message from {} rejected by topic validator", who);
+					network.report_peer(who.clone(), rep::TOPIC_VALIDATION_FAILED);
+					if let Some(ref metrics) = self.metrics {
+						metrics.topic_validation_failures.inc();
+					}
+					continue;
+				}
+			}
+
 			if let Some((topic, keep)) = validation_result {
 				network.report_peer(who.clone(), rep::GOSSIP_SUCCESS);
 				if let Some(ref mut peer) = self.peers.get_mut(&who) {
@@ -515,11 +685,41 @@ mod tests {
 					topic: $topic,
 					message: ConsensusMessage { data: $m, engine_id: [0, 0, 0, 0]},
 					sender: None,
+					registered_at: Instant::now(),
 				});
 			}
 		}
 	}
 
+	#[derive(Clone, Default)]
+	struct NoOpNetwork {
+		reports: Arc<std::sync::Mutex<Vec<(PeerId, crate::ReputationChange)>>>,
+	}
+
+	impl Network<Block> for NoOpNetwork {
+		fn event_stream(
+			&self,
+		) -> std::pin::Pin<Box<dyn futures::Stream<Item = crate::Event> + Send>> {
+			unimplemented!("Not required in tests")
+		}
+
+		fn report_peer(&self, who: PeerId, reputation: crate::ReputationChange) {
+			self.reports.lock().unwrap().push((who, reputation));
+		}
+
+		fn disconnect_peer(&self, _: PeerId) {}
+
+		fn write_notification(&self, _: PeerId, _: crate::ConsensusEngineId, _: Vec<u8>) {}
+
+		fn register_notifications_protocol(
+			&self,
+			_: ConsensusEngineId,
+			_: std::borrow::Cow<'static, [u8]>,
+		) {}
+
+		fn announce(&self, _: H256, _: Vec<u8>) {}
+	}
+
 	struct AllowAll;
 	impl Validator<Block> for AllowAll {
 		fn validate(
@@ -556,7 +756,7 @@ mod tests {
 
 		let prev_hash = H256::random();
 		let best_hash = H256::random();
-		let mut consensus = ConsensusGossip::<Block>::new();
+		let mut consensus = ConsensusGossip::<Block>::new("test", None);
 		let m1_hash = H256::random();
 		let m2_hash = H256::random();
 		let m1 = vec![1, 2, 3];
@@ -583,9 +783,104 @@ mod tests {
 		assert!(consensus.known_messages.contains(&m2_hash));
 	}
 
+	#[test]
+	fn collects_garbage_according_to_topic_policy() {
+		struct ExpireOdd;
+		impl crate::TopicValidator<Block> for ExpireOdd {
+			fn message_expired(&self, data: &[u8]) -> bool {
+				data[0] % 2 == 1
+			}
+		}
+
+		let mut consensus = ConsensusGossip::<Block>::new("test", None);
+		consensus.register_validator_internal([0, 0, 0, 0], Arc::new(AllowAll));
+
+		let timed_topic = [1; 32].into();
+		let validated_topic = [2; 32].into();
+		let other_topic = [3; 32].into();
+
+		let message = |data: Vec<u8>| ConsensusMessage { data, engine_id: [0, 0, 0, 0] };
+		consensus.register_message(timed_topic, message(vec![0]));
+		consensus.register_message(validated_topic, message(vec![1]));
+		consensus.register_message(validated_topic, message(vec![2]));
+		consensus.register_message(other_topic, message(vec![3]));
+
+		consensus.register_topic_policy([0, 0, 0, 0], timed_topic, TopicPolicy {
+			expiration: Some(time::Duration::from_secs(0)),
+			..Default::default()
+		});
+		consensus.register_topic_policy([0, 0, 0, 0], validated_topic, TopicPolicy {
+			validator: Some(Arc::new(ExpireOdd)),
+			..Default::default()
+		});
+
+		consensus.collect_garbage();
+
+		let remaining: Vec<_> = consensus.messages.iter().map(|e| e.message.data.clone()).collect();
+		assert_eq!(remaining, vec![vec![2], vec![3]]);
+	}
+
+	#[test]
+	fn topic_validator_rejects_incoming_messages() {
+		struct RejectAll;
+		impl crate::TopicValidator<Block> for RejectAll {
+			fn validate(
+				&self,
+				_context: &mut dyn ValidatorContext<Block>,
+				_sender: &PeerId,
+				_data: &[u8],
+			) -> bool {
+				false
+			}
+		}
+
+		let mut consensus = ConsensusGossip::<Block>::new("test", None);
+		consensus.register_validator_internal([0, 0, 0, 0], Arc::new(AllowAll));
+
+		let mut network = NoOpNetwork::default();
+		let peer_id = PeerId::random();
+		consensus.new_peer(&mut network, peer_id.clone(), Roles::FULL);
+
+		// `AllowAll` puts every message under the default topic.
+		let message = ConsensusMessage { data: vec![1, 2, 3], engine_id: [0, 0, 0, 0] };
+		consensus.on_incoming(&mut network, peer_id.clone(), vec![message.clone()]);
+		assert_eq!(consensus.messages.len(), 1);
+
+		consensus.register_topic_policy([0, 0, 0, 0], H256::default(), TopicPolicy {
+			validator: Some(Arc::new(RejectAll)),
+			..Default::default()
+		});
+
+		let message = ConsensusMessage { data: vec![4, 5, 6], engine_id: [0, 0, 0, 0] };
+		consensus.on_incoming(&mut network, peer_id.clone(), vec![message]);
+		assert_eq!(consensus.messages.len(), 1);
+		assert!(network.reports.lock().unwrap().contains(&(peer_id, rep::TOPIC_VALIDATION_FAILED)));
+	}
+
+	#[test]
+	fn metrics_are_registered_per_protocol_and_registry() {
+		let registry = Registry::new();
+		let other_registry = Registry::new();
+		let first = ConsensusGossip::<Block>::new("/first/1", Some(&registry));
+		let second = ConsensusGossip::<Block>::new("/second/1", Some(&registry));
+		let other = ConsensusGossip::<Block>::new("/first/1", Some(&other_registry));
+		let again = ConsensusGossip::<Block>::new("/first/1", Some(&registry));
+
+		let count = |gossip: &ConsensusGossip<Block>| gossip.metrics.as_ref()
+			.map(|metrics| metrics.registered_messages.get());
+		first.metrics.as_ref().unwrap().registered_messages.inc();
+		assert_eq!(count(&first), Some(1));
+		assert_eq!(count(&second), Some(0));
+		// another registry gets its own metrics.
+		assert_eq!(count(&other), Some(0));
+		assert_eq!(other_registry.gather().len(), 4);
+		// the metrics of a protocol already registered with the registry aren't registered again.
+		assert_eq!(count(&again), None);
+	}
+
 	#[test]
 	fn message_stream_include_those_sent_before_asking_for_stream() {
-		let mut consensus = ConsensusGossip::<Block>::new();
+		let mut consensus = ConsensusGossip::<Block>::new("test", None);
 		consensus.register_validator_internal([0, 0, 0, 0], Arc::new(AllowAll));
 
 		let message = ConsensusMessage { data: vec![4, 5, 6], engine_id: [0, 0, 0, 0] };
@@ -599,7 +894,7 @@ mod tests {
 
 	#[test]
 	fn can_keep_multiple_messages_per_topic() {
-		let mut consensus = ConsensusGossip::<Block>::new();
+		let mut consensus = ConsensusGossip::<Block>::new("test", None);
 
 		let topic = [1; 32].into();
 		let msg_a = ConsensusMessage { data: vec![1, 2, 3], engine_id: [0, 0, 0, 0] };
@@ -613,7 +908,7 @@ mod tests {
 
 	#[test]
 	fn can_keep_multiple_subscribers_per_topic() {
-		let mut consensus = ConsensusGossip::<Block>::new();
+		let mut consensus = ConsensusGossip::<Block>::new("test", None);
 		consensus.register_validator_internal([0, 0, 0, 0], Arc::new(AllowAll));
 
 		let data = vec![4, 5, 6];
@@ -631,7 +926,7 @@ mod tests {
 
 	#[test]
 	fn topics_are_localized_to_engine_id() {
-		let mut consensus = ConsensusGossip::<Block>::new();
+		let mut consensus = ConsensusGossip::<Block>::new("test", None);
 		consensus.register_validator_internal([0, 0, 0, 0], Arc::new(AllowAll));
 
 		let topic = [1; 32].into();
@@ -684,7 +979,7 @@ mod tests {
 			}
 		}
 
-		let mut consensus = ConsensusGossip::<Block>::new();
+		let mut consensus = ConsensusGossip::<Block>::new("test", None);
 		consensus.register_validator_internal([0, 0, 0, 0], Arc::new(AllowAll));
 
 		let mut network = TestNetwork;
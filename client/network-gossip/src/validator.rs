@@ -16,6 +16,7 @@
 
 use sc_network::{config::Roles, PeerId};
 use sp_runtime::traits::Block as BlockT;
+use std::{sync::Arc, time::Duration};
 
 /// Validates consensus messages.
 pub trait Validator<B: BlockT>: Send + Sync {
@@ -58,6 +59,61 @@ pub trait ValidatorContext<B: BlockT> {
 	fn send_topic(&mut self, who: &PeerId, topic: B::Hash, force: bool);
 }
 
+/// Validates messages on a single topic.
+///
+/// Topic validators are consulted after the engine-wide `Validator` has accepted a message and
+/// assigned it to the topic they are registered for.
+pub trait TopicValidator<B: BlockT>: Send + Sync {
+	/// Validate a message on the topic. Returning `false` discards the message.
+	fn validate(
+		&self,
+		_context: &mut dyn ValidatorContext<B>,
+		_sender: &PeerId,
+		_data: &[u8],
+	) -> bool {
+		true
+	}
+
+	/// Whether a message kept under the topic has expired.
+	fn message_expired(&self, _data: &[u8]) -> bool {
+		false
+	}
+}
+
+/// How messages on a given topic are validated, expired and rebroadcast.
+///
+/// A topic without a registered policy only uses the engine-wide `Validator` and is rebroadcast
+/// at the default interval.
+pub struct TopicPolicy<B: BlockT> {
+	/// Messages on the topic expire once they have been kept for this long.
+	pub expiration: Option<Duration>,
+	/// Interval at which messages on the topic are rebroadcast to all peers. If `None`, the
+	/// default rebroadcast interval is used.
+	pub rebroadcast_interval: Option<Duration>,
+	/// Additional validation and expiration logic for messages on the topic.
+	pub validator: Option<Arc<dyn TopicValidator<B>>>,
+}
+
+impl<B: BlockT> Default for TopicPolicy<B> {
+	fn default() -> Self {
+		TopicPolicy {
+			expiration: None,
+			rebroadcast_interval: None,
+			validator: None,
+		}
+	}
+}
+
+impl<B: BlockT> Clone for TopicPolicy<B> {
+	fn clone(&self) -> Self {
+		TopicPolicy {
+			expiration: self.expiration,
+			rebroadcast_interval: self.rebroadcast_interval,
+			validator: self.validator.clone(),
+		}
+	}
+}
+
 /// The reason for sending out the message.
 #[derive(Eq, PartialEq, Copy, Clone)]
 #[cfg_attr(test, derive(Debug))]
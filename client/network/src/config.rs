@@ -284,6 +284,15 @@ pub struct NetworkConfiguration {
 	pub transport: TransportConfig,
 	/// Maximum number of peers to ask the same blocks in parallel.
 	pub max_parallel_downloads: u32,
+	/// Maximum number of light client requests served to each peer per second.
+	///
+	/// The extra requests are ignored and the peer is reported.
+	pub max_light_requests_per_sec: u32,
+	/// Maximum size in bytes of the proofs sent in response to light client requests.
+	///
+	/// Storage read proofs stop being generated as soon as they exceed it, and empty proofs are
+	/// sent instead of larger ones.
+	pub max_light_proof_size: usize,
 }
 
 impl Default for NetworkConfiguration {
@@ -309,6 +318,8 @@ impl Default for NetworkConfiguration {
 				use_yamux_flow_control: false,
 			},
 			max_parallel_downloads: 5,
+			max_light_requests_per_sec: 32,
+			max_light_proof_size: 8 * 1024 * 1024,
 		}
 	}
 }
@@ -38,7 +38,9 @@ use sp_arithmetic::traits::SaturatedConversion;
 use message::{BlockAnnounce, BlockAttributes, Direction, FromBlock, Message, RequestId};
 use message::generic::Message as GenericMessage;
 use light_dispatch::{LightDispatch, LightDispatchNetwork, RequestData};
-use prometheus_endpoint::{Registry, Gauge, GaugeVec, PrometheusError, Opts, register, U64};
+use prometheus_endpoint::{
+	Registry, Gauge, GaugeVec, CounterVec, PrometheusError, Opts, register, U64,
+};
 use sync::{ChainSync, SyncState};
 use crate::service::{TransactionPool, ExHashT};
 use crate::config::{BoxFinalityProofRequestBuilder, Roles};
@@ -53,6 +55,7 @@ use crate::chain::{Client, FinalityProofProvider};
 use sc_client_api::{FetchChecker, ChangesProof, StorageProof};
 use crate::error;
 use util::LruHashSet;
+use light_limits::LightLimits;
 use wasm_timer::Instant;
 
 // Include sources generated from protobuf definitions.
@@ -66,6 +69,7 @@ pub mod api {
 }
 
 mod generic_proto;
+mod light_limits;
 mod util;
 
 pub mod block_requests;
@@ -134,6 +138,8 @@ mod rep {
 	pub const BAD_ROLE: Rep = Rep::new_fatal("Unsupported role");
 	/// Peer response data does not have requested bits.
 	pub const BAD_RESPONSE: Rep = Rep::new(-(1 << 12), "Incomplete response");
+	/// Peer sent more light client requests than allowed.
+	pub const LIGHT_REQUESTS_FLOOD: Rep = Rep::new(-(1 << 10), "Too many light client requests");
 }
 
 struct Metrics {
@@ -144,6 +150,7 @@ struct Metrics {
 	fork_targets: Gauge<U64>,
 	finality_proofs: GaugeVec<U64>,
 	justifications: GaugeVec<U64>,
+	light_requests: CounterVec<U64>,
 }
 
 impl Metrics {
@@ -189,6 +196,17 @@ impl Metrics {
 				)?;
 				register(g, r)?
 			},
+			light_requests: {
+				let c = CounterVec::new(
+					Opts::new(
+						"sync_light_requests",
+						"Number of light client requests, by whether they were served, \
+						rate limited or had a too large proof",
+					),
+					&["request", "outcome"],
+				)?;
+				register(c, r)?
+			},
 		})
 	}
 }
@@ -221,6 +239,8 @@ pub struct Protocol<B: BlockT, H: ExHashT> {
 	protocol_name_by_engine: HashMap<ConsensusEngineId, Cow<'static, [u8]>>,
 	/// For each protocol name, the legacy gossiping engine ID.
 	protocol_engine_by_name: HashMap<Cow<'static, [u8]>, ConsensusEngineId>,
+	/// Limits on the light client requests served to each peer.
+	light_limits: LightLimits,
 	/// Prometheus metrics.
 	metrics: Option<Metrics>,
 }
@@ -409,6 +429,10 @@ pub struct ProtocolConfig {
 	pub roles: Roles,
 	/// Maximum number of peers to ask the same blocks in parallel.
 	pub max_parallel_downloads: u32,
+	/// Maximum number of light client requests served to each peer per second.
+	pub max_light_requests_per_sec: u32,
+	/// Maximum size in bytes of the proofs sent in response to light client requests.
+	pub max_light_proof_size: usize,
 }
 
 impl Default for ProtocolConfig {
@@ -416,6 +440,8 @@ impl Default for ProtocolConfig {
 		ProtocolConfig {
 			roles: Roles::FULL,
 			max_parallel_downloads: 5,
+			max_light_requests_per_sec: 32,
+			max_light_proof_size: 8 * 1024 * 1024,
 		}
 	}
 }
@@ -457,6 +483,10 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 		let versions = &((MIN_VERSION as u8)..=(CURRENT_VERSION as u8)).collect::<Vec<u8>>();
 		let behaviour = GenericProto::new(protocol_id, versions, peerset);
 
+		let light_limits = LightLimits::new(
+			config.max_light_requests_per_sec,
+			config.max_light_proof_size,
+		);
 		let protocol = Protocol {
 			tick_timeout: Box::pin(interval(TICK_TIMEOUT)),
 			propagate_timeout: Box::pin(interval(PROPAGATE_TIMEOUT)),
@@ -477,6 +507,7 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 			behaviour,
 			protocol_name_by_engine: HashMap::new(),
 			protocol_engine_by_name: HashMap::new(),
+			light_limits,
 			metrics: if let Some(r) = metrics_registry {
 				Some(Metrics::register(r)?)
 			} else {
@@ -652,19 +683,28 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 			},
 			GenericMessage::Transactions(m) =>
 				self.on_extrinsics(who, m),
-			GenericMessage::RemoteCallRequest(request) => self.on_remote_call_request(who, request),
+			GenericMessage::RemoteCallRequest(request) =>
+				if self.light_request_allowed(&who, "call") {
+					self.on_remote_call_request(who, request)
+				},
 			GenericMessage::RemoteCallResponse(response) =>
 				self.on_remote_call_response(who, response),
 			GenericMessage::RemoteReadRequest(request) =>
-				self.on_remote_read_request(who, request),
+				if self.light_request_allowed(&who, "read") {
+					self.on_remote_read_request(who, request)
+				},
 			GenericMessage::RemoteReadResponse(response) =>
 				self.on_remote_read_response(who, response),
 			GenericMessage::RemoteHeaderRequest(request) =>
-				self.on_remote_header_request(who, request),
+				if self.light_request_allowed(&who, "header") {
+					self.on_remote_header_request(who, request)
+				},
 			GenericMessage::RemoteHeaderResponse(response) =>
 				self.on_remote_header_response(who, response),
 			GenericMessage::RemoteChangesRequest(request) =>
-				self.on_remote_changes_request(who, request),
+				if self.light_request_allowed(&who, "changes") {
+					self.on_remote_changes_request(who, request)
+				},
 			GenericMessage::RemoteChangesResponse(response) =>
 				self.on_remote_changes_response(who, response),
 			GenericMessage::FinalityProofRequest(request) =>
@@ -672,7 +712,9 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 			GenericMessage::FinalityProofResponse(response) =>
 				return self.on_finality_proof_response(who, response),
 			GenericMessage::RemoteReadChildRequest(request) =>
-				self.on_remote_read_child_request(who, request),
+				if self.light_request_allowed(&who, "read_child") {
+					self.on_remote_read_child_request(who, request)
+				},
 			GenericMessage::Consensus(msg) =>
 				return if self.protocol_name_by_engine.contains_key(&msg.engine_id) {
 					CustomMessageOutcome::NotificationsReceived {
@@ -749,6 +791,7 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 			self.handshaking_peers.remove(&peer);
 			self.context_data.peers.remove(&peer)
 		};
+		self.light_limits.on_disconnect(&peer);
 		if let Some(_peer_data) = removed {
 			self.sync.peer_disconnected(peer.clone());
 			self.light_dispatch.on_disconnect(LightDispatchIn {
@@ -1414,6 +1457,50 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 		self.sync.on_block_finalized(&hash, *header.number())
 	}
 
+	/// Whether a light client request of the peer may be served, the peer being reported when it
+	/// sends more of them than allowed.
+	fn light_request_allowed(&mut self, who: &PeerId, request: &str) -> bool {
+		if self.light_limits.allow_request(who, Instant::now()) {
+			return true;
+		}
+
+		debug!(target: "sync", "Ignoring {} light client request of {}: too many requests",
+			request, who);
+		self.peerset_handle.report_peer(who.clone(), rep::LIGHT_REQUESTS_FLOOD);
+		self.report_light_request(request, "rate_limited");
+		false
+	}
+
+	/// Whether a proof of the given encoded size may be sent in response to a light client
+	/// request of the peer, the request being counted as served if so.
+	///
+	/// Only used for the proofs that can't be bounded while being generated: read proofs give
+	/// up as soon as they exceed the limit instead.
+	fn light_proof_allowed(&mut self, who: &PeerId, request: &str, size: usize) -> bool {
+		if self.light_limits.allow_proof_size(size) {
+			self.report_light_request(request, "served");
+			return true;
+		}
+
+		debug!(target: "sync", "Refusing {} light client request of {}: proof of {} bytes",
+			request, who, size);
+		self.report_light_request(request, "proof_too_large");
+		false
+	}
+
+	/// Note that the proof generated for a light client request of the peer went over the limit.
+	fn report_light_proof_too_large(&self, who: &PeerId, request: &str) {
+		debug!(target: "sync", "Refusing {} light client request of {}: proof over {} bytes",
+			request, who, self.light_limits.max_proof_size());
+		self.report_light_request(request, "proof_too_large");
+	}
+
+	fn report_light_request(&self, request: &str, outcome: &str) {
+		if let Some(metrics) = &self.metrics {
+			metrics.light_requests.with_label_values(&[request, outcome]).inc();
+		}
+	}
+
 	fn on_remote_call_request(
 		&mut self,
 		who: PeerId,
@@ -1443,6 +1530,11 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 				StorageProof::empty()
 			}
 		};
+		let proof = if self.light_proof_allowed(&who, "call", proof.encoded_size()) {
+			proof
+		} else {
+			StorageProof::empty()
+		};
 
 		self.send_message(
 			&who,
@@ -1558,11 +1650,19 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 
 		trace!(target: "sync", "Remote read request {} from {} ({} at {})",
 			request.id, who, keys_str(), request.block);
-		let proof = match self.context_data.chain.read_proof(
+		let proof = match self.context_data.chain.read_proof_with_size_limit(
 			&BlockId::Hash(request.block),
-			&mut request.keys.iter().map(AsRef::as_ref)
+			&mut request.keys.iter().map(AsRef::as_ref),
+			self.light_limits.max_proof_size(),
 		) {
-			Ok(proof) => proof,
+			Ok(Some(proof)) => {
+				self.report_light_request("read", "served");
+				proof
+			},
+			Ok(None) => {
+				self.report_light_proof_too_large(&who, "read");
+				StorageProof::empty()
+			},
 			Err(error) => {
 				trace!(target: "sync", "Remote read request {} from {} ({} at {}) failed with: {}",
 					request.id,
@@ -1607,13 +1707,21 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 		trace!(target: "sync", "Remote read child request {} from {} ({} {} at {})",
 			request.id, who, request.storage_key.to_hex::<String>(), keys_str(), request.block);
 		let proof = if let Some(child_info) = ChildInfo::resolve_child_info(request.child_type, &request.child_info[..]) {
-			match self.context_data.chain.read_child_proof(
+			match self.context_data.chain.read_child_proof_with_size_limit(
 				&BlockId::Hash(request.block),
 				&request.storage_key,
 				child_info,
 				&mut request.keys.iter().map(AsRef::as_ref),
+				self.light_limits.max_proof_size(),
 			) {
-				Ok(proof) => proof,
+				Ok(Some(proof)) => {
+					self.report_light_request("read_child", "served");
+					proof
+				},
+				Ok(None) => {
+					self.report_light_proof_too_large(&who, "read_child");
+					StorageProof::empty()
+				},
 				Err(error) => {
 					trace!(target: "sync", "Remote read child request {} from {} ({} {} at {}) failed with: {}",
 						request.id,
@@ -1678,6 +1786,11 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 				(Default::default(), StorageProof::empty())
 			}
 		};
+		let (header, proof) = if self.light_proof_allowed(&who, "header", proof.encoded_size()) {
+			(header, proof)
+		} else {
+			(Default::default(), StorageProof::empty())
+		};
 		self.send_message(
 			&who,
 			GenericMessage::RemoteHeaderResponse(message::RemoteHeaderResponse {
@@ -1748,6 +1861,17 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 				}
 			}
 		};
+		let proof_size = proof.proof.encoded_size() + proof.roots_proof.encoded_size();
+		let proof = if self.light_proof_allowed(&who, "changes", proof_size) {
+			proof
+		} else {
+			ChangesProof::<B::Header> {
+				max_block: Zero::zero(),
+				proof: vec![],
+				roots: BTreeMap::new(),
+				roots_proof: StorageProof::empty(),
+			}
+		};
 		self.send_message(
 			&who,
 			GenericMessage::RemoteChangesResponse(message::RemoteChangesResponse {
@@ -2129,6 +2253,7 @@ mod tests {
 			ProtocolConfig {
 				roles: Roles::FULL,
 				max_parallel_downloads: 10,
+				..ProtocolConfig::default()
 			},
 			client.clone(),
 			Arc::new(AlwaysBadChecker),
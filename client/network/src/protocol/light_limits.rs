@@ -0,0 +1,95 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Limits on the light client requests served to each peer.
+//!
+//! A peer sending more requests per second than allowed gets the extra ones ignored, logged and
+//! counted, while the proofs exceeding the size ceiling are replaced with empty ones.
+
+use libp2p::PeerId;
+use std::{collections::HashMap, time::Duration};
+use wasm_timer::Instant;
+
+/// Duration of the windows the requests of a peer are counted over.
+const WINDOW: Duration = Duration::from_secs(1);
+
+/// Limits on the light client requests served to each peer.
+#[derive(Debug)]
+pub(crate) struct LightLimits {
+	max_requests_per_sec: u32,
+	max_proof_size: usize,
+	/// For each peer, the start of its current window and the number of its requests in it.
+	windows: HashMap<PeerId, (Instant, u32)>,
+}
+
+impl LightLimits {
+	/// Create limits allowing each peer the given number of requests per second, whose proofs
+	/// may not exceed the given size in bytes.
+	pub(crate) fn new(max_requests_per_sec: u32, max_proof_size: usize) -> Self {
+		LightLimits { max_requests_per_sec, max_proof_size, windows: HashMap::new() }
+	}
+
+	/// Whether a request of the peer received at `now` may be served, counting it if so.
+	pub(crate) fn allow_request(&mut self, who: &PeerId, now: Instant) -> bool {
+		let (start, count) = self.windows.entry(who.clone()).or_insert((now, 0));
+		if now.duration_since(*start) >= WINDOW {
+			*start = now;
+			*count = 0;
+		}
+
+		if *count >= self.max_requests_per_sec {
+			return false;
+		}
+		*count += 1;
+		true
+	}
+
+	/// Maximum size in bytes of the proofs sent.
+	pub(crate) fn max_proof_size(&self) -> usize {
+		self.max_proof_size
+	}
+
+	/// Whether a proof of the given encoded size may be sent.
+	pub(crate) fn allow_proof_size(&self, size: usize) -> bool {
+		size <= self.max_proof_size
+	}
+
+	/// Forget about the requests of a disconnected peer.
+	pub(crate) fn on_disconnect(&mut self, who: &PeerId) {
+		self.windows.remove(who);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn requests_are_limited_per_peer_and_second() {
+		let mut limits = LightLimits::new(2, 1024);
+		let (alice, bob) = (PeerId::random(), PeerId::random());
+		let now = Instant::now();
+
+		assert!(limits.allow_request(&alice, now));
+		assert!(limits.allow_request(&alice, now));
+		assert!(!limits.allow_request(&alice, now + Duration::from_millis(500)));
+		assert!(limits.allow_request(&bob, now));
+		assert!(limits.allow_request(&alice, now + WINDOW));
+
+		assert!(limits.allow_proof_size(1024));
+		assert!(!limits.allow_proof_size(1025));
+	}
+}
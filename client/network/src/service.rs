@@ -234,6 +234,8 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkWorker<B, H> {
 			protocol::ProtocolConfig {
 				roles: params.roles,
 				max_parallel_downloads: params.network_config.max_parallel_downloads,
+				max_light_requests_per_sec: params.network_config.max_light_requests_per_sec,
+				max_light_proof_size: params.network_config.max_light_proof_size,
 			},
 			params.chain.clone(),
 			checker.clone(),
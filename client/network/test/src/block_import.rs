@@ -84,7 +84,14 @@ fn async_import_queue_drops() {
 	// Perform this test multiple times since it exhibits non-deterministic behavior.
 	for _ in 0..100 {
 		let verifier = PassThroughVerifier(true);
-		let queue = BasicQueue::new(verifier, Box::new(substrate_test_runtime_client::new()), None, None);
+		let queue = BasicQueue::new(
+			verifier,
+			None,
+			Box::new(substrate_test_runtime_client::new()),
+			None,
+			None,
+			None,
+		);
 		drop(queue);
 	}
 }
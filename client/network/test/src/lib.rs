@@ -21,9 +21,9 @@ mod block_import;
 #[cfg(test)]
 mod sync;
 
-use std::{collections::HashMap, pin::Pin, sync::Arc, marker::PhantomData, task::{Poll, Context as FutureContext}};
+use std::{collections::{HashMap, HashSet}, pin::Pin, sync::Arc, marker::PhantomData, task::{Poll, Context as FutureContext}};
 
-use libp2p::build_multiaddr;
+use libp2p::{build_multiaddr, Multiaddr};
 use log::trace;
 use sc_network::config::FinalityProofProvider;
 use sp_blockchain::{
@@ -35,7 +35,8 @@ use sc_client::LongestChain;
 use sc_network::config::Roles;
 use sp_consensus::block_validation::DefaultBlockAnnounceValidator;
 use sp_consensus::import_queue::{
-	BasicQueue, BoxJustificationImport, Verifier, BoxFinalityProofImport,
+	BasicQueue, BoxJustificationImport, Verifier, BoxFinalityProofImport, PreVerifier,
+	SharedPreVerifier,
 };
 use sp_consensus::block_import::{BlockImport, ImportResult};
 use sp_consensus::Error as ConsensusError;
@@ -184,6 +185,13 @@ pub struct Peer<D> {
 	network: NetworkWorker<Block, <Block as BlockT>::Hash>,
 	imported_blocks_stream: Pin<Box<dyn Stream<Item = BlockImportNotification<Block>> + Send>>,
 	finality_notification_stream: Pin<Box<dyn Stream<Item = FinalityNotification<Block>> + Send>>,
+	listen_addr: Multiaddr,
+	/// The peers this one may be connected to, or `None` if it may be connected to any peer.
+	allowed_peers: Option<HashSet<PeerId>>,
+	/// The number of polls of the network skipped between two polls of this peer.
+	lag: u32,
+	/// The number of polls of the network skipped since this peer was last polled.
+	skipped_polls: u32,
 }
 
 impl<D> Peer<D> {
@@ -212,6 +220,53 @@ impl<D> Peer<D> {
 		self.num_peers() == 0
 	}
 
+	/// Returns the address this peer listens on.
+	pub fn listen_addr(&self) -> &Multiaddr {
+		&self.listen_addr
+	}
+
+	/// Delays everything this peer sends and receives, by only polling it once every `polls + 1`
+	/// polls of the network.
+	pub fn set_lag(&mut self, polls: u32) {
+		self.lag = polls;
+		self.skipped_polls = 0;
+	}
+
+	/// Returns true if this peer may be connected to `other`.
+	pub fn allows(&self, other: &PeerId) -> bool {
+		self.allowed_peers.as_ref().map_or(true, |allowed| allowed.contains(other))
+	}
+
+	/// Only lets this peer be connected to the given peers, disconnecting it from the others.
+	fn restrict_connections(&mut self, peers: Vec<(PeerId, Multiaddr)>) {
+		let service = self.network.service();
+		let allowed = peers.iter().map(|(id, _)| id.clone()).collect::<HashSet<_>>();
+
+		for (id, addr) in peers {
+			service.add_reserved_peer(format!("{}/p2p/{}", addr, id))
+				.expect("The addresses of the test network peers are valid; qed");
+		}
+		if let Some(previous) = self.allowed_peers.take() {
+			for id in previous.difference(&allowed) {
+				service.remove_reserved_peer(id.clone());
+			}
+		}
+		service.deny_unreserved_peers();
+
+		self.allowed_peers = Some(allowed);
+	}
+
+	/// Lets this peer be connected to any peer again.
+	fn lift_connection_restrictions(&mut self) {
+		if let Some(previous) = self.allowed_peers.take() {
+			let service = self.network.service();
+			for id in previous {
+				service.remove_reserved_peer(id);
+			}
+			service.accept_unreserved_peers();
+		}
+	}
+
 	/// Request a justification for the given block.
 	pub fn request_justification(&self, hash: &<Block as BlockT>::Hash, number: NumberFor<Block>) {
 		self.network.service().request_justification(hash, number);
@@ -497,6 +552,33 @@ impl<B: BlockT> VerifierAdapter<B> {
 	}
 }
 
+/// Records the headers rejected by a pre-verifier along with the ones rejected by the verifier.
+struct PreVerifierAdapter<B: BlockT> {
+	pre_verifier: SharedPreVerifier<B>,
+	failed_verifications: Arc<Mutex<HashMap<B::Hash, String>>>,
+}
+
+impl<B: BlockT> PreVerifier<B> for PreVerifierAdapter<B> {
+	fn pre_verify(&self, header: &B::Header) -> Result<(), String> {
+		self.pre_verifier.pre_verify(header).map_err(|e| {
+			self.failed_verifications.lock().insert(header.hash(), e.clone());
+			e
+		})
+	}
+}
+
+impl<B: BlockT> VerifierAdapter<B> {
+	fn adapt_pre_verifier(
+		&self,
+		pre_verifier: Option<SharedPreVerifier<B>>,
+	) -> Option<SharedPreVerifier<B>> {
+		pre_verifier.map(|pre_verifier| Arc::new(PreVerifierAdapter {
+			pre_verifier,
+			failed_verifications: self.failed_verifications.clone(),
+		}) as SharedPreVerifier<B>)
+	}
+}
+
 pub trait TestNetFactory: Sized {
 	type Verifier: 'static + Verifier<Block>;
 	type PeerData: Default;
@@ -510,6 +592,11 @@ pub trait TestNetFactory: Sized {
 		peer_data: &Self::PeerData,
 	) -> Self::Verifier;
 
+	/// Get the pre-verifier run ahead of the given verifier (if supported).
+	fn make_pre_verifier(&self, _verifier: &Self::Verifier) -> Option<SharedPreVerifier<Block>> {
+		None
+	}
+
 	/// Get reference to peer.
 	fn peer(&mut self, i: usize) -> &mut Peer<Self::PeerData>;
 	fn peers(&self) -> &Vec<Peer<Self::PeerData>>;
@@ -583,13 +670,16 @@ pub trait TestNetFactory: Sized {
 			config,
 			&data,
 		);
+		let pre_verifier = self.make_pre_verifier(&verifier);
 		let verifier = VerifierAdapter::new(Arc::new(Mutex::new(Box::new(verifier) as Box<_>)));
 
 		let import_queue = Box::new(BasicQueue::new(
 			verifier.clone(),
+			verifier.adapt_pre_verifier(pre_verifier),
 			Box::new(block_import.clone()),
 			justification_import,
 			finality_proof_import,
+			None,
 		));
 
 		let listen_addr = build_multiaddr![Memory(rand::random::<u64>())];
@@ -633,17 +723,49 @@ pub trait TestNetFactory: Sized {
 				block_import,
 				verifier,
 				network,
+				listen_addr,
+				allowed_peers: None,
+				lag: 0,
+				skipped_polls: 0,
 			});
 		});
 	}
 
 	/// Add a light peer.
 	fn add_light_peer(&mut self, config: &ProtocolConfig) {
+		let (client, backend) = substrate_test_runtime_client::new_light();
+		self.add_light_peer_with_client(config, client, backend);
+	}
+
+	/// Add a light peer syncing from the given checkpoint, the last block of a CHT of the given
+	/// size, rather than from the genesis block.
+	fn add_light_peer_with_checkpoint(
+		&mut self,
+		config: &ProtocolConfig,
+		cht_size: u32,
+		checkpoint: <Block as BlockT>::Header,
+		header_cht_roots: Vec<Hash>,
+	) {
+		let (client, backend) = substrate_test_runtime_client::new_light_with_cht_size(cht_size);
+		sc_client_api::light::Storage::import_checkpoint(
+			backend.blockchain().storage(),
+			checkpoint,
+			header_cht_roots,
+		).expect("The checkpoint is the last block of a CHT");
+		self.add_light_peer_with_client(config, client, backend);
+	}
+
+	/// Add a light peer with the given client.
+	fn add_light_peer_with_client(
+		&mut self,
+		config: &ProtocolConfig,
+		client: PeersLightClient,
+		backend: Arc<substrate_test_runtime_client::LightBackend>,
+	) {
 		let mut config = config.clone();
 		config.roles = Roles::LIGHT;
 
-		let (c, backend) = substrate_test_runtime_client::new_light();
-		let client = Arc::new(c);
+		let client = Arc::new(client);
 		let (
 			block_import,
 			justification_import,
@@ -657,13 +779,16 @@ pub trait TestNetFactory: Sized {
 			&config,
 			&data,
 		);
+		let pre_verifier = self.make_pre_verifier(&verifier);
 		let verifier = VerifierAdapter::new(Arc::new(Mutex::new(Box::new(verifier) as Box<_>)));
 
 		let import_queue = Box::new(BasicQueue::new(
 			verifier.clone(),
+			verifier.adapt_pre_verifier(pre_verifier),
 			Box::new(block_import.clone()),
 			justification_import,
 			finality_proof_import,
+			None,
 		));
 
 		let listen_addr = build_multiaddr![Memory(rand::random::<u64>())];
@@ -707,6 +832,10 @@ pub trait TestNetFactory: Sized {
 				imported_blocks_stream,
 				finality_notification_stream,
 				network,
+				listen_addr,
+				allowed_peers: None,
+				lag: 0,
+				skipped_polls: 0,
 			});
 		});
 	}
@@ -752,6 +881,75 @@ pub trait TestNetFactory: Sized {
 		Poll::Ready(())
 	}
 
+	/// Polls the testnet until every peer is connected to all the peers it may be connected to.
+	///
+	/// Must be executed in a task context.
+	fn poll_until_connected(&mut self, cx: &mut FutureContext) -> Poll<()> {
+		self.poll(cx);
+
+		let peers = self.peers();
+		for peer in peers.iter() {
+			let id = peer.id();
+			let expected = peers.iter()
+				.filter(|other| other.id() != id && peer.allows(&other.id()) && other.allows(&id))
+				.count();
+			if peer.num_peers() != expected {
+				return Poll::Pending
+			}
+		}
+		Poll::Ready(())
+	}
+
+	/// Splits the network in groups of peers, the peers of a group being only connected to each
+	/// other. The peers that are in no group are disconnected from all the others.
+	///
+	/// The peers added afterwards are not part of the partition, but can only be connected to
+	/// the peers that are in no group either.
+	fn partition(&mut self, groups: &[&[usize]]) {
+		self.mut_peers(|peers| {
+			let addresses = peers.iter()
+				.map(|peer| (peer.id(), peer.listen_addr.clone()))
+				.collect::<Vec<_>>();
+
+			for (i, peer) in peers.iter_mut().enumerate() {
+				let group = groups.iter().find(|group| group.contains(&i)).cloned().unwrap_or_default();
+				peer.restrict_connections(
+					group.iter().filter(|j| **j != i).map(|j| addresses[*j].clone()).collect(),
+				);
+			}
+		});
+	}
+
+	/// Lets all the peers be connected to each other again, undoing `partition` and
+	/// `disconnect_peer`.
+	fn heal_partition(&mut self) {
+		self.mut_peers(|peers| {
+			for peer in peers.iter_mut() {
+				peer.lift_connection_restrictions();
+			}
+		});
+	}
+
+	/// Disconnects a peer from all the others, as if it went offline.
+	fn disconnect_peer(&mut self, i: usize) {
+		self.peer(i).restrict_connections(Vec::new());
+	}
+
+	/// Lets a peer disconnected by `disconnect_peer` be connected to the others again.
+	///
+	/// It stays disconnected from the peers of a partition it was not part of.
+	fn reconnect_peer(&mut self, i: usize) {
+		self.peer(i).lift_connection_restrictions();
+	}
+
+	/// Blocks the current thread until every peer is connected to all the peers it may be
+	/// connected to.
+	///
+	/// Calls `poll_until_connected` repeatedly.
+	fn block_until_connected(&mut self) {
+		futures::executor::block_on(futures::future::poll_fn::<(), _>(|cx| self.poll_until_connected(cx)));
+	}
+
 	/// Blocks the current thread until we are sync'ed.
 	///
 	/// Calls `poll_until_sync` repeatedly.
@@ -770,6 +968,14 @@ pub trait TestNetFactory: Sized {
 	fn poll(&mut self, cx: &mut FutureContext) {
 		self.mut_peers(|peers| {
 			for peer in peers {
+				if peer.skipped_polls < peer.lag {
+					peer.skipped_polls += 1;
+					// make sure that the lagging peer gets polled eventually.
+					cx.waker().wake_by_ref();
+					continue
+				}
+				peer.skipped_polls = 0;
+
 				trace!(target: "sync", "-- Polling {}", peer.id());
 				if let Poll::Ready(res) = Pin::new(&mut peer.network).poll(cx) {
 					res.unwrap();
@@ -492,6 +492,41 @@ fn can_not_sync_from_light_peer() {
 	}));
 }
 
+#[test]
+fn light_peer_syncs_from_checkpoint() {
+	let _ = ::env_logger::try_init();
+	let cht_size = 8;
+
+	// given the network with 1 full node (#0)
+	let mut net = TestNet::new(1);
+	net.peer(0).push_blocks(24, false);
+	let full_hashes = (0..=24u64)
+		.map(|number| net.peer(0).client().header(&BlockId::Number(number)).unwrap().unwrap().hash())
+		.collect::<Vec<_>>();
+
+	// and 1 light node (#1) syncing from #16, the last block of CHT#1
+	let cht_roots = (0..2u64).map(|cht_number| sc_client::cht::compute_root::<
+		substrate_test_runtime_client::runtime::Header,
+		sp_runtime::traits::BlakeTwo256,
+		_,
+	>(
+		cht_size,
+		cht_number,
+		(sc_client::cht::start_number(cht_size, cht_number)..)
+			.map(|number| Ok(full_hashes.get(number as usize).cloned())),
+	).unwrap()).collect::<Vec<_>>();
+	let checkpoint = net.peer(0).client().header(&BlockId::Number(16)).unwrap().unwrap();
+	net.add_light_peer_with_checkpoint(&Default::default(), cht_size as u32, checkpoint, cht_roots);
+
+	// the light client only syncs the blocks after the checkpoint
+	net.block_until_sync();
+	let light_info = net.peer(1).client.info();
+	assert_eq!(light_info.best_number, 24);
+	assert_eq!(light_info.best_hash, full_hashes[24]);
+	assert!(net.peer(1).client().header(&BlockId::Hash(full_hashes[20])).unwrap().is_some());
+	assert!(net.peer(1).client().header(&BlockId::Hash(full_hashes[8])).unwrap().is_none());
+}
+
 #[test]
 fn light_peer_imports_header_from_announce() {
 	let _ = ::env_logger::try_init();
@@ -657,3 +692,74 @@ fn full_sync_requires_block_body() {
 	net.block_until_idle();
 	assert_eq!(net.peer(1).client.info().best_number, 0);
 }
+
+#[test]
+fn partitioned_peers_sync_once_healed() {
+	let _ = ::env_logger::try_init();
+	let mut net = TestNet::new(4);
+	net.block_until_connected();
+
+	net.partition(&[&[0, 1], &[2, 3]]);
+	net.block_until_connected();
+	assert_eq!(net.peer(0).num_peers(), 1);
+	assert_eq!(net.peer(2).num_peers(), 1);
+
+	net.peer(0).push_blocks(10, false);
+	net.peer(2).push_blocks(20, false);
+
+	// each group only syncs the blocks of its own peers.
+	block_on(futures::future::poll_fn::<(), _>(|cx| {
+		net.poll(cx);
+		if net.peer(1).client.info().best_number != 10 || net.peer(3).client.info().best_number != 20 {
+			return Poll::Pending
+		}
+		Poll::Ready(())
+	}));
+	net.block_until_idle();
+	assert_eq!(net.peer(0).client.info().best_number, 10);
+	assert_eq!(net.peer(2).client.info().best_number, 20);
+
+	net.heal_partition();
+	net.block_until_sync();
+	for peer in 0..4 {
+		assert_eq!(net.peer(peer).client.info().best_number, 20);
+	}
+}
+
+#[test]
+fn lagging_peer_syncs() {
+	let _ = ::env_logger::try_init();
+	let mut net = TestNet::new(2);
+	net.peer(1).set_lag(10);
+
+	net.peer(0).push_blocks(50, false);
+	net.block_until_sync();
+	let peer0 = &net.peers()[0];
+	assert!(net.peers()[1].blockchain_canon_equals(peer0));
+}
+
+#[test]
+fn disconnected_peer_catches_up_once_reconnected() {
+	let _ = ::env_logger::try_init();
+	let mut net = TestNet::new(3);
+	net.block_until_connected();
+
+	net.disconnect_peer(2);
+	net.block_until_connected();
+	assert!(net.peer(2).is_offline());
+
+	net.peer(0).push_blocks(30, false);
+	block_on(futures::future::poll_fn::<(), _>(|cx| {
+		net.poll(cx);
+		if net.peer(1).client.info().best_number != 30 {
+			return Poll::Pending
+		}
+		Poll::Ready(())
+	}));
+	assert_eq!(net.peer(2).client.info().best_number, 0);
+
+	net.reconnect_peer(2);
+	net.block_until_sync();
+	let peer0 = &net.peers()[0];
+	assert!(net.peers()[2].blockchain_canon_equals(peer0));
+}
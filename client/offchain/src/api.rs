@@ -174,6 +174,121 @@ impl<Storage: OffchainStorage> OffchainExt for Api<Storage> {
 	}
 }
 
+/// Read-only offchain externalities over the offchain database.
+///
+/// They are given to the runtime calls made outside of the offchain workers, which are limited to
+/// reading the persistent offchain storage.
+#[derive(Clone)]
+pub struct OffchainDb<Storage> {
+	/// Offchain Workers database.
+	db: Storage,
+}
+
+impl<Storage> OffchainDb<Storage> {
+	/// Create new read-only offchain externalities over the given database.
+	pub fn new(db: Storage) -> Self {
+		Self { db }
+	}
+}
+
+fn only_db_read<R>(name: &str) -> R {
+	panic!("The {:?} API is not available outside of the offchain workers.", name)
+}
+
+impl<Storage: OffchainStorage> OffchainExt for OffchainDb<Storage> {
+	fn is_validator(&self) -> bool {
+		only_db_read("is_validator")
+	}
+
+	fn network_state(&self) -> Result<OpaqueNetworkState, ()> {
+		only_db_read("network_state")
+	}
+
+	fn timestamp(&mut self) -> Timestamp {
+		only_db_read("timestamp")
+	}
+
+	fn sleep_until(&mut self, _deadline: Timestamp) {
+		only_db_read("sleep_until")
+	}
+
+	fn random_seed(&mut self) -> [u8; 32] {
+		only_db_read("random_seed")
+	}
+
+	fn local_storage_set(&mut self, _kind: StorageKind, _key: &[u8], _value: &[u8]) {
+		only_db_read("local_storage_set")
+	}
+
+	fn local_storage_compare_and_set(
+		&mut self,
+		_kind: StorageKind,
+		_key: &[u8],
+		_old_value: Option<&[u8]>,
+		_new_value: &[u8],
+	) -> bool {
+		only_db_read("local_storage_compare_and_set")
+	}
+
+	fn local_storage_get(&mut self, kind: StorageKind, key: &[u8]) -> Option<Vec<u8>> {
+		match kind {
+			StorageKind::PERSISTENT => self.db.get(STORAGE_PREFIX, key),
+			StorageKind::LOCAL => unavailable_yet(LOCAL_DB),
+		}
+	}
+
+	fn http_request_start(
+		&mut self,
+		_method: &str,
+		_uri: &str,
+		_meta: &[u8]
+	) -> Result<HttpRequestId, ()> {
+		only_db_read("http_request_start")
+	}
+
+	fn http_request_add_header(
+		&mut self,
+		_request_id: HttpRequestId,
+		_name: &str,
+		_value: &str
+	) -> Result<(), ()> {
+		only_db_read("http_request_add_header")
+	}
+
+	fn http_request_write_body(
+		&mut self,
+		_request_id: HttpRequestId,
+		_chunk: &[u8],
+		_deadline: Option<Timestamp>
+	) -> Result<(), HttpError> {
+		only_db_read("http_request_write_body")
+	}
+
+	fn http_response_wait(
+		&mut self,
+		_ids: &[HttpRequestId],
+		_deadline: Option<Timestamp>
+	) -> Vec<HttpRequestStatus> {
+		only_db_read("http_response_wait")
+	}
+
+	fn http_response_headers(
+		&mut self,
+		_request_id: HttpRequestId
+	) -> Vec<(Vec<u8>, Vec<u8>)> {
+		only_db_read("http_response_headers")
+	}
+
+	fn http_response_read_body(
+		&mut self,
+		_request_id: HttpRequestId,
+		_buffer: &mut [u8],
+		_deadline: Option<Timestamp>
+	) -> Result<usize, HttpError> {
+		only_db_read("http_response_read_body")
+	}
+}
+
 /// Information about the local node's network state.
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub struct NetworkState {
@@ -390,6 +505,22 @@ mod tests {
 		assert_eq!(api.local_storage_get(kind, key), Some(b"value".to_vec()));
 	}
 
+	#[test]
+	fn should_read_local_storage_outside_of_offchain_workers() {
+		// given
+		let kind = StorageKind::PERSISTENT;
+		let mut api = offchain_api().0;
+		let mut db = OffchainDb::new(api.db.clone());
+		let key = b"test";
+
+		// when
+		assert_eq!(db.local_storage_get(kind, key), None);
+		api.local_storage_set(kind, key, b"value");
+
+		// then
+		assert_eq!(db.local_storage_get(kind, key), Some(b"value".to_vec()));
+	}
+
 	#[test]
 	fn should_convert_network_states() {
 		// given
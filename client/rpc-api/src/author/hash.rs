@@ -30,3 +30,13 @@ pub enum ExtrinsicOrHash<Hash> {
 	/// Raw extrinsic bytes.
 	Extrinsic(Bytes),
 }
+
+/// RPC pending extrinsic, along with the block it expires at.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingExtrinsic {
+	/// Raw extrinsic bytes.
+	pub extrinsic: Bytes,
+	/// The number of the block from which the extrinsic is stale and gets dropped from the pool.
+	pub valid_till: u64,
+}
@@ -47,6 +47,8 @@ pub trait AuthorApi<Hash, BlockHash> {
 	) -> Result<()>;
 
 	/// Generate new session keys and returns the corresponding public keys.
+	///
+	/// Fails if the generated keys could not be found in the keystore afterwards.
 	#[rpc(name = "author_rotateKeys")]
 	fn rotate_keys(&self) -> Result<Bytes>;
 
@@ -68,6 +70,10 @@ pub trait AuthorApi<Hash, BlockHash> {
 	#[rpc(name = "author_pendingExtrinsics")]
 	fn pending_extrinsics(&self) -> Result<Vec<Bytes>>;
 
+	/// Returns all pending extrinsics, along with the block they expire at.
+	#[rpc(name = "author_pendingExtrinsicsWithExpiry")]
+	fn pending_extrinsics_with_expiry(&self) -> Result<Vec<hash::PendingExtrinsic>>;
+
 	/// Remove given extrinsic from the pool and temporarily ban it to prevent reimporting.
 	#[rpc(name = "author_removeExtrinsic")]
 	fn remove_extrinsic(&self,
@@ -32,6 +32,10 @@ pub enum Error {
 	/// Client error.
 	#[display(fmt="Client error: {}", _0)]
 	Client(Box<dyn std::error::Error + Send>),
+	/// Requested block range is invalid.
+	#[display(fmt = "Invalid block range: {}", _0)]
+	#[from(ignore)]
+	InvalidRange(String),
 	/// Other error type.
 	Other(String),
 }
@@ -56,6 +60,11 @@ impl From<Error> for rpc::Error {
 				message,
 				data: None,
 			},
+			Error::InvalidRange(_) => rpc::Error {
+				code: rpc::ErrorCode::ServerError(BASE_ERROR + 2),
+				message: format!("{}", e),
+				data: None,
+			},
 			e => errors::internal(e),
 		}
 	}
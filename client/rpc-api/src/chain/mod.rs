@@ -41,6 +41,27 @@ pub trait ChainApi<Number, Hash, Header, SignedBlock> {
 	#[rpc(name = "chain_getBlock")]
 	fn block(&self, hash: Option<Hash>) -> FutureResult<Option<SignedBlock>>;
 
+	/// Get headers of the canon chain blocks with numbers in the inclusive range `[from, to]`.
+	///
+	/// The result stops at the best block if `to` is beyond it.
+	#[rpc(name = "chain_getHeaderRange")]
+	fn header_range(
+		&self,
+		from: NumberOrHex<Number>,
+		to: NumberOrHex<Number>,
+	) -> FutureResult<Vec<Header>>;
+
+	/// Get headers and bodies of the canon chain blocks with numbers in the inclusive range
+	/// `[from, to]`.
+	///
+	/// The result stops at the best block if `to` is beyond it.
+	#[rpc(name = "chain_getBlockRange")]
+	fn block_range(
+		&self,
+		from: NumberOrHex<Number>,
+		to: NumberOrHex<Number>,
+	) -> FutureResult<Vec<SignedBlock>>;
+
 	/// Get hash of the n-th block in the canon chain.
 	///
 	/// By default returns latest block hash.
@@ -54,7 +75,10 @@ pub trait ChainApi<Number, Hash, Header, SignedBlock> {
 	#[rpc(name = "chain_getFinalizedHead", alias("chain_getFinalisedHead"))]
 	fn finalized_head(&self) -> Result<Hash>;
 
-	/// All head subscription
+	/// All head subscription.
+	///
+	/// Emits the header of every imported block, including blocks on forks and blocks imported
+	/// during the initial sync.
 	#[pubsub(subscription = "chain_allHead", subscribe, name = "chain_subscribeAllHeads")]
 	fn subscribe_all_heads(&self, metadata: Self::Metadata, subscriber: Subscriber<Header>);
 
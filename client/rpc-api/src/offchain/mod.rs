@@ -34,4 +34,12 @@ pub trait OffchainApi {
 	/// Get offchain local storage under given key and prefix.
 	#[rpc(name = "offchain_localStorageGet")]
 	fn get_local_storage(&self, kind: StorageKind, key: Bytes) -> Result<Option<Bytes>>;
+
+	/// Get all the offchain local storage keys and values whose key starts with given prefix.
+	#[rpc(name = "offchain_localStoragePairs")]
+	fn get_local_storage_pairs(
+		&self,
+		kind: StorageKind,
+		key_prefix: Bytes,
+	) -> Result<Vec<(Bytes, Bytes)>>;
 }
@@ -17,6 +17,7 @@
 //! Substrate state API.
 
 pub mod error;
+pub mod helpers;
 
 use jsonrpc_core::Result as RpcResult;
 use jsonrpc_core::futures::Future;
@@ -27,6 +28,7 @@ use sp_core::storage::{StorageKey, StorageData, StorageChangeSet};
 use sp_version::RuntimeVersion;
 use self::error::FutureResult;
 
+pub use self::helpers::ReadProof;
 pub use self::gen_client::Client as StateClient;
 
 /// Substrate state API
@@ -116,6 +118,43 @@ pub trait StateApi<Hash> {
 		hash: Option<Hash>
 	) -> FutureResult<Option<u64>>;
 
+	/// Returns the keys with prefix from a child storage with pagination support.
+	/// Up to `count` keys will be returned.
+	/// If `start_key` is passed, return next keys in storage in lexicographic order.
+	#[rpc(name = "childstate_getKeysPaged", alias("childstate_getKeysPagedAt"))]
+	fn child_storage_keys_paged(
+		&self,
+		child_storage_key: StorageKey,
+		child_info: StorageKey,
+		child_type: u32,
+		prefix: Option<StorageKey>,
+		count: u32,
+		start_key: Option<StorageKey>,
+		hash: Option<Hash>,
+	) -> FutureResult<Vec<StorageKey>>;
+
+	/// Returns child storage entries for multiple keys at a specific block's state.
+	#[rpc(name = "childstate_getStorageEntries")]
+	fn child_storage_entries(
+		&self,
+		child_storage_key: StorageKey,
+		child_info: StorageKey,
+		child_type: u32,
+		keys: Vec<StorageKey>,
+		hash: Option<Hash>,
+	) -> FutureResult<Vec<Option<StorageData>>>;
+
+	/// Returns proof of child storage entries at a specific block's state.
+	#[rpc(name = "childstate_getReadProof")]
+	fn read_child_proof(
+		&self,
+		child_storage_key: StorageKey,
+		child_info: StorageKey,
+		child_type: u32,
+		keys: Vec<StorageKey>,
+		hash: Option<Hash>,
+	) -> FutureResult<ReadProof<Hash>>;
+
 	/// Returns the runtime metadata as an opaque blob.
 	#[rpc(name = "state_getMetadata")]
 	fn metadata(&self, hash: Option<Hash>) -> FutureResult<Bytes>;
@@ -136,6 +175,32 @@ pub trait StateApi<Hash> {
 		hash: Option<Hash>
 	) -> FutureResult<Vec<StorageChangeSet<Hash>>>;
 
+	/// Query storage entries (by key) at a block. Defaults to the best block.
+	///
+	/// The result contains a single change set with the values of all the given keys.
+	#[rpc(name = "state_queryStorageAt")]
+	fn query_storage_at(
+		&self,
+		keys: Vec<StorageKey>,
+		at: Option<Hash>,
+	) -> FutureResult<Vec<StorageChangeSet<Hash>>>;
+
+	/// Re-execute the given block and return the spans and events emitted while doing so.
+	///
+	/// `targets` is a comma separated list of the targets to record, each optionally followed by
+	/// a level, e.g. `pallet_balances=trace,state`. Storage accesses are reported as events of the
+	/// `state` target, and can be restricted to keys starting with one of the hex encoded,
+	/// comma separated prefixes of `storage_keys`.
+	///
+	/// Executing a block is expensive, this call is therefore unsafe.
+	#[rpc(name = "state_traceBlock")]
+	fn trace_block(
+		&self,
+		block: Hash,
+		targets: Option<String>,
+		storage_keys: Option<String>,
+	) -> FutureResult<sp_rpc::tracing::BlockTrace<Hash>>;
+
 	/// New runtime version subscription
 	#[pubsub(
 		subscription = "state_runtimeVersion",
@@ -165,4 +230,59 @@ pub trait StateApi<Hash> {
 	fn unsubscribe_storage(
 		&self, metadata: Option<Self::Metadata>, id: SubscriptionId
 	) -> RpcResult<bool>;
+
+	/// New finalized storage subscription.
+	///
+	/// Only emits changes of the given keys at finalized blocks, so subscribers never observe
+	/// values from blocks that are later reverted.
+	#[pubsub(
+		subscription = "state_finalizedStorage",
+		subscribe,
+		name = "state_subscribeFinalizedStorage"
+	)]
+	fn subscribe_finalized_storage(
+		&self,
+		metadata: Self::Metadata,
+		subscriber: Subscriber<StorageChangeSet<Hash>>,
+		keys: Option<Vec<StorageKey>>,
+	);
+
+	/// Unsubscribe from finalized storage subscription
+	#[pubsub(
+		subscription = "state_finalizedStorage",
+		unsubscribe,
+		name = "state_unsubscribeFinalizedStorage"
+	)]
+	fn unsubscribe_finalized_storage(
+		&self, metadata: Option<Self::Metadata>, id: SubscriptionId
+	) -> RpcResult<bool>;
+
+	/// New child storage subscription.
+	///
+	/// Emits the changes of the given keys of a child storage, or of all its keys if none are
+	/// given.
+	#[pubsub(
+		subscription = "childstate_storage",
+		subscribe,
+		name = "childstate_subscribeStorage"
+	)]
+	fn subscribe_child_storage(
+		&self,
+		metadata: Self::Metadata,
+		subscriber: Subscriber<StorageChangeSet<Hash>>,
+		child_storage_key: StorageKey,
+		child_info: StorageKey,
+		child_type: u32,
+		keys: Option<Vec<StorageKey>>,
+	);
+
+	/// Unsubscribe from child storage subscription
+	#[pubsub(
+		subscription = "childstate_storage",
+		unsubscribe,
+		name = "childstate_unsubscribeStorage"
+	)]
+	fn unsubscribe_child_storage(
+		&self, metadata: Option<Self::Metadata>, id: SubscriptionId
+	) -> RpcResult<bool>;
 }
@@ -18,27 +18,32 @@
 
 #![warn(missing_docs)]
 
+mod middleware;
+
 use std::io;
-use jsonrpc_core::IoHandlerExtension;
+use jsonrpc_core::{IoHandlerExtension, MetaIoHandler};
 use log::error;
 use pubsub::PubSubMetadata;
 
-/// Maximal payload accepted by RPC servers.
-const MAX_PAYLOAD: usize = 15 * 1024 * 1024;
+pub use middleware::{PeerMetadata, RpcMetrics, RpcMiddleware};
+
+/// Default maximal payload accepted by RPC servers, in megabytes.
+const MAX_PAYLOAD: usize = 15;
 
 /// Default maximum number of connections for WS RPC servers.
 const WS_MAX_CONNECTIONS: usize = 100;
 
 /// The RPC IoHandler containing all requested APIs.
-pub type RpcHandler<T> = pubsub::PubSubHandler<T>;
+pub type RpcHandler<T> = pubsub::PubSubHandler<T, RpcMiddleware>;
 
 pub use self::inner::*;
 
 /// Construct rpc `IoHandler`
-pub fn rpc_handler<M: PubSubMetadata>(
-	extension: impl IoHandlerExtension<M>
+pub fn rpc_handler<M: PubSubMetadata + PeerMetadata>(
+	extension: impl IoHandlerExtension<M>,
+	rpc_middleware: RpcMiddleware,
 ) -> RpcHandler<M> {
-	let mut io = pubsub::PubSubHandler::default();
+	let mut io = pubsub::PubSubHandler::new(MetaIoHandler::with_middleware(rpc_middleware));
 	extension.augment(&mut io);
 
 	// add an endpoint to list all available methods.
@@ -59,6 +64,7 @@ pub fn rpc_handler<M: PubSubMetadata>(
 #[cfg(not(target_os = "unknown"))]
 mod inner {
 	use super::*;
+	use std::net::IpAddr;
 
 	/// Type alias for http server
 	pub type HttpServer = http::Server;
@@ -67,13 +73,29 @@ mod inner {
 
 	/// Start HTTP server listening on given address.
 	///
+	/// `max_payload` is the maximal size of a request, in megabytes.
+	///
+	/// The server doesn't expose the socket address of the peers, so the peer address of a request
+	/// is only known when `trust_proxy_headers` is set, from the headers set by the reverse proxy
+	/// in front of the node.
+	///
 	/// **Note**: Only available if `not(target_os = "unknown")`.
-	pub fn start_http<M: pubsub::PubSubMetadata + Default>(
+	pub fn start_http<M: pubsub::PubSubMetadata + PeerMetadata + From<IpAddr> + Default>(
 		addr: &std::net::SocketAddr,
 		cors: Option<&Vec<String>>,
+		max_payload: Option<usize>,
+		trust_proxy_headers: bool,
 		io: RpcHandler<M>,
 	) -> io::Result<http::Server> {
 		http::ServerBuilder::new(io)
+			.meta_extractor(move |request: &http::hyper::Request<http::hyper::Body>| {
+				let peer_addr = if trust_proxy_headers {
+					forwarded_addr(request.headers())
+				} else {
+					None
+				};
+				peer_addr.map(M::from).unwrap_or_default()
+			})
 			.threads(4)
 			.health_api(("/health", "system_health"))
 			.allowed_hosts(hosts_filtering(cors.is_some()))
@@ -83,21 +105,27 @@ mod inner {
 				http::RestApi::Unsecure
 			})
 			.cors(map_cors::<http::AccessControlAllowOrigin>(cors))
-			.max_request_body_size(MAX_PAYLOAD)
+			.max_request_body_size(max_payload_bytes(max_payload))
 			.start_http(addr)
 	}
 
 	/// Start WS server listening on given address.
 	///
+	/// `max_payload` is the maximal size of a message, in megabytes.
+	///
+	/// The server doesn't expose the socket address of the peers either, so the calls of each
+	/// connection are told apart by their pub/sub session.
+	///
 	/// **Note**: Only available if `not(target_os = "unknown")`.
 	pub fn start_ws<M: pubsub::PubSubMetadata + From<jsonrpc_core::futures::sync::mpsc::Sender<String>>> (
 		addr: &std::net::SocketAddr,
 		max_connections: Option<usize>,
 		cors: Option<&Vec<String>>,
+		max_payload: Option<usize>,
 		io: RpcHandler<M>,
 	) -> io::Result<ws::Server> {
 		ws::ServerBuilder::with_meta_extractor(io, |context: &ws::RequestContext| context.sender().into())
-			.max_payload(MAX_PAYLOAD)
+			.max_payload(max_payload_bytes(max_payload))
 			.max_connections(max_connections.unwrap_or(WS_MAX_CONNECTIONS))
 			.allowed_origins(map_cors(cors))
 			.allowed_hosts(hosts_filtering(cors.is_some()))
@@ -112,6 +140,25 @@ mod inner {
 			})
 	}
 
+	/// The address of the client a request is forwarded for, as given by the `X-Forwarded-For`
+	/// header or else the `X-Real-IP` one.
+	///
+	/// The last `X-Forwarded-For` entry is the one appended by the trusted proxy, the ones before
+	/// it are given by the client and can't be trusted.
+	pub(crate) fn forwarded_addr(headers: &http::hyper::HeaderMap) -> Option<IpAddr> {
+		let header = |name: &str| headers.get_all(name).iter()
+			.last()
+			.and_then(|value| value.to_str().ok());
+		header("x-forwarded-for")
+			.and_then(|value| value.rsplit(',').next())
+			.or_else(|| header("x-real-ip"))
+			.and_then(|addr| addr.trim().parse().ok())
+	}
+
+	fn max_payload_bytes(max_payload: Option<usize>) -> usize {
+		max_payload.unwrap_or(MAX_PAYLOAD).saturating_mul(1024 * 1024)
+	}
+
 	fn map_cors<T: for<'a> From<&'a str>>(
 		cors: Option<&Vec<String>>
 	) -> http::DomainsValidation<T> {
@@ -130,6 +177,37 @@ mod inner {
 	}
 }
 
+#[cfg(all(test, not(target_os = "unknown")))]
+mod tests {
+	use super::inner::forwarded_addr;
+	use http::hyper::{HeaderMap, header::HeaderValue};
+
+	fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+		let mut headers = HeaderMap::new();
+		for (name, value) in pairs {
+			headers.insert(*name, HeaderValue::from_static(*value));
+		}
+		headers
+	}
+
+	#[test]
+	fn forwarded_addr_is_the_last_forwarded_for_one() {
+		assert_eq!(
+			forwarded_addr(&headers(&[("x-forwarded-for", "10.0.0.1, 10.0.0.2"), ("x-real-ip", "10.0.0.3")])),
+			Some("10.0.0.2".parse().unwrap()),
+		);
+
+		// the entries spoofed by the client in a header of its own are ignored.
+		let mut spoofed = headers(&[("x-forwarded-for", "10.0.0.1")]);
+		spoofed.append("x-forwarded-for", HeaderValue::from_static("10.0.0.4"));
+		assert_eq!(forwarded_addr(&spoofed), Some("10.0.0.4".parse().unwrap()));
+
+		assert_eq!(forwarded_addr(&headers(&[("x-real-ip", "::1")])), Some("::1".parse().unwrap()));
+		assert_eq!(forwarded_addr(&headers(&[("x-forwarded-for", "unknown")])), None);
+		assert_eq!(forwarded_addr(&headers(&[])), None);
+	}
+}
+
 #[cfg(target_os = "unknown")]
 mod inner {
 }
@@ -0,0 +1,278 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Middleware for RPC requests.
+
+use std::{collections::HashMap, net::IpAddr, sync::Arc, time::{Duration, Instant}};
+use jsonrpc_core::{
+	Call, Error, ErrorCode, Failure, FutureOutput, FutureResponse, Middleware, Output, Version,
+	futures::{future::{self, Either}, Future},
+};
+use parking_lot::Mutex;
+use prometheus_endpoint::{
+	register, CounterVec, HistogramOpts, HistogramVec, Opts, PrometheusError, Registry, U64,
+};
+use pubsub::{PubSubMetadata, Session};
+
+/// Error code returned to calls rejected by the rate limiter.
+const RATE_LIMITED: i64 = -32005;
+
+/// Duration of the windows the calls of a client are counted over.
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(1);
+
+/// Label used for calls to methods the handler doesn't know about, so that arbitrary method
+/// names don't each create a new time series.
+const UNKNOWN_METHOD: &str = "unknown";
+
+/// Prometheus metrics of the RPC servers.
+#[derive(Clone)]
+pub struct RpcMetrics {
+	calls_total: CounterVec<U64>,
+	calls_time: HistogramVec,
+}
+
+impl RpcMetrics {
+	/// Register the RPC metrics in the given registry.
+	pub fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			calls_total: register(CounterVec::new(
+				Opts::new("rpc_calls_total", "Number of RPC calls received"),
+				&["protocol", "method"],
+			)?, registry)?,
+			calls_time: register(HistogramVec::new(
+				HistogramOpts::new("rpc_calls_time", "Time taken to answer RPC calls, in seconds"),
+				&["protocol", "method"],
+			)?, registry)?,
+		})
+	}
+}
+
+/// Metadata of RPC calls able to tell the address of the peer making them.
+pub trait PeerMetadata {
+	/// The address of the peer making the call, if known.
+	fn peer_addr(&self) -> Option<IpAddr>;
+}
+
+/// The client a call is accounted to by the rate limiter.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum Client {
+	/// A peer whose address is known.
+	Peer(IpAddr),
+	/// A pub/sub session, i.e. a WS connection, whose peer address is not known.
+	Session(usize),
+	/// Any other call.
+	Unknown,
+}
+
+impl Client {
+	fn of<M: PubSubMetadata + PeerMetadata>(meta: &M) -> Self {
+		match (meta.peer_addr(), meta.session()) {
+			(Some(addr), _) => Client::Peer(addr),
+			(None, Some(session)) => Client::Session(&*session as *const Session as usize),
+			(None, None) => Client::Unknown,
+		}
+	}
+}
+
+/// The calls made by each client during their current window.
+struct Windows {
+	/// When the windows older than `RATE_LIMIT_WINDOW` were last removed.
+	last_pruned: Instant,
+	/// The start of the current window of each client and the number of calls made since.
+	windows: HashMap<Client, (Instant, u32)>,
+}
+
+/// Limits the number of calls a single client can make per second.
+///
+/// Clients are told apart by their peer address when it is known, and by their pub/sub session
+/// otherwise. All the other calls share a single limit.
+struct RateLimiter {
+	max_calls_per_sec: u32,
+	windows: Mutex<Windows>,
+}
+
+impl RateLimiter {
+	fn new(max_calls_per_sec: u32) -> Self {
+		RateLimiter {
+			max_calls_per_sec,
+			windows: Mutex::new(Windows {
+				last_pruned: Instant::now(),
+				windows: HashMap::new(),
+			}),
+		}
+	}
+
+	/// Account for a new call of the given client and return whether it is allowed.
+	fn check(&self, client: Client) -> bool {
+		let now = Instant::now();
+		let mut windows = self.windows.lock();
+
+		// Forget the clients that made no call during the last window, so that the map only
+		// grows with the number of clients calling at the same time.
+		if now.duration_since(windows.last_pruned) >= RATE_LIMIT_WINDOW {
+			windows.windows.retain(|_, (start, _)| now.duration_since(*start) < RATE_LIMIT_WINDOW);
+			windows.last_pruned = now;
+		}
+
+		let window = windows.windows.entry(client).or_insert((now, 0));
+		if now.duration_since(window.0) >= RATE_LIMIT_WINDOW {
+			*window = (now, 0);
+		}
+		window.1 = window.1.saturating_add(1);
+		window.1 <= self.max_calls_per_sec
+	}
+}
+
+/// Middleware recording metrics of the calls made to an RPC server and optionally limiting
+/// their rate.
+#[derive(Clone)]
+pub struct RpcMiddleware {
+	metrics: Option<RpcMetrics>,
+	transport_label: &'static str,
+	rate_limiter: Option<Arc<RateLimiter>>,
+}
+
+impl RpcMiddleware {
+	/// Create a new middleware reporting to `metrics` under the given transport label.
+	pub fn new(metrics: Option<RpcMetrics>, transport_label: &'static str) -> Self {
+		RpcMiddleware {
+			metrics,
+			transport_label,
+			rate_limiter: None,
+		}
+	}
+
+	/// Reject calls of a client going above `max_calls_per_sec` calls per second.
+	///
+	/// `None` disables the limit.
+	pub fn with_rate_limit(mut self, max_calls_per_sec: Option<u32>) -> Self {
+		self.rate_limiter = max_calls_per_sec.map(|max_calls_per_sec| {
+			Arc::new(RateLimiter::new(max_calls_per_sec))
+		});
+		self
+	}
+}
+
+impl<M: PubSubMetadata + PeerMetadata> Middleware<M> for RpcMiddleware {
+	type Future = FutureResponse;
+	type CallFuture = FutureOutput;
+
+	fn on_call<F, X>(&self, call: Call, meta: M, next: F) -> Either<Self::CallFuture, X>
+	where
+		F: Fn(Call, M) -> X + Send + Sync,
+		X: Future<Item = Option<Output>, Error = ()> + Send + 'static,
+	{
+		if let Some(ref rate_limiter) = self.rate_limiter {
+			if !rate_limiter.check(Client::of(&meta)) {
+				return Either::A(Box::new(future::ok(rate_limited(&call))));
+			}
+		}
+
+		let metrics = match self.metrics {
+			Some(ref metrics) => metrics.clone(),
+			None => return Either::B(next(call, meta)),
+		};
+		let method = match call {
+			Call::MethodCall(ref call) => call.method.clone(),
+			Call::Notification(ref notification) => notification.method.clone(),
+			Call::Invalid { .. } => String::from("invalid"),
+		};
+		let transport_label = self.transport_label;
+		let start = Instant::now();
+
+		Either::A(Box::new(next(call, meta).map(move |output| {
+			let method = match output {
+				Some(Output::Failure(ref failure))
+					if failure.error.code == ErrorCode::MethodNotFound => UNKNOWN_METHOD,
+				_ => method.as_str(),
+			};
+			let labels = [transport_label, method];
+			metrics.calls_total.with_label_values(&labels).inc();
+			metrics.calls_time.with_label_values(&labels).observe(start.elapsed().as_secs_f64());
+			output
+		})))
+	}
+}
+
+/// Build the response to a call rejected by the rate limiter.
+fn rate_limited(call: &Call) -> Option<Output> {
+	let (jsonrpc, id) = match call {
+		Call::MethodCall(call) => (call.jsonrpc, call.id.clone()),
+		Call::Notification(_) => return None,
+		Call::Invalid { id } => (Some(Version::V2), id.clone()),
+	};
+
+	Some(Output::Failure(Failure {
+		jsonrpc,
+		error: Error {
+			code: ErrorCode::ServerError(RATE_LIMITED),
+			message: "Too many requests".into(),
+			data: None,
+		},
+		id,
+	}))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::net::Ipv4Addr;
+
+	fn limiter(max_calls_per_sec: u32) -> Arc<RateLimiter> {
+		RpcMiddleware::new(None, "test")
+			.with_rate_limit(Some(max_calls_per_sec))
+			.rate_limiter
+			.expect("rate limit is set; qed")
+	}
+
+	fn peer(last: u8) -> Client {
+		Client::Peer(IpAddr::V4(Ipv4Addr::new(10, 0, 0, last)))
+	}
+
+	#[test]
+	fn rate_limit_is_per_peer() {
+		let limiter = limiter(2);
+
+		assert!(limiter.check(peer(1)));
+		assert!(limiter.check(peer(1)));
+		assert!(!limiter.check(peer(1)));
+
+		assert!(limiter.check(peer(2)));
+		assert!(limiter.check(Client::Session(1)));
+		assert!(limiter.check(Client::Unknown));
+	}
+
+	#[test]
+	fn stale_windows_are_forgotten() {
+		let limiter = limiter(1);
+
+		assert!(limiter.check(peer(1)));
+		assert!(!limiter.check(peer(1)));
+		assert_eq!(limiter.windows.lock().windows.len(), 1);
+
+		{
+			let mut windows = limiter.windows.lock();
+			let past = Instant::now() - RATE_LIMIT_WINDOW;
+			windows.last_pruned = past;
+			windows.windows.get_mut(&peer(1)).expect("peer called; qed").0 = past;
+		}
+
+		assert!(limiter.check(peer(2)));
+		let windows = limiter.windows.lock();
+		assert_eq!(windows.windows.len(), 1);
+		assert!(windows.windows.contains_key(&peer(2)));
+	}
+}
@@ -55,7 +55,12 @@ struct TestSetup {
 
 impl Default for TestSetup {
 	fn default() -> Self {
-		let keystore = KeyStore::new();
+		Self::with_keystore(KeyStore::new())
+	}
+}
+
+impl TestSetup {
+	fn with_keystore(keystore: BareCryptoStorePtr) -> Self {
 		let client = Arc::new(
 			substrate_test_runtime_client::TestClientBuilder::new()
 				.set_keystore(keystore.clone())
@@ -64,6 +69,7 @@ impl Default for TestSetup {
 		let pool = Arc::new(BasicPool::new(
 			Default::default(),
 			Arc::new(FullChainApi::new(client.clone())),
+			None,
 		).0);
 		TestSetup {
 			runtime: runtime::Runtime::new().expect("Failed to create runtime in test setup"),
@@ -72,15 +78,14 @@ impl Default for TestSetup {
 			pool,
 		}
 	}
-}
 
-impl TestSetup {
 	fn author(&self) -> Author<FullTransactionPool, Client<Backend>> {
 		Author {
 			client: self.client.clone(),
 			pool: self.pool.clone(),
 			subscriptions: Subscriptions::new(Arc::new(self.runtime.executor())),
 			keystore: self.keystore.clone(),
+			deny_unsafe: DenyUnsafe::No,
 		}
 	}
 }
@@ -179,6 +184,22 @@ fn should_return_pending_extrinsics() {
 	);
 }
 
+#[test]
+fn should_return_pending_extrinsics_with_expiry() {
+	let p = TestSetup::default().author();
+
+	let ex = uxt(AccountKeyring::Alice, 0);
+	AuthorApi::submit_extrinsic(&p, ex.encode().into()).wait().unwrap();
+	assert_eq!(
+		p.pending_extrinsics_with_expiry().unwrap(),
+		vec![hash::PendingExtrinsic {
+			extrinsic: Bytes(ex.encode()),
+			// the transfers of the test runtime are valid for 64 blocks.
+			valid_till: 64,
+		}],
+	);
+}
+
 #[test]
 fn should_remove_extrinsics() {
 	let setup = TestSetup::default();
@@ -245,6 +266,38 @@ fn should_rotate_keys() {
 	assert_eq!(session_keys.sr25519, sr25519_key_pair.public().into());
 }
 
+#[test]
+fn should_rotate_keys_with_in_memory_keystore() {
+	let setup = TestSetup::with_keystore(sc_keystore::Store::new_in_memory());
+	let p = setup.author();
+
+	let new_public_keys = p.rotate_keys().expect("Rotates the keys");
+
+	assert_matches!(p.has_session_keys(new_public_keys.into()), Ok(true));
+}
+
+#[test]
+fn key_management_is_denied_when_unsafe_calls_are() {
+	let setup = TestSetup::default();
+	let p = Author {
+		deny_unsafe: DenyUnsafe::Yes,
+		..setup.author()
+	};
+
+	let suri = "//Alice";
+	let key_pair = ed25519::Pair::from_string(suri, None).expect("Generates keypair");
+	assert_matches!(
+		p.insert_key(
+			String::from_utf8(ED25519.0.to_vec()).expect("Keytype is a valid string"),
+			suri.to_string(),
+			key_pair.public().0.to_vec().into(),
+		),
+		Err(Error::UnsafeRpcCalled(_))
+	);
+	assert_matches!(p.rotate_keys(), Err(Error::UnsafeRpcCalled(_)));
+	assert!(setup.keystore.read().ed25519_public_keys(ED25519).is_empty());
+}
+
 #[test]
 fn test_has_session_keys() {
 	let setup = TestSetup::default();
@@ -27,7 +27,7 @@ use futures::{future, StreamExt, TryStreamExt};
 use log::warn;
 use rpc::{
 	Result as RpcResult,
-	futures::{stream, Future, Sink, Stream},
+	futures::{future::{join_all, result}, stream, Future, Sink, Stream},
 };
 
 use sc_rpc_api::Subscriptions;
@@ -39,7 +39,7 @@ use jsonrpc_pubsub::{typed::Subscriber, SubscriptionId};
 use sp_rpc::{number::NumberOrHex, list::ListOrValue};
 use sp_runtime::{
 	generic::{BlockId, SignedBlock},
-	traits::{Block as BlockT, Header, NumberFor},
+	traits::{Block as BlockT, Header, NumberFor, One, SaturatedConversion},
 };
 
 use self::error::{Result, Error, FutureResult};
@@ -48,6 +48,9 @@ pub use sc_rpc_api::chain::*;
 use sp_blockchain::HeaderBackend;
 use sc_client_api::BlockBackend;
 
+/// Maximum number of blocks that can be requested through a single range query.
+const MAX_BLOCK_RANGE: u64 = 256;
+
 /// Blockchain backend API
 trait ChainBackend<Client, Block: BlockT>: Send + Sync + 'static
 	where
@@ -74,6 +77,71 @@ trait ChainBackend<Client, Block: BlockT>: Send + Sync + 'static
 	/// Get header and body of a relay chain block.
 	fn block(&self, hash: Option<Block::Hash>) -> FutureResult<Option<SignedBlock<Block>>>;
 
+	/// Get headers of the canon chain blocks in the inclusive range `[from, to]`.
+	fn header_range(
+		&self,
+		from: NumberOrHex<NumberFor<Block>>,
+		to: NumberOrHex<NumberFor<Block>>,
+	) -> FutureResult<Vec<Block::Header>> {
+		let hashes = match self.canon_hashes(from, to) {
+			Ok(hashes) => hashes,
+			Err(err) => return Box::new(result(Err(err))),
+		};
+
+		Box::new(join_all(hashes.into_iter().map(|hash| self.header(Some(hash))))
+			.map(|headers| headers.into_iter().flatten().collect()))
+	}
+
+	/// Get headers and bodies of the canon chain blocks in the inclusive range `[from, to]`.
+	fn block_range(
+		&self,
+		from: NumberOrHex<NumberFor<Block>>,
+		to: NumberOrHex<NumberFor<Block>>,
+	) -> FutureResult<Vec<SignedBlock<Block>>> {
+		let hashes = match self.canon_hashes(from, to) {
+			Ok(hashes) => hashes,
+			Err(err) => return Box::new(result(Err(err))),
+		};
+
+		Box::new(join_all(hashes.into_iter().map(|hash| self.block(Some(hash))))
+			.map(|blocks| blocks.into_iter().flatten().collect()))
+	}
+
+	/// Get hashes of the canon chain blocks in the inclusive range `[from, to]`, stopping at the
+	/// first unknown block.
+	fn canon_hashes(
+		&self,
+		from: NumberOrHex<NumberFor<Block>>,
+		to: NumberOrHex<NumberFor<Block>>,
+	) -> Result<Vec<Block::Hash>> {
+		let from = from.to_number()?;
+		let to = to.to_number()?;
+		if from > to {
+			return Err(Error::InvalidRange(format!("from ({}) > to ({})", from, to)));
+		}
+
+		let len = (to - from).saturated_into::<u64>().saturating_add(1);
+		if len > MAX_BLOCK_RANGE {
+			return Err(Error::InvalidRange(format!(
+				"requested {} blocks, at most {} are allowed",
+				len,
+				MAX_BLOCK_RANGE,
+			)));
+		}
+
+		let mut hashes = Vec::with_capacity(len as usize);
+		let mut number = from;
+		while let Some(hash) = self.client().hash(number).map_err(client_err)? {
+			hashes.push(hash);
+			if number == to {
+				break;
+			}
+			number += One::one();
+		}
+
+		Ok(hashes)
+	}
+
 	/// Get hash of the n-th block in the canon chain.
 	///
 	/// By default returns latest block hash.
@@ -106,7 +174,7 @@ trait ChainBackend<Client, Block: BlockT>: Send + Sync + 'static
 			self.subscriptions(),
 			subscriber,
 			|| self.client().info().best_hash,
-			|| self.client().import_notification_stream()
+			|| self.client().every_import_notification_stream()
 				.map(|notification| Ok::<_, ()>(notification.header))
 				.compat(),
 		)
@@ -233,6 +301,22 @@ impl<Block, Client> ChainApi<NumberFor<Block>, Block::Hash, Block::Header, Signe
 		self.backend.block(hash)
 	}
 
+	fn header_range(
+		&self,
+		from: NumberOrHex<NumberFor<Block>>,
+		to: NumberOrHex<NumberFor<Block>>,
+	) -> FutureResult<Vec<Block::Header>> {
+		self.backend.header_range(from, to)
+	}
+
+	fn block_range(
+		&self,
+		from: NumberOrHex<NumberFor<Block>>,
+		to: NumberOrHex<NumberFor<Block>>,
+	) -> FutureResult<Vec<SignedBlock<Block>>> {
+		self.backend.block_range(from, to)
+	}
+
 	fn block_hash(
 		&self,
 		number: Option<ListOrValue<NumberOrHex<NumberFor<Block>>>>
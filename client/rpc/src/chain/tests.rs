@@ -113,6 +113,39 @@ fn should_return_a_block() {
 	);
 }
 
+#[test]
+fn should_return_header_and_block_ranges() {
+	let core = tokio::runtime::Runtime::new().unwrap();
+	let remote = core.executor();
+
+	let mut client = Arc::new(substrate_test_runtime_client::new());
+	let api = new_full(client.clone(), Subscriptions::new(Arc::new(remote)));
+
+	let mut hashes = vec![client.genesis_hash()];
+	for _ in 0..3 {
+		let block = client.new_block(Default::default()).unwrap().build().unwrap().block;
+		hashes.push(block.hash());
+		client.import(BlockOrigin::Own, block).unwrap();
+	}
+
+	assert_matches!(
+		api.header_range(1u64.into(), 2u64.into()).wait(),
+		Ok(ref headers) if headers.iter().map(|h| h.parent_hash).collect::<Vec<_>>() == hashes[0..2]
+	);
+
+	// ranges are cut at the best block.
+	assert_matches!(
+		api.block_range(2u64.into(), 10u64.into()).wait(),
+		Ok(ref blocks) if blocks.iter().map(|b| b.block.hash()).collect::<Vec<_>>() == hashes[2..]
+	);
+
+	assert_matches!(api.header_range(2u64.into(), 1u64.into()).wait(), Err(Error::InvalidRange(_)));
+	assert_matches!(
+		api.block_range(0u64.into(), (MAX_BLOCK_RANGE + 1).into()).wait(),
+		Err(Error::InvalidRange(_))
+	);
+}
+
 #[test]
 fn should_return_block_hash() {
 	let core = ::tokio::runtime::Runtime::new().unwrap();
@@ -248,6 +281,60 @@ fn should_notify_about_best_block() {
 	assert_eq!(core.block_on(next.into_future()).unwrap().0, None);
 }
 
+#[test]
+fn should_notify_about_every_imported_block() {
+	let mut core = ::tokio::runtime::Runtime::new().unwrap();
+	let remote = core.executor();
+	let (all_subscriber, all_id, all_transport) = Subscriber::new_test("test");
+	let (best_subscriber, best_id, best_transport) = Subscriber::new_test("test");
+	let genesis_hash;
+
+	{
+		let mut client = Arc::new(substrate_test_runtime_client::new());
+		let api = new_full(client.clone(), Subscriptions::new(Arc::new(remote)));
+		genesis_hash = client.genesis_hash();
+
+		api.subscribe_all_heads(Default::default(), all_subscriber);
+		api.subscribe_new_heads(Default::default(), best_subscriber);
+
+		// assert ids assigned
+		assert_eq!(core.block_on(all_id), Ok(Ok(SubscriptionId::Number(1))));
+		assert_eq!(core.block_on(best_id), Ok(Ok(SubscriptionId::Number(2))));
+
+		// a block imported during the initial sync
+		let block = client.new_block(Default::default()).unwrap().build().unwrap().block;
+		client.import(BlockOrigin::NetworkInitialSync, block).unwrap();
+
+		// a block on a fork of the genesis, which doesn't become the best block
+		let mut builder = client.new_block_at(
+			&BlockId::Hash(genesis_hash),
+			Default::default(),
+			false,
+		).unwrap();
+		builder.push_storage_change(vec![1], Some(vec![1])).unwrap();
+		let fork = builder.build().unwrap().block;
+		client.import(BlockOrigin::NetworkBroadcast, fork).unwrap();
+	}
+
+	let parent_hash = format!("\"parentHash\":\"{:?}\"", genesis_hash);
+
+	// assert initial head sent.
+	let (notification, next) = core.block_on(all_transport.into_future()).unwrap();
+	assert!(notification.is_some());
+	// assert both imported blocks sent, tagged with their parent hash
+	let (notification, next) = core.block_on(next.into_future()).unwrap();
+	assert!(notification.unwrap().contains(&parent_hash));
+	let (notification, next) = core.block_on(next.into_future()).unwrap();
+	assert!(notification.unwrap().contains(&parent_hash));
+	// no more notifications on this channel
+	assert_eq!(core.block_on(next.into_future()).unwrap().0, None);
+
+	// the new heads subscription only got the initial head.
+	let (notification, next) = core.block_on(best_transport.into_future()).unwrap();
+	assert!(notification.is_some());
+	assert_eq!(core.block_on(next.into_future()).unwrap().0, None);
+}
+
 #[test]
 fn should_notify_about_finalized_block() {
 	let mut core = ::tokio::runtime::Runtime::new().unwrap();
@@ -15,10 +15,11 @@
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
 //! RPC Metadata
-use std::sync::Arc;
+use std::{net::IpAddr, sync::Arc};
 
 use jsonrpc_pubsub::{Session, PubSubMetadata};
 use rpc::futures::sync::mpsc;
+use sc_rpc_server::PeerMetadata;
 
 /// RPC Metadata.
 ///
@@ -28,6 +29,7 @@ use rpc::futures::sync::mpsc;
 #[derive(Default, Clone)]
 pub struct Metadata {
 	session: Option<Arc<Session>>,
+	peer_addr: Option<IpAddr>,
 }
 
 impl rpc::Metadata for Metadata {}
@@ -37,11 +39,18 @@ impl PubSubMetadata for Metadata {
 	}
 }
 
+impl PeerMetadata for Metadata {
+	fn peer_addr(&self) -> Option<IpAddr> {
+		self.peer_addr
+	}
+}
+
 impl Metadata {
 	/// Create new `Metadata` with session (Pub/Sub) support.
 	pub fn new(transport: mpsc::Sender<String>) -> Self {
 		Metadata {
 			session: Some(Arc::new(Session::new(transport))),
+			peer_addr: None,
 		}
 	}
 
@@ -58,3 +67,12 @@ impl From<mpsc::Sender<String>> for Metadata {
 		Self::new(sender)
 	}
 }
+
+impl From<IpAddr> for Metadata {
+	fn from(peer_addr: IpAddr) -> Self {
+		Metadata {
+			session: None,
+			peer_addr: Some(peer_addr),
+		}
+	}
+}
@@ -21,7 +21,7 @@ use sp_core::{Bytes, offchain::storage::InMemOffchainStorage};
 #[test]
 fn local_storage_should_work() {
 	let storage = InMemOffchainStorage::default();
-	let offchain = Offchain::new(storage);
+	let offchain = Offchain::new(storage, DenyUnsafe::No);
 	let key = Bytes(b"offchain_storage".to_vec());
 	let value = Bytes(b"offchain_value".to_vec());
 
@@ -34,3 +34,47 @@ fn local_storage_should_work() {
 		Ok(Some(ref v)) if *v == value
 	);
 }
+
+#[test]
+fn local_storage_pairs_should_work() {
+	let storage = InMemOffchainStorage::default();
+	let offchain = Offchain::new(storage, DenyUnsafe::No);
+	let value = Bytes(b"offchain_value".to_vec());
+	for key in &[&b"nonce_1"[..], b"nonce_2", b"price"] {
+		offchain.set_local_storage(StorageKind::PERSISTENT, Bytes(key.to_vec()), value.clone())
+			.unwrap();
+	}
+
+	assert_matches!(
+		offchain.get_local_storage_pairs(StorageKind::PERSISTENT, Bytes(b"nonce".to_vec())),
+		Ok(ref pairs) if *pairs == vec![
+			(Bytes(b"nonce_1".to_vec()), value.clone()),
+			(Bytes(b"nonce_2".to_vec()), value.clone()),
+		]
+	);
+	assert_matches!(
+		offchain.get_local_storage_pairs(StorageKind::LOCAL, Bytes(Vec::new())),
+		Err(Error::UnavailableStorageKind)
+	);
+}
+
+#[test]
+fn offchain_calls_considered_unsafe() {
+	let storage = InMemOffchainStorage::default();
+	let offchain = Offchain::new(storage, DenyUnsafe::Yes);
+	let key = Bytes(b"offchain_storage".to_vec());
+	let value = Bytes(b"offchain_value".to_vec());
+
+	assert_matches!(
+		offchain.set_local_storage(StorageKind::PERSISTENT, key.clone(), value.clone()),
+		Err(Error::UnsafeRpcCalled(_))
+	);
+	assert_matches!(
+		offchain.get_local_storage(StorageKind::PERSISTENT, key.clone()),
+		Err(Error::UnsafeRpcCalled(_))
+	);
+	assert_matches!(
+		offchain.get_local_storage_pairs(StorageKind::PERSISTENT, key),
+		Err(Error::UnsafeRpcCalled(_))
+	);
+}
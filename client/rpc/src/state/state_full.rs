@@ -33,14 +33,26 @@ use sp_core::{
 };
 use sp_version::RuntimeVersion;
 use sp_runtime::{
-	generic::BlockId, traits::{Block as BlockT, NumberFor, SaturatedConversion},
+	generic::BlockId, traits::{Block as BlockT, Header as HeaderT, NumberFor, SaturatedConversion},
 };
+use sp_rpc::tracing::BlockTrace;
+use codec::Encode;
 
-use sp_api::{Metadata, ProvideRuntimeApi, CallApiAt};
+use sp_api::{Metadata, ProvideRuntimeApi, CallApiAt, ApiExt};
+use sp_block_builder::ExecuteBlockNoCheck;
 
-use super::{StateBackend, error::{FutureResult, Error, Result}, client_err, child_resolution_error};
+use super::{
+	StateBackend, ReadProof, error::{FutureResult, Error, Result},
+	client_err, child_resolution_error,
+};
 use std::marker::PhantomData;
-use sc_client_api::{CallExecutor, StorageProvider, ExecutorProvider};
+use sc_client_api::{BlockBackend, CallExecutor, StorageProvider, ExecutorProvider, ProofProvider};
+
+/// Targets traced by `state_traceBlock` if none are given.
+const DEFAULT_TRACE_TARGETS: &str = "pallet,frame,state";
+
+/// Target of the storage access events.
+const STATE_TRACE_TARGET: &str = "state";
 
 /// Ranges to query in state_queryStorage.
 struct QueryStorageRange<Block: BlockT> {
@@ -133,6 +145,38 @@ impl<BE, Block: BlockT, Client> FullState<BE, Block, Client>
 		})
 	}
 
+	/// Run `f` against the given block, or the best block if None is passed, preventing its state
+	/// from being pruned meanwhile.
+	fn at_pinned_block<T>(
+		&self,
+		block: Option<Block::Hash>,
+		f: impl FnOnce(Block::Hash) -> ClientResult<T>,
+	) -> ClientResult<T> {
+		let block = self.block_or_best(block)?;
+		self.client.pin_block(&block)?;
+		let result = f(block);
+		self.client.unpin_block(&block);
+		result
+	}
+
+	/// Prevent the states of the given blocks from being pruned while they are queried.
+	fn pin_blocks(&self, hashes: &[Block::Hash]) -> Result<()> {
+		for (index, hash) in hashes.iter().enumerate() {
+			if let Err(e) = self.client.pin_block(hash) {
+				self.unpin_blocks(&hashes[..index]);
+				return Err(client_err(e));
+			}
+		}
+		Ok(())
+	}
+
+	/// Release the states pinned with `pin_blocks`.
+	fn unpin_blocks(&self, hashes: &[Block::Hash]) {
+		for hash in hashes {
+			self.client.unpin_block(hash);
+		}
+	}
+
 	/// Iterates through range.unfiltered_range and check each block for changes of keys' values.
 	fn query_storage_unfiltered(
 		&self,
@@ -218,7 +262,7 @@ impl<BE, Block, Client> StateBackend<Block, Client> for FullState<BE, Block, Cli
 	Client: ExecutorProvider<Block> + StorageProvider<Block, BE> + HeaderBackend<Block>
 		+ HeaderMetadata<Block, Error = sp_blockchain::Error> + BlockchainEvents<Block>
 		+ CallApiAt<Block, Error = sp_blockchain::Error> + ProvideRuntimeApi<Block>
-		+ Send + Sync + 'static,
+		+ BlockBackend<Block> + ProofProvider<Block> + Send + Sync + 'static,
 	Client::Api: Metadata<Block, Error = sp_blockchain::Error>,
 {
 	fn call(
@@ -227,8 +271,7 @@ impl<BE, Block, Client> StateBackend<Block, Client> for FullState<BE, Block, Cli
 		method: String,
 		call_data: Bytes,
 	) -> FutureResult<Bytes> {
-		let r = self.block_or_best(block)
-			.and_then(|block| self
+		let r = self.at_pinned_block(block, |block| self
 				.client
 				.executor()
 				.call(
@@ -249,8 +292,7 @@ impl<BE, Block, Client> StateBackend<Block, Client> for FullState<BE, Block, Cli
 		prefix: StorageKey,
 	) -> FutureResult<Vec<StorageKey>> {
 		Box::new(result(
-			self.block_or_best(block)
-				.and_then(|block| self.client.storage_keys(&BlockId::Hash(block), &prefix))
+			self.at_pinned_block(block, |block| self.client.storage_keys(&BlockId::Hash(block), &prefix))
 				.map_err(client_err)))
 	}
 
@@ -260,8 +302,7 @@ impl<BE, Block, Client> StateBackend<Block, Client> for FullState<BE, Block, Cli
 		prefix: StorageKey,
 	) -> FutureResult<Vec<(StorageKey, StorageData)>> {
 		Box::new(result(
-			self.block_or_best(block)
-				.and_then(|block| self.client.storage_pairs(&BlockId::Hash(block), &prefix))
+			self.at_pinned_block(block, |block| self.client.storage_pairs(&BlockId::Hash(block), &prefix))
 				.map_err(client_err)))
 	}
 
@@ -273,13 +314,11 @@ impl<BE, Block, Client> StateBackend<Block, Client> for FullState<BE, Block, Cli
 		start_key: Option<StorageKey>,
 	) -> FutureResult<Vec<StorageKey>> {
 		Box::new(result(
-			self.block_or_best(block)
-				.and_then(|block|
-					self.client.storage_keys_iter(
-						&BlockId::Hash(block), prefix.as_ref(), start_key.as_ref()
-					)
-				)
-				.map(|v| v.take(count as usize).collect())
+			self.at_pinned_block(block, |block|
+				self.client.storage_keys_iter(
+					&BlockId::Hash(block), prefix.as_ref(), start_key.as_ref()
+				).map(|v| v.take(count as usize).collect())
+			)
 				.map_err(client_err)))
 	}
 
@@ -289,8 +328,7 @@ impl<BE, Block, Client> StateBackend<Block, Client> for FullState<BE, Block, Cli
 		key: StorageKey,
 	) -> FutureResult<Option<StorageData>> {
 		Box::new(result(
-			self.block_or_best(block)
-				.and_then(|block| self.client.storage(&BlockId::Hash(block), &key))
+			self.at_pinned_block(block, |block| self.client.storage(&BlockId::Hash(block), &key))
 				.map_err(client_err)))
 	}
 
@@ -300,8 +338,7 @@ impl<BE, Block, Client> StateBackend<Block, Client> for FullState<BE, Block, Cli
 		key: StorageKey,
 	) -> FutureResult<Option<Block::Hash>> {
 		Box::new(result(
-			self.block_or_best(block)
-				.and_then(|block| self.client.storage_hash(&BlockId::Hash(block), &key))
+			self.at_pinned_block(block, |block| self.client.storage_hash(&BlockId::Hash(block), &key))
 				.map_err(client_err)))
 	}
 
@@ -314,8 +351,7 @@ impl<BE, Block, Client> StateBackend<Block, Client> for FullState<BE, Block, Cli
 		prefix: StorageKey,
 	) -> FutureResult<Vec<StorageKey>> {
 		Box::new(result(
-			self.block_or_best(block)
-				.and_then(|block| self.client.child_storage_keys(
+			self.at_pinned_block(block, |block| self.client.child_storage_keys(
 					&BlockId::Hash(block),
 					&child_storage_key,
 					ChildInfo::resolve_child_info(child_type, &child_info.0[..])
@@ -334,8 +370,7 @@ impl<BE, Block, Client> StateBackend<Block, Client> for FullState<BE, Block, Cli
 		key: StorageKey,
 	) -> FutureResult<Option<StorageData>> {
 		Box::new(result(
-			self.block_or_best(block)
-				.and_then(|block| self.client.child_storage(
+			self.at_pinned_block(block, |block| self.client.child_storage(
 					&BlockId::Hash(block),
 					&child_storage_key,
 					ChildInfo::resolve_child_info(child_type, &child_info.0[..])
@@ -354,8 +389,7 @@ impl<BE, Block, Client> StateBackend<Block, Client> for FullState<BE, Block, Cli
 		key: StorageKey,
 	) -> FutureResult<Option<Block::Hash>> {
 		Box::new(result(
-			self.block_or_best(block)
-				.and_then(|block| self.client.child_storage_hash(
+			self.at_pinned_block(block, |block| self.client.child_storage_hash(
 					&BlockId::Hash(block),
 					&child_storage_key,
 					ChildInfo::resolve_child_info(child_type, &child_info.0[..])
@@ -365,10 +399,81 @@ impl<BE, Block, Client> StateBackend<Block, Client> for FullState<BE, Block, Cli
 				.map_err(client_err)))
 	}
 
+	fn child_storage_keys_paged(
+		&self,
+		block: Option<Block::Hash>,
+		child_storage_key: StorageKey,
+		child_info: StorageKey,
+		child_type: u32,
+		prefix: Option<StorageKey>,
+		count: u32,
+		start_key: Option<StorageKey>,
+	) -> FutureResult<Vec<StorageKey>> {
+		Box::new(result(
+			self.at_pinned_block(block, |block| self.client.child_storage_keys_iter(
+					&BlockId::Hash(block),
+					child_storage_key,
+					ChildInfo::resolve_child_info(child_type, &child_info.0[..])
+						.ok_or_else(child_resolution_error)?
+						.to_owned(),
+					prefix.as_ref(),
+					start_key.as_ref(),
+				).map(|keys| keys.take(count as usize).collect()))
+				.map_err(client_err)))
+	}
+
+	fn child_storage_entries(
+		&self,
+		block: Option<Block::Hash>,
+		child_storage_key: StorageKey,
+		child_info: StorageKey,
+		child_type: u32,
+		keys: Vec<StorageKey>,
+	) -> FutureResult<Vec<Option<StorageData>>> {
+		Box::new(result(
+			self.at_pinned_block(block, |block| {
+					let child_info = ChildInfo::resolve_child_info(child_type, &child_info.0[..])
+						.ok_or_else(child_resolution_error)?;
+					keys.iter()
+						.map(|key| self.client.child_storage(
+							&BlockId::Hash(block),
+							&child_storage_key,
+							child_info,
+							key,
+						))
+						.collect::<ClientResult<Vec<_>>>()
+				})
+				.map_err(client_err)))
+	}
+
+	fn read_child_proof(
+		&self,
+		block: Option<Block::Hash>,
+		child_storage_key: StorageKey,
+		child_info: StorageKey,
+		child_type: u32,
+		keys: Vec<StorageKey>,
+	) -> FutureResult<ReadProof<Block::Hash>> {
+		Box::new(result(
+			self.at_pinned_block(block, |block| {
+					let child_info = ChildInfo::resolve_child_info(child_type, &child_info.0[..])
+						.ok_or_else(child_resolution_error)?;
+					self.client.read_child_proof(
+						&BlockId::Hash(block),
+						&child_storage_key.0,
+						child_info,
+						&mut keys.iter().map(|key| &key.0[..]),
+					).map(|proof| ReadProof {
+						at: block,
+						proof: proof.iter_nodes().map(Into::into).collect(),
+					})
+				})
+				.map_err(client_err)))
+	}
+
 	fn metadata(&self, block: Option<Block::Hash>) -> FutureResult<Bytes> {
 		Box::new(result(
-			self.block_or_best(block)
-				.and_then(|block|
+			self.at_pinned_block(block, |block|
 					self.client.runtime_api().metadata(&BlockId::Hash(block)).map(Into::into)
 				)
 				.map_err(client_err)))
@@ -376,8 +481,7 @@ impl<BE, Block, Client> StateBackend<Block, Client> for FullState<BE, Block, Cli
 
 	fn runtime_version(&self, block: Option<Block::Hash>) -> FutureResult<RuntimeVersion> {
 		Box::new(result(
-			self.block_or_best(block)
-				.and_then(|block| self.client.runtime_version_at(&BlockId::Hash(block)))
+			self.at_pinned_block(block, |block| self.client.runtime_version_at(&BlockId::Hash(block)))
 				.map_err(client_err)))
 	}
 
@@ -389,11 +493,103 @@ impl<BE, Block, Client> StateBackend<Block, Client> for FullState<BE, Block, Cli
 	) -> FutureResult<Vec<StorageChangeSet<Block::Hash>>> {
 		let call_fn = move || {
 			let range = self.split_query_storage_range(from, to)?;
+			self.pin_blocks(&range.hashes)?;
 			let mut changes = Vec::new();
 			let mut last_values = HashMap::new();
-			self.query_storage_unfiltered(&range, &keys, &mut last_values, &mut changes)?;
-			self.query_storage_filtered(&range, &keys, &last_values, &mut changes)?;
-			Ok(changes)
+			let result = self
+				.query_storage_unfiltered(&range, &keys, &mut last_values, &mut changes)
+				.and_then(|_| {
+					self.query_storage_filtered(&range, &keys, &last_values, &mut changes)
+				});
+			self.unpin_blocks(&range.hashes);
+			result.map(|_| changes)
+		};
+		Box::new(result(call_fn()))
+	}
+
+	fn query_storage_at(
+		&self,
+		keys: Vec<StorageKey>,
+		at: Option<Block::Hash>,
+	) -> FutureResult<Vec<StorageChangeSet<Block::Hash>>> {
+		let call_fn = move || {
+			self.at_pinned_block(at, |block| {
+				let id = BlockId::Hash(block);
+				let changes = keys
+					.into_iter()
+					.map(|key| self.client.storage(&id, &key).map(|value| (key, value)))
+					.collect::<ClientResult<Vec<_>>>()?;
+				Ok(vec![StorageChangeSet { block, changes }])
+			}).map_err(client_err)
+		};
+		Box::new(result(call_fn()))
+	}
+
+	fn trace_block(
+		&self,
+		block: Block::Hash,
+		targets: Option<String>,
+		storage_keys: Option<String>,
+	) -> FutureResult<BlockTrace<Block::Hash>> {
+		let call_fn = move || {
+			let tracing_targets = targets.unwrap_or_else(|| DEFAULT_TRACE_TARGETS.into());
+			let storage_keys = storage_keys.unwrap_or_default();
+			let signed_block = self.client.block(&BlockId::Hash(block))
+				.and_then(|signed_block| signed_block.ok_or_else(||
+					ClientError::UnknownBlock(format!("{:?}", block))
+				))
+				.map_err(client_err)?;
+			let (mut header, extrinsics) = signed_block.block.deconstruct();
+			let parent_hash = *header.parent_hash();
+			// The seal is added after the runtime executed the block, it would fail the digest
+			// checks of the runtime.
+			header.digest_mut().logs.retain(|item| item.as_seal().is_none());
+			let encoded_block = Block::new(header, extrinsics).encode();
+			// The block is known to be valid, so its signatures and state root don't need to be
+			// checked again if the runtime can skip them.
+			let method = if self.client.runtime_api()
+				.has_api::<dyn ExecuteBlockNoCheck<Block, Error = ()>>(&BlockId::Hash(parent_hash))
+				.unwrap_or(false)
+			{
+				"ExecuteBlockNoCheck_execute_block_no_check"
+			} else {
+				"Core_execute_block"
+			};
+
+			self.client.pin_block(&parent_hash).map_err(client_err)?;
+			let (execution, spans, events) = sc_tracing::collect_block_trace(
+				&tracing_targets,
+				|| self.client.executor().call(
+					&BlockId::Hash(parent_hash),
+					method,
+					&encoded_block,
+					self.client.execution_extensions().strategies().importing,
+					None,
+				),
+			);
+			self.client.unpin_block(&parent_hash);
+			execution.map_err(client_err)?;
+
+			let prefixes = storage_keys.split(',')
+				.map(|prefix| prefix.trim().trim_start_matches("0x").to_lowercase())
+				.filter(|prefix| !prefix.is_empty())
+				.collect::<Vec<_>>();
+			let events = events.into_iter()
+				.filter(|event| event.target != STATE_TRACE_TARGET || prefixes.is_empty() ||
+					event.values.get("key").map_or(false, |key|
+						prefixes.iter().any(|prefix| key.starts_with(prefix.as_str()))
+					)
+				)
+				.collect();
+
+			Ok(BlockTrace {
+				block_hash: block,
+				parent_hash,
+				tracing_targets,
+				storage_keys,
+				spans,
+				events,
+			})
 		};
 		Box::new(result(call_fn()))
 	}
@@ -516,6 +712,183 @@ impl<BE, Block, Client> StateBackend<Block, Client> for FullState<BE, Block, Cli
 	) -> RpcResult<bool> {
 		Ok(self.subscriptions.cancel(id))
 	}
+
+	fn subscribe_finalized_storage(
+		&self,
+		_meta: crate::metadata::Metadata,
+		subscriber: Subscriber<StorageChangeSet<Block::Hash>>,
+		keys: Option<Vec<StorageKey>>,
+	) {
+		let keys = match keys {
+			Some(keys) if !keys.is_empty() => keys,
+			_ => {
+				let _ = subscriber.reject(Error::KeysRequired.into());
+				return;
+			},
+		};
+
+		let stream = self.client.finality_notification_stream();
+
+		// initial values at the last finalized block
+		let mut last_values = HashMap::new();
+		let block = self.client.info().finalized_hash;
+		let initial = finalized_storage_changes::<BE, _, _>(
+			&*self.client,
+			block,
+			&keys,
+			&mut last_values,
+		);
+		let initial = match initial {
+			Ok(changes) => vec![Ok(Ok(StorageChangeSet { block, changes }))],
+			Err(err) => {
+				let _ = subscriber.reject(client_err(err).into());
+				return;
+			},
+		};
+
+		let client = self.client.clone();
+		self.subscriptions.add(subscriber, |sink| {
+			let stream = stream
+				.filter_map(move |notification| {
+					let changes = finalized_storage_changes::<BE, _, _>(
+						&*client,
+						notification.hash,
+						&keys,
+						&mut last_values,
+					);
+					future::ready(match changes {
+						Ok(changes) if !changes.is_empty() => {
+							let changes = StorageChangeSet { block: notification.hash, changes };
+							Some(Ok::<_, ()>(Ok(changes)))
+						},
+						Ok(_) => None,
+						Err(err) => {
+							warn!(
+								"Error reading finalized storage at {:?}: {:?}",
+								notification.hash,
+								err,
+							);
+							None
+						},
+					})
+				})
+				.compat();
+
+			sink
+				.sink_map_err(|e| warn!("Error sending notifications: {:?}", e))
+				.send_all(stream::iter_result(initial).chain(stream))
+				// we ignore the resulting Stream (if the first stream is over we are unsubscribed)
+				.map(|_| ())
+		});
+	}
+
+	fn unsubscribe_finalized_storage(
+		&self,
+		_meta: Option<crate::metadata::Metadata>,
+		id: SubscriptionId,
+	) -> RpcResult<bool> {
+		Ok(self.subscriptions.cancel(id))
+	}
+
+	fn subscribe_child_storage(
+		&self,
+		_meta: crate::metadata::Metadata,
+		subscriber: Subscriber<StorageChangeSet<Block::Hash>>,
+		child_storage_key: StorageKey,
+		child_info: StorageKey,
+		child_type: u32,
+		keys: Option<Vec<StorageKey>>,
+	) {
+		// only listen to the child storage, no top-level key is matched by an empty filter
+		let stream = match self.client.storage_changes_notification_stream(
+			Some(&[]),
+			Some(&[(child_storage_key.clone(), keys.clone())]),
+		) {
+			Ok(stream) => stream,
+			Err(err) => {
+				let _ = subscriber.reject(client_err(err).into());
+				return;
+			},
+		};
+
+		// initial values
+		let initial = match keys {
+			Some(keys) => {
+				let block = self.client.info().best_hash;
+				let values = self.child_storage_entries(
+					Some(block),
+					child_storage_key.clone(),
+					child_info,
+					child_type,
+					keys.clone(),
+				).wait();
+				match values {
+					Ok(values) => vec![Ok(Ok(StorageChangeSet {
+						block,
+						changes: keys.into_iter().zip(values).collect(),
+					}))],
+					Err(err) => {
+						let _ = subscriber.reject(err.into());
+						return;
+					},
+				}
+			},
+			None => Vec::new(),
+		};
+
+		self.subscriptions.add(subscriber, |sink| {
+			let stream = stream
+				.map(move |(block, changes)| Ok::<_, ()>(Ok(StorageChangeSet {
+					block,
+					changes: changes.iter()
+						.filter_map(|(o_sk, k, v)| if o_sk == Some(&child_storage_key) {
+							Some((k.clone(), v.cloned()))
+						} else { None }).collect(),
+				})))
+				.compat();
+
+			sink
+				.sink_map_err(|e| warn!("Error sending notifications: {:?}", e))
+				.send_all(stream::iter_result(initial).chain(stream))
+				// we ignore the resulting Stream (if the first stream is over we are unsubscribed)
+				.map(|_| ())
+		});
+	}
+
+	fn unsubscribe_child_storage(
+		&self,
+		_meta: Option<crate::metadata::Metadata>,
+		id: SubscriptionId,
+	) -> RpcResult<bool> {
+		Ok(self.subscriptions.cancel(id))
+	}
+}
+
+/// Reads `keys` at the given block and returns the ones whose value differs from the one recorded
+/// in `last_values`, updating it.
+///
+/// Keys without a recorded value are always returned.
+fn finalized_storage_changes<BE, Block, Client>(
+	client: &Client,
+	block: Block::Hash,
+	keys: &[StorageKey],
+	last_values: &mut HashMap<StorageKey, Option<StorageData>>,
+) -> ClientResult<Vec<(StorageKey, Option<StorageData>)>> where
+	Block: BlockT,
+	BE: Backend<Block>,
+	Client: StorageProvider<Block, BE>,
+{
+	let id = BlockId::Hash(block);
+	let mut changes = Vec::new();
+	for key in keys {
+		let value = client.storage(&id, key)?;
+		if last_values.get(key) != Some(&value) {
+			changes.push((key.clone(), value.clone()));
+			last_values.insert(key.clone(), value);
+		}
+	}
+
+	Ok(changes)
 }
 
 /// Splits passed range into two subranges where:
@@ -52,12 +52,22 @@ use sp_core::{
 };
 use sp_version::RuntimeVersion;
 use sp_runtime::{generic::BlockId, traits::{Block as BlockT, HashFor}};
+use sp_rpc::tracing::BlockTrace;
 
-use super::{StateBackend, error::{FutureResult, Error}, client_err};
+use super::{StateBackend, ReadProof, error::{FutureResult, Error}, client_err};
 
 /// Storage data map of storage keys => (optional) storage value.
 type StorageMap = HashMap<StorageKey, Option<StorageData>>;
 
+/// Blocks at which a storage subscription checks for changes.
+#[derive(Clone, Copy)]
+enum SubscriptionBlocks {
+	/// Every imported block.
+	Best,
+	/// Every finalized block.
+	Finalized,
+}
+
 /// State API backend for light nodes.
 pub struct LightState<Block: BlockT, F: Fetcher<Block>, Client> {
 	client: Arc<Client>,
@@ -165,6 +175,119 @@ impl<Block: BlockT, F: Fetcher<Block> + 'static, Client> LightState<Block, F, Cl
 	}
 }
 
+impl<Block, F, Client> LightState<Block, F, Client>
+	where
+		Block: BlockT,
+		Client: BlockchainEvents<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+		F: Fetcher<Block> + 'static
+{
+	/// Subscribe to changes of the given keys, checking them at every block of `blocks`.
+	fn subscribe_storage_at(
+		&self,
+		subscriber: Subscriber<StorageChangeSet<Block::Hash>>,
+		keys: Option<Vec<StorageKey>>,
+		blocks: SubscriptionBlocks,
+	) {
+		let keys = match keys {
+			Some(keys) if !keys.is_empty() => keys,
+			_ => {
+				warn!("Cannot subscribe to all keys on light client. Subscription rejected.");
+				return;
+			}
+		};
+
+		let keys = keys.iter().cloned().collect::<HashSet<_>>();
+		let keys_to_check = keys.iter().map(|k| k.0.clone()).collect::<HashSet<_>>();
+		let subscription_id = self.subscriptions.add(subscriber, move |sink| {
+			let fetcher = self.fetcher.clone();
+			let remote_blockchain = self.remote_blockchain.clone();
+			let storage_subscriptions = self.storage_subscriptions.clone();
+			let initial_block = match blocks {
+				SubscriptionBlocks::Best => self.block_or_best(None),
+				SubscriptionBlocks::Finalized => self.client.info().finalized_hash,
+			};
+			let blocks_stream = match blocks {
+				SubscriptionBlocks::Best => self.client
+					.import_notification_stream()
+					.map(|notification| Ok::<_, ()>(notification.hash))
+					.boxed(),
+				SubscriptionBlocks::Finalized => self.client
+					.finality_notification_stream()
+					.map(|notification| Ok::<_, ()>(notification.hash))
+					.boxed(),
+			};
+			let initial_keys = keys_to_check.iter().cloned().collect::<Vec<_>>();
+
+			let changes_stream = subscription_stream::<Block, _, _, _, _, _, _, _, _>(
+				storage_subscriptions.clone(),
+				blocks_stream.compat(),
+				display_error(storage(
+					&*remote_blockchain,
+					fetcher.clone(),
+					initial_block,
+					initial_keys,
+				).map(move |r| r.map(|r| (initial_block, r)))),
+				move |block| {
+					// there'll be single request per block for all active subscriptions
+					// with all subscribed keys
+					let keys = storage_subscriptions
+						.lock()
+						.subscriptions_by_key
+						.keys()
+						.map(|k| k.0.clone())
+						.collect();
+
+					storage(
+						&*remote_blockchain,
+						fetcher.clone(),
+						block,
+						keys,
+					)
+				},
+				move |block, old_value, new_value| {
+					// let's only select keys which are valid for this subscription
+					let new_value = new_value
+						.iter()
+						.filter(|(k, _)| keys_to_check.contains(&k.0))
+						.map(|(k, v)| (k.clone(), v.clone()))
+						.collect::<HashMap<_, _>>();
+					let value_differs = old_value
+						.as_ref()
+						.map(|old_value| **old_value != new_value)
+						.unwrap_or(true);
+					match value_differs {
+						true => Some(StorageChangeSet {
+							block,
+							changes: new_value
+								.iter()
+								.map(|(k, v)| (k.clone(), v.clone()))
+								.collect(),
+						}),
+						false => None,
+					}
+				}
+			);
+
+			sink
+				.sink_map_err(|e| warn!("Error sending notifications: {:?}", e))
+				.send_all(changes_stream.map(|changes| Ok(changes)))
+				// we ignore the resulting Stream (if the first stream is over we are unsubscribed)
+				.map(|_| ())
+		});
+
+		// remember keys associated with this subscription
+		let mut storage_subscriptions = self.storage_subscriptions.lock();
+		storage_subscriptions.keys_by_subscription.insert(subscription_id.clone(), keys.clone());
+		for key in keys {
+			storage_subscriptions
+				.subscriptions_by_key
+				.entry(key)
+				.or_default()
+				.insert(subscription_id.clone());
+		}
+	}
+}
+
 impl<Block, F, Client> StateBackend<Block, Client> for LightState<Block, F, Client>
 	where
 		Block: BlockT,
@@ -302,6 +425,69 @@ impl<Block, F, Client> StateBackend<Block, Client> for LightState<Block, F, Clie
 		)
 	}
 
+	fn child_storage_keys_paged(
+		&self,
+		_block: Option<Block::Hash>,
+		_child_storage_key: StorageKey,
+		_child_info: StorageKey,
+		_child_type: u32,
+		_prefix: Option<StorageKey>,
+		_count: u32,
+		_start_key: Option<StorageKey>,
+	) -> FutureResult<Vec<StorageKey>> {
+		Box::new(result(Err(client_err(ClientError::NotAvailableOnLightClient))))
+	}
+
+	fn child_storage_entries(
+		&self,
+		block: Option<Block::Hash>,
+		child_storage_key: StorageKey,
+		child_info: StorageKey,
+		child_type: u32,
+		keys: Vec<StorageKey>,
+	) -> FutureResult<Vec<Option<StorageData>>> {
+		let block = self.block_or_best(block);
+		let fetcher = self.fetcher.clone();
+		let child_storage = resolve_header(&*self.remote_blockchain, &*self.fetcher, block)
+			.then(move |result| match result {
+				Ok(header) => Either::Left(fetcher.remote_read_child(RemoteReadChildRequest {
+					block,
+					header,
+					storage_key: child_storage_key.0,
+					child_info: child_info.0,
+					child_type,
+					keys: keys.iter().map(|key| key.0.clone()).collect(),
+					retry_count: Default::default(),
+				}).then(move |result| ready(result
+					.map(|data| keys
+						.iter()
+						.map(|key| data
+							.get(&key.0)
+							.cloned()
+							.expect("successful result has entry for all keys; qed")
+							.map(StorageData)
+						)
+						.collect::<Vec<_>>()
+					)
+					.map_err(client_err)
+				))),
+				Err(error) => Either::Right(ready(Err(error))),
+			});
+
+		Box::new(child_storage.boxed().compat())
+	}
+
+	fn read_child_proof(
+		&self,
+		_block: Option<Block::Hash>,
+		_child_storage_key: StorageKey,
+		_child_info: StorageKey,
+		_child_type: u32,
+		_keys: Vec<StorageKey>,
+	) -> FutureResult<ReadProof<Block::Hash>> {
+		Box::new(result(Err(client_err(ClientError::NotAvailableOnLightClient))))
+	}
+
 	fn metadata(&self, block: Option<Block::Hash>) -> FutureResult<Bytes> {
 		let metadata = self.call(block, "Metadata_metadata".into(), Bytes(Vec::new()))
 			.and_then(|metadata| OpaqueMetadata::decode(&mut &metadata.0[..])
@@ -331,99 +517,46 @@ impl<Block, F, Client> StateBackend<Block, Client> for LightState<Block, F, Clie
 		Box::new(result(Err(client_err(ClientError::NotAvailableOnLightClient))))
 	}
 
+	fn query_storage_at(
+		&self,
+		keys: Vec<StorageKey>,
+		at: Option<Block::Hash>,
+	) -> FutureResult<Vec<StorageChangeSet<Block::Hash>>> {
+		let block = self.block_or_best(at);
+		let requested_keys = keys.iter().map(|key| key.0.clone()).collect();
+		Box::new(storage(
+			&*self.remote_blockchain,
+			self.fetcher.clone(),
+			block,
+			requested_keys,
+		).boxed().compat().map(move |values| vec![StorageChangeSet {
+			block,
+			changes: keys
+				.into_iter()
+				.map(|key| {
+					let value = values.get(&key).cloned().unwrap_or_default();
+					(key, value)
+				})
+				.collect(),
+		}]))
+	}
+
+	fn trace_block(
+		&self,
+		_block: Block::Hash,
+		_targets: Option<String>,
+		_storage_keys: Option<String>,
+	) -> FutureResult<BlockTrace<Block::Hash>> {
+		Box::new(result(Err(client_err(ClientError::NotAvailableOnLightClient))))
+	}
+
 	fn subscribe_storage(
 		&self,
 		_meta: crate::metadata::Metadata,
 		subscriber: Subscriber<StorageChangeSet<Block::Hash>>,
 		keys: Option<Vec<StorageKey>>
 	) {
-		let keys = match keys {
-			Some(keys) if !keys.is_empty() => keys,
-			_ => {
-				warn!("Cannot subscribe to all keys on light client. Subscription rejected.");
-				return;
-			}
-		};
-
-		let keys = keys.iter().cloned().collect::<HashSet<_>>();
-		let keys_to_check = keys.iter().map(|k| k.0.clone()).collect::<HashSet<_>>();
-		let subscription_id = self.subscriptions.add(subscriber, move |sink| {
-			let fetcher = self.fetcher.clone();
-			let remote_blockchain = self.remote_blockchain.clone();
-			let storage_subscriptions = self.storage_subscriptions.clone();
-			let initial_block = self.block_or_best(None);
-			let initial_keys = keys_to_check.iter().cloned().collect::<Vec<_>>();
-
-			let changes_stream = subscription_stream::<Block, _, _, _, _, _, _, _, _>(
-				storage_subscriptions.clone(),
-				self.client
-					.import_notification_stream()
-					.map(|notification| Ok::<_, ()>(notification.hash))
-					.compat(),
-				display_error(storage(
-					&*remote_blockchain,
-					fetcher.clone(),
-					initial_block,
-					initial_keys,
-				).map(move |r| r.map(|r| (initial_block, r)))),
-				move |block| {
-					// there'll be single request per block for all active subscriptions
-					// with all subscribed keys
-					let keys = storage_subscriptions
-						.lock()
-						.subscriptions_by_key
-						.keys()
-						.map(|k| k.0.clone())
-						.collect();
-
-					storage(
-						&*remote_blockchain,
-						fetcher.clone(),
-						block,
-						keys,
-					)
-				},
-				move |block, old_value, new_value| {
-					// let's only select keys which are valid for this subscription
-					let new_value = new_value
-						.iter()
-						.filter(|(k, _)| keys_to_check.contains(&k.0))
-						.map(|(k, v)| (k.clone(), v.clone()))
-						.collect::<HashMap<_, _>>();
-					let value_differs = old_value
-						.as_ref()
-						.map(|old_value| **old_value != new_value)
-						.unwrap_or(true);
-					match value_differs {
-						true => Some(StorageChangeSet {
-							block,
-							changes: new_value
-								.iter()
-								.map(|(k, v)| (k.clone(), v.clone()))
-								.collect(),
-						}),
-						false => None,
-					}
-				}
-			);
-
-			sink
-				.sink_map_err(|e| warn!("Error sending notifications: {:?}", e))
-				.send_all(changes_stream.map(|changes| Ok(changes)))
-				// we ignore the resulting Stream (if the first stream is over we are unsubscribed)
-				.map(|_| ())
-		});
-
-		// remember keys associated with this subscription
-		let mut storage_subscriptions = self.storage_subscriptions.lock();
-		storage_subscriptions.keys_by_subscription.insert(subscription_id.clone(), keys.clone());
-		for key in keys {
-			storage_subscriptions
-				.subscriptions_by_key
-				.entry(key)
-				.or_default()
-				.insert(subscription_id.clone());
-		}
+		self.subscribe_storage_at(subscriber, keys, SubscriptionBlocks::Best)
 	}
 
 	fn unsubscribe_storage(
@@ -454,6 +587,43 @@ impl<Block, F, Client> StateBackend<Block, Client> for LightState<Block, F, Clie
 		Ok(true)
 	}
 
+	fn subscribe_finalized_storage(
+		&self,
+		_meta: crate::metadata::Metadata,
+		subscriber: Subscriber<StorageChangeSet<Block::Hash>>,
+		keys: Option<Vec<StorageKey>>
+	) {
+		self.subscribe_storage_at(subscriber, keys, SubscriptionBlocks::Finalized)
+	}
+
+	fn unsubscribe_finalized_storage(
+		&self,
+		meta: Option<crate::metadata::Metadata>,
+		id: SubscriptionId,
+	) -> RpcResult<bool> {
+		self.unsubscribe_storage(meta, id)
+	}
+
+	fn subscribe_child_storage(
+		&self,
+		_meta: crate::metadata::Metadata,
+		subscriber: Subscriber<StorageChangeSet<Block::Hash>>,
+		_child_storage_key: StorageKey,
+		_child_info: StorageKey,
+		_child_type: u32,
+		_keys: Option<Vec<StorageKey>>,
+	) {
+		let _ = subscriber.reject(client_err(ClientError::NotAvailableOnLightClient).into());
+	}
+
+	fn unsubscribe_child_storage(
+		&self,
+		_meta: Option<crate::metadata::Metadata>,
+		_id: SubscriptionId,
+	) -> RpcResult<bool> {
+		Ok(false)
+	}
+
 	fn subscribe_runtime_version(
 		&self,
 		_meta: crate::metadata::Metadata,
@@ -564,7 +734,7 @@ fn runtime_version<Block: BlockT, F: Fetcher<Block>>(
 		Bytes(Vec::new()),
 	)
 	.then(|version| ready(version.and_then(|version|
-		Decode::decode(&mut &version.0[..])
+		RuntimeVersion::decode_compat(&version.0)
 			.map_err(|e| client_err(ClientError::VersionInvalid(e.what().into())))
 	)))
 }
@@ -25,6 +25,7 @@ use sp_core::{storage::{well_known_keys, ChildInfo}, ChangesTrieConfiguration};
 use sp_core::hash::H256;
 use sc_block_builder::BlockBuilderProvider;
 use sp_io::hashing::blake2_256;
+use sp_runtime::generic::BlockId;
 use substrate_test_runtime_client::{
 	prelude::*,
 	sp_consensus::BlockOrigin,
@@ -46,7 +47,11 @@ fn should_return_storage() {
 		.add_extra_child_storage(STORAGE_KEY.to_vec(), CHILD_INFO, KEY.to_vec(), CHILD_VALUE.to_vec())
 		.build();
 	let genesis_hash = client.genesis_hash();
-	let client = new_full(Arc::new(client), Subscriptions::new(Arc::new(core.executor())));
+	let client = new_full(
+		Arc::new(client),
+		Subscriptions::new(Arc::new(core.executor())),
+		DenyUnsafe::No,
+	);
 	let key = StorageKey(KEY.to_vec());
 	let storage_key = StorageKey(STORAGE_KEY.to_vec());
 	let (child_info, child_type) = CHILD_INFO.info();
@@ -85,7 +90,7 @@ fn should_return_child_storage() {
 		.add_child_storage("test", "key", CHILD_INFO, vec![42_u8])
 		.build());
 	let genesis_hash = client.genesis_hash();
-	let client = new_full(client, Subscriptions::new(Arc::new(core.executor())));
+	let client = new_full(client, Subscriptions::new(Arc::new(core.executor())), DenyUnsafe::No);
 	let child_key = StorageKey(
 		well_known_keys::CHILD_STORAGE_KEY_PREFIX.iter().chain(b"test").cloned().collect()
 	);
@@ -124,12 +129,126 @@ fn should_return_child_storage() {
 	);
 }
 
+#[test]
+fn should_return_paged_child_keys_entries_and_proof() {
+	let (child_info, child_type) = CHILD_INFO.info();
+	let child_info = StorageKey(child_info.to_vec());
+	let core = tokio::runtime::Runtime::new().unwrap();
+	let client = Arc::new(substrate_test_runtime_client::TestClientBuilder::new()
+		.add_child_storage("test", "key1", CHILD_INFO, vec![1_u8])
+		.add_child_storage("test", "key2", CHILD_INFO, vec![2_u8])
+		.add_child_storage("test", "key3", CHILD_INFO, vec![3_u8])
+		.build());
+	let genesis_hash = client.genesis_hash();
+	let client = new_full(client, Subscriptions::new(Arc::new(core.executor())), DenyUnsafe::No);
+	let child_key = StorageKey(
+		well_known_keys::CHILD_STORAGE_KEY_PREFIX.iter().chain(b"test").cloned().collect()
+	);
+	let key = |k: &[u8]| StorageKey(k.to_vec());
+
+	assert_eq!(
+		client.child_storage_keys_paged(
+			child_key.clone(),
+			child_info.clone(),
+			child_type,
+			Some(key(b"key")),
+			2,
+			Some(key(b"key1")),
+			None,
+		).wait().unwrap(),
+		vec![key(b"key2"), key(b"key3")],
+	);
+	// the pages are read from the start of the prefix.
+	assert_eq!(
+		client.child_storage_keys_paged(
+			child_key.clone(),
+			child_info.clone(),
+			child_type,
+			Some(key(b"key")),
+			2,
+			None,
+			None,
+		).wait().unwrap(),
+		vec![key(b"key1"), key(b"key2")],
+	);
+	assert_matches!(
+		client.child_storage_keys_paged(
+			child_key.clone(),
+			child_info.clone(),
+			child_type,
+			None,
+			STORAGE_KEYS_PAGED_MAX_COUNT + 1,
+			None,
+			None,
+		).wait(),
+		Err(Error::InvalidCount { .. })
+	);
+	assert_eq!(
+		client.child_storage_entries(
+			child_key.clone(),
+			child_info.clone(),
+			child_type,
+			vec![key(b"key1"), key(b"missing"), key(b"key3")],
+			None,
+		).wait().unwrap(),
+		vec![Some(StorageData(vec![1])), None, Some(StorageData(vec![3]))],
+	);
+
+	let proof = client.read_child_proof(
+		child_key,
+		child_info,
+		child_type,
+		vec![key(b"key1")],
+		Some(genesis_hash),
+	).wait().unwrap();
+	assert_eq!(proof.at, genesis_hash);
+	assert!(!proof.proof.is_empty());
+}
+
+#[test]
+fn should_send_initial_child_storage_values() {
+	let (child_info, child_type) = CHILD_INFO.info();
+	let child_info = StorageKey(child_info.to_vec());
+	let mut core = tokio::runtime::Runtime::new().unwrap();
+	let remote = core.executor();
+	let (subscriber, id, transport) = Subscriber::new_test("test");
+
+	{
+		let client = Arc::new(substrate_test_runtime_client::TestClientBuilder::new()
+			.add_child_storage("test", "key", CHILD_INFO, vec![42_u8])
+			.build());
+		let api = new_full(client, Subscriptions::new(Arc::new(remote)), DenyUnsafe::No);
+		let child_key = StorageKey(
+			well_known_keys::CHILD_STORAGE_KEY_PREFIX.iter().chain(b"test").cloned().collect()
+		);
+
+		api.subscribe_child_storage(
+			Default::default(),
+			subscriber,
+			child_key,
+			child_info,
+			child_type,
+			Some(vec![StorageKey(b"key".to_vec())]),
+		);
+
+		// assert id assigned
+		assert_eq!(core.block_on(id), Ok(Ok(SubscriptionId::Number(1))));
+	}
+
+	// assert initial values sent to transport
+	let (notification, next) = core.block_on(transport.into_future()).unwrap();
+	let notification = notification.expect("initial values are sent");
+	assert!(notification.contains(r#""0x2a""#), "{}", notification);
+	// no more notifications on this channel
+	assert_eq!(core.block_on(next.into_future()).unwrap().0, None);
+}
+
 #[test]
 fn should_call_contract() {
 	let core = tokio::runtime::Runtime::new().unwrap();
 	let client = Arc::new(substrate_test_runtime_client::new());
 	let genesis_hash = client.genesis_hash();
-	let client = new_full(client, Subscriptions::new(Arc::new(core.executor())));
+	let client = new_full(client, Subscriptions::new(Arc::new(core.executor())), DenyUnsafe::No);
 
 	assert_matches!(
 		client.call("balanceOf".into(), Bytes(vec![1,2,3]), Some(genesis_hash).into()).wait(),
@@ -137,6 +256,69 @@ fn should_call_contract() {
 	)
 }
 
+#[test]
+fn should_query_storage_at_block() {
+	const KEY: &[u8] = b":mock";
+	const VALUE: &[u8] = b"hello world";
+
+	let core = tokio::runtime::Runtime::new().unwrap();
+	let client = TestClientBuilder::new()
+		.add_extra_storage(KEY.to_vec(), VALUE.to_vec())
+		.build();
+	let genesis_hash = client.genesis_hash();
+	let api = new_full(
+		Arc::new(client),
+		Subscriptions::new(Arc::new(core.executor())),
+		DenyUnsafe::No,
+	);
+	let key = StorageKey(KEY.to_vec());
+	let missing_key = StorageKey(b":missing".to_vec());
+
+	let result = api.query_storage_at(vec![key.clone(), missing_key.clone()], None).wait();
+	assert_eq!(result.unwrap(), vec![StorageChangeSet {
+		block: genesis_hash,
+		changes: vec![
+			(key, Some(StorageData(VALUE.to_vec()))),
+			(missing_key, None),
+		],
+	}]);
+}
+
+#[test]
+fn should_trace_block_storage_accesses() {
+	let core = tokio::runtime::Runtime::new().unwrap();
+	let mut client = Arc::new(substrate_test_runtime_client::new());
+	let block = client.new_block(Default::default()).unwrap().build().unwrap().block;
+	let hash = block.header.hash();
+	let parent_hash = block.header.parent_hash;
+	client.import(BlockOrigin::Own, block).unwrap();
+
+	let api = new_full(
+		client.clone(),
+		Subscriptions::new(Arc::new(core.executor())),
+		DenyUnsafe::No,
+	);
+	let trace = api.trace_block(hash, Some("state".into()), None).wait().unwrap();
+	assert_eq!(trace.block_hash, hash);
+	assert_eq!(trace.parent_hash, parent_hash);
+	assert!(!trace.events.is_empty());
+	assert!(trace.events.iter().all(|event| event.target == "state"));
+
+	// `:code`
+	let trace = api.trace_block(hash, Some("state".into()), Some("0x3a636f6465".into()))
+		.wait()
+		.unwrap();
+	assert!(trace.events.iter().all(|event|
+		event.values.get("key").map_or(false, |key| key.starts_with("3a636f6465"))
+	));
+
+	let api = new_full(client, Subscriptions::new(Arc::new(core.executor())), DenyUnsafe::Yes);
+	assert_matches!(
+		api.trace_block(hash, None, None).wait(),
+		Err(Error::UnsafeRpcCalled(_))
+	);
+}
+
 #[test]
 fn should_notify_about_storage_changes() {
 	let mut core = tokio::runtime::Runtime::new().unwrap();
@@ -145,7 +327,7 @@ fn should_notify_about_storage_changes() {
 
 	{
 		let mut client = Arc::new(substrate_test_runtime_client::new());
-		let api = new_full(client.clone(), Subscriptions::new(Arc::new(remote)));
+		let api = new_full(client.clone(), Subscriptions::new(Arc::new(remote)), DenyUnsafe::No);
 
 		api.subscribe_storage(Default::default(), subscriber, None.into());
 
@@ -178,7 +360,7 @@ fn should_send_initial_storage_changes_and_notifications() {
 
 	{
 		let mut client = Arc::new(substrate_test_runtime_client::new());
-		let api = new_full(client.clone(), Subscriptions::new(Arc::new(remote)));
+		let api = new_full(client.clone(), Subscriptions::new(Arc::new(remote)), DenyUnsafe::No);
 
 		let alice_balance_key = blake2_256(&runtime::system::balance_of_key(AccountKeyring::Alice.into()));
 
@@ -210,11 +392,76 @@ fn should_send_initial_storage_changes_and_notifications() {
 	assert_eq!(core.block_on(next.into_future()).unwrap().0, None);
 }
 
+#[test]
+fn should_notify_about_finalized_storage_changes_only() {
+	let mut core = tokio::runtime::Runtime::new().unwrap();
+	let remote = core.executor();
+	let (subscriber, id, transport) = Subscriber::new_test("test");
+
+	{
+		let mut client = Arc::new(substrate_test_runtime_client::new());
+		let api = new_full(client.clone(), Subscriptions::new(Arc::new(remote)), DenyUnsafe::No);
+
+		let alice_balance_key = blake2_256(&runtime::system::balance_of_key(AccountKeyring::Alice.into()));
+
+		api.subscribe_finalized_storage(Default::default(), subscriber, Some(vec![
+			StorageKey(alice_balance_key.to_vec()),
+		]).into());
+
+		// assert id assigned
+		assert_eq!(core.block_on(id), Ok(Ok(SubscriptionId::Number(1))));
+
+		let mut add_block = |nonce| {
+			let mut builder = client.new_block(Default::default()).unwrap();
+			builder.push_transfer(runtime::Transfer {
+				from: AccountKeyring::Alice.into(),
+				to: AccountKeyring::Ferdie.into(),
+				amount: 42,
+				nonce,
+			}).unwrap();
+			builder.build().unwrap().block
+		};
+
+		// import two blocks, but only finalize the first one.
+		let block = add_block(0);
+		client.import(BlockOrigin::Own, block).unwrap();
+		let block = add_block(1);
+		client.import(BlockOrigin::Own, block).unwrap();
+		client.finalize_block(BlockId::number(1), None).unwrap();
+	}
+
+	// assert initial values sent to transport
+	let (notification, next) = core.block_on(transport.into_future()).unwrap();
+	assert!(notification.is_some());
+	// assert a single notification for the finalized block
+	let (notification, next) = core.block_on(next.into_future()).unwrap();
+	assert!(notification.is_some());
+	// no more notifications on this channel
+	assert_eq!(core.block_on(next.into_future()).unwrap().0, None);
+}
+
+#[test]
+fn should_reject_finalized_storage_subscription_without_keys() {
+	let core = tokio::runtime::Runtime::new().unwrap();
+	let (subscriber, id, _transport) = Subscriber::new_test("test");
+
+	let client = Arc::new(substrate_test_runtime_client::new());
+	let api = new_full(client, Subscriptions::new(Arc::new(core.executor())), DenyUnsafe::No);
+
+	api.subscribe_finalized_storage(Default::default(), subscriber, None.into());
+
+	assert_matches!(id.wait(), Ok(Err(_)));
+}
+
 #[test]
 fn should_query_storage() {
 	fn run_tests(mut client: Arc<TestClient>) {
 		let core = tokio::runtime::Runtime::new().unwrap();
-		let api = new_full(client.clone(), Subscriptions::new(Arc::new(core.executor())));
+		let api = new_full(
+			client.clone(),
+			Subscriptions::new(Arc::new(core.executor())),
+			DenyUnsafe::No,
+		);
 
 		let mut add_block = |nonce| {
 			let mut builder = client.new_block(Default::default()).unwrap();
@@ -399,13 +646,18 @@ fn should_return_runtime_version() {
 	let core = tokio::runtime::Runtime::new().unwrap();
 
 	let client = Arc::new(substrate_test_runtime_client::new());
-	let api = new_full(client.clone(), Subscriptions::new(Arc::new(core.executor())));
+	let api = new_full(
+		client.clone(),
+		Subscriptions::new(Arc::new(core.executor())),
+		DenyUnsafe::No,
+	);
 
 	let result = "{\"specName\":\"test\",\"implName\":\"parity-test\",\"authoringVersion\":1,\
-		\"specVersion\":2,\"implVersion\":2,\"apis\":[[\"0xdf6acb689907609b\",2],\
+		\"specVersion\":2,\"implVersion\":2,\"apis\":[[\"0xdf6acb689907609b\",3],\
 		[\"0x37e397fc7c91f5e4\",1],[\"0xd2bc9897eed08f15\",1],[\"0x40fe3ad401f8959a\",4],\
 		[\"0xc6e9a76309f39b09\",1],[\"0xdd718d5cc53262d4\",1],[\"0xcbca25e39f142387\",1],\
-		[\"0xf78b278be53f454c\",2],[\"0xab3c0572291feb8b\",1],[\"0xbc9d89904f5b923f\",1]]}";
+		[\"0xf78b278be53f454c\",2],[\"0xab3c0572291feb8b\",1],[\"0xbc9d89904f5b923f\",1]],\
+		\"transactionVersion\":1}";
 
 	let runtime_version = api.runtime_version(None.into()).wait().unwrap();
 	let serialized = serde_json::to_string(&runtime_version).unwrap();
@@ -422,7 +674,11 @@ fn should_notify_on_runtime_version_initially() {
 
 	{
 		let client = Arc::new(substrate_test_runtime_client::new());
-		let api = new_full(client.clone(), Subscriptions::new(Arc::new(core.executor())));
+		let api = new_full(
+			client.clone(),
+			Subscriptions::new(Arc::new(core.executor())),
+			DenyUnsafe::No,
+		);
 
 		api.subscribe_runtime_version(Default::default(), subscriber);
 
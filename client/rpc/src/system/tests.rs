@@ -95,6 +95,13 @@ fn api<T: Into<Option<Status>>>(sync: T) -> System<Block> {
 				Request::NodeRoles(sender) => {
 					let _ = sender.send(vec![NodeRole::Authority]);
 				}
+				Request::SyncState(sender) => {
+					let _ = sender.send(SyncState {
+						starting_block: 1,
+						current_block: 2,
+						highest_block: Some(3),
+					});
+				}
 			};
 
 			future::ready(())
@@ -105,10 +112,10 @@ fn api<T: Into<Option<Status>>>(sync: T) -> System<Block> {
 		impl_version: "0.2.0".into(),
 		chain_name: "testchain".into(),
 		properties: Default::default(),
-	}, tx)
+	}, tx, DenyUnsafe::No)
 }
 
-fn wait_receiver<T>(rx: Receiver<T>) -> T {
+fn wait_receiver<T>(rx: impl rpc::futures::Future<Item = T, Error = rpc::Error>) -> T {
 	let mut runtime = tokio::runtime::current_thread::Runtime::new().unwrap();
 	runtime.block_on(rx).unwrap()
 }
@@ -245,6 +252,18 @@ fn system_node_roles() {
 	);
 }
 
+#[test]
+fn system_sync_state() {
+	assert_eq!(
+		wait_receiver(api(None).system_sync_state()),
+		SyncState {
+			starting_block: 1,
+			current_block: 2,
+			highest_block: Some(3),
+		}
+	);
+}
+
 #[test]
 fn system_network_add_reserved() {
 	let good_peer_id = "/ip4/198.51.100.19/tcp/30333/p2p/QmSk5HQbn6LhUwDiNMseVUjuRYhEtYj4aUZ6WfWoGURpdV";
@@ -268,3 +287,19 @@ fn system_network_remove_reserved() {
 	assert_eq!(runtime.block_on(good_fut), Ok(()));
 	assert!(runtime.block_on(bad_fut).is_err());
 }
+
+#[test]
+fn system_unsafe_calls_are_denied() {
+	let api = System { deny_unsafe: DenyUnsafe::Yes, ..api(None) };
+	let mut runtime = tokio::runtime::current_thread::Runtime::new().unwrap();
+
+	let good_peer_id = "/ip4/198.51.100.19/tcp/30333/p2p/QmSk5HQbn6LhUwDiNMseVUjuRYhEtYj4aUZ6WfWoGURpdV";
+	assert!(runtime.block_on(api.system_add_reserved_peer(good_peer_id.into())).is_err());
+	let good_peer_id = "QmSk5HQbn6LhUwDiNMseVUjuRYhEtYj4aUZ6WfWoGURpdV";
+	assert!(runtime.block_on(api.system_remove_reserved_peer(good_peer_id.into())).is_err());
+	assert!(runtime.block_on(api.system_peers()).is_err());
+	assert!(runtime.block_on(api.system_network_state()).is_err());
+
+	// Safe calls keep working.
+	assert_eq!(wait_receiver(api.system_node_roles()), vec![NodeRole::Authority]);
+}
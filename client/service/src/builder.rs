@@ -33,20 +33,22 @@ use futures::{
 	channel::mpsc,
 	future::ready,
 };
-use sc_keystore::{Store as Keystore};
+use sc_keystore::{Store as Keystore, RemoteKeystore};
 use log::{info, warn, error};
 use sc_network::config::{FinalityProofProvider, OnDemand, BoxFinalityProofRequestBuilder};
 use sc_network::{NetworkService, NetworkStateInfo};
 use parking_lot::{Mutex, RwLock};
 use sp_runtime::generic::BlockId;
+use sp_core::{hexdisplay::HexDisplay, storage::Storage, traits::BareCryptoStorePtr};
 use sp_runtime::traits::{
 	Block as BlockT, NumberFor, SaturatedConversion, HashFor, UniqueSaturatedInto,
+	Header as HeaderT, Zero,
 };
 use sp_api::ProvideRuntimeApi;
 use sc_executor::{NativeExecutor, NativeExecutionDispatch};
 use std::{
 	io::{Read, Write, Seek},
-	marker::PhantomData, sync::Arc, pin::Pin
+	marker::PhantomData, sync::Arc, pin::Pin, collections::BTreeMap,
 };
 use wasm_timer::SystemTime;
 use sysinfo::{get_current_pid, ProcessExt, System, SystemExt};
@@ -57,7 +59,9 @@ use prometheus_endpoint::{register, Gauge, U64, F64, Registry, PrometheusError,
 
 struct ServiceMetrics {
 	block_height_number: GaugeVec<U64>,
+	finality_lag_blocks: Gauge<U64>,
 	ready_transactions_number: Gauge<U64>,
+	banned_transactions_number: Gauge<U64>,
 	memory_usage_bytes: Gauge<U64>,
 	cpu_usage_percentage: Gauge<F64>,
 	network_per_sec_bytes: GaugeVec<U64>,
@@ -73,9 +77,16 @@ impl ServiceMetrics {
 				Opts::new("block_height_number", "Height of the chain"),
 				&["status"]
 			)?, registry)?,
+			finality_lag_blocks: register(Gauge::new(
+				"finality_lag_blocks",
+				"Number of blocks the finalized block is behind the best block",
+			)?, registry)?,
 			ready_transactions_number: register(Gauge::new(
 				"ready_transactions_number", "Number of transactions in the ready queue",
 			)?, registry)?,
+			banned_transactions_number: register(Gauge::new(
+				"banned_transactions_number", "Number of transactions banned from the pool",
+			)?, registry)?,
 			memory_usage_bytes: register(Gauge::new(
 				"memory_usage_bytes", "Node memory usage",
 			)?, registry)?,
@@ -102,6 +113,60 @@ impl ServiceMetrics {
 
 pub type BackgroundTask = Pin<Box<dyn Future<Output=()> + Send>>;
 
+/// Builds the node specific RPC extensions.
+///
+/// Whether unsafe RPC methods may be called depends on the interface an RPC server listens on,
+/// which is only known when the servers are started. The extensions are therefore built once for
+/// every server, as well as for the in-memory RPC handlers.
+pub trait RpcExtensionBuilder {
+	/// The type of the RPC extension that will be built.
+	type Output: sc_rpc::RpcExtension<sc_rpc::Metadata>;
+
+	/// Returns the RPC extension to expose on a server denying unsafe calls as per `deny_unsafe`.
+	///
+	/// `subscriptions` is shared with the builtin RPC modules of that server and drives the
+	/// subscriptions of the extension.
+	fn build(
+		&self,
+		deny_unsafe: sc_rpc::DenyUnsafe,
+		subscriptions: sc_rpc::Subscriptions,
+	) -> Self::Output;
+}
+
+impl<F, R> RpcExtensionBuilder for F where
+	F: Fn(sc_rpc::DenyUnsafe, sc_rpc::Subscriptions) -> R,
+	R: sc_rpc::RpcExtension<sc_rpc::Metadata>,
+{
+	type Output = R;
+
+	fn build(
+		&self,
+		deny_unsafe: sc_rpc::DenyUnsafe,
+		subscriptions: sc_rpc::Subscriptions,
+	) -> Self::Output {
+		(*self)(deny_unsafe, subscriptions)
+	}
+}
+
+/// An `RpcExtensionBuilder` exposing clones of the same, already built, RPC extension on every
+/// server.
+#[derive(Clone)]
+pub struct NoopRpcExtensionBuilder<R>(pub R);
+
+impl<R> RpcExtensionBuilder for NoopRpcExtensionBuilder<R> where
+	R: Clone + sc_rpc::RpcExtension<sc_rpc::Metadata>,
+{
+	type Output = R;
+
+	fn build(
+		&self,
+		_deny_unsafe: sc_rpc::DenyUnsafe,
+		_subscriptions: sc_rpc::Subscriptions,
+	) -> Self::Output {
+		self.0.clone()
+	}
+}
+
 /// Aggregator for the components required to build a service.
 ///
 /// # Usage
@@ -127,6 +192,7 @@ pub struct ServiceBuilder<TBl, TRtApi, TCl, TFchr, TSc, TImpQu, TFprb, TFpp,
 	backend: Arc<Backend>,
 	tasks_builder: TaskManagerBuilder,
 	keystore: Arc<RwLock<Keystore>>,
+	signing_keystore: BareCryptoStorePtr,
 	fetcher: Option<TFchr>,
 	select_chain: Option<TSc>,
 	pub (crate) import_queue: TImpQu,
@@ -192,6 +258,57 @@ type TFullParts<TBl, TRtApi, TExecDisp> = (
 	TaskManagerBuilder,
 );
 
+/// The fork blocks and the known bad blocks the chain spec declares in its extensions.
+///
+/// The clients refuse to import the blocks that contradict them.
+fn block_rules<TBl: BlockT>(
+	config: &Configuration,
+) -> (sc_client::ForkBlocks<TBl>, sc_client::BadBlocks<TBl>) {
+	let chain_spec = config.expect_chain_spec();
+	let fork_blocks = get_extension::<sc_client::ForkBlocks<TBl>>(chain_spec.extensions())
+		.cloned()
+		.unwrap_or_default();
+
+	let bad_blocks = get_extension::<sc_client::BadBlocks<TBl>>(chain_spec.extensions())
+		.cloned()
+		.unwrap_or_default();
+
+	(fork_blocks, bad_blocks)
+}
+
+/// Fails if the genesis block doesn't have the state root the chain spec expects, if any.
+fn check_genesis_state_root<TBl: BlockT>(
+	config: &Configuration,
+	genesis: Option<TBl::Header>,
+) -> Result<(), Error> {
+	let expected = match config.expect_chain_spec().genesis_state_root() {
+		Some(expected) => expected,
+		None => return Ok(()),
+	};
+	let genesis = genesis.ok_or("The genesis block is missing")?;
+	if genesis.state_root().as_ref() != expected {
+		return Err(Error::Other(format!(
+			"The genesis state root {:?} isn't the 0x{} one of the chain spec, \
+			the genesis of the spec has diverged from the one the root was computed from",
+			genesis.state_root(),
+			HexDisplay::from(&expected),
+		)));
+	}
+
+	Ok(())
+}
+
+/// The code substitutes of the chain spec, by block number.
+fn code_substitutes(config: &Configuration) -> Result<BTreeMap<u64, Vec<u8>>, Error> {
+	config.expect_chain_spec().code_substitutes().into_iter().map(|(number, code)| {
+		let number = number.parse().map_err(|_| Error::Other(format!(
+			"The code substitute block number `{}` of the chain spec isn't a number",
+			number,
+		)))?;
+		Ok((number, code))
+	}).collect()
+}
+
 /// Creates a new full client for the given config.
 pub fn new_full_client<TBl, TRtApi, TExecDisp>(
 	config: &Configuration,
@@ -209,7 +326,9 @@ fn new_full_parts<TBl, TRtApi, TExecDisp>(
 	TExecDisp: NativeExecutionDispatch + 'static,
 {
 	let keystore = match &config.keystore {
-		KeystoreConfig::Path { path, password } => Keystore::open(
+		KeystoreConfig::Path { path, password: Some(password), encrypt: true } =>
+			Keystore::open_encrypted(path.clone(), password.clone())?,
+		KeystoreConfig::Path { path, password, .. } => Keystore::open(
 			path.clone(),
 			password.clone()
 		)?,
@@ -225,27 +344,25 @@ fn new_full_parts<TBl, TRtApi, TExecDisp>(
 		config.max_runtime_instances,
 	);
 
-	let chain_spec = config.expect_chain_spec();
-	let fork_blocks = get_extension::<sc_client::ForkBlocks<TBl>>(chain_spec.extensions())
-		.cloned()
-		.unwrap_or_default();
-
-	let bad_blocks = get_extension::<sc_client::BadBlocks<TBl>>(chain_spec.extensions())
-		.cloned()
-		.unwrap_or_default();
+	let (fork_blocks, bad_blocks) = block_rules::<TBl>(config);
 
 	let (client, backend) = {
 		let db_config = sc_client_db::DatabaseSettings {
 			state_cache_size: config.state_cache_size,
 			state_cache_child_ratio:
 			config.state_cache_child_ratio.map(|v| (v, 100)),
+			trie_cache_size: config.trie_cache_size,
 			pruning: config.pruning.clone(),
+			keep_blocks: config.keep_blocks,
+			cht_size: config.cht_size,
 			source: match config.expect_database() {
 				DatabaseConfig::Path { path, cache_size } =>
 					sc_client_db::DatabaseSettingsSrc::Path {
 						path: path.clone(),
 						cache_size: cache_size.clone().map(|u| u as usize),
 					},
+				DatabaseConfig::ParityDb { path } =>
+					sc_client_db::DatabaseSettingsSrc::ParityDb { path: path.clone() },
 				DatabaseConfig::Custom(db) =>
 					sc_client_db::DatabaseSettingsSrc::Custom(db.clone()),
 			},
@@ -265,9 +382,12 @@ fn new_full_parts<TBl, TRtApi, TExecDisp>(
 			extensions,
 			Box::new(tasks_builder.spawn_handle()),
 			config.prometheus_config.as_ref().map(|config| config.registry.clone()),
+			code_substitutes(config)?,
 		)?
 	};
 
+	check_genesis_state_root::<TBl>(config, client.header(&BlockId::Number(Zero::zero()))?)?;
+
 	Ok((client, backend, keystore, tasks_builder))
 }
 
@@ -285,10 +405,14 @@ impl ServiceBuilder<(), (), (), (), (), (), (), (), (), (), ()> {
 		BoxFinalityProofRequestBuilder<TBl>,
 		Arc<dyn FinalityProofProvider<TBl>>,
 		(),
-		(),
+		NoopRpcExtensionBuilder<()>,
 		TFullBackend<TBl>,
 	>, Error> {
 		let (client, backend, keystore, tasks_builder) = new_full_parts(&config)?;
+		let signing_keystore: BareCryptoStorePtr = match &config.remote_signer {
+			Some(uri) => RemoteKeystore::open(uri, sc_keystore::remote::DEFAULT_TIMEOUT)?,
+			None => keystore.clone(),
+		};
 
 		let client = Arc::new(client);
 
@@ -297,6 +421,7 @@ impl ServiceBuilder<(), (), (), (), (), (), (), (), (), (), ()> {
 			client,
 			backend,
 			keystore,
+			signing_keystore,
 			tasks_builder,
 			fetcher: None,
 			select_chain: None,
@@ -304,7 +429,7 @@ impl ServiceBuilder<(), (), (), (), (), (), (), (), (), (), ()> {
 			finality_proof_request_builder: None,
 			finality_proof_provider: None,
 			transaction_pool: Arc::new(()),
-			rpc_extensions: Default::default(),
+			rpc_extensions: NoopRpcExtensionBuilder(()),
 			remote_backend: None,
 			background_tasks: Default::default(),
 			marker: PhantomData,
@@ -324,13 +449,15 @@ impl ServiceBuilder<(), (), (), (), (), (), (), (), (), (), ()> {
 		BoxFinalityProofRequestBuilder<TBl>,
 		Arc<dyn FinalityProofProvider<TBl>>,
 		(),
-		(),
+		NoopRpcExtensionBuilder<()>,
 		TLightBackend<TBl>,
 	>, Error> {
 		let tasks_builder = TaskManagerBuilder::new();
 
 		let keystore = match &config.keystore {
-			KeystoreConfig::Path { path, password } => Keystore::open(
+			KeystoreConfig::Path { path, password: Some(password), encrypt: true } =>
+				Keystore::open_encrypted(path.clone(), password.clone())?,
+			KeystoreConfig::Path { path, password, .. } => Keystore::open(
 				path.clone(),
 				password.clone()
 			)?,
@@ -349,13 +476,18 @@ impl ServiceBuilder<(), (), (), (), (), (), (), (), (), (), ()> {
 				state_cache_size: config.state_cache_size,
 				state_cache_child_ratio:
 					config.state_cache_child_ratio.map(|v| (v, 100)),
+				trie_cache_size: config.trie_cache_size,
 				pruning: config.pruning.clone(),
+				keep_blocks: config.keep_blocks,
+				cht_size: config.cht_size,
 				source: match config.expect_database() {
 					DatabaseConfig::Path { path, cache_size } =>
 						sc_client_db::DatabaseSettingsSrc::Path {
 							path: path.clone(),
 							cache_size: cache_size.clone().map(|u| u as usize),
 						},
+					DatabaseConfig::ParityDb { path } =>
+						sc_client_db::DatabaseSettingsSrc::ParityDb { path: path.clone() },
 					DatabaseConfig::Custom(db) =>
 						sc_client_db::DatabaseSettingsSrc::Custom(db.clone()),
 				},
@@ -368,24 +500,36 @@ impl ServiceBuilder<(), (), (), (), (), (), (), (), (), (), ()> {
 				light_blockchain.clone(),
 				executor.clone(),
 				Box::new(tasks_builder.spawn_handle()),
-			),
+				config.prometheus_config.as_ref().map(|config| config.registry.clone()),
+			).with_code_substitutes(code_substitutes(&config)?)?,
 		);
 		let fetcher = Arc::new(sc_network::config::OnDemand::new(fetch_checker));
 		let backend = sc_client::light::new_light_backend(light_blockchain);
 		let remote_blockchain = backend.remote_blockchain();
+		let (fork_blocks, bad_blocks) = block_rules::<TBl>(&config);
+		let checkpoint = get_extension::<sc_client::LightSyncCheckpoint<TBl>>(
+			config.expect_chain_spec().extensions(),
+		).cloned().unwrap_or_default();
 		let client = Arc::new(sc_client::light::new_light(
 			backend.clone(),
 			config.expect_chain_spec().as_storage_builder(),
+			fork_blocks,
+			bad_blocks,
+			checkpoint,
 			executor,
 			Box::new(tasks_builder.spawn_handle()),
 			config.prometheus_config.as_ref().map(|config| config.registry.clone()),
+			code_substitutes(&config)?,
 		)?);
+		check_genesis_state_root::<TBl>(&config, client.header(&BlockId::Number(Zero::zero()))?)?;
 
 		Ok(ServiceBuilder {
 			config,
 			client,
 			backend,
 			tasks_builder,
+			// light clients don't author nor vote.
+			signing_keystore: keystore.clone(),
 			keystore,
 			fetcher: Some(fetcher.clone()),
 			select_chain: None,
@@ -393,7 +537,7 @@ impl ServiceBuilder<(), (), (), (), (), (), (), (), (), (), ()> {
 			finality_proof_request_builder: None,
 			finality_proof_provider: None,
 			transaction_pool: Arc::new(()),
-			rpc_extensions: Default::default(),
+			rpc_extensions: NoopRpcExtensionBuilder(()),
 			remote_backend: Some(remote_blockchain),
 			background_tasks: Default::default(),
 			marker: PhantomData,
@@ -425,6 +569,12 @@ impl<TBl, TRtApi, TCl, TFchr, TSc, TImpQu, TFprb, TFpp, TExPool, TRpc, Backend>
 		self.keystore.clone()
 	}
 
+	/// Returns a reference to the keystore the consensus keys sign with, which is the remote
+	/// signer if one is configured and the keystore otherwise.
+	pub fn signing_keystore(&self) -> BareCryptoStorePtr {
+		self.signing_keystore.clone()
+	}
+
 	/// Returns a reference to the transaction pool stored in this builder
 	pub fn pool(&self) -> Arc<TExPool> {
 		self.transaction_pool.clone()
@@ -460,6 +610,7 @@ impl<TBl, TRtApi, TCl, TFchr, TSc, TImpQu, TFprb, TFpp, TExPool, TRpc, Backend>
 			backend: self.backend,
 			tasks_builder: self.tasks_builder,
 			keystore: self.keystore,
+			signing_keystore: self.signing_keystore,
 			fetcher: self.fetcher,
 			select_chain,
 			import_queue: self.import_queue,
@@ -503,6 +654,7 @@ impl<TBl, TRtApi, TCl, TFchr, TSc, TImpQu, TFprb, TFpp, TExPool, TRpc, Backend>
 			backend: self.backend,
 			tasks_builder: self.tasks_builder,
 			keystore: self.keystore,
+			signing_keystore: self.signing_keystore,
 			fetcher: self.fetcher,
 			select_chain: self.select_chain,
 			import_queue,
@@ -541,6 +693,7 @@ impl<TBl, TRtApi, TCl, TFchr, TSc, TImpQu, TFprb, TFpp, TExPool, TRpc, Backend>
 			backend: self.backend,
 			tasks_builder: self.tasks_builder,
 			keystore: self.keystore,
+			signing_keystore: self.signing_keystore,
 			fetcher: self.fetcher,
 			select_chain: self.select_chain,
 			import_queue: self.import_queue,
@@ -603,6 +756,7 @@ impl<TBl, TRtApi, TCl, TFchr, TSc, TImpQu, TFprb, TFpp, TExPool, TRpc, Backend>
 			backend: self.backend,
 			tasks_builder: self.tasks_builder,
 			keystore: self.keystore,
+			signing_keystore: self.signing_keystore,
 			fetcher: self.fetcher,
 			select_chain: self.select_chain,
 			import_queue,
@@ -637,12 +791,16 @@ impl<TBl, TRtApi, TCl, TFchr, TSc, TImpQu, TFprb, TFpp, TExPool, TRpc, Backend>
 	}
 
 	/// Defines which transaction pool to use.
+	///
+	/// The builder is given the Prometheus registry to register the metrics of the pool in, if
+	/// Prometheus is enabled.
 	pub fn with_transaction_pool<UExPool>(
 		mut self,
 		transaction_pool_builder: impl FnOnce(
 			sc_transaction_pool::txpool::Options,
 			Arc<TCl>,
 			Option<TFchr>,
+			Option<&Registry>,
 		) -> Result<(UExPool, Option<BackgroundTask>), Error>
 	) -> Result<ServiceBuilder<TBl, TRtApi, TCl, TFchr, TSc, TImpQu, TFprb, TFpp,
 		UExPool, TRpc, Backend>, Error>
@@ -651,6 +809,7 @@ impl<TBl, TRtApi, TCl, TFchr, TSc, TImpQu, TFprb, TFpp, TExPool, TRpc, Backend>
 			self.config.transaction_pool.clone(),
 			self.client.clone(),
 			self.fetcher.clone(),
+			self.config.prometheus_registry(),
 		)?;
 
 		if let Some(background_task) = background_task{
@@ -663,6 +822,7 @@ impl<TBl, TRtApi, TCl, TFchr, TSc, TImpQu, TFprb, TFpp, TExPool, TRpc, Backend>
 			tasks_builder: self.tasks_builder,
 			backend: self.backend,
 			keystore: self.keystore,
+			signing_keystore: self.signing_keystore,
 			fetcher: self.fetcher,
 			select_chain: self.select_chain,
 			import_queue: self.import_queue,
@@ -677,11 +837,30 @@ impl<TBl, TRtApi, TCl, TFchr, TSc, TImpQu, TFprb, TFpp, TExPool, TRpc, Backend>
 	}
 
 	/// Defines the RPC extensions to use.
+	///
+	/// The same extensions are exposed on every RPC server, see
+	/// [`with_rpc_extensions_builder`](ServiceBuilder::with_rpc_extensions_builder) to build them
+	/// for each server instead.
 	pub fn with_rpc_extensions<URpc>(
 		self,
 		rpc_ext_builder: impl FnOnce(&Self) -> Result<URpc, Error>,
 	) -> Result<ServiceBuilder<TBl, TRtApi, TCl, TFchr, TSc, TImpQu, TFprb, TFpp,
-		TExPool, URpc, Backend>, Error>
+		TExPool, NoopRpcExtensionBuilder<URpc>, Backend>, Error>
+	where TSc: Clone, TFchr: Clone, URpc: Clone + sc_rpc::RpcExtension<sc_rpc::Metadata> {
+		self.with_rpc_extensions_builder(|builder|
+			rpc_ext_builder(builder).map(NoopRpcExtensionBuilder)
+		)
+	}
+
+	/// Defines the builder of the RPC extensions to use.
+	///
+	/// The returned [`RpcExtensionBuilder`] is called for every RPC server, which allows
+	/// extensions to only expose their unsafe methods on servers that allow them.
+	pub fn with_rpc_extensions_builder<URpcBuilder: RpcExtensionBuilder>(
+		self,
+		rpc_ext_builder: impl FnOnce(&Self) -> Result<URpcBuilder, Error>,
+	) -> Result<ServiceBuilder<TBl, TRtApi, TCl, TFchr, TSc, TImpQu, TFprb, TFpp,
+		TExPool, URpcBuilder, Backend>, Error>
 	where TSc: Clone, TFchr: Clone {
 		let rpc_extensions = rpc_ext_builder(&self)?;
 
@@ -691,6 +870,7 @@ impl<TBl, TRtApi, TCl, TFchr, TSc, TImpQu, TFprb, TFpp, TExPool, TRpc, Backend>
 			backend: self.backend,
 			tasks_builder: self.tasks_builder,
 			keystore: self.keystore,
+			signing_keystore: self.signing_keystore,
 			fetcher: self.fetcher,
 			select_chain: self.select_chain,
 			import_queue: self.import_queue,
@@ -712,11 +892,12 @@ pub trait ServiceBuilderCommand {
 	type Block: BlockT;
 	/// Native execution dispatch required by some commands.
 	type NativeDispatch: NativeExecutionDispatch + 'static;
-	/// Starts the process of importing blocks.
+	/// Starts the process of importing blocks, either SCALE-encoded if `binary` or in JSON.
 	fn import_blocks(
 		self,
 		input: impl Read + Seek + Send + 'static,
 		force: bool,
+		binary: bool,
 	) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send>>;
 
 	/// Performs the blocks export.
@@ -734,11 +915,17 @@ pub trait ServiceBuilderCommand {
 		blocks: NumberFor<Self::Block>
 	) -> Result<(), Error>;
 
-	/// Re-validate known block.
+	/// Re-execute a known block on top of its parent's state, verifying its state root.
 	fn check_block(
 		self,
 		block: BlockId<Self::Block>
 	) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send>>;
+
+	/// Export the raw state at the given `block`, or at the best block if `None`.
+	fn export_raw_state(
+		&self,
+		block: Option<BlockId<Self::Block>>,
+	) -> Result<Storage, Error>;
 }
 
 impl<TBl, TRtApi, TBackend, TExec, TSc, TImpQu, TExPool, TRpc>
@@ -770,7 +957,7 @@ ServiceBuilder<
 	TSc: Clone,
 	TImpQu: 'static + ImportQueue<TBl>,
 	TExPool: MaintainedTransactionPool<Block=TBl, Hash = <TBl as BlockT>::Hash> + MallocSizeOfWasm + 'static,
-	TRpc: sc_rpc::RpcExtension<sc_rpc::Metadata> + Clone,
+	TRpc: RpcExtensionBuilder,
 {
 
 	/// Set an ExecutionExtensionsFactory
@@ -803,6 +990,7 @@ ServiceBuilder<
 			fetcher: on_demand,
 			backend,
 			keystore,
+			signing_keystore,
 			select_chain,
 			import_queue,
 			finality_proof_request_builder,
@@ -819,9 +1007,6 @@ ServiceBuilder<
 			config.dev_key_seed.clone().map(|s| vec![s]).unwrap_or_default(),
 		)?;
 
-		// A side-channel for essential tasks to communicate shutdown.
-		let (essential_failed_tx, essential_failed_rx) = mpsc::unbounded();
-
 		let import_queue = Box::new(import_queue);
 		let chain_info = client.chain_info();
 		let chain_spec = config.expect_chain_spec();
@@ -888,6 +1073,10 @@ ServiceBuilder<
 		let network_status_sinks = Arc::new(Mutex::new(status_sinks::StatusSinks::new()));
 
 		let offchain_storage = backend.offchain_storage();
+		// make the offchain database readable by the runtime calls.
+		if let Some(db) = offchain_storage.clone() {
+			client.execution_extensions().register_offchain_db(sc_offchain::OffchainDb::new(db));
+		}
 		let offchain_workers = match (config.offchain_worker, offchain_storage.clone()) {
 			(true, Some(db)) => {
 				Some(Arc::new(sc_offchain::OffchainWorkers::new(client.clone(), db)))
@@ -900,6 +1089,7 @@ ServiceBuilder<
 		};
 
 		let spawn_handle = tasks_builder.spawn_handle();
+		let spawn_essential_handle = tasks_builder.spawn_essential_handle();
 
 		// Spawn background tasks which were stacked during the
 		// service building.
@@ -913,17 +1103,23 @@ ServiceBuilder<
 			let offchain = offchain_workers.as_ref().map(Arc::downgrade);
 			let notifications_spawn_handle = tasks_builder.spawn_handle();
 			let network_state_info: Arc<dyn NetworkStateInfo + Send + Sync> = network.clone();
+			let network_ = network.clone();
 			let is_validator = config.roles.is_authority();
 
 			let (import_stream, finality_stream) = (
-				client.import_notification_stream().map(|n| ChainEvent::NewBlock {
+				client.import_notification_stream().map(move |n| ChainEvent::NewBlock {
 					id: BlockId::Hash(n.hash),
 					header: n.header,
 					retracted: n.retracted,
 					is_new_best: n.is_new_best,
+					is_major_syncing: network_.is_major_syncing(),
 				}),
-				client.finality_notification_stream().map(|n| ChainEvent::Finalized {
-					hash: n.hash
+				client.finality_notification_stream().flat_map(|n| {
+					// the pool is notified of each of the finalized blocks.
+					let finalized = n.tree_route.iter().cloned().chain(std::iter::once(n.hash))
+						.map(|hash| ChainEvent::Finalized { hash })
+						.collect::<Vec<_>>();
+					futures::stream::iter(finalized)
 				})
 			);
 			let events = futures::stream::select(import_stream, finality_stream)
@@ -1077,12 +1273,14 @@ ServiceBuilder<
 				metrics.memory_usage_bytes.set(memory);
 				metrics.cpu_usage_percentage.set(f64::from(cpu_usage));
 				metrics.ready_transactions_number.set(txpool_status.ready as u64);
+				metrics.banned_transactions_number.set(txpool_status.banned as u64);
 
 				metrics.network_per_sec_bytes.with_label_values(&["download"]).set(net_status.average_download_per_sec);
 				metrics.network_per_sec_bytes.with_label_values(&["upload"]).set(net_status.average_upload_per_sec);
 
 				metrics.block_height_number.with_label_values(&["finalized"]).set(finalized_number);
 				metrics.block_height_number.with_label_values(&["best"]).set(best_number);
+				metrics.finality_lag_blocks.set(best_number.saturating_sub(finalized_number));
 
 				if let Some(best_seen_block) = best_seen_block {
 					metrics.block_height_number.with_label_values(&["sync_target"]).set(best_seen_block);
@@ -1124,9 +1322,44 @@ ServiceBuilder<
 			tel_task_2,
 		);
 
+		// Health check endpoint.
+		if let Some(health_config) = config.health_config.clone() {
+			let status = crate::health::SharedHealthStatus::default();
+			let status_ = status.clone();
+			let client_ = client.clone();
+			let network_ = network.clone();
+			let (health_tx, health_rx) = mpsc::unbounded::<(NetworkStatus<_>, NetworkState)>();
+			network_status_sinks.lock().push(std::time::Duration::from_secs(1), health_tx);
+			let health_task = health_rx.for_each(move |(net_status, _)| {
+				let info = client_.usage_info();
+				*status_.write() = Some(crate::health::HealthStatus {
+					is_syncing: network_.is_major_syncing(),
+					peers: net_status.num_connected_peers,
+					best_number: info.chain.best_number.saturated_into::<u64>(),
+					finalized_number: info.chain.finalized_number.saturated_into::<u64>(),
+				});
+				ready(())
+			});
+			spawn_handle.spawn("health-status", health_task);
+
+			#[cfg(not(target_os = "unknown"))]
+			spawn_handle.spawn(
+				"health-endpoint",
+				crate::health::serve(health_config, status).map(|result| if let Err(e) = result {
+					error!("{}", e);
+				}),
+			);
+		}
+
 		// RPC
 		let (system_rpc_tx, system_rpc_rx) = mpsc::unbounded();
-		let gen_handler = || {
+		let rpc_metrics = config.prometheus_config.as_ref()
+			.map(|config| sc_rpc_server::RpcMetrics::register(&config.registry))
+			.transpose()?;
+		let gen_handler = |
+			deny_unsafe: sc_rpc::DenyUnsafe,
+			rpc_middleware: sc_rpc_server::RpcMiddleware,
+		| {
 			use sc_rpc::{chain, state, author, system, offchain};
 
 			let system_info = sc_rpc::system::SystemInfo {
@@ -1151,50 +1384,59 @@ ServiceBuilder<
 					client.clone(),
 					subscriptions.clone(),
 					remote_backend.clone(),
-					on_demand.clone()
+					on_demand.clone(),
+					deny_unsafe,
 				);
 				(chain, state)
 
 			} else {
 				// Full nodes
 				let chain = sc_rpc::chain::new_full(client.clone(), subscriptions.clone());
-				let state = sc_rpc::state::new_full(client.clone(), subscriptions.clone());
+				let state = sc_rpc::state::new_full(
+					client.clone(),
+					subscriptions.clone(),
+					deny_unsafe,
+				);
 				(chain, state)
 			};
 
 			let author = sc_rpc::author::Author::new(
 				client.clone(),
 				transaction_pool.clone(),
-				subscriptions,
+				subscriptions.clone(),
 				keystore.clone(),
+				deny_unsafe,
 			);
-			let system = system::System::new(system_info, system_rpc_tx.clone());
+			let system = system::System::new(system_info, system_rpc_tx.clone(), deny_unsafe);
 
 			match offchain_storage.clone() {
 				Some(storage) => {
-					let offchain = sc_rpc::offchain::Offchain::new(storage);
+					let offchain = sc_rpc::offchain::Offchain::new(storage, deny_unsafe);
 					sc_rpc_server::rpc_handler((
 						state::StateApi::to_delegate(state),
 						chain::ChainApi::to_delegate(chain),
 						offchain::OffchainApi::to_delegate(offchain),
 						author::AuthorApi::to_delegate(author),
 						system::SystemApi::to_delegate(system),
-						rpc_extensions.clone(),
-					))
+						rpc_extensions.build(deny_unsafe, subscriptions),
+					), rpc_middleware)
 				},
 				None => sc_rpc_server::rpc_handler((
 					state::StateApi::to_delegate(state),
 					chain::ChainApi::to_delegate(chain),
 					author::AuthorApi::to_delegate(author),
 					system::SystemApi::to_delegate(system),
-					rpc_extensions.clone(),
-				))
+					rpc_extensions.build(deny_unsafe, subscriptions),
+				), rpc_middleware)
 			}
 		};
-		let rpc_handlers = gen_handler();
-		let rpc = start_rpc_servers(&config, gen_handler)?;
+		let rpc_handlers = gen_handler(
+			sc_rpc::DenyUnsafe::No,
+			sc_rpc_server::RpcMiddleware::new(rpc_metrics.clone(), "in-memory"),
+		);
+		let rpc = start_rpc_servers(&config, gen_handler, rpc_metrics.as_ref())?;
 
-		spawn_handle.spawn(
+		spawn_essential_handle.spawn(
 			"network-worker",
 			build_network_future(
 				config.roles,
@@ -1267,19 +1509,21 @@ ServiceBuilder<
 
 		Ok(Service {
 			client,
-			task_manager: tasks_builder.into_task_manager(config.task_executor.ok_or(Error::TaskExecutorRequired)?),
+			task_manager: tasks_builder.into_task_manager(
+				config.task_executor.ok_or(Error::TaskExecutorRequired)?,
+				config.prometheus_config.as_ref().map(|config| &config.registry),
+			)?,
 			network,
 			network_status_sinks,
 			select_chain,
 			transaction_pool,
-			essential_failed_tx,
-			essential_failed_rx,
 			rpc_handlers,
 			_rpc: rpc,
 			_telemetry: telemetry,
 			_offchain_workers: offchain_workers,
 			_telemetry_on_connect_sinks: telemetry_connection_sinks.clone(),
 			keystore,
+			signing_keystore,
 			marker: PhantomData::<TBl>,
 			prometheus_registry: config.prometheus_config.map(|config| config.registry)
 		})
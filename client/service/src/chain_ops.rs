@@ -23,7 +23,7 @@ use sc_chain_spec::ChainSpec;
 use log::{warn, info};
 use futures::{future, prelude::*};
 use sp_runtime::traits::{
-	Block as BlockT, NumberFor, One, Zero, Header, SaturatedConversion
+	Block as BlockT, NumberFor, One, Zero, Header, SaturatedConversion, HashFor
 };
 use sp_runtime::generic::{BlockId, SignedBlock};
 use codec::{Decode, Encode, IoReader};
@@ -34,14 +34,129 @@ use sp_consensus::{
 };
 use sc_executor::{NativeExecutor, NativeExecutionDispatch};
 
-use std::{io::{Read, Write, Seek}, pin::Pin};
-use sc_client_api::BlockBackend;
+use sp_core::storage::{well_known_keys, ChildInfo, Storage, StorageChild, StorageKey};
+
+use std::{io::{Read, Write, Seek}, path::Path, pin::Pin, time::Instant};
+use sc_client_api::{BlockBackend, StorageProvider};
+
+/// Prefix of the keys of the default child tries.
+const DEFAULT_CHILD_STORAGE_KEY_PREFIX: &[u8] = b":child_storage:default:";
+
+/// Reads the blocks of an input, either SCALE-encoded or in JSON.
+///
+/// The SCALE-encoded input starts with the number of blocks it contains, while the JSON input is
+/// a stream of blocks whose length is only known once it's been read entirely.
+enum BlockIter<R: Read, B: BlockT> {
+	Binary {
+		num_expected_blocks: u64,
+		read_block_count: u64,
+		reader: IoReader<R>,
+	},
+	Json {
+		reader: serde_json::StreamDeserializer<'static, serde_json::de::IoRead<R>, SignedBlock<B>>,
+		read_block_count: u64,
+	},
+}
+
+impl<R: Read, B: BlockT + serde::de::DeserializeOwned> BlockIter<R, B> {
+	fn new(input: R, binary: bool) -> Result<Self, String> {
+		if binary {
+			let mut reader = IoReader(input);
+			let num_expected_blocks: u64 = Decode::decode(&mut reader)
+				.map_err(|e| format!("Error reading file: {}", e))?;
+			Ok(BlockIter::Binary { num_expected_blocks, read_block_count: 0, reader })
+		} else {
+			let reader = serde_json::Deserializer::from_reader(input).into_iter();
+			Ok(BlockIter::Json { reader, read_block_count: 0 })
+		}
+	}
+
+	/// The number of blocks read so far.
+	fn read_block_count(&self) -> u64 {
+		match self {
+			BlockIter::Binary { read_block_count, .. }
+			| BlockIter::Json { read_block_count, .. } => *read_block_count,
+		}
+	}
+
+	/// The number of blocks of the input, if known upfront.
+	fn num_expected_blocks(&self) -> Option<u64> {
+		match self {
+			BlockIter::Binary { num_expected_blocks, .. } => Some(*num_expected_blocks),
+			BlockIter::Json { .. } => None,
+		}
+	}
+}
+
+impl<R: Read, B: BlockT + serde::de::DeserializeOwned> Iterator for BlockIter<R, B> {
+	type Item = Result<SignedBlock<B>, String>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		match self {
+			BlockIter::Binary { num_expected_blocks, read_block_count, reader } => {
+				if read_block_count >= num_expected_blocks {
+					return None;
+				}
+
+				let block = SignedBlock::<B>::decode(reader)
+					.map_err(|e| {
+						format!("Error reading block data at {}: {}", read_block_count, e)
+					});
+				*read_block_count += 1;
+				Some(block)
+			},
+			BlockIter::Json { reader, read_block_count } => {
+				let block = reader.next()?
+					.map_err(|e| {
+						format!("Error reading block data at {}: {}", read_block_count, e)
+					});
+				*read_block_count += 1;
+				Some(block)
+			},
+		}
+	}
+}
 
 /// Build a chain spec json
 pub fn build_spec(spec: &dyn ChainSpec, raw: bool) -> error::Result<String> {
 	Ok(spec.as_json(raw)?)
 }
 
+/// Compute the state root of the genesis block built from the chain spec.
+///
+/// It is computed the way the client does when it initializes its database.
+pub fn genesis_state_root<B: BlockT>(spec: &dyn ChainSpec) -> error::Result<B::Hash> {
+	use sp_state_machine::Backend;
+
+	let storage = spec.as_storage_builder().build_storage()?;
+	let child_delta = storage.children.into_iter().map(|(storage_key, child_content)| (
+		storage_key,
+		child_content.data.into_iter().map(|(k, v)| (k, Some(v))),
+		child_content.child_info,
+	));
+	let (root, _) = sp_state_machine::InMemoryBackend::<HashFor<B>>::default().full_storage_root(
+		storage.top.into_iter().map(|(k, v)| (k, Some(v))),
+		child_delta,
+	);
+
+	Ok(root)
+}
+
+/// Copy the RocksDB database at `rocksdb_path` into a new ParityDB database at `paritydb_path`.
+pub fn migrate_to_parity_db(rocksdb_path: &Path, paritydb_path: &Path) -> error::Result<()> {
+	#[cfg(all(feature = "rocksdb", feature = "parity-db"))]
+	{
+		sc_client_db::migrate_from_rocksdb(rocksdb_path, paritydb_path)
+			.map_err(|e| format!("Failed to migrate the database to ParityDB: {}", e).into())
+	}
+
+	#[cfg(not(all(feature = "rocksdb", feature = "parity-db")))]
+	{
+		let _ = (rocksdb_path, paritydb_path);
+		Err("Migrating to ParityDB requires both the RocksDB and ParityDB backends".into())
+	}
+}
+
 impl<
 	TBl, TRtApi, TBackend,
 	TExecDisp, TFchr, TSc, TImpQu, TFprb, TFpp,
@@ -51,7 +166,7 @@ impl<
 	Client<TBackend, LocalCallExecutor<TBackend, NativeExecutor<TExecDisp>>, TBl, TRtApi>,
 	TFchr, TSc, TImpQu, TFprb, TFpp, TExPool, TRpc, Backend
 > where
-	TBl: BlockT,
+	TBl: BlockT + serde::de::DeserializeOwned,
 	TBackend: 'static + sc_client_api::backend::Backend<TBl> + Send,
 	TExecDisp: 'static + NativeExecutionDispatch,
 	TImpQu: 'static + ImportQueue<TBl>,
@@ -64,6 +179,7 @@ impl<
 		self,
 		input: impl Read + Seek + Send + 'static,
 		force: bool,
+		binary: bool,
 	) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send>> {
 		struct WaitLink {
 			imported_blocks: u64,
@@ -101,10 +217,17 @@ impl<
 		let client = self.client;
 		let mut queue = self.import_queue;
 
-		let mut io_reader_input = IoReader(input);
-		let mut count = None::<u64>;
-		let mut read_block_count = 0;
+		let mut blocks = match BlockIter::<_, TBl>::new(input, binary) {
+			Ok(blocks) => blocks,
+			Err(err) => return Box::pin(future::err(err.into())),
+		};
+		let mut reading_done = false;
 		let mut link = WaitLink::new();
+		let start = Instant::now();
+
+		if let Some(count) = blocks.num_expected_blocks() {
+			info!("Importing {} blocks", count);
+		}
 
 		// Importing blocks is implemented as a future, because we want the operation to be
 		// interruptible.
@@ -114,27 +237,10 @@ impl<
 		// This makes it possible either to interleave other operations in-between the block imports,
 		// or to stop the operation completely.
 		let import = future::poll_fn(move |cx| {
-			// Start by reading the number of blocks if not done so already.
-			let count = match count {
-				Some(c) => c,
-				None => {
-					let c: u64 = match Decode::decode(&mut io_reader_input) {
-						Ok(c) => c,
-						Err(err) => {
-							let err = format!("Error reading file: {}", err);
-							return std::task::Poll::Ready(Err(From::from(err)));
-						},
-					};
-					info!("Importing {} blocks", c);
-					count = Some(c);
-					c
-				}
-			};
-
 			// Read blocks from the input.
-			if read_block_count < count {
-				match SignedBlock::<Self::Block>::decode(&mut io_reader_input) {
-					Ok(signed) => {
+			if !reading_done {
+				match blocks.next() {
+					Some(Ok(signed)) => {
 						let (header, extrinsics) = signed.block.deconstruct();
 						let hash = header.hash();
 						// import queue handles verification and importing it into the client
@@ -149,16 +255,16 @@ impl<
 								import_existing: force,
 							}
 						]);
-					}
-					Err(e) => {
-						warn!("Error reading block data at {}: {}", read_block_count, e);
-						return std::task::Poll::Ready(Ok(()));
-					}
-				}
 
-				read_block_count += 1;
-				if read_block_count % 1000 == 0 {
-					info!("#{} blocks were added to the queue", read_block_count);
+						if blocks.read_block_count() % 1000 == 0 {
+							info!("#{} blocks were added to the queue", blocks.read_block_count());
+						}
+					},
+					Some(Err(e)) => {
+						warn!("{}", e);
+						return std::task::Poll::Ready(Ok(()));
+					},
+					None => reading_done = true,
 				}
 
 				cx.waker().wake_by_ref();
@@ -169,23 +275,29 @@ impl<
 			queue.poll_actions(cx, &mut link);
 
 			if link.has_error {
-				info!(
+				return std::task::Poll::Ready(Err(format!(
 					"Stopping after #{} blocks because of an error",
 					link.imported_blocks,
-				);
-				return std::task::Poll::Ready(Ok(()));
+				).into()));
 			}
 
 			if link.imported_blocks / 1000 != blocks_before / 1000 {
+				let elapsed = start.elapsed().as_secs_f64();
 				info!(
-					"#{} blocks were imported (#{} left)",
+					"#{} blocks were imported (#{} left, {:.1} blocks/s)",
 					link.imported_blocks,
-					count - link.imported_blocks
+					blocks.read_block_count().saturating_sub(link.imported_blocks),
+					link.imported_blocks as f64 / elapsed.max(1.0),
 				);
 			}
 
-			if link.imported_blocks >= count {
-				info!("Imported {} blocks. Best: #{}", read_block_count, client.chain_info().best_number);
+			if link.imported_blocks >= blocks.read_block_count() {
+				info!(
+					"Imported {} blocks in {}s. Best: #{}",
+					blocks.read_block_count(),
+					start.elapsed().as_secs(),
+					client.chain_info().best_number,
+				);
 				return std::task::Poll::Ready(Ok(()));
 
 			} else {
@@ -284,16 +396,68 @@ impl<
 		self,
 		block_id: BlockId<TBl>
 	) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send>> {
-		match self.client.block(&block_id) {
-			Ok(Some(block)) => {
-				let mut buf = Vec::new();
-				1u64.encode_to(&mut buf);
-				block.encode_to(&mut buf);
-				let reader = std::io::Cursor::new(buf);
-				self.import_blocks(reader, true)
+		let block = match self.client.block(&block_id) {
+			Ok(Some(block)) => block,
+			Ok(None) => return Box::pin(future::err("Unknown block".into())),
+			Err(e) => return Box::pin(future::err(format!("Error reading block: {:?}", e).into())),
+		};
+
+		let number = *block.block.header().number();
+		let hash = block.block.header().hash();
+		let state_root = *block.block.header().state_root();
+
+		// The block is imported again through the import queue, whose verifier strips the seal
+		// of the block before it is executed, and the client checks that the state root it
+		// computes matches the one of the header.
+		let mut buf = Vec::new();
+		1u64.encode_to(&mut buf);
+		block.encode_to(&mut buf);
+		let reader = std::io::Cursor::new(buf);
+		Box::pin(self.import_blocks(reader, true, true).map_ok(move |()| info!(
+			"Block #{} ({}) executed successfully, state root {}",
+			number,
+			hash,
+			state_root,
+		)))
+	}
+
+	fn export_raw_state(
+		&self,
+		block: Option<BlockId<TBl>>,
+	) -> Result<Storage, Error> {
+		let block = block.unwrap_or_else(|| BlockId::Hash(self.client.chain_info().best_hash));
+
+		let empty_key = StorageKey(Vec::new());
+		let mut storage = Storage::default();
+
+		for (key, value) in self.client.storage_pairs(&block, &empty_key)? {
+			if !well_known_keys::is_child_trie_key_valid(&key.0) {
+				storage.top.insert(key.0, value.0);
+				continue;
 			}
-			Ok(None) => Box::pin(future::err("Unknown block".into())),
-			Err(e) => Box::pin(future::err(format!("Error reading block: {:?}", e).into())),
+
+			// The root of a child trie is not part of the genesis, it is recomputed from the
+			// child storage. The unique id of a default child trie is the end of its key.
+			let unique_id = key.0[DEFAULT_CHILD_STORAGE_KEY_PREFIX.len()..].to_vec();
+			let child_info = ChildInfo::new_default(&unique_id);
+			let data = self.client.child_storage_keys(&block, &key, child_info, &empty_key)?
+				.into_iter()
+				.map(|child_key| {
+					let child_value = self.client.child_storage(
+						&block,
+						&key,
+						child_info,
+						&child_key,
+					)?;
+					Ok::<_, Error>(child_value.map(|v| (child_key.0, v.0)))
+				})
+				.filter_map(Result::transpose)
+				.collect::<Result<_, Error>>()?;
+
+			let child = StorageChild { data, child_info: child_info.to_owned() };
+			storage.children.insert(key.0, child);
 		}
+
+		Ok(storage)
 	}
 }
@@ -17,7 +17,7 @@
 //! Service configuration.
 
 pub use sc_client::ExecutionStrategies;
-pub use sc_client_db::{kvdb::KeyValueDB, PruningMode};
+pub use sc_client_db::{kvdb::KeyValueDB, PruningMode, KeepBlocks};
 pub use sc_network::config::{ExtTransport, NetworkConfiguration, Roles};
 pub use sc_executor::WasmExecutionMethod;
 
@@ -70,14 +70,23 @@ pub struct Configuration {
 	pub config_dir: Option<PathBuf>,
 	/// Configuration for the keystore.
 	pub keystore: KeystoreConfig,
+	/// URI of a remote signer holding the consensus keys, e.g. in front of an HSM. BABE and
+	/// GRANDPA sign through it instead of the keystore when given.
+	pub remote_signer: Option<String>,
 	/// Configuration for the database.
 	pub database: Option<DatabaseConfig>,
 	/// Size of internal state cache in Bytes
 	pub state_cache_size: usize,
 	/// Size in percent of cache size dedicated to child tries
 	pub state_cache_child_ratio: Option<usize>,
+	/// Size of the trie node cache in Bytes
+	pub trie_cache_size: usize,
 	/// Pruning settings.
 	pub pruning: PruningMode,
+	/// Block body pruning settings.
+	pub keep_blocks: KeepBlocks,
+	/// The size of the CHTs, which must be the same for all the nodes of the network.
+	pub cht_size: u32,
 	/// Chain configuration.
 	pub chain_spec: Option<Box<dyn ChainSpec>>,
 	/// Node name.
@@ -94,8 +103,18 @@ pub struct Configuration {
 	pub rpc_ws_max_connections: Option<usize>,
 	/// CORS settings for HTTP & WS servers. `None` if all origins are allowed.
 	pub rpc_cors: Option<Vec<String>>,
+	/// Maximum size of an RPC request or message, in megabytes. `None` if default.
+	pub rpc_max_payload: Option<usize>,
+	/// Maximum number of RPC calls per second a single connection can make. `None` if unlimited.
+	pub rpc_rate_limit: Option<u32>,
+	/// Whether HTTP requests are rate limited per client address, as forwarded by a reverse proxy.
+	pub rpc_rate_limit_trust_proxy_headers: bool,
+	/// Which RPC methods are exposed by the HTTP & WS servers.
+	pub rpc_methods: RpcMethods,
 	/// Prometheus endpoint configuration. `None` if disabled.
 	pub prometheus_config: Option<PrometheusConfig>,
+	/// Health check endpoint configuration. `None` if disabled.
+	pub health_config: Option<HealthConfig>,
 	/// Telemetry service URL. `None` if disabled.
 	pub telemetry_endpoints: Option<TelemetryEndpoints>,
 	/// External WASM transport for the telemetry. If `Some`, when connection to a telemetry
@@ -111,8 +130,13 @@ pub struct Configuration {
 	pub sentry_mode: bool,
 	/// Enable authoring even when offline.
 	pub force_authoring: bool,
+	/// Number of unfinalized blocks after which block authoring is slowed down.
+	pub max_unfinalized_blocks: Option<u32>,
 	/// Disable GRANDPA when running in validator mode
 	pub disable_grandpa: bool,
+	/// Number of blocks the best block can be ahead of the last finalized block before the
+	/// GRANDPA voter warns about a finality stall.
+	pub grandpa_stall_threshold: Option<u32>,
 	/// Development key seed.
 	///
 	/// When running in development mode, the seed will be used to generate authority keys by the keystore.
@@ -127,6 +151,19 @@ pub struct Configuration {
 	///
 	/// The default value is 8.
 	pub max_runtime_instances: usize,
+	/// Format of the status lines regularly printed by the node.
+	pub informant_output_format: InformantOutputFormat,
+}
+
+/// Format of the status lines regularly printed by the informant of the node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InformantOutputFormat {
+	/// Human readable lines, with colors.
+	Coloured,
+	/// Human readable lines, without colors.
+	Plain,
+	/// Lines of `key=value` pairs, to be parsed by log aggregators.
+	Machine,
 }
 
 /// Configuration of the client keystore.
@@ -139,7 +176,9 @@ pub enum KeystoreConfig {
 		/// The path of the keystore.
 		path: PathBuf,
 		/// Node keystore's password.
-		password: Option<Protected<String>>
+		password: Option<Protected<String>>,
+		/// Whether the key files written to the keystore are encrypted with its password.
+		encrypt: bool,
 	},
 	/// In-memory keystore. Recommended for in-browser nodes.
 	InMemory,
@@ -166,10 +205,44 @@ pub enum DatabaseConfig {
 		cache_size: Option<u32>,
 	},
 
+	/// ParityDB database at a specific path.
+	ParityDb {
+		/// Path to the database.
+		path: PathBuf,
+	},
+
 	/// A custom implementation of an already-open database.
 	Custom(Arc<dyn KeyValueDB>),
 }
 
+impl DatabaseConfig {
+	/// Returns the path of the database, if it is stored on disk.
+	pub fn path(&self) -> Option<&Path> {
+		match self {
+			DatabaseConfig::Path { path, .. } | DatabaseConfig::ParityDb { path } => Some(path),
+			DatabaseConfig::Custom(_) => None,
+		}
+	}
+}
+
+/// Available RPC methods.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RpcMethods {
+	/// Expose every RPC method only when RPC is listening on `localhost`,
+	/// otherwise serve only safe RPC methods.
+	Auto,
+	/// Allow only a safe subset of RPC methods.
+	Safe,
+	/// Expose every RPC method (even potentially unsafe ones).
+	Unsafe,
+}
+
+impl Default for RpcMethods {
+	fn default() -> RpcMethods {
+		RpcMethods::Auto
+	}
+}
+
 /// Configuration of the Prometheus endpoint.
 #[derive(Clone)]
 pub struct PrometheusConfig {
@@ -192,6 +265,30 @@ impl PrometheusConfig {
 	}
 }
 
+/// Configuration of the health check endpoint.
+#[derive(Debug, Clone)]
+pub struct HealthConfig {
+	/// Address to listen on.
+	pub address: SocketAddr,
+	/// Minimum number of peers the node must be connected to for it to be ready.
+	pub min_peers: usize,
+	/// Maximum number of blocks between the best and the finalized block for the node to be
+	/// ready.
+	pub max_finality_lag: u64,
+}
+
+impl HealthConfig {
+	/// Create a new config with the default thresholds: at least one peer and at most 32 blocks
+	/// not finalized yet.
+	pub fn new(address: SocketAddr) -> Self {
+		HealthConfig {
+			address,
+			min_peers: 1,
+			max_finality_lag: 32,
+		}
+	}
+}
+
 impl Default for Configuration {
 	/// Create a default config
 	fn default() -> Self {
@@ -207,28 +304,40 @@ impl Default for Configuration {
 			transaction_pool: Default::default(),
 			network: Default::default(),
 			keystore: KeystoreConfig::None,
+			remote_signer: None,
 			database: None,
 			state_cache_size: Default::default(),
 			state_cache_child_ratio: Default::default(),
+			trie_cache_size: Default::default(),
 			pruning: PruningMode::default(),
+			keep_blocks: KeepBlocks::default(),
+			cht_size: sc_client::cht::size(),
 			wasm_method: WasmExecutionMethod::Interpreted,
 			execution_strategies: Default::default(),
 			rpc_http: None,
 			rpc_ws: None,
 			rpc_ws_max_connections: None,
 			rpc_cors: Some(vec![]),
+			rpc_max_payload: None,
+			rpc_rate_limit: None,
+			rpc_rate_limit_trust_proxy_headers: false,
+			rpc_methods: Default::default(),
 			prometheus_config: None,
+			health_config: None,
 			telemetry_endpoints: None,
 			telemetry_external_transport: None,
 			default_heap_pages: None,
 			offchain_worker: Default::default(),
 			sentry_mode: false,
 			force_authoring: false,
+			max_unfinalized_blocks: None,
 			disable_grandpa: false,
+			grandpa_stall_threshold: None,
 			dev_key_seed: None,
 			tracing_targets: Default::default(),
 			tracing_receiver: Default::default(),
 			max_runtime_instances: 8,
+			informant_output_format: InformantOutputFormat::Coloured,
 		}
 	}
 }
@@ -254,6 +363,11 @@ impl Configuration {
 		format!("{}/v{}", self.impl_name, self.full_version())
 	}
 
+	/// The registry the metrics of the subsystems are registered in, if Prometheus is enabled.
+	pub fn prometheus_registry(&self) -> Option<&Registry> {
+		self.prometheus_config.as_ref().map(|config| &config.registry)
+	}
+
 	/// Generate a PathBuf to sub in the chain configuration directory
 	/// if given
 	pub fn in_chain_config_dir(&self, sub: &str) -> Option<PathBuf> {
@@ -0,0 +1,160 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! HTTP endpoint reporting the health of the node, for the load balancers in front of it.
+//!
+//! `GET /health` answers `200 OK` with the status of the node as a JSON object. `GET /ready`
+//! answers `200 OK` with the same object if the node is ready to serve requests, and
+//! `503 Service Unavailable` with the reasons it isn't otherwise: it is syncing, it has too few
+//! peers or its finalized block lags too far behind its best block.
+
+use std::sync::Arc;
+use parking_lot::RwLock;
+use serde_json::json;
+
+use crate::config::HealthConfig;
+
+/// Status of the node, as reported by the health check endpoint.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct HealthStatus {
+	/// Whether the node is doing a major sync.
+	pub(crate) is_syncing: bool,
+	/// Number of peers the node is connected to.
+	pub(crate) peers: usize,
+	/// Number of the best block.
+	pub(crate) best_number: u64,
+	/// Number of the finalized block.
+	pub(crate) finalized_number: u64,
+}
+
+/// The status of the node, shared with the endpoint. `None` until it is first known.
+pub(crate) type SharedHealthStatus = Arc<RwLock<Option<HealthStatus>>>;
+
+impl HealthStatus {
+	/// Returns the reasons the node isn't ready to serve requests, empty if it is.
+	fn unready_reasons(&self, config: &HealthConfig) -> Vec<String> {
+		let mut reasons = Vec::new();
+		if self.is_syncing {
+			reasons.push("the node is syncing".to_string());
+		}
+		if self.peers < config.min_peers {
+			reasons.push(format!("{} peers, expected at least {}", self.peers, config.min_peers));
+		}
+		if self.finality_lag() > config.max_finality_lag {
+			reasons.push(format!(
+				"finality lags {} blocks behind, expected at most {}",
+				self.finality_lag(),
+				config.max_finality_lag,
+			));
+		}
+
+		reasons
+	}
+
+	fn finality_lag(&self) -> u64 {
+		self.best_number.saturating_sub(self.finalized_number)
+	}
+}
+
+/// Returns the status code and the body of the answer to a request for the given path.
+fn respond(path: &str, config: &HealthConfig, status: Option<&HealthStatus>) -> (u16, String) {
+	let ready = match path {
+		"/health" => false,
+		"/ready" => true,
+		_ => return (404, "Not found.".into()),
+	};
+
+	let status = match status {
+		Some(status) => status,
+		None => return (503, json!({ "reasons": ["the node is starting"] }).to_string()),
+	};
+
+	let reasons = status.unready_reasons(config);
+	let body = json!({
+		"isSyncing": status.is_syncing,
+		"peers": status.peers,
+		"bestNumber": status.best_number,
+		"finalizedNumber": status.finalized_number,
+		"finalityLag": status.finality_lag(),
+		"ready": reasons.is_empty(),
+		"reasons": reasons,
+	});
+
+	let code = if ready && !reasons.is_empty() { 503 } else { 200 };
+	(code, body.to_string())
+}
+
+/// Serves the health check endpoint until an error happens.
+#[cfg(not(target_os = "unknown"))]
+pub(crate) async fn serve(config: HealthConfig, status: SharedHealthStatus) -> Result<(), String> {
+	use hyper::{Body, Response, Server, service::{make_service_fn, service_fn}};
+
+	let address = config.address;
+	let config = Arc::new(config);
+	let service = make_service_fn(move |_| {
+		let config = config.clone();
+		let status = status.clone();
+		async move {
+			Ok::<_, hyper::Error>(service_fn(move |request: hyper::Request<Body>| {
+				let (code, body) = respond(request.uri().path(), &config, status.read().as_ref());
+				let response = Response::builder()
+					.status(code)
+					.header("Content-Type", "application/json")
+					.body(Body::from(body));
+				async move { response }
+			}))
+		}
+	});
+
+	let server = Server::try_bind(&address)
+		.map_err(|e| format!("Failed to listen on {} for the health checks: {}", address, e))?;
+	log::info!("Health check endpoint started at {}", address);
+	server.serve(service).await.map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn config() -> HealthConfig {
+		HealthConfig::new("127.0.0.1:9616".parse().unwrap())
+	}
+
+	#[test]
+	fn node_is_ready_once_synced_with_peers_and_finality() {
+		let synced = HealthStatus {
+			is_syncing: false,
+			peers: 3,
+			best_number: 100,
+			finalized_number: 98,
+		};
+		assert_eq!(respond("/ready", &config(), Some(&synced)).0, 200);
+		assert_eq!(respond("/ready", &config(), None).0, 503);
+		assert_eq!(respond("/metrics", &config(), Some(&synced)).0, 404);
+
+		let syncing = HealthStatus { is_syncing: true, ..synced.clone() };
+		let lonely = HealthStatus { peers: 0, ..synced.clone() };
+		let lagging = HealthStatus { finalized_number: 50, ..synced.clone() };
+		for status in &[syncing, lonely, lagging] {
+			assert_eq!(respond("/health", &config(), Some(status)).0, 200);
+			let (code, body) = respond("/ready", &config(), Some(status));
+			assert_eq!(code, 503);
+			let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+			assert_eq!(body["ready"], false);
+			assert_eq!(body["reasons"].as_array().unwrap().len(), 1);
+		}
+	}
+}
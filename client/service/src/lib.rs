@@ -25,6 +25,7 @@ pub mod chain_ops;
 pub mod error;
 
 mod builder;
+mod health;
 mod status_sinks;
 mod task_manager;
 
@@ -42,23 +43,23 @@ use futures::{
 	Future, FutureExt, Stream, StreamExt,
 	channel::mpsc,
 	compat::*,
-	sink::SinkExt,
 	task::{Spawn, FutureObj, SpawnError},
 };
 use sc_network::{NetworkService, network_state::NetworkState, PeerId, ReportHandle};
-use log::{log, warn, debug, error, Level};
+use log::{log, warn, debug, Level};
 use codec::{Encode, Decode};
 use sp_runtime::generic::BlockId;
 use sp_runtime::traits::{NumberFor, Block as BlockT};
+use sp_blockchain::HeaderBackend;
 use parity_util_mem::MallocSizeOf;
 
 pub use self::error::Error;
 pub use self::builder::{
 	new_full_client,
 	ServiceBuilder, ServiceBuilderCommand, TFullClient, TLightClient, TFullBackend, TLightBackend,
-	TFullCallExecutor, TLightCallExecutor,
+	TFullCallExecutor, TLightCallExecutor, RpcExtensionBuilder, NoopRpcExtensionBuilder,
 };
-pub use config::{Configuration, Roles, PruningMode};
+pub use config::{Configuration, Roles, PruningMode, KeepBlocks, RpcMethods};
 pub use sc_chain_spec::{
 	ChainSpec, GenericChainSpec, Properties, RuntimeGenesis, Extension as ChainSpecExtension
 };
@@ -71,7 +72,7 @@ pub use sc_executor::NativeExecutionDispatch;
 pub use std::{ops::Deref, result::Result, sync::Arc};
 #[doc(hidden)]
 pub use sc_network::config::{FinalityProofProvider, OnDemand, BoxFinalityProofRequestBuilder};
-pub use task_manager::{TaskManagerBuilder, SpawnTaskHandle};
+pub use task_manager::{TaskManagerBuilder, SpawnTaskHandle, SpawnEssentialTaskHandle};
 use task_manager::TaskManager;
 
 const DEFAULT_PROTOCOL_ID: &str = "sup";
@@ -96,17 +97,13 @@ pub struct Service<TBl, TCl, TSc, TNetStatus, TNet, TTxPool, TOc> {
 	/// For each element, every time the `Interval` fires we push an element on the sender.
 	network_status_sinks: Arc<Mutex<status_sinks::StatusSinks<(TNetStatus, NetworkState)>>>,
 	transaction_pool: Arc<TTxPool>,
-	/// Send a signal when a spawned essential task has concluded. The next time
-	/// the service future is polled it should complete with an error.
-	essential_failed_tx: mpsc::UnboundedSender<()>,
-	/// A receiver for spawned essential-tasks concluding.
-	essential_failed_rx: mpsc::UnboundedReceiver<()>,
 	rpc_handlers: sc_rpc_server::RpcHandler<sc_rpc::Metadata>,
 	_rpc: Box<dyn std::any::Any + Send + Sync>,
 	_telemetry: Option<sc_telemetry::Telemetry>,
 	_telemetry_on_connect_sinks: Arc<Mutex<Vec<futures::channel::mpsc::UnboundedSender<()>>>>,
 	_offchain_workers: Option<Arc<TOc>>,
 	keystore: sc_keystore::KeyStorePtr,
+	signing_keystore: sp_core::traits::BareCryptoStorePtr,
 	marker: PhantomData<TBl>,
 	prometheus_registry: Option<prometheus_endpoint::Registry>,
 }
@@ -146,9 +143,16 @@ pub trait AbstractService: 'static + Future<Output = Result<(), Error>> +
 	/// Returns a handle for spawning tasks.
 	fn spawn_task_handle(&self) -> SpawnTaskHandle;
 
+	/// Returns a handle for spawning essential tasks.
+	fn spawn_essential_task_handle(&self) -> SpawnEssentialTaskHandle;
+
 	/// Returns the keystore that stores keys.
 	fn keystore(&self) -> sc_keystore::KeyStorePtr;
 
+	/// Returns the keystore the consensus keys sign with, which is the remote signer if one is
+	/// configured and the keystore otherwise.
+	fn signing_keystore(&self) -> sp_core::traits::BareCryptoStorePtr;
+
 	/// Starts an RPC query.
 	///
 	/// The query is passed as a string and must be a JSON text similar to what an HTTP client
@@ -217,26 +221,26 @@ where
 		self.keystore.clone()
 	}
 
+	fn signing_keystore(&self) -> sp_core::traits::BareCryptoStorePtr {
+		self.signing_keystore.clone()
+	}
+
 	fn spawn_task(&self, name: impl Into<Cow<'static, str>>, task: impl Future<Output = ()> + Send + 'static) {
 		self.task_manager.spawn(name, task)
 	}
 
 	fn spawn_essential_task(&self, name: impl Into<Cow<'static, str>>, task: impl Future<Output = ()> + Send + 'static) {
-		let mut essential_failed = self.essential_failed_tx.clone();
-		let essential_task = std::panic::AssertUnwindSafe(task)
-			.catch_unwind()
-			.map(move |_| {
-				error!("Essential task failed. Shutting down service.");
-				let _ = essential_failed.send(());
-			});
-
-		let _ = self.spawn_task(name, essential_task);
+		self.task_manager.spawn_essential(name, task)
 	}
 
 	fn spawn_task_handle(&self) -> SpawnTaskHandle {
 		self.task_manager.spawn_handle()
 	}
 
+	fn spawn_essential_task_handle(&self) -> SpawnEssentialTaskHandle {
+		self.task_manager.spawn_essential_handle()
+	}
+
 	fn rpc_query(&self, mem: &RpcSession, request: &str) -> Pin<Box<dyn Future<Output = Option<String>> + Send>> {
 		Box::pin(
 			self.rpc_handlers.handle_request(request, mem.metadata.clone())
@@ -286,13 +290,10 @@ impl<TBl, TCl, TSc, TNetStatus, TNet, TTxPool, TOc> Future for
 	fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
 		let this = Pin::into_inner(self);
 
-		match Pin::new(&mut this.essential_failed_rx).poll_next(cx) {
-			Poll::Pending => {},
-			Poll::Ready(_) => {
-				// Ready(None) should not be possible since we hold a live
-				// sender.
-				return Poll::Ready(Err(Error::Other("Essential task failed.".into())));
-			}
+		if let Poll::Ready(()) = this.task_manager.poll_essential_task_failure(cx) {
+			// Terminate the other tasks right away rather than once the service is dropped.
+			this.task_manager.terminate();
+			return Poll::Ready(Err(Error::Other("Essential task failed.".into())));
 		}
 
 		this.task_manager.process_receiver(cx);
@@ -319,7 +320,7 @@ impl<TBl, TCl, TSc, TNetStatus, TNet, TTxPool, TOc> Spawn for
 /// The `status_sink` contain a list of senders to send a periodic network status to.
 fn build_network_future<
 	B: BlockT,
-	C: sc_client::BlockchainEvents<B>,
+	C: sc_client::BlockchainEvents<B> + HeaderBackend<B>,
 	H: sc_network::ExHashT
 > (
 	roles: Roles,
@@ -331,6 +332,7 @@ fn build_network_future<
 ) -> impl Future<Output = ()> {
 	let mut imported_blocks_stream = client.import_notification_stream().fuse();
 	let mut finality_notification_stream = client.finality_notification_stream().fuse();
+	let starting_block = client.info().best_number;
 
 	futures::future::poll_fn(move |cx| {
 		let before_polling = Instant::now();
@@ -406,6 +408,13 @@ fn build_network_future<
 
 					let _ = sender.send(node_roles);
 				}
+				sc_rpc::system::Request::SyncState(sender) => {
+					let _ = sender.send(sc_rpc::system::SyncState {
+						starting_block,
+						current_block: client.info().best_number,
+						highest_block: network.best_seen_block(),
+					});
+				}
 			};
 		}
 
@@ -489,9 +498,15 @@ mod waiting {
 
 /// Starts RPC servers that run in their own thread, and returns an opaque object that keeps them alive.
 #[cfg(not(target_os = "unknown"))]
-fn start_rpc_servers<H: FnMut() -> sc_rpc_server::RpcHandler<sc_rpc::Metadata>>(
+fn start_rpc_servers<
+	H: FnMut(
+		sc_rpc::DenyUnsafe,
+		sc_rpc_server::RpcMiddleware,
+	) -> sc_rpc_server::RpcHandler<sc_rpc::Metadata>
+>(
 	config: &Configuration,
-	mut gen_handler: H
+	mut gen_handler: H,
+	rpc_metrics: Option<&sc_rpc_server::RpcMetrics>,
 ) -> Result<Box<dyn std::any::Any + Send + Sync>, error::Error> {
 	fn maybe_start_server<T, F>(address: Option<SocketAddr>, mut start: F) -> Result<Option<T>, io::Error>
 		where F: FnMut(&SocketAddr) -> Result<T, io::Error>,
@@ -511,10 +526,33 @@ fn start_rpc_servers<H: FnMut() -> sc_rpc_server::RpcHandler<sc_rpc::Metadata>>(
 		})
 	}
 
+	fn deny_unsafe(addr: &SocketAddr, methods: &config::RpcMethods) -> sc_rpc::DenyUnsafe {
+		let is_exposed_addr = !addr.ip().is_loopback();
+		match (is_exposed_addr, methods) {
+			| (_, config::RpcMethods::Unsafe)
+			| (false, config::RpcMethods::Auto) => sc_rpc::DenyUnsafe::No,
+			_ => sc_rpc::DenyUnsafe::Yes,
+		}
+	}
+
+	let rpc_middleware = |transport_label| {
+		sc_rpc_server::RpcMiddleware::new(rpc_metrics.cloned(), transport_label)
+			.with_rate_limit(config.rpc_rate_limit)
+	};
+
 	Ok(Box::new((
 		maybe_start_server(
 			config.rpc_http,
-			|address| sc_rpc_server::start_http(address, config.rpc_cors.as_ref(), gen_handler()),
+			|address| sc_rpc_server::start_http(
+				address,
+				config.rpc_cors.as_ref(),
+				config.rpc_max_payload,
+				config.rpc_rate_limit_trust_proxy_headers,
+				gen_handler(
+					deny_unsafe(address, &config.rpc_methods),
+					rpc_middleware("http"),
+				),
+			),
 		)?.map(|s| waiting::HttpServer(Some(s))),
 		maybe_start_server(
 			config.rpc_ws,
@@ -522,7 +560,11 @@ fn start_rpc_servers<H: FnMut() -> sc_rpc_server::RpcHandler<sc_rpc::Metadata>>(
 				address,
 				config.rpc_ws_max_connections,
 				config.rpc_cors.as_ref(),
-				gen_handler(),
+				config.rpc_max_payload,
+				gen_handler(
+					deny_unsafe(address, &config.rpc_methods),
+					rpc_middleware("ws"),
+				),
 			),
 		)?.map(|s| waiting::WsServer(Some(s))).map(Mutex::new),
 	)))
@@ -530,9 +572,15 @@ fn start_rpc_servers<H: FnMut() -> sc_rpc_server::RpcHandler<sc_rpc::Metadata>>(
 
 /// Starts RPC servers that run in their own thread, and returns an opaque object that keeps them alive.
 #[cfg(target_os = "unknown")]
-fn start_rpc_servers<H: FnMut() -> sc_rpc_server::RpcHandler<sc_rpc::Metadata>>(
+fn start_rpc_servers<
+	H: FnMut(
+		sc_rpc::DenyUnsafe,
+		sc_rpc_server::RpcMiddleware,
+	) -> sc_rpc_server::RpcHandler<sc_rpc::Metadata>
+>(
 	_: &Configuration,
-	_: H
+	_: H,
+	_: Option<&sc_rpc_server::RpcMetrics>,
 ) -> Result<Box<dyn std::any::Any + Send + Sync>, error::Error> {
 	Ok(Box::new(()))
 }
@@ -621,7 +669,11 @@ where
 		match Decode::decode(&mut &encoded[..]) {
 			Ok(uxt) => {
 				let best_block_id = BlockId::hash(self.client.info().best_hash);
-				let import_future = self.pool.submit_one(&best_block_id, uxt);
+				let import_future = self.pool.submit_one(
+					&best_block_id,
+					sp_transaction_pool::TransactionSource::External,
+					uxt,
+				);
 				let import_future = import_future
 					.map(move |import_result| {
 						match import_result {
@@ -669,6 +721,7 @@ mod tests {
 		let pool = Arc::new(BasicPool::new(
 			Default::default(),
 			Arc::new(FullChainApi::new(client.clone())),
+			None,
 		).0);
 		let best = longest_chain.best_chain().unwrap();
 		let transaction = Transfer {
@@ -677,8 +730,12 @@ mod tests {
 			from: AccountKeyring::Alice.into(),
 			to: Default::default(),
 		}.into_signed_tx();
-		block_on(pool.submit_one(&BlockId::hash(best.hash()), transaction.clone())).unwrap();
-		block_on(pool.submit_one(&BlockId::hash(best.hash()), Extrinsic::IncludeData(vec![1]))).unwrap();
+		let source = sp_transaction_pool::TransactionSource::External;
+		block_on(pool.submit_one(&BlockId::hash(best.hash()), source, transaction.clone()))
+			.unwrap();
+		block_on(
+			pool.submit_one(&BlockId::hash(best.hash()), source, Extrinsic::IncludeData(vec![1]))
+		).unwrap();
 		assert_eq!(pool.status().ready, 2);
 
 		// when
@@ -12,11 +12,15 @@
 // GNU General Public License for more details.
 
 //! Substrate service tasks management module.
+//!
+//! The tasks are either essential or not. The service exits, terminating all the other tasks,
+//! once an essential task ends or panics, while the panic of a non-essential task is only logged.
 
 use std::{
 	result::Result, sync::Arc,
 	task::{Poll, Context},
 	borrow::Cow, pin::Pin,
+	panic::AssertUnwindSafe,
 };
 use exit_future::Signal;
 use log::{debug, error};
@@ -26,6 +30,10 @@ use futures::{
 	compat::*,
 	task::{Spawn, FutureObj, SpawnError},
 };
+use prometheus_endpoint::{
+	register, CounterVec, Histogram, HistogramOpts, HistogramVec, Opts, PrometheusError,
+	Registry, U64,
+};
 use sc_client_api::CloneableSpawn;
 
 /// Type alias for service task executor (usually runtime).
@@ -45,6 +53,10 @@ pub struct TaskManagerBuilder {
 	to_spawn_tx: TaskScheduler,
 	/// Receiver for futures that must be spawned as background tasks.
 	to_spawn_rx: mpsc::UnboundedReceiver<(Pin<Box<dyn Future<Output = ()> + Send>>, Cow<'static, str>)>,
+	/// Sender of a signal when an essential task has concluded.
+	essential_failed_tx: mpsc::UnboundedSender<()>,
+	/// Receiver of the signals the essential tasks send when they conclude.
+	essential_failed_rx: mpsc::UnboundedReceiver<()>,
 }
 
 impl TaskManagerBuilder {
@@ -52,11 +64,14 @@ impl TaskManagerBuilder {
 	pub fn new() -> Self {
 		let (signal, on_exit) = exit_future::signal();
 		let (to_spawn_tx, to_spawn_rx) = mpsc::unbounded();
+		let (essential_failed_tx, essential_failed_rx) = mpsc::unbounded();
 		Self {
 			on_exit,
 			signal: Some(signal),
 			to_spawn_tx,
 			to_spawn_rx,
+			essential_failed_tx,
+			essential_failed_rx,
 		}
 	}
 
@@ -71,21 +86,42 @@ impl TaskManagerBuilder {
 		}
 	}
 
+	/// Get spawn handle of essential tasks.
+	///
+	/// The service exits once a task spawned through this handle ends.
+	pub fn spawn_essential_handle(&self) -> SpawnEssentialTaskHandle {
+		SpawnEssentialTaskHandle {
+			essential_failed_tx: self.essential_failed_tx.clone(),
+			inner: self.spawn_handle(),
+		}
+	}
+
 	/// Convert into actual task manager from initial setup.
-	pub(crate) fn into_task_manager(self, executor: ServiceTaskExecutor) -> TaskManager {
+	///
+	/// The metrics of the tasks are registered in the registry, if any.
+	pub(crate) fn into_task_manager(
+		self,
+		executor: ServiceTaskExecutor,
+		prometheus_registry: Option<&Registry>,
+	) -> Result<TaskManager, PrometheusError> {
 		let TaskManagerBuilder {
 			on_exit,
 			signal,
 			to_spawn_rx,
-			to_spawn_tx
+			to_spawn_tx,
+			essential_failed_tx,
+			essential_failed_rx,
 		} = self;
-		TaskManager {
+		Ok(TaskManager {
 			on_exit,
 			signal,
 			to_spawn_tx,
 			to_spawn_rx,
+			essential_failed_tx,
+			essential_failed_rx,
 			executor,
-		}
+			metrics: prometheus_registry.map(Metrics::register).transpose()?,
+		})
 	}
 }
 
@@ -110,6 +146,36 @@ impl SpawnTaskHandle {
 	}
 }
 
+/// An handle for spawning essential tasks in the service.
+///
+/// The service exits once one of these tasks ends, be it by finishing or panicking.
+#[derive(Clone)]
+pub struct SpawnEssentialTaskHandle {
+	essential_failed_tx: mpsc::UnboundedSender<()>,
+	inner: SpawnTaskHandle,
+}
+
+impl SpawnEssentialTaskHandle {
+	/// Spawns the given essential task with the given name.
+	pub fn spawn(
+		&self,
+		name: impl Into<Cow<'static, str>>,
+		task: impl Future<Output = ()> + Send + 'static,
+	) {
+		let name = name.into();
+		let essential_failed = self.essential_failed_tx.clone();
+		let name_ = name.clone();
+		let essential_task = AssertUnwindSafe(task)
+			.catch_unwind()
+			.map(move |_| {
+				error!("Essential task `{}` failed. Shutting down service.", name_);
+				let _ = essential_failed.unbounded_send(());
+			});
+
+		self.inner.spawn(name, essential_task);
+	}
+}
+
 impl Spawn for SpawnTaskHandle {
 	fn spawn_obj(&self, future: FutureObj<'static, ()>)
 	-> Result<(), SpawnError> {
@@ -145,8 +211,14 @@ pub struct TaskManager {
 	to_spawn_tx: TaskScheduler,
 	/// Receiver for futures that must be spawned as background tasks.
 	to_spawn_rx: mpsc::UnboundedReceiver<(Pin<Box<dyn Future<Output = ()> + Send>>, Cow<'static, str>)>,
+	/// Sender of a signal when an essential task has concluded.
+	essential_failed_tx: mpsc::UnboundedSender<()>,
+	/// Receiver of the signals the essential tasks send when they conclude.
+	essential_failed_rx: mpsc::UnboundedReceiver<()>,
 	/// How to spawn background tasks.
 	executor: ServiceTaskExecutor,
+	/// The metrics of the tasks, if enabled.
+	metrics: Option<Metrics>,
 }
 
 impl TaskManager {
@@ -162,6 +234,15 @@ impl TaskManager {
 		}
 	}
 
+	/// Spawn an essential task, the service exiting once it ends.
+	pub(super) fn spawn_essential(
+		&self,
+		name: impl Into<Cow<'static, str>>,
+		task: impl Future<Output = ()> + Send + 'static,
+	) {
+		self.spawn_essential_handle().spawn(name, task)
+	}
+
 	pub(super) fn spawn_handle(&self) -> SpawnTaskHandle {
 		SpawnTaskHandle {
 			on_exit: self.on_exit.clone(),
@@ -169,15 +250,62 @@ impl TaskManager {
 		}
 	}
 
+	pub(super) fn spawn_essential_handle(&self) -> SpawnEssentialTaskHandle {
+		SpawnEssentialTaskHandle {
+			essential_failed_tx: self.essential_failed_tx.clone(),
+			inner: self.spawn_handle(),
+		}
+	}
+
 	/// Get sender where background/async tasks can be sent.
 	pub(super) fn scheduler(&self) -> TaskScheduler {
 		self.to_spawn_tx.clone()
 	}
 
 	/// Process background task receiver.
+	///
+	/// The panics of the tasks are caught, so that they don't take down the thread of the
+	/// executor, and the tasks are measured if the metrics are enabled.
 	pub(super) fn process_receiver(&mut self, cx: &mut Context) {
 		while let Poll::Ready(Some((task_to_spawn, name))) = Pin::new(&mut self.to_spawn_rx).poll_next(cx) {
-			(self.executor)(Box::pin(futures_diagnose::diagnose(name, task_to_spawn)));
+			let metrics = self.metrics.clone();
+			if let Some(metrics) = metrics.as_ref() {
+				metrics.tasks_spawned.with_label_values(&[&*name]).inc();
+			}
+
+			let task = Measured {
+				inner: AssertUnwindSafe(task_to_spawn).catch_unwind(),
+				poll_duration: metrics.as_ref()
+					.map(|metrics| metrics.poll_duration.with_label_values(&[&*name])),
+			};
+			let name_ = name.clone();
+			let task = task.map(move |result| {
+				let reason = match result {
+					Ok(()) => "finished",
+					Err(_) => {
+						error!("Task `{}` panicked", name_);
+						"panic"
+					},
+				};
+				if let Some(metrics) = metrics {
+					metrics.tasks_ended.with_label_values(&[&*name_, reason]).inc();
+				}
+			});
+
+			(self.executor)(Box::pin(futures_diagnose::diagnose(name, task)));
+		}
+	}
+
+	/// Poll whether an essential task has concluded, the service having to exit then.
+	pub(super) fn poll_essential_task_failure(&mut self, cx: &mut Context) -> Poll<()> {
+		// `Ready(None)` is not possible since we hold a live sender.
+		Pin::new(&mut self.essential_failed_rx).poll_next(cx).map(drop)
+	}
+
+	/// Signal all the tasks to terminate.
+	pub(super) fn terminate(&mut self) {
+		if let Some(signal) = self.signal.take() {
+			let _ = signal.fire();
 		}
 	}
 
@@ -190,8 +318,94 @@ impl TaskManager {
 impl Drop for TaskManager {
 	fn drop(&mut self) {
 		debug!(target: "service", "Tasks manager shutdown");
-		if let Some(signal) = self.signal.take() {
-			let _ = signal.fire();
-		}
+		self.terminate();
+	}
+}
+
+/// A future recording how long each of its polls take.
+struct Measured<F> {
+	inner: F,
+	poll_duration: Option<Histogram>,
+}
+
+impl<F: Future + Unpin> Future for Measured<F> {
+	type Output = F::Output;
+
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+		let _timer = self.poll_duration.as_ref().map(|histogram| histogram.start_timer());
+		Pin::new(&mut self.inner).poll(cx)
+	}
+}
+
+#[derive(Clone)]
+struct Metrics {
+	poll_duration: HistogramVec,
+	tasks_spawned: CounterVec<U64>,
+	tasks_ended: CounterVec<U64>,
+}
+
+impl Metrics {
+	fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			poll_duration: register(HistogramVec::new(
+				HistogramOpts {
+					common_opts: Opts::new(
+						"tasks_polling_duration",
+						"Duration in seconds of each invocation of Future::poll"
+					),
+					buckets: vec![0.001, 0.004, 0.016, 0.064, 0.256, 1.024, 4.096, 16.384],
+				},
+				&["task_name"]
+			)?, registry)?,
+			tasks_spawned: register(CounterVec::new(
+				Opts::new(
+					"tasks_spawned_total",
+					"Total number of tasks that have been spawned on the Service"
+				),
+				&["task_name"]
+			)?, registry)?,
+			tasks_ended: register(CounterVec::new(
+				Opts::new(
+					"tasks_ended_total",
+					"Total number of tasks that have ended, by whether they finished or panicked"
+				),
+				&["task_name", "reason"]
+			)?, registry)?,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use parking_lot::Mutex;
+
+	#[test]
+	fn tasks_terminate_once_an_essential_task_panics() {
+		let spawned = Arc::new(Mutex::new(Vec::new()));
+		let spawned_ = spawned.clone();
+		let executor: ServiceTaskExecutor = Arc::new(move |task| spawned_.lock().push(task));
+		let mut task_manager = TaskManagerBuilder::new().into_task_manager(executor, None).unwrap();
+
+		task_manager.spawn("never-ending", futures::future::pending());
+		task_manager.spawn("panicking", async { panic!("non-essential task panicked") });
+		task_manager.spawn_essential("essential", async { panic!("essential task panicked") });
+		futures::executor::block_on(futures::future::poll_fn(|cx| {
+			task_manager.process_receiver(cx);
+			assert_eq!(task_manager.poll_essential_task_failure(cx), Poll::Pending);
+			Poll::Ready(())
+		}));
+
+		let mut tasks = std::mem::replace(&mut *spawned.lock(), Vec::new()).into_iter();
+		let (never_ending, panicking, essential) =
+			(tasks.next().unwrap(), tasks.next().unwrap(), tasks.next().unwrap());
+		futures::executor::block_on(panicking);
+		futures::executor::block_on(essential);
+		futures::executor::block_on(futures::future::poll_fn(|cx| {
+			task_manager.poll_essential_task_failure(cx)
+		}));
+
+		task_manager.terminate();
+		futures::executor::block_on(never_ending);
 	}
 }
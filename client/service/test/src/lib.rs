@@ -40,7 +40,7 @@ use sc_service::{
 use sc_network::{multiaddr, Multiaddr, NetworkStateInfo};
 use sc_network::config::{NetworkConfiguration, TransportConfig, NodeKeyConfig, Secret, NonReservedPeerMode};
 use sp_runtime::{generic::BlockId, traits::Block as BlockT};
-use sp_transaction_pool::TransactionPool;
+use sp_transaction_pool::{TransactionPool, TransactionSource};
 
 /// Maximum duration of single wait call.
 const MAX_WAIT_TIME: Duration = Duration::from_secs(60 * 3);
@@ -171,6 +171,8 @@ fn node_config<G: RuntimeGenesis + 'static, E: ChainSpecExtension + Clone + 'sta
 			use_yamux_flow_control: true,
 		},
 		max_parallel_downloads: NetworkConfiguration::default().max_parallel_downloads,
+		max_light_requests_per_sec: NetworkConfiguration::default().max_light_requests_per_sec,
+		max_light_proof_size: NetworkConfiguration::default().max_light_proof_size,
 	};
 
 	Configuration {
@@ -183,8 +185,10 @@ fn node_config<G: RuntimeGenesis + 'static, E: ChainSpecExtension + Clone + 'sta
 		network: network_config,
 		keystore: KeystoreConfig::Path {
 			path: root.join("key"),
-			password: None
+			password: None,
+			encrypt: false,
 		},
+		remote_signer: None,
 		config_dir: Some(root.clone()),
 		database: Some(DatabaseConfig::Path {
 			path: root.join("db"),
@@ -192,7 +196,10 @@ fn node_config<G: RuntimeGenesis + 'static, E: ChainSpecExtension + Clone + 'sta
 		}),
 		state_cache_size: 16777216,
 		state_cache_child_ratio: None,
+		trie_cache_size: 16777216,
 		pruning: Default::default(),
+		keep_blocks: Default::default(),
+		cht_size: sc_client::cht::size(),
 		chain_spec: Some(Box::new((*spec).clone())),
 		name: format!("Node {}", index),
 		wasm_method: sc_service::config::WasmExecutionMethod::Interpreted,
@@ -201,17 +208,25 @@ fn node_config<G: RuntimeGenesis + 'static, E: ChainSpecExtension + Clone + 'sta
 		rpc_ws: None,
 		rpc_ws_max_connections: None,
 		rpc_cors: None,
+		rpc_max_payload: None,
+		rpc_rate_limit: None,
+		rpc_rate_limit_trust_proxy_headers: false,
+		rpc_methods: Default::default(),
 		prometheus_config: None,
+		health_config: None,
 		telemetry_endpoints: None,
 		telemetry_external_transport: None,
 		default_heap_pages: None,
 		offchain_worker: false,
 		sentry_mode: false,
 		force_authoring: false,
+		max_unfinalized_blocks: None,
 		disable_grandpa: false,
+		grandpa_stall_threshold: None,
 		dev_key_seed: key_seed,
 		tracing_targets: None,
 		tracing_receiver: Default::default(),
+		informant_output_format: sc_service::config::InformantOutputFormat::Plain,
 		max_runtime_instances: 8,
 	}
 }
@@ -484,7 +499,11 @@ pub fn sync<G, E, Fb, F, Lb, L, B, ExF, U>(
 	let extrinsic = extrinsic_factory(&first_service.get(), first_user_data);
 
 	futures::executor::block_on(
-		first_service.get().transaction_pool().submit_one(&best_block, extrinsic)
+		first_service.get().transaction_pool().submit_one(
+			&best_block,
+			TransactionSource::External,
+			extrinsic,
+		)
 	).expect("failed to submit extrinsic");
 
 	network.run_until_all_full(
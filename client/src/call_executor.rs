@@ -14,19 +14,23 @@
 // You should have received a copy of the GNU General Public License
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::{sync::Arc, panic::UnwindSafe, result, cell::RefCell};
+use std::{sync::Arc, panic::UnwindSafe, result, cell::RefCell, collections::{BTreeMap, HashMap}};
+use parking_lot::Mutex;
 use codec::{Encode, Decode};
 use sp_runtime::{
-	generic::BlockId, traits::{Block as BlockT, HashFor, NumberFor},
+	generic::BlockId, traits::{Block as BlockT, HashFor, NumberFor, UniqueSaturatedInto},
 };
 use sp_state_machine::{
 	self, OverlayedChanges, Ext, ExecutionManager, StateMachine, ExecutionStrategy,
-	backend::Backend as _, StorageProof,
+	backend::Backend as _, StorageProof, BasicExternalities,
 };
 use sc_executor::{RuntimeVersion, RuntimeInfo, NativeVersion};
 use sp_externalities::Extensions;
-use sp_core::{NativeOrEncoded, NeverNativeValue, traits::CodeExecutor};
+use sp_core::{
+	NativeOrEncoded, NeverNativeValue, traits::{CodeExecutor, RuntimeCode, WrappedRuntimeCode},
+};
 use sp_api::{ProofRecorder, InitializeBlock, StorageTransactionCache};
+use sp_blockchain::HeaderBackend;
 use sc_client_api::{backend, call_executor::CallExecutor, CloneableSpawn};
 
 /// Call executor that executes methods locally, querying all required
@@ -35,6 +39,7 @@ pub struct LocalCallExecutor<B, E> {
 	backend: Arc<B>,
 	executor: E,
 	spawn_handle: Box<dyn CloneableSpawn>,
+	code_substitutes: Arc<CodeSubstitutes>,
 }
 
 impl<B, E> LocalCallExecutor<B, E> {
@@ -48,20 +53,140 @@ impl<B, E> LocalCallExecutor<B, E> {
 			backend,
 			executor,
 			spawn_handle,
+			code_substitutes: Default::default(),
 		}
 	}
 }
 
+impl<B, E: RuntimeInfo> LocalCallExecutor<B, E> {
+	/// Execute the given runtime codes instead of the on-chain one, from the given block numbers.
+	///
+	/// The code substituted from a block is executed at this block and the ones after it, as long
+	/// as their on-chain runtime has the spec version of the substitute. This allows to recover
+	/// from a runtime that can't be executed, until it is replaced by a runtime upgrade.
+	pub fn with_code_substitutes(
+		mut self,
+		code_substitutes: BTreeMap<u64, Vec<u8>>,
+	) -> sp_blockchain::Result<Self> {
+		self.code_substitutes = Arc::new(CodeSubstitutes::new(code_substitutes, &self.executor)?);
+		Ok(self)
+	}
+}
+
 impl<B, E> Clone for LocalCallExecutor<B, E> where E: Clone {
 	fn clone(&self) -> Self {
 		LocalCallExecutor {
 			backend: self.backend.clone(),
 			executor: self.executor.clone(),
 			spawn_handle: self.spawn_handle.clone(),
+			code_substitutes: self.code_substitutes.clone(),
 		}
 	}
 }
 
+/// A runtime code executed instead of the on-chain one.
+struct CodeSubstitute {
+	code: WrappedRuntimeCode<'static>,
+	hash: Vec<u8>,
+	spec_version: u32,
+}
+
+/// The code substitutes, by the number of the block they are executed from.
+#[derive(Default)]
+pub(crate) struct CodeSubstitutes {
+	substitutes: BTreeMap<u64, CodeSubstitute>,
+	/// The spec versions of the on-chain runtimes, by code hash.
+	///
+	/// The version of a runtime is only known by executing it, so it is executed once per code.
+	onchain_spec_versions: Mutex<HashMap<Vec<u8>, u32>>,
+}
+
+impl CodeSubstitutes {
+	/// Read the spec versions of the given substitutes with the executor.
+	pub(crate) fn new(
+		code_substitutes: BTreeMap<u64, Vec<u8>>,
+		executor: &impl RuntimeInfo,
+	) -> sp_blockchain::Result<Self> {
+		let mut substitutes = BTreeMap::new();
+		for (number, code) in code_substitutes {
+			let hash = sp_core::blake2_256(&code).to_vec();
+			let code = WrappedRuntimeCode(code.into());
+			let spec_version = executor.runtime_version(
+				&mut BasicExternalities::default(),
+				&RuntimeCode { code_fetcher: &code, heap_pages: None, hash: hash.clone() },
+			).map_err(|e| sp_blockchain::Error::VersionInvalid(format!("{:?}", e)))?.spec_version;
+			substitutes.insert(number, CodeSubstitute { code, hash, spec_version });
+		}
+
+		Ok(CodeSubstitutes { substitutes, onchain_spec_versions: Default::default() })
+	}
+
+	/// The runtime code to execute at the given block, the on-chain one unless it is substituted.
+	///
+	/// `onchain_spec_version` is only called the first time a substitute may replace the given
+	/// on-chain code.
+	pub(crate) fn runtime_code<'a>(
+		&'a self,
+		number: u64,
+		onchain_code: RuntimeCode<'a>,
+		onchain_spec_version: impl FnOnce(&RuntimeCode) -> Option<u32>,
+	) -> RuntimeCode<'a> {
+		let substitute = match self.substitutes.range(..=number).next_back() {
+			Some((_, substitute)) => substitute,
+			None => return onchain_code,
+		};
+
+		let cached_spec_version = self.onchain_spec_versions.lock().get(&onchain_code.hash).copied();
+		let spec_version = match cached_spec_version {
+			Some(spec_version) => spec_version,
+			None => match onchain_spec_version(&onchain_code) {
+				Some(spec_version) => {
+					self.onchain_spec_versions.lock()
+						.insert(onchain_code.hash.clone(), spec_version);
+					spec_version
+				},
+				None => return onchain_code,
+			},
+		};
+
+		if spec_version == substitute.spec_version {
+			RuntimeCode {
+				code_fetcher: &substitute.code,
+				heap_pages: onchain_code.heap_pages,
+				hash: substitute.hash.clone(),
+			}
+		} else {
+			onchain_code
+		}
+	}
+}
+
+impl<B, E> LocalCallExecutor<B, E> where E: RuntimeInfo {
+	/// The runtime code to execute at the given block, the on-chain one unless it is substituted.
+	fn runtime_code<'a, Block, S>(
+		&'a self,
+		id: &BlockId<Block>,
+		state: &S,
+		onchain_code: RuntimeCode<'a>,
+	) -> sp_blockchain::Result<RuntimeCode<'a>> where
+		Block: BlockT,
+		B: backend::Backend<Block>,
+		S: sp_state_machine::Backend<HashFor<Block>>,
+	{
+		if self.code_substitutes.substitutes.is_empty() {
+			return Ok(onchain_code);
+		}
+
+		let number = self.backend.blockchain().expect_block_number_from_id(id)?;
+		Ok(self.code_substitutes.runtime_code(number.unique_saturated_into(), onchain_code, |code| {
+			let mut overlay = OverlayedChanges::default();
+			let mut cache = StorageTransactionCache::<Block, S>::default();
+			let mut ext = Ext::new(&mut overlay, &mut cache, state, None, None);
+			self.executor.runtime_version(&mut ext, code).ok().map(|v| v.spec_version)
+		}))
+	}
+}
+
 impl<B, E, Block> CallExecutor<Block> for LocalCallExecutor<B, E>
 where
 	B: backend::Backend<Block>,
@@ -86,6 +211,7 @@ where
 		)?;
 		let state = self.backend.state_at(*id)?;
 		let state_runtime_code = sp_state_machine::backend::BackendRuntimeCode::new(&state);
+		let runtime_code = self.runtime_code(id, &state, state_runtime_code.runtime_code()?)?;
 		let return_data = StateMachine::new(
 			&state,
 			changes_trie,
@@ -94,7 +220,7 @@ where
 			method,
 			call_data,
 			extensions.unwrap_or_default(),
-			&state_runtime_code.runtime_code()?,
+			&runtime_code,
 			self.spawn_handle.clone(),
 		).execute_using_consensus_failure_handler::<_, NeverNativeValue, fn() -> _>(
 			strategy.get_manager(),
@@ -153,7 +279,11 @@ where
 				let state_runtime_code = sp_state_machine::backend::BackendRuntimeCode::new(&trie_state);
 				// It is important to extract the runtime code here before we create the proof
 				// recorder.
-				let runtime_code = state_runtime_code.runtime_code()?;
+				let runtime_code = self.runtime_code(
+					at,
+					trie_state,
+					state_runtime_code.runtime_code()?,
+				)?;
 
 				let backend = sp_state_machine::ProvingBackend::new_with_recorder(
 					trie_state,
@@ -177,6 +307,11 @@ where
 			},
 			None => {
 				let state_runtime_code = sp_state_machine::backend::BackendRuntimeCode::new(&state);
+				let runtime_code = self.runtime_code(
+					at,
+					&state,
+					state_runtime_code.runtime_code()?,
+				)?;
 				StateMachine::new(
 					&state,
 					changes_trie_state,
@@ -185,7 +320,7 @@ where
 					method,
 					call_data,
 					extensions.unwrap_or_default(),
-					&state_runtime_code.runtime_code()?,
+					&runtime_code,
 					self.spawn_handle.clone(),
 				)
 				.with_storage_transaction_cache(storage_transaction_cache.as_mut().map(|c| &mut **c))
@@ -201,6 +336,8 @@ where
 			self.backend.changes_trie_storage(),
 		)?;
 		let state = self.backend.state_at(*id)?;
+		let state_runtime_code = sp_state_machine::backend::BackendRuntimeCode::new(&state);
+		let runtime_code = self.runtime_code(id, &state, state_runtime_code.runtime_code()?)?;
 		let mut cache = StorageTransactionCache::<Block, B::State>::default();
 		let mut ext = Ext::new(
 			&mut overlay,
@@ -209,8 +346,7 @@ where
 			changes_trie_state,
 			None,
 		);
-		let state_runtime_code = sp_state_machine::backend::BackendRuntimeCode::new(&state);
-		self.executor.runtime_version(&mut ext, &state_runtime_code.runtime_code()?)
+		self.executor.runtime_version(&mut ext, &runtime_code)
 			.map_err(|e| sp_blockchain::Error::VersionInvalid(format!("{:?}", e)).into())
 	}
 
@@ -255,3 +391,70 @@ impl<B, E, Block> sp_version::GetRuntimeVersion<Block> for LocalCallExecutor<B,
 		CallExecutor::runtime_version(self, at).map_err(|e| format!("{:?}", e))
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::cell::Cell;
+
+	fn substitutes(spec_versions: &[(u64, u32)]) -> CodeSubstitutes {
+		CodeSubstitutes {
+			substitutes: spec_versions.iter().map(|(number, spec_version)| {
+				let substitute = CodeSubstitute {
+					code: WrappedRuntimeCode(vec![*number as u8].into()),
+					hash: vec![*number as u8],
+					spec_version: *spec_version,
+				};
+				(*number, substitute)
+			}).collect(),
+			onchain_spec_versions: Default::default(),
+		}
+	}
+
+	fn onchain_code(code: &WrappedRuntimeCode<'static>) -> RuntimeCode {
+		RuntimeCode { code_fetcher: code, heap_pages: None, hash: code.0.to_vec() }
+	}
+
+	#[test]
+	fn code_is_substituted_from_its_block_while_the_spec_version_is_unchanged() {
+		let substitutes = substitutes(&[(10, 1), (20, 2)]);
+		// the on-chain codes are named after their spec version.
+		let codes = [WrappedRuntimeCode(vec![0].into()), WrappedRuntimeCode(vec![1].into())];
+		let code_at = |number, spec_version: usize| substitutes.runtime_code(
+			number,
+			onchain_code(&codes[spec_version - 1]),
+			|_| Some(spec_version as u32),
+		).hash;
+
+		assert_eq!(code_at(9, 1), vec![0]);
+		assert_eq!(code_at(10, 1), vec![10]);
+		assert_eq!(code_at(15, 1), vec![10]);
+		assert_eq!(code_at(15, 2), vec![1]);
+		assert_eq!(code_at(25, 2), vec![20]);
+	}
+
+	#[test]
+	fn onchain_spec_version_is_read_once_per_code() {
+		let substitutes = substitutes(&[(10, 1)]);
+		let code = WrappedRuntimeCode(vec![0].into());
+		let reads = Cell::new(0);
+		let code_at = |number| substitutes.runtime_code(number, onchain_code(&code), |_| {
+			reads.set(reads.get() + 1);
+			Some(1)
+		}).hash;
+
+		assert_eq!(code_at(5), vec![0]);
+		assert_eq!(reads.get(), 0);
+		assert_eq!(code_at(10), vec![10]);
+		assert_eq!(code_at(11), vec![10]);
+		assert_eq!(reads.get(), 1);
+	}
+
+	#[test]
+	fn code_is_not_substituted_when_the_onchain_spec_version_is_unknown() {
+		let substitutes = substitutes(&[(10, 1)]);
+		let code = WrappedRuntimeCode(vec![0].into());
+
+		assert_eq!(substitutes.runtime_code(10, onchain_code(&code), |_| None).hash, vec![0]);
+	}
+}
@@ -23,6 +23,7 @@
 //! root has. A correct proof implies that the claimed block is identical to the one
 //! we discarded.
 
+use std::convert::TryInto;
 use hash_db;
 use codec::Encode;
 use sp_trie;
@@ -36,8 +37,9 @@ use sp_state_machine::{
 
 use sp_blockchain::{Error as ClientError, Result as ClientResult};
 
-/// The size of each CHT. This value is passed to every CHT-related function from
-/// production code. Other values are passed from tests.
+/// The default size of each CHT. This value is passed to every CHT-related function from
+/// production code, unless the databases are configured with another size. Other values are
+/// passed from tests.
 const SIZE: u32 = 2048;
 
 /// Gets default CHT size.
@@ -300,6 +302,30 @@ pub fn end_number<N: AtLeast32Bit>(cht_size: N, cht_num: N) -> N {
 	(cht_num + N::one()) * cht_size
 }
 
+/// Check that a light client checkpoint at the given block is the last block of a CHT and comes
+/// with the roots of the header CHTs of all the blocks up to it.
+pub fn check_checkpoint<N: AtLeast32Bit + Clone + std::fmt::Display>(
+	cht_size: N,
+	block_num: N,
+	header_cht_roots: usize,
+) -> ClientResult<()> {
+	let expected_roots: Option<usize> =
+		if block_num.is_zero() || !(block_num.clone() % cht_size.clone()).is_zero() {
+			None
+		} else {
+			(block_num.clone() / cht_size).try_into().ok()
+		};
+
+	match expected_roots {
+		Some(expected_roots) if expected_roots == header_cht_roots => Ok(()),
+		_ => Err(ClientError::Backend(format!(
+			"Checkpoint #{} is not the last block of a CHT given with the {} CHT roots up to it",
+			block_num,
+			header_cht_roots,
+		))),
+	}
+}
+
 /// Convert a block number to a CHT number.
 /// Returns `None` for `block_num` == 0, `Some` otherwise.
 pub fn block_to_cht_number<N: AtLeast32Bit>(cht_size: N, block_num: N) -> Option<N> {
@@ -377,6 +403,17 @@ mod tests {
 		assert_eq!(end_number(SIZE, 2u32), SIZE + SIZE + SIZE);
 	}
 
+	#[test]
+	fn check_checkpoint_works() {
+		assert!(check_checkpoint(SIZE, 0, 0).is_err());
+		assert!(check_checkpoint(SIZE, SIZE - 1, 0).is_err());
+		assert!(check_checkpoint(SIZE, SIZE, 0).is_err());
+		assert!(check_checkpoint(SIZE, SIZE, 1).is_ok());
+		assert!(check_checkpoint(SIZE, SIZE * 3, 2).is_err());
+		assert!(check_checkpoint(SIZE, SIZE * 3, 3).is_ok());
+		assert!(check_checkpoint(SIZE, SIZE * 3 + 1, 3).is_err());
+	}
+
 	#[test]
 	fn build_pairs_fails_when_no_enough_blocks() {
 		assert!(build_pairs::<Header, _>(SIZE as _, 0,
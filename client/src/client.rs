@@ -27,7 +27,7 @@ use codec::{Encode, Decode};
 use hash_db::Prefix;
 use sp_core::{
 	ChangesTrieConfiguration, convert_hash, traits::CodeExecutor,
-	NativeOrEncoded, storage::{StorageKey, StorageData, well_known_keys, ChildInfo},
+	NativeOrEncoded, storage::{StorageKey, StorageData, well_known_keys, ChildInfo, OwnedChildInfo},
 };
 use sc_telemetry::{telemetry, SUBSTRATE_INFO};
 use sp_runtime::{
@@ -40,7 +40,8 @@ use sp_runtime::{
 };
 use sp_state_machine::{
 	DBValue, Backend as StateBackend, ChangesTrieAnchorBlockId,
-	prove_read, prove_child_read, ChangesTrieRootsStorage, ChangesTrieStorage,
+	prove_read, prove_child_read, prove_read_with_size_limit, prove_child_read_with_size_limit,
+	ChangesTrieRootsStorage, ChangesTrieStorage,
 	ChangesTrieConfigurationRange, key_changes, key_changes_proof,
 };
 use sc_executor::{RuntimeVersion, RuntimeInfo};
@@ -65,21 +66,21 @@ use sc_block_builder::{BlockBuilderApi, BlockBuilderProvider};
 pub use sc_client_api::{
 	backend::{
 		self, BlockImportOperation, PrunableStateChangesTrieStorage,
-		ClientImportOperation, Finalizer, ImportSummary, NewBlockState,
+		ClientImportOperation, Finalizer, ImportSummary, FinalizeSummary, NewBlockState,
 		changes_tries_state_at_block, StorageProvider,
 		LockImportRun,
 	},
 	client::{
 		ImportNotifications, FinalityNotification, FinalityNotifications, BlockImportNotification,
 		ClientInfo, BlockchainEvents, BlockBackend, ProvideUncles, BadBlocks, ForkBlocks,
-		BlockOf,
+		LightSyncCheckpoint, BlockOf,
 	},
 	execution_extensions::{ExecutionExtensions, ExecutionStrategies},
 	notifications::{StorageNotifications, StorageEventStream},
 	CallExecutor, ExecutorProvider, ProofProvider, CloneableSpawn,
 };
 use sp_blockchain::Error;
-use prometheus_endpoint::Registry;
+use prometheus_endpoint::{register, HistogramOpts, HistogramVec, PrometheusError, Registry};
 
 use crate::{
 	call_executor::LocalCallExecutor,
@@ -94,14 +95,39 @@ pub struct Client<B, E, Block, RA> where Block: BlockT {
 	executor: E,
 	storage_notifications: Mutex<StorageNotifications<Block>>,
 	import_notification_sinks: Mutex<Vec<mpsc::UnboundedSender<BlockImportNotification<Block>>>>,
+	every_import_notification_sinks: Mutex<Vec<mpsc::UnboundedSender<BlockImportNotification<Block>>>>,
 	finality_notification_sinks: Mutex<Vec<mpsc::UnboundedSender<FinalityNotification<Block>>>>,
 	// holds the block hash currently being imported. TODO: replace this with block queue
 	importing_block: RwLock<Option<Block::Hash>>,
 	block_rules: BlockRules<Block>,
 	execution_extensions: ExecutionExtensions<Block>,
+	cht_size: NumberFor<Block>,
+	metrics: Option<Metrics>,
 	_phantom: PhantomData<RA>,
 }
 
+/// Prometheus metrics of the client.
+struct Metrics {
+	commit_time: HistogramVec,
+}
+
+impl Metrics {
+	fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			commit_time: register(
+				HistogramVec::new(
+					HistogramOpts::new(
+						"database_commit_time",
+						"Time taken to commit the import operations to the database, in seconds",
+					),
+					&["operation"],
+				)?,
+				registry,
+			)?,
+		})
+	}
+}
+
 // used in importing a block, where additional changes are made after the runtime
 // executed.
 enum PrePostHeader<H> {
@@ -130,12 +156,16 @@ impl<H> PrePostHeader<H> {
 }
 
 /// Create an instance of in-memory client.
+///
+/// The given runtime codes are executed instead of the on-chain one from the given block numbers,
+/// see `LocalCallExecutor::with_code_substitutes`.
 pub fn new_in_mem<E, Block, S, RA>(
 	executor: E,
 	genesis_storage: &S,
 	keystore: Option<sp_core::traits::BareCryptoStorePtr>,
 	prometheus_registry: Option<Registry>,
 	spawn_handle: Box<dyn CloneableSpawn>,
+	code_substitutes: BTreeMap<u64, Vec<u8>>,
 ) -> sp_blockchain::Result<Client<
 	in_mem::Backend<Block>,
 	LocalCallExecutor<in_mem::Backend<Block>, E>,
@@ -146,7 +176,15 @@ pub fn new_in_mem<E, Block, S, RA>(
 	S: BuildStorage,
 	Block: BlockT,
 {
-	new_with_backend(Arc::new(in_mem::Backend::new()), executor, genesis_storage, keystore, spawn_handle, prometheus_registry)
+	new_with_backend(
+		Arc::new(in_mem::Backend::new()),
+		executor,
+		genesis_storage,
+		keystore,
+		spawn_handle,
+		prometheus_registry,
+		code_substitutes,
+	)
 }
 
 /// Create a client with the explicitly provided backend.
@@ -158,6 +196,7 @@ pub fn new_with_backend<B, E, Block, S, RA>(
 	keystore: Option<sp_core::traits::BareCryptoStorePtr>,
 	spawn_handle: Box<dyn CloneableSpawn>,
 	prometheus_registry: Option<Registry>,
+	code_substitutes: BTreeMap<u64, Vec<u8>>,
 ) -> sp_blockchain::Result<Client<B, LocalCallExecutor<B, E>, Block, RA>>
 	where
 		E: CodeExecutor + RuntimeInfo,
@@ -165,7 +204,8 @@ pub fn new_with_backend<B, E, Block, S, RA>(
 		Block: BlockT,
 		B: backend::LocalBackend<Block> + 'static,
 {
-	let call_executor = LocalCallExecutor::new(backend.clone(), executor, spawn_handle);
+	let call_executor = LocalCallExecutor::new(backend.clone(), executor, spawn_handle)
+		.with_code_substitutes(code_substitutes)?;
 	let extensions = ExecutionExtensions::new(Default::default(), keystore);
 	Client::new(
 		backend,
@@ -174,6 +214,7 @@ pub fn new_with_backend<B, E, Block, S, RA>(
 		Default::default(),
 		Default::default(),
 		extensions,
+		cht::size(),
 		prometheus_registry,
 	)
 }
@@ -203,16 +244,30 @@ impl<B, E, Block, RA> LockImportRun<Block, B> for Client<B, E, Block, RA>
 			let mut op = ClientImportOperation {
 				op: self.backend.begin_operation()?,
 				notify_imported: None,
-				notify_finalized: Vec::new(),
+				notify_every_imported: None,
+				notify_finalized: None,
 			};
 
 			let r = f(&mut op)?;
 
-			let ClientImportOperation { op, notify_imported, notify_finalized } = op;
+			let ClientImportOperation {
+				op,
+				notify_imported,
+				notify_every_imported,
+				notify_finalized,
+			} = op;
+			let operation = if notify_imported.is_some() { "import" } else { "finalize" };
+			let started = std::time::Instant::now();
 			self.backend.commit_operation(op)?;
+			if let Some(metrics) = self.metrics.as_ref() {
+				metrics.commit_time
+					.with_label_values(&[operation])
+					.observe(started.elapsed().as_secs_f64());
+			}
 
 			self.notify_finalized(notify_finalized)?;
 			self.notify_imported(notify_imported)?;
+			self.notify_every_imported(notify_every_imported);
 
 			Ok(r)
 		};
@@ -245,6 +300,8 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 	Block: BlockT,
 {
 	/// Creates new Substrate Client with given blockchain and code executor.
+	///
+	/// The header and changes proofs are served against the CHTs of `cht_size` blocks.
 	pub fn new(
 		backend: Arc<B>,
 		executor: E,
@@ -252,7 +309,8 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		fork_blocks: ForkBlocks<Block>,
 		bad_blocks: BadBlocks<Block>,
 		execution_extensions: ExecutionExtensions<Block>,
-		_prometheus_registry: Option<Registry>,
+		cht_size: NumberFor<Block>,
+		prometheus_registry: Option<Registry>,
 	) -> sp_blockchain::Result<Self> {
 		if backend.blockchain().header(BlockId::Number(Zero::zero()))?.is_none() {
 			let genesis_storage = build_genesis_storage.build_storage()?;
@@ -278,10 +336,19 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 			executor,
 			storage_notifications: Default::default(),
 			import_notification_sinks: Default::default(),
+			every_import_notification_sinks: Default::default(),
 			finality_notification_sinks: Default::default(),
 			importing_block: Default::default(),
 			block_rules: BlockRules::new(fork_blocks, bad_blocks),
 			execution_extensions,
+			cht_size,
+			metrics: prometheus_registry.and_then(|registry| match Metrics::register(&registry) {
+				Ok(metrics) => Some(metrics),
+				Err(e) => {
+					warn!("Failed to register the client metrics: {:?}", e);
+					None
+				},
+			}),
 			_phantom: Default::default(),
 		})
 	}
@@ -742,9 +809,23 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 
 		operation.op.insert_aux(aux)?;
 
+		operation.notify_every_imported = Some(BlockImportNotification {
+			hash,
+			origin,
+			header: import_headers.post().clone(),
+			is_new_best,
+			retracted: retracted.clone(),
+		});
+
 		if make_notifications {
 			if finalized {
-				operation.notify_finalized.push(hash);
+				// the parent is finalized by now, by this operation or a previous one.
+				let stale_heads = self.displaced_leaves(
+					*import_headers.post().number() - One::one(),
+					parent_hash,
+					hash,
+				)?;
+				operation.push_finalized(vec![hash], stale_heads);
 			}
 
 			operation.notify_imported = Some(ImportSummary {
@@ -878,50 +959,100 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		operation.op.mark_finalized(BlockId::Hash(block), justification)?;
 
 		if notify {
-			// sometimes when syncing, tons of blocks can be finalized at once.
-			// we'll send notifications spuriously in that case.
-			const MAX_TO_NOTIFY: usize = 256;
-			let enacted = route_from_finalized.enacted();
-			let start = enacted.len() - ::std::cmp::min(enacted.len(), MAX_TO_NOTIFY);
-			for finalized in &enacted[start..] {
-				operation.notify_finalized.push(finalized.hash);
-			}
+			let parent_hash = enacted.len().checked_sub(2)
+				.map(|parent| enacted[parent].hash)
+				.unwrap_or(last_finalized);
+			let stale_heads = self.displaced_leaves(
+				route_from_finalized.common_block().number,
+				parent_hash,
+				block,
+			)?;
+			operation.push_finalized(enacted.iter().map(|e| e.hash).collect(), stale_heads);
 		}
 
 		Ok(())
 	}
 
+	/// Returns the leaves that are descendants of the block at `last_finalized_number` on the
+	/// chain of `finalized`, but not of `finalized` itself.
+	///
+	/// `finalized` doesn't need to be imported yet, but its parent does.
+	fn displaced_leaves(
+		&self,
+		last_finalized_number: NumberFor<Block>,
+		finalized_parent: Block::Hash,
+		finalized: Block::Hash,
+	) -> sp_blockchain::Result<Vec<Block::Hash>> {
+		let leaves = match self.backend.blockchain().leaves() {
+			Ok(leaves) => leaves,
+			// light clients don't keep track of the forks.
+			Err(Error::NotAvailableOnLightClient) => return Ok(Vec::new()),
+			Err(e) => return Err(e),
+		};
+
+		let mut displaced = Vec::new();
+		for leaf in leaves {
+			// the parent of a block being imported is only a leaf until the block is.
+			if leaf == finalized || leaf == finalized_parent {
+				continue;
+			}
+
+			let route = sp_blockchain::tree_route(
+				self.backend.blockchain(),
+				finalized_parent,
+				leaf,
+			)?;
+			let is_displaced = if route.common_block().hash == finalized_parent {
+				route.enacted().first().map_or(true, |e| e.hash != finalized)
+			} else {
+				// otherwise the leaf was displaced by the finalization of an ancestor already.
+				route.common_block().number >= last_finalized_number
+			};
+			if is_displaced {
+				displaced.push(leaf);
+			}
+		}
+
+		Ok(displaced)
+	}
+
 	fn notify_finalized(
 		&self,
-		notify_finalized: Vec<Block::Hash>,
+		notify_finalized: Option<FinalizeSummary<Block>>,
 	) -> sp_blockchain::Result<()> {
 		let mut sinks = self.finality_notification_sinks.lock();
 
-		if notify_finalized.is_empty() {
-			// cleanup any closed finality notification sinks
-			// since we won't be running the loop below which
-			// would also remove any closed sinks.
-			sinks.retain(|sink| !sink.is_closed());
+		let mut notify_finalized = match notify_finalized {
+			Some(notify_finalized) => notify_finalized,
+			None => {
+				// cleanup any closed finality notification sinks
+				// since we won't be sending any notification below
+				// which would also remove any closed sinks.
+				sinks.retain(|sink| !sink.is_closed());
 
-			return Ok(());
-		}
+				return Ok(());
+			}
+		};
 
-		for finalized_hash in notify_finalized {
-			let header = self.header(&BlockId::Hash(finalized_hash))?
-				.expect("header already known to exist in DB because it is indicated in the tree route; qed");
+		let finalized_hash = notify_finalized.finalized.pop()
+			.expect("a finalize summary is only created along with a finalized block; qed");
+		let header = self.header(&BlockId::Hash(finalized_hash))?
+			.expect("header already known to exist in DB because it is indicated in the tree \
+				route; qed");
 
-			telemetry!(SUBSTRATE_INFO; "notify.finalized";
-				"height" => format!("{}", header.number()),
-				"best" => ?finalized_hash,
-			);
+		telemetry!(SUBSTRATE_INFO; "notify.finalized";
+			"height" => format!("{}", header.number()),
+			"best" => ?finalized_hash,
+		);
 
-			let notification = FinalityNotification {
-				header,
-				hash: finalized_hash,
-			};
+		let notification = FinalityNotification {
+			header,
+			hash: finalized_hash,
+			tree_route: notify_finalized.finalized.into(),
+			stale_heads: notify_finalized.stale_heads.into(),
+		};
 
-			sinks.retain(|sink| sink.unbounded_send(notification.clone()).is_ok());
-		}
+		sinks.retain(|sink| sink.unbounded_send(notification.clone()).is_ok());
 
 		Ok(())
 	}
@@ -971,6 +1102,15 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		Ok(())
 	}
 
+	fn notify_every_imported(&self, notification: Option<BlockImportNotification<Block>>) {
+		match notification {
+			Some(notification) => self.every_import_notification_sinks.lock()
+				.retain(|sink| sink.unbounded_send(notification.clone()).is_ok()),
+			None => self.every_import_notification_sinks.lock()
+				.retain(|sink| !sink.is_closed()),
+		}
+	}
+
 	/// Attempts to revert the chain by `n` blocks guaranteeing that no block is
 	/// reverted past the last finalized block. Returns the number of blocks
 	/// that were successfully reverted.
@@ -1109,6 +1249,35 @@ impl<B, E, Block, RA> ProofProvider<Block> for Client<B, E, Block, RA> where
 				.map_err(Into::into))
 	}
 
+	fn read_proof_with_size_limit(
+		&self,
+		id: &BlockId<Block>,
+		keys: &mut dyn Iterator<Item=&[u8]>,
+		size_limit: usize,
+	) -> sp_blockchain::Result<Option<StorageProof>> {
+		self.state_at(id)
+			.and_then(|state| prove_read_with_size_limit(state, keys, size_limit)
+				.map_err(Into::into))
+	}
+
+	fn read_child_proof_with_size_limit(
+		&self,
+		id: &BlockId<Block>,
+		storage_key: &[u8],
+		child_info: ChildInfo,
+		keys: &mut dyn Iterator<Item=&[u8]>,
+		size_limit: usize,
+	) -> sp_blockchain::Result<Option<StorageProof>> {
+		self.state_at(id)
+			.and_then(|state| prove_child_read_with_size_limit(
+				state,
+				storage_key,
+				child_info,
+				keys,
+				size_limit,
+			).map_err(Into::into))
+	}
+
 	fn execution_proof(
 		&self,
 		id: &BlockId<Block>,
@@ -1138,7 +1307,7 @@ impl<B, E, Block, RA> ProofProvider<Block> for Client<B, E, Block, RA> where
 	}
 
 	fn header_proof(&self, id: &BlockId<Block>) -> sp_blockchain::Result<(Block::Header, StorageProof)> {
-		self.header_proof_with_cht_size(id, cht::size())
+		self.header_proof_with_cht_size(id, self.cht_size)
 	}
 
 	fn key_changes_proof(
@@ -1157,7 +1326,7 @@ impl<B, E, Block, RA> ProofProvider<Block> for Client<B, E, Block, RA> where
 			max,
 			storage_key,
 			key,
-			cht::size(),
+			self.cht_size,
 		)
 	}
 }
@@ -1220,6 +1389,18 @@ impl<B, E, Block, RA>  ExecutorProvider<Block> for Client<B, E, Block, RA> where
 	}
 }
 
+/// The key a `KeyIterator` iterating the keys under `prefix` after `start_key` starts from.
+fn iteration_start(prefix: Option<&StorageKey>, start_key: Option<&StorageKey>) -> Vec<u8> {
+	match (prefix, start_key) {
+		// a start key before the prefix would end the iteration at the first key
+		// that doesn't match the prefix, so we start at the prefix instead.
+		(Some(prefix), Some(start_key)) if start_key.0 < prefix.0 => prefix.0.clone(),
+		(_, Some(start_key)) => start_key.0.clone(),
+		(Some(prefix), None) => prefix.0.clone(),
+		(None, None) => Vec::new(),
+	}
+}
+
 impl<B, E, Block, RA> StorageProvider<Block, B> for Client<B, E, Block, RA> where
 	B: backend::Backend<Block>,
 	E: CallExecutor<Block>,
@@ -1253,11 +1434,20 @@ impl<B, E, Block, RA> StorageProvider<Block, B> for Client<B, E, Block, RA> wher
 		start_key: Option<&StorageKey>
 	) -> sp_blockchain::Result<KeyIterator<'a, B::State, Block>> {
 		let state = self.state_at(id)?;
-		let start_key = start_key
-			.or(prefix)
-			.map(|key| key.0.clone())
-			.unwrap_or_else(Vec::new);
-		Ok(KeyIterator::new(state, prefix, start_key))
+		Ok(KeyIterator::new(state, prefix, iteration_start(prefix, start_key)))
+	}
+
+	fn child_storage_keys_iter<'a>(
+		&self,
+		id: &BlockId<Block>,
+		child_storage_key: StorageKey,
+		child_info: OwnedChildInfo,
+		prefix: Option<&'a StorageKey>,
+		start_key: Option<&StorageKey>,
+	) -> sp_blockchain::Result<KeyIterator<'a, B::State, Block>> {
+		let state = self.state_at(id)?;
+		let start_key = iteration_start(prefix, start_key);
+		Ok(KeyIterator::new_child(state, child_storage_key, child_info, prefix, start_key))
 	}
 
 
@@ -1321,6 +1511,14 @@ impl<B, E, Block, RA> StorageProvider<Block, B> for Client<B, E, Block, RA> wher
 		)
 	}
 
+	fn pin_block(&self, hash: &Block::Hash) -> sp_blockchain::Result<()> {
+		self.backend.pin_block(hash)
+	}
+
+	fn unpin_block(&self, hash: &Block::Hash) {
+		self.backend.unpin_block(hash)
+	}
+
 	fn max_key_changes_range(
 		&self,
 		first: NumberFor<Block>,
@@ -1769,6 +1967,12 @@ where
 		stream
 	}
 
+	fn every_import_notification_stream(&self) -> ImportNotifications<Block> {
+		let (sink, stream) = mpsc::unbounded();
+		self.every_import_notification_sinks.lock().push(sink);
+		stream
+	}
+
 	fn finality_notification_stream(&self) -> FinalityNotifications<Block> {
 		let (sink, stream) = mpsc::unbounded();
 		self.finality_notification_sinks.lock().push(sink);
@@ -2007,7 +2211,7 @@ pub(crate) mod tests {
 	use substrate_test_runtime_client::{
 		prelude::*,
 		client_ext::ClientExt,
-		sc_client_db::{Backend, DatabaseSettings, DatabaseSettingsSrc, PruningMode},
+		sc_client_db::{Backend, DatabaseSettings, DatabaseSettingsSrc, KeepBlocks, PruningMode},
 		runtime::{self, Block, Transfer, RuntimeApi, TestAPI},
 	};
 	use hex_literal::hex;
@@ -2940,6 +3144,61 @@ pub(crate) mod tests {
 		);
 	}
 
+	#[test]
+	fn finality_notification_should_contain_tree_route_and_stale_heads() {
+		let mut client = substrate_test_runtime_client::new();
+		let mut finality_notifications = client.finality_notification_stream();
+
+		let import_block_at = |
+			client: &mut substrate_test_runtime_client::TestClient,
+			parent: BlockId<Block>,
+			amount,
+		| {
+			let mut builder = client.new_block_at(&parent, Default::default(), false).unwrap();
+			// needed to make sure the forks get different hashes
+			if amount > 0 {
+				builder.push_transfer(Transfer {
+					from: AccountKeyring::Alice.into(),
+					to: AccountKeyring::Ferdie.into(),
+					amount,
+					nonce: 0,
+				}).unwrap();
+			}
+			let block = builder.build().unwrap().block;
+			client.import(BlockOrigin::Own, block.clone()).unwrap();
+			block.hash()
+		};
+
+		// G -> A1 -> A2 -> A3
+		//  \      		//   \      -> B2
+		//    -> C1
+		let a1 = import_block_at(&mut client, BlockId::Number(0), 0);
+		let a2 = import_block_at(&mut client, BlockId::Hash(a1), 0);
+		let a3 = import_block_at(&mut client, BlockId::Hash(a2), 0);
+		let b2 = import_block_at(&mut client, BlockId::Hash(a1), 1);
+		let c1 = import_block_at(&mut client, BlockId::Number(0), 2);
+
+		ClientExt::finalize_block(&client, BlockId::Hash(a2), None).unwrap();
+
+		let notification = finality_notifications.try_next().unwrap().unwrap();
+		assert_eq!(notification.hash, a2);
+		assert_eq!(&*notification.tree_route, &[a1][..]);
+		let mut stale_heads = notification.stale_heads.to_vec();
+		stale_heads.sort();
+		let mut expected = vec![b2, c1];
+		expected.sort();
+		assert_eq!(stale_heads, expected);
+		assert!(finality_notifications.try_next().is_err());
+
+		// the heads displaced by a previous finalization aren't reported again.
+		ClientExt::finalize_block(&client, BlockId::Hash(a3), None).unwrap();
+
+		let notification = finality_notifications.try_next().unwrap().unwrap();
+		assert_eq!(notification.hash, a3);
+		assert!(notification.tree_route.is_empty());
+		assert!(notification.stale_heads.is_empty());
+	}
+
 	#[test]
 	fn get_header_by_block_number_doesnt_panic() {
 		let client = substrate_test_runtime_client::new();
@@ -3021,7 +3280,10 @@ pub(crate) mod tests {
 			DatabaseSettings {
 				state_cache_size: 1 << 20,
 				state_cache_child_ratio: None,
+				trie_cache_size: 1 << 20,
 				pruning: PruningMode::ArchiveAll,
+				keep_blocks: KeepBlocks::All,
+				cht_size: cht::size(),
 				source: DatabaseSettingsSrc::Path {
 					path: tmp.path().into(),
 					cache_size: None,
@@ -3223,7 +3485,10 @@ pub(crate) mod tests {
 				DatabaseSettings {
 					state_cache_size: 1 << 20,
 					state_cache_child_ratio: None,
+					trie_cache_size: 1 << 20,
 					pruning: PruningMode::keep_blocks(1),
+					keep_blocks: KeepBlocks::All,
+					cht_size: cht::size(),
 					source: DatabaseSettingsSrc::Path {
 						path: tmp.path().into(),
 						cache_size: None,
@@ -3438,6 +3703,13 @@ pub(crate) mod tests {
 			.map(|x| x.0)
 			.collect();
 		assert_eq!(res, Vec::<Vec<u8>>::new());
+
+		// a start key before the prefix starts the iteration at the prefix.
+		let res: Vec<_> = client.storage_keys_iter(&BlockId::Number(0), Some(&prefix), Some(&StorageKey(hex!("01").to_vec())))
+			.unwrap()
+			.map(|x| x.0)
+			.collect();
+		assert_eq!(res, [hex!("3a636f6465").to_vec(), hex!("3a686561707061676573").to_vec()]);
 	}
 
 	#[test]
@@ -3491,6 +3763,7 @@ pub(crate) mod tests {
 				None,
 				None,
 				sp_core::tasks::executor(),
+				Default::default(),
 			)
 			.unwrap();
 
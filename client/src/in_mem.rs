@@ -24,7 +24,7 @@ use sp_core::offchain::storage::{
 	InMemOffchainStorage as OffchainStorage
 };
 use sp_runtime::generic::BlockId;
-use sp_runtime::traits::{Block as BlockT, Header as HeaderT, Zero, NumberFor, HashFor};
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT, Zero, One, NumberFor, HashFor};
 use sp_runtime::{Justification, Storage};
 use sp_state_machine::{
 	ChangesTrieTransaction, InMemoryBackend, Backend as StateBackend, StorageCollection,
@@ -417,6 +417,43 @@ impl<Block: BlockT> sc_client_api::light::Storage<Block> for Blockchain<Block>
 		Ok(())
 	}
 
+	fn import_checkpoint(
+		&self,
+		header: Block::Header,
+		header_cht_roots: Vec<Block::Hash>,
+	) -> sp_blockchain::Result<()> {
+		let cht_size = sc_client_api::light::Storage::<Block>::cht_size(self);
+		let hash = header.hash();
+		let number = *header.number();
+		crate::cht::check_checkpoint(cht_size, number, header_cht_roots.len())?;
+
+		let mut storage = self.storage.write();
+		if !storage.best_number.is_zero() {
+			return Err(sp_blockchain::Error::Backend(
+				format!("Cannot import checkpoint {:?} once blocks past the genesis are known", hash),
+			));
+		}
+
+		for (cht_number, cht_root) in header_cht_roots.into_iter().enumerate() {
+			let cht_number = (cht_number as u32).into();
+			let mut block = crate::cht::start_number(cht_size, cht_number);
+			while block <= crate::cht::end_number(cht_size, cht_number) {
+				storage.header_cht_roots.insert(block, cht_root);
+				block += One::one();
+			}
+		}
+
+		storage.leaves.import(hash, number, *header.parent_hash());
+		storage.blocks.insert(hash, StoredBlock::new(header, None, None));
+		storage.hashes.insert(number, hash);
+		storage.best_hash = hash;
+		storage.best_number = number;
+		storage.finalized_hash = hash;
+		storage.finalized_number = number;
+
+		Ok(())
+	}
+
 	fn set_head(&self, id: BlockId<Block>) -> sp_blockchain::Result<()> {
 		Blockchain::set_head(self, id)
 	}
@@ -429,6 +466,10 @@ impl<Block: BlockT> sc_client_api::light::Storage<Block> for Blockchain<Block>
 		Blockchain::finalize_header(self, id, None)
 	}
 
+	fn cht_size(&self) -> NumberFor<Block> {
+		crate::cht::size()
+	}
+
 	fn header_cht_root(
 		&self,
 		_cht_size: NumberFor<Block>,
@@ -101,7 +101,7 @@ pub use crate::{
 		new_in_mem,
 		BlockBackend, ImportNotifications, FinalityNotifications, BlockchainEvents, LockImportRun,
 		BlockImportNotification, Client, ClientInfo, ExecutionStrategies, FinalityNotification,
-		LongestChain, BlockOf, ProvideUncles, BadBlocks, ForkBlocks, apply_aux,
+		LongestChain, BlockOf, ProvideUncles, BadBlocks, ForkBlocks, LightSyncCheckpoint, apply_aux,
 	},
 	leaves::LeafSet,
 };
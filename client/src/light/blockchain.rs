@@ -40,7 +40,6 @@ pub use sc_client_api::{
 		RemoteBlockchain, LocalOrRemote, Storage
 	}
 };
-use crate::cht;
 use crate::light::fetcher::{Fetcher, RemoteHeaderRequest};
 
 /// Light client blockchain.
@@ -164,7 +163,7 @@ impl<S, Block: BlockT> RemoteBlockchain<Block> for Blockchain<S>
 		}
 
 		Ok(LocalOrRemote::Remote(RemoteHeaderRequest {
-			cht_root: match self.storage.header_cht_root(cht::size(), number)? {
+			cht_root: match self.storage.header_cht_root(self.storage.cht_size(), number)? {
 				Some(cht_root) => cht_root,
 				None => return Ok(LocalOrRemote::Unknown),
 			},
@@ -200,6 +199,7 @@ pub mod tests {
 	use parking_lot::Mutex;
 	use substrate_test_runtime_client::runtime::{Hash, Block, Header};
 	use sc_client_api::blockchain::Info;
+	use crate::cht;
 	use super::*;
 
 	pub type DummyBlockchain = Blockchain<DummyStorage>;
@@ -289,6 +289,14 @@ pub mod tests {
 			Ok(())
 		}
 
+		fn import_checkpoint(
+			&self,
+			_header: Header,
+			_header_cht_roots: Vec<Hash>,
+		) -> ClientResult<()> {
+			Err(ClientError::Backend("Test error".into()))
+		}
+
 		fn set_head(&self, _block: BlockId<Block>) -> ClientResult<()> {
 			Err(ClientError::Backend("Test error".into()))
 		}
@@ -301,6 +309,10 @@ pub mod tests {
 			Err(ClientError::Backend("Test error".into()))
 		}
 
+		fn cht_size(&self) -> u64 {
+			cht::size()
+		}
+
 		fn header_cht_root(&self, _cht_size: u64, _block: u64) -> ClientResult<Option<Hash>> {
 			Err(ClientError::Backend("Test error".into()))
 		}
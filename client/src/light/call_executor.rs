@@ -23,12 +23,14 @@ use std::{
 use codec::{Encode, Decode};
 use sp_core::{convert_hash, NativeOrEncoded, traits::CodeExecutor};
 use sp_runtime::{
-	generic::BlockId, traits::{One, Block as BlockT, Header as HeaderT, HashFor},
+	generic::BlockId,
+	traits::{One, Block as BlockT, Header as HeaderT, HashFor, UniqueSaturatedInto},
 };
 use sp_externalities::Extensions;
 use sp_state_machine::{
 	self, Backend as StateBackend, OverlayedChanges, ExecutionStrategy, create_proof_check_backend,
 	execution_proof_check_on_trie_backend, ExecutionManager, StorageProof, CloneableSpawn,
+	BasicExternalities,
 };
 use hash_db::Hasher;
 
@@ -41,7 +43,9 @@ use sc_client_api::{
 	light::RemoteCallRequest,
 	call_executor::CallExecutor,
 };
-use sc_executor::{RuntimeVersion, NativeVersion};
+use sc_executor::{RuntimeVersion, RuntimeInfo, NativeVersion};
+
+use crate::call_executor::CodeSubstitutes;
 
 /// Call executor that is able to execute calls only on genesis state.
 ///
@@ -222,13 +226,38 @@ pub fn check_execution_proof<Header, E, H>(
 ) -> ClientResult<Vec<u8>>
 	where
 		Header: HeaderT,
-		E: CodeExecutor + Clone + 'static,
+		E: CodeExecutor + RuntimeInfo + Clone + 'static,
+		H: Hasher,
+		H::Out: Ord + codec::Codec + 'static,
+{
+	check_execution_proof_with_code_substitutes::<Header, E, H>(
+		executor,
+		spawn_handle,
+		&Default::default(),
+		request,
+		remote_proof,
+	)
+}
+
+/// Check remote contextual execution proof like `check_execution_proof`, executing the given code
+/// substitutes instead of the on-chain runtime.
+pub(crate) fn check_execution_proof_with_code_substitutes<Header, E, H>(
+	executor: &E,
+	spawn_handle: Box<dyn CloneableSpawn>,
+	code_substitutes: &CodeSubstitutes,
+	request: &RemoteCallRequest<Header>,
+	remote_proof: StorageProof,
+) -> ClientResult<Vec<u8>>
+	where
+		Header: HeaderT,
+		E: CodeExecutor + RuntimeInfo + Clone + 'static,
 		H: Hasher,
 		H::Out: Ord + codec::Codec + 'static,
 {
 	check_execution_proof_with_make_header::<Header, E, H, _>(
 		executor,
 		spawn_handle,
+		code_substitutes,
 		request,
 		remote_proof,
 		|header| <Header as HeaderT>::new(
@@ -244,13 +273,14 @@ pub fn check_execution_proof<Header, E, H>(
 fn check_execution_proof_with_make_header<Header, E, H, MakeNextHeader: Fn(&Header) -> Header>(
 	executor: &E,
 	spawn_handle: Box<dyn CloneableSpawn>,
+	code_substitutes: &CodeSubstitutes,
 	request: &RemoteCallRequest<Header>,
 	remote_proof: StorageProof,
 	make_next_header: MakeNextHeader,
 ) -> ClientResult<Vec<u8>>
 	where
 		Header: HeaderT,
-		E: CodeExecutor + Clone + 'static,
+		E: CodeExecutor + RuntimeInfo + Clone + 'static,
 		H: Hasher,
 		H::Out: Ord + codec::Codec + 'static,
 {
@@ -264,7 +294,13 @@ fn check_execution_proof_with_make_header<Header, E, H, MakeNextHeader: Fn(&Head
 
 	// TODO: Remove when solved: https://github.com/paritytech/substrate/issues/5047
 	let backend_runtime_code = sp_state_machine::backend::BackendRuntimeCode::new(&trie_backend);
-	let runtime_code = backend_runtime_code.runtime_code()?;
+	let runtime_code = code_substitutes.runtime_code(
+		(*request.header.number()).unique_saturated_into(),
+		backend_runtime_code.runtime_code()?,
+		// the version of a runtime doesn't depend on the state.
+		|code| executor.runtime_version(&mut BasicExternalities::default(), code)
+			.ok().map(|v| v.spec_version),
+	);
 
 	execution_proof_check_on_trie_backend::<H, Header::Number, _>(
 		&trie_backend,
@@ -421,6 +457,7 @@ mod tests {
 			let execution_result = check_execution_proof_with_make_header::<_, _, BlakeTwo256, _>(
 				&local_executor(),
 				tasks_executor(),
+				&Default::default(),
 				&RemoteCallRequest {
 					block: substrate_test_runtime_client::runtime::Hash::default(),
 					header: remote_header,
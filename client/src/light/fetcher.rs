@@ -22,7 +22,12 @@ use std::marker::PhantomData;
 
 use hash_db::{HashDB, Hasher, EMPTY_PREFIX};
 use codec::{Decode, Encode};
+use log::warn;
+use lru::LruCache;
+use parking_lot::Mutex;
+use prometheus_endpoint::{register, CounterVec, Opts, PrometheusError, Registry, U64};
 use sp_core::{convert_hash, traits::CodeExecutor};
+use sc_executor::RuntimeInfo;
 use sp_runtime::traits::{
 	Block as BlockT, Header as HeaderT, Hash, HashFor, NumberFor,
 	AtLeast32Bit, CheckedConversion,
@@ -44,24 +49,102 @@ pub use sc_client_api::{
 	},
 };
 use crate::light::blockchain::{Blockchain};
-use crate::light::call_executor::check_execution_proof;
+use crate::light::call_executor::check_execution_proof_with_code_substitutes;
+use crate::call_executor::CodeSubstitutes;
+
+/// The number of CHT entries whose proof has been checked that are kept in the cache.
+const VERIFIED_CHT_ENTRIES_CACHE_SIZE: usize = 4096;
 
 /// Remote data checker.
 pub struct LightDataChecker<E, H, B: BlockT, S: BlockchainStorage<B>> {
 	blockchain: Arc<Blockchain<S>>,
 	executor: E,
 	spawn_handle: Box<dyn CloneableSpawn>,
+	/// The hashes proven to be in the CHTs, by CHT root and block number.
+	///
+	/// The headers and changes trie roots proven once are not checked again when a remote node
+	/// sends them back, e.g. when the same blocks are queried repeatedly.
+	verified_cht_entries: Mutex<LruCache<(B::Hash, NumberFor<B>), B::Hash>>,
+	code_substitutes: CodeSubstitutes,
+	metrics: Option<Metrics>,
 	_hasher: PhantomData<(B, H)>,
 }
 
+/// Prometheus metrics of the light data checker.
+struct Metrics {
+	failed_checks: CounterVec<U64>,
+}
+
+impl Metrics {
+	fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			failed_checks: register(
+				CounterVec::new(
+					Opts::new(
+						"light_fetch_checks_failed_total",
+						"Number of the responses of remote nodes whose proof is invalid",
+					),
+					&["request"],
+				)?,
+				registry,
+			)?,
+		})
+	}
+}
+
 impl<E, H, B: BlockT, S: BlockchainStorage<B>> LightDataChecker<E, H, B, S> {
 	/// Create new light data checker.
-	pub fn new(blockchain: Arc<Blockchain<S>>, executor: E, spawn_handle: Box<dyn CloneableSpawn>) -> Self {
+	pub fn new(
+		blockchain: Arc<Blockchain<S>>,
+		executor: E,
+		spawn_handle: Box<dyn CloneableSpawn>,
+		prometheus_registry: Option<Registry>,
+	) -> Self {
 		Self {
-			blockchain, executor, spawn_handle, _hasher: PhantomData
+			blockchain,
+			executor,
+			spawn_handle,
+			verified_cht_entries: Mutex::new(LruCache::new(VERIFIED_CHT_ENTRIES_CACHE_SIZE)),
+			code_substitutes: Default::default(),
+			metrics: prometheus_registry.and_then(|registry| match Metrics::register(&registry) {
+				Ok(metrics) => Some(metrics),
+				Err(e) => {
+					warn!("Failed to register the light data checker metrics: {:?}", e);
+					None
+				},
+			}),
+			_hasher: PhantomData
 		}
 	}
 
+	/// Execute the given runtime codes instead of the on-chain one when checking the remote calls,
+	/// like the local calls of `LocalCallExecutor::with_code_substitutes`.
+	pub fn with_code_substitutes(
+		mut self,
+		code_substitutes: BTreeMap<u64, Vec<u8>>,
+	) -> ClientResult<Self> where E: RuntimeInfo {
+		self.code_substitutes = CodeSubstitutes::new(code_substitutes, &self.executor)?;
+		Ok(self)
+	}
+
+	/// Whether the hash has already been proven to be the one of the block in the CHT.
+	fn is_verified_cht_entry(&self, cht_root: B::Hash, block: NumberFor<B>, hash: B::Hash) -> bool {
+		self.verified_cht_entries.lock().get(&(cht_root, block)) == Some(&hash)
+	}
+
+	/// Remember that the hash has been proven to be the one of the block in the CHT.
+	fn on_verified_cht_entry(&self, cht_root: B::Hash, block: NumberFor<B>, hash: B::Hash) {
+		self.verified_cht_entries.lock().put((cht_root, block), hash);
+	}
+
+	/// Count the check of the response to the request in the metrics if it failed.
+	fn note_check<T>(&self, request: &str, result: ClientResult<T>) -> ClientResult<T> {
+		if let (Err(_), Some(metrics)) = (&result, &self.metrics) {
+			metrics.failed_checks.with_label_values(&[request]).inc();
+		}
+		result
+	}
+
 	/// Check remote changes query proof assuming that CHT-s are of given size.
 	fn check_changes_proof_with_cht_size(
 		&self,
@@ -172,6 +255,12 @@ impl<E, H, B: BlockT, S: BlockchainStorage<B>> LightDataChecker<E, H, B, S> {
 
 			// check changes trie root for every block within CHT range
 			for block in cht_blocks {
+				// skip the roots that have already been proven
+				let remote_changes_trie_root = remote_roots[&block];
+				if self.is_verified_cht_entry(local_cht_root, block, remote_changes_trie_root) {
+					continue;
+				}
+
 				// check if the proofs storage contains the root
 				// normally this happens in when the proving backend is created, but since
 				// we share the storage for multiple checks, do it here
@@ -183,13 +272,13 @@ impl<E, H, B: BlockT, S: BlockchainStorage<B>> LightDataChecker<E, H, B, S> {
 
 				// check proof for single changes trie root
 				let proving_backend = TrieBackend::new(storage, cht_root);
-				let remote_changes_trie_root = remote_roots[&block];
 				cht::check_proof_on_proving_backend::<B::Header, H>(
 					local_cht_root,
 					block,
 					remote_changes_trie_root,
 					&proving_backend,
 				)?;
+				self.on_verified_cht_entry(local_cht_root, block, remote_changes_trie_root);
 
 				// and return the storage to use in following checks
 				storage = proving_backend.into_storage();
@@ -203,7 +292,7 @@ impl<E, H, B: BlockT, S: BlockchainStorage<B>> LightDataChecker<E, H, B, S> {
 impl<E, Block, H, S> FetchChecker<Block> for LightDataChecker<E, H, Block, S>
 	where
 		Block: BlockT,
-		E: CodeExecutor + Clone + 'static,
+		E: CodeExecutor + RuntimeInfo + Clone + 'static,
 		H: Hasher,
 		H::Out: Ord + codec::Codec + 'static,
 		S: BlockchainStorage<Block>,
@@ -215,14 +304,23 @@ impl<E, Block, H, S> FetchChecker<Block> for LightDataChecker<E, H, Block, S>
 		remote_proof: StorageProof,
 	) -> ClientResult<Block::Header> {
 		let remote_header = remote_header.ok_or_else(||
-			ClientError::from(ClientError::InvalidCHTProof))?;
-		let remote_header_hash = remote_header.hash();
-		cht::check_proof::<Block::Header, H>(
-			request.cht_root,
-			request.block,
-			remote_header_hash,
-			remote_proof,
-		).map(|_| remote_header)
+			ClientError::from(ClientError::InvalidCHTProof));
+		let result = remote_header.and_then(|remote_header| {
+			let remote_header_hash = remote_header.hash();
+			if self.is_verified_cht_entry(request.cht_root, request.block, remote_header_hash) {
+				return Ok(remote_header);
+			}
+
+			cht::check_proof::<Block::Header, H>(
+				request.cht_root,
+				request.block,
+				remote_header_hash,
+				remote_proof,
+			)?;
+			self.on_verified_cht_entry(request.cht_root, request.block, remote_header_hash);
+			Ok(remote_header)
+		});
+		self.note_check("header", result)
 	}
 
 	fn check_read_proof(
@@ -230,11 +328,12 @@ impl<E, Block, H, S> FetchChecker<Block> for LightDataChecker<E, H, Block, S>
 		request: &RemoteReadRequest<Block::Header>,
 		remote_proof: StorageProof,
 	) -> ClientResult<HashMap<Vec<u8>, Option<Vec<u8>>>> {
-		read_proof_check::<H, _>(
+		let result = read_proof_check::<H, _>(
 			convert_hash(request.header.state_root()),
 			remote_proof,
 			request.keys.iter(),
-		).map_err(Into::into)
+		).map_err(Into::into);
+		self.note_check("read", result)
 	}
 
 	fn check_read_child_proof(
@@ -242,12 +341,13 @@ impl<E, Block, H, S> FetchChecker<Block> for LightDataChecker<E, H, Block, S>
 		request: &RemoteReadChildRequest<Block::Header>,
 		remote_proof: StorageProof,
 	) -> ClientResult<HashMap<Vec<u8>, Option<Vec<u8>>>> {
-		read_child_proof_check::<H, _>(
+		let result = read_child_proof_check::<H, _>(
 			convert_hash(request.header.state_root()),
 			remote_proof,
 			&request.storage_key,
 			request.keys.iter(),
-		).map_err(Into::into)
+		).map_err(Into::into);
+		self.note_check("read_child", result)
 	}
 
 	fn check_execution_proof(
@@ -255,12 +355,14 @@ impl<E, Block, H, S> FetchChecker<Block> for LightDataChecker<E, H, Block, S>
 		request: &RemoteCallRequest<Block::Header>,
 		remote_proof: StorageProof,
 	) -> ClientResult<Vec<u8>> {
-		check_execution_proof::<_, _, H>(
+		let result = check_execution_proof_with_code_substitutes::<_, _, H>(
 			&self.executor,
 			self.spawn_handle.clone(),
+			&self.code_substitutes,
 			request,
 			remote_proof,
-		)
+		);
+		self.note_check("call", result)
 	}
 
 	fn check_changes_proof(
@@ -268,7 +370,9 @@ impl<E, Block, H, S> FetchChecker<Block> for LightDataChecker<E, H, Block, S>
 		request: &RemoteChangesRequest<Block::Header>,
 		remote_proof: ChangesProof<Block::Header>
 	) -> ClientResult<Vec<(NumberFor<Block>, u32)>> {
-		self.check_changes_proof_with_cht_size(request, remote_proof, cht::size())
+		let cht_size = self.blockchain.storage().cht_size();
+		let result = self.check_changes_proof_with_cht_size(request, remote_proof, cht_size);
+		self.note_check("changes", result)
 	}
 
 	fn check_body_proof(
@@ -280,15 +384,15 @@ impl<E, Block, H, S> FetchChecker<Block> for LightDataChecker<E, H, Block, S>
 		let extrinsics_root = HashFor::<Block>::ordered_trie_root(
 			body.iter().map(Encode::encode).collect(),
 		);
-		if *request.header.extrinsics_root() == extrinsics_root {
+		let result = if *request.header.extrinsics_root() == extrinsics_root {
 			Ok(body)
 		} else {
 			Err(format!("RemoteBodyRequest: invalid extrinsics root expected: {} but got {}",
 				*request.header.extrinsics_root(),
 				extrinsics_root,
 			).into())
-		}
-
+		};
+		self.note_check("body", result)
 	}
 }
 
@@ -404,6 +508,7 @@ pub mod tests {
 			Arc::new(DummyBlockchain::new(DummyStorage::new())),
 			local_executor(),
 			tasks_executor(),
+			None,
 		);
 		(local_checker, remote_block_header, remote_read_proof, heap_pages)
 	}
@@ -453,6 +558,7 @@ pub mod tests {
 			Arc::new(DummyBlockchain::new(DummyStorage::new())),
 			local_executor(),
 			tasks_executor(),
+			None,
 		);
 		(local_checker, remote_block_header, remote_read_proof, child_value)
 	}
@@ -484,6 +590,7 @@ pub mod tests {
 			Arc::new(DummyBlockchain::new(DummyStorage::new())),
 			local_executor(),
 			tasks_executor(),
+			None,
 		);
 		(local_checker, local_cht_root, remote_block_header, remote_header_proof)
 	}
@@ -563,6 +670,32 @@ pub mod tests {
 		}, Some(remote_block_header.clone()), remote_header_proof).is_err());
 	}
 
+	#[test]
+	fn verified_headers_are_not_checked_again() {
+		let (local_checker, local_cht_root, remote_block_header, remote_header_proof) =
+			prepare_for_header_proof_check(true);
+		let local_checker = &local_checker as &dyn FetchChecker<Block>;
+		let request = RemoteHeaderRequest::<Header> {
+			cht_root: local_cht_root,
+			block: 1,
+			retry_count: None,
+		};
+		let check = |header: &Header, proof| {
+			local_checker.check_header_proof(&request, Some(header.clone()), proof)
+		};
+		assert!(check(&remote_block_header, StorageProof::empty()).is_err());
+		assert!(check(&remote_block_header, remote_header_proof).is_ok());
+
+		// the proof of the header has been checked once
+		let checked_header = check(&remote_block_header, StorageProof::empty()).unwrap();
+		assert_eq!(checked_header, remote_block_header);
+
+		// but not the proof of other headers
+		let mut other_header = remote_block_header;
+		other_header.number = 100;
+		assert!(check(&other_header, StorageProof::empty()).is_err());
+	}
+
 	#[test]
 	fn changes_proof_is_generated_and_checked_when_headers_are_not_pruned() {
 		let (remote_client, local_roots, test_cases) = prepare_client_with_key_changes();
@@ -570,6 +703,7 @@ pub mod tests {
 			Arc::new(DummyBlockchain::new(DummyStorage::new())),
 			local_executor(),
 			tasks_executor(),
+			None,
 		);
 		let local_checker = &local_checker as &dyn FetchChecker<Block>;
 		let max = remote_client.chain_info().best_number;
@@ -645,6 +779,7 @@ pub mod tests {
 			Arc::new(DummyBlockchain::new(local_storage)),
 			local_executor(),
 			tasks_executor(),
+			None,
 		);
 
 		// check proof on local client
@@ -680,6 +815,7 @@ pub mod tests {
 			Arc::new(DummyBlockchain::new(DummyStorage::new())),
 			local_executor(),
 			tasks_executor(),
+			None,
 		);
 		let local_checker = &local_checker as &dyn FetchChecker<Block>;
 		let max = remote_client.chain_info().best_number;
@@ -768,6 +904,7 @@ pub mod tests {
 			Arc::new(DummyBlockchain::new(DummyStorage::new())),
 			local_executor(),
 			tasks_executor(),
+			None,
 		);
 		assert!(local_checker.check_changes_tries_proof(4, &remote_proof.roots,
 			remote_proof.roots_proof.clone()).is_err());
@@ -779,6 +916,7 @@ pub mod tests {
 			Arc::new(DummyBlockchain::new(local_storage)),
 			local_executor(),
 			tasks_executor(),
+			None,
 		);
 		let result = local_checker.check_changes_tries_proof(
 			4, &remote_proof.roots, StorageProof::empty()
@@ -797,6 +935,7 @@ pub mod tests {
 			Arc::new(DummyBlockchain::new(DummyStorage::new())),
 			local_executor(),
 			tasks_executor(),
+			None,
 		);
 
 		let body_request = RemoteBodyRequest {
@@ -821,6 +960,7 @@ pub mod tests {
 			Arc::new(DummyBlockchain::new(DummyStorage::new())),
 			local_executor(),
 			tasks_executor(),
+			None,
 		);
 
 		let body_request = RemoteBodyRequest {
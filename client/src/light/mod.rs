@@ -21,19 +21,20 @@ pub mod blockchain;
 pub mod call_executor;
 pub mod fetcher;
 
-use std::sync::Arc;
+use std::{sync::Arc, collections::BTreeMap};
 
 use sc_executor::RuntimeInfo;
 use sp_core::traits::CodeExecutor;
 use sp_runtime::BuildStorage;
-use sp_runtime::traits::{Block as BlockT, HashFor};
-use sp_blockchain::Result as ClientResult;
+use sp_runtime::traits::{Block as BlockT, HashFor, Zero};
+use sp_blockchain::{HeaderBackend, Result as ClientResult};
 use prometheus_endpoint::Registry;
 
 use crate::call_executor::LocalCallExecutor;
 use crate::client::Client;
 use sc_client_api::{
-	light::Storage as BlockchainStorage, CloneableSpawn,
+	light::Storage as BlockchainStorage, CloneableSpawn, ForkBlocks, BadBlocks,
+	LightSyncCheckpoint,
 };
 use crate::light::backend::Backend;
 use crate::light::blockchain::Blockchain;
@@ -55,12 +56,24 @@ pub fn new_light_backend<B, S>(blockchain: Arc<Blockchain<S>>) -> Arc<Backend<S,
 }
 
 /// Create an instance of light client.
+///
+/// Like a full client, it refuses the headers that don't match the fork blocks or are known bad.
+/// A client that hasn't synced any block yet syncs from the checkpoint, if any, rather than from
+/// the genesis block.
+///
+/// The given runtime codes are executed instead of the on-chain one from the given block numbers,
+/// see `LocalCallExecutor::with_code_substitutes`. The remote calls are checked with the same
+/// substitutes by `LightDataChecker::with_code_substitutes`.
 pub fn new_light<B, S, RA, E>(
 	backend: Arc<Backend<S, HashFor<B>>>,
 	genesis_storage: &dyn BuildStorage,
+	fork_blocks: ForkBlocks<B>,
+	bad_blocks: BadBlocks<B>,
+	checkpoint: LightSyncCheckpoint<B>,
 	code_executor: E,
 	spawn_handle: Box<dyn CloneableSpawn>,
 	prometheus_registry: Option<Registry>,
+	code_substitutes: BTreeMap<u64, Vec<u8>>,
 ) -> ClientResult<
 		Client<
 			Backend<S, HashFor<B>>,
@@ -77,27 +90,43 @@ pub fn new_light<B, S, RA, E>(
 		S: BlockchainStorage<B> + 'static,
 		E: CodeExecutor + RuntimeInfo + Clone + 'static,
 {
-	let local_executor = LocalCallExecutor::new(backend.clone(), code_executor, spawn_handle.clone());
+	let local_executor = LocalCallExecutor::new(backend.clone(), code_executor, spawn_handle.clone())
+		.with_code_substitutes(code_substitutes)?;
 	let executor = GenesisCallExecutor::new(backend.clone(), local_executor);
-	Client::new(
+	let cht_size = backend.blockchain().storage().cht_size();
+	let blockchain = backend.blockchain().clone();
+	let client = Client::new(
 		backend,
 		executor,
 		genesis_storage,
+		fork_blocks,
+		bad_blocks,
 		Default::default(),
-		Default::default(),
-		Default::default(),
+		cht_size,
 		prometheus_registry,
-	)
+	)?;
+
+	// the genesis block is only imported by the client, so the checkpoint is imported on top of it.
+	if let Some((header, header_cht_roots)) = checkpoint {
+		if blockchain.info().best_number.is_zero() {
+			blockchain.storage().import_checkpoint(header, header_cht_roots)?;
+		}
+	}
+
+	Ok(client)
 }
 
 /// Create an instance of fetch data checker.
+///
+/// The responses whose proof is invalid are counted in the metrics of the registry.
 pub fn new_fetch_checker<E, B: BlockT, S: BlockchainStorage<B>>(
 	blockchain: Arc<Blockchain<S>>,
 	executor: E,
 	spawn_handle: Box<dyn CloneableSpawn>,
+	prometheus_registry: Option<Registry>,
 ) -> LightDataChecker<E, HashFor<B>, B, S>
 	where
 		E: CodeExecutor,
 {
-	LightDataChecker::new(blockchain, executor, spawn_handle)
+	LightDataChecker::new(blockchain, executor, spawn_handle, prometheus_registry)
 }
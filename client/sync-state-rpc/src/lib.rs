@@ -0,0 +1,167 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A RPC handler to create sync states for light clients.
+//!
+//! The sync state is the last finalized block along with the BABE and GRANDPA data needed to
+//! verify its descendants, so that light clients can start syncing from it instead of from the
+//! genesis block. It is only meaningful for chains running BABE and GRANDPA.
+//!
+//! The BABE epoch of the finalized block and the one after it are included on their own as
+//! well, so that light clients can verify the slot claims of the next blocks from their
+//! authorities and randomness without decoding the epoch changes tree.
+
+#![warn(missing_docs)]
+
+use std::sync::Arc;
+
+use jsonrpc_derive::rpc;
+use parity_scale_codec::Encode;
+use sc_chain_spec::SerializableLightSyncState;
+use sc_client_api::AuxStore;
+use sc_rpc_api::DenyUnsafe;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_consensus_babe::BabeApi;
+use sp_runtime::generic::BlockId;
+use sp_runtime::traits::{Block as BlockT, NumberFor, Zero};
+
+type SharedAuthoritySet<TBl> =
+	sc_finality_grandpa::SharedAuthoritySet<<TBl as BlockT>::Hash, NumberFor<TBl>>;
+type SharedEpochChanges<TBl> =
+	sc_consensus_epochs::SharedEpochChanges<TBl, sc_consensus_babe::Epoch>;
+
+/// Errors encountered while generating the sync state.
+#[derive(Debug, derive_more::Display, derive_more::From)]
+pub enum Error {
+	/// Client error.
+	#[display(fmt = "Client error: {}", _0)]
+	Client(sp_blockchain::Error),
+	/// The BABE weight of the finalized block isn't known.
+	#[display(fmt = "Failed to load the BABE weight of block {}", _0)]
+	#[from(ignore)]
+	MissingBlockWeight(String),
+	/// The BABE epochs couldn't be fetched from the runtime.
+	#[display(fmt = "Failed to fetch the BABE epochs: {}", _0)]
+	#[from(ignore)]
+	RuntimeApi(String),
+	/// The chain spec couldn't be generated.
+	#[display(fmt = "Failed to generate the chain spec: {}", _0)]
+	#[from(ignore)]
+	ChainSpec(String),
+}
+
+impl From<Error> for jsonrpc_core::Error {
+	fn from(error: Error) -> Self {
+		jsonrpc_core::Error {
+			message: format!("{}", error),
+			code: jsonrpc_core::ErrorCode::ServerError(1),
+			data: None,
+		}
+	}
+}
+
+/// An api for sync state RPC calls.
+#[rpc]
+pub trait SyncStateRpcApi {
+	/// Returns the JSON chain spec the node is running, along with the sync state of the last
+	/// finalized block.
+	#[rpc(name = "sync_state_genSyncSpec")]
+	fn system_gen_sync_spec(&self, raw: bool) -> jsonrpc_core::Result<jsonrpc_core::Value>;
+}
+
+/// The handler for sync state RPC calls.
+pub struct SyncStateRpcHandler<TBl: BlockT, TCl> {
+	chain_spec: Box<dyn sc_chain_spec::ChainSpec>,
+	client: Arc<TCl>,
+	shared_authority_set: SharedAuthoritySet<TBl>,
+	shared_epoch_changes: SharedEpochChanges<TBl>,
+	deny_unsafe: DenyUnsafe,
+}
+
+impl<TBl, TCl> SyncStateRpcHandler<TBl, TCl>
+	where
+		TBl: BlockT,
+		TCl: HeaderBackend<TBl> + AuxStore + ProvideRuntimeApi<TBl> + Send + Sync + 'static,
+		TCl::Api: BabeApi<TBl>,
+		<TCl::Api as sp_api::ApiErrorExt>::Error: std::fmt::Debug,
+{
+	/// Create a new handler.
+	pub fn new(
+		chain_spec: Box<dyn sc_chain_spec::ChainSpec>,
+		client: Arc<TCl>,
+		shared_authority_set: SharedAuthoritySet<TBl>,
+		shared_epoch_changes: SharedEpochChanges<TBl>,
+		deny_unsafe: DenyUnsafe,
+	) -> Self {
+		Self {
+			chain_spec,
+			client,
+			shared_authority_set,
+			shared_epoch_changes,
+			deny_unsafe,
+		}
+	}
+
+	fn build_sync_state(&self) -> Result<SerializableLightSyncState, Error> {
+		let info = self.client.info();
+		let finalized_header = self.client.header(BlockId::Hash(info.finalized_hash))?
+			.ok_or_else(|| sp_blockchain::Error::UnknownBlock(info.finalized_hash.to_string()))?;
+
+		// the weight of the genesis block is never stored, as no block was authored for it.
+		let finalized_block_weight = sc_consensus_babe::aux_schema::load_block_weight(
+			&*self.client,
+			info.finalized_hash,
+		)?
+			.or_else(|| if info.finalized_number.is_zero() { Some(0) } else { None })
+			.ok_or_else(|| Error::MissingBlockWeight(info.finalized_hash.to_string()))?;
+
+		let at = BlockId::Hash(info.finalized_hash);
+		let runtime_api = self.client.runtime_api();
+		let babe_current_epoch = runtime_api.current_epoch(&at)
+			.map_err(|e| Error::RuntimeApi(format!("{:?}", e)))?;
+		let babe_next_epoch = runtime_api.next_epoch(&at)
+			.map_err(|e| Error::RuntimeApi(format!("{:?}", e)))?;
+
+		Ok(SerializableLightSyncState {
+			finalized_block_header: finalized_header.encode().into(),
+			babe_epoch_changes: self.shared_epoch_changes.lock().encode().into(),
+			babe_finalized_block_weight: finalized_block_weight,
+			babe_current_epoch: babe_current_epoch.encode().into(),
+			babe_next_epoch: babe_next_epoch.encode().into(),
+			grandpa_authority_set: self.shared_authority_set.clone_inner().encode().into(),
+		})
+	}
+}
+
+impl<TBl, TCl> SyncStateRpcApi for SyncStateRpcHandler<TBl, TCl>
+	where
+		TBl: BlockT,
+		TCl: HeaderBackend<TBl> + AuxStore + ProvideRuntimeApi<TBl> + Send + Sync + 'static,
+		TCl::Api: BabeApi<TBl>,
+		<TCl::Api as sp_api::ApiErrorExt>::Error: std::fmt::Debug,
+{
+	fn system_gen_sync_spec(&self, raw: bool) -> jsonrpc_core::Result<jsonrpc_core::Value> {
+		self.deny_unsafe.check_if_safe()?;
+
+		let mut chain_spec = self.chain_spec.cloned_box();
+		chain_spec.set_light_sync_state(self.build_sync_state()?);
+
+		let json = chain_spec.as_json(raw).map_err(Error::ChainSpec)?;
+		serde_json::from_str(&json)
+			.map_err(|e| Error::ChainSpec(e.to_string()).into())
+	}
+}
@@ -30,6 +30,11 @@
 //! will also deregister the global logger and replace it with a logger that discards messages.
 //! The `Stream` generates [`TelemetryEvent`]s.
 //!
+//! Each endpoint only receives the messages whose verbosity is lower than or equal to its own.
+//! Messages never wait for a slow endpoint: they are dropped when its queue is full or when it is
+//! disconnected, and counted by [`Telemetry::dropped_messages`]. Disconnected endpoints are
+//! reconnected to with an exponential backoff.
+//!
 //! > **Note**: Cloning the [`Telemetry`] and polling from multiple clones has an unspecified behaviour.
 //!
 //! # Example
@@ -64,6 +69,7 @@ use log::{error, warn};
 use parking_lot::Mutex;
 use serde::{Serialize, Deserialize};
 use std::{pin::Pin, sync::Arc, task::{Context, Poll}, time::Duration};
+use std::sync::atomic::{AtomicU64, Ordering};
 use wasm_timer::Instant;
 
 pub use libp2p::wasm_ext::ExtTransport;
@@ -120,6 +126,8 @@ pub const CONSENSUS_INFO: &str = "1";
 #[derive(Clone)]
 pub struct Telemetry {
 	inner: Arc<Mutex<TelemetryInner>>,
+	/// Number of messages that have been dropped instead of being sent to an endpoint.
+	dropped_messages: Arc<AtomicU64>,
 	/// Slog guard so that we don't get deregistered.
 	_guard: Arc<slog_scope::GlobalLoggerGuard>,
 }
@@ -141,6 +149,8 @@ struct TelemetryInner {
 struct TelemetryDrain {
 	/// Sends log entries.
 	sender: std::panic::AssertUnwindSafe<mpsc::Sender<async_record::AsyncRecord>>,
+	/// Incremented when the channel is full.
+	dropped_messages: Arc<AtomicU64>,
 }
 
 /// Initializes the telemetry. See the crate root documentation for more information.
@@ -157,14 +167,23 @@ pub fn init_telemetry(config: TelemetryConfig) -> Telemetry {
 		}
 	}
 
+	let dropped_messages = Arc::new(AtomicU64::new(0));
 	let (sender, receiver) = mpsc::channel(16);
 	let guard = {
-		let logger = TelemetryDrain { sender: std::panic::AssertUnwindSafe(sender) };
+		let logger = TelemetryDrain {
+			sender: std::panic::AssertUnwindSafe(sender),
+			dropped_messages: dropped_messages.clone(),
+		};
 		let root = slog::Logger::root(slog::Drain::fuse(logger), slog::o!());
 		slog_scope::set_global_logger(root)
 	};
 
-	let worker = match worker::TelemetryWorker::new(endpoints, config.wasm_external_transport) {
+	let worker = worker::TelemetryWorker::new(
+		endpoints,
+		config.wasm_external_transport,
+		dropped_messages.clone(),
+	);
+	let worker = match worker {
 		Ok(w) => Some(w),
 		Err(err) => {
 			error!(target: "telemetry", "Failed to initialize telemetry worker: {:?}", err);
@@ -177,10 +196,21 @@ pub fn init_telemetry(config: TelemetryConfig) -> Telemetry {
 			worker,
 			receiver,
 		})),
+		dropped_messages,
 		_guard: Arc::new(guard),
 	}
 }
 
+impl Telemetry {
+	/// Returns the number of messages that have been dropped since the telemetry was initialized,
+	/// because an endpoint was unreachable or too slow to process them.
+	///
+	/// A message dropped for several endpoints is counted once for each of them.
+	pub fn dropped_messages(&self) -> u64 {
+		self.dropped_messages.load(Ordering::Relaxed)
+	}
+}
+
 /// Event generated when polling the worker.
 #[derive(Debug)]
 pub enum TelemetryEvent {
@@ -260,6 +290,7 @@ impl slog::Drain for TelemetryDrain {
 		// Note: interestingly, `try_send` requires a `&mut` because it modifies some internal value, while `clone()`
 		// is lock-free.
 		if let Err(err) = self.sender.clone().try_send(serialized) {
+			self.dropped_messages.fetch_add(1, Ordering::Relaxed);
 			warn!(target: "telemetry", "Ignored telemetry message because of error on channel: {:?}", err);
 		}
 
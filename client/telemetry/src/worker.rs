@@ -20,8 +20,9 @@
 //!
 //! - Create a `TelemetryWorker` with `TelemetryWorker::new`.
 //! - Send messages to the telemetry with `TelemetryWorker::send_message`. Messages will only be
-//!   sent to the appropriate targets. Messages may be ignored if the target happens to be
-//!   temporarily unreachable.
+//!   sent to the appropriate targets. Messages are dropped if the target happens to be
+//!   temporarily unreachable or too slow to process them, and counted in the counter passed to
+//!   `TelemetryWorker::new`.
 //! - You must appropriately poll the worker with `TelemetryWorker::poll`. Polling will/may produce
 //!   events indicating what happened since the latest polling.
 //!
@@ -32,6 +33,7 @@ use libp2p::{core::transport::OptionalTransport, Multiaddr, Transport, wasm_ext}
 use log::{trace, warn, error};
 use slog::Drain;
 use std::{io, pin::Pin, task::Context, task::Poll, time};
+use std::sync::{Arc, atomic::{AtomicU64, Ordering}};
 
 mod node;
 
@@ -52,6 +54,8 @@ pub enum TelemetryWorkerEvent {
 pub struct TelemetryWorker {
 	/// List of nodes with their maximum verbosity level.
 	nodes: Vec<(node::Node<WsTrans>, u8)>,
+	/// Number of messages that have been dropped instead of being sent to a node.
+	dropped_messages: Arc<AtomicU64>,
 }
 
 trait StreamAndSink<I>: Stream + Sink<I> {}
@@ -72,9 +76,13 @@ impl TelemetryWorker {
 	/// The endpoints must be a list of targets, plus a verbosity level. When you send a message
 	/// to the telemetry, only the targets whose verbosity is higher than the verbosity of the
 	/// message will receive it.
+	///
+	/// Each message that a node doesn't receive because it is unreachable or its queue is full
+	/// increments `dropped_messages`.
 	pub fn new(
 		endpoints: impl IntoIterator<Item = (Multiaddr, u8)>,
-		wasm_external_transport: impl Into<Option<wasm_ext::ExtTransport>>
+		wasm_external_transport: impl Into<Option<wasm_ext::ExtTransport>>,
+		dropped_messages: Arc<AtomicU64>,
 	) -> Result<Self, io::Error> {
 		let transport = match wasm_external_transport.into() {
 			Some(t) => OptionalTransport::some(t),
@@ -115,7 +123,8 @@ impl TelemetryWorker {
 			nodes: endpoints.into_iter().map(|(addr, verbosity)| {
 				let node = node::Node::new(transport.clone(), addr);
 				(node, verbosity)
-			}).collect()
+			}).collect(),
+			dropped_messages,
 		})
 	}
 
@@ -173,8 +182,11 @@ impl TelemetryWorker {
 				continue;
 			}
 
-			// `send_message` returns an error if we're not connected, which we silently ignore.
-			let _ = node.send_message(&serialized.clone()[..]);
+			// `send_message` returns an error if we're not connected or if the node is too slow
+			// to keep up, in which case the message is dropped rather than waited upon.
+			if node.send_message(&serialized.clone()[..]).is_err() {
+				self.dropped_messages.fetch_add(1, Ordering::Relaxed);
+			}
 		}
 
 		Ok(())
@@ -25,9 +25,16 @@ use log::{trace, debug, warn, error};
 use rand::Rng as _;
 use std::{collections::VecDeque, fmt, mem, pin::Pin, task::Context, task::Poll, time::Duration};
 
-/// Maximum number of pending telemetry messages.
+/// Maximum number of pending telemetry messages. Messages sent while the queue is full are
+/// dropped rather than waiting for a slow endpoint.
 const MAX_PENDING: usize = 10;
 
+/// Delay before the first attempt to reconnect to a node.
+const INITIAL_RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Maximum delay between two attempts to reconnect to a node.
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(5 * 60);
+
 /// Handler for a single telemetry node.
 pub struct Node<TTrans: Transport> {
 	/// Address of the node.
@@ -36,6 +43,8 @@ pub struct Node<TTrans: Transport> {
 	socket: NodeSocket<TTrans>,
 	/// Transport used to establish new connections.
 	transport: TTrans,
+	/// Delay to wait before the next reconnection attempt, doubled after each failure.
+	reconnect_delay: Duration,
 }
 
 enum NodeSocket<TTrans: Transport> {
@@ -90,9 +99,18 @@ impl<TTrans: Transport> Node<TTrans> {
 			addr,
 			socket: NodeSocket::ReconnectNow,
 			transport,
+			reconnect_delay: INITIAL_RECONNECT_DELAY,
 		}
 	}
 
+	/// Returns a `Delay` for the next reconnection attempt, and increases the delay of the
+	/// following one.
+	fn next_reconnect_delay(&mut self) -> Delay {
+		let delay = gen_rand_reconnect_delay(self.reconnect_delay);
+		self.reconnect_delay = next_backoff(self.reconnect_delay);
+		Delay::new(delay)
+	}
+
 	/// Returns the address that was passed to `new`.
 	pub fn addr(&self) -> &Multiaddr {
 		&self.addr
@@ -106,17 +124,18 @@ where TTrans: Clone + Unpin, TTrans::Dial: Unpin,
 		+ Unpin,
 	TSinkErr: fmt::Debug
 {
-	/// Sends a WebSocket frame to the node. Returns an error if we are not connected to the node.
+	/// Sends a WebSocket frame to the node. Returns an error if we are not connected to the node
+	/// or if its queue is full, in which case the message is dropped.
 	///
 	/// After calling this method, you should call `poll` in order for it to be properly processed.
 	pub fn send_message(&mut self, payload: impl Into<BytesMut>) -> Result<(), ()> {
 		if let NodeSocket::Connected(NodeSocketConnected { pending, .. }) = &mut self.socket {
-			if pending.len() <= MAX_PENDING {
+			if pending.len() < MAX_PENDING {
 				trace!(target: "telemetry", "Adding log entry to queue for {:?}", self.addr);
 				pending.push_back(payload.into());
 				Ok(())
 			} else {
-				warn!(target: "telemetry", "Rejected log entry because queue is full for {:?}",
+				trace!(target: "telemetry", "Rejected log entry because queue is full for {:?}",
 					self.addr);
 				Err(())
 			}
@@ -138,7 +157,7 @@ where TTrans: Clone + Unpin, TTrans::Dial: Unpin,
 						},
 						Poll::Ready(Err(err)) => {
 							warn!(target: "telemetry", "Disconnected from {}: {:?}", self.addr, err);
-							let timeout = gen_rand_reconnect_delay();
+							let timeout = self.next_reconnect_delay();
 							self.socket = NodeSocket::WaitingReconnect(timeout);
 							return Poll::Ready(NodeEvent::Disconnected(err))
 						}
@@ -147,6 +166,7 @@ where TTrans: Clone + Unpin, TTrans::Dial: Unpin,
 				NodeSocket::Dialing(mut s) => match Future::poll(Pin::new(&mut s), cx) {
 					Poll::Ready(Ok(sink)) => {
 						debug!(target: "telemetry", "Connected to {}", self.addr);
+						self.reconnect_delay = INITIAL_RECONNECT_DELAY;
 						let conn = NodeSocketConnected {
 							sink,
 							pending: VecDeque::new(),
@@ -159,7 +179,7 @@ where TTrans: Clone + Unpin, TTrans::Dial: Unpin,
 					Poll::Pending => break NodeSocket::Dialing(s),
 					Poll::Ready(Err(err)) => {
 						warn!(target: "telemetry", "Error while dialing {}: {:?}", self.addr, err);
-						let timeout = gen_rand_reconnect_delay();
+						let timeout = self.next_reconnect_delay();
 						socket = NodeSocket::WaitingReconnect(timeout);
 					}
 				}
@@ -170,7 +190,7 @@ where TTrans: Clone + Unpin, TTrans::Dial: Unpin,
 					}
 					Err(err) => {
 						warn!(target: "telemetry", "Error while dialing {}: {:?}", self.addr, err);
-						let timeout = gen_rand_reconnect_delay();
+						let timeout = self.next_reconnect_delay();
 						socket = NodeSocket::WaitingReconnect(timeout);
 					}
 				}
@@ -191,13 +211,18 @@ where TTrans: Clone + Unpin, TTrans::Dial: Unpin,
 	}
 }
 
-/// Generates a `Delay` object with a random timeout.
+/// Generates a random timeout between the given delay and twice that delay.
 ///
 /// If there are general connection issues, not all endpoints should be synchronized in their
 /// re-connection time.
-fn gen_rand_reconnect_delay() -> Delay {
-	let random_delay = rand::thread_rng().gen_range(5, 10);
-	Delay::new(Duration::from_secs(random_delay))
+fn gen_rand_reconnect_delay(delay: Duration) -> Duration {
+	let delay = delay.as_millis() as u64;
+	Duration::from_millis(rand::thread_rng().gen_range(delay, delay * 2))
+}
+
+/// Returns the delay following `delay` in the exponential backoff between reconnections.
+fn next_backoff(delay: Duration) -> Duration {
+	std::cmp::min(delay * 2, MAX_RECONNECT_DELAY)
 }
 
 impl<TTrans: Transport, TSinkErr> NodeSocketConnected<TTrans>
@@ -302,3 +327,22 @@ impl<TTrans: Transport> fmt::Debug for Node<TTrans> {
 			.finish()
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn reconnect_delay_grows_exponentially_up_to_the_maximum() {
+		let mut delay = INITIAL_RECONNECT_DELAY;
+		let mut delays = Vec::new();
+		for _ in 0..8 {
+			let timeout = gen_rand_reconnect_delay(delay);
+			assert!(timeout >= delay && timeout < delay * 2);
+			delays.push(delay.as_secs());
+			delay = next_backoff(delay);
+		}
+
+		assert_eq!(delays, vec![5, 10, 20, 40, 80, 160, 300, 300]);
+	}
+}
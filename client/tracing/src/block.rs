@@ -0,0 +1,249 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Collection of the spans and events emitted while executing a block, so that they can be
+//! returned to the caller instead of being logged.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use tracing_core::{
+	dispatcher::{self, Dispatch},
+	event::Event,
+	field::{Visit, Field},
+	Level,
+	metadata::Metadata,
+	span::{Attributes, Id, Record},
+	subscriber::Subscriber,
+};
+use sp_rpc::tracing::{Span, Event as TraceEvent, Values};
+
+use crate::{parse_target, wasm_target_and_name};
+
+/// Run `f`, collecting the spans and events it emits for the given targets.
+///
+/// `targets` has the same format as for the `ProfilingSubscriber`. The subscriber is only set
+/// for the current thread and for the duration of `f`.
+pub fn collect_block_trace<R>(
+	targets: &str,
+	f: impl FnOnce() -> R,
+) -> (R, Vec<Span>, Vec<TraceEvent>) {
+	let dispatch = Dispatch::new(BlockSubscriber::new(targets));
+	let result = dispatcher::with_default(&dispatch, f);
+	let (spans, events) = dispatch.downcast_ref::<BlockSubscriber>()
+		.expect("the dispatch was created from a `BlockSubscriber` above; qed")
+		.take_trace();
+
+	(result, spans, events)
+}
+
+struct SpanDatum {
+	span: Span,
+	start_time: Instant,
+	overall_time: Duration,
+}
+
+struct ValuesVisitor<'a>(&'a mut Values);
+
+impl<'a> Visit for ValuesVisitor<'a> {
+	fn record_str(&mut self, field: &Field, value: &str) {
+		self.0.insert(field.name().to_string(), value.to_string());
+	}
+
+	fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+		self.0.insert(field.name().to_string(), format!("{:?}", value));
+	}
+}
+
+/// Subscriber keeping the spans and events in memory.
+///
+/// Execution is expected to happen on a single thread, which is what allows tracking the
+/// current span with a simple stack.
+struct BlockSubscriber {
+	targets: Vec<(String, Level)>,
+	next_id: AtomicU64,
+	current_spans: Mutex<Vec<u64>>,
+	spans: Mutex<BTreeMap<u64, SpanDatum>>,
+	events: Mutex<Vec<TraceEvent>>,
+}
+
+impl BlockSubscriber {
+	fn new(targets: &str) -> Self {
+		BlockSubscriber {
+			targets: targets.split(',').map(|s| parse_target(s.trim())).collect(),
+			next_id: AtomicU64::new(1),
+			current_spans: Mutex::new(Vec::new()),
+			spans: Mutex::new(BTreeMap::new()),
+			events: Mutex::new(Vec::new()),
+		}
+	}
+
+	fn is_enabled(&self, target: &str, level: &Level) -> bool {
+		self.targets.iter().any(|(t, l)| target.starts_with(t.as_str()) && level <= l)
+	}
+
+	fn current_span(&self) -> Option<u64> {
+		self.current_spans.lock().last().cloned()
+	}
+
+	fn take_trace(&self) -> (Vec<Span>, Vec<TraceEvent>) {
+		let spans = std::mem::replace(&mut *self.spans.lock(), BTreeMap::new())
+			.into_iter()
+			.map(|(_, mut datum)| {
+				datum.span.overall_time = datum.overall_time.as_nanos() as u64;
+				datum.span
+			})
+			.collect();
+		let events = std::mem::replace(&mut *self.events.lock(), Vec::new());
+
+		(spans, events)
+	}
+}
+
+impl Subscriber for BlockSubscriber {
+	fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+		// The actual target of the spans of the runtime is only known once they are created.
+		metadata.target() == sp_tracing::WASM_TRACE_IDENTIFIER
+			|| self.is_enabled(metadata.target(), metadata.level())
+	}
+
+	fn new_span(&self, attrs: &Attributes<'_>) -> Id {
+		let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+		let parent_id = attrs.parent().map(|p| p.into_u64())
+			.or_else(|| if attrs.is_contextual() { self.current_span() } else { None });
+		let mut values = Values::new();
+		attrs.record(&mut ValuesVisitor(&mut values));
+		let wasm_target_and_name = wasm_target_and_name(attrs.metadata(), |key| values.remove(key));
+		let (target, name) = match wasm_target_and_name {
+			Some((target, _)) if !self.is_enabled(&target, attrs.metadata().level()) =>
+				return Id::from_u64(id),
+			Some(target_and_name) => target_and_name,
+			None => (
+				attrs.metadata().target().to_string(),
+				attrs.metadata().name().to_string(),
+			),
+		};
+		let span = Span {
+			id,
+			parent_id,
+			name,
+			target,
+			line: attrs.metadata().line().unwrap_or(0),
+			overall_time: 0,
+			values,
+		};
+		self.spans.lock().insert(id, SpanDatum {
+			span,
+			start_time: Instant::now(),
+			overall_time: Duration::from_nanos(0),
+		});
+		Id::from_u64(id)
+	}
+
+	fn record(&self, span: &Id, values: &Record<'_>) {
+		if let Some(datum) = self.spans.lock().get_mut(&span.into_u64()) {
+			values.record(&mut ValuesVisitor(&mut datum.span.values));
+		}
+	}
+
+	fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+	fn event(&self, event: &Event<'_>) {
+		let parent_id = event.parent().map(|p| p.into_u64())
+			.or_else(|| if event.is_contextual() { self.current_span() } else { None });
+		let mut values = Values::new();
+		event.record(&mut ValuesVisitor(&mut values));
+		self.events.lock().push(TraceEvent {
+			target: event.metadata().target().to_string(),
+			parent_id,
+			values,
+		});
+	}
+
+	fn enter(&self, span: &Id) {
+		if let Some(datum) = self.spans.lock().get_mut(&span.into_u64()) {
+			datum.start_time = Instant::now();
+			self.current_spans.lock().push(span.into_u64());
+		}
+	}
+
+	fn exit(&self, span: &Id) {
+		let id = span.into_u64();
+		{
+			let mut current_spans = self.current_spans.lock();
+			if let Some(pos) = current_spans.iter().rposition(|s| *s == id) {
+				current_spans.remove(pos);
+			}
+		}
+		if let Some(datum) = self.spans.lock().get_mut(&id) {
+			datum.overall_time += datum.start_time.elapsed();
+		}
+	}
+
+	// Spans are kept until the end of the trace, closing them doesn't drop any data.
+	fn try_close(&self, _span: Id) -> bool {
+		true
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn collects_spans_and_events_of_enabled_targets() {
+		let (result, spans, events) = collect_block_trace("pallet,state=debug", || {
+			let span = tracing::span!(target: "pallet_test", tracing::Level::DEBUG, "execute", n = 1);
+			let _guard = span.enter();
+			tracing::debug!(target: "state", key = "3a636f6465");
+			tracing::trace!(target: "state", key = "ignored");
+			tracing::debug!(target: "other", key = "ignored");
+			42
+		});
+
+		assert_eq!(result, 42);
+		assert_eq!(spans.len(), 1);
+		assert_eq!(spans[0].name, "execute");
+		assert_eq!(spans[0].target, "pallet_test");
+		assert_eq!(spans[0].parent_id, None);
+		assert_eq!(spans[0].values.get("n").map(String::as_str), Some("1"));
+
+		assert_eq!(events.len(), 1);
+		assert_eq!(events[0].target, "state");
+		assert_eq!(events[0].parent_id, Some(spans[0].id));
+		assert_eq!(events[0].values.get("key").map(String::as_str), Some("3a636f6465"));
+	}
+
+	#[test]
+	fn spans_of_the_runtime_get_their_actual_target_and_name() {
+		let (_, spans, _) = collect_block_trace("pallet_balances", || {
+			sp_tracing::proxy::set_context(10, Some(2));
+			let transfer = sp_tracing::proxy::enter_span("pallet_balances", "transfer");
+			let ignored = sp_tracing::proxy::enter_span("pallet_timestamp", "set");
+			sp_tracing::proxy::exit_span(ignored);
+			sp_tracing::proxy::exit_span(transfer);
+		});
+
+		assert_eq!(spans.len(), 1);
+		assert_eq!(spans[0].name, "transfer");
+		assert_eq!(spans[0].target, "pallet_balances");
+		assert_eq!(spans[0].values.get("block_number").map(String::as_str), Some("10"));
+		assert_eq!(spans[0].values.get("extrinsic_index").map(String::as_str), Some("2"));
+		assert!(!spans[0].values.contains_key(sp_tracing::WASM_TARGET_KEY));
+	}
+}
@@ -35,7 +35,19 @@
 //! let _guard = span.enter();
 //! ```
 //! Currently we provide `Log` (default), `Telemetry` variants for `Receiver`
+//!
+//! The spans of the runtime executed in Wasm are received as well, with their actual target and
+//! name and tagged with the number of the block and the index of the extrinsic they are entered
+//! in, see `sp_tracing`.
+//!
+//! Spans and events can also be collected in memory with [`collect_block_trace`], which is used
+//! to trace the re-execution of a block.
+//!
+//! The spans a thread is in are returned by [`current_spans`], e.g. to add them to the log lines.
+
+mod block;
 
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fmt;
 use std::sync::atomic::{AtomicU64, Ordering};
@@ -55,6 +67,8 @@ use tracing_core::{
 
 use sc_telemetry::{telemetry, SUBSTRATE_INFO};
 
+pub use block::collect_block_trace;
+
 /// Used to configure how to receive the metrics
 #[derive(Debug, Clone)]
 pub enum TracingReceiver {
@@ -73,8 +87,8 @@ impl Default for TracingReceiver {
 #[derive(Debug)]
 struct SpanDatum {
 	id: u64,
-	name: &'static str,
-	target: &'static str,
+	name: String,
+	target: String,
 	level: Level,
 	line: u32,
 	start_time: Instant,
@@ -85,7 +99,19 @@ struct SpanDatum {
 #[derive(Clone, Debug)]
 struct Visitor(Vec<(String, String)>);
 
+impl Visitor {
+	/// Removes the value of the given field.
+	fn take(&mut self, name: &str) -> Option<String> {
+		let index = self.0.iter().position(|(key, _)| key == name)?;
+		Some(self.0.remove(index).1)
+	}
+}
+
 impl Visit for Visitor {
+	fn record_str(&mut self, field: &Field, value: &str) {
+		self.0.push((field.name().to_string(), value.to_string()));
+	}
+
 	fn record_i64(&mut self, field: &Field, value: i64) {
 		self.record_debug(field, &value)
 	}
@@ -143,6 +169,29 @@ impl Value for Visitor {
 	}
 }
 
+thread_local! {
+	/// The spans entered on the current thread, from the outermost one.
+	static CURRENT_SPANS: RefCell<Vec<SpanContext>> = RefCell::new(Vec::new());
+}
+
+/// A span a thread is in.
+#[derive(Clone, Debug)]
+pub struct SpanContext {
+	/// Name of the span.
+	pub name: String,
+	/// Target of the span.
+	pub target: String,
+	/// Values recorded with the span.
+	pub fields: Vec<(String, String)>,
+}
+
+/// Returns the spans the current thread is in, from the outermost one.
+///
+/// Only the spans of the targets a `ProfilingSubscriber` is enabled for are known.
+pub fn current_spans() -> Vec<SpanContext> {
+	CURRENT_SPANS.with(|spans| spans.borrow().clone())
+}
+
 /// Responsible for assigning ids to new spans, which are not re-used.
 pub struct ProfilingSubscriber {
 	next_id: AtomicU64,
@@ -164,6 +213,25 @@ impl ProfilingSubscriber {
 			span_data: Mutex::new(HashMap::new()),
 		}
 	}
+
+	fn is_enabled(&self, target: &str, level: &Level) -> bool {
+		self.targets.iter().any(|t| target.starts_with(t.0.as_str()) && level <= &t.1)
+	}
+}
+
+/// Returns the actual target and name of a span of the runtime executed in Wasm, taking them out
+/// of its values, or `None` if the span isn't one.
+fn wasm_target_and_name(
+	metadata: &Metadata<'_>,
+	mut take_value: impl FnMut(&str) -> Option<String>,
+) -> Option<(String, String)> {
+	if metadata.target() != sp_tracing::WASM_TRACE_IDENTIFIER {
+		return None;
+	}
+
+	let target = take_value(sp_tracing::WASM_TARGET_KEY)?;
+	let name = take_value(sp_tracing::WASM_NAME_KEY)?;
+	Some((target, name))
 }
 
 // Default to TRACE if no level given or unable to parse Level
@@ -185,6 +253,11 @@ fn parse_target(s: &str) -> (String, Level) {
 
 impl Subscriber for ProfilingSubscriber {
 	fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+		// The actual target of the spans of the runtime is only known once they are created.
+		if metadata.target() == sp_tracing::WASM_TRACE_IDENTIFIER {
+			return true;
+		}
+
 		for t in &self.targets {
 			if metadata.target().starts_with(t.0.as_str()) && metadata.level() <= &t.1 {
 				log::debug!("Enabled target: {}, level: {}", metadata.target(), metadata.level());
@@ -200,10 +273,15 @@ impl Subscriber for ProfilingSubscriber {
 		let id = self.next_id.fetch_add(1, Ordering::Relaxed);
 		let mut values = Visitor(Vec::new());
 		attrs.record(&mut values);
+		let (target, name) = wasm_target_and_name(attrs.metadata(), |key| values.take(key))
+			.unwrap_or_else(|| (
+				attrs.metadata().target().to_string(),
+				attrs.metadata().name().to_string(),
+			));
 		let span_datum = SpanDatum {
 			id,
-			name: attrs.metadata().name(),
-			target: attrs.metadata().target(),
+			name,
+			target,
 			level: attrs.metadata().level().clone(),
 			line: attrs.metadata().line().unwrap_or(0),
 			start_time: Instant::now(),
@@ -225,6 +303,14 @@ impl Subscriber for ProfilingSubscriber {
 		let start_time = Instant::now();
 		if let Some(mut s) = span_data.get_mut(&span.into_u64()) {
 			s.start_time = start_time;
+			if self.is_enabled(&s.target, &s.level) {
+				let context = SpanContext {
+					name: s.name.clone(),
+					target: s.target.clone(),
+					fields: s.values.0.clone(),
+				};
+				CURRENT_SPANS.with(|spans| spans.borrow_mut().push(context));
+			}
 		} else {
 			log::warn!("Tried to enter span {:?} that has already been closed!", span);
 		}
@@ -235,13 +321,18 @@ impl Subscriber for ProfilingSubscriber {
 		let end_time = Instant::now();
 		if let Some(mut s) = span_data.get_mut(&span.into_u64()) {
 			s.overall_time = end_time - s.start_time + s.overall_time;
+			if self.is_enabled(&s.target, &s.level) {
+				CURRENT_SPANS.with(|spans| spans.borrow_mut().pop());
+			}
 		}
 	}
 
 	fn try_close(&self, span: Id) -> bool {
 		let mut span_data = self.span_data.lock();
 		if let Some(data) = span_data.remove(&span.into_u64()) {
-			self.send_span(data);
+			if self.is_enabled(&data.target, &data.level) {
+				self.send_span(data);
+			}
 		};
 		true
 	}
@@ -19,7 +19,7 @@
 //! For a more full-featured pool, have a look at the `pool` module.
 
 use std::{
-	collections::HashSet,
+	collections::{BTreeMap, HashSet},
 	fmt,
 	hash,
 	sync::Arc,
@@ -33,6 +33,7 @@ use sp_runtime::transaction_validity::{
 	TransactionTag as Tag,
 	TransactionLongevity as Longevity,
 	TransactionPriority as Priority,
+	TransactionSource as Source,
 };
 use sp_transaction_pool::{error, PoolStatus, InPoolTransaction};
 
@@ -102,6 +103,8 @@ pub struct Transaction<Hash, Extrinsic> {
 	pub provides: Vec<Tag>,
 	/// Should that transaction be propagated.
 	pub propagate: bool,
+	/// Source of that transaction.
+	pub source: Source,
 }
 
 impl<Hash, Extrinsic> AsRef<Extrinsic> for Transaction<Hash, Extrinsic> {
@@ -159,6 +162,7 @@ impl<Hash: Clone, Extrinsic: Clone> Transaction<Hash, Extrinsic> {
 			requires: self.requires.clone(),
 			provides: self.provides.clone(),
 			propagate: self.propagate,
+			source: self.source,
 		}
 	}
 }
@@ -185,6 +189,7 @@ impl<Hash, Extrinsic> fmt::Debug for Transaction<Hash, Extrinsic> where
 		write!(fmt, "valid_till: {:?}, ", &self.valid_till)?;
 		write!(fmt, "bytes: {:?}, ", &self.bytes)?;
 		write!(fmt, "propagate: {:?}, ", &self.propagate)?;
+		write!(fmt, "source: {:?}, ", &self.source)?;
 		write!(fmt, "requires: [")?;
 		print_tags(fmt, &self.requires)?;
 		write!(fmt, "], provides: [")?;
@@ -213,6 +218,7 @@ const RECENTLY_PRUNED_TAGS: usize = 2;
 #[cfg_attr(not(target_os = "unknown"), derive(parity_util_mem::MallocSizeOf))]
 pub struct BasePool<Hash: hash::Hash + Eq, Ex> {
 	reject_future_transactions: bool,
+	sender_limit: SenderLimit,
 	future: FutureTransactions<Hash, Ex>,
 	ready: ReadyTransactions<Hash, Ex>,
 	/// Store recently pruned tags (for last two invocations).
@@ -221,6 +227,10 @@ pub struct BasePool<Hash: hash::Hash + Eq, Ex> {
 	/// transactions to future in case they were just stuck in verification.
 	recently_pruned: [HashSet<Tag>; RECENTLY_PRUNED_TAGS],
 	recently_pruned_index: usize,
+	/// The hashes of the imported mortal transactions, by the block they expire at.
+	///
+	/// The transactions are forgotten once they expire or are removed from the pool.
+	expiries: BTreeMap<Longevity, HashSet<Hash>>,
 }
 
 impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> Default for BasePool<Hash, Ex> {
@@ -234,13 +244,24 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 	pub fn new(reject_future_transactions: bool) -> Self {
 		BasePool {
 			reject_future_transactions,
+			sender_limit: SenderLimit {
+				ready: usize::max_value(),
+				future: usize::max_value(),
+			},
 			future: Default::default(),
 			ready: Default::default(),
 			recently_pruned: Default::default(),
 			recently_pruned_index: 0,
+			expiries: Default::default(),
 		}
 	}
 
+	/// Limit the number of transactions of a single sender in the queues.
+	pub fn with_sender_limit(mut self, sender_limit: SenderLimit) -> Self {
+		self.sender_limit = sender_limit;
+		self
+	}
+
 	/// Temporary enables future transactions, runs closure and then restores
 	/// `reject_future_transactions` flag back to previous value.
 	///
@@ -264,6 +285,18 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 	pub fn import(
 		&mut self,
 		tx: Transaction<Hash, Ex>,
+	) -> error::Result<Imported<Hash, Ex>> {
+		let (hash, valid_till) = (tx.hash.clone(), tx.valid_till);
+		let imported = self.import_unchecked_expiry(tx)?;
+		if valid_till != Longevity::max_value() {
+			self.expiries.entry(valid_till).or_default().insert(hash);
+		}
+		Ok(imported)
+	}
+
+	fn import_unchecked_expiry(
+		&mut self,
+		tx: Transaction<Hash, Ex>,
 	) -> error::Result<Imported<Hash, Ex>> {
 		if self.future.contains(&tx.hash) || self.ready.contains(&tx.hash) {
 			return Err(error::Error::AlreadyImported(Box::new(tx.hash.clone())))
@@ -288,11 +321,28 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 				return Err(error::Error::RejectedFutureTransaction);
 			}
 
+			let connected = self.future.count_connected(
+				&tx.transaction.requires,
+				&tx.transaction.provides,
+				self.sender_limit.future,
+			);
+			if connected >= self.sender_limit.future {
+				return Err(error::Error::SenderLimitReached);
+			}
+
 			let hash = tx.transaction.hash.clone();
 			self.future.import(tx);
 			return Ok(Imported::Future { hash });
 		}
 
+		let connected = self.ready.count_connected(
+			&tx.transaction.requires,
+			self.sender_limit.ready,
+		);
+		if connected >= self.sender_limit.ready {
+			return Err(error::Error::SenderLimitReached);
+		}
+
 		self.import_to_ready(tx)
 	}
 
@@ -320,6 +370,7 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 
 			// import this transaction
 			let current_hash = tx.transaction.hash.clone();
+			let valid_till = tx.transaction.valid_till;
 			match self.ready.import(tx) {
 				Ok(mut replaced) => {
 					if !first {
@@ -333,12 +384,15 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 					debug!(target: "txpool", "[{:?}] Error importing: {:?}", current_hash, e);
 					return Err(e)
 				} else {
+					self.forget_expiry(&current_hash, valid_till);
 					failed.push(current_hash);
 				},
 			}
 			first = false;
 		}
 
+		self.forget_expiries(&removed);
+
 		// An edge case when importing transaction caused
 		// some future transactions to be imported and that
 		// future transactions pushed out current transaction.
@@ -347,7 +401,8 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 		if removed.iter().any(|tx| tx.hash == hash) {
 			// We still need to remove all transactions that we promoted
 			// since they depend on each other and will never get to the best iterator.
-			self.ready.remove_subtree(&promoted);
+			let removed = self.ready.remove_subtree(&promoted);
+			self.forget_expiries(&removed);
 
 			debug!(target: "txpool", "[{:?}] Cycle detected, bailing.", hash);
 			return Err(error::Error::CycleDetected)
@@ -391,11 +446,43 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 		self.ready.by_hash(hash)
 	}
 
+	/// Returns the transactions of the pool which expire at or before `block_number`, and
+	/// forgets their expiry.
+	///
+	/// The returned transactions may have been revalidated with a later expiry since they were
+	/// first imported, in which case that later expiry is still known.
+	pub fn take_expired(&mut self, block_number: u64) -> Vec<Arc<Transaction<Hash, Ex>>> {
+		let alive = self.expiries.split_off(&block_number.saturating_add(1));
+		let hashes = std::mem::replace(&mut self.expiries, alive)
+			.into_iter()
+			.flat_map(|(_, hashes)| hashes)
+			.collect::<Vec<_>>();
+		self.by_hashes(&hashes).into_iter().filter_map(|tx| tx).collect()
+	}
+
+	/// Forgets the expiry of the given transactions, removed from the pool.
+	fn forget_expiries(&mut self, removed: &[Arc<Transaction<Hash, Ex>>]) {
+		for tx in removed {
+			self.forget_expiry(&tx.hash, tx.valid_till);
+		}
+	}
+
+	/// Forgets the expiry of the transaction, removed from the pool.
+	fn forget_expiry(&mut self, hash: &Hash, valid_till: Longevity) {
+		if let Some(hashes) = self.expiries.get_mut(&valid_till) {
+			hashes.remove(hash);
+			if hashes.is_empty() {
+				self.expiries.remove(&valid_till);
+			}
+		}
+	}
+
 	/// Makes sure that the transactions in the queues stay within provided limits.
 	///
 	/// Removes and returns worst transactions from the queues and all transactions that depend on them.
 	/// Technically the worst transaction should be evaluated by computing the entire pending set.
-	/// We use a simplified approach to remove the transaction that occupies the pool for the longest time.
+	/// We use a simplified approach to remove the transaction with the lowest priority,
+	/// and of those the one that occupies the pool for the longest time.
 	pub fn enforce_limits(&mut self, ready: &Limit, future: &Limit) -> Vec<Arc<Transaction<Hash, Ex>>> {
 		let mut removed = vec![];
 
@@ -406,7 +493,9 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 					let transaction = &current.transaction;
 					match minimal {
 						None => Some(transaction.clone()),
-						Some(ref tx) if tx.insertion_id > transaction.insertion_id => {
+						Some(ref tx) if (tx.transaction.priority, tx.insertion_id) >
+							(transaction.transaction.priority, transaction.insertion_id) =>
+						{
 							Some(transaction.clone())
 						},
 						other => other,
@@ -426,7 +515,9 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 				.fold(|minimal, current| {
 					match minimal {
 						None => Some(current.clone()),
-						Some(ref tx) if tx.imported_at > current.imported_at => {
+						Some(ref tx) if (tx.transaction.priority, tx.imported_at) >
+							(current.transaction.priority, current.imported_at) =>
+						{
 							Some(current.clone())
 						},
 						other => other,
@@ -454,12 +545,15 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 	pub fn remove_subtree(&mut self, hashes: &[Hash]) -> Vec<Arc<Transaction<Hash, Ex>>> {
 		let mut removed = self.ready.remove_subtree(hashes);
 		removed.extend(self.future.remove(hashes));
+		self.forget_expiries(&removed);
 		removed
 	}
 
 	/// Removes and returns all transactions from the future queue.
 	pub fn clear_future(&mut self) -> Vec<Arc<Transaction<Hash, Ex>>> {
-		self.future.clear()
+		let removed = self.future.clear();
+		self.forget_expiries(&removed);
+		removed
 	}
 
 	/// Prunes transactions that provide given list of tags.
@@ -484,14 +578,17 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 			recently_pruned.insert(tag);
 		}
 
+		self.forget_expiries(&pruned);
+
 		let mut promoted = vec![];
 		let mut failed = vec![];
 		for tx in to_import {
-			let hash = tx.transaction.hash.clone();
+			let (hash, valid_till) = (tx.transaction.hash.clone(), tx.transaction.valid_till);
 			match self.import_to_ready(tx) {
 				Ok(res) => promoted.push(res),
 				Err(e) => {
 					warn!(target: "txpool", "[{:?}] Failed to promote during pruning: {:?}", hash, e);
+					self.forget_expiry(&hash, valid_till);
 					failed.push(hash)
 				},
 			}
@@ -511,6 +608,8 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 			ready_bytes: self.ready.bytes(),
 			future: self.future.len(),
 			future_bytes: self.future.bytes(),
+			// bans are kept by the validated pool.
+			banned: 0,
 		}
 	}
 }
@@ -531,9 +630,22 @@ impl Limit {
 	}
 }
 
+/// Limits of the transactions of a single sender.
+///
+/// The pool doesn't know who sends the transactions, so the transactions of a sender are the ones
+/// connected by their tags in a queue, like the transactions of an account ordered by nonce.
+#[derive(Debug, Clone)]
+pub struct SenderLimit {
+	/// Maximal number of transactions of a sender in the ready queue.
+	pub ready: usize,
+	/// Maximal number of transactions of a sender in the future queue.
+	pub future: usize,
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use assert_matches::assert_matches;
 
 	type Hash = u64;
 
@@ -556,6 +668,7 @@ mod tests {
 			requires: vec![],
 			provides: vec![vec![1]],
 			propagate: true,
+			source: Source::External,
 		}).unwrap();
 
 		// then
@@ -578,6 +691,7 @@ mod tests {
 			requires: vec![],
 			provides: vec![vec![1]],
 			propagate: true,
+			source: Source::External,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![1u8],
@@ -588,6 +702,7 @@ mod tests {
 			requires: vec![],
 			provides: vec![vec![1]],
 			propagate: true,
+			source: Source::External,
 		}).unwrap_err();
 
 		// then
@@ -611,6 +726,7 @@ mod tests {
 			requires: vec![vec![0]],
 			provides: vec![vec![1]],
 			propagate: true,
+			source: Source::External,
 		}).unwrap();
 		assert_eq!(pool.ready().count(), 0);
 		assert_eq!(pool.ready.len(), 0);
@@ -623,6 +739,7 @@ mod tests {
 			requires: vec![],
 			provides: vec![vec![0]],
 			propagate: true,
+			source: Source::External,
 		}).unwrap();
 
 		// then
@@ -645,6 +762,7 @@ mod tests {
 			requires: vec![vec![0]],
 			provides: vec![vec![1]],
 			propagate: true,
+			source: Source::External,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![3u8],
@@ -655,6 +773,7 @@ mod tests {
 			requires: vec![vec![2]],
 			provides: vec![],
 			propagate: true,
+			source: Source::External,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![2u8],
@@ -665,6 +784,7 @@ mod tests {
 			requires: vec![vec![1]],
 			provides: vec![vec![3], vec![2]],
 			propagate: true,
+			source: Source::External,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![4u8],
@@ -675,6 +795,7 @@ mod tests {
 			requires: vec![vec![3], vec![4]],
 			provides: vec![],
 			propagate: true,
+			source: Source::External,
 		}).unwrap();
 		assert_eq!(pool.ready().count(), 0);
 		assert_eq!(pool.ready.len(), 0);
@@ -688,6 +809,7 @@ mod tests {
 			requires: vec![],
 			provides: vec![vec![0], vec![4]],
 			propagate: true,
+			source: Source::External,
 		}).unwrap();
 
 		// then
@@ -720,6 +842,7 @@ mod tests {
 			requires: vec![vec![0]],
 			provides: vec![vec![1]],
 			propagate: true,
+			source: Source::External,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![3u8],
@@ -730,6 +853,7 @@ mod tests {
 			requires: vec![vec![1]],
 			provides: vec![vec![2]],
 			propagate: true,
+			source: Source::External,
 		}).unwrap();
 		assert_eq!(pool.ready().count(), 0);
 		assert_eq!(pool.ready.len(), 0);
@@ -744,6 +868,7 @@ mod tests {
 			requires: vec![vec![2]],
 			provides: vec![vec![0]],
 			propagate: true,
+			source: Source::External,
 		}).unwrap();
 
 		// then
@@ -764,6 +889,7 @@ mod tests {
 			requires: vec![],
 			provides: vec![vec![0]],
 			propagate: true,
+			source: Source::External,
 		}).unwrap();
 		let mut it = pool.ready().into_iter().map(|tx| tx.data[0]);
 		assert_eq!(it.next(), Some(4));
@@ -792,6 +918,7 @@ mod tests {
 			requires: vec![vec![0]],
 			provides: vec![vec![1]],
 			propagate: true,
+			source: Source::External,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![3u8],
@@ -802,6 +929,7 @@ mod tests {
 			requires: vec![vec![1]],
 			provides: vec![vec![2]],
 			propagate: true,
+			source: Source::External,
 		}).unwrap();
 		assert_eq!(pool.ready().count(), 0);
 		assert_eq!(pool.ready.len(), 0);
@@ -816,6 +944,7 @@ mod tests {
 			requires: vec![vec![2]],
 			provides: vec![vec![0]],
 			propagate: true,
+			source: Source::External,
 		}).unwrap();
 
 		// then
@@ -836,6 +965,7 @@ mod tests {
 			requires: vec![],
 			provides: vec![vec![0]],
 			propagate: true,
+			source: Source::External,
 		}).unwrap_err();
 		let mut it = pool.ready().into_iter().map(|tx| tx.data[0]);
 		assert_eq!(it.next(), None);
@@ -859,6 +989,7 @@ mod tests {
 			requires: vec![],
 			provides: vec![vec![0], vec![4]],
 			propagate: true,
+			source: Source::External,
 		}).expect("import 1 should be ok");
 		pool.import(Transaction {
 			data: vec![3u8; 1024],
@@ -869,6 +1000,7 @@ mod tests {
 			requires: vec![],
 			provides: vec![vec![2], vec![7]],
 			propagate: true,
+			source: Source::External,
 		}).expect("import 2 should be ok");
 
 		assert!(parity_util_mem::malloc_size(&pool) > 5000);
@@ -887,6 +1019,7 @@ mod tests {
 			requires: vec![],
 			provides: vec![vec![0], vec![4]],
 			propagate: true,
+			source: Source::External,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![1u8],
@@ -897,6 +1030,7 @@ mod tests {
 			requires: vec![vec![0]],
 			provides: vec![vec![1]],
 			propagate: true,
+			source: Source::External,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![3u8],
@@ -907,6 +1041,7 @@ mod tests {
 			requires: vec![vec![2]],
 			provides: vec![],
 			propagate: true,
+			source: Source::External,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![2u8],
@@ -917,6 +1052,7 @@ mod tests {
 			requires: vec![vec![1]],
 			provides: vec![vec![3], vec![2]],
 			propagate: true,
+			source: Source::External,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![4u8],
@@ -927,6 +1063,7 @@ mod tests {
 			requires: vec![vec![3], vec![4]],
 			provides: vec![],
 			propagate: true,
+			source: Source::External,
 		}).unwrap();
 		// future
 		pool.import(Transaction {
@@ -938,6 +1075,7 @@ mod tests {
 			requires: vec![vec![11]],
 			provides: vec![],
 			propagate: true,
+			source: Source::External,
 		}).unwrap();
 		assert_eq!(pool.ready().count(), 5);
 		assert_eq!(pool.future.len(), 1);
@@ -964,6 +1102,7 @@ mod tests {
 			requires: vec![vec![0]],
 			provides: vec![vec![100]],
 			propagate: true,
+			source: Source::External,
 		}).unwrap();
 		// ready
 		pool.import(Transaction {
@@ -975,6 +1114,7 @@ mod tests {
 			requires: vec![],
 			provides: vec![vec![1]],
 			propagate: true,
+			source: Source::External,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![2u8],
@@ -985,6 +1125,7 @@ mod tests {
 			requires: vec![vec![2]],
 			provides: vec![vec![3]],
 			propagate: true,
+			source: Source::External,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![3u8],
@@ -995,6 +1136,7 @@ mod tests {
 			requires: vec![vec![1]],
 			provides: vec![vec![2]],
 			propagate: true,
+			source: Source::External,
 		}).unwrap();
 		pool.import(Transaction {
 			data: vec![4u8],
@@ -1005,6 +1147,7 @@ mod tests {
 			requires: vec![vec![3], vec![2]],
 			provides: vec![vec![4]],
 			propagate: true,
+			source: Source::External,
 		}).unwrap();
 
 		assert_eq!(pool.ready().count(), 4);
@@ -1040,6 +1183,7 @@ mod tests {
 				requires: vec![vec![3], vec![2]],
 				provides: vec![vec![4]],
 				propagate: true,
+				source: Source::External,
 			}),
 			"Transaction { \
 hash: 4, priority: 1000, valid_till: 64, bytes: 1, propagate: true, \
@@ -1058,6 +1202,7 @@ requires: [03,02], provides: [04], data: [4]}".to_owned()
 				requires: vec![vec![3], vec![2]],
 				provides: vec![vec![4]],
 				propagate: true,
+				source: Source::External,
 		}.is_propagable(), true);
 
 		assert_eq!(Transaction {
@@ -1069,6 +1214,7 @@ requires: [03,02], provides: [04], data: [4]}".to_owned()
 				requires: vec![vec![3], vec![2]],
 				provides: vec![vec![4]],
 				propagate: false,
+				source: Source::External,
 		}.is_propagable(), false);
 	}
 
@@ -1090,6 +1236,7 @@ requires: [03,02], provides: [04], data: [4]}".to_owned()
 			requires: vec![vec![0]],
 			provides: vec![],
 			propagate: true,
+			source: Source::External,
 		});
 
 		if let Err(error::Error::RejectedFutureTransaction) = err {
@@ -1113,6 +1260,7 @@ requires: [03,02], provides: [04], data: [4]}".to_owned()
 			requires: vec![vec![0]],
 			provides: vec![],
 			propagate: true,
+			source: Source::External,
 		}).unwrap();
 
 		// then
@@ -1142,6 +1290,7 @@ requires: [03,02], provides: [04], data: [4]}".to_owned()
 				requires: vec![vec![0]],
 				provides: vec![],
 				propagate: true,
+				source: Source::External,
 			}).unwrap();
 
 			flag
@@ -1152,4 +1301,141 @@ requires: [03,02], provides: [04], data: [4]}".to_owned()
 		assert_eq!(pool.reject_future_transactions, true);
 		assert_eq!(pool.future.len(), 1);
 	}
+
+	#[test]
+	fn should_evict_transactions_with_the_lowest_priority_first() {
+		// given
+		let mut pool = pool();
+		let tx = |hash: u64, priority: u64, requires: Vec<Tag>| Transaction {
+			data: vec![hash as u8],
+			bytes: 1,
+			hash,
+			priority,
+			valid_till: 64u64,
+			requires,
+			provides: vec![vec![hash as u8]],
+			propagate: true,
+			source: Source::External,
+		};
+		pool.import(tx(1, 5, vec![])).unwrap();
+		pool.import(tx(2, 1, vec![])).unwrap();
+		pool.import(tx(3, 1, vec![])).unwrap();
+		pool.import(tx(4, 5, vec![vec![10]])).unwrap();
+		pool.import(tx(5, 1, vec![vec![10]])).unwrap();
+
+		// when
+		let limit = Limit {
+			count: 2,
+			total_bytes: 100,
+		};
+		let future_limit = Limit {
+			count: 1,
+			total_bytes: 100,
+		};
+		let removed = pool.enforce_limits(&limit, &future_limit);
+
+		// then
+		assert_eq!(removed.iter().map(|tx| tx.hash).collect::<Vec<_>>(), vec![2, 5]);
+		assert_eq!(pool.ready().map(|tx| tx.hash).collect::<Vec<_>>(), vec![1, 3]);
+		assert_eq!(pool.futures().map(|tx| tx.hash).collect::<Vec<_>>(), vec![4]);
+	}
+
+	#[test]
+	fn should_limit_transactions_of_a_sender() {
+		// given
+		let mut pool = pool().with_sender_limit(SenderLimit {
+			ready: 2,
+			future: 2,
+		});
+		let tx = |hash: u64, requires: Vec<Tag>| Transaction {
+			data: vec![hash as u8],
+			bytes: 1,
+			hash,
+			priority: 5u64,
+			valid_till: 64u64,
+			requires,
+			provides: vec![vec![hash as u8]],
+			propagate: true,
+			source: Source::External,
+		};
+
+		// when
+		pool.import(tx(1, vec![])).unwrap();
+		pool.import(tx(2, vec![vec![1]])).unwrap();
+		let ready_err = pool.import(tx(3, vec![vec![2]])).unwrap_err();
+		// the future transactions are connected whatever the order they come in.
+		pool.import(tx(6, vec![vec![5]])).unwrap();
+		pool.import(tx(5, vec![vec![4]])).unwrap();
+		let future_err = pool.import(tx(7, vec![vec![6]])).unwrap_err();
+		// others are not limited.
+		pool.import(tx(8, vec![])).unwrap();
+		pool.import(tx(9, vec![vec![20]])).unwrap();
+
+		// then
+		assert_matches!(ready_err, error::Error::SenderLimitReached);
+		assert_matches!(future_err, error::Error::SenderLimitReached);
+		assert_eq!(pool.ready.len(), 3);
+		assert_eq!(pool.future.len(), 3);
+	}
+
+	#[test]
+	fn should_take_expired_transactions() {
+		// given
+		let mut pool = pool();
+		let tx = |hash: u64, valid_till: u64| Transaction {
+			data: vec![hash as u8],
+			bytes: 1,
+			hash,
+			priority: 5u64,
+			valid_till,
+			requires: vec![],
+			provides: vec![vec![hash as u8]],
+			propagate: true,
+			source: Source::External,
+		};
+		pool.import(tx(1, 10)).unwrap();
+		pool.import(tx(2, 20)).unwrap();
+		pool.import(tx(3, 10)).unwrap();
+		pool.import(tx(4, Longevity::max_value())).unwrap();
+		pool.remove_subtree(&[3]);
+
+		// when
+		let expired = pool.take_expired(15);
+
+		// then
+		assert_eq!(expired.iter().map(|tx| tx.hash).collect::<Vec<_>>(), vec![1]);
+		assert!(pool.take_expired(15).is_empty());
+		let expired = pool.take_expired(u64::max_value());
+		assert_eq!(expired.iter().map(|tx| tx.hash).collect::<Vec<_>>(), vec![2]);
+	}
+
+	#[test]
+	fn should_forget_the_expiry_of_removed_transactions() {
+		// given
+		let mut pool = pool();
+		let tx = |hash: u64, valid_till: u64| Transaction {
+			data: vec![hash as u8],
+			bytes: 1,
+			hash,
+			priority: 5u64,
+			valid_till,
+			requires: vec![],
+			provides: vec![vec![hash as u8]],
+			propagate: true,
+			source: Source::External,
+		};
+		pool.import(tx(1, 10)).unwrap();
+		pool.import(tx(2, 20)).unwrap();
+		pool.import(tx(3, 20)).unwrap();
+
+		// when
+		pool.remove_subtree(&[1]);
+		pool.prune_tags(vec![vec![2]]);
+
+		// then
+		assert_eq!(pool.expiries, vec![(20, vec![3].into_iter().collect())].into_iter().collect());
+		pool.clear_future();
+		pool.remove_subtree(&[3]);
+		assert!(pool.expiries.is_empty());
+	}
 }
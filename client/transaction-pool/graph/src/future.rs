@@ -161,6 +161,44 @@ impl<Hash: hash::Hash + Eq + Clone, Ex> FutureTransactions<Hash, Ex> {
 		self.waiting.contains_key(hash)
 	}
 
+	/// Returns the number of transactions connected to a transaction requiring and providing
+	/// given tags.
+	///
+	/// These are the transactions it waits for and the ones waiting for it, directly or not.
+	/// The count stops at `limit`.
+	pub fn count_connected(&self, requires: &[Tag], provides: &[Tag], limit: usize) -> usize {
+		let providers = self.waiting.values()
+			.flat_map(|tx| {
+				let hash = &tx.transaction.hash;
+				tx.transaction.provides.iter().map(move |tag| (tag, hash))
+			})
+			.collect::<HashMap<_, _>>();
+		let neighbours = |requires: &[Tag], provides: &[Tag]| -> Vec<Hash> {
+			requires.iter()
+				.filter_map(|tag| providers.get(tag).map(|hash| (*hash).clone()))
+				.chain(
+					provides.iter().filter_map(|tag| self.wanted_tags.get(tag)).flatten().cloned()
+				)
+				.collect()
+		};
+
+		let mut visited = HashSet::new();
+		let mut to_visit = neighbours(requires, provides);
+		while let Some(hash) = to_visit.pop() {
+			if visited.len() >= limit {
+				break;
+			}
+			if !visited.insert(hash.clone()) {
+				continue;
+			}
+
+			let tx = &self.waiting.get(&hash).expect(WAITING_PROOF).transaction;
+			to_visit.append(&mut neighbours(&tx.requires, &tx.provides));
+		}
+
+		visited.len()
+	}
+
 	/// Returns a list of known transactions
 	pub fn by_hashes(&self, hashes: &[Hash]) -> Vec<Option<Arc<Transaction<Hash, Ex>>>> {
 		hashes.iter().map(|h| self.waiting.get(h).map(|x| x.transaction.clone())).collect()
@@ -249,6 +287,7 @@ impl<Hash: hash::Hash + Eq + Clone, Ex> FutureTransactions<Hash, Ex> {
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use sp_runtime::transaction_validity::TransactionSource as Source;
 
 	#[test]
 	fn can_track_heap_size() {
@@ -263,6 +302,7 @@ mod tests {
 				requires: vec![vec![1], vec![2]],
 				provides: vec![vec![3], vec![4]],
 				propagate: true,
+				source: Source::External,
 			}.into(),
 			missing_tags: vec![vec![1u8], vec![2u8]].into_iter().collect(),
 			imported_at: std::time::Instant::now(),
@@ -230,6 +230,34 @@ impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
 		self.ready.read().contains_key(hash)
 	}
 
+	/// Returns the number of transactions connected to a transaction requiring given tags.
+	///
+	/// These are the transactions it depends on, directly or not, along with all the other
+	/// transactions depending on them. The count stops at `limit`.
+	pub fn count_connected(&self, requires: &[Tag], limit: usize) -> usize {
+		let ready = self.ready.read();
+		let providers = |requires: &[Tag]| -> Vec<Hash> {
+			requires.iter().filter_map(|tag| self.provided_tags.get(tag)).cloned().collect()
+		};
+
+		let mut visited = HashSet::new();
+		let mut to_visit = providers(requires);
+		while let Some(hash) = to_visit.pop() {
+			if visited.len() >= limit {
+				break;
+			}
+			if !visited.insert(hash.clone()) {
+				continue;
+			}
+
+			let tx = ready.get(&hash).expect(HASH_READY);
+			to_visit.append(&mut providers(&tx.transaction.transaction.requires));
+			to_visit.extend(tx.unlocks.iter().cloned());
+		}
+
+		visited.len()
+	}
+
 	/// Retrive transaction by hash
 	pub fn by_hash(&self, hash: &Hash) -> Option<Arc<Transaction<Hash, Ex>>> {
 		self.by_hashes(&[hash.clone()]).into_iter().next().unwrap_or(None)
@@ -545,6 +573,7 @@ fn remove_item<T: PartialEq>(vec: &mut Vec<T>, item: &T) {
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use sp_runtime::transaction_validity::TransactionSource as Source;
 
 	fn tx(id: u8) -> Transaction<u64, Vec<u8>> {
 		Transaction {
@@ -556,6 +585,7 @@ mod tests {
 			requires: vec![vec![1], vec![2]],
 			provides: vec![vec![3], vec![4]],
 			propagate: true,
+			source: Source::External,
 		}
 	}
 
@@ -656,6 +686,7 @@ mod tests {
 			requires: vec![tx1.provides[0].clone()],
 			provides: vec![],
 			propagate: true,
+			source: Source::External,
 		};
 
 		// when
@@ -688,6 +719,7 @@ mod tests {
 			requires: vec![],
 			provides: vec![],
 			propagate: true,
+			source: Source::External,
 		};
 		import(&mut ready, tx).unwrap();
 
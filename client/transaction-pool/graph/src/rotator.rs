@@ -46,8 +46,15 @@ pub struct PoolRotator<Hash> {
 
 impl<Hash: hash::Hash + Eq> Default for PoolRotator<Hash> {
 	fn default() -> Self {
+		PoolRotator::new(Duration::from_secs(60 * 30))
+	}
+}
+
+impl<Hash: hash::Hash + Eq> PoolRotator<Hash> {
+	/// Create a rotator banning the extrinsics for the given duration.
+	pub fn new(ban_time: Duration) -> Self {
 		PoolRotator {
-			ban_time: Duration::from_secs(60 * 30),
+			ban_time,
 			banned_until: Default::default(),
 		}
 	}
@@ -59,6 +66,12 @@ impl<Hash: hash::Hash + Eq + Clone> PoolRotator<Hash> {
 		self.banned_until.read().contains_key(hash)
 	}
 
+	/// Returns the number of extrinsics currently banned, including those whose ban has expired
+	/// but hasn't been cleared yet.
+	pub fn banned_count(&self) -> usize {
+		self.banned_until.read().len()
+	}
+
 	/// Bans given set of hashes.
 	pub fn ban(&self, now: &Instant, hashes: impl IntoIterator<Item=Hash>) {
 		let mut banned = self.banned_until.write();
@@ -100,15 +113,13 @@ impl<Hash: hash::Hash + Eq + Clone> PoolRotator<Hash> {
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use sp_runtime::transaction_validity::TransactionSource as Source;
 
 	type Hash = u64;
 	type Ex = ();
 
 	fn rotator() -> PoolRotator<Hash> {
-		PoolRotator {
-			ban_time: Duration::from_millis(10),
-			..Default::default()
-		}
+		PoolRotator::new(Duration::from_millis(10))
 	}
 
 	fn tx() -> (Hash, Transaction<Hash, Ex>) {
@@ -122,6 +133,7 @@ mod tests {
 			requires: vec![],
 			provides: vec![],
 			propagate: true,
+			source: Source::External,
 		};
 
 		(hash, tx)
@@ -188,6 +200,7 @@ mod tests {
 				requires: vec![],
 				provides: vec![],
 				propagate: true,
+				source: Source::External,
 			}
 		}
 
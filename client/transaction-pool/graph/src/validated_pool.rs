@@ -32,7 +32,7 @@ use parking_lot::{Mutex, RwLock};
 use sp_runtime::{
 	generic::BlockId,
 	traits::{self, SaturatedConversion},
-	transaction_validity::{TransactionTag as Tag, ValidTransaction},
+	transaction_validity::{TransactionTag as Tag, ValidTransaction, TransactionSource},
 };
 use sp_transaction_pool::{error, PoolStatus};
 use wasm_timer::Instant;
@@ -58,6 +58,7 @@ impl<Hash, Ex, Error> ValidatedTransaction<Hash, Ex, Error> {
 	pub fn valid_at(
 		at: u64,
 		hash: Hash,
+		source: TransactionSource,
 		data: Ex,
 		bytes: usize,
 		validity: ValidTransaction,
@@ -66,6 +67,7 @@ impl<Hash, Ex, Error> ValidatedTransaction<Hash, Ex, Error> {
 			data,
 			bytes,
 			hash,
+			source,
 			priority: validity.priority,
 			requires: validity.requires,
 			provides: validity.provides,
@@ -112,14 +114,16 @@ where
 impl<B: ChainApi> ValidatedPool<B> {
 	/// Create a new transaction pool.
 	pub fn new(options: Options, api: Arc<B>) -> Self {
-		let base_pool = base::BasePool::new(options.reject_future_transactions);
+		let base_pool = base::BasePool::new(options.reject_future_transactions)
+			.with_sender_limit(options.sender.clone());
+		let rotator = PoolRotator::new(options.ban_time);
 		ValidatedPool {
 			options,
 			listener: Default::default(),
 			api,
 			pool: RwLock::new(base_pool),
 			import_notification_sinks: Default::default(),
-			rotator: Default::default(),
+			rotator,
 		}
 	}
 
@@ -461,25 +465,14 @@ impl<B: ChainApi> ValidatedPool<B> {
 			.ok_or_else(|| error::Error::InvalidBlockId(format!("{:?}", at)).into())?
 			.saturated_into::<u64>();
 		let now = Instant::now();
-		let to_remove = {
-			self.ready()
-				.filter(|tx| self.rotator.ban_if_stale(&now, block_number, &tx))
-				.map(|tx| tx.hash.clone())
-				.collect::<Vec<_>>()
-		};
-		let futures_to_remove: Vec<ExHash<B>> = {
-			let p = self.pool.read();
-			let mut hashes = Vec::new();
-			for tx in p.futures() {
-				if self.rotator.ban_if_stale(&now, block_number, &tx) {
-					hashes.push(tx.hash.clone());
-				}
-			}
-			hashes
-		};
+		// the transactions are looked up by their expiry rather than by going through the pool
+		let expired = self.pool.write().take_expired(block_number);
+		let to_remove = expired.into_iter()
+			.filter(|tx| self.rotator.ban_if_stale(&now, block_number, &tx))
+			.map(|tx| tx.hash.clone())
+			.collect::<Vec<_>>();
 		// removing old transactions
 		self.remove_invalid(&to_remove);
-		self.remove_invalid(&futures_to_remove);
 		// clear banned transactions timeouts
 		self.rotator.clear_timeouts(&now);
 
@@ -551,7 +544,9 @@ impl<B: ChainApi> ValidatedPool<B> {
 
 	/// Returns pool status.
 	pub fn status(&self) -> PoolStatus {
-		self.pool.read().status()
+		let mut status = self.pool.read().status();
+		status.banned = self.rotator.banned_count();
+		status
 	}
 
 	/// Notify all watchers that transactions in the block with hash have been finalized
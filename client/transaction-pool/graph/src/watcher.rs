@@ -82,7 +82,7 @@ impl<H: Clone, BH: Clone> Sender<H, BH> {
 		self.send(TransactionStatus::Future)
 	}
 
-	/// Some state change (perhaps another extrinsic was included) rendered this extrinsic invalid.
+	/// Extrinsic has been replaced in the pool by another extrinsic providing the same tags.
 	pub fn usurped(&mut self, hash: H) {
 		self.send(TransactionStatus::Usurped(hash));
 		self.is_finalized = true;
@@ -99,7 +99,7 @@ impl<H: Clone, BH: Clone> Sender<H, BH> {
 		self.is_finalized = true;
 	}
 
-	/// The block this extrinsic was included in has been retracted
+	/// The block this extrinsic was included in didn't get finalized in time.
 	pub fn finality_timeout(&mut self, hash: BH) {
 		self.send(TransactionStatus::FinalityTimeout(hash));
 		self.is_finalized = true;
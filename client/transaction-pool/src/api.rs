@@ -29,10 +29,10 @@ use sc_client_api::{
 };
 use sp_runtime::{
 	generic::BlockId, traits::{self, Block as BlockT, BlockIdTo, Header as HeaderT, Hash as HashT},
-	transaction_validity::TransactionValidity,
+	transaction_validity::{TransactionValidity, TransactionSource},
 };
 use sp_transaction_pool::runtime_api::TaggedTransactionQueue;
-use sp_api::ProvideRuntimeApi;
+use sp_api::{ProvideRuntimeApi, RuntimeApiInfo, RuntimeVersion};
 
 use crate::error::{self, Error};
 
@@ -81,6 +81,7 @@ impl<Client, Block> sc_transaction_graph::ChainApi for FullChainApi<Client, Bloc
 	fn validate_transaction(
 		&self,
 		at: &BlockId<Self::Block>,
+		source: TransactionSource,
 		uxt: sc_transaction_graph::ExtrinsicFor<Self>,
 	) -> Self::ValidationFuture {
 		let (tx, rx) = oneshot::channel();
@@ -88,8 +89,18 @@ impl<Client, Block> sc_transaction_graph::ChainApi for FullChainApi<Client, Bloc
 		let at = at.clone();
 
 		self.pool.spawn_ok(futures_diagnose::diagnose("validate-transaction", async move {
-			let res = client.runtime_api().validate_transaction(&at, uxt)
-				.map_err(|e| Error::RuntimeApi(format!("{:?}", e)));
+			let runtime_api = client.runtime_api();
+			let has_v2 = runtime_api
+				.has_api_with::<dyn TaggedTransactionQueue<Block, Error = ()>, _>(&at, |v| v >= 2)
+				.unwrap_or_default();
+			let res = if has_v2 {
+				runtime_api.validate_transaction(&at, source, uxt)
+			} else {
+				// the runtimes before the version 2 of the api don't know about the source.
+				#[allow(deprecated)]
+				runtime_api.validate_transaction_before_version_2(&at, uxt)
+			};
+			let res = res.map_err(|e| Error::RuntimeApi(format!("{:?}", e)));
 			if let Err(e) = tx.send(res) {
 				log::warn!("Unable to send a validate transaction result: {:?}", e);
 			}
@@ -154,12 +165,13 @@ impl<Client, F, Block> sc_transaction_graph::ChainApi for LightChainApi<Client,
 	type Block = Block;
 	type Hash = Block::Hash;
 	type Error = error::Error;
-	type ValidationFuture = Box<dyn Future<Output = error::Result<TransactionValidity>> + Send + Unpin>;
+	type ValidationFuture = Pin<Box<dyn Future<Output = error::Result<TransactionValidity>> + Send>>;
 	type BodyFuture = Pin<Box<dyn Future<Output = error::Result<Option<Vec<<Self::Block as BlockT>::Extrinsic>>>> + Send>>;
 
 	fn validate_transaction(
 		&self,
 		at: &BlockId<Self::Block>,
+		source: TransactionSource,
 		uxt: sc_transaction_graph::ExtrinsicFor<Self>,
 	) -> Self::ValidationFuture {
 		let header_hash = self.client.expect_block_hash_from_id(at);
@@ -168,27 +180,37 @@ impl<Client, F, Block> sc_transaction_graph::ChainApi for LightChainApi<Client,
 				.map(|header| (header_hash, header)));
 		let (block, header) = match header_and_hash {
 			Ok((header_hash, header)) => (header_hash, header),
-			Err(err) => return Box::new(ready(Err(err.into()))),
+			Err(err) => return Box::pin(ready(Err(err.into()))),
 		};
-		let remote_validation_request = self.fetcher.remote_call(RemoteCallRequest {
-			block,
-			header,
-			method: "TaggedTransactionQueue_validate_transaction".into(),
-			call_data: uxt.encode(),
-			retry_count: None,
-		});
-		let remote_validation_request = remote_validation_request.then(move |result| {
-			let result: error::Result<TransactionValidity> = result
-				.map_err(Into::into)
-				.and_then(|result| Decode::decode(&mut &result[..])
-					.map_err(|e| Error::RuntimeApi(
-						format!("Error decoding tx validation result: {:?}", e)
-					))
-				);
-			ready(result)
-		});
-
-		Box::new(remote_validation_request)
+
+		let fetcher = self.fetcher.clone();
+		let remote_call = move |method: &str, call_data: Vec<u8>| fetcher.remote_call(
+			RemoteCallRequest {
+				block,
+				header: header.clone(),
+				method: method.into(),
+				call_data,
+				retry_count: None,
+			},
+		);
+
+		async move {
+			let version = remote_call("Core_version", Vec::new()).await?;
+			let version = RuntimeVersion::decode_compat(&version)
+				.map_err(|e| Error::RuntimeApi(format!("Error decoding runtime version: {:?}", e)))?;
+
+			// the runtimes before the version 2 of the api don't know about the source.
+			let has_v2 = version.has_api_with(
+				&<dyn TaggedTransactionQueue<Block, Error = ()> as RuntimeApiInfo>::ID,
+				|v| v >= 2,
+			);
+			let call_data = if has_v2 { (source, uxt).encode() } else { uxt.encode() };
+
+			let result = remote_call("TaggedTransactionQueue_validate_transaction", call_data).await?;
+			let validity: TransactionValidity = Decode::decode(&mut &result[..])
+				.map_err(|e| Error::RuntimeApi(format!("Error decoding tx validation result: {:?}", e)))?;
+			Ok(validity)
+		}.boxed()
 	}
 
 	fn block_id_to_number(&self, at: &BlockId<Self::Block>) -> error::Result<Option<sc_transaction_graph::NumberFor<Self>>> {
@@ -22,6 +22,7 @@
 
 mod api;
 pub mod error;
+mod metrics;
 mod revalidation;
 
 #[cfg(any(feature = "test-helpers", test))]
@@ -36,13 +37,17 @@ use parking_lot::Mutex;
 
 use sp_runtime::{
 	generic::BlockId,
-	traits::{Block as BlockT, NumberFor, AtLeast32Bit, Extrinsic, Zero},
+	traits::{Block as BlockT, NumberFor, AtLeast32Bit, Extrinsic, Zero, Header as HeaderT},
 };
 use sp_transaction_pool::{
 	TransactionPool, PoolStatus, ImportNotificationStream, TxHash, TransactionFor,
 	TransactionStatusStreamFor, MaintainedTransactionPool, PoolFuture, ChainEvent,
+	TransactionSource,
 };
 use wasm_timer::Instant;
+use prometheus_endpoint::Registry as PrometheusRegistry;
+
+use crate::metrics::MetricsLink as PrometheusMetrics;
 
 type BoxedReadyIterator<Hash, Data> = Box<dyn Iterator<Item=Arc<sc_transaction_graph::base_pool::Transaction<Hash, Data>>> + Send>;
 
@@ -61,6 +66,7 @@ pub struct BasicPool<PoolApi, Block>
 	revalidation_strategy: Arc<Mutex<RevalidationStrategy<NumberFor<Block>>>>,
 	revalidation_queue: Arc<revalidation::RevalidationQueue<PoolApi>>,
 	ready_poll: Arc<Mutex<ReadyPoll<ReadyIteratorFor<PoolApi>, Block>>>,
+	metrics: PrometheusMetrics,
 }
 
 struct ReadyPoll<T, Block: BlockT> {
@@ -142,12 +148,13 @@ impl<PoolApi, Block> BasicPool<PoolApi, Block>
 	/// Create new basic transaction pool with provided api.
 	///
 	/// It will also optionally return background task that might be started by the
-	/// caller.
+	/// caller. The metrics of the pool are registered in the Prometheus registry, if any.
 	pub fn new(
 		options: sc_transaction_graph::Options,
 		pool_api: Arc<PoolApi>,
+		prometheus: Option<&PrometheusRegistry>,
 	) -> (Self, Option<Pin<Box<dyn Future<Output=()> + Send>>>) {
-		Self::with_revalidation_type(options, pool_api, RevalidationType::Full)
+		Self::with_revalidation_type(options, pool_api, prometheus, RevalidationType::Full)
 	}
 
 	/// Create new basic transaction pool with provided api and custom
@@ -155,13 +162,21 @@ impl<PoolApi, Block> BasicPool<PoolApi, Block>
 	pub fn with_revalidation_type(
 		options: sc_transaction_graph::Options,
 		pool_api: Arc<PoolApi>,
+		prometheus: Option<&PrometheusRegistry>,
 		revalidation_type: RevalidationType,
 	) -> (Self, Option<Pin<Box<dyn Future<Output=()> + Send>>>) {
+		let batch_size = options.revalidation_batch_size;
+		let interval = options.revalidation_interval;
 		let pool = Arc::new(sc_transaction_graph::Pool::new(options, pool_api.clone()));
 		let (revalidation_queue, background_task) = match revalidation_type {
 			RevalidationType::Light => (revalidation::RevalidationQueue::new(pool_api.clone(), pool.clone()), None),
 			RevalidationType::Full => {
-				let (queue, background) = revalidation::RevalidationQueue::new_background(pool_api.clone(), pool.clone());
+				let (queue, background) = revalidation::RevalidationQueue::new_background(
+					pool_api.clone(),
+					pool.clone(),
+					batch_size,
+					interval,
+				);
 				(queue, Some(background))
 			},
 		};
@@ -177,6 +192,7 @@ impl<PoolApi, Block> BasicPool<PoolApi, Block>
 					}
 				)),
 				ready_poll: Default::default(),
+				metrics: PrometheusMetrics::new(prometheus),
 			},
 			background_task,
 		)
@@ -201,39 +217,61 @@ impl<PoolApi, Block> TransactionPool for BasicPool<PoolApi, Block>
 	fn submit_at(
 		&self,
 		at: &BlockId<Self::Block>,
+		source: TransactionSource,
 		xts: Vec<TransactionFor<Self>>,
 	) -> PoolFuture<Vec<Result<TxHash<Self>, Self::Error>>, Self::Error> {
 		let pool = self.pool.clone();
 		let at = *at;
+		let metrics = self.metrics.clone();
+		metrics.report(|metrics| metrics.submitted_transactions.inc_by(xts.len() as u64));
+
 		async move {
-			pool.submit_at(&at, xts, false).await
+			let results = pool.submit_at(&at, source, xts, false).await;
+			if let Ok(results) = results.as_ref() {
+				let invalid = results.iter().filter(|result| result.is_err()).count();
+				metrics.report(|metrics| metrics.validations_invalid.inc_by(invalid as u64));
+			}
+			results
 		}.boxed()
 	}
 
 	fn submit_one(
 		&self,
 		at: &BlockId<Self::Block>,
+		source: TransactionSource,
 		xt: TransactionFor<Self>,
 	) -> PoolFuture<TxHash<Self>, Self::Error> {
 		let pool = self.pool.clone();
 		let at = *at;
+		let metrics = self.metrics.clone();
+		metrics.report(|metrics| metrics.submitted_transactions.inc());
+
 		async move {
-			pool.submit_one(&at, xt).await
+			let result = pool.submit_one(&at, source, xt).await;
+			if result.is_err() {
+				metrics.report(|metrics| metrics.validations_invalid.inc());
+			}
+			result
 		}.boxed()
 	}
 
 	fn submit_and_watch(
 		&self,
 		at: &BlockId<Self::Block>,
+		source: TransactionSource,
 		xt: TransactionFor<Self>,
 	) -> PoolFuture<Box<TransactionStatusStreamFor<Self>>, Self::Error> {
 		let at = *at;
 		let pool = self.pool.clone();
+		let metrics = self.metrics.clone();
+		metrics.report(|metrics| metrics.submitted_transactions.inc());
 
 		async move {
-			pool.submit_and_watch(&at, xt)
-				.map(|result| result.map(|watcher| Box::new(watcher.into_stream()) as _))
-				.await
+			let result = pool.submit_and_watch(&at, source, xt).await;
+			if result.is_err() {
+				metrics.report(|metrics| metrics.validations_invalid.inc());
+			}
+			result.map(|watcher| Box::new(watcher.into_stream()) as _)
 		}.boxed()
 	}
 
@@ -374,8 +412,9 @@ impl<PoolApi, Block> MaintainedTransactionPool for BasicPool<PoolApi, Block>
 {
 	fn maintain(&self, event: ChainEvent<Self::Block>) -> Pin<Box<dyn Future<Output=()> + Send>> {
 		match event {
-			ChainEvent::NewBlock { id, retracted, .. } => {
+			ChainEvent::NewBlock { id, header, retracted, is_major_syncing, .. } => {
 				let id = id.clone();
+				let parent_id = BlockId::hash(header.parent_hash().clone());
 				let pool = self.pool.clone();
 				let api = self.api.clone();
 
@@ -392,40 +431,55 @@ impl<PoolApi, Block> MaintainedTransactionPool for BasicPool<PoolApi, Block>
 					Some(std::time::Duration::from_secs(60)),
 					Some(20.into()),
 				);
+				// the blocks imported during a major sync are soon outdated, so revalidating the
+				// transactions against them is a waste.
+				let revalidate = next_action.revalidate && !is_major_syncing;
 				let revalidation_strategy = self.revalidation_strategy.clone();
 				let retracted = retracted.clone();
 				let revalidation_queue = self.revalidation_queue.clone();
 				let ready_poll = self.ready_poll.clone();
+				let metrics = self.metrics.clone();
 
 				async move {
 					// We don't query block if we won't prune anything
 					if !pool.validated_pool().status().is_empty() {
-						let hashes = api.block_body(&id).await
+						let extrinsics = api.block_body(&id).await
 							.unwrap_or_else(|e| {
 								log::warn!("Prune known transactions: error request {:?}!", e);
 								None
 							})
-							.unwrap_or_default()
-							.into_iter()
-							.map(|tx| pool.hash_of(&tx))
-							.collect::<Vec<_>>();
+							.unwrap_or_default();
 
-						if let Err(e) = pool.prune_known(&id, &hashes) {
+						// The transactions of the block that aren't in the pool, e.g. authored by
+						// another node on a fork, are validated at the parent block so that the
+						// future transactions depending on their tags are promoted.
+						if let Err(e) = pool.prune(&id, &parent_id, &extrinsics).await {
 							log::error!("Cannot prune known in the pool {:?}!", e);
 						}
+						metrics.report(|metrics|
+							metrics.block_transactions_pruned.inc_by(extrinsics.len() as u64)
+						);
+
+						// The transactions past their longevity can't be included anymore.
+						if let Err(e) = pool.validated_pool().clear_stale(&id) {
+							log::error!("Cannot clear stale transactions in the pool {:?}!", e);
+						}
 					}
 
 					let extra_pool = pool.clone();
 					// After #5200 lands, this arguably might be moved to the handler of "all blocks notification".
 					ready_poll.lock().trigger(block_number, move || Box::new(extra_pool.validated_pool().ready()));
 
+					// notify txs awaiting finality that their block has been retracted, whether
+					// they are resubmitted or not.
+					for retracted_hash in &retracted {
+						pool.validated_pool().on_block_retracted(retracted_hash.clone());
+					}
+
 					if next_action.resubmit {
 						let mut resubmit_transactions = Vec::new();
 
 						for retracted_hash in retracted {
-							// notify txs awaiting finality that it has been retracted
-							pool.validated_pool().on_block_retracted(retracted_hash.clone());
-
 							let block_transactions = api.block_body(&BlockId::hash(retracted_hash.clone())).await
 								.unwrap_or_else(|e| {
 									log::warn!("Failed to fetch block body {:?}!", e);
@@ -437,7 +491,17 @@ impl<PoolApi, Block> MaintainedTransactionPool for BasicPool<PoolApi, Block>
 
 							resubmit_transactions.extend(block_transactions);
 						}
-						if let Err(e) = pool.submit_at(&id, resubmit_transactions, true).await {
+						metrics.report(|metrics| metrics.block_transactions_resubmitted
+							.inc_by(resubmit_transactions.len() as u64)
+						);
+						// the transactions of the retracted blocks are no longer part of the
+						// canonical chain, so they are considered as external ones.
+						if let Err(e) = pool.submit_at(
+							&id,
+							TransactionSource::External,
+							resubmit_transactions,
+							true,
+						).await {
 							log::debug!(
 								target: "txpool",
 								"[{:?}] Error re-submitting transactions: {:?}", id, e
@@ -445,7 +509,7 @@ impl<PoolApi, Block> MaintainedTransactionPool for BasicPool<PoolApi, Block>
 						}
 					}
 
-					if next_action.revalidate {
+					if revalidate {
 						let hashes = pool.validated_pool().ready().map(|tx| tx.hash.clone()).collect();
 						revalidation_queue.revalidate_later(block_number, hashes).await;
 					}
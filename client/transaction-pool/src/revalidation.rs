@@ -27,13 +27,9 @@ use futures::{prelude::*, channel::mpsc, stream::unfold};
 use std::time::Duration;
 use futures_timer::Delay;
 
-#[cfg(not(test))]
-const BACKGROUND_REVALIDATION_INTERVAL: Duration = Duration::from_millis(200);
 #[cfg(test)]
 pub const BACKGROUND_REVALIDATION_INTERVAL: Duration = Duration::from_millis(5);
 
-const BACKGROUND_REVALIDATION_BATCH_SIZE: usize = 20;
-
 /// Payload from queue to worker.
 struct WorkerPayload<Api: ChainApi> {
 	at: NumberFor<Api>,
@@ -47,6 +43,8 @@ struct RevalidationWorker<Api: ChainApi> {
 	api: Arc<Api>,
 	pool: Arc<Pool<Api>>,
 	best_block: NumberFor<Api>,
+	batch_size: usize,
+	interval: Duration,
 	block_ordered: BTreeMap<NumberFor<Api>, HashSet<ExHash<Api>>>,
 	members: HashMap<ExHash<Api>, NumberFor<Api>>,
 }
@@ -78,7 +76,7 @@ async fn batch_revalidate<Api: ChainApi>(
 			None => continue,
 		};
 
-		match api.validate_transaction(&BlockId::Number(at), ext.data.clone()).await {
+		match api.validate_transaction(&BlockId::Number(at), ext.source, ext.data.clone()).await {
 			Ok(Err(TransactionValidityError::Invalid(err))) => {
 				log::debug!(target: "txpool", "[{:?}]: Revalidation: invalid {:?}", ext_hash, err);
 				invalid_hashes.push(ext_hash);
@@ -94,6 +92,7 @@ async fn batch_revalidate<Api: ChainApi>(
 					ValidatedTransaction::valid_at(
 						at.saturated_into::<u64>(),
 						ext_hash,
+						ext.source,
 						ext.data.clone(),
 						api.hash_and_length(&ext.data).1,
 						validity,
@@ -122,10 +121,14 @@ impl<Api: ChainApi> RevalidationWorker<Api> {
 	fn new(
 		api: Arc<Api>,
 		pool: Arc<Pool<Api>>,
+		batch_size: usize,
+		interval: Duration,
 	) -> Self {
 		Self {
 			api,
 			pool,
+			batch_size,
+			interval,
 			block_ordered: Default::default(),
 			members: Default::default(),
 			best_block: Zero::zero(),
@@ -134,7 +137,7 @@ impl<Api: ChainApi> RevalidationWorker<Api> {
 
 	fn prepare_batch(&mut self) -> Vec<ExHash<Api>> {
 		let mut queued_exts = Vec::new();
-		let mut left = BACKGROUND_REVALIDATION_BATCH_SIZE;
+		let mut left = self.batch_size;
 
 		// Take maximum of count transaction by order
 		// which they got into the pool
@@ -207,7 +210,7 @@ impl<Api: ChainApi> RevalidationWorker<Api> {
 	/// from the queue and also accepts messages to enqueue some more
 	/// transactions from the pool.
 	pub async fn run(mut self, from_queue: mpsc::UnboundedReceiver<WorkerPayload<Api>>) {
-		let interval = interval(BACKGROUND_REVALIDATION_INTERVAL).fuse();
+		let interval = interval(self.interval).fuse();
 		let from_queue = from_queue.fuse();
 		futures::pin_mut!(interval, from_queue);
 		let this = &mut self;
@@ -270,12 +273,17 @@ where
 	}
 
 	/// New revalidation queue with background worker.
-	pub fn new_background(api: Arc<Api>, pool: Arc<Pool<Api>>) ->
-		(Self, Pin<Box<dyn Future<Output=()> + Send>>)
-	{
+	///
+	/// The worker revalidates `batch_size` transactions every `interval`.
+	pub fn new_background(
+		api: Arc<Api>,
+		pool: Arc<Pool<Api>>,
+		batch_size: usize,
+		interval: Duration,
+	) -> (Self, Pin<Box<dyn Future<Output=()> + Send>>) {
 		let (to_worker, from_queue) = mpsc::unbounded();
 
-		let worker = RevalidationWorker::new(api.clone(), pool.clone());
+		let worker = RevalidationWorker::new(api.clone(), pool.clone(), batch_size, interval);
 
 		let queue =
 			Self {
@@ -317,6 +325,7 @@ mod tests {
 	use sc_transaction_graph::Pool;
 	use substrate_test_runtime_transaction_pool::{TestApi, uxt};
 	use futures::executor::block_on;
+	use sp_runtime::transaction_validity::TransactionSource;
 	use substrate_test_runtime_client::{
 		AccountKeyring::*,
 	};
@@ -334,7 +343,9 @@ mod tests {
 		let queue = Arc::new(RevalidationQueue::new(api.clone(), pool.clone()));
 
 		let uxt = uxt(Alice, 0);
-		let uxt_hash = block_on(pool.submit_one(&BlockId::number(0), uxt.clone())).expect("Should be valid");
+		let uxt_hash = block_on(
+			pool.submit_one(&BlockId::number(0), TransactionSource::External, uxt.clone())
+		).expect("Should be valid");
 
 		block_on(queue.revalidate_later(0, vec![uxt_hash]));
 
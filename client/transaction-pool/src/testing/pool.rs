@@ -30,12 +30,22 @@ use substrate_test_runtime_transaction_pool::{TestApi, uxt};
 use crate::revalidation::BACKGROUND_REVALIDATION_INTERVAL;
 use futures::task::Poll;
 
+const SOURCE: TransactionSource = TransactionSource::External;
+
 fn pool() -> Pool<TestApi> {
 	Pool::new(Default::default(), TestApi::with_alice_nonce(209).into())
 }
 
 fn maintained_pool() -> (BasicPool<TestApi, Block>, futures::executor::ThreadPool) {
-	let (pool, background_task) = BasicPool::new(Default::default(), std::sync::Arc::new(TestApi::with_alice_nonce(209)));
+	let options = txpool::Options {
+		revalidation_interval: BACKGROUND_REVALIDATION_INTERVAL,
+		..Default::default()
+	};
+	let (pool, background_task) = BasicPool::new(
+		options,
+		std::sync::Arc::new(TestApi::with_alice_nonce(209)),
+		None,
+	);
 
 	let thread_pool = futures::executor::ThreadPool::new().unwrap();
 	thread_pool.spawn_ok(background_task.expect("basic pool have background task"));
@@ -55,7 +65,7 @@ fn header(number: u64) -> Header {
 #[test]
 fn submission_should_work() {
 	let pool = pool();
-	block_on(pool.submit_one(&BlockId::number(0), uxt(Alice, 209))).unwrap();
+	block_on(pool.submit_one(&BlockId::number(0), SOURCE, uxt(Alice, 209))).unwrap();
 
 	let pending: Vec<_> = pool.validated_pool().ready().map(|a| a.data.transfer().nonce).collect();
 	assert_eq!(pending, vec![209]);
@@ -64,8 +74,8 @@ fn submission_should_work() {
 #[test]
 fn multiple_submission_should_work() {
 	let pool = pool();
-	block_on(pool.submit_one(&BlockId::number(0), uxt(Alice, 209))).unwrap();
-	block_on(pool.submit_one(&BlockId::number(0), uxt(Alice, 210))).unwrap();
+	block_on(pool.submit_one(&BlockId::number(0), SOURCE, uxt(Alice, 209))).unwrap();
+	block_on(pool.submit_one(&BlockId::number(0), SOURCE, uxt(Alice, 210))).unwrap();
 
 	let pending: Vec<_> = pool.validated_pool().ready().map(|a| a.data.transfer().nonce).collect();
 	assert_eq!(pending, vec![209, 210]);
@@ -74,7 +84,7 @@ fn multiple_submission_should_work() {
 #[test]
 fn early_nonce_should_be_culled() {
 	let pool = pool();
-	block_on(pool.submit_one(&BlockId::number(0), uxt(Alice, 208))).unwrap();
+	block_on(pool.submit_one(&BlockId::number(0), SOURCE, uxt(Alice, 208))).unwrap();
 
 	let pending: Vec<_> = pool.validated_pool().ready().map(|a| a.data.transfer().nonce).collect();
 	assert_eq!(pending, Vec::<Index>::new());
@@ -84,11 +94,11 @@ fn early_nonce_should_be_culled() {
 fn late_nonce_should_be_queued() {
 	let pool = pool();
 
-	block_on(pool.submit_one(&BlockId::number(0), uxt(Alice, 210))).unwrap();
+	block_on(pool.submit_one(&BlockId::number(0), SOURCE, uxt(Alice, 210))).unwrap();
 	let pending: Vec<_> = pool.validated_pool().ready().map(|a| a.data.transfer().nonce).collect();
 	assert_eq!(pending, Vec::<Index>::new());
 
-	block_on(pool.submit_one(&BlockId::number(0), uxt(Alice, 209))).unwrap();
+	block_on(pool.submit_one(&BlockId::number(0), SOURCE, uxt(Alice, 209))).unwrap();
 	let pending: Vec<_> = pool.validated_pool().ready().map(|a| a.data.transfer().nonce).collect();
 	assert_eq!(pending, vec![209, 210]);
 }
@@ -96,8 +106,8 @@ fn late_nonce_should_be_queued() {
 #[test]
 fn prune_tags_should_work() {
 	let pool = pool();
-	let hash209 = block_on(pool.submit_one(&BlockId::number(0), uxt(Alice, 209))).unwrap();
-	block_on(pool.submit_one(&BlockId::number(0), uxt(Alice, 210))).unwrap();
+	let hash209 = block_on(pool.submit_one(&BlockId::number(0), SOURCE, uxt(Alice, 209))).unwrap();
+	block_on(pool.submit_one(&BlockId::number(0), SOURCE, uxt(Alice, 210))).unwrap();
 
 	let pending: Vec<_> = pool.validated_pool().ready().map(|a| a.data.transfer().nonce).collect();
 	assert_eq!(pending, vec![209, 210]);
@@ -118,16 +128,16 @@ fn prune_tags_should_work() {
 fn should_ban_invalid_transactions() {
 	let pool = pool();
 	let uxt = uxt(Alice, 209);
-	let hash = block_on(pool.submit_one(&BlockId::number(0), uxt.clone())).unwrap();
+	let hash = block_on(pool.submit_one(&BlockId::number(0), SOURCE, uxt.clone())).unwrap();
 	pool.validated_pool().remove_invalid(&[hash]);
-	block_on(pool.submit_one(&BlockId::number(0), uxt.clone())).unwrap_err();
+	block_on(pool.submit_one(&BlockId::number(0), SOURCE, uxt.clone())).unwrap_err();
 
 	// when
 	let pending: Vec<_> = pool.validated_pool().ready().map(|a| a.data.transfer().nonce).collect();
 	assert_eq!(pending, Vec::<Index>::new());
 
 	// then
-	block_on(pool.submit_one(&BlockId::number(0), uxt.clone())).unwrap_err();
+	block_on(pool.submit_one(&BlockId::number(0), SOURCE, uxt.clone())).unwrap_err();
 }
 
 #[test]
@@ -138,7 +148,7 @@ fn should_correctly_prune_transactions_providing_more_than_one_tag() {
 	}));
 	let pool = Pool::new(Default::default(), api.clone());
 	let xt = uxt(Alice, 209);
-	block_on(pool.submit_one(&BlockId::number(0), xt.clone())).expect("1. Imported");
+	block_on(pool.submit_one(&BlockId::number(0), SOURCE, xt.clone())).expect("1. Imported");
 	assert_eq!(pool.validated_pool().status().ready, 1);
 
 	// remove the transaction that just got imported.
@@ -151,7 +161,7 @@ fn should_correctly_prune_transactions_providing_more_than_one_tag() {
 	// so now let's insert another transaction that also provides the 155
 	api.increment_nonce(Alice.into());
 	let xt = uxt(Alice, 211);
-	block_on(pool.submit_one(&BlockId::number(2), xt.clone())).expect("2. Imported");
+	block_on(pool.submit_one(&BlockId::number(2), SOURCE, xt.clone())).expect("2. Imported");
 	assert_eq!(pool.validated_pool().status().ready, 1);
 	assert_eq!(pool.validated_pool().status().future, 1);
 	let pending: Vec<_> = pool.validated_pool().ready().map(|a| a.data.transfer().nonce).collect();
@@ -168,6 +178,7 @@ fn block_event(id: u64) -> ChainEvent<Block> {
 	ChainEvent::NewBlock {
 		id: BlockId::number(id),
 		is_new_best: true,
+		is_major_syncing: false,
 		retracted: vec![],
 		header: header(id),
 	}
@@ -177,6 +188,7 @@ fn block_event_with_retracted(id: u64, retracted: Vec<Hash>) -> ChainEvent<Block
 	ChainEvent::NewBlock {
 		id: BlockId::number(id),
 		is_new_best: true,
+		is_major_syncing: false,
 		retracted: retracted,
 		header: header(id),
 	}
@@ -189,7 +201,7 @@ fn should_prune_old_during_maintenance() {
 
 	let (pool, _guard) = maintained_pool();
 
-	block_on(pool.submit_one(&BlockId::number(0), xt.clone())).expect("1. Imported");
+	block_on(pool.submit_one(&BlockId::number(0), SOURCE, xt.clone())).expect("1. Imported");
 	assert_eq!(pool.status().ready, 1);
 
 	pool.api.push_block(1, vec![xt.clone()]);
@@ -198,14 +210,49 @@ fn should_prune_old_during_maintenance() {
 	assert_eq!(pool.status().ready, 0);
 }
 
+#[test]
+fn should_promote_future_when_a_block_includes_an_unknown_transaction() {
+	let (pool, _guard) = maintained_pool();
+
+	block_on(pool.submit_one(&BlockId::number(0), SOURCE, uxt(Alice, 210))).expect("1. Imported");
+	assert_eq!(pool.status().ready, 0);
+	assert_eq!(pool.status().future, 1);
+
+	// the transaction unlocking the future one was never submitted to this pool.
+	pool.api.push_block(1, vec![uxt(Alice, 209)]);
+
+	block_on(pool.maintain(block_event(1)));
+	assert_eq!(pool.status().ready, 1);
+	assert_eq!(pool.status().future, 0);
+}
+
+#[test]
+fn should_drop_stale_during_maintenance() {
+	let xt = uxt(Alice, 209);
+
+	let (pool, _guard) = maintained_pool();
+
+	block_on(pool.submit_one(&BlockId::number(0), SOURCE, xt.clone())).expect("1. Imported");
+	assert_eq!(pool.status().ready, 1);
+
+	// the transaction is valid for 64 blocks, and isn't part of any of them.
+	pool.api.push_block(63, vec![]);
+	block_on(pool.maintain(block_event(63)));
+	assert_eq!(pool.status().ready, 1);
+
+	pool.api.push_block(64, vec![]);
+	block_on(pool.maintain(block_event(64)));
+	assert_eq!(pool.status().ready, 0);
+}
+
 #[test]
 fn should_revalidate_during_maintenance() {
 	let xt1 = uxt(Alice, 209);
 	let xt2 = uxt(Alice, 210);
 
 	let (pool, _guard) = maintained_pool();
-	block_on(pool.submit_one(&BlockId::number(0), xt1.clone())).expect("1. Imported");
-	block_on(pool.submit_one(&BlockId::number(0), xt2.clone())).expect("2. Imported");
+	block_on(pool.submit_one(&BlockId::number(0), SOURCE, xt1.clone())).expect("1. Imported");
+	block_on(pool.submit_one(&BlockId::number(0), SOURCE, xt2.clone())).expect("2. Imported");
 	assert_eq!(pool.status().ready, 2);
 	assert_eq!(pool.api.validation_requests().len(), 2);
 
@@ -222,6 +269,33 @@ fn should_revalidate_during_maintenance() {
 	assert_eq!(pool.api.validation_requests().len(), 3);
 }
 
+#[test]
+fn should_not_revalidate_during_major_sync() {
+	let xt1 = uxt(Alice, 209);
+	let xt2 = uxt(Alice, 210);
+
+	let (pool, _guard) = maintained_pool();
+	block_on(pool.submit_one(&BlockId::number(0), SOURCE, xt1.clone())).expect("1. Imported");
+	block_on(pool.submit_one(&BlockId::number(0), SOURCE, xt2.clone())).expect("2. Imported");
+	assert_eq!(pool.api.validation_requests().len(), 2);
+
+	pool.api.push_block(1, vec![xt1.clone()]);
+
+	let event = ChainEvent::NewBlock {
+		id: BlockId::number(1),
+		is_new_best: true,
+		is_major_syncing: true,
+		retracted: vec![],
+		header: header(1),
+	};
+	block_on(pool.maintain(event));
+	block_on(futures_timer::Delay::new(BACKGROUND_REVALIDATION_INTERVAL*2));
+
+	// the included transaction is still pruned, but the other one isn't revalidated.
+	assert_eq!(pool.status().ready, 1);
+	assert_eq!(pool.api.validation_requests().len(), 2);
+}
+
 #[test]
 fn should_resubmit_from_retracted_during_maintenance() {
 	let xt = uxt(Alice, 209);
@@ -229,7 +303,7 @@ fn should_resubmit_from_retracted_during_maintenance() {
 
 	let (pool, _guard) = maintained_pool();
 
-	block_on(pool.submit_one(&BlockId::number(0), xt.clone())).expect("1. Imported");
+	block_on(pool.submit_one(&BlockId::number(0), SOURCE, xt.clone())).expect("1. Imported");
 	assert_eq!(pool.status().ready, 1);
 
 	pool.api.push_block(1, vec![]);
@@ -248,7 +322,7 @@ fn should_not_retain_invalid_hashes_from_retracted() {
 
 	let (pool, _guard) = maintained_pool();
 
-	block_on(pool.submit_one(&BlockId::number(0), xt.clone())).expect("1. Imported");
+	block_on(pool.submit_one(&BlockId::number(0), SOURCE, xt.clone())).expect("1. Imported");
 	assert_eq!(pool.status().ready, 1);
 
 	pool.api.push_block(1, vec![]);
@@ -274,7 +348,7 @@ fn should_revalidate_transaction_multiple_times() {
 
 	let (pool, _guard) = maintained_pool();
 
-	block_on(pool.submit_one(&BlockId::number(0), xt.clone())).expect("1. Imported");
+	block_on(pool.submit_one(&BlockId::number(0), SOURCE, xt.clone())).expect("1. Imported");
 	assert_eq!(pool.status().ready, 1);
 
 	pool.api.push_block(1, vec![xt.clone()]);
@@ -283,7 +357,7 @@ fn should_revalidate_transaction_multiple_times() {
 	block_on(pool.maintain(block_event(1)));
 	block_on(futures_timer::Delay::new(BACKGROUND_REVALIDATION_INTERVAL*2));
 
-	block_on(pool.submit_one(&BlockId::number(0), xt.clone())).expect("1. Imported");
+	block_on(pool.submit_one(&BlockId::number(0), SOURCE, xt.clone())).expect("1. Imported");
 	assert_eq!(pool.status().ready, 1);
 
 	pool.api.push_block(2, vec![]);
@@ -304,8 +378,8 @@ fn should_revalidate_across_many_blocks() {
 
 	let (pool, _guard) = maintained_pool();
 
-	block_on(pool.submit_one(&BlockId::number(1), xt1.clone())).expect("1. Imported");
-	block_on(pool.submit_one(&BlockId::number(1), xt2.clone())).expect("1. Imported");
+	block_on(pool.submit_one(&BlockId::number(1), SOURCE, xt1.clone())).expect("1. Imported");
+	block_on(pool.submit_one(&BlockId::number(1), SOURCE, xt2.clone())).expect("1. Imported");
 	assert_eq!(pool.status().ready, 2);
 
 	pool.api.push_block(1, vec![]);
@@ -313,7 +387,7 @@ fn should_revalidate_across_many_blocks() {
 	block_on(futures_timer::Delay::new(BACKGROUND_REVALIDATION_INTERVAL*2));
 
 
-	block_on(pool.submit_one(&BlockId::number(2), xt3.clone())).expect("1. Imported");
+	block_on(pool.submit_one(&BlockId::number(2), SOURCE, xt3.clone())).expect("1. Imported");
 	assert_eq!(pool.status().ready, 3);
 
 	pool.api.push_block(2, vec![xt1.clone()]);
@@ -336,15 +410,20 @@ fn should_push_watchers_during_maintaince() {
 	let (pool, _guard) = maintained_pool();
 
 	let tx0 = alice_uxt(0);
-	let watcher0 = block_on(pool.submit_and_watch(&BlockId::Number(0), tx0.clone())).unwrap();
+	let watcher0 = block_on(pool.submit_and_watch(&BlockId::Number(0), SOURCE, tx0.clone()))
+		.unwrap();
 	let tx1 = alice_uxt(1);
-	let watcher1 = block_on(pool.submit_and_watch(&BlockId::Number(0), tx1.clone())).unwrap();
+	let watcher1 = block_on(pool.submit_and_watch(&BlockId::Number(0), SOURCE, tx1.clone()))
+		.unwrap();
 	let tx2 = alice_uxt(2);
-	let watcher2 = block_on(pool.submit_and_watch(&BlockId::Number(0), tx2.clone())).unwrap();
+	let watcher2 = block_on(pool.submit_and_watch(&BlockId::Number(0), SOURCE, tx2.clone()))
+		.unwrap();
 	let tx3 = alice_uxt(3);
-	let watcher3 = block_on(pool.submit_and_watch(&BlockId::Number(0), tx3.clone())).unwrap();
+	let watcher3 = block_on(pool.submit_and_watch(&BlockId::Number(0), SOURCE, tx3.clone()))
+		.unwrap();
 	let tx4 = alice_uxt(4);
-	let watcher4 = block_on(pool.submit_and_watch(&BlockId::Number(0), tx4.clone())).unwrap();
+	let watcher4 = block_on(pool.submit_and_watch(&BlockId::Number(0), SOURCE, tx4.clone()))
+		.unwrap();
 	assert_eq!(pool.status().ready, 5);
 
 	// when
@@ -394,13 +473,39 @@ fn should_push_watchers_during_maintaince() {
 	);
 }
 
+#[test]
+fn should_notify_retracted_without_resubmission() {
+	// given
+	let (pool, _) = BasicPool::with_revalidation_type(
+		Default::default(),
+		std::sync::Arc::new(TestApi::with_alice_nonce(209)),
+		None,
+		RevalidationType::Light,
+	);
+	let xt = uxt(Alice, 209);
+	let watcher = block_on(pool.submit_and_watch(&BlockId::number(0), SOURCE, xt.clone())).unwrap();
+
+	let header_hash = pool.api.push_block(1, vec![xt]).hash();
+	block_on(pool.maintain(block_event(1)));
+
+	// when
+	pool.api.push_block(2, vec![]);
+	block_on(pool.maintain(block_event_with_retracted(2, vec![header_hash])));
+
+	// then
+	let mut stream = futures::executor::block_on_stream(watcher);
+	assert_eq!(stream.next(), Some(TransactionStatus::Ready));
+	assert_eq!(stream.next(), Some(TransactionStatus::InBlock(header_hash)));
+	assert_eq!(stream.next(), Some(TransactionStatus::Retracted(header_hash)));
+}
+
 #[test]
 fn can_track_heap_size() {
 	let (pool, _guard) = maintained_pool();
-	block_on(pool.submit_one(&BlockId::number(0), uxt(Alice, 209))).expect("1. Imported");
-	block_on(pool.submit_one(&BlockId::number(0), uxt(Alice, 210))).expect("1. Imported");
-	block_on(pool.submit_one(&BlockId::number(0), uxt(Alice, 211))).expect("1. Imported");
-	block_on(pool.submit_one(&BlockId::number(0), uxt(Alice, 212))).expect("1. Imported");
+	block_on(pool.submit_one(&BlockId::number(0), SOURCE, uxt(Alice, 209))).expect("1. Imported");
+	block_on(pool.submit_one(&BlockId::number(0), SOURCE, uxt(Alice, 210))).expect("1. Imported");
+	block_on(pool.submit_one(&BlockId::number(0), SOURCE, uxt(Alice, 211))).expect("1. Imported");
+	block_on(pool.submit_one(&BlockId::number(0), SOURCE, uxt(Alice, 212))).expect("1. Imported");
 
 	assert!(parity_util_mem::malloc_size(&pool) > 3000);
 }
@@ -410,14 +515,16 @@ fn finalization() {
 	let xt = uxt(Alice, 209);
 	let api = TestApi::with_alice_nonce(209);
 	api.push_block(1, vec![]);
-	let (pool, _background) = BasicPool::new(Default::default(), api.into());
-	let watcher = block_on(pool.submit_and_watch(&BlockId::number(1), xt.clone())).expect("1. Imported");
+	let (pool, _background) = BasicPool::new(Default::default(), api.into(), None);
+	let watcher = block_on(pool.submit_and_watch(&BlockId::number(1), SOURCE, xt.clone()))
+		.expect("1. Imported");
 	pool.api.push_block(2, vec![xt.clone()]);
 
 	let header = pool.api.chain().read().header_by_number.get(&2).cloned().unwrap();
 	let event = ChainEvent::NewBlock {
 		id: BlockId::Hash(header.hash()),
 		is_new_best: true,
+		is_major_syncing: false,
 		header: header.clone(),
 		retracted: vec![]
 	};
@@ -439,7 +546,7 @@ fn fork_aware_finalization() {
 	// starting block A1 (last finalized.)
 	api.push_block(1, vec![]);
 
-	let (pool, _background) = BasicPool::new(Default::default(), api.into());
+	let (pool, _background) = BasicPool::new(Default::default(), api.into(), None);
 	let mut canon_watchers = vec![];
 
 	let from_alice = uxt(Alice, 1);
@@ -461,7 +568,9 @@ fn fork_aware_finalization() {
 
 	// block B1
 	{
-		let watcher = block_on(pool.submit_and_watch(&BlockId::number(1), from_alice.clone())).expect("1. Imported");
+		let watcher = block_on(
+			pool.submit_and_watch(&BlockId::number(1), SOURCE, from_alice.clone())
+		).expect("1. Imported");
 		let header = pool.api.push_block(2, vec![from_alice.clone()]);
 		canon_watchers.push((watcher, header.hash()));
 		assert_eq!(pool.status().ready, 1);
@@ -469,6 +578,7 @@ fn fork_aware_finalization() {
 		let event = ChainEvent::NewBlock {
 			id: BlockId::Number(2),
 			is_new_best: true,
+			is_major_syncing: false,
 			header: header.clone(),
 			retracted: vec![],
 		};
@@ -482,12 +592,14 @@ fn fork_aware_finalization() {
 	// block C2
 	{
 		let header = pool.api.push_fork_block_with_parent(b1, vec![from_dave.clone()]);
-		from_dave_watcher = block_on(pool.submit_and_watch(&BlockId::number(1), from_dave.clone()))
-			.expect("1. Imported");
+		from_dave_watcher = block_on(
+			pool.submit_and_watch(&BlockId::number(1), SOURCE, from_dave.clone())
+		).expect("1. Imported");
 		assert_eq!(pool.status().ready, 1);
 		let event = ChainEvent::NewBlock {
 			id: BlockId::Hash(header.hash()),
 			is_new_best: true,
+			is_major_syncing: false,
 			header: header.clone(),
 			retracted: vec![]
 		};
@@ -498,13 +610,16 @@ fn fork_aware_finalization() {
 
 	// block D2
 	{
-		from_bob_watcher = block_on(pool.submit_and_watch(&BlockId::number(1), from_bob.clone())).expect("1. Imported");
+		from_bob_watcher = block_on(
+			pool.submit_and_watch(&BlockId::number(1), SOURCE, from_bob.clone())
+		).expect("1. Imported");
 		assert_eq!(pool.status().ready, 1);
 		let header = pool.api.push_fork_block_with_parent(c2, vec![from_bob.clone()]);
 
 		let event = ChainEvent::NewBlock {
 			id: BlockId::Hash(header.hash()),
 			is_new_best: true,
+			is_major_syncing: false,
 			header: header.clone(),
 			retracted: vec![]
 		};
@@ -515,7 +630,9 @@ fn fork_aware_finalization() {
 
 	// block C1
 	{
-		let watcher = block_on(pool.submit_and_watch(&BlockId::number(1), from_charlie.clone())).expect("1.Imported");
+		let watcher = block_on(
+			pool.submit_and_watch(&BlockId::number(1), SOURCE, from_charlie.clone())
+		).expect("1.Imported");
 		assert_eq!(pool.status().ready, 1);
 		let header = pool.api.push_block(3, vec![from_charlie.clone()]);
 
@@ -523,6 +640,7 @@ fn fork_aware_finalization() {
 		let event = ChainEvent::NewBlock {
 			id: BlockId::Number(3),
 			is_new_best: true,
+			is_major_syncing: false,
 			header: header.clone(),
 			retracted: vec![c2, d2],
 		};
@@ -535,7 +653,8 @@ fn fork_aware_finalization() {
 	// block D1
 	{
 		let xt = uxt(Eve, 0);
-		let w = block_on(pool.submit_and_watch(&BlockId::number(1), xt.clone())).expect("1. Imported");
+		let w = block_on(pool.submit_and_watch(&BlockId::number(1), SOURCE, xt.clone()))
+			.expect("1. Imported");
 		assert_eq!(pool.status().ready, 3);
 		let header = pool.api.push_block(4, vec![xt.clone()]);
 		canon_watchers.push((w, header.hash()));
@@ -543,6 +662,7 @@ fn fork_aware_finalization() {
 		let event = ChainEvent::NewBlock {
 			id: BlockId::Hash(header.hash()),
 			is_new_best: true,
+			is_major_syncing: false,
 			header: header.clone(),
 			retracted: vec![]
 		};
@@ -562,6 +682,7 @@ fn fork_aware_finalization() {
 		let event = ChainEvent::NewBlock {
 			id: BlockId::Hash(header.hash()),
 			is_new_best: true,
+			is_major_syncing: false,
 			header: header.clone(),
 			retracted: vec![]
 		};
@@ -607,7 +728,7 @@ fn fork_aware_finalization() {
 fn ready_set_should_not_resolve_before_block_update() {
 	let (pool, _guard) = maintained_pool();
 	let xt1 = uxt(Alice, 209);
-	block_on(pool.submit_one(&BlockId::number(1), xt1.clone())).expect("1. Imported");
+	block_on(pool.submit_one(&BlockId::number(1), SOURCE, xt1.clone())).expect("1. Imported");
 
 	assert!(pool.ready_at(1).now_or_never().is_none());
 }
@@ -619,7 +740,7 @@ fn ready_set_should_resolve_after_block_update() {
 
 	let xt1 = uxt(Alice, 209);
 
-	block_on(pool.submit_one(&BlockId::number(1), xt1.clone())).expect("1. Imported");
+	block_on(pool.submit_one(&BlockId::number(1), SOURCE, xt1.clone())).expect("1. Imported");
 	block_on(pool.maintain(block_event(1)));
 
 	assert!(pool.ready_at(1).now_or_never().is_some());
@@ -632,7 +753,7 @@ fn ready_set_should_eventually_resolve_when_block_update_arrives() {
 
 	let xt1 = uxt(Alice, 209);
 
-	block_on(pool.submit_one(&BlockId::number(1), xt1.clone())).expect("1. Imported");
+	block_on(pool.submit_one(&BlockId::number(1), SOURCE, xt1.clone())).expect("1. Imported");
 
 	let noop_waker = futures::task::noop_waker();
 	let mut context = futures::task::Context::from_waker(&noop_waker);
@@ -653,4 +774,81 @@ fn ready_set_should_eventually_resolve_when_block_update_arrives() {
 			assert_eq!(data.len(), 1);
 		}
 	}
-}
\ No newline at end of file
+}
+
+#[test]
+fn should_report_submitted_transactions_to_prometheus() {
+	let registry = prometheus_endpoint::Registry::new();
+	let (pool, _background) = BasicPool::new(
+		Default::default(),
+		std::sync::Arc::new(TestApi::with_alice_nonce(209)),
+		Some(&registry),
+	);
+
+	let xt = uxt(Alice, 209);
+	block_on(pool.submit_one(&BlockId::number(0), SOURCE, xt.clone())).unwrap();
+	block_on(pool.submit_one(&BlockId::number(0), SOURCE, xt)).unwrap_err();
+	block_on(pool.submit_at(&BlockId::number(0), SOURCE, vec![uxt(Alice, 210)])).unwrap();
+
+	let mut reported = false;
+	pool.metrics.report(|metrics| {
+		assert_eq!(metrics.submitted_transactions.get(), 3);
+		assert_eq!(metrics.validations_invalid.get(), 1);
+		reported = true;
+	});
+	assert!(reported);
+}
+
+/// The call data of the light client's remote validation of a transaction, against a runtime with
+/// the given version of the `TaggedTransactionQueue` api.
+fn light_validation_call_data(api_version: u32, xt: Extrinsic) -> Vec<u8> {
+	use codec::Encode;
+	use parking_lot::Mutex;
+	use sc_client_api::light::RemoteCallRequest;
+	use sp_api::{RuntimeApiInfo, RuntimeVersion};
+	use sp_runtime::transaction_validity::TransactionValidity;
+	use sp_transaction_pool::runtime_api::TaggedTransactionQueue;
+	use txpool::ChainApi;
+
+	let call_data = std::sync::Arc::new(Mutex::new(None));
+	let recorded = call_data.clone();
+	let remote_call = move |request: RemoteCallRequest<Header>| match &request.method[..] {
+		"Core_version" => Ok::<_, sp_blockchain::Error>(RuntimeVersion {
+			spec_name: "test".into(),
+			impl_name: "test".into(),
+			authoring_version: 1,
+			spec_version: 1,
+			impl_version: 1,
+			apis: vec![(
+				<dyn TaggedTransactionQueue<Block, Error = ()> as RuntimeApiInfo>::ID,
+				api_version,
+			)].into(),
+			transaction_version: 1,
+		}.encode()),
+		"TaggedTransactionQueue_validate_transaction" => {
+			*recorded.lock() = Some(request.call_data);
+			Ok(TransactionValidity::Ok(ValidTransaction::default()).encode())
+		},
+		method => panic!("Unexpected remote call of {}", method),
+	};
+	let fetcher = substrate_test_runtime_client::LightFetcher::default()
+		.with_remote_call(Some(Box::new(remote_call)));
+	let api = LightChainApi::new(
+		std::sync::Arc::new(substrate_test_runtime_client::new()),
+		std::sync::Arc::new(fetcher),
+	);
+
+	let validity = block_on(api.validate_transaction(&BlockId::number(0), SOURCE, xt));
+	assert!(validity.unwrap().is_ok());
+	let call_data = call_data.lock().take();
+	call_data.expect("the transaction is validated by the remote node")
+}
+
+#[test]
+fn light_validation_only_passes_the_source_to_the_runtimes_expecting_it() {
+	use codec::Encode;
+
+	let xt = uxt(Alice, 209);
+	assert_eq!(light_validation_call_data(2, xt.clone()), (SOURCE, xt.clone()).encode());
+	assert_eq!(light_validation_call_data(1, xt.clone()), xt.encode());
+}
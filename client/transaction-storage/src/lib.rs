@@ -0,0 +1,477 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Client side of the transaction storage module.
+//!
+//! The blobs stored with `pallet-transaction-storage` are part of the body of the block storing
+//! them, but a blob renewed in a later block isn't part of the body of that block. The
+//! [`BlobStore`] keeps the data of the blobs which are still to be proved, indexed by content
+//! hash, along with the block at which each of them expires. It is filled out of the finalized
+//! blocks by [`index_finalized_blocks`], and used by [`build_proof`] to build the storage proof
+//! the block authors must include.
+//!
+//! The module must be part of the runtime under the name `TransactionStorage`.
+
+use std::{cmp, collections::HashMap, marker::PhantomData, sync::Arc};
+use codec::{Encode, Decode};
+use futures::prelude::*;
+use log::warn;
+use sc_client_api::{AuxStore, Backend, BlockBackend, BlockchainEvents, StorageProvider};
+use sp_blockchain::HeaderBackend;
+use sp_core::{hashing::{blake2_256, twox_64, twox_128}, hexdisplay::HexDisplay, storage::StorageKey};
+use sp_runtime::{generic::BlockId, traits::{Block as BlockT, NumberFor, One, Saturating, Zero}};
+use sp_transaction_storage_proof::{ChunkHash, TransactionInfo, TransactionStorageProof};
+
+const LOG_TARGET: &str = "transaction-storage";
+
+const BLOB_PREFIX: &[u8] = b"transaction_storage_blob";
+const EXPIRY_PREFIX: &[u8] = b"transaction_storage_expiry";
+const BLOCK_PREFIX: &[u8] = b"transaction_storage_block";
+const LAST_INDEXED_KEY: &[u8] = b"transaction_storage_last_indexed";
+
+/// A call of the transaction storage module found in a block body.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum StorageCall<N> {
+	/// A blob is stored.
+	Store(Vec<u8>),
+	/// The blob at the given index of the given block is renewed.
+	Renew(N, u32),
+}
+
+/// A blob kept by the store.
+#[derive(Encode, Decode)]
+struct BlobEntry<N> {
+	/// The data of the blob.
+	data: Vec<u8>,
+	/// The block at which the blob is proved for the last time.
+	expiry: N,
+}
+
+fn prefixed_key(prefix: &[u8], key: impl Encode) -> Vec<u8> {
+	let mut prefixed = prefix.to_vec();
+	key.encode_to(&mut prefixed);
+	prefixed
+}
+
+/// Keeps the data of the blobs which are still to be proved, in the auxiliary storage of the
+/// client.
+pub struct BlobStore<Block, S> {
+	aux: Arc<S>,
+	_phantom: PhantomData<Block>,
+}
+
+impl<Block, S> Clone for BlobStore<Block, S> {
+	fn clone(&self) -> Self {
+		BlobStore { aux: self.aux.clone(), _phantom: PhantomData }
+	}
+}
+
+impl<Block: BlockT, S: AuxStore> BlobStore<Block, S> {
+	/// Create a store keeping the blobs in the auxiliary storage of `aux`.
+	pub fn new(aux: Arc<S>) -> Self {
+		BlobStore { aux, _phantom: PhantomData }
+	}
+
+	fn get<T: Decode>(&self, key: &[u8]) -> sp_blockchain::Result<Option<T>> {
+		match self.aux.get_aux(key)? {
+			Some(value) => T::decode(&mut &value[..])
+				.map(Some)
+				.map_err(|e| sp_blockchain::Error::Backend(
+					format!("Invalid transaction storage entry: {:?}", e.what())
+				)),
+			None => Ok(None),
+		}
+	}
+
+	fn entry(&self, content_hash: &ChunkHash) -> sp_blockchain::Result<Option<BlobEntry<NumberFor<Block>>>> {
+		self.get(&prefixed_key(BLOB_PREFIX, content_hash))
+	}
+
+	fn expiring(&self, number: NumberFor<Block>) -> sp_blockchain::Result<Vec<ChunkHash>> {
+		Ok(self.get(&prefixed_key(EXPIRY_PREFIX, number))?.unwrap_or_default())
+	}
+
+	/// The data of the blob with the given content hash, if kept.
+	pub fn blob(&self, content_hash: &ChunkHash) -> sp_blockchain::Result<Option<Vec<u8>>> {
+		Ok(self.entry(content_hash)?.map(|entry| entry.data))
+	}
+
+	/// The content hashes of the blobs stored or renewed at the given block, in order, as long
+	/// as they can be renewed.
+	pub fn block_blobs(&self, number: NumberFor<Block>) -> sp_blockchain::Result<Vec<ChunkHash>> {
+		Ok(self.get(&prefixed_key(BLOCK_PREFIX, number))?.unwrap_or_default())
+	}
+
+	/// The last block indexed.
+	pub fn last_indexed(&self) -> sp_blockchain::Result<NumberFor<Block>> {
+		Ok(self.get(LAST_INDEXED_KEY)?.unwrap_or_else(Zero::zero))
+	}
+
+	/// Record the blobs stored or renewed at the block `number`, in order, which are then kept
+	/// until the block `number + period`, and remove the blobs whose last proof is at `number`.
+	///
+	/// The data of the blobs not kept yet is taken out of `stored`, which maps the content hash of
+	/// the blobs stored in the block to their data.
+	pub fn index_block(
+		&self,
+		number: NumberFor<Block>,
+		content_hashes: &[ChunkHash],
+		stored: &HashMap<ChunkHash, Vec<u8>>,
+		period: NumberFor<Block>,
+	) -> sp_blockchain::Result<()> {
+		let expiry = number.saturating_add(period);
+		let mut entries = HashMap::new();
+		for content_hash in content_hashes {
+			let entry = match entries.remove(content_hash) {
+				Some(entry) => Some(entry),
+				None => self.entry(content_hash)?,
+			};
+			let entry = match (entry, stored.get(content_hash)) {
+				(Some(entry), _) => BlobEntry { data: entry.data, expiry: cmp::max(entry.expiry, expiry) },
+				(None, Some(data)) => BlobEntry { data: data.clone(), expiry },
+				(None, None) => {
+					warn!(
+						target: LOG_TARGET,
+						"The data of blob {} stored at block {} is missing",
+						HexDisplay::from(content_hash),
+						number,
+					);
+					continue;
+				}
+			};
+			entries.insert(*content_hash, entry);
+		}
+
+		let mut expiring = self.expiring(expiry)?;
+		for content_hash in entries.keys() {
+			if !expiring.contains(content_hash) {
+				expiring.push(*content_hash);
+			}
+		}
+
+		let mut deleted = Vec::new();
+		for content_hash in self.expiring(number)? {
+			if entries.contains_key(&content_hash) {
+				continue;
+			}
+			if let Some(entry) = self.entry(&content_hash)? {
+				if entry.expiry <= number {
+					deleted.push(prefixed_key(BLOB_PREFIX, content_hash));
+				}
+			}
+		}
+		deleted.push(prefixed_key(EXPIRY_PREFIX, number));
+		if number >= period {
+			deleted.push(prefixed_key(BLOCK_PREFIX, number - period));
+		}
+
+		let mut inserted: Vec<_> = entries.into_iter()
+			.map(|(content_hash, entry)| (prefixed_key(BLOB_PREFIX, content_hash), entry.encode()))
+			.collect();
+		inserted.push((prefixed_key(EXPIRY_PREFIX, expiry), expiring.encode()));
+		inserted.push((prefixed_key(BLOCK_PREFIX, number), content_hashes.encode()));
+		inserted.push((LAST_INDEXED_KEY.to_vec(), number.encode()));
+
+		let inserted: Vec<_> = inserted.iter().map(|(key, value)| (&key[..], &value[..])).collect();
+		let deleted: Vec<_> = deleted.iter().map(|key| &key[..]).collect();
+		self.aux.insert_aux(&inserted, &deleted)
+	}
+}
+
+/// The storage key of the blobs stored at block `number`.
+fn transactions_key(number: impl Encode) -> StorageKey {
+	let mut key = twox_128(b"TransactionStorage").to_vec();
+	key.extend_from_slice(&twox_128(b"Transactions"));
+	let number = number.encode();
+	key.extend_from_slice(&twox_64(&number));
+	key.extend_from_slice(&number);
+	StorageKey(key)
+}
+
+/// The blobs stored at block `number` which are still kept, read from the state at block `at`.
+fn transactions<Block, B, C>(
+	client: &C,
+	at: &BlockId<Block>,
+	number: NumberFor<Block>,
+) -> sp_blockchain::Result<Option<Vec<TransactionInfo>>> where
+	Block: BlockT,
+	B: Backend<Block>,
+	C: StorageProvider<Block, B>,
+{
+	match client.storage(at, &transactions_key(number))? {
+		Some(data) => Decode::decode(&mut &data.0[..])
+			.map(Some)
+			.map_err(|e| sp_blockchain::Error::Backend(
+				format!("Invalid stored transactions: {:?}", e.what())
+			)),
+		None => Ok(None),
+	}
+}
+
+/// The calls of the transaction storage module of the block body, and the data of the blobs
+/// stored by these calls, by content hash.
+fn block_calls<Block, C, F>(
+	client: &C,
+	hash: Block::Hash,
+	extract: &F,
+) -> sp_blockchain::Result<(Vec<StorageCall<NumberFor<Block>>>, HashMap<ChunkHash, Vec<u8>>)> where
+	Block: BlockT,
+	C: BlockBackend<Block>,
+	F: Fn(&Block::Extrinsic) -> Option<StorageCall<NumberFor<Block>>>,
+{
+	let calls: Vec<_> = client.block_body(&BlockId::Hash(hash))?
+		.unwrap_or_default()
+		.iter()
+		.filter_map(extract)
+		.collect();
+	let stored = calls.iter()
+		.filter_map(|call| match call {
+			StorageCall::Store(data) => Some((blake2_256(data), data.clone())),
+			StorageCall::Renew(..) => None,
+		})
+		.collect();
+	Ok((calls, stored))
+}
+
+/// Index the blobs of the finalized block `number`.
+///
+/// The blobs are read from the state of the block. If it was pruned, all the calls of the block
+/// are assumed to have succeeded.
+fn index_block<Block, B, C, F>(
+	client: &C,
+	store: &BlobStore<Block, C>,
+	number: NumberFor<Block>,
+	period: NumberFor<Block>,
+	extract: &F,
+) -> sp_blockchain::Result<()> where
+	Block: BlockT,
+	B: Backend<Block>,
+	C: HeaderBackend<Block> + BlockBackend<Block> + StorageProvider<Block, B> + AuxStore,
+	F: Fn(&Block::Extrinsic) -> Option<StorageCall<NumberFor<Block>>>,
+{
+	let hash = client.hash(number)?
+		.ok_or_else(|| sp_blockchain::Error::UnknownBlock(format!("{}", number)))?;
+	let (calls, stored) = block_calls(client, hash, extract)?;
+	let content_hashes = match transactions(client, &BlockId::Hash(hash), number) {
+		Ok(transactions) => transactions.unwrap_or_default()
+			.into_iter()
+			.map(|info| info.content_hash)
+			.collect(),
+		Err(_) => {
+			let mut content_hashes = Vec::new();
+			for call in calls {
+				match call {
+					StorageCall::Store(data) => content_hashes.push(blake2_256(&data)),
+					StorageCall::Renew(block, index) => content_hashes.extend(
+						store.block_blobs(block)?.get(index as usize).cloned()
+					),
+				}
+			}
+			content_hashes
+		}
+	};
+	store.index_block(number, &content_hashes, &stored, period)
+}
+
+/// Index the blobs of the finalized blocks into `store`, as they get finalized.
+///
+/// The blocks finalized while the node wasn't running are indexed first. `extract` returns the
+/// call of the transaction storage module made by an extrinsic, if any, and `period` is the
+/// `StoragePeriod` of the module.
+pub async fn index_finalized_blocks<Block, B, C, F>(
+	client: Arc<C>,
+	store: BlobStore<Block, C>,
+	period: NumberFor<Block>,
+	extract: F,
+) where
+	Block: BlockT,
+	B: Backend<Block>,
+	C: BlockchainEvents<Block> + HeaderBackend<Block> + BlockBackend<Block>
+		+ StorageProvider<Block, B> + AuxStore,
+	F: Fn(&Block::Extrinsic) -> Option<StorageCall<NumberFor<Block>>>,
+{
+	let mut finality_notifications = client.finality_notification_stream();
+	loop {
+		let finalized = client.info().finalized_number;
+		let indexed = store.last_indexed().and_then(|mut number| {
+			while number < finalized {
+				number += One::one();
+				index_block(&*client, &store, number, period, &extract)?;
+			}
+			Ok(())
+		});
+		if let Err(e) = indexed {
+			warn!(target: LOG_TARGET, "Failed to index the stored transactions: {:?}", e);
+		}
+
+		if finality_notifications.next().await.is_none() {
+			break;
+		}
+	}
+}
+
+/// Build the storage proof to include in a block built on top of the best block, out of the blobs
+/// kept by `store`.
+///
+/// The blobs of a block which isn't indexed yet are taken out of its body. Returns `None` if
+/// there is nothing to prove.
+pub fn build_proof<Block, B, C, F>(
+	client: &C,
+	store: &BlobStore<Block, C>,
+	period: NumberFor<Block>,
+	extract: &F,
+) -> sp_blockchain::Result<Option<TransactionStorageProof>> where
+	Block: BlockT,
+	B: Backend<Block>,
+	C: HeaderBackend<Block> + BlockBackend<Block> + StorageProvider<Block, B> + AuxStore,
+	F: Fn(&Block::Extrinsic) -> Option<StorageCall<NumberFor<Block>>>,
+{
+	let info = client.info();
+	let number = info.best_number + One::one();
+	if number <= period {
+		return Ok(None);
+	}
+	let target = number - period;
+	let transactions = match transactions(client, &BlockId::Hash(info.best_hash), target)? {
+		Some(transactions) => transactions,
+		None => return Ok(None),
+	};
+
+	let mut body_blobs = None;
+	let mut blobs = Vec::with_capacity(transactions.len());
+	for transaction in transactions {
+		let data = match store.blob(&transaction.content_hash)? {
+			Some(data) => Some(data),
+			None => {
+				if body_blobs.is_none() {
+					let hash = client.hash(target)?
+						.ok_or_else(|| sp_blockchain::Error::UnknownBlock(format!("{}", target)))?;
+					body_blobs = Some(block_calls(client, hash, extract)?.1);
+				}
+				body_blobs.as_ref().and_then(|blobs| blobs.get(&transaction.content_hash).cloned())
+			}
+		};
+		let data = data.ok_or_else(|| sp_blockchain::Error::Msg(format!(
+			"The data of blob {} is missing",
+			HexDisplay::from(&transaction.content_hash),
+		)))?;
+		blobs.push(data);
+	}
+	Ok(sp_transaction_storage_proof::build_proof(info.best_hash.as_ref(), &blobs))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use parking_lot::Mutex;
+	use sp_runtime::testing::{Block as RawBlock, ExtrinsicWrapper};
+
+	type Block = RawBlock<ExtrinsicWrapper<u64>>;
+
+	#[derive(Default)]
+	struct TestAux(Mutex<HashMap<Vec<u8>, Vec<u8>>>);
+
+	impl AuxStore for TestAux {
+		fn insert_aux<
+			'a,
+			'b: 'a,
+			'c: 'a,
+			I: IntoIterator<Item=&'a(&'c [u8], &'c [u8])>,
+			D: IntoIterator<Item=&'a &'b [u8]>,
+		>(&self, insert: I, delete: D) -> sp_blockchain::Result<()> {
+			let mut aux = self.0.lock();
+			for (key, value) in insert {
+				aux.insert(key.to_vec(), value.to_vec());
+			}
+			for key in delete {
+				aux.remove(*key);
+			}
+			Ok(())
+		}
+
+		fn get_aux(&self, key: &[u8]) -> sp_blockchain::Result<Option<Vec<u8>>> {
+			Ok(self.0.lock().get(key).cloned())
+		}
+	}
+
+	fn store() -> BlobStore<Block, TestAux> {
+		BlobStore::new(Arc::new(TestAux::default()))
+	}
+
+	fn stored(blobs: &[&[u8]]) -> HashMap<ChunkHash, Vec<u8>> {
+		blobs.iter().map(|data| (blake2_256(data), data.to_vec())).collect()
+	}
+
+	#[test]
+	fn blobs_are_kept_until_proved() {
+		let store = store();
+		let (a, b): (&[u8], &[u8]) = (b"first blob", b"second blob");
+		store.index_block(1, &[blake2_256(a), blake2_256(b)], &stored(&[a, b]), 10).unwrap();
+		assert_eq!(store.last_indexed().unwrap(), 1);
+		assert_eq!(store.block_blobs(1).unwrap(), vec![blake2_256(a), blake2_256(b)]);
+		assert_eq!(store.blob(&blake2_256(a)).unwrap(), Some(a.to_vec()));
+
+		for number in 2..11 {
+			store.index_block(number, &[], &HashMap::new(), 10).unwrap();
+			assert!(store.blob(&blake2_256(a)).unwrap().is_some());
+		}
+		store.index_block(11, &[], &HashMap::new(), 10).unwrap();
+		assert!(store.blob(&blake2_256(a)).unwrap().is_none());
+		assert!(store.blob(&blake2_256(b)).unwrap().is_none());
+		assert!(store.block_blobs(1).unwrap().is_empty());
+	}
+
+	#[test]
+	fn renewed_blobs_are_kept_without_their_data() {
+		let store = store();
+		let data: &[u8] = b"renewed blob";
+		store.index_block(1, &[blake2_256(data)], &stored(&[data]), 10).unwrap();
+		// The renewing block doesn't contain the data of the blob.
+		store.index_block(5, &[blake2_256(data)], &HashMap::new(), 10).unwrap();
+
+		store.index_block(11, &[], &HashMap::new(), 10).unwrap();
+		assert_eq!(store.blob(&blake2_256(data)).unwrap(), Some(data.to_vec()));
+		store.index_block(15, &[], &HashMap::new(), 10).unwrap();
+		assert!(store.blob(&blake2_256(data)).unwrap().is_none());
+	}
+
+	#[test]
+	fn blobs_renewed_in_their_last_proved_block_are_kept() {
+		let store = store();
+		let data: &[u8] = b"blob";
+		store.index_block(1, &[blake2_256(data)], &stored(&[data]), 10).unwrap();
+		store.index_block(11, &[blake2_256(data)], &HashMap::new(), 10).unwrap();
+		assert_eq!(store.blob(&blake2_256(data)).unwrap(), Some(data.to_vec()));
+		store.index_block(21, &[], &HashMap::new(), 10).unwrap();
+		assert!(store.blob(&blake2_256(data)).unwrap().is_none());
+	}
+
+	#[test]
+	fn blobs_without_data_are_skipped() {
+		let store = store();
+		store.index_block(1, &[[1; 32]], &HashMap::new(), 10).unwrap();
+		assert!(store.blob(&[1; 32]).unwrap().is_none());
+		assert_eq!(store.last_indexed().unwrap(), 1);
+	}
+
+	#[test]
+	fn transactions_key_matches_the_module_storage() {
+		let key = transactions_key(5u32);
+		assert_eq!(&key.0[..16], &twox_128(b"TransactionStorage")[..]);
+		assert_eq!(&key.0[16..32], &twox_128(b"Transactions")[..]);
+		assert_eq!(&key.0[40..], &5u32.encode()[..]);
+	}
+}
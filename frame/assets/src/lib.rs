@@ -294,7 +294,7 @@ mod tests {
 		type Version = ();
 		type ModuleToIndex = ();
 		type AccountData = ();
-		type MigrateAccount = (); type OnNewAccount = ();
+		type MigrateAccount = (); type MigrateAccountData = (); type OnNewAccount = ();
 		type OnKilledAccount = ();
 	}
 	impl Trait for Test {
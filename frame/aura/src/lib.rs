@@ -77,6 +77,9 @@ decl_storage! {
 
 		/// The current authorities
 		pub Authorities get(fn authorities): Vec<T::AuthorityId>;
+
+		/// The slot of the current block, as given by its pre-runtime digest.
+		pub CurrentSlot get(fn current_slot): u64;
 	}
 	add_extra_genesis {
 		config(authorities): Vec<T::AuthorityId>;
@@ -85,7 +88,14 @@ decl_storage! {
 }
 
 decl_module! {
-	pub struct Module<T: Trait> for enum Call where origin: T::Origin { }
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		fn on_initialize() {
+			// store the slot of the block for the modules initialized after this one.
+			if let Some(slot) = Self::slot_from_digest() {
+				CurrentSlot::put(slot);
+			}
+		}
+	}
 }
 
 impl<T: Trait> Module<T> {
@@ -99,6 +109,19 @@ impl<T: Trait> Module<T> {
 		<frame_system::Module<T>>::deposit_log(log.into());
 	}
 
+	/// The slot given by the pre-runtime digest of the current block, if any.
+	fn slot_from_digest() -> Option<u64> {
+		let digest = <frame_system::Module<T>>::digest();
+		let pre_runtime_digests = digest.logs.iter().filter_map(|d| d.as_pre_runtime());
+		for (id, mut data) in pre_runtime_digests {
+			if id == AURA_ENGINE_ID {
+				return u64::decode(&mut data).ok()
+			}
+		}
+
+		None
+	}
+
 	fn initialize_authorities(authorities: &[T::AuthorityId]) {
 		if !authorities.is_empty() {
 			assert!(<Authorities<T>>::get().is_empty(), "Authorities are already initialized!");
@@ -151,7 +174,8 @@ impl<T: Trait> FindAuthor<u32> for Module<T> {
 		for (id, mut data) in digests.into_iter() {
 			if id == AURA_ENGINE_ID {
 				if let Ok(slot_num) = u64::decode(&mut data) {
-					let author_index = slot_num % Self::authorities().len() as u64;
+					// There is no author without authorities.
+					let author_index = slot_num.checked_rem(Self::authorities().len() as u64)?;
 					return Some(author_index as u32)
 				}
 			}
@@ -62,7 +62,7 @@ impl frame_system::Trait for Test {
 	type Version = ();
 	type ModuleToIndex = ();
 	type AccountData = ();
-	type MigrateAccount = (); type OnNewAccount = ();
+	type MigrateAccount = (); type MigrateAccountData = (); type OnNewAccount = ();
 	type OnKilledAccount = ();
 }
 
@@ -85,3 +85,4 @@ pub fn new_test_ext(authorities: Vec<u64>) -> sp_io::TestExternalities {
 }
 
 pub type Aura = Module<Test>;
+pub type System = frame_system::Module<Test>;
@@ -18,7 +18,11 @@
 
 #![cfg(test)]
 
-use crate::mock::{Aura, new_test_ext};
+use crate::mock::{Aura, System, new_test_ext};
+use codec::Encode;
+use frame_support::traits::{FindAuthor, OnInitialize};
+use sp_consensus_aura::AURA_ENGINE_ID;
+use sp_runtime::{Digest, DigestItem};
 
 #[test]
 fn initial_values() {
@@ -27,3 +31,30 @@ fn initial_values() {
 		assert_eq!(Aura::authorities().len(), 4);
 	});
 }
+
+#[test]
+fn current_slot_and_author_are_read_from_the_pre_runtime_digest() {
+	new_test_ext(vec![0, 1, 2, 3]).execute_with(|| {
+		let digest = Digest { logs: vec![DigestItem::PreRuntime(AURA_ENGINE_ID, 6u64.encode())] };
+		System::initialize(
+			&1,
+			&Default::default(),
+			&Default::default(),
+			&digest,
+			frame_system::InitKind::Full,
+		);
+		Aura::on_initialize(1);
+
+		assert_eq!(Aura::current_slot(), 6);
+		let pre_runtime_digests = digest.logs.iter().filter_map(|d| d.as_pre_runtime());
+		assert_eq!(Aura::find_author(pre_runtime_digests), Some(2));
+	});
+}
+
+#[test]
+fn no_author_is_found_without_authorities() {
+	new_test_ext(vec![]).execute_with(|| {
+		let digest = DigestItem::<sp_core::H256>::PreRuntime(AURA_ENGINE_ID, 6u64.encode());
+		assert_eq!(Aura::find_author(digest.as_pre_runtime()), None);
+	});
+}
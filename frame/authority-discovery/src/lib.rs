@@ -17,7 +17,8 @@
 //! # Authority discovery module.
 //!
 //! This module is used by the `client/authority-discovery` to retrieve the
-//! current set of authorities.
+//! current and the next set of authorities, so the authorities of the next session are
+//! already discoverable and connected to before the session starts.
 
 // Ensure we're `no_std` when compiling for Wasm.
 #![cfg_attr(not(feature = "std"), no_std)]
@@ -33,6 +34,8 @@ decl_storage! {
 	trait Store for Module<T: Trait> as AuthorityDiscovery {
 		/// Keys of the current authority set.
 		Keys get(fn keys): Vec<AuthorityId>;
+		/// Keys of the next authority set.
+		NextKeys get(fn next_keys): Vec<AuthorityId>;
 	}
 	add_extra_genesis {
 		config(keys): Vec<AuthorityId>;
@@ -51,10 +54,16 @@ impl<T: Trait> Module<T> {
 		Keys::get()
 	}
 
+	/// Retrieve authority identifiers of the next authority set.
+	pub fn next_authorities() -> Vec<AuthorityId> {
+		NextKeys::get()
+	}
+
 	fn initialize_keys(keys: &[AuthorityId]) {
 		if !keys.is_empty() {
 			assert!(Keys::get().is_empty(), "Keys are already initialized!");
 			Keys::put(keys);
+			NextKeys::put(keys);
 		}
 	}
 }
@@ -74,7 +83,7 @@ impl<T: Trait> pallet_session::OneSessionHandler<T::AccountId> for Module<T> {
 		Self::initialize_keys(&keys);
 	}
 
-	fn on_new_session<'a, I: 'a>(changed: bool, validators: I, _queued_validators: I)
+	fn on_new_session<'a, I: 'a>(changed: bool, validators: I, queued_validators: I)
 	where
 		I: Iterator<Item = (&'a T::AccountId, Self::Key)>,
 	{
@@ -82,6 +91,8 @@ impl<T: Trait> pallet_session::OneSessionHandler<T::AccountId> for Module<T> {
 		if changed {
 			Keys::put(validators.map(|x| x.1).collect::<Vec<_>>());
 		}
+		// The queued validators can change even if the current ones don't.
+		NextKeys::put(queued_validators.map(|x| x.1).collect::<Vec<_>>());
 	}
 
 	fn on_disabled(_i: usize) {
@@ -126,6 +137,7 @@ mod tests {
 	impl pallet_session::historical::Trait for Test {
 		type FullIdentification = ();
 		type FullIdentificationOf = ();
+		type HistoricalFullIdentificationOf = ();
 	}
 
 	pub type BlockNumber = u64;
@@ -158,7 +170,7 @@ mod tests {
 		type Version = ();
 		type ModuleToIndex = ();
 		type AccountData = ();
-		type MigrateAccount = (); type OnNewAccount = ();
+		type MigrateAccount = (); type MigrateAccountData = (); type OnNewAccount = ();
 		type OnKilledAccount = ();
 	}
 
@@ -243,4 +255,38 @@ mod tests {
 			assert_eq!(second_authorities, AuthorityDiscovery::authorities());
 		});
 	}
+
+	#[test]
+	fn next_authorities_returns_the_queued_authority_set() {
+		let account_id = AuthorityPair::from_seed_slice(vec![10; 32].as_ref()).unwrap().public();
+
+		let authorities: Vec<AuthorityId> = vec![0, 1, 2].into_iter()
+			.map(|i| AuthorityPair::from_seed_slice(vec![i; 32].as_ref()).unwrap().public())
+			.map(AuthorityId::from)
+			.collect();
+
+		let current = vec![(&account_id, authorities[0].clone()), (&account_id, authorities[1].clone())];
+		let queued = vec![(&account_id, authorities[1].clone()), (&account_id, authorities[2].clone())];
+
+		let t = frame_system::GenesisConfig::default()
+			.build_storage::<Test>()
+			.unwrap();
+
+		TestExternalities::new(t).execute_with(|| {
+			use pallet_session::OneSessionHandler;
+
+			AuthorityDiscovery::on_genesis_session(current.clone().into_iter());
+			assert_eq!(AuthorityDiscovery::authorities(), authorities[..2].to_vec());
+			assert_eq!(AuthorityDiscovery::next_authorities(), authorities[..2].to_vec());
+
+			// The queued authorities are the next ones even if the current ones don't change.
+			AuthorityDiscovery::on_new_session(false, current.clone().into_iter(), queued.clone().into_iter());
+			assert_eq!(AuthorityDiscovery::authorities(), authorities[..2].to_vec());
+			assert_eq!(AuthorityDiscovery::next_authorities(), authorities[1..].to_vec());
+
+			AuthorityDiscovery::on_new_session(true, queued.clone().into_iter(), queued.into_iter());
+			assert_eq!(AuthorityDiscovery::authorities(), authorities[1..].to_vec());
+			assert_eq!(AuthorityDiscovery::next_authorities(), authorities[1..].to_vec());
+		});
+	}
 }
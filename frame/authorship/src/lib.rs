@@ -195,7 +195,10 @@ decl_module! {
 
 			<Self as Store>::DidSetUncles::put(false);
 
-			T::EventHandler::note_author(Self::author());
+			// the author is only noted when it is found, not to credit the default account.
+			if let Some(author) = Self::find_author() {
+				T::EventHandler::note_author(author);
+			}
 		}
 
 		fn on_finalize() {
@@ -225,20 +228,27 @@ impl<T: Trait> Module<T> {
 	///
 	/// This is safe to invoke in `on_initialize` implementations, as well
 	/// as afterwards.
+	///
+	/// The default account is returned if the author can't be found.
 	pub fn author() -> T::AccountId {
+		Self::find_author().unwrap_or_default()
+	}
+
+	/// Find the author of the block in its pre-runtime digests, if any.
+	///
+	/// This is safe to invoke in `on_initialize` implementations, as well
+	/// as afterwards.
+	pub fn find_author() -> Option<T::AccountId> {
 		// Check the memoized storage value.
 		if let Some(author) = <Self as Store>::Author::get() {
-			return author;
+			return Some(author);
 		}
 
 		let digest = <frame_system::Module<T>>::digest();
 		let pre_runtime_digests = digest.logs.iter().filter_map(|d| d.as_pre_runtime());
-		if let Some(author) = T::FindAuthor::find_author(pre_runtime_digests) {
-			<Self as Store>::Author::put(&author);
-			author
-		} else {
-			Default::default()
-		}
+		let author = T::FindAuthor::find_author(pre_runtime_digests)?;
+		<Self as Store>::Author::put(&author);
+		Some(author)
 	}
 
 	fn verify_and_import_uncles(new_uncles: Vec<T::Header>) -> dispatch::DispatchResult {
@@ -432,7 +442,7 @@ mod tests {
 		type Version = ();
 		type ModuleToIndex = ();
 		type AccountData = ();
-		type MigrateAccount = (); type OnNewAccount = ();
+		type MigrateAccount = (); type MigrateAccountData = (); type OnNewAccount = ();
 		type OnKilledAccount = ();
 	}
 
@@ -686,6 +696,22 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn no_author_is_found_without_pre_runtime_digest() {
+		new_test_ext().execute_with(|| {
+			System::initialize(
+				&1,
+				&Default::default(),
+				&Default::default(),
+				&Default::default(),
+				Default::default(),
+			);
+
+			assert_eq!(Authorship::find_author(), None);
+			assert_eq!(Authorship::author(), Default::default());
+		});
+	}
+
 	#[test]
 	fn one_uncle_per_author_per_number() {
 		type Filter = OnePerAuthorPerHeight<VerifyBlock, u64>;
@@ -65,7 +65,7 @@ impl frame_system::Trait for Test {
 	type MaximumBlockLength = MaximumBlockLength;
 	type ModuleToIndex = ();
 	type AccountData = ();
-	type MigrateAccount = (); type OnNewAccount = ();
+	type MigrateAccount = (); type MigrateAccountData = (); type OnNewAccount = ();
 	type OnKilledAccount = ();
 }
 
@@ -96,12 +96,18 @@ impl Trait for Test {
 	type EpochDuration = EpochDuration;
 	type ExpectedBlockTime = ExpectedBlockTime;
 	type EpochChangeTrigger = crate::ExternalTrigger;
+
+	type KeyOwnerProof = sp_core::Void;
+	type KeyOwnerIdentification = sp_core::Void;
+	type KeyOwnerProofSystem = ();
+	type HandleEquivocation = ();
 }
 
 pub fn new_test_ext(authorities: Vec<DummyValidatorId>) -> sp_io::TestExternalities {
 	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
 	GenesisConfig {
 		authorities: authorities.into_iter().map(|a| (UintAuthorityId(a).to_public_key(), 1)).collect(),
+		allowed_slots: Default::default(),
 	}.assimilate_storage::<Test>(&mut t).unwrap();
 	t.into()
 }
@@ -18,7 +18,9 @@
 
 use super::*;
 use mock::{new_test_ext, Babe, System};
-use sp_runtime::{traits::OnFinalize, testing::{Digest, DigestItem}};
+use sp_runtime::{traits::{Header as _, OnFinalize}, testing::{Digest, DigestItem, Header}};
+use sp_consensus_babe::{AuthorityPair, EquivocationProof, check_equivocation_proof};
+use sp_core::{H256, Pair};
 use pallet_session::ShouldEndSession;
 
 const EMPTY_RANDOMNESS: [u8; 32] = [
@@ -120,6 +122,67 @@ fn first_block_epoch_zero_start() {
 	})
 }
 
+#[test]
+fn current_and_next_epoch_data() {
+	new_test_ext(vec![0, 1, 2, 3]).execute_with(|| {
+		let genesis_slot = 100;
+		let pre_digest = make_pre_digest(0, genesis_slot, [1; 32], [0xff; 64]);
+
+		System::initialize(
+			&1,
+			&Default::default(),
+			&Default::default(),
+			&pre_digest,
+			Default::default(),
+		);
+		// initializes the genesis slot.
+		assert!(!Babe::should_end_session(1));
+
+		let current_epoch = Babe::current_epoch();
+		assert_eq!(current_epoch.epoch_index, 0);
+		assert_eq!(current_epoch.start_slot, genesis_slot);
+		assert_eq!(current_epoch.duration, 3);
+		assert_eq!(current_epoch.authorities, Babe::authorities());
+
+		// the genesis authorities also run the next epoch.
+		let next_epoch = Babe::next_epoch();
+		assert_eq!(next_epoch.epoch_index, 1);
+		assert_eq!(next_epoch.start_slot, genesis_slot + 3);
+		assert_eq!(next_epoch.authorities, Babe::authorities());
+		assert_eq!(next_epoch.randomness, Babe::randomness());
+	})
+}
+
+#[test]
+fn secondary_vrf_output_is_included_in_randomness() {
+	new_test_ext(vec![0, 1, 2, 3]).execute_with(|| {
+		let vrf_output = [2; 32];
+		let digest_data = sp_consensus_babe::digests::RawPreDigest::SecondaryVRF {
+			authority_index: 1,
+			slot_number: 100,
+			vrf_output,
+			vrf_proof: [0xff; 64],
+		};
+		let log = DigestItem::PreRuntime(BABE_ENGINE_ID, digest_data.encode());
+		let pre_digest = Digest { logs: vec![log] };
+
+		System::initialize(
+			&1,
+			&Default::default(),
+			&Default::default(),
+			&pre_digest,
+			Default::default(),
+		);
+		Babe::on_finalize(1);
+
+		assert_eq!(UnderConstruction::get(0), vec![vrf_output]);
+		assert_eq!(
+			Babe::find_author(pre_digest.logs.iter().filter_map(|log| log.as_pre_runtime())),
+			Some(1),
+		);
+	})
+}
+
 #[test]
 fn authority_index() {
 	new_test_ext(vec![0, 1, 2, 3]).execute_with(|| {
@@ -128,3 +191,72 @@ fn authority_index() {
 			"Trivially invalid authorities are ignored")
 	})
 }
+
+#[test]
+fn author_is_found_only_among_the_authorities() {
+	new_test_ext(vec![0, 1, 2, 3]).execute_with(|| {
+		let author = |authority_index| {
+			let digest = make_pre_digest(authority_index, 6, [1; 32], [0xff; 64]);
+			Babe::find_author(digest.logs.iter().filter_map(|d| d.as_pre_runtime()))
+		};
+
+		assert_eq!(author(2), Some(2));
+		assert_eq!(author(4), None);
+	})
+}
+
+fn make_sealed_header(
+	pair: &AuthorityPair,
+	authority_index: sp_consensus_babe::AuthorityIndex,
+	slot_number: sp_consensus_babe::SlotNumber,
+	extrinsics_root: H256,
+) -> Header {
+	let digest_data = sp_consensus_babe::digests::RawPreDigest::Secondary {
+		authority_index,
+		slot_number,
+	};
+	let log = DigestItem::PreRuntime(BABE_ENGINE_ID, digest_data.encode());
+	let mut header = Header::new(
+		1,
+		extrinsics_root,
+		Default::default(),
+		Default::default(),
+		Digest { logs: vec![log] },
+	);
+
+	let signature = pair.sign(header.hash().as_ref());
+	header.digest_mut().push(DigestItem::Seal(BABE_ENGINE_ID, signature.encode()));
+	header
+}
+
+#[test]
+fn equivocation_proofs_are_checked() {
+	let pair = AuthorityPair::from_seed(&[1; 32]);
+	let other_pair = AuthorityPair::from_seed(&[2; 32]);
+	let first_header = make_sealed_header(&pair, 0, 10, H256::repeat_byte(1));
+	let second_header = make_sealed_header(&pair, 0, 10, H256::repeat_byte(2));
+
+	let proof = |first_header: &Header, second_header: &Header| EquivocationProof {
+		offender: pair.public(),
+		slot_number: 10,
+		first_header: first_header.clone(),
+		second_header: second_header.clone(),
+	};
+
+	assert!(check_equivocation_proof(proof(&first_header, &second_header)));
+
+	// the same header twice isn't an equivocation
+	assert!(!check_equivocation_proof(proof(&first_header, &first_header)));
+
+	// both headers must be for the slot of the proof
+	let other_slot_header = make_sealed_header(&pair, 0, 11, H256::repeat_byte(2));
+	assert!(!check_equivocation_proof(proof(&first_header, &other_slot_header)));
+
+	// both headers must claim the same authority index
+	let other_index_header = make_sealed_header(&pair, 1, 10, H256::repeat_byte(2));
+	assert!(!check_equivocation_proof(proof(&first_header, &other_index_header)));
+
+	// both headers must be signed by the offender
+	let other_signer_header = make_sealed_header(&other_pair, 0, 10, H256::repeat_byte(2));
+	assert!(!check_equivocation_proof(proof(&first_header, &other_signer_header)));
+}
@@ -66,7 +66,7 @@ impl frame_system::Trait for Test {
 	type Version = ();
 	type ModuleToIndex = ();
 	type AccountData = super::AccountData<u64>;
-	type MigrateAccount = (); type OnNewAccount = ();
+	type MigrateAccount = (); type MigrateAccountData = (); type OnNewAccount = ();
 	type OnKilledAccount = ();
 }
 parameter_types! {
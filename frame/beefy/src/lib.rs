@@ -0,0 +1,334 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # BEEFY module.
+//!
+//! This module keeps track of the BEEFY keys registered by the validators through the session
+//! module, and announces the changes of the set of BEEFY authorities to the client in the
+//! header digests, along with the identifier of the new set.
+//!
+//! It also keeps the peaks of a Merkle Mountain Range (MMR) of the hashes of the blocks of the
+//! chain, and deposits its root in the digest of every block: this is the payload the BEEFY
+//! voters sign commitments to. The leaves of the range are the keccak-256 hashes of the blocks,
+//! the nodes hash the concatenation of their children with keccak-256, and the root "bags" the
+//! peaks from right to left the same way. As a block can't include its own hash, the range
+//! deposited in the digest of a block ends with its parent.
+
+// Ensure we're `no_std` when compiling for Wasm.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::prelude::*;
+use codec::Encode;
+use frame_support::{decl_module, decl_storage};
+use sp_io::hashing::keccak_256;
+use sp_runtime::generic::DigestItem;
+use sp_beefy::{
+	AuthorityId, AuthorityIndex, ConsensusLog, MmrRootHash, ValidatorSet, BEEFY_ENGINE_ID,
+};
+
+/// The module's config trait.
+pub trait Trait: frame_system::Trait {}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Beefy {
+		/// The current set of BEEFY authorities.
+		Authorities get(fn authorities): Vec<AuthorityId>;
+
+		/// The identifier of the current set of BEEFY authorities.
+		ValidatorSetId get(fn validator_set_id): sp_beefy::ValidatorSetId;
+
+		/// The BEEFY authorities of the next session.
+		NextAuthorities get(fn next_authorities): Vec<AuthorityId>;
+
+		/// The peaks of the Merkle Mountain Range of the blocks, from left to right, along with
+		/// their heights.
+		MmrPeaks get(fn mmr_peaks): Vec<(u32, MmrRootHash)>;
+	}
+	add_extra_genesis {
+		config(authorities): Vec<AuthorityId>;
+		build(|config| Module::<T>::initialize_authorities(&config.authorities))
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		fn on_initialize() {
+			let parent_hash = <frame_system::Module<T>>::parent_hash();
+			let root = Self::append_mmr_leaf(MmrRootHash::from(keccak_256(parent_hash.as_ref())));
+			Self::deposit_log(ConsensusLog::MmrRoot(root));
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The current set of BEEFY authorities, along with its identifier.
+	pub fn validator_set() -> ValidatorSet<AuthorityId> {
+		ValidatorSet {
+			validators: Self::authorities(),
+			id: Self::validator_set_id(),
+		}
+	}
+
+	/// Append a leaf to the Merkle Mountain Range, returning its new root.
+	fn append_mmr_leaf(leaf: MmrRootHash) -> MmrRootHash {
+		let mut peaks = MmrPeaks::get();
+
+		// merge the peaks of the same height, which may only be the rightmost ones.
+		let mut peak = (0, leaf);
+		while let Some((height, left)) = peaks.last().cloned() {
+			if height != peak.0 {
+				break;
+			}
+			peaks.pop();
+			peak = (height + 1, merge_mmr_nodes(&left, &peak.1));
+		}
+		peaks.push(peak);
+
+		let mut bagged = peaks.iter().rev().map(|(_, hash)| *hash);
+		let rightmost = bagged.next().expect("a peak was just pushed; qed");
+		let root = bagged.fold(rightmost, |right, left| merge_mmr_nodes(&left, &right));
+
+		MmrPeaks::put(peaks);
+		root
+	}
+
+	fn change_authorities(new: Vec<AuthorityId>, queued: Vec<AuthorityId>) {
+		// the voters don't need to know about the sets which are the same as the previous one.
+		if new != Authorities::get() {
+			Authorities::put(&new);
+
+			let next_id = ValidatorSetId::get() + 1;
+			ValidatorSetId::put(next_id);
+
+			Self::deposit_log(ConsensusLog::AuthoritiesChange(ValidatorSet {
+				validators: new,
+				id: next_id,
+			}));
+		}
+
+		NextAuthorities::put(queued);
+	}
+
+	fn initialize_authorities(authorities: &[AuthorityId]) {
+		if !authorities.is_empty() {
+			assert!(Authorities::get().is_empty(), "Authorities are already initialized!");
+			Authorities::put(authorities);
+			ValidatorSetId::put(0);
+			NextAuthorities::put(authorities);
+		}
+	}
+
+	fn deposit_log(log: ConsensusLog<AuthorityId>) {
+		let log: DigestItem<T::Hash> = DigestItem::Consensus(BEEFY_ENGINE_ID, log.encode());
+		<frame_system::Module<T>>::deposit_log(log.into());
+	}
+}
+
+/// The parent of two nodes of the Merkle Mountain Range.
+fn merge_mmr_nodes(left: &MmrRootHash, right: &MmrRootHash) -> MmrRootHash {
+	let mut concatenated = [0u8; 64];
+	concatenated[..32].copy_from_slice(left.as_ref());
+	concatenated[32..].copy_from_slice(right.as_ref());
+	MmrRootHash::from(keccak_256(&concatenated))
+}
+
+impl<T: Trait> sp_runtime::BoundToRuntimeAppPublic for Module<T> {
+	type Public = AuthorityId;
+}
+
+impl<T: Trait> pallet_session::OneSessionHandler<T::AccountId> for Module<T> {
+	type Key = AuthorityId;
+
+	fn on_genesis_session<'a, I: 'a>(validators: I)
+	where
+		I: Iterator<Item = (&'a T::AccountId, Self::Key)>,
+	{
+		let authorities = validators.map(|(_, k)| k).collect::<Vec<_>>();
+		Self::initialize_authorities(&authorities);
+	}
+
+	fn on_new_session<'a, I: 'a>(changed: bool, validators: I, queued_validators: I)
+	where
+		I: Iterator<Item = (&'a T::AccountId, Self::Key)>,
+	{
+		if changed {
+			let next_authorities = validators.map(|(_, k)| k).collect::<Vec<_>>();
+			let next_queued_authorities = queued_validators.map(|(_, k)| k).collect::<Vec<_>>();
+
+			Self::change_authorities(next_authorities, next_queued_authorities);
+		}
+	}
+
+	fn on_disabled(i: usize) {
+		Self::deposit_log(ConsensusLog::<AuthorityId>::OnDisabled(i as AuthorityIndex));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_beefy::AuthorityPair;
+	use sp_application_crypto::Pair;
+	use sp_core::H256;
+	use sp_io::TestExternalities;
+	use sp_runtime::{
+		testing::Header, traits::{BlakeTwo256, Header as _, IdentityLookup}, Perbill,
+	};
+	use codec::Decode;
+	use frame_support::{impl_outer_origin, parameter_types, traits::OnInitialize, weights::Weight};
+	use pallet_session::OneSessionHandler;
+
+	type Beefy = Module<Test>;
+	type System = frame_system::Module<Test>;
+
+	impl_outer_origin! {
+		pub enum Origin for Test  where system = frame_system {}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	impl Trait for Test {}
+
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::one();
+	}
+
+	impl frame_system::Trait for Test {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Call = ();
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type MaximumBlockLength = MaximumBlockLength;
+		type Version = ();
+		type ModuleToIndex = ();
+		type AccountData = ();
+		type MigrateAccount = (); type MigrateAccountData = (); type OnNewAccount = ();
+		type OnKilledAccount = ();
+	}
+
+	fn authority(seed: u8) -> AuthorityId {
+		AuthorityPair::from_string(&format!("//{}", seed), None).unwrap().public()
+	}
+
+	fn new_test_ext(authorities: Vec<AuthorityId>) -> TestExternalities {
+		let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		GenesisConfig {
+			authorities,
+		}.assimilate_storage::<Test>(&mut t).unwrap();
+		t.into()
+	}
+
+	#[test]
+	fn genesis_authorities_are_the_first_set() {
+		new_test_ext(vec![authority(0), authority(1)]).execute_with(|| {
+			assert_eq!(
+				Beefy::validator_set(),
+				ValidatorSet { validators: vec![authority(0), authority(1)], id: 0 },
+			);
+			assert_eq!(Beefy::next_authorities(), vec![authority(0), authority(1)]);
+		});
+	}
+
+	#[test]
+	fn authorities_change_is_announced_with_a_new_set_id() {
+		new_test_ext(vec![authority(0), authority(1)]).execute_with(|| {
+			System::initialize(
+				&1,
+				&Default::default(),
+				&Default::default(),
+				&Default::default(),
+				Default::default(),
+			);
+
+			let account = 0;
+			let validators = vec![(&account, authority(1)), (&account, authority(2))];
+			let queued = vec![(&account, authority(2))];
+
+			// the set only changes when the session keys did.
+			Beefy::on_new_session(false, validators.clone().into_iter(), vec![].into_iter());
+			assert_eq!(Beefy::validator_set_id(), 0);
+
+			Beefy::on_new_session(true, validators.into_iter(), queued.into_iter());
+			let set = ValidatorSet { validators: vec![authority(1), authority(2)], id: 1 };
+			assert_eq!(Beefy::validator_set(), set);
+			assert_eq!(Beefy::next_authorities(), vec![authority(2)]);
+
+			let log: DigestItem<H256> = DigestItem::Consensus(
+				BEEFY_ENGINE_ID,
+				ConsensusLog::AuthoritiesChange(set).encode(),
+			);
+			assert_eq!(System::digest().logs, vec![log]);
+		});
+	}
+
+	fn mmr_root_of(header: &Header) -> Option<MmrRootHash> {
+		header.digest.logs.iter().find_map(|log| match log {
+			DigestItem::Consensus(BEEFY_ENGINE_ID, log) => {
+				match ConsensusLog::<AuthorityId>::decode(&mut &log[..]) {
+					Ok(ConsensusLog::MmrRoot(root)) => Some(root),
+					_ => None,
+				}
+			},
+			_ => None,
+		})
+	}
+
+	#[test]
+	fn mmr_root_of_the_parent_blocks_is_deposited_in_every_block() {
+		new_test_ext(vec![authority(0)]).execute_with(|| {
+			let mut parent_hash = H256::repeat_byte(1);
+			let mut leaves = Vec::new();
+			let mut roots = Vec::new();
+
+			for number in 1..=3 {
+				System::initialize(
+					&number,
+					&parent_hash,
+					&Default::default(),
+					&Default::default(),
+					Default::default(),
+				);
+				Beefy::on_initialize(number);
+				leaves.push(MmrRootHash::from(keccak_256(parent_hash.as_ref())));
+
+				let header = System::finalize();
+				roots.push(mmr_root_of(&header).expect("the root is deposited"));
+				parent_hash = header.hash();
+			}
+
+			// a single leaf is its own root.
+			assert_eq!(roots[0], leaves[0]);
+			// the two first leaves are merged into a single peak.
+			let peak = merge_mmr_nodes(&leaves[0], &leaves[1]);
+			assert_eq!(roots[1], peak);
+			// and the third leaf is bagged with it.
+			assert_eq!(roots[2], merge_mmr_nodes(&peak, &leaves[2]));
+			assert_eq!(Beefy::mmr_peaks(), vec![(1, peak), (0, leaves[2])]);
+		});
+	}
+}
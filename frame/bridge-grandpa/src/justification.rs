@@ -0,0 +1,160 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Verification of the GRANDPA justifications of the bridged chain.
+//!
+//! The justifications are encoded as the GRANDPA client encodes them: a commit for the justified
+//! header along with the headers routing the target of each precommit to the target of the commit.
+
+use sp_std::prelude::*;
+use sp_std::collections::{btree_map::BTreeMap, btree_set::BTreeSet};
+use codec::{Encode, Decode};
+use sp_finality_grandpa::{AuthorityId, AuthoritySignature, AuthorityList, RoundNumber, SetId};
+use sp_runtime::{RuntimeDebug, traits::Header as HeaderT};
+
+/// A GRANDPA commit for a header of the bridged chain.
+pub type Commit<Header> = grandpa::Commit<
+	<Header as HeaderT>::Hash,
+	<Header as HeaderT>::Number,
+	AuthoritySignature,
+	AuthorityId,
+>;
+
+/// A GRANDPA justification of the finality of a header of the bridged chain.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug)]
+pub struct GrandpaJustification<Header: HeaderT> {
+	/// The round in which the header was finalized.
+	pub round: RoundNumber,
+	/// The commit finalizing the header.
+	pub commit: Commit<Header>,
+	/// The headers routing the targets of the precommits to the target of the commit.
+	pub votes_ancestries: Vec<Header>,
+}
+
+/// The reasons a justification can be rejected for.
+#[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum Error {
+	/// The justification couldn't be decoded.
+	Decode,
+	/// The justification is for another header.
+	InvalidTarget,
+	/// There are more precommits than authorities in the set.
+	TooManyPrecommits,
+	/// A precommit is signed by an authority outside of the set.
+	UnknownAuthority,
+	/// The signature of a precommit is invalid.
+	InvalidSignature,
+	/// The target of a precommit doesn't descend from the target of the commit.
+	InvalidAncestry,
+	/// Some headers of the ancestries are not needed by any precommit.
+	UnusedAncestry,
+	/// The authorities signing the precommits don't have a supermajority of the weight.
+	NotEnoughWeight,
+}
+
+/// Decode a justification and check that it finalizes the given header for the given set of
+/// authorities.
+pub fn verify_justification<Header: HeaderT>(
+	finalized_target: (Header::Hash, Header::Number),
+	set_id: SetId,
+	authorities: &AuthorityList,
+	encoded: &[u8],
+) -> Result<GrandpaJustification<Header>, Error> {
+	let justification = GrandpaJustification::<Header>::decode(&mut &*encoded)
+		.map_err(|_| Error::Decode)?;
+
+	justification.verify_target(finalized_target, set_id, authorities).map(|_| justification)
+}
+
+impl<Header: HeaderT> GrandpaJustification<Header> {
+	/// Check that the justification finalizes the given header for the given set of authorities.
+	pub fn verify_target(
+		&self,
+		finalized_target: (Header::Hash, Header::Number),
+		set_id: SetId,
+		authorities: &AuthorityList,
+	) -> Result<(), Error> {
+		if (self.commit.target_hash, self.commit.target_number) != finalized_target {
+			return Err(Error::InvalidTarget);
+		}
+
+		self.verify(set_id, authorities)
+	}
+
+	/// Check the signatures and the weight of the precommits and the ancestry of their targets.
+	///
+	/// There can't be more precommits than authorities, and only the first precommit of each
+	/// authority is checked and counted, the others being ignored.
+	pub fn verify(&self, set_id: SetId, authorities: &AuthorityList) -> Result<(), Error> {
+		if self.commit.precommits.len() > authorities.len() {
+			return Err(Error::TooManyPrecommits);
+		}
+
+		let weights = authorities.iter().cloned().collect::<BTreeMap<_, _>>();
+		let total_weight = weights.values().fold(0u64, |total, weight| total.saturating_add(*weight));
+		let threshold = total_weight - total_weight.saturating_sub(1) / 3;
+
+		let ancestry = self.votes_ancestries.iter()
+			.map(|header| (header.hash(), header))
+			.collect::<BTreeMap<_, _>>();
+		if ancestry.len() != self.votes_ancestries.len() {
+			return Err(Error::UnusedAncestry);
+		}
+
+		let mut signers = BTreeSet::new();
+		let mut visited = BTreeSet::new();
+		let mut signed_weight = 0u64;
+
+		for signed in &self.commit.precommits {
+			let weight = weights.get(&signed.id).ok_or(Error::UnknownAuthority)?;
+
+			// an equivocating authority is only checked and counted once.
+			if !signers.insert(signed.id.clone()) {
+				continue;
+			}
+
+			sp_finality_grandpa::check_message_signature(
+				&grandpa::Message::Precommit(signed.precommit.clone()),
+				&signed.id,
+				&signed.signature,
+				self.round,
+				set_id,
+			).map_err(|_| Error::InvalidSignature)?;
+			signed_weight = signed_weight.saturating_add(*weight);
+
+			let mut current = signed.precommit.target_hash;
+			while current != self.commit.target_hash {
+				let header = ancestry.get(&current).ok_or(Error::InvalidAncestry)?;
+				if *header.number() <= self.commit.target_number {
+					return Err(Error::InvalidAncestry);
+				}
+
+				visited.insert(current);
+				current = *header.parent_hash();
+			}
+		}
+
+		if visited.len() != ancestry.len() {
+			return Err(Error::UnusedAncestry);
+		}
+
+		if total_weight == 0 || signed_weight < threshold {
+			return Err(Error::NotEnoughWeight);
+		}
+
+		Ok(())
+	}
+}
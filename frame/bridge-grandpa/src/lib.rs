@@ -0,0 +1,813 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # GRANDPA Bridge Module
+//!
+//! - [`bridge_grandpa::Trait`](./trait.Trait.html)
+//! - [`Call`](./enum.Call.html)
+//!
+//! ## Overview
+//!
+//! The GRANDPA bridge module follows the finality of a foreign (bridged) Substrate chain. It
+//! keeps the GRANDPA authority set of the bridged chain and imports the headers it finalizes,
+//! trusting nothing but the authority set it was initialized with.
+//!
+//! Headers are submitted in chains descending from the best finalized header, along with the
+//! GRANDPA justification of the last one. The authority set changes scheduled in the digests of
+//! the headers are followed: a change signaled by a header is enacted once the header at its
+//! delay is finalized, and no header past it is accepted before that. The forced changes can't
+//! be followed, since they are never justified, and the bridge must be initialized again when the
+//! bridged chain forces a change.
+//!
+//! Only the last `HeadersToKeep` imported headers are kept, the older ones being pruned.
+//!
+//! The finalized headers are exposed to the other modules through the
+//! [`FinalizedHeaders`](./trait.FinalizedHeaders.html) trait, along with the verification of the
+//! storage proofs of the bridged chain against their state roots.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! * `initialize` - Set the header the bridge starts from, along with the authority set finalizing
+//!   its descendants.
+//! * `submit_finality_proof` - Import a chain of headers finalized by a justification.
+//!
+//! [`Call`]: ./enum.Call.html
+//! [`Trait`]: ./trait.Trait.html
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub mod justification;
+
+pub use justification::GrandpaJustification;
+
+use sp_std::prelude::*;
+use codec::{Encode, Decode};
+use sp_runtime::{
+	RuntimeDebug, generic::OpaqueDigestItemId,
+	traits::{Header as HeaderT, MaybeSerializeDeserialize, One},
+};
+use sp_finality_grandpa::{
+	AuthorityList, ConsensusLog, ScheduledChange, SetId, GRANDPA_ENGINE_ID,
+};
+use frame_support::{
+	decl_module, decl_event, decl_storage, decl_error, ensure, Parameter,
+	traits::Get, weights::{SimpleDispatchInfo, FunctionOf, DispatchClass, Weight},
+};
+use frame_system::{self as system, ensure_signed, ensure_root};
+
+/// The header of the bridged chain.
+pub type BridgedHeader<T> = <T as Trait>::BridgedHeader;
+/// The hash of a header of the bridged chain.
+pub type BridgedHash<T> = <BridgedHeader<T> as HeaderT>::Hash;
+/// The number of a header of the bridged chain.
+pub type BridgedNumber<T> = <BridgedHeader<T> as HeaderT>::Number;
+
+pub trait Trait: frame_system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// The header of the bridged chain.
+	type BridgedHeader: HeaderT + Parameter + MaybeSerializeDeserialize;
+
+	/// The maximum number of headers that can be imported by a single finality proof.
+	type MaxHeadersInProof: Get<u32>;
+
+	/// The number of imported headers kept, the older ones being pruned.
+	type HeadersToKeep: Get<u32>;
+}
+
+/// An authority set of the bridged chain.
+#[derive(Clone, Default, PartialEq, Eq, Encode, Decode, RuntimeDebug)]
+pub struct AuthoritySet {
+	/// The authorities, along with their weights.
+	pub authorities: AuthorityList,
+	/// The id of the set.
+	pub set_id: SetId,
+}
+
+/// An authority set change signaled by a finalized header of the bridged chain.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug)]
+pub struct PendingChange<Number> {
+	/// The authorities of the next set, along with their weights.
+	pub next_authorities: AuthorityList,
+	/// The number of the header whose finalization enacts the change.
+	pub enact_at: Number,
+}
+
+/// The reasons a storage proof of the bridged chain can be rejected for.
+#[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum StorageProofError {
+	/// The header the proof is against isn't known to be finalized.
+	UnknownHeader,
+	/// The proof doesn't match the state root of the header.
+	InvalidProof,
+}
+
+/// The headers of a bridged chain known to be finalized, for the modules acting upon the messages
+/// and the storage of the bridged chain.
+pub trait FinalizedHeaders<Header: HeaderT> {
+	/// The best finalized header, if the bridge is initialized.
+	fn best_finalized() -> Option<Header>;
+
+	/// A finalized header, if it is known.
+	fn finalized_header(hash: &Header::Hash) -> Option<Header>;
+
+	/// Read the value of a storage key at a finalized header from a proof of the storage of the
+	/// bridged chain.
+	fn read_storage_proof(
+		hash: &Header::Hash,
+		proof: Vec<Vec<u8>>,
+		key: &[u8],
+	) -> Result<Option<Vec<u8>>, StorageProofError> {
+		let header = Self::finalized_header(hash).ok_or(StorageProofError::UnknownHeader)?;
+		let db = sp_trie::StorageProof::new(proof).into_memory_db::<Header::Hashing>();
+
+		sp_trie::read_trie_value::<sp_trie::Layout<Header::Hashing>, _>(&db, header.state_root(), key)
+			.map_err(|_| StorageProofError::InvalidProof)
+	}
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as BridgeGrandpa {
+		/// The hash of the best finalized header of the bridged chain.
+		pub BestFinalized get(fn best_finalized_hash): Option<BridgedHash<T>>;
+
+		/// The finalized headers of the bridged chain.
+		pub ImportedHeaders get(fn imported_header):
+			map hasher(blake2_128_concat) BridgedHash<T> => Option<BridgedHeader<T>>;
+
+		/// The hashes of the last `HeadersToKeep` imported headers, as a ring buffer.
+		ImportedHashes: map hasher(twox_64_concat) u32 => Option<BridgedHash<T>>;
+
+		/// The index of the next slot of `ImportedHashes` to be used.
+		ImportedHashesPointer: u32;
+
+		/// The authority set finalizing the descendants of the best finalized header.
+		pub CurrentAuthoritySet get(fn current_authority_set): AuthoritySet;
+
+		/// The authority set change signaled by a finalized header and not enacted yet.
+		pub NextAuthoritySet get(fn next_authority_set): Option<PendingChange<BridgedNumber<T>>>;
+	}
+	add_extra_genesis {
+		config(initial_header): Option<BridgedHeader<T>>;
+		config(authorities): AuthorityList;
+		config(set_id): SetId;
+		build(|config| {
+			if let Some(ref header) = config.initial_header {
+				<Module<T>>::initialize_bridge(header.clone(), AuthoritySet {
+					authorities: config.authorities.clone(),
+					set_id: config.set_id,
+				});
+			}
+		})
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where Hash = BridgedHash<T>, Number = BridgedNumber<T> {
+		/// The bridge was initialized from the given header for the given authority set.
+		Initialized(Hash, SetId),
+		/// The given header was finalized.
+		Finalized(Hash, Number),
+		/// The authority set was changed to the given one.
+		AuthoritySetChanged(SetId),
+	}
+);
+
+decl_error! {
+	/// Error for the GRANDPA bridge module.
+	pub enum Error for Module<T: Trait> {
+		/// The bridge isn't initialized.
+		NotInitialized,
+		/// No header was submitted.
+		NoHeaders,
+		/// More than `MaxHeadersInProof` headers were submitted.
+		TooManyHeaders,
+		/// The headers don't descend from the best finalized header one after the other.
+		NotDescendant,
+		/// A header is past the enactment of an authority set change.
+		ChangeNotEnacted,
+		/// The justification doesn't finalize the last header for the current authority set.
+		InvalidJustification,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		fn deposit_event() = default;
+
+		/// The maximum number of headers that can be imported by a single finality proof.
+		const MaxHeadersInProof: u32 = T::MaxHeadersInProof::get();
+
+		/// The number of imported headers kept, the older ones being pruned.
+		const HeadersToKeep: u32 = T::HeadersToKeep::get();
+
+		/// Start following the finality of the bridged chain from the given header, which is
+		/// trusted to be finalized, with the given authority set finalizing its descendants.
+		///
+		/// The headers imported before are kept, but the pending authority set change is
+		/// forgotten. This is how the bridge recovers from a forced change of the bridged chain.
+		///
+		/// The dispatch origin for this call must be _Root_.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - Up to five storage writes and two storage removals.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedOperational(50_000)]
+		fn initialize(origin, header: BridgedHeader<T>, authorities: AuthorityList, set_id: SetId) {
+			ensure_root(origin)?;
+
+			let hash = header.hash();
+			Self::initialize_bridge(header, AuthoritySet { authorities, set_id });
+			Self::deposit_event(RawEvent::Initialized(hash, set_id));
+		}
+
+		/// Import a chain of headers of the bridged chain, descending one after the other from the
+		/// best finalized header, along with the justification finalizing the last one.
+		///
+		/// The authority set changes signaled by the headers are scheduled, and the headers must
+		/// not go past the enactment of a scheduled change. A change is enacted once the header at
+		/// its delay is finalized.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// # <weight>
+		/// - O(H + P + A) where H is the number of headers, bounded by `MaxHeadersInProof`, P the
+		///   number of precommits of the justification, bounded by the number of authorities, and
+		///   A the number of headers of its votes ancestries.
+		/// - One signature verification per precommit.
+		/// - H * 3 storage writes, H storage removals and up to three storage reads/writes.
+		/// - Up to two events.
+		/// # </weight>
+		#[weight = FunctionOf(
+			|args: (&Vec<BridgedHeader<T>>, &GrandpaJustification<BridgedHeader<T>>)| {
+				submit_finality_proof_weight(
+					args.0.len(),
+					args.1.commit.precommits.len(),
+					args.1.votes_ancestries.len(),
+				)
+			},
+			DispatchClass::Normal,
+			true
+		)]
+		fn submit_finality_proof(
+			origin,
+			headers: Vec<BridgedHeader<T>>,
+			justification: GrandpaJustification<BridgedHeader<T>>,
+		) {
+			ensure_signed(origin)?;
+
+			ensure!(!headers.is_empty(), Error::<T>::NoHeaders);
+			ensure!(
+				headers.len() <= T::MaxHeadersInProof::get() as usize,
+				Error::<T>::TooManyHeaders,
+			);
+
+			let best_finalized = Self::best_finalized_hash()
+				.and_then(|hash| <ImportedHeaders<T>>::get(hash))
+				.ok_or(Error::<T>::NotInitialized)?;
+
+			let mut pending_change = Self::next_authority_set();
+			let mut parent = &best_finalized;
+			for header in &headers {
+				ensure!(
+					header.parent_hash() == &parent.hash()
+						&& *header.number() == *parent.number() + One::one(),
+					Error::<T>::NotDescendant,
+				);
+
+				match pending_change {
+					Some(ref change) => ensure!(
+						*header.number() <= change.enact_at,
+						Error::<T>::ChangeNotEnacted,
+					),
+					None => pending_change = find_scheduled_change(header).map(|change| PendingChange {
+						next_authorities: change.next_authorities,
+						enact_at: *header.number() + change.delay,
+					}),
+				}
+
+				parent = header;
+			}
+
+			let (target_hash, target_number) = (parent.hash(), *parent.number());
+			let AuthoritySet { authorities, set_id } = Self::current_authority_set();
+			justification.verify_target((target_hash, target_number), set_id, &authorities)
+				.map_err(|_| Error::<T>::InvalidJustification)?;
+
+			for header in headers {
+				Self::import_header(header);
+			}
+			<BestFinalized<T>>::put(target_hash);
+			Self::deposit_event(RawEvent::Finalized(target_hash, target_number));
+
+			match pending_change {
+				Some(change) if change.enact_at == target_number => {
+					<NextAuthoritySet<T>>::kill();
+					CurrentAuthoritySet::put(AuthoritySet {
+						authorities: change.next_authorities,
+						set_id: set_id + 1,
+					});
+					Self::deposit_event(RawEvent::AuthoritySetChanged(set_id + 1));
+				},
+				Some(change) => <NextAuthoritySet<T>>::put(change),
+				None => {},
+			}
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	fn initialize_bridge(header: BridgedHeader<T>, authority_set: AuthoritySet) {
+		let hash = header.hash();
+		Self::import_header(header);
+		<BestFinalized<T>>::put(hash);
+		CurrentAuthoritySet::put(authority_set);
+		<NextAuthoritySet<T>>::kill();
+	}
+
+	/// Keep the header, pruning the oldest one if `HeadersToKeep` headers are already kept.
+	fn import_header(header: BridgedHeader<T>) {
+		let hash = header.hash();
+		// the hashes are only in the ring buffer once, so that they are pruned once they're the
+		// oldest.
+		if <ImportedHeaders<T>>::contains_key(hash) {
+			return;
+		}
+
+		let index = ImportedHashesPointer::get();
+		if let Some(pruned) = <ImportedHashes<T>>::get(index) {
+			<ImportedHeaders<T>>::remove(pruned);
+		}
+		<ImportedHashes<T>>::insert(index, hash);
+		ImportedHashesPointer::put((index + 1) % T::HeadersToKeep::get().max(1));
+		<ImportedHeaders<T>>::insert(hash, header);
+	}
+}
+
+/// The weight of importing `headers` headers finalized by a justification with `precommits`
+/// precommits and `ancestries` headers of votes ancestries.
+fn submit_finality_proof_weight(headers: usize, precommits: usize, ancestries: usize) -> Weight {
+	let weight = (headers as Weight).saturating_mul(50_000)
+		.saturating_add((precommits as Weight).saturating_mul(100_000))
+		.saturating_add((ancestries as Weight).saturating_mul(10_000));
+	weight.saturating_add(200_000)
+}
+
+impl<T: Trait> FinalizedHeaders<BridgedHeader<T>> for Module<T> {
+	fn best_finalized() -> Option<BridgedHeader<T>> {
+		Self::best_finalized_hash().and_then(|hash| <ImportedHeaders<T>>::get(hash))
+	}
+
+	fn finalized_header(hash: &BridgedHash<T>) -> Option<BridgedHeader<T>> {
+		<ImportedHeaders<T>>::get(hash)
+	}
+}
+
+/// The first authority set change scheduled in the digest of a header.
+fn find_scheduled_change<H: HeaderT>(header: &H) -> Option<ScheduledChange<H::Number>> {
+	let id = OpaqueDigestItemId::Consensus(&GRANDPA_ENGINE_ID);
+
+	header.digest().convert_first(|log| log.try_to(id)
+		.and_then(|log: ConsensusLog<H::Number>| log.try_into_change()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use frame_support::{
+		assert_ok, assert_noop, impl_outer_origin, parameter_types,
+		weights::{Weight, GetDispatchInfo},
+	};
+	use sp_core::H256;
+	use sp_keyring::Ed25519Keyring;
+	use sp_runtime::{
+		Perbill, DigestItem, testing::Header, traits::{BlakeTwo256, IdentityLookup, BadOrigin},
+	};
+	use sp_trie::TrieMut;
+
+	impl_outer_origin! {
+		pub enum Origin for Test  where system = frame_system {}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::one();
+	}
+	impl frame_system::Trait for Test {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Call = ();
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+		type ModuleToIndex = ();
+		type AccountData = ();
+		type MigrateAccount = (); type MigrateAccountData = (); type OnNewAccount = ();
+		type OnKilledAccount = ();
+	}
+	parameter_types! {
+		pub const MaxHeadersInProof: u32 = 4;
+		pub const HeadersToKeep: u32 = 5;
+	}
+	impl Trait for Test {
+		type Event = ();
+		type BridgedHeader = TestHeader;
+		type MaxHeadersInProof = MaxHeadersInProof;
+		type HeadersToKeep = HeadersToKeep;
+	}
+	type Bridge = Module<Test>;
+
+	// the testing header can't be deserialized, so the bridged chain uses the generic one.
+	type TestHeader = sp_runtime::generic::Header<u64, BlakeTwo256>;
+
+	fn authorities(keys: &[Ed25519Keyring]) -> AuthorityList {
+		keys.iter().map(|key| (key.public().into(), 1)).collect()
+	}
+
+	fn genesis_header() -> TestHeader {
+		TestHeader::new(0, Default::default(), Default::default(), Default::default(), Default::default())
+	}
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		GenesisConfig::<Test> {
+			initial_header: Some(genesis_header()),
+			authorities: authorities(&[
+				Ed25519Keyring::Alice,
+				Ed25519Keyring::Bob,
+				Ed25519Keyring::Charlie,
+				Ed25519Keyring::Dave,
+			]),
+			set_id: 0,
+		}.assimilate_storage(&mut t).unwrap();
+		t.into()
+	}
+
+	fn child_of(parent: &TestHeader) -> TestHeader {
+		TestHeader::new(
+			parent.number + 1,
+			Default::default(),
+			Default::default(),
+			parent.hash(),
+			Default::default(),
+		)
+	}
+
+	fn chain(parent: &TestHeader, count: usize) -> Vec<TestHeader> {
+		let mut headers: Vec<TestHeader> = Vec::new();
+		for _ in 0..count {
+			let header = child_of(headers.last().unwrap_or(parent));
+			headers.push(header);
+		}
+		headers
+	}
+
+	fn signal_change(header: &mut TestHeader, next: &[Ed25519Keyring], delay: u64) {
+		let change = ScheduledChange { next_authorities: authorities(next), delay };
+		header.digest.push(DigestItem::Consensus(
+			GRANDPA_ENGINE_ID,
+			ConsensusLog::ScheduledChange(change).encode(),
+		));
+	}
+
+	fn justify(
+		target: &TestHeader,
+		set_id: SetId,
+		signers: &[Ed25519Keyring],
+	) -> GrandpaJustification<TestHeader> {
+		let round = 1;
+		let precommit = grandpa::Precommit { target_hash: target.hash(), target_number: target.number };
+		let payload = sp_finality_grandpa::localized_payload(
+			round,
+			set_id,
+			&grandpa::Message::<H256, u64>::Precommit(precommit.clone()),
+		);
+		let precommits = signers.iter().map(|signer| grandpa::SignedPrecommit {
+			precommit: precommit.clone(),
+			signature: signer.sign(&payload).into(),
+			id: signer.public().into(),
+		}).collect();
+
+		GrandpaJustification {
+			round,
+			commit: grandpa::Commit {
+				target_hash: target.hash(),
+				target_number: target.number,
+				precommits,
+			},
+			votes_ancestries: Vec::new(),
+		}
+	}
+
+	const SIGNERS: [Ed25519Keyring; 3] = [
+		Ed25519Keyring::Alice,
+		Ed25519Keyring::Bob,
+		Ed25519Keyring::Charlie,
+	];
+
+	#[test]
+	fn finalized_headers_are_imported() {
+		new_test_ext().execute_with(|| {
+			let headers = chain(&genesis_header(), 3);
+			let target = headers[2].clone();
+
+			assert_ok!(Bridge::submit_finality_proof(
+				Origin::signed(1),
+				headers.clone(),
+				justify(&target, 0, &SIGNERS),
+			));
+
+			assert_eq!(Bridge::best_finalized_hash(), Some(target.hash()));
+			assert_eq!(<Bridge as FinalizedHeaders<_>>::best_finalized(), Some(target));
+			for header in headers {
+				assert_eq!(Bridge::finalized_header(&header.hash()), Some(header));
+			}
+		});
+	}
+
+	#[test]
+	fn invalid_finality_proofs_are_rejected() {
+		new_test_ext().execute_with(|| {
+			let headers = chain(&genesis_header(), 2);
+			let target = &headers[1];
+
+			assert_noop!(
+				Bridge::submit_finality_proof(
+					Origin::signed(1),
+					Vec::new(),
+					justify(target, 0, &SIGNERS),
+				),
+				Error::<Test>::NoHeaders,
+			);
+			assert_noop!(
+				Bridge::submit_finality_proof(
+					Origin::signed(1),
+					chain(&genesis_header(), 5),
+					justify(target, 0, &SIGNERS),
+				),
+				Error::<Test>::TooManyHeaders,
+			);
+			assert_noop!(
+				Bridge::submit_finality_proof(
+					Origin::signed(1),
+					vec![headers[1].clone()],
+					justify(target, 0, &SIGNERS),
+				),
+				Error::<Test>::NotDescendant,
+			);
+
+			// not enough authorities, or another set or header.
+			for justification in vec![
+				justify(target, 0, &SIGNERS[..2]),
+				justify(target, 1, &SIGNERS),
+				justify(&headers[0], 0, &SIGNERS),
+				justify(target, 0, &[Ed25519Keyring::Alice, Ed25519Keyring::Bob, Ed25519Keyring::Eve]),
+			] {
+				assert_noop!(
+					Bridge::submit_finality_proof(Origin::signed(1), headers.clone(), justification),
+					Error::<Test>::InvalidJustification,
+				);
+			}
+		});
+	}
+
+	#[test]
+	fn duplicate_and_excess_precommits_are_handled() {
+		new_test_ext().execute_with(|| {
+			let headers = chain(&genesis_header(), 1);
+			let target = &headers[0];
+
+			// the precommits of an authority are only counted once.
+			let duplicated = [Ed25519Keyring::Alice, Ed25519Keyring::Bob, Ed25519Keyring::Alice];
+			assert_eq!(
+				justify(target, 0, &duplicated).verify(0, &Bridge::current_authority_set().authorities),
+				Err(justification::Error::NotEnoughWeight),
+			);
+
+			// there can't be more precommits than authorities.
+			let mut signers = SIGNERS.to_vec();
+			signers.extend(&[Ed25519Keyring::Dave, Ed25519Keyring::Alice]);
+			assert_eq!(
+				justify(target, 0, &signers).verify(0, &Bridge::current_authority_set().authorities),
+				Err(justification::Error::TooManyPrecommits),
+			);
+
+			// the duplicate precommits are ignored once there are enough.
+			let mut signers = SIGNERS.to_vec();
+			signers.push(Ed25519Keyring::Alice);
+			assert_ok!(Bridge::submit_finality_proof(
+				Origin::signed(1),
+				headers.clone(),
+				justify(target, 0, &signers),
+			));
+		});
+	}
+
+	#[test]
+	fn submit_finality_proof_is_weighed_by_precommits() {
+		let headers = chain(&genesis_header(), 1);
+		let call = |signers: &[Ed25519Keyring]| {
+			Call::<Test>::submit_finality_proof(headers.clone(), justify(&headers[0], 0, signers))
+				.get_dispatch_info()
+				.weight
+		};
+		assert!(call(&SIGNERS[..2]) < call(&SIGNERS));
+	}
+
+	#[test]
+	fn old_headers_are_pruned() {
+		new_test_ext().execute_with(|| {
+			let genesis = genesis_header();
+			let headers = chain(&genesis, 6);
+			assert_ok!(Bridge::submit_finality_proof(
+				Origin::signed(1),
+				headers[..3].to_vec(),
+				justify(&headers[2], 0, &SIGNERS),
+			));
+			assert_ok!(Bridge::submit_finality_proof(
+				Origin::signed(1),
+				headers[3..].to_vec(),
+				justify(&headers[5], 0, &SIGNERS),
+			));
+
+			// the genesis header and the first imported header are pruned.
+			assert_eq!(Bridge::finalized_header(&genesis.hash()), None);
+			assert_eq!(Bridge::finalized_header(&headers[0].hash()), None);
+			for header in &headers[1..] {
+				assert_eq!(Bridge::finalized_header(&header.hash()), Some(header.clone()));
+			}
+			assert_eq!(<Bridge as FinalizedHeaders<_>>::best_finalized(), Some(headers[5].clone()));
+
+			// initializing from a kept header doesn't keep it twice.
+			assert_ok!(Bridge::initialize(
+				Origin::ROOT,
+				headers[5].clone(),
+				Bridge::current_authority_set().authorities,
+				0,
+			));
+			let next = chain(&headers[5], 4);
+			assert_ok!(Bridge::submit_finality_proof(
+				Origin::signed(1),
+				next.clone(),
+				justify(&next[3], 0, &SIGNERS),
+			));
+			assert_eq!(Bridge::finalized_header(&headers[5].hash()), Some(headers[5].clone()));
+			assert_eq!(Bridge::finalized_header(&headers[4].hash()), None);
+		});
+	}
+
+	#[test]
+	fn scheduled_authority_set_changes_are_enacted() {
+		new_test_ext().execute_with(|| {
+			let next = [Ed25519Keyring::Eve, Ed25519Keyring::Ferdie];
+
+			let mut headers = chain(&genesis_header(), 1);
+			signal_change(&mut headers[0], &next, 2);
+			let first = headers[0].clone();
+			headers.extend(chain(&first, 3));
+
+			// the old authorities don't finalize past the change.
+			assert_noop!(
+				Bridge::submit_finality_proof(
+					Origin::signed(1),
+					headers.clone(),
+					justify(&headers[3], 0, &SIGNERS),
+				),
+				Error::<Test>::ChangeNotEnacted,
+			);
+
+			assert_ok!(Bridge::submit_finality_proof(
+				Origin::signed(1),
+				headers[..2].to_vec(),
+				justify(&headers[1], 0, &SIGNERS),
+			));
+			assert_eq!(Bridge::current_authority_set().set_id, 0);
+			assert_eq!(Bridge::next_authority_set().map(|change| change.enact_at), Some(3));
+
+			assert_ok!(Bridge::submit_finality_proof(
+				Origin::signed(1),
+				vec![headers[2].clone()],
+				justify(&headers[2], 0, &SIGNERS),
+			));
+			assert_eq!(
+				Bridge::current_authority_set(),
+				AuthoritySet { authorities: authorities(&next), set_id: 1 },
+			);
+			assert_eq!(Bridge::next_authority_set(), None);
+
+			assert_noop!(
+				Bridge::submit_finality_proof(
+					Origin::signed(1),
+					vec![headers[3].clone()],
+					justify(&headers[3], 0, &SIGNERS),
+				),
+				Error::<Test>::InvalidJustification,
+			);
+			assert_ok!(Bridge::submit_finality_proof(
+				Origin::signed(1),
+				vec![headers[3].clone()],
+				justify(&headers[3], 1, &next),
+			));
+			assert_eq!(Bridge::best_finalized_hash(), Some(headers[3].hash()));
+		});
+	}
+
+	#[test]
+	fn initialize_resets_the_authority_set() {
+		new_test_ext().execute_with(|| {
+			let header = chain(&genesis_header(), 1).remove(0);
+			let next = [Ed25519Keyring::Eve];
+
+			assert_noop!(
+				Bridge::initialize(Origin::signed(1), header.clone(), authorities(&next), 5),
+				BadOrigin,
+			);
+			assert_ok!(Bridge::initialize(Origin::ROOT, header.clone(), authorities(&next), 5));
+
+			assert_eq!(Bridge::best_finalized_hash(), Some(header.hash()));
+			assert_eq!(
+				Bridge::current_authority_set(),
+				AuthoritySet { authorities: authorities(&next), set_id: 5 },
+			);
+
+			let headers = chain(&header, 1);
+			assert_ok!(Bridge::submit_finality_proof(
+				Origin::signed(1),
+				headers.clone(),
+				justify(&headers[0], 5, &next),
+			));
+		});
+	}
+
+	#[test]
+	fn storage_proofs_are_checked_against_finalized_headers() {
+		new_test_ext().execute_with(|| {
+			let mut db = sp_trie::MemoryDB::<BlakeTwo256>::default();
+			let mut state_root = Default::default();
+			{
+				let mut trie = sp_trie::TrieDBMut::<sp_trie::Layout<BlakeTwo256>>::new(
+					&mut db,
+					&mut state_root,
+				);
+				trie.insert(b"key", b"value").unwrap();
+			}
+			let proof: Vec<Vec<u8>> = db.drain().into_iter().map(|(_, (node, _))| node).collect();
+
+			let mut header = child_of(&genesis_header());
+			header.state_root = state_root;
+			assert_eq!(
+				Bridge::read_storage_proof(&header.hash(), proof.clone(), b"key"),
+				Err(StorageProofError::UnknownHeader),
+			);
+
+			assert_ok!(Bridge::submit_finality_proof(
+				Origin::signed(1),
+				vec![header.clone()],
+				justify(&header, 0, &SIGNERS),
+			));
+			assert_eq!(
+				Bridge::read_storage_proof(&header.hash(), proof.clone(), b"key"),
+				Ok(Some(b"value".to_vec())),
+			);
+			assert_eq!(Bridge::read_storage_proof(&header.hash(), proof, b"other"), Ok(None));
+			assert_eq!(
+				Bridge::read_storage_proof(&header.hash(), Vec::new(), b"key"),
+				Err(StorageProofError::InvalidProof),
+			);
+		});
+	}
+}
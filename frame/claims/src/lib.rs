@@ -0,0 +1,713 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Claims Module
+//!
+//! - [`claims::Trait`](./trait.Trait.html)
+//! - [`Call`](./enum.Call.html)
+//!
+//! ## Overview
+//!
+//! The claims module pays out the balances allocated to Ethereum addresses at genesis. The
+//! holder of the Ethereum key proves the control of the address by signing a message naming the
+//! account to be paid with the `personal_sign` scheme of EIP-191, and submits the signature as
+//! an unsigned transaction, since the account may not have any funds to pay fees with yet.
+//!
+//! A claim may additionally require the claimer to accept a statement, which is then signed
+//! along with the account, and may come with a vesting schedule which is attached to the paid
+//! account.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! * `claim` - Claim the balance of an Ethereum address which doesn't require a statement.
+//! * `claim_attest` - Claim the balance of an Ethereum address, accepting its statement.
+//! * `mint_claim` - Allocate a new claim to an Ethereum address.
+//!
+//! [`Call`]: ./enum.Call.html
+//! [`Trait`]: ./trait.Trait.html
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::prelude::*;
+use codec::{Encode, Decode};
+#[cfg(feature = "std")]
+use serde::{Serialize, Deserialize};
+use sp_io::{hashing::keccak_256, crypto::secp256k1_ecdsa_recover};
+use sp_runtime::{
+	RuntimeDebug,
+	traits::{Zero, CheckedSub, Saturating},
+	transaction_validity::{
+		TransactionLongevity, TransactionValidity, ValidTransaction, InvalidTransaction,
+		TransactionSource,
+	},
+};
+use frame_support::{
+	decl_module, decl_event, decl_storage, decl_error, ensure,
+	traits::{Currency, Get, VestingSchedule},
+	weights::SimpleDispatchInfo,
+};
+use frame_system::{self as system, ensure_root, ensure_none};
+
+type CurrencyOf<T> = <<T as Trait>::VestingSchedule as VestingSchedule<
+	<T as frame_system::Trait>::AccountId
+>>::Currency;
+type BalanceOf<T> = <CurrencyOf<T> as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
+
+pub trait Trait: frame_system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// The vesting schedules attached to the paid accounts, along with the currency paid.
+	type VestingSchedule: VestingSchedule<Self::AccountId, Moment = Self::BlockNumber>;
+
+	/// The prefix of the signed messages, usually the name of the chain, so that the signatures
+	/// can't be replayed on another chain.
+	type Prefix: Get<&'static [u8]>;
+}
+
+/// An Ethereum address, i.e. the last 20 bytes of the Keccak-256 hash of the public key.
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode, Default, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct EthereumAddress(pub [u8; 20]);
+
+/// A recoverable ECDSA signature over secp256k1, in the RSV format.
+#[derive(Encode, Decode, Clone)]
+pub struct EcdsaSignature(pub [u8; 65]);
+
+impl PartialEq for EcdsaSignature {
+	fn eq(&self, other: &Self) -> bool {
+		&self.0[..] == &other.0[..]
+	}
+}
+
+impl sp_std::fmt::Debug for EcdsaSignature {
+	fn fmt(&self, f: &mut sp_std::fmt::Formatter<'_>) -> sp_std::fmt::Result {
+		write!(f, "EcdsaSignature({:?})", &self.0[..])
+	}
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Claims {
+		/// The balances allocated to the Ethereum addresses, which are yet to be claimed.
+		pub Claims get(fn claims): map hasher(identity) EthereumAddress => Option<BalanceOf<T>>;
+
+		/// The sum of the balances which are yet to be claimed.
+		pub Total get(fn total): BalanceOf<T>;
+
+		/// The vesting schedules of the claims, as the amount locked, the amount unlocked per
+		/// block and the block the unlocking starts at.
+		pub Vesting get(fn vesting):
+			map hasher(identity) EthereumAddress
+			=> Option<(BalanceOf<T>, BalanceOf<T>, T::BlockNumber)>;
+
+		/// The statements the claimers of the Ethereum addresses must accept.
+		pub Statements get(fn statement): map hasher(identity) EthereumAddress => Option<Vec<u8>>;
+	}
+	add_extra_genesis {
+		config(claims): Vec<(EthereumAddress, BalanceOf<T>, Option<Vec<u8>>)>;
+		config(vesting): Vec<(EthereumAddress, (BalanceOf<T>, BalanceOf<T>, T::BlockNumber))>;
+		build(|config: &GenesisConfig<T>| {
+			let mut total: BalanceOf<T> = Zero::zero();
+			for (address, value, statement) in config.claims.iter() {
+				<Claims<T>>::insert(address, value);
+				total = total.saturating_add(*value);
+				if let Some(statement) = statement {
+					Statements::insert(address, statement);
+				}
+			}
+			<Total<T>>::put(total);
+			for (address, vesting) in config.vesting.iter() {
+				<Vesting<T>>::insert(address, vesting);
+			}
+		})
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where
+		Balance = BalanceOf<T>,
+		AccountId = <T as frame_system::Trait>::AccountId,
+	{
+		/// The balance of the Ethereum address was claimed and paid to the account.
+		Claimed(AccountId, EthereumAddress, Balance),
+	}
+);
+
+decl_error! {
+	/// Error for the claims module.
+	pub enum Error for Module<T: Trait> {
+		/// The signature doesn't recover an Ethereum address.
+		InvalidEthereumSignature,
+		/// The Ethereum address has no balance to claim.
+		SignerHasNoClaim,
+		/// The claim requires a statement to be accepted, so `claim_attest` must be used.
+		StatementRequired,
+		/// The statement isn't the one of the claim.
+		InvalidStatement,
+		/// The total of the claims is lower than the claim, which means the state is corrupt.
+		PotUnderflow,
+		/// The account paid already has a vesting schedule.
+		VestedBalanceExists,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		/// The prefix of the signed messages.
+		const Prefix: &[u8] = T::Prefix::get();
+
+		fn deposit_event() = default;
+
+		/// Claim the balance of an Ethereum address, paying it to `dest`.
+		///
+		/// The dispatch origin for this call must be _None_. The call is validated by checking
+		/// that `ethereum_signature` is the signature of the message naming `dest` by an
+		/// Ethereum address with a claim which doesn't require a statement.
+		///
+		/// Emits `Claimed`.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - One signature recovery.
+		/// - Up to five storage reads and writes.
+		/// - One balance deposit, up to one vesting schedule addition.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(1_000_000)]
+		fn claim(origin, dest: T::AccountId, ethereum_signature: EcdsaSignature) {
+			ensure_none(origin)?;
+
+			let data = dest.using_encoded(to_ascii_hex);
+			let signer = Self::eth_recover(&ethereum_signature, &data, &[])
+				.ok_or(Error::<T>::InvalidEthereumSignature)?;
+			ensure!(!Statements::contains_key(&signer), Error::<T>::StatementRequired);
+
+			Self::process_claim(signer, dest)?;
+		}
+
+		/// Claim the balance of an Ethereum address, paying it to `dest` and accepting the
+		/// `statement` of the claim.
+		///
+		/// The dispatch origin for this call must be _None_. The call is validated by checking
+		/// that `ethereum_signature` is the signature of the message naming `dest` followed by
+		/// `statement`, by an Ethereum address whose claim requires `statement`.
+		///
+		/// Emits `Claimed`.
+		///
+		/// # <weight>
+		/// - O(S) where S is the length of the statement.
+		/// - One signature recovery.
+		/// - Up to six storage reads and writes.
+		/// - One balance deposit, up to one vesting schedule addition.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(1_000_000)]
+		fn claim_attest(
+			origin,
+			dest: T::AccountId,
+			ethereum_signature: EcdsaSignature,
+			statement: Vec<u8>,
+		) {
+			ensure_none(origin)?;
+
+			let data = dest.using_encoded(to_ascii_hex);
+			let signer = Self::eth_recover(&ethereum_signature, &data, &statement)
+				.ok_or(Error::<T>::InvalidEthereumSignature)?;
+			ensure!(
+				Self::statement(&signer).as_ref() == Some(&statement),
+				Error::<T>::InvalidStatement,
+			);
+
+			Self::process_claim(signer, dest)?;
+			Statements::remove(&signer);
+		}
+
+		/// Allocate `value` to the Ethereum address `who`, along with an optional vesting
+		/// schedule and an optional statement to be accepted by the claimer.
+		///
+		/// An existing claim of `who` is replaced, along with its vesting schedule and statement.
+		///
+		/// The dispatch origin for this call must be _Root_.
+		///
+		/// # <weight>
+		/// - O(S) where S is the length of the statement.
+		/// - Up to four storage writes.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedOperational(30_000)]
+		fn mint_claim(
+			origin,
+			who: EthereumAddress,
+			value: BalanceOf<T>,
+			vesting_schedule: Option<(BalanceOf<T>, BalanceOf<T>, T::BlockNumber)>,
+			statement: Option<Vec<u8>>,
+		) {
+			ensure_root(origin)?;
+
+			let previous = <Claims<T>>::get(&who).unwrap_or_else(Zero::zero);
+			<Total<T>>::mutate(|t| *t = t.saturating_sub(previous).saturating_add(value));
+			<Claims<T>>::insert(who, value);
+			match vesting_schedule {
+				Some(vesting_schedule) => <Vesting<T>>::insert(who, vesting_schedule),
+				None => <Vesting<T>>::remove(who),
+			}
+			match statement {
+				Some(statement) => Statements::insert(who, statement),
+				None => Statements::remove(who),
+			}
+		}
+	}
+}
+
+/// Encode the given bytes as lower-case hexadecimal ASCII, as the accounts are named in the
+/// signed messages.
+fn to_ascii_hex(data: &[u8]) -> Vec<u8> {
+	let mut r = Vec::with_capacity(data.len() * 2);
+	let mut push_nibble = |n| r.push(if n < 10 { b'0' + n } else { b'a' - 10 + n });
+	for &b in data.iter() {
+		push_nibble(b / 16);
+		push_nibble(b % 16);
+	}
+	r
+}
+
+impl<T: Trait> Module<T> {
+	/// The message signed to claim a balance, as specified by EIP-191 for `personal_sign`: the
+	/// prefix of the chain, followed by the account and the statement.
+	fn ethereum_signable_message(what: &[u8], extra: &[u8]) -> Vec<u8> {
+		let prefix = T::Prefix::get();
+		let mut l = prefix.len() + what.len() + extra.len();
+		let mut rev = Vec::new();
+		while l > 0 {
+			rev.push(b'0' + (l % 10) as u8);
+			l /= 10;
+		}
+		let mut v = b"\x19Ethereum Signed Message:\n".to_vec();
+		v.extend(rev.into_iter().rev());
+		v.extend_from_slice(&prefix[..]);
+		v.extend_from_slice(what);
+		v.extend_from_slice(extra);
+		v
+	}
+
+	/// Recover the Ethereum address which signed the message made of `what` and `extra`.
+	fn eth_recover(s: &EcdsaSignature, what: &[u8], extra: &[u8]) -> Option<EthereumAddress> {
+		let msg = keccak_256(&Self::ethereum_signable_message(what, extra));
+		let mut res = EthereumAddress::default();
+		res.0.copy_from_slice(&keccak_256(&secp256k1_ecdsa_recover(&s.0, &msg).ok()?[..])[12..]);
+		Some(res)
+	}
+
+	/// Pay the claim of `signer` to `dest`, attaching its vesting schedule.
+	fn process_claim(signer: EthereumAddress, dest: T::AccountId) -> sp_runtime::DispatchResult {
+		let balance_due = <Claims<T>>::get(&signer).ok_or(Error::<T>::SignerHasNoClaim)?;
+		let new_total = Self::total().checked_sub(&balance_due).ok_or(Error::<T>::PotUnderflow)?;
+
+		let vesting = <Vesting<T>>::get(&signer);
+		ensure!(
+			vesting.is_none() || T::VestingSchedule::vesting_balance(&dest).is_none(),
+			Error::<T>::VestedBalanceExists,
+		);
+
+		CurrencyOf::<T>::deposit_creating(&dest, balance_due);
+		if let Some((locked, per_block, starting_block)) = vesting {
+			T::VestingSchedule::add_vesting_schedule(&dest, locked, per_block, starting_block)
+				.expect("no other vesting schedule exists, as checked above; qed");
+		}
+
+		<Total<T>>::put(new_total);
+		<Claims<T>>::remove(&signer);
+		<Vesting<T>>::remove(&signer);
+
+		Self::deposit_event(RawEvent::Claimed(dest, signer, balance_due));
+		Ok(())
+	}
+}
+
+impl<T: Trait> frame_support::unsigned::ValidateUnsigned for Module<T> {
+	type Call = Call<T>;
+
+	fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+		const PRIORITY: u64 = 100;
+
+		let signer = match call {
+			Call::claim(account, ethereum_signature) => {
+				let data = account.using_encoded(to_ascii_hex);
+				let signer = Self::eth_recover(&ethereum_signature, &data, &[])
+					.ok_or(InvalidTransaction::BadProof)?;
+				if Statements::contains_key(&signer) {
+					return InvalidTransaction::Custom(ValidityError::StatementRequired.into()).into();
+				}
+				signer
+			},
+			Call::claim_attest(account, ethereum_signature, statement) => {
+				let data = account.using_encoded(to_ascii_hex);
+				let signer = Self::eth_recover(&ethereum_signature, &data, &statement)
+					.ok_or(InvalidTransaction::BadProof)?;
+				if Self::statement(&signer).as_ref() != Some(statement) {
+					return InvalidTransaction::Custom(ValidityError::InvalidStatement.into()).into();
+				}
+				signer
+			},
+			_ => return InvalidTransaction::Call.into(),
+		};
+
+		if !<Claims<T>>::contains_key(&signer) {
+			return InvalidTransaction::Custom(ValidityError::SignerHasNoClaim.into()).into();
+		}
+
+		Ok(ValidTransaction {
+			priority: PRIORITY,
+			requires: vec![],
+			provides: vec![("claims", signer).encode()],
+			longevity: TransactionLongevity::max_value(),
+			propagate: true,
+		})
+	}
+}
+
+/// The reasons for an unsigned claim to be invalid, beyond a bad signature.
+#[repr(u8)]
+pub enum ValidityError {
+	/// The Ethereum address has no balance to claim.
+	SignerHasNoClaim = 0,
+	/// The claim requires a statement to be accepted.
+	StatementRequired = 1,
+	/// The statement isn't the one of the claim.
+	InvalidStatement = 2,
+}
+
+impl From<ValidityError> for u8 {
+	fn from(err: ValidityError) -> Self {
+		err as u8
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use hex_literal::hex;
+	use sp_core::H256;
+	use sp_runtime::{
+		Perbill, testing::Header, traits::{BlakeTwo256, IdentityLookup, Identity, BadOrigin},
+	};
+	use frame_support::{
+		assert_ok, assert_noop, assert_err, impl_outer_origin, parameter_types, weights::Weight,
+		unsigned::ValidateUnsigned,
+	};
+
+	impl_outer_origin! {
+		pub enum Origin for Test  where system = frame_system {}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::one();
+	}
+	impl frame_system::Trait for Test {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Call = ();
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+		type ModuleToIndex = ();
+		type AccountData = pallet_balances::AccountData<u64>;
+		type MigrateAccount = (); type MigrateAccountData = (); type OnNewAccount = ();
+		type OnKilledAccount = ();
+	}
+	parameter_types! {
+		pub const ExistentialDeposit: u64 = 1;
+		pub const MinVestedTransfer: u64 = 0;
+		pub const Prefix: &'static [u8] = b"Pay RUSTs to the TEST account:";
+	}
+	impl pallet_balances::Trait for Test {
+		type Balance = u64;
+		type DustRemoval = ();
+		type Event = ();
+		type ExistentialDeposit = ExistentialDeposit;
+		type AccountStore = System;
+	}
+	impl pallet_vesting::Trait for Test {
+		type Event = ();
+		type Currency = Balances;
+		type BlockNumberToBalance = Identity;
+		type MinVestedTransfer = MinVestedTransfer;
+	}
+	impl Trait for Test {
+		type Event = ();
+		type VestingSchedule = Vesting;
+		type Prefix = Prefix;
+	}
+	type System = frame_system::Module<Test>;
+	type Balances = pallet_balances::Module<Test>;
+	type Vesting = pallet_vesting::Module<Test>;
+	type Claims = Module<Test>;
+
+	fn alice() -> secp256k1::SecretKey {
+		secp256k1::SecretKey::parse(&keccak_256(b"Alice")).unwrap()
+	}
+	fn bob() -> secp256k1::SecretKey {
+		secp256k1::SecretKey::parse(&keccak_256(b"Bob")).unwrap()
+	}
+	fn dave() -> secp256k1::SecretKey {
+		secp256k1::SecretKey::parse(&keccak_256(b"Dave")).unwrap()
+	}
+
+	fn eth(secret: &secp256k1::SecretKey) -> EthereumAddress {
+		let public = secp256k1::PublicKey::from_secret_key(secret);
+		let mut res = EthereumAddress::default();
+		res.0.copy_from_slice(&keccak_256(&public.serialize()[1..65])[12..]);
+		res
+	}
+
+	fn sig(secret: &secp256k1::SecretKey, what: &[u8], extra: &[u8]) -> EcdsaSignature {
+		let msg = keccak_256(&Claims::ethereum_signable_message(&to_ascii_hex(what), extra));
+		let (sig, recovery_id) = secp256k1::sign(&secp256k1::Message::parse(&msg), secret);
+		let mut r = [0u8; 65];
+		r[0..64].copy_from_slice(&sig.serialize()[..]);
+		r[64] = recovery_id.serialize();
+		EcdsaSignature(r)
+	}
+
+	const STATEMENT: &[u8] = b"I accept the terms.";
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		pallet_balances::GenesisConfig::<Test>::default().assimilate_storage(&mut t).unwrap();
+		GenesisConfig::<Test> {
+			claims: vec![
+				(eth(&alice()), 100, None),
+				(eth(&dave()), 200, Some(STATEMENT.to_vec())),
+			],
+			vesting: vec![(eth(&alice()), (50, 10, 1))],
+		}.assimilate_storage(&mut t).unwrap();
+		t.into()
+	}
+
+	#[test]
+	fn basic_setup_works() {
+		new_test_ext().execute_with(|| {
+			assert_eq!(Claims::total(), 300);
+			assert_eq!(Claims::claims(&eth(&alice())), Some(100));
+			assert_eq!(Claims::claims(&eth(&bob())), None);
+			assert_eq!(Claims::vesting(&eth(&alice())), Some((50, 10, 1)));
+			assert_eq!(Claims::statement(&eth(&dave())), Some(STATEMENT.to_vec()));
+		});
+	}
+
+	#[test]
+	fn serde_works() {
+		let x = EthereumAddress(hex!["0123456789abcdef0123456789abcdef01234567"]);
+		let y = serde_json::to_string(&x).unwrap();
+		let z: EthereumAddress = serde_json::from_str(&y).unwrap();
+		assert_eq!(x, z);
+	}
+
+	#[test]
+	fn claiming_works_with_the_vesting_schedule() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Claims::claim(Origin::NONE, 42, sig(&alice(), &42u64.encode(), &[])));
+			assert_eq!(Balances::free_balance(42), 100);
+			assert_eq!(Vesting::vesting_balance(&42), Some(50));
+			assert_eq!(Claims::total(), 200);
+			assert_eq!(Claims::claims(&eth(&alice())), None);
+			assert_eq!(Claims::vesting(&eth(&alice())), None);
+
+			assert_noop!(
+				Claims::claim(Origin::NONE, 42, sig(&alice(), &42u64.encode(), &[])),
+				Error::<Test>::SignerHasNoClaim,
+			);
+		});
+	}
+
+	#[test]
+	fn claims_are_checked() {
+		new_test_ext().execute_with(|| {
+			assert_noop!(
+				Claims::claim(Origin::signed(42), 42, sig(&alice(), &42u64.encode(), &[])),
+				BadOrigin,
+			);
+			assert_noop!(
+				Claims::claim(Origin::NONE, 42, sig(&bob(), &42u64.encode(), &[])),
+				Error::<Test>::SignerHasNoClaim,
+			);
+			// the signature names another account, so it recovers another address.
+			assert_noop!(
+				Claims::claim(Origin::NONE, 42, sig(&alice(), &69u64.encode(), &[])),
+				Error::<Test>::SignerHasNoClaim,
+			);
+			assert_noop!(
+				Claims::claim(Origin::NONE, 42, EcdsaSignature([0xff; 65])),
+				Error::<Test>::InvalidEthereumSignature,
+			);
+			assert_noop!(
+				Claims::claim(Origin::NONE, 42, sig(&dave(), &42u64.encode(), &[])),
+				Error::<Test>::StatementRequired,
+			);
+		});
+	}
+
+	#[test]
+	fn claiming_with_a_statement_works() {
+		new_test_ext().execute_with(|| {
+			let wrong = b"I accept nothing.";
+			assert_noop!(
+				Claims::claim_attest(
+					Origin::NONE,
+					42,
+					sig(&dave(), &42u64.encode(), wrong),
+					wrong.to_vec(),
+				),
+				Error::<Test>::InvalidStatement,
+			);
+			assert_noop!(
+				Claims::claim_attest(
+					Origin::NONE,
+					42,
+					sig(&alice(), &42u64.encode(), STATEMENT),
+					STATEMENT.to_vec(),
+				),
+				Error::<Test>::InvalidStatement,
+			);
+
+			assert_ok!(Claims::claim_attest(
+				Origin::NONE,
+				42,
+				sig(&dave(), &42u64.encode(), STATEMENT),
+				STATEMENT.to_vec(),
+			));
+			assert_eq!(Balances::free_balance(42), 200);
+			assert_eq!(Vesting::vesting_balance(&42), None);
+			assert_eq!(Claims::statement(&eth(&dave())), None);
+			assert_eq!(Claims::total(), 100);
+		});
+	}
+
+	#[test]
+	fn vested_claims_are_refused_to_vesting_accounts() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Claims::mint_claim(Origin::ROOT, eth(&bob()), 200, Some((50, 10, 1)), None));
+			assert_ok!(Claims::claim(Origin::NONE, 42, sig(&alice(), &42u64.encode(), &[])));
+			assert_noop!(
+				Claims::claim(Origin::NONE, 42, sig(&bob(), &42u64.encode(), &[])),
+				Error::<Test>::VestedBalanceExists,
+			);
+		});
+	}
+
+	#[test]
+	fn minting_claims_works() {
+		new_test_ext().execute_with(|| {
+			assert_noop!(
+				Claims::mint_claim(Origin::signed(42), eth(&bob()), 200, None, None),
+				BadOrigin,
+			);
+			assert_ok!(Claims::mint_claim(Origin::ROOT, eth(&bob()), 200, None, None));
+			assert_eq!(Claims::total(), 500);
+
+			assert_ok!(Claims::claim(Origin::NONE, 69, sig(&bob(), &69u64.encode(), &[])));
+			assert_eq!(Balances::free_balance(69), 200);
+			assert_eq!(Vesting::vesting_balance(&69), None);
+			assert_eq!(Claims::total(), 300);
+		});
+	}
+
+	#[test]
+	fn minting_over_a_claim_replaces_it() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Claims::mint_claim(Origin::ROOT, eth(&alice()), 150, None, None));
+			assert_eq!(Claims::total(), 350);
+			assert_eq!(Claims::claims(&eth(&alice())), Some(150));
+			assert_eq!(Claims::vesting(&eth(&alice())), None);
+
+			assert_ok!(Claims::mint_claim(Origin::ROOT, eth(&dave()), 50, None, None));
+			assert_eq!(Claims::total(), 200);
+			assert_eq!(Claims::statement(&eth(&dave())), None);
+
+			assert_ok!(Claims::claim(Origin::NONE, 42, sig(&alice(), &42u64.encode(), &[])));
+			assert_eq!(Balances::free_balance(42), 150);
+			assert_eq!(Claims::total(), 50);
+		});
+	}
+
+	#[test]
+	fn validate_unsigned_works() {
+		use sp_runtime::transaction_validity::TransactionSource::External;
+
+		new_test_ext().execute_with(|| {
+			assert_eq!(
+				<Module<Test>>::validate_unsigned(
+					External,
+					&Call::claim(1, sig(&alice(), &1u64.encode(), &[])),
+				),
+				Ok(ValidTransaction {
+					priority: 100,
+					requires: vec![],
+					provides: vec![("claims", eth(&alice())).encode()],
+					longevity: TransactionLongevity::max_value(),
+					propagate: true,
+				}),
+			);
+			assert_eq!(
+				<Module<Test>>::validate_unsigned(External, &Call::claim(0, EcdsaSignature([0; 65]))),
+				InvalidTransaction::BadProof.into(),
+			);
+			assert_eq!(
+				<Module<Test>>::validate_unsigned(
+					External,
+					&Call::claim(1, sig(&bob(), &1u64.encode(), &[])),
+				),
+				InvalidTransaction::Custom(ValidityError::SignerHasNoClaim.into()).into(),
+			);
+			assert_eq!(
+				<Module<Test>>::validate_unsigned(
+					External,
+					&Call::claim(1, sig(&dave(), &1u64.encode(), &[])),
+				),
+				InvalidTransaction::Custom(ValidityError::StatementRequired.into()).into(),
+			);
+			assert!(<Module<Test>>::validate_unsigned(
+				External,
+				&Call::claim_attest(1, sig(&dave(), &1u64.encode(), STATEMENT), STATEMENT.to_vec()),
+			).is_ok());
+			assert_err!(
+				<Module<Test>>::validate_unsigned(
+					External,
+					&Call::mint_claim(eth(&bob()), 200, None, None),
+				),
+				InvalidTransaction::Call,
+			);
+		});
+	}
+}
@@ -550,7 +550,7 @@ mod tests {
 		type Version = ();
 		type ModuleToIndex = ();
 		type AccountData = ();
-		type MigrateAccount = (); type OnNewAccount = ();
+		type MigrateAccount = (); type MigrateAccountData = (); type OnNewAccount = ();
 		type OnKilledAccount = ();
 	}
 	impl Trait<Instance1> for Test {
@@ -127,7 +127,6 @@ use frame_support::{
 };
 use frame_support::traits::{OnKilledAccount, OnUnbalanced, Currency, Get, Time, Randomness};
 use frame_system::{self as system, ensure_signed, RawOrigin, ensure_root};
-use sp_core::storage::well_known_keys::CHILD_STORAGE_KEY_PREFIX;
 use pallet_contracts_primitives::{RentProjection, ContractAccessError};
 
 pub type CodeHash<T> = <T as frame_system::Trait>::Hash;
@@ -225,16 +224,16 @@ pub struct RawAliveContractInfo<CodeHash, Balance, BlockNumber> {
 }
 
 impl<CodeHash, Balance, BlockNumber> RawAliveContractInfo<CodeHash, Balance, BlockNumber> {
-	/// Associated child trie unique id is built from the hash part of the trie id.
-	pub fn child_trie_unique_id(&self) -> child::ChildInfo {
-		trie_unique_id(&self.trie_id[..])
+	/// The child trie of the storage of the contract.
+	pub fn child_trie(&self) -> child::ChildTrie {
+		child_trie(&self.trie_id[..])
 	}
 }
 
-/// Associated child trie unique id is built from the hash part of the trie id.
-pub(crate) fn trie_unique_id(trie_id: &[u8]) -> child::ChildInfo {
-	let start = CHILD_STORAGE_KEY_PREFIX.len() + b"default:".len();
-	child::ChildInfo::new_default(&trie_id[start ..])
+/// The child trie of the contract storage with the given trie id.
+pub(crate) fn child_trie(trie_id: &[u8]) -> child::ChildTrie {
+	child::ChildTrie::from_storage_key(trie_id.to_vec())
+		.expect("trie ids are the storage keys of default child tries; qed")
 }
 
 pub type TombstoneContractInfo<T> =
@@ -268,8 +267,8 @@ pub trait TrieIdGenerator<AccountId> {
 	/// The implementation must ensure every new trie id is unique: two consecutive calls with the
 	/// same parameter needs to return different trie id values.
 	///
-	/// Also, the implementation is responsible for ensuring that `TrieId` starts with
-	/// `:child_storage:`.
+	/// Also, the implementation is responsible for ensuring that `TrieId` is the storage key of a
+	/// default child trie, i.e. that it starts with `:child_storage:default:`.
 	/// TODO: We want to change this, see https://github.com/paritytech/substrate/issues/2325
 	fn trie_id(account_id: &AccountId) -> TrieId;
 }
@@ -296,11 +295,7 @@ where
 		buf.extend_from_slice(&new_seed.to_le_bytes()[..]);
 
 		// TODO: see https://github.com/paritytech/substrate/issues/2325
-		CHILD_STORAGE_KEY_PREFIX.iter()
-			.chain(b"default:")
-			.chain(T::Hashing::hash(&buf[..]).as_ref().iter())
-			.cloned()
-			.collect()
+		child::ChildTrie::new(T::Hashing::hash(&buf[..]).as_ref()).storage_key().to_vec()
 	}
 }
 
@@ -341,6 +336,8 @@ parameter_types! {
 	pub const DefaultMaxValueSize: u32 = 16_384;
 	/// A reasonable default value for [`Trait::BlockGasLimit`].
 	pub const DefaultBlockGasLimit: u32 = 10_000_000;
+	/// A reasonable default value for [`Trait::RemoveKeysLimit`].
+	pub const DefaultRemoveKeysLimit: u32 = 1024;
 }
 
 pub trait Trait: frame_system::Trait {
@@ -424,6 +421,11 @@ pub trait Trait: frame_system::Trait {
 
 	/// The maximum amount of gas that could be expended per block.
 	type BlockGasLimit: Get<Gas>;
+
+	/// The greatest number of storage items of a removed contract deleted at once.
+	///
+	/// The storage of a removed contract which has more items is deleted in the following blocks.
+	type RemoveKeysLimit: Get<u32>;
 }
 
 /// Simple contract address determiner.
@@ -543,6 +545,9 @@ decl_module! {
 		/// default value is 10_000_000.
 		const BlockGasLimit: Gas = T::BlockGasLimit::get();
 
+		/// The greatest number of storage items of a removed contract deleted at once.
+		const RemoveKeysLimit: u32 = T::RemoveKeysLimit::get();
+
 		fn deposit_event() = default;
 
 		/// Updates the schedule for metering contracts.
@@ -663,6 +668,17 @@ decl_module! {
 			}
 		}
 
+		fn on_initialize() {
+			// the storage of the removed contracts is deleted one contract at a time.
+			let mut queue = DeletionQueue::get();
+			if let Some(child_trie) = queue.first() {
+				if child_trie.kill_storage_limit(T::RemoveKeysLimit::get()) {
+					queue.remove(0);
+					DeletionQueue::put(queue);
+				}
+			}
+		}
+
 		fn on_finalize() {
 			GasSpent::kill();
 		}
@@ -714,6 +730,14 @@ impl<T: Trait> Module<T> {
 }
 
 impl<T: Trait> Module<T> {
+	/// Delete the storage of a removed contract, up to `RemoveKeysLimit` of its items, and queue
+	/// the rest for deletion in the following blocks.
+	pub(crate) fn remove_storage(child_trie: child::ChildTrie) {
+		if !child_trie.kill_storage_limit(T::RemoveKeysLimit::get()) {
+			DeletionQueue::mutate(|queue| queue.push(child_trie));
+		}
+	}
+
 	fn execute_wasm(
 		origin: T::AccountId,
 		gas_limit: Gas,
@@ -813,18 +837,11 @@ impl<T: Trait> Module<T> {
 			origin_contract.last_write
 		};
 
+		let child_trie = origin_contract.child_trie();
 		let key_values_taken = delta.iter()
 			.filter_map(|key| {
-				child::get_raw(
-					&origin_contract.trie_id,
-					origin_contract.child_trie_unique_id(),
-					&blake2_256(key),
-				).map(|value| {
-					child::kill(
-						&origin_contract.trie_id,
-						origin_contract.child_trie_unique_id(),
-						&blake2_256(key),
-					);
+				child_trie.get_raw(&blake2_256(key)).map(|value| {
+					child_trie.kill_raw(&blake2_256(key));
 
 					(key, value)
 				})
@@ -834,20 +851,13 @@ impl<T: Trait> Module<T> {
 		let tombstone = <TombstoneContractInfo<T>>::new(
 			// This operation is cheap enough because last_write (delta not included)
 			// is not this block as it has been checked earlier.
-			&child::child_root(
-				&origin_contract.trie_id,
-			)[..],
+			&child_trie.root()[..],
 			code_hash,
 		);
 
 		if tombstone != dest_tombstone {
 			for (key, value) in key_values_taken {
-				child::put_raw(
-					&origin_contract.trie_id,
-					origin_contract.child_trie_unique_id(),
-					&blake2_256(key),
-					&value,
-				);
+				child_trie.put_raw(&blake2_256(key), &value);
 			}
 
 			return Err(Error::<T>::InvalidTombstone.into());
@@ -938,6 +948,8 @@ decl_storage! {
 		pub ContractInfoOf: map hasher(twox_64_concat) T::AccountId => Option<ContractInfo<T>>;
 		/// The price of one unit of gas.
 		GasPrice get(fn gas_price) config(): BalanceOf<T> = 1.into();
+		/// The child tries of the removed contracts whose storage isn't entirely deleted yet.
+		DeletionQueue get(fn deletion_queue): Vec<child::ChildTrie>;
 	}
 }
 
@@ -948,7 +960,7 @@ decl_storage! {
 impl<T: Trait> OnKilledAccount<T::AccountId> for Module<T> {
 	fn on_killed_account(who: &T::AccountId) {
 		if let Some(ContractInfo::Alive(info)) = <ContractInfoOf<T>>::take(who) {
-			child::kill_storage(&info.trie_id, info.child_trie_unique_id());
+			<Module<T>>::remove_storage(info.child_trie());
 		}
 	}
 }
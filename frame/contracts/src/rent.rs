@@ -20,7 +20,6 @@ use crate::{
 	AliveContractInfo, BalanceOf, ContractInfo, ContractInfoOf, Module, RawEvent,
 	TombstoneContractInfo, Trait,
 };
-use frame_support::storage::child;
 use frame_support::traits::{Currency, ExistenceRequirement, Get, OnUnbalanced, WithdrawReason};
 use frame_support::StorageMap;
 use pallet_contracts_primitives::{ContractAccessError, RentProjection, RentProjectionResult};
@@ -222,10 +221,7 @@ fn enact_verdict<T: Trait>(
 		Verdict::Exempt => return Some(ContractInfo::Alive(alive_contract_info)),
 		Verdict::Kill => {
 			<ContractInfoOf<T>>::remove(account);
-			child::kill_storage(
-				&alive_contract_info.trie_id,
-				alive_contract_info.child_trie_unique_id(),
-			);
+			<Module<T>>::remove_storage(alive_contract_info.child_trie());
 			<Module<T>>::deposit_event(RawEvent::Evicted(account.clone(), false));
 			None
 		}
@@ -235,7 +231,8 @@ fn enact_verdict<T: Trait>(
 			}
 
 			// Note: this operation is heavy.
-			let child_storage_root = child::child_root(&alive_contract_info.trie_id);
+			let child_trie = alive_contract_info.child_trie();
+			let child_storage_root = child_trie.root();
 
 			let tombstone = <TombstoneContractInfo<T>>::new(
 				&child_storage_root[..],
@@ -244,10 +241,7 @@ fn enact_verdict<T: Trait>(
 			let tombstone_info = ContractInfo::Tombstone(tombstone);
 			<ContractInfoOf<T>>::insert(account, &tombstone_info);
 
-			child::kill_storage(
-				&alive_contract_info.trie_id,
-				alive_contract_info.child_trie_unique_id(),
-			);
+			<Module<T>>::remove_storage(child_trie);
 
 			<Module<T>>::deposit_event(RawEvent::Evicted(account.clone(), true));
 			Some(tombstone_info)
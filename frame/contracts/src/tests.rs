@@ -34,7 +34,7 @@ use sp_runtime::{
 };
 use frame_support::{
 	assert_ok, assert_err, impl_outer_dispatch, impl_outer_event, impl_outer_origin, parameter_types,
-	storage::child, StorageMap, StorageValue, traits::{Currency, Get},
+	storage::child, StorageMap, StorageValue, traits::{Currency, Get, OnInitialize},
 	weights::{DispatchInfo, DispatchClass, Weight},
 };
 use std::{cell::RefCell, sync::atomic::{AtomicUsize, Ordering}};
@@ -116,7 +116,7 @@ impl frame_system::Trait for Test {
 	type Version = ();
 	type ModuleToIndex = ();
 	type AccountData = pallet_balances::AccountData<u64>;
-	type MigrateAccount = (); type OnNewAccount = ();
+	type MigrateAccount = (); type MigrateAccountData = (); type OnNewAccount = ();
 	type OnKilledAccount = Contracts;
 }
 impl pallet_balances::Trait for Test {
@@ -148,6 +148,7 @@ parameter_types! {
 	pub const InstantiateBaseFee: u64 = 175;
 	pub const MaxDepth: u32 = 100;
 	pub const MaxValueSize: u32 = 16_384;
+	pub const RemoveKeysLimit: u32 = 8;
 }
 impl Trait for Test {
 	type Currency = Balances;
@@ -174,6 +175,7 @@ impl Trait for Test {
 	type MaxDepth = MaxDepth;
 	type MaxValueSize = MaxValueSize;
 	type BlockGasLimit = BlockGasLimit;
+	type RemoveKeysLimit = RemoveKeysLimit;
 }
 
 type Balances = pallet_balances::Module<Test>;
@@ -2736,3 +2738,24 @@ fn get_runtime_storage() {
 		));
 	});
 }
+
+#[test]
+fn storage_of_removed_contracts_is_deleted_across_blocks() {
+	ExtBuilder::default().build().execute_with(|| {
+		let child_trie = child::ChildTrie::new(b"removed_contract");
+		for i in 0..20u32 {
+			child_trie.put_raw(&i.encode(), &[1]);
+		}
+
+		// the first `RemoveKeysLimit` keys are deleted right away, the rest is queued.
+		Contracts::remove_storage(child_trie.clone());
+		assert_eq!(Contracts::deletion_queue(), vec![child_trie.clone()]);
+
+		Contracts::on_initialize(2);
+		assert_eq!(Contracts::deletion_queue(), vec![child_trie.clone()]);
+
+		Contracts::on_initialize(3);
+		assert!(Contracts::deletion_queue().is_empty());
+		assert_eq!(child_trie.get_raw(&19u32.encode()), None);
+	});
+}
@@ -0,0 +1,690 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Crowdfund Module
+//!
+//! - [`crowdfund::Trait`](./trait.Trait.html)
+//! - [`Call`](./enum.Call.html)
+//!
+//! ## Overview
+//!
+//! The Crowdfund module lets anyone raise funds for a beneficiary until a given block. The funds
+//! raised are held by an account of the fund, and the contribution of each account is kept in a
+//! child trie of the fund, so that the funds don't weigh on the main trie and are removed with
+//! bounded batches of storage operations.
+//!
+//! If the fund reaches its goal, the funds raised are dispensed to the beneficiary. Otherwise the
+//! contributors withdraw their contribution, or anyone refunds them in batches. After the
+//! retirement period, the fund can be dissolved: its contributions are removed in batches, the
+//! deposit returns to its creator and the funds which weren't withdrawn are unclaimed.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! * `create` - Create a fund, reserving a deposit.
+//! * `contribute` - Contribute to a fund until its end.
+//! * `withdraw` - Withdraw one's contribution to a fund which didn't reach its goal.
+//! * `refund` - Refund a batch of the contributors of a fund which didn't reach its goal.
+//! * `dispense` - Dispense the funds raised by a fund which reached its goal to its beneficiary.
+//! * `dissolve` - Remove a batch of the contributions of a retired fund, then the fund itself.
+//!
+//! [`Call`]: ./enum.Call.html
+//! [`Trait`]: ./trait.Trait.html
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::prelude::*;
+use codec::{Encode, Decode};
+use sp_runtime::{
+	ModuleId, RuntimeDebug,
+	traits::{AccountIdConversion, Saturating, Zero},
+};
+use frame_support::{
+	decl_module, decl_event, decl_storage, ensure, decl_error,
+	storage::child::{ChildTrie, ChildTriePrefix},
+	traits::{
+		Currency, ReservableCurrency, OnUnbalanced, Get, Imbalance, ExistenceRequirement::AllowDeath,
+	},
+	weights::{SimpleDispatchInfo, FunctionOf, DispatchClass, Weight},
+};
+use frame_system::{self as system, ensure_signed};
+
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
+type NegativeImbalanceOf<T> = <<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::NegativeImbalance;
+
+/// The module's id, used for deriving the accounts of the funds.
+const MODULE_ID: ModuleId = ModuleId(*b"py/cfund");
+
+/// The index of a fund.
+pub type FundIndex = u32;
+
+pub trait Trait: frame_system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// The currency trait.
+	type Currency: ReservableCurrency<Self::AccountId>;
+
+	/// The deposit reserved when creating a fund.
+	type SubmissionDeposit: Get<BalanceOf<Self>>;
+
+	/// The smallest contribution to a fund.
+	type MinContribution: Get<BalanceOf<Self>>;
+
+	/// The number of blocks after its end until a fund can be dissolved.
+	type RetirementPeriod: Get<Self::BlockNumber>;
+
+	/// The greatest number of contributions refunded or removed by a single call.
+	type RemoveKeysLimit: Get<u32>;
+
+	/// What to do with the funds which weren't withdrawn before a fund is dissolved.
+	type Unclaimed: OnUnbalanced<NegativeImbalanceOf<Self>>;
+}
+
+/// Information of a fund.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct FundInfo<AccountId, Balance, BlockNumber> {
+	/// The account which created the fund and reserved the deposit.
+	depositor: AccountId,
+	/// The deposit reserved by the depositor.
+	deposit: Balance,
+	/// The account receiving the funds raised if the goal is reached.
+	beneficiary: AccountId,
+	/// The smallest amount to raise for the funds to be dispensed.
+	goal: Balance,
+	/// The total amount raised.
+	raised: Balance,
+	/// The block after which no more contributions are accepted.
+	end: BlockNumber,
+	/// Whether the funds raised have been dispensed to the beneficiary.
+	dispensed: bool,
+}
+
+impl<AccountId, Balance, BlockNumber> FundInfo<AccountId, Balance, BlockNumber> {
+	/// The total amount raised.
+	pub fn raised(&self) -> &Balance {
+		&self.raised
+	}
+}
+
+/// The prefix of the child tries of the contributions to the funds.
+pub struct Contributions;
+impl ChildTriePrefix for Contributions {
+	const PREFIX: &'static [u8] = b"crowdfund";
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Crowdfund {
+		/// Information of the funds that aren't dissolved.
+		Funds get(fn funds):
+			map hasher(twox_64_concat) FundIndex
+			=> Option<FundInfo<T::AccountId, BalanceOf<T>, T::BlockNumber>>;
+		/// The number of funds created so far, which is the index of the next one.
+		FundCount get(fn fund_count): FundIndex;
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where AccountId = <T as frame_system::Trait>::AccountId, Balance = BalanceOf<T> {
+		/// A fund was created.
+		Created(FundIndex),
+		/// An account contributed the given amount to a fund.
+		Contributed(AccountId, FundIndex, Balance),
+		/// An account withdrew the given amount from a fund.
+		Withdrew(AccountId, FundIndex, Balance),
+		/// A batch of the contributors of a fund were refunded, and some remain.
+		PartiallyRefunded(FundIndex),
+		/// All the contributors of a fund were refunded.
+		AllRefunded(FundIndex),
+		/// The funds raised by a fund were dispensed to the beneficiary.
+		Dispensed(FundIndex, AccountId, Balance),
+		/// A batch of the contributions of a fund were removed, and some remain.
+		PartiallyDissolved(FundIndex),
+		/// A fund was dissolved, and the given funds were unclaimed.
+		Dissolved(FundIndex, Balance),
+	}
+);
+
+decl_error! {
+	/// Error for the crowdfund module.
+	pub enum Error for Module<T: Trait> {
+		/// The end of a fund must be in the future.
+		EndTooEarly,
+		/// There is no fund with the given index.
+		InvalidIndex,
+		/// The contribution is smaller than the minimum.
+		ContributionTooSmall,
+		/// The fund doesn't accept contributions anymore.
+		ContributionPeriodOver,
+		/// The fund still accepts contributions.
+		FundNotEnded,
+		/// The fund reached its goal, so the contributions can't be refunded.
+		GoalReached,
+		/// The fund didn't reach its goal, so the funds can't be dispensed.
+		GoalNotReached,
+		/// The funds have already been dispensed.
+		AlreadyDispensed,
+		/// The account didn't contribute to the fund.
+		NoContribution,
+		/// The retirement period of the fund isn't over.
+		FundNotRetired,
+		/// The fund reached its goal, so its funds must be dispensed before it's dissolved.
+		NotDispensed,
+		/// There are too many funds to index a new one.
+		Overflow,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		fn deposit_event() = default;
+
+		/// The deposit reserved when creating a fund.
+		const SubmissionDeposit: BalanceOf<T> = T::SubmissionDeposit::get();
+
+		/// The smallest contribution to a fund.
+		const MinContribution: BalanceOf<T> = T::MinContribution::get();
+
+		/// The number of blocks after its end until a fund can be dissolved.
+		const RetirementPeriod: T::BlockNumber = T::RetirementPeriod::get();
+
+		/// The greatest number of contributions refunded or removed by a single call.
+		const RemoveKeysLimit: u32 = T::RemoveKeysLimit::get();
+
+		/// Create a fund raising at least `goal` for `beneficiary` until the block `end`.
+		///
+		/// A deposit of `SubmissionDeposit` is reserved until the fund is dissolved.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - One balance operation.
+		/// - Two storage writes.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(100_000)]
+		fn create(origin, beneficiary: T::AccountId, goal: BalanceOf<T>, end: T::BlockNumber) {
+			let depositor = ensure_signed(origin)?;
+
+			ensure!(end > <frame_system::Module<T>>::block_number(), Error::<T>::EndTooEarly);
+
+			let index = FundCount::get();
+			let next_index = index.checked_add(1).ok_or(Error::<T>::Overflow)?;
+
+			let deposit = T::SubmissionDeposit::get();
+			T::Currency::reserve(&depositor, deposit)?;
+
+			FundCount::put(next_index);
+			<Funds<T>>::insert(index, FundInfo {
+				depositor,
+				deposit,
+				beneficiary,
+				goal,
+				raised: Zero::zero(),
+				end,
+				dispensed: false,
+			});
+
+			Self::deposit_event(RawEvent::Created(index));
+		}
+
+		/// Contribute `value` to the fund of the given index, until its end.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - One balance transfer.
+		/// - Two storage reads and writes, one of them in the child trie of the fund.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(100_000)]
+		fn contribute(origin, index: FundIndex, value: BalanceOf<T>) {
+			let who = ensure_signed(origin)?;
+
+			ensure!(value >= T::MinContribution::get(), Error::<T>::ContributionTooSmall);
+			let mut fund = Self::funds(index).ok_or(Error::<T>::InvalidIndex)?;
+			ensure!(
+				fund.end >= <frame_system::Module<T>>::block_number(),
+				Error::<T>::ContributionPeriodOver,
+			);
+
+			T::Currency::transfer(&who, &Self::fund_account_id(index), value, AllowDeath)?;
+
+			let contributions = Self::contributions(index);
+			let contribution: BalanceOf<T> = contributions.get_or_default(&who);
+			contributions.put(&who, &contribution.saturating_add(value));
+
+			fund.raised = fund.raised.saturating_add(value);
+			<Funds<T>>::insert(index, fund);
+
+			Self::deposit_event(RawEvent::Contributed(who, index, value));
+		}
+
+		/// Withdraw the contribution of the sender to the fund of the given index, which ended
+		/// without reaching its goal.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - One balance transfer.
+		/// - Two storage reads and writes, one of them in the child trie of the fund.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(100_000)]
+		fn withdraw(origin, index: FundIndex) {
+			let who = ensure_signed(origin)?;
+
+			let fund = Self::funds(index).ok_or(Error::<T>::InvalidIndex)?;
+			Self::ensure_refundable(&fund)?;
+
+			let value = Self::refund_contribution(index, &who)?.ok_or(Error::<T>::NoContribution)?;
+
+			Self::deposit_event(RawEvent::Withdrew(who, index, value));
+		}
+
+		/// Refund at most `RemoveKeysLimit` of the contributors of the fund of the given index,
+		/// which ended without reaching its goal.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// # <weight>
+		/// - O(K) where K is `RemoveKeysLimit`.
+		/// - K balance transfers.
+		/// - K storage reads and writes in the child trie of the fund, K storage writes of the fund.
+		/// - One event.
+		/// # </weight>
+		#[weight = FunctionOf(
+			|_: (&FundIndex,)| 100_000 + 100_000 * T::RemoveKeysLimit::get() as Weight,
+			DispatchClass::Normal,
+			true
+		)]
+		fn refund(origin, index: FundIndex) {
+			ensure_signed(origin)?;
+
+			let fund = Self::funds(index).ok_or(Error::<T>::InvalidIndex)?;
+			Self::ensure_refundable(&fund)?;
+
+			let contributions = Self::contributions(index);
+			for _ in 0..T::RemoveKeysLimit::get() {
+				// the encoded accounts are never empty, so the first one comes after the empty key.
+				let who = match contributions.next_key(&[])
+					.and_then(|key| T::AccountId::decode(&mut &key[..]).ok())
+				{
+					Some(who) => who,
+					None => break,
+				};
+				Self::refund_contribution(index, &who)?;
+			}
+
+			if contributions.next_key(&[]).is_none() {
+				Self::deposit_event(RawEvent::AllRefunded(index));
+			} else {
+				Self::deposit_event(RawEvent::PartiallyRefunded(index));
+			}
+		}
+
+		/// Dispense the funds raised by the fund of the given index, which ended after reaching
+		/// its goal, to its beneficiary.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - One balance transfer.
+		/// - One storage read and write.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(100_000)]
+		fn dispense(origin, index: FundIndex) {
+			ensure_signed(origin)?;
+
+			let mut fund = Self::funds(index).ok_or(Error::<T>::InvalidIndex)?;
+			ensure!(
+				fund.end < <frame_system::Module<T>>::block_number(),
+				Error::<T>::FundNotEnded,
+			);
+			ensure!(fund.raised >= fund.goal, Error::<T>::GoalNotReached);
+			ensure!(!fund.dispensed, Error::<T>::AlreadyDispensed);
+
+			let account = Self::fund_account_id(index);
+			let value = T::Currency::free_balance(&account);
+			T::Currency::transfer(&account, &fund.beneficiary, value, AllowDeath)?;
+
+			fund.dispensed = true;
+			let beneficiary = fund.beneficiary.clone();
+			<Funds<T>>::insert(index, fund);
+
+			Self::deposit_event(RawEvent::Dispensed(index, beneficiary, value));
+		}
+
+		/// Remove at most `RemoveKeysLimit` of the contributions to the fund of the given index,
+		/// after its retirement period. Once all of them are removed, the fund is removed, the
+		/// deposit is returned to its depositor and the funds which weren't withdrawn are
+		/// unclaimed.
+		///
+		/// A fund which reached its goal can only be dissolved once its funds are dispensed.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// # <weight>
+		/// - O(K) where K is `RemoveKeysLimit`.
+		/// - K storage reads and writes in the child trie of the fund.
+		/// - At most two balance operations.
+		/// - One storage read and write.
+		/// - One event.
+		/// # </weight>
+		#[weight = FunctionOf(
+			|_: (&FundIndex,)| 100_000 + 50_000 * T::RemoveKeysLimit::get() as Weight,
+			DispatchClass::Normal,
+			true
+		)]
+		fn dissolve(origin, index: FundIndex) {
+			ensure_signed(origin)?;
+
+			let fund = Self::funds(index).ok_or(Error::<T>::InvalidIndex)?;
+			ensure!(
+				fund.end.saturating_add(T::RetirementPeriod::get())
+					< <frame_system::Module<T>>::block_number(),
+				Error::<T>::FundNotRetired,
+			);
+			ensure!(fund.dispensed || fund.raised < fund.goal, Error::<T>::NotDispensed);
+
+			if !Self::contributions(index).kill_storage_limit(T::RemoveKeysLimit::get()) {
+				Self::deposit_event(RawEvent::PartiallyDissolved(index));
+				return Ok(())
+			}
+
+			let account = Self::fund_account_id(index);
+			let (unclaimed, _) = T::Currency::slash(&account, T::Currency::free_balance(&account));
+			let unclaimed_value = unclaimed.peek();
+			T::Unclaimed::on_unbalanced(unclaimed);
+
+			let _ = T::Currency::unreserve(&fund.depositor, fund.deposit);
+			<Funds<T>>::remove(index);
+
+			Self::deposit_event(RawEvent::Dissolved(index, unclaimed_value));
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The account holding the funds raised by the fund of the given index.
+	pub fn fund_account_id(index: FundIndex) -> T::AccountId {
+		MODULE_ID.into_sub_account(index)
+	}
+
+	/// The child trie of the contributions to the fund of the given index.
+	pub fn contributions(index: FundIndex) -> ChildTrie {
+		Contributions::child_trie(&index)
+	}
+
+	/// The contribution of an account to the fund of the given index.
+	pub fn contribution(index: FundIndex, who: &T::AccountId) -> BalanceOf<T> {
+		Self::contributions(index).get_or_default(who)
+	}
+
+	/// Ensure the contributions to the fund can be refunded.
+	fn ensure_refundable(
+		fund: &FundInfo<T::AccountId, BalanceOf<T>, T::BlockNumber>,
+	) -> Result<(), Error<T>> {
+		ensure!(fund.end < <frame_system::Module<T>>::block_number(), Error::<T>::FundNotEnded);
+		ensure!(fund.raised < fund.goal, Error::<T>::GoalReached);
+		Ok(())
+	}
+
+	/// Refund the contribution of an account to the fund of the given index, which is deducted
+	/// from the funds raised, returning its value if it contributed.
+	fn refund_contribution(
+		index: FundIndex,
+		who: &T::AccountId,
+	) -> Result<Option<BalanceOf<T>>, sp_runtime::DispatchError> {
+		let contributions = Self::contributions(index);
+		let value: BalanceOf<T> = match contributions.get(who) {
+			Some(value) => value,
+			None => return Ok(None),
+		};
+
+		T::Currency::transfer(&Self::fund_account_id(index), who, value, AllowDeath)?;
+		contributions.kill(who);
+		<Funds<T>>::mutate(index, |fund| if let Some(fund) = fund {
+			fund.raised = fund.raised.saturating_sub(value);
+		});
+
+		Ok(Some(value))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use frame_support::{
+		assert_ok, assert_noop, impl_outer_origin, parameter_types, weights::Weight, StorageValue,
+	};
+	use sp_core::H256;
+	use sp_runtime::{
+		Perbill, testing::Header, traits::{BlakeTwo256, IdentityLookup},
+	};
+
+	impl_outer_origin! {
+		pub enum Origin for Test  where system = frame_system {}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::one();
+	}
+	impl frame_system::Trait for Test {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Call = ();
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+		type ModuleToIndex = ();
+		type AccountData = pallet_balances::AccountData<u64>;
+		type MigrateAccount = (); type MigrateAccountData = (); type OnNewAccount = ();
+		type OnKilledAccount = ();
+	}
+	parameter_types! {
+		pub const ExistentialDeposit: u64 = 1;
+	}
+	impl pallet_balances::Trait for Test {
+		type Balance = u64;
+		type Event = ();
+		type DustRemoval = ();
+		type ExistentialDeposit = ExistentialDeposit;
+		type AccountStore = System;
+	}
+	parameter_types! {
+		pub const SubmissionDeposit: u64 = 1;
+		pub const MinContribution: u64 = 10;
+		pub const RetirementPeriod: u64 = 5;
+		pub const RemoveKeysLimit: u32 = 2;
+	}
+	impl Trait for Test {
+		type Event = ();
+		type Currency = Balances;
+		type SubmissionDeposit = SubmissionDeposit;
+		type MinContribution = MinContribution;
+		type RetirementPeriod = RetirementPeriod;
+		type RemoveKeysLimit = RemoveKeysLimit;
+		type Unclaimed = ();
+	}
+	type System = frame_system::Module<Test>;
+	type Balances = pallet_balances::Module<Test>;
+	type Crowdfund = Module<Test>;
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		pallet_balances::GenesisConfig::<Test> {
+			balances: vec![(1, 1000), (2, 1000), (3, 1000), (4, 1000)],
+		}.assimilate_storage(&mut t).unwrap();
+		t.into()
+	}
+
+	#[test]
+	fn contributions_are_kept_in_the_child_trie_of_the_fund() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			assert_noop!(Crowdfund::create(Origin::signed(1), 10, 100, 1), Error::<Test>::EndTooEarly);
+			assert_ok!(Crowdfund::create(Origin::signed(1), 10, 100, 5));
+			assert_eq!(Balances::reserved_balance(1), 1);
+			assert_eq!(Crowdfund::fund_count(), 1);
+
+			assert_noop!(
+				Crowdfund::contribute(Origin::signed(2), 0, 9),
+				Error::<Test>::ContributionTooSmall,
+			);
+			assert_noop!(Crowdfund::contribute(Origin::signed(2), 1, 10), Error::<Test>::InvalidIndex);
+			assert_ok!(Crowdfund::contribute(Origin::signed(2), 0, 20));
+			assert_ok!(Crowdfund::contribute(Origin::signed(2), 0, 30));
+			assert_ok!(Crowdfund::contribute(Origin::signed(3), 0, 40));
+
+			assert_eq!(Crowdfund::contribution(0, &2), 50);
+			assert_eq!(Crowdfund::contribution(0, &3), 40);
+			assert_eq!(Crowdfund::funds(0).unwrap().raised(), &90);
+			assert_eq!(Balances::free_balance(Crowdfund::fund_account_id(0)), 90);
+			assert_eq!(Balances::free_balance(2), 950);
+
+			System::set_block_number(6);
+			assert_noop!(
+				Crowdfund::contribute(Origin::signed(2), 0, 10),
+				Error::<Test>::ContributionPeriodOver,
+			);
+
+			FundCount::put(FundIndex::max_value());
+			assert_noop!(Crowdfund::create(Origin::signed(1), 10, 100, 10), Error::<Test>::Overflow);
+		});
+	}
+
+	#[test]
+	fn funds_are_dispensed_when_the_goal_is_reached() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			assert_ok!(Crowdfund::create(Origin::signed(1), 10, 100, 5));
+			assert_ok!(Crowdfund::contribute(Origin::signed(2), 0, 60));
+			assert_ok!(Crowdfund::contribute(Origin::signed(3), 0, 50));
+
+			assert_noop!(Crowdfund::dispense(Origin::signed(4), 0), Error::<Test>::FundNotEnded);
+			System::set_block_number(6);
+			assert_noop!(Crowdfund::withdraw(Origin::signed(2), 0), Error::<Test>::GoalReached);
+
+			assert_ok!(Crowdfund::dispense(Origin::signed(4), 0));
+			assert_eq!(Balances::free_balance(10), 110);
+			assert_noop!(Crowdfund::dispense(Origin::signed(4), 0), Error::<Test>::AlreadyDispensed);
+		});
+	}
+
+	#[test]
+	fn funds_reaching_their_goal_are_dissolved_once_dispensed() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			assert_ok!(Crowdfund::create(Origin::signed(1), 10, 100, 5));
+			assert_ok!(Crowdfund::contribute(Origin::signed(2), 0, 100));
+
+			System::set_block_number(11);
+			assert_noop!(Crowdfund::dissolve(Origin::signed(1), 0), Error::<Test>::NotDispensed);
+			assert_eq!(Balances::free_balance(Crowdfund::fund_account_id(0)), 100);
+
+			assert_ok!(Crowdfund::dispense(Origin::signed(4), 0));
+			assert_ok!(Crowdfund::dissolve(Origin::signed(1), 0));
+			assert!(Crowdfund::funds(0).is_none());
+			assert_eq!(Balances::free_balance(10), 100);
+		});
+	}
+
+	#[test]
+	fn contributions_are_refunded_when_the_goal_is_not_reached() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			assert_ok!(Crowdfund::create(Origin::signed(1), 10, 100, 5));
+			for who in 1..=4 {
+				assert_ok!(Crowdfund::contribute(Origin::signed(who), 0, 20));
+			}
+
+			System::set_block_number(6);
+			assert_noop!(Crowdfund::dispense(Origin::signed(4), 0), Error::<Test>::GoalNotReached);
+
+			assert_ok!(Crowdfund::withdraw(Origin::signed(1), 0));
+			assert_eq!(Balances::free_balance(1), 999);
+			assert_eq!(Crowdfund::funds(0).unwrap().raised(), &60);
+			assert_noop!(Crowdfund::withdraw(Origin::signed(1), 0), Error::<Test>::NoContribution);
+
+			// the remaining contributors are refunded in batches of `RemoveKeysLimit`.
+			assert_ok!(Crowdfund::refund(Origin::signed(1), 0));
+			assert_eq!((2..=4).filter(|who| Crowdfund::contribution(0, who) == 0).count(), 2);
+			assert_ok!(Crowdfund::refund(Origin::signed(1), 0));
+			for who in 2..=4 {
+				assert_eq!(Crowdfund::contribution(0, &who), 0);
+				assert_eq!(Balances::free_balance(who), 1000);
+			}
+			assert_eq!(Crowdfund::funds(0).unwrap().raised(), &0);
+			assert_eq!(Balances::free_balance(Crowdfund::fund_account_id(0)), 0);
+		});
+	}
+
+	#[test]
+	fn retired_funds_are_dissolved_in_batches() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			assert_ok!(Crowdfund::create(Origin::signed(1), 10, 100, 5));
+			for who in 1..=4 {
+				assert_ok!(Crowdfund::contribute(Origin::signed(who), 0, 20));
+			}
+
+			System::set_block_number(6);
+			assert_ok!(Crowdfund::withdraw(Origin::signed(2), 0));
+			assert_noop!(Crowdfund::dissolve(Origin::signed(1), 0), Error::<Test>::FundNotRetired);
+
+			System::set_block_number(11);
+			assert_ok!(Crowdfund::dissolve(Origin::signed(1), 0));
+			assert!(Crowdfund::funds(0).is_some());
+			assert!(Crowdfund::contributions(0).next_key(&[]).is_some());
+
+			assert_ok!(Crowdfund::dissolve(Origin::signed(1), 0));
+			assert!(Crowdfund::funds(0).is_none());
+			assert_eq!(Crowdfund::contributions(0).next_key(&[]), None);
+			assert_eq!(Crowdfund::contribution(0, &3), 0);
+
+			// the deposit is returned and the funds which weren't withdrawn are unclaimed.
+			assert_eq!(Balances::reserved_balance(1), 0);
+			assert_eq!(Balances::free_balance(1), 980);
+			assert_eq!(Balances::free_balance(Crowdfund::fund_account_id(0)), 0);
+			assert_eq!(Balances::free_balance(3), 980);
+			assert_noop!(Crowdfund::dissolve(Origin::signed(1), 0), Error::<Test>::InvalidIndex);
+		});
+	}
+}
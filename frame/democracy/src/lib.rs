@@ -1639,7 +1639,7 @@ mod tests {
 		type Version = ();
 		type ModuleToIndex = ();
 		type AccountData = pallet_balances::AccountData<u64>;
-		type MigrateAccount = (); type OnNewAccount = ();
+		type MigrateAccount = (); type MigrateAccountData = (); type OnNewAccount = ();
 		type OnKilledAccount = ();
 	}
 	parameter_types! {
@@ -0,0 +1,224 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Difficulty Module
+//!
+//! - [`difficulty::Trait`](./trait.Trait.html)
+//!
+//! ## Overview
+//!
+//! The Difficulty module retargets the Proof-of-Work difficulty on-chain, so that blocks are
+//! mined at the target block time on average.
+//!
+//! Every time the timestamp of a block is set, the difficulty of the next block is adjusted by
+//! the ratio of the target block time to the time elapsed since the previous block. To avoid
+//! abrupt changes, the elapsed time is first averaged with the target block time according to
+//! the damp factor, and then kept within the clamp factor of the target block time.
+//!
+//! The difficulty of the next block is exposed to the client through the `DifficultyApi`
+//! runtime API of `sp-consensus-pow`, which the runtime implements with
+//! [`Module::difficulty`](./struct.Module.html#method.difficulty).
+//!
+//! ## Usage
+//!
+//! The module must be set as the `OnTimestampSet` handler of the Timestamp module.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use frame_support::{decl_module, decl_storage, traits::Get};
+use sp_core::U256;
+use sp_runtime::traits::{Saturating, UniqueSaturatedInto};
+use sp_timestamp::OnTimestampSet;
+
+pub trait Trait: pallet_timestamp::Trait {
+	/// The block time the difficulty is adjusted towards.
+	type TargetBlockTime: Get<Self::Moment>;
+
+	/// The elapsed time used for the adjustment is the average of the actual elapsed time,
+	/// weighted 1, and the target block time, weighted `DampFactor - 1`.
+	type DampFactor: Get<u32>;
+
+	/// The elapsed time used for the adjustment is kept between `1 / ClampFactor` and
+	/// `ClampFactor` times the target block time.
+	type ClampFactor: Get<u32>;
+
+	/// The difficulty is never adjusted below this.
+	type MinDifficulty: Get<U256>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Difficulty {
+		/// The difficulty of the next block.
+		pub Difficulty get(fn difficulty) config(): U256;
+
+		/// The timestamp of the last block.
+		LastTimestamp get(fn last_timestamp): Option<T::Moment>;
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		/// The block time the difficulty is adjusted towards.
+		const TargetBlockTime: T::Moment = T::TargetBlockTime::get();
+
+		/// The damp factor of the adjustment.
+		const DampFactor: u32 = T::DampFactor::get();
+
+		/// The clamp factor of the adjustment.
+		const ClampFactor: u32 = T::ClampFactor::get();
+
+		/// The minimum difficulty.
+		const MinDifficulty: U256 = T::MinDifficulty::get();
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Adjust the difficulty given the time elapsed since the last block.
+	fn adjust(difficulty: U256, elapsed: u128) -> U256 {
+		let target: u128 = T::TargetBlockTime::get().unique_saturated_into();
+		let damp = u128::from(T::DampFactor::get().max(1));
+		let clamp = u128::from(T::ClampFactor::get().max(1));
+
+		let damped = elapsed.saturating_add((damp - 1).saturating_mul(target)) / damp;
+		let clamped = damped
+			.max(target / clamp)
+			.min(target.saturating_mul(clamp))
+			.max(1);
+
+		let adjusted = difficulty.saturating_mul(U256::from(target)) / U256::from(clamped);
+		adjusted.max(T::MinDifficulty::get())
+	}
+}
+
+impl<T: Trait> OnTimestampSet<T::Moment> for Module<T> {
+	fn on_timestamp_set(moment: T::Moment) {
+		// the difficulty of the first block after genesis is the one from the genesis config.
+		if let Some(last_timestamp) = <LastTimestamp<T>>::get() {
+			let elapsed = moment.saturating_sub(last_timestamp).unique_saturated_into();
+			Difficulty::mutate(|difficulty| *difficulty = Self::adjust(*difficulty, elapsed));
+		}
+
+		<LastTimestamp<T>>::put(moment);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use frame_support::{impl_outer_origin, parameter_types, weights::Weight};
+	use sp_io::TestExternalities;
+	use sp_core::H256;
+	use sp_runtime::{Perbill, traits::{BlakeTwo256, IdentityLookup}, testing::Header};
+
+	impl_outer_origin! {
+		pub enum Origin for Test  where system = frame_system {}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::one();
+	}
+	impl frame_system::Trait for Test {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Call = ();
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type MaximumBlockLength = MaximumBlockLength;
+		type Version = ();
+		type ModuleToIndex = ();
+		type AccountData = ();
+		type MigrateAccount = (); type MigrateAccountData = (); type OnNewAccount = ();
+		type OnKilledAccount = ();
+	}
+	parameter_types! {
+		pub const MinimumPeriod: u64 = 1;
+	}
+	impl pallet_timestamp::Trait for Test {
+		type Moment = u64;
+		type OnTimestampSet = DifficultyModule;
+		type MinimumPeriod = MinimumPeriod;
+	}
+	parameter_types! {
+		pub const TargetBlockTime: u64 = 10;
+		pub const DampFactor: u32 = 2;
+		pub const ClampFactor: u32 = 4;
+		pub const MinDifficulty: U256 = U256([100, 0, 0, 0]);
+	}
+	impl Trait for Test {
+		type TargetBlockTime = TargetBlockTime;
+		type DampFactor = DampFactor;
+		type ClampFactor = ClampFactor;
+		type MinDifficulty = MinDifficulty;
+	}
+	type DifficultyModule = Module<Test>;
+
+	fn new_test_ext(difficulty: u64) -> TestExternalities {
+		let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		GenesisConfig {
+			difficulty: U256::from(difficulty),
+		}.assimilate_storage::<Test>(&mut t).unwrap();
+		t.into()
+	}
+
+	#[test]
+	fn difficulty_follows_the_block_time() {
+		new_test_ext(1000).execute_with(|| {
+			DifficultyModule::on_timestamp_set(100);
+			assert_eq!(DifficultyModule::difficulty(), U256::from(1000));
+
+			// on target.
+			DifficultyModule::on_timestamp_set(110);
+			assert_eq!(DifficultyModule::difficulty(), U256::from(1000));
+
+			// too slow: damped to 15, so the difficulty drops by a third.
+			DifficultyModule::on_timestamp_set(130);
+			assert_eq!(DifficultyModule::difficulty(), U256::from(666));
+
+			// too fast: damped to 6.
+			DifficultyModule::on_timestamp_set(132);
+			assert_eq!(DifficultyModule::difficulty(), U256::from(1110));
+		});
+	}
+
+	#[test]
+	fn adjustment_is_clamped_and_bounded() {
+		new_test_ext(1000).execute_with(|| {
+			DifficultyModule::on_timestamp_set(100);
+
+			// damped to 505, clamped to 40.
+			DifficultyModule::on_timestamp_set(1100);
+			assert_eq!(DifficultyModule::difficulty(), U256::from(250));
+
+			// never below the minimum difficulty.
+			DifficultyModule::on_timestamp_set(2100);
+			assert_eq!(DifficultyModule::difficulty(), U256::from(100));
+		});
+	}
+}
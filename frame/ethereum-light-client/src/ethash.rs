@@ -0,0 +1,337 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Verification of the Ethash proof of work.
+//!
+//! The dataset an Ethash proof of work is computed with is several gigabytes large, so it can't
+//! be generated on-chain. Instead, each epoch is committed to by the root of a binary Merkle tree
+//! over the 128 bytes pages of its dataset, supplied by governance. The leaves of the tree are the
+//! Keccak-256 hashes of the pages, padded with zero hashes up to a power of two, and each node is
+//! the Keccak-256 hash of the concatenation of its children.
+//!
+//! The submitter of a header then provides the 64 pages the proof of work accesses, in order,
+//! along with the Merkle proof of each of them, and the mix digest is computed from these pages.
+
+use sp_std::prelude::*;
+use codec::{Encode, Decode};
+use sp_core::{H256, H512, U256, U512};
+use sp_runtime::RuntimeDebug;
+#[cfg(feature = "std")]
+use serde::{Serialize, Deserialize};
+use sha3::{Digest, Keccak512};
+use crate::header::keccak_256;
+
+/// The number of blocks of an epoch, after which the dataset changes.
+pub const EPOCH_LENGTH: u64 = 30_000;
+
+/// The number of dataset pages accessed by a proof of work.
+pub const ACCESSES: usize = 64;
+
+/// The size of a page of the dataset, in bytes.
+pub const PAGE_SIZE: u64 = 128;
+
+/// The size of a page of the dataset, in 32 bits words.
+const PAGE_WORDS: usize = 32;
+
+const FNV_PRIME: u32 = 0x0100_0193;
+
+/// The commitment to the dataset of an epoch.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Default, PartialEq, Eq, Encode, Decode, RuntimeDebug)]
+pub struct DatasetCommitment {
+	/// The root of the Merkle tree of the pages of the dataset.
+	pub root: H256,
+	/// The size of the dataset, in bytes.
+	pub full_size: u64,
+}
+
+impl DatasetCommitment {
+	/// The number of pages of the dataset.
+	pub fn page_count(&self) -> u64 {
+		self.full_size / PAGE_SIZE
+	}
+
+	/// The depth of the Merkle tree of the pages.
+	pub fn depth(&self) -> usize {
+		match self.page_count() {
+			0 | 1 => 0,
+			count => (64 - (count - 1).leading_zeros()) as usize,
+		}
+	}
+}
+
+/// A page of the dataset accessed by a proof of work, along with the proof of its inclusion in
+/// the Merkle tree of the dataset.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug)]
+pub struct DatasetPage {
+	/// The two 64 bytes items of the page.
+	pub items: [H512; 2],
+	/// The hashes of the siblings of the path from the page to the root, from the leaf up.
+	pub proof: Vec<H256>,
+}
+
+impl DatasetPage {
+	/// The leaf of the page in the Merkle tree of the dataset.
+	pub fn leaf(&self) -> H256 {
+		let mut page = [0u8; 128];
+		page[..64].copy_from_slice(self.items[0].as_bytes());
+		page[64..].copy_from_slice(self.items[1].as_bytes());
+		keccak_256(&page)
+	}
+
+	/// Check that the page is the one at `index` in the dataset of the commitment.
+	pub fn verify(&self, commitment: &DatasetCommitment, index: u64) -> bool {
+		if self.proof.len() != commitment.depth() {
+			return false;
+		}
+
+		let mut node = self.leaf();
+		let mut index = index;
+		for sibling in &self.proof {
+			node = if index & 1 == 0 {
+				hash_pair(&node, sibling)
+			} else {
+				hash_pair(sibling, &node)
+			};
+			index >>= 1;
+		}
+
+		node == commitment.root
+	}
+
+	fn words(&self) -> [u32; PAGE_WORDS] {
+		let mut words = [0u32; PAGE_WORDS];
+		for (i, word) in words.iter_mut().enumerate() {
+			let item = self.items[i / 16].as_bytes();
+			*word = le_word(&item[(i % 16) * 4..]);
+		}
+		words
+	}
+}
+
+/// The reasons a proof of work can be rejected for.
+#[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum Error {
+	/// The dataset of the commitment has no page.
+	EmptyDataset,
+	/// The number of the accessed pages isn't `ACCESSES`.
+	WrongPageCount,
+	/// An accessed page isn't the one at its index in the dataset.
+	InvalidPage,
+	/// The mix digest of the header isn't the one computed from the pages.
+	MixHashMismatch,
+	/// The proof of work doesn't meet the difficulty.
+	InsufficientWork,
+}
+
+/// Check the proof of work of a header from the dataset pages it accesses.
+pub fn verify_seal(
+	seal_hash: H256,
+	nonce: [u8; 8],
+	mix_hash: H256,
+	difficulty: U256,
+	commitment: &DatasetCommitment,
+	pages: &[DatasetPage],
+) -> Result<(), Error> {
+	if pages.len() != ACCESSES {
+		return Err(Error::WrongPageCount);
+	}
+
+	let (mix, result) = hashimoto(seal_hash, nonce, commitment.page_count(), |access, index| {
+		let page = &pages[access];
+		if page.verify(commitment, index) {
+			Ok(page.words())
+		} else {
+			Err(Error::InvalidPage)
+		}
+	})?;
+
+	if mix != mix_hash {
+		return Err(Error::MixHashMismatch);
+	}
+
+	// the result must not exceed `2^256 / difficulty`.
+	if U256::from_big_endian(result.as_bytes()).full_mul(difficulty) > U512::one() << 256usize {
+		return Err(Error::InsufficientWork);
+	}
+
+	Ok(())
+}
+
+/// Compute the mix digest and the result of a proof of work, looking up the page at an index of
+/// the dataset for each access.
+pub fn hashimoto<F>(
+	seal_hash: H256,
+	nonce: [u8; 8],
+	page_count: u64,
+	mut lookup: F,
+) -> Result<(H256, H256), Error> where
+	F: FnMut(usize, u64) -> Result<[u32; PAGE_WORDS], Error>,
+{
+	if page_count == 0 {
+		return Err(Error::EmptyDataset);
+	}
+
+	// the nonce is hashed in little endian.
+	let mut nonce = nonce;
+	nonce.reverse();
+	let mut hasher = Keccak512::new();
+	hasher.input(seal_hash.as_bytes());
+	hasher.input(&nonce);
+	let seed = hasher.result();
+
+	let mut mix = [0u32; PAGE_WORDS];
+	for (i, word) in mix.iter_mut().enumerate() {
+		*word = le_word(&seed[(i % 16) * 4..]);
+	}
+
+	for access in 0..ACCESSES {
+		let index = fnv(access as u32 ^ le_word(&seed), mix[access % PAGE_WORDS]) as u64
+			% page_count;
+		let page = lookup(access, index)?;
+		for (word, data) in mix.iter_mut().zip(page.iter()) {
+			*word = fnv(*word, *data);
+		}
+	}
+
+	let mut digest = [0u8; 32];
+	for (i, words) in mix.chunks(4).enumerate() {
+		let word = fnv(fnv(fnv(words[0], words[1]), words[2]), words[3]);
+		digest[i * 4..(i + 1) * 4].copy_from_slice(&word.to_le_bytes());
+	}
+
+	let mut result = [0u8; 96];
+	result[..64].copy_from_slice(&seed);
+	result[64..].copy_from_slice(&digest);
+
+	Ok((H256(digest), keccak_256(&result)))
+}
+
+fn fnv(a: u32, b: u32) -> u32 {
+	a.wrapping_mul(FNV_PRIME) ^ b
+}
+
+fn le_word(bytes: &[u8]) -> u32 {
+	u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+fn hash_pair(left: &H256, right: &H256) -> H256 {
+	let mut pair = [0u8; 64];
+	pair[..32].copy_from_slice(left.as_bytes());
+	pair[32..].copy_from_slice(right.as_bytes());
+	keccak_256(&pair)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// A dataset small enough to be held in memory, along with its Merkle tree.
+	struct TestDataset {
+		pages: Vec<[H512; 2]>,
+		levels: Vec<Vec<H256>>,
+	}
+
+	impl TestDataset {
+		fn new(page_count: usize) -> Self {
+			let pages = (0..page_count)
+				.map(|i| [H512::repeat_byte(i as u8), H512::repeat_byte(!(i as u8))])
+				.collect::<Vec<_>>();
+
+			let mut leaves = pages.iter()
+				.map(|items| DatasetPage { items: *items, proof: Vec::new() }.leaf())
+				.collect::<Vec<_>>();
+			leaves.resize(page_count.next_power_of_two(), H256::zero());
+
+			let mut levels = vec![leaves];
+			while levels.last().unwrap().len() > 1 {
+				let level = levels.last().unwrap()
+					.chunks(2)
+					.map(|pair| hash_pair(&pair[0], &pair[1]))
+					.collect();
+				levels.push(level);
+			}
+
+			TestDataset { pages, levels }
+		}
+
+		fn commitment(&self) -> DatasetCommitment {
+			DatasetCommitment {
+				root: self.levels.last().unwrap()[0],
+				full_size: self.pages.len() as u64 * PAGE_SIZE,
+			}
+		}
+
+		fn page(&self, index: u64) -> DatasetPage {
+			let proof = self.levels[..self.levels.len() - 1].iter()
+				.enumerate()
+				.map(|(depth, level)| level[((index >> depth) ^ 1) as usize])
+				.collect();
+			DatasetPage { items: self.pages[index as usize], proof }
+		}
+
+		/// Compute the mix digest of a proof of work, along with the pages it accesses.
+		fn seal(&self, seal_hash: H256, nonce: [u8; 8]) -> (H256, Vec<DatasetPage>) {
+			let mut pages = Vec::new();
+			let (mix, _) = hashimoto(seal_hash, nonce, self.pages.len() as u64, |_, index| {
+				let page = self.page(index);
+				let words = page.words();
+				pages.push(page);
+				Ok(words)
+			}).unwrap();
+			(mix, pages)
+		}
+	}
+
+	#[test]
+	fn seals_are_verified_against_the_dataset() {
+		let dataset = TestDataset::new(5);
+		let commitment = dataset.commitment();
+		let seal_hash = H256::repeat_byte(7);
+		let nonce = [1, 2, 3, 4, 5, 6, 7, 8];
+		let (mix, pages) = dataset.seal(seal_hash, nonce);
+
+		assert_eq!(commitment.depth(), 3);
+		assert_eq!(verify_seal(seal_hash, nonce, mix, 1.into(), &commitment, &pages), Ok(()));
+
+		assert_eq!(
+			verify_seal(seal_hash, nonce, H256::zero(), 1.into(), &commitment, &pages),
+			Err(Error::MixHashMismatch),
+		);
+		assert_eq!(
+			verify_seal(seal_hash, nonce, mix, U256::max_value(), &commitment, &pages),
+			Err(Error::InsufficientWork),
+		);
+		assert_eq!(
+			verify_seal(seal_hash, nonce, mix, 1.into(), &commitment, &pages[1..]),
+			Err(Error::WrongPageCount),
+		);
+
+		// the pages must be exactly the accessed ones.
+		let mut tampered = pages.clone();
+		tampered[3].items[0] = H512::repeat_byte(42);
+		assert_eq!(
+			verify_seal(seal_hash, nonce, mix, 1.into(), &commitment, &tampered),
+			Err(Error::InvalidPage),
+		);
+
+		let other_nonce = [8, 7, 6, 5, 4, 3, 2, 1];
+		assert_ne!(
+			verify_seal(seal_hash, other_nonce, mix, 1.into(), &commitment, &pages),
+			Ok(()),
+		);
+	}
+}
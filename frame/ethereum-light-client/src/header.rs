@@ -0,0 +1,198 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The Ethereum headers and the rules they follow.
+
+use sp_std::prelude::*;
+use codec::{Encode, Decode};
+use sp_core::{H160, H256, U256};
+use sp_runtime::RuntimeDebug;
+use rlp::RlpStream;
+use sha3::{Digest, Keccak256};
+
+/// The size of the bloom filter of the logs of a block.
+pub const LOGS_BLOOM_SIZE: usize = 256;
+
+/// The maximum size of the extra data of a header.
+pub const MAX_EXTRA_DATA_SIZE: usize = 32;
+
+/// The lowest difficulty of a header.
+pub const MINIMUM_DIFFICULTY: u64 = 131_072;
+
+/// The lowest gas limit of a block.
+pub const MINIMUM_GAS_LIMIT: u64 = 5_000;
+
+/// An Ethereum header.
+#[derive(Clone, Default, PartialEq, Eq, Encode, Decode, RuntimeDebug)]
+pub struct EthereumHeader {
+	/// The hash of the parent header.
+	pub parent_hash: H256,
+	/// The hash of the list of the ommers of the block.
+	pub ommers_hash: H256,
+	/// The address the rewards of the block go to.
+	pub beneficiary: H160,
+	/// The root of the state trie after the block.
+	pub state_root: H256,
+	/// The root of the trie of the transactions of the block.
+	pub transactions_root: H256,
+	/// The root of the trie of the receipts of the transactions of the block.
+	pub receipts_root: H256,
+	/// The bloom filter of the logs of the block.
+	pub logs_bloom: Vec<u8>,
+	/// The difficulty of the block.
+	pub difficulty: U256,
+	/// The number of the block.
+	pub number: u64,
+	/// The gas limit of the block.
+	pub gas_limit: U256,
+	/// The gas used by the transactions of the block.
+	pub gas_used: U256,
+	/// The time the block was sealed at, in seconds.
+	pub timestamp: u64,
+	/// The arbitrary data of the block.
+	pub extra_data: Vec<u8>,
+	/// The mix digest of the proof of work.
+	pub mix_hash: H256,
+	/// The nonce of the proof of work.
+	pub nonce: [u8; 8],
+}
+
+impl EthereumHeader {
+	/// The hash of the header, identifying the block.
+	pub fn hash(&self) -> H256 {
+		keccak_256(&self.rlp(true))
+	}
+
+	/// The hash of the header without its seal, which the proof of work is computed for.
+	pub fn seal_hash(&self) -> H256 {
+		keccak_256(&self.rlp(false))
+	}
+
+	/// The epoch of the Ethash dataset the proof of work of the header is computed with.
+	pub fn epoch(&self) -> u64 {
+		self.number / crate::ethash::EPOCH_LENGTH
+	}
+
+	fn rlp(&self, with_seal: bool) -> Vec<u8> {
+		let mut s = RlpStream::new_list(if with_seal { 15 } else { 13 });
+		s.append(&self.parent_hash);
+		s.append(&self.ommers_hash);
+		s.append(&self.beneficiary);
+		s.append(&self.state_root);
+		s.append(&self.transactions_root);
+		s.append(&self.receipts_root);
+		s.append(&self.logs_bloom);
+		s.append(&self.difficulty);
+		s.append(&self.number);
+		s.append(&self.gas_limit);
+		s.append(&self.gas_used);
+		s.append(&self.timestamp);
+		s.append(&self.extra_data);
+		if with_seal {
+			s.append(&self.mix_hash);
+			s.append(&self.nonce.to_vec());
+		}
+		s.out()
+	}
+}
+
+/// The hash of an empty list of ommers.
+pub fn empty_ommers_hash() -> H256 {
+	keccak_256(&rlp::EMPTY_LIST_RLP)
+}
+
+/// The difficulty of a child of `parent` sealed at `timestamp`, with the difficulty bomb delayed
+/// by `bomb_delay` blocks.
+///
+/// This is the difficulty adjustment in use since Byzantium (EIP-100), the later forks only
+/// delaying the bomb.
+pub fn calculate_difficulty(parent: &EthereumHeader, timestamp: u64, bomb_delay: u64) -> U256 {
+	let parent_has_ommers = parent.ommers_hash != empty_ommers_hash();
+	let step = parent.difficulty / 2048;
+	let elapsed = timestamp.saturating_sub(parent.timestamp) / 9;
+
+	let increase: u64 = if parent_has_ommers { 2 } else { 1 };
+	let mut difficulty = if elapsed <= increase {
+		parent.difficulty.saturating_add(step * (increase - elapsed))
+	} else {
+		parent.difficulty.saturating_sub(step * (elapsed - increase).min(99))
+	};
+	difficulty = difficulty.max(MINIMUM_DIFFICULTY.into());
+
+	let period = (parent.number + 1).saturating_sub(bomb_delay) / 100_000;
+	if period > 1 {
+		difficulty = difficulty.saturating_add(U256::one() << (period - 2) as usize);
+	}
+
+	difficulty
+}
+
+pub(crate) fn keccak_256(data: &[u8]) -> H256 {
+	H256::from_slice(Keccak256::digest(data).as_slice())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn parent() -> EthereumHeader {
+		EthereumHeader {
+			ommers_hash: empty_ommers_hash(),
+			difficulty: 2_048_000_000.into(),
+			number: 9_000_000,
+			timestamp: 1_000,
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn empty_ommers_hash_is_correct() {
+		assert_eq!(
+			empty_ommers_hash(),
+			"1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347".parse().unwrap(),
+		);
+	}
+
+	#[test]
+	fn difficulty_follows_the_block_time() {
+		let parent = parent();
+		let step = parent.difficulty / 2048;
+
+		// fast blocks increase the difficulty, slow blocks decrease it.
+		assert_eq!(calculate_difficulty(&parent, 1_005, 9_000_000), parent.difficulty + step);
+		assert_eq!(calculate_difficulty(&parent, 1_010, 9_000_000), parent.difficulty);
+		assert_eq!(calculate_difficulty(&parent, 1_030, 9_000_000), parent.difficulty - step * 2);
+		assert_eq!(
+			calculate_difficulty(&parent, 100_000, 9_000_000),
+			parent.difficulty - step * 99,
+		);
+
+		// ommers make the blocks harder.
+		let with_ommers = EthereumHeader { ommers_hash: H256::repeat_byte(1), ..parent.clone() };
+		assert_eq!(calculate_difficulty(&with_ommers, 1_010, 9_000_000), parent.difficulty + step);
+	}
+
+	#[test]
+	fn difficulty_bomb_is_delayed() {
+		let parent = EthereumHeader { difficulty: MINIMUM_DIFFICULTY.into(), ..parent() };
+
+		assert_eq!(calculate_difficulty(&parent, 1_010, 9_000_000), MINIMUM_DIFFICULTY.into());
+		assert_eq!(
+			calculate_difficulty(&parent, 1_010, 5_000_000),
+			U256::from(MINIMUM_DIFFICULTY) + (U256::one() << 38),
+		);
+	}
+}
@@ -0,0 +1,583 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Ethereum Light Client Module
+//!
+//! - [`ethereum_light_client::Trait`](./trait.Trait.html)
+//! - [`Call`](./enum.Call.html)
+//!
+//! ## Overview
+//!
+//! The Ethereum light client module follows the proof of work chain of Ethereum. Headers are
+//! imported on top of a trusted header the module is initialized with, once their difficulty and
+//! their Ethash proof of work are checked, and the best chain is the one with the highest total
+//! difficulty.
+//!
+//! The Ethash datasets can't be generated on-chain, so the datasets of the epochs are committed
+//! to by governance, and the submitters of the headers prove the dataset pages their proof of
+//! work accesses against these commitments. See the [`ethash`](./ethash/index.html) module.
+//!
+//! The other modules can act upon the events of Ethereum through the
+//! [`VerifyReceipt`](./trait.VerifyReceipt.html) trait, which proves the inclusion of a receipt in
+//! a block of the best chain with enough confirmations.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! * `initialize` - Set the trusted header the chain is followed from.
+//! * `import_header` - Import a header along with the dataset pages of its proof of work.
+//! * `set_dataset_commitments` - Set the commitments to the datasets of some epochs.
+//!
+//! [`Call`]: ./enum.Call.html
+//! [`Trait`]: ./trait.Trait.html
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub mod ethash;
+pub mod header;
+pub mod receipt;
+
+use sp_std::prelude::*;
+use codec::{Encode, Decode};
+use sp_core::{H256, U256};
+use sp_runtime::{RuntimeDebug, DispatchError, DispatchResult, traits::EnsureOrigin};
+use frame_support::{
+	decl_module, decl_event, decl_storage, decl_error, ensure,
+	traits::Get, weights::SimpleDispatchInfo,
+};
+use frame_system::{self as system, ensure_signed, ensure_root};
+
+pub use ethash::{DatasetCommitment, DatasetPage};
+pub use header::EthereumHeader;
+pub use receipt::{Receipt, ReceiptProof, Log, TransactionOutcome};
+
+pub trait Trait: frame_system::Trait {
+	/// The overarching event type.
+	type Event: From<Event> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// The origin which may set the commitments to the datasets. Root can always do this.
+	type DatasetOrigin: EnsureOrigin<Self::Origin>;
+
+	/// The number of blocks the difficulty bomb is delayed by.
+	type DifficultyBombDelay: Get<u64>;
+
+	/// The number of descendants a block needs on the best chain for its receipts to be accepted.
+	type ConfirmationsRequired: Get<u64>;
+
+	/// The number of blocks of headers kept below the best header.
+	type HeadersToKeep: Get<u64>;
+}
+
+/// An imported header, along with the total difficulty of the chain it ends.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug)]
+pub struct StoredHeader {
+	/// The header.
+	pub header: EthereumHeader,
+	/// The sum of the difficulties of the header and its ancestors.
+	pub total_difficulty: U256,
+}
+
+/// A verifier of the receipts of the Ethereum blocks, for the modules acting upon the events of
+/// Ethereum.
+pub trait VerifyReceipt {
+	/// Check that the receipt proven by `proof` is included in the given block, and that the
+	/// block is on the best chain with enough confirmations, then return the receipt.
+	fn verify_receipt_inclusion(
+		block_hash: H256,
+		proof: &ReceiptProof,
+	) -> Result<Receipt, DispatchError>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as EthereumLightClient {
+		/// The imported headers, along with the total difficulty of the chains they end.
+		pub Headers get(fn header): map hasher(blake2_128_concat) H256 => Option<StoredHeader>;
+
+		/// The hashes of the imported headers, by number.
+		HeadersByNumber: map hasher(twox_64_concat) u64 => Vec<H256>;
+
+		/// The hashes of the headers of the best chain, by number.
+		pub CanonicalHashes get(fn canonical_hash): map hasher(twox_64_concat) u64 => Option<H256>;
+
+		/// The hash of the best header.
+		pub BestHeader get(fn best_header_hash): Option<H256>;
+
+		/// The number of the oldest headers kept.
+		OldestHeader: u64;
+
+		/// The commitments to the datasets of the epochs.
+		pub DatasetCommitments get(fn dataset_commitment) config():
+			map hasher(twox_64_concat) u64 => Option<DatasetCommitment>;
+	}
+}
+
+decl_event!(
+	pub enum Event {
+		/// The chain is followed from the given header.
+		Initialized(H256, u64),
+		/// The given header was imported.
+		HeaderImported(H256, u64),
+		/// The given header is the new best header.
+		NewBestHeader(H256, u64),
+		/// The commitment to the dataset of the given epoch was set.
+		DatasetCommitmentSet(u64),
+	}
+);
+
+decl_error! {
+	/// Error for the Ethereum light client module.
+	pub enum Error for Module<T: Trait> {
+		/// The module is already initialized.
+		AlreadyInitialized,
+		/// The header is already imported.
+		DuplicateHeader,
+		/// The parent of the header isn't imported.
+		UnknownParent,
+		/// The logs bloom or the extra data of the header are malformed.
+		MalformedHeader,
+		/// The number of the header doesn't follow the one of its parent.
+		InvalidNumber,
+		/// The header isn't sealed after its parent.
+		InvalidTimestamp,
+		/// The gas limit of the header changes too much, or is exceeded.
+		InvalidGasLimit,
+		/// The difficulty of the header doesn't follow the one of its parent.
+		InvalidDifficulty,
+		/// The dataset of the epoch of the header isn't committed to.
+		UnknownDataset,
+		/// The proof of work of the header is invalid.
+		InvalidSeal,
+		/// The block isn't imported.
+		UnknownBlock,
+		/// The block isn't on the best chain.
+		NotCanonical,
+		/// The block doesn't have enough confirmations yet.
+		NotConfirmed,
+		/// The receipt isn't proven to be included in the block.
+		InvalidReceiptProof,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		fn deposit_event() = default;
+
+		/// The number of blocks the difficulty bomb is delayed by.
+		const DifficultyBombDelay: u64 = T::DifficultyBombDelay::get();
+
+		/// The number of descendants a block needs on the best chain for its receipts to be
+		/// accepted.
+		const ConfirmationsRequired: u64 = T::ConfirmationsRequired::get();
+
+		/// The number of blocks of headers kept below the best header.
+		const HeadersToKeep: u64 = T::HeadersToKeep::get();
+
+		/// Start following the chain from the given header, which is trusted, along with the
+		/// total difficulty of the chain it ends.
+		///
+		/// The dispatch origin for this call must be _Root_.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - Five storage writes.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedOperational(50_000)]
+		fn initialize(origin, header: EthereumHeader, total_difficulty: U256) {
+			ensure_root(origin)?;
+			ensure!(Self::best_header_hash().is_none(), Error::<T>::AlreadyInitialized);
+
+			let (hash, number) = (header.hash(), header.number);
+			Headers::insert(hash, StoredHeader { header, total_difficulty });
+			HeadersByNumber::insert(number, vec![hash]);
+			CanonicalHashes::insert(number, hash);
+			BestHeader::put(hash);
+			OldestHeader::put(number);
+
+			Self::deposit_event(Event::Initialized(hash, number));
+		}
+
+		/// Import a header on top of an imported one, along with the dataset pages its proof of
+		/// work accesses, in order.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// # <weight>
+		/// - O(P) where P is the depth of the Merkle tree of the dataset, for the verification of
+		///   the proofs of each of the 64 pages.
+		/// - Up to three storage reads and three storage writes to import the header.
+		/// - O(R + D) storage writes, where R is the depth of a reorganization of the best chain
+		///   and D the number of pruned headers.
+		/// - Up to two events.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(1_000_000)]
+		fn import_header(origin, header: EthereumHeader, pages: Vec<DatasetPage>) {
+			ensure_signed(origin)?;
+
+			let hash = header.hash();
+			ensure!(!Headers::contains_key(hash), Error::<T>::DuplicateHeader);
+			let parent = Self::header(header.parent_hash).ok_or(Error::<T>::UnknownParent)?;
+			Self::check_header(&header, &parent.header)?;
+
+			let commitment = Self::dataset_commitment(header.epoch())
+				.ok_or(Error::<T>::UnknownDataset)?;
+			ethash::verify_seal(
+				header.seal_hash(),
+				header.nonce,
+				header.mix_hash,
+				header.difficulty,
+				&commitment,
+				&pages,
+			).map_err(|_| Error::<T>::InvalidSeal)?;
+
+			let (number, parent_hash) = (header.number, header.parent_hash);
+			let total_difficulty = parent.total_difficulty.saturating_add(header.difficulty);
+			Headers::insert(hash, StoredHeader { header, total_difficulty });
+			HeadersByNumber::mutate(number, |hashes| hashes.push(hash));
+			Self::deposit_event(Event::HeaderImported(hash, number));
+
+			let best_difficulty = Self::best_header().map(|best| best.total_difficulty);
+			if best_difficulty.map_or(true, |best| total_difficulty > best) {
+				Self::set_best_header(hash, number, parent_hash);
+				Self::prune_headers(number);
+				Self::deposit_event(Event::NewBestHeader(hash, number));
+			}
+		}
+
+		/// Set the commitments to the datasets of the given epochs.
+		///
+		/// The dispatch origin for this call must be _Root_ or match `T::DatasetOrigin`.
+		///
+		/// # <weight>
+		/// - O(C) where C is the number of commitments.
+		/// - C storage writes and events.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedOperational(50_000)]
+		fn set_dataset_commitments(origin, commitments: Vec<(u64, DatasetCommitment)>) {
+			T::DatasetOrigin::try_origin(origin)
+				.map(|_| ())
+				.or_else(ensure_root)?;
+
+			for (epoch, commitment) in commitments {
+				DatasetCommitments::insert(epoch, commitment);
+				Self::deposit_event(Event::DatasetCommitmentSet(epoch));
+			}
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The best header, if the module is initialized.
+	pub fn best_header() -> Option<StoredHeader> {
+		Self::best_header_hash().and_then(|hash| Self::header(hash))
+	}
+
+	fn check_header(header: &EthereumHeader, parent: &EthereumHeader) -> DispatchResult {
+		ensure!(
+			header.logs_bloom.len() == header::LOGS_BLOOM_SIZE
+				&& header.extra_data.len() <= header::MAX_EXTRA_DATA_SIZE,
+			Error::<T>::MalformedHeader,
+		);
+		ensure!(header.number == parent.number + 1, Error::<T>::InvalidNumber);
+		ensure!(header.timestamp > parent.timestamp, Error::<T>::InvalidTimestamp);
+
+		let gas_limit_change = if header.gas_limit > parent.gas_limit {
+			header.gas_limit - parent.gas_limit
+		} else {
+			parent.gas_limit - header.gas_limit
+		};
+		ensure!(
+			gas_limit_change < parent.gas_limit / 1024
+				&& header.gas_limit >= header::MINIMUM_GAS_LIMIT.into()
+				&& header.gas_used <= header.gas_limit,
+			Error::<T>::InvalidGasLimit,
+		);
+
+		let difficulty = header::calculate_difficulty(
+			parent,
+			header.timestamp,
+			T::DifficultyBombDelay::get(),
+		);
+		ensure!(header.difficulty == difficulty, Error::<T>::InvalidDifficulty);
+
+		Ok(())
+	}
+
+	/// Make the given header the best one, and its ancestors the best chain.
+	fn set_best_header(hash: H256, number: u64, parent_hash: H256) {
+		// the best chain may get shorter.
+		if let Some(previous) = Self::best_header() {
+			for above in number + 1..=previous.header.number {
+				CanonicalHashes::remove(above);
+			}
+		}
+		BestHeader::put(hash);
+
+		let (mut hash, mut number, mut parent_hash) = (hash, number, parent_hash);
+		while Self::canonical_hash(number) != Some(hash) {
+			CanonicalHashes::insert(number, hash);
+
+			match Self::header(parent_hash) {
+				Some(parent) => {
+					hash = parent_hash;
+					number = parent.header.number;
+					parent_hash = parent.header.parent_hash;
+				},
+				None => break,
+			}
+		}
+	}
+
+	/// Forget the headers more than `HeadersToKeep` blocks below the best one.
+	fn prune_headers(best_number: u64) {
+		let keep_from = best_number.saturating_sub(T::HeadersToKeep::get());
+
+		let mut oldest = OldestHeader::get();
+		while oldest < keep_from {
+			for hash in HeadersByNumber::take(oldest) {
+				Headers::remove(hash);
+			}
+			CanonicalHashes::remove(oldest);
+			oldest += 1;
+		}
+		OldestHeader::put(oldest);
+	}
+}
+
+impl<T: Trait> VerifyReceipt for Module<T> {
+	fn verify_receipt_inclusion(
+		block_hash: H256,
+		proof: &ReceiptProof,
+	) -> Result<Receipt, DispatchError> {
+		let header = Self::header(block_hash).ok_or(Error::<T>::UnknownBlock)?.header;
+		ensure!(Self::canonical_hash(header.number) == Some(block_hash), Error::<T>::NotCanonical);
+
+		let best_number = Self::best_header().map_or(0, |best| best.header.number);
+		ensure!(
+			best_number >= header.number.saturating_add(T::ConfirmationsRequired::get()),
+			Error::<T>::NotConfirmed,
+		);
+
+		let receipt = receipt::read_trie_proof(
+			&header.receipts_root,
+			&rlp::encode(&proof.index),
+			&proof.nodes,
+		).ok().and_then(|receipt| receipt).ok_or(Error::<T>::InvalidReceiptProof)?;
+
+		rlp::decode(&receipt).map_err(|_| Error::<T>::InvalidReceiptProof.into())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use frame_support::{
+		assert_ok, assert_noop, impl_outer_origin, parameter_types, weights::Weight,
+	};
+	use frame_system::EnsureRoot;
+	use sp_runtime::{
+		Perbill, testing::Header, traits::{BlakeTwo256, IdentityLookup, BadOrigin},
+	};
+	use crate::receipt::tests::{receipt_rlp, receipts_trie};
+
+	impl_outer_origin! {
+		pub enum Origin for Test  where system = frame_system {}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::one();
+	}
+	impl frame_system::Trait for Test {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Call = ();
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+		type ModuleToIndex = ();
+		type AccountData = ();
+		type MigrateAccount = (); type MigrateAccountData = (); type OnNewAccount = ();
+		type OnKilledAccount = ();
+	}
+	parameter_types! {
+		pub const DifficultyBombDelay: u64 = 9_000_000;
+		pub const ConfirmationsRequired: u64 = 0;
+		pub const HeadersToKeep: u64 = 100;
+	}
+	impl Trait for Test {
+		type Event = ();
+		type DatasetOrigin = EnsureRoot<u64>;
+		type DifficultyBombDelay = DifficultyBombDelay;
+		type ConfirmationsRequired = ConfirmationsRequired;
+		type HeadersToKeep = HeadersToKeep;
+	}
+	type LightClient = Module<Test>;
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		frame_system::GenesisConfig::default().build_storage::<Test>().unwrap().into()
+	}
+
+	fn trusted_header() -> EthereumHeader {
+		EthereumHeader {
+			ommers_hash: header::empty_ommers_hash(),
+			logs_bloom: vec![0; header::LOGS_BLOOM_SIZE],
+			difficulty: 2_000_000.into(),
+			number: 100,
+			gas_limit: 8_000_000.into(),
+			timestamp: 1_000,
+			..Default::default()
+		}
+	}
+
+	fn child_of(parent: &EthereumHeader) -> EthereumHeader {
+		let timestamp = parent.timestamp + 13;
+		EthereumHeader {
+			parent_hash: parent.hash(),
+			number: parent.number + 1,
+			difficulty: header::calculate_difficulty(parent, timestamp, 9_000_000),
+			timestamp,
+			..parent.clone()
+		}
+	}
+
+	#[test]
+	fn initialize_works_once() {
+		new_test_ext().execute_with(|| {
+			let header = trusted_header();
+
+			assert_noop!(
+				LightClient::initialize(Origin::signed(1), header.clone(), 0.into()),
+				BadOrigin,
+			);
+			assert_ok!(LightClient::initialize(Origin::ROOT, header.clone(), 5.into()));
+			assert_noop!(
+				LightClient::initialize(Origin::ROOT, header.clone(), 5.into()),
+				Error::<Test>::AlreadyInitialized,
+			);
+
+			assert_eq!(LightClient::best_header_hash(), Some(header.hash()));
+			assert_eq!(LightClient::canonical_hash(100), Some(header.hash()));
+			assert_eq!(
+				LightClient::best_header(),
+				Some(StoredHeader { header, total_difficulty: 5.into() }),
+			);
+		});
+	}
+
+	#[test]
+	fn headers_breaking_the_rules_are_rejected() {
+		new_test_ext().execute_with(|| {
+			let parent = trusted_header();
+			assert_ok!(LightClient::initialize(Origin::ROOT, parent.clone(), 0.into()));
+
+			let import = |header: EthereumHeader| LightClient::import_header(
+				Origin::signed(1),
+				header,
+				Vec::new(),
+			);
+			let child = child_of(&parent);
+
+			assert_noop!(import(parent.clone()), Error::<Test>::DuplicateHeader);
+			assert_noop!(
+				import(EthereumHeader { parent_hash: H256::repeat_byte(1), ..child.clone() }),
+				Error::<Test>::UnknownParent,
+			);
+			assert_noop!(
+				import(EthereumHeader { extra_data: vec![0; 33], ..child.clone() }),
+				Error::<Test>::MalformedHeader,
+			);
+			assert_noop!(
+				import(EthereumHeader { number: 102, ..child.clone() }),
+				Error::<Test>::InvalidNumber,
+			);
+			assert_noop!(
+				import(EthereumHeader { timestamp: 1_000, ..child.clone() }),
+				Error::<Test>::InvalidTimestamp,
+			);
+			assert_noop!(
+				import(EthereumHeader { gas_limit: 9_000_000.into(), ..child.clone() }),
+				Error::<Test>::InvalidGasLimit,
+			);
+			assert_noop!(
+				import(EthereumHeader { difficulty: child.difficulty + 1, ..child.clone() }),
+				Error::<Test>::InvalidDifficulty,
+			);
+			assert_noop!(import(child.clone()), Error::<Test>::UnknownDataset);
+
+			let commitment = DatasetCommitment { root: H256::repeat_byte(1), full_size: 1 << 20 };
+			assert_noop!(
+				LightClient::set_dataset_commitments(Origin::signed(1), vec![(0, commitment)]),
+				BadOrigin,
+			);
+			assert_ok!(LightClient::set_dataset_commitments(Origin::ROOT, vec![(0, commitment)]));
+			assert_eq!(LightClient::dataset_commitment(0), Some(commitment));
+
+			assert_noop!(import(child), Error::<Test>::InvalidSeal);
+		});
+	}
+
+	#[test]
+	fn receipts_are_verified_against_the_best_chain() {
+		new_test_ext().execute_with(|| {
+			let receipts = [
+				receipt_rlp(true, H256::repeat_byte(2)),
+				receipt_rlp(true, H256::repeat_byte(3)),
+			];
+			let (receipts_root, nodes) = receipts_trie([&receipts[0], &receipts[1]]);
+			let header = EthereumHeader { receipts_root, ..trusted_header() };
+			let hash = header.hash();
+			assert_ok!(LightClient::initialize(Origin::ROOT, header, 0.into()));
+
+			let proof = |index| ReceiptProof { index, nodes: nodes.clone() };
+			let receipt = LightClient::verify_receipt_inclusion(hash, &proof(1)).unwrap();
+			assert_eq!(receipt.logs[0].topics, vec![H256::repeat_byte(3)]);
+
+			assert_eq!(
+				LightClient::verify_receipt_inclusion(H256::repeat_byte(1), &proof(1)),
+				Err(Error::<Test>::UnknownBlock.into()),
+			);
+			assert_eq!(
+				LightClient::verify_receipt_inclusion(hash, &proof(2)),
+				Err(Error::<Test>::InvalidReceiptProof.into()),
+			);
+			assert_eq!(
+				LightClient::verify_receipt_inclusion(
+					hash,
+					&ReceiptProof { index: 0, nodes: nodes[..1].to_vec() },
+				),
+				Err(Error::<Test>::InvalidReceiptProof.into()),
+			);
+		});
+	}
+}
@@ -0,0 +1,285 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The Ethereum receipts and the proofs of their inclusion in a block.
+
+use sp_std::prelude::*;
+use sp_std::collections::btree_map::BTreeMap;
+use codec::{Encode, Decode};
+use sp_core::{H160, H256, U256};
+use sp_runtime::RuntimeDebug;
+use rlp::{Rlp, DecoderError};
+use crate::header::keccak_256;
+
+/// A log emitted by a transaction.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug)]
+pub struct Log {
+	/// The address of the contract emitting the log.
+	pub address: H160,
+	/// The topics of the log.
+	pub topics: Vec<H256>,
+	/// The data of the log.
+	pub data: Vec<u8>,
+}
+
+impl rlp::Decodable for Log {
+	fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+		if rlp.item_count()? != 3 {
+			return Err(DecoderError::RlpIncorrectListLen);
+		}
+
+		Ok(Log {
+			address: rlp.val_at(0)?,
+			topics: rlp.list_at(1)?,
+			data: rlp.val_at(2)?,
+		})
+	}
+}
+
+/// The outcome of a transaction.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug)]
+pub enum TransactionOutcome {
+	/// The state root after the transaction, before Byzantium.
+	StateRoot(H256),
+	/// Whether the transaction succeeded, since Byzantium.
+	Status(bool),
+}
+
+/// The receipt of a transaction.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug)]
+pub struct Receipt {
+	/// The outcome of the transaction.
+	pub outcome: TransactionOutcome,
+	/// The gas used by the block up to and including the transaction.
+	pub cumulative_gas_used: U256,
+	/// The bloom filter of the logs of the transaction.
+	pub logs_bloom: Vec<u8>,
+	/// The logs emitted by the transaction.
+	pub logs: Vec<Log>,
+}
+
+impl rlp::Decodable for Receipt {
+	fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+		if rlp.item_count()? != 4 {
+			return Err(DecoderError::RlpIncorrectListLen);
+		}
+
+		let outcome = rlp.at(0)?;
+		let outcome = match outcome.data()? {
+			root if root.len() == 32 => TransactionOutcome::StateRoot(H256::from_slice(root)),
+			&[] => TransactionOutcome::Status(false),
+			&[1] => TransactionOutcome::Status(true),
+			_ => return Err(DecoderError::Custom("Invalid transaction outcome")),
+		};
+
+		Ok(Receipt {
+			outcome,
+			cumulative_gas_used: rlp.val_at(1)?,
+			logs_bloom: rlp.val_at(2)?,
+			logs: rlp.list_at(3)?,
+		})
+	}
+}
+
+/// A proof of the inclusion of a receipt in a block.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug)]
+pub struct ReceiptProof {
+	/// The index of the transaction in the block.
+	pub index: u64,
+	/// The nodes of the receipts trie on the path to the receipt.
+	pub nodes: Vec<Vec<u8>>,
+}
+
+/// The reasons a Merkle Patricia trie proof can be rejected for.
+#[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum TrieError {
+	/// A node on the path of the key is missing from the proof.
+	IncompleteProof,
+	/// A node of the proof can't be decoded.
+	InvalidNode,
+}
+
+impl From<DecoderError> for TrieError {
+	fn from(_: DecoderError) -> Self {
+		TrieError::InvalidNode
+	}
+}
+
+/// Look up the value of a key in an Ethereum Merkle Patricia trie, from the nodes on its path.
+///
+/// `Ok(None)` is returned when the nodes prove that the key isn't in the trie.
+pub fn read_trie_proof(
+	root: &H256,
+	key: &[u8],
+	proof: &[Vec<u8>],
+) -> Result<Option<Vec<u8>>, TrieError> {
+	let nodes = proof.iter()
+		.map(|node| (keccak_256(node), &node[..]))
+		.collect::<BTreeMap<_, _>>();
+	let nibbles = key.iter()
+		.flat_map(|byte| vec![byte >> 4, byte & 0x0f])
+		.collect::<Vec<_>>();
+
+	let mut path = &nibbles[..];
+	let mut node = *nodes.get(root).ok_or(TrieError::IncompleteProof)?;
+
+	loop {
+		let rlp = Rlp::new(node);
+		let child = match rlp.item_count()? {
+			// a branch node.
+			17 => match path.split_first() {
+				None => return trie_value(rlp.at(16)?),
+				Some((nibble, rest)) => {
+					path = rest;
+					rlp.at(*nibble as usize)?
+				},
+			},
+			// a leaf or an extension node.
+			2 => {
+				let (partial, is_leaf) = decode_partial_path(rlp.at(0)?.data()?)?;
+				if is_leaf {
+					return if path == &partial[..] { trie_value(rlp.at(1)?) } else { Ok(None) };
+				}
+				if !path.starts_with(&partial) {
+					return Ok(None);
+				}
+
+				path = &path[partial.len()..];
+				rlp.at(1)?
+			},
+			_ => return Err(TrieError::InvalidNode),
+		};
+
+		// the nodes shorter than a hash are embedded in their parent.
+		node = if child.is_list() {
+			child.as_raw()
+		} else {
+			match child.data()? {
+				&[] => return Ok(None),
+				hash if hash.len() == 32 => *nodes.get(&H256::from_slice(hash))
+					.ok_or(TrieError::IncompleteProof)?,
+				_ => return Err(TrieError::InvalidNode),
+			}
+		};
+	}
+}
+
+fn trie_value(rlp: Rlp) -> Result<Option<Vec<u8>>, TrieError> {
+	let value = rlp.data()?;
+	Ok(if value.is_empty() { None } else { Some(value.to_vec()) })
+}
+
+/// Decode the hex-prefix encoded path of a leaf or an extension node, and whether it is a leaf.
+fn decode_partial_path(encoded: &[u8]) -> Result<(Vec<u8>, bool), TrieError> {
+	let (first, rest) = encoded.split_first().ok_or(TrieError::InvalidNode)?;
+	let flag = first >> 4;
+	if flag > 3 {
+		return Err(TrieError::InvalidNode);
+	}
+
+	let mut nibbles = Vec::with_capacity(rest.len() * 2 + 1);
+	if flag & 1 == 1 {
+		nibbles.push(first & 0x0f);
+	}
+	for byte in rest {
+		nibbles.push(byte >> 4);
+		nibbles.push(byte & 0x0f);
+	}
+
+	Ok((nibbles, flag & 2 == 2))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+	use super::*;
+	use rlp::RlpStream;
+
+	pub(crate) fn receipt_rlp(status: bool, topic: H256) -> Vec<u8> {
+		let mut s = RlpStream::new_list(4);
+		s.append(&if status { vec![1u8] } else { Vec::new() });
+		s.append(&U256::from(21_000));
+		s.append(&vec![0u8; 256]);
+		s.begin_list(1);
+		s.begin_list(3);
+		s.append(&H160::repeat_byte(1));
+		s.begin_list(1);
+		s.append(&topic);
+		s.append(&vec![1u8, 2, 3]);
+		s.out()
+	}
+
+	fn leaf(partial_path: u8, value: &[u8]) -> Vec<u8> {
+		let mut s = RlpStream::new_list(2);
+		s.append(&vec![partial_path]);
+		s.append(&value.to_vec());
+		s.out()
+	}
+
+	/// The receipts trie of a block with two transactions, along with its nodes.
+	pub(crate) fn receipts_trie(receipts: [&[u8]; 2]) -> (H256, Vec<Vec<u8>>) {
+		// the keys of the receipts are `0x80` and `0x01`, the encodings of their indices, so the
+		// root is a branch with the leaves at its children `8` and `0`.
+		let leaves = [leaf(0x30, receipts[0]), leaf(0x31, receipts[1])];
+
+		let mut s = RlpStream::new_list(17);
+		for nibble in 0..17 {
+			match nibble {
+				8 => s.append(&keccak_256(&leaves[0])),
+				0 => s.append(&keccak_256(&leaves[1])),
+				_ => s.append_empty_data(),
+			};
+		}
+		let branch = s.out();
+
+		(keccak_256(&branch), vec![branch, leaves[0].clone(), leaves[1].clone()])
+	}
+
+	#[test]
+	fn receipts_are_read_from_trie_proofs() {
+		let receipts = [
+			receipt_rlp(true, H256::repeat_byte(2)),
+			receipt_rlp(false, H256::repeat_byte(3)),
+		];
+		let (root, nodes) = receipts_trie([&receipts[0], &receipts[1]]);
+
+		let key = |index: u64| rlp::encode(&index);
+		assert_eq!(read_trie_proof(&root, &key(0), &nodes), Ok(Some(receipts[0].clone())));
+		assert_eq!(read_trie_proof(&root, &key(1), &nodes), Ok(Some(receipts[1].clone())));
+		assert_eq!(read_trie_proof(&root, &key(2), &nodes), Ok(None));
+		assert_eq!(read_trie_proof(&root, &key(9), &nodes), Ok(None));
+
+		assert_eq!(
+			read_trie_proof(&root, &key(1), &nodes[..2]),
+			Err(TrieError::IncompleteProof),
+		);
+		assert_eq!(
+			read_trie_proof(&H256::repeat_byte(1), &key(1), &nodes),
+			Err(TrieError::IncompleteProof),
+		);
+
+		let receipt: Receipt = rlp::decode(&receipts[0]).unwrap();
+		assert_eq!(receipt, Receipt {
+			outcome: TransactionOutcome::Status(true),
+			cumulative_gas_used: 21_000.into(),
+			logs_bloom: vec![0; 256],
+			logs: vec![Log {
+				address: H160::repeat_byte(1),
+				topics: vec![H256::repeat_byte(2)],
+				data: vec![1, 2, 3],
+			}],
+		});
+	}
+}
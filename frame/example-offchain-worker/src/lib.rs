@@ -52,7 +52,9 @@ use sp_core::crypto::KeyTypeId;
 use sp_runtime::{
 	offchain::{http, Duration, storage::StorageValueRef},
 	traits::Zero,
-	transaction_validity::{InvalidTransaction, ValidTransaction, TransactionValidity},
+	transaction_validity::{
+		InvalidTransaction, ValidTransaction, TransactionValidity, TransactionSource,
+	},
 };
 
 #[cfg(test)]
@@ -492,7 +494,10 @@ impl<T: Trait> frame_support::unsigned::ValidateUnsigned for Module<T> {
 	/// By default unsigned transactions are disallowed, but implementing the validator
 	/// here we make sure that some particular calls (the ones produced by offchain worker)
 	/// are being whitelisted and marked as valid.
-	fn validate_unsigned(call: &Self::Call) -> TransactionValidity {
+	fn validate_unsigned(
+		_source: TransactionSource,
+		call: &Self::Call,
+	) -> TransactionValidity {
 		// Firstly let's check that we call the right function.
 		if let Call::submit_price_unsigned(block_number, new_price) = call {
 			// Now let's check if the transaction has any chance to succeed.
@@ -59,12 +59,17 @@
 //! # pub type Balances = u64;
 //! # pub type AllModules = u64;
 //! # pub enum Runtime {};
-//! # use sp_runtime::transaction_validity::{TransactionValidity, UnknownTransaction};
+//! # use sp_runtime::transaction_validity::{
+//! # 	TransactionValidity, UnknownTransaction, TransactionSource,
+//! # };
 //! # use sp_runtime::traits::ValidateUnsigned;
 //! # impl ValidateUnsigned for Runtime {
 //! # 	type Call = ();
 //! #
-//! # 	fn validate_unsigned(_call: &Self::Call) -> TransactionValidity {
+//! # 	fn validate_unsigned(
+//! # 		_source: TransactionSource,
+//! # 		_call: &Self::Call,
+//! # 	) -> TransactionValidity {
 //! # 		UnknownTransaction::NoUnsignedValidator.into()
 //! # 	}
 //! # }
@@ -75,14 +80,17 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 use sp_std::{prelude::*, marker::PhantomData};
-use frame_support::{storage::StorageValue, weights::{GetDispatchInfo, WeighBlock, DispatchInfo}};
+use frame_support::{
+	storage::StorageValue, traits::OnRuntimeUpgrade,
+	weights::{GetDispatchInfo, WeighBlock, DispatchInfo},
+};
 use sp_runtime::{
 	generic::Digest, ApplyExtrinsicResult,
 	traits::{
 		self, Header, Zero, One, Checkable, Applyable, CheckEqual, OnFinalize, OnInitialize,
-		NumberFor, Block as BlockT, OffchainWorker, Dispatchable, Saturating, OnRuntimeUpgrade,
+		NumberFor, Block as BlockT, OffchainWorker, Dispatchable, Saturating, UniqueSaturatedInto,
 	},
-	transaction_validity::TransactionValidity,
+	transaction_validity::{TransactionValidity, TransactionSource},
 };
 use sp_runtime::traits::ValidateUnsigned;
 use codec::{Codec, Encode};
@@ -176,14 +184,14 @@ where
 		extrinsics_root: &System::Hash,
 		digest: &Digest<System::Hash>,
 	) {
-		if Self::runtime_upgraded() {
+		Self::set_tracing_context(*block_number, None);
+		let upgrade_weight = if Self::runtime_upgraded() {
 			// System is not part of `AllModules`, so we need to call this manually.
-			<frame_system::Module::<System> as OnRuntimeUpgrade>::on_runtime_upgrade();
-			<AllModules as OnRuntimeUpgrade>::on_runtime_upgrade();
-			<frame_system::Module<System>>::register_extra_weight_unchecked(
-				<AllModules as WeighBlock<System::BlockNumber>>::on_runtime_upgrade()
-			);
-		}
+			let weight = <frame_system::Module::<System> as OnRuntimeUpgrade>::on_runtime_upgrade();
+			Some(weight.saturating_add(<AllModules as OnRuntimeUpgrade>::on_runtime_upgrade()))
+		} else {
+			None
+		};
 		<frame_system::Module<System>>::initialize(
 			block_number,
 			parent_hash,
@@ -191,7 +199,15 @@ where
 			digest,
 			frame_system::InitKind::Full,
 		);
+		// The events of the previous block are only removed by the initialization.
+		if let Some(weight) = upgrade_weight {
+			<frame_system::Module<System>>::register_extra_weight_unchecked(weight);
+			<frame_system::Module<System>>::note_runtime_upgraded(weight);
+		}
 		<frame_system::Module<System> as OnInitialize<System::BlockNumber>>::on_initialize(*block_number);
+		<frame_system::Module<System>>::register_extra_weight_unchecked(
+			<frame_system::Module<System>>::migrate_account_data()
+		);
 		<AllModules as OnInitialize<System::BlockNumber>>::on_initialize(*block_number);
 		<frame_system::Module<System>>::register_extra_weight_unchecked(
 			<AllModules as WeighBlock<System::BlockNumber>>::on_initialize(*block_number)
@@ -201,6 +217,12 @@ where
 		);
 	}
 
+	/// Tag the spans of the runtime entered from now on with the number of the block and the
+	/// index of the extrinsic being applied, if any.
+	fn set_tracing_context(block_number: System::BlockNumber, extrinsic_index: Option<u32>) {
+		sp_io::wasm_tracing::set_context(block_number.unique_saturated_into(), extrinsic_index);
+	}
+
 	/// Returns if the runtime was upgraded since the last time this function was called.
 	fn runtime_upgraded() -> bool {
 		let last = frame_system::LastRuntimeUpgrade::get();
@@ -235,6 +257,16 @@ where
 
 	/// Actually execute all transitions for `block`.
 	pub fn execute_block(block: Block) {
+		Self::execute_block_impl(block, true)
+	}
+
+	/// Execute all transitions for `block`, without verifying the signatures of its extrinsics
+	/// nor its state root, as the `ExecuteBlockNoCheck` runtime api does.
+	pub fn execute_block_no_check(block: Block) {
+		Self::execute_block_impl(block, false)
+	}
+
+	fn execute_block_impl(block: Block, check: bool) {
 		Self::initialize_block(block.header());
 
 		// any initial checks
@@ -242,18 +274,23 @@ where
 
 		// execute extrinsics
 		let (header, extrinsics) = block.deconstruct();
-		Self::execute_extrinsics_with_book_keeping(extrinsics, *header.number());
+		Self::execute_extrinsics_with_book_keeping(extrinsics, *header.number(), check);
 
 		// any final checks
-		Self::final_checks(&header);
+		Self::final_checks(&header, check);
 	}
 
 	/// Execute given extrinsics and take care of post-extrinsics book-keeping.
-	fn execute_extrinsics_with_book_keeping(extrinsics: Vec<Block::Extrinsic>, block_number: NumberFor<Block>) {
-		extrinsics.into_iter().for_each(Self::apply_extrinsic_no_note);
+	fn execute_extrinsics_with_book_keeping(
+		extrinsics: Vec<Block::Extrinsic>,
+		block_number: NumberFor<Block>,
+		check_signatures: bool,
+	) {
+		extrinsics.into_iter().for_each(|uxt| Self::apply_extrinsic_no_note(uxt, check_signatures));
 
 		// post-extrinsics book-keeping
 		<frame_system::Module<System>>::note_finished_extrinsics();
+		Self::set_tracing_context(block_number, None);
 		<frame_system::Module<System> as OnFinalize<System::BlockNumber>>::on_finalize(block_number);
 		<AllModules as OnFinalize<System::BlockNumber>>::on_finalize(block_number);
 	}
@@ -263,6 +300,7 @@ where
 	pub fn finalize_block() -> System::Header {
 		<frame_system::Module<System>>::note_finished_extrinsics();
 		let block_number = <frame_system::Module<System>>::block_number();
+		Self::set_tracing_context(block_number, None);
 		<frame_system::Module<System> as OnFinalize<System::BlockNumber>>::on_finalize(block_number);
 		<AllModules as OnFinalize<System::BlockNumber>>::on_finalize(block_number);
 
@@ -278,13 +316,13 @@ where
 	pub fn apply_extrinsic(uxt: Block::Extrinsic) -> ApplyExtrinsicResult {
 		let encoded = uxt.encode();
 		let encoded_len = encoded.len();
-		Self::apply_extrinsic_with_len(uxt, encoded_len, Some(encoded))
+		Self::apply_extrinsic_with_len(uxt, encoded_len, Some(encoded), true)
 	}
 
 	/// Apply an extrinsic inside the block execution function.
-	fn apply_extrinsic_no_note(uxt: Block::Extrinsic) {
+	fn apply_extrinsic_no_note(uxt: Block::Extrinsic, check_signature: bool) {
 		let l = uxt.encode().len();
-		match Self::apply_extrinsic_with_len(uxt, l, None) {
+		match Self::apply_extrinsic_with_len(uxt, l, None, check_signature) {
 			Ok(_) => (),
 			Err(e) => { let err: &'static str = e.into(); panic!(err) },
 		}
@@ -295,9 +333,19 @@ where
 		uxt: Block::Extrinsic,
 		encoded_len: usize,
 		to_note: Option<Vec<u8>>,
+		check_signature: bool,
 	) -> ApplyExtrinsicResult {
+		Self::set_tracing_context(
+			<frame_system::Module<System>>::block_number(),
+			<frame_system::Module<System>>::extrinsic_index(),
+		);
+
 		// Verify that the signature is good.
-		let xt = uxt.check(&Default::default())?;
+		let xt = if check_signature {
+			uxt.check(&Default::default())?
+		} else {
+			uxt.check_without_signature(&Default::default())?
+		};
 
 		// We don't need to make sure to `note_extrinsic` only after we know it's going to be
 		// executed to prevent it from leaking in storage since at this point, it will either
@@ -317,7 +365,7 @@ where
 		Ok(r)
 	}
 
-	fn final_checks(header: &System::Header) {
+	fn final_checks(header: &System::Header, check_state_root: bool) {
 		// remove temporaries
 		let new_header = <frame_system::Module<System>>::finalize();
 
@@ -333,6 +381,10 @@ where
 			assert!(header_item == computed_item, "Digest item must match that calculated.");
 		}
 
+		if !check_state_root {
+			return;
+		}
+
 		// check storage root.
 		let storage_root = new_header.state_root();
 		header.state_root().check_equal(&storage_root);
@@ -343,12 +395,15 @@ where
 	/// side-effects; it merely checks whether the transaction would panic if it were included or not.
 	///
 	/// Changes made to storage should be discarded.
-	pub fn validate_transaction(uxt: Block::Extrinsic) -> TransactionValidity {
+	pub fn validate_transaction(
+		source: TransactionSource,
+		uxt: Block::Extrinsic,
+	) -> TransactionValidity {
 		let encoded_len = uxt.using_encoded(|d| d.len());
 		let xt = uxt.check(&Default::default())?;
 
 		let dispatch_info = xt.get_dispatch_info();
-		xt.validate::<UnsignedValidator>(dispatch_info, encoded_len)
+		xt.validate::<UnsignedValidator>(source, dispatch_info, encoded_len)
 	}
 
 	/// Start an offchain worker and generate extrinsics.
@@ -386,7 +441,9 @@ mod tests {
 	use sp_runtime::{
 		generic::Era, Perbill, DispatchError, testing::{Digest, Header, Block},
 		traits::{Header as HeaderT, BlakeTwo256, IdentityLookup, ConvertInto},
-		transaction_validity::{InvalidTransaction, UnknownTransaction, TransactionValidityError},
+		transaction_validity::{
+			InvalidTransaction, UnknownTransaction, TransactionValidityError, TransactionSource,
+		},
 	};
 	use frame_support::{
 		impl_outer_event, impl_outer_origin, parameter_types, impl_outer_dispatch,
@@ -398,12 +455,16 @@ mod tests {
 	use hex_literal::hex;
 
 	mod custom {
-		use frame_support::weights::SimpleDispatchInfo;
+		use frame_support::weights::{SimpleDispatchInfo, Weight};
 
 		pub trait Trait: frame_system::Trait {}
 
 		frame_support::decl_module! {
 			pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+				fn on_runtime_upgrade() -> Weight {
+					200
+				}
+
 				#[weight = SimpleDispatchInfo::FixedNormal(100)]
 				fn some_function(origin) {
 					// NOTE: does not make any different.
@@ -481,7 +542,7 @@ mod tests {
 		type Version = RuntimeVersion;
 		type ModuleToIndex = ();
 		type AccountData = pallet_balances::AccountData<u64>;
-		type MigrateAccount = (); type OnNewAccount = ();
+		type MigrateAccount = (); type MigrateAccountData = (); type OnNewAccount = ();
 		type OnKilledAccount = ();
 	}
 	parameter_types! {
@@ -516,7 +577,10 @@ mod tests {
 			Ok(())
 		}
 
-		fn validate_unsigned(call: &Self::Call) -> TransactionValidity {
+		fn validate_unsigned(
+			_source: TransactionSource,
+			call: &Self::Call,
+		) -> TransactionValidity {
 			match call {
 				Call::Balances(BalancesCall::set_balance(_, _, _)) => Ok(Default::default()),
 				_ => UnknownTransaction::NoUnsignedValidator.into(),
@@ -537,7 +601,7 @@ mod tests {
 	}
 
 	type SignedExtra = (
-		frame_system::CheckEra<Runtime>,
+		frame_system::CheckMortality<Runtime>,
 		frame_system::CheckNonce<Runtime>,
 		frame_system::CheckWeight<Runtime>,
 		pallet_transaction_payment::ChargeTransactionPayment<Runtime>
@@ -548,7 +612,7 @@ mod tests {
 
 	fn extra(nonce: u64, fee: u64) -> SignedExtra {
 		(
-			frame_system::CheckEra::from(Era::Immortal),
+			frame_system::CheckMortality::from(Era::Immortal),
 			frame_system::CheckNonce::from(nonce),
 			frame_system::CheckWeight::new(),
 			pallet_transaction_payment::ChargeTransactionPayment::from(fee)
@@ -624,6 +688,22 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn block_import_of_bad_state_root_without_check_works() {
+		new_test_ext(1).execute_with(|| {
+			Executive::execute_block_no_check(Block {
+				header: Header {
+					parent_hash: [69u8; 32].into(),
+					number: 1,
+					state_root: [0u8; 32].into(),
+					extrinsics_root: hex!("03170a2e7597b7b7e3d84c05391d139a62b157e78786d8c082f29dcf4c111314").into(),
+					digest: Digest { logs: vec![], },
+				},
+				extrinsics: vec![],
+			});
+		});
+	}
+
 	#[test]
 	#[should_panic]
 	fn block_import_of_bad_extrinsic_root_fails() {
@@ -730,7 +810,10 @@ mod tests {
 		let mut t = new_test_ext(1);
 
 		t.execute_with(|| {
-			assert_eq!(Executive::validate_transaction(xt.clone()), Ok(Default::default()));
+			assert_eq!(
+				Executive::validate_transaction(TransactionSource::External, xt.clone()),
+				Ok(Default::default()),
+			);
 			assert_eq!(Executive::apply_extrinsic(xt), Ok(Err(DispatchError::BadOrigin)));
 		});
 	}
@@ -789,6 +872,22 @@ mod tests {
 		})
 	}
 
+	#[test]
+	fn runtime_upgrade_weight_is_stored_and_announced() {
+		new_test_ext(1).execute_with(|| {
+			RUNTIME_VERSION.with(|v| *v.borrow_mut() = sp_version::RuntimeVersion {
+				spec_version: 1,
+				..Default::default()
+			});
+			Executive::initialize_block(&Header::new_from_number(1));
+
+			assert_eq!(<frame_system::Module<Runtime>>::all_extrinsics_weight(), 200 + 150 + 25);
+			assert!(System::events().iter().any(|record|
+				record.event == MetaEvent::system(system::RawEvent::RuntimeUpgraded(1, 200))
+			));
+		})
+	}
+
 	#[test]
 	fn runtime_upgraded_should_work() {
 		new_test_ext(1).execute_with(|| {
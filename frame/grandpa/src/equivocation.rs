@@ -0,0 +1,179 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Reporting of GRANDPA equivocations.
+//!
+//! Defines the offence of a voter casting two different votes in the same
+//! round, and the `HandleEquivocation` trait the module uses both to report
+//! such offences once their proofs have been verified, and to submit the
+//! unsigned report extrinsics built by the client from an offchain context.
+
+use sp_std::prelude::*;
+use codec::{Decode, Encode};
+use frame_support::dispatch::DispatchResult;
+use frame_system::offchain::SubmitUnsignedTransaction;
+use sp_finality_grandpa::{EquivocationProof, RoundNumber, SetId};
+use sp_runtime::{DispatchError, Perbill, PerThing};
+use sp_staking::{
+	SessionIndex,
+	offence::{Kind, Offence, OffenceError, ReportOffence},
+};
+
+use crate::{Call, Module, Trait};
+
+/// Handling of GRANDPA equivocation reports.
+pub trait HandleEquivocation<T: Trait> {
+	/// Report an equivocation offence, rewarding the given reporters.
+	fn report_offence(
+		reporters: Vec<T::AccountId>,
+		offence: GrandpaEquivocationOffence<T::KeyOwnerIdentification>,
+	) -> Result<(), OffenceError>;
+
+	/// Submit an unsigned `report_equivocation_unsigned` extrinsic to the pool.
+	fn submit_unsigned_equivocation_report(
+		equivocation_proof: EquivocationProof<T::Hash, T::BlockNumber>,
+		key_owner_proof: T::KeyOwnerProof,
+	) -> DispatchResult;
+}
+
+impl<T: Trait> HandleEquivocation<T> for () {
+	fn report_offence(
+		_reporters: Vec<T::AccountId>,
+		_offence: GrandpaEquivocationOffence<T::KeyOwnerIdentification>,
+	) -> Result<(), OffenceError> {
+		Ok(())
+	}
+
+	fn submit_unsigned_equivocation_report(
+		_equivocation_proof: EquivocationProof<T::Hash, T::BlockNumber>,
+		_key_owner_proof: T::KeyOwnerProof,
+	) -> DispatchResult {
+		Ok(())
+	}
+}
+
+/// An equivocation handler reporting the offences to `R` (e.g. the offences
+/// module) and submitting the report extrinsics through `S`.
+pub struct EquivocationHandler<R, S> {
+	_phantom: sp_std::marker::PhantomData<(R, S)>,
+}
+
+impl<R, S> Default for EquivocationHandler<R, S> {
+	fn default() -> Self {
+		Self {
+			_phantom: Default::default(),
+		}
+	}
+}
+
+impl<T, R, S> HandleEquivocation<T> for EquivocationHandler<R, S>
+where
+	T: Trait,
+	R: ReportOffence<
+		T::AccountId,
+		T::KeyOwnerIdentification,
+		GrandpaEquivocationOffence<T::KeyOwnerIdentification>,
+	>,
+	S: SubmitUnsignedTransaction<T, <T as frame_system::Trait>::Call>,
+	<T as frame_system::Trait>::Call: From<Call<T>>,
+{
+	fn report_offence(
+		reporters: Vec<T::AccountId>,
+		offence: GrandpaEquivocationOffence<T::KeyOwnerIdentification>,
+	) -> Result<(), OffenceError> {
+		R::report_offence(reporters, offence)
+	}
+
+	fn submit_unsigned_equivocation_report(
+		equivocation_proof: EquivocationProof<T::Hash, T::BlockNumber>,
+		key_owner_proof: T::KeyOwnerProof,
+	) -> DispatchResult {
+		let call = Call::report_equivocation_unsigned(equivocation_proof, key_owner_proof);
+
+		S::submit_unsigned(call)
+			.map_err(|_| DispatchError::Other("failed to submit equivocation report"))
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Submit an unsigned extrinsic reporting the given equivocation. Only
+	/// useful in an offchain context, e.g. when called through the runtime API.
+	pub fn submit_unsigned_equivocation_report(
+		equivocation_proof: EquivocationProof<T::Hash, T::BlockNumber>,
+		key_owner_proof: T::KeyOwnerProof,
+	) -> Option<()> {
+		T::HandleEquivocation::submit_unsigned_equivocation_report(
+			equivocation_proof,
+			key_owner_proof,
+		)
+		.ok()
+	}
+}
+
+/// A round number and set id which point on the time of an offence.
+#[derive(Copy, Clone, PartialOrd, Ord, Eq, PartialEq, Encode, Decode)]
+pub struct GrandpaTimeSlot {
+	// The order of these matters for `derive(Ord)`.
+	/// Grandpa Set ID.
+	pub set_id: SetId,
+	/// Round number.
+	pub round: RoundNumber,
+}
+
+/// A grandpa equivocation offence report.
+pub struct GrandpaEquivocationOffence<FullIdentification> {
+	/// Time slot at which this incident happened.
+	pub time_slot: GrandpaTimeSlot,
+	/// The session index in which the incident happened.
+	pub session_index: SessionIndex,
+	/// The size of the validator set at the time of the offence.
+	pub validator_set_count: u32,
+	/// The authority which produced this equivocation.
+	pub offender: FullIdentification,
+}
+
+impl<FullIdentification: Clone> Offence<FullIdentification>
+	for GrandpaEquivocationOffence<FullIdentification>
+{
+	const ID: Kind = *b"grandpa:equivoca";
+	type TimeSlot = GrandpaTimeSlot;
+
+	fn offenders(&self) -> Vec<FullIdentification> {
+		vec![self.offender.clone()]
+	}
+
+	fn session_index(&self) -> SessionIndex {
+		self.session_index
+	}
+
+	fn validator_set_count(&self) -> u32 {
+		self.validator_set_count
+	}
+
+	fn time_slot(&self) -> Self::TimeSlot {
+		self.time_slot
+	}
+
+	fn slash_fraction(
+		offenders_count: u32,
+		validator_set_count: u32,
+	) -> Perbill {
+		// the formula is min((3k / n)^2, 1)
+		let x = Perbill::from_rational_approximation(3 * offenders_count, validator_set_count);
+		// _ ^ 2
+		x.square()
+	}
+}
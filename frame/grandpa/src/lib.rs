@@ -32,26 +32,62 @@ pub use sp_finality_grandpa as fg_primitives;
 
 use sp_std::prelude::*;
 use codec::{self as codec, Encode, Decode};
-use frame_support::{decl_event, decl_storage, decl_module, decl_error, storage};
-use sp_runtime::{
-	DispatchResult, generic::{DigestItem, OpaqueDigestItemId}, traits::Zero, Perbill, PerThing,
+use frame_support::{
+	decl_event, decl_storage, decl_module, decl_error, storage, Parameter,
+	traits::KeyOwnerProofSystem, weights::SimpleDispatchInfo,
 };
-use sp_staking::{
-	SessionIndex,
-	offence::{Offence, Kind},
+use sp_runtime::{
+	DispatchResult, KeyTypeId, generic::{DigestItem, OpaqueDigestItemId}, traits::Zero,
+	transaction_validity::{
+		InvalidTransaction, TransactionPriority, TransactionSource, TransactionValidity,
+		ValidTransaction,
+	},
 };
+use sp_session::{GetSessionNumber, GetValidatorCount};
+use sp_staking::SessionIndex;
 use fg_primitives::{
-	GRANDPA_AUTHORITIES_KEY, GRANDPA_ENGINE_ID, ScheduledChange, ConsensusLog, SetId, RoundNumber,
+	GRANDPA_AUTHORITIES_KEY, GRANDPA_ENGINE_ID, ScheduledChange, ConsensusLog, SetId,
+	EquivocationProof,
 };
 pub use fg_primitives::{AuthorityId, AuthorityList, AuthorityWeight, VersionedAuthorityList};
-use frame_system::{self as system, ensure_signed, DigestOf};
+use frame_system::{self as system, ensure_none, ensure_root, ensure_signed, DigestOf};
 
+mod equivocation;
 mod mock;
 mod tests;
 
+pub use equivocation::{
+	EquivocationHandler, GrandpaEquivocationOffence, GrandpaTimeSlot, HandleEquivocation,
+};
+
+/// Number of blocks an unsigned equivocation report stays valid for in the pool.
+const REPORT_LONGEVITY: u64 = 64;
+
 pub trait Trait: frame_system::Trait {
 	/// The event type of this module.
 	type Event: From<Event> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// The proof of key ownership, used for validating equivocation reports.
+	/// The proof must include the session index and validator count of the
+	/// session at which the equivocation occurred.
+	type KeyOwnerProof: Parameter + GetSessionNumber + GetValidatorCount;
+
+	/// The identification of a key owner, used when reporting equivocations.
+	type KeyOwnerIdentification: Parameter;
+
+	/// A system for proving ownership of keys, i.e. that a given key was part
+	/// of a validator set, needed for validating equivocation reports.
+	type KeyOwnerProofSystem: KeyOwnerProofSystem<
+		(KeyTypeId, AuthorityId),
+		Proof = Self::KeyOwnerProof,
+		IdentificationTuple = Self::KeyOwnerIdentification,
+	>;
+
+	/// The equivocation handling subsystem, reporting the offences of valid
+	/// equivocation reports and submitting unsigned report extrinsics.
+	/// NOTE: the `ValidateUnsigned` part of the module must be included in
+	/// `construct_runtime!` for the unsigned reports to be accepted.
+	type HandleEquivocation: HandleEquivocation<Self>;
 }
 
 /// A stored pending change, old format.
@@ -146,6 +182,12 @@ decl_error! {
 		ChangePending,
 		/// Cannot signal forced change so soon after last.
 		TooSoon,
+		/// An equivocation proof provided as part of an equivocation report is invalid.
+		InvalidEquivocationProof,
+		/// A key ownership proof provided as part of an equivocation report is invalid.
+		InvalidKeyOwnershipProof,
+		/// A given equivocation report is valid but already previously reported.
+		DuplicateOffenceReport,
 	}
 }
 
@@ -199,10 +241,56 @@ decl_module! {
 
 		fn deposit_event() = default;
 
-		/// Report some misbehavior.
-		fn report_misbehavior(origin, _report: Vec<u8>) {
-			ensure_signed(origin)?;
-			// FIXME: https://github.com/paritytech/substrate/issues/1112
+		/// Report voter equivocation/misbehavior. This method will verify the
+		/// equivocation proof and validate the given key ownership proof
+		/// against the extracted offender. If both are valid, the offence
+		/// will be reported.
+		#[weight = SimpleDispatchInfo::FixedNormal(10_000_000)]
+		fn report_equivocation(
+			origin,
+			equivocation_proof: EquivocationProof<T::Hash, T::BlockNumber>,
+			key_owner_proof: T::KeyOwnerProof,
+		) {
+			let reporter = ensure_signed(origin)?;
+
+			Self::do_report_equivocation(
+				Some(reporter),
+				equivocation_proof,
+				key_owner_proof,
+			)?;
+		}
+
+		/// Report voter equivocation/misbehavior, as `report_equivocation`
+		/// does. This extrinsic is unsigned, it is submitted by the voters that
+		/// detect the equivocation and validated in `ValidateUnsigned`, so the
+		/// offence is reported without any reporters.
+		#[weight = SimpleDispatchInfo::FixedNormal(10_000_000)]
+		fn report_equivocation_unsigned(
+			origin,
+			equivocation_proof: EquivocationProof<T::Hash, T::BlockNumber>,
+			key_owner_proof: T::KeyOwnerProof,
+		) {
+			ensure_none(origin)?;
+
+			Self::do_report_equivocation(None, equivocation_proof, key_owner_proof)?;
+		}
+
+		/// Note that the current authority set of the GRANDPA finality gadget has
+		/// stalled. This will trigger a forced authority set change at the beginning
+		/// of the next session, to be enacted `delay` blocks after that. The delay
+		/// should be high enough to safely assume that the block signalling the
+		/// forced change will not be re-orged (e.g. 1000 blocks). The GRANDPA voters
+		/// will start the new authority set using the given finalized block as base.
+		/// Only callable by root.
+		#[weight = SimpleDispatchInfo::FixedOperational(10_000)]
+		fn note_stalled(
+			origin,
+			delay: T::BlockNumber,
+			best_finalized_block_number: T::BlockNumber,
+		) {
+			ensure_root(origin)?;
+
+			Self::on_stalled(delay, best_finalized_block_number)
 		}
 
 		fn on_runtime_upgrade() {
@@ -369,6 +457,13 @@ impl<T: Trait> Module<T> {
 		}
 	}
 
+	/// Note that the current authority set has stalled, so that the next
+	/// session change forces an authority set change, enacted after `further_wait`
+	/// blocks and using `median` as the last finalized block of the stalled set.
+	pub fn on_stalled(further_wait: T::BlockNumber, median: T::BlockNumber) {
+		<Stalled<T>>::put((further_wait, median));
+	}
+
 	/// Deposit one of this module's logs.
 	fn deposit_log(log: ConsensusLog<T::BlockNumber>) {
 		let log: DigestItem<T::Hash> = DigestItem::Consensus(GRANDPA_ENGINE_ID, log.encode());
@@ -391,6 +486,71 @@ impl<T: Trait> Module<T> {
 			Self::set_grandpa_authorities(&Authorities::take());
 		}
 	}
+
+	fn do_report_equivocation(
+		reporter: Option<T::AccountId>,
+		equivocation_proof: EquivocationProof<T::Hash, T::BlockNumber>,
+		key_owner_proof: T::KeyOwnerProof,
+	) -> DispatchResult {
+		let offender = equivocation_proof.offender().clone();
+		let set_id = equivocation_proof.set_id();
+		let round = equivocation_proof.round();
+
+		// validate the equivocation proof (check votes are different and
+		// signatures are valid).
+		fg_primitives::check_equivocation_proof(equivocation_proof)
+			.map_err(|_| Error::<T>::InvalidEquivocationProof)?;
+
+		// fetch the last session index of the set before the reported one, there's
+		// none for the genesis set.
+		let previous_set_id_session_index = if set_id == 0 {
+			None
+		} else {
+			let session_index = Self::session_for_set(set_id - 1)
+				.ok_or(Error::<T>::InvalidEquivocationProof)?;
+
+			Some(session_index)
+		};
+
+		let set_id_session_index = Self::session_for_set(set_id)
+			.ok_or(Error::<T>::InvalidEquivocationProof)?;
+
+		// the set was responsible for the sessions after the last session of the
+		// previous set, up to its own last session, so the key ownership proof
+		// must be for one of those.
+		let session_index = key_owner_proof.session();
+		if session_index > set_id_session_index ||
+			previous_set_id_session_index
+				.map(|previous_index| session_index <= previous_index)
+				.unwrap_or(false)
+		{
+			return Err(Error::<T>::InvalidEquivocationProof.into());
+		}
+
+		let validator_set_count = key_owner_proof.validator_count();
+
+		// check the membership proof and extract the offender's id
+		let key = (fg_primitives::KEY_TYPE, offender);
+		let offender = T::KeyOwnerProofSystem::check_proof(key, key_owner_proof)
+			.ok_or(Error::<T>::InvalidKeyOwnershipProof)?;
+
+		let offence = GrandpaEquivocationOffence {
+			time_slot: GrandpaTimeSlot { set_id, round },
+			session_index,
+			validator_set_count,
+			offender,
+		};
+
+		let reporters = match reporter {
+			Some(id) => vec![id],
+			None => vec![],
+		};
+
+		T::HandleEquivocation::report_offence(reporters, offence)
+			.map_err(|_| Error::<T>::DuplicateOffenceReport)?;
+
+		Ok(())
+	}
 }
 
 impl<T: Trait> Module<T> {
@@ -450,15 +610,23 @@ impl<T: Trait> pallet_session::OneSessionHandler<T::AccountId> for Module<T>
 	{
 		// Always issue a change if `session` says that the validators have changed.
 		// Even if their session keys are the same as before, the underlying economic
-		// identities have changed.
-		let current_set_id = if changed {
+		// identities have changed. A stalled set is also always replaced, through a
+		// forced change, whether the validators have changed or not.
+		let current_set_id = if changed || <Stalled<T>>::exists() {
 			let next_authorities = validators.map(|(_, k)| (k, 1)).collect::<Vec<_>>();
-			if let Some((further_wait, median)) = <Stalled<T>>::take() {
-				let _ = Self::schedule_change(next_authorities, further_wait, Some(median));
+			let res = if let Some((further_wait, median)) = <Stalled<T>>::take() {
+				Self::schedule_change(next_authorities, further_wait, Some(median))
 			} else {
-				let _ = Self::schedule_change(next_authorities, Zero::zero(), None);
+				Self::schedule_change(next_authorities, Zero::zero(), None)
+			};
+
+			if res.is_ok() {
+				CurrentSetId::mutate(|s| { *s += 1; *s })
+			} else {
+				// no change was scheduled (e.g. a forced change too soon after the
+				// previous one), so the current set is still the same.
+				Self::current_set_id()
 			}
-			CurrentSetId::mutate(|s| { *s += 1; *s })
 		} else {
 			// nothing's changed, neither economic conditions nor session keys. update the pointer
 			// of the current set.
@@ -481,58 +649,45 @@ impl<T: Trait> pallet_finality_tracker::OnFinalizationStalled<T::BlockNumber> fo
 		// when we record old authority sets, we can use `pallet_finality_tracker::median`
 		// to figure out _who_ failed. until then, we can't meaningfully guard
 		// against `next == last` the way that normal session changes do.
-		<Stalled<T>>::put((further_wait, median));
+		Module::<T>::on_stalled(further_wait, median)
 	}
 }
 
-/// A round number and set id which point on the time of an offence.
-#[derive(Copy, Clone, PartialOrd, Ord, Eq, PartialEq, Encode, Decode)]
-struct GrandpaTimeSlot {
-	// The order of these matters for `derive(Ord)`.
-	set_id: SetId,
-	round: RoundNumber,
-}
-
-// TODO [slashing]: Integrate this.
-/// A grandpa equivocation offence report.
-struct GrandpaEquivocationOffence<FullIdentification> {
-	/// Time slot at which this incident happened.
-	time_slot: GrandpaTimeSlot,
-	/// The session index in which the incident happened.
-	session_index: SessionIndex,
-	/// The size of the validator set at the time of the offence.
-	validator_set_count: u32,
-	/// The authority which produced this equivocation.
-	offender: FullIdentification,
-}
+impl<T: Trait> frame_support::unsigned::ValidateUnsigned for Module<T> {
+	type Call = Call<T>;
 
-impl<FullIdentification: Clone> Offence<FullIdentification> for GrandpaEquivocationOffence<FullIdentification> {
-	const ID: Kind = *b"grandpa:equivoca";
-	type TimeSlot = GrandpaTimeSlot;
-
-	fn offenders(&self) -> Vec<FullIdentification> {
-		vec![self.offender.clone()]
-	}
-
-	fn session_index(&self) -> SessionIndex {
-		self.session_index
-	}
+	fn validate_unsigned(source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+		if let Call::report_equivocation_unsigned(equivocation_proof, _) = call {
+			// discard the reports not coming from the local node, which are the only ones
+			// allowed to submit them.
+			match source {
+				TransactionSource::Local | TransactionSource::InBlock => { /* allowed */ },
+				TransactionSource::External => return InvalidTransaction::Call.into(),
+			}
 
-	fn validator_set_count(&self) -> u32 {
-		self.validator_set_count
-	}
+			// the key ownership proof is checked on dispatch, only the
+			// signatures of the votes are checked before entering the pool.
+			if fg_primitives::check_equivocation_proof(equivocation_proof.clone()).is_err() {
+				return InvalidTransaction::BadProof.into();
+			}
 
-	fn time_slot(&self) -> Self::TimeSlot {
-		self.time_slot
-	}
+			let tag = (
+				equivocation_proof.offender().clone(),
+				equivocation_proof.set_id(),
+				equivocation_proof.round(),
+			);
 
-	fn slash_fraction(
-		offenders_count: u32,
-		validator_set_count: u32,
-	) -> Perbill {
-		// the formula is min((3k / n)^2, 1)
-		let x = Perbill::from_rational_approximation(3 * offenders_count, validator_set_count);
-		// _ ^ 2
-		x.square()
+			Ok(ValidTransaction {
+				priority: TransactionPriority::max_value(),
+				requires: vec![],
+				// only one report for the same offender in the same round
+				provides: vec![tag.encode()],
+				longevity: REPORT_LONGEVITY,
+				// reports are submitted by the voters of the local node
+				propagate: false,
+			})
+		} else {
+			InvalidTransaction::Call.into()
+		}
 	}
 }
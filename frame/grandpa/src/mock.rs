@@ -41,6 +41,11 @@ pub struct Test;
 
 impl Trait for Test {
 	type Event = TestEvent;
+
+	type KeyOwnerProof = sp_core::Void;
+	type KeyOwnerIdentification = sp_core::Void;
+	type KeyOwnerProofSystem = ();
+	type HandleEquivocation = ();
 }
 parameter_types! {
 	pub const BlockHashCount: u64 = 250;
@@ -66,7 +71,7 @@ impl frame_system::Trait for Test {
 	type Version = ();
 	type ModuleToIndex = ();
 	type AccountData = ();
-	type MigrateAccount = (); type OnNewAccount = ();
+	type MigrateAccount = (); type MigrateAccountData = (); type OnNewAccount = ();
 	type OnKilledAccount = ();
 }
 
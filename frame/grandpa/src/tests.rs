@@ -211,6 +211,19 @@ fn dispatch_forced_change() {
 	});
 }
 
+#[test]
+fn note_stalled_is_root_only() {
+	new_test_ext(vec![(1, 1), (2, 1), (3, 1)]).execute_with(|| {
+		initialize_block(1, Default::default());
+
+		assert!(Grandpa::note_stalled(Origin::signed(1), 1000, 0).is_err());
+		assert_eq!(Grandpa::stalled(), None);
+
+		assert!(Grandpa::note_stalled(Origin::ROOT, 1000, 0).is_ok());
+		assert_eq!(Grandpa::stalled(), Some((1000, 0)));
+	});
+}
+
 #[test]
 fn schedule_pause_only_when_live() {
 	new_test_ext(vec![(1, 1), (2, 1), (3, 1)]).execute_with(|| {
@@ -336,3 +349,50 @@ fn authorities_migration() {
 		assert_eq!(Grandpa::grandpa_authorities(), authorities);
 	});
 }
+
+#[test]
+fn equivocation_proofs_are_checked() {
+	use sp_core::Pair;
+	use fg_primitives::{AuthorityPair, Equivocation, check_equivocation_proof, localized_payload};
+
+	let pair = AuthorityPair::from_seed(&[1; 32]);
+	let other_pair = AuthorityPair::from_seed(&[2; 32]);
+	let (set_id, round) = (1, 2);
+
+	let signed_prevote = |pair: &AuthorityPair, target_hash: H256| {
+		let prevote = grandpa::Prevote { target_hash, target_number: 1u64 };
+		let message = grandpa::Message::Prevote(prevote.clone());
+		let signature = pair.sign(&localized_payload(round, set_id, &message));
+		(prevote, signature)
+	};
+
+	let proof = |first, second| fg_primitives::EquivocationProof::new(
+		set_id,
+		Equivocation::Prevote(grandpa::Equivocation {
+			round_number: round,
+			identity: pair.public(),
+			first,
+			second,
+		}),
+	);
+
+	let first_vote = signed_prevote(&pair, H256::repeat_byte(1));
+	let second_vote = signed_prevote(&pair, H256::repeat_byte(2));
+	assert!(check_equivocation_proof(proof(first_vote.clone(), second_vote.clone())).is_ok());
+
+	// voting twice for the same block isn't an equivocation
+	assert!(check_equivocation_proof(proof(first_vote.clone(), first_vote.clone())).is_err());
+
+	// both votes must be signed by the offender
+	let other_signer_vote = signed_prevote(&other_pair, H256::repeat_byte(2));
+	assert!(check_equivocation_proof(proof(first_vote.clone(), other_signer_vote)).is_err());
+
+	// the votes must be signed for the set and round of the proof
+	let mut other_round_vote = second_vote;
+	other_round_vote.1 = pair.sign(&localized_payload(
+		round + 1,
+		set_id,
+		&grandpa::Message::Prevote(other_round_vote.0.clone()),
+	));
+	assert!(check_equivocation_proof(proof(first_vote, other_round_vote)).is_err());
+}
@@ -119,7 +119,7 @@ impl frame_system::Trait for Runtime {
 	type Version = ();
 	type ModuleToIndex = ();
 	type AccountData = ();
-	type MigrateAccount = (); type OnNewAccount = ();
+	type MigrateAccount = (); type MigrateAccountData = (); type OnNewAccount = ();
 	type OnKilledAccount = ();
 }
 
@@ -146,6 +146,7 @@ impl pallet_session::Trait for Runtime {
 impl pallet_session::historical::Trait for Runtime {
 	type FullIdentification = u64;
 	type FullIdentificationOf = ConvertInto;
+	type HistoricalFullIdentificationOf = pallet_session::historical::SessionIndependent<ConvertInto>;
 }
 
 parameter_types! {
@@ -21,8 +21,14 @@
 //!
 //! ## Overview
 //!
-//! Nicks is a trivial module for keeping track of account names on-chain. It makes no effort to
-//! create a name hierarchy, be a DNS replacement or provide reverse lookups.
+//! Nicks is a lightweight module for labelling accounts on-chain, for the chains which want
+//! naming without the identity machinery. It makes no effort to create a name hierarchy, be a
+//! DNS replacement or provide reverse lookups.
+//!
+//! Names must be valid UTF-8 and may not start with one of the reserved prefixes, which only
+//! the force origin may hand out. A name expires `ExpiryPeriod` blocks after it is set, unless
+//! its owner renews it by reserving `RenewalFee` more; an expired name is no longer reported and may be
+//! cleared by anyone, the deposit being returned to its owner.
 //!
 //! ## Interface
 //!
@@ -30,8 +36,11 @@
 //!
 //! * `set_name` - Set the associated name of an account; a small deposit is reserved if not already
 //!   taken.
+//! * `renew_name` - Reserve the renewal fee to extend the expiry of an account's name.
 //! * `clear_name` - Remove an account's associated name; the deposit is returned.
+//! * `clear_expired_name` - Remove an expired name; the deposit is returned to its owner.
 //! * `kill_name` - Forcibly remove the associated name; the deposit is lost.
+//! * `force_name` - Forcibly set the associated name of an account.
 //!
 //! [`Call`]: ./enum.Call.html
 //! [`Trait`]: ./trait.Trait.html
@@ -39,13 +48,14 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 use sp_std::prelude::*;
+use codec::{Encode, Decode};
 use sp_runtime::{
-	traits::{StaticLookup, EnsureOrigin, Zero}
+	RuntimeDebug, traits::{StaticLookup, EnsureOrigin, Zero, Saturating},
 };
 use frame_support::{
 	decl_module, decl_event, decl_storage, ensure, decl_error,
 	traits::{Currency, ReservableCurrency, OnUnbalanced, Get},
-	weights::SimpleDispatchInfo,
+	weights::SimpleDispatchInfo, storage::IterableStorageMap,
 };
 use frame_system::{self as system, ensure_signed, ensure_root};
 
@@ -62,36 +72,90 @@ pub trait Trait: frame_system::Trait {
 	/// Reservation fee.
 	type ReservationFee: Get<BalanceOf<Self>>;
 
+	/// The amount reserved on top of the deposit to renew a name.
+	type RenewalFee: Get<BalanceOf<Self>>;
+
+	/// The number of blocks a name lasts for once set or renewed.
+	type ExpiryPeriod: Get<Self::BlockNumber>;
+
 	/// What to do with slashed funds.
 	type Slashed: OnUnbalanced<NegativeImbalanceOf<Self>>;
 
-	/// The origin which may forcibly set or remove a name. Root can always do this.
+	/// The origin which may forcibly set a name. Root can always do this.
 	type ForceOrigin: EnsureOrigin<Self::Origin>;
 
+	/// The origin which may remove a name and slash its deposit. Root can always do this.
+	type SlashOrigin: EnsureOrigin<Self::Origin>;
+
 	/// The minimum length a name may be.
 	type MinLength: Get<usize>;
 
 	/// The maximum length a name may be.
 	type MaxLength: Get<usize>;
+
+	/// The prefixes of the names which only the force origin may set.
+	type ReservedPrefixes: Get<&'static [&'static [u8]]>;
+}
+
+/// The name of an account, along with the deposit reserved for it.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug)]
+pub struct Registration<Balance, BlockNumber> {
+	/// The name, a UTF-8-encoded string.
+	pub name: Vec<u8>,
+	/// The amount reserved from the account, renewal fees included.
+	pub deposit: Balance,
+	/// The block the name expires at, unless renewed.
+	pub expiry: BlockNumber,
+}
+
+// A value placed in storage that represents the current version of the Nicks storage.
+// This value is used by the `on_runtime_upgrade` logic to determine whether we run
+// storage migration logic.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+enum Releases {
+	/// Names are stored as a `(name, deposit)` tuple, and don't expire.
+	V1_0_0,
+	/// Names are stored as a `Registration`.
+	V2_0_0,
+}
+
+impl Default for Releases {
+	fn default() -> Self {
+		Releases::V1_0_0
+	}
 }
 
 decl_storage! {
 	trait Store for Module<T: Trait> as Nicks {
-		/// The lookup table for names.
-		NameOf: map hasher(twox_64_concat) T::AccountId => Option<(Vec<u8>, BalanceOf<T>)>;
+		/// The lookup table for names, including the expired ones which aren't cleared yet.
+		NameOf: map hasher(twox_64_concat) T::AccountId
+			=> Option<Registration<BalanceOf<T>, T::BlockNumber>>;
+
+		/// Storage version of the pallet.
+		///
+		/// This is set to v2.0.0 for new networks.
+		StorageVersion build(|_| Releases::V2_0_0): Releases;
 	}
 }
 
 decl_event!(
-	pub enum Event<T> where AccountId = <T as frame_system::Trait>::AccountId, Balance = BalanceOf<T> {
+	pub enum Event<T> where
+		AccountId = <T as frame_system::Trait>::AccountId,
+		Balance = BalanceOf<T>,
+		BlockNumber = <T as frame_system::Trait>::BlockNumber,
+	{
 		/// A name was set.
 		NameSet(AccountId),
 		/// A name was forcibly set.
 		NameForced(AccountId),
 		/// A name was changed.
 		NameChanged(AccountId),
+		/// A name was renewed until the given block.
+		NameRenewed(AccountId, BlockNumber),
 		/// A name was cleared, and the given balance returned.
 		NameCleared(AccountId, Balance),
+		/// An expired name was cleared, and the given balance returned.
+		NameExpired(AccountId, Balance),
 		/// A name was removed and the given balance slashed.
 		NameKilled(AccountId, Balance),
 	}
@@ -104,8 +168,16 @@ decl_error! {
 		TooShort,
 		/// A name is too long.
 		TooLong,
+		/// A name isn't valid UTF-8.
+		InvalidUtf8,
+		/// A name starts with a reserved prefix.
+		Reserved,
 		/// An account isn't named.
 		Unnamed,
+		/// The name of an account expired, and must be renewed first.
+		Expired,
+		/// The name of an account didn't expire yet.
+		NotExpired,
 	}
 }
 
@@ -116,27 +188,38 @@ decl_module! {
 
 		fn deposit_event() = default;
 
+		fn on_runtime_upgrade() {
+			migrate::<T>();
+		}
+
 		/// Reservation fee.
 		const ReservationFee: BalanceOf<T> = T::ReservationFee::get();
 
+		/// The amount reserved on top of the deposit to renew a name.
+		const RenewalFee: BalanceOf<T> = T::RenewalFee::get();
+
+		/// The number of blocks a name lasts for once set or renewed.
+		const ExpiryPeriod: T::BlockNumber = T::ExpiryPeriod::get();
+
 		/// The minimum length a name may be.
 		const MinLength: u32 = T::MinLength::get() as u32;
 
 		/// The maximum length a name may be.
 		const MaxLength: u32 = T::MaxLength::get() as u32;
 
-		/// Set an account's name. The name should be a UTF-8-encoded string by convention, though
-		/// we don't check it.
+		/// Set an account's name, which must be a UTF-8-encoded string which doesn't start with
+		/// one of the `T::ReservedPrefixes`.
 		///
 		/// The name may not be more than `T::MaxLength` bytes, nor less than `T::MinLength` bytes.
 		///
 		/// If the account doesn't already have a name, then a fee of `ReservationFee` is reserved
-		/// in the account.
+		/// in the account and the name expires `T::ExpiryPeriod` blocks later. Changing a name
+		/// doesn't change its expiry, and an expired name must be renewed before being changed.
 		///
 		/// The dispatch origin for this call must be _Signed_.
 		///
 		/// # <weight>
-		/// - O(1).
+		/// - O(N + P) where N is the length of the name and P the size of the reserved prefixes.
 		/// - At most one balance operation.
 		/// - One storage read/write.
 		/// - One event.
@@ -147,18 +230,52 @@ decl_module! {
 
 			ensure!(name.len() >= T::MinLength::get(), Error::<T>::TooShort);
 			ensure!(name.len() <= T::MaxLength::get(), Error::<T>::TooLong);
+			ensure!(sp_std::str::from_utf8(&name).is_ok(), Error::<T>::InvalidUtf8);
+			ensure!(
+				!T::ReservedPrefixes::get().iter().any(|prefix| name.starts_with(prefix)),
+				Error::<T>::Reserved,
+			);
 
-			let deposit = if let Some((_, deposit)) = <NameOf<T>>::get(&sender) {
-				Self::deposit_event(RawEvent::NameSet(sender.clone()));
-				deposit
+			let registration = if let Some(registration) = <NameOf<T>>::get(&sender) {
+				ensure!(!Self::is_expired(&registration), Error::<T>::Expired);
+				Self::deposit_event(RawEvent::NameChanged(sender.clone()));
+				Registration { name, ..registration }
 			} else {
 				let deposit = T::ReservationFee::get();
 				T::Currency::reserve(&sender, deposit.clone())?;
-				Self::deposit_event(RawEvent::NameChanged(sender.clone()));
-				deposit
+				Self::deposit_event(RawEvent::NameSet(sender.clone()));
+				Registration { name, deposit, expiry: Self::next_expiry() }
 			};
 
-			<NameOf<T>>::insert(&sender, (name, deposit));
+			<NameOf<T>>::insert(&sender, registration);
+		}
+
+		/// Renew an account's name, reserving `RenewalFee` more to have it expire
+		/// `T::ExpiryPeriod` blocks from now. The fee is added to the deposit of the name, so it is
+		/// returned along with it. Expired names which aren't cleared yet may be renewed too.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - One balance operation.
+		/// - One storage read/write.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn renew_name(origin) {
+			let sender = ensure_signed(origin)?;
+
+			let mut registration = <NameOf<T>>::get(&sender).ok_or(Error::<T>::Unnamed)?;
+			let fee = T::RenewalFee::get();
+			T::Currency::reserve(&sender, fee.clone())?;
+
+			registration.deposit = registration.deposit.saturating_add(fee);
+			registration.expiry = Self::next_expiry();
+			let expiry = registration.expiry;
+			<NameOf<T>>::insert(&sender, registration);
+
+			Self::deposit_event(RawEvent::NameRenewed(sender, expiry));
 		}
 
 		/// Clear an account's name and return the deposit. Fails if the account was not named.
@@ -174,19 +291,43 @@ decl_module! {
 		fn clear_name(origin) {
 			let sender = ensure_signed(origin)?;
 
-			let deposit = <NameOf<T>>::take(&sender).ok_or(Error::<T>::Unnamed)?.1;
+			let deposit = <NameOf<T>>::take(&sender).ok_or(Error::<T>::Unnamed)?.deposit;
 
 			let _ = T::Currency::unreserve(&sender, deposit.clone());
 
 			Self::deposit_event(RawEvent::NameCleared(sender, deposit));
 		}
 
+		/// Clear the expired name of `target` and return the deposit to it.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - One balance operation.
+		/// - One storage read/write.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn clear_expired_name(origin, target: <T::Lookup as StaticLookup>::Source) {
+			ensure_signed(origin)?;
+
+			let target = T::Lookup::lookup(target)?;
+			let registration = <NameOf<T>>::get(&target).ok_or(Error::<T>::Unnamed)?;
+			ensure!(Self::is_expired(&registration), Error::<T>::NotExpired);
+			<NameOf<T>>::remove(&target);
+
+			let _ = T::Currency::unreserve(&target, registration.deposit.clone());
+
+			Self::deposit_event(RawEvent::NameExpired(target, registration.deposit));
+		}
+
 		/// Remove an account's name and take charge of the deposit.
 		///
 		/// Fails if `who` has not been named. The deposit is dealt with through `T::Slashed`
 		/// imbalance handler.
 		///
-		/// The dispatch origin for this call must be _Root_ or match `T::ForceOrigin`.
+		/// The dispatch origin for this call must be _Root_ or match `T::SlashOrigin`.
 		///
 		/// # <weight>
 		/// - O(1).
@@ -196,23 +337,23 @@ decl_module! {
 		/// # </weight>
 		#[weight = SimpleDispatchInfo::FixedNormal(70_000)]
 		fn kill_name(origin, target: <T::Lookup as StaticLookup>::Source) {
-			T::ForceOrigin::try_origin(origin)
+			T::SlashOrigin::try_origin(origin)
 				.map(|_| ())
 				.or_else(ensure_root)?;
 
 			// Figure out who we're meant to be clearing.
 			let target = T::Lookup::lookup(target)?;
 			// Grab their deposit (and check that they have one).
-			let deposit = <NameOf<T>>::take(&target).ok_or(Error::<T>::Unnamed)?.1;
+			let deposit = <NameOf<T>>::take(&target).ok_or(Error::<T>::Unnamed)?.deposit;
 			// Slash their deposit from them.
 			T::Slashed::on_unbalanced(T::Currency::slash_reserved(&target, deposit.clone()).0);
 
 			Self::deposit_event(RawEvent::NameKilled(target, deposit));
 		}
 
-		/// Set a third-party account's name with no deposit.
+		/// Set a third-party account's name with no deposit, renewing it.
 		///
-		/// No length checking is done on the name.
+		/// No checking is done on the name, so it may start with a reserved prefix.
 		///
 		/// The dispatch origin for this call must be _Root_ or match `T::ForceOrigin`.
 		///
@@ -229,21 +370,50 @@ decl_module! {
 				.or_else(ensure_root)?;
 
 			let target = T::Lookup::lookup(target)?;
-			let deposit = <NameOf<T>>::get(&target).map(|x| x.1).unwrap_or_else(Zero::zero);
-			<NameOf<T>>::insert(&target, (name, deposit));
+			let deposit = <NameOf<T>>::get(&target).map(|x| x.deposit).unwrap_or_else(Zero::zero);
+			<NameOf<T>>::insert(&target, Registration { name, deposit, expiry: Self::next_expiry() });
 
 			Self::deposit_event(RawEvent::NameForced(target));
 		}
 	}
 }
 
+/// Migrate the names from the `(name, deposit)` tuples of v1.0.0 to registrations, expiring
+/// `T::ExpiryPeriod` blocks after the upgrade.
+fn migrate<T: Trait>() {
+	if StorageVersion::get() == Releases::V1_0_0 {
+		let expiry = <Module<T>>::next_expiry();
+		<NameOf<T>>::translate(|_, (name, deposit): (Vec<u8>, BalanceOf<T>)| {
+			Some(Registration { name, deposit, expiry })
+		});
+		StorageVersion::put(Releases::V2_0_0);
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The name of `who`, unless it expired.
+	pub fn name_of(who: &T::AccountId) -> Option<Vec<u8>> {
+		<NameOf<T>>::get(who)
+			.filter(|registration| !Self::is_expired(registration))
+			.map(|registration| registration.name)
+	}
+
+	fn is_expired(registration: &Registration<BalanceOf<T>, T::BlockNumber>) -> bool {
+		registration.expiry <= <frame_system::Module<T>>::block_number()
+	}
+
+	fn next_expiry() -> T::BlockNumber {
+		<frame_system::Module<T>>::block_number().saturating_add(T::ExpiryPeriod::get())
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
 
 	use frame_support::{
 		assert_ok, assert_noop, impl_outer_origin, parameter_types, weights::Weight,
-		ord_parameter_types
+		ord_parameter_types, storage::unhashed, traits::OnRuntimeUpgrade,
 	};
 	use sp_core::H256;
 	use frame_system::EnsureSignedBy;
@@ -286,7 +456,7 @@ mod tests {
 		type Version = ();
 		type ModuleToIndex = ();
 		type AccountData = pallet_balances::AccountData<u64>;
-		type MigrateAccount = (); type OnNewAccount = ();
+		type MigrateAccount = (); type MigrateAccountData = (); type OnNewAccount = ();
 		type OnKilledAccount = ();
 	}
 	parameter_types! {
@@ -301,20 +471,28 @@ mod tests {
 	}
 	parameter_types! {
 		pub const ReservationFee: u64 = 2;
+		pub const RenewalFee: u64 = 1;
+		pub const ExpiryPeriod: u64 = 100;
 		pub const MinLength: usize = 3;
 		pub const MaxLength: usize = 16;
+		pub const ReservedPrefixes: &'static [&'static [u8]] = &[b"Root", b"Council"];
 	}
 	ord_parameter_types! {
 		pub const One: u64 = 1;
+		pub const Two: u64 = 2;
 	}
 	impl Trait for Test {
 		type Event = ();
 		type Currency = Balances;
 		type ReservationFee = ReservationFee;
+		type RenewalFee = RenewalFee;
+		type ExpiryPeriod = ExpiryPeriod;
 		type Slashed = ();
 		type ForceOrigin = EnsureSignedBy<One, u64>;
+		type SlashOrigin = EnsureSignedBy<Two, u64>;
 		type MinLength = MinLength;
 		type MaxLength = MaxLength;
+		type ReservedPrefixes = ReservedPrefixes;
 	}
 	type System = frame_system::Module<Test>;
 	type Balances = pallet_balances::Module<Test>;
@@ -339,7 +517,8 @@ mod tests {
 		new_test_ext().execute_with(|| {
 			assert_ok!(Nicks::set_name(Origin::signed(2), b"Dave".to_vec()));
 			assert_eq!(Balances::total_balance(&2), 10);
-			assert_ok!(Nicks::kill_name(Origin::signed(1), 2));
+			assert_noop!(Nicks::kill_name(Origin::signed(1), 2), BadOrigin);
+			assert_ok!(Nicks::kill_name(Origin::signed(2), 2));
 			assert_eq!(Balances::total_balance(&2), 8);
 			assert_eq!(<NameOf<Test>>::get(2), None);
 		});
@@ -357,7 +536,13 @@ mod tests {
 			assert_eq!(Balances::reserved_balance(2), 2);
 			assert_ok!(Nicks::force_name(Origin::signed(1), 2, b"Dr. David Brubeck, III".to_vec()));
 			assert_eq!(Balances::reserved_balance(2), 2);
-			assert_eq!(<NameOf<Test>>::get(2).unwrap(), (b"Dr. David Brubeck, III".to_vec(), 2));
+			assert_eq!(
+				<NameOf<Test>>::get(2).unwrap(),
+				Registration { name: b"Dr. David Brubeck, III".to_vec(), deposit: 2, expiry: 100 },
+			);
+
+			assert_ok!(Nicks::force_name(Origin::signed(1), 3, b"Root".to_vec()));
+			assert_eq!(Nicks::name_of(&3), Some(b"Root".to_vec()));
 		});
 	}
 
@@ -367,12 +552,12 @@ mod tests {
 			assert_ok!(Nicks::set_name(Origin::signed(1), b"Gav".to_vec()));
 			assert_eq!(Balances::reserved_balance(1), 2);
 			assert_eq!(Balances::free_balance(1), 8);
-			assert_eq!(<NameOf<Test>>::get(1).unwrap().0, b"Gav".to_vec());
+			assert_eq!(Nicks::name_of(&1), Some(b"Gav".to_vec()));
 
 			assert_ok!(Nicks::set_name(Origin::signed(1), b"Gavin".to_vec()));
 			assert_eq!(Balances::reserved_balance(1), 2);
 			assert_eq!(Balances::free_balance(1), 8);
-			assert_eq!(<NameOf<Test>>::get(1).unwrap().0, b"Gavin".to_vec());
+			assert_eq!(Nicks::name_of(&1), Some(b"Gavin".to_vec()));
 
 			assert_ok!(Nicks::clear_name(Origin::signed(1)));
 			assert_eq!(Balances::reserved_balance(1), 0);
@@ -395,9 +580,88 @@ mod tests {
 				Nicks::set_name(Origin::signed(1), b"Gavin James Wood, Esquire".to_vec()),
 				Error::<Test>::TooLong
 			);
+			assert_noop!(
+				Nicks::set_name(Origin::signed(1), vec![0xf0, 0x28, 0x8c, 0x28]),
+				Error::<Test>::InvalidUtf8
+			);
+			assert_noop!(
+				Nicks::set_name(Origin::signed(1), b"Council of Gav".to_vec()),
+				Error::<Test>::Reserved
+			);
 			assert_ok!(Nicks::set_name(Origin::signed(1), b"Dave".to_vec()));
-			assert_noop!(Nicks::kill_name(Origin::signed(2), 1), BadOrigin);
+			assert_noop!(Nicks::kill_name(Origin::signed(1), 1), BadOrigin);
 			assert_noop!(Nicks::force_name(Origin::signed(2), 1, b"Whatever".to_vec()), BadOrigin);
+			assert_noop!(Nicks::clear_expired_name(Origin::signed(2), 1), Error::<Test>::NotExpired);
+		});
+	}
+
+	#[test]
+	fn names_expire_unless_renewed() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Nicks::set_name(Origin::signed(1), b"Gav".to_vec()));
+			assert_noop!(Nicks::renew_name(Origin::signed(2)), Error::<Test>::Unnamed);
+
+			System::set_block_number(50);
+			assert_ok!(Nicks::renew_name(Origin::signed(1)));
+			assert_eq!(
+				<NameOf<Test>>::get(1).unwrap(),
+				Registration { name: b"Gav".to_vec(), deposit: 3, expiry: 150 },
+			);
+			assert_eq!(Balances::free_balance(1), 7);
+			assert_eq!(Balances::reserved_balance(1), 3);
+
+			System::set_block_number(150);
+			assert_eq!(Nicks::name_of(&1), None);
+			assert_noop!(
+				Nicks::set_name(Origin::signed(1), b"Gavin".to_vec()),
+				Error::<Test>::Expired
+			);
+
+			assert_ok!(Nicks::clear_expired_name(Origin::signed(2), 1));
+			assert_eq!(<NameOf<Test>>::get(1), None);
+			assert_eq!(Balances::free_balance(1), 10);
+			assert_eq!(Balances::reserved_balance(1), 0);
+			assert_noop!(Nicks::clear_expired_name(Origin::signed(2), 1), Error::<Test>::Unnamed);
+		});
+	}
+
+	#[test]
+	fn renewal_fees_are_slashed_along_with_the_deposit() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Nicks::set_name(Origin::signed(1), b"Gav".to_vec()));
+			assert_ok!(Nicks::renew_name(Origin::signed(1)));
+			assert_eq!(Balances::reserved_balance(1), 3);
+
+			assert_ok!(Nicks::kill_name(Origin::signed(2), 1));
+			assert_eq!(Balances::reserved_balance(1), 0);
+			assert_eq!(Balances::total_balance(&1), 7);
+		});
+	}
+
+	#[test]
+	fn names_are_migrated_to_registrations() {
+		new_test_ext().execute_with(|| {
+			assert_eq!(StorageVersion::get(), Releases::V1_0_0);
+			unhashed::put(&<NameOf<Test>>::hashed_key_for(1), &(b"Gav".to_vec(), 2u64));
+			unhashed::put(&<NameOf<Test>>::hashed_key_for(2), &(b"Dave".to_vec(), 0u64));
+
+			System::set_block_number(10);
+			<Nicks as OnRuntimeUpgrade>::on_runtime_upgrade();
+
+			assert_eq!(StorageVersion::get(), Releases::V2_0_0);
+			assert_eq!(
+				<NameOf<Test>>::get(1).unwrap(),
+				Registration { name: b"Gav".to_vec(), deposit: 2, expiry: 110 },
+			);
+			assert_eq!(
+				<NameOf<Test>>::get(2).unwrap(),
+				Registration { name: b"Dave".to_vec(), deposit: 0, expiry: 110 },
+			);
+
+			// Only run once.
+			System::set_block_number(20);
+			<Nicks as OnRuntimeUpgrade>::on_runtime_upgrade();
+			assert_eq!(<NameOf<Test>>::get(1).unwrap().expiry, 110);
 		});
 	}
 }
@@ -0,0 +1,714 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Oracle Module
+//!
+//! - [`oracle::Trait`](./trait.Trait.html)
+//! - [`Call`](./enum.Call.html)
+//!
+//! ## Overview
+//!
+//! The oracle module keeps data feeds, such as the prices of pairs of assets, fed by a set of
+//! operators. The operator set is managed by governance, typically through the membership module
+//! as the module implements `ChangeMembers`.
+//!
+//! The operators feed keyed values either with signed transactions, or with unsigned
+//! transactions carrying a payload signed with their oracle key, so that they don't need funds
+//! to pay the fees with. Each time a value is fed, the median of the values fed by the
+//! operators within `StalePeriod` blocks is recomputed, as long as there are at least
+//! `MinimumCount` of them, and kept along with the history of the medians over `TwapWindow`
+//! blocks, out of which the time-weighted average is computed.
+//!
+//! An aggregated value which isn't updated for `StalePeriod` blocks is stale, and isn't provided
+//! through the [`DataProvider`](./trait.DataProvider.html) trait anymore.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! * `feed_values` - Feed values as an operator.
+//! * `feed_values_unsigned` - Feed values with a payload signed by the oracle key of an operator.
+//! * `set_key` - Set the oracle key of an operator.
+//!
+//! [`Call`]: ./enum.Call.html
+//! [`Trait`]: ./trait.Trait.html
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::prelude::*;
+use codec::{Encode, Decode};
+use sp_application_crypto::RuntimeAppPublic;
+use sp_runtime::{
+	RuntimeDebug,
+	traits::{Member, Saturating, Zero, AtLeast32Bit, UniqueSaturatedInto, CheckedAdd, CheckedMul},
+	transaction_validity::{
+		TransactionValidity, ValidTransaction, InvalidTransaction, TransactionSource,
+		TransactionPriority,
+	},
+};
+use frame_support::{
+	decl_module, decl_event, decl_storage, decl_error, ensure, Parameter,
+	traits::{Get, ChangeMembers, InitializeMembers},
+	weights::{SimpleDispatchInfo, FunctionOf, DispatchClass, Weight},
+};
+use frame_system::{self as system, ensure_signed, ensure_none};
+
+pub mod sr25519 {
+	mod app_sr25519 {
+		use sp_application_crypto::{app_crypto, key_types::ORACLE, sr25519};
+		app_crypto!(sr25519, ORACLE);
+	}
+
+	sp_application_crypto::with_pair! {
+		/// An oracle keypair using sr25519 as its crypto.
+		pub type OperatorPair = app_sr25519::Pair;
+	}
+
+	/// An oracle signature using sr25519 as its crypto.
+	pub type OperatorSignature = app_sr25519::Signature;
+
+	/// An oracle identifier using sr25519 as its crypto.
+	pub type OperatorId = app_sr25519::Public;
+}
+
+pub trait Trait: frame_system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// The key of a data feed.
+	type OracleKey: Parameter + Member + Ord;
+
+	/// The values of the data feeds.
+	type OracleValue: Parameter + Member + AtLeast32Bit + Copy + Default;
+
+	/// The keys the operators sign the unsigned payloads with.
+	type OperatorId: Member + Parameter + RuntimeAppPublic + Default + Ord;
+
+	/// The minimum number of fresh values the median is computed out of.
+	type MinimumCount: Get<u32>;
+
+	/// The number of blocks after which a value is stale.
+	type StalePeriod: Get<Self::BlockNumber>;
+
+	/// The number of blocks the time-weighted average is computed over.
+	type TwapWindow: Get<Self::BlockNumber>;
+
+	/// The maximum number of values fed at once.
+	type MaxFeedValues: Get<u32>;
+
+	/// A configuration for base priority of unsigned transactions.
+	///
+	/// This is exposed so that it can be tuned for particular runtime, when
+	/// multiple modules send unsigned transactions.
+	type UnsignedPriority: Get<TransactionPriority>;
+}
+
+/// A value, along with the block it was fed or aggregated at.
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode, Default, RuntimeDebug)]
+pub struct TimestampedValue<Value, BlockNumber> {
+	/// The value.
+	pub value: Value,
+	/// The block the value was fed or aggregated at.
+	pub block: BlockNumber,
+}
+
+/// The payload of an unsigned feed, signed with the oracle key of the operator.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug)]
+pub struct FeedPayload<AccountId, Key, Value> {
+	/// The operator feeding the values.
+	pub operator: AccountId,
+	/// The number of unsigned feeds of the operator so far.
+	pub nonce: u32,
+	/// The keys and values fed.
+	pub values: Vec<(Key, Value)>,
+}
+
+/// A provider of the values of the data feeds, for the modules consuming them.
+pub trait DataProvider<Key, Value> {
+	/// The aggregated value of `key`, unless it's stale.
+	fn get(key: &Key) -> Option<Value>;
+}
+
+type TimestampedValueOf<T> = TimestampedValue<<T as Trait>::OracleValue, <T as frame_system::Trait>::BlockNumber>;
+type FeedPayloadOf<T> = FeedPayload<<T as frame_system::Trait>::AccountId, <T as Trait>::OracleKey, <T as Trait>::OracleValue>;
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Oracle {
+		/// The operators, sorted.
+		pub Operators get(fn operators): Vec<T::AccountId>;
+
+		/// The oracle keys of the operators.
+		pub OperatorKeys get(fn operator_key): map hasher(twox_64_concat) T::AccountId
+			=> Option<T::OperatorId>;
+
+		/// The number of unsigned feeds of each operator.
+		pub Nonces get(fn nonce): map hasher(twox_64_concat) T::AccountId => u32;
+
+		/// The values last fed by each operator.
+		pub RawValues get(fn raw_value):
+			double_map hasher(twox_64_concat) T::AccountId, hasher(twox_64_concat) T::OracleKey
+			=> Option<TimestampedValueOf<T>>;
+
+		/// The aggregated values.
+		pub Values get(fn value): map hasher(twox_64_concat) T::OracleKey
+			=> Option<TimestampedValueOf<T>>;
+
+		/// The aggregated values over the last `TwapWindow` blocks, oldest first, along with the
+		/// last one before.
+		pub History get(fn history): map hasher(twox_64_concat) T::OracleKey
+			=> Vec<TimestampedValueOf<T>>;
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where
+		AccountId = <T as frame_system::Trait>::AccountId,
+		OracleKey = <T as Trait>::OracleKey,
+		OracleValue = <T as Trait>::OracleValue,
+	{
+		/// An operator fed the given values.
+		NewFeedData(AccountId, Vec<(OracleKey, OracleValue)>),
+		/// The value of the given key was aggregated.
+		ValueAggregated(OracleKey, OracleValue),
+		/// The oracle key of an operator was set.
+		OperatorKeySet(AccountId),
+	}
+);
+
+decl_error! {
+	/// Error for the oracle module.
+	pub enum Error for Module<T: Trait> {
+		/// The account isn't an operator.
+		NotOperator,
+		/// The nonce of the payload isn't the one of the operator.
+		InvalidNonce,
+		/// More than `MaxFeedValues` values are fed at once.
+		TooManyValues,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		fn deposit_event() = default;
+
+		/// The minimum number of fresh values the median is computed out of.
+		const MinimumCount: u32 = T::MinimumCount::get();
+
+		/// The number of blocks after which a value is stale.
+		const StalePeriod: T::BlockNumber = T::StalePeriod::get();
+
+		/// The number of blocks the time-weighted average is computed over.
+		const TwapWindow: T::BlockNumber = T::TwapWindow::get();
+
+		/// The maximum number of values fed at once.
+		const MaxFeedValues: u32 = T::MaxFeedValues::get();
+
+		/// Feed the given values, at most `MaxFeedValues` of them.
+		///
+		/// The dispatch origin for this call must be _Signed_ by an operator.
+		///
+		/// # <weight>
+		/// - O(V * O) where V is the number of values and O the number of operators.
+		/// - V * (O + 3) storage reads and V * 3 storage writes.
+		/// - V + 1 events.
+		/// # </weight>
+		#[weight = FunctionOf(
+			|args: (&Vec<(T::OracleKey, T::OracleValue)>,)| Self::feed_weight(args.0.len()),
+			DispatchClass::Normal,
+			true
+		)]
+		fn feed_values(origin, values: Vec<(T::OracleKey, T::OracleValue)>) {
+			let who = ensure_signed(origin)?;
+			ensure!(Self::operators().binary_search(&who).is_ok(), Error::<T>::NotOperator);
+			ensure!(values.len() <= T::MaxFeedValues::get() as usize, Error::<T>::TooManyValues);
+
+			Self::do_feed_values(who, values);
+		}
+
+		/// Feed the values of the given payload, signed with the oracle key of its operator, at
+		/// most `MaxFeedValues` of them.
+		///
+		/// The dispatch origin for this call must be _None_. The call is validated by checking
+		/// the signature and the nonce of the payload.
+		///
+		/// # <weight>
+		/// - O(V * O) where V is the number of values and O the number of operators.
+		/// - V * (O + 3) + 1 storage reads and V * 3 + 1 storage writes.
+		/// - V + 1 events.
+		/// # </weight>
+		#[weight = FunctionOf(
+			|args: (&FeedPayloadOf<T>, &<T::OperatorId as RuntimeAppPublic>::Signature)| {
+				Self::feed_weight(args.0.values.len())
+			},
+			DispatchClass::Normal,
+			true
+		)]
+		fn feed_values_unsigned(
+			origin,
+			payload: FeedPayloadOf<T>,
+			_signature: <T::OperatorId as RuntimeAppPublic>::Signature,
+		) {
+			ensure_none(origin)?;
+			ensure!(
+				Self::operators().binary_search(&payload.operator).is_ok(),
+				Error::<T>::NotOperator,
+			);
+			ensure!(
+				payload.values.len() <= T::MaxFeedValues::get() as usize,
+				Error::<T>::TooManyValues,
+			);
+			ensure!(payload.nonce == Self::nonce(&payload.operator), Error::<T>::InvalidNonce);
+
+			Nonces::<T>::insert(&payload.operator, payload.nonce.wrapping_add(1));
+			Self::do_feed_values(payload.operator, payload.values);
+		}
+
+		/// Set the oracle key the unsigned payloads of the sender are signed with.
+		///
+		/// The dispatch origin for this call must be _Signed_ by an operator.
+		///
+		/// # <weight>
+		/// - O(O) where O is the number of operators.
+		/// - Two storage reads and one storage write.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn set_key(origin, key: T::OperatorId) {
+			let who = ensure_signed(origin)?;
+			ensure!(Self::operators().binary_search(&who).is_ok(), Error::<T>::NotOperator);
+
+			OperatorKeys::<T>::insert(&who, key);
+
+			Self::deposit_event(RawEvent::OperatorKeySet(who));
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The time-weighted average of the aggregated values of `key` over the last `TwapWindow`
+	/// blocks, unless the value is stale or the weighted sum of the values overflows.
+	pub fn twap(key: &T::OracleKey) -> Option<T::OracleValue> {
+		let now = <frame_system::Module<T>>::block_number();
+		let window_start = now.saturating_sub(T::TwapWindow::get());
+		let history = Self::history(key);
+		let last = history.last()?;
+		if Self::is_stale(last) {
+			return None;
+		}
+
+		let mut weighted_sum = T::OracleValue::zero();
+		let mut total_weight = T::OracleValue::zero();
+		for (i, entry) in history.iter().enumerate() {
+			let from = entry.block.max(window_start);
+			let until = history.get(i + 1).map_or(now, |next| next.block);
+			let weight: u32 = until.saturating_sub(from).unique_saturated_into();
+			let weight = T::OracleValue::from(weight);
+			weighted_sum = weighted_sum.checked_add(&entry.value.checked_mul(&weight)?)?;
+			total_weight = total_weight.saturating_add(weight);
+		}
+
+		if total_weight.is_zero() {
+			Some(last.value)
+		} else {
+			Some(weighted_sum / total_weight)
+		}
+	}
+
+	/// The weight of feeding `count` values.
+	fn feed_weight(count: usize) -> Weight {
+		(count as Weight).saturating_mul(100_000).saturating_add(50_000)
+	}
+
+	fn is_stale(value: &TimestampedValueOf<T>) -> bool {
+		let now = <frame_system::Module<T>>::block_number();
+		now.saturating_sub(value.block) >= T::StalePeriod::get()
+	}
+
+	fn do_feed_values(who: T::AccountId, values: Vec<(T::OracleKey, T::OracleValue)>) {
+		let block = <frame_system::Module<T>>::block_number();
+		for (key, value) in values.iter() {
+			RawValues::<T>::insert(&who, key, TimestampedValue { value: *value, block });
+			Self::aggregate(key);
+		}
+
+		Self::deposit_event(RawEvent::NewFeedData(who, values));
+	}
+
+	/// Recompute the median of the fresh values of `key`, if there are enough of them.
+	fn aggregate(key: &T::OracleKey) {
+		let mut values = Self::operators().iter()
+			.filter_map(|operator| Self::raw_value(operator, key))
+			.filter(|raw| !Self::is_stale(raw))
+			.map(|raw| raw.value)
+			.collect::<Vec<_>>();
+		if values.is_empty() || (values.len() as u32) < T::MinimumCount::get() {
+			return;
+		}
+
+		// the lower median, so that it's always one of the fed values.
+		values.sort_unstable();
+		let median = values[(values.len() - 1) / 2];
+		let block = <frame_system::Module<T>>::block_number();
+		let aggregated = TimestampedValue { value: median, block };
+		Values::<T>::insert(key, aggregated);
+
+		let window_start = block.saturating_sub(T::TwapWindow::get());
+		History::<T>::mutate(key, |history| {
+			match history.last_mut() {
+				Some(last) if last.block == block => *last = aggregated,
+				_ => history.push(aggregated),
+			}
+			// keep the last value before the window, which is in effect at its start.
+			let in_window = history.iter().position(|entry| entry.block > window_start)
+				.unwrap_or(history.len());
+			history.drain(..in_window.saturating_sub(1));
+		});
+
+		Self::deposit_event(RawEvent::ValueAggregated(key.clone(), median));
+	}
+}
+
+impl<T: Trait> DataProvider<T::OracleKey, T::OracleValue> for Module<T> {
+	fn get(key: &T::OracleKey) -> Option<T::OracleValue> {
+		Self::value(key).filter(|value| !Self::is_stale(value)).map(|value| value.value)
+	}
+}
+
+impl<T: Trait> ChangeMembers<T::AccountId> for Module<T> {
+	fn change_members_sorted(
+		_incoming: &[T::AccountId],
+		outgoing: &[T::AccountId],
+		sorted_new: &[T::AccountId],
+	) {
+		for operator in outgoing {
+			OperatorKeys::<T>::remove(operator);
+			RawValues::<T>::remove_prefix(operator);
+		}
+		Operators::<T>::put(sorted_new);
+	}
+}
+
+impl<T: Trait> InitializeMembers<T::AccountId> for Module<T> {
+	fn initialize_members(operators: &[T::AccountId]) {
+		if !operators.is_empty() {
+			assert!(Self::operators().is_empty(), "Operators are already initialized!");
+			let mut operators = operators.to_vec();
+			operators.sort();
+			Operators::<T>::put(operators);
+		}
+	}
+}
+
+impl<T: Trait> frame_support::unsigned::ValidateUnsigned for Module<T> {
+	type Call = Call<T>;
+
+	fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+		if let Call::feed_values_unsigned(payload, signature) = call {
+			if Self::operators().binary_search(&payload.operator).is_err() {
+				return InvalidTransaction::BadProof.into();
+			}
+			if payload.values.len() > T::MaxFeedValues::get() as usize {
+				return InvalidTransaction::ExhaustsResources.into();
+			}
+
+			let nonce = Self::nonce(&payload.operator);
+			if payload.nonce < nonce {
+				return InvalidTransaction::Stale.into();
+			}
+			if payload.nonce > nonce {
+				return InvalidTransaction::Future.into();
+			}
+
+			let key = match Self::operator_key(&payload.operator) {
+				Some(key) => key,
+				None => return InvalidTransaction::BadProof.into(),
+			};
+			// check signature (this is expensive so we do it last).
+			let signature_valid = payload.using_encoded(|encoded| key.verify(&encoded, signature));
+			if !signature_valid {
+				return InvalidTransaction::BadProof.into();
+			}
+
+			Ok(ValidTransaction {
+				priority: T::UnsignedPriority::get(),
+				requires: vec![],
+				provides: vec![(&payload.operator, payload.nonce).encode()],
+				longevity: 64,
+				propagate: true,
+			})
+		} else {
+			InvalidTransaction::Call.into()
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use frame_support::{
+		assert_ok, assert_noop, impl_outer_origin, parameter_types, weights::{Weight, GetDispatchInfo},
+	};
+	use sp_core::H256;
+	use sp_runtime::{
+		Perbill,
+		testing::{Header, UintAuthorityId},
+		traits::{BlakeTwo256, IdentityLookup, BadOrigin},
+		transaction_validity::TransactionSource::External,
+	};
+	use frame_support::unsigned::ValidateUnsigned;
+
+	impl_outer_origin! {
+		pub enum Origin for Test  where system = frame_system {}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::one();
+	}
+	impl frame_system::Trait for Test {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Call = ();
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+		type ModuleToIndex = ();
+		type AccountData = ();
+		type MigrateAccount = (); type MigrateAccountData = (); type OnNewAccount = ();
+		type OnKilledAccount = ();
+	}
+	parameter_types! {
+		pub const MinimumCount: u32 = 2;
+		pub const StalePeriod: u64 = 10;
+		pub const TwapWindow: u64 = 10;
+		pub const MaxFeedValues: u32 = 3;
+		pub const UnsignedPriority: TransactionPriority = 1 << 20;
+	}
+	impl Trait for Test {
+		type Event = ();
+		type OracleKey = u32;
+		type OracleValue = u64;
+		type OperatorId = UintAuthorityId;
+		type MinimumCount = MinimumCount;
+		type StalePeriod = StalePeriod;
+		type TwapWindow = TwapWindow;
+		type MaxFeedValues = MaxFeedValues;
+		type UnsignedPriority = UnsignedPriority;
+	}
+	type System = frame_system::Module<Test>;
+	type Oracle = Module<Test>;
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		let t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		let mut ext: sp_io::TestExternalities = t.into();
+		ext.execute_with(|| Oracle::initialize_members(&[3, 1, 2]));
+		ext
+	}
+
+	fn signed_feed(operator: u64, nonce: u32, values: Vec<(u32, u64)>) -> Call<Test> {
+		let payload = FeedPayload { operator, nonce, values };
+		let signature = UintAuthorityId(operator).sign(&payload.encode()).unwrap();
+		Call::feed_values_unsigned(payload, signature)
+	}
+
+	#[test]
+	fn the_median_of_the_fresh_values_is_aggregated() {
+		new_test_ext().execute_with(|| {
+			assert_eq!(Oracle::operators(), vec![1, 2, 3]);
+			assert_noop!(Oracle::feed_values(Origin::signed(4), vec![(0, 10)]), Error::<Test>::NotOperator);
+
+			System::set_block_number(1);
+			assert_ok!(Oracle::feed_values(Origin::signed(1), vec![(0, 10)]));
+			// not enough values yet.
+			assert_eq!(Oracle::get(&0), None);
+
+			assert_ok!(Oracle::feed_values(Origin::signed(2), vec![(0, 30)]));
+			assert_eq!(Oracle::get(&0), Some(10));
+			assert_ok!(Oracle::feed_values(Origin::signed(3), vec![(0, 20)]));
+			assert_eq!(Oracle::get(&0), Some(20));
+
+			// the value of 1 is stale, so the median is out of 2 and 3 only.
+			System::set_block_number(11);
+			assert_eq!(Oracle::get(&0), None);
+			assert_ok!(Oracle::feed_values(Origin::signed(2), vec![(0, 40)]));
+			assert_eq!(Oracle::get(&0), None);
+			assert_ok!(Oracle::feed_values(Origin::signed(3), vec![(0, 50)]));
+			assert_eq!(Oracle::get(&0), Some(40));
+		});
+	}
+
+	#[test]
+	fn the_time_weighted_average_is_computed_over_the_window() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			assert_ok!(Oracle::feed_values(Origin::signed(1), vec![(0, 10)]));
+			assert_ok!(Oracle::feed_values(Origin::signed(2), vec![(0, 10)]));
+
+			System::set_block_number(5);
+			assert_ok!(Oracle::feed_values(Origin::signed(1), vec![(0, 40)]));
+			assert_ok!(Oracle::feed_values(Origin::signed(2), vec![(0, 40)]));
+			// the new value isn't in effect for any block yet.
+			assert_eq!(Oracle::twap(&0), Some(10));
+
+			// 10 for 4 blocks, then 40 for 4 blocks.
+			System::set_block_number(9);
+			assert_eq!(Oracle::twap(&0), Some(25));
+
+			// 10 for 1 block, then 40 for 9 blocks, the window starting at block 4.
+			System::set_block_number(14);
+			assert_ok!(Oracle::feed_values(Origin::signed(1), vec![(0, 40)]));
+			assert_eq!(Oracle::history(&0).len(), 3);
+			assert_eq!(Oracle::twap(&0), Some(37));
+
+			// the first value is out of the window.
+			System::set_block_number(16);
+			assert_eq!(Oracle::twap(&0), Some(40));
+
+			System::set_block_number(24);
+			assert_eq!(Oracle::twap(&0), None);
+		});
+	}
+
+	#[test]
+	fn the_time_weighted_average_is_none_on_overflow() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			assert_ok!(Oracle::feed_values(Origin::signed(1), vec![(0, u64::max_value())]));
+			assert_ok!(Oracle::feed_values(Origin::signed(2), vec![(0, u64::max_value())]));
+			assert_eq!(Oracle::twap(&0), Some(u64::max_value()));
+
+			// the value times its 2 blocks of weight overflows.
+			System::set_block_number(3);
+			assert_eq!(Oracle::twap(&0), None);
+		});
+	}
+
+	#[test]
+	fn unsigned_feeds_are_validated() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			assert_noop!(Oracle::set_key(Origin::signed(4), UintAuthorityId(4)), Error::<Test>::NotOperator);
+			assert_eq!(
+				Oracle::validate_unsigned(External, &signed_feed(1, 0, vec![(0, 10)])),
+				InvalidTransaction::BadProof.into(),
+			);
+			assert_ok!(Oracle::set_key(Origin::signed(1), UintAuthorityId(1)));
+
+			assert_eq!(
+				Oracle::validate_unsigned(External, &signed_feed(1, 1, vec![(0, 10)])),
+				InvalidTransaction::Future.into(),
+			);
+			assert_eq!(
+				Oracle::validate_unsigned(External, &signed_feed(4, 0, vec![(0, 10)])),
+				InvalidTransaction::BadProof.into(),
+			);
+			let other_payload = FeedPayload { operator: 2, nonce: 0, values: vec![(0, 10)] };
+			let forged = UintAuthorityId(1).sign(&other_payload.encode()).unwrap();
+			let payload = FeedPayload { operator: 1, ..other_payload };
+			assert_eq!(
+				Oracle::validate_unsigned(External, &Call::feed_values_unsigned(payload, forged)),
+				InvalidTransaction::BadProof.into(),
+			);
+			assert!(Oracle::validate_unsigned(External, &signed_feed(1, 0, vec![(0, 10)])).is_ok());
+
+			let payload = FeedPayload { operator: 1, nonce: 0, values: vec![(0, 10)] };
+			let signature = UintAuthorityId(1).sign(&payload.encode()).unwrap();
+			assert_noop!(
+				Oracle::feed_values_unsigned(Origin::signed(1), payload.clone(), signature.clone()),
+				BadOrigin,
+			);
+			assert_ok!(Oracle::feed_values_unsigned(Origin::NONE, payload.clone(), signature.clone()));
+			assert_eq!(Oracle::raw_value(&1, &0), Some(TimestampedValue { value: 10, block: 1 }));
+			assert_eq!(Oracle::nonce(&1), 1);
+			assert_noop!(
+				Oracle::feed_values_unsigned(Origin::NONE, payload, signature),
+				Error::<Test>::InvalidNonce,
+			);
+			assert_eq!(
+				Oracle::validate_unsigned(External, &signed_feed(1, 0, vec![(0, 10)])),
+				InvalidTransaction::Stale.into(),
+			);
+		});
+	}
+
+	#[test]
+	fn outgoing_operators_lose_their_keys_and_values() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			assert_ok!(Oracle::set_key(Origin::signed(3), UintAuthorityId(3)));
+			assert_ok!(Oracle::feed_values(Origin::signed(3), vec![(0, 10), (1, 20)]));
+			assert_ok!(Oracle::feed_values(Origin::signed(2), vec![(0, 30)]));
+
+			Oracle::change_members(&[4], &[3], vec![4, 2, 1]);
+			assert_eq!(Oracle::operators(), vec![1, 2, 4]);
+			assert_eq!(Oracle::operator_key(&3), None);
+			assert_eq!(Oracle::raw_value(&3, &0), None);
+			assert_eq!(Oracle::raw_value(&3, &1), None);
+			assert_eq!(Oracle::raw_value(&2, &0), Some(TimestampedValue { value: 30, block: 1 }));
+			assert_noop!(Oracle::feed_values(Origin::signed(3), vec![(0, 10)]), Error::<Test>::NotOperator);
+
+			// the values of 3 aren't aggregated even if it becomes an operator again.
+			Oracle::change_members(&[3], &[], vec![1, 2, 3, 4]);
+			assert_ok!(Oracle::feed_values(Origin::signed(1), vec![(1, 40)]));
+			assert_eq!(Oracle::value(&1), None);
+		});
+	}
+
+	#[test]
+	fn feeds_are_bounded() {
+		new_test_ext().execute_with(|| {
+			System::set_block_number(1);
+			let values = vec![(0, 10), (1, 10), (2, 10), (3, 10)];
+			assert_noop!(
+				Oracle::feed_values(Origin::signed(1), values.clone()),
+				Error::<Test>::TooManyValues,
+			);
+
+			assert_ok!(Oracle::set_key(Origin::signed(1), UintAuthorityId(1)));
+			assert_eq!(
+				Oracle::validate_unsigned(External, &signed_feed(1, 0, values.clone())),
+				InvalidTransaction::ExhaustsResources.into(),
+			);
+			let payload = FeedPayload { operator: 1, nonce: 0, values: values.clone() };
+			let signature = UintAuthorityId(1).sign(&payload.encode()).unwrap();
+			assert_noop!(
+				Oracle::feed_values_unsigned(Origin::NONE, payload, signature),
+				Error::<Test>::TooManyValues,
+			);
+
+			// the weight grows with the number of values.
+			let call = Call::<Test>::feed_values(values[..1].to_vec());
+			let more = Call::<Test>::feed_values(values[..3].to_vec());
+			assert!(call.get_dispatch_info().weight < more.get_dispatch_info().weight);
+		});
+	}
+}
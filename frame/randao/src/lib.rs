@@ -0,0 +1,559 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # RANDAO Module
+//!
+//! - [`randao::Trait`](./trait.Trait.html)
+//! - [`Call`](./enum.Call.html)
+//!
+//! ## Overview
+//!
+//! The RANDAO module provides randomness generated by a set of participants rather than by the
+//! block authors. The participants bond some funds to register, then the randomness is built in
+//! rounds: during the first `CommitPeriod` blocks of a round the participants commit to a secret
+//! by posting its hash, and during the next `RevealPeriod` blocks they reveal the secret.
+//!
+//! The revealed secrets are XORed together, and at the end of the round the result is mixed into
+//! the random seed. A participant who committed and didn't reveal is slashed `NonRevealPenalty`
+//! out of its bond and deregistered, since withholding a secret is the one way to bias the
+//! output.
+//!
+//! The output is as good as a single honest participant: it can't be predicted as long as one
+//! of the revealed secrets is unknown to the others, though the last revealer may still choose
+//! between two outputs by withholding its secret, at the cost of the penalty.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! * `register` - Bond `ParticipantBond` to become a participant.
+//! * `unregister` - Stop being a participant, unbonding.
+//! * `commit` - Commit to a secret during the commit phase of a round.
+//! * `reveal` - Reveal the secret committed to during the reveal phase of the round.
+//!
+//! The module implements the `Randomness` trait, drawing from the seed of the last round.
+//!
+//! [`Call`]: ./enum.Call.html
+//! [`Trait`]: ./trait.Trait.html
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::prelude::*;
+use codec::Encode;
+use sp_runtime::traits::{Hash, Saturating, Zero};
+use frame_support::{
+	decl_module, decl_event, decl_storage, decl_error, ensure,
+	traits::{Currency, ReservableCurrency, OnUnbalanced, Get, Randomness},
+	weights::SimpleDispatchInfo,
+};
+use frame_system::{self as system, ensure_signed};
+
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
+type NegativeImbalanceOf<T> = <<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::NegativeImbalance;
+
+/// The index of a round.
+pub type RoundIndex = u32;
+
+pub trait Trait: frame_system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// The currency the participants bond.
+	type Currency: ReservableCurrency<Self::AccountId>;
+
+	/// The amount bonded to become a participant.
+	type ParticipantBond: Get<BalanceOf<Self>>;
+
+	/// The amount slashed from a participant who doesn't reveal its secret.
+	type NonRevealPenalty: Get<BalanceOf<Self>>;
+
+	/// What to do with slashed funds.
+	type Slashed: OnUnbalanced<NegativeImbalanceOf<Self>>;
+
+	/// The number of blocks of the commit phase of a round.
+	type CommitPeriod: Get<Self::BlockNumber>;
+
+	/// The number of blocks of the reveal phase of a round.
+	type RevealPeriod: Get<Self::BlockNumber>;
+
+	/// The maximum number of participants.
+	type MaxParticipants: Get<u32>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Randao {
+		/// The registered participants, sorted.
+		pub Participants get(fn participants): Vec<T::AccountId>;
+
+		/// The index of the current round.
+		pub Round get(fn round): RoundIndex;
+
+		/// The block the current round started at.
+		pub RoundStart get(fn round_start): T::BlockNumber;
+
+		/// The commitments of the current round which aren't revealed yet.
+		pub Commitments get(fn commitment): map hasher(twox_64_concat) T::AccountId => Option<T::Hash>;
+
+		/// The participants who committed in the current round, sorted.
+		Committers: Vec<T::AccountId>;
+
+		/// The XOR of the secrets revealed in the current round, along with their number.
+		Aggregate: (T::Hash, u32);
+
+		/// The random seed output by the last round with a reveal.
+		pub RandomSeed get(fn random_seed_value): T::Hash;
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where
+		AccountId = <T as frame_system::Trait>::AccountId,
+		Balance = BalanceOf<T>,
+		Hash = <T as frame_system::Trait>::Hash,
+	{
+		/// A participant registered.
+		Registered(AccountId),
+		/// A participant unregistered.
+		Unregistered(AccountId),
+		/// A participant committed to a secret.
+		Committed(AccountId),
+		/// A participant revealed its secret.
+		Revealed(AccountId),
+		/// A participant didn't reveal its secret, was slashed the given amount and deregistered.
+		NonRevealerSlashed(AccountId, Balance),
+		/// A round completed with the given number of reveals, and the given random seed.
+		RoundCompleted(RoundIndex, u32, Hash),
+	}
+);
+
+decl_error! {
+	/// Error for the RANDAO module.
+	pub enum Error for Module<T: Trait> {
+		/// The account is already a participant.
+		AlreadyRegistered,
+		/// The account isn't a participant.
+		NotRegistered,
+		/// There are already `MaxParticipants` participants.
+		TooManyParticipants,
+		/// The participant committed in the current round, and must reveal first.
+		PendingReveal,
+		/// The current round isn't in its commit phase.
+		NotCommitPhase,
+		/// The current round isn't in its reveal phase.
+		NotRevealPhase,
+		/// The participant already committed in the current round.
+		AlreadyCommitted,
+		/// The participant has no commitment to reveal.
+		NoCommitment,
+		/// The secret doesn't match the commitment.
+		InvalidReveal,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		fn deposit_event() = default;
+
+		/// The amount bonded to become a participant.
+		const ParticipantBond: BalanceOf<T> = T::ParticipantBond::get();
+
+		/// The amount slashed from a participant who doesn't reveal its secret.
+		const NonRevealPenalty: BalanceOf<T> = T::NonRevealPenalty::get();
+
+		/// The number of blocks of the commit phase of a round.
+		const CommitPeriod: T::BlockNumber = T::CommitPeriod::get();
+
+		/// The number of blocks of the reveal phase of a round.
+		const RevealPeriod: T::BlockNumber = T::RevealPeriod::get();
+
+		/// The maximum number of participants.
+		const MaxParticipants: u32 = T::MaxParticipants::get();
+
+		fn on_initialize(now: T::BlockNumber) {
+			let round_length = T::CommitPeriod::get().saturating_add(T::RevealPeriod::get());
+			if now.saturating_sub(Self::round_start()) >= round_length {
+				Self::complete_round();
+				RoundStart::<T>::put(now);
+			}
+		}
+
+		/// Become a participant, reserving `ParticipantBond`.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// # <weight>
+		/// - O(P) where P is the number of participants.
+		/// - One balance reserve operation.
+		/// - One storage read and write.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn register(origin) {
+			let who = ensure_signed(origin)?;
+
+			let mut participants = Self::participants();
+			let index = participants.binary_search(&who).err().ok_or(Error::<T>::AlreadyRegistered)?;
+			ensure!(
+				(participants.len() as u32) < T::MaxParticipants::get(),
+				Error::<T>::TooManyParticipants,
+			);
+			T::Currency::reserve(&who, T::ParticipantBond::get())?;
+
+			participants.insert(index, who.clone());
+			<Participants<T>>::put(participants);
+
+			Self::deposit_event(RawEvent::Registered(who));
+		}
+
+		/// Stop being a participant, unreserving the bond. A participant who committed in the
+		/// current round must wait for the round to complete.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// # <weight>
+		/// - O(P) where P is the number of participants.
+		/// - One balance unreserve operation.
+		/// - Two storage reads and one storage write.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn unregister(origin) {
+			let who = ensure_signed(origin)?;
+
+			let mut participants = Self::participants();
+			let index = participants.binary_search(&who).ok().ok_or(Error::<T>::NotRegistered)?;
+			ensure!(
+				<Committers<T>>::get().binary_search(&who).is_err(),
+				Error::<T>::PendingReveal,
+			);
+
+			participants.remove(index);
+			<Participants<T>>::put(participants);
+			T::Currency::unreserve(&who, T::ParticipantBond::get());
+
+			Self::deposit_event(RawEvent::Unregistered(who));
+		}
+
+		/// Commit to a secret in the current round, with the hash of the encoding of the secret
+		/// followed by the account of the participant.
+		///
+		/// The dispatch origin for this call must be _Signed_ by a participant, during the commit
+		/// phase of the round.
+		///
+		/// # <weight>
+		/// - O(P) where P is the number of participants.
+		/// - Three storage reads and two storage writes.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn commit(origin, commitment: T::Hash) {
+			let who = ensure_signed(origin)?;
+
+			ensure!(Self::participants().binary_search(&who).is_ok(), Error::<T>::NotRegistered);
+			ensure!(Self::elapsed() < T::CommitPeriod::get(), Error::<T>::NotCommitPhase);
+
+			let mut committers = <Committers<T>>::get();
+			let index = committers.binary_search(&who).err().ok_or(Error::<T>::AlreadyCommitted)?;
+			committers.insert(index, who.clone());
+			<Committers<T>>::put(committers);
+			<Commitments<T>>::insert(&who, commitment);
+
+			Self::deposit_event(RawEvent::Committed(who));
+		}
+
+		/// Reveal the secret committed to in the current round.
+		///
+		/// The dispatch origin for this call must be _Signed_ by a participant who committed,
+		/// during the reveal phase of the round.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - Two storage reads and two storage writes.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn reveal(origin, secret: T::Hash) {
+			let who = ensure_signed(origin)?;
+
+			ensure!(Self::elapsed() >= T::CommitPeriod::get(), Error::<T>::NotRevealPhase);
+			let commitment = <Commitments<T>>::get(&who).ok_or(Error::<T>::NoCommitment)?;
+			ensure!(commitment == Self::commitment_of(&secret, &who), Error::<T>::InvalidReveal);
+
+			<Commitments<T>>::remove(&who);
+			<Aggregate<T>>::mutate(|(aggregate, reveals)| {
+				*aggregate = *aggregate ^ secret;
+				*reveals += 1;
+			});
+
+			Self::deposit_event(RawEvent::Revealed(who));
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The commitment of `who` to `secret`.
+	pub fn commitment_of(secret: &T::Hash, who: &T::AccountId) -> T::Hash {
+		(secret, who).using_encoded(T::Hashing::hash)
+	}
+
+	/// The number of blocks elapsed since the start of the current round.
+	fn elapsed() -> T::BlockNumber {
+		<frame_system::Module<T>>::block_number().saturating_sub(Self::round_start())
+	}
+
+	/// Slash the participants who didn't reveal, mix the revealed secrets into the random seed
+	/// and start the next round.
+	fn complete_round() {
+		let mut participants = Self::participants();
+		for who in <Committers<T>>::take() {
+			if <Commitments<T>>::take(&who).is_none() {
+				continue;
+			}
+
+			let penalty = T::NonRevealPenalty::get();
+			let (imbalance, _) = T::Currency::slash_reserved(&who, penalty);
+			T::Slashed::on_unbalanced(imbalance);
+			T::Currency::unreserve(&who, T::ParticipantBond::get().saturating_sub(penalty));
+			if let Ok(index) = participants.binary_search(&who) {
+				participants.remove(index);
+			}
+
+			Self::deposit_event(RawEvent::NonRevealerSlashed(who, penalty));
+		}
+		<Participants<T>>::put(participants);
+
+		let round = Self::round();
+		let (aggregate, reveals) = <Aggregate<T>>::take();
+		if !reveals.is_zero() {
+			let seed = (Self::random_seed_value(), aggregate, round).using_encoded(T::Hashing::hash);
+			<RandomSeed<T>>::put(seed);
+		}
+		Round::put(round.wrapping_add(1));
+
+		Self::deposit_event(RawEvent::RoundCompleted(round, reveals, Self::random_seed_value()));
+	}
+}
+
+impl<T: Trait> Randomness<T::Hash> for Module<T> {
+	/// Get a random value out of the seed of the last round with a reveal.
+	///
+	/// ### Security Notes
+	///
+	/// The seed only changes once per round, so the value of a subject is known to everyone
+	/// from the beginning of the round until its end. Name, in advance, the round whose seed
+	/// will be used, rather than drawing from the current one.
+	fn random(subject: &[u8]) -> T::Hash {
+		(subject, Self::random_seed_value()).using_encoded(T::Hashing::hash)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use frame_support::{
+		assert_ok, assert_noop, impl_outer_origin, parameter_types, weights::Weight,
+		traits::OnInitialize,
+	};
+	use sp_core::H256;
+	use sp_runtime::{Perbill, testing::Header, traits::{BlakeTwo256, IdentityLookup}};
+
+	impl_outer_origin! {
+		pub enum Origin for Test  where system = frame_system {}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::one();
+	}
+	impl frame_system::Trait for Test {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Call = ();
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+		type ModuleToIndex = ();
+		type AccountData = pallet_balances::AccountData<u64>;
+		type MigrateAccount = (); type MigrateAccountData = (); type OnNewAccount = ();
+		type OnKilledAccount = ();
+	}
+	parameter_types! {
+		pub const ExistentialDeposit: u64 = 1;
+	}
+	impl pallet_balances::Trait for Test {
+		type Balance = u64;
+		type Event = ();
+		type DustRemoval = ();
+		type ExistentialDeposit = ExistentialDeposit;
+		type AccountStore = System;
+	}
+	parameter_types! {
+		pub const ParticipantBond: u64 = 10;
+		pub const NonRevealPenalty: u64 = 4;
+		pub const CommitPeriod: u64 = 5;
+		pub const RevealPeriod: u64 = 5;
+		pub const MaxParticipants: u32 = 3;
+	}
+	impl Trait for Test {
+		type Event = ();
+		type Currency = Balances;
+		type ParticipantBond = ParticipantBond;
+		type NonRevealPenalty = NonRevealPenalty;
+		type Slashed = ();
+		type CommitPeriod = CommitPeriod;
+		type RevealPeriod = RevealPeriod;
+		type MaxParticipants = MaxParticipants;
+	}
+	type System = frame_system::Module<Test>;
+	type Balances = pallet_balances::Module<Test>;
+	type Randao = Module<Test>;
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		pallet_balances::GenesisConfig::<Test> {
+			balances: vec![(1, 100), (2, 100), (3, 100), (4, 100), (5, 5)],
+		}.assimilate_storage(&mut t).unwrap();
+		t.into()
+	}
+
+	fn run_to_block(n: u64) {
+		while System::block_number() < n {
+			System::set_block_number(System::block_number() + 1);
+			Randao::on_initialize(System::block_number());
+		}
+	}
+
+	fn secret(byte: u8) -> H256 {
+		H256::repeat_byte(byte)
+	}
+
+	#[test]
+	fn registration_works() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Randao::register(Origin::signed(2)));
+			assert_ok!(Randao::register(Origin::signed(1)));
+			assert_noop!(Randao::register(Origin::signed(1)), Error::<Test>::AlreadyRegistered);
+			assert_noop!(
+				Randao::register(Origin::signed(5)),
+				pallet_balances::Error::<Test, _>::InsufficientBalance,
+			);
+			assert_ok!(Randao::register(Origin::signed(3)));
+			assert_noop!(Randao::register(Origin::signed(4)), Error::<Test>::TooManyParticipants);
+			assert_eq!(Randao::participants(), vec![1, 2, 3]);
+			assert_eq!(Balances::reserved_balance(1), 10);
+
+			assert_ok!(Randao::commit(Origin::signed(1), Randao::commitment_of(&secret(1), &1)));
+			assert_noop!(Randao::unregister(Origin::signed(1)), Error::<Test>::PendingReveal);
+			assert_noop!(Randao::unregister(Origin::signed(4)), Error::<Test>::NotRegistered);
+			assert_ok!(Randao::unregister(Origin::signed(2)));
+			assert_eq!(Randao::participants(), vec![1, 3]);
+			assert_eq!(Balances::reserved_balance(2), 0);
+		});
+	}
+
+	#[test]
+	fn commit_and_reveal_follow_the_phases() {
+		new_test_ext().execute_with(|| {
+			run_to_block(1);
+			assert_ok!(Randao::register(Origin::signed(1)));
+
+			let commitment = Randao::commitment_of(&secret(1), &1);
+			assert_noop!(
+				Randao::commit(Origin::signed(2), commitment),
+				Error::<Test>::NotRegistered,
+			);
+			assert_noop!(Randao::reveal(Origin::signed(1), secret(1)), Error::<Test>::NotRevealPhase);
+			assert_ok!(Randao::commit(Origin::signed(1), commitment));
+			assert_noop!(
+				Randao::commit(Origin::signed(1), commitment),
+				Error::<Test>::AlreadyCommitted,
+			);
+
+			run_to_block(5);
+			assert_noop!(Randao::commit(Origin::signed(1), commitment), Error::<Test>::NotCommitPhase);
+			assert_noop!(Randao::reveal(Origin::signed(1), secret(2)), Error::<Test>::InvalidReveal);
+			assert_noop!(Randao::reveal(Origin::signed(2), secret(1)), Error::<Test>::NoCommitment);
+			assert_ok!(Randao::reveal(Origin::signed(1), secret(1)));
+			assert_noop!(Randao::reveal(Origin::signed(1), secret(1)), Error::<Test>::NoCommitment);
+		});
+	}
+
+	#[test]
+	fn reveals_are_mixed_into_the_seed() {
+		new_test_ext().execute_with(|| {
+			for who in 1..=2 {
+				assert_ok!(Randao::register(Origin::signed(who)));
+				assert_ok!(Randao::commit(
+					Origin::signed(who),
+					Randao::commitment_of(&secret(who as u8), &who),
+				));
+			}
+			run_to_block(5);
+			assert_ok!(Randao::reveal(Origin::signed(1), secret(1)));
+			assert_ok!(Randao::reveal(Origin::signed(2), secret(2)));
+
+			run_to_block(10);
+			let seed = (H256::zero(), secret(3), 0u32).using_encoded(BlakeTwo256::hash);
+			assert_eq!(Randao::round(), 1);
+			assert_eq!(Randao::round_start(), 10);
+			assert_eq!(Randao::random_seed_value(), seed);
+			assert_eq!(Randao::random(b"subject"), (&b"subject"[..], seed).using_encoded(BlakeTwo256::hash));
+
+			// a round without reveals keeps the seed.
+			run_to_block(20);
+			assert_eq!(Randao::round(), 2);
+			assert_eq!(Randao::random_seed_value(), seed);
+		});
+	}
+
+	#[test]
+	fn non_revealers_are_slashed() {
+		new_test_ext().execute_with(|| {
+			for who in 1..=2 {
+				assert_ok!(Randao::register(Origin::signed(who)));
+				assert_ok!(Randao::commit(
+					Origin::signed(who),
+					Randao::commitment_of(&secret(who as u8), &who),
+				));
+			}
+			run_to_block(5);
+			assert_ok!(Randao::reveal(Origin::signed(1), secret(1)));
+
+			run_to_block(10);
+			assert_eq!(Randao::participants(), vec![1]);
+			assert_eq!(Balances::reserved_balance(2), 0);
+			assert_eq!(Balances::free_balance(2), 96);
+			assert_eq!(Balances::reserved_balance(1), 10);
+			assert_eq!(Randao::commitment(&2), None);
+		});
+	}
+}
@@ -76,6 +76,16 @@
 //! 10. When the recovered account becomes reaped (i.e. its free and reserved
 //!     balance drops to zero), the final recovery link is removed.
 //!
+//! ### Call Filters
+//!
+//! The calls a rescuer is able to make through `as_recovered` are restricted by the
+//! call filter of the recovered account, which the account owner can set ahead of
+//! time with `set_call_filter`. Accounts which never set a call filter use the default
+//! one of the runtime. A rescuer can never give themselves more power by changing the
+//! call filter of the recovered account to one which allows more calls than the current
+//! one, and every call made through `as_recovered` emits an event identifying the
+//! rescuer along with the result of the call.
+//!
 //! ### Malicious Recovery Attempts
 //!
 //! Initializing a the recovery process for a recoverable account is open and
@@ -127,6 +137,7 @@
 //! #### For General Users
 //!
 //! * `create_recovery` - Create a recovery configuration for your account and make it recoverable.
+//! * `set_call_filter` - Restrict the calls a rescuer can make through your account once recovered.
 //! * `initiate_recovery` - Start the recovery process for a recoverable account.
 //!
 //! #### For Friends of a Recoverable Account
@@ -152,15 +163,15 @@
 
 use sp_std::prelude::*;
 use sp_runtime::{
-	traits::{Dispatchable, SaturatedConversion, CheckedAdd, CheckedMul},
+	traits::{Dispatchable, SaturatedConversion, CheckedAdd, CheckedMul, Member},
 	DispatchResult
 };
 use codec::{Encode, Decode};
 
 use frame_support::{
 	decl_module, decl_event, decl_storage, decl_error, ensure,
-	Parameter, RuntimeDebug, weights::{GetDispatchInfo, SimpleDispatchInfo, FunctionOf},
-	traits::{Currency, ReservableCurrency, Get, BalanceStatus},
+	Parameter, RuntimeDebug, IsSubType, weights::{GetDispatchInfo, SimpleDispatchInfo, FunctionOf},
+	traits::{Currency, ReservableCurrency, Get, BalanceStatus, InstanceFilter},
 };
 use frame_system::{self as system, ensure_signed, ensure_root};
 use frame_support::traits::MigrateAccount;
@@ -179,7 +190,8 @@ pub trait Trait: frame_system::Trait {
 	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
 
 	/// The overarching call type.
-	type Call: Parameter + Dispatchable<Origin=Self::Origin> + GetDispatchInfo;
+	type Call: Parameter + Dispatchable<Origin=Self::Origin> + GetDispatchInfo
+		+ IsSubType<Module<Self>, Self>;
 
 	/// The currency mechanism.
 	type Currency: ReservableCurrency<Self::AccountId>;
@@ -206,6 +218,13 @@ pub trait Trait: frame_system::Trait {
 	/// `sizeof(BlockNumber, Balance + T * AccountId)` bytes. Where T is a configurable
 	/// threshold.
 	type RecoveryDeposit: Get<BalanceOf<Self>>;
+
+	/// The kinds of calls a rescuer may make through a recovered account.
+	///
+	/// The default value is used for the accounts which did not set any call filter,
+	/// and should not allow a rescuer to do more than reclaiming the funds of the
+	/// recovered account.
+	type CallFilter: Parameter + Member + Default + InstanceFilter<<Self as Trait>::Call>;
 }
 
 /// An active recovery process.
@@ -255,6 +274,10 @@ decl_storage! {
 		/// Map from the user who can access it to the recovered account.
 		pub Proxy get(fn proxy):
 			map hasher(blake2_128_concat) T::AccountId => Option<T::AccountId>;
+
+		/// The call filters the rescuers of the accounts are subject to.
+		pub CallFilters get(fn call_filter):
+			map hasher(blake2_128_concat) T::AccountId => T::CallFilter;
 	}
 }
 
@@ -262,6 +285,7 @@ impl<T: Trait> MigrateAccount<T::AccountId> for Module<T> {
 	fn migrate_account(a: &T::AccountId) {
 		Recoverable::<T>::migrate_key_from_blake(a);
 		Proxy::<T>::migrate_key_from_blake(a);
+		CallFilters::<T>::migrate_key_from_blake(a);
 	}
 }
 
@@ -269,6 +293,7 @@ decl_event! {
 	/// Events type.
 	pub enum Event<T> where
 		AccountId = <T as system::Trait>::AccountId,
+		CallFilter = <T as Trait>::CallFilter,
 	{
 		/// A recovery process has been set up for an account
 		RecoveryCreated(AccountId),
@@ -282,6 +307,10 @@ decl_event! {
 		AccountRecovered(AccountId, AccountId),
 		/// A recovery process has been removed for an account
 		RecoveryRemoved(AccountId),
+		/// The call filter of an account has been set
+		CallFilterSet(AccountId, CallFilter),
+		/// A call has been made through account_1 by its rescuer account_2, with the given result
+		RecoveredCallExecuted(AccountId, AccountId, DispatchResult),
 	}
 }
 
@@ -319,6 +348,8 @@ decl_error! {
 		Overflow,
 		/// This account is already set up for recovery
 		AlreadyProxy,
+		/// The call filter of the recovered account does not allow this call
+		Filtered,
 	}
 }
 
@@ -334,13 +365,21 @@ decl_module! {
 		/// The dispatch origin for this call must be _Signed_ and registered to
 		/// be able to make calls on behalf of the recovered account.
 		///
+		/// The call must be allowed by the call filter of the recovered account. If it
+		/// sets a new call filter for the recovered account, this one must not allow more
+		/// calls than the current one.
+		///
 		/// Parameters:
 		/// - `account`: The recovered account you want to make a call on-behalf-of.
 		/// - `call`: The call you want to make with the recovered account.
 		///
+		/// Emits `RecoveredCallExecuted` with the result of the call, which is returned as well.
+		///
 		/// # <weight>
 		/// - The weight of the `call` + 10,000.
 		/// - One storage lookup to check account is recovered by `who`. O(1)
+		/// - One storage lookup to get the call filter of the account. O(1)
+		/// - One event.
 		/// # </weight>
 		#[weight = FunctionOf(
 			|args: (&T::AccountId, &Box<<T as Trait>::Call>)| args.1.get_dispatch_info().weight + 10_000,
@@ -355,7 +394,15 @@ decl_module! {
 			// Check `who` is allowed to make a call on behalf of `account`
 			let target = Self::proxy(&who).ok_or(Error::<T>::NotAllowed)?;
 			ensure!(&target == &account, Error::<T>::NotAllowed);
-			call.dispatch(frame_system::RawOrigin::Signed(account).into())
+			// Check the call is allowed by the call filter of `account`, and doesn't widen it
+			let filter = Self::call_filter(&account);
+			ensure!(filter.filter(&*call), Error::<T>::Filtered);
+			if let Some(Call::set_call_filter(new_filter)) = call.is_sub_type() {
+				ensure!(filter.is_superset(new_filter), Error::<T>::Filtered);
+			}
+			let result = call.dispatch(frame_system::RawOrigin::Signed(account.clone()).into());
+			Self::deposit_event(RawEvent::RecoveredCallExecuted(account, who, result.clone()));
+			result
 		}
 
 		/// Allow ROOT to bypass the recovery process and set an a rescuer account
@@ -444,6 +491,25 @@ decl_module! {
 			Self::deposit_event(RawEvent::RecoveryCreated(who));
 		}
 
+		/// Set the call filter of your account, restricting the calls its rescuers can make
+		/// through `as_recovered`.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// Parameters:
+		/// - `filter`: The kinds of calls the rescuers of your account are able to make.
+		///
+		/// # <weight>
+		/// - One storage write. O(1)
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(10_000)]
+		fn set_call_filter(origin, filter: T::CallFilter) {
+			let who = ensure_signed(origin)?;
+			<CallFilters<T>>::insert(&who, &filter);
+			Self::deposit_event(RawEvent::CallFilterSet(who, filter));
+		}
+
 		/// Initiate the process for recovering a recoverable account.
 		///
 		/// Payment: `RecoveryDeposit` balance will be reserved for initiating the
@@ -20,7 +20,7 @@ use super::*;
 
 use frame_support::{
 	impl_outer_origin, impl_outer_dispatch, impl_outer_event, parameter_types,
-	weights::Weight,
+	weights::Weight, traits::InstanceFilter,
 };
 use sp_core::H256;
 // The testing primitives are very useful for avoiding having to work with signatures
@@ -79,7 +79,7 @@ impl frame_system::Trait for Test {
 	type Version = ();
 	type ModuleToIndex = ();
 	type AccountData = pallet_balances::AccountData<u128>;
-	type MigrateAccount = (); type OnNewAccount = ();
+	type MigrateAccount = (); type MigrateAccountData = (); type OnNewAccount = ();
 	type OnKilledAccount = ();
 }
 
@@ -102,6 +102,34 @@ parameter_types! {
 	pub const RecoveryDeposit: u64 = 10;
 }
 
+/// The call filter of the accounts: rescuers may either make any call, or only the calls of
+/// the recovery module.
+#[derive(Copy, Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub enum CallFilter {
+	Any,
+	RecoveryOnly,
+}
+
+impl Default for CallFilter {
+	fn default() -> Self { CallFilter::Any }
+}
+
+impl InstanceFilter<Call> for CallFilter {
+	fn filter(&self, c: &Call) -> bool {
+		match self {
+			CallFilter::Any => true,
+			CallFilter::RecoveryOnly => match c {
+				Call::Recovery(..) => true,
+				_ => false,
+			},
+		}
+	}
+
+	fn is_superset(&self, o: &Self) -> bool {
+		self == o || *self == CallFilter::Any
+	}
+}
+
 impl Trait for Test {
 	type Event = TestEvent;
 	type Call = Call;
@@ -110,6 +138,7 @@ impl Trait for Test {
 	type FriendDepositFactor = FriendDepositFactor;
 	type MaxFriends = MaxFriends;
 	type RecoveryDeposit = RecoveryDeposit;
+	type CallFilter = CallFilter;
 }
 
 pub type Recovery = Module<Test>;
@@ -18,10 +18,10 @@
 
 use super::*;
 use mock::{
-	Recovery, Balances, Test, Origin, Call, BalancesCall, RecoveryCall,
-	new_test_ext, run_to_block
+	Recovery, Balances, System, Test, Origin, Call, BalancesCall, RecoveryCall, CallFilter,
+	TestEvent, new_test_ext, run_to_block
 };
-use sp_runtime::traits::{BadOrigin};
+use sp_runtime::{DispatchError, traits::{BadOrigin}};
 use frame_support::{
 	assert_noop, assert_ok,
 	traits::{Currency},
@@ -55,6 +55,54 @@ fn set_recovered_works() {
 	});
 }
 
+#[test]
+fn as_recovered_respects_call_filter() {
+	new_test_ext().execute_with(|| {
+		// Account 5 only allows its rescuers to make calls to the recovery module
+		assert_ok!(Recovery::set_call_filter(Origin::signed(5), CallFilter::RecoveryOnly));
+		assert_eq!(Recovery::call_filter(&5), CallFilter::RecoveryOnly);
+		assert_ok!(Recovery::set_recovered(Origin::ROOT, 5, 1));
+		// Account 1 cannot drain the funds of account 5
+		let call = Box::new(Call::Balances(BalancesCall::transfer(1, 100)));
+		assert_noop!(Recovery::as_recovered(Origin::signed(1), 5, call), Error::<Test>::Filtered);
+		// Nor widen the call filter of account 5
+		let call = Box::new(Call::Recovery(RecoveryCall::set_call_filter(CallFilter::Any)));
+		assert_noop!(Recovery::as_recovered(Origin::signed(1), 5, call), Error::<Test>::Filtered);
+		// But it can make the calls allowed by the call filter
+		let call = Box::new(Call::Recovery(RecoveryCall::set_call_filter(CallFilter::RecoveryOnly)));
+		assert_ok!(Recovery::as_recovered(Origin::signed(1), 5, call));
+		// Account 5 can still widen its own call filter
+		assert_ok!(Recovery::set_call_filter(Origin::signed(5), CallFilter::Any));
+		let call = Box::new(Call::Balances(BalancesCall::transfer(1, 100)));
+		assert_ok!(Recovery::as_recovered(Origin::signed(1), 5, call));
+		assert_eq!(Balances::free_balance(1), 200);
+	});
+}
+
+#[test]
+fn as_recovered_emits_the_result_of_the_call() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Recovery::set_recovered(Origin::ROOT, 5, 1));
+		let call = Box::new(Call::Balances(BalancesCall::transfer(1, 50)));
+		assert_ok!(Recovery::as_recovered(Origin::signed(1), 5, call));
+		assert_eq!(
+			System::events().last().unwrap().event,
+			TestEvent::recovery(RawEvent::RecoveredCallExecuted(5, 1, Ok(()))),
+		);
+		// A failing call fails `as_recovered` and is reported in the event
+		let call = Box::new(Call::Balances(BalancesCall::transfer(1, 100)));
+		let err = DispatchError::from(pallet_balances::Error::<Test, _>::InsufficientBalance);
+		assert_eq!(Recovery::as_recovered(Origin::signed(1), 5, call), Err(err));
+		let err = err.stripped();
+		assert_eq!(
+			System::events().last().unwrap().event,
+			TestEvent::recovery(RawEvent::RecoveredCallExecuted(5, 1, Err(err))),
+		);
+		assert_eq!(Balances::free_balance(5), 50);
+	});
+}
+
 #[test]
 fn recovery_life_cycle_works() {
 	new_test_ext().execute_with(|| {
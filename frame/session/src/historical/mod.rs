@@ -24,18 +24,28 @@
 //!
 //! These roots and proofs of inclusion can be generated at any time during the current session.
 //! Afterwards, the proofs can be fed to a consensus module when reporting misbehavior.
+//!
+//! The validators of each stored session are kept along with their keys, so that the proofs can
+//! also be generated for any historical session until it is pruned. Their full identification in
+//! that session is not duplicated here, but given by `HistoricalFullIdentificationOf`.
+//!
+//! Only the root of the trie of each new validator set is computed in the block, natively through
+//! `Hash::trie_root`. Building the trie to generate a proof is costly for a large validator set, so
+//! the [`offchain`](./offchain/index.html) module builds and stores the tries in an offchain worker
+//! instead, and the proofs are generated from them out of the blocks.
 
 use sp_std::prelude::*;
 use codec::{Encode, Decode};
 use sp_runtime::{KeyTypeId, RuntimeDebug};
-use sp_runtime::traits::{Convert, OpaqueKeys};
+use sp_runtime::traits::{Convert, Hash, OpaqueKeys};
 use frame_support::{decl_module, decl_storage};
-use frame_support::{Parameter, print};
+use frame_support::Parameter;
 use sp_trie::{MemoryDB, Trie, TrieMut, Recorder, EMPTY_PREFIX};
 use sp_trie::trie_types::{TrieDBMut, TrieDB};
+use sp_session::{GetSessionNumber, GetValidatorCount, ValidatorCount};
 use super::{SessionIndex, Module as SessionModule};
 
-type ValidatorCount = u32;
+pub mod offchain;
 
 /// Trait necessary for the historical module.
 pub trait Trait: super::Trait {
@@ -50,6 +60,16 @@ pub trait Trait: super::Trait {
 	///
 	/// It must return the identification for the current session index.
 	type FullIdentificationOf: Convert<Self::ValidatorId, Option<Self::FullIdentification>>;
+
+	/// A conversion from a historical session index and a validator ID to the full
+	/// identification of the validator in that session.
+	///
+	/// It must return the identification returned by the `SessionManager` when the session was
+	/// planned, for as long as the session is stored, or no proofs can be generated for it.
+	type HistoricalFullIdentificationOf: Convert<
+		(SessionIndex, Self::ValidatorId),
+		Option<Self::FullIdentification>,
+	>;
 }
 
 decl_storage! {
@@ -59,6 +79,13 @@ decl_storage! {
 			map hasher(twox_64_concat) SessionIndex => Option<(T::Hash, ValidatorCount)>;
 		/// The range of historical sessions we store. [first, last)
 		StoredRange: Option<(SessionIndex, SessionIndex)>;
+		/// The validators of the historical sessions in which the validator set changed, with
+		/// their keys.
+		///
+		/// The sessions in which it didn't change have the validators of the closest previous
+		/// session which has an entry.
+		HistoricalValidators:
+			map hasher(twox_64_concat) SessionIndex => Option<Vec<(T::ValidatorId, T::Keys)>>;
 		/// Deprecated.
 		CachedObsolete:
 			map hasher(twox_64_concat) SessionIndex
@@ -106,6 +133,16 @@ impl<T: Trait> Module<T> {
 
 			(start..up_to).for_each(<Self as Store>::HistoricalSessions::remove);
 
+			// the validators of the first kept session may be those of a pruned one.
+			let last_pruned_validators = (start..up_to)
+				.filter_map(<Self as Store>::HistoricalValidators::take)
+				.last();
+			if let Some(validators) = last_pruned_validators {
+				if up_to < end && !<Self as Store>::HistoricalValidators::contains_key(up_to) {
+					<Self as Store>::HistoricalValidators::insert(up_to, validators);
+				}
+			}
+
 			let new_start = up_to;
 			*range = if new_start == end {
 				None // nothing is stored.
@@ -114,6 +151,56 @@ impl<T: Trait> Module<T> {
 			}
 		})
 	}
+
+	/// Prove the ownership of a key in the given historical session, as long as it is stored.
+	///
+	/// Unlike `KeyOwnerProofSystem::prove`, which only proves for the current session, the proof
+	/// is generated from the validators as they were when the session was planned. This rebuilds
+	/// the trie of the session: out of the blocks, `offchain::prove_session_membership` proves
+	/// from the trie stored by the offchain worker instead.
+	pub fn prove_historical(session: SessionIndex, key: (KeyTypeId, &[u8])) -> Option<Proof> {
+		let (root, validator_count) = Self::historical_root(session)?;
+		let (start, _) = <Self as Store>::StoredRange::get()?;
+		let (set_session, validators) = (start..=session).rev()
+			.find_map(|s| <Self as Store>::HistoricalValidators::get(s).map(|v| (s, v)))?;
+
+		let trie = ProvingTrie::<T>::generate_for(Self::identify(set_session, validators)).ok()?;
+		if trie.root != root {
+			return None
+		}
+
+		let (id, data) = key;
+		trie.prove(id, data).map(|trie_nodes| Proof {
+			session,
+			trie_nodes,
+			validator_count,
+		})
+	}
+
+	/// Attach their keys to the validators, leaving out those which have none.
+	fn with_keys<I>(validators: I) -> Vec<(T::ValidatorId, T::FullIdentification, T::Keys)>
+		where I: IntoIterator<Item=(T::ValidatorId, T::FullIdentification)>
+	{
+		validators.into_iter()
+			.filter_map(|(validator, full_id)| {
+				<SessionModule<T>>::load_keys(&validator).map(|keys| (validator, full_id, keys))
+			})
+			.collect()
+	}
+
+	/// Attach to the validators stored for the given session their full identification in that
+	/// session, leaving out those which have none.
+	fn identify(
+		session: SessionIndex,
+		validators: Vec<(T::ValidatorId, T::Keys)>,
+	) -> Vec<(T::ValidatorId, T::FullIdentification, T::Keys)> {
+		validators.into_iter()
+			.filter_map(|(validator, keys)| {
+				T::HistoricalFullIdentificationOf::convert((session, validator.clone()))
+					.map(|full_id| (validator, full_id, keys))
+			})
+			.collect()
+	}
 }
 
 /// Specialization of the crate-level `SessionManager` which returns the set of full identification
@@ -145,13 +232,19 @@ impl<T: Trait, I> crate::SessionManager<T::ValidatorId> for NoteHistoricalRoot<T
 
 		if let Some(new_validators) = new_validators_and_id {
 			let count = new_validators.len() as u32;
-			match ProvingTrie::<T>::generate_for(new_validators) {
-				Ok(trie) => <HistoricalSessions<T>>::insert(new_index, &(trie.root, count)),
-				Err(reason) => {
-					print("Failed to generate historical ancestry-inclusion proof.");
-					print(reason);
-				}
-			};
+			let new_validators = <Module<T>>::with_keys(new_validators);
+			// only the root is committed to, the trie itself is built by the offchain worker.
+			let root = T::Hashing::trie_root(
+				ProvingTrie::<T>::entries(new_validators.iter().cloned()),
+			);
+
+			<HistoricalSessions<T>>::insert(new_index, &(root, count));
+			<HistoricalValidators<T>>::insert(
+				new_index,
+				new_validators.into_iter()
+					.map(|(validator, _, keys)| (validator, keys))
+					.collect::<Vec<_>>(),
+			);
 		} else {
 			let previous_index = new_index.saturating_sub(1);
 			if let Some(previous_session) = <HistoricalSessions<T>>::get(previous_index) {
@@ -169,6 +262,18 @@ impl<T: Trait, I> crate::SessionManager<T::ValidatorId> for NoteHistoricalRoot<T
 	}
 }
 
+/// A `HistoricalFullIdentificationOf` conversion for full identifications which don't change
+/// across sessions, from their conversion `C` from validator ID.
+pub struct SessionIndependent<C>(sp_std::marker::PhantomData<C>);
+
+impl<C, V, F> Convert<(SessionIndex, V), Option<F>> for SessionIndependent<C>
+	where C: Convert<V, Option<F>>
+{
+	fn convert((_, validator): (SessionIndex, V)) -> Option<F> {
+		C::convert(validator)
+	}
+}
+
 /// A tuple of the validator's ID and their full identification.
 pub type IdentificationTuple<T> = (<T as crate::Trait>::ValidatorId, <T as Trait>::FullIdentification);
 
@@ -179,36 +284,38 @@ pub struct ProvingTrie<T: Trait> {
 }
 
 impl<T: Trait> ProvingTrie<T> {
+	/// The entries of the trie of the given validators, in insertion order.
+	fn entries<I>(validators: I) -> Vec<(Vec<u8>, Vec<u8>)>
+		where I: IntoIterator<Item=(T::ValidatorId, T::FullIdentification, T::Keys)>
+	{
+		let mut entries = Vec::new();
+		for (i, (validator, full_id, keys)) in validators.into_iter().enumerate() {
+			let i = i as u32;
+			let full_id = (validator, full_id);
+
+			// map each key to the owner index.
+			for key_id in T::Keys::key_ids() {
+				let key = keys.get_raw(*key_id);
+				entries.push(((key_id, key).encode(), i.encode()));
+			}
+
+			// map each owner index to the full identification.
+			entries.push((i.encode(), full_id.encode()));
+		}
+
+		entries
+	}
+
 	fn generate_for<I>(validators: I) -> Result<Self, &'static str>
-		where I: IntoIterator<Item=(T::ValidatorId, T::FullIdentification)>
+		where I: IntoIterator<Item=(T::ValidatorId, T::FullIdentification, T::Keys)>
 	{
 		let mut db = MemoryDB::default();
 		let mut root = Default::default();
 
 		{
 			let mut trie = TrieDBMut::new(&mut db, &mut root);
-			for (i, (validator, full_id)) in validators.into_iter().enumerate() {
-				let i = i as u32;
-				let keys = match <SessionModule<T>>::load_keys(&validator) {
-					None => continue,
-					Some(k) => k,
-				};
-
-				let full_id = (validator, full_id);
-
-				// map each key to the owner index.
-				for key_id in T::Keys::key_ids() {
-					let key = keys.get_raw(*key_id);
-					let res = (key_id, key).using_encoded(|k|
-						i.using_encoded(|v| trie.insert(k, v))
-					);
-
-					let _ = res.map_err(|_| "failed to insert into trie")?;
-				}
-
-				// map each owner index to the full identification.
-				let _ = i.using_encoded(|k| full_id.using_encoded(|v| trie.insert(k, v)))
-					.map_err(|_| "failed to insert into trie")?;
+			for (key, value) in Self::entries(validators) {
+				let _ = trie.insert(&key, &value).map_err(|_| "failed to insert into trie")?;
 			}
 		}
 
@@ -276,6 +383,7 @@ impl<T: Trait> ProvingTrie<T> {
 pub struct Proof {
 	session: SessionIndex,
 	trie_nodes: Vec<Vec<u8>>,
+	validator_count: ValidatorCount,
 }
 
 impl Proof {
@@ -285,6 +393,18 @@ impl Proof {
 	}
 }
 
+impl GetSessionNumber for Proof {
+	fn session(&self) -> SessionIndex {
+		self.session
+	}
+}
+
+impl GetValidatorCount for Proof {
+	fn validator_count(&self) -> ValidatorCount {
+		self.validator_count
+	}
+}
+
 impl<T: Trait, D: AsRef<[u8]>> frame_support::traits::KeyOwnerProofSystem<(KeyTypeId, D)>
 	for Module<T>
 {
@@ -293,18 +413,25 @@ impl<T: Trait, D: AsRef<[u8]>> frame_support::traits::KeyOwnerProofSystem<(KeyTy
 
 	fn prove(key: (KeyTypeId, D)) -> Option<Self::Proof> {
 		let session = <SessionModule<T>>::current_index();
-		let validators = <SessionModule<T>>::validators().into_iter()
+		if let Some(proof) = Self::prove_historical(session, (key.0, key.1.as_ref())) {
+			return Some(proof)
+		}
+
+		let validators = <SessionModule<T>>::validators();
+		let validator_count = validators.len() as ValidatorCount;
+		let validators = validators.into_iter()
 			.filter_map(|validator| {
 				T::FullIdentificationOf::convert(validator.clone())
 					.map(|full_id| (validator, full_id))
 			});
-		let trie = ProvingTrie::<T>::generate_for(validators).ok()?;
+		let trie = ProvingTrie::<T>::generate_for(Self::with_keys(validators)).ok()?;
 
 		let (id, data) = key;
 
 		trie.prove(id, data.as_ref()).map(|trie_nodes| Proof {
 			session,
 			trie_nodes,
+			validator_count,
 		})
 	}
 
@@ -337,7 +464,7 @@ mod tests {
 
 	type Historical = Module<Test>;
 
-	fn new_test_ext() -> sp_io::TestExternalities {
+	pub(crate) fn new_test_ext() -> sp_io::TestExternalities {
 		let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
 		crate::GenesisConfig::<Test> {
 			keys: NEXT_VALIDATORS.with(|l|
@@ -382,6 +509,43 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn historical_proof_is_good() {
+		new_test_ext().execute_with(|| {
+			set_next_validators(vec![1, 2]);
+			force_new_session();
+
+			System::set_block_number(1);
+			Session::on_initialize(1);
+			let session = Session::current_index();
+
+			for i in 2..5u64 {
+				set_next_validators(vec![2, 3]);
+				force_new_session();
+
+				System::set_block_number(i);
+				Session::on_initialize(i);
+			}
+			assert!(Session::current_index() > session + 1);
+
+			let encoded_key_1 = UintAuthorityId(1).encode();
+			let proof = Historical::prove_historical(session, (DUMMY, &encoded_key_1[..])).unwrap();
+			assert_eq!(proof.session(), session);
+			assert_eq!(
+				Historical::check_proof((DUMMY, &encoded_key_1[..]), proof),
+				Some((1, 1)),
+			);
+
+			// the proofs of the sessions that are still stored can be generated once pruned.
+			let encoded_key_3 = UintAuthorityId(3).encode();
+			let last = Session::current_index();
+			assert!(Historical::prove_historical(last, (DUMMY, &encoded_key_3[..])).is_some());
+			Historical::prune_up_to(last);
+			assert!(Historical::prove_historical(session, (DUMMY, &encoded_key_1[..])).is_none());
+			assert!(Historical::prove_historical(last, (DUMMY, &encoded_key_3[..])).is_some());
+		});
+	}
+
 	#[test]
 	fn prune_up_to_works() {
 		new_test_ext().execute_with(|| {
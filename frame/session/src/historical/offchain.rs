@@ -0,0 +1,188 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Off-chain construction of the historical proving tries.
+//!
+//! The runtime only commits to the root of the trie of each historical session, along with the
+//! validators and keys it is built from. Building the trie of a large validator set is costly, so the
+//! offchain worker builds it once for each stored validator set, checks it against the on-chain
+//! root and keeps its nodes in the persistent offchain storage. The key ownership proofs are then
+//! generated from these nodes instead of rebuilding the trie.
+//!
+//! [`store_session_tries`](./fn.store_session_tries.html) must be called from the offchain worker
+//! of the runtime. [`prove_session_membership`](./fn.prove_session_membership.html) only reads the
+//! offchain database, so it can also be called from the runtime API calls of the node, which are
+//! given read access to it.
+
+use sp_std::prelude::*;
+use codec::Encode;
+use sp_runtime::{KeyTypeId, offchain::storage::StorageValueRef};
+use super::{Trait, Module, Proof, ProvingTrie, StoredRange, HistoricalValidators, SessionIndex};
+
+/// The prefix of the keys of the stored tries.
+const TRIE_PREFIX: &[u8] = b"session_historical::trie";
+
+/// The key of the range of sessions whose tries are stored.
+const STORED_RANGE_KEY: &[u8] = b"session_historical::stored_range";
+
+fn trie_key(session: SessionIndex) -> Vec<u8> {
+	(TRIE_PREFIX, session).encode()
+}
+
+/// Build and store the tries of the sessions stored on-chain since the last call, and forget
+/// those of the pruned sessions.
+pub fn store_session_tries<T: Trait>() {
+	let range = StorageValueRef::persistent(STORED_RANGE_KEY);
+	let (start, end) = match StoredRange::get() {
+		Some(stored) => stored,
+		None => (0, 0),
+	};
+	let (cached_start, cached_end) = range.get::<(SessionIndex, SessionIndex)>()
+		.flatten()
+		.unwrap_or((start, start));
+
+	// the offchain storage can't remove a value, so it is overwritten with an empty one.
+	for session in cached_start..sp_std::cmp::min(start, cached_end) {
+		StorageValueRef::persistent(&trie_key(session)).set(&());
+	}
+
+	// the validators of the first stored session may have been carried over from a pruned one.
+	if start < cached_end && !has_trie::<T>(start) {
+		store_trie::<T>(start);
+	}
+	for session in sp_std::cmp::max(start, cached_end)..end {
+		store_trie::<T>(session);
+	}
+
+	range.set(&(start, end));
+}
+
+/// Prove the ownership of a key in the given historical session from the stored trie of its
+/// validators.
+///
+/// Returns `None` until the offchain worker has stored the trie of the session.
+pub fn prove_session_membership<T: Trait>(
+	session: SessionIndex,
+	key: (KeyTypeId, &[u8]),
+) -> Option<Proof> {
+	let (root, validator_count) = <Module<T>>::historical_root(session)?;
+	let (start, _) = StoredRange::get()?;
+	let trie_session = (start..=session).rev()
+		.find(|s| <HistoricalValidators<T>>::contains_key(s))?;
+
+	let (_, nodes) = StorageValueRef::persistent(&trie_key(trie_session))
+		.get::<(T::Hash, Vec<Vec<u8>>)>()
+		.flatten()
+		.filter(|(stored_root, _)| stored_root == &root)?;
+
+	let (id, data) = key;
+	ProvingTrie::<T>::from_nodes(root, &nodes).prove(id, data).map(|trie_nodes| Proof {
+		session,
+		trie_nodes,
+		validator_count,
+	})
+}
+
+/// Whether the trie of the given session is stored.
+fn has_trie<T: Trait>(session: SessionIndex) -> bool {
+	StorageValueRef::persistent(&trie_key(session))
+		.get::<(T::Hash, Vec<Vec<u8>>)>()
+		.flatten()
+		.is_some()
+}
+
+/// Build the trie of the validators stored for the given session, if any, and store its root and
+/// nodes.
+fn store_trie<T: Trait>(session: SessionIndex) {
+	let validators = match <HistoricalValidators<T>>::get(session) {
+		Some(validators) => <Module<T>>::identify(session, validators),
+		None => return,
+	};
+
+	if let Ok(mut trie) = ProvingTrie::<T>::generate_for(validators) {
+		let nodes = trie.db.drain().into_iter().map(|(_, (node, _))| node).collect::<Vec<_>>();
+		StorageValueRef::persistent(&trie_key(session)).set(&(trie.root, nodes));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use super::super::tests::new_test_ext;
+	use sp_core::crypto::key_types::DUMMY;
+	use sp_core::offchain::{OffchainExt, OffchainStorage, testing::TestOffchainExt};
+	use sp_runtime::{traits::OnInitialize, testing::UintAuthorityId};
+	use crate::mock::{force_new_session, set_next_validators, Test, System, Session};
+	use frame_support::traits::KeyOwnerProofSystem;
+
+	type Historical = Module<Test>;
+
+	#[test]
+	fn proofs_are_generated_from_the_stored_tries() {
+		let mut ext = new_test_ext();
+		let (offchain, state) = TestOffchainExt::new();
+		ext.register_extension(OffchainExt::new(offchain));
+
+		ext.execute_with(|| {
+			set_next_validators(vec![1, 2]);
+			force_new_session();
+
+			System::set_block_number(1);
+			Session::on_initialize(1);
+			let session = Session::current_index();
+
+			for i in 2..5u64 {
+				set_next_validators(vec![2, 3]);
+				force_new_session();
+
+				System::set_block_number(i);
+				Session::on_initialize(i);
+			}
+
+			let stored_trie = |s| state.read().persistent_storage.get(b"", &trie_key(s))
+				.map_or(false, |trie| !trie.is_empty());
+
+			// the proofs are only generated once the offchain worker stored the tries.
+			let encoded_key_1 = UintAuthorityId(1).encode();
+			assert!(prove_session_membership::<Test>(session, (DUMMY, &encoded_key_1[..])).is_none());
+
+			store_session_tries::<Test>();
+			assert!((0..=session).any(stored_trie));
+
+			let proof = prove_session_membership::<Test>(session, (DUMMY, &encoded_key_1[..]))
+				.unwrap();
+			assert_eq!(
+				Some(proof.clone()),
+				Historical::prove_historical(session, (DUMMY, &encoded_key_1[..])),
+			);
+			assert_eq!(
+				Historical::check_proof((DUMMY, &encoded_key_1[..]), proof),
+				Some((1, 1)),
+			);
+
+			// the tries of the pruned sessions are forgotten.
+			let last = Session::current_index();
+			Historical::prune_up_to(last);
+			store_session_tries::<Test>();
+
+			assert!(prove_session_membership::<Test>(session, (DUMMY, &encoded_key_1[..])).is_none());
+			assert!(!(0..last).any(stored_trie));
+
+			let encoded_key_3 = UintAuthorityId(3).encode();
+			assert!(prove_session_membership::<Test>(last, (DUMMY, &encoded_key_3[..])).is_some());
+		});
+	}
+}
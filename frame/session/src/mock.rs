@@ -179,7 +179,7 @@ impl frame_system::Trait for Test {
 	type Version = ();
 	type ModuleToIndex = ();
 	type AccountData = ();
-	type MigrateAccount = (); type OnNewAccount = ();
+	type MigrateAccount = (); type MigrateAccountData = (); type OnNewAccount = ();
 	type OnKilledAccount = ();
 }
 
@@ -211,6 +211,8 @@ impl Trait for Test {
 impl crate::historical::Trait for Test {
 	type FullIdentification = u64;
 	type FullIdentificationOf = sp_runtime::traits::ConvertInto;
+	type HistoricalFullIdentificationOf =
+		crate::historical::SessionIndependent<sp_runtime::traits::ConvertInto>;
 }
 
 pub type System = frame_system::Module<Test>;
@@ -0,0 +1,92 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Runtime API definition for staking module.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::prelude::*;
+use codec::{Encode, Codec, Decode};
+#[cfg(feature = "std")]
+use serde::{Serialize, Deserialize};
+use sp_runtime::RuntimeDebug;
+
+/// Counter for the number of eras that have passed.
+pub type EraIndex = u32;
+
+/// Simple index type with which we can count sessions.
+pub type SessionIndex = u32;
+
+/// A slash applied to a nominator, for an offence of a validator it was exposed to.
+#[derive(Eq, PartialEq, Clone, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct NominatorSlash<AccountId, Balance> {
+	/// The active era when the slash was applied.
+	pub era: EraIndex,
+	/// The stash of the offending validator.
+	pub validator: AccountId,
+	/// The value slashed from the bond of the nominator.
+	pub value: Balance,
+}
+
+/// A slashing span of a stash, with the value slashed during it.
+///
+/// A stash is slashed at most once per span for each validator it is exposed to, by the
+/// greatest of the slashes in the eras of the span.
+#[derive(Eq, PartialEq, Clone, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct SlashingSpanInfo<Balance> {
+	/// The index of the span, unique for the stash.
+	pub index: u32,
+	/// The first era of the span.
+	pub start: EraIndex,
+	/// The number of eras of the span, `None` for the ongoing one.
+	pub length: Option<EraIndex>,
+	/// The value slashed from the stash during the span.
+	pub slashed: Balance,
+}
+
+/// The slashes which affected a nominator.
+#[derive(Eq, PartialEq, Clone, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct NominatorSlashes<AccountId, Balance> {
+	/// The slashes applied within the bonding duration, oldest first.
+	pub slashes: Vec<NominatorSlash<AccountId, Balance>>,
+	/// The slashing spans which are still tracked, most recent first.
+	pub spans: Vec<SlashingSpanInfo<Balance>>,
+}
+
+impl<AccountId, Balance> Default for NominatorSlashes<AccountId, Balance> {
+	fn default() -> Self {
+		NominatorSlashes { slashes: Vec::new(), spans: Vec::new() }
+	}
+}
+
+sp_api::decl_runtime_apis! {
+	pub trait StakingApi<AccountId, Balance> where
+		AccountId: Codec,
+		Balance: Codec,
+	{
+		/// The slashes which affected the nominator of the given stash account.
+		fn nominator_slashes(stash: AccountId) -> NominatorSlashes<AccountId, Balance>;
+
+		/// The session at which the era following the current one is planned to start, if any.
+		fn planned_era_start() -> Option<SessionIndex>;
+	}
+}
@@ -115,6 +115,10 @@
 //!
 //! Slashing logic is further described in the documentation of the `slashing` module.
 //!
+//! The [`DisablingStrategy`](./trait.DisablingStrategy.html) of the runtime can instead only
+//! disable some offenders until the end of the session, and limits how many validators can be
+//! disabled at the same time.
+//!
 //! Similar to slashing, rewards are also shared among a validator and its associated nominators.
 //! Yet, the reward funds are not always transferred to the stash account and can be configured.
 //! See [Reward Calculation](#reward-calculation) for more details.
@@ -287,6 +291,7 @@ use frame_system::{self as system, ensure_signed, ensure_root};
 
 use sp_phragmen::ExtendedBalance;
 use frame_support::traits::MigrateAccount;
+pub use pallet_staking_rpc_runtime_api::{NominatorSlash, NominatorSlashes, SlashingSpanInfo};
 
 const DEFAULT_MINIMUM_VALIDATOR_COUNT: u32 = 4;
 pub const MAX_NOMINATIONS: usize = 16;
@@ -577,6 +582,8 @@ pub trait SessionInterface<AccountId>: frame_system::Trait {
 	/// This allows preventing a situation where there is too many validators
 	/// disabled and block production stalls.
 	fn disable_validator(validator: &AccountId) -> Result<bool, ()>;
+	/// Get the number of validators disabled in the current session.
+	fn disabled_validator_count() -> u32;
 	/// Get the validators from session.
 	fn validators() -> Vec<AccountId>;
 	/// Prune historical session tries up to but not including the given index.
@@ -597,6 +604,10 @@ impl<T: Trait> SessionInterface<<T as frame_system::Trait>::AccountId> for T whe
 		<pallet_session::Module<T>>::disable(validator)
 	}
 
+	fn disabled_validator_count() -> u32 {
+		<pallet_session::Module<T>>::disabled_validators().len() as u32
+	}
+
 	fn validators() -> Vec<<T as frame_system::Trait>::AccountId> {
 		<pallet_session::Module<T>>::validators()
 	}
@@ -606,6 +617,48 @@ impl<T: Trait> SessionInterface<<T as frame_system::Trait>::AccountId> for T whe
 	}
 }
 
+/// Decides which offenders are only disabled until the end of the session instead of being
+/// slashed, and how many validators can be disabled at the same time.
+pub trait DisablingStrategy<AccountId> {
+	/// Whether the offender, reported with the given slash fraction, is only disabled.
+	fn disable_only(offender: &AccountId, slash_fraction: Perbill) -> bool;
+	/// The greatest number of validators that can be disabled at the same time, out of the given
+	/// number of validators.
+	fn max_disabled(validator_count: u32) -> u32;
+}
+
+/// Slashes all the offenders and doesn't limit the disabled validators.
+impl<AccountId> DisablingStrategy<AccountId> for () {
+	fn disable_only(_: &AccountId, _: Perbill) -> bool {
+		false
+	}
+
+	fn max_disabled(validator_count: u32) -> u32 {
+		validator_count
+	}
+}
+
+/// Only disables the offenders reported with a slash fraction of at most `Threshold`, and at most
+/// `MaxDisabled` of the validators at the same time.
+pub struct DisableBelowThreshold<Threshold, MaxDisabled>(
+	sp_std::marker::PhantomData<(Threshold, MaxDisabled)>
+);
+
+impl<AccountId, Threshold, MaxDisabled> DisablingStrategy<AccountId>
+	for DisableBelowThreshold<Threshold, MaxDisabled>
+where
+	Threshold: Get<Perbill>,
+	MaxDisabled: Get<Perbill>,
+{
+	fn disable_only(_: &AccountId, slash_fraction: Perbill) -> bool {
+		slash_fraction <= Threshold::get()
+	}
+
+	fn max_disabled(validator_count: u32) -> u32 {
+		MaxDisabled::get() * validator_count
+	}
+}
+
 pub trait Trait: frame_system::Trait {
 	/// The staking balance.
 	type Currency: LockableCurrency<Self::AccountId, Moment=Self::BlockNumber>;
@@ -652,6 +705,10 @@ pub trait Trait: frame_system::Trait {
 	/// Interface for interacting with a session module.
 	type SessionInterface: self::SessionInterface<Self::AccountId>;
 
+	/// Which offenders are only disabled instead of slashed, and how many validators can be
+	/// disabled at the same time.
+	type DisablingStrategy: DisablingStrategy<Self::AccountId>;
+
 	/// The NPoS reward curve to use.
 	type RewardCurve: Get<&'static PiecewiseLinear<'static>>;
 
@@ -660,6 +717,13 @@ pub trait Trait: frame_system::Trait {
 	/// For each validator only the `$MaxNominatorRewardedPerValidator` biggest stakers can claim
 	/// their reward. This used to limit the i/o cost for the nominator payout.
 	type MaxNominatorRewardedPerValidator: Get<u32>;
+
+	/// The greatest increase of its commission a validator can make in an era after the one it
+	/// last changed it in.
+	type MaxCommissionChangePerEra: Get<Perbill>;
+
+	/// The smallest commission a validator can take.
+	type MinCommission: Get<Perbill>;
 }
 
 /// Mode of era-forcing.
@@ -687,6 +751,7 @@ impl Default for Forcing {
 enum Releases {
 	V1_0_0Ancient,
 	V2_0_0,
+	V3_0_0,
 }
 
 impl Default for Releases {
@@ -733,6 +798,14 @@ decl_storage! {
 		pub Validators get(fn validators):
 			map hasher(twox_64_concat) T::AccountId => ValidatorPrefs;
 
+		/// The era in which each validator, by stash account, last changed its commission, and the
+		/// commission it then set.
+		///
+		/// This is kept when the validator chills, so that it can't raise its commission faster
+		/// than `T::MaxCommissionChangePerEra` by validating again.
+		pub CommissionLastChanged get(fn commission_last_changed):
+			map hasher(twox_64_concat) T::AccountId => Option<(EraIndex, Perbill)>;
+
 		/// The map from nominator stash key to the set of stash keys of all validators to nominate.
 		pub Nominators get(fn nominators):
 			map hasher(twox_64_concat) T::AccountId => Option<Nominations<T::AccountId>>;
@@ -807,6 +880,12 @@ decl_storage! {
 		/// True if the next session change will be a new era regardless of index.
 		pub ForceEra get(fn force_era) config(): Forcing;
 
+		/// The minimum number of sessions an era lasts, even when a new era is forced.
+		pub MinEraDuration get(fn min_era_duration) config(): SessionIndex;
+
+		/// The latest session planned by the session module, i.e. whose validator set is queued.
+		pub CurrentPlannedSession get(fn current_planned_session): SessionIndex;
+
 		/// The percentage of the slash that is distributed to reporters.
 		///
 		/// The rest of the slashed value is handled by the `Slash`.
@@ -840,6 +919,10 @@ decl_storage! {
 		/// Slashing spans for stash accounts.
 		SlashingSpans: map hasher(twox_64_concat) T::AccountId => Option<slashing::SlashingSpans>;
 
+		/// The slashes applied to each nominator, by stash account, within the bonding duration.
+		pub NominatorSlashHistory get(fn nominator_slash_history):
+			map hasher(twox_64_concat) T::AccountId => Vec<NominatorSlash<T::AccountId, BalanceOf<T>>>;
+
 		/// Records information about the maximum slash of a stash within a slashing span,
 		/// as well as how much reward has been paid out.
 		SpanSlash:
@@ -852,7 +935,7 @@ decl_storage! {
 		/// Storage version of the pallet.
 		///
 		/// This is set to v2.0.0 for new networks.
-		StorageVersion build(|_: &GenesisConfig<T>| Releases::V2_0_0): Releases;
+		StorageVersion build(|_: &GenesisConfig<T>| Releases::V3_0_0): Releases;
 	}
 	add_extra_genesis {
 		config(stakers):
@@ -907,6 +990,13 @@ decl_event!(
 		/// An account has called `withdraw_unbonded` and removed unbonding chunks worth `Balance`
 		/// from the unlocking queue.
 		Withdrawn(AccountId, Balance),
+		/// The mode of era-forcing changed to the given one.
+		ForceEraChanged(Forcing),
+		/// The minimum number of sessions an era lasts changed to the given one.
+		MinEraDurationChanged(SessionIndex),
+		/// An offending validator has been disabled until the end of the session instead of being
+		/// slashed.
+		Disabled(AccountId),
 	}
 );
 
@@ -941,6 +1031,10 @@ decl_error! {
 		InvalidNumberOfNominations,
 		/// Items are not sorted and unique.
 		NotSortedAndUnique,
+		/// Commission is lower than the minimum commission.
+		CommissionTooLow,
+		/// Commission is raised faster than `MaxCommissionChangePerEra` allows.
+		CommissionIncreaseTooFast,
 	}
 }
 
@@ -952,6 +1046,13 @@ decl_module! {
 		/// Number of eras that staked funds must remain bonded for.
 		const BondingDuration: EraIndex = T::BondingDuration::get();
 
+		/// The greatest increase of its commission a validator can make for each era since it last
+		/// changed it.
+		const MaxCommissionChangePerEra: Perbill = T::MaxCommissionChangePerEra::get();
+
+		/// The smallest commission a validator can take.
+		const MinCommission: Perbill = T::MinCommission::get();
+
 		type Error = Error<T>;
 
 		fn deposit_event() = default;
@@ -1172,6 +1273,11 @@ decl_module! {
 		///
 		/// The dispatch origin for this call must be _Signed_ by the controller, not the stash.
 		///
+		/// The commission must be at least `T::MinCommission`, and can only be raised by
+		/// `T::MaxCommissionChangePerEra` in an era after the one it was last changed in. The
+		/// first commission of a validator is at most `T::MaxCommissionChangePerEra` above
+		/// `T::MinCommission`.
+		///
 		/// # <weight>
 		/// - Independent of the arguments. Insignificant complexity.
 		/// - Contains a limited number of reads.
@@ -1182,6 +1288,26 @@ decl_module! {
 			let controller = ensure_signed(origin)?;
 			let ledger = Self::ledger(&controller).ok_or(Error::<T>::NotController)?;
 			let stash = &ledger.stash;
+			ensure!(prefs.commission >= T::MinCommission::get(), Error::<T>::CommissionTooLow);
+
+			let current_era = Self::current_era().unwrap_or(0);
+			let last_change = Self::commission_last_changed(stash);
+			// a validator which never set a commission is considered to have set the minimum one
+			// in the previous era, so its first commission is bounded too.
+			let (era, commission) = last_change
+				.unwrap_or((current_era.saturating_sub(1), T::MinCommission::get()));
+			// the allowance doesn't accumulate over the eras the commission is left unchanged.
+			let max_increase = if era < current_era {
+				T::MaxCommissionChangePerEra::get().deconstruct()
+			} else {
+				0
+			};
+			let increase = prefs.commission.deconstruct().saturating_sub(commission.deconstruct());
+			ensure!(increase <= max_increase, Error::<T>::CommissionIncreaseTooFast);
+
+			if last_change.map_or(true, |(_, commission)| commission != prefs.commission) {
+				<CommissionLastChanged<T>>::insert(stash, (current_era, prefs.commission));
+			}
 			<Nominators<T>>::remove(stash);
 			<Validators<T>>::insert(stash, prefs);
 		}
@@ -1300,7 +1426,7 @@ decl_module! {
 		#[weight = SimpleDispatchInfo::FixedNormal(5_000)]
 		fn force_no_eras(origin) {
 			ensure_root(origin)?;
-			ForceEra::put(Forcing::ForceNone);
+			Self::set_force_era(Forcing::ForceNone);
 		}
 
 		/// Force there to be a new era at the end of the next session. After this, it will be
@@ -1312,7 +1438,7 @@ decl_module! {
 		#[weight = SimpleDispatchInfo::FixedNormal(5_000)]
 		fn force_new_era(origin) {
 			ensure_root(origin)?;
-			ForceEra::put(Forcing::ForceNew);
+			Self::set_force_era(Forcing::ForceNew);
 		}
 
 		/// Set the validators who cannot be slashed (if any).
@@ -1342,7 +1468,21 @@ decl_module! {
 		#[weight = SimpleDispatchInfo::FixedNormal(5_000)]
 		fn force_new_era_always(origin) {
 			ensure_root(origin)?;
-			ForceEra::put(Forcing::ForceAlways);
+			Self::set_force_era(Forcing::ForceAlways);
+		}
+
+		/// Set the minimum number of sessions an era lasts, even when a new era is forced.
+		///
+		/// An era which is not forced lasts the greatest of this and `SessionsPerEra` sessions.
+		///
+		/// # <weight>
+		/// - One storage write.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(5_000)]
+		fn set_min_era_duration(origin, #[compact] sessions: SessionIndex) {
+			ensure_root(origin)?;
+			MinEraDuration::put(sessions);
+			Self::deposit_event(RawEvent::MinEraDurationChanged(sessions));
 		}
 
 		/// Cancel enactment of a deferred slash. Can be called by either the root origin or
@@ -1502,6 +1642,16 @@ fn migrate<T: Trait>() {
 			ErasTotalStake::<T>::migrate_key_from_blake(era);
 		}
 	}
+
+	if StorageVersion::get() == Releases::V2_0_0 {
+		// the current commissions are considered as changed in the current era, so that they
+		// can't be raised right after the upgrade either.
+		let current_era = CurrentEra::get().unwrap_or(0);
+		for (stash, prefs) in <Validators<T>>::iter() {
+			<CommissionLastChanged<T>>::insert(stash, (current_era, prefs.commission));
+		}
+		StorageVersion::put(Releases::V3_0_0);
+	}
 }
 
 impl<T: Trait> Module<T> {
@@ -1512,6 +1662,24 @@ impl<T: Trait> Module<T> {
 		Self::bonded(stash).and_then(Self::ledger).map(|l| l.active).unwrap_or_default()
 	}
 
+	/// The slashes applied to the nominator of the given stash account within the bonding
+	/// duration, together with its slashing spans.
+	pub fn nominator_slashes(stash: &T::AccountId) -> NominatorSlashes<T::AccountId, BalanceOf<T>> {
+		let spans = <Self as Store>::SlashingSpans::get(stash)
+			.map(|spans| spans.iter().map(|span| SlashingSpanInfo {
+				index: span.index,
+				start: span.start,
+				length: span.length,
+				slashed: *<Self as Store>::SpanSlash::get(&(stash.clone(), span.index)).amount_slashed(),
+			}).collect())
+			.unwrap_or_default();
+
+		NominatorSlashes {
+			slashes: Self::nominator_slash_history(stash),
+			spans,
+		}
+	}
+
 	// MUTABLES (DANGEROUS)
 
 	fn do_payout_nominator(who: T::AccountId, era: EraIndex, validators: Vec<(T::AccountId, u32)>)
@@ -1665,6 +1833,8 @@ impl<T: Trait> Module<T> {
 
 	/// Plan a new session potentially trigger a new era.
 	fn new_session(session_index: SessionIndex) -> Option<Vec<T::AccountId>> {
+		CurrentPlannedSession::put(session_index);
+
 		if let Some(current_era) = Self::current_era() {
 			// Initial era has been set.
 
@@ -1677,10 +1847,12 @@ impl<T: Trait> Module<T> {
 			let era_length = session_index.checked_sub(current_era_start_session_index)
 				.unwrap_or(0); // Must never happen.
 
+			let min_era_length = Self::min_era_duration();
 			match ForceEra::get() {
-				Forcing::ForceNew => ForceEra::kill(),
-				Forcing::ForceAlways => (),
-				Forcing::NotForcing if era_length >= T::SessionsPerEra::get() => (),
+				Forcing::ForceNew if era_length >= min_era_length =>
+					Self::set_force_era(Forcing::NotForcing),
+				Forcing::ForceAlways if era_length >= min_era_length => (),
+				Forcing::NotForcing if era_length >= T::SessionsPerEra::get().max(min_era_length) => (),
 				_ => return None,
 			}
 
@@ -1754,6 +1926,11 @@ impl<T: Trait> Module<T> {
 				}
 
 				if let Some(&(_, first_session)) = bonded.first() {
+					// the sessions of the eras within the history depth are kept as well, so
+					// that the exposures of their validators can still be proven.
+					let first_session = Self::eras_start_session_index(
+						active_era.saturating_sub(Self::history_depth())
+					).map_or(first_session, |session| session.min(first_session));
 					T::SessionInterface::prune_historical_up_to(first_session);
 				}
 			}
@@ -1967,6 +2144,7 @@ impl<T: Trait> Module<T> {
 
 		<Payee<T>>::remove(stash);
 		<Validators<T>>::remove(stash);
+		<CommissionLastChanged<T>>::remove(stash);
 		<Nominators<T>>::remove(stash);
 
 		slashing::clear_stash_metadata::<T>(stash);
@@ -2001,12 +2179,70 @@ impl<T: Trait> Module<T> {
 		}
 	}
 
+	/// Disable a validator until the end of the session, unless the `DisablingStrategy` doesn't
+	/// allow any more disabled validators. Forces a new era if the session has too many of them.
+	///
+	/// Returns whether the validator is disabled.
+	fn disable_validator(stash: &T::AccountId) -> bool {
+		let validator_count = T::SessionInterface::validators().len() as u32;
+		if T::SessionInterface::disabled_validator_count()
+			>= T::DisablingStrategy::max_disabled(validator_count)
+		{
+			return false
+		}
+
+		match T::SessionInterface::disable_validator(stash) {
+			Ok(force_new_era) => {
+				if force_new_era {
+					// force a new era, to select a new validator set
+					Self::ensure_new_era()
+				}
+				true
+			},
+			Err(()) => false,
+		}
+	}
+
 	/// Ensures that at the end of the current session there will be a new era.
 	fn ensure_new_era() {
 		match ForceEra::get() {
 			Forcing::ForceAlways | Forcing::ForceNew => (),
-			_ => ForceEra::put(Forcing::ForceNew),
+			_ => Self::set_force_era(Forcing::ForceNew),
+		}
+	}
+
+	/// Set the mode of era-forcing, depositing an event if it changed.
+	fn set_force_era(forcing: Forcing) {
+		if ForceEra::get() == forcing {
+			return
 		}
+
+		if forcing == Forcing::NotForcing {
+			ForceEra::kill();
+		} else {
+			ForceEra::put(forcing);
+		}
+		Self::deposit_event(RawEvent::ForceEraChanged(forcing));
+	}
+
+	/// The session at which the era following the current one is planned to start, if any.
+	///
+	/// This is the earliest session allowed by the current mode of era-forcing and the minimum
+	/// era duration, it is `None` while new eras are avoided. The era becomes active when its
+	/// first session starts.
+	pub fn planned_era_start() -> Option<SessionIndex> {
+		let current_era = Self::current_era()?;
+		let current_era_start = Self::eras_start_session_index(current_era)?;
+		let era_length = match ForceEra::get() {
+			Forcing::ForceNone => return None,
+			Forcing::NotForcing => T::SessionsPerEra::get().max(Self::min_era_duration()),
+			Forcing::ForceNew | Forcing::ForceAlways => Self::min_era_duration(),
+		};
+
+		Some(
+			current_era_start.saturating_add(era_length)
+				.max(Self::current_planned_session().saturating_add(1))
+		)
 	}
 }
 
@@ -2100,6 +2336,31 @@ impl<T: Trait> Convert<T::AccountId, Option<Exposure<T::AccountId, BalanceOf<T>>
 	}
 }
 
+/// A typed conversion from a session index and a stash account ID to the exposure of nominators
+/// on that account in that session.
+///
+/// The exposure is the one of the latest era planned to start at or before the session, as
+/// returned by the `historical::SessionManager` when that era was planned. Only the eras within
+/// the history depth are known.
+pub struct HistoricalExposureOf<T>(sp_std::marker::PhantomData<T>);
+
+impl<T: Trait> Convert<(SessionIndex, T::AccountId), Option<Exposure<T::AccountId, BalanceOf<T>>>>
+	for HistoricalExposureOf<T>
+{
+	fn convert(
+		(session, validator): (SessionIndex, T::AccountId),
+	) -> Option<Exposure<T::AccountId, BalanceOf<T>>> {
+		let current_era = <Module<T>>::current_era()?;
+		let first_era = current_era.saturating_sub(<Module<T>>::history_depth());
+
+		(first_era..=current_era).rev()
+			.find(|era| {
+				<Module<T>>::eras_start_session_index(era).map_or(false, |start| start <= session)
+			})
+			.map(|era| <Module<T>>::eras_stakers(era, &validator))
+	}
+}
+
 /// This is intended to be used with `FilterHistoricalOffences`.
 impl <T: Trait> OnOffenceHandler<T::AccountId, pallet_session::historical::IdentificationTuple<T>> for Module<T> where
 	T: pallet_session::Trait<ValidatorId = <T as frame_system::Trait>::AccountId>,
@@ -2164,6 +2425,13 @@ impl <T: Trait> OnOffenceHandler<T::AccountId, pallet_session::historical::Ident
 				continue
 			}
 
+			if T::DisablingStrategy::disable_only(stash, *slash_fraction) {
+				if Self::disable_validator(stash) {
+					Self::deposit_event(RawEvent::Disabled(stash.clone()));
+				}
+				continue
+			}
+
 			let unapplied = slashing::compute_slash::<T>(slashing::SlashParams {
 				stash,
 				slash: *slash_fraction,
@@ -30,7 +30,8 @@ use frame_support::{
 };
 use crate::{
 	EraIndex, GenesisConfig, Module, Trait, StakerStatus, ValidatorPrefs, RewardDestination,
-	Nominators, inflation, SessionInterface, Exposure, ErasStakers, ErasRewardPoints
+	Nominators, inflation, SessionInterface, Exposure, ErasStakers, ErasRewardPoints,
+	DisablingStrategy,
 };
 
 /// The AccountId alias in this test module.
@@ -51,6 +52,8 @@ thread_local! {
 	static SESSION: RefCell<(Vec<AccountId>, HashSet<AccountId>)> = RefCell::new(Default::default());
 	static EXISTENTIAL_DEPOSIT: RefCell<u64> = RefCell::new(0);
 	static SLASH_DEFER_DURATION: RefCell<EraIndex> = RefCell::new(0);
+	static DISABLE_ONLY_THRESHOLD: RefCell<Option<Perbill>> = RefCell::new(None);
+	static MAX_DISABLED: RefCell<Option<u32>> = RefCell::new(None);
 }
 
 pub struct TestSessionHandler;
@@ -97,6 +100,19 @@ impl Get<EraIndex> for SlashDeferDuration {
 	}
 }
 
+/// Only disables the offenders reported with a slash fraction of at most the threshold set by
+/// the `ExtBuilder`, if any.
+pub struct TestDisablingStrategy;
+impl DisablingStrategy<AccountId> for TestDisablingStrategy {
+	fn disable_only(_: &AccountId, slash_fraction: Perbill) -> bool {
+		DISABLE_ONLY_THRESHOLD.with(|v| v.borrow().map_or(false, |t| slash_fraction <= t))
+	}
+
+	fn max_disabled(validator_count: u32) -> u32 {
+		MAX_DISABLED.with(|v| v.borrow().unwrap_or(validator_count))
+	}
+}
+
 impl_outer_origin!{
 	pub enum Origin for Test  where system = frame_system {}
 }
@@ -138,7 +154,7 @@ impl frame_system::Trait for Test {
 	type Version = ();
 	type ModuleToIndex = ();
 	type AccountData = pallet_balances::AccountData<u64>;
-	type MigrateAccount = (); type OnNewAccount = ();
+	type MigrateAccount = (); type MigrateAccountData = (); type OnNewAccount = ();
 	type OnKilledAccount = ();
 }
 impl pallet_balances::Trait for Test {
@@ -168,6 +184,7 @@ impl pallet_session::Trait for Test {
 impl pallet_session::historical::Trait for Test {
 	type FullIdentification = crate::Exposure<AccountId, Balance>;
 	type FullIdentificationOf = crate::ExposureOf<Test>;
+	type HistoricalFullIdentificationOf = crate::HistoricalExposureOf<Test>;
 }
 impl pallet_authorship::Trait for Test {
 	type FindAuthor = Author11;
@@ -198,6 +215,8 @@ parameter_types! {
 	pub const BondingDuration: EraIndex = 3;
 	pub const RewardCurve: &'static PiecewiseLinear<'static> = &I_NPOS;
 	pub const MaxNominatorRewardedPerValidator: u32 = 64;
+	pub const MaxCommissionChangePerEra: Perbill = Perbill::from_percent(10);
+	pub const MinCommission: Perbill = Perbill::zero();
 }
 impl Trait for Test {
 	type Currency = pallet_balances::Module<Self>;
@@ -212,8 +231,11 @@ impl Trait for Test {
 	type SlashCancelOrigin = frame_system::EnsureRoot<Self::AccountId>;
 	type BondingDuration = BondingDuration;
 	type SessionInterface = Self;
+	type DisablingStrategy = TestDisablingStrategy;
 	type RewardCurve = RewardCurve;
 	type MaxNominatorRewardedPerValidator = MaxNominatorRewardedPerValidator;
+	type MaxCommissionChangePerEra = MaxCommissionChangePerEra;
+	type MinCommission = MinCommission;
 }
 
 pub struct ExtBuilder {
@@ -227,6 +249,8 @@ pub struct ExtBuilder {
 	num_validators: Option<u32>,
 	invulnerables: Vec<u64>,
 	stakers: bool,
+	disable_only_threshold: Option<Perbill>,
+	max_disabled: Option<u32>,
 }
 
 impl Default for ExtBuilder {
@@ -242,6 +266,8 @@ impl Default for ExtBuilder {
 			num_validators: None,
 			invulnerables: vec![],
 			stakers: true,
+			disable_only_threshold: None,
+			max_disabled: None,
 		}
 	}
 }
@@ -283,9 +309,19 @@ impl ExtBuilder {
 		self.invulnerables = invulnerables;
 		self
 	}
+	pub fn disable_only_threshold(mut self, threshold: Perbill) -> Self {
+		self.disable_only_threshold = Some(threshold);
+		self
+	}
+	pub fn max_disabled(mut self, max_disabled: u32) -> Self {
+		self.max_disabled = Some(max_disabled);
+		self
+	}
 	pub fn set_associated_consts(&self) {
 		EXISTENTIAL_DEPOSIT.with(|v| *v.borrow_mut() = self.existential_deposit);
 		SLASH_DEFER_DURATION.with(|v| *v.borrow_mut() = self.slash_defer_duration);
+		DISABLE_ONLY_THRESHOLD.with(|v| *v.borrow_mut() = self.disable_only_threshold);
+		MAX_DISABLED.with(|v| *v.borrow_mut() = self.max_disabled);
 	}
 
 	pub fn stakers(mut self, has_stakers: bool) -> Self {
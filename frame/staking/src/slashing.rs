@@ -50,12 +50,12 @@
 
 use super::{
 	EraIndex, Trait, Module, Store, BalanceOf, Exposure, Perbill, SessionInterface,
-	NegativeImbalanceOf, UnappliedSlash,
+	NegativeImbalanceOf, UnappliedSlash, NominatorSlash,
 };
 use sp_runtime::{traits::{Zero, Saturating}, PerThing};
 use frame_support::{
 	StorageMap, StorageDoubleMap,
-	traits::{Currency, OnUnbalanced, Imbalance},
+	traits::{Currency, OnUnbalanced, Imbalance, Get},
 };
 use sp_std::vec::Vec;
 use codec::{Encode, Decode};
@@ -181,7 +181,6 @@ pub(crate) struct SpanRecord<Balance> {
 
 impl<Balance> SpanRecord<Balance> {
 	/// The value of stash balance slashed in this span.
-	#[cfg(test)]
 	pub(crate) fn amount_slashed(&self) -> &Balance {
 		&self.slashed
 	}
@@ -287,10 +286,7 @@ pub(crate) fn compute_slash<T: Trait>(params: SlashParams<T>)
 			<Module<T>>::chill_stash(stash);
 
 			// make sure to disable validator till the end of this session
-			if T::SessionInterface::disable_validator(stash).unwrap_or(false) {
-				// force a new era, to select a new validator set
-				<Module<T>>::ensure_new_era()
-			}
+			<Module<T>>::disable_validator(stash);
 		}
 	}
 
@@ -327,10 +323,7 @@ fn kick_out_if_recent<T: Trait>(
 		<Module<T>>::chill_stash(params.stash);
 
 		// make sure to disable validator till the end of this session
-		if T::SessionInterface::disable_validator(params.stash).unwrap_or(false) {
-			// force a new era, to select a new validator set
-			<Module<T>>::ensure_new_era()
-		}
+		<Module<T>>::disable_validator(params.stash);
 	}
 }
 
@@ -548,6 +541,8 @@ pub(crate) fn clear_era_metadata<T: Trait>(obsolete_era: EraIndex) {
 
 /// Clear slashing metadata for a dead account.
 pub(crate) fn clear_stash_metadata<T: Trait>(stash: &T::AccountId) {
+	<Module<T> as Store>::NominatorSlashHistory::remove(stash);
+
 	let spans = match <Module<T> as Store>::SlashingSpans::take(stash) {
 		None => return,
 		Some(s) => s,
@@ -566,20 +561,22 @@ pub(crate) fn clear_stash_metadata<T: Trait>(stash: &T::AccountId) {
 // apply the slash to a stash account, deducting any missing funds from the reward
 // payout, saturating at 0. this is mildly unfair but also an edge-case that
 // can only occur when overlapping locked funds have been slashed.
+//
+// returns the value slashed from the ledger of the stash.
 pub fn do_slash<T: Trait>(
 	stash: &T::AccountId,
 	value: BalanceOf<T>,
 	reward_payout: &mut BalanceOf<T>,
 	slashed_imbalance: &mut NegativeImbalanceOf<T>,
-) {
+) -> BalanceOf<T> {
 	let controller = match <Module<T>>::bonded(stash) {
-		None => return, // defensive: should always exist.
+		None => return Zero::zero(), // defensive: should always exist.
 		Some(c) => c,
 	};
 
 	let mut ledger = match <Module<T>>::ledger(&controller) {
 		Some(ledger) => ledger,
-		None => return, // nothing to do.
+		None => return Zero::zero(), // nothing to do.
 	};
 
 	let value = ledger.slash(value, T::Currency::minimum_balance());
@@ -600,6 +597,8 @@ pub fn do_slash<T: Trait>(
 			super::RawEvent::Slash(stash.clone(), value)
 		);
 	}
+
+	value
 }
 
 /// Apply a previously-unapplied slash.
@@ -615,17 +614,35 @@ pub(crate) fn apply_slash<T: Trait>(unapplied_slash: UnappliedSlash<T::AccountId
 	);
 
 	for &(ref nominator, nominator_slash) in &unapplied_slash.others {
-		do_slash::<T>(
+		let value = do_slash::<T>(
 			&nominator,
 			nominator_slash,
 			&mut reward_payout,
 			&mut slashed_imbalance,
 		);
+
+		if !value.is_zero() {
+			note_nominator_slash::<T>(nominator, &unapplied_slash.validator, value);
+		}
 	}
 
 	pay_reporters::<T>(reward_payout, slashed_imbalance, &unapplied_slash.reporters);
 }
 
+// record a slash applied to a nominator, forgetting those applied before the bonding duration.
+fn note_nominator_slash<T: Trait>(
+	nominator: &T::AccountId,
+	validator: &T::AccountId,
+	value: BalanceOf<T>,
+) {
+	let era = <Module<T>>::active_era().map(|e| e.index).unwrap_or(0);
+	let first_kept = era.saturating_sub(T::BondingDuration::get());
+
+	<Module<T> as Store>::NominatorSlashHistory::mutate(nominator, |slashes| {
+		slashes.retain(|slash| slash.era >= first_kept);
+		slashes.push(NominatorSlash { era, validator: validator.clone(), value });
+	});
+}
 
 /// Apply a reward payout to some reporters, paying the rewards out of the slashed imbalance.
 fn pay_reporters<T: Trait>(
@@ -18,11 +18,11 @@
 
 use super::*;
 use mock::*;
-use sp_runtime::{assert_eq_error_rate, traits::{OnInitialize, BadOrigin}};
+use sp_runtime::{assert_eq_error_rate, traits::{OnInitialize, BadOrigin}, testing::UintAuthorityId};
 use sp_staking::offence::OffenceDetails;
 use frame_support::{
 	assert_ok, assert_noop,
-	traits::{Currency, ReservableCurrency},
+	traits::{Currency, ReservableCurrency, KeyOwnerProofSystem},
 	StorageMap,
 };
 use pallet_balances::Error as BalancesError;
@@ -751,6 +751,123 @@ fn forcing_new_era_works() {
 	});
 }
 
+#[test]
+fn min_era_duration_delays_the_new_eras() {
+	ExtBuilder::default().build().execute_with(|| {
+		start_era(1);
+		assert_eq!(Staking::eras_start_session_index(1), Some(3));
+		assert_eq!(Staking::planned_era_start(), Some(6));
+
+		assert_ok!(Staking::set_min_era_duration(Origin::ROOT, 5));
+		assert_eq!(Staking::planned_era_start(), Some(8));
+
+		// a forced era waits for the minimum duration.
+		assert_ok!(Staking::force_new_era(Origin::ROOT));
+		assert_eq!(Staking::planned_era_start(), Some(8));
+		start_session(6);
+		assert_eq!(Staking::current_era(), Some(1));
+		assert_eq!(ForceEra::get(), Forcing::ForceNew);
+		start_session(7);
+		assert_eq!(Staking::current_era(), Some(2));
+		assert_eq!(Staking::eras_start_session_index(2), Some(8));
+		assert_eq!(ForceEra::get(), Forcing::NotForcing);
+		start_session(8);
+		assert_eq!(Staking::active_era().unwrap().index, 2);
+
+		// so does an era which is not forced, when the minimum exceeds `SessionsPerEra`.
+		assert_eq!(Staking::planned_era_start(), Some(13));
+		start_session(11);
+		assert_eq!(Staking::active_era().unwrap().index, 2);
+		start_session(13);
+		assert_eq!(Staking::active_era().unwrap().index, 3);
+
+		assert_ok!(Staking::force_no_eras(Origin::ROOT));
+		assert_eq!(Staking::planned_era_start(), None);
+	});
+}
+
+#[test]
+fn commission_increases_are_rate_limited() {
+	ExtBuilder::default().build().execute_with(|| {
+		let validate = |percent| Staking::validate(
+			Origin::signed(10),
+			ValidatorPrefs { commission: Perbill::from_percent(percent) },
+		);
+
+		// the genesis validators set no commission in the first era.
+		assert_eq!(Staking::commission_last_changed(&11), Some((0, Perbill::zero())));
+		assert_noop!(validate(5), Error::<Test>::CommissionIncreaseTooFast);
+
+		// the allowance doesn't accumulate over the eras.
+		start_era(2);
+		assert_noop!(validate(11), Error::<Test>::CommissionIncreaseTooFast);
+		assert_ok!(validate(10));
+		assert_eq!(Staking::commission_last_changed(&11), Some((2, Perbill::from_percent(10))));
+
+		// decreasing it is always possible, but restarts the count.
+		assert_ok!(validate(5));
+		assert_noop!(validate(6), Error::<Test>::CommissionIncreaseTooFast);
+
+		// chilling doesn't lift the limit either.
+		assert_ok!(Staking::chill(Origin::signed(10)));
+		assert_noop!(validate(6), Error::<Test>::CommissionIncreaseTooFast);
+
+		start_era(3);
+		assert_noop!(validate(16), Error::<Test>::CommissionIncreaseTooFast);
+		assert_ok!(validate(15));
+	});
+}
+
+#[test]
+fn first_commission_is_rate_limited() {
+	ExtBuilder::default().build().execute_with(|| {
+		start_era(1);
+		let _ = Balances::make_free_balance_be(&1, 1000);
+		assert_ok!(Staking::bond(Origin::signed(1), 2, 1000, RewardDestination::Controller));
+		assert_eq!(Staking::commission_last_changed(&1), None);
+
+		let validate = |percent| Staking::validate(
+			Origin::signed(2),
+			ValidatorPrefs { commission: Perbill::from_percent(percent) },
+		);
+		assert_noop!(validate(11), Error::<Test>::CommissionIncreaseTooFast);
+		assert_ok!(validate(10));
+		assert_eq!(Staking::commission_last_changed(&1), Some((1, Perbill::from_percent(10))));
+	});
+}
+
+#[test]
+fn exposures_can_be_proven_within_the_history_depth() {
+	ExtBuilder::default().build().execute_with(|| {
+		start_era(1);
+		let session = Session::current_index();
+		let exposure = Staking::eras_stakers(1, 11);
+		let key = UintAuthorityId(11).encode();
+
+		// past the bonding duration, but within the history depth.
+		start_era(6);
+		let proof = pallet_session::historical::Module::<Test>::prove_historical(
+			session,
+			(sp_core::crypto::key_types::DUMMY, &key[..]),
+		).unwrap();
+		assert_eq!(
+			<pallet_session::historical::Module<Test> as KeyOwnerProofSystem<_>>::check_proof(
+				(sp_core::crypto::key_types::DUMMY, &key[..]),
+				proof,
+			),
+			Some((11, exposure)),
+		);
+
+		// the sessions before the history depth are pruned.
+		HistoryDepth::put(2);
+		start_era(7);
+		assert!(pallet_session::historical::Module::<Test>::prove_historical(
+			session,
+			(sp_core::crypto::key_types::DUMMY, &key[..]),
+		).is_none());
+	});
+}
+
 #[test]
 fn cannot_transfer_staked_balance() {
 	// Tests that a stash account cannot transfer funds
@@ -2478,6 +2595,53 @@ fn slashing_nominators_by_span_max() {
 	});
 }
 
+#[test]
+fn nominator_slashes_are_reported() {
+	ExtBuilder::default().build().execute_with(|| {
+		start_era(1);
+		start_era(2);
+		start_era(3);
+
+		assert_eq!(Staking::nominator_slashes(&101), Default::default());
+
+		let exposure_11 = Staking::eras_stakers(Staking::active_era().unwrap().index, 11);
+		let exposure_21 = Staking::eras_stakers(Staking::active_era().unwrap().index, 21);
+		let nominated_value_11 = exposure_11.others.iter().find(|o| o.who == 101).unwrap().value;
+		let nominated_value_21 = exposure_21.others.iter().find(|o| o.who == 101).unwrap().value;
+
+		on_offence_in_era(
+			&[OffenceDetails { offender: (11, exposure_11), reporters: vec![] }],
+			&[Perbill::from_percent(10)],
+			2,
+		);
+		on_offence_in_era(
+			&[OffenceDetails { offender: (21, exposure_21), reporters: vec![] }],
+			&[Perbill::from_percent(30)],
+			3,
+		);
+
+		// both offences are in the first span, only the excess of the second slash is applied.
+		let slash_1_amount = Perbill::from_percent(10) * nominated_value_11;
+		let slash_2_amount = Perbill::from_percent(30) * nominated_value_21;
+		assert_eq!(
+			Staking::nominator_slashes(&101),
+			NominatorSlashes {
+				slashes: vec![
+					NominatorSlash { era: 3, validator: 11, value: slash_1_amount },
+					NominatorSlash { era: 3, validator: 21, value: slash_2_amount - slash_1_amount },
+				],
+				spans: vec![
+					SlashingSpanInfo { index: 1, start: 4, length: None, slashed: 0 },
+					SlashingSpanInfo { index: 0, start: 0, length: Some(4), slashed: slash_2_amount },
+				],
+			},
+		);
+
+		// the validators are not reported as nominators.
+		assert!(Staking::nominator_slashes(&11).slashes.is_empty());
+	});
+}
+
 #[test]
 fn slashes_are_summed_across_spans() {
 	ExtBuilder::default().build().execute_with(|| {
@@ -3039,3 +3203,67 @@ fn set_history_depth_works() {
 		assert!(!<Staking as Store>::ErasTotalStake::contains_key(10 - 5));
 	});
 }
+
+#[test]
+fn disabling_strategy_disables_instead_of_slashing() {
+	ExtBuilder::default()
+		.disable_only_threshold(Perbill::from_percent(5))
+		.max_disabled(1)
+		.build()
+		.execute_with(|| {
+			let balance_11 = Balances::free_balance(11);
+			let balance_21 = Balances::free_balance(21);
+
+			// a light offence only disables the validator.
+			on_offence_now(
+				&[OffenceDetails {
+					offender: (11, Staking::eras_stakers(Staking::active_era().unwrap().index, 11)),
+					reporters: vec![],
+				}],
+				&[Perbill::from_percent(5)],
+			);
+
+			assert!(is_disabled(10));
+			assert!(<Validators<Test>>::contains_key(11));
+			assert_eq!(Balances::free_balance(11), balance_11);
+
+			// no more validators can be disabled in this session.
+			on_offence_now(
+				&[OffenceDetails {
+					offender: (21, Staking::eras_stakers(Staking::active_era().unwrap().index, 21)),
+					reporters: vec![],
+				}],
+				&[Perbill::from_percent(1)],
+			);
+
+			assert!(!is_disabled(20));
+			assert!(<Validators<Test>>::contains_key(21));
+			assert_eq!(Balances::free_balance(21), balance_21);
+
+			// a heavier offence is still slashed, even if the validator can't be disabled.
+			on_offence_now(
+				&[OffenceDetails {
+					offender: (21, Staking::eras_stakers(Staking::active_era().unwrap().index, 21)),
+					reporters: vec![],
+				}],
+				&[Perbill::from_percent(10)],
+			);
+
+			assert!(!is_disabled(20));
+			assert!(!<Validators<Test>>::contains_key(21));
+			assert!(Balances::free_balance(21) < balance_21);
+
+			// the validators can be disabled again in the next session.
+			start_session(1);
+
+			on_offence_now(
+				&[OffenceDetails {
+					offender: (21, Staking::eras_stakers(Staking::active_era().unwrap().index, 21)),
+					reporters: vec![],
+				}],
+				&[Perbill::from_percent(1)],
+			);
+
+			assert!(is_disabled(20));
+		});
+}
@@ -33,7 +33,19 @@
 //! Only the sudo key can call the dispatchable functions from the Sudo module.
 //!
 //! * `sudo` - Make a `Root` call to a dispatchable function.
+//! * `sudo_unchecked_weight` - Make a `Root` call to a dispatchable function, with the given
+//!   weight rather than the one of the call.
 //! * `set_key` - Assign a new account to be the sudo key.
+//! * `sudo_as` - Make a `Signed` call from a given account to a dispatchable function.
+//! * `announce` - Announce a `Root` call to a dispatchable function, made `AnnounceDelay` blocks
+//!   later.
+//! * `cancel_announcement` - Cancel an announced call.
+//!
+//! ### Announce Delay
+//!
+//! If `AnnounceDelay` is non-zero, the sudo key can't make calls right away: calls must be
+//! announced with `announce`, and are made at the beginning of the block `AnnounceDelay` blocks
+//! later, so that the community gets notice of the pending sudo actions.
 //!
 //! ## Usage
 //!
@@ -86,13 +98,18 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 use sp_std::prelude::*;
-use sp_runtime::{traits::{StaticLookup, Dispatchable}, DispatchError};
+use sp_runtime::{traits::{StaticLookup, Dispatchable, Zero, One, Saturating}, DispatchError};
 
 use frame_support::{
-	Parameter, decl_module, decl_event, decl_storage, decl_error, ensure,
+	Parameter, decl_module, decl_event, decl_storage, decl_error, ensure, traits::Get,
 };
-use frame_support::weights::{GetDispatchInfo, FunctionOf};
-use frame_system::{self as system, ensure_signed};
+use frame_support::weights::{GetDispatchInfo, FunctionOf, Weight, DispatchClass, SimpleDispatchInfo};
+use frame_system::{self as system, ensure_signed, ensure_root};
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
 
 pub trait Trait: frame_system::Trait {
 	/// The overarching event type.
@@ -100,6 +117,13 @@ pub trait Trait: frame_system::Trait {
 
 	/// A sudo-able call.
 	type Call: Parameter + Dispatchable<Origin=Self::Origin> + GetDispatchInfo;
+
+	/// The number of blocks between the announcement of a call and the call. If zero, calls
+	/// are made right away.
+	type AnnounceDelay: Get<Self::BlockNumber>;
+
+	/// The maximum number of calls announced to be made at the same block.
+	type MaxAnnouncementsPerBlock: Get<u32>;
 }
 
 decl_module! {
@@ -109,9 +133,27 @@ decl_module! {
 
 		fn deposit_event() = default;
 
+		/// The number of blocks between the announcement of a call and the call.
+		const AnnounceDelay: T::BlockNumber = T::AnnounceDelay::get();
+
+		/// The maximum number of calls announced to be made at the same block.
+		const MaxAnnouncementsPerBlock: u32 = T::MaxAnnouncementsPerBlock::get();
+
+		fn on_initialize(now: T::BlockNumber) {
+			for index in <Agenda<T>>::take(now) {
+				if let Some((_, call)) = <Announcements<T>>::take(index) {
+					<frame_system::Module<T>>::register_extra_weight_unchecked(
+						call.get_dispatch_info().weight
+					);
+					let res = Self::dispatch_root(call);
+					Self::deposit_event(RawEvent::AnnouncementDone(index, res));
+				}
+			}
+		}
+
 		/// Authenticates the sudo key and dispatches a function call with `Root` origin.
 		///
-		/// The dispatch origin for this call must be _Signed_.
+		/// The dispatch origin for this call must be _Signed_, and `AnnounceDelay` must be zero.
 		///
 		/// # <weight>
 		/// - O(1).
@@ -128,22 +170,41 @@ decl_module! {
 			// This is a public call, so we ensure that the origin is some signed account.
 			let sender = ensure_signed(origin)?;
 			ensure!(sender == Self::key(), Error::<T>::RequireSudo);
+			ensure!(T::AnnounceDelay::get().is_zero(), Error::<T>::RequireAnnouncement);
 
-			let res = match call.dispatch(frame_system::RawOrigin::Root.into()) {
-				Ok(_) => true,
-				Err(e) => {
-					let e: DispatchError = e.into();
-					sp_runtime::print(e);
-					false
-				}
-			};
+			let res = Self::dispatch_root(*call);
+			Self::deposit_event(RawEvent::Sudid(res));
+		}
 
+		/// Authenticates the sudo key and dispatches a function call with `Root` origin.
+		/// This function does not check the weight of the call, and instead allows the
+		/// sudo key to specify the weight of the call.
+		///
+		/// The dispatch origin for this call must be _Signed_, and `AnnounceDelay` must be zero.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - The weight of this call is defined by the caller.
+		/// # </weight>
+		#[weight = FunctionOf(
+			|args: (&Box<<T as Trait>::Call>, &Weight)| *args.1,
+			|_: (&Box<<T as Trait>::Call>, &Weight)| DispatchClass::Operational,
+			true
+		)]
+		fn sudo_unchecked_weight(origin, call: Box<<T as Trait>::Call>, _weight: Weight) {
+			// This is a public call, so we ensure that the origin is some signed account.
+			let sender = ensure_signed(origin)?;
+			ensure!(sender == Self::key(), Error::<T>::RequireSudo);
+			ensure!(T::AnnounceDelay::get().is_zero(), Error::<T>::RequireAnnouncement);
+
+			let res = Self::dispatch_root(*call);
 			Self::deposit_event(RawEvent::Sudid(res));
 		}
 
 		/// Authenticates the current sudo key and sets the given AccountId (`new`) as the new sudo key.
 		///
-		/// The dispatch origin for this call must be _Signed_.
+		/// The dispatch origin for this call must be _Signed_ when `AnnounceDelay` is zero.
+		/// Otherwise the change must be announced, and is made with _Root_ origin.
 		///
 		/// # <weight>
 		/// - O(1).
@@ -151,9 +212,12 @@ decl_module! {
 		/// - One DB change.
 		/// # </weight>
 		fn set_key(origin, new: <T::Lookup as StaticLookup>::Source) {
-			// This is a public call, so we ensure that the origin is some signed account.
-			let sender = ensure_signed(origin)?;
-			ensure!(sender == Self::key(), Error::<T>::RequireSudo);
+			if ensure_root(origin.clone()).is_err() {
+				// This is a public call, so we ensure that the origin is some signed account.
+				let sender = ensure_signed(origin)?;
+				ensure!(sender == Self::key(), Error::<T>::RequireSudo);
+				ensure!(T::AnnounceDelay::get().is_zero(), Error::<T>::RequireAnnouncement);
+			}
 			let new = T::Lookup::lookup(new)?;
 
 			Self::deposit_event(RawEvent::KeyChanged(Self::key()));
@@ -163,7 +227,7 @@ decl_module! {
 		/// Authenticates the sudo key and dispatches a function call with `Signed` origin from
 		/// a given account.
 		///
-		/// The dispatch origin for this call must be _Signed_.
+		/// The dispatch origin for this call must be _Signed_, and `AnnounceDelay` must be zero.
 		///
 		/// # <weight>
 		/// - O(1).
@@ -184,6 +248,7 @@ decl_module! {
 			// This is a public call, so we ensure that the origin is some signed account.
 			let sender = ensure_signed(origin)?;
 			ensure!(sender == Self::key(), Error::<T>::RequireSudo);
+			ensure!(T::AnnounceDelay::get().is_zero(), Error::<T>::RequireAnnouncement);
 
 			let who = T::Lookup::lookup(who)?;
 
@@ -198,17 +263,114 @@ decl_module! {
 
 			Self::deposit_event(RawEvent::SudoAsDone(res));
 		}
+
+		/// Authenticates the sudo key and announces a function call, dispatched with `Root`
+		/// origin at the beginning of the block `AnnounceDelay` blocks later.
+		///
+		/// At most `MaxAnnouncementsPerBlock` calls can be announced for the same block, and
+		/// their summed weight can't exceed the maximum block weight.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// # <weight>
+		/// - O(A) where A is the number of calls announced for the same block.
+		/// - Limited storage reads.
+		/// - Three DB writes, one DB write (event).
+		/// - Weight of derivative `call` execution + 10,000, charged at the announcement. The
+		///   weight of the call is also registered in the block it is made in.
+		/// # </weight>
+		#[weight = FunctionOf(
+			|args: (&Box<<T as Trait>::Call>,)| args.0.get_dispatch_info().weight + 10_000,
+			|_: (&Box<<T as Trait>::Call>,)| DispatchClass::Operational,
+			true
+		)]
+		fn announce(origin, call: Box<<T as Trait>::Call>) {
+			// This is a public call, so we ensure that the origin is some signed account.
+			let sender = ensure_signed(origin)?;
+			ensure!(sender == Self::key(), Error::<T>::RequireSudo);
+
+			let when = <frame_system::Module<T>>::block_number()
+				.saturating_add(T::AnnounceDelay::get().max(One::one()));
+			ensure!(
+				(<Agenda<T>>::decode_len(when).unwrap_or(0) as u32) < T::MaxAnnouncementsPerBlock::get(),
+				Error::<T>::TooManyAnnouncements,
+			);
+			// the weight of the announced calls is registered unchecked, so it must fit in a block.
+			let announced_weight = <Agenda<T>>::get(when).into_iter()
+				.filter_map(<Announcements<T>>::get)
+				.fold(call.get_dispatch_info().weight, |weight, (_, call)| {
+					weight.saturating_add(call.get_dispatch_info().weight)
+				});
+			ensure!(
+				announced_weight <= <T as frame_system::Trait>::MaximumBlockWeight::get(),
+				Error::<T>::AnnouncementsTooHeavy,
+			);
+
+			let index = AnnouncementCount::get();
+			AnnouncementCount::put(index.wrapping_add(1));
+			<Announcements<T>>::insert(index, (when, *call));
+			<Agenda<T>>::append_or_insert(when, &[index][..]);
+
+			Self::deposit_event(RawEvent::Announced(index, when));
+		}
+
+		/// Authenticates the sudo key and cancels the announced call of the given index.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// # <weight>
+		/// - O(A) where A is the number of calls announced for the same block.
+		/// - Limited storage reads.
+		/// - Two DB writes, one DB write (event).
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedOperational(10_000)]
+		fn cancel_announcement(origin, index: AnnouncementIndex) {
+			// This is a public call, so we ensure that the origin is some signed account.
+			let sender = ensure_signed(origin)?;
+			ensure!(sender == Self::key(), Error::<T>::RequireSudo);
+
+			let (when, _) = <Announcements<T>>::take(index).ok_or(Error::<T>::UnknownAnnouncement)?;
+			<Agenda<T>>::mutate(when, |indexes| indexes.retain(|i| *i != index));
+
+			Self::deposit_event(RawEvent::AnnouncementCancelled(index));
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Dispatch `call` with `Root` origin, returning whether it succeeded.
+	fn dispatch_root(call: <T as Trait>::Call) -> bool {
+		match call.dispatch(frame_system::RawOrigin::Root.into()) {
+			Ok(_) => true,
+			Err(e) => {
+				let e: DispatchError = e.into();
+				sp_runtime::print(e);
+				false
+			}
+		}
 	}
 }
 
+/// The index of an announced call.
+pub type AnnouncementIndex = u32;
+
 decl_event!(
-	pub enum Event<T> where AccountId = <T as frame_system::Trait>::AccountId {
+	pub enum Event<T> where
+		AccountId = <T as frame_system::Trait>::AccountId,
+		BlockNumber = <T as frame_system::Trait>::BlockNumber,
+	{
 		/// A sudo just took place.
 		Sudid(bool),
 		/// The sudoer just switched identity; the old key is supplied.
 		KeyChanged(AccountId),
 		/// A sudo just took place.
 		SudoAsDone(bool),
+		/// A call was announced, to be made at the given block.
+		Announced(AnnouncementIndex, BlockNumber),
+		/// An announced call was cancelled.
+		AnnouncementCancelled(AnnouncementIndex),
+		/// An announced call was made.
+		AnnouncementDone(AnnouncementIndex, bool),
 	}
 );
 
@@ -216,6 +378,17 @@ decl_storage! {
 	trait Store for Module<T: Trait> as Sudo {
 		/// The `AccountId` of the sudo key.
 		Key get(fn key) config(): T::AccountId;
+
+		/// The number of calls announced so far.
+		AnnouncementCount get(fn announcement_count): AnnouncementIndex;
+
+		/// The announced calls, along with the block they are to be made at.
+		Announcements get(fn announcement):
+			map hasher(twox_64_concat) AnnouncementIndex
+			=> Option<(T::BlockNumber, <T as Trait>::Call)>;
+
+		/// The indexes of the calls announced to be made at each block.
+		Agenda: map hasher(twox_64_concat) T::BlockNumber => Vec<AnnouncementIndex>;
 	}
 }
 
@@ -224,5 +397,13 @@ decl_error! {
 	pub enum Error for Module<T: Trait> {
 		/// Sender must be the Sudo account
 		RequireSudo,
+		/// Calls must be announced first, as the announce delay isn't zero
+		RequireAnnouncement,
+		/// No call is announced with the given index
+		UnknownAnnouncement,
+		/// The maximum number of calls are already announced for the same block
+		TooManyAnnouncements,
+		/// The calls announced for the same block would exceed the maximum block weight
+		AnnouncementsTooHeavy,
 	}
 }
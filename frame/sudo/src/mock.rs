@@ -0,0 +1,156 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Test utilities
+
+use super::*;
+
+use std::cell::RefCell;
+use codec::Decode;
+use frame_support::{
+	impl_outer_origin, impl_outer_dispatch, impl_outer_event, parameter_types, weights::Weight,
+};
+use sp_core::H256;
+// The testing primitives are very useful for avoiding having to work with signatures
+// or public keys. `u64` is used as the `AccountId` and no `Signature`s are required.
+use sp_runtime::{
+	Perbill, traits::{BlakeTwo256, IdentityLookup, OnInitialize, OnFinalize}, testing::Header,
+};
+use crate as sudo;
+
+impl_outer_origin! {
+	pub enum Origin for Test where system = frame_system {}
+}
+
+impl_outer_event! {
+	pub enum TestEvent for Test {
+		system<T>,
+		sudo<T>,
+	}
+}
+
+impl_outer_dispatch! {
+	pub enum Call for Test where origin: Origin {
+		frame_system::System,
+		sudo::Sudo,
+	}
+}
+
+// For testing the pallet, we construct most of a mock runtime. This means
+// first constructing a configuration type (`Test`) which `impl`s each of the
+// configuration traits of pallets we want to use.
+#[derive(Clone, Eq, PartialEq)]
+pub struct Test;
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: Weight = 1_000_000;
+	pub const MaximumBlockLength: u32 = 2 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::one();
+}
+
+impl frame_system::Trait for Test {
+	type Origin = Origin;
+	type Call = Call;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = TestEvent;
+	type BlockHashCount = BlockHashCount;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type MaximumBlockLength = MaximumBlockLength;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type Version = ();
+	type ModuleToIndex = ();
+	type AccountData = ();
+	type MigrateAccount = (); type MigrateAccountData = (); type OnNewAccount = ();
+	type OnKilledAccount = ();
+}
+
+thread_local! {
+	static ANNOUNCE_DELAY: RefCell<u64> = RefCell::new(0);
+}
+
+pub struct AnnounceDelay;
+impl Get<u64> for AnnounceDelay {
+	fn get() -> u64 { ANNOUNCE_DELAY.with(|v| *v.borrow()) }
+}
+
+parameter_types! {
+	pub const MaxAnnouncementsPerBlock: u32 = 2;
+}
+
+impl Trait for Test {
+	type Event = TestEvent;
+	type Call = Call;
+	type AnnounceDelay = AnnounceDelay;
+	type MaxAnnouncementsPerBlock = MaxAnnouncementsPerBlock;
+}
+
+pub type Sudo = Module<Test>;
+pub type System = frame_system::Module<Test>;
+
+pub type SystemCall = frame_system::Call<Test>;
+pub type SudoCall = super::Call<Test>;
+
+pub struct ExtBuilder {
+	announce_delay: u64,
+}
+
+impl Default for ExtBuilder {
+	fn default() -> Self {
+		Self { announce_delay: 0 }
+	}
+}
+
+impl ExtBuilder {
+	pub fn announce_delay(mut self, announce_delay: u64) -> Self {
+		self.announce_delay = announce_delay;
+		self
+	}
+
+	pub fn build(self) -> sp_io::TestExternalities {
+		ANNOUNCE_DELAY.with(|v| *v.borrow_mut() = self.announce_delay);
+		let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		GenesisConfig::<Test> {
+			key: 1,
+		}.assimilate_storage(&mut t).unwrap();
+		let mut ext: sp_io::TestExternalities = t.into();
+		ext.execute_with(|| System::set_block_number(1));
+		ext
+	}
+}
+
+/// Run until a particular block.
+pub fn run_to_block(n: u64) {
+	while System::block_number() < n {
+		Sudo::on_finalize(System::block_number());
+		System::on_finalize(System::block_number());
+		System::set_block_number(System::block_number() + 1);
+		System::on_initialize(System::block_number());
+		Sudo::on_initialize(System::block_number());
+	}
+}
+
+/// The heap pages set through `SystemCall::set_heap_pages`, if any.
+pub fn heap_pages() -> Option<u64> {
+	sp_io::storage::get(sp_core::storage::well_known_keys::HEAP_PAGES)
+		.map(|pages| u64::decode(&mut &pages[..]).unwrap())
+}
@@ -0,0 +1,154 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Tests for the module.
+
+use super::*;
+use mock::{
+	Sudo, System, Test, Origin, Call, SystemCall, SudoCall, TestEvent, ExtBuilder, run_to_block,
+	heap_pages,
+};
+use frame_support::{assert_noop, assert_ok};
+
+fn last_event() -> TestEvent {
+	System::events().pop().expect("Event expected").event
+}
+
+#[test]
+fn sudo_works_without_announce_delay() {
+	ExtBuilder::default().build().execute_with(|| {
+		let call = Box::new(Call::System(SystemCall::set_heap_pages(42)));
+		assert_noop!(Sudo::sudo(Origin::signed(2), call.clone()), Error::<Test>::RequireSudo);
+		assert_ok!(Sudo::sudo(Origin::signed(1), call));
+		assert_eq!(heap_pages(), Some(42));
+		assert_eq!(last_event(), TestEvent::sudo(RawEvent::Sudid(true)));
+
+		assert_ok!(Sudo::set_key(Origin::signed(1), 2));
+		assert_eq!(Sudo::key(), 2);
+	});
+}
+
+#[test]
+fn announce_delay_requires_announcements() {
+	ExtBuilder::default().announce_delay(3).build().execute_with(|| {
+		let call = Box::new(Call::System(SystemCall::set_heap_pages(42)));
+		assert_noop!(
+			Sudo::sudo(Origin::signed(1), call.clone()),
+			Error::<Test>::RequireAnnouncement,
+		);
+		assert_noop!(
+			Sudo::sudo_unchecked_weight(Origin::signed(1), call.clone(), 0),
+			Error::<Test>::RequireAnnouncement,
+		);
+		assert_noop!(
+			Sudo::sudo_as(Origin::signed(1), 2, call),
+			Error::<Test>::RequireAnnouncement,
+		);
+		assert_noop!(Sudo::set_key(Origin::signed(1), 2), Error::<Test>::RequireAnnouncement);
+	});
+}
+
+#[test]
+fn announced_calls_are_made_after_the_delay() {
+	ExtBuilder::default().announce_delay(3).build().execute_with(|| {
+		let call = Box::new(Call::System(SystemCall::set_heap_pages(42)));
+		assert_noop!(Sudo::announce(Origin::signed(2), call.clone()), Error::<Test>::RequireSudo);
+		assert_ok!(Sudo::announce(Origin::signed(1), call));
+		assert_eq!(last_event(), TestEvent::sudo(RawEvent::Announced(0, 4)));
+		assert_eq!(Sudo::announcement_count(), 1);
+		assert!(Sudo::announcement(0).is_some());
+
+		run_to_block(3);
+		assert_eq!(heap_pages(), None);
+		assert_eq!(System::all_extrinsics_weight(), 0);
+
+		run_to_block(4);
+		assert_eq!(heap_pages(), Some(42));
+		assert_eq!(last_event(), TestEvent::sudo(RawEvent::AnnouncementDone(0, true)));
+		assert!(Sudo::announcement(0).is_none());
+		// The weight of the call is registered in the block it is made in.
+		assert_eq!(System::all_extrinsics_weight(), 10_000);
+	});
+}
+
+#[test]
+fn announced_calls_can_be_cancelled() {
+	ExtBuilder::default().announce_delay(3).build().execute_with(|| {
+		let call = Box::new(Call::System(SystemCall::set_heap_pages(42)));
+		assert_ok!(Sudo::announce(Origin::signed(1), call));
+
+		assert_noop!(Sudo::cancel_announcement(Origin::signed(2), 0), Error::<Test>::RequireSudo);
+		assert_noop!(
+			Sudo::cancel_announcement(Origin::signed(1), 1),
+			Error::<Test>::UnknownAnnouncement,
+		);
+		assert_ok!(Sudo::cancel_announcement(Origin::signed(1), 0));
+		assert_eq!(last_event(), TestEvent::sudo(RawEvent::AnnouncementCancelled(0)));
+		assert!(Sudo::announcement(0).is_none());
+
+		run_to_block(5);
+		assert_eq!(heap_pages(), None);
+		assert_eq!(System::all_extrinsics_weight(), 0);
+	});
+}
+
+#[test]
+fn key_changes_are_announced() {
+	ExtBuilder::default().announce_delay(3).build().execute_with(|| {
+		let call = Box::new(Call::Sudo(SudoCall::set_key(2)));
+		assert_ok!(Sudo::announce(Origin::signed(1), call));
+		run_to_block(3);
+		assert_eq!(Sudo::key(), 1);
+		run_to_block(4);
+		assert_eq!(Sudo::key(), 2);
+		assert_eq!(last_event(), TestEvent::sudo(RawEvent::AnnouncementDone(0, true)));
+	});
+}
+
+#[test]
+fn announcements_per_block_are_bounded() {
+	ExtBuilder::default().announce_delay(3).build().execute_with(|| {
+		let call = Box::new(Call::System(SystemCall::set_heap_pages(42)));
+		assert_ok!(Sudo::announce(Origin::signed(1), call.clone()));
+		assert_ok!(Sudo::announce(Origin::signed(1), call.clone()));
+		assert_noop!(
+			Sudo::announce(Origin::signed(1), call.clone()),
+			Error::<Test>::TooManyAnnouncements,
+		);
+
+		// Calls can be announced again once one is cancelled, or at the next block.
+		assert_ok!(Sudo::cancel_announcement(Origin::signed(1), 1));
+		assert_ok!(Sudo::announce(Origin::signed(1), call.clone()));
+		run_to_block(2);
+		assert_ok!(Sudo::announce(Origin::signed(1), call));
+	});
+}
+
+#[test]
+fn weight_of_announcements_per_block_is_bounded() {
+	ExtBuilder::default().announce_delay(3).build().execute_with(|| {
+		let inner = Box::new(Call::System(SystemCall::set_heap_pages(42)));
+		let call = Box::new(Call::Sudo(SudoCall::sudo_unchecked_weight(inner, 600_000)));
+		assert_ok!(Sudo::announce(Origin::signed(1), call.clone()));
+		assert_noop!(
+			Sudo::announce(Origin::signed(1), call.clone()),
+			Error::<Test>::AnnouncementsTooHeavy,
+		);
+
+		run_to_block(2);
+		assert_ok!(Sudo::announce(Origin::signed(1), call));
+	});
+}
@@ -199,7 +199,9 @@ impl<T> Parameter for T where T: Codec + EncodeLike + Clone + Eq + fmt::Debug {}
 /// * `on_runtime_upgrade`: Executes at the beginning of a block prior to on_initialize when there
 /// is a runtime upgrade. This allows each module to upgrade its storage before the storage items are used.
 /// As such, **calling other modules must be avoided**!! Using this function will implement the
-/// [`OnRuntimeUpgrade`](../sp_runtime/traits/trait.OnRuntimeUpgrade.html) trait.
+/// [`OnRuntimeUpgrade`](./traits/trait.OnRuntimeUpgrade.html) trait. The weight consumed by the
+/// upgrade is the one given by its `#[weight]` attribute, unless it is declared as
+/// `fn on_runtime_upgrade() -> Weight`, then it returns it.
 /// * `on_initialize`: Executes at the beginning of a block. Using this function will
 /// implement the [`OnInitialize`](../sp_runtime/traits/trait.OnInitialize.html) trait.
 /// * `on_finalize`: Executes at the end of a block. Using this function will
@@ -441,6 +443,45 @@ macro_rules! decl_module {
 			$($rest)*
 		);
 	};
+	// Add on_runtime_upgrade, returning its weight.
+	(@normalize
+		$(#[$attr:meta])*
+		pub struct $mod_type:ident<
+			$trait_instance:ident: $trait_name:ident$(<I>, I: $instantiable:path $(= $module_default_instance:path)?)?
+		>
+		for enum $call_type:ident where origin: $origin_type:ty, system = $system:ident
+		{ $( $other_where_bounds:tt )* }
+		{ $( $deposit_event:tt )* }
+		{ $( $on_initialize:tt )* }
+		{}
+		{ $( $on_finalize:tt )* }
+		{ $( $offchain:tt )* }
+		{ $( $constants:tt )* }
+		{ $( $error_type:tt )* }
+		[ $( $dispatchables:tt )* ]
+		$(#[doc = $doc_attr:tt])*
+		fn on_runtime_upgrade() -> $return:ty { $( $impl:tt )* }
+		$($rest:tt)*
+	) => {
+		$crate::decl_module!(@normalize
+			$(#[$attr])*
+			pub struct $mod_type<$trait_instance: $trait_name$(<I>, I: $instantiable $(= $module_default_instance)?)?>
+			for enum $call_type where origin: $origin_type, system = $system
+			{ $( $other_where_bounds )* }
+			{ $( $deposit_event )* }
+			{ $( $on_initialize )* }
+			{
+				#[weight = $crate::dispatch::SimpleDispatchInfo::zero()]
+				fn on_runtime_upgrade() -> $return { $( $impl )* }
+			}
+			{ $( $on_finalize )* }
+			{ $( $offchain )* }
+			{ $( $constants )* }
+			{ $( $error_type )* }
+			[ $( $dispatchables )* ]
+			$($rest)*
+		);
+	};
 	// Add on_runtime_upgrade, given weight.
 	(@normalize
 		$(#[$attr:meta])*
@@ -979,6 +1020,7 @@ macro_rules! decl_module {
 					let span = tracing::span!(tracing::Level::DEBUG, "on_initialize");
 					let _enter = span.enter();
 				}
+				$crate::enter_wasm_span!("on_initialize");
 				{ $( $impl )* }
 			}
 		}
@@ -1001,6 +1043,7 @@ macro_rules! decl_module {
 					let span = tracing::span!(tracing::Level::DEBUG, "on_initialize");
 					let _enter = span.enter();
 				}
+				$crate::enter_wasm_span!("on_initialize");
 				{ $( $impl )* }
 			}
 		}
@@ -1021,18 +1064,37 @@ macro_rules! decl_module {
 		{ $( $other_where_bounds:tt )* }
 		#[weight = $weight:expr]
 		fn on_runtime_upgrade() { $( $impl:tt )* }
+	) => {
+		$crate::decl_module! {
+			@impl_on_runtime_upgrade
+			$module<$trait_instance: $trait_name$(<I>, $instance: $instantiable)?>;
+			{ $( $other_where_bounds )* }
+			#[weight = $weight]
+			fn on_runtime_upgrade() -> $crate::dispatch::Weight {
+				{ $( $impl )* }
+				<dyn $crate::dispatch::WeighData<()>>::weigh_data(&$weight, ())
+			}
+		}
+	};
+
+	(@impl_on_runtime_upgrade
+		$module:ident<$trait_instance:ident: $trait_name:ident$(<I>, $instance:ident: $instantiable:path)?>;
+		{ $( $other_where_bounds:tt )* }
+		#[weight = $weight:expr]
+		fn on_runtime_upgrade() -> $return:ty { $( $impl:tt )* }
 	) => {
 		impl<$trait_instance: $trait_name$(<I>, $instance: $instantiable)?>
-			$crate::sp_runtime::traits::OnRuntimeUpgrade
+			$crate::traits::OnRuntimeUpgrade
 			for $module<$trait_instance$(, $instance)?> where $( $other_where_bounds )*
 		{
-			fn on_runtime_upgrade() {
+			fn on_runtime_upgrade() -> $return {
 				use $crate::sp_std::if_std;
 				if_std! {
 					use $crate::tracing;
 					let span = tracing::span!(tracing::Level::DEBUG, "on_runtime_upgrade");
 					let _enter = span.enter();
 				}
+				$crate::enter_wasm_span!("on_runtime_upgrade");
 				{ $( $impl )* }
 			}
 		}
@@ -1043,7 +1105,7 @@ macro_rules! decl_module {
 		{ $( $other_where_bounds:tt )* }
 	) => {
 		impl<$trait_instance: $trait_name$(<I>, $instance: $instantiable)?>
-			$crate::sp_runtime::traits::OnRuntimeUpgrade
+			$crate::traits::OnRuntimeUpgrade
 			for $module<$trait_instance$(, $instance)?> where $( $other_where_bounds )*
 		{}
 	};
@@ -1066,6 +1128,7 @@ macro_rules! decl_module {
 					let span = tracing::span!(tracing::Level::DEBUG, "on_finalize");
 					let _enter = span.enter();
 				}
+				$crate::enter_wasm_span!("on_finalize");
 				{ $( $impl )* }
 			}
 		}
@@ -1088,6 +1151,7 @@ macro_rules! decl_module {
 					let span = tracing::span!(tracing::Level::DEBUG, "on_finalize");
 					let _enter = span.enter();
 				}
+				$crate::enter_wasm_span!("on_finalize");
 				{ $( $impl )* }
 			}
 		}
@@ -1109,7 +1173,8 @@ macro_rules! decl_module {
 		{ $( $other_where_bounds:tt )* }
 		@runtime_upgrade $(
 			#[weight = $weight_runtime_update:expr]
-			fn on_runtime_upgrade($( $param_runtime_upgrade:ident : $param_ty_runtime_upgrade:ty )*) { $( $impl_runtime_upgrade:tt )* }
+			fn on_runtime_upgrade($( $param_runtime_upgrade:ident : $param_ty_runtime_upgrade:ty )*)
+				$( -> $return_runtime_upgrade:ty )? { $( $impl_runtime_upgrade:tt )* }
 		)?
 		@init $(
 			#[weight = $weight_initialize:expr]
@@ -1126,7 +1191,7 @@ macro_rules! decl_module {
 		{
 			$(
 				fn on_runtime_upgrade() -> $crate::dispatch::Weight {
-					<dyn $crate::dispatch::WeighData<()>>::weigh_data(&$weight_initialize, ())
+					<dyn $crate::dispatch::WeighData<()>>::weigh_data(&$weight_runtime_update, ())
 				}
 			)?
 			$(
@@ -1199,6 +1264,7 @@ macro_rules! decl_module {
 				let span = tracing::span!(tracing::Level::DEBUG, stringify!($name));
 				let _enter = span.enter();
 			}
+			$crate::enter_wasm_span!(stringify!($name));
 			{
 				{ $( $impl )* }
 				Ok(())
@@ -1225,6 +1291,7 @@ macro_rules! decl_module {
 				let span = tracing::span!(tracing::Level::DEBUG, stringify!($name));
 				let _enter = span.enter();
 			}
+			$crate::enter_wasm_span!(stringify!($name));
 			{ $( $impl )* }
 		}
 	};
@@ -1782,6 +1849,26 @@ macro_rules! impl_outer_dispatch {
 	}
 }
 
+/// Enter a span of the runtime executed in Wasm with the given name, exited at the end of the
+/// scope. The spans of the native runtime are entered with `tracing` directly.
+#[cfg(feature = "std")]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! enter_wasm_span {
+	( $name:expr ) => {}
+}
+
+/// Enter a span of the runtime executed in Wasm with the given name, exited at the end of the
+/// scope. The spans of the native runtime are entered with `tracing` directly.
+#[cfg(not(feature = "std"))]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! enter_wasm_span {
+	( $name:expr ) => {
+		let _wasm_span_guard = $crate::sp_io::TracingSpanGuard::new(module_path!(), $name);
+	}
+}
+
 /// Implement metadata for dispatch.
 #[macro_export]
 #[doc(hidden)]
@@ -2076,7 +2163,8 @@ macro_rules! __check_reserved_fn_name {
 #[allow(dead_code)]
 mod tests {
 	use super::*;
-	use crate::sp_runtime::traits::{OnInitialize, OnFinalize, OnRuntimeUpgrade};
+	use crate::sp_runtime::traits::{OnInitialize, OnFinalize};
+	use crate::traits::OnRuntimeUpgrade;
 	use crate::weights::{DispatchInfo, DispatchClass};
 	use crate::traits::{CallMetadata, GetCallMetadata, GetCallName};
 
@@ -2266,7 +2354,7 @@ mod tests {
 
 	#[test]
 	fn on_runtime_upgrade_should_work() {
-		<Module<TraitImpl> as OnRuntimeUpgrade>::on_runtime_upgrade();
+		assert_eq!(<Module<TraitImpl> as OnRuntimeUpgrade>::on_runtime_upgrade(), 69);
 	}
 
 	#[test]
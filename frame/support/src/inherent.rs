@@ -19,7 +19,9 @@ pub use crate::sp_std::vec::Vec;
 #[doc(hidden)]
 pub use crate::sp_runtime::traits::{Block as BlockT, Extrinsic};
 #[doc(hidden)]
-pub use sp_inherents::{InherentData, ProvideInherent, CheckInherentsResult, IsFatalError};
+pub use sp_inherents::{
+	InherentData, InherentIdentifier, ProvideInherent, CheckInherentsResult, IsFatalError,
+};
 
 
 /// Implement the outer inherent.
@@ -48,12 +50,28 @@ macro_rules! impl_outer_inherent {
 		trait InherentDataExt {
 			fn create_extrinsics(&self) ->
 				$crate::inherent::Vec<<$block as $crate::inherent::BlockT>::Extrinsic>;
+			fn create_extrinsics_with_identifiers(&self) -> $crate::inherent::Vec<(
+				$crate::inherent::InherentIdentifier,
+				bool,
+				<$block as $crate::inherent::BlockT>::Extrinsic,
+			)>;
 			fn check_extrinsics(&self, block: &$block) -> $crate::inherent::CheckInherentsResult;
 		}
 
 		impl InherentDataExt for $crate::inherent::InherentData {
 			fn create_extrinsics(&self) ->
 				$crate::inherent::Vec<<$block as $crate::inherent::BlockT>::Extrinsic> {
+				self.create_extrinsics_with_identifiers()
+					.into_iter()
+					.map(|(_, _, inherent)| inherent)
+					.collect()
+			}
+
+			fn create_extrinsics_with_identifiers(&self) -> $crate::inherent::Vec<(
+				$crate::inherent::InherentIdentifier,
+				bool,
+				<$block as $crate::inherent::BlockT>::Extrinsic,
+			)> {
 				use $crate::inherent::ProvideInherent;
 				use $crate::inherent::Extrinsic;
 
@@ -61,10 +79,11 @@ macro_rules! impl_outer_inherent {
 
 				$(
 					if let Some(inherent) = $module::create_inherent(self) {
-						inherents.push($uncheckedextrinsic::new(
+						let inherent = $uncheckedextrinsic::new(
 							Call::$call(inherent),
 							None,
-						).expect("Runtime UncheckedExtrinsic is not Opaque, so it has to return `Some`; qed"));
+						).expect("Runtime UncheckedExtrinsic is not Opaque, so it has to return `Some`; qed");
+						inherents.push(($module::INHERENT_IDENTIFIER, $module::IS_MANDATORY, inherent));
 					}
 				)*
 
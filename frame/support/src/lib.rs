@@ -41,6 +41,8 @@ pub use paste;
 #[doc(hidden)]
 pub use sp_state_machine::BasicExternalities;
 #[doc(hidden)]
+pub use sp_io;
+#[doc(hidden)]
 pub use sp_io::storage::root as storage_root;
 #[doc(hidden)]
 pub use sp_runtime::RuntimeDebug;
@@ -216,10 +218,7 @@ macro_rules! assert_ok {
 	}
 }
 
-/// The void type - it cannot exist.
-// Oh rust, you crack me up...
-#[derive(Clone, Eq, PartialEq, RuntimeDebug)]
-pub enum Void {}
+pub use sp_core::Void;
 
 #[cfg(feature = "std")]
 #[doc(hidden)]
@@ -23,12 +23,19 @@
 //! A **key collision free** unique id is required as parameter to avoid key collision
 //! between child tries.
 //! This unique id management and generation responsibility is delegated to pallet module.
+//!
+//! A pallet creating a child trie for each of its ids, e.g. for each contract or each fund,
+//! implements [`ChildTriePrefix`] to derive a collision free [`ChildTrie`] from each id.
 // NOTE: could replace unhashed by having only one kind of storage (root being null storage key (storage_key can become Option<&[u8]>).
 
 use crate::sp_std::prelude::*;
 use codec::{Codec, Encode, Decode};
+use sp_runtime::RuntimeDebug;
 pub use sp_core::storage::ChildInfo;
 
+/// The prefix of the storage keys of the default child tries, followed by their unique id.
+pub const DEFAULT_CHILD_STORAGE_KEY_PREFIX: &[u8] = b":child_storage:default:";
+
 /// Return the value of the item in storage under `key`, or `None` if there is no explicit entry.
 pub fn get<T: Decode + Sized>(
 	storage_key: &[u8],
@@ -172,6 +179,35 @@ pub fn kill_storage(
 	)
 }
 
+/// Remove at most `limit` of the `storage_key` key/values, in lexicographic order of their keys.
+///
+/// Returns `true` if the child storage is empty on return. Killing a large child storage in
+/// batches of bounded size keeps the weight of each call bounded.
+pub fn kill_storage_limit(
+	storage_key: &[u8],
+	child_info: ChildInfo,
+	limit: u32,
+) -> bool {
+	let (data, child_type) = child_info.info();
+	let mut removed = 0;
+	// the empty key is the first one, but `child_next_key` only returns the keys after the given
+	// one.
+	let mut next = if sp_io::storage::child_get(storage_key, data, child_type, &[]).is_some() {
+		Some(Vec::new())
+	} else {
+		sp_io::storage::child_next_key(storage_key, data, child_type, &[])
+	};
+	while let Some(key) = next {
+		if removed == limit {
+			return false
+		}
+		sp_io::storage::child_clear(storage_key, data, child_type, &key);
+		removed += 1;
+		next = sp_io::storage::child_next_key(storage_key, data, child_type, &key);
+	}
+	true
+}
+
 /// Ensure `key` has no explicit entry in storage.
 pub fn kill(
 	storage_key: &[u8],
@@ -219,6 +255,21 @@ pub fn put_raw(
 	)
 }
 
+/// Get the first key of the `storage_key` storage after `key`, in lexicographic order.
+pub fn next_key(
+	storage_key: &[u8],
+	child_info: ChildInfo,
+	key: &[u8],
+) -> Option<Vec<u8>> {
+	let (data, child_type) = child_info.info();
+	sp_io::storage::child_next_key(
+		storage_key,
+		data,
+		child_type,
+		key,
+	)
+}
+
 /// Calculate current child root value.
 pub fn child_root(
 	storage_key: &[u8],
@@ -227,3 +278,200 @@ pub fn child_root(
 		storage_key,
 	)
 }
+
+/// A default child trie, identified by its storage key in the top trie.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug)]
+pub struct ChildTrie {
+	storage_key: Vec<u8>,
+}
+
+impl ChildTrie {
+	/// The child trie of the given unique id.
+	pub fn new(unique_id: &[u8]) -> Self {
+		ChildTrie {
+			storage_key: [DEFAULT_CHILD_STORAGE_KEY_PREFIX, unique_id].concat(),
+		}
+	}
+
+	/// The child trie of the given storage key, if it is the one of a default child trie.
+	pub fn from_storage_key(storage_key: Vec<u8>) -> Option<Self> {
+		if storage_key.starts_with(DEFAULT_CHILD_STORAGE_KEY_PREFIX) {
+			Some(ChildTrie { storage_key })
+		} else {
+			None
+		}
+	}
+
+	/// The storage key of the child trie in the top trie.
+	pub fn storage_key(&self) -> &[u8] {
+		&self.storage_key
+	}
+
+	/// The unique id of the child trie.
+	pub fn unique_id(&self) -> &[u8] {
+		&self.storage_key[DEFAULT_CHILD_STORAGE_KEY_PREFIX.len()..]
+	}
+
+	/// The child info of the child trie.
+	pub fn child_info(&self) -> ChildInfo {
+		ChildInfo::new_default(self.unique_id())
+	}
+
+	/// Return the value of the item under the encoded `key`, or `None` if there is no explicit
+	/// entry.
+	pub fn get<K: Encode, V: Decode + Sized>(&self, key: &K) -> Option<V> {
+		key.using_encoded(|key| get(&self.storage_key, self.child_info(), key))
+	}
+
+	/// Return the value of the item under the encoded `key`, or the type's default if there is no
+	/// explicit entry.
+	pub fn get_or_default<K: Encode, V: Decode + Sized + Default>(&self, key: &K) -> V {
+		self.get(key).unwrap_or_else(Default::default)
+	}
+
+	/// Put `value` under the encoded `key`.
+	pub fn put<K: Encode, V: Encode>(&self, key: &K, value: &V) {
+		key.using_encoded(|key| put(&self.storage_key, self.child_info(), key, value))
+	}
+
+	/// Remove the encoded `key`, returning its value if it had an explicit entry or `None`
+	/// otherwise.
+	pub fn take<K: Encode, V: Decode + Sized>(&self, key: &K) -> Option<V> {
+		key.using_encoded(|key| take(&self.storage_key, self.child_info(), key))
+	}
+
+	/// Check to see if the encoded `key` has an explicit entry.
+	pub fn exists<K: Encode>(&self, key: &K) -> bool {
+		key.using_encoded(|key| exists(&self.storage_key, self.child_info(), key))
+	}
+
+	/// Ensure the encoded `key` has no explicit entry.
+	pub fn kill<K: Encode>(&self, key: &K) {
+		key.using_encoded(|key| kill(&self.storage_key, self.child_info(), key))
+	}
+
+	/// Get the raw value under the raw `key`.
+	pub fn get_raw(&self, key: &[u8]) -> Option<Vec<u8>> {
+		get_raw(&self.storage_key, self.child_info(), key)
+	}
+
+	/// Put the raw `value` under the raw `key`.
+	pub fn put_raw(&self, key: &[u8], value: &[u8]) {
+		put_raw(&self.storage_key, self.child_info(), key, value)
+	}
+
+	/// Ensure the raw `key` has no explicit entry.
+	pub fn kill_raw(&self, key: &[u8]) {
+		kill(&self.storage_key, self.child_info(), key)
+	}
+
+	/// Get the first raw key of the child trie after `key`, in lexicographic order.
+	pub fn next_key(&self, key: &[u8]) -> Option<Vec<u8>> {
+		next_key(&self.storage_key, self.child_info(), key)
+	}
+
+	/// Remove all the key/values of the child trie.
+	pub fn kill_storage(&self) {
+		kill_storage(&self.storage_key, self.child_info())
+	}
+
+	/// Remove at most `limit` of the key/values of the child trie, returning `true` if it is
+	/// empty on return.
+	pub fn kill_storage_limit(&self, limit: u32) -> bool {
+		kill_storage_limit(&self.storage_key, self.child_info(), limit)
+	}
+
+	/// Calculate the current root of the child trie.
+	pub fn root(&self) -> Vec<u8> {
+		child_root(&self.storage_key)
+	}
+}
+
+/// A prefix, unique among those of the runtime, from which a pallet derives a child trie for each
+/// of its ids.
+pub trait ChildTriePrefix {
+	/// The prefix.
+	const PREFIX: &'static [u8];
+
+	/// The child trie of the given id, whose unique id is the hash of the prefix and the id.
+	fn child_trie<Id: Encode>(id: &Id) -> ChildTrie {
+		ChildTrie::new(&(Self::PREFIX, id).using_encoded(sp_io::hashing::blake2_256))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_io::TestExternalities;
+
+	struct Funds;
+	impl ChildTriePrefix for Funds {
+		const PREFIX: &'static [u8] = b"funds";
+	}
+
+	struct Contracts;
+	impl ChildTriePrefix for Contracts {
+		const PREFIX: &'static [u8] = b"contracts";
+	}
+
+	#[test]
+	fn child_tries_of_prefixes_are_distinct() {
+		TestExternalities::default().execute_with(|| {
+			let fund = Funds::child_trie(&1u32);
+			assert_eq!(fund, Funds::child_trie(&1u32));
+			assert_ne!(fund, Funds::child_trie(&2u32));
+			assert_ne!(fund, Contracts::child_trie(&1u32));
+			assert_eq!(ChildTrie::from_storage_key(fund.storage_key().to_vec()), Some(fund.clone()));
+			assert_eq!(ChildTrie::from_storage_key(b":code".to_vec()), None);
+
+			fund.put(&10u64, &100u64);
+			assert_eq!(fund.get(&10u64), Some(100u64));
+			assert_eq!(Funds::child_trie(&2u32).get::<_, u64>(&10u64), None);
+			assert_eq!(Contracts::child_trie(&1u32).get::<_, u64>(&10u64), None);
+
+			assert_eq!(fund.take(&10u64), Some(100u64));
+			assert!(!fund.exists(&10u64));
+		});
+	}
+
+	#[test]
+	fn kill_storage_limit_works() {
+		TestExternalities::default().execute_with(|| {
+			let fund = Funds::child_trie(&1u32);
+			let other = Funds::child_trie(&2u32);
+			for i in 0..5u64 {
+				fund.put(&i, &i);
+			}
+			other.put(&0u64, &0u64);
+
+			assert!(!fund.kill_storage_limit(2));
+			assert_eq!((0..5u64).filter(|i| fund.exists(i)).count(), 3);
+			assert!(!fund.kill_storage_limit(2));
+			assert!(fund.kill_storage_limit(2));
+			assert!((0..5u64).all(|i| !fund.exists(&i)));
+			assert!(fund.kill_storage_limit(0));
+
+			assert!(other.exists(&0u64));
+			assert!(!other.kill_storage_limit(0));
+		});
+	}
+
+	#[test]
+	fn kill_storage_limit_removes_the_empty_key() {
+		TestExternalities::default().execute_with(|| {
+			let fund = Funds::child_trie(&1u32);
+			fund.put_raw(&[], b"empty");
+			fund.put_raw(b"key", b"value");
+
+			assert!(!fund.kill_storage_limit(1));
+			assert_eq!(fund.get_raw(&[]), None);
+			assert_eq!(fund.get_raw(b"key"), Some(b"value".to_vec()));
+			assert!(fund.kill_storage_limit(1));
+			assert_eq!(fund.get_raw(b"key"), None);
+
+			fund.put_raw(&[], b"empty");
+			assert!(!fund.kill_storage_limit(0));
+			assert!(fund.kill_storage_limit(1));
+		});
+	}
+}
@@ -28,6 +28,7 @@ use sp_runtime::{
 };
 use crate::dispatch::Parameter;
 use crate::storage::StorageMap;
+use crate::weights::Weight;
 
 /// Migrate a given account.
 #[impl_trait_for_tuples::impl_for_tuples(30)]
@@ -180,6 +181,21 @@ pub trait Contains<T: Ord> {
 	fn count() -> usize { Self::sorted_members().len() }
 }
 
+/// Something which describes the calls that may be made on behalf of an account, such as the
+/// ones a rescuer may make through a recovered account.
+pub trait InstanceFilter<T>: Sized + Send + Sync {
+	/// Determine whether the call `c` may be made under this filter.
+	fn filter(&self, c: &T) -> bool;
+
+	/// Determine whether this filter allows at least all the calls that `o` allows.
+	fn is_superset(&self, _o: &Self) -> bool { false }
+}
+
+impl<T> InstanceFilter<T> for () {
+	fn filter(&self, _: &T) -> bool { true }
+	fn is_superset(&self, _o: &Self) -> bool { true }
+}
+
 /// Determiner to say whether a given account is unused.
 pub trait IsDeadAccount<AccountId> {
 	/// Is the given account dead?
@@ -206,6 +222,24 @@ pub trait OnKilledAccount<AccountId> {
 	fn on_killed_account(who: &AccountId);
 }
 
+/// The runtime upgrade trait. Implementing this lets you express what should happen
+/// when the runtime upgrades, and changes may need to occur to your module.
+pub trait OnRuntimeUpgrade {
+	/// Perform a module upgrade.
+	///
+	/// Returns the weight consumed by the upgrade, which is accounted in the weight of the block.
+	fn on_runtime_upgrade() -> Weight { 0 }
+}
+
+#[impl_trait_for_tuples::impl_for_tuples(30)]
+impl OnRuntimeUpgrade for Tuple {
+	fn on_runtime_upgrade() -> Weight {
+		let mut weight: Weight = 0;
+		for_tuples!( #( weight = weight.saturating_add(Tuple::on_runtime_upgrade()); )* );
+		weight
+	}
+}
+
 /// A trait for finding the author of a block header based on the `PreRuntime` digests contained
 /// within it.
 pub trait FindAuthor<Author> {
@@ -251,6 +285,20 @@ pub trait KeyOwnerProofSystem<Key> {
 	fn check_proof(key: Key, proof: Self::Proof) -> Option<Self::IdentificationTuple>;
 }
 
+impl<Key> KeyOwnerProofSystem<Key> for () {
+	// The proof and identification tuples are the void type, as no proof can ever be made.
+	type Proof = crate::Void;
+	type IdentificationTuple = crate::Void;
+
+	fn prove(_key: Key) -> Option<Self::Proof> {
+		None
+	}
+
+	fn check_proof(_key: Key, _proof: Self::Proof) -> Option<Self::IdentificationTuple> {
+		None
+	}
+}
+
 /// Handler for when some currency "account" decreased in balance for
 /// some reason.
 ///
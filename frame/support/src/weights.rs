@@ -70,7 +70,9 @@ pub trait ClassifyDispatch<T> {
 /// Means of determining the weight of a block's life cycle hooks: `on_initialize`, `on_finalize`,
 ///  `on_runtime_upgrade`, and such.
 pub trait WeighBlock<BlockNumber> {
-	/// Return the weight of the block's on_runtime_upgrade hook.
+	/// Return the weight declared for the block's on_runtime_upgrade hook.
+	///
+	/// The weight accounted for the upgrade is the one returned by the hook itself.
 	fn on_runtime_upgrade() -> Weight { Zero::zero() }
 	/// Return the weight of the block's on_initialize hook.
 	fn on_initialize(_: BlockNumber) -> Weight { Zero::zero() }
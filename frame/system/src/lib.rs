@@ -50,7 +50,8 @@
 //!     exceed the limits.
 //!   - [`CheckNonce`]: Checks the nonce of the transaction. Contains a single payload of type
 //!     `T::Index`.
-//!   - [`CheckEra`]: Checks the era of the transaction. Contains a single payload of type `Era`.
+//!   - [`CheckMortality`]: Checks the era of the transaction. Contains a single payload of type
+//!     `Era`.
 //!   - [`CheckGenesis`]: Checks the provided genesis hash of the transaction. Must be a part of the
 //!     signed payload of the transaction.
 //!   - [`CheckVersion`]: Checks that the runtime version is the same as the one encoded in the
@@ -128,6 +129,8 @@ use sp_io::TestExternalities;
 pub mod offchain;
 mod migration;
 
+pub use migration::MigrateAccountData;
+
 /// Compute the trie root of a list of extrinsics.
 pub fn extrinsics_root<H: Hash, E: codec::Encode>(extrinsics: &[E]) -> H::Output {
 	extrinsics_data_root::<H>(extrinsics.iter().map(codec::Encode::encode).collect())
@@ -225,6 +228,9 @@ pub trait Trait: 'static + Eq + Clone {
 
 	/// Migrate an account.
 	type MigrateAccount: MigrateAccount<Self::AccountId>;
+
+	/// Translation of the `AccountData` of the accounts from the layout of a previous runtime.
+	type MigrateAccountData: MigrateAccountData<Self::AccountData>;
 }
 
 pub type DigestOf<T> = generic::Digest<<T as Trait>::Hash>;
@@ -397,6 +403,21 @@ decl_storage! {
 
 		/// Stores the `spec_version` and `spec_name` of when the last runtime upgrade happened.
 		pub LastRuntimeUpgrade build(|_| Some(LastRuntimeUpgradeInfo::from(T::Version::get()))): Option<LastRuntimeUpgradeInfo>;
+
+		/// The version of the layout of the `AccountData` of the accounts, see `MigrateAccountData`.
+		pub AccountDataVersion build(|_| <T::MigrateAccountData as MigrateAccountData<T::AccountData>>::VERSION): u32;
+
+		/// The key of the last account translated by the ongoing migration of the `AccountData`, if
+		/// any.
+		AccountDataMigrationCursor: Option<Vec<u8>>;
+
+		/// The accounts after the cursor which were translated on access by the ongoing migration of
+		/// the `AccountData`.
+		AccountDataTranslatedAhead: map hasher(blake2_128_concat) T::AccountId => bool;
+
+		/// Whether the marks of the accounts translated ahead of the last migration of the
+		/// `AccountData` are still being removed.
+		AccountDataMigrationCleanup: bool;
 	}
 	add_extra_genesis {
 		config(changes_trie_config): Option<ChangesTrieConfiguration>;
@@ -432,6 +453,11 @@ decl_event!(
 		NewAccount(AccountId),
 		/// An account was reaped.
 		KilledAccount(AccountId),
+		/// The runtime was upgraded to the given spec version, its migrations consuming the
+		/// given weight.
+		RuntimeUpgraded(u32, Weight),
+		/// The migration of the `AccountData` to the given version of its layout completed.
+		AccountDataMigrated(u32),
 	}
 );
 
@@ -460,8 +486,12 @@ decl_module! {
 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
 		type Error = Error<T>;
 
+		/// The longest period of the mortal eras of the transactions.
+		const MaxMortalityPeriod: u64 = Module::<T>::max_mortality_period();
+
 		fn on_runtime_upgrade() {
 			migration::migrate::<T>();
+			migration::start_account_data_migration::<T>();
 
 			// Remove the old `RuntimeUpgraded` storage entry.
 			let mut runtime_upgraded_key = sp_io::hashing::twox_128(b"System").to_vec();
@@ -501,7 +531,7 @@ decl_module! {
 
 			let current_version = T::Version::get();
 			let new_version = sp_io::misc::runtime_version(&code)
-				.and_then(|v| RuntimeVersion::decode(&mut &v[..]).ok())
+				.and_then(|v| RuntimeVersion::decode_compat(&v).ok())
 				.ok_or_else(|| Error::<T>::FailedToExtractRuntimeVersion)?;
 
 			if new_version.spec_name != current_version.spec_name {
@@ -572,6 +602,7 @@ decl_module! {
 		#[weight = SimpleDispatchInfo::FixedOperational(25_000)]
 		fn suicide(origin) {
 			let who = ensure_signed(origin)?;
+			migration::migrate_account_data::<T>(&who);
 			let account = Account::<T>::get(&who);
 			ensure!(account.refcount == 0, Error::<T>::NonZeroRefCount);
 			ensure!(account.data == T::AccountData::default(), Error::<T>::NonDefaultComposite);
@@ -719,6 +750,16 @@ pub enum RefStatus {
 }
 
 impl<T: Trait> Module<T> {
+	/// The longest period of the mortal eras of the transactions: the number of block hashes
+	/// kept, rounded down to the previous era period, which are powers of two. The birth block
+	/// of a transaction is then known for as long as it is alive.
+	pub fn max_mortality_period() -> u64 {
+		match T::BlockHashCount::get().saturated_into::<u64>() {
+			0 => 0,
+			count => 1 << (63 - count.leading_zeros()),
+		}
+	}
+
 	/// Deposits an event into this block's event record.
 	pub fn deposit_event(event: impl Into<T::Event>) {
 		Self::deposit_event_indexed(&[], event.into());
@@ -726,22 +767,26 @@ impl<T: Trait> Module<T> {
 
 	/// Increment the reference counter on an account.
 	pub fn inc_ref(who: &T::AccountId) {
+		migration::migrate_account_data::<T>(who);
 		Account::<T>::mutate(who, |a| a.refcount = a.refcount.saturating_add(1));
 	}
 
 	/// Decrement the reference counter on an account. This *MUST* only be done once for every time
 	/// you called `inc_ref` on `who`.
 	pub fn dec_ref(who: &T::AccountId) {
+		migration::migrate_account_data::<T>(who);
 		Account::<T>::mutate(who, |a| a.refcount = a.refcount.saturating_sub(1));
 	}
 
 	/// The number of outstanding references for the account `who`.
 	pub fn refs(who: &T::AccountId) -> RefCount {
+		migration::migrate_account_data::<T>(who);
 		Account::<T>::get(who).refcount
 	}
 
 	/// True if the account has no outstanding references.
 	pub fn allow_death(who: &T::AccountId) -> bool {
+		migration::migrate_account_data::<T>(who);
 		Account::<T>::get(who).refcount == 0
 	}
 
@@ -858,6 +903,13 @@ impl<T: Trait> Module<T> {
 		}
 	}
 
+	/// Deposit the event of the runtime upgrade whose migrations consumed the given weight.
+	///
+	/// This is called by the executive once the block of the upgrade is initialized.
+	pub fn note_runtime_upgraded(weight: Weight) {
+		Self::deposit_event(RawEvent::RuntimeUpgraded(T::Version::get().spec_version, weight));
+	}
+
 	/// Remove temporary "environment" entries in storage.
 	pub fn finalize() -> T::Header {
 		ExtrinsicCount::kill();
@@ -956,15 +1008,25 @@ impl<T: Trait> Module<T> {
 	pub fn runtime_version() -> RuntimeVersion { T::Version::get() }
 
 	/// Retrieve the account transaction counter from storage.
-	pub fn account_nonce(who: impl EncodeLike<T::AccountId>) -> T::Index {
+	pub fn account_nonce(who: &T::AccountId) -> T::Index {
+		migration::migrate_account_data::<T>(who);
 		Account::<T>::get(who).nonce
 	}
 
 	/// Increment a particular account's nonce by 1.
-	pub fn inc_account_nonce(who: impl EncodeLike<T::AccountId>) {
+	pub fn inc_account_nonce(who: &T::AccountId) {
+		migration::migrate_account_data::<T>(who);
 		Account::<T>::mutate(who, |a| a.nonce += T::Index::one());
 	}
 
+	/// Translates the next batch of accounts of the ongoing migration of the `AccountData`, if
+	/// any, returning the weight it consumed.
+	///
+	/// This is called by the executive at the beginning of each block.
+	pub fn migrate_account_data() -> Weight {
+		migration::migrate_account_data_batch::<T>()
+	}
+
 	/// Note what the extrinsic data of the current extrinsic index is. If this
 	/// is called, then ensure `derive_extrinsics` is also called before
 	/// block-building is completed.
@@ -1026,6 +1088,7 @@ impl<T: Trait> Module<T> {
 	/// This is a no-op if the account doesn't already exist. If it does then it will ensure
 	/// cleanups (those in `on_killed_account`) take place.
 	fn kill_account(who: &T::AccountId) {
+		migration::migrate_account_data::<T>(who);
 		if Account::<T>::contains_key(who) {
 			let account = Account::<T>::take(who);
 			if account.refcount > 0 {
@@ -1060,12 +1123,14 @@ impl<T: Trait> Happened<T::AccountId> for CallKillAccount<T> {
 // Anything more complex will need more sophisticated logic.
 impl<T: Trait> StoredMap<T::AccountId, T::AccountData> for Module<T> {
 	fn get(k: &T::AccountId) -> T::AccountData {
+		migration::migrate_account_data::<T>(k);
 		Account::<T>::get(k).data
 	}
 	fn is_explicit(k: &T::AccountId) -> bool {
 		Account::<T>::contains_key(k)
 	}
 	fn insert(k: &T::AccountId, data: T::AccountData) {
+		migration::migrate_account_data::<T>(k);
 		let existed = Account::<T>::contains_key(k);
 		Account::<T>::mutate(k, |a| a.data = data);
 		if !existed {
@@ -1076,6 +1141,7 @@ impl<T: Trait> StoredMap<T::AccountId, T::AccountData> for Module<T> {
 		Self::kill_account(k)
 	}
 	fn mutate<R>(k: &T::AccountId, f: impl FnOnce(&mut T::AccountData) -> R) -> R {
+		migration::migrate_account_data::<T>(k);
 		let existed = Account::<T>::contains_key(k);
 		let r = Account::<T>::mutate(k, |a| f(&mut a.data));
 		if !existed {
@@ -1087,6 +1153,7 @@ impl<T: Trait> StoredMap<T::AccountId, T::AccountData> for Module<T> {
 		Self::try_mutate_exists(k, |x| -> Result<R, Infallible> { Ok(f(x)) }).expect("Infallible; qed")
 	}
 	fn try_mutate_exists<R, E>(k: &T::AccountId, f: impl FnOnce(&mut Option<T::AccountData>) -> Result<R, E>) -> Result<R, E> {
+		migration::migrate_account_data::<T>(k);
 		Account::<T>::try_mutate_exists(k, |maybe_value| {
 			let existed = maybe_value.is_some();
 			let (maybe_prefix, mut maybe_data) = split_inner(
@@ -1317,6 +1384,7 @@ impl<T: Trait> SignedExtension for CheckNonce<T> {
 		_info: Self::DispatchInfo,
 		_len: usize,
 	) -> Result<(), TransactionValidityError> {
+		migration::migrate_account_data::<T>(who);
 		let mut account = Account::<T>::get(who);
 		if self.0 != account.nonce {
 			return Err(
@@ -1340,6 +1408,7 @@ impl<T: Trait> SignedExtension for CheckNonce<T> {
 		_len: usize,
 	) -> TransactionValidity {
 		// check index
+		migration::migrate_account_data::<T>(who);
 		let account = Account::<T>::get(who);
 		if self.0 < account.nonce {
 			return InvalidTransaction::Stale.into()
@@ -1369,20 +1438,32 @@ impl<T: Trait> IsDeadAccount<T::AccountId> for Module<T> {
 }
 
 /// Check for transaction mortality.
+///
+/// The period of a mortal era can't exceed `max_period`, above which the birth block of the
+/// transaction could be forgotten while it is still alive.
 #[derive(Encode, Decode, Clone, Eq, PartialEq)]
-pub struct CheckEra<T: Trait + Send + Sync>(Era, sp_std::marker::PhantomData<T>);
+pub struct CheckMortality<T: Trait + Send + Sync>(Era, sp_std::marker::PhantomData<T>);
 
-impl<T: Trait + Send + Sync> CheckEra<T> {
+/// The former name of `CheckMortality`.
+#[deprecated(note = "Renamed to `CheckMortality`")]
+pub type CheckEra<T> = CheckMortality<T>;
+
+impl<T: Trait + Send + Sync> CheckMortality<T> {
 	/// utility constructor. Used only in client/factory code.
 	pub fn from(era: Era) -> Self {
 		Self(era, sp_std::marker::PhantomData)
 	}
+
+	/// The longest period of a mortal era, see `Module::max_mortality_period`.
+	pub fn max_period() -> u64 {
+		<Module<T>>::max_mortality_period()
+	}
 }
 
-impl<T: Trait + Send + Sync> Debug for CheckEra<T> {
+impl<T: Trait + Send + Sync> Debug for CheckMortality<T> {
 	#[cfg(feature = "std")]
 	fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
-		write!(f, "CheckEra({:?})", self.0)
+		write!(f, "CheckMortality({:?})", self.0)
 	}
 
 	#[cfg(not(feature = "std"))]
@@ -1391,13 +1472,13 @@ impl<T: Trait + Send + Sync> Debug for CheckEra<T> {
 	}
 }
 
-impl<T: Trait + Send + Sync> SignedExtension for CheckEra<T> {
+impl<T: Trait + Send + Sync> SignedExtension for CheckMortality<T> {
 	type AccountId = T::AccountId;
 	type Call = T::Call;
 	type AdditionalSigned = T::Hash;
 	type DispatchInfo = DispatchInfo;
 	type Pre = ();
-	const IDENTIFIER: &'static str = "CheckEra";
+	const IDENTIFIER: &'static str = "CheckMortality";
 
 	fn validate(
 		&self,
@@ -1406,6 +1487,12 @@ impl<T: Trait + Send + Sync> SignedExtension for CheckEra<T> {
 		_info: Self::DispatchInfo,
 		_len: usize,
 	) -> TransactionValidity {
+		if let Era::Mortal(period, _) = self.0 {
+			if period > Self::max_period() {
+				return InvalidTransaction::BadMortality.into();
+			}
+		}
+
 		let current_u64 = <Module<T>>::block_number().saturated_into::<u64>();
 		let valid_till = self.0.death(current_u64);
 		Ok(ValidTransaction {
@@ -1416,7 +1503,12 @@ impl<T: Trait + Send + Sync> SignedExtension for CheckEra<T> {
 
 	fn additional_signed(&self) -> Result<Self::AdditionalSigned, TransactionValidityError> {
 		let current_u64 = <Module<T>>::block_number().saturated_into::<u64>();
-		let n = self.0.birth(current_u64).saturated_into::<T::BlockNumber>();
+		let birth = self.0.birth(current_u64);
+		if birth > current_u64 {
+			return Err(InvalidTransaction::Future.into());
+		}
+
+		let n = birth.saturated_into::<T::BlockNumber>();
 		if !<BlockHash<T>>::contains_key(n) {
 			Err(InvalidTransaction::AncientBirthBlock.into())
 		} else {
@@ -1519,7 +1611,7 @@ mod tests {
 	use sp_std::cell::RefCell;
 	use sp_core::H256;
 	use sp_runtime::{traits::{BlakeTwo256, IdentityLookup}, testing::Header, DispatchError};
-	use frame_support::{impl_outer_origin, parameter_types};
+	use frame_support::{impl_outer_origin, parameter_types, traits::OnRuntimeUpgrade};
 
 	impl_outer_origin! {
 		pub enum Origin for Test where system = super {}
@@ -1540,6 +1632,7 @@ mod tests {
 			spec_version: 1,
 			impl_version: 1,
 			apis: sp_version::create_apis_vec!([]),
+			transaction_version: 1,
 		};
 	}
 
@@ -1552,6 +1645,16 @@ mod tests {
 		fn on_killed_account(who: &u64) { KILLED.with(|r| r.borrow_mut().push(*who)) }
 	}
 
+	/// Doubles the data of the accounts, which used to be a `u16`.
+	pub struct DoubleAccountData;
+	impl MigrateAccountData<u32> for DoubleAccountData {
+		const VERSION: u32 = 1;
+		const BATCH_SIZE: u32 = 2;
+		type Old = u16;
+
+		fn translate(old: u16) -> u32 { old as u32 * 2 }
+	}
+
 	impl Trait for Test {
 		type Origin = Origin;
 		type Call = ();
@@ -1570,7 +1673,9 @@ mod tests {
 		type Version = Version;
 		type ModuleToIndex = ();
 		type AccountData = u32;
-		type MigrateAccount = (); type OnNewAccount = ();
+		type MigrateAccount = ();
+		type MigrateAccountData = DoubleAccountData;
+		type OnNewAccount = ();
 		type OnKilledAccount = RecordKilled;
 	}
 
@@ -1744,6 +1849,53 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn account_data_is_translated_when_its_layout_changes() {
+		new_test_ext().execute_with(|| {
+			for who in 1..=5u64 {
+				let old = AccountInfo { nonce: 1u64, refcount: 0, data: who as u16 };
+				storage::unhashed::put(&Account::<Test>::hashed_key_for(who), &old);
+			}
+			AccountDataVersion::put(0);
+
+			<System as OnRuntimeUpgrade>::on_runtime_upgrade();
+			assert_eq!(AccountDataVersion::get(), 1);
+
+			// an account accessed before the batches reach it is translated once.
+			assert_eq!(<System as StoredMap<_, _>>::get(&3), 6);
+			assert_eq!(<System as StoredMap<_, _>>::get(&3), 6);
+			assert_eq!(System::account_nonce(&3), 1);
+			assert_eq!(System::all_extrinsics_weight(), 10_000);
+			// an account which doesn't exist is marked, and never reached by the batches.
+			assert_eq!(<System as StoredMap<_, _>>::get(&42), 0);
+
+			assert_eq!(System::migrate_account_data(), 20_000);
+			assert_eq!(System::migrate_account_data(), 20_000);
+			assert_eq!(System::migrate_account_data(), 10_000);
+			assert!(!AccountDataMigrationCursor::exists());
+			assert!(!AccountDataTranslatedAhead::<Test>::contains_key(3));
+			assert!(AccountDataTranslatedAhead::<Test>::contains_key(42));
+
+			// the remaining marks are removed in batches as well.
+			assert!(AccountDataMigrationCleanup::get());
+			assert!(System::events().is_empty());
+			assert_eq!(System::migrate_account_data(), 5_000);
+			assert!(!AccountDataTranslatedAhead::<Test>::contains_key(42));
+			assert!(!AccountDataMigrationCleanup::get());
+			assert_eq!(System::events().len(), 1);
+			assert_eq!(System::migrate_account_data(), 0);
+
+			for who in 1..=5u64 {
+				assert_eq!(Account::<Test>::get(who), AccountInfo { nonce: 1, refcount: 0, data: who as u32 * 2 });
+			}
+
+			// the accounts are not translated again by the next upgrade.
+			<System as OnRuntimeUpgrade>::on_runtime_upgrade();
+			assert!(!AccountDataMigrationCursor::exists());
+			assert_eq!(<System as StoredMap<_, _>>::get(&3), 6);
+		});
+	}
+
 	#[test]
 	fn prunes_block_hash_mappings() {
 		new_test_ext().execute_with(|| {
@@ -1796,6 +1948,24 @@ mod tests {
 		})
 	}
 
+	#[test]
+	fn signed_ext_check_nonce_chains_the_nonces() {
+		new_test_ext().execute_with(|| {
+			Account::<Test>::insert(1, AccountInfo { nonce: 1, refcount: 0, data: 0 });
+			let info = DispatchInfo::default();
+			let tag = |nonce: u64| (1u64, nonce).encode();
+
+			let ready = CheckNonce::<Test>(1).validate(&1, CALL, info, 0).unwrap();
+			assert_eq!(ready.provides, vec![tag(1)]);
+			assert!(ready.requires.is_empty());
+
+			// the transaction after a gap waits for the one right before it.
+			let future = CheckNonce::<Test>(5).validate(&1, CALL, info, 0).unwrap();
+			assert_eq!(future.provides, vec![tag(5)]);
+			assert_eq!(future.requires, vec![tag(4)]);
+		})
+	}
+
 	#[test]
 	fn signed_ext_check_weight_works_normal_tx() {
 		new_test_ext().execute_with(|| {
@@ -1923,14 +2093,37 @@ mod tests {
 		new_test_ext().execute_with(|| {
 			// future
 			assert_eq!(
-				CheckEra::<Test>::from(Era::mortal(4, 2)).additional_signed().err().unwrap(),
+				CheckMortality::<Test>::from(Era::mortal(4, 2)).additional_signed().err().unwrap(),
+				InvalidTransaction::Future.into(),
+			);
+
+			// ancient
+			System::set_block_number(13);
+			assert_eq!(
+				CheckMortality::<Test>::from(Era::mortal(4, 12)).additional_signed().err().unwrap(),
 				InvalidTransaction::AncientBirthBlock.into(),
 			);
 
 			// correct
-			System::set_block_number(13);
 			<BlockHash<Test>>::insert(12, H256::repeat_byte(1));
-			assert!(CheckEra::<Test>::from(Era::mortal(4, 12)).additional_signed().is_ok());
+			assert!(CheckMortality::<Test>::from(Era::mortal(4, 12)).additional_signed().is_ok());
+		})
+	}
+
+	#[test]
+	fn signed_ext_check_era_should_bound_the_period() {
+		new_test_ext().execute_with(|| {
+			let normal = DispatchInfo { weight: 100, class: DispatchClass::Normal, pays_fee: true };
+			// the 10 block hashes kept are rounded down.
+			assert_eq!(CheckMortality::<Test>::max_period(), 8);
+
+			let ext = CheckMortality::<Test>::from(Era::mortal(8, 0));
+			assert!(ext.validate(&1, CALL, normal, 0).is_ok());
+			let ext = CheckMortality::<Test>::from(Era::mortal(16, 0));
+			assert_eq!(
+				ext.validate(&1, CALL, normal, 0).err().unwrap(),
+				InvalidTransaction::BadMortality.into(),
+			);
 		})
 	}
 
@@ -1941,12 +2134,12 @@ mod tests {
 			let len = 0_usize;
 			let ext = (
 				CheckWeight::<Test>(PhantomData),
-				CheckEra::<Test>::from(Era::mortal(16, 256)),
+				CheckMortality::<Test>::from(Era::mortal(8, 256)),
 			);
 			System::set_block_number(17);
 			<BlockHash<Test>>::insert(16, H256::repeat_byte(1));
 
-			assert_eq!(ext.validate(&1, CALL, normal, len).unwrap().longevity, 15);
+			assert_eq!(ext.validate(&1, CALL, normal, len).unwrap().longevity, 7);
 		})
 	}
 
@@ -35,3 +35,162 @@ pub fn migrate<T: Trait>() {
 	}
 	sp_runtime::print(count);
 }
+
+/// The weight of translating the data of one account.
+const ACCOUNT_DATA_TRANSLATION_WEIGHT: Weight = 10_000;
+/// The weight of removing the mark of one account translated ahead of the migration.
+const TRANSLATED_AHEAD_REMOVAL_WEIGHT: Weight = 5_000;
+
+/// A translation of the `AccountData` of the accounts from the layout of the previous runtime.
+///
+/// When the runtime is upgraded to one whose `VERSION` is greater than the version of the stored
+/// accounts, the accounts start being translated, `BATCH_SIZE` of them at the beginning of each
+/// block. The accounts accessed before being reached are translated right away, so that none of
+/// them is ever decoded with the wrong layout.
+///
+/// Once all the accounts are translated, the marks of the accounts translated ahead are removed,
+/// `BATCH_SIZE` of them at the beginning of each block, after which the `AccountDataMigrated`
+/// event is deposited. The translation to a new layout must be complete before the runtime is
+/// upgraded to the next one.
+///
+/// The accesses to the accounts only check the progress of the migration when `VERSION` isn't
+/// zero, so runtimes should set `MigrateAccountData` back to `()` once the migration completed.
+pub trait MigrateAccountData<AccountData> {
+	/// The version of the current layout of the `AccountData`, zero if no translation is
+	/// configured.
+	const VERSION: u32;
+	/// The maximum number of accounts translated at the beginning of a block.
+	const BATCH_SIZE: u32;
+	/// The layout of the previous version of the `AccountData`.
+	type Old: Decode;
+
+	/// Translates the data of an account from the previous layout.
+	fn translate(old: Self::Old) -> AccountData;
+}
+
+impl<AccountData: Default> MigrateAccountData<AccountData> for () {
+	const VERSION: u32 = 0;
+	const BATCH_SIZE: u32 = 0;
+	type Old = ();
+
+	fn translate(_: ()) -> AccountData {
+		Default::default()
+	}
+}
+
+/// Starts translating the accounts if the layout of their data is older than the current one.
+pub fn start_account_data_migration<T: Trait>() {
+	let version = <T::MigrateAccountData as MigrateAccountData<T::AccountData>>::VERSION;
+	if AccountDataVersion::get() >= version {
+		return
+	}
+
+	if AccountDataMigrationCursor::exists() || AccountDataMigrationCleanup::get() {
+		debug::warn!("Starting a migration of the account data before the previous one completed.");
+	}
+	AccountDataVersion::put(version);
+	AccountDataMigrationCursor::put(Account::<T>::final_prefix().to_vec());
+}
+
+/// Translates the next batch of accounts of the ongoing migration, if any, returning the weight
+/// it consumed.
+pub fn migrate_account_data_batch<T: Trait>() -> Weight {
+	if !is_configured::<T>() {
+		return 0
+	}
+	let batch_size = <T::MigrateAccountData as MigrateAccountData<T::AccountData>>::BATCH_SIZE;
+	if AccountDataMigrationCleanup::get() {
+		return remove_translated_ahead_batch::<T>(batch_size)
+	}
+
+	let mut cursor = match AccountDataMigrationCursor::get() {
+		Some(cursor) => cursor,
+		None => return 0,
+	};
+	let prefix = Account::<T>::final_prefix();
+
+	for translated in 0..batch_size {
+		let key = match sp_io::storage::next_key(&cursor).filter(|key| key.starts_with(&prefix)) {
+			Some(key) => key,
+			None => {
+				AccountDataMigrationCursor::kill();
+				AccountDataMigrationCleanup::put(true);
+				return ACCOUNT_DATA_TRANSLATION_WEIGHT.saturating_mul(translated as Weight)
+			},
+		};
+
+		// the key is the final prefix, the `blake2_128` of the account id and the account id.
+		let translated_ahead = T::AccountId::decode(&mut &key[prefix.len() + 16..])
+			.map(|who| AccountDataTranslatedAhead::<T>::take(&who))
+			.unwrap_or(false);
+		if !translated_ahead {
+			translate_account_data::<T>(&key);
+		}
+		cursor = key;
+	}
+
+	AccountDataMigrationCursor::put(cursor);
+	ACCOUNT_DATA_TRANSLATION_WEIGHT.saturating_mul(batch_size as Weight)
+}
+
+/// Removes the next `batch_size` marks of the accounts translated ahead of the migration, which
+/// completes once none is left, returning the weight it consumed.
+fn remove_translated_ahead_batch<T: Trait>(batch_size: u32) -> Weight {
+	let prefix = AccountDataTranslatedAhead::<T>::final_prefix();
+	for removed in 0..batch_size {
+		// the removed keys are skipped, so the next mark is always the first one.
+		match sp_io::storage::next_key(&prefix).filter(|key| key.starts_with(&prefix)) {
+			Some(key) => storage::unhashed::kill(&key),
+			None => {
+				AccountDataMigrationCleanup::kill();
+				Module::<T>::deposit_event(RawEvent::AccountDataMigrated(AccountDataVersion::get()));
+				return TRANSLATED_AHEAD_REMOVAL_WEIGHT.saturating_mul(removed as Weight)
+			},
+		}
+	}
+	TRANSLATED_AHEAD_REMOVAL_WEIGHT.saturating_mul(batch_size as Weight)
+}
+
+/// Whether a translation of the `AccountData` is configured, in which case the accesses to the
+/// accounts check the progress of the migration.
+fn is_configured<T: Trait>() -> bool {
+	<T::MigrateAccountData as MigrateAccountData<T::AccountData>>::VERSION != 0
+}
+
+/// Translates the data of `who` if the ongoing migration, if any, didn't reach it yet, registering
+/// the weight of the translation.
+///
+/// The account is also marked as translated when it doesn't exist, since it can only be created
+/// with the current layout.
+pub fn migrate_account_data<T: Trait>(who: &T::AccountId) {
+	if !is_configured::<T>() {
+		return
+	}
+	let cursor = match AccountDataMigrationCursor::get() {
+		Some(cursor) => cursor,
+		None => return,
+	};
+
+	let key = Account::<T>::hashed_key_for(who);
+	if key > cursor && !AccountDataTranslatedAhead::<T>::get(who) {
+		translate_account_data::<T>(&key);
+		AccountDataTranslatedAhead::<T>::insert(who, true);
+		Module::<T>::register_extra_weight_unchecked(ACCOUNT_DATA_TRANSLATION_WEIGHT);
+	}
+}
+
+/// Translates the data of the account stored at `key` from the previous layout.
+fn translate_account_data<T: Trait>(key: &[u8]) {
+	type OldAccountInfo<T> = AccountInfo<
+		<T as Trait>::Index,
+		<<T as Trait>::MigrateAccountData as MigrateAccountData<<T as Trait>::AccountData>>::Old,
+	>;
+
+	if let Some(old) = storage::unhashed::get::<OldAccountInfo<T>>(key) {
+		storage::unhashed::put(key, &AccountInfo {
+			nonce: old.nonce,
+			refcount: old.refcount,
+			data: T::MigrateAccountData::translate(old.data),
+		});
+	}
+}
@@ -128,6 +128,7 @@ pub trait SignAndSubmitTransaction<T: crate::Trait, Call> {
 	fn sign_and_submit(call: impl Into<Call>, public: PublicOf<T, Call, Self>) -> Result<(), ()> {
 		let call = call.into();
 		let id = public.clone().into_account();
+		super::migration::migrate_account_data::<T>(&id);
 		let mut account = super::Account::<T>::get(&id);
 		debug::native::debug!(
 			target: "offchain",
@@ -199,6 +199,7 @@ impl<T: Trait> ProvideInherent for Module<T> {
 	type Call = Call<T>;
 	type Error = InherentError;
 	const INHERENT_IDENTIFIER: InherentIdentifier = INHERENT_IDENTIFIER;
+	const IS_MANDATORY: bool = true;
 
 	fn create_inherent(data: &InherentData) -> Option<Self::Call> {
 		let data: T::Moment = extract_inherent_data(data)
@@ -278,7 +279,7 @@ mod tests {
 		type Version = ();
 		type ModuleToIndex = ();
 		type AccountData = ();
-		type MigrateAccount = (); type OnNewAccount = ();
+		type MigrateAccount = (); type MigrateAccountData = (); type OnNewAccount = ();
 		type OnKilledAccount = ();
 	}
 	parameter_types! {
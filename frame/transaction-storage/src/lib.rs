@@ -0,0 +1,382 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Transaction Storage Module
+//!
+//! - [`transaction_storage::Trait`](./trait.Trait.html)
+//! - [`Call`](./enum.Call.html)
+//!
+//! ## Overview
+//!
+//! The transaction storage module lets users store blobs of data on chain, so that they are
+//! available for a retention period of `StoragePeriod` blocks, at a fee per byte.
+//!
+//! The blobs themselves are not kept in the state: they are part of the block bodies, which the
+//! full nodes are expected to keep. Only the content hash of each blob is kept in the state,
+//! along with the root of the binary merkle tree of its chunks.
+//!
+//! Block authors prove that they still keep the blobs by including the storage proof inherent in
+//! each block: at block `n`, a chunk of the blobs stored at block `n - StoragePeriod` is selected
+//! pseudo-randomly out of the parent hash, and the block must carry it along with its merkle
+//! proof. A block lacking a required proof is invalid. Once proved, the blobs of that block
+//! expire, unless they were renewed.
+//!
+//! The nodes keep the data of the blobs to be proved in the blob store of
+//! `sc-transaction-storage`, indexed by content hash, so that the blobs renewed in a block whose
+//! body doesn't contain them can still be proved. The proof is provided to the block authors by
+//! the `InherentDataProvider` of `sp-transaction-storage-proof`.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! * `store` - Store a blob, paying `EntryFee` plus `ByteFee` per byte.
+//! * `renew` - Keep a blob stored for another `StoragePeriod` blocks, paying the same fee.
+//! * `check_proof` - The storage proof inherent, included by the block author.
+//!
+//! [`Call`]: ./enum.Call.html
+//! [`Trait`]: ./trait.Trait.html
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
+use sp_std::{prelude::*, result};
+use sp_io::hashing::blake2_256;
+use sp_runtime::traits::{Zero, Saturating, SaturatedConversion};
+use sp_inherents::{InherentIdentifier, ProvideInherent, InherentData};
+use frame_support::{
+	decl_module, decl_event, decl_storage, decl_error, ensure,
+	traits::{Currency, Get, OnUnbalanced, WithdrawReason, ExistenceRequirement},
+	weights::{SimpleDispatchInfo, FunctionOf, DispatchClass, Weight},
+};
+use frame_system::{self as system, ensure_signed, ensure_none};
+use sp_transaction_storage_proof::{
+	INHERENT_IDENTIFIER, InherentError, TransactionStorageProof, TransactionStorageProofInherentData,
+	ChunkHash, num_chunks, chunk_root, random_chunk, verify_chunk_proof,
+};
+
+pub use sp_transaction_storage_proof::TransactionInfo;
+
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
+type NegativeImbalanceOf<T> =
+	<<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::NegativeImbalance;
+
+pub trait Trait: frame_system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// The currency the storage fees are paid in.
+	type Currency: Currency<Self::AccountId>;
+
+	/// What to do with the storage fees.
+	type FeeDestination: OnUnbalanced<NegativeImbalanceOf<Self>>;
+
+	/// The fee paid for each byte stored.
+	type ByteFee: Get<BalanceOf<Self>>;
+
+	/// The fee paid for each blob stored.
+	type EntryFee: Get<BalanceOf<Self>>;
+
+	/// The maximum size of a blob, in bytes.
+	type MaxTransactionSize: Get<u32>;
+
+	/// The maximum number of blobs stored in a block.
+	type MaxBlockTransactions: Get<u32>;
+
+	/// The number of blocks a blob is kept for, and after which its storage is proved.
+	type StoragePeriod: Get<Self::BlockNumber>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as TransactionStorage {
+		/// The blobs stored at each block which are still kept.
+		pub Transactions get(fn transactions):
+			map hasher(twox_64_concat) T::BlockNumber => Option<Vec<TransactionInfo>>;
+
+		/// The total number of chunks of the blobs stored at each block which are still kept.
+		pub ChunkCount get(fn chunk_count): map hasher(twox_64_concat) T::BlockNumber => u32;
+
+		/// The blobs stored in the current block.
+		BlockTransactions: Vec<TransactionInfo>;
+
+		/// Whether the storage proof has been checked in the current block.
+		ProofChecked: bool;
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where AccountId = <T as frame_system::Trait>::AccountId {
+		/// A blob was stored by the account, at the given index of the block, with the given
+		/// content hash.
+		Stored(AccountId, u32, ChunkHash),
+		/// A blob was renewed by the account, at the given index of the block.
+		Renewed(AccountId, u32),
+		/// The storage proof was checked.
+		ProofChecked,
+	}
+);
+
+decl_error! {
+	/// Error for the transaction storage module.
+	pub enum Error for Module<T: Trait> {
+		/// The blob is empty.
+		EmptyTransaction,
+		/// The blob is larger than `MaxTransactionSize`.
+		TransactionTooLarge,
+		/// The block already holds `MaxBlockTransactions` blobs.
+		TooManyTransactions,
+		/// The blob to renew isn't kept anymore, or never existed.
+		RenewedNotFound,
+		/// No blob is to be proved in this block.
+		UnexpectedProof,
+		/// The storage proof was already checked in this block.
+		DoubleCheck,
+		/// The storage proof doesn't match the blobs to be proved.
+		InvalidProof,
+		/// The storage proof has a chunk larger than `CHUNK_SIZE`, or more than `MAX_PROOF_DEPTH`
+		/// hashes.
+		ProofTooLarge,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		fn deposit_event() = default;
+
+		/// The fee paid for each byte stored.
+		const ByteFee: BalanceOf<T> = T::ByteFee::get();
+
+		/// The fee paid for each blob stored.
+		const EntryFee: BalanceOf<T> = T::EntryFee::get();
+
+		/// The maximum size of a blob, in bytes.
+		const MaxTransactionSize: u32 = T::MaxTransactionSize::get();
+
+		/// The maximum number of blobs stored in a block.
+		const MaxBlockTransactions: u32 = T::MaxBlockTransactions::get();
+
+		/// The number of blocks a blob is kept for.
+		const StoragePeriod: T::BlockNumber = T::StoragePeriod::get();
+
+		/// Store a blob for `StoragePeriod` blocks, paying `EntryFee` plus `ByteFee` for each
+		/// of its bytes.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// - `data`: The blob, at most `MaxTransactionSize` bytes long.
+		///
+		/// Emits `Stored`.
+		///
+		/// # <weight>
+		/// - O(D) where D is the size of the blob, for hashing it.
+		/// - One balance withdraw operation.
+		/// - One storage read and write.
+		/// - One event.
+		/// # </weight>
+		#[weight = FunctionOf(
+			|args: (&Vec<u8>,)| 100_000 + 10 * args.0.len() as Weight,
+			DispatchClass::Normal,
+			true
+		)]
+		fn store(origin, data: Vec<u8>) {
+			let who = ensure_signed(origin)?;
+			ensure!(!data.is_empty(), Error::<T>::EmptyTransaction);
+			ensure!(data.len() <= T::MaxTransactionSize::get() as usize, Error::<T>::TransactionTooLarge);
+
+			let content_hash = blake2_256(&data);
+			let index = Self::push_transaction(&who, chunk_root(&data), content_hash, data.len() as u32)?;
+
+			Self::deposit_event(RawEvent::Stored(who, index, content_hash));
+		}
+
+		/// Keep a blob stored for another `StoragePeriod` blocks, paying the same fee as for
+		/// storing it.
+		///
+		/// The renewed blob is considered as stored in the current block, even though it's not
+		/// part of the block body: the nodes keep it in their blob store, indexed by content hash.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// - `block`: The block the blob was stored or last renewed at.
+		/// - `index`: The index of the blob within that block.
+		///
+		/// Emits `Renewed`.
+		///
+		/// # <weight>
+		/// - O(T) where T is the number of blobs stored at `block`.
+		/// - One balance withdraw operation.
+		/// - Two storage reads and one storage write.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(100_000)]
+		fn renew(origin, block: T::BlockNumber, index: u32) {
+			let who = ensure_signed(origin)?;
+			let info = Self::transactions(block)
+				.and_then(|transactions| transactions.get(index as usize).cloned())
+				.ok_or(Error::<T>::RenewedNotFound)?;
+
+			let index = Self::push_transaction(&who, info.chunk_root, info.content_hash, info.size)?;
+
+			Self::deposit_event(RawEvent::Renewed(who, index));
+		}
+
+		/// Check the proof that a chunk of the blobs stored `StoragePeriod` blocks ago is still
+		/// kept.
+		///
+		/// The chunk is selected out of all the chunks of those blobs using the parent hash as
+		/// the source of randomness.
+		///
+		/// The dispatch origin for this call must be _None_.
+		///
+		/// This is an inherent, which is required in each block having blobs to prove.
+		///
+		/// Emits `ProofChecked`.
+		///
+		/// # <weight>
+		/// - O(T + P) where T is the number of blobs to prove and P the number of hashes of the
+		///   proof, which is at most `MAX_PROOF_DEPTH`.
+		/// - Three storage reads and one storage write.
+		/// - One event.
+		/// # </weight>
+		#[weight = FunctionOf(
+			|args: (&TransactionStorageProof,)| 100_000 + 10_000 * args.0.proof.len() as Weight,
+			DispatchClass::Operational,
+			true
+		)]
+		fn check_proof(origin, proof: TransactionStorageProof) {
+			ensure_none(origin)?;
+			ensure!(proof.is_bounded(), Error::<T>::ProofTooLarge);
+			ensure!(!ProofChecked::get(), Error::<T>::DoubleCheck);
+
+			let target = Self::proved_block().ok_or(Error::<T>::UnexpectedProof)?;
+			let total_chunks = Self::chunk_count(target);
+			ensure!(total_chunks > 0, Error::<T>::UnexpectedProof);
+			let transactions = Self::transactions(target).ok_or(Error::<T>::UnexpectedProof)?;
+
+			let parent_hash = <frame_system::Module<T>>::parent_hash();
+			let selected = random_chunk(parent_hash.as_ref(), total_chunks);
+			let position = transactions.iter()
+				.position(|info| info.block_chunks > selected)
+				.ok_or(Error::<T>::InvalidProof)?;
+			let first_chunk = if position == 0 { 0 } else { transactions[position - 1].block_chunks };
+			let info = &transactions[position];
+
+			ensure!(
+				verify_chunk_proof(
+					&info.chunk_root,
+					selected - first_chunk,
+					info.block_chunks - first_chunk,
+					&proof,
+				),
+				Error::<T>::InvalidProof,
+			);
+
+			ProofChecked::put(true);
+			Self::deposit_event(RawEvent::ProofChecked);
+		}
+
+		fn on_finalize(now: T::BlockNumber) {
+			if let Some(target) = Self::proved_block() {
+				if !Self::chunk_count(target).is_zero() {
+					assert!(ProofChecked::take(), "Storage proof must be checked once in the block");
+				}
+				Transactions::<T>::remove(target);
+				ChunkCount::<T>::remove(target);
+			}
+			ProofChecked::kill();
+
+			let transactions = BlockTransactions::take();
+			if let Some(last) = transactions.last() {
+				ChunkCount::<T>::insert(now, last.block_chunks);
+				Transactions::<T>::insert(now, transactions);
+			}
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The block whose blobs are to be proved in the current block, if any.
+	fn proved_block() -> Option<T::BlockNumber> {
+		let now = <frame_system::Module<T>>::block_number();
+		let period = T::StoragePeriod::get();
+		if now > period {
+			Some(now - period)
+		} else {
+			None
+		}
+	}
+
+	/// Charge `who` for storing `size` bytes, and record the blob in the current block,
+	/// returning its index.
+	fn push_transaction(
+		who: &T::AccountId,
+		chunk_root: ChunkHash,
+		content_hash: ChunkHash,
+		size: u32,
+	) -> result::Result<u32, sp_runtime::DispatchError> {
+		let mut transactions = BlockTransactions::get();
+		ensure!(
+			transactions.len() < T::MaxBlockTransactions::get() as usize,
+			Error::<T>::TooManyTransactions,
+		);
+
+		let fee = T::ByteFee::get()
+			.saturating_mul(size.saturated_into())
+			.saturating_add(T::EntryFee::get());
+		let imbalance = T::Currency::withdraw(
+			who,
+			fee,
+			WithdrawReason::Fee.into(),
+			ExistenceRequirement::KeepAlive,
+		)?;
+		T::FeeDestination::on_unbalanced(imbalance);
+
+		let block_chunks = transactions.last()
+			.map_or(0, |last| last.block_chunks)
+			.saturating_add(num_chunks(size));
+		let index = transactions.len() as u32;
+		transactions.push(TransactionInfo { chunk_root, content_hash, size, block_chunks });
+		BlockTransactions::put(transactions);
+		Ok(index)
+	}
+}
+
+impl<T: Trait> ProvideInherent for Module<T> {
+	type Call = Call<T>;
+	type Error = InherentError;
+	const INHERENT_IDENTIFIER: InherentIdentifier = INHERENT_IDENTIFIER;
+	const IS_MANDATORY: bool = true;
+
+	fn create_inherent(data: &InherentData) -> Option<Self::Call> {
+		let proof = data.storage_proof().ok().and_then(|proof| proof)?;
+		Some(Call::check_proof(proof))
+	}
+
+	fn check_inherent(call: &Self::Call, _data: &InherentData) -> result::Result<(), Self::Error> {
+		// The proof itself is verified when dispatched, as the chunk to prove depends on the
+		// parent hash, which isn't known yet when the inherents are checked.
+		match call {
+			Call::check_proof(proof) if !proof.is_bounded() => Err(InherentError::ProofTooLarge),
+			_ => Ok(()),
+		}
+	}
+}
@@ -0,0 +1,183 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Tests for the module.
+
+use super::*;
+use mock::{TransactionStorage, Balances, System, Test, Origin, new_test_ext, run_to_block};
+use sp_runtime::traits::{OnFinalize, BadOrigin};
+use sp_transaction_storage_proof::{build_proof, chunk_proof, CHUNK_SIZE, MAX_PROOF_DEPTH};
+use frame_support::{assert_noop, assert_ok};
+
+fn blob(size: usize) -> Vec<u8> {
+	(0..size).map(|i| (i % 251) as u8).collect()
+}
+
+fn proof_for(transactions: &[Vec<u8>]) -> TransactionStorageProof {
+	build_proof(System::parent_hash().as_ref(), transactions).unwrap()
+}
+
+#[test]
+fn store_should_work() {
+	new_test_ext().execute_with(|| {
+		let data = blob(2000);
+		assert_ok!(TransactionStorage::store(Origin::signed(1), data.clone()));
+		assert_eq!(Balances::free_balance(1), 100_000 - 200 - 2 * 2000);
+		assert_ok!(TransactionStorage::store(Origin::signed(2), vec![1; 10]));
+
+		run_to_block(2);
+		let transactions = TransactionStorage::transactions(1).unwrap();
+		assert_eq!(transactions.len(), 2);
+		assert_eq!(transactions[0].content_hash, blake2_256(&data));
+		assert_eq!(transactions[0].chunk_root, chunk_root(&data));
+		assert_eq!(transactions[0].block_chunks, 8);
+		assert_eq!(transactions[1].block_chunks, 9);
+		assert_eq!(TransactionStorage::chunk_count(1), 9);
+		assert!(TransactionStorage::transactions(2).is_none());
+	});
+}
+
+#[test]
+fn store_checks_the_blob() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(TransactionStorage::store(Origin::signed(1), vec![]), Error::<Test>::EmptyTransaction);
+		assert_noop!(
+			TransactionStorage::store(Origin::signed(1), blob(4097)),
+			Error::<Test>::TransactionTooLarge,
+		);
+		// The fees must be affordable while keeping the account alive.
+		assert_noop!(
+			TransactionStorage::store(Origin::signed(3), blob(10)),
+			pallet_balances::Error::<Test, _>::InsufficientBalance,
+		);
+		assert_ok!(TransactionStorage::store(Origin::signed(1), blob(10)));
+		assert_ok!(TransactionStorage::store(Origin::signed(1), blob(10)));
+		assert_noop!(
+			TransactionStorage::store(Origin::signed(1), blob(10)),
+			Error::<Test>::TooManyTransactions,
+		);
+	});
+}
+
+#[test]
+fn proofs_should_work() {
+	new_test_ext().execute_with(|| {
+		let transactions = vec![blob(2000), blob(300)];
+		for data in &transactions {
+			assert_ok!(TransactionStorage::store(Origin::signed(1), data.clone()));
+		}
+		run_to_block(11);
+
+		let proof = proof_for(&transactions);
+		assert_ok!(TransactionStorage::check_proof(Origin::NONE, proof.clone()));
+		assert_noop!(TransactionStorage::check_proof(Origin::NONE, proof), Error::<Test>::DoubleCheck);
+		TransactionStorage::on_finalize(11);
+		assert!(TransactionStorage::transactions(1).is_none());
+		assert_eq!(TransactionStorage::chunk_count(1), 0);
+	});
+}
+
+#[test]
+fn invalid_proofs_are_rejected() {
+	new_test_ext().execute_with(|| {
+		let data = blob(2000);
+		assert_ok!(TransactionStorage::store(Origin::signed(1), data.clone()));
+		let proof = proof_for(&[data.clone()]);
+		// Nothing is to be proved yet.
+		assert_noop!(
+			TransactionStorage::check_proof(Origin::NONE, proof.clone()),
+			Error::<Test>::UnexpectedProof,
+		);
+		run_to_block(11);
+
+		let selected = random_chunk(System::parent_hash().as_ref(), 8);
+		let wrong_proof = chunk_proof(&data, (selected + 1) % 8).unwrap();
+		assert_noop!(
+			TransactionStorage::check_proof(Origin::NONE, wrong_proof),
+			Error::<Test>::InvalidProof,
+		);
+		assert_noop!(
+			TransactionStorage::check_proof(Origin::signed(1), proof.clone()),
+			BadOrigin,
+		);
+		let mut too_large_proof = proof.clone();
+		too_large_proof.proof.resize(MAX_PROOF_DEPTH + 1, [0; 32]);
+		assert_noop!(
+			TransactionStorage::check_proof(Origin::NONE, too_large_proof),
+			Error::<Test>::ProofTooLarge,
+		);
+		assert_ok!(TransactionStorage::check_proof(Origin::NONE, proof));
+	});
+}
+
+#[test]
+#[should_panic(expected = "Storage proof must be checked once in the block")]
+fn blocks_without_required_proof_are_invalid() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(TransactionStorage::store(Origin::signed(1), blob(10)));
+		run_to_block(11);
+		TransactionStorage::on_finalize(11);
+	});
+}
+
+#[test]
+fn renew_should_work() {
+	new_test_ext().execute_with(|| {
+		let data = blob(1000);
+		assert_noop!(TransactionStorage::renew(Origin::signed(1), 1, 0), Error::<Test>::RenewedNotFound);
+		assert_ok!(TransactionStorage::store(Origin::signed(1), data.clone()));
+		run_to_block(5);
+		assert_noop!(TransactionStorage::renew(Origin::signed(2), 1, 1), Error::<Test>::RenewedNotFound);
+		assert_ok!(TransactionStorage::renew(Origin::signed(2), 1, 0));
+		assert_eq!(Balances::free_balance(2), 100_000 - 200 - 2 * 1000);
+		run_to_block(11);
+
+		assert_ok!(TransactionStorage::check_proof(Origin::NONE, proof_for(&[data.clone()])));
+		run_to_block(15);
+		// The blob renewed at block 5 must still be proved.
+		assert_eq!(TransactionStorage::transactions(5).unwrap()[0].content_hash, blake2_256(&data));
+		assert_ok!(TransactionStorage::check_proof(Origin::NONE, proof_for(&[data])));
+		TransactionStorage::on_finalize(15);
+		assert!(TransactionStorage::transactions(5).is_none());
+	});
+}
+
+#[test]
+fn create_inherent_uses_the_provided_proof() {
+	let proof = TransactionStorageProof { chunk: vec![1, 2, 3], proof: vec![] };
+	let mut data = InherentData::new();
+	assert!(TransactionStorage::create_inherent(&data).is_none());
+	data.put_data(INHERENT_IDENTIFIER, &proof).unwrap();
+	assert_eq!(TransactionStorage::create_inherent(&data), Some(Call::check_proof(proof)));
+}
+
+#[test]
+fn check_inherent_rejects_too_large_proofs() {
+	let data = InherentData::new();
+	let proof = TransactionStorageProof { chunk: vec![1, 2, 3], proof: vec![[0; 32]; MAX_PROOF_DEPTH] };
+	assert_eq!(TransactionStorage::check_inherent(&Call::check_proof(proof), &data), Ok(()));
+
+	let proof = TransactionStorageProof { chunk: vec![0; CHUNK_SIZE + 1], proof: vec![] };
+	assert_eq!(
+		TransactionStorage::check_inherent(&Call::check_proof(proof), &data),
+		Err(InherentError::ProofTooLarge),
+	);
+	let proof = TransactionStorageProof { chunk: vec![], proof: vec![[0; 32]; MAX_PROOF_DEPTH + 1] };
+	assert_eq!(
+		TransactionStorage::check_inherent(&Call::check_proof(proof), &data),
+		Err(InherentError::ProofTooLarge),
+	);
+}
@@ -0,0 +1,235 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Uniques pallet benchmarking.
+
+use super::*;
+
+use frame_system::RawOrigin;
+use frame_benchmarking::{benchmarks, account};
+use sp_runtime::traits::Bounded;
+
+use crate::Module as Uniques;
+
+const SEED: u32 = 0;
+const MAX_ITEMS: u32 = 1000;
+
+// Creates a class owned and administered by a funded caller.
+fn create_class<T: Trait>() -> T::AccountId {
+	let caller: T::AccountId = account("caller", 0, SEED);
+	let _ = T::Currency::make_free_balance_be(&caller, BalanceOf::<T>::max_value());
+	let caller_lookup = T::Lookup::unlookup(caller.clone());
+	assert!(Uniques::<T>::create(
+		RawOrigin::Signed(caller.clone()).into(),
+		Default::default(),
+		caller_lookup,
+	).is_ok());
+	caller
+}
+
+// Mints the instance `index` of the class to its owner.
+fn mint_instance<T: Trait>(owner: &T::AccountId, index: u32) {
+	assert!(Uniques::<T>::mint(
+		RawOrigin::Signed(owner.clone()).into(),
+		Default::default(),
+		index.into(),
+		T::Lookup::unlookup(owner.clone()),
+	).is_ok());
+}
+
+benchmarks! {
+	_ { }
+
+	create {
+		let caller: T::AccountId = account("caller", 0, SEED);
+		let _ = T::Currency::make_free_balance_be(&caller, BalanceOf::<T>::max_value());
+		let caller_lookup = T::Lookup::unlookup(caller.clone());
+	}: _(RawOrigin::Signed(caller), Default::default(), caller_lookup)
+
+	force_create {
+		let caller: T::AccountId = account("caller", 0, SEED);
+		let caller_lookup = T::Lookup::unlookup(caller);
+	}: _(RawOrigin::Root, Default::default(), caller_lookup, true)
+
+	destroy {
+		let n in 0 .. MAX_ITEMS;
+		let m in 0 .. MAX_ITEMS;
+		let a in 0 .. MAX_ITEMS;
+
+		let caller = create_class::<T>();
+		for i in 0..n {
+			mint_instance::<T>(&caller, i);
+		}
+		for i in 0..m {
+			if i >= n {
+				mint_instance::<T>(&caller, i);
+			}
+			Uniques::<T>::set_metadata(
+				RawOrigin::Signed(caller.clone()).into(),
+				Default::default(),
+				i.into(),
+				vec![0; T::StringLimit::get() as usize],
+				false,
+			)?;
+		}
+		for i in 0..a {
+			Uniques::<T>::set_attribute(
+				RawOrigin::Signed(caller.clone()).into(),
+				Default::default(),
+				None,
+				i.encode(),
+				vec![0; T::ValueLimit::get() as usize],
+			)?;
+		}
+		let witness = Uniques::<T>::class(T::ClassId::default())
+			.ok_or("class not created")?
+			.destroy_witness();
+	}: _(RawOrigin::Signed(caller), Default::default(), witness)
+
+	mint {
+		let caller = create_class::<T>();
+		let caller_lookup = T::Lookup::unlookup(caller.clone());
+	}: _(RawOrigin::Signed(caller), Default::default(), Default::default(), caller_lookup)
+
+	burn {
+		let caller = create_class::<T>();
+		mint_instance::<T>(&caller, 0);
+		Uniques::<T>::set_metadata(
+			RawOrigin::Signed(caller.clone()).into(),
+			Default::default(),
+			Default::default(),
+			vec![0; T::StringLimit::get() as usize],
+			false,
+		)?;
+	}: _(RawOrigin::Signed(caller), Default::default(), Default::default(), None)
+
+	transfer {
+		let caller = create_class::<T>();
+		mint_instance::<T>(&caller, 0);
+		let target: T::AccountId = account("target", 0, SEED);
+		let target_lookup = T::Lookup::unlookup(target);
+	}: _(RawOrigin::Signed(caller), Default::default(), Default::default(), target_lookup)
+
+	freeze {
+		let caller = create_class::<T>();
+		mint_instance::<T>(&caller, 0);
+	}: _(RawOrigin::Signed(caller), Default::default(), Default::default())
+
+	thaw {
+		let caller = create_class::<T>();
+		mint_instance::<T>(&caller, 0);
+		Uniques::<T>::freeze(
+			RawOrigin::Signed(caller.clone()).into(),
+			Default::default(),
+			Default::default(),
+		)?;
+	}: _(RawOrigin::Signed(caller), Default::default(), Default::default())
+
+	freeze_class {
+		let caller = create_class::<T>();
+	}: _(RawOrigin::Signed(caller), Default::default())
+
+	thaw_class {
+		let caller = create_class::<T>();
+		Uniques::<T>::freeze_class(RawOrigin::Signed(caller.clone()).into(), Default::default())?;
+	}: _(RawOrigin::Signed(caller), Default::default())
+
+	transfer_ownership {
+		let caller = create_class::<T>();
+		let target: T::AccountId = account("target", 0, SEED);
+		let target_lookup = T::Lookup::unlookup(target.clone());
+		let _ = T::Currency::make_free_balance_be(&target, BalanceOf::<T>::max_value());
+	}: _(RawOrigin::Signed(caller), Default::default(), target_lookup)
+
+	set_team {
+		let caller = create_class::<T>();
+		let target: T::AccountId = account("target", 0, SEED);
+		let target_lookup = T::Lookup::unlookup(target);
+	}: _(RawOrigin::Signed(caller), Default::default(), target_lookup.clone(), target_lookup)
+
+	approve_transfer {
+		let caller = create_class::<T>();
+		mint_instance::<T>(&caller, 0);
+		let delegate: T::AccountId = account("delegate", 0, SEED);
+		let delegate_lookup = T::Lookup::unlookup(delegate);
+	}: _(RawOrigin::Signed(caller), Default::default(), Default::default(), delegate_lookup)
+
+	cancel_approval {
+		let caller = create_class::<T>();
+		mint_instance::<T>(&caller, 0);
+		let delegate: T::AccountId = account("delegate", 0, SEED);
+		let delegate_lookup = T::Lookup::unlookup(delegate);
+		Uniques::<T>::approve_transfer(
+			RawOrigin::Signed(caller.clone()).into(),
+			Default::default(),
+			Default::default(),
+			delegate_lookup.clone(),
+		)?;
+	}: _(RawOrigin::Signed(caller), Default::default(), Default::default(), Some(delegate_lookup))
+
+	set_class_metadata {
+		let caller = create_class::<T>();
+		let data = vec![0; T::StringLimit::get() as usize];
+	}: _(RawOrigin::Signed(caller), Default::default(), data, false)
+
+	clear_class_metadata {
+		let caller = create_class::<T>();
+		Uniques::<T>::set_class_metadata(
+			RawOrigin::Signed(caller.clone()).into(),
+			Default::default(),
+			vec![0; T::StringLimit::get() as usize],
+			false,
+		)?;
+	}: _(RawOrigin::Signed(caller), Default::default())
+
+	set_metadata {
+		let caller = create_class::<T>();
+		mint_instance::<T>(&caller, 0);
+		let data = vec![0; T::StringLimit::get() as usize];
+	}: _(RawOrigin::Signed(caller), Default::default(), Default::default(), data, false)
+
+	clear_metadata {
+		let caller = create_class::<T>();
+		mint_instance::<T>(&caller, 0);
+		Uniques::<T>::set_metadata(
+			RawOrigin::Signed(caller.clone()).into(),
+			Default::default(),
+			Default::default(),
+			vec![0; T::StringLimit::get() as usize],
+			false,
+		)?;
+	}: _(RawOrigin::Signed(caller), Default::default(), Default::default())
+
+	set_attribute {
+		let caller = create_class::<T>();
+		mint_instance::<T>(&caller, 0);
+		let key = vec![0; T::KeyLimit::get() as usize];
+		let value = vec![0; T::ValueLimit::get() as usize];
+	}: _(RawOrigin::Signed(caller), Default::default(), Some(Default::default()), key, value)
+
+	clear_attribute {
+		let caller = create_class::<T>();
+		mint_instance::<T>(&caller, 0);
+		let key = vec![0; T::KeyLimit::get() as usize];
+		Uniques::<T>::set_attribute(
+			RawOrigin::Signed(caller.clone()).into(),
+			Default::default(),
+			Some(Default::default()),
+			key.clone(),
+			vec![0; T::ValueLimit::get() as usize],
+		)?;
+	}: _(RawOrigin::Signed(caller), Default::default(), Some(Default::default()), key)
+}
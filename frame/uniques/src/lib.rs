@@ -0,0 +1,1235 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Uniques Module
+//!
+//! - [`uniques::Trait`](./trait.Trait.html)
+//! - [`Call`](./enum.Call.html)
+//!
+//! ## Overview
+//!
+//! The uniques module handles non-fungible assets, so that the chains don't have to use
+//! contracts for them. Assets are instances of classes, and each instance of a class has its
+//! own identifier and a single owner.
+//!
+//! A class has an owner, who may set the metadata and the attributes of the class and its
+//! instances and hand the class over, an admin, who mints, burns and moves the instances, and a
+//! freezer, who freezes the transfers of the instances or of the whole class. The owner of an
+//! instance may transfer it, or approve a delegate to transfer it once.
+//!
+//! Storing a class, its instances, metadata and attributes requires deposits, which are reserved
+//! from the owner of the class, unless the class is free holding, as the classes created by
+//! `ForceOrigin` may be.
+//!
+//! ## Interface
+//!
+//! ### Permissionless Functions
+//!
+//! * `create` - Create a class, reserving `ClassDeposit`.
+//!
+//! ### Class Owner Functions
+//!
+//! * `destroy` - Destroy a class and all of its instances, metadata and attributes.
+//! * `transfer_ownership` - Hand the class, and its deposits, over to another account.
+//! * `set_team` - Set the admin and the freezer of a class.
+//! * `set_class_metadata` - Set the metadata of a class.
+//! * `clear_class_metadata` - Clear the metadata of a class.
+//! * `set_metadata` - Set the metadata of an instance.
+//! * `clear_metadata` - Clear the metadata of an instance.
+//! * `set_attribute` - Set an attribute of a class or of an instance.
+//! * `clear_attribute` - Clear an attribute of a class or of an instance.
+//!
+//! ### Admin Functions
+//!
+//! * `mint` - Mint an instance of a class, reserving `InstanceDeposit`.
+//! * `thaw` - Thaw an instance.
+//! * `thaw_class` - Thaw all the instances of a class.
+//!
+//! ### Freezer Functions
+//!
+//! * `freeze` - Freeze the transfers of an instance.
+//! * `freeze_class` - Freeze the transfers of all the instances of a class.
+//!
+//! ### Instance Owner Functions
+//!
+//! * `transfer` - Transfer an instance, which the admin and the approved delegate may do too.
+//! * `burn` - Burn an instance, which the admin may do too.
+//! * `approve_transfer` - Approve a delegate to transfer an instance.
+//! * `cancel_approval` - Cancel the approval of a delegate.
+//!
+//! ### Privileged Functions
+//!
+//! * `force_create` - Create a class, which may be free holding, without reserving a deposit.
+//!
+//! [`Call`]: ./enum.Call.html
+//! [`Trait`]: ./trait.Trait.html
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+use sp_std::prelude::*;
+use codec::{Encode, Decode};
+use sp_runtime::{
+	RuntimeDebug, DispatchResult, DispatchError,
+	traits::{StaticLookup, AtLeast32Bit, Zero, Saturating, EnsureOrigin},
+};
+use frame_support::{
+	Parameter, decl_module, decl_event, decl_storage, decl_error, ensure, IterableStorageDoubleMap,
+	traits::{Currency, ReservableCurrency, Get, BalanceStatus},
+	weights::{SimpleDispatchInfo, FunctionOf, DispatchClass, Weight},
+};
+use frame_system::{self as system, ensure_signed};
+
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
+
+pub trait Trait: frame_system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// The identifier of a class.
+	type ClassId: Parameter + AtLeast32Bit + Default + Copy;
+
+	/// The identifier of an instance within its class.
+	type InstanceId: Parameter + AtLeast32Bit + Default + Copy;
+
+	/// The currency the deposits are reserved in.
+	type Currency: ReservableCurrency<Self::AccountId>;
+
+	/// The origin which may create free holding classes, and act as the owner of any class.
+	type ForceOrigin: EnsureOrigin<Self::Origin>;
+
+	/// The deposit reserved to create a class.
+	type ClassDeposit: Get<BalanceOf<Self>>;
+
+	/// The deposit reserved to mint an instance.
+	type InstanceDeposit: Get<BalanceOf<Self>>;
+
+	/// The base deposit reserved to set the metadata of a class or of an instance.
+	type MetadataDepositBase: Get<BalanceOf<Self>>;
+
+	/// The base deposit reserved to set an attribute.
+	type AttributeDepositBase: Get<BalanceOf<Self>>;
+
+	/// The additional deposit reserved per byte of metadata or attribute.
+	type DepositPerByte: Get<BalanceOf<Self>>;
+
+	/// The maximum length of the metadata.
+	type StringLimit: Get<u32>;
+
+	/// The maximum length of the key of an attribute.
+	type KeyLimit: Get<u32>;
+
+	/// The maximum length of the value of an attribute.
+	type ValueLimit: Get<u32>;
+}
+
+/// The details of a class.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, Default, RuntimeDebug)]
+pub struct ClassDetails<AccountId, Balance> {
+	/// The owner of the class, who may set the metadata and the attributes and destroy it.
+	pub owner: AccountId,
+	/// The admin of the class, who mints, burns and moves the instances.
+	pub admin: AccountId,
+	/// The freezer of the class, who freezes the transfers of the instances.
+	pub freezer: AccountId,
+	/// The sum of the deposits reserved from the owner for the class.
+	pub total_deposit: Balance,
+	/// Whether the class requires no deposits.
+	pub free_holding: bool,
+	/// The number of instances.
+	pub instances: u32,
+	/// The number of instances with metadata.
+	pub instance_metadatas: u32,
+	/// The number of attributes of the class and its instances.
+	pub attributes: u32,
+	/// Whether the transfers of all the instances are frozen.
+	pub is_frozen: bool,
+}
+
+/// The numbers of items a class has, which its destruction must be weighed for.
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode, Default, RuntimeDebug)]
+pub struct DestroyWitness {
+	/// The number of instances.
+	pub instances: u32,
+	/// The number of instances with metadata.
+	pub instance_metadatas: u32,
+	/// The number of attributes of the class and its instances.
+	pub attributes: u32,
+}
+
+impl<AccountId, Balance> ClassDetails<AccountId, Balance> {
+	/// The witness of the destruction of the class.
+	pub fn destroy_witness(&self) -> DestroyWitness {
+		DestroyWitness {
+			instances: self.instances,
+			instance_metadatas: self.instance_metadatas,
+			attributes: self.attributes,
+		}
+	}
+}
+
+/// The details of an instance.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, Default, RuntimeDebug)]
+pub struct InstanceDetails<AccountId, Balance> {
+	/// The owner of the instance.
+	pub owner: AccountId,
+	/// The account approved to transfer the instance, if any.
+	pub approved: Option<AccountId>,
+	/// Whether the transfers of the instance are frozen.
+	pub is_frozen: bool,
+	/// The deposit reserved from the owner of the class for the instance.
+	pub deposit: Balance,
+}
+
+/// The metadata of a class or of an instance.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, Default, RuntimeDebug)]
+pub struct Metadata<Balance> {
+	/// The deposit reserved from the owner of the class for the metadata.
+	pub deposit: Balance,
+	/// The metadata, usually a reference to off-chain data.
+	pub data: Vec<u8>,
+	/// Whether the metadata can't be changed anymore, but by `ForceOrigin`.
+	pub is_frozen: bool,
+}
+
+type ClassDetailsOf<T> = ClassDetails<<T as frame_system::Trait>::AccountId, BalanceOf<T>>;
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Uniques {
+		/// The details of the classes.
+		pub Class get(fn class): map hasher(blake2_128_concat) T::ClassId => Option<ClassDetailsOf<T>>;
+
+		/// The details of the instances.
+		pub Asset get(fn asset):
+			double_map hasher(blake2_128_concat) T::ClassId, hasher(blake2_128_concat) T::InstanceId
+			=> Option<InstanceDetails<T::AccountId, BalanceOf<T>>>;
+
+		/// The metadata of the classes.
+		pub ClassMetadataOf get(fn class_metadata):
+			map hasher(blake2_128_concat) T::ClassId => Option<Metadata<BalanceOf<T>>>;
+
+		/// The metadata of the instances.
+		pub InstanceMetadataOf get(fn instance_metadata):
+			double_map hasher(blake2_128_concat) T::ClassId, hasher(blake2_128_concat) T::InstanceId
+			=> Option<Metadata<BalanceOf<T>>>;
+
+		/// The attributes of the classes, and of their instances, along with their deposits,
+		/// keyed by the class and the instance, or `None` for the attributes of the class.
+		pub Attribute get(fn attribute):
+			double_map hasher(blake2_128_concat) (T::ClassId, Option<T::InstanceId>),
+				hasher(blake2_128_concat) Vec<u8>
+			=> Option<(Vec<u8>, BalanceOf<T>)>;
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where
+		AccountId = <T as frame_system::Trait>::AccountId,
+		ClassId = <T as Trait>::ClassId,
+		InstanceId = <T as Trait>::InstanceId,
+	{
+		/// A class was created by the first account, and is owned by the second one.
+		Created(ClassId, AccountId, AccountId),
+		/// A class was created by `ForceOrigin`.
+		ForceCreated(ClassId, AccountId),
+		/// A class was destroyed.
+		Destroyed(ClassId),
+		/// An instance was minted.
+		Issued(ClassId, InstanceId, AccountId),
+		/// An instance was transferred from the first account to the second one.
+		Transferred(ClassId, InstanceId, AccountId, AccountId),
+		/// An instance was burned.
+		Burned(ClassId, InstanceId, AccountId),
+		/// The transfers of an instance were frozen.
+		Frozen(ClassId, InstanceId),
+		/// The transfers of an instance were thawed.
+		Thawed(ClassId, InstanceId),
+		/// The transfers of the instances of a class were frozen.
+		ClassFrozen(ClassId),
+		/// The transfers of the instances of a class were thawed.
+		ClassThawed(ClassId),
+		/// The class was handed over to the account.
+		OwnerChanged(ClassId, AccountId),
+		/// The admin and the freezer of a class were changed.
+		TeamChanged(ClassId, AccountId, AccountId),
+		/// The owner of an instance approved a delegate to transfer it.
+		ApprovedTransfer(ClassId, InstanceId, AccountId, AccountId),
+		/// The approval of a delegate was cancelled.
+		ApprovalCancelled(ClassId, InstanceId, AccountId, AccountId),
+		/// The metadata of a class was set.
+		ClassMetadataSet(ClassId, Vec<u8>, bool),
+		/// The metadata of a class was cleared.
+		ClassMetadataCleared(ClassId),
+		/// The metadata of an instance was set.
+		MetadataSet(ClassId, InstanceId, Vec<u8>, bool),
+		/// The metadata of an instance was cleared.
+		MetadataCleared(ClassId, InstanceId),
+		/// An attribute of a class, or of one of its instances, was set.
+		AttributeSet(ClassId, Option<InstanceId>, Vec<u8>, Vec<u8>),
+		/// An attribute of a class, or of one of its instances, was cleared.
+		AttributeCleared(ClassId, Option<InstanceId>, Vec<u8>),
+	}
+);
+
+decl_error! {
+	/// Error for the uniques module.
+	pub enum Error for Module<T: Trait> {
+		/// The signer doesn't have the permission for the operation.
+		NoPermission,
+		/// The class or the instance doesn't exist.
+		Unknown,
+		/// The class or the instance already exists.
+		InUse,
+		/// The owner of the instance isn't the one expected.
+		WrongOwner,
+		/// The witness doesn't match the class.
+		BadWitness,
+		/// The instance, its class or the metadata is frozen.
+		Frozen,
+		/// The delegate isn't the approved one.
+		WrongDelegate,
+		/// No delegate is approved.
+		NoDelegate,
+		/// The metadata, the key or the value is too long.
+		TooLong,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		fn deposit_event() = default;
+
+		/// The deposit reserved to create a class.
+		const ClassDeposit: BalanceOf<T> = T::ClassDeposit::get();
+
+		/// The deposit reserved to mint an instance.
+		const InstanceDeposit: BalanceOf<T> = T::InstanceDeposit::get();
+
+		/// The base deposit reserved to set the metadata of a class or of an instance.
+		const MetadataDepositBase: BalanceOf<T> = T::MetadataDepositBase::get();
+
+		/// The base deposit reserved to set an attribute.
+		const AttributeDepositBase: BalanceOf<T> = T::AttributeDepositBase::get();
+
+		/// The additional deposit reserved per byte of metadata or attribute.
+		const DepositPerByte: BalanceOf<T> = T::DepositPerByte::get();
+
+		/// The maximum length of the metadata.
+		const StringLimit: u32 = T::StringLimit::get();
+
+		/// The maximum length of the key of an attribute.
+		const KeyLimit: u32 = T::KeyLimit::get();
+
+		/// The maximum length of the value of an attribute.
+		const ValueLimit: u32 = T::ValueLimit::get();
+
+		/// Create a class owned by the sender, reserving `ClassDeposit` from it.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// - `class`: The identifier of the class, which must be unused.
+		/// - `admin`: The admin and the freezer of the class.
+		///
+		/// Emits `Created`.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - One balance reserve operation.
+		/// - One storage read and write.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(200_000)]
+		fn create(origin, class: T::ClassId, admin: <T::Lookup as StaticLookup>::Source) {
+			let owner = ensure_signed(origin)?;
+			let admin = T::Lookup::lookup(admin)?;
+			ensure!(!Class::<T>::contains_key(class), Error::<T>::InUse);
+
+			let deposit = T::ClassDeposit::get();
+			T::Currency::reserve(&owner, deposit)?;
+
+			Class::<T>::insert(class, ClassDetails {
+				owner: owner.clone(),
+				admin: admin.clone(),
+				freezer: admin.clone(),
+				total_deposit: deposit,
+				free_holding: false,
+				instances: 0,
+				instance_metadatas: 0,
+				attributes: 0,
+				is_frozen: false,
+			});
+
+			Self::deposit_event(RawEvent::Created(class, owner, admin));
+		}
+
+		/// Create a class owned by `owner`, without reserving a deposit.
+		///
+		/// The dispatch origin for this call must match `T::ForceOrigin`.
+		///
+		/// - `class`: The identifier of the class, which must be unused.
+		/// - `owner`: The owner, admin and freezer of the class.
+		/// - `free_holding`: Whether the class requires no deposits.
+		///
+		/// Emits `ForceCreated`.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - One storage read and write.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedOperational(100_000)]
+		fn force_create(
+			origin,
+			class: T::ClassId,
+			owner: <T::Lookup as StaticLookup>::Source,
+			free_holding: bool,
+		) {
+			T::ForceOrigin::ensure_origin(origin)?;
+			let owner = T::Lookup::lookup(owner)?;
+			ensure!(!Class::<T>::contains_key(class), Error::<T>::InUse);
+
+			Class::<T>::insert(class, ClassDetails {
+				owner: owner.clone(),
+				admin: owner.clone(),
+				freezer: owner.clone(),
+				total_deposit: Zero::zero(),
+				free_holding,
+				instances: 0,
+				instance_metadatas: 0,
+				attributes: 0,
+				is_frozen: false,
+			});
+
+			Self::deposit_event(RawEvent::ForceCreated(class, owner));
+		}
+
+		/// Destroy a class, along with all of its instances, metadata and attributes, and
+		/// unreserve the deposits.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the owner of the class, or
+		/// match `T::ForceOrigin`.
+		///
+		/// - `class`: The identifier of the class.
+		/// - `witness`: The numbers of items of the class, as given by its `destroy_witness`.
+		///
+		/// Emits `Destroyed`.
+		///
+		/// # <weight>
+		/// - O(N + M + A) where N is the number of instances, M the number of instances with
+		///   metadata and A the number of attributes.
+		/// - One balance unreserve operation.
+		/// - N + M + A + 2 storage removals, and N + 1 storage prefix removals.
+		/// - One event.
+		/// # </weight>
+		#[weight = FunctionOf(
+			|args: (&T::ClassId, &DestroyWitness)| {
+				let witness = args.1;
+				200_000 + 20_000 * (
+					witness.instances as Weight
+					+ witness.instance_metadatas as Weight
+					+ witness.attributes as Weight
+				)
+			},
+			DispatchClass::Normal,
+			true
+		)]
+		fn destroy(origin, class: T::ClassId, witness: DestroyWitness) {
+			let maybe_check_owner = Self::ensure_owner_or_force(origin)?;
+
+			let details = Class::<T>::get(class).ok_or(Error::<T>::Unknown)?;
+			if let Some(check_owner) = maybe_check_owner {
+				ensure!(details.owner == check_owner, Error::<T>::NoPermission);
+			}
+			ensure!(details.destroy_witness() == witness, Error::<T>::BadWitness);
+
+			Class::<T>::remove(class);
+			for (instance, _) in Asset::<T>::drain(class) {
+				Attribute::<T>::remove_prefix((class, Some(instance)));
+			}
+			ClassMetadataOf::<T>::remove(class);
+			InstanceMetadataOf::<T>::remove_prefix(class);
+			Attribute::<T>::remove_prefix((class, None::<T::InstanceId>));
+			T::Currency::unreserve(&details.owner, details.total_deposit);
+
+			Self::deposit_event(RawEvent::Destroyed(class));
+		}
+
+		/// Mint an instance of a class, reserving `InstanceDeposit` from the owner of the
+		/// class.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the admin of the class.
+		///
+		/// - `class`: The identifier of the class.
+		/// - `instance`: The identifier of the instance, which must be unused.
+		/// - `owner`: The owner of the instance.
+		///
+		/// Emits `Issued`.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - One balance reserve operation.
+		/// - Two storage reads and writes.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(200_000)]
+		fn mint(
+			origin,
+			class: T::ClassId,
+			instance: T::InstanceId,
+			owner: <T::Lookup as StaticLookup>::Source,
+		) {
+			let origin = ensure_signed(origin)?;
+			let owner = T::Lookup::lookup(owner)?;
+			ensure!(!Asset::<T>::contains_key(class, instance), Error::<T>::InUse);
+
+			Class::<T>::try_mutate(class, |maybe_details| -> DispatchResult {
+				let details = maybe_details.as_mut().ok_or(Error::<T>::Unknown)?;
+				ensure!(details.admin == origin, Error::<T>::NoPermission);
+
+				let deposit = if details.free_holding {
+					Zero::zero()
+				} else {
+					T::InstanceDeposit::get()
+				};
+				T::Currency::reserve(&details.owner, deposit)?;
+				details.total_deposit = details.total_deposit.saturating_add(deposit);
+				details.instances = details.instances.saturating_add(1);
+
+				Asset::<T>::insert(class, instance, InstanceDetails {
+					owner: owner.clone(),
+					approved: None,
+					is_frozen: false,
+					deposit,
+				});
+				Ok(())
+			})?;
+
+			Self::deposit_event(RawEvent::Issued(class, instance, owner));
+		}
+
+		/// Burn an instance, removing its metadata and attributes and unreserving their
+		/// deposits along with the one of the instance.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the owner of the instance or
+		/// by the admin of the class.
+		///
+		/// - `class`: The identifier of the class.
+		/// - `instance`: The identifier of the instance.
+		/// - `check_owner`: The owner the instance is expected to have, if any.
+		///
+		/// Emits `Burned`.
+		///
+		/// # <weight>
+		/// - O(A) where A is the number of attributes of the instance.
+		/// - One balance unreserve operation.
+		/// - Three storage reads and writes, and A storage removals.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(200_000)]
+		fn burn(
+			origin,
+			class: T::ClassId,
+			instance: T::InstanceId,
+			check_owner: Option<<T::Lookup as StaticLookup>::Source>,
+		) {
+			let origin = ensure_signed(origin)?;
+			let check_owner = check_owner.map(T::Lookup::lookup).transpose()?;
+
+			let owner = Class::<T>::try_mutate(class, |maybe_details| {
+				let details = maybe_details.as_mut().ok_or(Error::<T>::Unknown)?;
+				let instance_details = Asset::<T>::get(class, instance).ok_or(Error::<T>::Unknown)?;
+				ensure!(
+					instance_details.owner == origin || details.admin == origin,
+					Error::<T>::NoPermission,
+				);
+				ensure!(
+					check_owner.map_or(true, |owner| owner == instance_details.owner),
+					Error::<T>::WrongOwner,
+				);
+
+				let mut deposit = instance_details.deposit;
+				if let Some(metadata) = InstanceMetadataOf::<T>::take(class, instance) {
+					deposit = deposit.saturating_add(metadata.deposit);
+					details.instance_metadatas = details.instance_metadatas.saturating_sub(1);
+				}
+				// the attributes would otherwise be inherited by an instance minted with the same
+				// identifier.
+				for (_, (_, attribute_deposit)) in Attribute::<T>::drain((class, Some(instance))) {
+					deposit = deposit.saturating_add(attribute_deposit);
+					details.attributes = details.attributes.saturating_sub(1);
+				}
+				T::Currency::unreserve(&details.owner, deposit);
+				details.total_deposit = details.total_deposit.saturating_sub(deposit);
+				details.instances = details.instances.saturating_sub(1);
+
+				Asset::<T>::remove(class, instance);
+				Ok::<_, Error<T>>(instance_details.owner)
+			})?;
+
+			Self::deposit_event(RawEvent::Burned(class, instance, owner));
+		}
+
+		/// Transfer an instance to `dest`, cancelling the approval of any delegate.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the owner of the instance, by
+		/// the admin of the class, or by the approved delegate.
+		///
+		/// - `class`: The identifier of the class.
+		/// - `instance`: The identifier of the instance.
+		/// - `dest`: The new owner of the instance.
+		///
+		/// Emits `Transferred`.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - Two storage reads and one storage write.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(100_000)]
+		fn transfer(
+			origin,
+			class: T::ClassId,
+			instance: T::InstanceId,
+			dest: <T::Lookup as StaticLookup>::Source,
+		) {
+			let origin = ensure_signed(origin)?;
+			let dest = T::Lookup::lookup(dest)?;
+
+			let details = Class::<T>::get(class).ok_or(Error::<T>::Unknown)?;
+			ensure!(!details.is_frozen, Error::<T>::Frozen);
+
+			let mut instance_details = Asset::<T>::get(class, instance).ok_or(Error::<T>::Unknown)?;
+			ensure!(!instance_details.is_frozen, Error::<T>::Frozen);
+			ensure!(
+				instance_details.owner == origin
+					|| details.admin == origin
+					|| instance_details.approved.as_ref() == Some(&origin),
+				Error::<T>::NoPermission,
+			);
+
+			let from = sp_std::mem::replace(&mut instance_details.owner, dest.clone());
+			instance_details.approved = None;
+			Asset::<T>::insert(class, instance, instance_details);
+
+			Self::deposit_event(RawEvent::Transferred(class, instance, from, dest));
+		}
+
+		/// Freeze the transfers of an instance.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the freezer of the class.
+		///
+		/// Emits `Frozen`.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - Two storage reads and one storage write.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn freeze(origin, class: T::ClassId, instance: T::InstanceId) {
+			let origin = ensure_signed(origin)?;
+
+			let details = Class::<T>::get(class).ok_or(Error::<T>::Unknown)?;
+			ensure!(details.freezer == origin, Error::<T>::NoPermission);
+			Asset::<T>::try_mutate(class, instance, |maybe_instance| -> DispatchResult {
+				maybe_instance.as_mut().ok_or(Error::<T>::Unknown)?.is_frozen = true;
+				Ok(())
+			})?;
+
+			Self::deposit_event(RawEvent::Frozen(class, instance));
+		}
+
+		/// Thaw the transfers of an instance.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the admin of the class.
+		///
+		/// Emits `Thawed`.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - Two storage reads and one storage write.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn thaw(origin, class: T::ClassId, instance: T::InstanceId) {
+			let origin = ensure_signed(origin)?;
+
+			let details = Class::<T>::get(class).ok_or(Error::<T>::Unknown)?;
+			ensure!(details.admin == origin, Error::<T>::NoPermission);
+			Asset::<T>::try_mutate(class, instance, |maybe_instance| -> DispatchResult {
+				maybe_instance.as_mut().ok_or(Error::<T>::Unknown)?.is_frozen = false;
+				Ok(())
+			})?;
+
+			Self::deposit_event(RawEvent::Thawed(class, instance));
+		}
+
+		/// Freeze the transfers of all the instances of a class.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the freezer of the class.
+		///
+		/// Emits `ClassFrozen`.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - One storage read and write.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn freeze_class(origin, class: T::ClassId) {
+			let origin = ensure_signed(origin)?;
+
+			Class::<T>::try_mutate(class, |maybe_details| -> DispatchResult {
+				let details = maybe_details.as_mut().ok_or(Error::<T>::Unknown)?;
+				ensure!(details.freezer == origin, Error::<T>::NoPermission);
+				details.is_frozen = true;
+				Ok(())
+			})?;
+
+			Self::deposit_event(RawEvent::ClassFrozen(class));
+		}
+
+		/// Thaw the transfers of the instances of a class.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the admin of the class.
+		///
+		/// Emits `ClassThawed`.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - One storage read and write.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn thaw_class(origin, class: T::ClassId) {
+			let origin = ensure_signed(origin)?;
+
+			Class::<T>::try_mutate(class, |maybe_details| -> DispatchResult {
+				let details = maybe_details.as_mut().ok_or(Error::<T>::Unknown)?;
+				ensure!(details.admin == origin, Error::<T>::NoPermission);
+				details.is_frozen = false;
+				Ok(())
+			})?;
+
+			Self::deposit_event(RawEvent::ClassThawed(class));
+		}
+
+		/// Hand a class over to `owner`, moving the deposits of the class to it.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the owner of the class.
+		///
+		/// Emits `OwnerChanged`.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - One balance repatriate operation.
+		/// - One storage read and write.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(100_000)]
+		fn transfer_ownership(
+			origin,
+			class: T::ClassId,
+			owner: <T::Lookup as StaticLookup>::Source,
+		) {
+			let origin = ensure_signed(origin)?;
+			let owner = T::Lookup::lookup(owner)?;
+
+			Class::<T>::try_mutate(class, |maybe_details| -> DispatchResult {
+				let details = maybe_details.as_mut().ok_or(Error::<T>::Unknown)?;
+				ensure!(details.owner == origin, Error::<T>::NoPermission);
+				if details.owner == owner {
+					return Ok(());
+				}
+
+				T::Currency::repatriate_reserved(
+					&details.owner,
+					&owner,
+					details.total_deposit,
+					BalanceStatus::Reserved,
+				)?;
+				details.owner = owner.clone();
+				Ok(())
+			})?;
+
+			Self::deposit_event(RawEvent::OwnerChanged(class, owner));
+		}
+
+		/// Set the admin and the freezer of a class.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the owner of the class.
+		///
+		/// Emits `TeamChanged`.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - One storage read and write.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn set_team(
+			origin,
+			class: T::ClassId,
+			admin: <T::Lookup as StaticLookup>::Source,
+			freezer: <T::Lookup as StaticLookup>::Source,
+		) {
+			let origin = ensure_signed(origin)?;
+			let admin = T::Lookup::lookup(admin)?;
+			let freezer = T::Lookup::lookup(freezer)?;
+
+			Class::<T>::try_mutate(class, |maybe_details| -> DispatchResult {
+				let details = maybe_details.as_mut().ok_or(Error::<T>::Unknown)?;
+				ensure!(details.owner == origin, Error::<T>::NoPermission);
+				details.admin = admin.clone();
+				details.freezer = freezer.clone();
+				Ok(())
+			})?;
+
+			Self::deposit_event(RawEvent::TeamChanged(class, admin, freezer));
+		}
+
+		/// Approve `delegate` to transfer an instance once, replacing any other delegate.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the owner of the instance or by
+		/// the admin of the class.
+		///
+		/// Emits `ApprovedTransfer`.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - Two storage reads and one storage write.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn approve_transfer(
+			origin,
+			class: T::ClassId,
+			instance: T::InstanceId,
+			delegate: <T::Lookup as StaticLookup>::Source,
+		) {
+			let origin = ensure_signed(origin)?;
+			let delegate = T::Lookup::lookup(delegate)?;
+
+			let details = Class::<T>::get(class).ok_or(Error::<T>::Unknown)?;
+			let mut instance_details = Asset::<T>::get(class, instance).ok_or(Error::<T>::Unknown)?;
+			ensure!(
+				instance_details.owner == origin || details.admin == origin,
+				Error::<T>::NoPermission,
+			);
+
+			instance_details.approved = Some(delegate.clone());
+			let owner = instance_details.owner.clone();
+			Asset::<T>::insert(class, instance, instance_details);
+
+			Self::deposit_event(RawEvent::ApprovedTransfer(class, instance, owner, delegate));
+		}
+
+		/// Cancel the approval of the delegate of an instance.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the owner of the instance or by
+		/// the admin of the class.
+		///
+		/// - `maybe_check_delegate`: The delegate the instance is expected to have, if any.
+		///
+		/// Emits `ApprovalCancelled`.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - Two storage reads and one storage write.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn cancel_approval(
+			origin,
+			class: T::ClassId,
+			instance: T::InstanceId,
+			maybe_check_delegate: Option<<T::Lookup as StaticLookup>::Source>,
+		) {
+			let origin = ensure_signed(origin)?;
+			let maybe_check_delegate = maybe_check_delegate.map(T::Lookup::lookup).transpose()?;
+
+			let details = Class::<T>::get(class).ok_or(Error::<T>::Unknown)?;
+			let mut instance_details = Asset::<T>::get(class, instance).ok_or(Error::<T>::Unknown)?;
+			ensure!(
+				instance_details.owner == origin || details.admin == origin,
+				Error::<T>::NoPermission,
+			);
+
+			let delegate = instance_details.approved.take().ok_or(Error::<T>::NoDelegate)?;
+			if let Some(check_delegate) = maybe_check_delegate {
+				ensure!(check_delegate == delegate, Error::<T>::WrongDelegate);
+			}
+			let owner = instance_details.owner.clone();
+			Asset::<T>::insert(class, instance, instance_details);
+
+			Self::deposit_event(RawEvent::ApprovalCancelled(class, instance, owner, delegate));
+		}
+
+		/// Set the metadata of a class, reserving `MetadataDepositBase` and `DepositPerByte`
+		/// for each byte of `data` from the owner of the class.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the owner of the class, or
+		/// match `T::ForceOrigin`, in which case no deposit is reserved and frozen
+		/// metadata may be changed.
+		///
+		/// - `data`: The metadata, at most `StringLimit` bytes long.
+		/// - `is_frozen`: Whether the metadata can't be changed anymore.
+		///
+		/// Emits `ClassMetadataSet`.
+		///
+		/// # <weight>
+		/// - O(D) where D is the length of the metadata.
+		/// - One balance reserve or unreserve operation.
+		/// - Two storage reads and writes.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(200_000)]
+		fn set_class_metadata(origin, class: T::ClassId, data: Vec<u8>, is_frozen: bool) {
+			let maybe_check_owner = Self::ensure_owner_or_force(origin)?;
+			ensure!(data.len() <= T::StringLimit::get() as usize, Error::<T>::TooLong);
+
+			Class::<T>::try_mutate(class, |maybe_details| -> DispatchResult {
+				let details = maybe_details.as_mut().ok_or(Error::<T>::Unknown)?;
+				if let Some(check_owner) = &maybe_check_owner {
+					ensure!(&details.owner == check_owner, Error::<T>::NoPermission);
+				}
+
+				let old = ClassMetadataOf::<T>::get(class);
+				ensure!(
+					maybe_check_owner.is_none() || !old.as_ref().map_or(false, |m| m.is_frozen),
+					Error::<T>::Frozen,
+				);
+
+				let deposit = if details.free_holding || maybe_check_owner.is_none() {
+					Zero::zero()
+				} else {
+					Self::metadata_deposit(data.len())
+				};
+				Self::adjust_deposit(details, old.map_or_else(Zero::zero, |m| m.deposit), deposit)?;
+
+				ClassMetadataOf::<T>::insert(class, Metadata { deposit, data: data.clone(), is_frozen });
+				Ok(())
+			})?;
+
+			Self::deposit_event(RawEvent::ClassMetadataSet(class, data, is_frozen));
+		}
+
+		/// Clear the metadata of a class, unreserving its deposit.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the owner of the class, or
+		/// match `T::ForceOrigin`, in which case frozen metadata may be cleared.
+		///
+		/// Emits `ClassMetadataCleared`.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - One balance unreserve operation.
+		/// - Two storage reads and writes.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(100_000)]
+		fn clear_class_metadata(origin, class: T::ClassId) {
+			let maybe_check_owner = Self::ensure_owner_or_force(origin)?;
+
+			Class::<T>::try_mutate(class, |maybe_details| -> DispatchResult {
+				let details = maybe_details.as_mut().ok_or(Error::<T>::Unknown)?;
+				if let Some(check_owner) = &maybe_check_owner {
+					ensure!(&details.owner == check_owner, Error::<T>::NoPermission);
+				}
+
+				let old = ClassMetadataOf::<T>::get(class).ok_or(Error::<T>::Unknown)?;
+				ensure!(maybe_check_owner.is_none() || !old.is_frozen, Error::<T>::Frozen);
+
+				Self::adjust_deposit(details, old.deposit, Zero::zero())?;
+				ClassMetadataOf::<T>::remove(class);
+				Ok(())
+			})?;
+
+			Self::deposit_event(RawEvent::ClassMetadataCleared(class));
+		}
+
+		/// Set the metadata of an instance, reserving `MetadataDepositBase` and
+		/// `DepositPerByte` for each byte of `data` from the owner of the class.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the owner of the class, or
+		/// match `T::ForceOrigin`, in which case no deposit is reserved and frozen
+		/// metadata may be changed.
+		///
+		/// - `data`: The metadata, at most `StringLimit` bytes long.
+		/// - `is_frozen`: Whether the metadata can't be changed anymore.
+		///
+		/// Emits `MetadataSet`.
+		///
+		/// # <weight>
+		/// - O(D) where D is the length of the metadata.
+		/// - One balance reserve or unreserve operation.
+		/// - Three storage reads and two storage writes.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(200_000)]
+		fn set_metadata(
+			origin,
+			class: T::ClassId,
+			instance: T::InstanceId,
+			data: Vec<u8>,
+			is_frozen: bool,
+		) {
+			let maybe_check_owner = Self::ensure_owner_or_force(origin)?;
+			ensure!(data.len() <= T::StringLimit::get() as usize, Error::<T>::TooLong);
+
+			Class::<T>::try_mutate(class, |maybe_details| -> DispatchResult {
+				let details = maybe_details.as_mut().ok_or(Error::<T>::Unknown)?;
+				if let Some(check_owner) = &maybe_check_owner {
+					ensure!(&details.owner == check_owner, Error::<T>::NoPermission);
+				}
+				ensure!(Asset::<T>::contains_key(class, instance), Error::<T>::Unknown);
+
+				let old = InstanceMetadataOf::<T>::get(class, instance);
+				ensure!(
+					maybe_check_owner.is_none() || !old.as_ref().map_or(false, |m| m.is_frozen),
+					Error::<T>::Frozen,
+				);
+				if old.is_none() {
+					details.instance_metadatas = details.instance_metadatas.saturating_add(1);
+				}
+
+				let deposit = if details.free_holding || maybe_check_owner.is_none() {
+					Zero::zero()
+				} else {
+					Self::metadata_deposit(data.len())
+				};
+				Self::adjust_deposit(details, old.map_or_else(Zero::zero, |m| m.deposit), deposit)?;
+
+				InstanceMetadataOf::<T>::insert(
+					class,
+					instance,
+					Metadata { deposit, data: data.clone(), is_frozen },
+				);
+				Ok(())
+			})?;
+
+			Self::deposit_event(RawEvent::MetadataSet(class, instance, data, is_frozen));
+		}
+
+		/// Clear the metadata of an instance, unreserving its deposit.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the owner of the class, or
+		/// match `T::ForceOrigin`, in which case frozen metadata may be cleared.
+		///
+		/// Emits `MetadataCleared`.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - One balance unreserve operation.
+		/// - Two storage reads and writes.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(100_000)]
+		fn clear_metadata(origin, class: T::ClassId, instance: T::InstanceId) {
+			let maybe_check_owner = Self::ensure_owner_or_force(origin)?;
+
+			Class::<T>::try_mutate(class, |maybe_details| -> DispatchResult {
+				let details = maybe_details.as_mut().ok_or(Error::<T>::Unknown)?;
+				if let Some(check_owner) = &maybe_check_owner {
+					ensure!(&details.owner == check_owner, Error::<T>::NoPermission);
+				}
+
+				let old = InstanceMetadataOf::<T>::get(class, instance).ok_or(Error::<T>::Unknown)?;
+				ensure!(maybe_check_owner.is_none() || !old.is_frozen, Error::<T>::Frozen);
+
+				Self::adjust_deposit(details, old.deposit, Zero::zero())?;
+				details.instance_metadatas = details.instance_metadatas.saturating_sub(1);
+				InstanceMetadataOf::<T>::remove(class, instance);
+				Ok(())
+			})?;
+
+			Self::deposit_event(RawEvent::MetadataCleared(class, instance));
+		}
+
+		/// Set an attribute of a class, or of one of its instances, reserving
+		/// `AttributeDepositBase` and `DepositPerByte` for each byte of the key and the value
+		/// from the owner of the class.
+		///
+		/// The attributes of a class or instance whose metadata is frozen can't be changed.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the owner of the class, or
+		/// match `T::ForceOrigin`, in which case no deposit is reserved and the attributes of
+		/// frozen metadata may be changed.
+		///
+		/// - `maybe_instance`: The instance, or `None` for an attribute of the class.
+		/// - `key`: The key, at most `KeyLimit` bytes long.
+		/// - `value`: The value, at most `ValueLimit` bytes long.
+		///
+		/// Emits `AttributeSet`.
+		///
+		/// # <weight>
+		/// - O(K + V) where K is the length of the key and V the one of the value.
+		/// - One balance reserve or unreserve operation.
+		/// - Four storage reads and two storage writes.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(200_000)]
+		fn set_attribute(
+			origin,
+			class: T::ClassId,
+			maybe_instance: Option<T::InstanceId>,
+			key: Vec<u8>,
+			value: Vec<u8>,
+		) {
+			let maybe_check_owner = Self::ensure_owner_or_force(origin)?;
+			ensure!(key.len() <= T::KeyLimit::get() as usize, Error::<T>::TooLong);
+			ensure!(value.len() <= T::ValueLimit::get() as usize, Error::<T>::TooLong);
+
+			Class::<T>::try_mutate(class, |maybe_details| -> DispatchResult {
+				let details = maybe_details.as_mut().ok_or(Error::<T>::Unknown)?;
+				if let Some(check_owner) = &maybe_check_owner {
+					ensure!(&details.owner == check_owner, Error::<T>::NoPermission);
+				}
+
+				if let Some(instance) = maybe_instance {
+					ensure!(Asset::<T>::contains_key(class, instance), Error::<T>::Unknown);
+				}
+				Self::ensure_attributes_unfrozen(class, maybe_instance, &maybe_check_owner)?;
+
+				let old = Attribute::<T>::get((class, maybe_instance), &key);
+				if old.is_none() {
+					details.attributes = details.attributes.saturating_add(1);
+				}
+
+				let deposit = if details.free_holding || maybe_check_owner.is_none() {
+					Zero::zero()
+				} else {
+					T::AttributeDepositBase::get().saturating_add(
+						Self::deposit_for_bytes(key.len().saturating_add(value.len()))
+					)
+				};
+				Self::adjust_deposit(details, old.map_or_else(Zero::zero, |(_, d)| d), deposit)?;
+
+				Attribute::<T>::insert((class, maybe_instance), &key, (value.clone(), deposit));
+				Ok(())
+			})?;
+
+			Self::deposit_event(RawEvent::AttributeSet(class, maybe_instance, key, value));
+		}
+
+		/// Clear an attribute of a class, or of one of its instances, unreserving its deposit.
+		///
+		/// The attributes of a class or instance whose metadata is frozen can't be cleared.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the owner of the class, or
+		/// match `T::ForceOrigin`, in which case the attributes of frozen metadata may be
+		/// cleared.
+		///
+		/// Emits `AttributeCleared`.
+		///
+		/// # <weight>
+		/// - O(K) where K is the length of the key.
+		/// - One balance unreserve operation.
+		/// - Two storage reads and writes.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(100_000)]
+		fn clear_attribute(
+			origin,
+			class: T::ClassId,
+			maybe_instance: Option<T::InstanceId>,
+			key: Vec<u8>,
+		) {
+			let maybe_check_owner = Self::ensure_owner_or_force(origin)?;
+
+			Class::<T>::try_mutate(class, |maybe_details| -> DispatchResult {
+				let details = maybe_details.as_mut().ok_or(Error::<T>::Unknown)?;
+				if let Some(check_owner) = &maybe_check_owner {
+					ensure!(&details.owner == check_owner, Error::<T>::NoPermission);
+				}
+
+				Self::ensure_attributes_unfrozen(class, maybe_instance, &maybe_check_owner)?;
+
+				let (_, deposit) = Attribute::<T>::take((class, maybe_instance), &key)
+					.ok_or(Error::<T>::Unknown)?;
+				Self::adjust_deposit(details, deposit, Zero::zero())?;
+				details.attributes = details.attributes.saturating_sub(1);
+				Ok(())
+			})?;
+
+			Self::deposit_event(RawEvent::AttributeCleared(class, maybe_instance, key));
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The owner of an instance, if it exists.
+	pub fn owner(class: T::ClassId, instance: T::InstanceId) -> Option<T::AccountId> {
+		Asset::<T>::get(class, instance).map(|details| details.owner)
+	}
+
+	/// Ensure that the origin either matches `ForceOrigin`, or is signed, in which case the
+	/// signer is returned to be checked against the owner of the class.
+	fn ensure_owner_or_force(origin: T::Origin) -> Result<Option<T::AccountId>, DispatchError> {
+		T::ForceOrigin::try_origin(origin)
+			.map(|_| None)
+			.or_else(|origin| ensure_signed(origin).map(Some).map_err(DispatchError::from))
+	}
+
+	/// Ensure that the attributes of `class`, or of its instance `maybe_instance`, may be
+	/// changed: their metadata must not be frozen, unless `maybe_check_owner` is `None`, i.e.
+	/// the origin matched `ForceOrigin`.
+	fn ensure_attributes_unfrozen(
+		class: T::ClassId,
+		maybe_instance: Option<T::InstanceId>,
+		maybe_check_owner: &Option<T::AccountId>,
+	) -> DispatchResult {
+		let is_frozen = match maybe_instance {
+			None => ClassMetadataOf::<T>::get(class).map_or(false, |m| m.is_frozen),
+			Some(instance) =>
+				InstanceMetadataOf::<T>::get(class, instance).map_or(false, |m| m.is_frozen),
+		};
+		ensure!(maybe_check_owner.is_none() || !is_frozen, Error::<T>::Frozen);
+		Ok(())
+	}
+
+	fn deposit_for_bytes(bytes: usize) -> BalanceOf<T> {
+		T::DepositPerByte::get().saturating_mul((bytes as u32).into())
+	}
+
+	fn metadata_deposit(bytes: usize) -> BalanceOf<T> {
+		T::MetadataDepositBase::get().saturating_add(Self::deposit_for_bytes(bytes))
+	}
+
+	/// Replace the `old` deposit of an item of the class with the `new` one.
+	fn adjust_deposit(
+		details: &mut ClassDetailsOf<T>,
+		old: BalanceOf<T>,
+		new: BalanceOf<T>,
+	) -> DispatchResult {
+		if new > old {
+			T::Currency::reserve(&details.owner, new - old)?;
+		} else {
+			T::Currency::unreserve(&details.owner, old - new);
+		}
+		details.total_deposit = details.total_deposit.saturating_add(new).saturating_sub(old);
+		Ok(())
+	}
+}
@@ -0,0 +1,332 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Tests for the module.
+
+use super::*;
+use mock::{Uniques, Balances, Test, Origin, new_test_ext};
+use sp_runtime::traits::BadOrigin;
+use frame_support::{assert_noop, assert_ok, traits::Currency};
+
+#[test]
+fn create_and_destroy_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Uniques::create(Origin::signed(1), 0, 2));
+		assert_eq!(Balances::reserved_balance(1), 2);
+		let details = Uniques::class(0).unwrap();
+		assert_eq!((details.owner, details.admin, details.freezer), (1, 2, 2));
+		assert_noop!(Uniques::create(Origin::signed(3), 0, 3), Error::<Test>::InUse);
+
+		let witness = details.destroy_witness();
+		assert_noop!(Uniques::destroy(Origin::signed(2), 0, witness), Error::<Test>::NoPermission);
+		assert_ok!(Uniques::destroy(Origin::signed(1), 0, witness));
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert!(Uniques::class(0).is_none());
+	});
+}
+
+#[test]
+fn force_create_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(Uniques::force_create(Origin::signed(1), 0, 1, true), BadOrigin);
+		assert_ok!(Uniques::force_create(Origin::ROOT, 0, 1, true));
+		assert_ok!(Uniques::mint(Origin::signed(1), 0, 42, 2));
+		assert_ok!(Uniques::set_metadata(Origin::signed(1), 0, 42, vec![0; 10], false));
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Uniques::owner(0, 42), Some(2));
+	});
+}
+
+#[test]
+fn destroy_should_clear_everything() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Uniques::create(Origin::signed(1), 0, 1));
+		assert_ok!(Uniques::mint(Origin::signed(1), 0, 42, 2));
+		assert_ok!(Uniques::mint(Origin::signed(1), 0, 69, 3));
+		assert_ok!(Uniques::set_metadata(Origin::signed(1), 0, 42, vec![0; 10], false));
+		assert_ok!(Uniques::set_class_metadata(Origin::signed(1), 0, vec![0; 10], false));
+		assert_ok!(Uniques::set_attribute(Origin::signed(1), 0, Some(69), vec![0], vec![0]));
+		assert_eq!(Balances::reserved_balance(1), 2 + 2 + 11 + 11 + 3);
+
+		let witness = Uniques::class(0).unwrap().destroy_witness();
+		assert_eq!(witness, DestroyWitness { instances: 2, instance_metadatas: 1, attributes: 1 });
+		let bad_witness = DestroyWitness { attributes: 0, ..witness };
+		assert_noop!(Uniques::destroy(Origin::signed(1), 0, bad_witness), Error::<Test>::BadWitness);
+
+		assert_ok!(Uniques::destroy(Origin::signed(1), 0, witness));
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert!(Uniques::asset(0, 42).is_none());
+		assert!(Uniques::instance_metadata(0, 42).is_none());
+		assert!(Uniques::class_metadata(0).is_none());
+		assert!(Uniques::attribute((0, Some(69u32)), vec![0u8]).is_none());
+	});
+}
+
+#[test]
+fn mint_and_burn_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Uniques::create(Origin::signed(1), 0, 2));
+		assert_noop!(Uniques::mint(Origin::signed(1), 0, 42, 3), Error::<Test>::NoPermission);
+		assert_ok!(Uniques::mint(Origin::signed(2), 0, 42, 3));
+		assert_noop!(Uniques::mint(Origin::signed(2), 0, 42, 3), Error::<Test>::InUse);
+		assert_eq!(Uniques::owner(0, 42), Some(3));
+		assert_eq!(Balances::reserved_balance(1), 3);
+
+		assert_noop!(Uniques::burn(Origin::signed(4), 0, 42, None), Error::<Test>::NoPermission);
+		assert_noop!(Uniques::burn(Origin::signed(3), 0, 42, Some(4)), Error::<Test>::WrongOwner);
+		assert_ok!(Uniques::burn(Origin::signed(3), 0, 42, Some(3)));
+		assert_eq!(Uniques::owner(0, 42), None);
+		assert_eq!(Balances::reserved_balance(1), 2);
+		assert_eq!(Uniques::class(0).unwrap().instances, 0);
+	});
+}
+
+#[test]
+fn transfer_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Uniques::create(Origin::signed(1), 0, 1));
+		assert_ok!(Uniques::mint(Origin::signed(1), 0, 42, 2));
+
+		assert_noop!(Uniques::transfer(Origin::signed(3), 0, 42, 4), Error::<Test>::NoPermission);
+		assert_ok!(Uniques::transfer(Origin::signed(2), 0, 42, 3));
+		assert_eq!(Uniques::owner(0, 42), Some(3));
+		// The admin may move the instance too.
+		assert_ok!(Uniques::transfer(Origin::signed(1), 0, 42, 4));
+		assert_eq!(Uniques::owner(0, 42), Some(4));
+		assert_noop!(Uniques::transfer(Origin::signed(1), 0, 69, 4), Error::<Test>::Unknown);
+	});
+}
+
+#[test]
+fn freezing_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Uniques::create(Origin::signed(1), 0, 1));
+		assert_ok!(Uniques::mint(Origin::signed(1), 0, 42, 2));
+		assert_ok!(Uniques::set_team(Origin::signed(1), 0, 1, 3));
+
+		assert_noop!(Uniques::freeze(Origin::signed(1), 0, 42), Error::<Test>::NoPermission);
+		assert_ok!(Uniques::freeze(Origin::signed(3), 0, 42));
+		assert_noop!(Uniques::transfer(Origin::signed(2), 0, 42, 4), Error::<Test>::Frozen);
+		assert_noop!(Uniques::thaw(Origin::signed(3), 0, 42), Error::<Test>::NoPermission);
+		assert_ok!(Uniques::thaw(Origin::signed(1), 0, 42));
+
+		assert_ok!(Uniques::freeze_class(Origin::signed(3), 0));
+		assert_noop!(Uniques::transfer(Origin::signed(2), 0, 42, 4), Error::<Test>::Frozen);
+		assert_ok!(Uniques::thaw_class(Origin::signed(1), 0));
+		assert_ok!(Uniques::transfer(Origin::signed(2), 0, 42, 4));
+	});
+}
+
+#[test]
+fn transfer_ownership_should_move_the_deposits() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Uniques::create(Origin::signed(1), 0, 1));
+		assert_ok!(Uniques::mint(Origin::signed(1), 0, 42, 2));
+		assert_ok!(Uniques::set_class_metadata(Origin::signed(1), 0, vec![0; 10], false));
+		assert_eq!(Balances::reserved_balance(1), 2 + 1 + 11);
+
+		assert_noop!(
+			Uniques::transfer_ownership(Origin::signed(2), 0, 2),
+			Error::<Test>::NoPermission,
+		);
+		assert_ok!(Uniques::transfer_ownership(Origin::signed(1), 0, 2));
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Balances::reserved_balance(2), 14);
+		assert_eq!(Balances::free_balance(1), 86);
+		assert_eq!(Uniques::class(0).unwrap().owner, 2);
+
+		// The new owner now pays for the metadata.
+		assert_ok!(Uniques::clear_class_metadata(Origin::signed(2), 0));
+		assert_eq!(Balances::reserved_balance(2), 3);
+	});
+}
+
+#[test]
+fn approvals_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Uniques::create(Origin::signed(1), 0, 1));
+		assert_ok!(Uniques::mint(Origin::signed(1), 0, 42, 2));
+
+		assert_noop!(Uniques::approve_transfer(Origin::signed(3), 0, 42, 3), Error::<Test>::NoPermission);
+		assert_ok!(Uniques::approve_transfer(Origin::signed(2), 0, 42, 3));
+		assert_ok!(Uniques::transfer(Origin::signed(3), 0, 42, 4));
+		assert_eq!(Uniques::owner(0, 42), Some(4));
+		// The approval is used up by the transfer.
+		assert_noop!(Uniques::transfer(Origin::signed(3), 0, 42, 3), Error::<Test>::NoPermission);
+
+		assert_ok!(Uniques::approve_transfer(Origin::signed(4), 0, 42, 3));
+		assert_noop!(
+			Uniques::cancel_approval(Origin::signed(4), 0, 42, Some(2)),
+			Error::<Test>::WrongDelegate,
+		);
+		assert_ok!(Uniques::cancel_approval(Origin::signed(4), 0, 42, Some(3)));
+		assert_noop!(Uniques::cancel_approval(Origin::signed(4), 0, 42, None), Error::<Test>::NoDelegate);
+		assert_noop!(Uniques::transfer(Origin::signed(3), 0, 42, 3), Error::<Test>::NoPermission);
+	});
+}
+
+#[test]
+fn metadata_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Uniques::create(Origin::signed(1), 0, 1));
+		assert_ok!(Uniques::mint(Origin::signed(1), 0, 42, 2));
+
+		assert_noop!(
+			Uniques::set_metadata(Origin::signed(2), 0, 42, vec![0; 10], false),
+			Error::<Test>::NoPermission,
+		);
+		assert_noop!(
+			Uniques::set_metadata(Origin::signed(1), 0, 69, vec![0; 10], false),
+			Error::<Test>::Unknown,
+		);
+		assert_noop!(
+			Uniques::set_metadata(Origin::signed(1), 0, 42, vec![0; 51], false),
+			Error::<Test>::TooLong,
+		);
+
+		assert_ok!(Uniques::set_metadata(Origin::signed(1), 0, 42, vec![0; 10], false));
+		assert_eq!(Balances::reserved_balance(1), 2 + 1 + 11);
+		// Shrinking the metadata unreserves the difference.
+		assert_ok!(Uniques::set_metadata(Origin::signed(1), 0, 42, vec![0; 5], true));
+		assert_eq!(Balances::reserved_balance(1), 2 + 1 + 6);
+		assert_eq!(Uniques::class(0).unwrap().instance_metadatas, 1);
+
+		// Frozen metadata may only be changed by `ForceOrigin`.
+		assert_noop!(
+			Uniques::set_metadata(Origin::signed(1), 0, 42, vec![0; 10], false),
+			Error::<Test>::Frozen,
+		);
+		assert_noop!(Uniques::clear_metadata(Origin::signed(1), 0, 42), Error::<Test>::Frozen);
+		assert_ok!(Uniques::clear_metadata(Origin::ROOT, 0, 42));
+		assert_eq!(Balances::reserved_balance(1), 3);
+		assert_eq!(Uniques::class(0).unwrap().instance_metadatas, 0);
+
+		assert_ok!(Uniques::set_class_metadata(Origin::signed(1), 0, vec![0; 10], false));
+		assert_eq!(Balances::reserved_balance(1), 3 + 11);
+		assert_ok!(Uniques::clear_class_metadata(Origin::signed(1), 0));
+		assert_eq!(Balances::reserved_balance(1), 3);
+	});
+}
+
+#[test]
+fn burn_should_unreserve_the_metadata_deposit() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Uniques::create(Origin::signed(1), 0, 1));
+		assert_ok!(Uniques::mint(Origin::signed(1), 0, 42, 2));
+		assert_ok!(Uniques::set_metadata(Origin::signed(1), 0, 42, vec![0; 10], false));
+		assert_ok!(Uniques::burn(Origin::signed(2), 0, 42, None));
+		assert_eq!(Balances::reserved_balance(1), 2);
+		assert!(Uniques::instance_metadata(0, 42).is_none());
+		assert_eq!(Uniques::class(0).unwrap().total_deposit, 2);
+	});
+}
+
+#[test]
+fn attributes_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Uniques::create(Origin::signed(1), 0, 1));
+		assert_ok!(Uniques::mint(Origin::signed(1), 0, 42, 1));
+
+		assert_noop!(
+			Uniques::set_attribute(Origin::signed(2), 0, None, vec![0], vec![0]),
+			Error::<Test>::NoPermission,
+		);
+		assert_noop!(
+			Uniques::set_attribute(Origin::signed(1), 0, None, vec![0; 51], vec![0]),
+			Error::<Test>::TooLong,
+		);
+
+		assert_ok!(Uniques::set_attribute(Origin::signed(1), 0, None, vec![0], vec![0]));
+		assert_ok!(Uniques::set_attribute(Origin::signed(1), 0, Some(42), vec![0], vec![0; 4]));
+		assert_eq!(Uniques::attribute((0, None::<u32>), vec![0u8]), Some((vec![0], 3)));
+		assert_eq!(Uniques::attribute((0, Some(42u32)), vec![0u8]), Some((vec![0; 4], 6)));
+		assert_eq!(Balances::reserved_balance(1), 2 + 1 + 3 + 6);
+		assert_eq!(Uniques::class(0).unwrap().attributes, 2);
+
+		assert_ok!(Uniques::set_attribute(Origin::signed(1), 0, Some(42), vec![0], vec![0]));
+		assert_eq!(Balances::reserved_balance(1), 2 + 1 + 3 + 3);
+		assert_eq!(Uniques::class(0).unwrap().attributes, 2);
+
+		assert_ok!(Uniques::clear_attribute(Origin::signed(1), 0, None, vec![0]));
+		assert_noop!(
+			Uniques::clear_attribute(Origin::signed(1), 0, None, vec![0]),
+			Error::<Test>::Unknown,
+		);
+		assert_eq!(Balances::reserved_balance(1), 2 + 1 + 3);
+		assert_eq!(Uniques::class(0).unwrap().attributes, 1);
+
+		// burning the instance removes its attributes, which a new instance doesn't inherit.
+		assert_ok!(Uniques::burn(Origin::signed(1), 0, 42, None));
+		assert_eq!(Balances::reserved_balance(1), 2);
+		assert_eq!(Uniques::class(0).unwrap().attributes, 0);
+		assert_eq!(Uniques::class(0).unwrap().total_deposit, 2);
+		assert_ok!(Uniques::mint(Origin::signed(1), 0, 42, 1));
+		assert!(Uniques::attribute((0, Some(42u32)), vec![0u8]).is_none());
+	});
+}
+
+#[test]
+fn attributes_require_an_instance_and_unfrozen_metadata() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Uniques::create(Origin::signed(1), 0, 1));
+		assert_noop!(
+			Uniques::set_attribute(Origin::signed(1), 0, Some(42), vec![0], vec![0]),
+			Error::<Test>::Unknown,
+		);
+
+		assert_ok!(Uniques::mint(Origin::signed(1), 0, 42, 1));
+		assert_ok!(Uniques::set_attribute(Origin::signed(1), 0, Some(42), vec![0], vec![0]));
+		assert_ok!(Uniques::set_metadata(Origin::signed(1), 0, 42, vec![0], true));
+		assert_noop!(
+			Uniques::set_attribute(Origin::signed(1), 0, Some(42), vec![0], vec![1]),
+			Error::<Test>::Frozen,
+		);
+		assert_noop!(
+			Uniques::clear_attribute(Origin::signed(1), 0, Some(42), vec![0]),
+			Error::<Test>::Frozen,
+		);
+		// the attributes of the class aren't frozen along with the ones of its instance.
+		assert_ok!(Uniques::set_attribute(Origin::signed(1), 0, None, vec![0], vec![0]));
+
+		assert_ok!(Uniques::set_class_metadata(Origin::signed(1), 0, vec![0], true));
+		assert_noop!(
+			Uniques::set_attribute(Origin::signed(1), 0, None, vec![0], vec![1]),
+			Error::<Test>::Frozen,
+		);
+
+		// the force origin may still change them.
+		assert_ok!(Uniques::set_attribute(Origin::ROOT, 0, Some(42), vec![0], vec![1]));
+		assert_ok!(Uniques::clear_attribute(Origin::ROOT, 0, None, vec![0]));
+		assert_eq!(Uniques::attribute((0, Some(42u32)), vec![0u8]), Some((vec![1], 0)));
+	});
+}
+
+#[test]
+fn deposits_require_enough_balance() {
+	new_test_ext().execute_with(|| {
+		let _ = Balances::make_free_balance_be(&1, 3);
+		assert_ok!(Uniques::create(Origin::signed(1), 0, 1));
+		assert_noop!(
+			Uniques::set_class_metadata(Origin::signed(1), 0, vec![0; 10], false),
+			pallet_balances::Error::<Test, _>::InsufficientBalance,
+		);
+		assert_ok!(Uniques::mint(Origin::signed(1), 0, 42, 2));
+		assert_noop!(
+			Uniques::mint(Origin::signed(1), 0, 69, 2),
+			pallet_balances::Error::<Test, _>::InsufficientBalance,
+		);
+	});
+}
@@ -330,6 +330,14 @@ fn generate_runtime_api_base_structures() -> Result<TokenStream> {
 					})
 			}
 
+			fn collect_changed_keys(&mut self) {
+				self.changes.borrow_mut().set_collect_committed_keys(true);
+			}
+
+			fn take_changed_keys(&mut self) -> Vec<Vec<u8>> {
+				self.changes.borrow_mut().take_committed_keys()
+			}
+
 			fn into_storage_changes(
 				&self,
 				backend: &Self::StateBackend,
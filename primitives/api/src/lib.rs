@@ -59,6 +59,7 @@ pub use sp_runtime::{
 pub use sp_core::{offchain, ExecutionContext};
 #[doc(hidden)]
 pub use sp_version::{ApiId, RuntimeVersion, ApisVec, create_apis_vec};
+use sp_version::OldRuntimeVersion;
 #[doc(hidden)]
 pub use sp_std::{slice, mem};
 #[cfg(feature = "std")]
@@ -220,6 +221,7 @@ pub use sp_api_proc_macro::decl_runtime_apis;
 ///     impl_version: 0,
 ///     // Here we are exposing the runtime api versions.
 ///     apis: RUNTIME_API_VERSIONS,
+///     transaction_version: 1,
 /// };
 ///
 /// # fn main() {}
@@ -319,6 +321,14 @@ pub trait ApiExt<Block: BlockT>: ApiErrorExt {
 	/// If `record_proof` was not called before, this will return `None`.
 	fn extract_proof(&mut self) -> Option<StorageProof>;
 
+	/// Start collecting the keys of the storage changed by the api calls.
+	fn collect_changed_keys(&mut self);
+
+	/// Take the keys of the storage changed by the api calls since the previous call.
+	///
+	/// If `collect_changed_keys` was not called before, this will return nothing.
+	fn take_changed_keys(&mut self) -> Vec<Vec<u8>>;
+
 	/// Convert the api object into the storage changes that were done while executing runtime
 	/// api functions.
 	///
@@ -462,8 +472,11 @@ pub type ApiErrorFor<T, Block> = <<T as ProvideRuntimeApi<Block>>::Api as ApiErr
 decl_runtime_apis! {
 	/// The `Core` runtime api that every Substrate runtime needs to implement.
 	#[core_trait]
-	#[api_version(2)]
+	#[api_version(3)]
 	pub trait Core {
+		/// Returns the version of the runtime.
+		#[changed_in(3)]
+		fn version() -> OldRuntimeVersion;
 		/// Returns the version of the runtime.
 		fn version() -> RuntimeVersion;
 		/// Execute the given block.
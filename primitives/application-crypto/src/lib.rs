@@ -35,6 +35,7 @@ pub use serde;
 #[doc(hidden)]
 pub use sp_std::{ops::Deref, vec::Vec};
 
+pub mod ecdsa;
 pub mod ed25519;
 pub mod sr25519;
 mod traits;
@@ -40,9 +40,13 @@ sp_api::decl_runtime_apis! {
 	/// The authority discovery api.
 	///
 	/// This api is used by the `client/authority-discovery` module to retrieve identifiers
-	/// of the current authority set.
+	/// of the current and the next authority set.
+	#[api_version(2)]
 	pub trait AuthorityDiscoveryApi {
 		/// Retrieve authority identifiers of the current authority set.
 		fn authorities() -> Vec<AuthorityId>;
+
+		/// Retrieve authority identifiers of the next authority set.
+		fn next_authorities() -> Vec<AuthorityId>;
 	}
 }
@@ -0,0 +1,83 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The commitments voted for by the BEEFY authorities.
+
+use codec::{Encode, Decode};
+use sp_runtime::RuntimeDebug;
+use sp_std::vec::Vec;
+
+use crate::ValidatorSetId;
+
+/// A commitment of the authorities to the payload of a finalized block.
+///
+/// The authorities sign the encoded commitment.
+#[derive(Decode, Encode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct Commitment<TBlockNumber, TPayload> {
+	/// The payload committed to, e.g. the root of the Merkle Mountain Range of the chain.
+	pub payload: TPayload,
+	/// The number of the finalized block the payload is taken from.
+	pub block_number: TBlockNumber,
+	/// The identifier of the set of authorities signing the commitment.
+	///
+	/// A commitment signed by a set of authorities also proves that the next set, announced
+	/// in the block, is the one given in the `ConsensusLog::AuthoritiesChange` digest.
+	pub validator_set_id: ValidatorSetId,
+}
+
+/// A commitment, along with the signatures of the authorities.
+///
+/// The signatures are aggregated in the order of the authorities of the set, with `None` for
+/// the authorities that didn't sign, so that the commitment is checked against the set alone.
+#[derive(Decode, Encode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct SignedCommitment<TBlockNumber, TPayload, TSignature> {
+	/// The commitment signed.
+	pub commitment: Commitment<TBlockNumber, TPayload>,
+	/// The signatures of the authorities, in the order of the set.
+	pub signatures: Vec<Option<TSignature>>,
+}
+
+impl<TBlockNumber, TPayload, TSignature> SignedCommitment<TBlockNumber, TPayload, TSignature> {
+	/// The number of authorities that signed the commitment.
+	pub fn no_of_signatures(&self) -> usize {
+		self.signatures.iter().filter(|signature| signature.is_some()).count()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	type TestCommitment = Commitment<u64, [u8; 4]>;
+	type TestSignedCommitment = SignedCommitment<u64, [u8; 4], u32>;
+
+	#[test]
+	fn signed_commitment_encoding_roundtrips() {
+		let commitment = TestCommitment {
+			payload: *b"root",
+			block_number: 5,
+			validator_set_id: 0,
+		};
+		let signed = TestSignedCommitment {
+			commitment,
+			signatures: vec![None, Some(1), Some(2)],
+		};
+
+		let encoded = signed.encode();
+		assert_eq!(TestSignedCommitment::decode(&mut &encoded[..]).unwrap(), signed);
+		assert_eq!(signed.no_of_signatures(), 2);
+	}
+}
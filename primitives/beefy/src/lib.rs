@@ -0,0 +1,120 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Primitives for BEEFY integration, suitable for WASM compilation.
+//!
+//! BEEFY is a secondary finality gadget running on top of the primary one (e.g. GRANDPA). Its
+//! voters sign commitments over a payload of the blocks finalized by the primary gadget, usually
+//! the root of a Merkle Mountain Range of the chain's blocks, so that other chains can follow
+//! the finality of this one by only checking the signatures of a single commitment.
+//!
+//! The authorities sign with ECDSA (secp256k1) keys, as their signatures can be checked, and
+//! recovered from, cheaply by the other chains, e.g. Ethereum.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+mod commitment;
+
+pub use commitment::{Commitment, SignedCommitment};
+
+use codec::{Encode, Decode, Codec};
+use sp_runtime::{ConsensusEngineId, RuntimeDebug};
+use sp_std::vec::Vec;
+
+mod app {
+	use sp_application_crypto::{app_crypto, key_types::BEEFY, ecdsa};
+	app_crypto!(ecdsa, BEEFY);
+}
+
+sp_application_crypto::with_pair! {
+	/// The BEEFY crypto scheme defined via the keypair type.
+	pub type AuthorityPair = app::Pair;
+}
+
+/// Key type for the BEEFY module.
+pub const KEY_TYPE: sp_application_crypto::KeyTypeId = sp_application_crypto::key_types::BEEFY;
+
+/// Identity of a BEEFY authority.
+pub type AuthorityId = app::Public;
+
+/// Signature for a BEEFY authority.
+pub type AuthoritySignature = app::Signature;
+
+/// The `ConsensusEngineId` of BEEFY.
+pub const BEEFY_ENGINE_ID: ConsensusEngineId = *b"BEEF";
+
+/// The index of an authority.
+pub type AuthorityIndex = u32;
+
+/// The monotonic identifier of a BEEFY set of authorities.
+pub type ValidatorSetId = u64;
+
+/// The hash of the root of a Merkle Mountain Range, the usual payload of the commitments.
+pub type MmrRootHash = sp_core::H256;
+
+/// A set of BEEFY authorities, along with its identifier.
+#[derive(Decode, Encode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct ValidatorSet<AuthorityId> {
+	/// The public keys of the authorities, in the order their signatures are found in the
+	/// signed commitments.
+	pub validators: Vec<AuthorityId>,
+	/// The identifier of the set.
+	pub id: ValidatorSetId,
+}
+
+impl<AuthorityId> ValidatorSet<AuthorityId> {
+	/// The number of signatures a commitment needs to be final, i.e. more than two thirds of
+	/// the authorities.
+	pub fn threshold(&self) -> usize {
+		self.validators.len() - (self.validators.len().saturating_sub(1)) / 3
+	}
+}
+
+/// A consensus log item for BEEFY.
+#[derive(Decode, Encode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub enum ConsensusLog<AuthorityId: Codec> {
+	/// The authorities have changed, starting with this block.
+	#[codec(index = "1")]
+	AuthoritiesChange(ValidatorSet<AuthorityId>),
+	/// Disable the authority with the given index.
+	#[codec(index = "2")]
+	OnDisabled(AuthorityIndex),
+	/// The root of the Merkle Mountain Range of the chain, up to this block.
+	#[codec(index = "3")]
+	MmrRoot(MmrRootHash),
+}
+
+/// The message gossiped by the BEEFY voters: a vote of one of them for a commitment.
+#[derive(Decode, Encode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct VoteMessage<Payload, BlockNumber, AuthorityId, Signature> {
+	/// The commitment voted for.
+	pub commitment: Commitment<BlockNumber, Payload>,
+	/// The public key of the voter.
+	pub id: AuthorityId,
+	/// The signature of the voter over the encoded commitment.
+	pub signature: Signature,
+}
+
+sp_api::decl_runtime_apis! {
+	/// APIs for integrating the BEEFY gadget into runtimes.
+	pub trait BeefyApi {
+		/// Get the current set of BEEFY authorities.
+		///
+		/// When called at block B, it returns the set of authorities voting for the
+		/// commitments on the descendants of this block.
+		fn validator_set() -> ValidatorSet<AuthorityId>;
+	}
+}
@@ -20,11 +20,11 @@
 
 use sp_runtime::{traits::Block as BlockT, ApplyExtrinsicResult};
 
-use sp_inherents::{InherentData, CheckInherentsResult};
+use sp_inherents::{InherentData, InherentIdentifier, CheckInherentsResult};
 
 sp_api::decl_runtime_apis! {
 	/// The `BlockBuilder` api trait that provides the required functionality for building a block.
-	#[api_version(4)]
+	#[api_version(5)]
 	pub trait BlockBuilder {
 		/// Apply the given extrinsic.
 		///
@@ -38,9 +38,27 @@ sp_api::decl_runtime_apis! {
 		fn inherent_extrinsics(
 			inherent: InherentData,
 		) -> sp_std::vec::Vec<<Block as BlockT>::Extrinsic>;
+		/// Generate inherent extrinsics along with the identifier of the inherent data each one is
+		/// created from and whether the block is invalid without it, so that they can be applied
+		/// separately.
+		fn inherent_extrinsics_with_identifiers(
+			inherent: InherentData,
+		) -> sp_std::vec::Vec<(InherentIdentifier, bool, <Block as BlockT>::Extrinsic)>;
 		/// Check that the inherents are valid. The inherent data will vary from chain to chain.
 		fn check_inherents(block: Block, data: InherentData) -> CheckInherentsResult;
 		/// Generate a random seed.
 		fn random_seed() -> <Block as BlockT>::Hash;
 	}
+
+	/// The `ExecuteBlockNoCheck` api trait that provides the execution of the blocks without
+	/// verifying the signatures of their extrinsics nor their state root.
+	///
+	/// This is only meant for replaying blocks already known to be valid, so that the measures of
+	/// `benchmark-block` and `state_traceBlock` are about the runtime itself. It is meant to be
+	/// only implemented by the runtimes built for such uses.
+	pub trait ExecuteBlockNoCheck {
+		/// Execute the given block without verifying the signatures of its extrinsics nor its
+		/// state root.
+		fn execute_block_no_check(block: Block);
+	}
 }
@@ -56,6 +56,10 @@ pub enum Error {
 	#[display(fmt = "UnknownBlock: {}", _0)]
 	#[from(ignore)]
 	UnknownBlock(String),
+	/// The state of the block has been pruned.
+	#[display(fmt = "State already discarded for {}", _0)]
+	#[from(ignore)]
+	StatePruned(String),
 	/// The `apply_extrinsic` is not valid due to the given `TransactionValidityError`.
 	#[display(fmt = "{:?}", _0)]
 	ApplyExtrinsicFailed(ApplyExtrinsicFailed),
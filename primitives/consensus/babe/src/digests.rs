@@ -38,7 +38,8 @@ use sp_std::vec::Vec;
 
 /// A BABE pre-runtime digest. This contains all data required to validate a
 /// block and for the BABE runtime module. Slots can be assigned to a primary
-/// (VRF based) and to a secondary (slot number based).
+/// (VRF based) and to a secondary (slot number based), optionally along with a
+/// VRF output.
 #[cfg(feature = "std")]
 #[derive(Clone, Debug)]
 pub enum PreDigest {
@@ -60,6 +61,17 @@ pub enum PreDigest {
 		/// Slot number
 		slot_number: SlotNumber,
 	},
+	/// A secondary deterministic slot assignment with VRF outputs.
+	SecondaryVRF {
+		/// VRF output
+		vrf_output: VRFOutput,
+		/// VRF proof
+		vrf_proof: VRFProof,
+		/// Authority index
+		authority_index: super::AuthorityIndex,
+		/// Slot number
+		slot_number: SlotNumber,
+	},
 }
 
 #[cfg(feature = "std")]
@@ -69,6 +81,7 @@ impl PreDigest {
 		match self {
 			PreDigest::Primary { authority_index, .. } => *authority_index,
 			PreDigest::Secondary { authority_index, .. } => *authority_index,
+			PreDigest::SecondaryVRF { authority_index, .. } => *authority_index,
 		}
 	}
 
@@ -77,6 +90,7 @@ impl PreDigest {
 		match self {
 			PreDigest::Primary { slot_number, .. } => *slot_number,
 			PreDigest::Secondary { slot_number, .. } => *slot_number,
+			PreDigest::SecondaryVRF { slot_number, .. } => *slot_number,
 		}
 	}
 
@@ -85,7 +99,7 @@ impl PreDigest {
 	pub fn added_weight(&self) -> crate::BabeBlockWeight {
 		match self {
 			PreDigest::Primary { .. } => 1,
-			PreDigest::Secondary { .. } => 0,
+			PreDigest::Secondary { .. } | PreDigest::SecondaryVRF { .. } => 0,
 		}
 	}
 }
@@ -118,6 +132,18 @@ pub enum RawPreDigest {
 		/// Slot number
 		slot_number: SlotNumber,
 	},
+	/// A secondary deterministic slot assignment with VRF outputs.
+	#[codec(index = "3")]
+	SecondaryVRF {
+		/// Authority index
+		authority_index: AuthorityIndex,
+		/// Slot number
+		slot_number: SlotNumber,
+		/// VRF output
+		vrf_output: [u8; VRF_OUTPUT_LENGTH],
+		/// VRF proof
+		vrf_proof: [u8; VRF_PROOF_LENGTH],
+	},
 }
 
 impl RawPreDigest {
@@ -126,6 +152,25 @@ impl RawPreDigest {
 		match self {
 			RawPreDigest::Primary { slot_number, .. } => *slot_number,
 			RawPreDigest::Secondary { slot_number, .. } => *slot_number,
+			RawPreDigest::SecondaryVRF { slot_number, .. } => *slot_number,
+		}
+	}
+
+	/// Returns the authority index of the pre digest.
+	pub fn authority_index(&self) -> AuthorityIndex {
+		match self {
+			RawPreDigest::Primary { authority_index, .. } => *authority_index,
+			RawPreDigest::Secondary { authority_index, .. } => *authority_index,
+			RawPreDigest::SecondaryVRF { authority_index, .. } => *authority_index,
+		}
+	}
+
+	/// Returns the VRF output of the pre digest, if any.
+	pub fn vrf_output(&self) -> Option<&[u8; VRF_OUTPUT_LENGTH]> {
+		match self {
+			RawPreDigest::Primary { vrf_output, .. } => Some(vrf_output),
+			RawPreDigest::SecondaryVRF { vrf_output, .. } => Some(vrf_output),
+			RawPreDigest::Secondary { .. } => None,
 		}
 	}
 }
@@ -156,6 +201,19 @@ impl Encode for PreDigest {
 					slot_number: *slot_number,
 				}
 			},
+			PreDigest::SecondaryVRF {
+				vrf_output,
+				vrf_proof,
+				authority_index,
+				slot_number,
+			} => {
+				RawPreDigest::SecondaryVRF {
+					vrf_output: *vrf_output.as_bytes(),
+					vrf_proof: vrf_proof.to_bytes(),
+					authority_index: *authority_index,
+					slot_number: *slot_number,
+				}
+			},
 		};
 
 		codec::Encode::encode(&raw)
@@ -184,6 +242,14 @@ impl Decode for PreDigest {
 			RawPreDigest::Secondary { authority_index, slot_number } => {
 				PreDigest::Secondary { authority_index, slot_number }
 			},
+			RawPreDigest::SecondaryVRF { vrf_output, vrf_proof, authority_index, slot_number } => {
+				PreDigest::SecondaryVRF {
+					vrf_proof: VRFProof::from_bytes(&vrf_proof).map_err(convert_error)?,
+					vrf_output: VRFOutput::from_bytes(&vrf_output).map_err(convert_error)?,
+					authority_index,
+					slot_number,
+				}
+			},
 		};
 
 		Ok(pre_digest)
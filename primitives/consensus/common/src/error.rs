@@ -79,6 +79,9 @@ pub enum Error {
 	#[display(fmt="Chain lookup failed: {}", _0)]
 	#[from(ignore)]
 	ChainLookup(String),
+	/// Signing failed
+	#[display(fmt="Failed to sign using key: {:?}. Reason: {}", _0, _1)]
+	CannotSign(Vec<u8>, String),
 }
 
 impl error::Error for Error {
@@ -25,7 +25,7 @@
 //! instantiated. The `BasicQueue` and `BasicVerifier` traits allow serial
 //! queues to be instantiated simply.
 
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 use sp_runtime::{Justification, traits::{Block as BlockT, Header as _, NumberFor}};
 use crate::error::Error as ConsensusError;
 use crate::block_import::{
@@ -90,6 +90,21 @@ pub trait Verifier<B: BlockT>: Send + Sync {
 	) -> Result<(BlockImportParams<B, ()>, Option<Vec<(CacheKeyId, Vec<u8>)>>), String>;
 }
 
+/// The checks of a header that don't depend on the blocks before it, e.g. checking the
+/// proof-of-work of its seal.
+///
+/// Unlike the `Verifier`, which verifies and imports the queued blocks one after the other, these
+/// checks are run concurrently for all the queued blocks, on a pool of threads. The `Verifier` is
+/// only given the headers that passed them, and shouldn't repeat them.
+pub trait PreVerifier<B: BlockT>: Send + Sync {
+	/// Check the given header, returning an error message presented to the user in the logs if
+	/// it is invalid.
+	fn pre_verify(&self, header: &B::Header) -> Result<(), String>;
+}
+
+/// Shared pre-verifier used by the queue.
+pub type SharedPreVerifier<B> = Arc<dyn PreVerifier<B>>;
+
 /// Blocks import queue API.
 ///
 /// The `import_*` methods can be called in order to send elements for the import queue to verify.
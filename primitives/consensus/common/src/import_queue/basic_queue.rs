@@ -14,20 +14,79 @@
 // You should have received a copy of the GNU General Public License
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::{mem, pin::Pin, time::Duration, marker::PhantomData, sync::Arc};
+use std::{mem, pin::Pin, time::{Duration, Instant}, marker::PhantomData, sync::Arc};
 use futures::{prelude::*, channel::mpsc, task::Context, task::Poll};
 use futures_timer::Delay;
 use parking_lot::{Mutex, Condvar};
+use prometheus_endpoint::{
+	register, Counter, CounterVec, Histogram, HistogramOpts, Opts, PrometheusError, Registry, U64,
+};
 use sp_runtime::{Justification, traits::{Block as BlockT, Header as HeaderT, NumberFor}};
 
 use crate::block_import::BlockOrigin;
 use crate::import_queue::{
 	BlockImportResult, BlockImportError, Verifier, BoxBlockImport, BoxFinalityProofImport,
-	BoxJustificationImport, ImportQueue, Link, Origin,
+	BoxJustificationImport, ImportQueue, Link, Origin, SharedPreVerifier,
 	IncomingBlock, import_single_block,
 	buffered_link::{self, BufferedLinkSender, BufferedLinkReceiver}
 };
 
+/// Prometheus metrics of the import queue.
+#[derive(Clone)]
+struct Metrics {
+	processed_blocks: CounterVec<U64>,
+	block_import_time: Histogram,
+	justification_import_time: Histogram,
+	failed_justifications: Counter<U64>,
+}
+
+impl Metrics {
+	fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			processed_blocks: register(
+				CounterVec::new(
+					Opts::new(
+						"import_queue_processed_blocks_total",
+						"Number of blocks processed by the import queue",
+					),
+					&["result"],
+				)?,
+				registry,
+			)?,
+			block_import_time: register(
+				Histogram::with_opts(HistogramOpts::new(
+					"import_queue_block_verification_and_import_time",
+					"Time taken to verify and import the blocks, in seconds",
+				))?,
+				registry,
+			)?,
+			justification_import_time: register(
+				Histogram::with_opts(HistogramOpts::new(
+					"import_queue_justification_import_time",
+					"Time taken to import the justifications, in seconds",
+				))?,
+				registry,
+			)?,
+			failed_justifications: register(
+				Counter::new(
+					"import_queue_failed_justifications_total",
+					"Number of justifications which failed to be imported",
+				)?,
+				registry,
+			)?,
+		})
+	}
+
+	fn report_block_import<N>(&self, result: &Result<BlockImportResult<N>, BlockImportError>) {
+		let label = match result {
+			Ok(_) => "success",
+			Err(BlockImportError::Cancelled) => "cancelled",
+			Err(_) => "failure",
+		};
+		self.processed_blocks.with_label_values(&[label]).inc();
+	}
+}
+
 /// Interface to a basic block import queue that is importing blocks sequentially in a separate
 /// task, with plugable verification.
 pub struct BasicQueue<B: BlockT, Transaction> {
@@ -67,20 +126,43 @@ impl<B: BlockT, Transaction: Send + 'static> BasicQueue<B, Transaction> {
 	/// Instantiate a new basic queue, with given verifier.
 	///
 	/// This creates a background task, and calls `on_start` on the justification importer and
-	/// finality proof importer.
+	/// finality proof importer. If a pre-verifier is given, the queued headers are pre-verified
+	/// on a pool of threads, ahead of their verification and import.
+	///
+	/// The metrics of the queue are registered in the Prometheus registry, if any.
 	pub fn new<V: 'static + Verifier<B>>(
 		verifier: V,
+		pre_verifier: Option<SharedPreVerifier<B>>,
 		block_import: BoxBlockImport<B, Transaction>,
 		justification_import: Option<BoxJustificationImport<B>>,
 		finality_proof_import: Option<BoxFinalityProofImport<B>>,
+		prometheus_registry: Option<&Registry>,
 	) -> Self {
+		let metrics = prometheus_registry.and_then(|registry| match Metrics::register(registry) {
+			Ok(metrics) => Some(metrics),
+			Err(e) => {
+				warn!(target: "sync", "Failed to register the import queue metrics: {:?}", e);
+				None
+			},
+		});
+
+		let pre_verification = pre_verifier.map(|pre_verifier| {
+			let pool = futures::executor::ThreadPool::builder()
+				.name_prefix("import-queue-verifier-")
+				.create()
+				.ok();
+			(pre_verifier, pool)
+		});
+
 		let (result_sender, result_port) = buffered_link::buffered_link();
 		let (future, worker_sender) = BlockImportWorker::new(
 			result_sender,
 			verifier,
+			pre_verification,
 			block_import,
 			justification_import,
 			finality_proof_import,
+			metrics,
 		);
 
 		let guard = Arc::new((Mutex::new(0usize), Condvar::new()));
@@ -180,11 +262,20 @@ enum ToWorkerMsg<B: BlockT> {
 	ImportFinalityProof(Origin, B::Hash, NumberFor<B>, Vec<u8>),
 }
 
+/// The pre-verifier of the queue, and the pool of threads it runs on. The headers are
+/// pre-verified on the thread of the import when the pool couldn't be created.
+type PreVerification<B> = (SharedPreVerifier<B>, Option<futures::executor::ThreadPool>);
+
+/// The result of the pre-verification of a queued header.
+type PreVerificationResult = Pin<Box<dyn Future<Output = Result<(), String>> + Send>>;
+
 struct BlockImportWorker<B: BlockT, Transaction> {
 	result_sender: BufferedLinkSender<B>,
+	pre_verification: Option<PreVerification<B>>,
 	justification_import: Option<BoxJustificationImport<B>>,
 	finality_proof_import: Option<BoxFinalityProofImport<B>>,
 	delay_between_blocks: Duration,
+	metrics: Option<Metrics>,
 	_phantom: PhantomData<Transaction>,
 }
 
@@ -192,17 +283,21 @@ impl<B: BlockT, Transaction: Send> BlockImportWorker<B, Transaction> {
 	fn new<V: 'static + Verifier<B>>(
 		result_sender: BufferedLinkSender<B>,
 		verifier: V,
+		pre_verification: Option<PreVerification<B>>,
 		block_import: BoxBlockImport<B, Transaction>,
 		justification_import: Option<BoxJustificationImport<B>>,
 		finality_proof_import: Option<BoxFinalityProofImport<B>>,
+		metrics: Option<Metrics>,
 	) -> (impl Future<Output = ()> + Send, mpsc::UnboundedSender<ToWorkerMsg<B>>) {
 		let (sender, mut port) = mpsc::unbounded();
 
 		let mut worker = BlockImportWorker {
 			result_sender,
+			pre_verification,
 			justification_import,
 			finality_proof_import,
 			delay_between_blocks: Duration::new(0, 0),
+			metrics,
 			_phantom: PhantomData,
 		};
 
@@ -293,14 +388,55 @@ impl<B: BlockT, Transaction: Send> BlockImportWorker<B, Transaction> {
 		blocks: Vec<IncomingBlock<B>>
 	) -> impl Future<Output = (BoxBlockImport<B, Transaction>, V)> {
 		let mut result_sender = self.result_sender.clone();
+		let pre_verification_results = self.pre_verify_blocks(&blocks);
 
-		import_many_blocks(block_import, origin, blocks, verifier, self.delay_between_blocks)
+		import_many_blocks(
+			block_import,
+			origin,
+			blocks,
+			pre_verification_results,
+			verifier,
+			self.delay_between_blocks,
+			self.metrics.clone(),
+		)
 			.then(move |(imported, count, results, block_import, verifier)| {
 				result_sender.blocks_processed(imported, count, results);
 				future::ready((block_import, verifier))
 			})
 	}
 
+	/// Start the pre-verification of the headers of the given blocks, if there is a
+	/// pre-verifier, returning the result of each of them.
+	fn pre_verify_blocks(
+		&self,
+		blocks: &[IncomingBlock<B>],
+	) -> Vec<Option<PreVerificationResult>> {
+		let (pre_verifier, pool) = match self.pre_verification {
+			Some(ref pre_verification) => pre_verification,
+			None => return blocks.iter().map(|_| None).collect(),
+		};
+
+		blocks.iter().map(|block| {
+			let header = block.header.clone()?;
+			let pre_verifier = pre_verifier.clone();
+
+			let result: PreVerificationResult = match pool {
+				Some(pool) => {
+					let (tx, rx) = futures::channel::oneshot::channel();
+					pool.spawn_ok(async move {
+						let _ = tx.send(pre_verifier.pre_verify(&header));
+					});
+					Box::pin(rx.map(|result| result.unwrap_or_else(|_|
+						Err("Pre-verification of the header was cancelled".into())
+					)))
+				},
+				None => Box::pin(future::ready(pre_verifier.pre_verify(&header))),
+			};
+
+			Some(result)
+		}).collect()
+	}
+
 	fn import_finality_proof<V: 'static + Verifier<B>>(
 		&mut self,
 		verifier: &mut V,
@@ -333,6 +469,7 @@ impl<B: BlockT, Transaction: Send> BlockImportWorker<B, Transaction> {
 		number: NumberFor<B>,
 		justification: Justification
 	) {
+		let started = Instant::now();
 		let success = self.justification_import.as_mut().map(|justification_import| {
 			justification_import.import_justification(hash, number, justification)
 				.map_err(|e| {
@@ -348,6 +485,13 @@ impl<B: BlockT, Transaction: Send> BlockImportWorker<B, Transaction> {
 				}).is_ok()
 		}).unwrap_or(false);
 
+		if let Some(metrics) = self.metrics.as_ref() {
+			metrics.justification_import_time.observe(started.elapsed().as_secs_f64());
+			if !success {
+				metrics.failed_justifications.inc();
+			}
+		}
+
 		self.result_sender.justification_imported(who, &hash, number, success);
 	}
 }
@@ -359,12 +503,17 @@ impl<B: BlockT, Transaction: Send> BlockImportWorker<B, Transaction> {
 ///
 /// The returned `Future` yields at every imported block, which makes the execution more
 /// fine-grained and making it possible to interrupt the process.
+///
+/// A block is only verified and imported once the pre-verification of its header, if any, has
+/// succeeded.
 fn import_many_blocks<B: BlockT, V: Verifier<B>, Transaction>(
 	import_handle: BoxBlockImport<B, Transaction>,
 	blocks_origin: BlockOrigin,
 	blocks: Vec<IncomingBlock<B>>,
+	pre_verification_results: Vec<Option<PreVerificationResult>>,
 	verifier: V,
 	delay_between_blocks: Duration,
+	metrics: Option<Metrics>,
 ) -> impl Future<
 	Output = (
 		usize,
@@ -391,7 +540,8 @@ fn import_many_blocks<B: BlockT, V: Verifier<B>, Transaction>(
 	let mut imported = 0;
 	let mut results = vec![];
 	let mut has_error = false;
-	let mut blocks = blocks.into_iter();
+	let mut blocks = blocks.into_iter().zip(pre_verification_results.into_iter());
+	let mut pre_verifying = None;
 	let mut import_handle = Some(import_handle);
 	let mut waiting = None;
 	let mut verifier = Some(verifier);
@@ -409,7 +559,8 @@ fn import_many_blocks<B: BlockT, V: Verifier<B>, Transaction>(
 		waiting = None;
 
 		// Is there any block left to import?
-		let block = match blocks.next() {
+		let next_block = pre_verifying.take().or_else(|| blocks.next());
+		let (block, pre_verification_result) = match next_block {
 			Some(b) => b,
 			None => {
 				// No block left to import, success!
@@ -430,20 +581,46 @@ fn import_many_blocks<B: BlockT, V: Verifier<B>, Transaction>(
 		let verifier = verifier.as_mut()
 			.expect("Future polled again after it has finished");
 
+		// Wait for the pre-verification of the header to complete, unless the block is cancelled.
+		let pre_verification = match pre_verification_result {
+			Some(mut result) if !has_error => match Future::poll(result.as_mut(), cx) {
+				Poll::Ready(result) => result,
+				Poll::Pending => {
+					pre_verifying = Some((block, Some(result)));
+					return Poll::Pending;
+				},
+			},
+			_ => Ok(()),
+		};
+
 		let block_number = block.header.as_ref().map(|h| h.number().clone());
 		let block_hash = block.hash;
 		let import_result = if has_error {
 			Err(BlockImportError::Cancelled)
+		} else if let Err(msg) = pre_verification {
+			trace!(target: "sync",
+				"Pre-verifying {:?}({}) failed: {}", block_number, block_hash, msg,
+			);
+			Err(BlockImportError::VerificationFailed(block.origin, msg))
 		} else {
 			// The actual import.
-			import_single_block(
+			let started = Instant::now();
+			let result = import_single_block(
 				&mut **import_handle,
 				blocks_origin.clone(),
 				block,
 				verifier,
-			)
+			);
+			if let Some(metrics) = metrics.as_ref() {
+				metrics.block_import_time.observe(started.elapsed().as_secs_f64());
+			}
+			result
 		};
 
+		if let Some(metrics) = metrics.as_ref() {
+			metrics.report_block_import(&import_result);
+		}
+
 		if import_result.is_ok() {
 			trace!(target: "sync", "Block imported successfully {:?} ({})", block_number, block_hash);
 			imported += 1;
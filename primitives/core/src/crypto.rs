@@ -268,23 +268,40 @@ pub trait Ss58Codec: Sized + AsMut<[u8]> + AsRef<[u8]> + Default {
 				_ => Err(PublicError::UnknownVersion),
 			})
 	}
-	/// Some if the string is a properly encoded SS58Check address.
+	/// Some if the string is a properly encoded SS58Check address, along with the network the
+	/// address belongs to.
+	///
+	/// The prefixes below 64 are encoded on a single byte, the ones up to 16383 on two bytes.
 	#[cfg(feature = "std")]
 	fn from_ss58check_with_version(s: &str) -> Result<(Self, Ss58AddressFormat), PublicError> {
 		let mut res = Self::default();
 		let len = res.as_mut().len();
 		let d = s.from_base58().map_err(|_| PublicError::BadBase58)?; // failure here would be invalid encoding.
-		if d.len() != len + 3 {
+		let (prefix_len, ident) = match d.get(0) {
+			Some(0..=63) => (1, d[0] as u16),
+			Some(64..=127) if d.len() > 1 => {
+				// the lower bits of the identifier are in the first byte, the upper in the second.
+				let lower = (d[0] << 2) | (d[1] >> 6);
+				let upper = d[1] & 0b0011_1111;
+				(2, (lower as u16) | ((upper as u16) << 8))
+			},
+			Some(64..=127) => return Err(PublicError::BadLength),
+			// the prefixes starting with 128 and above are reserved.
+			_ => return Err(PublicError::UnknownVersion),
+		};
+		if d.len() != prefix_len + len + 2 {
 			// Invalid length.
 			return Err(PublicError::BadLength);
 		}
-		let ver = d[0].try_into().map_err(|_: ()| PublicError::UnknownVersion)?;
+		// prefixes which aren't known are those of custom networks.
+		let ver = ident.try_into().unwrap_or(Ss58AddressFormat::Custom(ident));
 
-		if d[len + 1..len + 3] != ss58hash(&d[0..len + 1]).as_bytes()[0..2] {
+		let body_len = prefix_len + len;
+		if d[body_len..body_len + 2] != ss58hash(&d[0..body_len]).as_bytes()[0..2] {
 			// Invalid checksum.
 			return Err(PublicError::InvalidChecksum);
 		}
-		res.as_mut().copy_from_slice(&d[1..len + 1]);
+		res.as_mut().copy_from_slice(&d[prefix_len..body_len]);
 		Ok((res, ver))
 	}
 	/// Some if the string is a properly encoded SS58Check address, optionally with
@@ -300,18 +317,33 @@ pub trait Ss58Codec: Sized + AsMut<[u8]> + AsRef<[u8]> + Default {
 	}
 
 	/// Return the ss58-check string for this key.
-
+	///
+	/// Fails if the prefix of the version doesn't fit in the 14 bits of the two-byte prefixes.
 	#[cfg(feature = "std")]
-	fn to_ss58check_with_version(&self, version: Ss58AddressFormat) -> String {
-		let mut v = vec![version.into()];
+	fn to_ss58check_with_version(&self, version: Ss58AddressFormat) -> Result<String, PublicError> {
+		let ident = u16::from(version);
+		let mut v = match ident {
+			0..=63 => vec![ident as u8],
+			16_384..=u16::MAX => return Err(PublicError::UnknownVersion),
+			_ => {
+				// the first byte gets the bits 2 to 7 of the identifier, tagged with `0b01`, the
+				// second one the bits 0 and 1 followed by the bits 8 to 13.
+				let first = ((ident & 0b0000_0000_1111_1100) as u8) >> 2;
+				let second = ((ident >> 8) as u8) | (((ident & 0b0000_0000_0000_0011) as u8) << 6);
+				vec![first | 0b0100_0000, second]
+			},
+		};
 		v.extend(self.as_ref());
 		let r = ss58hash(&v);
 		v.extend(&r.as_bytes()[0..2]);
-		v.to_base58()
+		Ok(v.to_base58())
 	}
 	/// Return the ss58-check string for this key.
 	#[cfg(feature = "std")]
-	fn to_ss58check(&self) -> String { self.to_ss58check_with_version(*DEFAULT_VERSION.lock()) }
+	fn to_ss58check(&self) -> String {
+		self.to_ss58check_with_version(*DEFAULT_VERSION.lock())
+			.expect("the default version is checked when it is set; qed")
+	}
 	/// Some if the string is a properly encoded SS58Check address, optionally with
 	/// a derivation path following.
 	#[cfg(feature = "std")]
@@ -355,8 +387,8 @@ macro_rules! ss58_address_format {
 		#[derive(Copy, Clone, PartialEq, Eq)]
 		pub enum Ss58AddressFormat {
 			$(#[doc = $desc] $identifier),*,
-			/// Use a manually provided numeric value.
-			Custom(u8),
+			/// Use a manually provided numeric value, at most 16383.
+			Custom(u16),
 		}
 
 		static ALL_SS58_ADDRESS_FORMATS: [Ss58AddressFormat; 0 $(+ { let _ = $number; 1})*] = [
@@ -378,8 +410,8 @@ macro_rules! ss58_address_format {
 			}
 		}
 
-		impl From<Ss58AddressFormat> for u8 {
-			fn from(x: Ss58AddressFormat) -> u8 {
+		impl From<Ss58AddressFormat> for u16 {
+			fn from(x: Ss58AddressFormat) -> u16 {
 				match x {
 					$(Ss58AddressFormat::$identifier => $number),*,
 					Ss58AddressFormat::Custom(n) => n,
@@ -387,10 +419,10 @@ macro_rules! ss58_address_format {
 			}
 		}
 
-		impl TryFrom<u8> for Ss58AddressFormat {
+		impl TryFrom<u16> for Ss58AddressFormat {
 			type Error = ();
 
-			fn try_from(x: u8) -> Result<Ss58AddressFormat, ()> {
+			fn try_from(x: u16) -> Result<Ss58AddressFormat, ()> {
 				match x {
 					$($number => Ok(Ss58AddressFormat::$identifier)),*,
 					_ => Err(()),
@@ -404,7 +436,10 @@ macro_rules! ss58_address_format {
 			fn try_from(x: &'a str) -> Result<Ss58AddressFormat, ()> {
 				match x {
 					$($name => Ok(Ss58AddressFormat::$identifier)),*,
-					a => a.parse::<u8>().map(Ss58AddressFormat::Custom).map_err(|_| ()),
+					a => a.parse::<u16>().ok()
+						.filter(|n| *n < 16_384)
+						.map(Ss58AddressFormat::Custom)
+						.ok_or(()),
 				}
 			}
 		}
@@ -473,12 +508,17 @@ ss58_address_format!(
 
 /// Set the default "version" (actually, this is a bit of a misnomer and the version byte is
 /// typically used not just to encode format/version but also network identity) that is used for
-/// encoding and decoding SS58 addresses. If an unknown version is provided then it fails.
+/// encoding and decoding SS58 addresses. If a custom version above 16383 is provided then it
+/// fails.
 ///
 /// See `ss58_address_format!` for all current known "versions".
 #[cfg(feature = "std")]
-pub fn set_default_ss58_version(version: Ss58AddressFormat) {
-	*DEFAULT_VERSION.lock() = version
+pub fn set_default_ss58_version(version: Ss58AddressFormat) -> Result<(), PublicError> {
+	if u16::from(version) >= 16_384 {
+		return Err(PublicError::UnknownVersion);
+	}
+	*DEFAULT_VERSION.lock() = version;
+	Ok(())
 }
 
 #[cfg(feature = "std")]
@@ -941,6 +981,30 @@ impl<'a> TryFrom<&'a str> for KeyTypeId {
 	}
 }
 
+/// An identifier for a cryptographic algorithm, e.g. the one used by the keys of a key type.
+#[derive(
+	Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Encode, Decode, crate::RuntimeDebug
+)]
+pub struct CryptoTypeId(pub [u8; 4]);
+
+/// A raw public key, along with the identifier of its cryptographic algorithm.
+///
+/// This is how a keystore refers to a key without knowing its concrete type, e.g. when signing
+/// with a key held outside of the node.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Encode, Decode, crate::RuntimeDebug)]
+pub struct CryptoTypePublicPair(pub CryptoTypeId, pub Vec<u8>);
+
+#[cfg(feature = "std")]
+impl sp_std::fmt::Display for CryptoTypePublicPair {
+	fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		let id = match std::str::from_utf8(&(self.0).0[..]) {
+			Ok(id) => id.to_string(),
+			Err(_) => format!("{:#?}", self.0),
+		};
+		write!(f, "{}-{}", id, hex::encode(&self.1))
+	}
+}
+
 /// Known key types; this also functions as a global registry of key types for projects wishing to
 /// avoid collisions with each other.
 ///
@@ -961,6 +1025,10 @@ pub mod key_types {
 	pub const IM_ONLINE: KeyTypeId = KeyTypeId(*b"imon");
 	/// Key type for AuthorityDiscovery module, built-in.
 	pub const AUTHORITY_DISCOVERY: KeyTypeId = KeyTypeId(*b"audi");
+	/// Key type for Beefy module, built-in.
+	pub const BEEFY: KeyTypeId = KeyTypeId(*b"beef");
+	/// Key type for Oracle module, built-in.
+	pub const ORACLE: KeyTypeId = KeyTypeId(*b"orcl");
 	/// A key type ID useful for tests.
 	pub const DUMMY: KeyTypeId = KeyTypeId(*b"dumy");
 }
@@ -1126,4 +1194,23 @@ mod tests {
 			Ok(TestPair::Standard{phrase: "hello world".to_owned(), password: Some("password".to_owned()), path: vec![DeriveJunction::soft(1), DeriveJunction::hard("DOT")]})
 		);
 	}
+
+	#[test]
+	fn ss58check_rejects_reserved_prefixes() {
+		let ss58 = |prefix: u8| {
+			let mut v = vec![prefix];
+			v.extend(&ss58hash(&v).as_bytes()[0..2]);
+			v.to_base58()
+		};
+
+		assert!(TestPublic::from_ss58check_with_version(&ss58(42)).unwrap().1
+			== Ss58AddressFormat::SubstrateAccount);
+		assert!(TestPublic::from_ss58check_with_version(&ss58(50)).unwrap().1
+			== Ss58AddressFormat::Custom(50));
+		assert_eq!(
+			TestPublic::from_ss58check_with_version(&ss58(200)).map(|_| ()),
+			Err(PublicError::UnknownVersion),
+		);
+		assert!(Ss58AddressFormat::try_from("20000").is_err());
+	}
 }
@@ -38,7 +38,9 @@ use crate::crypto::{Pair as TraitPair, DeriveJunction, SecretStringError};
 use crate::crypto::Ss58Codec;
 #[cfg(feature = "std")]
 use serde::{de, Serializer, Serialize, Deserializer, Deserialize};
-use crate::{crypto::{Public as TraitPublic, UncheckedFrom, CryptoType, Derive}};
+use crate::crypto::{
+	Public as TraitPublic, UncheckedFrom, CryptoType, Derive, CryptoTypeId, CryptoTypePublicPair,
+};
 use sp_runtime_interface::pass_by::PassByInner;
 use sp_std::ops::Deref;
 
@@ -53,6 +55,9 @@ type Seed = [u8; 32];
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Encode, Decode, Default, PassByInner)]
 pub struct Public(pub [u8; 32]);
 
+/// An identifier used to match public keys against ed25519 keys.
+pub const CRYPTO_ID: CryptoTypeId = CryptoTypeId(*b"ed25");
+
 /// A key pair.
 #[cfg(feature = "full_crypto")]
 pub struct Pair(ed25519_dalek::Keypair);
@@ -378,6 +383,18 @@ impl TraitPublic for Public {
 
 impl Derive for Public {}
 
+impl From<Public> for CryptoTypePublicPair {
+	fn from(key: Public) -> Self {
+		(&key).into()
+	}
+}
+
+impl From<&Public> for CryptoTypePublicPair {
+	fn from(key: &Public) -> Self {
+		CryptoTypePublicPair(CRYPTO_ID, key.to_raw_vec())
+	}
+}
+
 /// Derive a single hard junction.
 #[cfg(feature = "full_crypto")]
 fn derive_hard_junction(secret_seed: &Seed, cc: &[u8; 32]) -> Seed {
@@ -69,6 +69,8 @@ pub mod uint;
 mod changes_trie;
 #[cfg(feature = "std")]
 pub mod traits;
+#[cfg(feature = "std")]
+pub mod vrf;
 pub mod testing;
 #[cfg(feature = "std")]
 pub mod tasks;
@@ -110,8 +112,13 @@ impl ExecutionContext {
 		match self {
 			Importing | Syncing | BlockConstruction =>
 				offchain::Capabilities::none(),
-			// Enable keystore by default for offchain calls. CC @bkchr
-			OffchainCall(None) => [offchain::Capability::Keystore][..].into(),
+			// Enable keystore, transaction pool and reading the offchain database by default for
+			// offchain calls.
+			OffchainCall(None) => [
+				offchain::Capability::Keystore,
+				offchain::Capability::TransactionPool,
+				offchain::Capability::OffchainWorkerDbRead,
+			][..].into(),
 			OffchainCall(Some((_, capabilities))) => *capabilities,
 		}
 	}
@@ -296,6 +303,11 @@ impl From<LogLevel> for log::Level {
 	}
 }
 
+/// The void type - it cannot exist.
+// Oh rust, you crack me up...
+#[derive(Clone, Decode, Encode, Eq, PartialEq, RuntimeDebug)]
+pub enum Void {}
+
 /// Encodes the given value into a buffer and returns the pointer and the length as a single `u64`.
 ///
 /// When Substrate calls into Wasm it expects a fixed signature for functions exported
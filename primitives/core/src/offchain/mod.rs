@@ -36,6 +36,12 @@ pub trait OffchainStorage: Clone + Send + Sync {
 	/// Retrieve a value from storage under given key and prefix.
 	fn get(&self, prefix: &[u8], key: &[u8]) -> Option<Vec<u8>>;
 
+	/// Retrieve all the keys and values stored under given prefix, whose key starts with
+	/// `key_prefix`.
+	///
+	/// The returned keys don't include `prefix`.
+	fn iter_prefix(&self, prefix: &[u8], key_prefix: &[u8]) -> Vec<(Vec<u8>, Vec<u8>)>;
+
 	/// Replace the value in storage if given old_value matches the current one.
 	///
 	/// Returns `true` if the value has been set and false otherwise.
@@ -36,6 +36,16 @@ impl OffchainStorage for InMemOffchainStorage {
 		self.storage.get(&key).cloned()
 	}
 
+	fn iter_prefix(&self, prefix: &[u8], key_prefix: &[u8]) -> Vec<(Vec<u8>, Vec<u8>)> {
+		let full_prefix: Vec<u8> = prefix.iter().chain(key_prefix).cloned().collect();
+		let mut pairs: Vec<_> = self.storage.iter()
+			.filter(|(key, _)| key.starts_with(&full_prefix))
+			.map(|(key, value)| (key[prefix.len()..].to_vec(), value.clone()))
+			.collect();
+		pairs.sort();
+		pairs
+	}
+
 	fn compare_and_set(
 		&mut self,
 		prefix: &[u8],
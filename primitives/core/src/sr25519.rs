@@ -39,7 +39,9 @@ use crate::crypto::{
 #[cfg(feature = "std")]
 use crate::crypto::Ss58Codec;
 
-use crate::{crypto::{Public as TraitPublic, UncheckedFrom, CryptoType, Derive}};
+use crate::crypto::{
+	Public as TraitPublic, UncheckedFrom, CryptoType, Derive, CryptoTypeId, CryptoTypePublicPair,
+};
 use crate::hash::{H256, H512};
 use codec::{Encode, Decode};
 use sp_std::ops::Deref;
@@ -59,6 +61,9 @@ const SIGNING_CTX: &[u8] = b"substrate";
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Encode, Decode, Default, PassByInner)]
 pub struct Public(pub [u8; 32]);
 
+/// An identifier used to match public keys against sr25519 keys.
+pub const CRYPTO_ID: CryptoTypeId = CryptoTypeId(*b"sr25");
+
 /// An Schnorrkel/Ristretto x25519 ("sr25519") key pair.
 #[cfg(feature = "full_crypto")]
 pub struct Pair(Keypair);
@@ -355,6 +360,18 @@ impl Derive for Public {
 	}
 }
 
+impl From<Public> for CryptoTypePublicPair {
+	fn from(key: Public) -> Self {
+		(&key).into()
+	}
+}
+
+impl From<&Public> for CryptoTypePublicPair {
+	fn from(key: &Public) -> Self {
+		CryptoTypePublicPair(CRYPTO_ID, key.to_raw_vec())
+	}
+}
+
 impl Public {
 	/// A new instance from the given 32-byte `data`.
 	///
@@ -775,6 +792,46 @@ mod test {
 		assert_eq!(cmp, public);
 	}
 
+	#[test]
+	fn ss58check_roundtrip_works_with_custom_prefix() {
+		use crate::crypto::Ss58AddressFormat;
+
+		let (pair, _) = Pair::generate();
+		let public = pair.public();
+		let s = public.to_ss58check_with_version(Ss58AddressFormat::Custom(50)).unwrap();
+		let (cmp, version) = Public::from_ss58check_with_version(&s).unwrap();
+		assert_eq!(cmp, public);
+		assert!(version == Ss58AddressFormat::Custom(50));
+		// custom prefixes are only accepted where they are the default.
+		assert!(Public::from_ss58check(&s).is_err());
+	}
+
+	#[test]
+	fn ss58check_roundtrip_works_with_two_byte_prefixes() {
+		use crate::crypto::{Ss58AddressFormat, PublicError, set_default_ss58_version};
+
+		let (pair, _) = Pair::generate();
+		let public = pair.public();
+		for ident in vec![64, 255, 256, 1000, 16_383] {
+			let s = public.to_ss58check_with_version(Ss58AddressFormat::Custom(ident)).unwrap();
+			let (cmp, version) = Public::from_ss58check_with_version(&s).unwrap();
+			assert_eq!(cmp, public);
+			assert!(version == Ss58AddressFormat::Custom(ident));
+		}
+
+		// the known networks are reported as such.
+		let s = public.to_ss58check_with_version(Ss58AddressFormat::KusamaAccount).unwrap();
+		let (_, version) = Public::from_ss58check_with_version(&s).unwrap();
+		assert!(version == Ss58AddressFormat::KusamaAccount);
+
+		// the prefixes above 16383 can't be encoded.
+		assert_eq!(
+			public.to_ss58check_with_version(Ss58AddressFormat::Custom(16_384)),
+			Err(PublicError::UnknownVersion),
+		);
+		assert!(set_default_ss58_version(Ss58AddressFormat::Custom(16_384)).is_err());
+	}
+
 	#[test]
 	fn verify_from_wasm_works() {
 		// The values in this test case are compared to the output of `node-test.js` in schnorrkel-js.
@@ -16,7 +16,11 @@
 
 //! Shareable Substrate traits.
 
-use crate::{crypto::KeyTypeId, ed25519, sr25519};
+use crate::{
+	crypto::{KeyTypeId, CryptoTypePublicPair, Pair, Public},
+	ecdsa, ed25519, sr25519,
+	vrf::{VRFTranscriptData, VRFSignature, make_transcript},
+};
 
 use std::{
 	fmt::{Debug, Display}, panic::UnwindSafe, sync::Arc, borrow::Cow,
@@ -24,6 +28,32 @@ use std::{
 
 pub use sp_externalities::{Externalities, ExternalitiesExt};
 
+/// An error of a keystore.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BareCryptoStoreError {
+	/// The cryptographic algorithm of the key isn't supported by the keystore.
+	KeyNotSupported(KeyTypeId),
+	/// The private key of the given public key couldn't be found.
+	PairNotFound(String),
+	/// The keystore couldn't be reached, e.g. a remote signer that is down.
+	Unavailable(String),
+	/// Any other error.
+	Other(String),
+}
+
+impl Display for BareCryptoStoreError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			BareCryptoStoreError::KeyNotSupported(id) => write!(f, "Key not supported: {:?}", id),
+			BareCryptoStoreError::PairNotFound(key) => write!(f, "Pair not found: {}", key),
+			BareCryptoStoreError::Unavailable(e) => write!(f, "Keystore unavailable: {}", e),
+			BareCryptoStoreError::Other(e) => write!(f, "Keystore error: {}", e),
+		}
+	}
+}
+
+impl std::error::Error for BareCryptoStoreError {}
+
 /// Something that generates, stores and provides access to keys.
 pub trait BareCryptoStore: Send + Sync {
 	/// Returns all sr25519 public keys for the given key type.
@@ -57,6 +87,22 @@ pub trait BareCryptoStore: Send + Sync {
 	/// Returns the ed25519 key pair for the given key type and public key combination.
 	fn ed25519_key_pair(&self, id: KeyTypeId, pub_key: &ed25519::Public) -> Option<ed25519::Pair>;
 
+	/// Returns all ecdsa public keys for the given key type.
+	fn ecdsa_public_keys(&self, id: KeyTypeId) -> Vec<ecdsa::Public>;
+	/// Generate a new ecdsa key pair for the given key type and an optional seed.
+	///
+	/// If the given seed is `Some(_)`, the key pair will only be stored in memory.
+	///
+	/// Returns the public key of the generated key pair.
+	fn ecdsa_generate_new(
+		&mut self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<ecdsa::Public, String>;
+
+	/// Returns the ecdsa key pair for the given key type and public key combination.
+	fn ecdsa_key_pair(&self, id: KeyTypeId, pub_key: &ecdsa::Public) -> Option<ecdsa::Pair>;
+
 	/// Insert a new key. This doesn't require any known of the crypto; but a public key must be
 	/// manually provided.
 	///
@@ -72,6 +118,75 @@ pub trait BareCryptoStore: Send + Sync {
 	///
 	/// Returns `true` iff all private keys could be found.
 	fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool;
+
+	/// Returns all the public keys for the given key type, along with their cryptographic
+	/// algorithm.
+	///
+	/// Unlike the `*_public_keys` methods, this doesn't require knowing the concrete types of
+	/// the keys, which is how the consensus engines pick their authority keys.
+	fn keys(&self, id: KeyTypeId) -> Result<Vec<CryptoTypePublicPair>, BareCryptoStoreError> {
+		let mut keys = self.sr25519_public_keys(id)
+			.into_iter()
+			.map(CryptoTypePublicPair::from)
+			.collect::<Vec<_>>();
+		keys.extend(self.ed25519_public_keys(id).into_iter().map(CryptoTypePublicPair::from));
+		keys.extend(self.ecdsa_public_keys(id).into_iter().map(CryptoTypePublicPair::from));
+		Ok(keys)
+	}
+
+	/// Sign the message with the private key of the given public key and key type.
+	///
+	/// Returns the SCALE encoded signature. This is the only way to sign with a key that is
+	/// held outside of the node, as the keystore doesn't hand its key pairs out then.
+	fn sign_with(
+		&self,
+		id: KeyTypeId,
+		key: &CryptoTypePublicPair,
+		msg: &[u8],
+	) -> Result<Vec<u8>, BareCryptoStoreError> {
+		match key.0 {
+			ed25519::CRYPTO_ID if key.1.len() == 32 => {
+				let public = ed25519::Public::from_slice(&key.1);
+				self.ed25519_key_pair(id, &public)
+					.map(|pair| codec::Encode::encode(&pair.sign(msg)))
+					.ok_or_else(|| BareCryptoStoreError::PairNotFound(key.to_string()))
+			},
+			sr25519::CRYPTO_ID if key.1.len() == 32 => {
+				let public = sr25519::Public::from_slice(&key.1);
+				self.sr25519_key_pair(id, &public)
+					.map(|pair| codec::Encode::encode(&pair.sign(msg)))
+					.ok_or_else(|| BareCryptoStoreError::PairNotFound(key.to_string()))
+			},
+			ecdsa::CRYPTO_ID if key.1.len() == 33 => {
+				let public = ecdsa::Public::from_slice(&key.1);
+				self.ecdsa_key_pair(id, &public)
+					.map(|pair| codec::Encode::encode(&pair.sign(msg)))
+					.ok_or_else(|| BareCryptoStoreError::PairNotFound(key.to_string()))
+			},
+			_ => Err(BareCryptoStoreError::KeyNotSupported(id)),
+		}
+	}
+
+	/// Sign the VRF transcript with the private sr25519 key of the given public key and key type.
+	///
+	/// The transcript is given as data, so that it can be rebuilt wherever the key is held.
+	fn sr25519_vrf_sign(
+		&self,
+		id: KeyTypeId,
+		public: &sr25519::Public,
+		transcript_data: VRFTranscriptData,
+	) -> Result<VRFSignature, BareCryptoStoreError> {
+		let pair = self.sr25519_key_pair(id, public).ok_or_else(|| {
+			BareCryptoStoreError::PairNotFound(CryptoTypePublicPair::from(public).to_string())
+		})?;
+		let keypair: &schnorrkel::Keypair = pair.as_ref();
+		let (inout, proof, _) = keypair.vrf_sign(make_transcript(transcript_data));
+
+		Ok(VRFSignature {
+			output: inout.to_output(),
+			proof,
+		})
+	}
 }
 
 /// A pointer to the key store.
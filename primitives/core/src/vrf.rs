@@ -0,0 +1,88 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! VRF transcripts and signatures, in a form a keystore can sign without sharing the key.
+
+use merlin::Transcript;
+use schnorrkel::vrf::{VRFOutput, VRFProof};
+
+/// A value of a VRF transcript.
+#[derive(Clone, PartialEq, Eq, crate::RuntimeDebug)]
+pub enum VRFTranscriptValue {
+	/// Bytes appended with `append_message`.
+	Bytes(Vec<u8>),
+	/// A number appended with `append_u64`.
+	U64(u64),
+}
+
+/// The data a VRF transcript is built from.
+///
+/// This is given to the keystore instead of the transcript itself, which can't be serialized,
+/// so that the transcript can be rebuilt wherever the key is held.
+#[derive(Clone, PartialEq, Eq, crate::RuntimeDebug)]
+pub struct VRFTranscriptData {
+	/// The label of the transcript.
+	pub label: &'static [u8],
+	/// The labeled values appended to the transcript, in order.
+	pub items: Vec<(&'static str, VRFTranscriptValue)>,
+}
+
+/// A VRF signature: the output of the VRF, along with the proof that it is the right one.
+#[derive(Clone, Debug)]
+pub struct VRFSignature {
+	/// The output of the VRF.
+	pub output: VRFOutput,
+	/// The proof of the output.
+	pub proof: VRFProof,
+}
+
+/// Build the transcript from its data.
+pub fn make_transcript(data: VRFTranscriptData) -> Transcript {
+	let mut transcript = Transcript::new(data.label);
+	for (label, value) in data.items {
+		match value {
+			VRFTranscriptValue::Bytes(bytes) => transcript.append_message(label.as_bytes(), &bytes),
+			VRFTranscriptValue::U64(number) => transcript.append_u64(label.as_bytes(), number),
+		}
+	}
+	transcript
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{crypto::Pair as _, sr25519};
+
+	#[test]
+	fn transcript_is_built_from_its_data() {
+		let data = VRFTranscriptData {
+			label: b"test",
+			items: vec![
+				("slot number", VRFTranscriptValue::U64(42)),
+				("randomness", VRFTranscriptValue::Bytes(vec![1; 32])),
+			],
+		};
+		let mut transcript = Transcript::new(b"test");
+		transcript.append_u64(b"slot number", 42);
+		transcript.append_message(b"randomness", &[1; 32]);
+
+		let pair = sr25519::Pair::from_seed(&[7; 32]);
+		let keypair: &schnorrkel::Keypair = pair.as_ref();
+		let (from_data, _, _) = keypair.vrf_sign(make_transcript(data));
+		let (expected, _, _) = keypair.vrf_sign(transcript);
+		assert_eq!(from_data.to_output(), expected.to_output());
+	}
+}
@@ -21,8 +21,10 @@
 //! is stored in the `InherentData`. This `InherentData` is constructed by the node and given to
 //! the runtime.
 //!
-//! Types that provide data for inherents, should implement `InherentDataProvider` and need to be
-//! registered at `InherentDataProviders`.
+//! Types that provide data for inherents, should implement `ProvideInherentData` and need to be
+//! registered at `InherentDataProviders`. Inherent data that depends on the block being built or
+//! verified is provided by an `InherentDataProvider` instead, which `CreateInherentDataProviders`
+//! creates for each block from its parent hash, possibly by doing asynchronous work first.
 //!
 //! In the runtime, modules need to implement `ProvideInherent` when they can create and/or check
 //! inherents. By implementing `ProvideInherent`, a module is not enforced to create an inherent.
@@ -41,7 +43,7 @@ use sp_std::{collections::btree_map::{BTreeMap, IntoIter, Entry}, vec::Vec};
 use parking_lot::RwLock;
 
 #[cfg(feature = "std")]
-use std::{sync::Arc, format};
+use std::{sync::Arc, format, future::Future, pin::Pin};
 
 /// An error that can occur within the inherent data system.
 #[cfg(feature = "std")]
@@ -302,12 +304,7 @@ impl InherentDataProviders {
 
 	/// Create inherent data.
 	pub fn create_inherent_data(&self) -> Result<InherentData, Error> {
-		let mut data = InherentData::new();
-		self.providers.read().iter().try_for_each(|p| {
-			p.provide_inherent_data(&mut data)
-				.map_err(|e| format!("Error for `{:?}`: {:?}", p.inherent_identifier(), e))
-		})?;
-		Ok(data)
+		InherentDataProvider::create_inherent_data(self)
 	}
 
 	/// Converts a given encoded error into a `String`.
@@ -367,6 +364,133 @@ fn error_to_string_fallback(identifier: &InherentIdentifier) -> String {
 	)
 }
 
+/// Something that provides the inherent data of one block.
+///
+/// Providers are created for each block by `CreateInherentDataProviders` and are combined by
+/// putting them in a tuple.
+#[cfg(feature = "std")]
+pub trait InherentDataProvider: Send + Sync {
+	/// Provide the inherent data that should be included in the block.
+	///
+	/// The data should be stored in the given `InherentData` structure.
+	fn provide_inherent_data(&self, inherent_data: &mut InherentData) -> Result<(), Error>;
+
+	/// Create the inherent data of the block, with only the data of this provider.
+	fn create_inherent_data(&self) -> Result<InherentData, Error> {
+		let mut inherent_data = InherentData::new();
+		self.provide_inherent_data(&mut inherent_data)?;
+		Ok(inherent_data)
+	}
+
+	/// Convert the given encoded error of the inherent with the given identifier to a string.
+	///
+	/// `None` should be returned if the inherent isn't provided by this provider.
+	fn error_to_string(&self, identifier: &InherentIdentifier, error: &[u8]) -> Option<String>;
+
+	/// Describe the given encoded error of the inherent with the given identifier, whether the
+	/// inherent is provided by this provider or not.
+	fn describe_error(&self, identifier: &InherentIdentifier, error: &[u8]) -> String {
+		self.error_to_string(identifier, error).unwrap_or_else(|| format!(
+			"Error while checking inherent of type \"{}\", but this inherent type is unknown.",
+			String::from_utf8_lossy(identifier),
+		))
+	}
+}
+
+#[cfg(feature = "std")]
+#[impl_trait_for_tuples::impl_for_tuples(8)]
+impl InherentDataProvider for Tuple {
+	fn provide_inherent_data(&self, inherent_data: &mut InherentData) -> Result<(), Error> {
+		for_tuples!( #( Tuple.provide_inherent_data(inherent_data)?; )* );
+		Ok(())
+	}
+
+	fn error_to_string(&self, identifier: &InherentIdentifier, error: &[u8]) -> Option<String> {
+		for_tuples!( #(
+			if let Some(error) = Tuple.error_to_string(identifier, error) {
+				return Some(error)
+			}
+		)* );
+		None
+	}
+}
+
+#[cfg(feature = "std")]
+impl InherentDataProvider for InherentDataProviders {
+	fn provide_inherent_data(&self, inherent_data: &mut InherentData) -> Result<(), Error> {
+		self.providers.read().iter().try_for_each(|p| {
+			p.provide_inherent_data(inherent_data).map_err(|e|
+				format!("Error for `{:?}`: {:?}", p.inherent_identifier(), e).into()
+			)
+		})
+	}
+
+	fn error_to_string(&self, identifier: &InherentIdentifier, error: &[u8]) -> Option<String> {
+		self.providers.read().iter()
+			.find(|p| p.inherent_identifier() == identifier)
+			.map(|p|
+				p.error_to_string(error).unwrap_or_else(|| error_to_string_fallback(identifier))
+			)
+	}
+}
+
+/// The future resolving to the `InherentDataProvider` of a block.
+#[cfg(feature = "std")]
+pub type InherentDataProviderFuture<P> = Pin<Box<dyn Future<Output = Result<P, Error>> + Send>>;
+
+/// Something that creates the `InherentDataProvider` of each block.
+///
+/// The provider is created from the hash of the parent of the block being built or verified, so
+/// it can provide data that depends on the parent, e.g. the head of a relay chain or the value
+/// of an oracle, which it may need to fetch first.
+///
+/// It is implemented by the closures returning the future of an `InherentDataProvider`, and by
+/// `InherentDataProviders`, which provide the same data whatever the parent.
+#[cfg(feature = "std")]
+pub trait CreateInherentDataProviders<Hash, ExtraArgs>: Send + Sync {
+	/// The inherent data provider created for each block.
+	type InherentDataProvider: InherentDataProvider + 'static;
+
+	/// Create the inherent data provider of the block built on top of `parent`.
+	fn create_inherent_data_providers(
+		&self,
+		parent: Hash,
+		extra_args: ExtraArgs,
+	) -> InherentDataProviderFuture<Self::InherentDataProvider>;
+}
+
+#[cfg(feature = "std")]
+impl<F, Fut, Hash, ExtraArgs, P> CreateInherentDataProviders<Hash, ExtraArgs> for F
+	where
+		F: Fn(Hash, ExtraArgs) -> Fut + Send + Sync,
+		Fut: Future<Output = Result<P, Error>> + Send + 'static,
+		P: InherentDataProvider + 'static,
+{
+	type InherentDataProvider = P;
+
+	fn create_inherent_data_providers(
+		&self,
+		parent: Hash,
+		extra_args: ExtraArgs,
+	) -> InherentDataProviderFuture<P> {
+		Box::pin((*self)(parent, extra_args))
+	}
+}
+
+#[cfg(feature = "std")]
+impl<Hash, ExtraArgs> CreateInherentDataProviders<Hash, ExtraArgs> for InherentDataProviders {
+	type InherentDataProvider = Self;
+
+	fn create_inherent_data_providers(
+		&self,
+		_: Hash,
+		_: ExtraArgs,
+	) -> InherentDataProviderFuture<Self> {
+		let providers = self.clone();
+		Box::pin(async move { Ok(providers) })
+	}
+}
+
 /// Did we encounter a fatal error while checking an inherent?
 ///
 /// A fatal error is everything that fails while checking an inherent error, e.g. the inherent
@@ -404,6 +528,11 @@ pub trait ProvideInherent {
 	type Error: codec::Encode + IsFatalError;
 	/// The inherent identifier used by this inherent.
 	const INHERENT_IDENTIFIER: self::InherentIdentifier;
+	/// Whether a block is invalid without this inherent once it can be created.
+	///
+	/// Block authors never leave out a mandatory inherent that can't be applied, but rather give
+	/// up on building the block.
+	const IS_MANDATORY: bool = false;
 
 	/// Create an inherent out of the given `InherentData`.
 	fn create_inherent(data: &InherentData) -> Option<Self::Call>;
@@ -546,6 +675,54 @@ mod tests {
 		);
 	}
 
+	struct ParentInherentDataProvider(u64);
+
+	impl InherentDataProvider for ParentInherentDataProvider {
+		fn provide_inherent_data(&self, inherent_data: &mut InherentData) -> Result<(), Error> {
+			inherent_data.put_data(TEST_INHERENT_1, &self.0)
+		}
+
+		fn error_to_string(&self, identifier: &InherentIdentifier, _: &[u8]) -> Option<String> {
+			if *identifier == TEST_INHERENT_1 {
+				Some("Parent error!".into())
+			} else {
+				None
+			}
+		}
+	}
+
+	#[test]
+	fn inherent_data_providers_are_created_for_each_parent() {
+		let providers = InherentDataProviders::new();
+		providers.register_provider(TestInherentDataProvider::new()).unwrap();
+
+		let create = move |parent: u64, _: ()| {
+			let providers = providers.clone();
+			async move { Ok::<_, Error>((providers, ParentInherentDataProvider(parent + 1))) }
+		};
+
+		for parent in 0..3 {
+			let provider = futures::executor::block_on(
+				create.create_inherent_data_providers(parent, ())
+			).unwrap();
+			let inherent_data = provider.create_inherent_data().unwrap();
+
+			assert_eq!(inherent_data.get_data::<u32>(&TEST_INHERENT_0).unwrap().unwrap(), 42);
+			assert_eq!(
+				inherent_data.get_data::<u64>(&TEST_INHERENT_1).unwrap().unwrap(),
+				parent + 1,
+			);
+			assert_eq!(
+				provider.error_to_string(&TEST_INHERENT_0, &[1, 2]).as_deref(),
+				Some(ERROR_TO_STRING),
+			);
+			assert_eq!(
+				provider.error_to_string(&TEST_INHERENT_1, &[1, 2]).as_deref(),
+				Some("Parent error!"),
+			);
+		}
+	}
+
 	#[test]
 	fn check_inherents_result_encodes_and_decodes() {
 		let mut result = CheckInherentsResult::new();
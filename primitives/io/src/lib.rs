@@ -41,7 +41,7 @@ use sp_core::{
 };
 
 use sp_core::{
-	crypto::KeyTypeId, ed25519, sr25519, H256, LogLevel,
+	crypto::KeyTypeId, ecdsa, ed25519, sr25519, H256, LogLevel,
 	offchain::{
 		Timestamp, HttpRequestId, HttpRequestStatus, HttpError, StorageKind, OpaqueNetworkState,
 	},
@@ -473,6 +473,53 @@ pub trait Crypto {
 		sr25519::Pair::verify(sig, msg, pubkey)
 	}
 
+	/// Returns all `ecdsa` public keys for the given key id from the keystore.
+	fn ecdsa_public_keys(&mut self, id: KeyTypeId) -> Vec<ecdsa::Public> {
+		self.extension::<KeystoreExt>()
+			.expect("No `keystore` associated for the current context!")
+			.read()
+			.ecdsa_public_keys(id)
+	}
+
+	/// Generate an `ecdsa` key for the given key type using an optional `seed` and
+	/// store it in the keystore.
+	///
+	/// The `seed` needs to be a valid utf8.
+	///
+	/// Returns the public key.
+	fn ecdsa_generate(&mut self, id: KeyTypeId, seed: Option<Vec<u8>>) -> ecdsa::Public {
+		let seed = seed.as_ref().map(|s| std::str::from_utf8(&s).expect("Seed is valid utf8!"));
+		self.extension::<KeystoreExt>()
+			.expect("No `keystore` associated for the current context!")
+			.write()
+			.ecdsa_generate_new(id, seed)
+			.expect("`ecdsa_generate` failed")
+	}
+
+	/// Sign the given `msg` with the `ecdsa` key that corresponds to the given public key and
+	/// key type in the keystore.
+	///
+	/// Returns the signature.
+	fn ecdsa_sign(
+		&mut self,
+		id: KeyTypeId,
+		pub_key: &ecdsa::Public,
+		msg: &[u8],
+	) -> Option<ecdsa::Signature> {
+		self.extension::<KeystoreExt>()
+			.expect("No `keystore` associated for the current context!")
+			.read()
+			.ecdsa_key_pair(id, &pub_key)
+			.map(|k| k.sign(msg))
+	}
+
+	/// Verify an `ecdsa` signature.
+	///
+	/// Returns `true` when the verification in successful.
+	fn ecdsa_verify(sig: &ecdsa::Signature, msg: &[u8], pub_key: &ecdsa::Public) -> bool {
+		ecdsa::Pair::verify(sig, msg, pub_key)
+	}
+
 	/// Verify and recover a SECP256k1 ECDSA signature.
 	///
 	/// - `sig` is passed in RSV format. V should be either `0/1` or `27/28`.
@@ -777,6 +824,89 @@ pub trait Logging {
 	}
 }
 
+/// Interface that provides functions for the tracing of the runtime.
+///
+/// The spans are created on the host, see `sp_tracing` for how they are recorded.
+#[runtime_interface]
+pub trait WasmTracing {
+	/// Set the number of the block being executed and the index of the extrinsic being applied,
+	/// which the spans entered afterwards are tagged with.
+	fn set_context(block_number: u64, extrinsic_index: Option<u32>) {
+		sp_tracing::proxy::set_context(block_number, extrinsic_index)
+	}
+
+	/// Whether the spans of the runtime may be recorded at all. When they aren't, `enter_span`
+	/// doesn't need to be called.
+	fn enabled() -> bool {
+		sp_tracing::proxy::enabled()
+	}
+
+	/// Enter a span with the given target and name.
+	///
+	/// Returns the id of the span, to be passed to `exit_span`. Prefer using a `TracingSpanGuard`.
+	fn enter_span(target: &str, name: &str) -> u64 {
+		sp_tracing::proxy::enter_span(target, name)
+	}
+
+	/// Exit the span with the given id.
+	fn exit_span(id: u64) {
+		sp_tracing::proxy::exit_span(id)
+	}
+}
+
+/// Guard entering a span of the runtime when created and exiting it when dropped.
+///
+/// No span is entered, nor exited, when tracing isn't enabled.
+pub struct TracingSpanGuard(Option<u64>);
+
+impl TracingSpanGuard {
+	/// Enter a span with the given target and name.
+	pub fn new(target: &str, name: &str) -> Self {
+		if !tracing_enabled() {
+			return TracingSpanGuard(None);
+		}
+
+		match wasm_tracing::enter_span(target, name) {
+			0 => TracingSpanGuard(None),
+			id => TracingSpanGuard(Some(id)),
+		}
+	}
+}
+
+impl Drop for TracingSpanGuard {
+	fn drop(&mut self) {
+		if let Some(id) = self.0.take() {
+			wasm_tracing::exit_span(id)
+		}
+	}
+}
+
+/// Whether tracing is enabled, only asked to the host once per call into the runtime, as the
+/// memory of the runtime is reset between calls.
+#[cfg(not(feature = "std"))]
+fn tracing_enabled() -> bool {
+	use core::sync::atomic::{AtomicU8, Ordering};
+
+	const UNKNOWN: u8 = 0;
+	const DISABLED: u8 = 1;
+	const ENABLED: u8 = 2;
+	static TRACING_ENABLED: AtomicU8 = AtomicU8::new(UNKNOWN);
+
+	match TRACING_ENABLED.load(Ordering::Relaxed) {
+		UNKNOWN => {
+			let enabled = wasm_tracing::enabled();
+			TRACING_ENABLED.store(if enabled { ENABLED } else { DISABLED }, Ordering::Relaxed);
+			enabled
+		},
+		state => state == ENABLED,
+	}
+}
+
+#[cfg(feature = "std")]
+fn tracing_enabled() -> bool {
+	wasm_tracing::enabled()
+}
+
 /// Wasm-only interface that provides functions for interacting with the sandbox.
 #[runtime_interface(wasm_only)]
 pub trait Sandbox {
@@ -927,6 +1057,7 @@ pub type SubstrateHostFunctions = (
 	hashing::HostFunctions,
 	allocator::HostFunctions,
 	logging::HostFunctions,
+	wasm_tracing::HostFunctions,
 	sandbox::HostFunctions,
 	crate::trie::HostFunctions,
 );
@@ -0,0 +1,109 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Types for the traces collected while re-executing a block.
+
+use std::collections::BTreeMap;
+use serde::{Serialize, Deserialize};
+
+/// All the spans and events collected while re-executing a block.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockTrace<Hash> {
+	/// Hash of the traced block.
+	pub block_hash: Hash,
+	/// Hash of the parent of the traced block.
+	pub parent_hash: Hash,
+	/// Comma separated list of the targets the spans and events were recorded for.
+	pub tracing_targets: String,
+	/// Comma separated list of the storage key prefixes the storage events were filtered with.
+	pub storage_keys: String,
+	/// Spans entered while executing the block, ordered by creation.
+	pub spans: Vec<Span>,
+	/// Events emitted while executing the block, in emission order.
+	pub events: Vec<Event>,
+}
+
+/// A single tracing span.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Span {
+	/// Id of the span, unique within a trace.
+	pub id: u64,
+	/// Id of the span this one was entered in, if any.
+	pub parent_id: Option<u64>,
+	/// Name of the span.
+	pub name: String,
+	/// Target of the span, typically the module path.
+	pub target: String,
+	/// Line of the span in its source file.
+	pub line: u32,
+	/// Total time spent inside the span, in nanoseconds.
+	pub overall_time: u64,
+	/// Values recorded with the span.
+	pub values: Values,
+}
+
+/// A single tracing event.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Event {
+	/// Target of the event, typically the module path.
+	pub target: String,
+	/// Id of the span the event was emitted in, if any.
+	pub parent_id: Option<u64>,
+	/// Values recorded with the event.
+	pub values: Values,
+}
+
+/// Values recorded with a span or an event, formatted as strings.
+pub type Values = BTreeMap<String, String>;
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn should_serialize_block_trace() {
+		let mut values = Values::new();
+		values.insert("key".into(), "3a636f6465".into());
+		let trace = BlockTrace {
+			block_hash: 2u32,
+			parent_hash: 1u32,
+			tracing_targets: "state".into(),
+			storage_keys: "".into(),
+			spans: vec![Span {
+				id: 1,
+				parent_id: None,
+				name: "on_initialize".into(),
+				target: "frame_system".into(),
+				line: 10,
+				overall_time: 100,
+				values: Values::new(),
+			}],
+			events: vec![Event {
+				target: "state".into(),
+				parent_id: Some(1),
+				values,
+			}],
+		};
+
+		crate::assert_deser(
+			r#"{"blockHash":2,"parentHash":1,"tracingTargets":"state","storageKeys":"","spans":[{"id":1,"parentId":null,"name":"on_initialize","target":"frame_system","line":10,"overallTime":100,"values":{}}],"events":[{"target":"state","parentId":1,"values":{"key":"3a636f6465"}}]}"#,
+			trace,
+		);
+	}
+}
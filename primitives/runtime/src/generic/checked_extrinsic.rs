@@ -21,7 +21,7 @@ use crate::traits::{
 	self, Member, MaybeDisplay, SignedExtension, Dispatchable,
 };
 use crate::traits::ValidateUnsigned;
-use crate::transaction_validity::TransactionValidity;
+use crate::transaction_validity::{TransactionValidity, TransactionSource};
 
 /// Definition of something that the external world might want to say; its
 /// existence implies that it has been checked and is good, particularly with
@@ -50,6 +50,7 @@ where
 
 	fn validate<U: ValidateUnsigned<Call = Self::Call>>(
 		&self,
+		source: TransactionSource,
 		info: Self::DispatchInfo,
 		len: usize,
 	) -> TransactionValidity {
@@ -57,7 +58,7 @@ where
 			Extra::validate(extra, id, &self.function, info.clone(), len)
 		} else {
 			let valid = Extra::validate_unsigned(&self.function, info, len)?;
-			let unsigned_validation = U::validate_unsigned(&self.function)?;
+			let unsigned_validation = U::validate_unsigned(source, &self.function)?;
 			Ok(valid.combine_with(unsigned_validation))
 		}
 	}
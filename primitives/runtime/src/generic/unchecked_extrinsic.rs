@@ -143,6 +143,22 @@ where
 			},
 		})
 	}
+
+	fn check_without_signature(
+		self,
+		lookup: &Lookup,
+	) -> Result<Self::Checked, TransactionValidityError> {
+		Ok(match self.signature {
+			Some((signed, _, extra)) => CheckedExtrinsic {
+				signed: Some((lookup.lookup(signed)?, extra)),
+				function: self.function,
+			},
+			None => CheckedExtrinsic {
+				signed: None,
+				function: self.function,
+			},
+		})
+	}
 }
 
 impl<Address, Call, Signature, Extra> ExtrinsicMetadata
@@ -420,6 +436,20 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn badly_signed_check_without_signature_should_work() {
+		let ux = Ex::new_signed(
+			vec![0u8; 0],
+			TEST_ACCOUNT,
+			TestSig(TEST_ACCOUNT, vec![0u8; 0]),
+			TestExtra,
+		);
+		assert_eq!(
+			<Ex as Checkable<TestContext>>::check_without_signature(ux, &Default::default()),
+			Ok(CEx { signed: Some((TEST_ACCOUNT, TestExtra)), function: vec![0u8; 0] }),
+		);
+	}
+
 	#[test]
 	fn signed_check_should_work() {
 		let ux = Ex::new_signed(
@@ -685,12 +685,34 @@ pub fn print(print: impl traits::Printable) {
 	print.print();
 }
 
+/// Derive the account id of a multi-signature account from the sorted list of its signatories
+/// and the number of them that are required, as the `utility` module does.
+///
+/// NOTE: `signatories` must be sorted. If it is not, then you'll get the wrong answer.
+pub fn multi_account_id<AccountId: Encode + Decode + Default>(
+	signatories: &[AccountId],
+	threshold: u16,
+) -> AccountId {
+	let entropy = (b"modlpy/utilisuba", signatories, threshold)
+		.using_encoded(sp_io::hashing::blake2_256);
+	AccountId::decode(&mut &entropy[..]).unwrap_or_default()
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
 	use codec::{Encode, Decode};
 	use sp_core::crypto::Pair;
 
+	#[test]
+	fn multi_account_id_depends_on_the_signatories_and_the_threshold() {
+		let id = multi_account_id::<AccountId32>(&[[1; 32].into(), [2; 32].into()], 2);
+		assert_ne!(id, AccountId32::default());
+		assert_eq!(id, multi_account_id(&[[1; 32].into(), [2; 32].into()], 2));
+		assert_ne!(id, multi_account_id(&[[1; 32].into(), [2; 32].into()], 1));
+		assert_ne!(id, multi_account_id(&[[1; 32].into(), [3; 32].into()], 2));
+	}
+
 	#[test]
 	fn opaque_extrinsic_serialization() {
 		let ex = super::OpaqueExtrinsic(vec![1, 2, 3, 4]);
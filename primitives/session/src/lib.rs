@@ -26,6 +26,7 @@ use sp_runtime::{generic::BlockId, traits::Block as BlockT};
 use sp_api::ProvideRuntimeApi;
 
 use sp_core::crypto::KeyTypeId;
+use sp_staking::SessionIndex;
 
 sp_api::decl_runtime_apis! {
 	/// Session keys runtime api.
@@ -44,6 +45,49 @@ sp_api::decl_runtime_apis! {
 		/// Returns the list of public raw public keys + key type.
 		fn decode_session_keys(encoded: Vec<u8>) -> Option<Vec<(Vec<u8>, KeyTypeId)>>;
 	}
+
+	/// Historical sessions runtime api.
+	pub trait HistoricalSessionApi {
+		/// Generate a proof of the ownership of the given key in the given session, as long as
+		/// the session is still stored by the runtime.
+		///
+		/// The proof may be generated from data stored in the offchain database by the offchain
+		/// workers, so it may not be available until they have run for the session.
+		///
+		/// Returns the SCALE encoded proof.
+		fn generate_key_ownership_proof(
+			session: SessionIndex,
+			key_type: KeyTypeId,
+			key: Vec<u8>,
+		) -> Option<Vec<u8>>;
+	}
+}
+
+/// Number of validators in a given session.
+pub type ValidatorCount = u32;
+
+/// Something that knows which session it refers to, e.g. a proof of key ownership.
+pub trait GetSessionNumber {
+	/// The session this refers to.
+	fn session(&self) -> SessionIndex;
+}
+
+/// Something that knows how many validators were in the session it refers to.
+pub trait GetValidatorCount {
+	/// The number of validators in the session this refers to.
+	fn validator_count(&self) -> ValidatorCount;
+}
+
+impl GetSessionNumber for sp_core::Void {
+	fn session(&self) -> SessionIndex {
+		match *self {}
+	}
+}
+
+impl GetValidatorCount for sp_core::Void {
+	fn validator_count(&self) -> ValidatorCount {
+		match *self {}
+	}
 }
 
 /// Generate the initial session keys with the given seeds, at the given block and store them in
@@ -161,6 +161,12 @@ where
 			HexDisplay::from(&key),
 			result.as_ref().map(HexDisplay::from)
 		);
+		tracing::trace!(target: "state",
+			method = "Get",
+			ext_id = self.id,
+			key = %HexDisplay::from(&key),
+			result = ?result.as_ref().map(HexDisplay::from)
+		);
 		result
 	}
 
@@ -319,6 +325,12 @@ where
 			HexDisplay::from(&key),
 			value.as_ref().map(HexDisplay::from)
 		);
+		tracing::trace!(target: "state",
+			method = "Put",
+			ext_id = self.id,
+			key = %HexDisplay::from(&key),
+			value = ?value.as_ref().map(HexDisplay::from)
+		);
 		let _guard = sp_panic_handler::AbortGuard::force_abort();
 		if is_child_storage_key(&key) {
 			warn!(target: "trie", "Refuse to directly set child storage key");
@@ -371,6 +383,11 @@ where
 			self.id,
 			HexDisplay::from(&prefix),
 		);
+		tracing::trace!(target: "state",
+			method = "ClearPrefix",
+			ext_id = self.id,
+			key = %HexDisplay::from(&prefix)
+		);
 		let _guard = sp_panic_handler::AbortGuard::force_abort();
 		if is_child_storage_key(prefix) {
 			warn!(target: "trie", "Refuse to directly clear prefix that is part of child storage key");
@@ -587,6 +604,7 @@ mod tests {
 			].into_iter().collect(),
 			committed: Default::default(),
 			collect_extrinsics: true,
+			committed_keys: None,
 		}
 	}
 
@@ -42,7 +42,9 @@ mod trie_backend;
 mod trie_backend_essence;
 mod stats;
 
-pub use sp_trie::{trie_types::{Layout, TrieDBMut}, StorageProof, TrieMut, DBValue, MemoryDB};
+pub use sp_trie::{
+	trie_types::{Layout, TrieDBMut}, StorageProof, CompactProof, TrieMut, DBValue, MemoryDB,
+};
 pub use testing::TestExternalities;
 pub use basic::BasicExternalities;
 pub use ext::Ext;
@@ -67,7 +69,8 @@ pub use overlayed_changes::{
 	StorageCollection, ChildStorageCollection,
 };
 pub use proving_backend::{
-	create_proof_check_backend, ProofRecorder, ProvingBackend, ProvingBackendRecorder,
+	create_proof_check_backend, create_compact_proof_check_backend, ProofRecorder, ProvingBackend,
+	ProvingBackendRecorder,
 };
 pub use trie_backend_essence::{TrieBackendStorage, Storage};
 pub use trie_backend::TrieBackend;
@@ -612,6 +615,91 @@ where
 	prove_child_read_on_trie_backend(trie_backend, storage_key, child_info, keys)
 }
 
+/// Generate storage read proof, giving up as soon as it exceeds `size_limit` bytes.
+///
+/// Returns `None` when the proof of the keys read so far is larger than the limit.
+pub fn prove_read_with_size_limit<B, H, I>(
+	mut backend: B,
+	keys: I,
+	size_limit: usize,
+) -> Result<Option<StorageProof>, Box<dyn Error>>
+where
+	B: Backend<H>,
+	H: Hasher,
+	H::Out: Ord + Codec,
+	I: IntoIterator,
+	I::Item: AsRef<[u8]>,
+{
+	let trie_backend = backend.as_trie_backend()
+		.ok_or_else(|| Box::new(ExecutionError::UnableToGenerateProof) as Box<dyn Error>)?;
+	let proving_backend = proving_backend::ProvingBackend::<_, H>::new(trie_backend);
+	for key in keys.into_iter() {
+		proving_backend
+			.storage(key.as_ref())
+			.map_err(|e| Box::new(e) as Box<dyn Error>)?;
+		if proving_backend.estimate_encoded_size() > size_limit {
+			return Ok(None);
+		}
+	}
+	Ok(Some(proving_backend.extract_proof()))
+}
+
+/// Generate child storage read proof, giving up as soon as it exceeds `size_limit` bytes.
+///
+/// Returns `None` when the proof of the keys read so far is larger than the limit.
+pub fn prove_child_read_with_size_limit<B, H, I>(
+	mut backend: B,
+	storage_key: &[u8],
+	child_info: ChildInfo,
+	keys: I,
+	size_limit: usize,
+) -> Result<Option<StorageProof>, Box<dyn Error>>
+where
+	B: Backend<H>,
+	H: Hasher,
+	H::Out: Ord + Codec,
+	I: IntoIterator,
+	I::Item: AsRef<[u8]>,
+{
+	let trie_backend = backend.as_trie_backend()
+		.ok_or_else(|| Box::new(ExecutionError::UnableToGenerateProof) as Box<dyn Error>)?;
+	let proving_backend = proving_backend::ProvingBackend::<_, H>::new(trie_backend);
+	for key in keys.into_iter() {
+		proving_backend
+			.child_storage(storage_key, child_info.clone(), key.as_ref())
+			.map_err(|e| Box::new(e) as Box<dyn Error>)?;
+		if proving_backend.estimate_encoded_size() > size_limit {
+			return Ok(None);
+		}
+	}
+	Ok(Some(proving_backend.extract_proof()))
+}
+
+/// Generate storage read proof in the compact form.
+///
+/// The proof is checked with `read_compact_proof_check`.
+pub fn prove_read_compact<B, H, I>(
+	mut backend: B,
+	keys: I,
+) -> Result<CompactProof, Box<dyn Error>>
+where
+	B: Backend<H>,
+	H: Hasher,
+	H::Out: Ord + Codec,
+	I: IntoIterator,
+	I::Item: AsRef<[u8]>,
+{
+	let trie_backend = backend.as_trie_backend()
+		.ok_or_else(|| Box::new(ExecutionError::UnableToGenerateProof) as Box<dyn Error>)?;
+	let proving_backend = proving_backend::ProvingBackend::<_, H>::new(trie_backend);
+	for key in keys.into_iter() {
+		proving_backend
+			.storage(key.as_ref())
+			.map_err(|e| Box::new(e) as Box<dyn Error>)?;
+	}
+	proving_backend.extract_compact_proof()
+}
+
 /// Generate storage read proof on pre-created trie backend.
 pub fn prove_read_on_trie_backend<S, H, I>(
 	trie_backend: &TrieBackend<S, H>,
@@ -633,6 +721,49 @@ where
 	Ok(proving_backend.extract_proof())
 }
 
+/// Generate a size-bounded storage read proof of the keys starting with `prefix`, in
+/// lexicographic order, along with their values.
+///
+/// The keys are proven from the one following `start_at`, when resuming a previous range, until
+/// the proof reaches `size_limit` bytes, one key being proven at least. Returns the proof and the
+/// number of keys it covers, both being checked with `read_range_proof_check`.
+pub fn prove_range_read_with_size<B, H>(
+	mut backend: B,
+	prefix: Option<&[u8]>,
+	size_limit: usize,
+	start_at: Option<&[u8]>,
+) -> Result<(StorageProof, u32), Box<dyn Error>>
+where
+	B: Backend<H>,
+	H: Hasher,
+	H::Out: Ord + Codec,
+{
+	let trie_backend = backend.as_trie_backend()
+		.ok_or_else(|| Box::new(ExecutionError::UnableToGenerateProof) as Box<dyn Error>)?;
+	prove_range_read_with_size_on_trie_backend(trie_backend, prefix, size_limit, start_at)
+}
+
+/// Generate a size-bounded storage read proof of a key range on pre-created trie backend.
+pub fn prove_range_read_with_size_on_trie_backend<S, H>(
+	trie_backend: &TrieBackend<S, H>,
+	prefix: Option<&[u8]>,
+	size_limit: usize,
+	start_at: Option<&[u8]>,
+) -> Result<(StorageProof, u32), Box<dyn Error>>
+where
+	S: trie_backend_essence::TrieBackendStorage<H>,
+	H: Hasher,
+	H::Out: Ord + Codec,
+{
+	let proving_backend = proving_backend::ProvingBackend::<_, H>::new(trie_backend);
+	let mut count = 0;
+	for_range_key_values(&proving_backend, prefix.unwrap_or(&[]), start_at, |_, _| {
+		count += 1;
+		proving_backend.estimate_encoded_size() < size_limit
+	})?;
+	Ok((proving_backend.extract_proof(), count))
+}
+
 /// Generate storage read proof on pre-created trie backend.
 pub fn prove_child_read_on_trie_backend<S, H, I>(
 	trie_backend: &TrieBackend<S, H>,
@@ -677,6 +808,112 @@ where
 	Ok(result)
 }
 
+/// Check storage read proof of a key range, generated by `prove_range_read_with_size` call.
+///
+/// Returns the `count` keys of the range along with their values, and whether they are the last
+/// keys starting with `prefix`. Otherwise, the range is to be resumed from the last key.
+pub fn read_range_proof_check<H>(
+	root: H::Out,
+	proof: StorageProof,
+	prefix: Option<&[u8]>,
+	count: u32,
+	start_at: Option<&[u8]>,
+) -> Result<(Vec<(Vec<u8>, Vec<u8>)>, bool), Box<dyn Error>>
+where
+	H: Hasher,
+	H::Out: Ord + Codec,
+{
+	let proving_backend = create_proof_check_backend::<H>(root, proof)?;
+	let mut result = Vec::new();
+	let completed = for_range_key_values(
+		&proving_backend,
+		prefix.unwrap_or(&[]),
+		start_at,
+		|key, value| {
+			if result.len() == count as usize {
+				return false;
+			}
+			result.push((key.to_vec(), value));
+			true
+		},
+	);
+
+	match completed {
+		Ok(completed) if result.len() == count as usize => Ok((result, completed)),
+		// the proof doesn't have to show what follows the range it covers.
+		Err(_) if result.len() == count as usize => Ok((result, false)),
+		Ok(_) => Err(Box::new(ExecutionError::InvalidProof)),
+		Err(e) => Err(e),
+	}
+}
+
+/// Call `f` with the keys starting with `prefix` and their values in lexicographic order, from
+/// the key following `start_at` if any, until it returns `false`.
+///
+/// Returns whether all the keys have been iterated.
+fn for_range_key_values<B, H, F>(
+	backend: &B,
+	prefix: &[u8],
+	start_at: Option<&[u8]>,
+	mut f: F,
+) -> Result<bool, Box<dyn Error>>
+where
+	B: Backend<H>,
+	H: Hasher,
+	F: FnMut(&[u8], Vec<u8>) -> bool,
+{
+	let mut key = match start_at {
+		Some(start_at) => start_at.to_vec(),
+		None => {
+			// the prefix itself is the first key of the range.
+			let value = backend.storage(prefix).map_err(|e| Box::new(e) as Box<dyn Error>)?;
+			if let Some(value) = value {
+				if !f(prefix, value) {
+					return Ok(false);
+				}
+			}
+			prefix.to_vec()
+		},
+	};
+
+	loop {
+		let next_key = backend.next_storage_key(&key)
+			.map_err(|e| Box::new(e) as Box<dyn Error>)?;
+		key = match next_key {
+			Some(next_key) if next_key.starts_with(prefix) => next_key,
+			_ => return Ok(true),
+		};
+
+		let value = backend.storage(&key).map_err(|e| Box::new(e) as Box<dyn Error>)?;
+		if let Some(value) = value {
+			if !f(&key, value) {
+				return Ok(false);
+			}
+		}
+	}
+}
+
+/// Check storage read proof in the compact form, generated by `prove_read_compact` call.
+pub fn read_compact_proof_check<H, I>(
+	root: H::Out,
+	proof: CompactProof,
+	keys: I,
+) -> Result<HashMap<Vec<u8>, Option<Vec<u8>>>, Box<dyn Error>>
+where
+	H: Hasher,
+	H::Out: Ord + Codec,
+	I: IntoIterator,
+	I::Item: AsRef<[u8]>,
+{
+	let proving_backend = create_compact_proof_check_backend::<H>(root, proof)?;
+	let mut result = HashMap::new();
+	for key in keys.into_iter() {
+		let value = read_proof_check_on_proving_backend(&proving_backend, key.as_ref())?;
+		result.insert(key.as_ref().to_vec(), value);
+	}
+	Ok(result)
+}
+
 /// Check child storage read proof, generated by `prove_child_read` call.
 pub fn read_child_proof_check<H, I>(
 	root: H::Out,
@@ -1033,6 +1270,67 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn prove_range_read_with_size_and_proof_check_works() {
+		let remote_backend = trie_backend::tests::test_trie();
+		let remote_root = remote_backend.storage_root(::std::iter::empty()).0;
+		let mut expected = remote_backend.pairs();
+		expected.sort();
+
+		// prove all the keys a few at a time, resuming from the last one
+		let mut result = Vec::new();
+		let mut completed = false;
+		while !completed {
+			let start_at = result.last().map(|(key, _): &(Vec<u8>, Vec<u8>)| key.clone());
+			let (proof, count) = prove_range_read_with_size_on_trie_backend(
+				&remote_backend,
+				None,
+				1000,
+				start_at.as_ref().map(Vec::as_slice),
+			).unwrap();
+			assert!(count > 0 && (count as usize) < expected.len());
+
+			let (pairs, range_completed) = read_range_proof_check::<BlakeTwo256>(
+				remote_root,
+				proof,
+				None,
+				count,
+				start_at.as_ref().map(Vec::as_slice),
+			).unwrap();
+			assert_eq!(pairs.len(), count as usize);
+			result.extend(pairs);
+			completed = range_completed;
+		}
+		assert_eq!(result, expected);
+
+		// prove the keys starting with a prefix at once
+		let (proof, count) = prove_range_read_with_size_on_trie_backend(
+			&remote_backend,
+			Some(b"value"),
+			usize::max_value(),
+			None,
+		).unwrap();
+		assert_eq!(count, 2);
+		let (pairs, completed) = read_range_proof_check::<BlakeTwo256>(
+			remote_root,
+			proof.clone(),
+			Some(b"value"),
+			count,
+			None,
+		).unwrap();
+		assert_eq!(pairs, vec![(b"value1".to_vec(), vec![42]), (b"value2".to_vec(), vec![24])]);
+		assert!(completed);
+
+		// and check that the proof doesn't cover more keys than proven
+		assert!(read_range_proof_check::<BlakeTwo256>(
+			remote_root,
+			proof,
+			Some(b"value"),
+			count + 1,
+			None,
+		).is_err());
+	}
+
 	#[test]
 	fn prove_read_and_proof_check_works() {
 		// fetch read proof from 'remote' full node
@@ -1087,6 +1385,72 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn prove_read_with_size_limit_gives_up_on_large_proofs() {
+		let remote_backend = trie_backend::tests::test_trie();
+		let remote_root = remote_backend.storage_root(::std::iter::empty()).0;
+		let proof = prove_read(remote_backend, &[b"value2"]).unwrap();
+		let size: usize = proof.iter_nodes().map(|n| n.len()).sum();
+
+		let remote_backend = trie_backend::tests::test_trie();
+		let proof = prove_read_with_size_limit(remote_backend, &[b"value2"], size)
+			.unwrap()
+			.unwrap();
+		assert_eq!(
+			read_proof_check::<BlakeTwo256, _>(remote_root, proof, &[b"value2"])
+				.unwrap()
+				.into_iter()
+				.collect::<Vec<_>>(),
+			vec![(b"value2".to_vec(), Some(vec![24]))],
+		);
+		let remote_backend = trie_backend::tests::test_trie();
+		assert_eq!(
+			prove_read_with_size_limit(remote_backend, &[b"value2"], size - 1).unwrap(),
+			None,
+		);
+		let remote_backend = trie_backend::tests::test_trie();
+		assert_eq!(
+			prove_child_read_with_size_limit(
+				remote_backend,
+				b":child_storage:default:sub1",
+				CHILD_INFO_1,
+				&[b"value3"],
+				0,
+			).unwrap(),
+			None,
+		);
+	}
+
+	#[test]
+	fn compact_read_proof_is_smaller_and_checks() {
+		let remote_backend = trie_backend::tests::test_trie();
+		let remote_root = remote_backend.storage_root(::std::iter::empty()).0;
+		let proof = prove_read(remote_backend, &[b"value2"]).unwrap();
+		let remote_backend = trie_backend::tests::test_trie();
+		let compact_proof = prove_read_compact(remote_backend, &[b"value2"]).unwrap();
+		assert!(compact_proof.encoded_size() < proof.encoded_size());
+
+		let local_result = read_compact_proof_check::<BlakeTwo256, _>(
+			remote_root,
+			compact_proof.clone(),
+			&[b"value2"],
+		).unwrap();
+		assert_eq!(
+			local_result.into_iter().collect::<Vec<_>>(),
+			vec![(b"value2".to_vec(), Some(vec![24]))],
+		);
+		assert!(read_compact_proof_check::<BlakeTwo256, _>(
+			remote_root,
+			compact_proof.clone(),
+			&[&[0xff]],
+		).is_err());
+		assert!(read_compact_proof_check::<BlakeTwo256, _>(
+			Default::default(),
+			compact_proof,
+			&[b"value2"],
+		).is_err());
+	}
+
 	#[test]
 	fn child_storage_uuid() {
 		const CHILD_INFO_1: ChildInfo<'static> = ChildInfo::new_default(b"unique_id_1");
@@ -57,6 +57,8 @@ pub struct OverlayedChanges {
 	pub(crate) committed: OverlayedChangeSet,
 	/// True if extrinsics stats must be collected.
 	pub(crate) collect_extrinsics: bool,
+	/// Keys of the top trie committed since they were last taken, if they are collected.
+	pub(crate) committed_keys: Option<Vec<StorageKey>>,
 }
 
 /// The storage value, used inside OverlayedChanges.
@@ -200,6 +202,22 @@ impl OverlayedChanges {
 		self.collect_extrinsics = collect_extrinsics;
 	}
 
+	/// Ask to collect/not to collect the keys of the top trie that get committed.
+	///
+	/// The collected keys are returned by `take_committed_keys`.
+	pub fn set_collect_committed_keys(&mut self, collect_committed_keys: bool) {
+		self.committed_keys = if collect_committed_keys { Some(Vec::new()) } else { None };
+	}
+
+	/// Take the keys of the top trie committed since the previous call.
+	///
+	/// Returns nothing if the keys aren't collected.
+	pub fn take_committed_keys(&mut self) -> Vec<StorageKey> {
+		self.committed_keys.as_mut()
+			.map(|keys| mem::replace(keys, Vec::new()))
+			.unwrap_or_default()
+	}
+
 	/// Returns a double-Option: None if the key is unknown (i.e. and the query should be referred
 	/// to the backend); Some(None) if the key has been deleted. Some(Some(...)) for a key whose
 	/// value has been set.
@@ -394,6 +412,10 @@ impl OverlayedChanges {
 
 	/// Commit prospective changes to state.
 	pub fn commit_prospective(&mut self) {
+		if let Some(ref mut committed_keys) = self.committed_keys {
+			committed_keys.extend(self.prospective.top.keys().cloned());
+		}
+
 		if self.committed.is_empty() {
 			mem::swap(&mut self.prospective, &mut self.committed);
 		} else {
@@ -709,6 +731,23 @@ mod tests {
 		assert!(overlayed.storage(&key).unwrap().is_none());
 	}
 
+	#[test]
+	fn committed_keys_are_collected_on_request() {
+		let mut overlayed = OverlayedChanges::default();
+		overlayed.set_storage(vec![1], Some(vec![1]));
+		overlayed.commit_prospective();
+		assert!(overlayed.take_committed_keys().is_empty());
+
+		overlayed.set_collect_committed_keys(true);
+		overlayed.set_storage(vec![2], Some(vec![2]));
+		overlayed.set_storage(vec![3], None);
+		overlayed.commit_prospective();
+		overlayed.set_storage(vec![4], Some(vec![4]));
+		overlayed.discard_prospective();
+		assert_eq!(overlayed.take_committed_keys(), vec![vec![2], vec![3]]);
+		assert!(overlayed.take_committed_keys().is_empty());
+	}
+
 	#[test]
 	fn overlayed_storage_root_works() {
 		let initial: BTreeMap<_, _> = vec![
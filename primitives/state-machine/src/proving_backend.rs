@@ -23,7 +23,7 @@ use log::debug;
 use hash_db::{Hasher, HashDB, EMPTY_PREFIX, Prefix};
 use sp_trie::{
 	MemoryDB, default_child_trie_root, read_trie_value_with, read_child_trie_value_with,
-	record_all_keys, StorageProof,
+	record_all_keys, StorageProof, CompactProof,
 };
 pub use sp_trie::Recorder;
 pub use sp_trie::trie_types::{Layout, TrieError};
@@ -160,6 +160,23 @@ impl<'a, S: 'a + TrieBackendStorage<H>, H: 'a + Hasher> ProvingBackend<'a, S, H>
 			.collect();
 		StorageProof::new(trie_nodes)
 	}
+
+	/// Estimate the size of the gathered proof, as the size of its trie nodes in bytes.
+	pub fn estimate_encoded_size(&self) -> usize {
+		self.0.essence().backend_storage().proof_recorder
+			.read()
+			.values()
+			.filter_map(|v| v.as_ref().map(|v| v.len()))
+			.sum()
+	}
+
+	/// Extracting the gathered proof, in the compact form.
+	pub fn extract_compact_proof(&self) -> Result<CompactProof, Box<dyn Error>> {
+		let root = self.0.essence().root().clone();
+		self.extract_proof()
+			.into_compact_proof::<H>(root)
+			.map_err(|e| Box::new(e) as Box<dyn Error>)
+	}
 }
 
 impl<'a, S: 'a + TrieBackendStorage<H>, H: 'a + Hasher> TrieBackendStorage<H>
@@ -303,6 +320,20 @@ where
 	}
 }
 
+/// Create a proof check backend from a proof in the compact form.
+pub fn create_compact_proof_check_backend<H>(
+	root: H::Out,
+	proof: CompactProof,
+) -> Result<TrieBackend<MemoryDB<H>, H>, Box<dyn Error>>
+where
+	H: Hasher,
+	H::Out: Codec,
+{
+	let db = proof.to_memory_db::<H>(&root)
+		.map_err(|e| Box::new(e) as Box<dyn Error>)?;
+	Ok(TrieBackend::new(db, root))
+}
+
 #[cfg(test)]
 mod tests {
 	use crate::InMemoryBackend;
@@ -0,0 +1,47 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Primitives for the tracing of the runtime.
+//!
+//! The spans of the runtime executed in Wasm are entered and exited through the `wasm_tracing`
+//! host functions of `sp-io`, and created on the host by the [`proxy`]. They are `tracing` spans
+//! whose target and name are [`WASM_TRACE_IDENTIFIER`], the actual target and name of the span
+//! being recorded in its [`WASM_TARGET_KEY`] and [`WASM_NAME_KEY`] values. The number of the
+//! block and the index of the extrinsic being executed are recorded in its [`BLOCK_NUMBER_KEY`]
+//! and [`EXTRINSIC_INDEX_KEY`] values.
+//!
+//! The subscribers of the node, e.g. the one set up with `--tracing-targets`, are expected to
+//! substitute the actual target and name of these spans to the recorded ones.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "std")]
+pub mod proxy;
+
+/// Target and name of the spans created on the host for the spans of the runtime.
+pub const WASM_TRACE_IDENTIFIER: &str = "wasm_tracing";
+
+/// Key of the value holding the actual target of a span of the runtime.
+pub const WASM_TARGET_KEY: &str = "wasm_target";
+
+/// Key of the value holding the actual name of a span of the runtime.
+pub const WASM_NAME_KEY: &str = "wasm_name";
+
+/// Key of the value holding the number of the block executed when the span was entered.
+pub const BLOCK_NUMBER_KEY: &str = "block_number";
+
+/// Key of the value holding the index of the extrinsic applied when the span was entered.
+pub const EXTRINSIC_INDEX_KEY: &str = "extrinsic_index";
@@ -0,0 +1,132 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Creation on the host of the spans entered by the runtime executed in Wasm.
+
+use std::cell::RefCell;
+use tracing::{dispatcher, span::Id, Level, Span};
+
+use crate::WASM_TRACE_IDENTIFIER;
+
+thread_local! {
+	static PROXY: RefCell<TracingProxy> = RefCell::new(TracingProxy::default());
+}
+
+/// The spans of the runtime entered on the current thread, and the context they are entered in.
+#[derive(Default)]
+struct TracingProxy {
+	/// The entered spans with their ids, from the outermost one.
+	spans: Vec<(u64, Span)>,
+	/// The number of the block being executed, and the index of the extrinsic being applied.
+	context: Option<(u64, Option<u32>)>,
+}
+
+/// Set the number of the block executed by the runtime and the index of the extrinsic it applies,
+/// which the spans entered afterwards on the current thread are tagged with.
+pub fn set_context(block_number: u64, extrinsic_index: Option<u32>) {
+	PROXY.with(|proxy| proxy.borrow_mut().context = Some((block_number, extrinsic_index)));
+}
+
+/// Whether a subscriber may be interested in the spans of the runtime, which doesn't need to be
+/// asked for each span when none was ever set.
+pub fn enabled() -> bool {
+	dispatcher::has_been_set()
+}
+
+/// The number of spans of the runtime currently entered on the current thread.
+pub fn entered_spans() -> usize {
+	PROXY.with(|proxy| proxy.borrow().spans.len())
+}
+
+/// Exit and close the spans entered on the current thread after the first `count` ones.
+///
+/// Used once a call into the runtime is over, as the spans of a runtime that panicked or trapped
+/// are never exited by it.
+pub fn exit_spans_after(count: usize) {
+	PROXY.with(|proxy| {
+		let mut proxy = proxy.borrow_mut();
+		if count < proxy.spans.len() {
+			for (span_id, span) in proxy.spans.drain(count..).rev() {
+				dispatcher::get_default(|dispatch| dispatch.exit(&Id::from_u64(span_id)));
+				drop(span);
+			}
+		}
+	})
+}
+
+/// Create a span with the given target and name and enter it, returning its id.
+///
+/// Returns `0` when the subscriber of the current thread isn't interested in the span.
+pub fn enter_span(target: &str, name: &str) -> u64 {
+	PROXY.with(|proxy| {
+		let mut proxy = proxy.borrow_mut();
+		let span = match proxy.context {
+			None => tracing::span!(
+				target: WASM_TRACE_IDENTIFIER,
+				Level::INFO,
+				WASM_TRACE_IDENTIFIER,
+				wasm_target = target,
+				wasm_name = name
+			),
+			Some((block_number, None)) => tracing::span!(
+				target: WASM_TRACE_IDENTIFIER,
+				Level::INFO,
+				WASM_TRACE_IDENTIFIER,
+				wasm_target = target,
+				wasm_name = name,
+				block_number = block_number
+			),
+			Some((block_number, Some(extrinsic_index))) => tracing::span!(
+				target: WASM_TRACE_IDENTIFIER,
+				Level::INFO,
+				WASM_TRACE_IDENTIFIER,
+				wasm_target = target,
+				wasm_name = name,
+				block_number = block_number,
+				extrinsic_index = extrinsic_index
+			),
+		};
+
+		match span.id() {
+			Some(id) => {
+				dispatcher::get_default(|dispatch| dispatch.enter(&id));
+				let id = id.into_u64();
+				proxy.spans.push((id, span));
+				id
+			},
+			None => 0,
+		}
+	})
+}
+
+/// Exit the span with the given id and close it.
+///
+/// The spans entered after it and not exited yet are exited and closed as well.
+pub fn exit_span(id: u64) {
+	if id == 0 {
+		return;
+	}
+
+	PROXY.with(|proxy| {
+		let mut proxy = proxy.borrow_mut();
+		if let Some(index) = proxy.spans.iter().rposition(|(span_id, _)| *span_id == id) {
+			for (span_id, span) in proxy.spans.drain(index..).rev() {
+				dispatcher::get_default(|dispatch| dispatch.exit(&Id::from_u64(span_id)));
+				drop(span);
+			}
+		}
+	})
+}
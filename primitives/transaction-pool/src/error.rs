@@ -63,6 +63,9 @@ pub enum Error {
 	/// The pool is not accepting future transactions.
 	#[display(fmt="The pool is not accepting future transactions")]
 	RejectedFutureTransaction,
+	/// The sender of the transaction already has too many transactions in the pool.
+	#[display(fmt="The sender has too many transactions in the pool")]
+	SenderLimitReached,
 }
 
 impl std::error::Error for Error {}
@@ -0,0 +1,307 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Storage proof primitives. Contains types and helpers to prove that the chunks of the stored
+//! transactions are still kept by the block authors.
+//!
+//! The data of a transaction is split in chunks of `CHUNK_SIZE` bytes, whose hashes are the
+//! leaves of a binary merkle tree. A proof is made of a chunk along with the hashes of its
+//! siblings on the path to the root of that tree.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::prelude::*;
+use codec::{Encode, Decode};
+use sp_inherents::{InherentIdentifier, InherentData, IsFatalError};
+use sp_io::hashing::blake2_256;
+use sp_runtime::RuntimeDebug;
+
+#[cfg(feature = "std")]
+use sp_inherents::ProvideInherentData;
+
+/// The identifier for the storage proof inherent.
+pub const INHERENT_IDENTIFIER: InherentIdentifier = *b"tx_proof";
+/// The size of the chunks the data of the transactions is split in.
+pub const CHUNK_SIZE: usize = 256;
+/// The maximum number of hashes in a proof, which is the depth of the tree of `u32::max_value()`
+/// chunks.
+pub const MAX_PROOF_DEPTH: usize = 32;
+
+/// The type of the inherent.
+pub type InherentType = TransactionStorageProof;
+
+/// The hash of a chunk, or of a node of the tree of the chunks of a transaction.
+pub type ChunkHash = [u8; 32];
+
+/// Errors that can occur while checking the storage proof inherent.
+#[derive(Encode, PartialEq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Decode))]
+pub enum InherentError {
+	/// The proof doesn't match the chunk root of the stored transaction.
+	InvalidProof,
+	/// The proof has a chunk larger than `CHUNK_SIZE`, or more than `MAX_PROOF_DEPTH` hashes.
+	ProofTooLarge,
+}
+
+impl IsFatalError for InherentError {
+	fn is_fatal_error(&self) -> bool {
+		true
+	}
+}
+
+/// The information kept in the state about a stored transaction.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, Default, RuntimeDebug)]
+pub struct TransactionInfo {
+	/// The root of the tree of the chunks of the transaction data.
+	pub chunk_root: ChunkHash,
+	/// The hash of the transaction data.
+	pub content_hash: ChunkHash,
+	/// The size of the transaction data, in bytes.
+	pub size: u32,
+	/// The number of chunks of the transactions stored in the block so far, this one included.
+	pub block_chunks: u32,
+}
+
+/// A proof that a chunk of a stored transaction is kept.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct TransactionStorageProof {
+	/// The data of the chunk.
+	pub chunk: Vec<u8>,
+	/// The hashes of the siblings of the chunk on its path to the root, starting from the leaves.
+	pub proof: Vec<ChunkHash>,
+}
+
+/// Auxiliary trait to extract the storage proof inherent data.
+pub trait TransactionStorageProofInherentData {
+	/// Get the storage proof inherent data, if any was provided.
+	fn storage_proof(&self) -> Result<Option<InherentType>, sp_inherents::Error>;
+}
+
+impl TransactionStorageProof {
+	/// Whether the proof is small enough to be checked: a chunk of at most `CHUNK_SIZE` bytes and
+	/// at most `MAX_PROOF_DEPTH` hashes.
+	pub fn is_bounded(&self) -> bool {
+		self.chunk.len() <= CHUNK_SIZE && self.proof.len() <= MAX_PROOF_DEPTH
+	}
+}
+
+impl TransactionStorageProofInherentData for InherentData {
+	fn storage_proof(&self) -> Result<Option<InherentType>, sp_inherents::Error> {
+		self.get_data(&INHERENT_IDENTIFIER)
+	}
+}
+
+/// The number of chunks `size` bytes of data are split in.
+pub fn num_chunks(size: u32) -> u32 {
+	(size + CHUNK_SIZE as u32 - 1) / CHUNK_SIZE as u32
+}
+
+/// Select the chunk to be proved out of `total_chunks`, using `random_hash` as the source of
+/// randomness.
+pub fn random_chunk(random_hash: &[u8], total_chunks: u32) -> u32 {
+	let hash = blake2_256(random_hash);
+	let mut buf = [0u8; 4];
+	buf.copy_from_slice(&hash[0..4]);
+	u32::from_le_bytes(buf) % total_chunks
+}
+
+fn hash_pair(left: &ChunkHash, right: &ChunkHash) -> ChunkHash {
+	let mut buf = [0u8; 64];
+	buf[..32].copy_from_slice(left);
+	buf[32..].copy_from_slice(right);
+	blake2_256(&buf)
+}
+
+/// The hashes of the chunks of `data`.
+fn chunk_hashes(data: &[u8]) -> Vec<ChunkHash> {
+	data.chunks(CHUNK_SIZE).map(blake2_256).collect()
+}
+
+/// The root of the tree of the chunks of `data`.
+///
+/// The nodes of each level of the tree are hashed by pairs, an odd node being moved as is to the
+/// next level.
+pub fn chunk_root(data: &[u8]) -> ChunkHash {
+	let mut level = chunk_hashes(data);
+	if level.is_empty() {
+		return blake2_256(&[]);
+	}
+	while level.len() > 1 {
+		level = level.chunks(2)
+			.map(|pair| if pair.len() == 2 { hash_pair(&pair[0], &pair[1]) } else { pair[0] })
+			.collect();
+	}
+	level[0]
+}
+
+/// Build the proof of the chunk `index` of `data`, or `None` if there is no such chunk.
+pub fn chunk_proof(data: &[u8], index: u32) -> Option<TransactionStorageProof> {
+	let chunk = data.chunks(CHUNK_SIZE).nth(index as usize)?.to_vec();
+	let mut level = chunk_hashes(data);
+	let mut index = index as usize;
+	let mut proof = Vec::new();
+	while level.len() > 1 {
+		if let Some(sibling) = level.get(index ^ 1) {
+			proof.push(*sibling);
+		}
+		level = level.chunks(2)
+			.map(|pair| if pair.len() == 2 { hash_pair(&pair[0], &pair[1]) } else { pair[0] })
+			.collect();
+		index /= 2;
+	}
+	Some(TransactionStorageProof { chunk, proof })
+}
+
+/// Check that `proof` proves the chunk `index` of the `total_chunks` chunks of the data whose
+/// chunk root is `root`.
+pub fn verify_chunk_proof(
+	root: &ChunkHash,
+	index: u32,
+	total_chunks: u32,
+	proof: &TransactionStorageProof,
+) -> bool {
+	if index >= total_chunks || !proof.is_bounded() {
+		return false;
+	}
+	let mut hash = blake2_256(&proof.chunk);
+	let mut index = index;
+	let mut len = total_chunks;
+	let mut siblings = proof.proof.iter();
+	while len > 1 {
+		if index ^ 1 < len {
+			let sibling = match siblings.next() {
+				Some(sibling) => sibling,
+				None => return false,
+			};
+			hash = if index % 2 == 0 { hash_pair(&hash, sibling) } else { hash_pair(sibling, &hash) };
+		}
+		index /= 2;
+		len = (len + 1) / 2;
+	}
+	siblings.next().is_none() && &hash == root
+}
+
+/// Build the proof expected in the block whose random seed is `random_hash`, out of the data
+/// of the transactions stored in the block to be proved, in order.
+///
+/// Returns `None` if there is nothing to prove.
+pub fn build_proof(random_hash: &[u8], transactions: &[Vec<u8>]) -> Option<TransactionStorageProof> {
+	let total_chunks: u32 = transactions.iter().map(|data| num_chunks(data.len() as u32)).sum();
+	if total_chunks == 0 {
+		return None;
+	}
+	let mut selected = random_chunk(random_hash, total_chunks);
+	for data in transactions {
+		let chunks = num_chunks(data.len() as u32);
+		if selected < chunks {
+			return chunk_proof(data, selected);
+		}
+		selected -= chunks;
+	}
+	None
+}
+
+/// Provider for the storage proof inherent data.
+///
+/// The proof is built for each block by `build_proof`, which returns `None` when there is nothing
+/// to prove.
+#[cfg(feature = "std")]
+pub struct InherentDataProvider<F> {
+	build_proof: F,
+}
+
+#[cfg(feature = "std")]
+impl<F> InherentDataProvider<F> {
+	pub fn new(build_proof: F) -> Self {
+		InherentDataProvider { build_proof }
+	}
+}
+
+#[cfg(feature = "std")]
+impl<F> ProvideInherentData for InherentDataProvider<F>
+	where F: Fn() -> Result<Option<TransactionStorageProof>, sp_inherents::Error>
+{
+	fn inherent_identifier(&self) -> &'static InherentIdentifier {
+		&INHERENT_IDENTIFIER
+	}
+
+	fn provide_inherent_data(
+		&self,
+		inherent_data: &mut InherentData,
+	) -> Result<(), sp_inherents::Error> {
+		match (self.build_proof)()? {
+			Some(proof) => inherent_data.put_data(INHERENT_IDENTIFIER, &proof),
+			None => Ok(()),
+		}
+	}
+
+	fn error_to_string(&self, error: &[u8]) -> Option<String> {
+		InherentError::decode(&mut &error[..]).map(|e| format!("{:?}", e)).ok()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn proofs_of_all_chunks_verify() {
+		for size in &[1, 255, 256, 257, 1000, 5 * 256, 7 * 256 + 3] {
+			let data: Vec<u8> = (0..*size).map(|i| i as u8).collect();
+			let root = chunk_root(&data);
+			let total = num_chunks(*size as u32);
+			for index in 0..total {
+				let proof = chunk_proof(&data, index).unwrap();
+				assert!(verify_chunk_proof(&root, index, total, &proof));
+			}
+			assert!(chunk_proof(&data, total).is_none());
+		}
+	}
+
+	#[test]
+	fn tampered_proofs_do_not_verify() {
+		let data = vec![42u8; 3 * CHUNK_SIZE];
+		let root = chunk_root(&data);
+		let mut proof = chunk_proof(&data, 1).unwrap();
+		proof.chunk[0] = 0;
+		assert!(!verify_chunk_proof(&root, 1, 3, &proof));
+
+		let mut proof = chunk_proof(&data, 1).unwrap();
+		proof.proof.push([0; 32]);
+		assert!(!verify_chunk_proof(&root, 1, 3, &proof));
+
+		let mut proof = chunk_proof(&data, 1).unwrap();
+		proof.proof.resize(MAX_PROOF_DEPTH + 1, [0; 32]);
+		assert!(!proof.is_bounded());
+		assert!(!verify_chunk_proof(&root, 1, 3, &proof));
+	}
+
+	#[test]
+	fn build_proof_selects_the_random_chunk() {
+		let transactions = vec![vec![1u8; 2 * CHUNK_SIZE], vec![2u8; 3 * CHUNK_SIZE]];
+		let random_hash = [7u8; 32];
+		let selected = random_chunk(&random_hash, 5);
+		let proof = build_proof(&random_hash, &transactions).unwrap();
+		let (data, index) = if selected < 2 {
+			(&transactions[0], selected)
+		} else {
+			(&transactions[1], selected - 2)
+		};
+		let total = num_chunks(data.len() as u32);
+		assert!(verify_chunk_proof(&chunk_root(data), index, total, &proof));
+		assert!(build_proof(&random_hash, &[]).is_none());
+	}
+}
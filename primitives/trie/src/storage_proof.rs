@@ -62,6 +62,16 @@ impl StorageProof {
 		self.into()
 	}
 
+	/// Encode the proof of the trie with the given `root` in the compact form.
+	pub fn into_compact_proof<H: Hasher>(
+		self,
+		root: H::Out,
+	) -> Result<CompactProof, crate::CompactProofError<crate::Layout<H>>> {
+		let db = self.into_memory_db::<H>();
+		crate::encode_compact::<crate::Layout<H>, _>(&db, &root)
+			.map(|encoded_nodes| CompactProof { encoded_nodes })
+	}
+
 	/// Merges multiple storage proofs covering potentially different sets of keys into one proof
 	/// covering all keys. The merged proof output may be smaller than the aggregate size of the input
 	/// proofs due to deduplication of trie nodes.
@@ -76,6 +86,48 @@ impl StorageProof {
 	}
 }
 
+/// A storage proof in the compact form, see `crate::encode_compact`.
+///
+/// It is usually smaller than the equivalent `StorageProof`, as the hashes of the nodes it
+/// contains are omitted from their parents.
+#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
+pub struct CompactProof {
+	encoded_nodes: Vec<Vec<u8>>,
+}
+
+impl CompactProof {
+	/// Returns the encoded nodes of the proof.
+	pub fn encoded_nodes(&self) -> &[Vec<u8>] {
+		&self.encoded_nodes
+	}
+
+	/// Decode the proof of the trie with the given `root` into a `MemoryDB`.
+	///
+	/// Fails if the proof isn't a proof of that trie.
+	pub fn to_memory_db<H: Hasher>(
+		&self,
+		root: &H::Out,
+	) -> Result<crate::MemoryDB<H>, crate::CompactProofError<crate::Layout<H>>> {
+		let mut db = crate::MemoryDB::default();
+		crate::decode_compact::<crate::Layout<H>, _>(&mut db, &self.encoded_nodes, root)?;
+		Ok(db)
+	}
+
+	/// Decode the proof of the trie with the given `root` into a regular `StorageProof`.
+	pub fn to_storage_proof<H: Hasher>(
+		&self,
+		root: &H::Out,
+	) -> Result<StorageProof, crate::CompactProofError<crate::Layout<H>>> {
+		let db = self.to_memory_db::<H>(root)?;
+		let trie_nodes = db.drain()
+			.into_iter()
+			.filter(|(_, (_, rc))| *rc > 0)
+			.map(|(_, (node, _))| node)
+			.collect();
+		Ok(StorageProof::new(trie_nodes))
+	}
+}
+
 /// An iterator over trie nodes constructed from a storage proof. The nodes are not guaranteed to
 /// be traversed in any particular order.
 pub struct StorageProofNodeIterator {
@@ -0,0 +1,157 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Compact encoding of the partial tries found in storage proofs.
+//!
+//! In the compact encoding, the hash of a child node is omitted from its parent whenever the
+//! child is part of the encoding, since the verifier can recompute it. The nodes of the top
+//! trie come first, followed by the nodes of each child trie whose root is found in the top
+//! trie, in the order of their storage keys.
+
+use sp_std::boxed::Box;
+use sp_std::vec::Vec;
+use hash_db::{HashDB, HashDBRef};
+use sp_core::storage::well_known_keys::CHILD_STORAGE_KEY_PREFIX;
+use trie_db::{Trie, TrieDB};
+use crate::{CompactProofError, TrieHash, TrieLayout, EMPTY_PREFIX};
+
+/// Error for the compact encoding and decoding of partial tries.
+#[derive(Debug)]
+pub enum Error<H, CodecError> {
+	/// The root of the decoded top trie isn't the expected one.
+	RootMismatch(H, H),
+	/// The proof contains nodes that aren't part of any trie.
+	ExtraneousNodes,
+	/// Error returned by the trie.
+	Trie(Box<trie_db::TrieError<H, CodecError>>),
+}
+
+impl<H, CodecError> From<Box<trie_db::TrieError<H, CodecError>>> for Error<H, CodecError> {
+	fn from(error: Box<trie_db::TrieError<H, CodecError>>) -> Self {
+		Error::Trie(error)
+	}
+}
+
+#[cfg(feature = "std")]
+impl<H: std::fmt::Debug, CodecError: std::fmt::Debug> std::fmt::Display for Error<H, CodecError> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Error::RootMismatch(expected, got) =>
+				write!(f, "Unexpected trie root: expected {:?}, got {:?}", expected, got),
+			Error::ExtraneousNodes => write!(f, "Extraneous nodes in the proof"),
+			Error::Trie(error) => write!(f, "Trie error: {:?}", error),
+		}
+	}
+}
+
+/// Encode the partial trie with the given `root` found in `db` in the compact form, along with
+/// the partial child tries whose nodes are in `db`.
+pub fn encode_compact<L, DB>(
+	db: &DB,
+	root: &TrieHash<L>,
+) -> Result<Vec<Vec<u8>>, CompactProofError<L>>
+	where
+		L: TrieLayout,
+		DB: HashDBRef<L::Hash, trie_db::DBValue>,
+{
+	let child_roots = child_roots::<L, _>(db, root)?;
+
+	let trie = TrieDB::<L>::new(db, root)?;
+	let mut encoded = trie_db::encode_compact::<L>(&trie)?;
+	for child_root in child_roots {
+		// only the child tries read by the proof have their root node in it.
+		if !db.contains(&child_root, EMPTY_PREFIX) {
+			continue;
+		}
+
+		let trie = TrieDB::<L>::new(db, &child_root)?;
+		encoded.extend(trie_db::encode_compact::<L>(&trie)?);
+	}
+
+	Ok(encoded)
+}
+
+/// Decode the compact encoding of a partial top trie and its child tries into `db`.
+///
+/// Returns an error if the root of the decoded top trie isn't `expected_root`, or if some of
+/// the encoded nodes aren't part of any of the decoded tries.
+pub fn decode_compact<L, DB>(
+	db: &mut DB,
+	encoded: &[Vec<u8>],
+	expected_root: &TrieHash<L>,
+) -> Result<(), CompactProofError<L>>
+	where
+		L: TrieLayout,
+		DB: HashDB<L::Hash, trie_db::DBValue> + HashDBRef<L::Hash, trie_db::DBValue>,
+{
+	let (root, mut used) = trie_db::decode_compact::<L, _, _>(db, encoded)?;
+	if root != *expected_root {
+		return Err(Error::RootMismatch(*expected_root, root));
+	}
+
+	let mut child_roots = child_roots::<L, _>(db, &root)?.into_iter();
+	while used < encoded.len() {
+		let (child_root, decoded) = trie_db::decode_compact::<L, _, _>(db, &encoded[used..])?;
+		// the child tries that weren't read by the proof are skipped by the encoding.
+		if !child_roots.any(|root| root == child_root) {
+			return Err(Error::ExtraneousNodes);
+		}
+		used += decoded;
+	}
+
+	Ok(())
+}
+
+/// The roots of the child tries of the partial trie with the given `root`, in the order of
+/// their storage keys.
+fn child_roots<L, DB>(
+	db: &DB,
+	root: &TrieHash<L>,
+) -> Result<Vec<TrieHash<L>>, CompactProofError<L>>
+	where
+		L: TrieLayout,
+		DB: HashDBRef<L::Hash, trie_db::DBValue>,
+{
+	let trie = TrieDB::<L>::new(db, root)?;
+	let mut iter = trie.iter()?;
+	let mut child_roots = Vec::new();
+	// the proof doesn't contain the path to the child roots if it hasn't read any.
+	if iter.seek(CHILD_STORAGE_KEY_PREFIX).is_err() {
+		return Ok(child_roots);
+	}
+
+	for item in iter {
+		match item {
+			Ok((key, value)) => {
+				if !key.starts_with(CHILD_STORAGE_KEY_PREFIX) {
+					break;
+				}
+				let mut child_root = <TrieHash<L>>::default();
+				if value.len() == child_root.as_ref().len() {
+					child_root.as_mut().copy_from_slice(&value[..]);
+					child_roots.push(child_root);
+				}
+			},
+			// the proof only contains the child roots it has read.
+			Err(error) => match *error {
+				trie_db::TrieError::IncompleteDatabase(_) => (),
+				error => return Err(Error::Trie(Box::new(error))),
+			},
+		}
+	}
+
+	Ok(child_roots)
+}
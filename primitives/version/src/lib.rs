@@ -93,6 +93,67 @@ pub struct RuntimeVersion {
 		)
 	)]
 	pub apis: ApisVec,
+
+	/// All existing dispatches are fully compatible when this number doesn't change. If this
+	/// number changes, then `spec_version` must change, also.
+	///
+	/// This number must change when an existing dispatchable (module ID, dispatch ID) is changed,
+	/// either through an alteration in its user-level semantics, a parameter added/removed/changed,
+	/// a dispatchable being removed, a module being removed, or a dispatchable/module changing its
+	/// index.
+	///
+	/// It need *not* change when a new module is added or when a dispatchable is added. Hardware
+	/// wallets and other offline signers rely on it to know when to refresh their metadata.
+	#[cfg_attr(feature = "std", serde(default = "default_transaction_version"))]
+	pub transaction_version: u32,
+}
+
+/// The transaction version of the runtimes that predate it.
+#[cfg(feature = "std")]
+fn default_transaction_version() -> u32 {
+	1
+}
+
+/// The runtime version of the runtimes written before the `transaction_version` was introduced.
+///
+/// It is returned by the version 2 of the `Core` runtime api.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, Default, sp_runtime::RuntimeDebug)]
+pub struct OldRuntimeVersion {
+	/// See `RuntimeVersion::spec_name`.
+	pub spec_name: RuntimeString,
+	/// See `RuntimeVersion::impl_name`.
+	pub impl_name: RuntimeString,
+	/// See `RuntimeVersion::authoring_version`.
+	pub authoring_version: u32,
+	/// See `RuntimeVersion::spec_version`.
+	pub spec_version: u32,
+	/// See `RuntimeVersion::impl_version`.
+	pub impl_version: u32,
+	/// See `RuntimeVersion::apis`.
+	pub apis: ApisVec,
+}
+
+impl From<OldRuntimeVersion> for RuntimeVersion {
+	fn from(x: OldRuntimeVersion) -> Self {
+		RuntimeVersion {
+			spec_name: x.spec_name,
+			impl_name: x.impl_name,
+			authoring_version: x.authoring_version,
+			spec_version: x.spec_version,
+			impl_version: x.impl_version,
+			apis: x.apis,
+			transaction_version: 1,
+		}
+	}
+}
+
+impl RuntimeVersion {
+	/// Decode the runtime version returned by the `Core_version` call of a runtime, the runtimes
+	/// that predate the `transaction_version` having the transaction version 1.
+	pub fn decode_compat(encoded: &[u8]) -> Result<Self, codec::Error> {
+		RuntimeVersion::decode(&mut &encoded[..])
+			.or_else(|_| OldRuntimeVersion::decode(&mut &encoded[..]).map(Into::into))
+	}
 }
 
 #[cfg(feature = "std")]
@@ -256,3 +317,25 @@ mod apis_serialize {
 		Ok(arr)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn versions_without_transaction_version_are_decoded() {
+		let old = OldRuntimeVersion {
+			spec_name: "test".into(),
+			spec_version: 2,
+			apis: create_apis_vec!([([1; 8], 3)]),
+			..Default::default()
+		};
+		assert_eq!(
+			RuntimeVersion::decode_compat(&old.encode()).unwrap(),
+			RuntimeVersion { transaction_version: 1, ..old.clone().into() },
+		);
+
+		let version = RuntimeVersion { transaction_version: 4, ..old.into() };
+		assert_eq!(RuntimeVersion::decode_compat(&version.encode()).unwrap(), version);
+	}
+}
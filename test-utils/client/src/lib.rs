@@ -211,6 +211,7 @@ impl<Block: BlockT, Executor, Backend, G: GenesisInit> TestClientBuilder<Block,
 				self.execution_strategies,
 				self.keystore.clone(),
 			),
+			sc_client::cht::size(),
 			None,
 		).expect("Creates new client");
 
@@ -344,8 +344,16 @@ pub fn new_light() -> (
 	sc_client::Client<LightBackend, LightExecutor, substrate_test_runtime::Block, substrate_test_runtime::RuntimeApi>,
 	Arc<LightBackend>,
 ) {
+	new_light_with_cht_size(sc_client::cht::size())
+}
 
-	let storage = sc_client_db::light::LightStorage::new_test();
+/// Creates new light client instance used for tests, replacing the finalized headers with CHTs of
+/// the given size.
+pub fn new_light_with_cht_size(cht_size: u32) -> (
+	sc_client::Client<LightBackend, LightExecutor, substrate_test_runtime::Block, substrate_test_runtime::RuntimeApi>,
+	Arc<LightBackend>,
+) {
+	let storage = sc_client_db::light::LightStorage::new_test_with_cht_size(cht_size);
 	let blockchain = Arc::new(sc_client::light::blockchain::Blockchain::new(storage));
 	let backend = Arc::new(LightBackend::new(blockchain.clone()));
 	let executor = new_native_executor();
@@ -86,7 +86,7 @@ struct RpcMessage {
 pub fn start_client(mut service: impl AbstractService) -> Client {
 	// Spawn informant
 	wasm_bindgen_futures::spawn_local(
-		sc_informant::build(&service, sc_informant::OutputFormat::Plain).map(drop)
+		sc_informant::build(&service, sc_informant::OutputFormat::Plain, None).map(drop)
 	);
 
 	// We dispatch a background task responsible for processing the service.
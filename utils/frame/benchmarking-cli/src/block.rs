@@ -0,0 +1,127 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The command benchmarking the execution of the blocks of a chain.
+
+use std::time::Instant;
+use sp_runtime::{generic::BlockId, traits::{Block as BlockT, Header as HeaderT}};
+use sp_api::{ProvideRuntimeApi, ApiExt};
+use sp_block_builder::ExecuteBlockNoCheck;
+use sc_client_api::{BlockBackend, CallExecutor, ExecutorProvider};
+use sc_cli::{ImportParams, SharedParams, VersionInfo};
+use sc_service::{Configuration, ChainSpec, Roles};
+use codec::Encode;
+
+/// The `benchmark-block` command used to benchmark the execution of the blocks of the database.
+///
+/// The blocks are executed with the `ExecuteBlockNoCheck` runtime api.
+#[derive(Debug, structopt::StructOpt, Clone)]
+pub struct BlockCmd {
+	/// Number of the first block to benchmark.
+	#[structopt(long)]
+	pub from: u32,
+
+	/// Number of the last block to benchmark.
+	#[structopt(long)]
+	pub to: u32,
+
+	/// Select how many times each block should be executed.
+	#[structopt(short, long, default_value = "1")]
+	pub repeat: u32,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub import_params: ImportParams,
+}
+
+impl BlockCmd {
+	/// Initialize
+	pub fn init(&self, version: &VersionInfo) -> sc_cli::Result<()> {
+		self.shared_params.init(version)
+	}
+
+	/// Runs the command and benchmarks the blocks of the client's database.
+	pub fn run<B, C>(self, client: C) -> sc_cli::Result<()> where
+		B: BlockT,
+		C: BlockBackend<B> + ExecutorProvider<B> + ProvideRuntimeApi<B>,
+		C::Api: ApiExt<B>,
+	{
+		let strategy = client.execution_extensions().strategies().importing;
+
+		println!("block,execution_time");
+		for number in self.from..=self.to {
+			let block = client.block(&BlockId::Number(number.into()))
+				.map_err(|e| format!("Failed to read block #{}: {}", number, e))?
+				.ok_or_else(|| format!("Block #{} isn't in the database", number))?
+				.block;
+			let (mut header, extrinsics) = block.deconstruct();
+			let parent = BlockId::Hash(*header.parent_hash());
+
+			let has_api = client.runtime_api()
+				.has_api::<dyn ExecuteBlockNoCheck<B, Error = ()>>(&parent)
+				.unwrap_or(false);
+			if !has_api {
+				return Err(format!(
+					"The runtime of block #{} doesn't implement the `ExecuteBlockNoCheck` api",
+					number,
+				).into())
+			}
+
+			// the seal is added by the consensus engine after the execution of the block, so the
+			// runtime would reject it.
+			header.digest_mut().logs.retain(|item| item.as_seal().is_none());
+			let encoded_block = B::new(header, extrinsics).encode();
+
+			for _ in 0..self.repeat {
+				let start = Instant::now();
+				client.executor().call(
+					&parent,
+					"ExecuteBlockNoCheck_execute_block_no_check",
+					&encoded_block,
+					strategy,
+					None,
+				).map_err(|e| format!("Error executing block #{}: {}", number, e))?;
+				println!("{},{:?}", number, start.elapsed().as_nanos());
+			}
+		}
+
+		eprintln!("Done.");
+
+		Ok(())
+	}
+
+	/// Update and prepare a `Configuration` with command line parameters
+	pub fn update_config(
+		&self,
+		mut config: &mut Configuration,
+		spec_factory: impl FnOnce(&str) -> Result<Box<dyn ChainSpec>, String>,
+		version: &VersionInfo,
+	) -> sc_cli::Result<()> {
+		self.shared_params.update_config(config, spec_factory, version)?;
+
+		// Make sure to configure keystore.
+		config.use_in_memory_keystore()?;
+
+		// and all import params (especially pruning that has to match db meta)
+		self.import_params.update_config(&mut config, Roles::FULL, self.shared_params.dev)?;
+
+		Ok(())
+	}
+}
@@ -25,6 +25,10 @@ use codec::{Encode, Decode};
 use frame_benchmarking::BenchmarkResults;
 use sp_core::tasks;
 
+mod block;
+
+pub use block::BlockCmd;
+
 /// The `benchmark` command used to benchmark FRAME Pallets.
 #[derive(Debug, structopt::StructOpt, Clone)]
 pub struct BenchmarkCmd {
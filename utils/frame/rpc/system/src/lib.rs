@@ -24,7 +24,7 @@ use sc_client::{
 	light::fetcher::{Fetcher, RemoteCallRequest},
 };
 use jsonrpc_core::{
-	Error, ErrorCode,
+	Error, ErrorCode, Result,
 	futures::future::{result, Future},
 };
 use jsonrpc_derive::rpc;
@@ -37,8 +37,10 @@ use sp_runtime::{
 	generic::BlockId,
 	traits,
 };
-use sp_core::hexdisplay::HexDisplay;
+use sp_core::{hexdisplay::HexDisplay, Bytes};
 use sp_transaction_pool::{TransactionPool, InPoolTransaction};
+use sp_block_builder::BlockBuilder;
+use sc_rpc_api::DenyUnsafe;
 
 pub use frame_system_rpc_runtime_api::AccountNonceApi;
 pub use self::gen_client::Client as SystemClient;
@@ -48,7 +50,7 @@ pub type FutureResult<T> = Box<dyn Future<Item = T, Error = Error> + Send>;
 
 /// System RPC methods.
 #[rpc]
-pub trait SystemApi<AccountId, Index> {
+pub trait SystemApi<BlockHash, AccountId, Index> {
 	/// Returns the next valid index (aka nonce) for given account.
 	///
 	/// This method takes into consideration all pending transactions
@@ -56,37 +58,59 @@ pub trait SystemApi<AccountId, Index> {
 	/// it fallbacks to query the index from the runtime (aka. state nonce).
 	#[rpc(name = "system_accountNextIndex", alias("account_nextIndex"))]
 	fn nonce(&self, account: AccountId) -> FutureResult<Index>;
+
+	/// Dry run an extrinsic at a given block (the best block by default).
+	///
+	/// The extrinsic is applied on top of the block's state without being broadcast or
+	/// imported. Returns the SCALE encoded `ApplyExtrinsicResult`.
+	///
+	/// Executing the extrinsic can be expensive, this call is therefore unsafe on full nodes.
+	#[rpc(name = "system_dryRun", alias("system_dryRunAt"))]
+	fn dry_run(&self, extrinsic: Bytes, at: Option<BlockHash>) -> FutureResult<Bytes>;
+
+	/// Returns the account id of the multi-signature account of the given signatories, any
+	/// `threshold` of them being required to dispatch a call from it.
+	///
+	/// The signatories are sorted before the account id is derived, as the `utility` module does.
+	#[rpc(name = "system_multiAccountId")]
+	fn multi_account_id(&self, signatories: Vec<AccountId>, threshold: u16) -> Result<AccountId>;
 }
 
 const RUNTIME_ERROR: i64 = 1;
+const DECODE_ERROR: i64 = 2;
+const INVALID_SIGNATORIES: i64 = 3;
 
 /// An implementation of System-specific RPC methods on full client.
 pub struct FullSystem<P: TransactionPool, C, B> {
 	client: Arc<C>,
 	pool: Arc<P>,
+	deny_unsafe: DenyUnsafe,
 	_marker: std::marker::PhantomData<B>,
 }
 
 impl<P: TransactionPool, C, B> FullSystem<P, C, B> {
 	/// Create new `FullSystem` given client and transaction pool.
-	pub fn new(client: Arc<C>, pool: Arc<P>) -> Self {
+	pub fn new(client: Arc<C>, pool: Arc<P>, deny_unsafe: DenyUnsafe) -> Self {
 		FullSystem {
 			client,
 			pool,
+			deny_unsafe,
 			_marker: Default::default(),
 		}
 	}
 }
 
-impl<P, C, Block, AccountId, Index> SystemApi<AccountId, Index> for FullSystem<P, C, Block>
+impl<P, C, Block, AccountId, Index> SystemApi<<Block as traits::Block>::Hash, AccountId, Index>
+	for FullSystem<P, C, Block>
 where
 	C: sp_api::ProvideRuntimeApi<Block>,
 	C: HeaderBackend<Block>,
 	C: Send + Sync + 'static,
 	C::Api: AccountNonceApi<Block, AccountId, Index>,
+	C::Api: BlockBuilder<Block>,
 	P: TransactionPool + 'static,
 	Block: traits::Block,
-	AccountId: Clone + std::fmt::Display + Codec,
+	AccountId: Clone + std::fmt::Display + Codec + Default + Ord,
 	Index: Clone + std::fmt::Display + Codec + Send + traits::AtLeast32Bit + 'static,
 {
 	fn nonce(&self, account: AccountId) -> FutureResult<Index> {
@@ -106,6 +130,42 @@ where
 
 		Box::new(result(get_nonce()))
 	}
+
+	fn dry_run(
+		&self,
+		extrinsic: Bytes,
+		at: Option<<Block as traits::Block>::Hash>,
+	) -> FutureResult<Bytes> {
+		if let Err(err) = self.deny_unsafe.check_if_safe() {
+			return Box::new(result(Err(err.into())));
+		}
+
+		let dry_run = || {
+			let api = self.client.runtime_api();
+			let at = BlockId::<Block>::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+			let uxt: <Block as traits::Block>::Extrinsic = Decode::decode(&mut &*extrinsic)
+				.map_err(|e| Error {
+					code: ErrorCode::ServerError(DECODE_ERROR),
+					message: "Unable to dry run extrinsic.".into(),
+					data: Some(format!("{:?}", e).into()),
+				})?;
+
+			let result = api.apply_extrinsic(&at, uxt).map_err(|e| Error {
+				code: ErrorCode::ServerError(RUNTIME_ERROR),
+				message: "Unable to dry run extrinsic.".into(),
+				data: Some(format!("{:?}", e).into()),
+			})?;
+
+			Ok(result.encode().into())
+		};
+
+		Box::new(result(dry_run()))
+	}
+
+	fn multi_account_id(&self, signatories: Vec<AccountId>, threshold: u16) -> Result<AccountId> {
+		multi_account_id(signatories, threshold)
+	}
 }
 
 /// An implementation of System-specific RPC methods on light client.
@@ -133,14 +193,15 @@ impl<P: TransactionPool, C, F, Block> LightSystem<P, C, F, Block> {
 	}
 }
 
-impl<P, C, F, Block, AccountId, Index> SystemApi<AccountId, Index> for LightSystem<P, C, F, Block>
+impl<P, C, F, Block, AccountId, Index> SystemApi<<Block as traits::Block>::Hash, AccountId, Index>
+	for LightSystem<P, C, F, Block>
 where
 	P: TransactionPool + 'static,
 	C: HeaderBackend<Block>,
 	C: Send + Sync + 'static,
 	F: Fetcher<Block> + 'static,
 	Block: traits::Block,
-	AccountId: Clone + std::fmt::Display + Codec + Send + 'static,
+	AccountId: Clone + std::fmt::Display + Codec + Default + Ord + Send + 'static,
 	Index: Clone + std::fmt::Display + Codec + Send + traits::AtLeast32Bit + 'static,
 {
 	fn nonce(&self, account: AccountId) -> FutureResult<Index> {
@@ -178,6 +239,66 @@ where
 
 		Box::new(future_nonce)
 	}
+
+	fn dry_run(
+		&self,
+		extrinsic: Bytes,
+		at: Option<<Block as traits::Block>::Hash>,
+	) -> FutureResult<Bytes> {
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		let future_at_header = future_header(
+			&*self.remote_blockchain,
+			&*self.fetcher,
+			BlockId::hash(at),
+		);
+		let fetcher = self.fetcher.clone();
+		let future_at_header = future_at_header
+			.and_then(move |maybe_header| ready(
+				match maybe_header {
+					Some(header) => Ok(header),
+					None => Err(ClientError::UnknownBlock(format!("{}", at))),
+				}
+			));
+		// The runtime decodes the extrinsic itself, so an invalid one is reported as a call
+		// failure rather than a decoding error here.
+		let future_result = future_at_header.and_then(move |header|
+			fetcher.remote_call(RemoteCallRequest {
+				block: at,
+				header,
+				method: "BlockBuilder_apply_extrinsic".into(),
+				call_data: extrinsic.0,
+				retry_count: None,
+			})
+		).compat();
+		let future_result = future_result.map(Bytes).map_err(|e| Error {
+			code: ErrorCode::ServerError(RUNTIME_ERROR),
+			message: "Unable to dry run extrinsic.".into(),
+			data: Some(format!("{:?}", e).into()),
+		});
+
+		Box::new(future_result)
+	}
+
+	fn multi_account_id(&self, signatories: Vec<AccountId>, threshold: u16) -> Result<AccountId> {
+		multi_account_id(signatories, threshold)
+	}
+}
+
+/// Derive the account id of the multi-signature account of the signatories, once sorted.
+fn multi_account_id<AccountId: Codec + Default + Ord>(
+	mut signatories: Vec<AccountId>,
+	threshold: u16,
+) -> Result<AccountId> {
+	signatories.sort();
+	if signatories.windows(2).any(|pair| pair[0] == pair[1]) {
+		return Err(Error {
+			code: ErrorCode::ServerError(INVALID_SIGNATORIES),
+			message: "The signatories contain duplicates.".into(),
+			data: None,
+		});
+	}
+
+	Ok(sp_runtime::multi_account_id(&signatories, threshold))
 }
 
 /// Adjust account nonce from state, so that tx with the nonce will be
@@ -197,7 +318,8 @@ fn adjust_nonce<P, AccountId, Index>(
 	//
 	// Since extrinsics are opaque to us, we look for them using
 	// `provides` tag. And increment the nonce if we find a transaction
-	// that matches the current one.
+	// that matches the current one. The future transactions are left out,
+	// as the gap before them has to be filled first.
 	let mut current_nonce = nonce.clone();
 	let mut current_tag = (account.clone(), nonce.clone()).encode();
 	for tx in pool.ready() {
@@ -209,8 +331,9 @@ fn adjust_nonce<P, AccountId, Index>(
 			tx.provides().iter().map(|x| format!("{}", HexDisplay::from(x))).collect::<Vec<_>>(),
 		);
 		// since transactions in `ready()` need to be ordered by nonce
-		// it's fine to continue with current iterator.
-		if tx.provides().get(0) == Some(&current_tag) {
+		// it's fine to continue with current iterator. The nonce tag isn't
+		// necessarily the first one, the signed extensions can provide others.
+		if tx.provides().contains(&current_tag) {
 			current_nonce += traits::One::one();
 			current_tag = (account.clone(), current_nonce.clone()).encode();
 		}
@@ -224,8 +347,12 @@ mod tests {
 	use super::*;
 
 	use futures::executor::block_on;
+	use sp_runtime::{
+		ApplyExtrinsicResult,
+		transaction_validity::{TransactionValidityError, InvalidTransaction},
+	};
 	use substrate_test_runtime_client::{
-		runtime::Transfer,
+		runtime::{AccountId, Transfer},
 		AccountKeyring,
 	};
 	use sc_transaction_pool::{BasicPool, FullChainApi};
@@ -236,7 +363,7 @@ mod tests {
 		let _ = env_logger::try_init();
 		let client = Arc::new(substrate_test_runtime_client::new());
 		let pool = Arc::new(
-			BasicPool::new(Default::default(), Arc::new(FullChainApi::new(client.clone()))).0
+			BasicPool::new(Default::default(), Arc::new(FullChainApi::new(client.clone())), None).0
 		);
 
 		let new_transaction = |nonce: u64| {
@@ -249,12 +376,13 @@ mod tests {
 			t.into_signed_tx()
 		};
 		// Populate the pool
+		let source = sp_transaction_pool::TransactionSource::External;
 		let ext0 = new_transaction(0);
-		block_on(pool.submit_one(&BlockId::number(0), ext0)).unwrap();
+		block_on(pool.submit_one(&BlockId::number(0), source, ext0)).unwrap();
 		let ext1 = new_transaction(1);
-		block_on(pool.submit_one(&BlockId::number(0), ext1)).unwrap();
+		block_on(pool.submit_one(&BlockId::number(0), source, ext1)).unwrap();
 
-		let accounts = FullSystem::new(client, pool);
+		let accounts = FullSystem::new(client, pool, DenyUnsafe::No);
 
 		// when
 		let nonce = accounts.nonce(AccountKeyring::Alice.into());
@@ -262,4 +390,109 @@ mod tests {
 		// then
 		assert_eq!(nonce.wait().unwrap(), 2);
 	}
+
+	#[test]
+	fn should_return_the_nonce_before_the_gap_of_future_transactions() {
+		// given
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let pool = Arc::new(
+			BasicPool::new(Default::default(), Arc::new(FullChainApi::new(client.clone())), None).0
+		);
+
+		let new_transaction = |nonce: u64| Transfer {
+			from: AccountKeyring::Alice.into(),
+			to: AccountKeyring::Bob.into(),
+			amount: 5,
+			nonce,
+		}.into_signed_tx();
+		let source = sp_transaction_pool::TransactionSource::External;
+		for nonce in vec![0, 1, 3] {
+			let xt = new_transaction(nonce);
+			block_on(pool.submit_one(&BlockId::number(0), source, xt)).unwrap();
+		}
+		assert_eq!(pool.status().ready, 2);
+		assert_eq!(pool.status().future, 1);
+
+		let accounts = FullSystem::new(client, pool, DenyUnsafe::No);
+
+		// when
+		let nonce = accounts.nonce(AccountKeyring::Alice.into());
+
+		// then
+		assert_eq!(nonce.wait().unwrap(), 2);
+	}
+
+	#[test]
+	fn dry_run_should_return_the_apply_result() {
+		// given
+		let _ = env_logger::try_init();
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let pool = Arc::new(
+			BasicPool::new(Default::default(), Arc::new(FullChainApi::new(client.clone())), None).0
+		);
+		let accounts = FullSystem::new(client, pool, DenyUnsafe::No);
+
+		let transfer = |nonce: u64| Transfer {
+			from: AccountKeyring::Alice.into(),
+			to: AccountKeyring::Bob.into(),
+			amount: 5,
+			nonce,
+		}.into_signed_tx();
+
+		// when
+		let valid = accounts.dry_run(transfer(0).encode().into(), None).wait().unwrap();
+		let stale = accounts.dry_run(transfer(100).encode().into(), None).wait().unwrap();
+
+		// then
+		let valid: ApplyExtrinsicResult = Decode::decode(&mut &valid[..]).unwrap();
+		assert_eq!(valid, Ok(Ok(())));
+		let stale: ApplyExtrinsicResult = Decode::decode(&mut &stale[..]).unwrap();
+		assert_eq!(stale, Err(TransactionValidityError::Invalid(InvalidTransaction::Stale)));
+	}
+
+	#[test]
+	fn multi_account_id_should_not_depend_on_the_order_of_the_signatories() {
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let pool = Arc::new(
+			BasicPool::new(Default::default(), Arc::new(FullChainApi::new(client.clone())), None).0
+		);
+		let accounts = FullSystem::new(client, pool, DenyUnsafe::No);
+		let alice: AccountId = AccountKeyring::Alice.into();
+		let bob: AccountId = AccountKeyring::Bob.into();
+
+		let id = accounts.multi_account_id(vec![bob, alice], 2).unwrap();
+		let mut sorted = vec![alice, bob];
+		sorted.sort();
+		assert_eq!(id, sp_runtime::multi_account_id(&sorted, 2));
+		assert_eq!(accounts.multi_account_id(vec![alice, bob], 2).unwrap(), id);
+
+		let error = accounts.multi_account_id(vec![alice, alice], 2).unwrap_err();
+		assert_eq!(error.code, ErrorCode::ServerError(INVALID_SIGNATORIES));
+	}
+
+	#[test]
+	fn dry_run_should_reject_undecodable_extrinsics() {
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let pool = Arc::new(
+			BasicPool::new(Default::default(), Arc::new(FullChainApi::new(client.clone())), None).0
+		);
+		let accounts = FullSystem::new(client, pool, DenyUnsafe::No);
+
+		let error = accounts.dry_run(vec![0xff].into(), None).wait().unwrap_err();
+
+		assert_eq!(error.code, ErrorCode::ServerError(DECODE_ERROR));
+	}
+
+	#[test]
+	fn dry_run_should_be_denied_when_unsafe_calls_are() {
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let pool = Arc::new(
+			BasicPool::new(Default::default(), Arc::new(FullChainApi::new(client.clone())), None).0
+		);
+		let accounts = FullSystem::new(client, pool, DenyUnsafe::Yes);
+
+		let error = accounts.dry_run(vec![].into(), None).wait().unwrap_err();
+
+		assert_eq!(error.code, ErrorCode::MethodNotFound);
+	}
 }
@@ -17,6 +17,7 @@
 use futures_util::{FutureExt, future::Future};
 pub use prometheus::{
 	Registry, Error as PrometheusError, Opts,
+	Histogram, HistogramOpts, HistogramVec,
 	core::{
 		GenericGauge as Gauge, GenericCounter as Counter,
 		GenericGaugeVec as GaugeVec, GenericCounterVec as CounterVec,